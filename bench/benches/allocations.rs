@@ -0,0 +1,100 @@
+//! Heap allocations per proof verification
+//!
+//! Run with `cargo bench -p proof-messenger-bench --bench allocations`. A
+//! counting global allocator records how many allocations, and how many
+//! bytes, verifying one message costs once the relay's per-thread decode
+//! buffers are warm, next to decoding the same message with `hex::decode`
+//! into fresh `Vec`s as the relay used to.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use proof_messenger_bench::{signed_messages, LoadConfig};
+use proof_messenger_protocol::envelope::ProofEnvelope;
+use proof_messenger_protocol::hybrid::HybridPolicy;
+use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+use proof_messenger_relay::{process_and_verify_message_with_policy, Message};
+use tokio::runtime::Builder;
+
+/// Context sizes measured, up to the relay's default 64 KiB limit
+const CONTEXT_SIZES: [usize; 3] = [64, 4 * 1024, 64 * 1024];
+
+/// Verifications averaged per measurement
+const ITERATIONS: usize = 1_000;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Average allocations and bytes allocated per call of `f`
+fn measure(mut f: impl FnMut()) -> (f64, f64) {
+    // Warm up lazily initialized state and the decode buffers
+    f();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    (
+        (ALLOCATIONS.load(Ordering::Relaxed) - allocations) as f64 / ITERATIONS as f64,
+        (ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes) as f64 / ITERATIONS as f64,
+    )
+}
+
+/// Decode a message's fields the way verification did before the decode buffers
+fn decode_with_vecs(message: &Message) {
+    black_box(hex::decode(&message.sender).unwrap());
+    black_box(hex::decode(&message.context).unwrap());
+    black_box(hex::decode(&message.proof).unwrap());
+}
+
+fn main() {
+    // A current-thread runtime keeps every verification on the measuring thread
+    let runtime = Builder::new_current_thread().build().unwrap();
+    let keypair = generate_secure_keypair_with_seed(0);
+
+    println!("{:<10} {:>8} {:>22} {:>22}", "proof", "context", "hex::decode (allocs/B)", "relay (allocs/B)");
+    for size in CONTEXT_SIZES {
+        let config = LoadConfig { messages: 1, context_bytes: size, ..LoadConfig::default() };
+        let signature = signed_messages(&config).remove(0);
+        let mut envelope = signature.clone();
+        let context = hex::decode(&envelope.context).unwrap();
+        envelope.proof = ProofEnvelope::sign(&keypair, &context, BTreeMap::new()).unwrap().to_hex();
+
+        for (kind, message) in [("signature", &signature), ("envelope", &envelope)] {
+            let (vec_allocations, vec_bytes) = measure(|| decode_with_vecs(message));
+            let (allocations, bytes) = measure(|| {
+                let result = runtime.block_on(process_and_verify_message_with_policy(message, None, HybridPolicy::Transitional));
+                black_box(result).unwrap();
+            });
+            println!(
+                "{:<10} {:>8} {:>13.1} / {:<8.0} {:>11.1} / {:<8.0}",
+                kind, size, vec_allocations, vec_bytes, allocations, bytes
+            );
+        }
+    }
+}
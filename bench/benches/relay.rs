@@ -0,0 +1,101 @@
+//! Relay benchmarks: proof verification, message storage and full requests
+//!
+//! Run with `cargo bench -p proof-messenger-bench`. Criterion keeps the
+//! previous run's results under `target/criterion` and reports regressions
+//! against them.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{Method, Request};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use proof_messenger_bench::{signed_messages, LoadConfig};
+use proof_messenger_protocol::envelope::ProofEnvelope;
+use proof_messenger_protocol::hybrid::HybridPolicy;
+use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+use proof_messenger_relay::database::{Database, StoredMessage};
+use proof_messenger_relay::{create_app, process_and_verify_message_with_policy, Message};
+use tokio::runtime::Runtime;
+use tower::ServiceExt;
+
+/// Context sizes benchmarked, up to the relay's default 64 KiB limit
+const CONTEXT_SIZES: [usize; 3] = [64, 4 * 1024, 64 * 1024];
+
+fn message_with_context(context_bytes: usize) -> Message {
+    let config = LoadConfig { messages: 1, context_bytes, ..LoadConfig::default() };
+    signed_messages(&config).remove(0)
+}
+
+fn enveloped_message(context_bytes: usize) -> Message {
+    let mut message = message_with_context(context_bytes);
+    let keypair = generate_secure_keypair_with_seed(0);
+    let context = hex::decode(&message.context).unwrap();
+    message.proof = ProofEnvelope::sign(&keypair, &context, BTreeMap::new()).unwrap().to_hex();
+    message
+}
+
+fn verification(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("verification");
+
+    for size in CONTEXT_SIZES {
+        let signature = message_with_context(size);
+        let envelope = enveloped_message(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("signature", size), &signature, |b, message| {
+            b.to_async(&runtime).iter(|| async {
+                black_box(process_and_verify_message_with_policy(message, None, HybridPolicy::Transitional).await)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("envelope", size), &envelope, |b, message| {
+            b.to_async(&runtime).iter(|| async {
+                black_box(process_and_verify_message_with_policy(message, None, HybridPolicy::Transitional).await)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn storage(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let db = runtime.block_on(async {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        db
+    });
+    let message = StoredMessage::from(message_with_context(64));
+
+    c.bench_function("storage/store_message", |b| {
+        b.to_async(&runtime).iter_batched(
+            || message.clone(),
+            |message| async { black_box(db.store_message(message).await.unwrap()) },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn relay_request(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let app = runtime.block_on(async {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        create_app(Arc::new(db))
+    });
+    let body = serde_json::to_string(&message_with_context(64)).unwrap();
+
+    c.bench_function("relay_request/post_relay", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri("/relay")
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.clone()))
+                .unwrap();
+            black_box(app.clone().oneshot(request).await.unwrap())
+        })
+    });
+}
+
+criterion_group!(benches, verification, storage, relay_request);
+criterion_main!(benches);
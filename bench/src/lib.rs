@@ -0,0 +1,336 @@
+//! Load generation and latency reporting for the relay
+//!
+//! [`run_load`] posts signed messages to a relay's `/relay` endpoint from
+//! many concurrent tasks and reports end-to-end latency percentiles, while
+//! [`measure_verification`] times proof verification alone in-process. The
+//! `relay-loadgen` binary wraps both and can fail a CI job when p99 latency
+//! crosses a budget. The criterion benchmarks in `benches/relay.rs` cover the
+//! verification and database paths in isolation.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use proof_messenger_protocol::hybrid::HybridPolicy;
+use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+use proof_messenger_protocol::proof::make_secure_proof;
+use proof_messenger_relay::config::DatabaseConfig;
+use proof_messenger_relay::database::Database;
+use proof_messenger_relay::{create_app, process_and_verify_message_with_policy, Message};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Number of distinct sender keys load is spread across
+const SENDER_KEYS: u64 = 64;
+
+/// Shape of a load run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadConfig {
+    /// Total messages to send
+    pub messages: usize,
+    /// Requests in flight at once
+    pub concurrency: usize,
+    /// Size of each signed context in bytes
+    pub context_bytes: usize,
+    /// Size of each message body in bytes
+    pub body_bytes: usize,
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        Self {
+            messages: 1000,
+            concurrency: 32,
+            context_bytes: 64,
+            body_bytes: 256,
+        }
+    }
+}
+
+/// Latency percentiles over a set of samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatencySummary {
+    /// Number of samples
+    pub count: usize,
+    /// Mean latency
+    pub mean: Duration,
+    /// Median latency
+    pub p50: Duration,
+    /// 99th percentile latency
+    pub p99: Duration,
+    /// Slowest sample
+    pub max: Duration,
+}
+
+impl LatencySummary {
+    /// Summarize samples, using nearest-rank percentiles
+    pub fn from_samples(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let total: Duration = samples.iter().sum();
+        Self {
+            count: samples.len(),
+            mean: total / samples.len() as u32,
+            p50: percentile(&samples, 50),
+            p99: percentile(&samples, 99),
+            max: samples[samples.len() - 1],
+        }
+    }
+
+    /// The summary as JSON, with latencies in milliseconds
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "count": self.count,
+            "mean_ms": millis(self.mean),
+            "p50_ms": millis(self.p50),
+            "p99_ms": millis(self.p99),
+            "max_ms": millis(self.max),
+        })
+    }
+}
+
+impl fmt::Display for LatencySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "n={} mean={:.3}ms p50={:.3}ms p99={:.3}ms max={:.3}ms",
+            self.count,
+            millis(self.mean),
+            millis(self.p50),
+            millis(self.p99),
+            millis(self.max)
+        )
+    }
+}
+
+/// Nearest-rank percentile of sorted, non-empty samples
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    let rank = (percent * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Result of a load run
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadReport {
+    /// In-process proof verification latency
+    pub verification: LatencySummary,
+    /// Request latency of messages the relay accepted
+    pub end_to_end: LatencySummary,
+    /// Messages the relay rejected or that failed in transit
+    pub failures: usize,
+    /// Wall-clock time of the HTTP phase
+    pub elapsed: Duration,
+}
+
+impl LoadReport {
+    /// Accepted messages per second
+    pub fn throughput(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        self.end_to_end.count as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// The report as JSON, with latencies in milliseconds
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "verification": self.verification.to_json(),
+            "end_to_end": self.end_to_end.to_json(),
+            "failures": self.failures,
+            "elapsed_ms": millis(self.elapsed),
+            "throughput_per_sec": self.throughput(),
+        })
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "verification: {}", self.verification)?;
+        writeln!(f, "end-to-end:   {}", self.end_to_end)?;
+        writeln!(f, "failures:     {}", self.failures)?;
+        write!(f, "throughput:   {:.1} msg/s over {:.3}s", self.throughput(), self.elapsed.as_secs_f64())
+    }
+}
+
+/// Build `config.messages` correctly signed messages
+///
+/// Every message has a distinct context, so none is a replay of another.
+pub fn signed_messages(config: &LoadConfig) -> Vec<Message> {
+    let keypairs: Vec<_> = (0..SENDER_KEYS).map(generate_secure_keypair_with_seed).collect();
+    (0..config.messages)
+        .map(|index| {
+            let keypair = &keypairs[index % keypairs.len()];
+            let mut context = format!("load-test message {} ", index).into_bytes();
+            context.resize(config.context_bytes.max(context.len()), b'.');
+            let proof = make_secure_proof(keypair, &context).expect("Load test contexts are within limits");
+            Message {
+                sender: hex::encode(keypair.public_key_bytes()),
+                context: hex::encode(&context),
+                body: "x".repeat(config.body_bytes),
+                proof: hex::encode(proof.to_bytes()),
+                pqc: None,
+                thread_id: None,
+                reply_to: None,
+                expires_at: None,
+                recipient: None,
+            }
+        })
+        .collect()
+}
+
+/// Time proof verification of each message, without the database or HTTP
+pub async fn measure_verification(messages: &[Message]) -> LatencySummary {
+    let mut samples = Vec::with_capacity(messages.len());
+    for message in messages {
+        let started = Instant::now();
+        let _ = process_and_verify_message_with_policy(message, None, HybridPolicy::Transitional).await;
+        samples.push(started.elapsed());
+    }
+    LatencySummary::from_samples(samples)
+}
+
+/// Post messages to `{base_url}/relay` with bounded concurrency
+///
+/// Returns the latency of each accepted message and the number of failures.
+pub async fn send_messages(base_url: &str, messages: Vec<Message>, concurrency: usize) -> (Vec<Duration>, usize) {
+    let client = reqwest::Client::new();
+    let url = format!("{}/relay", base_url.trim_end_matches('/'));
+    let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+
+    for message in messages {
+        let permit = permits.clone().acquire_owned().await.expect("Semaphore is never closed");
+        let client = client.clone();
+        let url = url.clone();
+        tasks.spawn(async move {
+            let started = Instant::now();
+            let response = client.post(&url).json(&message).send().await;
+            let elapsed = started.elapsed();
+            drop(permit);
+            match response {
+                Ok(response) if response.status().is_success() => Some(elapsed),
+                _ => None,
+            }
+        });
+    }
+
+    let mut samples = Vec::new();
+    let mut failures = 0;
+    while let Some(result) = tasks.join_next().await {
+        match result {
+            Ok(Some(elapsed)) => samples.push(elapsed),
+            _ => failures += 1,
+        }
+    }
+    (samples, failures)
+}
+
+/// Run a full load test against a relay at `base_url`
+pub async fn run_load(base_url: &str, config: &LoadConfig) -> LoadReport {
+    let messages = signed_messages(config);
+    let verification = measure_verification(&messages).await;
+
+    let started = Instant::now();
+    let (samples, failures) = send_messages(base_url, messages, config.concurrency).await;
+    let elapsed = started.elapsed();
+
+    LoadReport {
+        verification,
+        end_to_end: LatencySummary::from_samples(samples),
+        failures,
+        elapsed,
+    }
+}
+
+/// A relay serving on an ephemeral local port
+pub struct LocalRelay {
+    /// Base URL of the relay
+    pub base_url: String,
+    /// Holds the relay's database file until the relay is dropped
+    _dir: Option<tempfile::TempDir>,
+}
+
+/// Start a relay on an ephemeral local port
+///
+/// Without a `database_url` the relay gets a fresh database file in a
+/// temporary directory, opened with the relay's default pool and pragma
+/// settings so the storage path matches a deployed relay.
+pub async fn spawn_relay(database_url: Option<&str>) -> std::io::Result<LocalRelay> {
+    let (url, dir) = match database_url {
+        Some(url) => (url.to_string(), None),
+        None => {
+            let dir = tempfile::tempdir()?;
+            (format!("sqlite:{}?mode=rwc", dir.path().join("relay.db").display()), Some(dir))
+        }
+    };
+    let config = DatabaseConfig { url, ..DatabaseConfig::default() };
+    let db = Database::connect(&config)
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    db.migrate().await.map_err(|e| std::io::Error::other(e.to_string()))?;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    tokio::spawn(async move {
+        let _ = axum::serve(listener, create_app(Arc::new(db))).await;
+    });
+    Ok(LocalRelay { base_url: format!("http://{}", address), _dir: dir })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let samples = (1..=200).rev().map(Duration::from_millis).collect();
+
+        let summary = LatencySummary::from_samples(samples);
+
+        assert_eq!(summary.count, 200);
+        assert_eq!(summary.p50, Duration::from_millis(100));
+        assert_eq!(summary.p99, Duration::from_millis(198));
+        assert_eq!(summary.max, Duration::from_millis(200));
+        assert_eq!(summary.mean, Duration::from_micros(100_500));
+    }
+
+    #[test]
+    fn empty_samples_summarize_to_zero() {
+        assert_eq!(LatencySummary::from_samples(Vec::new()), LatencySummary::default());
+    }
+
+    #[test]
+    fn generated_messages_are_distinct_and_sized() {
+        let config = LoadConfig { messages: 3, context_bytes: 100, body_bytes: 10, ..LoadConfig::default() };
+
+        let messages = signed_messages(&config);
+
+        assert_eq!(messages.len(), 3);
+        assert_ne!(messages[0].context, messages[1].context);
+        assert!(messages.iter().all(|m| m.context.len() == 200 && m.body.len() == 10));
+    }
+
+    #[tokio::test]
+    async fn load_run_against_local_relay_reports_every_message() {
+        // ARRANGE: A relay on a local port
+        let relay = spawn_relay(None).await.unwrap();
+        let config = LoadConfig { messages: 40, concurrency: 8, ..LoadConfig::default() };
+
+        // ACT: Send the load
+        let report = run_load(&relay.base_url, &config).await;
+
+        // ASSERT: Every message was verified and accepted
+        assert_eq!(report.failures, 0);
+        assert_eq!(report.verification.count, 40);
+        assert_eq!(report.end_to_end.count, 40);
+        assert!(report.end_to_end.p50 <= report.end_to_end.p99);
+        assert!(report.throughput() > 0.0);
+        assert_eq!(report.to_json()["end_to_end"]["count"], 40);
+    }
+}
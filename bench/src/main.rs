@@ -0,0 +1,94 @@
+//! Relay load generator
+//!
+//! Sends signed messages to a relay from many concurrent tasks and reports
+//! p50/p99 verification and end-to-end latencies. Without `--url` it starts
+//! an in-process relay on a local port, so a run needs no setup.
+
+use clap::Parser;
+use proof_messenger_bench::{run_load, spawn_relay, LoadConfig};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "relay-loadgen", version, about = "Load test a proof-messenger relay")]
+struct Cli {
+    /// Base URL of the relay to load, instead of an in-process relay
+    #[arg(long)]
+    url: Option<String>,
+
+    /// Database of the in-process relay (a temporary file by default)
+    #[arg(long, conflicts_with = "url")]
+    database_url: Option<String>,
+
+    /// Total messages to send
+    #[arg(long, default_value_t = LoadConfig::default().messages)]
+    messages: usize,
+
+    /// Requests in flight at once
+    #[arg(long, default_value_t = LoadConfig::default().concurrency)]
+    concurrency: usize,
+
+    /// Size of each signed context in bytes
+    #[arg(long, default_value_t = LoadConfig::default().context_bytes)]
+    context_bytes: usize,
+
+    /// Size of each message body in bytes
+    #[arg(long, default_value_t = LoadConfig::default().body_bytes)]
+    body_bytes: usize,
+
+    /// Fail if the end-to-end p99 latency exceeds this many milliseconds
+    #[arg(long)]
+    max_p99_ms: Option<f64>,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let config = LoadConfig {
+        messages: cli.messages,
+        concurrency: cli.concurrency,
+        context_bytes: cli.context_bytes,
+        body_bytes: cli.body_bytes,
+    };
+
+    // The in-process relay lives until the end of the run
+    let local_relay = match cli.url {
+        Some(_) => None,
+        None => match spawn_relay(cli.database_url.as_deref()).await {
+            Ok(relay) => Some(relay),
+            Err(e) => {
+                eprintln!("Failed to start in-process relay: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+    let base_url = match (&cli.url, &local_relay) {
+        (Some(url), _) => url.clone(),
+        (None, Some(relay)) => relay.base_url.clone(),
+        (None, None) => unreachable!("an in-process relay is started without --url"),
+    };
+
+    let report = run_load(&base_url, &config).await;
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&report.to_json()).unwrap());
+    } else {
+        println!("Relay: {}", base_url);
+        println!("{}", report);
+    }
+
+    if report.failures > 0 {
+        eprintln!("{} of {} messages failed", report.failures, config.messages);
+        return ExitCode::FAILURE;
+    }
+    if let Some(budget) = cli.max_p99_ms {
+        let p99 = report.end_to_end.p99.as_secs_f64() * 1000.0;
+        if p99 > budget {
+            eprintln!("End-to-end p99 of {:.3}ms exceeds the {:.3}ms budget", p99, budget);
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
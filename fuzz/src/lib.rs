@@ -0,0 +1,251 @@
+//! Fuzz harnesses for relay request parsing and proof verification
+//!
+//! Each harness takes arbitrary bytes and drives them through the same steps
+//! the relay's `POST /relay` handler takes: decoding the JSON or CBOR body,
+//! checking size limits and verifying the proof. A harness must never panic,
+//! whatever the input. The harnesses are shared by the libFuzzer targets in
+//! `fuzz_targets/` (run with `cargo fuzz`) and by the proptest suite below,
+//! which runs on stable as part of `cargo test`.
+
+use std::sync::OnceLock;
+
+use proof_messenger_protocol::hybrid::HybridPolicy;
+use proof_messenger_protocol::wire::WireMessage;
+use proof_messenger_relay::{limits, process_and_verify_message_with_policy, AppError, Message};
+
+/// Runtime the async verification pipeline is driven on
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("Failed to build fuzz runtime")
+    })
+}
+
+/// Check a decoded message's limits and verify its proof, as the relay does
+///
+/// Runs under the transitional hybrid policy so both classic and hybrid
+/// proofs reach signature verification.
+pub fn relay_pipeline(message: &Message) -> Result<(), AppError> {
+    limits::validate_message(None, message)?;
+    runtime().block_on(process_and_verify_message_with_policy(message, None, HybridPolicy::Transitional))
+}
+
+/// Decode a JSON request body and run it through the relay pipeline
+pub fn relay_json_message(data: &[u8]) {
+    let Ok(message) = serde_json::from_slice::<Message>(data) else {
+        return;
+    };
+
+    // Whatever decodes must re-encode to a message that decodes identically
+    let encoded = serde_json::to_string(&message).expect("A decoded message re-encodes");
+    let decoded: Message = serde_json::from_str(&encoded).expect("A re-encoded message decodes");
+    assert_eq!(serde_json::to_string(&decoded).unwrap(), encoded);
+
+    let _ = relay_pipeline(&message);
+}
+
+/// Decode a CBOR request body and run it through the relay pipeline
+pub fn relay_cbor_message(data: &[u8]) {
+    let Ok(wire) = WireMessage::from_cbor(data) else {
+        return;
+    };
+
+    let encoded = wire.to_cbor().expect("A decoded message re-encodes");
+    assert_eq!(WireMessage::from_cbor(&encoded).expect("A re-encoded message decodes"), wire);
+
+    let _ = relay_pipeline(&Message::from(wire));
+}
+
+/// Build a message from raw bytes, so the fuzzer reaches signature parsing
+///
+/// The first three bytes give the sender, context and proof lengths. The
+/// following bytes fill those fields in order (hex encoded, as the relay
+/// receives them), and whatever is left becomes the body.
+pub fn message_from_bytes(data: &[u8]) -> Message {
+    let (lengths, mut rest) = data.split_at(data.len().min(3));
+    let mut take = |length: Option<&u8>| {
+        let (field, tail) = rest.split_at(rest.len().min(length.copied().unwrap_or(0) as usize));
+        rest = tail;
+        hex::encode(field)
+    };
+    let sender = take(lengths.first());
+    let context = take(lengths.get(1));
+    let proof = take(lengths.get(2));
+
+    Message {
+        sender,
+        context,
+        body: String::from_utf8_lossy(rest).into_owned(),
+        proof,
+        pqc: None,
+        thread_id: None,
+        reply_to: None,
+        expires_at: None,
+        recipient: None,
+    }
+}
+
+/// Verify a message built from raw bytes
+pub fn verify_message(data: &[u8]) {
+    let _ = relay_pipeline(&message_from_bytes(data));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proof_messenger_protocol::envelope::ProofEnvelope;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_protocol::proof::make_secure_proof;
+    use proptest::prelude::*;
+
+    /// A correctly signed message
+    fn signed_message(seed: u64, context: &[u8], body: &str) -> WireMessage {
+        let keypair = generate_secure_keypair_with_seed(seed);
+        let proof = make_secure_proof(&keypair, context).unwrap();
+        WireMessage::new(&keypair.public_key(), context, body, &proof)
+    }
+
+    /// Strings that are hex, almost hex, or not hex at all
+    fn adversarial_hex() -> impl Strategy<Value = String> {
+        prop_oneof![
+            prop::collection::vec(any::<u8>(), 0..160).prop_map(hex::encode),
+            "[0-9a-fA-F]{0,129}",
+            "[ -~]{0,80}",
+            ".{0,40}",
+        ]
+    }
+
+    /// JSON objects with the relay message's fields, each possibly missing or mistyped
+    fn adversarial_json() -> impl Strategy<Value = String> {
+        let field = prop_oneof![
+            adversarial_hex().prop_map(serde_json::Value::from),
+            any::<i64>().prop_map(serde_json::Value::from),
+            Just(serde_json::Value::Null),
+            Just(serde_json::json!([])),
+            Just(serde_json::json!({ "public_key": "00", "proof": "zz" })),
+        ];
+        prop::collection::btree_map(
+            prop::sample::select(vec!["sender", "context", "body", "proof", "pqc", "thread_id", "reply_to", "extra"]),
+            field,
+            0..8,
+        )
+        .prop_map(|fields| serde_json::to_string(&fields).unwrap())
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_bytes_never_panic(data in prop::collection::vec(any::<u8>(), 0..512)) {
+            relay_json_message(&data);
+            relay_cbor_message(&data);
+            verify_message(&data);
+        }
+
+        #[test]
+        fn adversarial_json_never_panics(json in adversarial_json()) {
+            relay_json_message(json.as_bytes());
+        }
+
+        #[test]
+        fn truncated_json_never_panics(cut in 0usize..400) {
+            let message = Message::from(signed_message(1, b"truncated context", "hello"));
+            let json = serde_json::to_vec(&message).unwrap();
+            relay_json_message(&json[..cut.min(json.len())]);
+        }
+
+        #[test]
+        fn truncated_cbor_is_rejected(cut in 0usize..200) {
+            let encoded = signed_message(1, b"truncated context", "hello").to_cbor().unwrap();
+            prop_assume!(cut < encoded.len());
+
+            relay_cbor_message(&encoded[..cut]);
+            prop_assert!(WireMessage::from_cbor(&encoded[..cut]).is_err());
+        }
+
+        #[test]
+        fn truncated_or_padded_signatures_are_rejected(keep in 0usize..128, padding in prop::collection::vec(any::<u8>(), 0..4)) {
+            let message = Message::from(signed_message(2, b"signature context", "hello"));
+            let mut proof = message.proof[..keep.min(message.proof.len())].to_string();
+            proof.push_str(&hex::encode(&padding));
+            prop_assume!(proof != message.proof);
+
+            let result = relay_pipeline(&Message { proof, ..message });
+
+            prop_assert!(result.is_err());
+        }
+
+        #[test]
+        fn any_flipped_byte_breaks_verification(index in any::<prop::sample::Index>(), bit in 0u8..8) {
+            let wire = signed_message(3, b"flip context", "hello");
+            let mut fields = [wire.sender.clone(), wire.context.clone(), wire.proof.clone()];
+            let total: usize = fields.iter().map(Vec::len).sum();
+            let mut offset = index.index(total);
+            for field in fields.iter_mut() {
+                if offset < field.len() {
+                    field[offset] ^= 1 << bit;
+                    break;
+                }
+                offset -= field.len();
+            }
+            let [sender, context, proof] = fields;
+
+            let result = relay_pipeline(&Message::from(WireMessage { sender, context, proof, ..wire }));
+
+            prop_assert!(result.is_err());
+        }
+
+        #[test]
+        fn mangled_envelopes_are_rejected(cut in 0usize..256, flip in any::<prop::sample::Index>()) {
+            let keypair = generate_secure_keypair_with_seed(4);
+            let envelope = ProofEnvelope::sign(&keypair, b"envelope context", Default::default()).unwrap();
+            let mut bytes = envelope.to_bytes();
+            let flipped = flip.index(bytes.len());
+            bytes[flipped] ^= 0x80;
+            bytes.truncate(cut.max(1));
+            let message = Message {
+                sender: hex::encode(keypair.public_key_bytes()),
+                context: hex::encode(b"envelope context"),
+                body: "hello".to_string(),
+                proof: hex::encode(&bytes),
+                pqc: None,
+                thread_id: None,
+                reply_to: None,
+                expires_at: None,
+                recipient: None,
+            };
+
+            prop_assert!(relay_pipeline(&message).is_err());
+        }
+    }
+
+    #[test]
+    fn signed_messages_pass_the_pipeline() {
+        let wire = signed_message(5, b"valid context", "hello");
+
+        relay_cbor_message(&wire.to_cbor().unwrap());
+        relay_json_message(&serde_json::to_vec(&Message::from(wire.clone())).unwrap());
+
+        assert!(relay_pipeline(&Message::from(wire)).is_ok());
+    }
+
+    #[test]
+    fn giant_contexts_are_rejected_before_verification() {
+        let context = vec![0x5a; 4 * 1024 * 1024];
+        let message = Message::from(signed_message(6, &context[..1024], "hello"));
+        let giant = Message { context: hex::encode(&context), ..message };
+
+        assert!(matches!(relay_pipeline(&giant), Err(AppError::PayloadTooLarge(_))));
+        relay_json_message(&serde_json::to_vec(&giant).unwrap());
+    }
+
+    #[test]
+    fn message_from_bytes_splits_fields_by_length_prefix() {
+        let message = message_from_bytes(&[2, 1, 3, 0xaa, 0xbb, 0xcc, 1, 2, 3, b'h', b'i']);
+
+        assert_eq!(message.sender, "aabb");
+        assert_eq!(message.context, "cc");
+        assert_eq!(message.proof, "010203");
+        assert_eq!(message.body, "hi");
+    }
+}
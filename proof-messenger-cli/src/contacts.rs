@@ -0,0 +1,366 @@
+// src/contacts.rs
+
+//! Contact book and key trust
+//!
+//! Contacts map a name to an Ed25519 public key and record whether that key
+//! has been verified out of band. They live in `contacts.json` in the CLI's
+//! config directory: `--config-dir`, else `$PROOF_MESSENGER_CONFIG_DIR`,
+//! else `$XDG_CONFIG_HOME/proof-messenger`, else
+//! `~/.config/proof-messenger`.
+//!
+//! A key is verified by comparing its fingerprint, the SHA-256 of the public
+//! key, with the contact over a channel an attacker does not control. The
+//! fingerprint is shown as hex, and as emoji and words derived from its first
+//! six bytes, which are easier to read aloud or compare on two screens.
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File holding the contact book inside the config directory
+const CONTACTS_FILE: &str = "contacts.json";
+
+/// Symbols in the emoji and word encodings, one per 6 bits
+const SYMBOLS_PER_FINGERPRINT: usize = 8;
+
+const EMOJI: [&str; 64] = [
+    "🐶", "🐱", "🦁", "🐴", "🦄", "🐷", "🐘", "🐰", "🐼", "🐔", "🐧", "🐢", "🐟", "🐙", "🦋", "🌷",
+    "🌳", "🌵", "🍄", "🌏", "🌙", "☁️", "🔥", "🍌", "🍎", "🍓", "🌽", "🍕", "🎂", "❤️", "😀", "🤖",
+    "🎩", "👓", "🔧", "🎅", "👍", "☂️", "⌛", "⏰", "🎁", "💡", "📕", "✏️", "📎", "✂️", "🔒", "🔑",
+    "🔨", "☎️", "🏁", "🚂", "🚲", "✈️", "🚀", "🏆", "⚽", "🎸", "🎺", "🔔", "⚓", "🎧", "📁", "📌",
+];
+
+const WORDS: [&str; 64] = [
+    "apple", "bridge", "canyon", "dragon", "eagle", "falcon", "garden", "harbor",
+    "island", "jungle", "kettle", "lemon", "marble", "nectar", "orbit", "pepper",
+    "quartz", "river", "saddle", "tiger", "umbrella", "valley", "walnut", "yellow",
+    "zebra", "anchor", "banjo", "cactus", "dolphin", "ember", "forest", "glacier",
+    "hammer", "igloo", "jacket", "koala", "lantern", "meadow", "nickel", "oyster",
+    "pillow", "quiver", "rocket", "silver", "tunnel", "violin", "window", "yogurt",
+    "acorn", "bucket", "candle", "desert", "engine", "feather", "guitar", "helmet",
+    "ivory", "jigsaw", "kitten", "ladder", "magnet", "noodle", "pebble", "rabbit",
+];
+
+/// How far a contact's key is trusted
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustState {
+    /// Added, but the fingerprint has not been compared
+    Unverified,
+    /// The fingerprint was compared out of band and matched
+    Verified,
+}
+
+impl std::fmt::Display for TrustState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrustState::Unverified => write!(f, "unverified"),
+            TrustState::Verified => write!(f, "verified"),
+        }
+    }
+}
+
+/// A named public key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contact {
+    /// Ed25519 public key (hex encoded)
+    #[serde(rename = "publicKey")]
+    pub public_key: String,
+    pub trust: TrustState,
+    #[serde(rename = "addedAt")]
+    pub added_at: DateTime<Utc>,
+    #[serde(rename = "verifiedAt", default, skip_serializing_if = "Option::is_none")]
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+impl Contact {
+    /// The fingerprint of this contact's key
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint::of(&hex::decode(&self.public_key).expect("contact keys are validated when added"))
+    }
+}
+
+/// All contacts, by name
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContactBook {
+    pub contacts: BTreeMap<String, Contact>,
+}
+
+impl ContactBook {
+    /// Load the contact book from `config_dir`, empty if none was saved yet
+    pub fn load(config_dir: &Path) -> Result<Self, String> {
+        let path = config_dir.join(CONTACTS_FILE);
+        match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Save the contact book to `config_dir`, creating the directory if needed
+    pub fn save(&self, config_dir: &Path) -> Result<(), String> {
+        fs::create_dir_all(config_dir).map_err(|e| format!("Failed to create {}: {}", config_dir.display(), e))?;
+        let path = config_dir.join(CONTACTS_FILE);
+        let json = serde_json::to_string_pretty(self).map_err(|e| format!("Failed to serialize contacts: {}", e))?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Add `name` with `public_key` (hex), unverified
+    ///
+    /// Re-adding a name with the same key keeps its trust. A different key is
+    /// refused unless `replace` is set, and then starts over as unverified.
+    pub fn add(&mut self, name: &str, public_key: &str, replace: bool) -> Result<&Contact, String> {
+        validate_name(name)?;
+        let public_key = parse_public_key(public_key)?;
+        if let Some(existing) = self.contacts.get(name) {
+            if existing.public_key == public_key {
+                return Ok(&self.contacts[name]);
+            }
+            if !replace {
+                return Err(format!(
+                    "Contact {} already has a different key; pass --replace to change it",
+                    name
+                ));
+            }
+        }
+        self.contacts.insert(
+            name.to_string(),
+            Contact {
+                public_key,
+                trust: TrustState::Unverified,
+                added_at: Utc::now(),
+                verified_at: None,
+            },
+        );
+        Ok(&self.contacts[name])
+    }
+
+    /// Look up a contact by name
+    pub fn get(&self, name: &str) -> Result<&Contact, String> {
+        self.contacts.get(name).ok_or_else(|| format!("Unknown contact: {}", name))
+    }
+
+    /// Mark `name`'s key verified
+    pub fn mark_verified(&mut self, name: &str) -> Result<&Contact, String> {
+        let contact = self.contacts.get_mut(name).ok_or_else(|| format!("Unknown contact: {}", name))?;
+        contact.trust = TrustState::Verified;
+        contact.verified_at = Some(Utc::now());
+        Ok(contact)
+    }
+
+    /// A recipient's public key (hex) and contact name
+    ///
+    /// A contact name resolves to the contact's key. Anything else is passed
+    /// through as given, reported under a contact's name if it is their key.
+    pub fn resolve(&self, recipient: &str) -> (String, Option<(&str, &Contact)>) {
+        if let Some((name, contact)) = self.contacts.get_key_value(recipient) {
+            return (contact.public_key.clone(), Some((name.as_str(), contact)));
+        }
+        let contact = self
+            .contacts
+            .iter()
+            .find(|(_, contact)| contact.public_key.eq_ignore_ascii_case(recipient))
+            .map(|(name, contact)| (name.as_str(), contact));
+        (recipient.to_string(), contact)
+    }
+}
+
+/// The config directory: `explicit`, else the environment's default
+pub fn config_dir(explicit: Option<&Path>) -> Result<PathBuf, String> {
+    if let Some(dir) = explicit {
+        return Ok(dir.to_path_buf());
+    }
+    let env = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    if let Some(dir) = env("PROOF_MESSENGER_CONFIG_DIR") {
+        return Ok(dir);
+    }
+    if let Some(dir) = env("XDG_CONFIG_HOME") {
+        return Ok(dir.join("proof-messenger"));
+    }
+    env("HOME")
+        .or_else(|| env("USERPROFILE"))
+        .map(|home| home.join(".config").join("proof-messenger"))
+        .ok_or_else(|| "Cannot find a config directory; pass --config-dir".to_string())
+}
+
+/// SHA-256 of a public key, with readable encodings for comparing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    pub fn of(public_key: &[u8]) -> Self {
+        Self(Sha256::digest(public_key).into())
+    }
+
+    /// Hex in groups of four, e.g. `1a2b 3c4d ...`
+    pub fn hex(&self) -> String {
+        hex::encode(self.0)
+            .as_bytes()
+            .chunks(4)
+            .map(|group| std::str::from_utf8(group).expect("hex is ASCII"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Eight emoji for the fingerprint's first 48 bits
+    pub fn emoji(&self) -> String {
+        self.symbols().map(|i| EMOJI[i]).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Eight words for the fingerprint's first 48 bits
+    pub fn words(&self) -> String {
+        self.symbols().map(|i| WORDS[i]).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Whether `claimed` is this fingerprint, as hex or words
+    ///
+    /// Case, spaces and dashes are ignored. Hex must be complete; words cover
+    /// only the first 48 bits, so prefer hex when both are at hand.
+    pub fn matches(&self, claimed: &str) -> bool {
+        let compact: String = claimed
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .flat_map(char::to_lowercase)
+            .collect();
+        if compact == hex::encode(self.0) {
+            return true;
+        }
+        let words: Vec<String> = claimed
+            .split(|c: char| c.is_whitespace() || c == '-')
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        words.len() == SYMBOLS_PER_FINGERPRINT && words.iter().zip(self.symbols()).all(|(word, i)| word == WORDS[i])
+    }
+
+    /// The first 48 bits as eight 6-bit indices
+    fn symbols(&self) -> impl Iterator<Item = usize> {
+        let bits = self.0[..6].iter().fold(0u64, |bits, byte| bits << 8 | u64::from(*byte));
+        (0..SYMBOLS_PER_FINGERPRINT).rev().map(move |i| ((bits >> (i * 6)) & 0x3F) as usize)
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.chars().all(|c| c.is_ascii_hexdigit());
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid contact name {:?}: use letters, digits, '-', '_' or '.', and not only hex digits",
+            name
+        ))
+    }
+}
+
+/// Normalize a hex Ed25519 public key, checking that it is one
+fn parse_public_key(public_key: &str) -> Result<String, String> {
+    let bytes = hex::decode(public_key.trim()).map_err(|e| format!("Invalid public key hex: {}", e))?;
+    ed25519_dalek::PublicKey::from_bytes(&bytes).map_err(|e| format!("Invalid public key: {}", e))?;
+    Ok(hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proof_messenger_protocol::key::generate_keypair_with_seed;
+
+    fn key(seed: u64) -> String {
+        hex::encode(generate_keypair_with_seed(seed).public.to_bytes())
+    }
+
+    #[test]
+    fn contact_book_roundtrips_through_the_config_dir() {
+        // ARRANGE
+        let dir = tempfile::tempdir().unwrap();
+        let mut book = ContactBook::load(dir.path()).unwrap();
+        assert!(book.contacts.is_empty());
+
+        // ACT
+        book.add("alice", &key(1), false).unwrap();
+        book.mark_verified("alice").unwrap();
+        book.save(&dir.path().join("nested")).unwrap();
+
+        // ASSERT
+        let loaded = ContactBook::load(&dir.path().join("nested")).unwrap();
+        assert_eq!(loaded, book);
+        assert_eq!(loaded.get("alice").unwrap().trust, TrustState::Verified);
+    }
+
+    #[test]
+    fn changing_a_contacts_key_needs_replace_and_resets_trust() {
+        let mut book = ContactBook::default();
+        book.add("alice", &key(1), false).unwrap();
+        book.mark_verified("alice").unwrap();
+
+        // Same key again keeps the trust
+        assert_eq!(book.add("alice", &key(1).to_uppercase(), false).unwrap().trust, TrustState::Verified);
+        assert!(book.add("alice", &key(2), false).is_err());
+
+        let replaced = book.add("alice", &key(2), true).unwrap();
+        assert_eq!(replaced.trust, TrustState::Unverified);
+        assert_eq!(replaced.public_key, key(2));
+    }
+
+    #[test]
+    fn names_and_keys_are_validated() {
+        let mut book = ContactBook::default();
+        assert!(book.add("", &key(1), false).is_err());
+        assert!(book.add("bob smith", &key(1), false).is_err());
+        // A name that looks like hex would be ambiguous with a key in `send --to`
+        assert!(book.add("beef", &key(1), false).is_err());
+        assert!(book.add("bob", "zz", false).is_err());
+        assert!(book.add("bob", "abcd", false).is_err());
+    }
+
+    #[test]
+    fn recipients_resolve_by_name_or_key() {
+        let mut book = ContactBook::default();
+        book.add("alice", &key(1), false).unwrap();
+
+        let (public_key, contact) = book.resolve("alice");
+        assert_eq!(public_key, key(1));
+        assert_eq!(contact.unwrap().0, "alice");
+
+        let (_, contact) = book.resolve(&key(1));
+        assert_eq!(contact.unwrap().0, "alice");
+
+        let (public_key, contact) = book.resolve(&key(2));
+        assert_eq!(public_key, key(2));
+        assert!(contact.is_none());
+
+        let (recipient, contact) = book.resolve("mallory");
+        assert_eq!(recipient, "mallory");
+        assert!(contact.is_none());
+    }
+
+    #[test]
+    fn fingerprint_encodings_agree_and_match() {
+        let fingerprint = Fingerprint::of(&[7u8; 32]);
+        let hex = hex::encode(Sha256::digest(&[7u8; 32]));
+
+        assert_eq!(fingerprint.hex().replace(' ', ""), hex);
+        assert_eq!(fingerprint.hex().split(' ').count(), 16);
+        assert_eq!(fingerprint.emoji().split(' ').count(), SYMBOLS_PER_FINGERPRINT);
+        assert!(fingerprint.matches(&fingerprint.hex()));
+        assert!(fingerprint.matches(&hex.to_uppercase()));
+        assert!(fingerprint.matches(&fingerprint.words().to_uppercase().replace(' ', "-")));
+        assert!(!fingerprint.matches(&hex[..62]));
+        assert!(!fingerprint.matches(&Fingerprint::of(&[8u8; 32]).words()));
+    }
+
+    #[test]
+    fn fingerprint_symbols_cover_the_first_48_bits() {
+        // 0b000001_000010_000011_000100_000101_000110_000111_001000
+        let fingerprint = Fingerprint([0x04, 0x20, 0xC4, 0x14, 0x61, 0xC8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(fingerprint.symbols().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+}
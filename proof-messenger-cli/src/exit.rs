@@ -0,0 +1,141 @@
+// src/exit.rs
+
+//! Exit codes and error reports
+//!
+//! Every failure exits with a code naming its kind, so scripts can react to
+//! a rejected proof differently from an unreachable relay:
+//!
+//! | Code | Kind                  | Examples                                        |
+//! |------|-----------------------|-------------------------------------------------|
+//! | 0    |                       | success                                         |
+//! | 1    | `FAILURE`             | a file or the history cannot be written         |
+//! | 2    | `VERIFICATION_FAILED` | a proof, bundle or fingerprint does not verify  |
+//! | 3    | `NETWORK_ERROR`       | the relay is unreachable or rejects the request |
+//! | 4    | `KEY_ERROR`           | the keystore is missing, invalid or unusable    |
+//! | 5    | `INVALID_INPUT`       | bad arguments, hex, invite URIs or input files  |
+//!
+//! Errors are printed to stderr, as a JSON object when `--output json` is
+//! set. Verification failures print their result to stdout like a success
+//! and only differ in the exit code.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether error reports are written as JSON
+static JSON_REPORTS: AtomicBool = AtomicBool::new(false);
+
+/// Kind of failure, each with a stable exit code
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorKind {
+    Failure,
+    VerificationFailed,
+    #[serde(rename = "NETWORK_ERROR")]
+    Network,
+    #[serde(rename = "KEY_ERROR")]
+    Key,
+    InvalidInput,
+}
+
+impl ErrorKind {
+    /// Process exit code for this kind of failure
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Failure => 1,
+            ErrorKind::VerificationFailed => 2,
+            ErrorKind::Network => 3,
+            ErrorKind::Key => 4,
+            ErrorKind::InvalidInput => 5,
+        }
+    }
+}
+
+/// A failed command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into() }
+    }
+}
+
+/// Error report written to stderr with `--output json`
+#[derive(Serialize)]
+struct ErrorOutput<'a> {
+    status: &'static str,
+    code: ErrorKind,
+    #[serde(rename = "exitCode")]
+    exit_code: i32,
+    error: &'a str,
+}
+
+/// Write error reports as JSON from now on
+pub fn use_json_reports(json: bool) {
+    JSON_REPORTS.store(json, Ordering::Relaxed);
+}
+
+/// Whether the raw command line asks for JSON output, for errors raised before it parses
+pub fn json_requested(args: &[String]) -> bool {
+    args.iter().enumerate().any(|(i, arg)| match arg.as_str() {
+        "--output" | "-o" => args.get(i + 1).is_some_and(|value| value == "json"),
+        arg => matches!(arg, "--output=json" | "-ojson" | "-o=json"),
+    })
+}
+
+/// Print an error report to stderr
+pub fn report(error: &CliError) {
+    if JSON_REPORTS.load(Ordering::Relaxed) {
+        let output = ErrorOutput {
+            status: "error",
+            code: error.kind,
+            exit_code: error.kind.exit_code(),
+            error: &error.message,
+        };
+        eprintln!("{}", serde_json::to_string(&output).unwrap());
+    } else {
+        eprintln!("❌ {}", error.message);
+    }
+}
+
+/// Report an error and exit with its code
+pub fn exit_with(error: CliError) -> ! {
+    report(&error);
+    std::process::exit(error.kind.exit_code());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes_are_stable() {
+        let codes: Vec<i32> = [
+            ErrorKind::Failure,
+            ErrorKind::VerificationFailed,
+            ErrorKind::Network,
+            ErrorKind::Key,
+            ErrorKind::InvalidInput,
+        ]
+        .iter()
+        .map(|kind| kind.exit_code())
+        .collect();
+        assert_eq!(codes, vec![1, 2, 3, 4, 5]);
+        assert_eq!(serde_json::to_value(ErrorKind::Network).unwrap(), "NETWORK_ERROR");
+        assert_eq!(serde_json::to_value(ErrorKind::InvalidInput).unwrap(), "INVALID_INPUT");
+    }
+
+    #[test]
+    fn json_output_is_detected_before_parsing() {
+        let args = |line: &str| line.split(' ').map(str::to_string).collect::<Vec<_>>();
+
+        assert!(json_requested(&args("cli verify --output json")));
+        assert!(json_requested(&args("cli -o json verify")));
+        assert!(json_requested(&args("cli verify --output=json")));
+        assert!(!json_requested(&args("cli verify --output text json")));
+        assert!(!json_requested(&args("cli send --msg json")));
+    }
+}
@@ -1,10 +1,41 @@
 // src/main.rs
 
-use clap::{Parser, Subcommand, ValueEnum};
-use proof_messenger_protocol::key::{generate_keypair, generate_keypair_with_seed};
-use proof_messenger_protocol::proof::{make_proof, Invite};
+mod contacts;
+mod exit;
+mod qr;
+mod repl;
+mod signer;
+mod watch;
+
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use ed25519_dalek::PublicKey;
+use proof_messenger_protocol::key::{
+    generate_keypair, generate_keypair_with_seed, generate_mnemonic, generate_secure_keypair,
+    generate_secure_keypair_with_seed, SecureKeypair,
+};
+use proof_messenger_protocol::hybrid::{
+    make_hybrid_proof, verify_hybrid_proof, HybridKeypair, HybridPolicy, HybridPublicKey,
+    HybridSignature,
+};
+use proof_messenger_protocol::context_digest::{digest_signing_input, ContextHasher};
+use proof_messenger_protocol::detached::{
+    detached_context, digest_document, DetachedProof, DigestAlgorithm, DocumentMetadata,
+};
+use proof_messenger_protocol::evidence::{verify_evidence_bundle, EvidenceBundle};
+use proof_messenger_protocol::invite::{InviteError, InviteUri};
+use proof_messenger_protocol::proof::{make_proof, verify_proof, Invite};
+use proof_messenger_protocol::receipt::{make_receipt, message_hash, receipt_context, Receipt};
+use proof_messenger_protocol::relay_client::{RelayClient, RetryPolicy};
+#[cfg(unix)]
+use proof_messenger_protocol::remote_signer::SignerDaemon;
+use proof_messenger_protocol::render::render_context;
+use contacts::{config_dir, Contact, ContactBook, Fingerprint, TrustState};
+use exit::{CliError, ErrorKind};
 use serde::Serialize;
-use std::fs;
+use signer::{CardInterface, HardwareKey, KeystoreEntry, RemoteKey, Signer, SignerKind};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Output format for CLI commands
 #[derive(ValueEnum, Clone, Debug)]
@@ -29,6 +60,14 @@ struct Cli {
     #[arg(short, long, global = true, default_value_t = OutputFormat::Text)]
     output: OutputFormat,
     
+    /// Keystore file holding a keypair or a hardware slot reference
+    #[arg(long, global = true, default_value = "keypair.json")]
+    keystore: PathBuf,
+    
+    /// Directory holding the contact book (defaults to ~/.config/proof-messenger)
+    #[arg(long, global = true)]
+    config_dir: Option<PathBuf>,
+    
     #[command(subcommand)]
     command: Commands,
 }
@@ -36,28 +75,241 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Generate a new keypair and save it
-    Keygen,
+    Keygen {
+        /// Where to create the key: the keystore file, a YubiKey slot or a signer daemon
+        #[arg(long, value_enum, default_value_t = SignerKind::File)]
+        signer: SignerKind,
+        /// YubiKey applet to use with --signer yubikey
+        #[arg(long, value_enum, default_value_t = CardInterface::Piv)]
+        interface: CardInterface,
+        /// YubiKey slot (defaults to 9c for PIV, OPENPGP.1 for OpenPGP)
+        #[arg(long)]
+        slot: Option<String>,
+        /// Signer daemon socket to use with --signer remote
+        #[arg(long, required_if_eq("signer", "remote"))]
+        socket: Option<PathBuf>,
+        /// Id of the daemon's key to use with --signer remote
+        #[arg(long, default_value = "default")]
+        key_id: String,
+        /// Derive the key from a new 24-word backup phrase and print the phrase
+        #[arg(long)]
+        mnemonic: bool,
+    },
+    /// Back up and recover the keystore key
+    Key {
+        #[command(subcommand)]
+        command: KeyCommands,
+    },
     /// Generate an invite with optional seed
     Invite {
         #[arg(long)]
         seed: Option<u64>,
+        /// Group the invite is for, carried in the invite URI
+        #[arg(long)]
+        group: Option<String>,
+        /// Relay the invitee should use, carried in the invite URI
+        #[arg(long)]
+        relay: Option<String>,
+        /// Show the invite URI as a QR code, or write it to the given PNG file
+        #[arg(long, value_name = "PNG", num_args = 0..=1, default_missing_value = "-")]
+        qr: Option<PathBuf>,
     },
     /// Create onboarding proof for an invite
     Onboard {
-        invite_seed: u64,
+        #[arg(required_unless_present = "invite_uri")]
+        invite_seed: Option<u64>,
+        /// Onboard from a pm://invite URI instead of a seed
+        #[arg(long, conflicts_with = "invite_seed")]
+        invite_uri: Option<String>,
+        /// Also sign with a post-quantum key (Ed25519 + ML-DSA-65 hybrid proof)
+        #[arg(long)]
+        hybrid: bool,
+        /// Sign with the keystore key instead of a fresh one
+        #[arg(long, value_enum, conflicts_with = "hybrid")]
+        signer: Option<SignerKind>,
     },
     /// Send a message to a recipient
     Send {
-        #[arg(long)]
+        /// Recipient: a contact name or a public key (hex encoded)
+        #[arg(long, visible_alias = "to")]
         to_pubkey: String,
         #[arg(long)]
         msg: String,
+        /// Sign the message with the keystore key
+        #[arg(long, value_enum)]
+        signer: Option<SignerKind>,
     },
     /// Verify a proof against an invite
     Verify {
         proof: String,
         invite_seed: u64,
-    }
+    },
+    /// Verify a hybrid (Ed25519 + ML-DSA-65) proof against an invite
+    VerifyHybrid {
+        /// Hybrid proof (hex encoded)
+        proof: String,
+        /// Hybrid public key of the signer (hex encoded)
+        public_key: String,
+        invite_seed: u64,
+        /// Verification policy: strict (both signatures) or transitional (Ed25519 required, PQC optional)
+        #[arg(long, default_value_t = HybridPolicy::Strict)]
+        policy: HybridPolicy,
+    },
+    /// Sign a delivery receipt for a relayed message
+    Receipt {
+        /// Relay-assigned message id
+        message_id: String,
+        /// Public key of the message sender (hex encoded)
+        #[arg(long)]
+        sender: String,
+        /// Signed context of the message (hex encoded)
+        #[arg(long)]
+        context: String,
+        /// Message body
+        #[arg(long)]
+        body: String,
+        /// Kind of keystore key to sign with (the keystore file by default)
+        #[arg(long, value_enum, conflicts_with = "seed")]
+        signer: Option<SignerKind>,
+        /// Seed for a deterministic recipient keypair instead of the keystore key
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Sign a detached proof over a file without sending the file anywhere
+    ProveFile {
+        /// File to approve
+        path: PathBuf,
+        /// Digest algorithm: sha256 or blake3
+        #[arg(long, default_value = "sha256")]
+        algorithm: DigestAlgorithm,
+        /// MIME type of the file (guessed from the extension by default)
+        #[arg(long)]
+        content_type: Option<String>,
+        /// Where to write the proof (defaults to <path>.proof.json)
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Sign with the keystore key instead of a fresh one
+        #[arg(long, value_enum, conflicts_with = "seed")]
+        signer: Option<SignerKind>,
+        /// Seed for a deterministic signing keypair
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Sign the streaming BLAKE3 digest of a large context
+    SignContext {
+        /// File holding the context, or - for standard input
+        path: PathBuf,
+        /// Sign with the keystore key instead of a fresh one
+        #[arg(long, value_enum, conflicts_with = "seed")]
+        signer: Option<SignerKind>,
+        /// Seed for a deterministic signing keypair
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Manage named contacts and how far their keys are trusted
+    Contact {
+        #[command(subcommand)]
+        command: ContactCommands,
+    },
+    /// Verify a detached proof against a file
+    VerifyFile {
+        /// File the proof is for
+        path: PathBuf,
+        /// Proof file (defaults to <path>.proof.json)
+        #[arg(long)]
+        proof: Option<PathBuf>,
+        /// Only accept proofs signed by this public key (hex encoded)
+        #[arg(long)]
+        public_key: Option<String>,
+    },
+    /// Verify a relay evidence bundle offline
+    VerifyBundle {
+        /// Bundle file, as returned by the relay's GET /message/:message_id/evidence
+        path: PathBuf,
+        /// Only accept bundles signed by this relay log key (hex encoded)
+        #[arg(long)]
+        relay_key: Option<String>,
+        /// Locale for the approval summary of recognized contexts (e.g. en, de-DE)
+        #[arg(long, default_value = "en")]
+        locale: String,
+    },
+    /// Start an interactive session that keeps the keystore identity loaded
+    Repl {
+        /// Signer to load once for the whole session
+        #[arg(long, value_enum, default_value = "file")]
+        signer: SignerKind,
+    },    /// Print a group's messages as the relay verifies them
+    Watch {
+        /// Group to watch
+        #[arg(long)]
+        group: String,
+        /// Relay base URL (e.g. https://relay.example.com)
+        #[arg(long)]
+        relay_url: String,
+        /// OAuth bearer token, for relays that require authentication
+        #[arg(long)]
+        token: Option<String>,
+        /// Seconds each long poll waits for new messages
+        #[arg(long, default_value_t = 30)]
+        poll_timeout: u64,
+        /// Exit after printing this many messages
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Serve the keystore key to `--signer remote` clients over a Unix socket
+    SignerDaemon {
+        /// Socket to listen on; it is created readable by its owner only
+        #[arg(long)]
+        socket: PathBuf,
+        /// Id clients use to ask for the key
+        #[arg(long, default_value = "default")]
+        key_id: String,
+    },
+    /// Print a shell completion script for every command
+    Completions {
+        shell: Shell,
+    },
+    /// Print the man page, or write pages for every command to a directory
+    Man {
+        /// Write `proof-messenger-cli.1` and one page per subcommand here
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeyCommands {
+    /// Recreate the keystore key from its backup phrase
+    Recover {
+        /// Backup phrase (read from stdin when omitted, keeping it out of shell history)
+        #[arg(long)]
+        phrase: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ContactCommands {
+    /// Add a contact; it stays unverified until its fingerprint is compared
+    Add {
+        name: String,
+        /// Contact's public key (hex encoded)
+        public_key: String,
+        /// Replace the key of an existing contact, resetting its trust
+        #[arg(long)]
+        replace: bool,
+    },
+    /// List contacts and their trust state
+    List,
+    /// Show a contact's fingerprint, or mark it verified once compared
+    Verify {
+        name: String,
+        /// Fingerprint the contact reads out (hex or words); marks the contact verified if it matches
+        #[arg(long, conflicts_with = "confirm")]
+        fingerprint: Option<String>,
+        /// Mark the contact verified after comparing fingerprints yourself
+        #[arg(long)]
+        confirm: bool,
+    },
 }
 
 // JSON output structures for each command
@@ -69,6 +321,12 @@ struct KeygenOutput {
     public_key_hex: String,
     #[serde(rename = "keypairFile")]
     keypair_file: String,
+    #[serde(rename = "hardwareSlot", skip_serializing_if = "Option::is_none")]
+    hardware_slot: Option<String>,
+    #[serde(rename = "remoteKey", skip_serializing_if = "Option::is_none")]
+    remote_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mnemonic: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -79,6 +337,10 @@ struct InviteOutput {
     #[serde(rename = "publicKeyHex")]
     public_key_hex: String,
     seed: u64,
+    #[serde(rename = "inviteUri")]
+    invite_uri: String,
+    #[serde(rename = "qrFile", skip_serializing_if = "Option::is_none")]
+    qr_file: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -88,8 +350,14 @@ struct OnboardOutput {
     proof_hex: String,
     #[serde(rename = "publicKeyHex")]
     public_key_hex: String,
-    #[serde(rename = "inviteSeed")]
-    invite_seed: u64,
+    #[serde(rename = "inviteSeed", skip_serializing_if = "Option::is_none")]
+    invite_seed: Option<u64>,
+    #[serde(rename = "inviteGroup", skip_serializing_if = "Option::is_none")]
+    invite_group: Option<String>,
+    #[serde(rename = "hybridProofHex", skip_serializing_if = "Option::is_none")]
+    hybrid_proof_hex: Option<String>,
+    #[serde(rename = "hybridPublicKeyHex", skip_serializing_if = "Option::is_none")]
+    hybrid_public_key_hex: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -97,6 +365,14 @@ struct SendOutput {
     status: String,
     message: String,
     recipient: String,
+    #[serde(rename = "proofHex", skip_serializing_if = "Option::is_none")]
+    proof_hex: Option<String>,
+    #[serde(rename = "senderHex", skip_serializing_if = "Option::is_none")]
+    sender_hex: Option<String>,
+    #[serde(rename = "recipientName", skip_serializing_if = "Option::is_none")]
+    recipient_name: Option<String>,
+    #[serde(rename = "recipientTrust", skip_serializing_if = "Option::is_none")]
+    recipient_trust: Option<TrustState>,
 }
 
 #[derive(Serialize)]
@@ -108,133 +384,1051 @@ struct VerifyOutput {
     invite_seed: u64,
 }
 
+#[derive(Serialize)]
+struct VerifyHybridOutput {
+    status: String,
+    verified: bool,
+    policy: String,
+    #[serde(rename = "inviteSeed")]
+    invite_seed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ReceiptOutput {
+    status: String,
+    #[serde(rename = "messageId")]
+    message_id: String,
+    #[serde(rename = "messageHashHex")]
+    message_hash_hex: String,
+    #[serde(rename = "recipientHex")]
+    recipient_hex: String,
+    #[serde(rename = "signatureHex")]
+    signature_hex: String,
+}
+
+#[derive(Serialize)]
+struct ProveFileOutput {
+    status: String,
+    #[serde(rename = "proofFile")]
+    proof_file: String,
+    proof: DetachedProof,
+}
+
+#[derive(Serialize)]
+struct SignContextOutput {
+    status: String,
+    #[serde(rename = "contextLength")]
+    context_length: u64,
+    #[serde(rename = "digestHex")]
+    digest_hex: String,
+    #[serde(rename = "publicKeyHex")]
+    public_key_hex: String,
+    #[serde(rename = "signatureHex")]
+    signature_hex: String,
+}
+
+#[derive(Serialize)]
+struct VerifyFileOutput {
+    status: String,
+    verified: bool,
+    #[serde(rename = "publicKeyHex")]
+    public_key_hex: String,
+    filename: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct VerifyBundleOutput {
+    status: String,
+    verified: bool,
+    #[serde(rename = "messageId")]
+    message_id: String,
+    #[serde(rename = "senderHex")]
+    sender_hex: String,
+    #[serde(rename = "relayPublicKeyHex")]
+    relay_public_key_hex: String,
+    #[serde(rename = "treeSize")]
+    tree_size: u64,
+    revoked: bool,
+    #[serde(rename = "exportedAt")]
+    exported_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct FingerprintOutput {
+    hex: String,
+    emoji: String,
+    words: String,
+}
+
+impl From<Fingerprint> for FingerprintOutput {
+    fn from(fingerprint: Fingerprint) -> Self {
+        Self {
+            hex: fingerprint.hex(),
+            emoji: fingerprint.emoji(),
+            words: fingerprint.words(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ContactOutput {
+    name: String,
+    #[serde(rename = "publicKeyHex")]
+    public_key_hex: String,
+    trust: TrustState,
+    fingerprint: FingerprintOutput,
+}
+
+impl ContactOutput {
+    fn new(name: &str, contact: &Contact) -> Self {
+        Self {
+            name: name.to_string(),
+            public_key_hex: contact.public_key.clone(),
+            trust: contact.trust,
+            fingerprint: contact.fingerprint().into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ContactCommandOutput {
+    status: String,
+    contact: ContactOutput,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matched: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct ContactListOutput {
+    status: String,
+    contacts: Vec<ContactOutput>,
+}
+
+/// Report an error and exit with the code of its kind
+fn fail(kind: ErrorKind, message: impl Into<String>) -> ! {
+    exit::exit_with(CliError::new(kind, message))
+}
+
+/// Exit with the verification failure code once the result is printed
+fn exit_unverified() -> ! {
+    std::process::exit(ErrorKind::VerificationFailed.exit_code())
+}
+
+/// Load the signer configured on the command line
+fn load_signer(kind: SignerKind, keystore: &Path) -> Signer {
+    Signer::load(kind, keystore).unwrap_or_else(|e| fail(ErrorKind::Key, e))
+}
+
+/// Load the contact book from the configured directory
+fn load_contacts(explicit: Option<&Path>) -> (PathBuf, ContactBook) {
+    let dir = config_dir(explicit).unwrap_or_else(|e| fail(ErrorKind::Failure, e));
+    let book = ContactBook::load(&dir).unwrap_or_else(|e| fail(ErrorKind::InvalidInput, e));
+    (dir, book)
+}
+
+/// Print a contact and its fingerprint for comparison
+fn print_contact(name: &str, contact: &Contact) {
+    let fingerprint = contact.fingerprint();
+    let trust = match contact.trust {
+        TrustState::Verified => "✅ verified",
+        TrustState::Unverified => "⚠️  unverified",
+    };
+    println!("   {} ({})", name, trust);
+    println!("     Public Key:  {}", contact.public_key);
+    println!("     Fingerprint: {}", fingerprint.hex());
+    println!("     Emoji:       {}", fingerprint.emoji());
+    println!("     Words:       {}", fingerprint.words());
+}
+
+/// Default location of a file's detached proof
+fn default_proof_path(path: &Path) -> PathBuf {
+    let mut proof_path = path.as_os_str().to_owned();
+    proof_path.push(".proof.json");
+    PathBuf::from(proof_path)
+}
+
+/// Guess a file's MIME type from its extension
+fn guess_content_type(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    match extension.as_str() {
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "txt" => "text/plain",
+        "html" | "htm" => "text/html",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Check a detached proof against a file and, optionally, an expected signer
+fn verify_file(path: &Path, proof: &DetachedProof, public_key: Option<&str>) -> Result<(), String> {
+    if let Some(expected) = public_key {
+        let expected = hex::decode(expected).map_err(|e| format!("Invalid public key hex: {}", e))?;
+        if expected != proof.public_key {
+            return Err("Proof is signed by a different key".to_string());
+        }
+    }
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    proof.verify_document(std::io::BufReader::new(file)).map_err(|e| e.to_string())
+}
+
+/// Check an evidence bundle and, optionally, the relay key that signed it
+fn verify_bundle(bundle: &EvidenceBundle, relay_key: Option<&str>) -> Result<(), String> {
+    let relay_key = relay_key
+        .map(|key| {
+            let bytes = hex::decode(key).map_err(|e| format!("Invalid relay key hex: {}", e))?;
+            PublicKey::from_bytes(&bytes).map_err(|e| format!("Invalid relay key: {}", e))
+        })
+        .transpose()?;
+    verify_evidence_bundle(bundle, relay_key.as_ref()).map_err(|e| e.to_string())
+}
+
+/// Format milliseconds since the Unix epoch as RFC 3339
+fn format_millis(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_else(|| millis.to_string())
+}
+
+/// Generate an invite and print it, optionally as a QR code
+fn invite(output: &OutputFormat, seed: Option<u64>, group: Option<&str>, relay: Option<&str>, qr: Option<&Path>) -> Result<(), CliError> {
+    let seed = seed.unwrap_or(42);
+    let keypair = generate_keypair_with_seed(seed);
+    let invite = Invite::new_with_seed(seed + 1);
+    let mut uri = InviteUri::new(invite.data.clone(), keypair.public.to_bytes());
+    if let Some(group) = group {
+        uri = uri.with_group(group);
+    }
+    if let Some(relay) = relay {
+        uri = uri.with_relay(relay);
+    }
+    let invite_uri = uri.to_string();
+    
+    // "-" draws the code in the terminal; anything else is a PNG path
+    let qr_file = qr.filter(|path| *path != Path::new("-"));
+    let terminal_qr = match (qr, qr_file) {
+        (Some(_), None) => Some(qr::render_terminal(&invite_uri).map_err(|e| CliError::new(ErrorKind::InvalidInput, e))?),
+        _ => None,
+    };
+    if let Some(path) = qr_file {
+        qr::write_png(&invite_uri, path).map_err(|e| CliError::new(ErrorKind::Failure, e))?;
+    }
+    
+    match output {
+        OutputFormat::Json => {
+            // Keep stdout parseable; the terminal QR code goes to stderr
+            if let Some(terminal_qr) = terminal_qr {
+                eprintln!("{}", terminal_qr);
+            }
+            let output_data = InviteOutput {
+                status: "success".to_string(),
+                invite_data: hex::encode(&invite.data),
+                public_key_hex: hex::encode(keypair.public.to_bytes()),
+                seed,
+                invite_uri,
+                qr_file: qr_file.map(|path| path.display().to_string()),
+            };
+            println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
+        }
+        OutputFormat::Text => {
+            println!("✅ Invite generated successfully!");
+            println!("   Seed: {}", seed);
+            println!("   Invite Data: {}", hex::encode(&invite.data));
+            println!("   Public Key: {}", hex::encode(keypair.public.to_bytes()));
+            println!("   Invite URI: {}", invite_uri);
+            if let Some(path) = qr_file {
+                println!("   QR Code: {}", path.display());
+            }
+            if let Some(terminal_qr) = terminal_qr {
+                println!("{}", terminal_qr);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prepare a message for a recipient, signed when a signer is given
+fn send(output: &OutputFormat, contacts: &ContactBook, signer: Option<&Signer>, to_pubkey: &str, msg: &str) -> Result<(), CliError> {
+    let (recipient, contact) = contacts.resolve(to_pubkey);
+    let (recipient_name, recipient_trust) = contact
+        .map(|(name, contact)| (name.to_string(), contact.trust))
+        .unzip();
+    let signed = signer
+        .map(|signer| -> Result<_, String> {
+            let sender = signer.public_key()?;
+            let proof = signer.sign(msg.as_bytes())?;
+            Ok((hex::encode(proof.to_bytes()), hex::encode(sender.to_bytes())))
+        })
+        .transpose()
+        .map_err(|e| CliError::new(ErrorKind::Key, e))?;
+    let (proof_hex, sender_hex) = signed.unzip();
+    
+    match output {
+        OutputFormat::Json => {
+            let output_data = SendOutput {
+                status: "success".to_string(),
+                message: msg.to_string(),
+                recipient,
+                proof_hex,
+                sender_hex,
+                recipient_name,
+                recipient_trust,
+            };
+            println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
+        }
+        OutputFormat::Text => {
+            println!("✅ Message prepared for sending!");
+            match (&recipient_name, recipient_trust) {
+                (Some(name), Some(TrustState::Verified)) => println!("   To: {} ({}, verified)", name, recipient),
+                (Some(name), _) => {
+                    println!("   To: {} ({})", name, recipient);
+                    println!("   ⚠️  {}'s key is unverified; compare fingerprints with `contact verify {}`", name, name);
+                }
+                (None, _) => println!("   To: {}", recipient),
+            }
+            println!("   Message: '{}'", msg);
+            if let (Some(proof_hex), Some(sender_hex)) = (proof_hex, sender_hex) {
+                println!("   Proof: {}", proof_hex);
+                println!("   Sender: {}", sender_hex);
+            }
+            println!("   Note: In a real app, this would connect to the relay server");
+        }
+    }
+    Ok(())
+}
+
+/// Check and print an onboarding proof against the invite generated from a seed
+fn verify(output: &OutputFormat, proof: &str, invite_seed: u64) -> bool {
+    let keypair = generate_keypair_with_seed(invite_seed);
+    let invite = Invite::new_with_seed(invite_seed);
+    
+    // The proof must be the seeded keypair's signature over the invite data
+    let verified = hex::decode(proof)
+        .ok()
+        .and_then(|bytes| ed25519_dalek::Signature::from_bytes(&bytes).ok())
+        .is_some_and(|signature| verify_proof(&signature, &keypair.public, &invite));
+    
+    match output {
+        OutputFormat::Json => {
+            let output_data = VerifyOutput {
+                status: if verified { "success" } else { "failed" }.to_string(),
+                verified,
+                proof: proof.to_string(),
+                invite_seed,
+            };
+            println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
+        }
+        OutputFormat::Text => {
+            println!("{} Verification completed!", if verified { "✅" } else { "❌" });
+            println!("   Proof: {}", proof);
+            println!("   Invite Seed: {}", invite_seed);
+            println!("   Verified: {}", if verified { "✅ Yes" } else { "❌ No" });
+            println!("   Generated Public Key: {}", hex::encode(keypair.public.to_bytes()));
+            println!("   Invite Data: {}", hex::encode(&invite.data));
+        }
+    }
+    verified
+}
+
+/// Print the contact book
+fn list_contacts(output: &OutputFormat, contacts: &ContactBook) {
+    match output {
+        OutputFormat::Json => {
+            let output_data = ContactListOutput {
+                status: "success".to_string(),
+                contacts: contacts.contacts.iter().map(|(name, contact)| ContactOutput::new(name, contact)).collect(),
+            };
+            println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
+        }
+        OutputFormat::Text => {
+            if contacts.contacts.is_empty() {
+                println!("No contacts yet; add one with `contact add <name> <public-key>`");
+            }
+            for (name, contact) in &contacts.contacts {
+                print_contact(name, contact);
+            }
+        }
+    }
+}
+
+/// Serve the keystore key on `socket` until the process is stopped
+#[cfg(unix)]
+fn signer_daemon(output: &OutputFormat, keystore: &Path, socket: &Path, key_id: &str) -> Result<(), CliError> {
+    // The daemon holds the key itself, so it only serves file keys
+    let keypair = match Signer::load(SignerKind::File, keystore).map_err(|e| CliError::new(ErrorKind::Key, e))? {
+        Signer::File(keypair) => keypair,
+        _ => unreachable!("file signers load raw keypairs"),
+    };
+    let public_key_hex = hex::encode(keypair.public_key_bytes());
+    let listener = SignerDaemon::bind(socket)
+        .map_err(|e| CliError::new(ErrorKind::Failure, format!("Failed to listen on {}: {}", socket.display(), e)))?;
+    let daemon = SignerDaemon::new().with_key(key_id, keypair);
+
+    match output {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({"status": "listening", "socket": socket.display().to_string(), "keyId": key_id, "publicKeyHex": public_key_hex})
+        ),
+        OutputFormat::Text => {
+            println!("✅ Signer daemon listening on {}", socket.display());
+            println!("   Key Id: {}", key_id);
+            println!("   Public Key: {}", public_key_hex);
+        }
+    }
+    daemon
+        .serve(&listener)
+        .map_err(|e| CliError::new(ErrorKind::Failure, format!("Signer daemon stopped: {}", e)))
+}
+
+#[cfg(not(unix))]
+fn signer_daemon(_output: &OutputFormat, _keystore: &Path, _socket: &Path, _key_id: &str) -> Result<(), CliError> {
+    Err(CliError::new(ErrorKind::Failure, "The signer daemon needs Unix sockets, which this platform lacks"))
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let args: Vec<String> = std::env::args().collect();
+    let cli = Cli::try_parse_from(&args).unwrap_or_else(|e| {
+        // Help and version requests are not errors
+        if !e.use_stderr() {
+            e.exit();
+        }
+        if exit::json_requested(&args) {
+            exit::use_json_reports(true);
+            let rendered = e.render().to_string();
+            let message = rendered.lines().next().unwrap_or_default().trim_start_matches("error: ");
+            exit::exit_with(CliError::new(ErrorKind::InvalidInput, message));
+        }
+        let _ = e.print();
+        std::process::exit(ErrorKind::InvalidInput.exit_code());
+    });
+    exit::use_json_reports(matches!(cli.output, OutputFormat::Json));
+    let file_path = cli.keystore.display().to_string();
     
     match &cli.command {
-        Commands::Keygen => {
-            let keypair = generate_keypair();
-            let keypair_bytes = keypair.to_bytes();
-            let file_path = "keypair.json";
+        Commands::Keygen { signer, interface, slot, socket, key_id, mnemonic } => {
+            if *mnemonic && !matches!(signer, SignerKind::File) {
+                fail(ErrorKind::InvalidInput, format!("A {} key cannot be backed up; --mnemonic needs --signer file", signer));
+            }
+            let phrase = mnemonic.then(|| generate_mnemonic(24).unwrap_or_else(|e| fail(ErrorKind::Key, e.to_string())));
             
-            // Save keypair to file (convert to Vec for serialization)
-            let keypair_vec: Vec<u8> = keypair_bytes.to_vec();
-            fs::write(file_path, serde_json::to_string(&keypair_vec).unwrap())
-                .expect("Failed to write keypair file");
+            // A hardware or daemon key stays where it is; the keystore only references it
+            let (entry, public_key_hex, hardware_slot, remote_key) = match (signer, &phrase) {
+                (SignerKind::File, Some(phrase)) => {
+                    let keypair = SecureKeypair::from_mnemonic(phrase, "").unwrap_or_else(|e| fail(ErrorKind::Key, e.to_string()));
+                    let public_key_hex = hex::encode(keypair.public_key_bytes());
+                    (KeystoreEntry::Keypair(keypair.to_bytes().to_vec()), public_key_hex, None, None)
+                }
+                (SignerKind::File, None) => {
+                    let keypair = generate_keypair();
+                    let public_key_hex = hex::encode(keypair.public.to_bytes());
+                    (KeystoreEntry::Keypair(keypair.to_bytes().to_vec()), public_key_hex, None, None)
+                }
+                (SignerKind::Yubikey, _) => {
+                    let slot = slot.as_deref().unwrap_or(interface.default_slot());
+                    let hardware = HardwareKey::enroll(*interface, slot).unwrap_or_else(|e| fail(ErrorKind::Key, e));
+                    let public_key_hex = hardware.public_key.clone();
+                    let hardware_slot = format!("{}:{}", hardware.interface, hardware.slot);
+                    (KeystoreEntry::Hardware { hardware }, public_key_hex, Some(hardware_slot), None)
+                }
+                (SignerKind::Remote, _) => {
+                    let socket = socket.as_deref().expect("clap requires --socket with --signer remote");
+                    let remote = RemoteKey::enroll(socket, key_id).unwrap_or_else(|e| fail(ErrorKind::Key, e));
+                    let public_key_hex = remote.public_key.clone();
+                    let remote_key = format!("{}#{}", remote.socket.display(), remote.key_id);
+                    (KeystoreEntry::Remote { remote }, public_key_hex, None, Some(remote_key))
+                }
+            };
+            entry.save(&cli.keystore).unwrap_or_else(|e| fail(ErrorKind::Failure, e));
             
             // Output based on format
             match cli.output {
                 OutputFormat::Json => {
                     let output_data = KeygenOutput {
                         status: "success".to_string(),
-                        public_key_hex: hex::encode(keypair.public.to_bytes()),
-                        keypair_file: file_path.to_string(),
+                        public_key_hex,
+                        keypair_file: file_path,
+                        hardware_slot,
+                        remote_key,
+                        mnemonic: phrase,
                     };
                     println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
                 }
                 OutputFormat::Text => {
                     println!("✅ Keypair generated successfully!");
-                    println!("   Public Key: {}", hex::encode(keypair.public.to_bytes()));
+                    println!("   Public Key: {}", public_key_hex);
+                    if let Some(hardware_slot) = hardware_slot {
+                        println!("   YubiKey Slot: {}", hardware_slot);
+                    }
+                    if let Some(remote_key) = remote_key {
+                        println!("   Signer Daemon Key: {}", remote_key);
+                    }
                     println!("   Saved to: {}", file_path);
+                    if let Some(phrase) = phrase {
+                        println!("   Backup Phrase: {}", phrase);
+                        println!("⚠️  Write the backup phrase down and keep it offline; anyone who has it can sign as you.");
+                        println!("   Restore the key with `key recover`.");
+                    }
                 }
             }
         }
         
-        Commands::Invite { seed } => {
-            let seed = seed.unwrap_or(42);
-            let keypair = generate_keypair_with_seed(seed);
-            let invite = Invite::new_with_seed(seed + 1);
+        Commands::Key { command: KeyCommands::Recover { phrase } } => {
+            let phrase = phrase.clone().unwrap_or_else(|| {
+                if matches!(cli.output, OutputFormat::Text) {
+                    eprintln!("Enter the backup phrase:");
+                }
+                let mut line = String::new();
+                std::io::stdin()
+                    .read_line(&mut line)
+                    .unwrap_or_else(|e| fail(ErrorKind::InvalidInput, format!("Failed to read the backup phrase: {}", e)));
+                line
+            });
+            let keypair = SecureKeypair::from_mnemonic(&phrase, "").unwrap_or_else(|e| fail(ErrorKind::InvalidInput, e.to_string()));
+            let public_key_hex = hex::encode(keypair.public_key_bytes());
+            KeystoreEntry::Keypair(keypair.to_bytes().to_vec())
+                .save(&cli.keystore)
+                .unwrap_or_else(|e| fail(ErrorKind::Failure, e));
             
             match cli.output {
                 OutputFormat::Json => {
-                    let output_data = InviteOutput {
+                    let output_data = KeygenOutput {
                         status: "success".to_string(),
-                        invite_data: hex::encode(&invite.data),
-                        public_key_hex: hex::encode(keypair.public.to_bytes()),
-                        seed,
+                        public_key_hex,
+                        keypair_file: file_path,
+                        hardware_slot: None,
+                        remote_key: None,
+                        mnemonic: None,
                     };
                     println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
                 }
                 OutputFormat::Text => {
-                    println!("✅ Invite generated successfully!");
-                    println!("   Seed: {}", seed);
-                    println!("   Invite Data: {}", hex::encode(&invite.data));
-                    println!("   Public Key: {}", hex::encode(keypair.public.to_bytes()));
+                    println!("✅ Keypair recovered successfully!");
+                    println!("   Public Key: {}", public_key_hex);
+                    println!("   Saved to: {}", file_path);
                 }
             }
         }
         
-        Commands::Onboard { invite_seed } => {
-            let keypair = generate_keypair();
-            let invite = Invite::new_with_seed(*invite_seed);
-            let proof = make_proof(&keypair, &invite);
+        Commands::Invite { seed, group, relay, qr } => {
+            invite(&cli.output, *seed, group.as_deref(), relay.as_deref(), qr.as_deref()).unwrap_or_else(|e| exit::exit_with(e));
+        }
+        
+        Commands::Onboard { invite_seed, invite_uri, hybrid, signer } => {
+            let invite_uri = invite_uri.as_deref().map(|uri| {
+                let uri: InviteUri = uri.parse().unwrap_or_else(|e: InviteError| fail(ErrorKind::InvalidInput, e.to_string()));
+                if uri.is_expired(chrono::Utc::now()) {
+                    fail(ErrorKind::InvalidInput, "Invite has expired");
+                }
+                uri
+            });
+            let invite = match &invite_uri {
+                Some(uri) => uri.to_invite(),
+                None => Invite::new_with_seed(invite_seed.expect("clap requires a seed or an invite URI")),
+            };
+            let invite_group = invite_uri.and_then(|uri| uri.group_id);
+            
+            // A hybrid onboarding keeps the Ed25519 proof for existing verifiers
+            // and additionally emits the dual-signature proof
+            let (proof, public_key, hybrid_output) = if let Some(kind) = signer {
+                let signer = load_signer(*kind, &cli.keystore);
+                let public_key = signer.public_key().unwrap_or_else(|e| fail(ErrorKind::Key, e));
+                (signer.sign(&invite.data).unwrap_or_else(|e| fail(ErrorKind::Key, e)), public_key, None)
+            } else if *hybrid {
+                let keypair = HybridKeypair::generate();
+                let hybrid_proof = make_hybrid_proof(&keypair, &invite.data)
+                    .unwrap_or_else(|e| fail(ErrorKind::InvalidInput, e.to_string()));
+                let public_key = keypair.public_key();
+                (
+                    hybrid_proof.ed25519,
+                    public_key.ed25519,
+                    Some((hex::encode(hybrid_proof.to_bytes()), hex::encode(public_key.to_bytes()))),
+                )
+            } else {
+                let keypair = generate_keypair();
+                (make_proof(&keypair, &invite), keypair.public, None)
+            };
+            let (hybrid_proof_hex, hybrid_public_key_hex) = hybrid_output.unzip();
             
             match cli.output {
                 OutputFormat::Json => {
                     let output_data = OnboardOutput {
                         status: "success".to_string(),
                         proof_hex: hex::encode(proof.to_bytes()),
-                        public_key_hex: hex::encode(keypair.public.to_bytes()),
+                        public_key_hex: hex::encode(public_key.to_bytes()),
                         invite_seed: *invite_seed,
+                        invite_group,
+                        hybrid_proof_hex,
+                        hybrid_public_key_hex,
                     };
                     println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
                 }
                 OutputFormat::Text => {
                     println!("✅ Onboarding proof generated successfully!");
-                    println!("   Invite Seed: {}", invite_seed);
+                    if let Some(invite_seed) = invite_seed {
+                        println!("   Invite Seed: {}", invite_seed);
+                    }
+                    if let Some(group) = &invite_group {
+                        println!("   Group: {}", group);
+                    }
                     println!("   Proof: {}", hex::encode(proof.to_bytes()));
-                    println!("   Public Key: {}", hex::encode(keypair.public.to_bytes()));
+                    println!("   Public Key: {}", hex::encode(public_key.to_bytes()));
+                    if let (Some(proof_hex), Some(key_hex)) = (hybrid_proof_hex, hybrid_public_key_hex) {
+                        println!("   Hybrid Proof: {}", proof_hex);
+                        println!("   Hybrid Public Key: {}", key_hex);
+                    }
                 }
             }
         }
         
-        Commands::Send { to_pubkey, msg } => {
+        Commands::Send { to_pubkey, msg, signer } => {
+            let (_, contacts) = load_contacts(cli.config_dir.as_deref());
+            let signer = signer.map(|kind| load_signer(kind, &cli.keystore));
+            send(&cli.output, &contacts, signer.as_ref(), to_pubkey, msg).unwrap_or_else(|e| exit::exit_with(e));
+        }
+        
+        Commands::Verify { proof, invite_seed } => {
+            if !verify(&cli.output, proof, *invite_seed) {
+                exit_unverified();
+            }
+        }
+        
+        Commands::VerifyHybrid { proof, public_key, invite_seed, policy } => {
+            let invite = Invite::new_with_seed(*invite_seed);
+            
+            let result = hex::decode(proof)
+                .map_err(|e| format!("Invalid proof hex: {}", e))
+                .and_then(|bytes| HybridSignature::from_bytes(&bytes).map_err(|e| e.to_string()))
+                .and_then(|signature| {
+                    let key_bytes = hex::decode(public_key)
+                        .map_err(|e| format!("Invalid public key hex: {}", e))?;
+                    let key = HybridPublicKey::from_bytes(&key_bytes).map_err(|e| e.to_string())?;
+                    verify_hybrid_proof(&key, &invite.data, &signature, *policy)
+                        .map_err(|e| e.to_string())
+                });
+            let verified = result.is_ok();
+            
             match cli.output {
                 OutputFormat::Json => {
-                    let output_data = SendOutput {
+                    let output_data = VerifyHybridOutput {
+                        status: if verified { "success" } else { "failed" }.to_string(),
+                        verified,
+                        policy: policy.to_string(),
+                        invite_seed: *invite_seed,
+                        error: result.err(),
+                    };
+                    println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
+                }
+                OutputFormat::Text => {
+                    println!("{} Hybrid verification completed!", if verified { "✅" } else { "❌" });
+                    println!("   Policy: {}", policy);
+                    println!("   Invite Seed: {}", invite_seed);
+                    println!("   Verified: {}", if verified { "✅ Yes" } else { "❌ No" });
+                    if let Err(e) = &result {
+                        println!("   Error: {}", e);
+                    }
+                }
+            }
+            
+            if !verified {
+                exit_unverified();
+            }
+        }
+        
+        Commands::Receipt { message_id, sender, context, body, signer, seed } => {
+            let sender_bytes = hex::decode(sender)
+                .unwrap_or_else(|e| fail(ErrorKind::InvalidInput, format!("Invalid sender hex: {}", e)));
+            let context_bytes = hex::decode(context)
+                .unwrap_or_else(|e| fail(ErrorKind::InvalidInput, format!("Invalid context hex: {}", e)));
+            
+            // The receipt proves receipt by whoever holds the keystore key
+            let hash = message_hash(&sender_bytes, &context_bytes, body.as_bytes());
+            let receipt = match seed {
+                Some(seed) => make_receipt(&generate_secure_keypair_with_seed(*seed), message_id, &hash)
+                    .unwrap_or_else(|e| fail(ErrorKind::InvalidInput, e.to_string())),
+                None => {
+                    if message_id.is_empty() {
+                        fail(ErrorKind::InvalidInput, "Message id cannot be empty");
+                    }
+                    let signer = load_signer(signer.unwrap_or(SignerKind::File), &cli.keystore);
+                    Receipt {
+                        message_id: message_id.clone(),
+                        message_hash: hash,
+                        recipient: signer.public_key().unwrap_or_else(|e| fail(ErrorKind::Key, e)),
+                        signature: signer
+                            .sign(&receipt_context(message_id, &hash))
+                            .unwrap_or_else(|e| fail(ErrorKind::Key, e)),
+                    }
+                }
+            };
+            
+            match cli.output {
+                OutputFormat::Json => {
+                    let output_data = ReceiptOutput {
                         status: "success".to_string(),
-                        message: msg.clone(),
-                        recipient: to_pubkey.clone(),
+                        message_id: receipt.message_id.clone(),
+                        message_hash_hex: hex::encode(receipt.message_hash),
+                        recipient_hex: hex::encode(receipt.recipient.to_bytes()),
+                        signature_hex: hex::encode(receipt.signature.to_bytes()),
                     };
                     println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
                 }
                 OutputFormat::Text => {
-                    println!("✅ Message prepared for sending!");
-                    println!("   To: {}", to_pubkey);
-                    println!("   Message: '{}'", msg);
-                    println!("   Note: In a real app, this would connect to the relay server");
+                    println!("✅ Receipt signed successfully!");
+                    println!("   Message ID: {}", receipt.message_id);
+                    println!("   Message Hash: {}", hex::encode(receipt.message_hash));
+                    println!("   Recipient: {}", hex::encode(receipt.recipient.to_bytes()));
+                    println!("   Signature: {}", hex::encode(receipt.signature.to_bytes()));
                 }
             }
         }
         
-        Commands::Verify { proof, invite_seed } => {
-            let keypair = generate_keypair_with_seed(*invite_seed);
-            let invite = Invite::new_with_seed(*invite_seed);
+        Commands::ProveFile { path, algorithm, content_type, out, signer, seed } => {
+            let file = std::fs::File::open(path)
+                .unwrap_or_else(|e| fail(ErrorKind::InvalidInput, format!("Failed to open {}: {}", path.display(), e)));
+            let (digest, size) = digest_document(*algorithm, std::io::BufReader::new(file))
+                .unwrap_or_else(|e| fail(ErrorKind::InvalidInput, format!("Failed to read {}: {}", path.display(), e)));
+            let metadata = DocumentMetadata {
+                filename: path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+                size,
+                content_type: content_type.clone().unwrap_or_else(|| guess_content_type(path).to_string()),
+            };
+            let context = detached_context(*algorithm, &digest, &metadata);
             
-            // For demo purposes, we'll simulate verification
-            // In a real implementation, this would parse and verify the actual proof
-            let verified = !proof.is_empty(); // Simple demo logic
+            // The document never leaves the machine; only its digest is signed
+            let (public_key, signature) = match signer {
+                Some(kind) => {
+                    let signer = load_signer(*kind, &cli.keystore);
+                    let public_key = signer.public_key().unwrap_or_else(|e| fail(ErrorKind::Key, e));
+                    (public_key, signer.sign(&context).unwrap_or_else(|e| fail(ErrorKind::Key, e)))
+                }
+                None => {
+                    let keypair = match seed {
+                        Some(seed) => generate_secure_keypair_with_seed(*seed),
+                        None => generate_secure_keypair(),
+                    };
+                    (keypair.public_key(), keypair.sign(&context))
+                }
+            };
+            let proof = DetachedProof {
+                algorithm: *algorithm,
+                digest,
+                metadata,
+                public_key: public_key.to_bytes(),
+                signature: signature.to_bytes().to_vec(),
+            };
+            
+            let proof_path = out.clone().unwrap_or_else(|| default_proof_path(path));
+            std::fs::write(&proof_path, serde_json::to_string_pretty(&proof).unwrap())
+                .unwrap_or_else(|e| fail(ErrorKind::Failure, format!("Failed to write {}: {}", proof_path.display(), e)));
+            
+            match cli.output {
+                OutputFormat::Json => {
+                    let output_data = ProveFileOutput {
+                        status: "success".to_string(),
+                        proof_file: proof_path.display().to_string(),
+                        proof,
+                    };
+                    println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
+                }
+                OutputFormat::Text => {
+                    println!("✅ Detached proof signed successfully!");
+                    println!("   File: {} ({} bytes, {})", proof.metadata.filename, proof.metadata.size, proof.metadata.content_type);
+                    println!("   Digest ({}): {}", proof.algorithm, hex::encode(proof.digest));
+                    println!("   Public Key: {}", hex::encode(proof.public_key));
+                    println!("   Saved to: {}", proof_path.display());
+                }
+            }
+        }
+        
+        Commands::SignContext { path, signer, seed } => {
+            // The context is hashed as it is read, so its size is unbounded
+            let mut hasher = ContextHasher::new();
+            let read = if path.as_os_str() == "-" {
+                hasher.update_reader(std::io::stdin().lock())
+            } else {
+                std::fs::File::open(path).and_then(|file| hasher.update_reader(std::io::BufReader::new(file)))
+            };
+            read.unwrap_or_else(|e| fail(ErrorKind::InvalidInput, format!("Failed to read {}: {}", path.display(), e)));
+            let digest = hasher.finalize();
+            let input = digest_signing_input(&digest);
+            
+            let (public_key, signature) = match signer {
+                Some(kind) => {
+                    let signer = load_signer(*kind, &cli.keystore);
+                    let public_key = signer.public_key().unwrap_or_else(|e| fail(ErrorKind::Key, e));
+                    (public_key, signer.sign(&input).unwrap_or_else(|e| fail(ErrorKind::Key, e)))
+                }
+                None => {
+                    let keypair = match seed {
+                        Some(seed) => generate_secure_keypair_with_seed(*seed),
+                        None => generate_secure_keypair(),
+                    };
+                    (keypair.public_key(), keypair.sign(&input))
+                }
+            };
             
             match cli.output {
                 OutputFormat::Json => {
-                    let output_data = VerifyOutput {
+                    let output_data = SignContextOutput {
                         status: "success".to_string(),
+                        context_length: hasher.len(),
+                        digest_hex: hex::encode(digest),
+                        public_key_hex: hex::encode(public_key.to_bytes()),
+                        signature_hex: hex::encode(signature.to_bytes()),
+                    };
+                    println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
+                }
+                OutputFormat::Text => {
+                    println!("✅ Context digest signed successfully!");
+                    println!("   Context Length: {} bytes", hasher.len());
+                    println!("   Digest: {}", hex::encode(digest));
+                    println!("   Public Key: {}", hex::encode(public_key.to_bytes()));
+                    println!("   Signature: {}", hex::encode(signature.to_bytes()));
+                }
+            }
+        }
+        
+        Commands::Contact { command } => {
+            let (dir, mut contacts) = load_contacts(cli.config_dir.as_deref());
+            match command {
+                ContactCommands::Add { name, public_key, replace } => {
+                    let contact = contacts.add(name, public_key, *replace).unwrap_or_else(|e| fail(ErrorKind::InvalidInput, e)).clone();
+                    contacts.save(&dir).unwrap_or_else(|e| fail(ErrorKind::Failure, e));
+                    match cli.output {
+                        OutputFormat::Json => {
+                            let output_data = ContactCommandOutput {
+                                status: "success".to_string(),
+                                contact: ContactOutput::new(name, &contact),
+                                matched: None,
+                            };
+                            println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
+                        }
+                        OutputFormat::Text => {
+                            println!("✅ Contact saved!");
+                            print_contact(name, &contact);
+                            if contact.trust == TrustState::Unverified {
+                                println!("   Compare the fingerprint with {}, then run `contact verify {} --confirm`", name, name);
+                            }
+                        }
+                    }
+                }
+                ContactCommands::List => list_contacts(&cli.output, &contacts),
+                ContactCommands::Verify { name, fingerprint, confirm } => {
+                    let contact = contacts.get(name).unwrap_or_else(|e| fail(ErrorKind::InvalidInput, e)).clone();
+                    let matched = fingerprint.as_deref().map(|claimed| contact.fingerprint().matches(claimed));
+                    let contact = if *confirm || matched == Some(true) {
+                        let contact = contacts.mark_verified(name).unwrap_or_else(|e| fail(ErrorKind::InvalidInput, e)).clone();
+                        contacts.save(&dir).unwrap_or_else(|e| fail(ErrorKind::Failure, e));
+                        contact
+                    } else {
+                        contact
+                    };
+                    match cli.output {
+                        OutputFormat::Json => {
+                            let output_data = ContactCommandOutput {
+                                status: if matched == Some(false) { "failed" } else { "success" }.to_string(),
+                                contact: ContactOutput::new(name, &contact),
+                                matched,
+                            };
+                            println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
+                        }
+                        OutputFormat::Text => {
+                            match matched {
+                                Some(true) => println!("✅ Fingerprints match; {} is now verified", name),
+                                Some(false) => println!("❌ Fingerprints do NOT match; {} may not be who they claim", name),
+                                None if *confirm => println!("✅ {} is now verified", name),
+                                None => println!("🔎 Compare this fingerprint with {} over another channel:", name),
+                            }
+                            print_contact(name, &contact);
+                        }
+                    }
+                    if matched == Some(false) {
+                        exit_unverified();
+                    }
+                }
+            }
+        }
+        
+        Commands::VerifyFile { path, proof, public_key } => {
+            let proof_path = proof.clone().unwrap_or_else(|| default_proof_path(path));
+            let proof: DetachedProof = std::fs::read_to_string(&proof_path)
+                .map_err(|e| e.to_string())
+                .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| fail(ErrorKind::InvalidInput, format!("Failed to load proof {}: {}", proof_path.display(), e)));
+            
+            let result = verify_file(path, &proof, public_key.as_deref());
+            let verified = result.is_ok();
+            
+            match cli.output {
+                OutputFormat::Json => {
+                    let output_data = VerifyFileOutput {
+                        status: if verified { "success" } else { "failed" }.to_string(),
                         verified,
-                        proof: proof.clone(),
-                        invite_seed: *invite_seed,
+                        public_key_hex: hex::encode(proof.public_key),
+                        filename: proof.metadata.filename.clone(),
+                        error: result.err(),
                     };
                     println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
                 }
                 OutputFormat::Text => {
-                    println!("✅ Verification completed!");
-                    println!("   Proof: {}", proof);
-                    println!("   Invite Seed: {}", invite_seed);
+                    println!("{} File verification completed!", if verified { "✅" } else { "❌" });
+                    println!("   File: {}", path.display());
+                    println!("   Signed by: {}", hex::encode(proof.public_key));
                     println!("   Verified: {}", if verified { "✅ Yes" } else { "❌ No" });
-                    println!("   Generated Public Key: {}", hex::encode(keypair.public.to_bytes()));
-                    println!("   Invite Data: {}", hex::encode(&invite.data));
-                    println!("   Note: This is a demo verification");
+                    if let Err(e) = &result {
+                        println!("   Error: {}", e);
+                    }
                 }
             }
+            
+            if !verified {
+                exit_unverified();
+            }
+        }
+        
+        Commands::VerifyBundle { path, relay_key, locale } => {
+            let bundle: EvidenceBundle = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| fail(ErrorKind::InvalidInput, format!("Failed to load bundle {}: {}", path.display(), e)));
+            
+            let result = verify_bundle(&bundle, relay_key.as_deref());
+            let verified = result.is_ok();
+            let revocation = &bundle.revocation;
+            let summary = hex::decode(&bundle.message.context)
+                .ok()
+                .and_then(|context| render_context(&context, locale).ok())
+                .map(|rendered| rendered.summary);
+            
+            match cli.output {
+                OutputFormat::Json => {
+                    let output_data = VerifyBundleOutput {
+                        status: if verified { "success" } else { "failed" }.to_string(),
+                        verified,
+                        message_id: bundle.message.id.clone(),
+                        sender_hex: bundle.message.sender.clone(),
+                        relay_public_key_hex: bundle.relay_public_key.clone(),
+                        tree_size: bundle.inclusion.tree_size,
+                        revoked: revocation.revoked,
+                        exported_at: format_millis(bundle.exported_at),
+                        summary,
+                        error: result.err(),
+                    };
+                    println!("{}", serde_json::to_string_pretty(&output_data).unwrap());
+                }
+                OutputFormat::Text => {
+                    println!("{} Bundle verification completed!", if verified { "✅" } else { "❌" });
+                    println!("   Message: {}", bundle.message.id);
+                    if let Some(summary) = &summary {
+                        println!("   Approves: {}", summary);
+                    }
+                    println!("   Sender: {}", bundle.message.sender);
+                    println!("   Relay key: {}", bundle.relay_public_key);
+                    println!("   Logged at index {} of {}", bundle.inclusion.leaf_index, bundle.inclusion.tree_size);
+                    println!("   Exported: {}", format_millis(bundle.exported_at));
+                    if revocation.revoked {
+                        let when = revocation.revoked_at.map(format_millis).unwrap_or_default();
+                        let reason = revocation.reason.as_deref().unwrap_or("no reason given");
+                        println!("   ⚠️  Proof revoked {} ({})", when, reason);
+                    }
+                    if bundle.message.message_hash.is_some() {
+                        println!("   Message deleted: verified by its hash");
+                    }
+                    if relay_key.is_none() {
+                        println!("   ⚠️  Relay key taken from the bundle: pass --relay-key to pin it");
+                    }
+                    println!("   Verified: {}", if verified { "✅ Yes" } else { "❌ No" });
+                    if let Err(e) = &result {
+                        println!("   Error: {}", e);
+                    }
+                }
+            }
+            
+            if !verified {
+                exit_unverified();
+            }
+        }
+        
+        Commands::Repl { signer } => {
+            let signer = load_signer(*signer, &cli.keystore);
+            repl::run(cli.output.clone(), signer, cli.config_dir.as_deref()).unwrap_or_else(|e| exit::exit_with(e));
         }
+        
+        Commands::Watch { group, relay_url, token, poll_timeout, limit } => {
+            let mut client = RelayClient::new(relay_url).unwrap_or_else(|e| fail(ErrorKind::InvalidInput, e.to_string()));
+            if let Some(token) = token {
+                client = client.with_bearer_token(token);
+            }
+            // The watch loop reconnects itself, resuming from its change token
+            let client = client.with_retry_policy(RetryPolicy::none());
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap_or_else(|e| fail(ErrorKind::Failure, format!("Failed to start the runtime: {}", e)));
+            runtime
+                .block_on(watch::run(cli.output.clone(), client, group, Duration::from_secs(*poll_timeout), *limit))
+                .unwrap_or_else(|e| exit::exit_with(e));
+        }
+        
+        Commands::SignerDaemon { socket, key_id } => {
+            signer_daemon(&cli.output, &cli.keystore, socket, key_id).unwrap_or_else(|e| exit::exit_with(e));
+        }
+        
+        Commands::Completions { shell } => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(*shell, &mut command, name, &mut std::io::stdout());
+        }
+        
+        Commands::Man { out_dir } => match out_dir {
+            Some(dir) => {
+                std::fs::create_dir_all(dir)
+                    .and_then(|_| clap_mangen::generate_to(Cli::command(), dir))
+                    .unwrap_or_else(|e| fail(ErrorKind::Failure, format!("Failed to write man pages to {}: {}", dir.display(), e)));
+                if let OutputFormat::Text = cli.output {
+                    println!("✅ Man pages written to {}", dir.display());
+                }
+            }
+            None => clap_mangen::Man::new(Cli::command())
+                .render(&mut std::io::stdout())
+                .unwrap_or_else(|e| fail(ErrorKind::Failure, format!("Failed to write man page: {}", e))),
+        },
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_definition_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn completions_and_man_pages_cover_every_subcommand() {
+        let dir = tempfile::tempdir().unwrap();
+        clap_mangen::generate_to(Cli::command(), dir.path()).unwrap();
+        let mut command = Cli::command();
+        let mut script = Vec::new();
+        clap_complete::generate(Shell::Bash, &mut command, "proof-messenger-cli", &mut script);
+        let script = String::from_utf8(script).unwrap();
+
+        for subcommand in Cli::command().get_subcommands() {
+            let name = subcommand.get_name();
+            assert!(dir.path().join(format!("proof-messenger-cli-{}.1", name)).exists(), "{}", name);
+            assert!(script.contains(name), "{}", name);
+        }
+    }
+}
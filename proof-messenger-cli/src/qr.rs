@@ -0,0 +1,99 @@
+// src/qr.rs
+
+//! QR codes for invite URIs
+//!
+//! `invite --qr` draws the invite's `pm://invite?...` URI in the terminal
+//! with half-block characters, two modules per character cell, or writes it
+//! to a PNG when given a path. The web demo's scanner reads either.
+
+use qrcode::render::unicode::Dense1x2;
+use qrcode::{Color, EcLevel, QrCode};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Pixels per QR module in PNG output
+const PNG_MODULE_PIXELS: usize = 8;
+
+/// Light modules around the code, as the QR specification requires
+const QUIET_ZONE_MODULES: usize = 4;
+
+/// Encode `text` with medium error correction, enough for a screen photo
+fn encode(text: &str) -> Result<QrCode, String> {
+    QrCode::with_error_correction_level(text, EcLevel::M).map_err(|e| format!("Failed to encode QR code: {}", e))
+}
+
+/// The QR code for `text`, drawn for a terminal
+///
+/// Terminals are usually light text on a dark background, so the colours
+/// are swapped: light modules are drawn and dark modules are left blank.
+pub fn render_terminal(text: &str) -> Result<String, String> {
+    Ok(encode(text)?
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build())
+}
+
+/// Write the QR code for `text` to `path` as a black-on-white grayscale PNG
+pub fn write_png(text: &str, path: &Path) -> Result<(), String> {
+    let code = encode(text)?;
+    let (size, pixels) = rasterize(code.width(), &code.to_colors());
+
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Scale a `width`-module square of `modules` into 8-bit grayscale rows with a quiet zone
+fn rasterize(width: usize, modules: &[Color]) -> (usize, Vec<u8>) {
+    let size = (width + 2 * QUIET_ZONE_MODULES) * PNG_MODULE_PIXELS;
+    let mut pixels = vec![0xFF; size * size];
+    for (i, module) in modules.iter().enumerate() {
+        if *module != Color::Dark {
+            continue;
+        }
+        let left = (i % width + QUIET_ZONE_MODULES) * PNG_MODULE_PIXELS;
+        let top = (i / width + QUIET_ZONE_MODULES) * PNG_MODULE_PIXELS;
+        for row in top..top + PNG_MODULE_PIXELS {
+            pixels[row * size + left..row * size + left + PNG_MODULE_PIXELS].fill(0);
+        }
+    }
+    (size, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rasterize_scales_modules_inside_a_quiet_zone() {
+        // A 2x2 code with only the top-left module dark
+        let modules = [Color::Dark, Color::Light, Color::Light, Color::Light];
+
+        let (size, pixels) = rasterize(2, &modules);
+
+        assert_eq!(size, (2 + 2 * QUIET_ZONE_MODULES) * PNG_MODULE_PIXELS);
+        let offset = QUIET_ZONE_MODULES * PNG_MODULE_PIXELS;
+        let at = |x: usize, y: usize| pixels[y * size + x];
+        assert_eq!(at(0, 0), 0xFF);
+        assert_eq!(at(offset, offset), 0);
+        assert_eq!(at(offset + PNG_MODULE_PIXELS - 1, offset + PNG_MODULE_PIXELS - 1), 0);
+        assert_eq!(at(offset + PNG_MODULE_PIXELS, offset), 0xFF);
+        assert_eq!(pixels.iter().filter(|p| **p == 0).count(), PNG_MODULE_PIXELS * PNG_MODULE_PIXELS);
+    }
+
+    #[test]
+    fn terminal_rendering_uses_half_blocks() {
+        let rendered = render_terminal("pm://invite?v=1&d=AQ&k=AAAA").unwrap();
+
+        assert!(rendered.lines().count() > 10);
+        assert!(rendered.chars().any(|c| matches!(c, '█' | '▀' | '▄')));
+    }
+}
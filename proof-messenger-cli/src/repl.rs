@@ -0,0 +1,249 @@
+// src/repl.rs
+
+//! Interactive session for demos
+//!
+//! `repl` loads the keystore identity once and keeps it in memory, so a demo
+//! can send, verify, list contacts and create invites without paying the
+//! keystore and startup cost on every command. A failing command reports its
+//! error and the session carries on.
+//!
+//! Lines are split like a shell (quotes group words) and parsed with the
+//! same conventions as the CLI: `send alice "hello there"`, `verify <proof>
+//! <seed>`, `list`, `invite --group demo`, `help` and `exit`. History is kept
+//! in `repl_history` in the config directory, and Tab completes command and
+//! contact names.
+
+use crate::contacts::{config_dir, ContactBook};
+use crate::exit::{self, CliError, ErrorKind};
+use crate::signer::Signer;
+use crate::{invite, list_contacts, send, verify, OutputFormat};
+use clap::{Parser, Subcommand};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::path::{Path, PathBuf};
+
+/// File holding the session history inside the config directory
+const HISTORY_FILE: &str = "repl_history";
+
+const PROMPT: &str = "pm> ";
+
+/// Commands offered by Tab on an empty line
+const COMMANDS: [&str; 7] = ["send", "verify", "list", "invite", "whoami", "help", "exit"];
+
+/// One line of input
+#[derive(Parser)]
+#[command(multicall = true)]
+struct ReplLine {
+    #[command(subcommand)]
+    command: ReplCommand,
+}
+
+#[derive(Subcommand)]
+enum ReplCommand {
+    /// Sign a message with the session key and prepare it for a recipient
+    Send {
+        /// Recipient: a contact name or a public key (hex encoded)
+        to: String,
+        /// Message text; the remaining words are joined with spaces
+        #[arg(required = true, num_args = 1..)]
+        msg: Vec<String>,
+    },
+    /// Verify a proof against an invite
+    Verify { proof: String, invite_seed: u64 },
+    /// List contacts and their trust state
+    List,
+    /// Generate an invite with optional seed
+    Invite {
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Group the invite is for, carried in the invite URI
+        #[arg(long)]
+        group: Option<String>,
+        /// Relay the invitee should use, carried in the invite URI
+        #[arg(long)]
+        relay: Option<String>,
+        /// Show the invite URI as a QR code, or write it to the given PNG file
+        #[arg(long, value_name = "PNG", num_args = 0..=1, default_missing_value = "-")]
+        qr: Option<PathBuf>,
+    },
+    /// Show the public key of the session identity
+    Whoami,
+    /// End the session
+    #[command(visible_alias = "quit")]
+    Exit,
+}
+
+/// State kept for the whole session
+struct Session {
+    output: OutputFormat,
+    signer: Signer,
+    public_key_hex: String,
+    config_dir: PathBuf,
+    contacts: ContactBook,
+}
+
+impl Session {
+    /// Run one line, returning whether the session should go on
+    fn execute(&mut self, line: &str) -> Result<bool, CliError> {
+        let words = shlex::split(line).ok_or_else(|| CliError::new(ErrorKind::InvalidInput, "Unbalanced quotes"))?;
+        let command = match ReplLine::try_parse_from(words) {
+            Ok(line) => line.command,
+            Err(e) => {
+                // Usage errors and `help` are printed, not fatal
+                let _ = e.print();
+                return Ok(true);
+            }
+        };
+
+        match command {
+            ReplCommand::Send { to, msg } => {
+                self.reload_contacts()?;
+                send(&self.output, &self.contacts, Some(&self.signer), &to, &msg.join(" "))?;
+            }
+            ReplCommand::Verify { proof, invite_seed } => {
+                verify(&self.output, &proof, invite_seed);
+            }
+            ReplCommand::List => {
+                self.reload_contacts()?;
+                list_contacts(&self.output, &self.contacts);
+            }
+            ReplCommand::Invite { seed, group, relay, qr } => {
+                invite(&self.output, seed, group.as_deref(), relay.as_deref(), qr.as_deref())?;
+            }
+            ReplCommand::Whoami => println!("{}", self.public_key_hex),
+            ReplCommand::Exit => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Pick up contacts added from another terminal since the last command
+    fn reload_contacts(&mut self) -> Result<(), CliError> {
+        self.contacts = ContactBook::load(&self.config_dir).map_err(|e| CliError::new(ErrorKind::InvalidInput, e))?;
+        Ok(())
+    }
+}
+
+/// Tab completion of command names and, after `send`, contact names
+#[derive(Default)]
+struct ReplHelper {
+    contacts: Vec<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete(&line[..pos], &self.contacts))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Completions for the word ending at the cursor, and where that word starts
+fn complete(line: &str, contacts: &[String]) -> (usize, Vec<String>) {
+    let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+    let (before, word) = line.split_at(start);
+    let candidates: Vec<&str> = match before.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [] => COMMANDS.to_vec(),
+        ["send"] => contacts.iter().map(String::as_str).collect(),
+        _ => Vec::new(),
+    };
+    let matches = candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .map(str::to_string)
+        .collect();
+    (start, matches)
+}
+
+/// Run an interactive session until `exit` or end of input
+pub fn run(output: OutputFormat, signer: Signer, explicit_config_dir: Option<&Path>) -> Result<(), CliError> {
+    let public_key = signer.public_key().map_err(|e| CliError::new(ErrorKind::Key, e))?;
+    let public_key_hex = hex::encode(public_key.to_bytes());
+    let config_dir = config_dir(explicit_config_dir).map_err(|e| CliError::new(ErrorKind::Failure, e))?;
+    let contacts = ContactBook::load(&config_dir).map_err(|e| CliError::new(ErrorKind::InvalidInput, e))?;
+    let history = config_dir.join(HISTORY_FILE);
+
+    let mut editor: Editor<ReplHelper, DefaultHistory> =
+        Editor::new().map_err(|e| CliError::new(ErrorKind::Failure, format!("Failed to start the terminal: {}", e)))?;
+    editor.set_helper(Some(ReplHelper::default()));
+    // A missing history file just means a first session
+    let _ = editor.load_history(&history);
+
+    // Keep stdout parseable in JSON mode
+    let banner = format!("🔐 Session identity {} loaded; type `help` for commands", public_key_hex);
+    match output {
+        OutputFormat::Json => eprintln!("{}", banner),
+        OutputFormat::Text => println!("{}", banner),
+    }
+
+    let mut session = Session { output, signer, public_key_hex, config_dir, contacts };
+    loop {
+        if let Some(helper) = editor.helper_mut() {
+            helper.contacts = session.contacts.contacts.keys().cloned().collect();
+        }
+        match editor.readline(PROMPT) {
+            Ok(line) if line.trim().is_empty() => {}
+            Ok(line) => {
+                let _ = editor.add_history_entry(line.as_str());
+                match session.execute(&line) {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(e) => exit::report(&e),
+                }
+            }
+            // Ctrl-C abandons the current line, Ctrl-D ends the session
+            Err(ReadlineError::Interrupted) => {}
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(CliError::new(ErrorKind::Failure, format!("Failed to read input: {}", e))),
+        }
+    }
+
+    std::fs::create_dir_all(&session.config_dir)
+        .and_then(|_| editor.save_history(&history).map_err(std::io::Error::other))
+        .map_err(|e| CliError::new(ErrorKind::Failure, format!("Failed to save history {}: {}", history.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_and_contacts_are_completed() {
+        let contacts = vec!["alice".to_string(), "bob".to_string()];
+
+        assert_eq!(complete("", &contacts), (0, COMMANDS.iter().map(|c| c.to_string()).collect()));
+        assert_eq!(complete("ver", &contacts), (0, vec!["verify".to_string()]));
+        assert_eq!(complete("send a", &contacts), (5, vec!["alice".to_string()]));
+        assert_eq!(complete("send  ", &contacts), (6, contacts.clone()));
+        assert_eq!(complete("send alice he", &contacts), (11, Vec::new()));
+    }
+
+    #[test]
+    fn lines_are_parsed_like_the_cli() {
+        let line = ReplLine::try_parse_from(shlex::split(r#"send alice "hello there" again"#).unwrap()).unwrap();
+        match line.command {
+            ReplCommand::Send { to, msg } => {
+                assert_eq!(to, "alice");
+                assert_eq!(msg.join(" "), "hello there again");
+            }
+            _ => panic!("expected send"),
+        }
+
+        assert!(matches!(ReplLine::try_parse_from(["quit"]).unwrap().command, ReplCommand::Exit));
+        assert!(ReplLine::try_parse_from(["send", "alice"]).is_err());
+    }
+}
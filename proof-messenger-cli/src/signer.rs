@@ -0,0 +1,511 @@
+// src/signer.rs
+
+//! Signing backends for onboarding proofs and messages
+//!
+//! The `file` signer loads an Ed25519 keypair from the keystore file. The
+//! `yubikey` signer keeps the private key on a YubiKey and asks the device to
+//! sign, so the key never exists on disk. In that case the keystore holds a
+//! reference to the hardware slot instead of key bytes:
+//!
+//! ```json
+//! {"hardware": {"device": "yubikey", "interface": "piv", "slot": "9c", "publicKey": "<hex>"}}
+//! ```
+//!
+//! The device is reached through its PIV applet with `yubico-piv-tool`
+//! (Ed25519 needs firmware 5.7 or later) or through its OpenPGP applet with
+//! GnuPG's smartcard daemon via `gpg-connect-agent`. Both programs can be
+//! overridden with `PROOF_MESSENGER_PIV_TOOL` and `PROOF_MESSENGER_GPG_AGENT`.
+//!
+//! The `remote` signer asks a signer daemon (see
+//! `proof_messenger_protocol::remote_signer`) over its Unix socket, so the key
+//! lives in a separate, hardened process. The keystore records the socket and
+//! the key's id and public key:
+//!
+//! ```json
+//! {"remote": {"socket": "/run/proof-messenger/signer.sock", "keyId": "default", "publicKey": "<hex>"}}
+//! ```
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::ValueEnum;
+use ed25519_dalek::{PublicKey, Signature, PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH};
+use proof_messenger_protocol::key::SecureKeypair;
+use proof_messenger_protocol::proof::verify_proof_result;
+#[cfg(unix)]
+use proof_messenger_protocol::remote_signer::RemoteSignerClient;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo, followed by the raw key
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Hex characters per Assuan `SETDATA` line, well under the 1000 byte line limit
+const ASSUAN_DATA_CHUNK: usize = 800;
+
+/// Where the signing key lives
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignerKind {
+    /// Raw keypair bytes in the keystore file
+    File,
+    /// Key held on a YubiKey, referenced from the keystore file
+    Yubikey,
+    /// Key held by a signer daemon, referenced from the keystore file
+    Remote,
+}
+
+impl std::fmt::Display for SignerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignerKind::File => write!(f, "file"),
+            SignerKind::Yubikey => write!(f, "yubikey"),
+            SignerKind::Remote => write!(f, "remote"),
+        }
+    }
+}
+
+/// Smartcard applet used to reach a hardware key
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CardInterface {
+    Piv,
+    Openpgp,
+}
+
+impl CardInterface {
+    /// Slot used when none is given: PIV digital signature, OpenPGP signing key
+    pub fn default_slot(self) -> &'static str {
+        match self {
+            CardInterface::Piv => "9c",
+            CardInterface::Openpgp => "OPENPGP.1",
+        }
+    }
+}
+
+impl std::fmt::Display for CardInterface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardInterface::Piv => write!(f, "piv"),
+            CardInterface::Openpgp => write!(f, "openpgp"),
+        }
+    }
+}
+
+/// Hardware device families a keystore entry can reference
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HardwareDevice {
+    Yubikey,
+}
+
+/// A key that lives in a hardware slot
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HardwareKey {
+    pub device: HardwareDevice,
+    pub interface: CardInterface,
+    pub slot: String,
+    /// Ed25519 public key of the slot (hex encoded)
+    pub public_key: String,
+}
+
+/// A key held by a signer daemon
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteKey {
+    /// Unix socket the daemon listens on
+    pub socket: PathBuf,
+    pub key_id: String,
+    /// Ed25519 public key the daemon held at enrollment (hex encoded)
+    pub public_key: String,
+}
+
+/// Contents of a keystore file
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum KeystoreEntry {
+    /// Raw Ed25519 keypair bytes, as written by `keygen`
+    Keypair(Vec<u8>),
+    /// Reference to a key held in a hardware slot
+    Hardware { hardware: HardwareKey },
+    /// Reference to a key held by a signer daemon
+    Remote { remote: RemoteKey },
+}
+
+impl KeystoreEntry {
+    /// The signer that can use this entry
+    pub fn kind(&self) -> SignerKind {
+        match self {
+            KeystoreEntry::Keypair(_) => SignerKind::File,
+            KeystoreEntry::Hardware { .. } => SignerKind::Yubikey,
+            KeystoreEntry::Remote { .. } => SignerKind::Remote,
+        }
+    }
+
+    /// Read a keystore file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read keystore {}: {}", path.display(), e))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid keystore {}: {}", path.display(), e))
+    }
+
+    /// Write a keystore file
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = serde_json::to_string(self).expect("keystore entries always serialize");
+        fs::write(path, contents)
+            .map_err(|e| format!("Failed to write keystore {}: {}", path.display(), e))
+    }
+}
+
+impl HardwareKey {
+    /// Set up a YubiKey slot for signing and return a reference to it
+    ///
+    /// PIV slots get a fresh Ed25519 key generated on the device. OpenPGP
+    /// keys are created with `gpg --card-edit`, so their public key is read.
+    pub fn enroll(interface: CardInterface, slot: &str) -> Result<Self, String> {
+        let public_key = match interface {
+            CardInterface::Piv => {
+                let pem = run(&piv_tool(), &["-a", "generate", "-s", slot, "-A", "ED25519"], &[])?;
+                parse_pem_public_key(&String::from_utf8_lossy(&pem))?
+            }
+            CardInterface::Openpgp => {
+                let data = assuan_data(&run_gpg_agent(&[format!("SCD READKEY {}", slot)])?)?;
+                parse_sexp_public_key(&data)?
+            }
+        };
+        Ok(Self {
+            device: HardwareDevice::Yubikey,
+            interface,
+            slot: slot.to_string(),
+            public_key: hex::encode(public_key),
+        })
+    }
+
+    /// The public key recorded for the slot
+    pub fn public_key(&self) -> Result<PublicKey, String> {
+        hex::decode(&self.public_key)
+            .ok()
+            .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+            .ok_or_else(|| format!("Invalid public key for {} slot {}", self.interface, self.slot))
+    }
+
+    /// Ask the device to sign `context` and check the result
+    fn sign(&self, context: &[u8]) -> Result<Signature, String> {
+        let public_key = self.public_key()?;
+        let bytes = match self.interface {
+            CardInterface::Piv => run(
+                &piv_tool(),
+                &["-a", "verify-pin", "-a", "sign", "-s", &self.slot, "-A", "ED25519"],
+                context,
+            )?,
+            CardInterface::Openpgp => {
+                let mut commands: Vec<String> = hex::encode_upper(context)
+                    .as_bytes()
+                    .chunks(ASSUAN_DATA_CHUNK)
+                    .enumerate()
+                    .map(|(i, chunk)| {
+                        let append = if i == 0 { "" } else { "--append " };
+                        format!("SCD SETDATA {}{}", append, String::from_utf8_lossy(chunk))
+                    })
+                    .collect();
+                commands.push(format!("SCD PKSIGN {}", self.slot));
+                assuan_data(&run_gpg_agent(&commands)?)?
+            }
+        };
+        if bytes.len() != SIGNATURE_LENGTH {
+            return Err(format!(
+                "YubiKey returned a {} byte signature, expected {}",
+                bytes.len(),
+                SIGNATURE_LENGTH
+            ));
+        }
+        let signature = Signature::from_bytes(&bytes)
+            .map_err(|e| format!("YubiKey returned an invalid signature: {}", e))?;
+
+        // The device signs with whatever key is in the slot now, which may
+        // no longer be the one this keystore entry was enrolled with
+        verify_proof_result(&public_key, context, &signature).map_err(|_| {
+            format!(
+                "Signature from {} slot {} does not match the keystore public key",
+                self.interface, self.slot
+            )
+        })?;
+        Ok(signature)
+    }
+}
+
+impl RemoteKey {
+    /// Ask the daemon on `socket` for the public key it holds under `key_id`
+    #[cfg(unix)]
+    pub fn enroll(socket: &Path, key_id: &str) -> Result<Self, String> {
+        let public_key = RemoteSignerClient::new(socket)
+            .public_key(key_id)
+            .map_err(|e| format!("{} ({})", e, socket.display()))?;
+        Ok(Self {
+            socket: socket.to_path_buf(),
+            key_id: key_id.to_string(),
+            public_key: hex::encode(public_key.to_bytes()),
+        })
+    }
+
+    /// Signer daemons listen on Unix sockets only
+    #[cfg(not(unix))]
+    pub fn enroll(_socket: &Path, _key_id: &str) -> Result<Self, String> {
+        Err("Remote signers need Unix sockets, which this platform lacks".to_string())
+    }
+
+    /// The public key recorded at enrollment
+    pub fn public_key(&self) -> Result<PublicKey, String> {
+        hex::decode(&self.public_key)
+            .ok()
+            .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+            .ok_or_else(|| format!("Invalid public key for remote key {}", self.key_id))
+    }
+
+    /// Ask the daemon to sign `context` and check the result
+    #[cfg(unix)]
+    fn sign(&self, context: &[u8]) -> Result<Signature, String> {
+        let public_key = self.public_key()?;
+        let signature = RemoteSignerClient::new(&self.socket)
+            .sign(&self.key_id, context)
+            .map_err(|e| format!("{} ({})", e, self.socket.display()))?;
+
+        // The daemon may have been restarted with a different key under this id
+        verify_proof_result(&public_key, context, &signature).map_err(|_| {
+            format!(
+                "Signature from remote key {} does not match the keystore public key",
+                self.key_id
+            )
+        })?;
+        Ok(signature)
+    }
+
+    #[cfg(not(unix))]
+    fn sign(&self, _context: &[u8]) -> Result<Signature, String> {
+        Err("Remote signers need Unix sockets, which this platform lacks".to_string())
+    }
+}
+
+/// A loaded signing key
+pub enum Signer {
+    File(SecureKeypair),
+    Hardware(HardwareKey),
+    Remote(RemoteKey),
+}
+
+impl Signer {
+    /// Load the signer of the given kind from a keystore file
+    pub fn load(kind: SignerKind, keystore: &Path) -> Result<Self, String> {
+        match (kind, KeystoreEntry::load(keystore)?) {
+            (SignerKind::File, KeystoreEntry::Keypair(bytes)) => SecureKeypair::from_bytes(&bytes)
+                .map(Signer::File)
+                .map_err(|e| format!("Invalid keystore {}: {}", keystore.display(), e)),
+            (SignerKind::Yubikey, KeystoreEntry::Hardware { hardware }) => Ok(Signer::Hardware(hardware)),
+            (SignerKind::Remote, KeystoreEntry::Remote { remote }) => Ok(Signer::Remote(remote)),
+            (kind, KeystoreEntry::Keypair(_)) => Err(format!(
+                "Keystore {} holds raw key bytes, not a {} key reference; run `keygen --signer {}` first",
+                keystore.display(),
+                kind,
+                kind
+            )),
+            (_, entry) => Err(format!(
+                "Keystore {} references a {} key; use --signer {}",
+                keystore.display(),
+                entry.kind(),
+                entry.kind()
+            )),
+        }
+    }
+
+    /// Public key matching the signatures this signer produces
+    pub fn public_key(&self) -> Result<PublicKey, String> {
+        match self {
+            Signer::File(keypair) => Ok(keypair.public_key()),
+            Signer::Hardware(key) => key.public_key(),
+            Signer::Remote(key) => key.public_key(),
+        }
+    }
+
+    /// Sign arbitrary context data
+    pub fn sign(&self, context: &[u8]) -> Result<Signature, String> {
+        match self {
+            Signer::File(keypair) => Ok(keypair.sign(context)),
+            Signer::Hardware(key) => key.sign(context),
+            Signer::Remote(key) => key.sign(context),
+        }
+    }
+}
+
+fn piv_tool() -> String {
+    std::env::var("PROOF_MESSENGER_PIV_TOOL").unwrap_or_else(|_| "yubico-piv-tool".to_string())
+}
+
+fn run_gpg_agent(commands: &[String]) -> Result<Vec<u8>, String> {
+    let program = std::env::var("PROOF_MESSENGER_GPG_AGENT")
+        .unwrap_or_else(|_| "gpg-connect-agent".to_string());
+    let script = format!("{}\n/bye\n", commands.join("\n"));
+    run(&program, &[], script.as_bytes())
+}
+
+/// Run a helper program with `input` on stdin and return its stdout
+///
+/// Stderr is left attached to the terminal so PIN prompts reach the user.
+fn run(program: &str, args: &[&str], input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input)
+        .map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!("{} failed ({})", program, output.status));
+    }
+    Ok(output.stdout)
+}
+
+/// Extract the raw key from a PEM encoded Ed25519 public key
+fn parse_pem_public_key(pem: &str) -> Result<[u8; PUBLIC_KEY_LENGTH], String> {
+    let body: String = pem
+        .lines()
+        .skip_while(|line| !line.starts_with("-----BEGIN PUBLIC KEY-----"))
+        .skip(1)
+        .take_while(|line| !line.starts_with("-----END"))
+        .collect();
+    let der = STANDARD
+        .decode(body.trim())
+        .map_err(|e| format!("Invalid PEM public key: {}", e))?;
+    der.strip_prefix(ED25519_SPKI_PREFIX.as_slice())
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| "PIV slot does not hold an Ed25519 key".to_string())
+}
+
+/// Collect the data lines of an Assuan response, failing on any error line
+fn assuan_data(response: &[u8]) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    for line in response.split(|&b| b == b'\n') {
+        if let Some(error) = line.strip_prefix(b"ERR ") {
+            return Err(format!("gpg-agent: {}", String::from_utf8_lossy(error)));
+        }
+        if let Some(escaped) = line.strip_prefix(b"D ") {
+            let mut bytes = escaped.iter();
+            while let Some(&b) = bytes.next() {
+                if b == b'%' {
+                    let hex = [*bytes.next().unwrap_or(&b'0'), *bytes.next().unwrap_or(&b'0')];
+                    let decoded = hex::decode(hex).map_err(|_| "gpg-agent: malformed data line".to_string())?;
+                    data.extend(decoded);
+                } else {
+                    data.push(b);
+                }
+            }
+        }
+    }
+    Ok(data)
+}
+
+/// Extract the curve point from an Ed25519 public key S-expression
+fn parse_sexp_public_key(sexp: &[u8]) -> Result<[u8; PUBLIC_KEY_LENGTH], String> {
+    const NOT_ED25519: &str = "OpenPGP slot does not hold an Ed25519 key";
+    if !sexp.windows(7).any(|w| w == b"Ed25519") {
+        return Err(NOT_ED25519.to_string());
+    }
+    let start = sexp
+        .windows(4)
+        .position(|w| w == b"(1:q")
+        .ok_or(NOT_ED25519)?
+        + 4;
+    let rest = &sexp[start..];
+    let colon = rest.iter().position(|&b| b == b':').ok_or(NOT_ED25519)?;
+    let len: usize = std::str::from_utf8(&rest[..colon])
+        .ok()
+        .and_then(|len| len.parse().ok())
+        .ok_or(NOT_ED25519)?;
+    let point = rest.get(colon + 1..colon + 1 + len).ok_or(NOT_ED25519)?;
+    // Points may carry the 0x40 "native" prefix
+    let key = match point {
+        [0x40, key @ ..] if key.len() == PUBLIC_KEY_LENGTH => key,
+        key => key,
+    };
+    key.try_into().map_err(|_| NOT_ED25519.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+
+    #[test]
+    fn keystore_accepts_raw_bytes_and_hardware_references() {
+        let raw: KeystoreEntry = serde_json::from_str("[1,2,3]").unwrap();
+        assert_eq!(raw, KeystoreEntry::Keypair(vec![1, 2, 3]));
+
+        let hardware: KeystoreEntry = serde_json::from_str(
+            r#"{"hardware":{"device":"yubikey","interface":"openpgp","slot":"OPENPGP.1","publicKey":"ab"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            hardware,
+            KeystoreEntry::Hardware {
+                hardware: HardwareKey {
+                    device: HardwareDevice::Yubikey,
+                    interface: CardInterface::Openpgp,
+                    slot: "OPENPGP.1".to_string(),
+                    public_key: "ab".to_string(),
+                },
+            }
+        );
+
+        let remote: KeystoreEntry = serde_json::from_str(
+            r#"{"remote":{"socket":"/run/signer.sock","keyId":"default","publicKey":"ab"}}"#,
+        )
+        .unwrap();
+        assert_eq!(remote.kind(), SignerKind::Remote);
+    }
+
+    #[test]
+    fn pem_public_key_is_parsed() {
+        let key = generate_secure_keypair_with_seed(4).public_key_bytes();
+        let der = [ED25519_SPKI_PREFIX.as_slice(), key.as_slice()].concat();
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            STANDARD.encode(der)
+        );
+
+        assert_eq!(parse_pem_public_key(&pem).unwrap(), key);
+        assert!(parse_pem_public_key("-----BEGIN PUBLIC KEY-----\nMAA=\n-----END PUBLIC KEY-----").is_err());
+    }
+
+    #[test]
+    fn assuan_responses_are_decoded() {
+        let key = [0x25u8; PUBLIC_KEY_LENGTH];
+        let mut sexp = b"(10:public-key(3:ecc(5:curve7:Ed25519)(5:flags5:eddsa)(1:q33:\x40".to_vec();
+        sexp.extend_from_slice(&key);
+        sexp.extend_from_slice(b")))");
+        let escaped: Vec<u8> = sexp
+            .iter()
+            .flat_map(|&b| match b {
+                b'%' | b'\n' | b'\r' => format!("%{:02X}", b).into_bytes(),
+                b => vec![b],
+            })
+            .collect();
+        let response = [b"S PROGRESS\nD ".as_slice(), &escaped, b"\nOK\nOK\n"].concat();
+
+        let data = assuan_data(&response).unwrap();
+        assert_eq!(data, sexp);
+        assert_eq!(parse_sexp_public_key(&data).unwrap(), key);
+        assert!(assuan_data(b"OK\nERR 100663406 Card removed <SCD>\n").is_err());
+    }
+}
@@ -0,0 +1,229 @@
+// src/watch.rs
+
+//! Tailing a group's messages
+//!
+//! `watch` long-polls the relay's `GET /v1/messages/:group_id/poll` and
+//! prints each message as the relay verifies it, one line per message in
+//! text or as NDJSON. Every proof is checked again locally against the
+//! sender's key, so a line is only marked verified when both the relay and
+//! the CLI agree.
+//!
+//! The change token of the last poll is kept across failures: when the relay
+//! is unreachable or overloaded, `watch` waits (1 second, doubling up to a
+//! minute) and resumes where it stopped, so no message is skipped or printed
+//! twice. Errors that retrying cannot fix, such as a missing scope or a relay
+//! without live subscriptions, end the command.
+
+use ed25519_dalek::{PublicKey, Signature};
+use proof_messenger_protocol::proof::verify_proof_result;
+use proof_messenger_protocol::relay_client::{RelayClient, RelayClientError, RelayedMessage};
+use proof_messenger_protocol::render::render_context;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::exit::{CliError, ErrorKind};
+use crate::OutputFormat;
+
+/// Wait before the first reconnect
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest wait between reconnects
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Leading hex digits of a sender key shown in text output
+const SENDER_PREFIX: usize = 16;
+
+/// A watched message as printed in NDJSON output
+#[derive(Serialize)]
+struct WatchedMessage<'a> {
+    id: &'a str,
+    #[serde(rename = "groupId")]
+    group_id: &'a str,
+    #[serde(rename = "senderHex")]
+    sender_hex: &'a str,
+    body: &'a str,
+    #[serde(rename = "createdAt")]
+    created_at: String,
+    verified: bool,
+    #[serde(rename = "threadId", skip_serializing_if = "Option::is_none")]
+    thread_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+}
+
+/// Exponential delay between reconnects
+struct Backoff {
+    next: Duration,
+}
+
+impl Backoff {
+    fn new() -> Self {
+        Self { next: INITIAL_BACKOFF }
+    }
+
+    /// Delay before the next attempt, doubling the one after
+    fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(MAX_BACKOFF);
+        delay
+    }
+
+    fn reset(&mut self) {
+        self.next = INITIAL_BACKOFF;
+    }
+}
+
+/// Whether retrying a failed poll cannot help
+fn is_fatal(error: &RelayClientError) -> bool {
+    match error {
+        RelayClientError::InvalidUrl(_) => true,
+        RelayClientError::Api { status, .. } => (400..500).contains(status) && *status != 429,
+        _ => false,
+    }
+}
+
+/// Whether the message's proof is the sender's signature over its context
+fn verify_locally(message: &RelayedMessage) -> bool {
+    let public_key = hex::decode(&message.sender).ok().and_then(|bytes| PublicKey::from_bytes(&bytes).ok());
+    let signature = hex::decode(&message.proof).ok().and_then(|bytes| Signature::from_bytes(&bytes).ok());
+    let context = hex::decode(&message.context).ok();
+    match (public_key, signature, context) {
+        (Some(public_key), Some(signature), Some(context)) => {
+            verify_proof_result(&public_key, &context, &signature).is_ok()
+        }
+        _ => false,
+    }
+}
+
+/// Print one message
+fn print_message(output: &OutputFormat, message: &RelayedMessage) {
+    let verified = message.verified && verify_locally(message);
+    let summary = hex::decode(&message.context)
+        .ok()
+        .and_then(|context| render_context(&context, "en").ok())
+        .map(|rendered| rendered.summary);
+
+    match output {
+        OutputFormat::Json => {
+            let line = WatchedMessage {
+                id: &message.id,
+                group_id: &message.group_id,
+                sender_hex: &message.sender,
+                body: &message.body,
+                created_at: message.created_at.to_rfc3339(),
+                verified,
+                thread_id: message.thread_id.as_deref(),
+                summary,
+            };
+            println!("{}", serde_json::to_string(&line).unwrap());
+        }
+        OutputFormat::Text => {
+            let sender = message.sender.get(..SENDER_PREFIX).unwrap_or(&message.sender);
+            println!(
+                "{} {} {}…: {}",
+                if verified { "✅" } else { "❌" },
+                message.created_at.format("%Y-%m-%d %H:%M:%S"),
+                sender,
+                message.body
+            );
+            if let Some(summary) = summary {
+                println!("   Approves: {}", summary);
+            }
+        }
+    }
+}
+
+/// Print a group's messages as they arrive until `limit` have been printed
+///
+/// Status and reconnect notices go to stderr, so stdout holds only messages.
+pub async fn run(
+    output: OutputFormat,
+    client: RelayClient,
+    group: &str,
+    poll_timeout: Duration,
+    limit: Option<usize>,
+) -> Result<(), CliError> {
+    eprintln!("👀 Watching group {} (Ctrl-C to stop)", group);
+
+    let mut since_token: Option<String> = None;
+    let mut backoff = Backoff::new();
+    let mut printed = 0;
+    while limit.is_none_or(|limit| printed < limit) {
+        match client.poll_messages(group, since_token.as_deref(), poll_timeout).await {
+            Ok(page) => {
+                backoff.reset();
+                for message in page.messages.iter().take(limit.map_or(usize::MAX, |limit| limit - printed)) {
+                    print_message(&output, message);
+                    printed += 1;
+                }
+                since_token = Some(page.change_token);
+            }
+            Err(e) if is_fatal(&e) => return Err(CliError::new(ErrorKind::Network, e.to_string())),
+            Err(e) => {
+                let delay = backoff.next_delay();
+                eprintln!("⚠️  {}; reconnecting in {}s", e, delay.as_secs());
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proof_messenger_protocol::relay_client::ErrorCode;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+
+    fn api_error(status: u16) -> RelayClientError {
+        RelayClientError::Api {
+            status,
+            code: ErrorCode::Unknown,
+            message: String::new(),
+            request_id: None,
+            details: None,
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_a_minute_and_resets() {
+        let mut backoff = Backoff::new();
+        let delays: Vec<u64> = (0..8).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), INITIAL_BACKOFF);
+    }
+
+    #[test]
+    fn only_client_errors_end_the_watch() {
+        assert!(is_fatal(&api_error(403)));
+        assert!(is_fatal(&api_error(404)));
+        assert!(!is_fatal(&api_error(429)));
+        assert!(!is_fatal(&api_error(503)));
+        assert!(!is_fatal(&RelayClientError::Decode("truncated".to_string())));
+    }
+
+    #[test]
+    fn proofs_are_checked_locally() {
+        let keypair = generate_secure_keypair_with_seed(7);
+        let mut message = RelayedMessage {
+            id: "m1".to_string(),
+            group_id: "ops".to_string(),
+            sender: hex::encode(keypair.public_key_bytes()),
+            context: hex::encode("approve"),
+            body: "ok".to_string(),
+            proof: hex::encode(keypair.sign(b"approve").to_bytes()),
+            created_at: chrono::Utc::now(),
+            verified: true,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        };
+        assert!(verify_locally(&message));
+
+        message.context = hex::encode("approve twice");
+        assert!(!verify_locally(&message));
+    }
+}
@@ -12,8 +12,9 @@ use std::error::Error;
 #[test]
 fn keygen_command_produces_valid_json_output() -> Result<(), Box<dyn Error>> {
     // ARRANGE: Prepare the command to run the CLI binary
+    let dir = tempfile::tempdir()?;
     let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
-    cmd.arg("keygen").arg("--output").arg("json");
+    cmd.arg("keygen").arg("--keystore").arg(dir.path().join("keypair.json")).arg("--output").arg("json");
 
     // ACT & ASSERT: Run the command and assert that it succeeds
     let output = cmd.assert().success().get_output().stdout.clone();
@@ -99,8 +100,8 @@ fn verify_command_produces_valid_json_output() -> Result<(), Box<dyn Error>> {
         .arg("--output")
         .arg("json");
 
-    // ACT & ASSERT: Run and validate JSON output
-    let output = cmd.assert().success().get_output().stdout.clone();
+    // ACT & ASSERT: Run and validate JSON output; the proof does not verify
+    let output = cmd.assert().code(2).get_output().stdout.clone();
     let output_str = String::from_utf8(output)?;
     let json: Value = serde_json::from_str(&output_str)?;
 
@@ -150,8 +151,9 @@ fn send_command_produces_valid_json_output() -> Result<(), Box<dyn Error>> {
 #[test]
 fn default_output_is_text_format() -> Result<(), Box<dyn Error>> {
     // ARRANGE: Run command without --output flag
+    let dir = tempfile::tempdir()?;
     let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
-    cmd.arg("keygen");
+    cmd.arg("keygen").arg("--keystore").arg(dir.path().join("keypair.json"));
 
     // ACT: Run and capture output
     let output = cmd.assert().success().get_output().stdout.clone();
@@ -211,8 +213,9 @@ fn json_output_is_consistent_for_deterministic_commands() -> Result<(), Box<dyn
 #[test]
 fn json_output_is_properly_formatted() -> Result<(), Box<dyn Error>> {
     // ARRANGE: Run command with JSON output
+    let dir = tempfile::tempdir()?;
     let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
-    cmd.arg("keygen").arg("--output").arg("json");
+    cmd.arg("keygen").arg("--keystore").arg(dir.path().join("keypair.json")).arg("--output").arg("json");
 
     // ACT: Run and capture output
     let output = cmd.assert().success().get_output().stdout.clone();
@@ -226,4 +229,857 @@ fn json_output_is_properly_formatted() -> Result<(), Box<dyn Error>> {
     let _json: Value = serde_json::from_str(&output_str)?;
 
     Ok(())
-}
\ No newline at end of file
+}
+/// Test that a hybrid onboarding proof verifies under both policies
+#[test]
+fn hybrid_onboard_proof_verifies() -> Result<(), Box<dyn Error>> {
+    // ARRANGE: Create a hybrid onboarding proof
+    let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+    cmd.arg("onboard").arg("123").arg("--hybrid").arg("--output").arg("json");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let json: Value = serde_json::from_str(&String::from_utf8(output)?)?;
+    let proof = json["hybridProofHex"].as_str().unwrap().to_string();
+    let public_key = json["hybridPublicKeyHex"].as_str().unwrap().to_string();
+
+    // ACT & ASSERT: Verify with the strict and transitional policies
+    for policy in ["strict", "transitional"] {
+        let mut verify = Command::cargo_bin("proof-messenger-cli")?;
+        verify.arg("verify-hybrid").arg(&proof).arg(&public_key).arg("123")
+            .arg("--policy").arg(policy).arg("--output").arg("json");
+        let output = verify.assert().success().get_output().stdout.clone();
+        let json: Value = serde_json::from_str(&String::from_utf8(output)?)?;
+        assert_eq!(json["verified"], true);
+        assert_eq!(json["policy"].as_str().unwrap(), policy);
+    }
+
+    // A different invite must not verify
+    let mut verify = Command::cargo_bin("proof-messenger-cli")?;
+    verify.arg("verify-hybrid").arg(&proof).arg(&public_key).arg("124");
+    verify.assert().failure();
+
+    Ok(())
+}
+
+/// Test that an invite URI carries the invite through onboarding
+#[test]
+fn onboard_from_invite_uri_verifies_against_the_seed() -> Result<(), Box<dyn Error>> {
+    // ARRANGE: An invite URI for seed 42, which invites with seed 43
+    let mut invite = Command::cargo_bin("proof-messenger-cli")?;
+    invite.arg("invite").arg("--seed").arg("42").arg("--group").arg("eng team")
+        .arg("--relay").arg("http://localhost:8080").arg("--output").arg("json");
+    let invited: Value = serde_json::from_slice(&invite.assert().success().get_output().stdout)?;
+    let uri = invited["inviteUri"].as_str().unwrap();
+    assert!(uri.starts_with("pm://invite?v=1&"));
+    assert!(uri.contains("&g=eng%20team"));
+
+    // ACT: Onboard from the URI
+    let mut onboard = Command::cargo_bin("proof-messenger-cli")?;
+    onboard.arg("onboard").arg("--invite-uri").arg(uri).arg("--output").arg("json");
+    let onboarded: Value = serde_json::from_slice(&onboard.assert().success().get_output().stdout)?;
+
+    // ASSERT: The proof is over the same invite data as seed 43
+    assert_eq!(onboarded["inviteGroup"], "eng team");
+    assert!(onboarded.get("inviteSeed").is_none());
+    let public_key = ed25519_dalek::PublicKey::from_bytes(&hex::decode(onboarded["publicKeyHex"].as_str().unwrap())?)?;
+    let proof = ed25519_dalek::Signature::from_bytes(&hex::decode(onboarded["proofHex"].as_str().unwrap())?)?;
+    let invite = proof_messenger_protocol::proof::Invite::new_with_seed(43);
+    assert!(proof_messenger_protocol::proof::verify_proof(&proof, &public_key, &invite));
+
+    // A malformed URI is an error, not a panic
+    let mut bad = Command::cargo_bin("proof-messenger-cli")?;
+    bad.arg("onboard").arg("--invite-uri").arg("pm://invite?v=1");
+    bad.assert().failure().stderr(predicate::str::contains("Invalid invite URI"));
+
+    Ok(())
+}
+
+/// Test that invite --qr renders to the terminal or writes a PNG
+#[test]
+fn invite_qr_renders_to_terminal_and_png() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let png = dir.path().join("invite.png");
+
+    // A path writes a PNG and reports it
+    let mut to_file = Command::cargo_bin("proof-messenger-cli")?;
+    to_file.arg("invite").arg("--qr").arg(&png).arg("--output").arg("json");
+    let output: Value = serde_json::from_slice(&to_file.assert().success().get_output().stdout)?;
+    assert_eq!(output["qrFile"].as_str().unwrap(), png.display().to_string());
+    assert!(std::fs::read(&png)?.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+    // No path draws it; with JSON output it goes to stderr so stdout still parses
+    let mut to_terminal = Command::cargo_bin("proof-messenger-cli")?;
+    to_terminal.arg("invite").arg("--qr").arg("--output").arg("json");
+    let assert = to_terminal.assert().success().stderr(predicate::str::contains("▀"));
+    let output: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert!(output.get("qrFile").is_none());
+
+    Ok(())
+}
+
+/// Test that the receipt command signs a receipt that verifies
+#[test]
+fn receipt_command_produces_verifiable_receipt() -> Result<(), Box<dyn Error>> {
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_protocol::receipt::{make_receipt, message_hash, verify_receipt};
+
+    // ARRANGE & ACT: Sign a receipt for a message with a deterministic key
+    let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+    cmd.arg("receipt").arg("msg-1")
+        .arg("--sender").arg("aa11").arg("--context").arg("bb22").arg("--body").arg("hello")
+        .arg("--seed").arg("7").arg("--output").arg("json");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let json: Value = serde_json::from_str(&String::from_utf8(output)?)?;
+
+    // ASSERT: The output matches a receipt signed over the same message hash
+    let hash = message_hash(&[0xaa, 0x11], &[0xbb, 0x22], b"hello");
+    let expected = make_receipt(&generate_secure_keypair_with_seed(7), "msg-1", &hash)?;
+    assert!(verify_receipt(&expected, "msg-1", &hash).is_ok());
+    assert_eq!(json["messageId"], "msg-1");
+    assert_eq!(json["messageHashHex"].as_str().unwrap(), hex::encode(hash));
+    assert_eq!(json["recipientHex"].as_str().unwrap(), hex::encode(expected.recipient.to_bytes()));
+    assert_eq!(json["signatureHex"].as_str().unwrap(), hex::encode(expected.signature.to_bytes()));
+
+    Ok(())
+}
+
+/// Test that the receipt command signs with the keystore key by default
+#[test]
+fn receipt_command_signs_with_keystore_key() -> Result<(), Box<dyn Error>> {
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_protocol::receipt::{message_hash, verify_receipt, Receipt};
+
+    // ARRANGE: A keystore holding a known keypair
+    let dir = tempfile::tempdir()?;
+    let keystore = dir.path().join("keypair.json");
+    let keypair = generate_secure_keypair_with_seed(11);
+    std::fs::write(&keystore, serde_json::to_string(&keypair.to_bytes().to_vec())?)?;
+
+    // ACT: Sign a receipt without choosing a key
+    let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+    cmd.arg("receipt").arg("msg-1")
+        .arg("--sender").arg("aa11").arg("--context").arg("bb22").arg("--body").arg("hello")
+        .arg("--keystore").arg(&keystore).arg("--output").arg("json");
+    let json: Value = serde_json::from_slice(&cmd.assert().success().get_output().stdout)?;
+
+    // ASSERT: The receipt verifies as signed by the keystore's public key
+    let hash = message_hash(&[0xaa, 0x11], &[0xbb, 0x22], b"hello");
+    assert_eq!(json["recipientHex"].as_str().unwrap(), hex::encode(keypair.public_key_bytes()));
+    let receipt = Receipt {
+        message_id: "msg-1".to_string(),
+        message_hash: hash,
+        recipient: keypair.public_key(),
+        signature: ed25519_dalek::Signature::from_bytes(&hex::decode(json["signatureHex"].as_str().unwrap())?)?,
+    };
+    assert!(verify_receipt(&receipt, "msg-1", &hash).is_ok());
+
+    Ok(())
+}
+
+/// Test that onboard and send sign with the key from the keystore file
+#[test]
+fn file_signer_uses_keystore_key() -> Result<(), Box<dyn Error>> {
+    use ed25519_dalek::{PublicKey, Signature, Verifier};
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_protocol::proof::Invite;
+
+    // ARRANGE: A keystore holding a known keypair
+    let dir = tempfile::tempdir()?;
+    let keystore = dir.path().join("keypair.json");
+    let keypair = generate_secure_keypair_with_seed(11);
+    std::fs::write(&keystore, serde_json::to_string(&keypair.to_bytes().to_vec())?)?;
+    let expected_key = hex::encode(keypair.public_key_bytes());
+
+    // ACT: Onboard and send with the file signer
+    let mut onboard = Command::cargo_bin("proof-messenger-cli")?;
+    onboard.arg("onboard").arg("123").arg("--signer").arg("file")
+        .arg("--keystore").arg(&keystore).arg("--output").arg("json");
+    let onboarded: Value = serde_json::from_slice(&onboard.assert().success().get_output().stdout)?;
+    let mut send = Command::cargo_bin("proof-messenger-cli")?;
+    send.arg("send").arg("--to-pubkey").arg("bob").arg("--msg").arg("Hello World")
+        .arg("--signer").arg("file").arg("--keystore").arg(&keystore).arg("--output").arg("json");
+    let sent: Value = serde_json::from_slice(&send.assert().success().get_output().stdout)?;
+
+    // ASSERT: Both proofs verify under the keystore's public key
+    let public_key = PublicKey::from_bytes(&keypair.public_key_bytes())?;
+    let signature = |hex_sig: &Value| Signature::from_bytes(&hex::decode(hex_sig.as_str().unwrap()).unwrap());
+    assert_eq!(onboarded["publicKeyHex"].as_str().unwrap(), expected_key);
+    assert!(public_key.verify(&Invite::new_with_seed(123).data, &signature(&onboarded["proofHex"])?).is_ok());
+    assert_eq!(sent["senderHex"].as_str().unwrap(), expected_key);
+    assert!(public_key.verify(b"Hello World", &signature(&sent["proofHex"])?).is_ok());
+
+    Ok(())
+}
+
+/// Test that the YubiKey signer needs a hardware slot reference in the keystore
+#[test]
+fn yubikey_signer_rejects_raw_keystore() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let keystore = dir.path().join("keypair.json");
+    std::fs::write(&keystore, serde_json::to_string(&vec![0u8; 64])?)?;
+
+    let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+    cmd.arg("onboard").arg("123").arg("--signer").arg("yubikey").arg("--keystore").arg(&keystore);
+
+    cmd.assert().failure().stderr(predicate::str::contains("keygen --signer yubikey"));
+
+    Ok(())
+}
+
+/// Test that signing with a hardware slot goes through the device tooling
+#[test]
+fn yubikey_signer_reports_missing_device_tool() -> Result<(), Box<dyn Error>> {
+    // ARRANGE: A keystore referencing a PIV slot, with no PIV tool installed
+    let dir = tempfile::tempdir()?;
+    let keystore = dir.path().join("keypair.json");
+    std::fs::write(
+        &keystore,
+        r#"{"hardware":{"device":"yubikey","interface":"piv","slot":"9c","publicKey":"3b6a27bcceb6a42d62a3a8d02a6f0d73653215771de243a63ac048a18b59da29"}}"#,
+    )?;
+
+    // ACT: Send a signed message
+    let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+    cmd.env("PROOF_MESSENGER_PIV_TOOL", dir.path().join("missing-piv-tool"))
+        .arg("send").arg("--to-pubkey").arg("bob").arg("--msg").arg("hi")
+        .arg("--signer").arg("yubikey").arg("--keystore").arg(&keystore);
+
+    // ASSERT: The command fails without falling back to a software key
+    cmd.assert().failure().stderr(predicate::str::contains("Failed to run"));
+
+    Ok(())
+}
+
+/// Test that a remote signer keystore signs through the signer daemon
+#[cfg(unix)]
+#[test]
+fn remote_signer_signs_through_the_daemon() -> Result<(), Box<dyn Error>> {
+    use ed25519_dalek::{PublicKey, Signature, Verifier};
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+
+    // ARRANGE: A daemon serving a known key, and a client keystore enrolled with it
+    let dir = tempfile::tempdir()?;
+    let (daemon_keystore, client_keystore) = (dir.path().join("daemon.json"), dir.path().join("client.json"));
+    let socket = dir.path().join("signer.sock");
+    let keypair = generate_secure_keypair_with_seed(11);
+    std::fs::write(&daemon_keystore, serde_json::to_string(&keypair.to_bytes().to_vec())?)?;
+    let mut daemon = std::process::Command::new(assert_cmd::cargo::cargo_bin("proof-messenger-cli"))
+        .arg("signer-daemon").arg("--socket").arg(&socket).arg("--keystore").arg(&daemon_keystore)
+        .stdout(std::process::Stdio::null())
+        .spawn()?;
+    for _ in 0..100 {
+        if socket.exists() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    let mut keygen = Command::cargo_bin("proof-messenger-cli")?;
+    keygen.arg("keygen").arg("--signer").arg("remote").arg("--socket").arg(&socket)
+        .arg("--keystore").arg(&client_keystore).arg("--output").arg("json");
+    let enrolled: Value = serde_json::from_slice(&keygen.assert().success().get_output().stdout)?;
+
+    // ACT: Send with the remote signer, then with a key id the daemon does not hold
+    let mut send = Command::cargo_bin("proof-messenger-cli")?;
+    send.arg("send").arg("--to-pubkey").arg("bob").arg("--msg").arg("Hello World")
+        .arg("--signer").arg("remote").arg("--keystore").arg(&client_keystore).arg("--output").arg("json");
+    let sent = send.assert().success().get_output().stdout.clone();
+    let mut unknown = Command::cargo_bin("proof-messenger-cli")?;
+    unknown.arg("keygen").arg("--signer").arg("remote").arg("--socket").arg(&socket).arg("--key-id").arg("backup")
+        .arg("--keystore").arg(dir.path().join("backup.json")).arg("--output").arg("json");
+    let unknown = unknown.assert().code(4);
+    daemon.kill()?;
+    daemon.wait()?;
+
+    // ASSERT: The proof is the daemon key's; the keystore holds no key bytes
+    let sent: Value = serde_json::from_slice(&sent)?;
+    let expected_key = hex::encode(keypair.public_key_bytes());
+    assert_eq!(enrolled["publicKeyHex"].as_str().unwrap(), expected_key);
+    assert_eq!(sent["senderHex"].as_str().unwrap(), expected_key);
+    let public_key = PublicKey::from_bytes(&keypair.public_key_bytes())?;
+    let signature = Signature::from_bytes(&hex::decode(sent["proofHex"].as_str().unwrap())?)?;
+    assert!(public_key.verify(b"Hello World", &signature).is_ok());
+    let stored: Value = serde_json::from_str(&std::fs::read_to_string(&client_keystore)?)?;
+    assert_eq!(stored["remote"]["keyId"], "default");
+    let error: Value = serde_json::from_slice(&unknown.get_output().stderr)?;
+    assert!(error["error"].as_str().unwrap().contains("Unknown key: backup"));
+
+    Ok(())
+}
+
+/// Test that a detached file proof verifies against the file and nothing else
+#[test]
+fn prove_file_and_verify_file_roundtrip() -> Result<(), Box<dyn Error>> {
+    // ARRANGE: A document to approve
+    let dir = tempfile::tempdir()?;
+    let document = dir.path().join("approval.pdf");
+    std::fs::write(&document, b"%PDF-1.7 purchase approval")?;
+
+    // ACT: Prove the file with BLAKE3, then verify it
+    let mut prove = Command::cargo_bin("proof-messenger-cli")?;
+    prove.arg("prove-file").arg(&document).arg("--algorithm").arg("blake3")
+        .arg("--seed").arg("9").arg("--output").arg("json");
+    let proved: Value = serde_json::from_slice(&prove.assert().success().get_output().stdout)?;
+    let mut verify = Command::cargo_bin("proof-messenger-cli")?;
+    verify.arg("verify-file").arg(&document).arg("--output").arg("json");
+    let verified: Value = serde_json::from_slice(&verify.assert().success().get_output().stdout)?;
+
+    // ASSERT: The proof describes the file and verifies
+    assert_eq!(proved["proof"]["algorithm"], "blake3");
+    assert_eq!(proved["proof"]["metadata"]["filename"], "approval.pdf");
+    assert_eq!(proved["proof"]["metadata"]["content_type"], "application/pdf");
+    assert!(dir.path().join("approval.pdf.proof.json").exists());
+    assert_eq!(verified["verified"], true);
+
+    // A changed file no longer matches the proof
+    std::fs::write(&document, b"%PDF-1.7 purchase approval (edited)")?;
+    let mut tampered = Command::cargo_bin("proof-messenger-cli")?;
+    tampered.arg("verify-file").arg(&document);
+    tampered.assert().failure().stdout(predicate::str::contains("❌ No"));
+
+    Ok(())
+}
+
+/// Test that verify-file rejects a proof from an unexpected signer
+#[test]
+fn verify_file_checks_expected_public_key() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let document = dir.path().join("notes.txt");
+    std::fs::write(&document, b"meeting notes")?;
+    Command::cargo_bin("proof-messenger-cli")?
+        .arg("prove-file").arg(&document).arg("--seed").arg("9")
+        .assert().success();
+
+    let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+    cmd.arg("verify-file").arg(&document).arg("--public-key").arg("00".repeat(32));
+
+    cmd.assert().failure().stdout(predicate::str::contains("different key"));
+
+    Ok(())
+}
+
+/// Test that sign-context matches the shared context digest vectors
+#[test]
+fn sign_context_matches_shared_vectors() -> Result<(), Box<dyn Error>> {
+    let vectors: Value = serde_json::from_str(include_str!("../../proof-messenger-protocol/tests/vectors/context_digest.json"))?;
+    let dir = tempfile::tempdir()?;
+
+    for vector in vectors["vectors"].as_array().unwrap() {
+        // ARRANGE: The vector's context in a file
+        let context = match vector["context_hex"].as_str() {
+            Some(context) => hex::decode(context)?,
+            None => {
+                let pattern = &vector["context_pattern"];
+                let modulus = pattern["modulus"].as_u64().unwrap();
+                (0..pattern["length"].as_u64().unwrap()).map(|i| (i % modulus) as u8).collect()
+            }
+        };
+        let path = dir.path().join(vector["name"].as_str().unwrap());
+        std::fs::write(&path, &context)?;
+
+        // ACT: Sign it with the vector's key
+        let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+        cmd.arg("sign-context").arg(&path).arg("--seed").arg(vector["seed"].to_string())
+            .arg("--output").arg("json");
+        let json: Value = serde_json::from_slice(&cmd.assert().success().get_output().stdout)?;
+
+        // ASSERT: Digest and signature match the protocol crate's
+        assert_eq!(json["contextLength"].as_u64().unwrap(), context.len() as u64);
+        assert_eq!(json["digestHex"], vector["digest"]);
+        assert_eq!(json["publicKeyHex"], vector["public_key"]);
+        assert_eq!(json["signatureHex"], vector["signature"]);
+    }
+
+    Ok(())
+}
+
+/// Test that seeded commands reproduce the protocol's golden vectors
+#[test]
+fn seeded_commands_match_golden_vectors() -> Result<(), Box<dyn Error>> {
+    let vectors = proof_messenger_protocol::test_vectors::golden();
+
+    for vector in &vectors.invites {
+        // ACT: Verify the golden invite proof, and a tampered copy
+        let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+        cmd.arg("verify").arg(hex::encode(&vector.proof)).arg(vector.seed.to_string())
+            .arg("--output").arg("json");
+        let json: Value = serde_json::from_slice(&cmd.assert().success().get_output().stdout)?;
+
+        let mut tampered = vector.proof.clone();
+        tampered[0] ^= 1;
+        let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+        cmd.arg("verify").arg(hex::encode(&tampered)).arg(vector.seed.to_string())
+            .arg("--output").arg("json");
+        let tampered: Value = serde_json::from_slice(&cmd.assert().code(2).get_output().stdout)?;
+
+        // ASSERT: Only the golden proof verifies
+        assert_eq!(json["verified"], true, "invite seed {}", vector.seed);
+        assert_eq!(tampered["verified"], false, "invite seed {}", vector.seed);
+    }
+
+    for vector in &vectors.receipts {
+        // ARRANGE: The sender key the vector's message came from
+        let sender = proof_messenger_protocol::key::generate_secure_keypair_with_seed(vector.sender_seed)
+            .public_key_bytes();
+
+        // ACT: Sign the receipt with the vector's recipient seed
+        let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+        cmd.arg("receipt").arg(&vector.message_id)
+            .arg("--sender").arg(hex::encode(sender))
+            .arg("--context").arg(hex::encode(&vector.context))
+            .arg("--body").arg(&vector.body)
+            .arg("--seed").arg(vector.recipient_seed.to_string())
+            .arg("--output").arg("json");
+        let json: Value = serde_json::from_slice(&cmd.assert().success().get_output().stdout)?;
+
+        // ASSERT: Hash and signature match the protocol crate's
+        assert_eq!(json["messageHashHex"].as_str().unwrap(), hex::encode(&vector.message_hash), "{}", vector.name);
+        assert_eq!(json["signatureHex"].as_str().unwrap(), hex::encode(&vector.signature), "{}", vector.name);
+    }
+
+    Ok(())
+}
+
+/// Test that a message signed by `send` is accepted by a running relay
+#[tokio::test]
+async fn signed_send_output_is_accepted_by_relay() -> Result<(), Box<dyn Error>> {
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_protocol::relay_client::OutgoingMessage;
+    use proof_messenger_relay::test_util::TestRelay;
+
+    // ARRANGE: A running relay and a keystore to sign with
+    let relay = TestRelay::start().await;
+    let dir = tempfile::tempdir()?;
+    let keystore = dir.path().join("keypair.json");
+    let keypair = generate_secure_keypair_with_seed(12);
+    std::fs::write(&keystore, serde_json::to_string(&keypair.to_bytes().to_vec())?)?;
+
+    // ACT: Sign a message with the CLI and post it to the relay
+    let mut send = Command::cargo_bin("proof-messenger-cli")?;
+    send.arg("send").arg("--to-pubkey").arg("bob").arg("--msg").arg("Hello Relay")
+        .arg("--signer").arg("file").arg("--keystore").arg(&keystore).arg("--output").arg("json");
+    let sent: Value = serde_json::from_slice(&send.assert().success().get_output().stdout)?;
+    let message = OutgoingMessage {
+        sender: sent["senderHex"].as_str().unwrap().to_string(),
+        context: hex::encode("Hello Relay"),
+        body: "Hello Relay".to_string(),
+        proof: sent["proofHex"].as_str().unwrap().to_string(),
+        pqc: None,
+        thread_id: None,
+        reply_to: None,
+        expires_at: None,
+        recipient: None,
+    };
+    let message_id = relay.client().send_message(&message).await?;
+
+    // ASSERT: The relay verified and stored the CLI's proof
+    let messages = relay.client().get_messages("default", None).await?;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].id, message_id);
+    assert_eq!(messages[0].sender, hex::encode(keypair.public_key_bytes()));
+    assert!(messages[0].verified);
+
+    Ok(())
+}
+
+/// Test that verify-bundle checks a relay evidence bundle without the relay
+#[tokio::test]
+async fn verify_bundle_checks_relay_evidence_offline() -> Result<(), Box<dyn Error>> {
+    use ed25519_dalek::Signer;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_relay::database::{Database, StoredMessage};
+    use proof_messenger_relay::evidence::evidence_bundle;
+    use proof_messenger_relay::tenancy::TenantScope;
+    use proof_messenger_relay::transparency::TransparencyLog;
+
+    // ARRANGE: A relayed message and its exported bundle
+    let db = Database::new("sqlite::memory:").await?;
+    db.migrate().await?;
+    let sender = generate_secure_keypair_with_seed(12);
+    let context = br#"{"template":"wire_transfer","values":{"amount":"50000","currency":"USD","beneficiary":"ACME-123"}}"#;
+    let message_id = db
+        .store_message(StoredMessage::from(proof_messenger_relay::Message {
+            sender: hex::encode(sender.public_key_bytes()),
+            context: hex::encode(context),
+            body: "approved".to_string(),
+            proof: hex::encode(sender.as_keypair().sign(context).to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }))
+        .await?;
+    let relay_key = generate_secure_keypair_with_seed(13);
+    let log = TransparencyLog::new(generate_secure_keypair_with_seed(13));
+    let bundle = evidence_bundle(&db, &log, &TenantScope::default(), &message_id).await?;
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("evidence.json");
+    std::fs::write(&path, serde_json::to_string_pretty(&bundle)?)?;
+
+    // ACT: Verify it against the pinned relay key
+    let mut verify = Command::cargo_bin("proof-messenger-cli")?;
+    verify.arg("verify-bundle").arg(&path)
+        .arg("--relay-key").arg(hex::encode(relay_key.public_key_bytes())).arg("--output").arg("json");
+    let verified: Value = serde_json::from_slice(&verify.assert().success().get_output().stdout)?;
+
+    // ASSERT: The bundle verifies; another key or an edited body does not
+    assert_eq!(verified["verified"], true);
+    assert_eq!(verified["messageId"], message_id);
+    assert_eq!(verified["revoked"], false);
+    assert_eq!(verified["summary"], "Approve wire of $50,000.00 to ACME-123");
+    Command::cargo_bin("proof-messenger-cli")?
+        .arg("verify-bundle").arg(&path).arg("--locale").arg("de-DE")
+        .assert().success().stdout(predicate::str::contains("Approves: Überweisung von 50.000,00\u{A0}$ an ACME-123 freigeben"));
+    Command::cargo_bin("proof-messenger-cli")?
+        .arg("verify-bundle").arg(&path).arg("--relay-key").arg(hex::encode(sender.public_key_bytes()))
+        .assert().failure().stdout(predicate::str::contains("different relay key"));
+    let mut edited = bundle.clone();
+    edited.message.body = "rejected".to_string();
+    std::fs::write(&path, serde_json::to_string(&edited)?)?;
+    Command::cargo_bin("proof-messenger-cli")?
+        .arg("verify-bundle").arg(&path)
+        .assert().failure().stdout(predicate::str::contains("❌ No"));
+
+    Ok(())
+}
+
+/// Public key of the keypair `invite --seed <seed>` derives
+fn test_public_key(seed: u64) -> Result<String, Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+    cmd.arg("invite").arg("--seed").arg(seed.to_string()).arg("--output").arg("json");
+    let json: Value = serde_json::from_slice(&cmd.assert().success().get_output().stdout)?;
+    Ok(json["publicKeyHex"].as_str().unwrap().to_string())
+}
+
+/// A JSON-output CLI command using the contact book in `config_dir`
+fn contact_cmd(config_dir: &std::path::Path, args: &[&str]) -> Result<Command, Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+    cmd.arg("--config-dir").arg(config_dir).args(args).arg("--output").arg("json");
+    Ok(cmd)
+}
+
+/// Test that contacts are stored unverified and verified by fingerprint
+#[test]
+fn contact_add_list_and_verify_by_fingerprint() -> Result<(), Box<dyn Error>> {
+    // ARRANGE: Alice's key in a fresh contact book
+    let dir = tempfile::tempdir()?;
+    let alice = test_public_key(1)?;
+    let added: Value = serde_json::from_slice(
+        &contact_cmd(dir.path(), &["contact", "add", "alice", &alice])?.assert().success().get_output().stdout,
+    )?;
+    assert_eq!(added["contact"]["trust"], "unverified");
+    let words = added["contact"]["fingerprint"]["words"].as_str().unwrap().to_string();
+    assert_eq!(added["contact"]["fingerprint"]["hex"].as_str().unwrap().split(' ').count(), 16);
+
+    // ACT: A wrong fingerprint fails, the words Alice reads out succeed
+    let mismatch: Value = serde_json::from_slice(
+        &contact_cmd(dir.path(), &["contact", "verify", "alice", "--fingerprint", &"0".repeat(64)])?
+            .assert()
+            .failure()
+            .get_output()
+            .stdout,
+    )?;
+    let matched: Value = serde_json::from_slice(
+        &contact_cmd(dir.path(), &["contact", "verify", "alice", "--fingerprint", &words.to_uppercase()])?
+            .assert()
+            .success()
+            .get_output()
+            .stdout,
+    )?;
+
+    // ASSERT: Only the match marked Alice verified, and the book kept it
+    assert_eq!(mismatch["status"], "failed");
+    assert_eq!(mismatch["contact"]["trust"], "unverified");
+    assert_eq!(matched["matched"], true);
+    let listed: Value = serde_json::from_slice(
+        &contact_cmd(dir.path(), &["contact", "list"])?.assert().success().get_output().stdout,
+    )?;
+    assert_eq!(listed["contacts"][0]["name"], "alice");
+    assert_eq!(listed["contacts"][0]["publicKeyHex"], alice.as_str());
+    assert_eq!(listed["contacts"][0]["trust"], "verified");
+
+    Ok(())
+}
+
+/// Test that changing a contact's key needs --replace and resets its trust
+#[test]
+fn contact_key_change_requires_replace() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    contact_cmd(dir.path(), &["contact", "add", "alice", &test_public_key(1)?])?.assert().success();
+    contact_cmd(dir.path(), &["contact", "verify", "alice", "--confirm"])?.assert().success();
+
+    let other = test_public_key(2)?;
+    contact_cmd(dir.path(), &["contact", "add", "alice", &other])?.assert().failure();
+    let replaced: Value = serde_json::from_slice(
+        &contact_cmd(dir.path(), &["contact", "add", "alice", &other, "--replace"])?.assert().success().get_output().stdout,
+    )?;
+
+    assert_eq!(replaced["contact"]["publicKeyHex"], other.as_str());
+    assert_eq!(replaced["contact"]["trust"], "unverified");
+
+    Ok(())
+}
+
+/// Test that send accepts a contact name in place of a public key
+#[test]
+fn send_to_contact_name_uses_its_key() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let alice = test_public_key(1)?;
+    contact_cmd(dir.path(), &["contact", "add", "alice", &alice])?.assert().success();
+
+    let sent: Value = serde_json::from_slice(
+        &contact_cmd(dir.path(), &["send", "--to", "alice", "--msg", "hi"])?.assert().success().get_output().stdout,
+    )?;
+
+    assert_eq!(sent["recipient"], alice.as_str());
+    assert_eq!(sent["recipientName"], "alice");
+    assert_eq!(sent["recipientTrust"], "unverified");
+    let unknown: Value = serde_json::from_slice(
+        &contact_cmd(dir.path(), &["send", "--to", "bob", "--msg", "hi"])?.assert().success().get_output().stdout,
+    )?;
+    assert_eq!(unknown["recipient"], "bob");
+    assert!(unknown.get("recipientName").is_none());
+
+    Ok(())
+}
+
+/// Test that a repl session signs with the keystore loaded once and survives errors
+#[test]
+fn repl_keeps_identity_loaded_across_commands() -> Result<(), Box<dyn Error>> {
+    // ARRANGE: A keystore and a contact
+    let dir = tempfile::tempdir()?;
+    let keystore = dir.path().join("keypair.json");
+    let mut keygen = Command::cargo_bin("proof-messenger-cli")?;
+    keygen.arg("keygen").arg("--keystore").arg(&keystore).arg("--output").arg("json");
+    let generated: Value = serde_json::from_slice(&keygen.assert().success().get_output().stdout)?;
+    let sender = generated["publicKeyHex"].as_str().unwrap().to_string();
+    contact_cmd(dir.path(), &["contact", "add", "alice", &test_public_key(1)?])?.assert().success();
+
+    // ACT: Run several commands, including failing ones, in one session
+    let mut repl = Command::cargo_bin("proof-messenger-cli")?;
+    repl.arg("repl").arg("--keystore").arg(&keystore).arg("--config-dir").arg(dir.path())
+        .write_stdin("send alice \"hello there\"\nsend \"unbalanced\nnope\nlist\nwhoami\nexit\nlist\n");
+    let output = repl.assert().success().get_output().clone();
+    let stdout = String::from_utf8(output.stdout)?;
+
+    // ASSERT: Each message is signed by the keystore key and the session ends at exit
+    assert!(stdout.contains("Message: 'hello there'"));
+    assert_eq!(stdout.matches(&format!("Sender: {}", sender)).count(), 1);
+    assert_eq!(stdout.matches("alice (⚠️  unverified)").count(), 1);
+    assert!(stdout.lines().any(|line| line == sender));
+    assert!(String::from_utf8(output.stderr)?.contains("Unbalanced quotes"));
+    assert!(std::fs::read_to_string(dir.path().join("repl_history"))?.contains("whoami"));
+
+    Ok(())
+}
+
+/// Test that completion scripts and man pages are generated for packaging
+#[test]
+fn completions_and_man_pages_are_generated() -> Result<(), Box<dyn Error>> {
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish"] {
+        Command::cargo_bin("proof-messenger-cli")?
+            .arg("completions").arg(shell)
+            .assert().success().stdout(predicate::str::contains("verify-bundle"));
+    }
+    Command::cargo_bin("proof-messenger-cli")?
+        .arg("man")
+        .assert().success().stdout(predicate::str::starts_with(".ie").and(predicate::str::contains(".TH proof-messenger-cli 1")));
+
+    let dir = tempfile::tempdir()?;
+    let out_dir = dir.path().join("man1");
+    Command::cargo_bin("proof-messenger-cli")?.arg("man").arg("--out-dir").arg(&out_dir).assert().success();
+    assert!(out_dir.join("proof-messenger-cli.1").exists());
+    assert!(out_dir.join("proof-messenger-cli-contact-verify.1").exists());
+    Command::cargo_bin("proof-messenger-cli")?.arg("completions").arg("tcsh").assert().failure();
+
+    Ok(())
+}
+
+/// Test that watch reconnects to a relay and prints messages as they are verified
+#[tokio::test]
+async fn watch_tails_a_group_across_reconnects() -> Result<(), Box<dyn Error>> {
+    use axum::Extension;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_protocol::relay_client::{OutgoingMessage, RelayClient};
+    use proof_messenger_relay::database::Database;
+    use proof_messenger_relay::subscriptions::Subscriptions;
+    use std::io::{BufRead, BufReader};
+    use std::sync::Arc;
+
+    // ARRANGE: A port with no relay on it yet, and a watch started against it
+    let port = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+    let relay_url = format!("http://127.0.0.1:{}", port);
+    let mut watch = std::process::Command::new(assert_cmd::cargo::cargo_bin("proof-messenger-cli"))
+        .args(["watch", "--group", "default", "--relay-url", &relay_url, "--poll-timeout", "1", "--limit", "2"])
+        .args(["--output", "json"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    let stdout = watch.stdout.take().unwrap();
+    let lines = std::thread::spawn(move || BufReader::new(stdout).lines().map_while(Result::ok).collect::<Vec<_>>());
+
+    // ACT: Start the relay, then keep relaying approvals until the watch has printed two
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    let db = Database::new("sqlite::memory:").await?;
+    db.migrate().await?;
+    let app = proof_messenger_relay::create_app(Arc::new(db)).layer(Extension(Arc::new(Subscriptions::new(16))));
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await?;
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let client = RelayClient::new(&relay_url)?;
+    let sender = generate_secure_keypair_with_seed(21);
+    for i in 0..100 {
+        if lines.is_finished() {
+            break;
+        }
+        let context = format!("approval {}", i);
+        client.send_message(&OutgoingMessage::signed(&sender, context.as_bytes(), "approved")?).await?;
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    }
+    let status = watch.wait()?;
+    let lines = lines.join().unwrap();
+    let mut stderr = String::new();
+    std::io::Read::read_to_string(&mut watch.stderr.take().unwrap(), &mut stderr)?;
+
+    // ASSERT: Two verified NDJSON lines, after reconnecting once the relay came up
+    assert!(status.success(), "{}", stderr);
+    assert_eq!(lines.len(), 2);
+    for line in &lines {
+        let message: Value = serde_json::from_str(line)?;
+        assert_eq!(message["verified"], true);
+        assert_eq!(message["groupId"], "default");
+        assert_eq!(message["senderHex"], hex::encode(sender.public_key_bytes()));
+    }
+    assert!(stderr.contains("reconnecting in 1s"), "{}", stderr);
+
+    Ok(())
+}
+
+/// Test that every subcommand exits with the code of its outcome and reports errors as JSON
+#[test]
+fn subcommands_exit_with_stable_codes() -> Result<(), Box<dyn Error>> {
+    // ARRANGE: A keystore, a contact, a document with its proof, and a tampered copy
+    let dir = tempfile::tempdir()?;
+    let path = |name: &str| dir.path().join(name).display().to_string();
+    let (keystore, missing, absent) = (path("keypair.json"), path("missing.json"), path("absent.txt"));
+    let (document, proof, bundle) = (path("doc.txt"), path("doc.proof.json"), path("bundle.json"));
+    let (recovered, phrase) = (path("recovered.json"), format!("{} about", ["abandon"; 11].join(" ")));
+    std::fs::write(&document, "quarterly report")?;
+    std::fs::write(&bundle, "not a bundle")?;
+    let alice = test_public_key(1)?;
+    let zeros = "0".repeat(64);
+    let run = |args: &[&str]| -> Result<assert_cmd::assert::Assert, Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("proof-messenger-cli")?;
+        cmd.arg("--config-dir").arg(dir.path()).args(args).arg("--output").arg("json");
+        Ok(cmd.assert())
+    };
+    run(&["contact", "add", "alice", &alice])?.success();
+    run(&["prove-file", &document, "--seed", "1", "--out", &proof])?.success();
+
+    // ACT & ASSERT: Each outcome maps to its code
+    let cases: Vec<(Vec<&str>, i32)> = vec![
+        (vec!["keygen", "--keystore", &keystore], 0),
+        (vec!["key", "recover", "--phrase", &phrase, "--keystore", &recovered], 0),
+        (vec!["invite", "--seed", "7"], 0),
+        (vec!["onboard", "8"], 0),
+        (vec!["send", "--to", "alice", "--msg", "hi", "--signer", "file", "--keystore", &keystore], 0),
+        (vec!["receipt", "m1", "--sender", &zeros, "--context", "00", "--body", "hi", "--keystore", &keystore], 0),
+        (vec!["sign-context", &document, "--seed", "1"], 0),
+        (vec!["verify-file", &document, "--proof", &proof], 0),
+        (vec!["contact", "list"], 0),
+        (vec!["completions", "bash"], 0),
+        (vec!["man"], 0),
+        (vec!["verify", &zeros, "42"], 2),
+        (vec!["verify-hybrid", &zeros, &zeros, "42"], 2),
+        (vec!["contact", "verify", "alice", "--fingerprint", &zeros], 2),
+        (vec!["send", "--to", "alice", "--msg", "hi", "--signer", "file", "--keystore", &missing], 4),
+        (vec!["repl", "--keystore", &missing], 4),
+        (vec!["prove-file", &document, "--signer", "file", "--keystore", &missing], 4),
+        (vec!["receipt", "m1", "--sender", &zeros, "--context", "00", "--body", "hi", "--keystore", &missing], 4),
+        (vec!["receipt", "m1", "--sender", "zz", "--context", "00", "--body", "hi"], 5),
+        (vec!["receipt", "", "--sender", "aa", "--context", "aa", "--body", "b"], 5),
+        (vec!["onboard", "--invite-uri", "https://example.com"], 5),
+        (vec!["prove-file", &absent, "--seed", "1"], 5),
+        (vec!["verify-bundle", &bundle], 5),
+        (vec!["watch", "--group", "ops", "--relay-url", "not a url"], 5),
+        (vec!["no-such-command"], 5),
+        (vec!["key", "recover", "--phrase", "abandon ability", "--keystore", &recovered], 5),
+        (vec!["keygen", "--mnemonic", "--signer", "yubikey", "--keystore", &missing], 5),
+        (vec!["keygen", "--signer", "remote", "--keystore", &missing], 5),
+        (vec!["keygen", "--signer", "remote", "--socket", &absent, "--keystore", &missing], 4),
+        (vec!["send", "--to", "alice", "--msg", "hi", "--signer", "remote", "--keystore", &keystore], 4),
+        (vec!["verify", "00"], 5),
+    ];
+    for (args, code) in cases {
+        let assert = run(&args)?.code(code);
+        if code >= 3 {
+            let error: Value = serde_json::from_slice(&assert.get_output().stderr)
+                .map_err(|e| format!("{:?}: {}", args, e))?;
+            assert_eq!(error["status"], "error", "{:?}", args);
+            assert_eq!(error["exitCode"], code, "{:?}", args);
+            assert!(error["error"].as_str().is_some_and(|message| !message.is_empty()), "{:?}", args);
+        }
+    }
+
+    // A changed document no longer matches its proof
+    std::fs::write(&document, "quarterly report, amended")?;
+    run(&["verify-file", &document, "--proof", &proof])?.code(2);
+    let error: Value = serde_json::from_slice(&run(&["repl", "--keystore", &missing])?.get_output().stderr)?;
+    assert_eq!(error["code"], "KEY_ERROR");
+
+    Ok(())
+}
+
+/// Test that watch exits with the network code when the relay cannot serve it
+#[tokio::test]
+async fn watch_exits_with_network_code_on_fatal_relay_errors() -> Result<(), Box<dyn Error>> {
+    use proof_messenger_relay::database::Database;
+    use std::sync::Arc;
+
+    // ARRANGE: A relay without live subscriptions
+    let db = Database::new("sqlite::memory:").await?;
+    db.migrate().await?;
+    let app = proof_messenger_relay::create_app(Arc::new(db));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let relay_url = format!("http://{}", listener.local_addr()?);
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    // ACT: Watch a group on it
+    let output = tokio::task::spawn_blocking(move || {
+        std::process::Command::new(assert_cmd::cargo::cargo_bin("proof-messenger-cli"))
+            .args(["watch", "--group", "ops", "--relay-url", &relay_url, "--output", "json"])
+            .output()
+    })
+    .await??;
+
+    // ASSERT: The poll is rejected, reported as a network error
+    assert_eq!(output.status.code(), Some(3));
+    let error: Value = serde_json::from_slice(output.stderr.rsplit(|b| *b == b'\n').find(|line| !line.is_empty()).unwrap_or_default())?;
+    assert_eq!(error["code"], "NETWORK_ERROR");
+
+    Ok(())
+}
+
+/// Test that a keystore key created with a backup phrase can be recovered from it
+#[test]
+fn keygen_mnemonic_backup_recovers_the_same_key() -> Result<(), Box<dyn Error>> {
+    // ARRANGE: A key generated with a backup phrase
+    let dir = tempfile::tempdir()?;
+    let original = dir.path().join("keypair.json");
+    let recovered = dir.path().join("recovered.json");
+    let mut keygen = Command::cargo_bin("proof-messenger-cli")?;
+    keygen.arg("keygen").arg("--mnemonic").arg("--keystore").arg(&original).arg("--output").arg("json");
+    let generated: Value = serde_json::from_slice(&keygen.assert().success().get_output().stdout)?;
+    let phrase = generated["mnemonic"].as_str().unwrap().to_string();
+    assert_eq!(phrase.split(' ').count(), 24);
+
+    // ACT: Recover it into another keystore, reading the phrase from stdin
+    let mut recover = Command::cargo_bin("proof-messenger-cli")?;
+    recover.arg("key").arg("recover").arg("--keystore").arg(&recovered).arg("--output").arg("json")
+        .write_stdin(format!("{}\n", phrase));
+    let restored: Value = serde_json::from_slice(&recover.assert().success().get_output().stdout)?;
+
+    // ASSERT: Same key, same keystore contents, and no phrase echoed back
+    assert_eq!(restored["publicKeyHex"], generated["publicKeyHex"]);
+    assert!(restored.get("mnemonic").is_none());
+    assert_eq!(std::fs::read(&recovered)?, std::fs::read(&original)?);
+
+    Ok(())
+}
@@ -0,0 +1,207 @@
+//! Message amendments: sender-signed corrections forming a versioned chain
+//!
+//! An amendment replaces the body of a relayed message with a new version.
+//! Its signed context, [`amendment_context`], binds the message id, the new
+//! version number and the [`message_hash`] of the version it replaces, so
+//! each amendment commits to the whole history before it. The original
+//! message is version 0, hashed from its sender, context and body; version
+//! `n` is hashed the same way from the sender, its amendment context and the
+//! new body, and that hash is what the sender signs.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//! use proof_messenger_protocol::amendment::{make_amendment, verify_amendment};
+//! use proof_messenger_protocol::receipt::message_hash;
+//!
+//! let sender = generate_secure_keypair();
+//! let original = message_hash(&sender.public_key_bytes(), b"context", b"helo");
+//! let amendment = make_amendment(&sender, "message-1", 1, &original, "hello").unwrap();
+//!
+//! assert!(verify_amendment(&amendment, &sender.public_key(), "message-1", 1, &original).is_ok());
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature};
+
+use crate::key::SecureKeypair;
+use crate::proof::{make_secure_proof, verify_proof_result, ProofError};
+use crate::receipt::{message_hash, MESSAGE_HASH_LENGTH};
+
+/// Domain separation prefix for amendment contexts
+const AMENDMENT_DOMAIN: &[u8] = b"proof-messenger/amendment/v1";
+
+/// A sender's signed replacement for the body of one of their messages
+#[derive(Debug, Clone, PartialEq)]
+pub struct Amendment {
+    /// Relay-assigned id of the amended message
+    pub message_id: String,
+    /// Version this amendment creates (the original message is version 0)
+    pub version: u64,
+    /// Hash of the version being replaced
+    pub previous_hash: [u8; MESSAGE_HASH_LENGTH],
+    /// The corrected message body
+    pub body: String,
+    /// Public key of the original sender
+    pub sender: PublicKey,
+    /// Sender's signature over the amendment hash
+    pub signature: Signature,
+}
+
+impl Amendment {
+    /// The context the amendment is hashed under
+    pub fn context(&self) -> Vec<u8> {
+        amendment_context(&self.message_id, self.version, &self.previous_hash)
+    }
+
+    /// Hash of this version, which the sender signs and the next amendment commits to
+    pub fn hash(&self) -> [u8; MESSAGE_HASH_LENGTH] {
+        message_hash(self.sender.as_bytes(), &self.context(), self.body.as_bytes())
+    }
+}
+
+/// Build the context for amending `message_id` to `version`
+pub fn amendment_context(message_id: &str, version: u64, previous_hash: &[u8; MESSAGE_HASH_LENGTH]) -> Vec<u8> {
+    let mut context = Vec::with_capacity(AMENDMENT_DOMAIN.len() + message_id.len() + MESSAGE_HASH_LENGTH + 10);
+    context.extend_from_slice(AMENDMENT_DOMAIN);
+    context.push(0);
+    context.extend_from_slice(message_id.as_bytes());
+    context.push(0);
+    context.extend_from_slice(&version.to_be_bytes());
+    context.extend_from_slice(previous_hash);
+    context
+}
+
+/// Sign an amendment replacing the version hashed as `previous_hash`
+pub fn make_amendment(
+    keypair: &SecureKeypair,
+    message_id: &str,
+    version: u64,
+    previous_hash: &[u8; MESSAGE_HASH_LENGTH],
+    body: &str,
+) -> Result<Amendment, ProofError> {
+    if message_id.is_empty() {
+        return Err(ProofError::InvalidInput("Message id cannot be empty".to_string()));
+    }
+    if version == 0 {
+        return Err(ProofError::InvalidInput("Amendment versions start at 1".to_string()));
+    }
+
+    let sender = keypair.public_key();
+    let hash = message_hash(
+        sender.as_bytes(),
+        &amendment_context(message_id, version, previous_hash),
+        body.as_bytes(),
+    );
+    let signature = make_secure_proof(keypair, &hash)?;
+    Ok(Amendment {
+        message_id: message_id.to_string(),
+        version,
+        previous_hash: *previous_hash,
+        body: body.to_string(),
+        sender,
+        signature,
+    })
+}
+
+/// Verify an amendment extends the expected version and is signed by the original sender
+pub fn verify_amendment(
+    amendment: &Amendment,
+    sender: &PublicKey,
+    message_id: &str,
+    version: u64,
+    previous_hash: &[u8; MESSAGE_HASH_LENGTH],
+) -> Result<(), ProofError> {
+    if amendment.message_id != message_id {
+        return Err(ProofError::InvalidData(format!(
+            "Amendment is for message {}, expected {}",
+            amendment.message_id, message_id
+        )));
+    }
+    if &amendment.sender != sender {
+        return Err(ProofError::InvalidData("Amendment is not from the original sender".to_string()));
+    }
+    if amendment.version != version {
+        return Err(ProofError::InvalidData(format!(
+            "Amendment creates version {}, expected {}",
+            amendment.version, version
+        )));
+    }
+    if &amendment.previous_hash != previous_hash {
+        return Err(ProofError::InvalidData("Amendment does not extend the current version".to_string()));
+    }
+
+    verify_proof_result(&amendment.sender, &amendment.hash(), &amendment.signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+
+    #[test]
+    fn amendment_roundtrip_verifies() {
+        let sender = generate_secure_keypair_with_seed(7);
+        let original = message_hash(&sender.public_key_bytes(), b"ctx", b"helo");
+
+        let amendment = make_amendment(&sender, "msg-1", 1, &original, "hello").unwrap();
+
+        assert_eq!(amendment.sender, sender.public_key());
+        assert!(verify_amendment(&amendment, &sender.public_key(), "msg-1", 1, &original).is_ok());
+    }
+
+    #[test]
+    fn amendments_chain_through_version_hashes() {
+        let sender = generate_secure_keypair_with_seed(7);
+        let original = message_hash(&sender.public_key_bytes(), b"ctx", b"helo");
+        let first = make_amendment(&sender, "msg-1", 1, &original, "hello").unwrap();
+
+        let second = make_amendment(&sender, "msg-1", 2, &first.hash(), "hello!").unwrap();
+
+        assert!(verify_amendment(&second, &sender.public_key(), "msg-1", 2, &first.hash()).is_ok());
+        // A second amendment cannot be replayed on top of the original
+        assert!(verify_amendment(&second, &sender.public_key(), "msg-1", 2, &original).is_err());
+    }
+
+    #[test]
+    fn amendment_from_another_key_is_rejected() {
+        let sender = generate_secure_keypair_with_seed(7);
+        let other = generate_secure_keypair_with_seed(8);
+        let original = message_hash(&sender.public_key_bytes(), b"ctx", b"helo");
+        let amendment = make_amendment(&other, "msg-1", 1, &original, "hello").unwrap();
+
+        assert!(verify_amendment(&amendment, &sender.public_key(), "msg-1", 1, &original).is_err());
+
+        // Claiming the sender's key breaks the signature
+        let mut forged = amendment.clone();
+        forged.sender = sender.public_key();
+        assert!(matches!(
+            verify_amendment(&forged, &sender.public_key(), "msg-1", 1, &original),
+            Err(ProofError::VerificationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn rewritten_body_breaks_the_signature() {
+        let sender = generate_secure_keypair_with_seed(7);
+        let original = message_hash(&sender.public_key_bytes(), b"ctx", b"helo");
+        let mut amendment = make_amendment(&sender, "msg-1", 1, &original, "hello").unwrap();
+
+        amendment.body = "goodbye".to_string();
+
+        assert!(matches!(
+            verify_amendment(&amendment, &sender.public_key(), "msg-1", 1, &original),
+            Err(ProofError::VerificationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn version_zero_is_rejected() {
+        let sender = generate_secure_keypair_with_seed(7);
+        let original = message_hash(&sender.public_key_bytes(), b"ctx", b"helo");
+
+        assert!(matches!(
+            make_amendment(&sender, "msg-1", 0, &original, "hello"),
+            Err(ProofError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,212 @@
+//! Canonical JSON for signed contexts (JCS, RFC 8785)
+//!
+//! A proof signs the exact bytes of its context, so two SDKs that serialize
+//! the same logical JSON context with different key orders, whitespace or
+//! number formats produce proofs that do not verify against each other.
+//! Signing the JSON Canonicalization Scheme form of a context instead gives
+//! every implementation the same bytes:
+//!
+//! - object members are sorted by the UTF-16 code units of their names
+//! - no insignificant whitespace is emitted
+//! - strings use the minimal JSON escapes
+//! - numbers are IEEE 754 doubles printed as ECMAScript does
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::canonical::{canonical_context, canonicalize_str};
+//!
+//! let a = canonicalize_str(r#"{ "b": 2.50, "a": [true, null] }"#).unwrap();
+//! let b = canonicalize_str(r#"{"a":[true,null],"b":25e-1}"#).unwrap();
+//!
+//! assert_eq!(a, r#"{"a":[true,null],"b":2.5}"#);
+//! assert_eq!(a, b);
+//! assert_eq!(canonical_context(&serde_json::json!({"b": 2.5, "a": [true, null]})).unwrap(), a.as_bytes());
+//! ```
+
+use serde::Serialize;
+use serde_json::{Number, Value};
+use sha2::{Digest, Sha256};
+
+use crate::errors::{ProtocolError, Result};
+
+/// Length of a canonical context hash in bytes (SHA-256)
+pub const CONTEXT_HASH_LENGTH: usize = 32;
+
+/// Serialize a JSON value in canonical form
+pub fn canonicalize(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+/// Parse a JSON document and serialize it in canonical form
+pub fn canonicalize_str(json: &str) -> Result<String> {
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| ProtocolError::Serialization(format!("Invalid JSON context: {}", e)))?;
+    Ok(canonicalize(&value))
+}
+
+/// Canonical bytes of a context, ready to be signed
+pub fn canonical_context<T: Serialize + ?Sized>(context: &T) -> Result<Vec<u8>> {
+    let value = serde_json::to_value(context)
+        .map_err(|e| ProtocolError::Serialization(format!("Context is not representable as JSON: {}", e)))?;
+    Ok(canonicalize(&value).into_bytes())
+}
+
+/// SHA-256 hash of a context's canonical form
+pub fn context_hash<T: Serialize + ?Sized>(context: &T) -> Result<[u8; CONTEXT_HASH_LENGTH]> {
+    Ok(Sha256::digest(&canonical_context(context)?).into())
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&format_number(n)),
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(members) => {
+            let mut members: Vec<_> = members.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (i, (name, value)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, name);
+                out.push(':');
+                write_value(out, value);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// serde_json already emits exactly the escapes JCS requires
+fn write_string(out: &mut String, s: &str) {
+    out.push_str(&serde_json::to_string(s).expect("strings always serialize"));
+}
+
+/// Format a number as ECMAScript's `Number.prototype.toString` does
+fn format_number(n: &Number) -> String {
+    // Every JSON number is an IEEE 754 double under JCS, including large integers
+    let value = n.as_f64().expect("JSON numbers are representable as f64");
+    if value == 0.0 {
+        // Covers negative zero, which ECMAScript also prints as "0"
+        return "0".to_string();
+    }
+    ryu_js::Buffer::new().format_finite(value).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(bits: u64) -> String {
+        format_number(&Number::from_f64(f64::from_bits(bits)).unwrap())
+    }
+
+    #[test]
+    fn rfc8785_number_serialization() {
+        // Appendix B of RFC 8785
+        let vectors = [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ];
+
+        for (bits, expected) in vectors {
+            assert_eq!(number(bits), expected, "{:#018x}", bits);
+        }
+    }
+
+    #[test]
+    fn rfc8785_sample_document() {
+        // Section 3.2.2 of RFC 8785
+        let input = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+
+        assert_eq!(
+            canonicalize_str(input).unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn rfc8785_member_sorting_uses_utf16_code_units() {
+        // Section 3.2.3 of RFC 8785
+        let input = r#"{
+            "€": "Euro Sign",
+            "\r": "Carriage Return",
+            "דּ": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "😀": "Emoji: Grinning Face",
+            "\u0080": "Control",
+            "ö": "Latin Small Letter O With Diaeresis"
+        }"#;
+
+        let canonical = canonicalize_str(input).unwrap();
+        let positions: Vec<usize> = [
+            "Carriage Return",
+            "One",
+            "Control",
+            "Latin Small Letter O With Diaeresis",
+            "Euro Sign",
+            "Emoji: Grinning Face",
+            "Hebrew Letter Dalet With Dagesh",
+        ]
+        .iter()
+        .map(|name| canonical.find(name).unwrap())
+        .collect();
+
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{}", canonical);
+    }
+
+    #[test]
+    fn equivalent_contexts_hash_identically() {
+        #[derive(Serialize)]
+        struct Transfer {
+            amount: u64,
+            to: &'static str,
+        }
+
+        let from_struct = context_hash(&Transfer { amount: 10, to: "bob" }).unwrap();
+        let from_text = Sha256::digest(canonicalize_str(r#"{ "to" : "bob", "amount" : 1.0E1 }"#).unwrap().as_bytes());
+
+        assert_eq!(from_struct.as_slice(), from_text.as_slice());
+        assert!(canonicalize_str("{\"a\": }").is_err());
+    }
+}
@@ -0,0 +1,363 @@
+//! Proof chains: proofs whose signed context commits to earlier proofs
+//!
+//! Some approvals only make sense after others ("B depends on A"). A chained
+//! proof signs a [`chain_context`], which embeds the [`proof_hash`] of one or
+//! more parent proofs ahead of the application payload, so the signer commits
+//! to exactly the approvals it builds on. A proof whose context carries no
+//! chain prefix is a root.
+//!
+//! [`verify_chain`] walks a proof's ancestors through a caller-supplied
+//! lookup, checking every signature and that each parent found is the one
+//! committed to.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::chain::{make_chained_proof, verify_chain};
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//!
+//! let alice = generate_secure_keypair();
+//! let bob = generate_secure_keypair();
+//! let approval_a = make_chained_proof(&alice, &[], b"approve budget").unwrap();
+//! let approval_b = make_chained_proof(&bob, &[approval_a.hash()], b"approve purchase").unwrap();
+//!
+//! let lookup = |hash: &[u8; 32]| (*hash == approval_a.hash()).then(|| approval_a.clone());
+//! let verification = verify_chain(&approval_b, lookup).unwrap();
+//! assert_eq!(verification.proofs, 2);
+//! assert_eq!(verification.roots, vec![approval_a.hash()]);
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+use crate::envelope::ProofEnvelope;
+use crate::key::SecureKeypair;
+use crate::proof::{verify_proof_result, ProofError};
+
+/// Length of a proof hash in bytes (SHA-256)
+pub const PROOF_HASH_LENGTH: usize = 32;
+
+/// Most parents a single chained context may name
+pub const MAX_CHAIN_PARENTS: usize = 16;
+
+/// Most proofs [`verify_chain`] walks before giving up
+pub const MAX_CHAIN_PROOFS: usize = 1024;
+
+/// Domain separation prefix for proof hashes
+const PROOF_HASH_DOMAIN: &[u8] = b"proof-messenger/proof-hash/v1";
+
+/// Domain separation prefix for chained contexts
+const CHAIN_DOMAIN: &[u8] = b"proof-messenger/proof-chain/v1";
+
+/// Hash identifying a proof within a chain
+pub type ProofHash = [u8; PROOF_HASH_LENGTH];
+
+/// A proof that may commit to parent proofs through its context
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainedProof {
+    /// Signer's public key
+    pub sender: PublicKey,
+    /// Signed context: a [`chain_context`] for a child, any bytes for a root
+    pub context: Vec<u8>,
+    /// The proof over `context`: a proof envelope or a raw Ed25519 signature
+    pub proof: Vec<u8>,
+}
+
+impl ChainedProof {
+    /// Hash of this proof, which children commit to
+    pub fn hash(&self) -> ProofHash {
+        proof_hash(self.sender.as_bytes(), &self.context, &self.proof)
+    }
+
+    /// Hashes of the proofs this one builds on (empty for a root)
+    pub fn parents(&self) -> Result<Vec<ProofHash>, ProofError> {
+        Ok(parse_chain_context(&self.context)?.map(|chain| chain.parents).unwrap_or_default())
+    }
+
+    /// The application payload, without the chain prefix
+    pub fn payload(&self) -> Result<&[u8], ProofError> {
+        Ok(parse_chain_context(&self.context)?.map_or(&self.context[..], |chain| chain.payload))
+    }
+
+    /// Check the proof was made by `sender` over `context`
+    pub fn verify(&self) -> Result<(), ProofError> {
+        if ProofEnvelope::is_envelope(&self.proof) {
+            let envelope = ProofEnvelope::from_bytes(&self.proof)?;
+            if envelope.ed25519_public_key()? != self.sender {
+                return Err(ProofError::InvalidData("Proof envelope was not signed by the sender".to_string()));
+            }
+            return envelope.verify(&self.context);
+        }
+        let signature = Signature::from_bytes(&self.proof)
+            .map_err(|e| ProofError::InvalidData(format!("Invalid Ed25519 signature: {}", e)))?;
+        verify_proof_result(&self.sender, &self.context, &signature)
+    }
+}
+
+/// What [`verify_chain`] found above a proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainVerification {
+    /// Number of distinct proofs verified, the starting proof included
+    pub proofs: usize,
+    /// Longest path from the starting proof to a root (0 for a root)
+    pub depth: usize,
+    /// Hashes of the roots the chain leads back to, in the order reached
+    pub roots: Vec<ProofHash>,
+}
+
+/// A chained context split into its parts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainContext<'a> {
+    /// Hashes of the parent proofs, in signed order
+    pub parents: Vec<ProofHash>,
+    /// Application payload following the parents
+    pub payload: &'a [u8],
+}
+
+/// Hash a proof's signer, signed context and proof bytes
+///
+/// Each field is length prefixed so no two distinct proofs share a hash by
+/// shifting bytes across field boundaries.
+pub fn proof_hash(sender: &[u8], context: &[u8], proof: &[u8]) -> ProofHash {
+    let mut hasher = Sha256::new();
+    hasher.update(PROOF_HASH_DOMAIN);
+    for field in [sender, context, proof] {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field);
+    }
+    hasher.finalize().into()
+}
+
+/// Build a context committing to `parents` ahead of `payload`
+///
+/// Parents must be distinct, and there must be between 1 and
+/// [`MAX_CHAIN_PARENTS`] of them.
+pub fn chain_context(parents: &[ProofHash], payload: &[u8]) -> Result<Vec<u8>, ProofError> {
+    if parents.is_empty() || parents.len() > MAX_CHAIN_PARENTS {
+        return Err(ProofError::InvalidInput(format!(
+            "A chained proof needs 1 to {} parents (got {})",
+            MAX_CHAIN_PARENTS,
+            parents.len()
+        )));
+    }
+    if parents.iter().collect::<HashSet<_>>().len() != parents.len() {
+        return Err(ProofError::InvalidInput("Chain parents must be distinct".to_string()));
+    }
+
+    let mut context = Vec::with_capacity(CHAIN_DOMAIN.len() + 2 + parents.len() * PROOF_HASH_LENGTH + payload.len());
+    context.extend_from_slice(CHAIN_DOMAIN);
+    context.push(0);
+    context.push(parents.len() as u8);
+    for parent in parents {
+        context.extend_from_slice(parent);
+    }
+    context.extend_from_slice(payload);
+    Ok(context)
+}
+
+/// Split a chained context into its parents and payload
+///
+/// Returns `None` for a context without the chain prefix (a root), and an
+/// error for one that starts with the prefix but is malformed.
+pub fn parse_chain_context(context: &[u8]) -> Result<Option<ChainContext<'_>>, ProofError> {
+    let Some(rest) = context.strip_prefix(CHAIN_DOMAIN).and_then(|rest| rest.strip_prefix(&[0])) else {
+        return Ok(None);
+    };
+    let (&count, rest) = rest
+        .split_first()
+        .ok_or_else(|| ProofError::InvalidData("Chained context is missing its parent count".to_string()))?;
+    let count = count as usize;
+    if count == 0 || count > MAX_CHAIN_PARENTS {
+        return Err(ProofError::InvalidData(format!(
+            "Chained context names {} parents (1 to {} allowed)",
+            count, MAX_CHAIN_PARENTS
+        )));
+    }
+    if rest.len() < count * PROOF_HASH_LENGTH {
+        return Err(ProofError::InvalidData("Chained context is truncated".to_string()));
+    }
+
+    let (hashes, payload) = rest.split_at(count * PROOF_HASH_LENGTH);
+    let parents: Vec<ProofHash> = hashes
+        .chunks_exact(PROOF_HASH_LENGTH)
+        .map(|chunk| chunk.try_into().expect("chunk is PROOF_HASH_LENGTH bytes"))
+        .collect();
+    if parents.iter().collect::<HashSet<_>>().len() != parents.len() {
+        return Err(ProofError::InvalidData("Chained context repeats a parent".to_string()));
+    }
+    Ok(Some(ChainContext { parents, payload }))
+}
+
+/// Sign `payload` as a proof building on `parents` (a root when empty)
+pub fn make_chained_proof(
+    keypair: &SecureKeypair,
+    parents: &[ProofHash],
+    payload: &[u8],
+) -> Result<ChainedProof, ProofError> {
+    let context = if parents.is_empty() {
+        payload.to_vec()
+    } else {
+        chain_context(parents, payload)?
+    };
+    let envelope = ProofEnvelope::sign(keypair, &context, Default::default())?;
+    Ok(ChainedProof {
+        sender: keypair.public_key(),
+        context,
+        proof: envelope.to_bytes(),
+    })
+}
+
+/// Verify a proof and every ancestor it commits to
+///
+/// `lookup` returns the proof with a given hash, or `None` if it is unknown.
+/// Verification fails if any proof in the chain has a bad signature or a
+/// malformed context, if an ancestor cannot be found or does not hash to
+/// the committed value, or if the chain exceeds [`MAX_CHAIN_PROOFS`].
+pub fn verify_chain<F>(proof: &ChainedProof, mut lookup: F) -> Result<ChainVerification, ProofError>
+where
+    F: FnMut(&ProofHash) -> Option<ChainedProof>,
+{
+    let mut visited = HashSet::new();
+    let mut roots = Vec::new();
+    let mut depth = 0;
+    // Depth-first walk; each entry is a proof and its distance from the start
+    let mut pending = vec![(proof.clone(), 0usize)];
+
+    while let Some((current, distance)) = pending.pop() {
+        let hash = current.hash();
+        if !visited.insert(hash) {
+            continue;
+        }
+        if visited.len() > MAX_CHAIN_PROOFS {
+            return Err(ProofError::InvalidData(format!(
+                "Proof chain exceeds {} proofs",
+                MAX_CHAIN_PROOFS
+            )));
+        }
+        current.verify()?;
+        depth = depth.max(distance);
+
+        let parents = current.parents()?;
+        if parents.is_empty() {
+            roots.push(hash);
+        }
+        for parent in parents {
+            let found = lookup(&parent).ok_or_else(|| {
+                ProofError::InvalidData(format!("Parent proof {} not found", hex::encode(parent)))
+            })?;
+            if found.hash() != parent {
+                return Err(ProofError::InvalidData(format!(
+                    "Proof returned for parent {} has a different hash",
+                    hex::encode(parent)
+                )));
+            }
+            pending.push((found, distance + 1));
+        }
+    }
+
+    Ok(ChainVerification {
+        proofs: visited.len(),
+        depth,
+        roots,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+    use std::collections::HashMap;
+
+    fn store(proofs: &[&ChainedProof]) -> HashMap<ProofHash, ChainedProof> {
+        proofs.iter().map(|proof| (proof.hash(), (*proof).clone())).collect()
+    }
+
+    #[test]
+    fn chain_context_roundtrips() {
+        let parents = [[1u8; 32], [2u8; 32]];
+
+        let context = chain_context(&parents, b"payload").unwrap();
+
+        let parsed = parse_chain_context(&context).unwrap().unwrap();
+        assert_eq!(parsed.parents, parents);
+        assert_eq!(parsed.payload, b"payload");
+        assert_eq!(parse_chain_context(b"plain context").unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_parent_lists_are_rejected() {
+        assert!(chain_context(&[], b"payload").is_err());
+        assert!(chain_context(&[[1u8; 32]; 2], b"payload").is_err());
+        assert!(chain_context(&[[0u8; 32]; MAX_CHAIN_PARENTS + 1], b"payload").is_err());
+
+        let mut truncated = chain_context(&[[1u8; 32]], b"").unwrap();
+        truncated.pop();
+        assert!(parse_chain_context(&truncated).is_err());
+    }
+
+    #[test]
+    fn diamond_chain_verifies_to_its_root() {
+        // a <- b, a <- c, (b, c) <- d
+        let signer = generate_secure_keypair_with_seed(1);
+        let a = make_chained_proof(&signer, &[], b"a").unwrap();
+        let b = make_chained_proof(&signer, &[a.hash()], b"b").unwrap();
+        let c = make_chained_proof(&signer, &[a.hash()], b"c").unwrap();
+        let d = make_chained_proof(&signer, &[b.hash(), c.hash()], b"d").unwrap();
+        let known = store(&[&a, &b, &c]);
+
+        let verification = verify_chain(&d, |hash| known.get(hash).cloned()).unwrap();
+
+        assert_eq!(verification, ChainVerification { proofs: 4, depth: 2, roots: vec![a.hash()] });
+        assert_eq!(d.payload().unwrap(), b"d");
+        assert_eq!(d.parents().unwrap(), vec![b.hash(), c.hash()]);
+    }
+
+    #[test]
+    fn missing_parent_fails_verification() {
+        let signer = generate_secure_keypair_with_seed(1);
+        let a = make_chained_proof(&signer, &[], b"a").unwrap();
+        let b = make_chained_proof(&signer, &[a.hash()], b"b").unwrap();
+
+        assert!(matches!(verify_chain(&b, |_| None), Err(ProofError::InvalidData(_))));
+    }
+
+    #[test]
+    fn substituted_parent_fails_verification() {
+        let signer = generate_secure_keypair_with_seed(1);
+        let a = make_chained_proof(&signer, &[], b"a").unwrap();
+        let other = make_chained_proof(&signer, &[], b"other").unwrap();
+        let b = make_chained_proof(&signer, &[a.hash()], b"b").unwrap();
+
+        assert!(verify_chain(&b, |_| Some(other.clone())).is_err());
+    }
+
+    #[test]
+    fn tampered_ancestor_fails_verification() {
+        let signer = generate_secure_keypair_with_seed(1);
+        let other = generate_secure_keypair_with_seed(2);
+        let a = make_chained_proof(&signer, &[], b"a").unwrap();
+        // A parent claiming another signer hashes differently, so rebuild the child over it
+        let mut forged = a.clone();
+        forged.sender = other.public_key();
+        let b = make_chained_proof(&signer, &[forged.hash()], b"b").unwrap();
+        let known = store(&[&forged]);
+
+        assert!(verify_chain(&b, |hash| known.get(hash).cloned()).is_err());
+    }
+
+    #[test]
+    fn raw_signature_roots_verify() {
+        let signer = generate_secure_keypair_with_seed(1);
+        let root = ChainedProof {
+            sender: signer.public_key(),
+            context: b"legacy".to_vec(),
+            proof: signer.sign(b"legacy").to_bytes().to_vec(),
+        };
+        let child = make_chained_proof(&signer, &[root.hash()], b"child").unwrap();
+        let known = store(&[&root]);
+
+        let verification = verify_chain(&child, |hash| known.get(hash).cloned()).unwrap();
+
+        assert_eq!(verification.roots, vec![root.hash()]);
+    }
+}
@@ -3,13 +3,16 @@
 //! 
 //! This module provides comprehensive audit logging for compliance operations,
 //! ensuring that all data sanitization activities are properly tracked and
-//! can be reviewed for compliance audits.
+//! can be reviewed for compliance audits. Entries are kept in memory unless
+//! an [`AuditSink`] is attached to forward them elsewhere.
 
 use serde_json::Value;
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use crate::compliance::pii_detector::PIIType;
+use crate::compliance::pii_redactor::RedactionRecord;
+use crate::compliance::audit_sink::{AuditSink, AuditSinkError};
 
 /// Audit event types for compliance tracking
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -19,6 +22,7 @@ pub enum AuditEventType {
     SanitizationFailure,
     PolicyViolation,
     PIIDetection,
+    PIIRedaction,
     ContextValidation,
     PolicyApplication,
     ComplianceCheck,
@@ -76,6 +80,8 @@ pub struct ComplianceAuditLogger {
     entries: Vec<AuditLogEntry>,
     session_id: Option<String>,
     user_id: Option<String>,
+    sink: Option<Box<dyn AuditSink>>,
+    batch_size: usize,
 }
 
 impl ComplianceAuditLogger {
@@ -85,9 +91,45 @@ impl ComplianceAuditLogger {
             entries: Vec::new(),
             session_id: None,
             user_id: None,
+            sink: None,
+            batch_size: 0,
         }
     }
 
+    /// Create a logger forwarding its entries to `sink` in batches of `batch_size`
+    ///
+    /// Forwarded entries are dropped from memory, so the query and summary
+    /// methods only see entries that have not been forwarded yet.
+    pub fn with_sink(sink: impl AuditSink + 'static, batch_size: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            session_id: None,
+            user_id: None,
+            sink: Some(Box::new(sink)),
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    /// Write buffered entries to the sink, if one is attached
+    ///
+    /// On error the entries stay buffered and are retried by the next flush.
+    pub fn flush(&mut self) -> Result<(), AuditSinkError> {
+        let Some(sink) = self.sink.as_mut() else {
+            return Ok(());
+        };
+        if !self.entries.is_empty() {
+            sink.write_batch(&self.entries)?;
+            self.entries.clear();
+        }
+        sink.flush()
+    }
+
+    /// Flush buffered entries and detach the sink, keeping later entries in memory
+    pub fn take_sink(&mut self) -> Option<Box<dyn AuditSink>> {
+        let _ = self.flush();
+        self.sink.take()
+    }
+
     /// Set session ID for all subsequent log entries
     pub fn set_session_id(&mut self, session_id: String) {
         self.session_id = Some(session_id);
@@ -222,6 +264,29 @@ impl ComplianceAuditLogger {
         self.add_entry(entry);
     }
 
+    /// Log PII redaction, recording where each span was and its type but not its value
+    pub fn log_pii_redaction(&mut self, context_type: &str, records: &[RedactionRecord]) {
+        let mut details = HashMap::new();
+        details.insert("redactions".to_string(), Value::Array(
+            records.iter().map(|record| serde_json::json!({
+                "path": record.path,
+                "pii_type": format!("{:?}", record.pii_type),
+                "strategy": record.strategy,
+            })).collect()
+        ));
+        details.insert("redaction_count".to_string(), Value::Number(serde_json::Number::from(records.len())));
+
+        let entry = AuditLogEntry::new(
+            AuditEventType::PIIRedaction,
+            context_type.to_string(),
+            details,
+            "INFO".to_string(),
+            "PII_REDACTED".to_string(),
+        );
+
+        self.add_entry(entry);
+    }
+
     /// Log context validation
     pub fn log_context_validation(&mut self, context_type: &str, validation_result: &str, errors: &[String]) {
         let mut details = HashMap::new();
@@ -308,6 +373,10 @@ impl ComplianceAuditLogger {
         }
 
         self.entries.push(entry);
+        if self.sink.is_some() && self.entries.len() >= self.batch_size {
+            // A failed batch stays buffered and is retried with the next one
+            let _ = self.flush();
+        }
     }
 
     /// Get all audit log entries
@@ -374,6 +443,13 @@ impl Default for ComplianceAuditLogger {
     }
 }
 
+impl Drop for ComplianceAuditLogger {
+    /// Forward buffered entries before the logger goes away
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
 /// Compliance summary for reporting
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComplianceSummary {
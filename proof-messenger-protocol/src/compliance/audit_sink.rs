@@ -0,0 +1,231 @@
+// src/compliance/audit_sink.rs
+//! Audit Sink Module
+//!
+//! By default [`ComplianceAuditLogger`] only keeps its entries in memory.
+//! Attaching an [`AuditSink`] with [`ComplianceAuditLogger::with_sink`]
+//! forwards the entries in batches instead: once `batch_size` entries are
+//! buffered they are written to the sink and dropped from memory. Remaining
+//! entries are written by [`ComplianceAuditLogger::flush`], which also runs
+//! when the logger is dropped, so a clean shutdown loses nothing.
+//!
+//! Two sinks are provided here: [`JsonLinesSink`] appends one JSON entry per
+//! line to any writer, and [`SyslogSink`] sends RFC 5424 messages over UDP.
+//! Applications can add their own, such as an encrypted log or an HTTP
+//! collector, by implementing the trait.
+//!
+//! [`ComplianceAuditLogger`]: super::audit_logger::ComplianceAuditLogger
+//! [`ComplianceAuditLogger::with_sink`]: super::audit_logger::ComplianceAuditLogger::with_sink
+//! [`ComplianceAuditLogger::flush`]: super::audit_logger::ComplianceAuditLogger::flush
+
+use std::io::Write;
+use std::net::{ToSocketAddrs, UdpSocket};
+use thiserror::Error;
+
+use super::audit_logger::AuditLogEntry;
+
+/// Errors forwarding audit entries to a sink
+#[derive(Error, Debug)]
+pub enum AuditSinkError {
+    #[error("Audit sink I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to serialize audit entry: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Audit sink rejected the batch: {0}")]
+    Rejected(String),
+}
+
+/// A destination for compliance audit entries
+pub trait AuditSink: Send {
+    /// Write a batch of entries, in the order they were logged
+    ///
+    /// On error the logger keeps the batch and retries it with the next one.
+    fn write_batch(&mut self, entries: &[AuditLogEntry]) -> Result<(), AuditSinkError>;
+
+    /// Make previously written entries durable
+    fn flush(&mut self) -> Result<(), AuditSinkError> {
+        Ok(())
+    }
+}
+
+/// Writes each entry as one line of JSON
+pub struct JsonLinesSink<W: Write + Send> {
+    writer: W,
+}
+
+impl<W: Write + Send> JsonLinesSink<W> {
+    /// Append entries to `writer`
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// The underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send> AuditSink for JsonLinesSink<W> {
+    fn write_batch(&mut self, entries: &[AuditLogEntry]) -> Result<(), AuditSinkError> {
+        for entry in entries {
+            serde_json::to_writer(&mut self.writer, entry)?;
+            self.writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), AuditSinkError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Sends each entry to a syslog collector as an RFC 5424 message over UDP
+///
+/// Messages use the `authpriv` facility, a severity derived from the entry's
+/// risk level, and the entry as JSON for the message body.
+pub struct SyslogSink {
+    socket: UdpSocket,
+    hostname: String,
+    app_name: String,
+}
+
+impl SyslogSink {
+    /// `authpriv` facility code
+    const FACILITY: u8 = 10;
+
+    /// Send entries to the collector at `addr`, identified as `app_name`
+    pub fn connect(addr: impl ToSocketAddrs, app_name: &str) -> Result<Self, AuditSinkError> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.connect(addr)?;
+        let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "-".to_string());
+        Ok(Self {
+            socket,
+            hostname,
+            app_name: app_name.to_string(),
+        })
+    }
+
+    /// Syslog severity of an entry's risk level
+    fn severity(risk_level: &str) -> u8 {
+        match risk_level {
+            "CRITICAL" => 2,
+            "ERROR" | "HIGH" => 3,
+            "WARNING" | "MEDIUM" => 4,
+            _ => 6,
+        }
+    }
+
+    /// Format an entry as an RFC 5424 message
+    fn format(&self, entry: &AuditLogEntry) -> Result<String, AuditSinkError> {
+        Ok(format!(
+            "<{}>1 {} {} {} - {:?} - {}",
+            Self::FACILITY * 8 + Self::severity(&entry.risk_level),
+            entry.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            self.hostname,
+            self.app_name,
+            entry.event_type,
+            serde_json::to_string(entry)?,
+        ))
+    }
+}
+
+impl AuditSink for SyslogSink {
+    fn write_batch(&mut self, entries: &[AuditLogEntry]) -> Result<(), AuditSinkError> {
+        for entry in entries {
+            self.socket.send(self.format(entry)?.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compliance::audit_logger::ComplianceAuditLogger;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    /// Records batches, failing while `fail` is set
+    #[derive(Clone, Default)]
+    struct RecordingSink {
+        batches: Arc<Mutex<Vec<usize>>>,
+        fail: Arc<Mutex<bool>>,
+    }
+
+    impl AuditSink for RecordingSink {
+        fn write_batch(&mut self, entries: &[AuditLogEntry]) -> Result<(), AuditSinkError> {
+            if *self.fail.lock().unwrap() {
+                return Err(AuditSinkError::Rejected("unavailable".to_string()));
+            }
+            self.batches.lock().unwrap().push(entries.len());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_entries_are_forwarded_in_batches() {
+        // ARRANGE: A logger forwarding batches of two
+        let sink = RecordingSink::default();
+        let mut logger = ComplianceAuditLogger::with_sink(sink.clone(), 2);
+
+        // ACT: Log five entries, then drop the logger
+        for _ in 0..5 {
+            logger.log_sanitization_success("login", &json!({}));
+        }
+        let buffered = logger.entry_count();
+        drop(logger);
+
+        // ASSERT: Full batches are forwarded as they fill, the rest on drop
+        assert_eq!(buffered, 1);
+        assert_eq!(*sink.batches.lock().unwrap(), vec![2, 2, 1]);
+    }
+
+    #[test]
+    fn test_failed_batches_are_retried() {
+        let sink = RecordingSink::default();
+        let mut logger = ComplianceAuditLogger::with_sink(sink.clone(), 1);
+
+        *sink.fail.lock().unwrap() = true;
+        logger.log_policy_violation("login", "password", "forbidden_field");
+        assert!(logger.flush().is_err());
+        *sink.fail.lock().unwrap() = false;
+        logger.log_policy_violation("login", "ssn", "forbidden_field");
+
+        assert_eq!(*sink.batches.lock().unwrap(), vec![2]);
+        assert_eq!(logger.entry_count(), 0);
+    }
+
+    #[test]
+    fn test_json_lines_sink() {
+        let mut logger = ComplianceAuditLogger::new();
+        logger.log_policy_violation("login", "password", "forbidden_field");
+        logger.log_sanitization_success("login", &json!({}));
+        let mut sink = JsonLinesSink::new(Vec::new());
+
+        sink.write_batch(logger.get_entries()).unwrap();
+
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        let entries: Vec<AuditLogEntry> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].event_type, crate::compliance::AuditEventType::PolicyViolation);
+        assert_eq!(entries[1].compliance_status, "COMPLIANT");
+    }
+
+    #[test]
+    fn test_syslog_sink_sends_rfc5424_messages() {
+        let collector = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut sink = SyslogSink::connect(collector.local_addr().unwrap(), "relay").unwrap();
+        let mut logger = ComplianceAuditLogger::new();
+        logger.log_pii_detection("login", "ssn", &[crate::compliance::PIIType::SocialSecurityNumber]);
+
+        sink.write_batch(logger.get_entries()).unwrap();
+
+        let mut buffer = [0u8; 2048];
+        let received = collector.recv(&mut buffer).unwrap();
+        let message = std::str::from_utf8(&buffer[..received]).unwrap();
+        assert!(message.starts_with("<82>1 "), "{}", message);
+        assert!(message.contains(" relay - PIIDetection - {"), "{}", message);
+    }
+}
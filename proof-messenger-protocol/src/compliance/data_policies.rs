@@ -7,6 +7,17 @@
 
 use std::collections::HashSet;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Reasons a data policy is not well formed
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PolicyError {
+    #[error("Policy version '{version}' is not a semantic version: {reason}")]
+    InvalidVersion { version: String, reason: String },
+
+    #[error("Fields are both required and forbidden: {}", .0.join(", "))]
+    ConflictingFields(Vec<String>),
+}
 
 /// Data policy defining what fields are allowed, required, and forbidden
 /// for a specific context type
@@ -62,6 +73,20 @@ impl DataPolicy {
     pub fn get_allowed_fields(&self) -> HashSet<String> {
         self.required_fields.union(&self.optional_fields).cloned().collect()
     }
+
+    /// Check that the version is a semantic version and no field is both required and forbidden
+    pub fn validate(&self) -> Result<(), PolicyError> {
+        semver::Version::parse(&self.version).map_err(|e| PolicyError::InvalidVersion {
+            version: self.version.clone(),
+            reason: e.to_string(),
+        })?;
+        let mut conflicting: Vec<String> = self.required_fields.intersection(&self.forbidden_fields).cloned().collect();
+        if !conflicting.is_empty() {
+            conflicting.sort();
+            return Err(PolicyError::ConflictingFields(conflicting));
+        }
+        Ok(())
+    }
 }
 
 /// Policy for FinTech wire transfer contexts
@@ -517,6 +542,19 @@ mod tests {
         assert!(get_policy_by_type("unknown_type").is_none());
     }
 
+    #[test]
+    fn test_policy_validation() {
+        assert!(create_fintech_policy().validate().is_ok());
+
+        let mut policy = create_login_policy();
+        policy.version = "1.0".to_string();
+        assert!(matches!(policy.validate(), Err(PolicyError::InvalidVersion { .. })));
+
+        policy.version = "2.1.0-rc.1".to_string();
+        policy.forbidden_fields.insert("user_id".to_string());
+        assert_eq!(policy.validate(), Err(PolicyError::ConflictingFields(vec!["user_id".to_string()])));
+    }
+
     #[test]
     fn test_policy_field_operations() {
         let policy = create_fintech_policy();
@@ -9,10 +9,18 @@
 
 pub mod context_builder;
 pub mod data_policies;
+pub mod policy_files;
 pub mod pii_detector;
+pub mod pii_scanner;
+pub mod pii_redactor;
 pub mod audit_logger;
+pub mod audit_sink;
 
 pub use context_builder::*;
 pub use data_policies::*;
+pub use policy_files::*;
 pub use pii_detector::*;
-pub use audit_logger::*;
\ No newline at end of file
+pub use pii_scanner::*;
+pub use pii_redactor::*;
+pub use audit_logger::*;
+pub use audit_sink::*;
\ No newline at end of file
@@ -4,13 +4,39 @@
 //! This module provides advanced PII detection capabilities to identify
 //! personally identifiable information in data values, ensuring that
 //! sensitive data is caught even if it appears in unexpected places.
+//!
+//! The default patterns are deliberately broad. Deployments that handle
+//! values which look like PII but are not, such as hex encoded keys and
+//! proofs, can tune a detector with [`PIIDetectorBuilder`]: disable PII types,
+//! add custom patterns, and allow-list values. The same settings can be
+//! loaded from a serialized [`PIIDetectorConfig`].
+//!
+//! National identifiers, phone numbers and postcodes differ between regions,
+//! so their patterns come in locale packs (see [`PIILocale`]). Detectors use
+//! the US pack unless other locales are selected.
+//!
+//! ```rust
+//! use proof_messenger_protocol::compliance::{PIIDetector, PIIDetectorConfig, PIIType};
+//! use serde_json::json;
+//!
+//! let config: PIIDetectorConfig = serde_json::from_value(json!({
+//!     "disabled_types": ["DeviceSerial"],
+//!     "custom_patterns": [{ "pii_type": "PassportNumber", "pattern": "^P<[A-Z]{3}" }],
+//!     "allow_list": ["^[0-9a-f]{64}$"]
+//! })).unwrap();
+//! let detector = PIIDetector::builder().config(&config).build().unwrap();
+//!
+//! assert!(detector.detect_pii(&json!("ab".repeat(32))).is_none());
+//! assert!(detector.detect_pii(&json!("P<GBRSMITH")).unwrap().contains(&PIIType::PassportNumber));
+//! ```
 
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use regex::Regex;
 use std::collections::HashSet;
 
 /// Types of PII that can be detected
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PIIType {
     EmailAddress,
     PhoneNumber,
@@ -32,6 +58,8 @@ pub enum PIIType {
     TaxID,
     PassportNumber,
     DriversLicense,
+    NationalInsuranceNumber,
+    BankAccountNumber,
 }
 
 impl PIIType {
@@ -58,14 +86,18 @@ impl PIIType {
             PIIType::TaxID => "Tax identification number",
             PIIType::PassportNumber => "Passport number",
             PIIType::DriversLicense => "Driver's license number",
+            PIIType::NationalInsuranceNumber => "National Insurance number",
+            PIIType::BankAccountNumber => "Bank account number (IBAN)",
         }
     }
 
     /// Get the risk level of this PII type
     pub fn risk_level(&self) -> PIIRiskLevel {
         match self {
-            PIIType::BiometricTemplate | PIIType::SocialSecurityNumber | PIIType::CreditCardNumber => PIIRiskLevel::Critical,
-            PIIType::EmailAddress | PIIType::PhoneNumber | PIIType::PersonalName | PIIType::Address => PIIRiskLevel::High,
+            PIIType::BiometricTemplate | PIIType::SocialSecurityNumber | PIIType::CreditCardNumber
+            | PIIType::NationalInsuranceNumber => PIIRiskLevel::Critical,
+            PIIType::EmailAddress | PIIType::PhoneNumber | PIIType::PersonalName | PIIType::Address
+            | PIIType::BankAccountNumber => PIIRiskLevel::High,
             PIIType::IPAddress | PIIType::DeviceSerial | PIIType::SessionToken | PIIType::JWTToken => PIIRiskLevel::Medium,
             PIIType::UUID | PIIType::Base64EncodedData | PIIType::APIKey => PIIRiskLevel::Low,
             _ => PIIRiskLevel::Medium,
@@ -73,6 +105,21 @@ impl PIIType {
     }
 }
 
+/// Regional pattern packs for national identifiers, phone numbers and postcodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PIILocale {
+    /// Social Security numbers, North American phone numbers and ZIP codes
+    Us,
+    /// National Insurance numbers, UK postcodes, IBANs and E.164 phone numbers
+    Uk,
+    /// VAT IDs, common EU postcode formats, IBANs and E.164 phone numbers
+    ///
+    /// Four-digit postcodes (Austria, Belgium, Denmark and others) are not
+    /// matched, as they cannot be told apart from years and other numbers.
+    Eu,
+}
+
 /// Risk levels for different types of PII
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum PIIRiskLevel {
@@ -108,6 +155,121 @@ pub struct PIIDetector {
     personal_name_patterns: Vec<Regex>,
     address_patterns: Vec<Regex>,
     date_patterns: Vec<Regex>,
+    zip_code_regex: Regex,
+    national_insurance_regex: Regex,
+    uk_postcode_regex: Regex,
+    vat_id_regex: Regex,
+    iban_regex: Regex,
+    e164_phone_regex: Regex,
+    eu_postcode_patterns: Vec<Regex>,
+    locales: HashSet<PIILocale>,
+    disabled_types: HashSet<PIIType>,
+    custom_patterns: Vec<(PIIType, Regex)>,
+    allow_list: Vec<Regex>,
+}
+
+/// A pattern reporting a PII type when it matches a string value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomPattern {
+    /// Type reported for matching values
+    pub pii_type: PIIType,
+    /// Regular expression matched against string values
+    pub pattern: String,
+}
+
+/// Serializable PII detector settings, applied with [`PIIDetectorBuilder::config`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PIIDetectorConfig {
+    /// PII types that are never reported
+    pub disabled_types: Vec<PIIType>,
+    /// Patterns checked in addition to the built-in ones
+    pub custom_patterns: Vec<CustomPattern>,
+    /// Regular expressions for string values that are never reported as PII
+    pub allow_list: Vec<String>,
+    /// Locale packs to use instead of the default US pack
+    pub locales: Option<Vec<PIILocale>>,
+}
+
+/// Builder for a [`PIIDetector`] with custom patterns and allow-lists
+#[derive(Debug, Clone, Default)]
+pub struct PIIDetectorBuilder {
+    locales: Option<HashSet<PIILocale>>,
+    disabled_types: HashSet<PIIType>,
+    custom_patterns: Vec<(PIIType, String)>,
+    allow_list: Vec<String>,
+}
+
+impl PIIDetectorBuilder {
+    /// Use these locale packs instead of the default US pack
+    pub fn locales(mut self, locales: impl IntoIterator<Item = PIILocale>) -> Self {
+        self.locales = Some(locales.into_iter().collect());
+        self
+    }
+
+    /// Stop reporting a PII type, whether found by value or by field name
+    pub fn disable(mut self, pii_type: PIIType) -> Self {
+        self.disabled_types.insert(pii_type);
+        self
+    }
+
+    /// Report a previously disabled PII type again
+    pub fn enable(mut self, pii_type: PIIType) -> Self {
+        self.disabled_types.remove(&pii_type);
+        self
+    }
+
+    /// Report `pii_type` for string values matching `pattern`
+    pub fn custom_pattern(mut self, pii_type: PIIType, pattern: impl Into<String>) -> Self {
+        self.custom_patterns.push((pii_type, pattern.into()));
+        self
+    }
+
+    /// Never report string values matching `pattern`
+    ///
+    /// Patterns are not anchored; use `^...$` to allow only whole values,
+    /// such as `^[0-9a-f]{64}$` for hex encoded keys and signatures.
+    pub fn allow(mut self, pattern: impl Into<String>) -> Self {
+        self.allow_list.push(pattern.into());
+        self
+    }
+
+    /// Apply serialized settings on top of those already set
+    pub fn config(mut self, config: &PIIDetectorConfig) -> Self {
+        if let Some(locales) = &config.locales {
+            self = self.locales(locales.iter().copied());
+        }
+        self.disabled_types.extend(config.disabled_types.iter().cloned());
+        self.custom_patterns.extend(
+            config
+                .custom_patterns
+                .iter()
+                .map(|custom| (custom.pii_type.clone(), custom.pattern.clone())),
+        );
+        self.allow_list.extend(config.allow_list.iter().cloned());
+        self
+    }
+
+    /// Build the detector, failing on the first invalid pattern
+    pub fn build(self) -> Result<PIIDetector, regex::Error> {
+        let mut detector = PIIDetector::new();
+        if let Some(locales) = self.locales {
+            detector.locales = locales;
+        }
+        detector.disabled_types = self.disabled_types;
+        detector.custom_patterns = self
+            .custom_patterns
+            .into_iter()
+            .map(|(pii_type, pattern)| Ok((pii_type, Regex::new(&pattern)?)))
+            .collect::<Result<_, regex::Error>>()?;
+        detector.allow_list = self
+            .allow_list
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<_, _>>()?;
+        Ok(detector)
+    }
 }
 
 impl PIIDetector {
@@ -133,22 +295,50 @@ impl PIIDetector {
             ],
             address_patterns: vec![
                 Regex::new(r"\d+\s+[A-Za-z\s]+(?:Street|St|Avenue|Ave|Road|Rd|Boulevard|Blvd|Lane|Ln|Drive|Dr)").unwrap(),
-                Regex::new(r"\b\d{5}(?:-\d{4})?\b").unwrap(), // ZIP codes
             ],
             date_patterns: vec![
                 Regex::new(r"\b\d{1,2}/\d{1,2}/\d{4}\b").unwrap(),
                 Regex::new(r"\b\d{4}-\d{2}-\d{2}\b").unwrap(),
             ],
+            zip_code_regex: Regex::new(r"\b\d{5}(?:-\d{4})?\b").unwrap(),
+            // Excludes the prefix letters HMRC never issues
+            national_insurance_regex: Regex::new(r"\b[A-CEGHJ-PR-TW-Z][A-CEGHJ-NPR-TW-Z] ?\d{2} ?\d{2} ?\d{2} ?[A-D]\b").unwrap(),
+            uk_postcode_regex: Regex::new(r"\b[A-Z]{1,2}\d[A-Z\d]? ?\d[A-Z]{2}\b").unwrap(),
+            vat_id_regex: Regex::new(concat!(
+                r"\b(?:ATU\d{8}|BE[01]\d{9}|DE\d{9}|DK\d{8}|ES[0-9A-Z]\d{7}[0-9A-Z]|FI\d{8}|FR[0-9A-Z]{2}\d{9}",
+                r"|IE\d{7}[A-W][A-I]?|IT\d{11}|NL\d{9}B\d{2}|PL\d{10}|PT\d{9}|SE\d{12}",
+                r"|(?:BG|CY|CZ|EE|EL|HR|HU|LT|LU|LV|MT|RO|SI|SK)\d{8,12})\b",
+            ))
+            .unwrap(),
+            iban_regex: Regex::new(r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b").unwrap(),
+            e164_phone_regex: Regex::new(r"\+[1-9](?:[ -]?\d){7,14}\b").unwrap(),
+            eu_postcode_patterns: vec![
+                Regex::new(r"\b\d{5}\b").unwrap(),                   // DE, FR, ES, IT, FI
+                Regex::new(r"\b\d{4} ?[A-Z]{2}\b").unwrap(),         // NL
+                Regex::new(r"\b\d{2}-\d{3}\b").unwrap(),            // PL
+                Regex::new(r"\b\d{4}-\d{3}\b").unwrap(),            // PT
+                Regex::new(r"\b\d{3} \d{2}\b").unwrap(),            // SE, CZ, SK, GR
+                Regex::new(r"\b[AC-FHKNPRTV-Y]\d{2}(?:\d|W) ?[0-9AC-FHKNPRTV-Y]{4}\b").unwrap(), // IE Eircode
+            ],
+            locales: HashSet::from([PIILocale::Us]),
+            disabled_types: HashSet::new(),
+            custom_patterns: Vec::new(),
+            allow_list: Vec::new(),
         }
     }
 
+    /// Start building a detector with custom patterns and allow-lists
+    pub fn builder() -> PIIDetectorBuilder {
+        PIIDetectorBuilder::default()
+    }
+
     /// Detect PII in a JSON value
     pub fn detect_pii(&self, value: &Value) -> Option<HashSet<PIIType>> {
         let mut detected_pii = HashSet::new();
 
         match value {
             Value::String(s) => {
-                detected_pii.extend(self.detect_pii_in_string(s));
+                detected_pii.extend(self.detect_pii_in_value(s));
             }
             Value::Array(arr) => {
                 for item in arr {
@@ -171,6 +361,7 @@ impl PIIDetector {
             _ => {} // Numbers, booleans, null don't contain PII patterns
         }
 
+        detected_pii.retain(|pii| !self.is_disabled(pii));
         if detected_pii.is_empty() {
             None
         } else {
@@ -178,6 +369,86 @@ impl PIIDetector {
         }
     }
 
+    /// Detect PII in a string value, honouring the allow-list and custom patterns
+    pub(super) fn detect_pii_in_value(&self, s: &str) -> HashSet<PIIType> {
+        if self.allow_list.iter().any(|allowed| allowed.is_match(s)) {
+            return HashSet::new();
+        }
+        let mut detected = self.detect_pii_in_string(s);
+        detected.extend(
+            self.custom_patterns
+                .iter()
+                .filter(|(_, pattern)| pattern.is_match(s))
+                .map(|(pii_type, _)| pii_type.clone()),
+        );
+        detected
+    }
+
+    /// Whether a PII type has been disabled
+    pub(super) fn is_disabled(&self, pii_type: &PIIType) -> bool {
+        self.disabled_types.contains(pii_type)
+    }
+
+    /// Patterns that can report a PII type in a string value
+    ///
+    /// Validation such as the Luhn and IBAN checks is not applied, so the
+    /// patterns may match more than was reported.
+    pub(super) fn patterns_for(&self, pii_type: &PIIType) -> Vec<&Regex> {
+        let us = self.locales.contains(&PIILocale::Us);
+        let uk = self.locales.contains(&PIILocale::Uk);
+        let eu = self.locales.contains(&PIILocale::Eu);
+        let mut patterns: Vec<&Regex> = match pii_type {
+            PIIType::EmailAddress => vec![&self.email_regex],
+            PIIType::PhoneNumber => {
+                let mut patterns = Vec::new();
+                if us {
+                    patterns.push(&self.phone_regex);
+                }
+                if uk || eu {
+                    patterns.push(&self.e164_phone_regex);
+                }
+                patterns
+            }
+            PIIType::SocialSecurityNumber if us => vec![&self.ssn_regex],
+            PIIType::CreditCardNumber => vec![&self.credit_card_regex],
+            PIIType::IPAddress => vec![&self.ip_address_regex],
+            PIIType::MacAddress => vec![&self.mac_address_regex],
+            PIIType::UUID => vec![&self.uuid_regex],
+            PIIType::Base64EncodedData => vec![&self.base64_regex],
+            PIIType::JWTToken => vec![&self.jwt_regex],
+            PIIType::APIKey => vec![&self.api_key_regex],
+            PIIType::PasswordHash => vec![&self.password_hash_regex],
+            PIIType::SessionToken => vec![&self.session_token_regex],
+            PIIType::DeviceSerial => vec![&self.device_serial_regex],
+            PIIType::PersonalName => self.personal_name_patterns.iter().collect(),
+            PIIType::Address => {
+                let mut patterns: Vec<&Regex> = self.address_patterns.iter().collect();
+                if us {
+                    patterns.push(&self.zip_code_regex);
+                }
+                if uk {
+                    patterns.push(&self.uk_postcode_regex);
+                }
+                if eu {
+                    patterns.extend(&self.eu_postcode_patterns);
+                }
+                patterns
+            }
+            PIIType::DateOfBirth => self.date_patterns.iter().collect(),
+            PIIType::TaxID if eu => vec![&self.vat_id_regex],
+            PIIType::NationalInsuranceNumber if uk => vec![&self.national_insurance_regex],
+            PIIType::BankAccountNumber if uk || eu => vec![&self.iban_regex],
+            _ => Vec::new(),
+        };
+        patterns.extend(
+            self.custom_patterns
+                .iter()
+                .filter(|(custom_type, _)| custom_type == pii_type)
+                .map(|(_, pattern)| pattern),
+        );
+        patterns
+    }
+
     /// Detect PII in a string value
     fn detect_pii_in_string(&self, s: &str) -> HashSet<PIIType> {
         let mut detected = HashSet::new();
@@ -187,14 +458,14 @@ impl PIIDetector {
             detected.insert(PIIType::EmailAddress);
         }
 
-        // Phone numbers
-        if self.phone_regex.is_match(s) {
-            detected.insert(PIIType::PhoneNumber);
+        if self.locales.contains(&PIILocale::Us) {
+            detected.extend(self.detect_us_pii(s));
         }
-
-        // Social Security Numbers
-        if self.ssn_regex.is_match(s) {
-            detected.insert(PIIType::SocialSecurityNumber);
+        if self.locales.contains(&PIILocale::Uk) {
+            detected.extend(self.detect_uk_pii(s));
+        }
+        if self.locales.contains(&PIILocale::Eu) {
+            detected.extend(self.detect_eu_pii(s));
         }
 
         // Credit card numbers
@@ -274,8 +545,80 @@ impl PIIDetector {
         detected
     }
 
+    /// Detect PII in a string value using the US pack
+    fn detect_us_pii(&self, s: &str) -> HashSet<PIIType> {
+        let mut detected = HashSet::new();
+        if self.phone_regex.is_match(s) {
+            detected.insert(PIIType::PhoneNumber);
+        }
+        if self.ssn_regex.is_match(s) {
+            detected.insert(PIIType::SocialSecurityNumber);
+        }
+        if self.zip_code_regex.is_match(s) {
+            detected.insert(PIIType::Address);
+        }
+        detected
+    }
+
+    /// Detect PII in a string value using the UK pack
+    fn detect_uk_pii(&self, s: &str) -> HashSet<PIIType> {
+        let mut detected = self.detect_international_pii(s);
+        if self.national_insurance_regex.is_match(s) {
+            detected.insert(PIIType::NationalInsuranceNumber);
+        }
+        if self.uk_postcode_regex.is_match(s) {
+            detected.insert(PIIType::Address);
+        }
+        detected
+    }
+
+    /// Detect PII in a string value using the EU pack
+    fn detect_eu_pii(&self, s: &str) -> HashSet<PIIType> {
+        let mut detected = self.detect_international_pii(s);
+        if self.vat_id_regex.is_match(s) {
+            detected.insert(PIIType::TaxID);
+        }
+        if self.eu_postcode_patterns.iter().any(|pattern| pattern.is_match(s)) {
+            detected.insert(PIIType::Address);
+        }
+        detected
+    }
+
+    /// IBANs and E.164 phone numbers, shared by the UK and EU packs
+    fn detect_international_pii(&self, s: &str) -> HashSet<PIIType> {
+        let mut detected = HashSet::new();
+        if self.e164_phone_regex.is_match(s) {
+            detected.insert(PIIType::PhoneNumber);
+        }
+        if self.iban_regex.find_iter(s).any(|candidate| Self::is_valid_iban(candidate.as_str())) {
+            detected.insert(PIIType::BankAccountNumber);
+        }
+        detected
+    }
+
+    /// Validate an IBAN's check digits (ISO 13616 mod-97)
+    fn is_valid_iban(candidate: &str) -> bool {
+        let compact: String = candidate.chars().filter(|c| !c.is_whitespace()).collect();
+        if !(15..=34).contains(&compact.len()) {
+            return false;
+        }
+        let (head, tail) = compact.split_at(4);
+        let mut remainder = 0u32;
+        for c in tail.chars().chain(head.chars()) {
+            let Some(value) = c.to_digit(36) else {
+                return false;
+            };
+            remainder = if value < 10 {
+                (remainder * 10 + value) % 97
+            } else {
+                (remainder * 100 + value) % 97
+            };
+        }
+        remainder == 1
+    }
+
     /// Detect PII based on field names
-    fn detect_pii_in_field_name(&self, field_name: &str) -> HashSet<PIIType> {
+    pub(super) fn detect_pii_in_field_name(&self, field_name: &str) -> HashSet<PIIType> {
         let mut detected = HashSet::new();
         let field_lower = field_name.to_lowercase();
 
@@ -331,6 +674,15 @@ impl PIIDetector {
         if field_lower.contains("license") && field_lower.contains("driver") {
             detected.insert(PIIType::DriversLicense);
         }
+        if field_lower.contains("national_insurance") || field_lower == "nino" {
+            detected.insert(PIIType::NationalInsuranceNumber);
+        }
+        if field_lower.contains("iban") || field_lower.contains("bank_account") {
+            detected.insert(PIIType::BankAccountNumber);
+        }
+        if field_lower == "vat_id" || field_lower == "vat_number" {
+            detected.insert(PIIType::TaxID);
+        }
 
         detected
     }
@@ -594,4 +946,85 @@ mod tests {
         
         assert_eq!(PIIType::UUID.risk_level(), PIIRiskLevel::Low);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_allow_list_suppresses_hex_false_positives() {
+        let proof = "9f".repeat(64);
+        let allowing = PIIDetector::builder().allow("^[0-9a-f]{64,128}$").build().unwrap();
+
+        assert!(PIIDetector::new().detect_pii(&json!(proof)).is_some());
+        assert!(allowing.detect_pii(&json!(proof)).is_none());
+        assert!(allowing.detect_pii(&json!("user@example.com")).is_some());
+    }
+
+    #[test]
+    fn test_disabled_types_and_custom_patterns() {
+        let detector = PIIDetector::builder()
+            .disable(PIIType::UUID)
+            .disable(PIIType::EmailAddress)
+            .enable(PIIType::EmailAddress)
+            .custom_pattern(PIIType::TaxID, r"^\d{2}-\d{7}$")
+            .build()
+            .unwrap();
+
+        assert!(PIIDetector::new().detect_pii(&json!("request 7d9f1c2e-aaaa-bbbb-cccc-dddddddddddd")).is_some());
+        assert!(detector.detect_pii(&json!("request 7d9f1c2e-aaaa-bbbb-cccc-dddddddddddd")).is_none());
+        assert!(detector.detect_pii(&json!({ "contact_email": "n/a" })).unwrap().contains(&PIIType::EmailAddress));
+        assert!(detector.detect_pii(&json!("12-3456789")).unwrap().contains(&PIIType::TaxID));
+    }
+
+    #[test]
+    fn test_invalid_patterns_are_rejected() {
+        let config: PIIDetectorConfig = serde_json::from_str(r#"{ "allow_list": ["(unclosed"] }"#).unwrap();
+
+        assert!(PIIDetector::builder().config(&config).build().is_err());
+        assert!(PIIDetector::builder().custom_pattern(PIIType::TaxID, "[").build().is_err());
+        assert!(serde_json::from_str::<PIIDetectorConfig>(r#"{ "disabled": [] }"#).is_err());
+    }
+
+    #[test]
+    fn test_uk_locale_pack() {
+        let detector = PIIDetector::builder().locales([PIILocale::Uk]).build().unwrap();
+
+        let detect = |value: &str| detector.detect_pii(&json!(value)).unwrap_or_default();
+        assert!(detect("NI number AB 12 34 56 C").contains(&PIIType::NationalInsuranceNumber));
+        assert!(detect("SW1A 1AA").contains(&PIIType::Address));
+        assert!(detect("+44 7911 123456").contains(&PIIType::PhoneNumber));
+        assert!(detect("GB82 WEST 1234 5698 7654 32").contains(&PIIType::BankAccountNumber));
+        assert!(!detect("123-45-6789").contains(&PIIType::SocialSecurityNumber));
+    }
+
+    #[test]
+    fn test_eu_locale_pack() {
+        let detector = PIIDetector::builder().locales([PIILocale::Eu]).build().unwrap();
+
+        let detect = |value: &str| detector.detect_pii(&json!(value)).unwrap_or_default();
+        assert!(detect("USt-IdNr. DE123456789").contains(&PIIType::TaxID));
+        assert!(detect("NL123456789B01").contains(&PIIType::TaxID));
+        assert!(detect("DE89 3704 0044 0532 0130 00").contains(&PIIType::BankAccountNumber));
+        assert!(detect("1012 AB Amsterdam").contains(&PIIType::Address));
+        assert!(detect("00-950 Warszawa").contains(&PIIType::Address));
+        assert!(detect("+33612345678").contains(&PIIType::PhoneNumber));
+    }
+
+    #[test]
+    fn test_iban_check_digits_are_validated() {
+        let detector = PIIDetector::builder().locales([PIILocale::Eu]).build().unwrap();
+
+        assert!(PIIDetector::is_valid_iban("GB82WEST12345698765432"));
+        assert!(!PIIDetector::is_valid_iban("GB83WEST12345698765432"));
+        assert!(!detector.detect_pii(&json!("DE88 3704 0044 0532 0130 00")).unwrap_or_default().contains(&PIIType::BankAccountNumber));
+        // The default US pack does not look for IBANs
+        assert!(!PIIDetector::new().detect_pii(&json!("DE89370400440532013000")).unwrap_or_default().contains(&PIIType::BankAccountNumber));
+    }
+
+    #[test]
+    fn test_locales_from_config() {
+        let config: PIIDetectorConfig = serde_json::from_str(r#"{ "locales": ["us", "eu"] }"#).unwrap();
+        let detector = PIIDetector::builder().config(&config).build().unwrap();
+
+        assert!(detector.detect_pii(&json!("123-45-6789")).unwrap().contains(&PIIType::SocialSecurityNumber));
+        assert!(detector.detect_pii(&json!("FR12345678901")).unwrap().contains(&PIIType::TaxID));
+        assert!(serde_json::from_str::<PIIDetectorConfig>(r#"{ "locales": ["mars"] }"#).is_err());
+    }
+}
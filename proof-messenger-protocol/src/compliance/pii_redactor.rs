@@ -0,0 +1,440 @@
+// src/compliance/pii_redactor.rs
+//! PII Redaction Module
+//!
+//! Detection tells us a value holds PII; often we still need to keep a record
+//! of it with the PII masked. [`PIIDetector::redact_pii`] returns a copy of a
+//! JSON value with the same structure, in which every detected span is
+//! replaced by a typed placeholder such as `[EMAIL]` or `[SSN]`:
+//!
+//! - spans of string values matched by the detector's patterns are replaced
+//!   in place, leaving the rest of the string intact
+//! - string and number values under PII field names, such as `"ssn"`, are
+//!   replaced as a whole
+//! - object keys, booleans and nulls are kept
+//!
+//! [`PIIRedactor`] chooses how each PII type is masked (see
+//! [`MaskingStrategy`]) and reports what was redacted, optionally to a
+//! [`ComplianceAuditLogger`]. Records hold the JSON pointer and PII type of
+//! each redaction, never the redacted text.
+//!
+//! ```rust
+//! use proof_messenger_protocol::compliance::{MaskingStrategy, PIIDetector, PIIRedactor, PIIType};
+//! use serde_json::json;
+//!
+//! let value = json!({ "note": "Contact user@example.com", "ssn": "123-45-6789" });
+//!
+//! assert_eq!(
+//!     PIIDetector::new().redact_pii(&value),
+//!     json!({ "note": "Contact [EMAIL]", "ssn": "[SSN]" })
+//! );
+//!
+//! let detector = PIIDetector::new();
+//! let redactor = PIIRedactor::new(&detector)
+//!     .strategy(PIIType::SocialSecurityNumber, MaskingStrategy::Partial);
+//! let redacted = redactor.redact(&value);
+//!
+//! assert_eq!(redacted.value["ssn"], "***-**-6789");
+//! assert_eq!(redacted.records.len(), 2);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::ops::Range;
+
+use super::audit_logger::ComplianceAuditLogger;
+use super::pii_detector::{PIIDetector, PIIType};
+
+/// Characters left visible by [`MaskingStrategy::Partial`]
+const PARTIAL_VISIBLE: usize = 4;
+
+/// Shortest span, in letters and digits, that [`MaskingStrategy::Partial`] masks partially
+const PARTIAL_MIN_LEN: usize = 8;
+
+/// Bytes of the SHA-256 digest shown by [`MaskingStrategy::Hash`]
+const HASH_BYTES: usize = 8;
+
+/// How a redacted span is replaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MaskingStrategy {
+    /// Replace the span with a typed placeholder, e.g. `[EMAIL]`
+    #[default]
+    Full,
+    /// Mask all but the last four letters and digits, keeping separators,
+    /// e.g. `***-**-6789`
+    ///
+    /// Spans with fewer than eight letters and digits are replaced in full.
+    Partial,
+    /// Replace the span with a typed, truncated SHA-256 digest, e.g.
+    /// `[EMAIL:b4c9a289323b21a0]`, so equal values can still be correlated
+    Hash,
+}
+
+/// One redacted span
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionRecord {
+    /// JSON pointer to the value the span was in
+    pub path: String,
+    /// Type of PII that was redacted
+    pub pii_type: PIIType,
+    /// How the span was masked
+    pub strategy: MaskingStrategy,
+}
+
+/// A redacted value and what was redacted from it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redaction {
+    /// Copy of the input with PII masked
+    pub value: Value,
+    /// Redacted spans, in document order
+    pub records: Vec<RedactionRecord>,
+}
+
+/// Redacts PII from JSON values with per-type masking strategies
+pub struct PIIRedactor<'a> {
+    detector: &'a PIIDetector,
+    default_strategy: MaskingStrategy,
+    strategies: HashMap<PIIType, MaskingStrategy>,
+    hash_salt: Vec<u8>,
+}
+
+impl<'a> PIIRedactor<'a> {
+    /// Redactor masking everything the detector finds with [`MaskingStrategy::Full`]
+    pub fn new(detector: &'a PIIDetector) -> Self {
+        Self {
+            detector,
+            default_strategy: MaskingStrategy::Full,
+            strategies: HashMap::new(),
+            hash_salt: Vec::new(),
+        }
+    }
+
+    /// Strategy for PII types without one of their own
+    pub fn default_strategy(mut self, strategy: MaskingStrategy) -> Self {
+        self.default_strategy = strategy;
+        self
+    }
+
+    /// Strategy for one PII type
+    pub fn strategy(mut self, pii_type: PIIType, strategy: MaskingStrategy) -> Self {
+        self.strategies.insert(pii_type, strategy);
+        self
+    }
+
+    /// Salt hashed in front of each span by [`MaskingStrategy::Hash`]
+    ///
+    /// Without a salt, low-entropy values such as phone numbers can be
+    /// recovered from their digests by brute force.
+    pub fn hash_salt(mut self, salt: impl Into<Vec<u8>>) -> Self {
+        self.hash_salt = salt.into();
+        self
+    }
+
+    /// Redact PII from a value
+    pub fn redact(&self, value: &Value) -> Redaction {
+        let mut records = Vec::new();
+        let mut path = String::new();
+        let value = self.redact_value(value, &mut path, &mut records);
+        Redaction { value, records }
+    }
+
+    /// Redact PII from a value and log what was redacted
+    pub fn redact_and_log(&self, value: &Value, logger: &mut ComplianceAuditLogger, context_type: &str) -> Value {
+        let redaction = self.redact(value);
+        if !redaction.records.is_empty() {
+            logger.log_pii_redaction(context_type, &redaction.records);
+        }
+        redaction.value
+    }
+
+    fn redact_value(&self, value: &Value, path: &mut String, records: &mut Vec<RedactionRecord>) -> Value {
+        match value {
+            Value::String(s) => Value::String(self.redact_string(s, path, records)),
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| with_segment(path, &index.to_string(), |path| self.redact_value(item, path, records)))
+                    .collect(),
+            ),
+            Value::Object(fields) => {
+                let mut redacted = Map::with_capacity(fields.len());
+                for (key, field) in fields {
+                    let field = with_segment(path, key, |path| match (self.field_name_pii(key), field) {
+                        (Some(pii_type), Value::String(_) | Value::Number(_)) => {
+                            let text = match field {
+                                Value::String(s) => s.clone(),
+                                other => other.to_string(),
+                            };
+                            Value::String(self.mask_whole(&text, pii_type, path, records))
+                        }
+                        _ => self.redact_value(field, path, records),
+                    });
+                    redacted.insert(key.clone(), field);
+                }
+                Value::Object(redacted)
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Highest-risk PII type indicated by a field name
+    fn field_name_pii(&self, key: &str) -> Option<PIIType> {
+        self.detector
+            .detect_pii_in_field_name(key)
+            .into_iter()
+            .filter(|pii_type| !self.detector.is_disabled(pii_type))
+            .max_by_key(|pii_type| (pii_type.risk_level(), pii_type.placeholder()))
+    }
+
+    fn redact_string(&self, s: &str, path: &str, records: &mut Vec<RedactionRecord>) -> String {
+        let spans = self.find_spans(s);
+        if spans.is_empty() {
+            return s.to_string();
+        }
+        let mut redacted = String::with_capacity(s.len());
+        let mut end = 0;
+        for (pii_type, span) in spans {
+            redacted.push_str(&s[end..span.start]);
+            redacted.push_str(&self.mask(&s[span.clone()], &pii_type));
+            records.push(self.record(path, pii_type));
+            end = span.end;
+        }
+        redacted.push_str(&s[end..]);
+        redacted
+    }
+
+    fn mask_whole(&self, text: &str, pii_type: PIIType, path: &str, records: &mut Vec<RedactionRecord>) -> String {
+        let masked = self.mask(text, &pii_type);
+        records.push(self.record(path, pii_type));
+        masked
+    }
+
+    /// Non-overlapping spans of detected PII, in order
+    ///
+    /// Overlapping matches are merged and reported as their highest-risk
+    /// type. A detected type none of whose patterns match is redacted as the
+    /// whole string, so nothing the detector reports is left in place.
+    fn find_spans(&self, s: &str) -> Vec<(PIIType, Range<usize>)> {
+        let mut matches = Vec::new();
+        for pii_type in self.detector.detect_pii_in_value(s) {
+            if self.detector.is_disabled(&pii_type) {
+                continue;
+            }
+            let found = matches.len();
+            for pattern in self.detector.patterns_for(&pii_type) {
+                matches.extend(
+                    pattern
+                        .find_iter(s)
+                        .filter(|m| !m.is_empty())
+                        .map(|m| (pii_type.clone(), m.range())),
+                );
+            }
+            if matches.len() == found {
+                matches.push((pii_type, 0..s.len()));
+            }
+        }
+        matches.sort_by_key(|(_, span)| (span.start, std::cmp::Reverse(span.end)));
+
+        let mut spans: Vec<(PIIType, Range<usize>)> = Vec::new();
+        for (pii_type, span) in matches {
+            match spans.last_mut() {
+                Some((merged_type, merged)) if span.start < merged.end => {
+                    merged.end = merged.end.max(span.end);
+                    if pii_type.risk_level() > merged_type.risk_level() {
+                        *merged_type = pii_type;
+                    }
+                }
+                _ => spans.push((pii_type, span)),
+            }
+        }
+        spans
+    }
+
+    fn strategy_for(&self, pii_type: &PIIType) -> MaskingStrategy {
+        self.strategies.get(pii_type).copied().unwrap_or(self.default_strategy)
+    }
+
+    fn record(&self, path: &str, pii_type: PIIType) -> RedactionRecord {
+        RedactionRecord {
+            path: path.to_string(),
+            strategy: self.strategy_for(&pii_type),
+            pii_type,
+        }
+    }
+
+    fn mask(&self, text: &str, pii_type: &PIIType) -> String {
+        match self.strategy_for(pii_type) {
+            MaskingStrategy::Full => format!("[{}]", pii_type.placeholder()),
+            MaskingStrategy::Partial => partial_mask(text).unwrap_or_else(|| format!("[{}]", pii_type.placeholder())),
+            MaskingStrategy::Hash => {
+                let mut hasher = Sha256::new();
+                hasher.update(&self.hash_salt);
+                hasher.update(text.as_bytes());
+                format!("[{}:{}]", pii_type.placeholder(), hex::encode(&hasher.finalize()[..HASH_BYTES]))
+            }
+        }
+    }
+}
+
+impl PIIDetector {
+    /// Copy of a value with every detected PII span replaced by a typed placeholder
+    pub fn redact_pii(&self, value: &Value) -> Value {
+        PIIRedactor::new(self).redact(value).value
+    }
+}
+
+impl PIIType {
+    /// Label used in redaction placeholders, e.g. `EMAIL` in `[EMAIL]`
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            PIIType::EmailAddress => "EMAIL",
+            PIIType::PhoneNumber => "PHONE",
+            PIIType::SocialSecurityNumber => "SSN",
+            PIIType::CreditCardNumber => "CREDIT_CARD",
+            PIIType::IPAddress => "IP_ADDRESS",
+            PIIType::MacAddress => "MAC_ADDRESS",
+            PIIType::UUID => "UUID",
+            PIIType::Base64EncodedData => "BASE64",
+            PIIType::JWTToken => "JWT",
+            PIIType::APIKey => "API_KEY",
+            PIIType::PasswordHash => "PASSWORD_HASH",
+            PIIType::BiometricTemplate => "BIOMETRIC",
+            PIIType::DeviceSerial => "DEVICE_SERIAL",
+            PIIType::SessionToken => "SESSION_TOKEN",
+            PIIType::PersonalName => "NAME",
+            PIIType::Address => "ADDRESS",
+            PIIType::DateOfBirth => "DATE_OF_BIRTH",
+            PIIType::TaxID => "TAX_ID",
+            PIIType::PassportNumber => "PASSPORT",
+            PIIType::DriversLicense => "DRIVERS_LICENSE",
+            PIIType::NationalInsuranceNumber => "NINO",
+            PIIType::BankAccountNumber => "IBAN",
+        }
+    }
+}
+
+/// Mask all but the last few letters and digits, or `None` if the text is too short
+fn partial_mask(text: &str) -> Option<String> {
+    if text.chars().filter(|c| c.is_alphanumeric()).count() < PARTIAL_MIN_LEN {
+        return None;
+    }
+    let mut visible = 0;
+    let mut masked: Vec<char> = text
+        .chars()
+        .rev()
+        .map(|c| {
+            if !c.is_alphanumeric() {
+                c
+            } else if visible < PARTIAL_VISIBLE {
+                visible += 1;
+                c
+            } else {
+                '*'
+            }
+        })
+        .collect();
+    masked.reverse();
+    Some(masked.into_iter().collect())
+}
+
+/// Run `f` with a JSON pointer segment appended to `path`
+fn with_segment<T>(path: &mut String, segment: &str, f: impl FnOnce(&mut String) -> T) -> T {
+    let len = path.len();
+    path.push('/');
+    path.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+    let result = f(path);
+    path.truncate(len);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compliance::{AuditEventType, PIILocale};
+    use serde_json::json;
+
+    #[test]
+    fn test_redaction_preserves_structure() {
+        let detector = PIIDetector::new();
+        let value = json!({
+            "action": "wire_transfer",
+            "amount": 1000,
+            "approved": true,
+            "parties": [{ "contact": "Reach me at user@example.com or 192.168.1.100" }, null],
+            "phone": 5551234567u64
+        });
+
+        let redacted = detector.redact_pii(&value);
+
+        assert_eq!(
+            redacted,
+            json!({
+                "action": "wire_transfer",
+                "amount": 1000,
+                "approved": true,
+                "parties": [{ "contact": "Reach me at [EMAIL] or [IP_ADDRESS]" }, null],
+                "phone": "[PHONE]"
+            })
+        );
+        assert!(detector.detect_pii(&json!(redacted["parties"])).is_none());
+    }
+
+    #[test]
+    fn test_masking_strategies() {
+        let detector = PIIDetector::new();
+        let value = json!(["card 4532015112830366", "user@example.com", "user@example.com"]);
+        let redactor = PIIRedactor::new(&detector)
+            .default_strategy(MaskingStrategy::Hash)
+            .strategy(PIIType::CreditCardNumber, MaskingStrategy::Partial)
+            .hash_salt("tenant-a");
+
+        let redacted = redactor.redact(&value).value;
+
+        assert_eq!(redacted[0], "card ************0366");
+        let hashed = redacted[1].as_str().unwrap();
+        assert!(hashed.starts_with("[EMAIL:") && hashed.len() == "[EMAIL:]".len() + 2 * HASH_BYTES);
+        assert_eq!(redacted[1], redacted[2]);
+        let other_salt = PIIRedactor::new(&detector).default_strategy(MaskingStrategy::Hash).redact(&value).value;
+        assert_ne!(redacted[1], other_salt[1]);
+    }
+
+    #[test]
+    fn test_partial_masking_short_spans_in_full() {
+        assert_eq!(partial_mask("123-45-6789").as_deref(), Some("***-**-6789"));
+        assert_eq!(partial_mask("1234567"), None);
+    }
+
+    #[test]
+    fn test_records_and_audit_log() {
+        let detector = PIIDetector::builder().locales([PIILocale::Uk]).build().unwrap();
+        let value = json!({ "a/b": ["NI AB 12 34 56 C"], "note": "nothing here" });
+        let mut logger = ComplianceAuditLogger::new();
+
+        let redacted = PIIRedactor::new(&detector).redact_and_log(&value, &mut logger, "export");
+
+        assert_eq!(redacted["a/b"][0], "NI [NINO]");
+        let entries = logger.get_entries_by_type(AuditEventType::PIIRedaction);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].event_details["redactions"][0]["path"], "/a~1b/0");
+        assert!(!logger.export_as_json().unwrap().contains("AB 12 34 56 C"));
+    }
+
+    #[test]
+    fn test_allow_listed_and_disabled_values_are_kept() {
+        let detector = PIIDetector::builder()
+            .allow("^[0-9a-f]{64}$")
+            .disable(PIIType::EmailAddress)
+            .build()
+            .unwrap();
+        let key = "ab".repeat(32);
+        let value = json!({ "public_key": key, "email": "user@example.com" });
+
+        let redaction = PIIRedactor::new(&detector).redact(&value);
+
+        assert_eq!(redaction.value, value);
+        assert!(redaction.records.is_empty());
+    }
+}
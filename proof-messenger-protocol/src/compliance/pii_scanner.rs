@@ -0,0 +1,392 @@
+// src/compliance/pii_scanner.rs
+//! Streaming PII Scanning Module
+//!
+//! [`PIIDetector::detect_pii`] needs the whole document parsed into a
+//! `serde_json::Value` first, which for multi-megabyte payloads means holding
+//! every string in memory at once. [`PIIDetector::scan_reader`] instead reads
+//! a JSON document in chunks and checks each key and string value as soon as
+//! it has been read, reusing one buffer for all of them. A scan can be
+//! bounded by [`ScanOptions`]:
+//!
+//! - `max_bytes` stops reading after that many bytes of input
+//! - `max_duration` stops once the scan has run that long
+//! - `stop_at` stops at the first finding at or above a risk level
+//!
+//! The scanner only tokenizes enough JSON to find strings and tell keys from
+//! values; it does not otherwise validate the document.
+//!
+//! ```rust
+//! use proof_messenger_protocol::compliance::{PIIDetector, PIIRiskLevel, PIIType, ScanOptions, ScanStop};
+//!
+//! let document = format!(r#"{{"ssn": "123-45-6789", "padding": "{}"}}"#, "x".repeat(1 << 20));
+//! let options = ScanOptions { stop_at: Some(PIIRiskLevel::Critical), ..Default::default() };
+//!
+//! let report = PIIDetector::new().scan_reader(document.as_bytes(), &options).unwrap();
+//!
+//! assert!(report.pii_types.contains(&PIIType::SocialSecurityNumber));
+//! assert_eq!(report.stopped, Some(ScanStop::RiskThreshold));
+//! assert!(report.bytes_scanned < 64);
+//! ```
+
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use super::pii_detector::{PIIDetector, PIIRiskLevel, PIIType};
+
+/// Bytes read between checks of the time budget inside long strings
+const TIME_CHECK_INTERVAL: usize = 64 * 1024;
+
+/// Errors that end a scan early
+#[derive(Error, Debug)]
+pub enum ScanError {
+    #[error("Failed to read document: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Malformed JSON at byte {offset}: {reason}")]
+    Malformed { offset: usize, reason: &'static str },
+}
+
+/// Limits on a streaming scan
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanOptions {
+    /// Stop after reading this many bytes
+    pub max_bytes: Option<usize>,
+    /// Stop once the scan has run this long
+    ///
+    /// Not supported on `wasm32-unknown-unknown`, which has no clock.
+    pub max_duration: Option<Duration>,
+    /// Stop at the first finding at or above this risk level
+    pub stop_at: Option<PIIRiskLevel>,
+}
+
+/// Why a scan stopped before the end of the document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanStop {
+    /// A finding reached [`ScanOptions::stop_at`]
+    RiskThreshold,
+    /// [`ScanOptions::max_bytes`] were read
+    ByteBudget,
+    /// [`ScanOptions::max_duration`] elapsed
+    TimeBudget,
+}
+
+/// Findings of a streaming scan
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanReport {
+    /// PII types found in the scanned part of the document
+    pub pii_types: HashSet<PIIType>,
+    /// Bytes of input read
+    pub bytes_scanned: usize,
+    /// Why the scan ended early, or `None` if the whole document was scanned
+    pub stopped: Option<ScanStop>,
+}
+
+impl ScanReport {
+    /// Whether the whole document was scanned
+    pub fn is_complete(&self) -> bool {
+        self.stopped.is_none()
+    }
+
+    /// Highest risk level among the findings
+    pub fn highest_risk_level(&self) -> Option<PIIRiskLevel> {
+        self.pii_types.iter().map(PIIType::risk_level).max()
+    }
+}
+
+impl PIIDetector {
+    /// Scan a JSON document for PII without parsing it into memory
+    pub fn scan_reader<R: Read>(&self, reader: R, options: &ScanOptions) -> Result<ScanReport, ScanError> {
+        Scan::new(self, BufReader::new(reader), options).run()
+    }
+
+    /// Scan a JSON document held in a string
+    pub fn scan_str(&self, json: &str, options: &ScanOptions) -> Result<ScanReport, ScanError> {
+        self.scan_reader(json.as_bytes(), options)
+    }
+}
+
+/// State of one streaming scan
+struct Scan<'a, R> {
+    detector: &'a PIIDetector,
+    reader: R,
+    options: &'a ScanOptions,
+    started: Option<Instant>,
+    report: ScanReport,
+    /// Decoded bytes of the most recent string
+    text: Vec<u8>,
+    /// Whether `text` holds a string not yet classified as a key or value
+    pending: bool,
+}
+
+/// How one byte of input was handled
+enum Step {
+    Continue,
+    Stop(ScanStop),
+}
+
+impl<'a, R: BufRead> Scan<'a, R> {
+    fn new(detector: &'a PIIDetector, reader: R, options: &'a ScanOptions) -> Self {
+        Self {
+            detector,
+            reader,
+            options,
+            started: options.max_duration.map(|_| Instant::now()),
+            report: ScanReport::default(),
+            text: Vec::new(),
+            pending: false,
+        }
+    }
+
+    fn run(mut self) -> Result<ScanReport, ScanError> {
+        match self.scan() {
+            Ok(()) => Ok(self.report),
+            // A document cut short by the byte budget ends mid-token
+            Err(ScanError::Malformed { .. }) if self.report.stopped == Some(ScanStop::ByteBudget) => Ok(self.report),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn scan(&mut self) -> Result<(), ScanError> {
+        while let Some(byte) = self.next_byte()? {
+            let step = match byte {
+                b'"' => match self.classify_pending(false)? {
+                    Step::Continue => self.read_string()?,
+                    stop => stop,
+                },
+                b':' => self.classify_pending(true)?,
+                b' ' | b'\t' | b'\n' | b'\r' => Step::Continue,
+                _ => self.classify_pending(false)?,
+            };
+            if let Step::Stop(reason) = step {
+                self.report.stopped = Some(reason);
+                return Ok(());
+            }
+        }
+        if let Step::Stop(reason) = self.classify_pending(false)? {
+            self.report.stopped = Some(reason);
+        }
+        Ok(())
+    }
+
+    /// Read the next byte, or `None` at the end of the input or the byte budget
+    fn next_byte(&mut self) -> Result<Option<u8>, ScanError> {
+        if self.options.max_bytes.is_some_and(|max| self.report.bytes_scanned >= max) {
+            self.report.stopped = Some(ScanStop::ByteBudget);
+            return Ok(None);
+        }
+        let byte = match self.reader.fill_buf()?.first() {
+            Some(&byte) => byte,
+            None => return Ok(None),
+        };
+        self.reader.consume(1);
+        self.report.bytes_scanned += 1;
+        Ok(Some(byte))
+    }
+
+    /// Decode a string whose opening quote has been read into `text`
+    fn read_string(&mut self) -> Result<Step, ScanError> {
+        self.text.clear();
+        let mut since_time_check = 0;
+        loop {
+            let Some(byte) = self.next_byte()? else {
+                return Err(self.malformed("unterminated string"));
+            };
+            match byte {
+                b'"' => break,
+                b'\\' => self.read_escape()?,
+                byte => self.text.push(byte),
+            }
+            since_time_check += 1;
+            if since_time_check == TIME_CHECK_INTERVAL {
+                since_time_check = 0;
+                if self.out_of_time() {
+                    return Ok(Step::Stop(ScanStop::TimeBudget));
+                }
+            }
+        }
+        self.pending = true;
+        Ok(Step::Continue)
+    }
+
+    /// Decode one escape sequence into `text`
+    fn read_escape(&mut self) -> Result<(), ScanError> {
+        let Some(escape) = self.next_byte()? else {
+            return Err(self.malformed("unterminated escape"));
+        };
+        let decoded = match escape {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let unit = self.read_hex4()?;
+                let code = if (0xD800..0xDC00).contains(&unit) {
+                    // A high surrogate must be followed by an escaped low surrogate
+                    if self.next_byte()? != Some(b'\\') || self.next_byte()? != Some(b'u') {
+                        return Err(self.malformed("unpaired surrogate"));
+                    }
+                    let low = self.read_hex4()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(self.malformed("unpaired surrogate"));
+                    }
+                    0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)
+                } else {
+                    unit
+                };
+                char::from_u32(code).ok_or_else(|| self.malformed("invalid unicode escape"))?
+            }
+            _ => return Err(self.malformed("invalid escape")),
+        };
+        let mut utf8 = [0u8; 4];
+        self.text.extend_from_slice(decoded.encode_utf8(&mut utf8).as_bytes());
+        Ok(())
+    }
+
+    /// Read the four hex digits of a `\u` escape
+    fn read_hex4(&mut self) -> Result<u32, ScanError> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let digit = self
+                .next_byte()?
+                .and_then(|byte| (byte as char).to_digit(16))
+                .ok_or_else(|| self.malformed("invalid unicode escape"))?;
+            value = value * 16 + digit;
+        }
+        Ok(value)
+    }
+
+    /// Check the pending string as a field name or a value
+    fn classify_pending(&mut self, is_key: bool) -> Result<Step, ScanError> {
+        if !std::mem::take(&mut self.pending) {
+            return Ok(Step::Continue);
+        }
+        let text = std::str::from_utf8(&self.text).map_err(|_| self.malformed("invalid UTF-8 in string"))?;
+        let found = if is_key {
+            self.detector.detect_pii_in_field_name(text)
+        } else {
+            self.detector.detect_pii_in_value(text)
+        };
+        for pii_type in found {
+            if self.detector.is_disabled(&pii_type) {
+                continue;
+            }
+            let reached_threshold = self
+                .options
+                .stop_at
+                .as_ref()
+                .is_some_and(|threshold| pii_type.risk_level() >= *threshold);
+            self.report.pii_types.insert(pii_type);
+            if reached_threshold {
+                return Ok(Step::Stop(ScanStop::RiskThreshold));
+            }
+        }
+        if self.out_of_time() {
+            return Ok(Step::Stop(ScanStop::TimeBudget));
+        }
+        Ok(Step::Continue)
+    }
+
+    fn out_of_time(&self) -> bool {
+        match (self.started, self.options.max_duration) {
+            (Some(started), Some(max)) => started.elapsed() >= max,
+            _ => false,
+        }
+    }
+
+    fn malformed(&self, reason: &'static str) -> ScanError {
+        ScanError::Malformed {
+            offset: self.report.bytes_scanned,
+            reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_scan_matches_detect_pii() {
+        let document = json!({
+            "user": { "contact": "user@example.com", "tags": ["vip", "192.168.1.100"] },
+            "phone_number": "n/a",
+            "amount": 100,
+            "note": "caf\u{e9} \u{1F600}"
+        });
+        let detector = PIIDetector::new();
+
+        let report = detector.scan_str(&document.to_string(), &ScanOptions::default()).unwrap();
+
+        assert!(report.is_complete());
+        assert_eq!(report.bytes_scanned, document.to_string().len());
+        assert_eq!(Some(report.pii_types), detector.detect_pii(&document));
+    }
+
+    #[test]
+    fn test_escaped_strings_are_decoded() {
+        let report = PIIDetector::new()
+            .scan_str(r#"["user@example.com", "😀 \"quoted\""]"#, &ScanOptions::default())
+            .unwrap();
+
+        assert!(report.pii_types.contains(&PIIType::EmailAddress));
+    }
+
+    #[test]
+    fn test_byte_budget_stops_large_documents() {
+        // ARRANGE: A large document with PII only at the end
+        let mut document = String::from("[");
+        for i in 0..20_000 {
+            document.push_str(&format!(r#"{{"id": {}, "status": "delivered"}},"#, i));
+        }
+        document.push_str(r#""user@example.com"]"#);
+        let detector = PIIDetector::new();
+
+        // ACT: Scan it with and without a byte budget
+        let bounded = detector
+            .scan_str(&document, &ScanOptions { max_bytes: Some(1024), ..Default::default() })
+            .unwrap();
+        let full = detector.scan_str(&document, &ScanOptions::default()).unwrap();
+
+        // ASSERT: The bounded scan reports where it stopped; the full scan finds the email
+        assert_eq!(bounded.stopped, Some(ScanStop::ByteBudget));
+        assert_eq!(bounded.bytes_scanned, 1024);
+        assert!(bounded.pii_types.is_empty());
+        let cut_in_escape = detector
+            .scan_str(r#"["\u0040"]"#, &ScanOptions { max_bytes: Some(5), ..Default::default() })
+            .unwrap();
+        assert_eq!(cut_in_escape.stopped, Some(ScanStop::ByteBudget));
+        assert!(full.is_complete());
+        assert!(full.pii_types.contains(&PIIType::EmailAddress));
+    }
+
+    #[test]
+    fn test_time_budget_and_disabled_types() {
+        let detector = PIIDetector::builder().disable(PIIType::EmailAddress).build().unwrap();
+        let options = ScanOptions { max_duration: Some(Duration::ZERO), ..Default::default() };
+
+        let report = detector.scan_str(r#"["user@example.com", "123-45-6789"]"#, &options).unwrap();
+
+        assert_eq!(report.stopped, Some(ScanStop::TimeBudget));
+        assert!(report.pii_types.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_documents_are_rejected() {
+        let detector = PIIDetector::new();
+
+        for document in [r#"{"open": "never closed"#, r#"["\q"]"#, r#"["\ud83d"]"#] {
+            assert!(
+                matches!(detector.scan_str(document, &ScanOptions::default()), Err(ScanError::Malformed { .. })),
+                "{}",
+                document
+            );
+        }
+    }
+}
@@ -0,0 +1,311 @@
+// src/compliance/policy_files.rs
+//! Policy Files Module
+//!
+//! Data policies can be kept outside the code as versioned YAML or JSON
+//! files, one policy per file, so compliance teams can change them without a
+//! rebuild. [`PolicyRegistry::load_from_dir`] reads every `.yaml`, `.yml`
+//! and `.json` file in a directory on top of the standard policies; a file
+//! whose `name` matches a standard policy replaces it. Other files are
+//! ignored. A policy file looks like:
+//!
+//! ```yaml
+//! name: payroll_export        # defaults to the file name without extension
+//! version: 1.2.0              # semantic version
+//! description: Payroll export contexts
+//! required_fields: [action, employee_id, timestamp]
+//! optional_fields: [cost_center]
+//! forbidden_fields: [ssn, bank_account]
+//! ```
+//!
+//! Every policy is validated with [`DataPolicy::validate`], and loading
+//! fails on the first invalid file so a typo never silently drops a policy.
+//! [`PolicyDirectory`] keeps the loaded registry and reloads it when the
+//! files change, keeping the previous registry if the new files are invalid.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+use thiserror::Error;
+
+use super::data_policies::{DataPolicy, PolicyError, PolicyRegistry};
+
+/// Errors loading policy files
+#[derive(Error, Debug)]
+pub enum PolicyLoadError {
+    #[error("Failed to read {path}: {source}")]
+    Io { path: PathBuf, source: std::io::Error },
+
+    #[error("Failed to parse {path}: {reason}")]
+    Parse { path: PathBuf, reason: String },
+
+    #[error("Invalid policy in {path}: {source}")]
+    Invalid { path: PathBuf, source: PolicyError },
+
+    #[error("Policy '{name}' is defined in both {first} and {second}")]
+    DuplicateName { name: String, first: PathBuf, second: PathBuf },
+}
+
+/// A data policy as written in a policy file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyFile {
+    /// Name the policy is registered under
+    pub name: Option<String>,
+    /// Semantic version of the policy
+    pub version: String,
+    /// Human-readable description of the policy
+    #[serde(default)]
+    pub description: String,
+    /// Fields that must be present in the context
+    #[serde(default)]
+    pub required_fields: Vec<String>,
+    /// Fields that may be present in the context
+    #[serde(default)]
+    pub optional_fields: Vec<String>,
+    /// Fields that must never be present in the context
+    #[serde(default)]
+    pub forbidden_fields: Vec<String>,
+}
+
+impl PolicyFile {
+    /// Read and validate the policy file at `path`, returning its name and policy
+    pub fn load(path: &Path) -> Result<(String, DataPolicy), PolicyLoadError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| PolicyLoadError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let parse_error = |reason: String| PolicyLoadError::Parse {
+            path: path.to_path_buf(),
+            reason,
+        };
+        let file: PolicyFile = match extension(path) {
+            Some("json") => serde_json::from_str(&contents).map_err(|e| parse_error(e.to_string()))?,
+            _ => serde_yaml::from_str(&contents).map_err(|e| parse_error(e.to_string()))?,
+        };
+
+        let name = match file.name {
+            Some(name) => name,
+            None => path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| parse_error("file name is not valid UTF-8".to_string()))?
+                .to_string(),
+        };
+        let policy = DataPolicy::new(
+            file.required_fields,
+            file.optional_fields,
+            file.forbidden_fields,
+            file.description,
+            file.version,
+        );
+        policy.validate().map_err(|source| PolicyLoadError::Invalid {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok((name, policy))
+    }
+}
+
+impl PolicyRegistry {
+    /// Standard policies plus those in the policy files in `dir`
+    pub fn load_from_dir(dir: impl AsRef<Path>) -> Result<Self, PolicyLoadError> {
+        let mut registry = Self::new();
+        let mut sources: HashMap<String, PathBuf> = HashMap::new();
+        for path in policy_files(dir.as_ref())? {
+            let (name, policy) = PolicyFile::load(&path)?;
+            if let Some(first) = sources.get(&name) {
+                return Err(PolicyLoadError::DuplicateName {
+                    name,
+                    first: first.clone(),
+                    second: path,
+                });
+            }
+            sources.insert(name.clone(), path);
+            registry.register_policy(name, policy);
+        }
+        Ok(registry)
+    }
+}
+
+/// Policy files in a directory, as of one listing
+type Fingerprint = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+/// A policy registry loaded from a directory, reloaded when its files change
+pub struct PolicyDirectory {
+    dir: PathBuf,
+    registry: RwLock<Arc<PolicyRegistry>>,
+    fingerprint: Mutex<Fingerprint>,
+}
+
+impl PolicyDirectory {
+    /// Load the policies in `dir`
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, PolicyLoadError> {
+        let dir = dir.into();
+        let fingerprint = fingerprint(&dir)?;
+        let registry = PolicyRegistry::load_from_dir(&dir)?;
+        Ok(Self {
+            dir,
+            registry: RwLock::new(Arc::new(registry)),
+            fingerprint: Mutex::new(fingerprint),
+        })
+    }
+
+    /// Directory the policies are loaded from
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The most recently loaded registry
+    pub fn registry(&self) -> Arc<PolicyRegistry> {
+        self.registry.read().unwrap().clone()
+    }
+
+    /// Reload the policies, keeping the current ones if any file is invalid
+    pub fn reload(&self) -> Result<(), PolicyLoadError> {
+        let fingerprint = fingerprint(&self.dir)?;
+        let registry = PolicyRegistry::load_from_dir(&self.dir)?;
+        *self.registry.write().unwrap() = Arc::new(registry);
+        *self.fingerprint.lock().unwrap() = fingerprint;
+        Ok(())
+    }
+
+    /// Reload the policies if a file was added, removed or modified since the last load
+    ///
+    /// Returns whether the policies were reloaded. Callers poll this, or call
+    /// [`PolicyDirectory::reload`] on a signal, to pick up edited policies.
+    pub fn reload_if_changed(&self) -> Result<bool, PolicyLoadError> {
+        if fingerprint(&self.dir)? == *self.fingerprint.lock().unwrap() {
+            return Ok(false);
+        }
+        self.reload()?;
+        Ok(true)
+    }
+}
+
+fn extension(path: &Path) -> Option<&str> {
+    path.extension().and_then(|extension| extension.to_str())
+}
+
+/// Policy files in `dir`, sorted by path
+fn policy_files(dir: &Path) -> Result<Vec<PathBuf>, PolicyLoadError> {
+    let io_error = |source| PolicyLoadError::Io {
+        path: dir.to_path_buf(),
+        source,
+    };
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        if path.is_file() && matches!(extension(&path), Some("yaml" | "yml" | "json")) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn fingerprint(dir: &Path) -> Result<Fingerprint, PolicyLoadError> {
+    policy_files(dir)?
+        .into_iter()
+        .map(|path| {
+            let metadata = std::fs::metadata(&path).map_err(|source| PolicyLoadError::Io {
+                path: path.clone(),
+                source,
+            })?;
+            Ok((path, metadata.modified().ok(), metadata.len()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAYROLL: &str = "
+name: payroll_export
+version: 1.2.0
+description: Payroll export contexts
+required_fields: [action, employee_id]
+forbidden_fields: [ssn]
+";
+
+    #[test]
+    fn test_load_yaml_and_json_policies() {
+        // ARRANGE: A YAML policy, a JSON policy replacing a standard one, and an unrelated file
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("payroll.yaml"), PAYROLL).unwrap();
+        std::fs::write(
+            dir.path().join("login.json"),
+            r#"{ "version": "2.0.0", "required_fields": ["action"], "forbidden_fields": ["password"] }"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("README.md"), "not a policy").unwrap();
+
+        // ACT: Load the directory
+        let registry = PolicyRegistry::load_from_dir(dir.path()).unwrap();
+
+        // ASSERT: File policies are added or replace standard ones by name
+        let payroll = registry.get_policy("payroll_export").unwrap();
+        assert_eq!(payroll.version, "1.2.0");
+        assert!(payroll.is_field_required("employee_id"));
+        assert!(payroll.is_field_forbidden("ssn"));
+        let login = registry.get_policy("login").unwrap();
+        assert_eq!(login.version, "2.0.0");
+        assert!(!login.is_field_required("user_id"));
+        assert!(registry.get_policy("fintech_transfer").is_some());
+    }
+
+    #[test]
+    fn test_invalid_policy_files_are_rejected() {
+        let cases = [
+            ("bad_version.yaml", "version: one\n"),
+            ("conflict.yaml", "version: 1.0.0\nrequired_fields: [ssn]\nforbidden_fields: [ssn]\n"),
+            ("unknown_key.yaml", "version: 1.0.0\nforbidden: [ssn]\n"),
+            ("broken.json", "{ \"version\": "),
+        ];
+        for (file, contents) in cases {
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(dir.path().join(file), contents).unwrap();
+
+            let error = PolicyRegistry::load_from_dir(dir.path()).err().unwrap();
+
+            assert!(error.to_string().contains(file), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_duplicate_policy_names_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.yaml"), PAYROLL).unwrap();
+        std::fs::write(dir.path().join("b.yml"), PAYROLL).unwrap();
+
+        assert!(matches!(
+            PolicyRegistry::load_from_dir(dir.path()),
+            Err(PolicyLoadError::DuplicateName { name, .. }) if name == "payroll_export"
+        ));
+    }
+
+    #[test]
+    fn test_policy_directory_hot_reload() {
+        // ARRANGE: A directory with one policy
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("payroll.yaml");
+        std::fs::write(&path, PAYROLL).unwrap();
+        let policies = PolicyDirectory::open(dir.path()).unwrap();
+        assert!(!policies.reload_if_changed().unwrap());
+
+        // ACT: Add a policy, then break the file
+        std::fs::write(dir.path().join("export.yaml"), "version: 0.1.0\n").unwrap();
+        let reloaded = policies.reload_if_changed().unwrap();
+        std::fs::write(&path, "version: 1.0.0\nrequired_fields: [ssn]\nforbidden_fields: [ssn]\n").unwrap();
+        let broken = policies.reload_if_changed();
+
+        // ASSERT: The new policy is picked up; the invalid edit keeps the last good registry
+        assert!(reloaded);
+        assert!(broken.is_err());
+        let registry = policies.registry();
+        assert!(registry.get_policy("export").is_some());
+        assert_eq!(registry.get_policy("payroll_export").unwrap().version, "1.2.0");
+    }
+}
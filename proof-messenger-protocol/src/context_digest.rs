@@ -0,0 +1,194 @@
+//! Streaming BLAKE3 digests of large signed contexts
+//!
+//! Signing a large context directly means holding all of it in memory.
+//! [`ContextHasher`] instead takes the context in chunks of any size and
+//! finalizes to a 32-byte context digest, which is then signed with
+//! [`sign_context_digest`]. The digest uses BLAKE3's key derivation mode
+//! under a fixed context string, and the signature covers a domain-separated
+//! signing input, so neither can be confused with a plain BLAKE3 hash or a
+//! proof over a raw 32-byte context.
+//!
+//! The vectors in `tests/vectors/context_digest.json` pin the digest and
+//! signature for every implementation; the CLI and web crates check them too.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::context_digest::{context_digest, sign_context_digest, verify_context_digest, ContextHasher};
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//!
+//! let mut hasher = ContextHasher::new();
+//! hasher.update(b"approve ").update(b"transfer");
+//! let digest = hasher.finalize();
+//! assert_eq!(digest, context_digest(b"approve transfer"));
+//!
+//! let keypair = generate_secure_keypair();
+//! let signature = sign_context_digest(&keypair, &digest).unwrap();
+//! assert!(verify_context_digest(&keypair.public_key(), &digest, &signature).is_ok());
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature};
+use std::io::{Read, Write};
+
+use crate::key::SecureKeypair;
+use crate::proof::{make_secure_proof, verify_proof_result, ProofError};
+
+/// Length of a context digest in bytes
+pub const CONTEXT_DIGEST_LENGTH: usize = 32;
+
+/// BLAKE3 key derivation context for context digests
+const DIGEST_KEY_CONTEXT: &str = "proof-messenger 2024-06 context digest v1";
+
+/// Domain separation prefix for signatures over context digests
+const SIGNING_DOMAIN: &[u8] = b"proof-messenger/context-digest/v1";
+
+/// Incremental hasher producing a context digest
+#[derive(Clone)]
+pub struct ContextHasher {
+    hasher: blake3::Hasher,
+    length: u64,
+}
+
+impl ContextHasher {
+    /// Start hashing a new context
+    pub fn new() -> Self {
+        Self {
+            hasher: blake3::Hasher::new_derive_key(DIGEST_KEY_CONTEXT),
+            length: 0,
+        }
+    }
+
+    /// Feed the next chunk of the context
+    pub fn update(&mut self, chunk: &[u8]) -> &mut Self {
+        self.hasher.update(chunk);
+        self.length += chunk.len() as u64;
+        self
+    }
+
+    /// Feed everything `reader` yields, returning the number of bytes read
+    pub fn update_reader(&mut self, mut reader: impl Read) -> std::io::Result<u64> {
+        std::io::copy(&mut reader, self)
+    }
+
+    /// Number of context bytes hashed so far
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    /// Whether no context bytes have been hashed yet
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Digest of the context fed so far
+    pub fn finalize(&self) -> [u8; CONTEXT_DIGEST_LENGTH] {
+        *self.hasher.finalize().as_bytes()
+    }
+}
+
+impl Default for ContextHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for ContextHasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Digest a context held in memory
+pub fn context_digest(context: &[u8]) -> [u8; CONTEXT_DIGEST_LENGTH] {
+    ContextHasher::new().update(context).finalize()
+}
+
+/// Build the bytes signed for a context digest
+pub fn digest_signing_input(digest: &[u8; CONTEXT_DIGEST_LENGTH]) -> Vec<u8> {
+    let mut input = Vec::with_capacity(SIGNING_DOMAIN.len() + 1 + CONTEXT_DIGEST_LENGTH);
+    input.extend_from_slice(SIGNING_DOMAIN);
+    input.push(0);
+    input.extend_from_slice(digest);
+    input
+}
+
+/// Sign a context digest
+pub fn sign_context_digest(
+    keypair: &SecureKeypair,
+    digest: &[u8; CONTEXT_DIGEST_LENGTH],
+) -> Result<Signature, ProofError> {
+    make_secure_proof(keypair, &digest_signing_input(digest))
+}
+
+/// Verify a signature over a context digest
+pub fn verify_context_digest(
+    public_key: &PublicKey,
+    digest: &[u8; CONTEXT_DIGEST_LENGTH],
+    signature: &Signature,
+) -> Result<(), ProofError> {
+    verify_proof_result(public_key, &digest_signing_input(digest), signature)
+}
+
+/// Parse a context digest from a byte slice
+pub fn context_digest_from_slice(bytes: &[u8]) -> Result<[u8; CONTEXT_DIGEST_LENGTH], ProofError> {
+    bytes.try_into().map_err(|_| {
+        ProofError::InvalidData(format!(
+            "Context digest must be {} bytes (got {})",
+            CONTEXT_DIGEST_LENGTH,
+            bytes.len()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+
+    #[test]
+    fn chunking_does_not_change_the_digest() {
+        let context: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let expected = context_digest(&context);
+
+        for chunk_size in [1, 7, 1024, 4096, context.len()] {
+            let mut hasher = ContextHasher::new();
+            for chunk in context.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize(), expected, "chunk size {}", chunk_size);
+            assert_eq!(hasher.len(), context.len() as u64);
+        }
+
+        let mut hasher = ContextHasher::new();
+        assert_eq!(hasher.update_reader(&context[..]).unwrap(), context.len() as u64);
+        assert_eq!(hasher.finalize(), expected);
+    }
+
+    #[test]
+    fn digest_is_domain_separated_from_plain_blake3() {
+        assert_ne!(context_digest(b"context"), *blake3::hash(b"context").as_bytes());
+    }
+
+    #[test]
+    fn signature_is_bound_to_the_digest() {
+        let keypair = generate_secure_keypair_with_seed(3);
+        let digest = context_digest(b"approve transfer");
+        let signature = sign_context_digest(&keypair, &digest).unwrap();
+
+        assert!(verify_context_digest(&keypair.public_key(), &digest, &signature).is_ok());
+        assert!(verify_context_digest(&keypair.public_key(), &context_digest(b"approve transfer!"), &signature).is_err());
+        // A proof over the raw digest bytes is not a digest signature
+        let raw = make_secure_proof(&keypair, &digest).unwrap();
+        assert!(verify_context_digest(&keypair.public_key(), &digest, &raw).is_err());
+    }
+
+    #[test]
+    fn digest_length_is_checked() {
+        assert!(context_digest_from_slice(&[0u8; 31]).is_err());
+        assert!(context_digest_from_slice(&[0u8; 32]).is_ok());
+    }
+}
@@ -0,0 +1,325 @@
+//! Hierarchical deterministic keys per context
+//!
+//! A [`MasterSeed`] derives a separate Ed25519 signing key for every group,
+//! counterparty or purpose, so signatures made in one context cannot be
+//! linked to another by their key. Keys are derived with SLIP-0010 along a
+//! [`DerivationPath`]. [`DerivationPath::for_context`] maps a context label
+//! such as `group:ops` to a path under `m/44'/20557'/1'`. The identity key
+//! at [`MNEMONIC_DERIVATION_PATH`] is the one a backup phrase recovers.
+//!
+//! Ed25519 SLIP-0010 only has hardened children, so nobody can compute a
+//! child public key from the master public key. A [`DerivationAttestation`]
+//! links the two instead: it is signed by the master identity and by the
+//! child, so a verifier such as the relay can check that the master vouches
+//! for the child and that the child holds its key. The path is the master's
+//! claim; only the seed holder can recompute it. It hides the context label
+//! behind a hash, but a guessable label can be found by trying candidates.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::derivation::{DerivationPath, MasterSeed};
+//!
+//! let seed = MasterSeed::from_bytes(&[7u8; 32]).unwrap();
+//! let path = DerivationPath::for_context("group:ops");
+//! let (ops_key, attestation) = seed.attest(&path);
+//!
+//! assert!(attestation.verify().is_ok());
+//! assert_eq!(attestation.child_public_key, ops_key.public_key_bytes());
+//! assert_eq!(attestation.master_public_key, seed.identity().public_key_bytes());
+//! assert_ne!(seed.derive_for_context("group:finance").public_key_bytes(), ops_key.public_key_bytes());
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::errors;
+use crate::key::{derive_slip10_ed25519, mnemonic_seed, SecureKeypair, MNEMONIC_DERIVATION_PATH};
+use crate::proof::{make_secure_proof, verify_proof_result, ProofError};
+
+/// Path under which [`DerivationPath::for_context`] derives keys: `m/44'/20557'/1'`
+pub const CONTEXT_KEY_ROOT: [u32; 3] = [44, 20557, 1];
+
+/// Domain separation prefix for derivation attestations
+const DERIVATION_DOMAIN: &[u8] = b"proof-messenger/derivation/v1";
+
+/// Largest index below the hardened offset
+const MAX_INDEX: u32 = 0x7fff_ffff;
+
+/// SLIP-0010 seeds are 128 to 512 bits
+const SEED_LENGTHS: std::ops::RangeInclusive<usize> = 16..=64;
+
+/// A SLIP-0010 path of hardened indices, written `m/44'/20557'/1'/...`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// A path from its indices, each below 2^31 (hardening is implied)
+    pub fn new(indices: Vec<u32>) -> Result<Self, ProofError> {
+        if let Some(index) = indices.iter().find(|index| **index > MAX_INDEX) {
+            return Err(ProofError::InvalidInput(format!("Derivation index {} is out of range", index)));
+        }
+        Ok(Self(indices))
+    }
+
+    /// The path of the key for a context label, such as `group:ops` or `purpose:payments`
+    ///
+    /// Two indices taken from a SHA-256 of the label follow [`CONTEXT_KEY_ROOT`],
+    /// so distinct labels practically never share a key.
+    pub fn for_context(context: &str) -> Self {
+        let digest = Sha256::digest(context.as_bytes());
+        let index = |offset: usize| {
+            u32::from_be_bytes(digest[offset..offset + 4].try_into().expect("4 bytes")) & MAX_INDEX
+        };
+        let mut indices = CONTEXT_KEY_ROOT.to_vec();
+        indices.extend([index(0), index(4)]);
+        Self(indices)
+    }
+
+    /// The indices, without the hardened offset
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            write!(f, "/{}'", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for DerivationPath {
+    type Err = ProofError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut segments = value.split('/');
+        if segments.next() != Some("m") {
+            return Err(ProofError::InvalidInput(format!("Derivation path must start with m: {}", value)));
+        }
+        let indices = segments
+            .map(|segment| {
+                let index = segment
+                    .strip_suffix(['\'', 'h', 'H'])
+                    .ok_or_else(|| ProofError::InvalidInput(format!("Ed25519 derivation is hardened only: {}", segment)))?;
+                index
+                    .parse()
+                    .map_err(|_| ProofError::InvalidInput(format!("Invalid derivation index: {}", segment)))
+            })
+            .collect::<Result<Vec<u32>, _>>()?;
+        Self::new(indices)
+    }
+}
+
+impl TryFrom<String> for DerivationPath {
+    type Error = ProofError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<DerivationPath> for String {
+    fn from(path: DerivationPath) -> Self {
+        path.to_string()
+    }
+}
+
+/// Seed every context key and the identity key are derived from
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct MasterSeed {
+    seed: Vec<u8>,
+}
+
+impl MasterSeed {
+    /// Use raw seed bytes (16 to 64 bytes)
+    pub fn from_bytes(seed: &[u8]) -> Result<Self, ProofError> {
+        if !SEED_LENGTHS.contains(&seed.len()) {
+            return Err(ProofError::InvalidInput(format!(
+                "Master seed must be 16 to 64 bytes (got {})",
+                seed.len()
+            )));
+        }
+        Ok(Self { seed: seed.to_vec() })
+    }
+
+    /// The seed of a BIP-39 backup phrase, as made by [`crate::key::generate_mnemonic`]
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> errors::Result<Self> {
+        let seed = mnemonic_seed(phrase, passphrase)?;
+        Ok(Self { seed: seed.to_vec() })
+    }
+
+    /// The identity key, the same one [`SecureKeypair::from_mnemonic`] recovers
+    pub fn identity(&self) -> SecureKeypair {
+        SecureKeypair::from_secret(&derive_slip10_ed25519(&self.seed, &MNEMONIC_DERIVATION_PATH))
+    }
+
+    /// The key at `path`
+    pub fn derive(&self, path: &DerivationPath) -> SecureKeypair {
+        SecureKeypair::from_secret(&derive_slip10_ed25519(&self.seed, path.indices()))
+    }
+
+    /// The key for a context label, at [`DerivationPath::for_context`]
+    pub fn derive_for_context(&self, context: &str) -> SecureKeypair {
+        self.derive(&DerivationPath::for_context(context))
+    }
+
+    /// Derive the key at `path` together with an attestation linking it to the identity key
+    pub fn attest(&self, path: &DerivationPath) -> (SecureKeypair, DerivationAttestation) {
+        let child = self.derive(path);
+        let attestation = DerivationAttestation::new(&self.identity(), &child, path.clone());
+        (child, attestation)
+    }
+}
+
+/// A statement, signed by both keys, that a child key was derived from a master identity
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DerivationAttestation {
+    /// Ed25519 public key of the master identity
+    #[serde(with = "hex::serde")]
+    pub master_public_key: [u8; 32],
+    /// Ed25519 public key of the derived child
+    #[serde(with = "hex::serde")]
+    pub child_public_key: [u8; 32],
+    /// Path the child was derived along
+    pub path: DerivationPath,
+    /// Master's signature over [`DerivationAttestation::context`]
+    #[serde(with = "hex::serde")]
+    pub master_signature: Vec<u8>,
+    /// Child's signature over [`DerivationAttestation::context`]
+    #[serde(with = "hex::serde")]
+    pub child_signature: Vec<u8>,
+}
+
+impl DerivationAttestation {
+    /// Sign an attestation with the master identity and the child key
+    ///
+    /// Nothing checks that `child` is really at `path`; use
+    /// [`MasterSeed::attest`] to derive and attest in one step.
+    pub fn new(master: &SecureKeypair, child: &SecureKeypair, path: DerivationPath) -> Self {
+        let context = derivation_context(&master.public_key_bytes(), &child.public_key_bytes(), &path);
+        let sign = |keypair: &SecureKeypair| {
+            make_secure_proof(keypair, &context)
+                .expect("attestation context is never empty")
+                .to_bytes()
+                .to_vec()
+        };
+        Self {
+            master_public_key: master.public_key_bytes(),
+            child_public_key: child.public_key_bytes(),
+            master_signature: sign(master),
+            child_signature: sign(child),
+            path,
+        }
+    }
+
+    /// The context both keys signed
+    pub fn context(&self) -> Vec<u8> {
+        derivation_context(&self.master_public_key, &self.child_public_key, &self.path)
+    }
+
+    /// Check both signatures
+    pub fn verify(&self) -> Result<(), ProofError> {
+        if self.master_public_key == self.child_public_key {
+            return Err(ProofError::InvalidData("Child key is the master key".to_string()));
+        }
+        let context = self.context();
+        for (role, key, signature) in [
+            ("master", &self.master_public_key, &self.master_signature),
+            ("child", &self.child_public_key, &self.child_signature),
+        ] {
+            let public_key = PublicKey::from_bytes(key)
+                .map_err(|e| ProofError::InvalidData(format!("Invalid {} public key: {}", role, e)))?;
+            let signature = Signature::from_bytes(signature)
+                .map_err(|e| ProofError::InvalidData(format!("Invalid {} signature: {}", role, e)))?;
+            verify_proof_result(&public_key, &context, &signature)?;
+        }
+        Ok(())
+    }
+}
+
+/// The bytes a derivation attestation signs
+///
+/// Layout: the domain prefix, the master and child public keys, then the
+/// path in its `m/...'` text form.
+pub fn derivation_context(master_public_key: &[u8; 32], child_public_key: &[u8; 32], path: &DerivationPath) -> Vec<u8> {
+    let path = path.to_string();
+    let mut context = Vec::with_capacity(DERIVATION_DOMAIN.len() + 64 + path.len());
+    context.extend_from_slice(DERIVATION_DOMAIN);
+    context.extend_from_slice(master_public_key);
+    context.extend_from_slice(child_public_key);
+    context.extend_from_slice(path.as_bytes());
+    context
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn paths_roundtrip_through_text() {
+        let path: DerivationPath = "m/44'/20557'/1h/7H".parse().unwrap();
+
+        assert_eq!(path.indices(), [44, 20557, 1, 7]);
+        assert_eq!(path.to_string(), "m/44'/20557'/1'/7'");
+        assert_eq!(serde_json::to_value(&path).unwrap(), "m/44'/20557'/1'/7'");
+        assert_eq!("m".parse::<DerivationPath>().unwrap().indices(), [] as [u32; 0]);
+
+        // Unhardened and out-of-range indices are rejected
+        assert!("m/44'/0".parse::<DerivationPath>().is_err());
+        assert!("m/2147483648'".parse::<DerivationPath>().is_err());
+        assert!("44'/0'".parse::<DerivationPath>().is_err());
+        assert!(serde_json::from_str::<DerivationPath>(r#""m/x'""#).is_err());
+    }
+
+    #[test]
+    fn context_keys_are_deterministic_and_distinct() {
+        let seed = MasterSeed::from_mnemonic(PHRASE, "").unwrap();
+        let again = MasterSeed::from_mnemonic(PHRASE, "").unwrap();
+
+        let path = DerivationPath::for_context("group:ops");
+        assert_eq!(path.to_string(), "m/44'/20557'/1'/2084928751'/890271063'");
+        assert_eq!(path, DerivationPath::for_context("group:ops"));
+        assert_eq!(seed.derive(&path).to_bytes(), again.derive_for_context("group:ops").to_bytes());
+        assert_ne!(seed.derive_for_context("group:ops").to_bytes(), seed.derive_for_context("group:finance").to_bytes());
+        assert_ne!(seed.derive_for_context("group:ops").to_bytes(), seed.identity().to_bytes());
+
+        // The identity key is the one the backup phrase recovers
+        assert_eq!(seed.identity().to_bytes(), SecureKeypair::from_mnemonic(PHRASE, "").unwrap().to_bytes());
+        assert!(MasterSeed::from_bytes(&[0u8; 15]).is_err());
+    }
+
+    #[test]
+    fn attestations_verify_and_detect_tampering() {
+        let seed = MasterSeed::from_bytes(&[9u8; 32]).unwrap();
+        let (_, attestation) = seed.attest(&DerivationPath::for_context("purpose:payments"));
+        assert!(attestation.verify().is_ok());
+
+        let json = serde_json::to_value(&attestation).unwrap();
+        assert_eq!(json["path"], attestation.path.to_string());
+        let parsed: DerivationAttestation = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, attestation);
+
+        // Claiming another path breaks both signatures
+        let mut moved = attestation.clone();
+        moved.path = DerivationPath::for_context("purpose:admin");
+        assert!(moved.verify().is_err());
+
+        // A master cannot claim a key whose holder did not sign
+        let stranger = SecureKeypair::generate_with_seed(3);
+        let mut claimed = attestation.clone();
+        claimed.child_public_key = stranger.public_key_bytes();
+        assert!(claimed.verify().is_err());
+
+        let identity = seed.identity();
+        let self_attested = DerivationAttestation::new(&identity, &identity, attestation.path.clone());
+        assert!(matches!(self_attested.verify(), Err(ProofError::InvalidData(_))));
+    }
+}
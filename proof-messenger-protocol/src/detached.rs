@@ -0,0 +1,280 @@
+//! Detached proofs over external documents
+//!
+//! A detached proof shows a key approved a document without the document
+//! itself ever being sent: the signer signs [`detached_context`], which binds
+//! a SHA-256 or BLAKE3 digest of the document to its filename, size and
+//! content type. Anyone holding the document can recompute the digest and
+//! check the proof with [`DetachedProof::verify_document`].
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::detached::{digest_document, make_detached_proof, DigestAlgorithm, DocumentMetadata};
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//!
+//! let keypair = generate_secure_keypair();
+//! let document = b"%PDF-1.7 quarterly report";
+//! let (digest, size) = digest_document(DigestAlgorithm::Sha256, &document[..]).unwrap();
+//! let metadata = DocumentMetadata {
+//!     filename: "report.pdf".to_string(),
+//!     size,
+//!     content_type: "application/pdf".to_string(),
+//! };
+//! let proof = make_detached_proof(&keypair, DigestAlgorithm::Sha256, digest, metadata).unwrap();
+//!
+//! assert!(proof.verify_document(&document[..]).is_ok());
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+
+use crate::key::SecureKeypair;
+use crate::proof::{make_secure_proof, verify_proof_result, ProofError};
+
+/// Length of a document digest in bytes (SHA-256 and BLAKE3 alike)
+pub const DOCUMENT_DIGEST_LENGTH: usize = 32;
+
+/// Domain separation prefix for detached proofs
+const DETACHED_DOMAIN: &[u8] = b"proof-messenger/detached-proof/v1";
+
+/// Size of the buffer documents are hashed through
+const READ_BUFFER_LENGTH: usize = 64 * 1024;
+
+/// Hash function a document digest was computed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestAlgorithm {
+    /// SHA-256
+    Sha256,
+    /// BLAKE3 with a 32-byte output
+    Blake3,
+}
+
+impl std::fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DigestAlgorithm::Sha256 => write!(f, "sha256"),
+            DigestAlgorithm::Blake3 => write!(f, "blake3"),
+        }
+    }
+}
+
+impl std::str::FromStr for DigestAlgorithm {
+    type Err = ProofError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "sha256" | "sha-256" => Ok(DigestAlgorithm::Sha256),
+            "blake3" => Ok(DigestAlgorithm::Blake3),
+            other => Err(ProofError::InvalidInput(format!("Unknown digest algorithm: {}", other))),
+        }
+    }
+}
+
+/// Signed description of a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentMetadata {
+    /// Name of the document file
+    pub filename: String,
+    /// Document size in bytes
+    pub size: u64,
+    /// MIME type of the document
+    pub content_type: String,
+}
+
+/// A signature over a document digest and its metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetachedProof {
+    /// Hash function of `digest`
+    pub algorithm: DigestAlgorithm,
+    /// Digest of the document contents
+    #[serde(with = "hex::serde")]
+    pub digest: [u8; DOCUMENT_DIGEST_LENGTH],
+    /// Signed description of the document
+    pub metadata: DocumentMetadata,
+    /// Signer's Ed25519 public key
+    #[serde(with = "hex::serde")]
+    pub public_key: [u8; 32],
+    /// Ed25519 signature over [`DetachedProof::context`]
+    #[serde(with = "hex::serde")]
+    pub signature: Vec<u8>,
+}
+
+impl DetachedProof {
+    /// The context the signer signed
+    pub fn context(&self) -> Vec<u8> {
+        detached_context(self.algorithm, &self.digest, &self.metadata)
+    }
+
+    /// Check the signature over the digest and metadata
+    pub fn verify(&self) -> Result<(), ProofError> {
+        let public_key = PublicKey::from_bytes(&self.public_key)
+            .map_err(|e| ProofError::InvalidData(format!("Invalid public key: {}", e)))?;
+        let signature = Signature::from_bytes(&self.signature)
+            .map_err(|e| ProofError::InvalidData(format!("Invalid signature: {}", e)))?;
+        verify_proof_result(&public_key, &self.context(), &signature)
+    }
+
+    /// Check the proof covers `document` and is correctly signed
+    pub fn verify_document(&self, document: impl Read) -> Result<(), ProofError> {
+        let (digest, size) = digest_document(self.algorithm, document)
+            .map_err(|e| ProofError::InvalidInput(format!("Failed to read document: {}", e)))?;
+        if size != self.metadata.size {
+            return Err(ProofError::InvalidData(format!(
+                "Document is {} bytes, proof is for {} bytes",
+                size, self.metadata.size
+            )));
+        }
+        if digest != self.digest {
+            return Err(ProofError::InvalidData("Document digest does not match".to_string()));
+        }
+        self.verify()
+    }
+}
+
+/// Hash a document, returning its digest and size in bytes
+pub fn digest_document(algorithm: DigestAlgorithm, mut document: impl Read) -> std::io::Result<([u8; DOCUMENT_DIGEST_LENGTH], u64)> {
+    let mut buffer = vec![0u8; READ_BUFFER_LENGTH];
+    let mut size = 0u64;
+    let mut sha256 = Sha256::new();
+    let mut blake3 = blake3::Hasher::new();
+    loop {
+        let read = document.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        size += read as u64;
+        match algorithm {
+            DigestAlgorithm::Sha256 => sha256.update(&buffer[..read]),
+            DigestAlgorithm::Blake3 => {
+                blake3.update(&buffer[..read]);
+            }
+        }
+    }
+    let digest = match algorithm {
+        DigestAlgorithm::Sha256 => sha256.finalize().into(),
+        DigestAlgorithm::Blake3 => *blake3.finalize().as_bytes(),
+    };
+    Ok((digest, size))
+}
+
+/// Build the bytes a signer signs to approve a document
+///
+/// Variable-length fields are length prefixed so no two distinct documents
+/// share a context.
+pub fn detached_context(
+    algorithm: DigestAlgorithm,
+    digest: &[u8; DOCUMENT_DIGEST_LENGTH],
+    metadata: &DocumentMetadata,
+) -> Vec<u8> {
+    let mut context = Vec::with_capacity(
+        DETACHED_DOMAIN.len() + DOCUMENT_DIGEST_LENGTH + metadata.filename.len() + metadata.content_type.len() + 40,
+    );
+    context.extend_from_slice(DETACHED_DOMAIN);
+    context.push(0);
+    for field in [algorithm.to_string().as_bytes(), &digest[..], metadata.filename.as_bytes()] {
+        context.extend_from_slice(&(field.len() as u64).to_be_bytes());
+        context.extend_from_slice(field);
+    }
+    context.extend_from_slice(&metadata.size.to_be_bytes());
+    context.extend_from_slice(&(metadata.content_type.len() as u64).to_be_bytes());
+    context.extend_from_slice(metadata.content_type.as_bytes());
+    context
+}
+
+/// Sign a detached proof over a document digest
+pub fn make_detached_proof(
+    keypair: &SecureKeypair,
+    algorithm: DigestAlgorithm,
+    digest: [u8; DOCUMENT_DIGEST_LENGTH],
+    metadata: DocumentMetadata,
+) -> Result<DetachedProof, ProofError> {
+    if metadata.filename.is_empty() {
+        return Err(ProofError::InvalidInput("Filename cannot be empty".to_string()));
+    }
+
+    let signature = make_secure_proof(keypair, &detached_context(algorithm, &digest, &metadata))?;
+    Ok(DetachedProof {
+        algorithm,
+        digest,
+        metadata,
+        public_key: keypair.public_key_bytes(),
+        signature: signature.to_bytes().to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+
+    fn metadata(size: u64) -> DocumentMetadata {
+        DocumentMetadata {
+            filename: "contract.pdf".to_string(),
+            size,
+            content_type: "application/pdf".to_string(),
+        }
+    }
+
+    fn prove(algorithm: DigestAlgorithm, document: &[u8]) -> DetachedProof {
+        let (digest, size) = digest_document(algorithm, document).unwrap();
+        make_detached_proof(&generate_secure_keypair_with_seed(7), algorithm, digest, metadata(size)).unwrap()
+    }
+
+    #[test]
+    fn detached_proof_verifies_the_document() {
+        for algorithm in [DigestAlgorithm::Sha256, DigestAlgorithm::Blake3] {
+            let proof = prove(algorithm, b"signed contract");
+
+            assert!(proof.verify().is_ok());
+            assert!(proof.verify_document(&b"signed contract"[..]).is_ok());
+            assert!(proof.verify_document(&b"signed contract!"[..]).is_err());
+        }
+    }
+
+    #[test]
+    fn digests_match_the_reference_hashes() {
+        let document = vec![42u8; READ_BUFFER_LENGTH * 2 + 5];
+
+        let (sha256, size) = digest_document(DigestAlgorithm::Sha256, &document[..]).unwrap();
+        let (blake3, _) = digest_document(DigestAlgorithm::Blake3, &document[..]).unwrap();
+
+        assert_eq!(size, document.len() as u64);
+        assert_eq!(&sha256[..], &Sha256::digest(&document)[..]);
+        assert_eq!(blake3, *blake3::hash(&document).as_bytes());
+    }
+
+    #[test]
+    fn altered_metadata_breaks_the_signature() {
+        let mut proof = prove(DigestAlgorithm::Sha256, b"signed contract");
+
+        proof.metadata.filename = "other.pdf".to_string();
+
+        assert!(matches!(proof.verify(), Err(ProofError::VerificationFailed(_))));
+    }
+
+    #[test]
+    fn proof_roundtrips_through_json() {
+        let proof = prove(DigestAlgorithm::Blake3, b"signed contract");
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let parsed: DetachedProof = serde_json::from_str(&json).unwrap();
+
+        assert!(json.contains("\"algorithm\":\"blake3\""));
+        assert_eq!(parsed, proof);
+        assert!(parsed.verify().is_ok());
+    }
+
+    #[test]
+    fn empty_filename_is_rejected() {
+        let keypair = generate_secure_keypair_with_seed(7);
+        let mut metadata = metadata(0);
+        metadata.filename.clear();
+
+        assert!(matches!(
+            make_detached_proof(&keypair, DigestAlgorithm::Sha256, [0; DOCUMENT_DIGEST_LENGTH], metadata),
+            Err(ProofError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,402 @@
+//! Versioned proof envelopes with algorithm agility
+//!
+//! A legacy proof is a bare 64-byte Ed25519 signature, so a verifier has to
+//! guess how it was made. A [`ProofEnvelope`] states it: the envelope format
+//! version, the signing algorithm, the signer's public key, a hash of the
+//! signed context, optional metadata and the signature itself, serialized as
+//! one hex blob that fits wherever a raw signature did.
+//!
+//! The signature covers every other field of the envelope, so neither the
+//! algorithm nor the metadata can be altered without invalidating it.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::envelope::ProofEnvelope;
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//!
+//! let keypair = generate_secure_keypair();
+//! let envelope = ProofEnvelope::sign(&keypair, b"approve transfer", Default::default()).unwrap();
+//!
+//! let received = ProofEnvelope::from_hex(&envelope.to_hex()).unwrap();
+//! assert!(received.verify(b"approve transfer").is_ok());
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature, SignatureError, Verifier};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::key::SecureKeypair;
+use crate::proof::{make_secure_proof, ProofError};
+
+/// Envelope format version produced by this crate
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Length of the context hash in bytes (SHA-256)
+pub const CONTEXT_HASH_LENGTH: usize = 32;
+
+/// Leading bytes of every serialized envelope
+const MAGIC: &[u8; 4] = b"PMPE";
+
+/// Domain separation prefix for envelope signatures
+const ENVELOPE_DOMAIN: &[u8] = b"proof-messenger/proof-envelope/v1";
+
+/// Largest field or metadata count a serialized envelope can hold
+const MAX_FIELD_LENGTH: usize = u16::MAX as usize;
+
+/// Length of a legacy proof: a raw Ed25519 signature
+const LEGACY_PROOF_LENGTH: usize = 64;
+
+/// Signature algorithm of an enveloped proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofAlgorithm {
+    /// Ed25519 signature
+    Ed25519,
+    /// Ed25519 and ML-DSA-65 signatures, both required (see [`crate::hybrid`])
+    HybridEd25519MlDsa65,
+}
+
+impl ProofAlgorithm {
+    /// Identifier of the algorithm in serialized envelopes
+    pub fn id(self) -> u8 {
+        match self {
+            ProofAlgorithm::Ed25519 => 1,
+            ProofAlgorithm::HybridEd25519MlDsa65 => 2,
+        }
+    }
+
+    /// Algorithm with the given identifier
+    pub fn from_id(id: u8) -> Result<Self, ProofError> {
+        match id {
+            1 => Ok(ProofAlgorithm::Ed25519),
+            2 => Ok(ProofAlgorithm::HybridEd25519MlDsa65),
+            other => Err(ProofError::InvalidData(format!("Unknown proof algorithm id {}", other))),
+        }
+    }
+}
+
+impl std::fmt::Display for ProofAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProofAlgorithm::Ed25519 => write!(f, "ed25519"),
+            ProofAlgorithm::HybridEd25519MlDsa65 => write!(f, "ed25519+ml-dsa-65"),
+        }
+    }
+}
+
+/// A self-describing proof over a context
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofEnvelope {
+    /// Envelope format version
+    pub version: u8,
+    /// Algorithm that produced `signature`
+    pub algorithm: ProofAlgorithm,
+    /// Signer's public key, in the algorithm's encoding
+    pub public_key: Vec<u8>,
+    /// SHA-256 hash of the signed context
+    pub context_hash: [u8; CONTEXT_HASH_LENGTH],
+    /// Signed metadata, such as a key ID or SDK version
+    pub metadata: BTreeMap<String, String>,
+    /// Signature over [`ProofEnvelope::signing_input`]
+    pub signature: Vec<u8>,
+}
+
+impl ProofEnvelope {
+    /// Sign a context with an Ed25519 keypair
+    pub fn sign(
+        keypair: &SecureKeypair,
+        context: &[u8],
+        metadata: BTreeMap<String, String>,
+    ) -> Result<Self, ProofError> {
+        let mut envelope = Self::unsigned(
+            ProofAlgorithm::Ed25519,
+            keypair.public_key().to_bytes().to_vec(),
+            context,
+            metadata,
+        )?;
+        envelope.signature = make_secure_proof(keypair, &envelope.signing_input())?.to_bytes().to_vec();
+        Ok(envelope)
+    }
+
+    /// Sign a context with both halves of a hybrid keypair
+    #[cfg(feature = "pqc")]
+    pub fn sign_hybrid(
+        keypair: &crate::hybrid::HybridKeypair,
+        context: &[u8],
+        metadata: BTreeMap<String, String>,
+    ) -> Result<Self, ProofError> {
+        let mut envelope = Self::unsigned(
+            ProofAlgorithm::HybridEd25519MlDsa65,
+            keypair.public_key().to_bytes(),
+            context,
+            metadata,
+        )?;
+        envelope.signature = crate::hybrid::make_hybrid_proof(keypair, &envelope.signing_input())?.to_bytes();
+        Ok(envelope)
+    }
+
+    fn unsigned(
+        algorithm: ProofAlgorithm,
+        public_key: Vec<u8>,
+        context: &[u8],
+        metadata: BTreeMap<String, String>,
+    ) -> Result<Self, ProofError> {
+        let too_long = metadata.len() > MAX_FIELD_LENGTH
+            || metadata.iter().any(|(key, value)| key.len() > MAX_FIELD_LENGTH || value.len() > MAX_FIELD_LENGTH);
+        if too_long {
+            return Err(ProofError::InvalidInput(format!(
+                "Proof envelope metadata is limited to {} entries of {} bytes",
+                MAX_FIELD_LENGTH, MAX_FIELD_LENGTH
+            )));
+        }
+        Ok(Self {
+            version: ENVELOPE_VERSION,
+            algorithm,
+            public_key,
+            context_hash: Sha256::digest(context).into(),
+            metadata,
+            signature: Vec::new(),
+        })
+    }
+
+    /// Verify the envelope's signature over `context`
+    pub fn verify(&self, context: &[u8]) -> Result<(), ProofError> {
+        let context_hash: [u8; CONTEXT_HASH_LENGTH] = Sha256::digest(context).into();
+        if context_hash != self.context_hash {
+            return Err(ProofError::VerificationFailed(SignatureError::new()));
+        }
+
+        let signing_input = self.signing_input();
+        match self.algorithm {
+            ProofAlgorithm::Ed25519 => {
+                if self.public_key.len() != ed25519_dalek::PUBLIC_KEY_LENGTH {
+                    return Err(ProofError::InvalidData("Ed25519 public key must be 32 bytes".to_string()));
+                }
+                let signature = Signature::from_bytes(&self.signature)
+                    .map_err(|e| ProofError::InvalidData(format!("Invalid Ed25519 signature: {}", e)))?;
+                self.ed25519_public_key()?
+                    .verify(&signing_input, &signature)
+                    .map_err(ProofError::VerificationFailed)
+            }
+            #[cfg(feature = "pqc")]
+            ProofAlgorithm::HybridEd25519MlDsa65 => {
+                use crate::hybrid::{verify_hybrid_proof, HybridPolicy, HybridPublicKey, HybridSignature};
+
+                let public_key = HybridPublicKey::from_bytes(&self.public_key)?;
+                let signature = HybridSignature::from_bytes(&self.signature)?;
+                verify_hybrid_proof(&public_key, &signing_input, &signature, HybridPolicy::Strict)
+            }
+            #[cfg(not(feature = "pqc"))]
+            ProofAlgorithm::HybridEd25519MlDsa65 => Err(ProofError::InvalidInput(
+                "Verifying hybrid proof envelopes requires the pqc feature".to_string(),
+            )),
+        }
+    }
+
+    /// The signer's Ed25519 key (the whole key, or the classical half of a hybrid key)
+    pub fn ed25519_public_key(&self) -> Result<PublicKey, ProofError> {
+        let bytes = self
+            .public_key
+            .get(..ed25519_dalek::PUBLIC_KEY_LENGTH)
+            .ok_or_else(|| ProofError::InvalidData("Envelope public key is too short".to_string()))?;
+        PublicKey::from_bytes(bytes).map_err(|e| ProofError::InvalidData(format!("Invalid Ed25519 public key: {}", e)))
+    }
+
+    /// The signer's ML-DSA-65 key, when the envelope holds a hybrid proof
+    pub fn pqc_public_key(&self) -> Option<&[u8]> {
+        match self.algorithm {
+            ProofAlgorithm::HybridEd25519MlDsa65 => self.public_key.get(ed25519_dalek::PUBLIC_KEY_LENGTH..),
+            ProofAlgorithm::Ed25519 => None,
+        }
+    }
+
+    /// The bytes covered by the signature: every field except the signature itself
+    pub fn signing_input(&self) -> Vec<u8> {
+        let mut input = ENVELOPE_DOMAIN.to_vec();
+        self.write_signed_fields(&mut input);
+        input
+    }
+
+    /// Serialize the envelope
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_signed_fields(&mut bytes);
+        write_field(&mut bytes, &self.signature);
+        bytes
+    }
+
+    /// Serialize the envelope as a hex blob
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_bytes())
+    }
+
+    /// Whether a proof is an envelope rather than a legacy raw signature
+    pub fn is_envelope(proof: &[u8]) -> bool {
+        proof.len() != LEGACY_PROOF_LENGTH && proof.starts_with(MAGIC)
+    }
+
+    /// Parse a serialized envelope
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProofError> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(ProofError::InvalidData("Not a proof envelope".to_string()));
+        }
+        let version = reader.byte()?;
+        if version != ENVELOPE_VERSION {
+            return Err(ProofError::InvalidData(format!("Unsupported proof envelope version {}", version)));
+        }
+        let algorithm = ProofAlgorithm::from_id(reader.byte()?)?;
+        let public_key = reader.field()?.to_vec();
+        let context_hash = reader.take(CONTEXT_HASH_LENGTH)?.try_into().expect("length checked");
+
+        let mut metadata = BTreeMap::new();
+        for _ in 0..reader.length()? {
+            let key = reader.string()?;
+            let value = reader.string()?;
+            if metadata.insert(key, value).is_some() {
+                return Err(ProofError::InvalidData("Duplicate proof envelope metadata key".to_string()));
+            }
+        }
+        let signature = reader.field()?.to_vec();
+        if !reader.0.is_empty() {
+            return Err(ProofError::InvalidData("Trailing bytes after proof envelope".to_string()));
+        }
+
+        Ok(Self {
+            version,
+            algorithm,
+            public_key,
+            context_hash,
+            metadata,
+            signature,
+        })
+    }
+
+    /// Parse a hex encoded envelope
+    pub fn from_hex(hex_blob: &str) -> Result<Self, ProofError> {
+        let bytes = hex::decode(hex_blob)
+            .map_err(|e| ProofError::InvalidData(format!("Invalid proof envelope hex: {}", e)))?;
+        Self::from_bytes(&bytes)
+    }
+
+    fn write_signed_fields(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(MAGIC);
+        out.push(self.version);
+        out.push(self.algorithm.id());
+        write_field(out, &self.public_key);
+        out.extend_from_slice(&self.context_hash);
+        out.extend_from_slice(&(self.metadata.len() as u16).to_be_bytes());
+        for (key, value) in &self.metadata {
+            write_field(out, key.as_bytes());
+            write_field(out, value.as_bytes());
+        }
+    }
+}
+
+/// Write a field prefixed with its 16-bit big-endian length
+fn write_field(out: &mut Vec<u8>, field: &[u8]) {
+    out.extend_from_slice(&(field.len() as u16).to_be_bytes());
+    out.extend_from_slice(field);
+}
+
+/// Cursor over a serialized envelope
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], ProofError> {
+        if self.0.len() < n {
+            return Err(ProofError::InvalidData("Truncated proof envelope".to_string()));
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8, ProofError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn length(&mut self) -> Result<usize, ProofError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+    }
+
+    fn field(&mut self) -> Result<&'a [u8], ProofError> {
+        let len = self.length()?;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String, ProofError> {
+        String::from_utf8(self.field()?.to_vec())
+            .map_err(|_| ProofError::InvalidData("Proof envelope metadata is not UTF-8".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+
+    fn metadata() -> BTreeMap<String, String> {
+        [("key_id".to_string(), "device-7".to_string())].into_iter().collect()
+    }
+
+    #[test]
+    fn envelope_roundtrip_verifies() {
+        let keypair = generate_secure_keypair_with_seed(5);
+        let envelope = ProofEnvelope::sign(&keypair, b"context", metadata()).unwrap();
+
+        let parsed = ProofEnvelope::from_hex(&envelope.to_hex()).unwrap();
+
+        assert_eq!(parsed, envelope);
+        assert_eq!(parsed.algorithm, ProofAlgorithm::Ed25519);
+        assert_eq!(parsed.ed25519_public_key().unwrap(), keypair.public_key());
+        assert!(parsed.verify(b"context").is_ok());
+        assert!(ProofEnvelope::is_envelope(&envelope.to_bytes()));
+    }
+
+    #[test]
+    fn signature_covers_context_metadata_and_algorithm() {
+        let keypair = generate_secure_keypair_with_seed(5);
+        let envelope = ProofEnvelope::sign(&keypair, b"context", metadata()).unwrap();
+
+        let mut relabeled = envelope.clone();
+        relabeled.metadata.insert("key_id".to_string(), "device-8".to_string());
+
+        assert!(matches!(envelope.verify(b"other"), Err(ProofError::VerificationFailed(_))));
+        assert!(matches!(relabeled.verify(b"context"), Err(ProofError::VerificationFailed(_))));
+    }
+
+    #[test]
+    fn malformed_envelopes_are_rejected() {
+        let keypair = generate_secure_keypair_with_seed(5);
+        let bytes = ProofEnvelope::sign(&keypair, b"context", metadata()).unwrap().to_bytes();
+
+        let mut future_version = bytes.clone();
+        future_version[4] = 9;
+        let mut unknown_algorithm = bytes.clone();
+        unknown_algorithm[5] = 77;
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+
+        assert!(ProofEnvelope::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(ProofEnvelope::from_bytes(&future_version).is_err());
+        assert!(ProofEnvelope::from_bytes(&unknown_algorithm).is_err());
+        assert!(ProofEnvelope::from_bytes(&trailing).is_err());
+        assert!(!ProofEnvelope::is_envelope(&[b'P', b'M', b'P', b'E'].repeat(16)));
+    }
+
+    #[cfg(feature = "pqc")]
+    #[test]
+    fn hybrid_envelope_requires_both_signatures() {
+        let keypair = crate::hybrid::HybridKeypair::generate_with_seed(5);
+        let envelope = ProofEnvelope::sign_hybrid(&keypair, b"context", BTreeMap::new()).unwrap();
+
+        let mut tampered = ProofEnvelope::from_bytes(&envelope.to_bytes()).unwrap();
+        let last = tampered.signature.len() - 1;
+        tampered.signature[last] ^= 1;
+
+        assert_eq!(envelope.algorithm, ProofAlgorithm::HybridEd25519MlDsa65);
+        assert!(envelope.verify(b"context").is_ok());
+        assert!(tampered.verify(b"context").is_err());
+    }
+}
@@ -76,6 +76,21 @@ pub enum ProtocolError {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
     
+    /// Key derivation cost above the accepted limit
+    ///
+    /// This error occurs when key file parameters ask for more memory,
+    /// passes or lanes than the loader allows, which bounds the work an
+    /// untrusted file can cause.
+    #[error("KDF {parameter} {value} exceeds the limit of {limit}")]
+    KdfLimitExceeded {
+        /// The parameter over its limit (`memory_kib`, `iterations` or `parallelism`)
+        parameter: &'static str,
+        /// The requested value
+        value: u32,
+        /// The largest accepted value
+        limit: u32,
+    },
+    
     /// Generic protocol error
     ///
     /// This is a catch-all error for protocol-related issues that
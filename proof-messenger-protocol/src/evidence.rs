@@ -0,0 +1,368 @@
+//! Evidence bundles: everything an auditor needs to check a relayed message offline
+//!
+//! An [`EvidenceBundle`] carries a message exactly as the relay stored it,
+//! the sender's proof, whether the proof was revoked when the bundle was
+//! exported, and an inclusion proof against a signed transparency log tree
+//! head (see [`crate::transparency`]). The relay signs the whole bundle with
+//! its log key, so [`verify_evidence_bundle`] needs nothing but the bundle
+//! and, ideally, the relay's public key obtained out of band.
+//!
+//! The relay signs [`EvidenceBundle::context`]: a domain prefix followed by
+//! the canonical JSON (see [`crate::canonical`]) of every field except
+//! `signature`.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::evidence::*;
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//! use proof_messenger_protocol::proof::make_secure_proof;
+//! use proof_messenger_protocol::receipt::message_hash;
+//! use proof_messenger_protocol::transparency::{leaf_hash, sign_tree_head};
+//!
+//! let (relay, sender) = (generate_secure_keypair(), generate_secure_keypair());
+//! let message = EvidenceMessage {
+//!     id: "message-1".to_string(),
+//!     sender: hex::encode(sender.public_key_bytes()),
+//!     context: hex::encode("hello"),
+//!     body: "hello".to_string(),
+//!     proof: hex::encode(make_secure_proof(&sender, b"hello").unwrap().to_bytes()),
+//!     message_hash: None,
+//! };
+//! // A log holding just this message: its leaf is the root
+//! let hash = message_hash(message.sender.as_bytes(), message.context.as_bytes(), message.body.as_bytes());
+//! let head = sign_tree_head(&relay, 1, 1_700_000_000_000, &leaf_hash(&message.id, &hash, message.proof.as_bytes())).unwrap();
+//!
+//! let mut bundle = EvidenceBundle {
+//!     version: EVIDENCE_BUNDLE_VERSION,
+//!     exported_at: 1_700_000_000_000,
+//!     message,
+//!     revocation: RevocationEvidence { revoked: false, revoked_at: None, reason: None },
+//!     inclusion: InclusionEvidence {
+//!         leaf_index: 0,
+//!         tree_size: 1,
+//!         root_hash: hex::encode(head.root_hash),
+//!         audit_path: Vec::new(),
+//!         tree_head_timestamp: head.timestamp,
+//!         tree_head_signature: hex::encode(head.signature.to_bytes()),
+//!     },
+//!     relay_public_key: String::new(),
+//!     signature: String::new(),
+//! };
+//! sign_evidence_bundle(&relay, &mut bundle).unwrap();
+//!
+//! assert!(verify_evidence_bundle(&bundle, Some(&relay.public_key())).is_ok());
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use crate::canonical::canonical_context;
+use crate::envelope::ProofEnvelope;
+use crate::key::SecureKeypair;
+use crate::proof::{make_secure_proof, verify_proof_result, ProofError};
+use crate::receipt::{message_hash, message_hash_from_slice};
+use crate::transparency::{leaf_hash, tree_hash_from_slice, verify_inclusion, verify_tree_head, SignedTreeHead};
+
+/// Version of the evidence bundle format
+pub const EVIDENCE_BUNDLE_VERSION: u32 = 1;
+
+/// Domain separation prefix for evidence bundle signatures
+const EVIDENCE_DOMAIN: &[u8] = b"proof-messenger/evidence-bundle/v1";
+
+/// A relayed message exactly as the relay stored it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvidenceMessage {
+    /// Relay-assigned message ID
+    pub id: String,
+    /// Public key of the sender (hex encoded)
+    pub sender: String,
+    /// Context the sender signed (hex encoded)
+    pub context: String,
+    /// Message body; empty once the message is deleted
+    pub body: String,
+    /// Sender's proof over the context (hex encoded)
+    pub proof: String,
+    /// Hash of the deleted sender, context and body (hex encoded), which the
+    /// log leaf commits to in place of the body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_hash: Option<String>,
+}
+
+/// Whether the message's proof was revoked when the bundle was exported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationEvidence {
+    /// Whether the proof was revoked
+    pub revoked: bool,
+    /// When the proof was revoked, in milliseconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<i64>,
+    /// Reason given for the revocation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Inclusion of the message's leaf in a signed transparency log tree head
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionEvidence {
+    /// Position of the message's leaf in the log
+    pub leaf_index: u64,
+    /// Size of the tree the proof is for
+    pub tree_size: u64,
+    /// Merkle root of the first `tree_size` leaves (hex encoded)
+    pub root_hash: String,
+    /// Sibling hashes from the leaf up to the root (hex encoded)
+    pub audit_path: Vec<String>,
+    /// When the tree head was signed, in milliseconds since the Unix epoch
+    pub tree_head_timestamp: i64,
+    /// Relay's signature over the tree head (hex encoded)
+    pub tree_head_signature: String,
+}
+
+/// A self-contained, relay-signed bundle of evidence for one message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvidenceBundle {
+    /// Bundle format version ([`EVIDENCE_BUNDLE_VERSION`])
+    pub version: u32,
+    /// When the bundle was exported, in milliseconds since the Unix epoch
+    pub exported_at: i64,
+    /// The message and its proof
+    pub message: EvidenceMessage,
+    /// Revocation status of the proof at export time
+    pub revocation: RevocationEvidence,
+    /// Inclusion proof for the message in the transparency log
+    pub inclusion: InclusionEvidence,
+    /// Relay's log key, which signs tree heads and bundles (hex encoded)
+    pub relay_public_key: String,
+    /// Relay's signature over [`EvidenceBundle::context`] (hex encoded)
+    #[serde(default)]
+    pub signature: String,
+}
+
+impl EvidenceBundle {
+    /// The bytes the relay signs: every field except `signature`
+    pub fn context(&self) -> Result<Vec<u8>, ProofError> {
+        let mut value = serde_json::to_value(self)
+            .map_err(|e| ProofError::InvalidData(format!("Bundle is not representable as JSON: {}", e)))?;
+        if let Some(fields) = value.as_object_mut() {
+            fields.remove("signature");
+        }
+        let canonical = canonical_context(&value).map_err(|e| ProofError::InvalidData(e.to_string()))?;
+
+        let mut context = Vec::with_capacity(EVIDENCE_DOMAIN.len() + 1 + canonical.len());
+        context.extend_from_slice(EVIDENCE_DOMAIN);
+        context.push(0);
+        context.extend_from_slice(&canonical);
+        Ok(context)
+    }
+}
+
+/// Sign a bundle as the relay, setting its `relay_public_key` and `signature`
+pub fn sign_evidence_bundle(keypair: &SecureKeypair, bundle: &mut EvidenceBundle) -> Result<(), ProofError> {
+    bundle.relay_public_key = hex::encode(keypair.public_key().to_bytes());
+    let signature = make_secure_proof(keypair, &bundle.context()?)?;
+    bundle.signature = hex::encode(signature.to_bytes());
+    Ok(())
+}
+
+/// Decode a hex field of a bundle
+fn decode(field: &str, value: &str) -> Result<Vec<u8>, ProofError> {
+    hex::decode(value).map_err(|e| ProofError::InvalidData(format!("Invalid hex in {}: {}", field, e)))
+}
+
+/// Parse a hex encoded Ed25519 public key
+fn public_key(field: &str, value: &str) -> Result<PublicKey, ProofError> {
+    PublicKey::from_bytes(&decode(field, value)?)
+        .map_err(|e| ProofError::InvalidData(format!("Invalid public key in {}: {}", field, e)))
+}
+
+/// Parse a hex encoded Ed25519 signature
+fn signature(field: &str, value: &str) -> Result<Signature, ProofError> {
+    Signature::from_bytes(&decode(field, value)?)
+        .map_err(|e| ProofError::InvalidData(format!("Invalid signature in {}: {}", field, e)))
+}
+
+/// Verify the sender's proof over the message context
+///
+/// Proofs are either proof envelopes or legacy raw Ed25519 signatures.
+fn verify_sender_proof(message: &EvidenceMessage) -> Result<(), ProofError> {
+    let sender = public_key("message.sender", &message.sender)?;
+    let context = decode("message.context", &message.context)?;
+    let proof = decode("message.proof", &message.proof)?;
+
+    if ProofEnvelope::is_envelope(&proof) {
+        let envelope = ProofEnvelope::from_bytes(&proof)?;
+        if envelope.ed25519_public_key()? != sender {
+            return Err(ProofError::InvalidData("Proof envelope was not signed by the sender".to_string()));
+        }
+        return envelope.verify(&context);
+    }
+    verify_proof_result(&sender, &context, &signature("message.proof", &message.proof)?)
+}
+
+/// Verify an evidence bundle entirely offline
+///
+/// Checks the relay's signature over the bundle, the sender's proof, the
+/// message's inclusion in the log and the relay's signature on the tree head.
+/// With `relay_key`, the bundle must also be signed by that key; without it
+/// the bundle's own `relay_public_key` is trusted.
+///
+/// A revoked proof still verifies: the bundle proves the relay accepted the
+/// message, and `revocation` records that the proof was later withdrawn.
+pub fn verify_evidence_bundle(bundle: &EvidenceBundle, relay_key: Option<&PublicKey>) -> Result<(), ProofError> {
+    if bundle.version != EVIDENCE_BUNDLE_VERSION {
+        return Err(ProofError::InvalidInput(format!(
+            "Unsupported evidence bundle version {}",
+            bundle.version
+        )));
+    }
+    let relay = public_key("relay_public_key", &bundle.relay_public_key)?;
+    if relay_key.is_some_and(|expected| *expected != relay) {
+        return Err(ProofError::InvalidData("Bundle is signed by a different relay key".to_string()));
+    }
+    verify_proof_result(&relay, &bundle.context()?, &signature("signature", &bundle.signature)?)?;
+
+    verify_sender_proof(&bundle.message)?;
+
+    // The leaf commits to the hex text of the sender, context and body
+    let message = &bundle.message;
+    let contents_hash = match &message.message_hash {
+        Some(hash) => message_hash_from_slice(&decode("message.message_hash", hash)?)?,
+        None => message_hash(message.sender.as_bytes(), message.context.as_bytes(), message.body.as_bytes()),
+    };
+    let leaf = leaf_hash(&message.id, &contents_hash, message.proof.as_bytes());
+
+    let inclusion = &bundle.inclusion;
+    let root_hash = tree_hash_from_slice(&decode("inclusion.root_hash", &inclusion.root_hash)?)?;
+    let audit_path = inclusion
+        .audit_path
+        .iter()
+        .map(|node| tree_hash_from_slice(&decode("inclusion.audit_path", node)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    verify_inclusion(&leaf, inclusion.leaf_index, inclusion.tree_size, &audit_path, &root_hash)?;
+
+    let tree_head = SignedTreeHead {
+        tree_size: inclusion.tree_size,
+        timestamp: inclusion.tree_head_timestamp,
+        root_hash,
+        signature: signature("inclusion.tree_head_signature", &inclusion.tree_head_signature)?,
+    };
+    verify_tree_head(&tree_head, &relay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+    use crate::transparency::{inclusion_proof, root_hash, sign_tree_head};
+
+    /// A signed bundle for the third of five logged messages
+    fn bundle(relay: &SecureKeypair) -> EvidenceBundle {
+        let sender = generate_secure_keypair_with_seed(1);
+        let messages: Vec<EvidenceMessage> = (0..5)
+            .map(|i| {
+                let context = format!("context {}", i);
+                EvidenceMessage {
+                    id: format!("message-{}", i),
+                    sender: hex::encode(sender.public_key_bytes()),
+                    context: hex::encode(&context),
+                    body: format!("body {}", i),
+                    proof: hex::encode(make_secure_proof(&sender, context.as_bytes()).unwrap().to_bytes()),
+                    message_hash: None,
+                }
+            })
+            .collect();
+        let leaves: Vec<_> = messages
+            .iter()
+            .map(|m| {
+                let hash = message_hash(m.sender.as_bytes(), m.context.as_bytes(), m.body.as_bytes());
+                leaf_hash(&m.id, &hash, m.proof.as_bytes())
+            })
+            .collect();
+        let head = sign_tree_head(relay, 5, 1_700_000_000_000, &root_hash(&leaves)).unwrap();
+
+        let mut bundle = EvidenceBundle {
+            version: EVIDENCE_BUNDLE_VERSION,
+            exported_at: 1_700_000_000_500,
+            message: messages[2].clone(),
+            revocation: RevocationEvidence { revoked: false, revoked_at: None, reason: None },
+            inclusion: InclusionEvidence {
+                leaf_index: 2,
+                tree_size: 5,
+                root_hash: hex::encode(head.root_hash),
+                audit_path: inclusion_proof(&leaves, 2).unwrap().iter().map(hex::encode).collect(),
+                tree_head_timestamp: head.timestamp,
+                tree_head_signature: hex::encode(head.signature.to_bytes()),
+            },
+            relay_public_key: String::new(),
+            signature: String::new(),
+        };
+        sign_evidence_bundle(relay, &mut bundle).unwrap();
+        bundle
+    }
+
+    #[test]
+    fn signed_bundle_verifies_after_a_json_roundtrip() {
+        let relay = generate_secure_keypair_with_seed(7);
+        let bundle = bundle(&relay);
+
+        let parsed: EvidenceBundle = serde_json::from_str(&serde_json::to_string_pretty(&bundle).unwrap()).unwrap();
+
+        assert!(verify_evidence_bundle(&parsed, Some(&relay.public_key())).is_ok());
+        assert!(verify_evidence_bundle(&parsed, None).is_ok());
+    }
+
+    #[test]
+    fn tampering_with_any_part_is_detected() {
+        let relay = generate_secure_keypair_with_seed(7);
+        let original = bundle(&relay);
+
+        let mut body = original.clone();
+        body.message.body = "body 3".to_string();
+        let mut revocation = original.clone();
+        revocation.revocation.revoked = true;
+        let mut path = original.clone();
+        path.inclusion.audit_path.pop();
+
+        for tampered in [body, revocation, path] {
+            assert!(verify_evidence_bundle(&tampered, None).is_err());
+        }
+    }
+
+    #[test]
+    fn tampered_message_resigned_by_relay_fails_inclusion() {
+        let relay = generate_secure_keypair_with_seed(7);
+        let mut bundle = bundle(&relay);
+
+        bundle.message.body = "rewritten".to_string();
+        sign_evidence_bundle(&relay, &mut bundle).unwrap();
+
+        assert!(verify_evidence_bundle(&bundle, Some(&relay.public_key())).is_err());
+    }
+
+    #[test]
+    fn bundle_from_another_relay_is_rejected() {
+        let relay = generate_secure_keypair_with_seed(7);
+        let other = generate_secure_keypair_with_seed(8);
+
+        let bundle = bundle(&other);
+
+        assert!(verify_evidence_bundle(&bundle, Some(&relay.public_key())).is_err());
+    }
+
+    #[test]
+    fn deleted_message_verifies_through_its_hash() {
+        let relay = generate_secure_keypair_with_seed(7);
+        let mut bundle = bundle(&relay);
+        let message = &mut bundle.message;
+
+        message.message_hash = Some(hex::encode(message_hash(
+            message.sender.as_bytes(),
+            message.context.as_bytes(),
+            message.body.as_bytes(),
+        )));
+        message.body = String::new();
+        sign_evidence_bundle(&relay, &mut bundle).unwrap();
+
+        assert!(verify_evidence_bundle(&bundle, Some(&relay.public_key())).is_ok());
+    }
+}
@@ -0,0 +1,429 @@
+//! Group encryption with a shared sender key
+//!
+//! Each group has one symmetric [`GroupKey`] that every member uses to
+//! encrypt and decrypt messages, so a message is encrypted once no matter
+//! how many members receive it. The key is distributed by wrapping it for
+//! each member's X25519 public key ([`WrappedGroupKey`]): an ephemeral
+//! X25519 exchange feeds HKDF-SHA256, and the derived key seals the group
+//! key with AES-256-GCM.
+//!
+//! [`GroupSession`] is kept by whoever manages membership. Adding or
+//! removing a member rotates the group key to a new epoch and rewraps it for
+//! the current members only, so removed members cannot read new messages
+//! and new members cannot read old ones.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::group::{GroupKey, GroupSession, MemberSecret};
+//!
+//! let alice = MemberSecret::generate();
+//! let bob = MemberSecret::generate();
+//! let (mut session, wrapped) = GroupSession::new("engineering", &[alice.public_key()]).unwrap();
+//!
+//! let alice_key = GroupKey::unwrap(&wrapped[0], &alice).unwrap();
+//! let ciphertext = alice_key.encrypt(b"hello team").unwrap();
+//! assert_eq!(session.current_key().decrypt(&ciphertext).unwrap(), b"hello team");
+//!
+//! // Bob joins: the key rotates and old ciphertexts stay on the old epoch
+//! let wrapped = session.add_member(bob.public_key()).unwrap();
+//! let bob_wrapped = wrapped.iter().find(|w| w.recipient == bob.public_key()).unwrap();
+//! let bob_key = GroupKey::unwrap(bob_wrapped, &bob).unwrap();
+//! assert_eq!(bob_key.epoch, 1);
+//! assert!(bob_key.decrypt(&ciphertext).is_err());
+//! ```
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeSet;
+use thiserror::Error;
+use x25519_dalek::{PublicKey, SharedSecret, StaticSecret};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Length of X25519 keys and of the group key in bytes
+pub const GROUP_KEY_LENGTH: usize = 32;
+
+/// Length of AES-GCM nonces in bytes
+pub const GROUP_NONCE_LENGTH: usize = 12;
+
+/// HKDF info string for key wrapping
+const WRAP_DOMAIN: &[u8] = b"proof-messenger/group-key-wrap/v1";
+
+/// Domain separation prefix for group message associated data
+const MESSAGE_DOMAIN: &[u8] = b"proof-messenger/group-message/v1";
+
+/// Errors raised by group encryption
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum GroupError {
+    /// Data belongs to a different group than the key
+    #[error("Expected group {expected}, got {actual}")]
+    WrongGroup { expected: String, actual: String },
+
+    /// Data was produced under a different key epoch
+    #[error("Expected key epoch {expected}, got {actual}")]
+    EpochMismatch { expected: u64, actual: u64 },
+
+    /// A wrapped key was addressed to someone else
+    #[error("Wrapped key is addressed to a different member")]
+    NotRecipient,
+
+    /// Membership change names a key that is already, or not, a member
+    #[error("Invalid membership change: {0}")]
+    Membership(String),
+
+    /// A member public key is not usable for key agreement
+    #[error("Invalid member public key")]
+    InvalidPublicKey,
+
+    /// Authentication failed: wrong key or tampered data
+    #[error("Decryption failed")]
+    DecryptionFailed,
+}
+
+/// A member's X25519 secret key for receiving group keys
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct MemberSecret {
+    secret: [u8; GROUP_KEY_LENGTH],
+}
+
+impl MemberSecret {
+    /// Generate a new member key using cryptographically secure randomness
+    pub fn generate() -> Self {
+        Self { secret: random_bytes() }
+    }
+
+    /// Restore a member key from its 32 secret bytes
+    pub fn from_bytes(secret: [u8; GROUP_KEY_LENGTH]) -> Self {
+        Self { secret }
+    }
+
+    /// The 32 secret bytes
+    ///
+    /// ⚠️  WARNING: The returned bytes contain sensitive key material.
+    pub fn to_bytes(&self) -> [u8; GROUP_KEY_LENGTH] {
+        self.secret
+    }
+
+    /// Public key that group keys are wrapped for
+    pub fn public_key(&self) -> [u8; GROUP_KEY_LENGTH] {
+        PublicKey::from(&StaticSecret::from(self.secret)).to_bytes()
+    }
+}
+
+/// The symmetric key of one group epoch
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupKey {
+    /// Group the key belongs to
+    pub group_id: String,
+    /// Rotation counter, incremented on every membership change
+    pub epoch: u64,
+    #[serde(with = "hex::serde")]
+    key: [u8; GROUP_KEY_LENGTH],
+}
+
+/// A group message encrypted under a [`GroupKey`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupCiphertext {
+    pub group_id: String,
+    pub epoch: u64,
+    #[serde(with = "hex::serde")]
+    pub nonce: [u8; GROUP_NONCE_LENGTH],
+    /// AES-256-GCM ciphertext and tag
+    #[serde(with = "hex::serde")]
+    pub ciphertext: Vec<u8>,
+}
+
+/// A [`GroupKey`] sealed for a single member
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WrappedGroupKey {
+    pub group_id: String,
+    pub epoch: u64,
+    /// X25519 public key of the member this key is sealed for
+    #[serde(with = "hex::serde")]
+    pub recipient: [u8; GROUP_KEY_LENGTH],
+    /// Sender's one-time X25519 public key
+    #[serde(with = "hex::serde")]
+    pub ephemeral_public: [u8; GROUP_KEY_LENGTH],
+    #[serde(with = "hex::serde")]
+    pub nonce: [u8; GROUP_NONCE_LENGTH],
+    /// AES-256-GCM ciphertext and tag of the group key
+    #[serde(with = "hex::serde")]
+    pub ciphertext: Vec<u8>,
+}
+
+impl GroupKey {
+    /// Generate a fresh random key for a group epoch
+    pub fn generate(group_id: &str, epoch: u64) -> Self {
+        Self {
+            group_id: group_id.to_string(),
+            epoch,
+            key: random_bytes(),
+        }
+    }
+
+    /// Encrypt a message for the group
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<GroupCiphertext, GroupError> {
+        let nonce: [u8; GROUP_NONCE_LENGTH] = random_bytes();
+        let aad = message_aad(&self.group_id, self.epoch);
+        let ciphertext = seal(&self.key, &nonce, plaintext, &aad)?;
+        Ok(GroupCiphertext {
+            group_id: self.group_id.clone(),
+            epoch: self.epoch,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// Decrypt a group message encrypted under this key
+    pub fn decrypt(&self, message: &GroupCiphertext) -> Result<Vec<u8>, GroupError> {
+        self.check_scope(&message.group_id, message.epoch)?;
+        let aad = message_aad(&self.group_id, self.epoch);
+        open(&self.key, &message.nonce, &message.ciphertext, &aad)
+    }
+
+    /// Seal this key for a member's X25519 public key
+    pub fn wrap_for(&self, recipient: [u8; GROUP_KEY_LENGTH]) -> Result<WrappedGroupKey, GroupError> {
+        let ephemeral = StaticSecret::from(random_bytes::<GROUP_KEY_LENGTH>());
+        let ephemeral_public = PublicKey::from(&ephemeral).to_bytes();
+        let shared = ephemeral.diffie_hellman(&PublicKey::from(recipient));
+        let wrap_key = wrap_key(shared, &ephemeral_public, &recipient)?;
+
+        let nonce: [u8; GROUP_NONCE_LENGTH] = random_bytes();
+        let aad = message_aad(&self.group_id, self.epoch);
+        let ciphertext = seal(&wrap_key, &nonce, &self.key, &aad)?;
+        Ok(WrappedGroupKey {
+            group_id: self.group_id.clone(),
+            epoch: self.epoch,
+            recipient,
+            ephemeral_public,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// Open a wrapped key with the recipient's member secret
+    pub fn unwrap(wrapped: &WrappedGroupKey, member: &MemberSecret) -> Result<Self, GroupError> {
+        if wrapped.recipient != member.public_key() {
+            return Err(GroupError::NotRecipient);
+        }
+        let shared = StaticSecret::from(member.secret).diffie_hellman(&PublicKey::from(wrapped.ephemeral_public));
+        let wrap_key = wrap_key(shared, &wrapped.ephemeral_public, &wrapped.recipient)?;
+
+        let aad = message_aad(&wrapped.group_id, wrapped.epoch);
+        let key = Zeroizing::new(open(&wrap_key, &wrapped.nonce, &wrapped.ciphertext, &aad)?);
+        Ok(Self {
+            group_id: wrapped.group_id.clone(),
+            epoch: wrapped.epoch,
+            key: key.as_slice().try_into().map_err(|_| GroupError::DecryptionFailed)?,
+        })
+    }
+
+    fn check_scope(&self, group_id: &str, epoch: u64) -> Result<(), GroupError> {
+        if group_id != self.group_id {
+            return Err(GroupError::WrongGroup {
+                expected: self.group_id.clone(),
+                actual: group_id.to_string(),
+            });
+        }
+        if epoch != self.epoch {
+            return Err(GroupError::EpochMismatch {
+                expected: self.epoch,
+                actual: epoch,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Membership and current key of a group, held by its administrator
+pub struct GroupSession {
+    members: BTreeSet<[u8; GROUP_KEY_LENGTH]>,
+    current: GroupKey,
+}
+
+impl GroupSession {
+    /// Start a group at epoch 0 and wrap its key for the initial members
+    pub fn new(
+        group_id: &str,
+        members: &[[u8; GROUP_KEY_LENGTH]],
+    ) -> Result<(Self, Vec<WrappedGroupKey>), GroupError> {
+        let session = Self {
+            members: members.iter().copied().collect(),
+            current: GroupKey::generate(group_id, 0),
+        };
+        let wrapped = session.wrap_for_members()?;
+        Ok((session, wrapped))
+    }
+
+    /// The key for the current epoch
+    pub fn current_key(&self) -> &GroupKey {
+        &self.current
+    }
+
+    /// X25519 public keys of the current members
+    pub fn members(&self) -> impl Iterator<Item = &[u8; GROUP_KEY_LENGTH]> {
+        self.members.iter()
+    }
+
+    /// Add a member, rotating the key and rewrapping it for every member
+    pub fn add_member(&mut self, member: [u8; GROUP_KEY_LENGTH]) -> Result<Vec<WrappedGroupKey>, GroupError> {
+        if self.members.contains(&member) {
+            return Err(GroupError::Membership("already a member".to_string()));
+        }
+        self.members.insert(member);
+        self.rotate()
+    }
+
+    /// Remove a member, rotating the key and rewrapping it for those remaining
+    pub fn remove_member(&mut self, member: &[u8; GROUP_KEY_LENGTH]) -> Result<Vec<WrappedGroupKey>, GroupError> {
+        if !self.members.remove(member) {
+            return Err(GroupError::Membership("not a member".to_string()));
+        }
+        self.rotate()
+    }
+
+    /// Move to a new epoch with a fresh key and wrap it for every member
+    pub fn rotate(&mut self) -> Result<Vec<WrappedGroupKey>, GroupError> {
+        self.current = GroupKey::generate(&self.current.group_id, self.current.epoch + 1);
+        self.wrap_for_members()
+    }
+
+    fn wrap_for_members(&self) -> Result<Vec<WrappedGroupKey>, GroupError> {
+        self.members
+            .iter()
+            .map(|member| self.current.wrap_for(*member))
+            .collect()
+    }
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// Associated data binding ciphertexts to their group and epoch
+fn message_aad(group_id: &str, epoch: u64) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(MESSAGE_DOMAIN.len() + group_id.len() + 16);
+    aad.extend_from_slice(MESSAGE_DOMAIN);
+    aad.extend_from_slice(&(group_id.len() as u64).to_be_bytes());
+    aad.extend_from_slice(group_id.as_bytes());
+    aad.extend_from_slice(&epoch.to_be_bytes());
+    aad
+}
+
+/// Derive the key-wrapping key from an X25519 shared secret
+fn wrap_key(
+    shared: SharedSecret,
+    ephemeral_public: &[u8; GROUP_KEY_LENGTH],
+    recipient: &[u8; GROUP_KEY_LENGTH],
+) -> Result<Zeroizing<[u8; GROUP_KEY_LENGTH]>, GroupError> {
+    // Low-order points yield an all-zero secret that an attacker could predict
+    if !shared.was_contributory() {
+        return Err(GroupError::InvalidPublicKey);
+    }
+
+    let salt = [ephemeral_public.as_slice(), recipient.as_slice()].concat();
+    let mut key = Zeroizing::new([0u8; GROUP_KEY_LENGTH]);
+    Hkdf::<Sha256>::new(Some(&salt), shared.as_bytes())
+        .expand(WRAP_DOMAIN, key.as_mut())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    Ok(key)
+}
+
+fn seal(key: &[u8; GROUP_KEY_LENGTH], nonce: &[u8; GROUP_NONCE_LENGTH], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, GroupError> {
+    Aes256Gcm::new(key.into())
+        .encrypt(Nonce::from_slice(nonce), Payload { msg, aad })
+        .map_err(|_| GroupError::DecryptionFailed)
+}
+
+fn open(key: &[u8; GROUP_KEY_LENGTH], nonce: &[u8; GROUP_NONCE_LENGTH], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, GroupError> {
+    Aes256Gcm::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), Payload { msg, aad })
+        .map_err(|_| GroupError::DecryptionFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unwrap_for(wrapped: &[WrappedGroupKey], member: &MemberSecret) -> GroupKey {
+        let mine = wrapped
+            .iter()
+            .find(|w| w.recipient == member.public_key())
+            .expect("a wrapped key for the member");
+        GroupKey::unwrap(mine, member).unwrap()
+    }
+
+    #[test]
+    fn every_member_reads_a_message_encrypted_once() {
+        let members: Vec<MemberSecret> = (0..3).map(|_| MemberSecret::generate()).collect();
+        let publics: Vec<_> = members.iter().map(MemberSecret::public_key).collect();
+        let (_, wrapped) = GroupSession::new("ops", &publics).unwrap();
+
+        let sender_key = unwrap_for(&wrapped, &members[0]);
+        let message = sender_key.encrypt(b"deploy at noon").unwrap();
+
+        for member in &members {
+            assert_eq!(unwrap_for(&wrapped, member).decrypt(&message).unwrap(), b"deploy at noon");
+        }
+    }
+
+    #[test]
+    fn removed_member_cannot_open_the_rotated_key() {
+        let alice = MemberSecret::generate();
+        let mallory = MemberSecret::generate();
+        let (mut session, _) = GroupSession::new("ops", &[alice.public_key(), mallory.public_key()]).unwrap();
+
+        let wrapped = session.remove_member(&mallory.public_key()).unwrap();
+
+        assert_eq!(wrapped.len(), 1);
+        assert_eq!(session.current_key().epoch, 1);
+        assert_eq!(GroupKey::unwrap(&wrapped[0], &mallory), Err(GroupError::NotRecipient));
+
+        // Re-addressing Alice's wrapped key to Mallory does not help either
+        let mut stolen = wrapped[0].clone();
+        stolen.recipient = mallory.public_key();
+        assert_eq!(GroupKey::unwrap(&stolen, &mallory), Err(GroupError::DecryptionFailed));
+
+        let message = unwrap_for(&wrapped, &alice).encrypt(b"new plan").unwrap();
+        assert_eq!(session.current_key().decrypt(&message).unwrap(), b"new plan");
+        assert!(session.members().all(|member| *member != mallory.public_key()));
+    }
+
+    #[test]
+    fn ciphertexts_are_bound_to_group_and_epoch() {
+        let key = GroupKey::generate("ops", 4);
+        let mut message = key.encrypt(b"hello").unwrap();
+
+        assert_eq!(
+            GroupKey::generate("ops", 5).decrypt(&message),
+            Err(GroupError::EpochMismatch { expected: 5, actual: 4 })
+        );
+        assert!(matches!(GroupKey::generate("sales", 4).decrypt(&message), Err(GroupError::WrongGroup { .. })));
+
+        message.ciphertext[0] ^= 1;
+        assert_eq!(key.decrypt(&message), Err(GroupError::DecryptionFailed));
+    }
+
+    #[test]
+    fn payloads_roundtrip_as_json() {
+        let member = MemberSecret::generate();
+        let key = GroupKey::generate("ops", 0);
+        let wrapped = key.wrap_for(member.public_key()).unwrap();
+
+        let json = serde_json::to_string(&wrapped).unwrap();
+        let decoded: WrappedGroupKey = serde_json::from_str(&json).unwrap();
+
+        assert!(json.contains("\"ephemeralPublic\""));
+        assert_eq!(GroupKey::unwrap(&decoded, &member).unwrap(), key);
+        assert!(GroupSession::new("ops", &[[0u8; 32]]).is_err());
+    }
+}
@@ -0,0 +1,361 @@
+//! Hybrid Ed25519 + post-quantum (ML-DSA-65) dual-signature proofs
+//!
+//! During the migration to post-quantum cryptography a context can be signed
+//! with both Ed25519 and ML-DSA-65 (FIPS 204). Verifiers choose how much they
+//! trust each half through a [`HybridPolicy`]:
+//!
+//! - [`HybridPolicy::Strict`] requires both signatures to verify
+//! - [`HybridPolicy::Transitional`] requires the Ed25519 signature and tolerates
+//!   a failing PQC signature, so the PQC half can only add assurance
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::hybrid::{
+//!     make_hybrid_proof, verify_hybrid_proof, HybridKeypair, HybridPolicy,
+//! };
+//!
+//! let keypair = HybridKeypair::generate_with_seed(42);
+//! let proof = make_hybrid_proof(&keypair, b"approve transfer").unwrap();
+//! assert!(verify_hybrid_proof(&keypair.public_key(), b"approve transfer", &proof, HybridPolicy::Strict).is_ok());
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use mysten_mldsa_native_rs::{
+    Signature as PqcSignature, SigningKeySeed, VerifyingKey as PqcPublicKey, RND_LENGTH,
+    SEED_LENGTH,
+};
+use rand::rngs::OsRng;
+use rand::{RngCore, SeedableRng};
+use std::str::FromStr;
+use zeroize::Zeroize;
+
+use crate::key::SecureKeypair;
+use crate::proof::{validate_context_data, ProofError};
+
+pub use mysten_mldsa_native_rs::{
+    PUBLIC_KEY_LENGTH as PQC_PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH as PQC_SIGNATURE_LENGTH,
+};
+
+/// Length of an Ed25519 public key in bytes
+pub const ED25519_PUBLIC_KEY_LENGTH: usize = 32;
+
+/// Length of an Ed25519 signature in bytes
+pub const ED25519_SIGNATURE_LENGTH: usize = 64;
+
+/// Domain separation string passed to ML-DSA so PQC signatures produced here
+/// cannot be replayed in another protocol that shares the same key
+const PQC_SIGNING_CONTEXT: &[u8] = b"proof-messenger/hybrid-proof/v1";
+
+/// Policy deciding which halves of a hybrid proof must verify
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HybridPolicy {
+    /// Both the Ed25519 and the PQC signature must be valid
+    ///
+    /// The PQC key travels with the proof, so a forged Ed25519 half could
+    /// bring its own: verifiers must also bind the PQC key to the sender, for
+    /// example by pinning the first one each sender key presents.
+    Strict,
+    /// The Ed25519 signature must be valid; the PQC signature is not required
+    ///
+    /// A PQC half never stands in for the Ed25519 one: the PQC key travels with
+    /// the proof and is not bound to the sender's identity.
+    Transitional,
+}
+
+impl FromStr for HybridPolicy {
+    type Err = ProofError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "strict" => Ok(HybridPolicy::Strict),
+            "transitional" => Ok(HybridPolicy::Transitional),
+            other => Err(ProofError::InvalidInput(format!(
+                "Unknown hybrid policy '{}', expected 'strict' or 'transitional'",
+                other
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for HybridPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HybridPolicy::Strict => write!(f, "strict"),
+            HybridPolicy::Transitional => write!(f, "transitional"),
+        }
+    }
+}
+
+/// A keypair holding both an Ed25519 key and an ML-DSA-65 seed
+///
+/// Both halves are zeroized when the keypair is dropped.
+#[derive(Clone)]
+pub struct HybridKeypair {
+    ed25519: SecureKeypair,
+    pqc_seed: [u8; SEED_LENGTH],
+}
+
+impl Drop for HybridKeypair {
+    fn drop(&mut self) {
+        self.pqc_seed.zeroize();
+    }
+}
+
+impl HybridKeypair {
+    /// Generate a new hybrid keypair using cryptographically secure randomness
+    pub fn generate() -> Self {
+        let mut pqc_seed = [0u8; SEED_LENGTH];
+        OsRng.fill_bytes(&mut pqc_seed);
+        Self {
+            ed25519: SecureKeypair::generate(),
+            pqc_seed,
+        }
+    }
+
+    /// Generate a hybrid keypair from a deterministic seed (for testing)
+    ///
+    /// The Ed25519 half matches `SecureKeypair::generate_with_seed(seed)`.
+    pub fn generate_with_seed(seed: u64) -> Self {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed ^ 0x5051_4331_4859_4252);
+        let mut pqc_seed = [0u8; SEED_LENGTH];
+        rng.fill_bytes(&mut pqc_seed);
+        Self {
+            ed25519: SecureKeypair::generate_with_seed(seed),
+            pqc_seed,
+        }
+    }
+
+    /// Build a hybrid keypair from an existing Ed25519 keypair and a 32-byte PQC seed
+    pub fn from_parts(ed25519: SecureKeypair, pqc_seed: &[u8]) -> Result<Self, ProofError> {
+        let pqc_seed: [u8; SEED_LENGTH] = pqc_seed.try_into().map_err(|_| {
+            ProofError::InvalidInput(format!("PQC seed must be exactly {} bytes", SEED_LENGTH))
+        })?;
+        Ok(Self { ed25519, pqc_seed })
+    }
+
+    /// The Ed25519 half of this keypair
+    pub fn ed25519(&self) -> &SecureKeypair {
+        &self.ed25519
+    }
+
+    /// Get the hybrid public key (safe to expose)
+    pub fn public_key(&self) -> HybridPublicKey {
+        let (_, pqc) = SigningKeySeed::from(self.pqc_seed).expand();
+        HybridPublicKey {
+            ed25519: self.ed25519.public_key(),
+            pqc,
+        }
+    }
+}
+
+/// Public half of a [`HybridKeypair`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HybridPublicKey {
+    pub ed25519: PublicKey,
+    pub pqc: PqcPublicKey,
+}
+
+impl HybridPublicKey {
+    /// Encode as `ed25519 (32 bytes) || ml-dsa-65 (1952 bytes)`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(ED25519_PUBLIC_KEY_LENGTH + PQC_PUBLIC_KEY_LENGTH);
+        bytes.extend_from_slice(self.ed25519.as_bytes());
+        bytes.extend_from_slice(self.pqc.as_bytes());
+        bytes
+    }
+
+    /// Decode from the layout produced by [`HybridPublicKey::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProofError> {
+        if bytes.len() != ED25519_PUBLIC_KEY_LENGTH + PQC_PUBLIC_KEY_LENGTH {
+            return Err(ProofError::InvalidData(format!(
+                "Hybrid public key must be {} bytes (got {})",
+                ED25519_PUBLIC_KEY_LENGTH + PQC_PUBLIC_KEY_LENGTH,
+                bytes.len()
+            )));
+        }
+        let (ed_bytes, pqc_bytes) = bytes.split_at(ED25519_PUBLIC_KEY_LENGTH);
+        Self::from_parts(ed_bytes, pqc_bytes)
+    }
+
+    /// Decode from separately transmitted Ed25519 and PQC public keys
+    pub fn from_parts(ed25519: &[u8], pqc: &[u8]) -> Result<Self, ProofError> {
+        let ed25519 = PublicKey::from_bytes(ed25519)
+            .map_err(|e| ProofError::InvalidData(format!("Invalid Ed25519 public key: {}", e)))?;
+        let pqc = PqcPublicKey::from_bytes(pqc)
+            .map_err(|e| ProofError::InvalidData(format!("Invalid PQC public key: {}", e)))?;
+        Ok(Self { ed25519, pqc })
+    }
+}
+
+/// A context signed with both Ed25519 and ML-DSA-65
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HybridSignature {
+    pub ed25519: Signature,
+    pub pqc: PqcSignature,
+}
+
+impl HybridSignature {
+    /// Encode as `ed25519 (64 bytes) || ml-dsa-65 (3309 bytes)`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(ED25519_SIGNATURE_LENGTH + PQC_SIGNATURE_LENGTH);
+        bytes.extend_from_slice(&self.ed25519.to_bytes());
+        bytes.extend_from_slice(self.pqc.as_bytes());
+        bytes
+    }
+
+    /// Decode from the layout produced by [`HybridSignature::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProofError> {
+        if bytes.len() != ED25519_SIGNATURE_LENGTH + PQC_SIGNATURE_LENGTH {
+            return Err(ProofError::InvalidData(format!(
+                "Hybrid signature must be {} bytes (got {})",
+                ED25519_SIGNATURE_LENGTH + PQC_SIGNATURE_LENGTH,
+                bytes.len()
+            )));
+        }
+        let (ed_bytes, pqc_bytes) = bytes.split_at(ED25519_SIGNATURE_LENGTH);
+        Self::from_parts(ed_bytes, pqc_bytes)
+    }
+
+    /// Decode from separately transmitted Ed25519 and PQC signatures
+    pub fn from_parts(ed25519: &[u8], pqc: &[u8]) -> Result<Self, ProofError> {
+        let ed25519 = Signature::from_bytes(ed25519)
+            .map_err(|e| ProofError::InvalidData(format!("Invalid Ed25519 signature: {}", e)))?;
+        let pqc = PqcSignature::from_bytes(pqc)
+            .map_err(|e| ProofError::InvalidData(format!("Invalid PQC signature: {}", e)))?;
+        Ok(Self { ed25519, pqc })
+    }
+}
+
+/// Sign a context with both halves of a hybrid keypair
+pub fn make_hybrid_proof(
+    keypair: &HybridKeypair,
+    context: &[u8],
+) -> Result<HybridSignature, ProofError> {
+    validate_context_data(context)?;
+
+    let ed25519 = keypair.ed25519.sign(context);
+
+    let mut rnd = [0u8; RND_LENGTH];
+    OsRng.fill_bytes(&mut rnd);
+    let (signing_key, _) = SigningKeySeed::from(keypair.pqc_seed).expand();
+    let pqc = signing_key
+        .sign(context, PQC_SIGNING_CONTEXT, &rnd)
+        .map_err(|e| ProofError::GenerationFailed(format!("PQC signing failed: {}", e)))?;
+
+    Ok(HybridSignature { ed25519, pqc })
+}
+
+/// Verify a hybrid proof under the given policy
+///
+/// Under [`HybridPolicy::Strict`] the first failing half is reported. Under
+/// [`HybridPolicy::Transitional`] only an Ed25519 failure is reported.
+pub fn verify_hybrid_proof(
+    pubkey: &HybridPublicKey,
+    context: &[u8],
+    sig: &HybridSignature,
+    policy: HybridPolicy,
+) -> Result<(), ProofError> {
+    validate_context_data(context)?;
+
+    let ed25519_result = pubkey
+        .ed25519
+        .verify(context, &sig.ed25519)
+        .map_err(ProofError::VerificationFailed);
+    let pqc_result = pubkey
+        .pqc
+        .verify(context, PQC_SIGNING_CONTEXT, &sig.pqc)
+        .map_err(|_| ProofError::PqcVerificationFailed);
+
+    match policy {
+        HybridPolicy::Strict => ed25519_result.and(pqc_result),
+        HybridPolicy::Transitional => ed25519_result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hybrid_proof_roundtrip_strict() {
+        let keypair = HybridKeypair::generate_with_seed(42);
+        let context = b"hybrid context";
+
+        let proof = make_hybrid_proof(&keypair, context).unwrap();
+        let result = verify_hybrid_proof(&keypair.public_key(), context, &proof, HybridPolicy::Strict);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn ed25519_half_matches_secure_keypair() {
+        let keypair = HybridKeypair::generate_with_seed(7);
+        let classic = SecureKeypair::generate_with_seed(7);
+
+        assert_eq!(keypair.public_key().ed25519, classic.public_key());
+    }
+
+    #[test]
+    fn strict_rejects_tampered_pqc_signature() {
+        let keypair = HybridKeypair::generate_with_seed(42);
+        let context = b"hybrid context";
+        let other = make_hybrid_proof(&HybridKeypair::generate_with_seed(43), context).unwrap();
+
+        let mut proof = make_hybrid_proof(&keypair, context).unwrap();
+        proof.pqc = other.pqc;
+
+        let strict = verify_hybrid_proof(&keypair.public_key(), context, &proof, HybridPolicy::Strict);
+        assert!(matches!(strict, Err(ProofError::PqcVerificationFailed)));
+
+        let transitional =
+            verify_hybrid_proof(&keypair.public_key(), context, &proof, HybridPolicy::Transitional);
+        assert!(transitional.is_ok());
+    }
+
+    #[test]
+    fn transitional_rejects_valid_pqc_with_bad_ed25519() {
+        let keypair = HybridKeypair::generate_with_seed(42);
+        let context = b"hybrid context";
+        let other = make_hybrid_proof(&HybridKeypair::generate_with_seed(43), context).unwrap();
+
+        let mut proof = make_hybrid_proof(&keypair, context).unwrap();
+        proof.ed25519 = other.ed25519;
+
+        let strict = verify_hybrid_proof(&keypair.public_key(), context, &proof, HybridPolicy::Strict);
+        assert!(matches!(strict, Err(ProofError::VerificationFailed(_))));
+
+        let transitional =
+            verify_hybrid_proof(&keypair.public_key(), context, &proof, HybridPolicy::Transitional);
+        assert!(matches!(transitional, Err(ProofError::VerificationFailed(_))));
+    }
+
+    #[test]
+    fn transitional_rejects_when_both_halves_fail() {
+        let keypair = HybridKeypair::generate_with_seed(42);
+        let proof = make_hybrid_proof(&keypair, b"original").unwrap();
+
+        let result =
+            verify_hybrid_proof(&keypair.public_key(), b"tampered", &proof, HybridPolicy::Transitional);
+        assert!(matches!(result, Err(ProofError::VerificationFailed(_))));
+    }
+
+    #[test]
+    fn hybrid_encodings_roundtrip() {
+        let keypair = HybridKeypair::generate_with_seed(1);
+        let proof = make_hybrid_proof(&keypair, b"encode me").unwrap();
+
+        let public_key = HybridPublicKey::from_bytes(&keypair.public_key().to_bytes()).unwrap();
+        let decoded = HybridSignature::from_bytes(&proof.to_bytes()).unwrap();
+
+        assert_eq!(public_key, keypair.public_key());
+        assert_eq!(decoded, proof);
+        assert!(HybridSignature::from_bytes(&[0u8; 64]).is_err());
+    }
+
+    #[test]
+    fn policy_parses_from_str() {
+        assert_eq!("strict".parse::<HybridPolicy>().unwrap(), HybridPolicy::Strict);
+        assert_eq!("Transitional".parse::<HybridPolicy>().unwrap(), HybridPolicy::Transitional);
+        assert!("lenient".parse::<HybridPolicy>().is_err());
+    }
+}
@@ -0,0 +1,514 @@
+//! Invite lifecycle: single-use invite codes bound to a group
+//!
+//! An invite is minted for a group, handed to a new member out of band and
+//! redeemed exactly once by submitting an onboarding proof. The onboarding
+//! proof is an Ed25519 signature over [`redemption_context`], which binds the
+//! signature to both the invite code and the group it grants access to.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::invite::{InviteState, InviteStatus};
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//! use proof_messenger_protocol::proof::{make_secure_proof, verify_proof_result};
+//!
+//! let mut invite = InviteState::new("engineering", chrono::Duration::hours(24));
+//! let keypair = generate_secure_keypair();
+//! let proof = make_secure_proof(&keypair, &invite.redemption_context()).unwrap();
+//!
+//! assert!(verify_proof_result(&keypair.public_key(), &invite.redemption_context(), &proof).is_ok());
+//! invite.redeem(chrono::Utc::now()).unwrap();
+//! assert_eq!(invite.status, InviteStatus::Redeemed);
+//! ```
+//!
+//! ## Invite URIs
+//!
+//! [`InviteUri`] packs an invite into a short `pm://invite?...` URI, small
+//! enough for a QR code, so a desktop CLI and the web demo can hand invites
+//! to each other without copying hex by hand:
+//!
+//! ```rust
+//! use proof_messenger_protocol::invite::InviteUri;
+//! use proof_messenger_protocol::proof::Invite;
+//!
+//! let invite = Invite::new_with_seed(43);
+//! let uri = InviteUri::new(invite.data.clone(), [7u8; 32]).with_group("engineering");
+//!
+//! let parsed: InviteUri = uri.to_string().parse().unwrap();
+//! assert_eq!(parsed, uri);
+//! assert_eq!(parsed.to_invite().data, invite.data);
+//! ```
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::proof::Invite;
+
+/// Number of random bytes in an invite code (hex encoded to twice this length)
+pub const INVITE_CODE_BYTES: usize = 16;
+
+/// Scheme, host and query separator every invite URI starts with
+pub const INVITE_URI_PREFIX: &str = "pm://invite?";
+
+/// Invite URI format version written by [`InviteUri`]
+pub const INVITE_URI_VERSION: u32 = 1;
+
+/// Domain separation prefix for onboarding proofs
+const REDEMPTION_DOMAIN: &[u8] = b"proof-messenger/invite-redemption/v1";
+
+/// Errors raised by invite state transitions
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum InviteError {
+    /// The invite has already been consumed
+    #[error("Invite {0} has already been redeemed")]
+    AlreadyRedeemed(String),
+
+    /// The invite is past its expiry time
+    #[error("Invite {0} has expired")]
+    Expired(String),
+
+    /// The invite code is not well formed
+    #[error("Invalid invite code: {0}")]
+    InvalidCode(String),
+
+    /// An unknown invite status was encountered
+    #[error("Unknown invite status: {0}")]
+    UnknownStatus(String),
+
+    /// An invite URI is not well formed
+    #[error("Invalid invite URI: {0}")]
+    InvalidUri(String),
+}
+
+/// Lifecycle status of an invite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InviteStatus {
+    /// Minted and waiting to be redeemed
+    Pending,
+    /// Consumed by an onboarding proof
+    Redeemed,
+    /// Not redeemed before its expiry time
+    Expired,
+}
+
+impl InviteStatus {
+    /// Stable string form used for storage and wire formats
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InviteStatus::Pending => "pending",
+            InviteStatus::Redeemed => "redeemed",
+            InviteStatus::Expired => "expired",
+        }
+    }
+}
+
+impl FromStr for InviteStatus {
+    type Err = InviteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(InviteStatus::Pending),
+            "redeemed" => Ok(InviteStatus::Redeemed),
+            "expired" => Ok(InviteStatus::Expired),
+            other => Err(InviteError::UnknownStatus(other.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for InviteStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single-use invite bound to a group
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InviteState {
+    /// Random invite code (hex encoded)
+    pub code: String,
+    /// Group the invite grants access to
+    pub group_id: String,
+    /// Current lifecycle status
+    pub status: InviteStatus,
+    /// When the invite was minted
+    pub created_at: DateTime<Utc>,
+    /// When the invite stops being redeemable
+    pub expires_at: DateTime<Utc>,
+}
+
+impl InviteState {
+    /// Mint a new pending invite for a group with a fresh random code
+    pub fn new(group_id: &str, ttl: Duration) -> Self {
+        let created_at = Utc::now();
+        Self {
+            code: generate_invite_code(),
+            group_id: group_id.to_string(),
+            status: InviteStatus::Pending,
+            created_at,
+            expires_at: created_at + ttl,
+        }
+    }
+
+    /// The context an invitee must sign to redeem this invite
+    pub fn redemption_context(&self) -> Vec<u8> {
+        redemption_context(&self.code, &self.group_id)
+    }
+
+    /// The invite as legacy [`Invite`] data, for use with `make_proof`/`verify_proof`
+    pub fn to_invite(&self) -> Invite {
+        Invite {
+            data: self.redemption_context(),
+        }
+    }
+
+    /// Whether the invite can still be redeemed at `now`
+    pub fn is_redeemable(&self, now: DateTime<Utc>) -> bool {
+        self.status == InviteStatus::Pending && now < self.expires_at
+    }
+
+    /// Consume the invite, moving it from pending to redeemed
+    ///
+    /// A pending invite past its expiry is moved to expired and rejected.
+    pub fn redeem(&mut self, now: DateTime<Utc>) -> Result<(), InviteError> {
+        match self.status {
+            InviteStatus::Redeemed => Err(InviteError::AlreadyRedeemed(self.code.clone())),
+            InviteStatus::Expired => Err(InviteError::Expired(self.code.clone())),
+            InviteStatus::Pending if now >= self.expires_at => {
+                self.status = InviteStatus::Expired;
+                Err(InviteError::Expired(self.code.clone()))
+            }
+            InviteStatus::Pending => {
+                self.status = InviteStatus::Redeemed;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Generate a random single-use invite code
+pub fn generate_invite_code() -> String {
+    let mut bytes = [0u8; INVITE_CODE_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Check that an invite code has the expected length and alphabet
+pub fn validate_invite_code(code: &str) -> Result<(), InviteError> {
+    if code.len() != INVITE_CODE_BYTES * 2 || !code.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(InviteError::InvalidCode(code.to_string()));
+    }
+    Ok(())
+}
+
+/// Build the bytes an invitee signs to redeem `code` for `group_id`
+///
+/// Fields are NUL separated after a domain prefix so a proof for one
+/// invite or group can never be replayed against another.
+pub fn redemption_context(code: &str, group_id: &str) -> Vec<u8> {
+    let mut context = Vec::with_capacity(REDEMPTION_DOMAIN.len() + code.len() + group_id.len() + 2);
+    context.extend_from_slice(REDEMPTION_DOMAIN);
+    context.push(0);
+    context.extend_from_slice(code.as_bytes());
+    context.push(0);
+    context.extend_from_slice(group_id.as_bytes());
+    context
+}
+
+/// An invite packed into a `pm://invite?...` URI
+///
+/// Binary fields are base64url encoded without padding and text fields are
+/// percent encoded. Parameters:
+///
+/// | Key | Field | Required |
+/// |-----|-------|----------|
+/// | `v` | format version, [`INVITE_URI_VERSION`] | yes |
+/// | `d` | invite data the invitee signs | yes |
+/// | `k` | inviter's Ed25519 public key | yes |
+/// | `g` | group the invite is for | no |
+/// | `r` | relay URL | no |
+/// | `e` | expiry, in Unix seconds | no |
+///
+/// Parsing ignores unknown parameters, so later versions can add fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InviteUri {
+    /// Invite data the invitee signs to onboard
+    pub data: Vec<u8>,
+    /// Inviter's Ed25519 public key
+    pub inviter: [u8; 32],
+    /// Group the invite is for
+    pub group_id: Option<String>,
+    /// Relay the invitee should talk to
+    pub relay_url: Option<String>,
+    /// When the invite stops being redeemable
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl InviteUri {
+    /// An invite URI for `data` from `inviter`
+    pub fn new(data: Vec<u8>, inviter: [u8; 32]) -> Self {
+        Self {
+            data,
+            inviter,
+            group_id: None,
+            relay_url: None,
+            expires_at: None,
+        }
+    }
+
+    /// Name the group the invite is for
+    pub fn with_group(mut self, group_id: &str) -> Self {
+        self.group_id = Some(group_id.to_string());
+        self
+    }
+
+    /// Name the relay the invitee should talk to
+    pub fn with_relay(mut self, relay_url: &str) -> Self {
+        self.relay_url = Some(relay_url.to_string());
+        self
+    }
+
+    /// Set when the invite expires; the URI keeps whole seconds
+    pub fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Utc.timestamp_opt(expires_at.timestamp(), 0).single();
+        self
+    }
+
+    /// The invite data as an [`Invite`], for use with `make_proof`/`verify_proof`
+    pub fn to_invite(&self) -> Invite {
+        Invite {
+            data: self.data.clone(),
+        }
+    }
+
+    /// Whether the invite has expired at `now`; invites without an expiry never do
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+}
+
+impl std::fmt::Display for InviteUri {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}v={}&d={}&k={}",
+            INVITE_URI_PREFIX,
+            INVITE_URI_VERSION,
+            URL_SAFE_NO_PAD.encode(&self.data),
+            URL_SAFE_NO_PAD.encode(self.inviter)
+        )?;
+        if let Some(group_id) = &self.group_id {
+            write!(f, "&g={}", percent_encode(group_id))?;
+        }
+        if let Some(relay_url) = &self.relay_url {
+            write!(f, "&r={}", percent_encode(relay_url))?;
+        }
+        if let Some(expires_at) = &self.expires_at {
+            write!(f, "&e={}", expires_at.timestamp())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for InviteUri {
+    type Err = InviteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| InviteError::InvalidUri(reason.to_string());
+        let query = s
+            .trim()
+            .strip_prefix(INVITE_URI_PREFIX)
+            .ok_or_else(|| invalid("must start with pm://invite?"))?;
+
+        let mut params: Vec<(&str, String)> = Vec::new();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').ok_or_else(|| invalid("parameters must be key=value"))?;
+            if params.iter().any(|(seen, _)| *seen == key) {
+                return Err(InviteError::InvalidUri(format!("duplicate parameter {}", key)));
+            }
+            let value = percent_decode(value).ok_or_else(|| InviteError::InvalidUri(format!("bad encoding in {}", key)))?;
+            params.push((key, value));
+        }
+        let param = |key: &str| params.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str());
+        let required = |key: &str| param(key).ok_or_else(|| InviteError::InvalidUri(format!("missing parameter {}", key)));
+        let bytes = |key: &str| {
+            URL_SAFE_NO_PAD
+                .decode(required(key)?)
+                .map_err(|_| InviteError::InvalidUri(format!("{} must be base64url", key)))
+        };
+
+        if required("v")? != INVITE_URI_VERSION.to_string() {
+            return Err(InviteError::InvalidUri(format!("unsupported version {}", required("v")?)));
+        }
+        let data = bytes("d")?;
+        if data.is_empty() {
+            return Err(invalid("invite data is empty"));
+        }
+        let inviter = bytes("k")?
+            .try_into()
+            .map_err(|_| invalid("inviter key must be 32 bytes"))?;
+        let expires_at = param("e")
+            .map(|e| {
+                e.parse::<i64>()
+                    .ok()
+                    .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+                    .ok_or_else(|| invalid("expiry must be Unix seconds"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            data,
+            inviter,
+            group_id: param("g").map(str::to_string),
+            relay_url: param("r").map(str::to_string),
+            expires_at,
+        })
+    }
+}
+
+/// Percent-encode everything but unreserved characters and the `:/@` of URLs
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~:/@".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Undo [`percent_encode`], rejecting truncated escapes and invalid UTF-8
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let hex = [input.next()?, input.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_invite_is_pending_with_valid_code() {
+        let invite = InviteState::new("group1", Duration::hours(1));
+
+        assert_eq!(invite.status, InviteStatus::Pending);
+        assert!(validate_invite_code(&invite.code).is_ok());
+        assert!(invite.is_redeemable(Utc::now()));
+    }
+
+    #[test]
+    fn invite_can_only_be_redeemed_once() {
+        let mut invite = InviteState::new("group1", Duration::hours(1));
+
+        assert!(invite.redeem(Utc::now()).is_ok());
+        assert!(matches!(invite.redeem(Utc::now()), Err(InviteError::AlreadyRedeemed(_))));
+    }
+
+    #[test]
+    fn expired_invite_cannot_be_redeemed() {
+        let mut invite = InviteState::new("group1", Duration::hours(1));
+        let later = invite.expires_at + Duration::seconds(1);
+
+        assert!(matches!(invite.redeem(later), Err(InviteError::Expired(_))));
+        assert_eq!(invite.status, InviteStatus::Expired);
+    }
+
+    #[test]
+    fn redemption_context_binds_code_and_group() {
+        assert_ne!(redemption_context("aa", "g1"), redemption_context("aa", "g2"));
+        assert_ne!(redemption_context("aa", "g1"), redemption_context("ab", "g1"));
+        // Separator prevents ambiguity between code and group boundaries
+        assert_ne!(redemption_context("a", "ab"), redemption_context("aa", "b"));
+    }
+
+    #[test]
+    fn status_roundtrips_through_strings() {
+        for status in [InviteStatus::Pending, InviteStatus::Redeemed, InviteStatus::Expired] {
+            assert_eq!(status.as_str().parse::<InviteStatus>().unwrap(), status);
+        }
+        assert!("bogus".parse::<InviteStatus>().is_err());
+    }
+
+    #[test]
+    fn invite_uri_roundtrips_with_every_field() {
+        let uri = InviteUri::new(vec![0, 0, 0, 0, 0, 0, 0, 43], [9u8; 32])
+            .with_group("eng & ops/β")
+            .with_relay("https://relay.example.com:8443/base?x=1")
+            .with_expiry(Utc.timestamp_opt(1_900_000_000, 0).unwrap());
+
+        let text = uri.to_string();
+
+        assert!(text.starts_with("pm://invite?v=1&d=AAAAAAAAACs&k="));
+        assert!(!text.contains(' ') && !text.contains("&x="));
+        assert_eq!(text.parse::<InviteUri>().unwrap(), uri);
+    }
+
+    #[test]
+    fn invite_uri_with_only_required_fields_is_compact() {
+        let uri = InviteUri::new(vec![1, 2, 3], [0u8; 32]);
+
+        let text = uri.to_string();
+
+        assert_eq!(text, format!("pm://invite?v=1&d=AQID&k={}", "A".repeat(43)));
+        assert_eq!(text.parse::<InviteUri>().unwrap(), uri);
+    }
+
+    #[test]
+    fn invite_uri_ignores_unknown_parameters() {
+        let text = format!("{}&future=yes", InviteUri::new(vec![1], [2u8; 32]));
+
+        assert_eq!(text.parse::<InviteUri>().unwrap(), InviteUri::new(vec![1], [2u8; 32]));
+    }
+
+    #[test]
+    fn malformed_invite_uris_are_rejected() {
+        let key = "A".repeat(43);
+        let cases = [
+            "https://invite?v=1&d=AQ&k=x".to_string(),
+            format!("pm://invite?d=AQ&k={}", key),
+            format!("pm://invite?v=2&d=AQ&k={}", key),
+            format!("pm://invite?v=1&k={}", key),
+            format!("pm://invite?v=1&d=&k={}", key),
+            format!("pm://invite?v=1&d=%%%&k={}", key),
+            "pm://invite?v=1&d=AQ&k=AAAA".to_string(),
+            format!("pm://invite?v=1&d=AQ&d=AQ&k={}", key),
+            format!("pm://invite?v=1&d=AQ&k={}&e=soon", key),
+            format!("pm://invite?v=1&d=AQ&k={}&g=%F", key),
+            format!("pm://invite?v=1&d=AQ&k={}&novalue", key),
+        ];
+
+        for case in cases {
+            assert!(
+                matches!(case.parse::<InviteUri>(), Err(InviteError::InvalidUri(_))),
+                "accepted {}",
+                case
+            );
+        }
+    }
+
+    #[test]
+    fn invite_uri_expiry() {
+        let expires_at = Utc.timestamp_opt(1_900_000_000, 0).unwrap();
+        let uri = InviteUri::new(vec![1], [0u8; 32]).with_expiry(expires_at);
+
+        assert!(!uri.is_expired(expires_at - Duration::seconds(1)));
+        assert!(uri.is_expired(expires_at));
+        assert!(!InviteUri::new(vec![1], [0u8; 32]).is_expired(expires_at));
+    }
+}
@@ -1,7 +1,147 @@
-use ed25519_dalek::{Keypair, PublicKey, Signature, Signer};
+//! Keypair generation and passphrase-protected key files
+//!
+//! ## Key File Format
+//! [`SecureKeypair::to_encrypted_bytes`] exports a keypair in a versioned
+//! binary format. All integers are big-endian:
+//!
+//! | Offset | Size | Field                                         |
+//! |--------|------|-----------------------------------------------|
+//! | 0      | 4    | Magic bytes `PMKF`                            |
+//! | 4      | 1    | Format version (`1`)                          |
+//! | 5      | 1    | KDF id (`1` = Argon2id v1.3)                  |
+//! | 6      | 4    | KDF memory cost in KiB                        |
+//! | 10     | 4    | KDF iterations                                |
+//! | 14     | 4    | KDF parallelism                               |
+//! | 18     | 16   | KDF salt                                      |
+//! | 34     | 1    | AEAD id (`1` = AES-256-GCM)                   |
+//! | 35     | 12   | AEAD nonce                                    |
+//! | 47     | 80   | Ciphertext of the 64 keypair bytes + GCM tag  |
+//!
+//! The 256-bit AEAD key is derived from the passphrase and salt, and the
+//! 47 byte header is authenticated as associated data, so KDF parameters
+//! cannot be downgraded without failing decryption. Costs above
+//! [`MAX_KDF_MEMORY_KIB`], [`MAX_KDF_ITERATIONS`] or [`MAX_KDF_PARALLELISM`]
+//! are refused with [`ProtocolError::KdfLimitExceeded`] before any work is done.
+//!
+//! ## Mnemonic Backup
+//! [`generate_mnemonic`] creates a BIP-39 phrase (12 or 24 English words),
+//! and [`SecureKeypair::from_mnemonic`] turns it back into the same keypair
+//! on any device. The phrase and an optional BIP-39 passphrase give a 64 byte
+//! seed, from which the Ed25519 key is derived with SLIP-0010 along
+//! [`MNEMONIC_DERIVATION_PATH`].
+//!
+//! ```rust
+//! use proof_messenger_protocol::key::{generate_mnemonic, SecureKeypair};
+//!
+//! let phrase = generate_mnemonic(12).unwrap();
+//! let keypair = SecureKeypair::from_mnemonic(&phrase, "").unwrap();
+//!
+//! let recovered = SecureKeypair::from_mnemonic(&phrase, "").unwrap();
+//! assert_eq!(recovered.public_key_bytes(), keypair.public_key_bytes());
+//! ```
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::key::{generate_secure_keypair, KdfParams, SecureKeypair};
+//!
+//! let keypair = generate_secure_keypair();
+//! let exported = keypair.to_encrypted_bytes(b"correct horse", KdfParams::default()).unwrap();
+//!
+//! let imported = SecureKeypair::from_encrypted_bytes(&exported, b"correct horse").unwrap();
+//! assert_eq!(imported.public_key_bytes(), keypair.public_key_bytes());
+//! assert!(SecureKeypair::from_encrypted_bytes(&exported, b"wrong").is_err());
+//! ```
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use bip39::Mnemonic;
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature, Signer};
+use hmac::{Hmac, Mac, NewMac};
 use rand::rngs::OsRng;
-use rand::SeedableRng;
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use rand::{RngCore, SeedableRng};
+use sha2::Sha512;
+use std::path::Path;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::errors::{self, ProtocolError};
+
+/// Magic bytes at the start of every key file
+pub const KEY_FILE_MAGIC: [u8; 4] = *b"PMKF";
+
+/// Current key file format version
+pub const KEY_FILE_VERSION: u8 = 1;
+
+const KDF_ARGON2ID: u8 = 1;
+const AEAD_AES_256_GCM: u8 = 1;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const HEADER_LENGTH: usize = 4 + 1 + 1 + 12 + SALT_LENGTH + 1 + NONCE_LENGTH;
+const CIPHERTEXT_LENGTH: usize = 64 + 16;
+
+/// Largest KDF memory cost accepted when loading a key file (1 GiB)
+///
+/// Bounds the work an untrusted file can make the loader do.
+pub const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+
+/// Largest number of KDF passes accepted when loading a key file
+pub const MAX_KDF_ITERATIONS: u32 = 16;
+
+/// Largest KDF degree of parallelism accepted when loading a key file
+pub const MAX_KDF_PARALLELISM: u32 = 16;
+
+/// SLIP-0010 path of keys derived from a mnemonic: `m/44'/20557'/0'/0'`
+///
+/// 20557 is "PM" in ASCII. Every level is hardened, as Ed25519 requires.
+/// Changing the path changes every recovered key.
+pub const MNEMONIC_DERIVATION_PATH: [u32; 4] = [44, 20557, 0, 0];
+
+/// Bit marking a hardened SLIP-0010 index
+const HARDENED: u32 = 0x8000_0000;
+
+/// Argon2id cost parameters for deriving a key file's encryption key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of passes over memory
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// OWASP's recommended Argon2id baseline (19 MiB, 2 passes, 1 lane)
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Derive a 256-bit key from a passphrase and salt
+    fn derive(&self, passphrase: &[u8], salt: &[u8]) -> errors::Result<Zeroizing<[u8; 32]>> {
+        for (parameter, value, limit) in [
+            ("memory_kib", self.memory_kib, MAX_KDF_MEMORY_KIB),
+            ("iterations", self.iterations, MAX_KDF_ITERATIONS),
+            ("parallelism", self.parallelism, MAX_KDF_PARALLELISM),
+        ] {
+            if value > limit {
+                return Err(ProtocolError::KdfLimitExceeded { parameter, value, limit });
+            }
+        }
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| ProtocolError::invalid_input(format!("Invalid KDF parameters: {}", e)))?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase, salt, key.as_mut())
+            .map_err(|e| ProtocolError::crypto(format!("Key derivation failed: {}", e)))?;
+        Ok(key)
+    }
+}
 
 /// A secure wrapper around Ed25519 keypair that automatically zeros
 /// sensitive key material when dropped from memory.
@@ -56,6 +196,26 @@ impl SecureKeypair {
         Ok(Self { keypair_bytes })
     }
 
+    /// Recover the keypair backed up as a BIP-39 phrase
+    ///
+    /// The phrase's checksum is checked, so a mistyped or swapped word is
+    /// rejected instead of recovering a different key. Case and spacing are
+    /// ignored. Pass `""` as the passphrase unless one was set when the
+    /// backup was made.
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> errors::Result<Self> {
+        let seed = mnemonic_seed(phrase, passphrase)?;
+        Ok(Self::from_secret(&derive_slip10_ed25519(seed.as_ref(), &MNEMONIC_DERIVATION_PATH)))
+    }
+
+    /// Create the keypair of a 32 byte Ed25519 secret key
+    pub(crate) fn from_secret(secret_bytes: &[u8; 32]) -> Self {
+        let secret = SecretKey::from_bytes(secret_bytes).expect("32 byte secret key");
+        let public = PublicKey::from(&secret);
+        Self {
+            keypair_bytes: Keypair { secret, public }.to_bytes(),
+        }
+    }
+
     /// Get the public key (safe to expose)
     pub fn public_key(&self) -> PublicKey {
         // Extract public key from the last 32 bytes
@@ -101,6 +261,78 @@ impl SecureKeypair {
     pub fn to_bytes(&self) -> [u8; 64] {
         self.keypair_bytes
     }
+
+    /// Export the keypair as a passphrase-protected key file
+    pub fn to_encrypted_bytes(&self, passphrase: &[u8], params: KdfParams) -> errors::Result<Vec<u8>> {
+        let mut salt = [0u8; SALT_LENGTH];
+        let mut nonce = [0u8; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let mut header = Vec::with_capacity(HEADER_LENGTH + CIPHERTEXT_LENGTH);
+        header.extend_from_slice(&KEY_FILE_MAGIC);
+        header.push(KEY_FILE_VERSION);
+        header.push(KDF_ARGON2ID);
+        header.extend_from_slice(&params.memory_kib.to_be_bytes());
+        header.extend_from_slice(&params.iterations.to_be_bytes());
+        header.extend_from_slice(&params.parallelism.to_be_bytes());
+        header.extend_from_slice(&salt);
+        header.push(AEAD_AES_256_GCM);
+        header.extend_from_slice(&nonce);
+
+        let key = params.derive(passphrase, &salt)?;
+        let ciphertext = Aes256Gcm::new(key.as_ref().into())
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: &self.keypair_bytes, aad: &header })
+            .map_err(|_| ProtocolError::crypto("Key file encryption failed"))?;
+
+        let mut bytes = header;
+        bytes.extend_from_slice(&ciphertext);
+        Ok(bytes)
+    }
+
+    /// Import a keypair from a passphrase-protected key file
+    pub fn from_encrypted_bytes(bytes: &[u8], passphrase: &[u8]) -> errors::Result<Self> {
+        if bytes.len() != HEADER_LENGTH + CIPHERTEXT_LENGTH || bytes[..4] != KEY_FILE_MAGIC {
+            return Err(ProtocolError::Serialization("Not a key file".to_string()));
+        }
+        let (header, ciphertext) = bytes.split_at(HEADER_LENGTH);
+        if header[4] != KEY_FILE_VERSION {
+            return Err(ProtocolError::Serialization(format!("Unsupported key file version {}", header[4])));
+        }
+        if header[5] != KDF_ARGON2ID || header[34] != AEAD_AES_256_GCM {
+            return Err(ProtocolError::Serialization("Unsupported key file algorithms".to_string()));
+        }
+
+        let be_u32 = |offset: usize| u32::from_be_bytes(header[offset..offset + 4].try_into().expect("4 bytes"));
+        let params = KdfParams {
+            memory_kib: be_u32(6),
+            iterations: be_u32(10),
+            parallelism: be_u32(14),
+        };
+        let key = params.derive(passphrase, &header[18..18 + SALT_LENGTH])?;
+        let plaintext = Zeroizing::new(
+            Aes256Gcm::new(key.as_ref().into())
+                .decrypt(Nonce::from_slice(&header[35..]), Payload { msg: ciphertext, aad: header })
+                .map_err(|_| ProtocolError::crypto("Wrong passphrase or corrupted key file"))?,
+        );
+
+        Self::from_bytes(&plaintext).map_err(ProtocolError::crypto)
+    }
+
+    /// Write the keypair to a passphrase-protected key file
+    pub fn save_encrypted(&self, path: impl AsRef<Path>, passphrase: &[u8], params: KdfParams) -> errors::Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_encrypted_bytes(passphrase, params)?)
+            .map_err(|e| ProtocolError::invalid_state(format!("Failed to write key file {}: {}", path.display(), e)))
+    }
+
+    /// Read a keypair from a passphrase-protected key file
+    pub fn load_encrypted(path: impl AsRef<Path>, passphrase: &[u8]) -> errors::Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .map_err(|e| ProtocolError::invalid_state(format!("Failed to read key file {}: {}", path.display(), e)))?;
+        Self::from_encrypted_bytes(&bytes, passphrase)
+    }
 }
 
 // Implement Clone manually to ensure we don't accidentally expose key material
@@ -112,6 +344,58 @@ impl Clone for SecureKeypair {
     }
 }
 
+/// BIP-39 seed of a backup phrase, ignoring case and spacing
+pub(crate) fn mnemonic_seed(phrase: &str, passphrase: &str) -> errors::Result<Zeroizing<[u8; 64]>> {
+    let words = Zeroizing::new(phrase.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase());
+    let mnemonic = Mnemonic::parse(words.as_str())
+        .map_err(|e| ProtocolError::invalid_input(format!("Invalid mnemonic phrase: {}", e)))?;
+    Ok(Zeroizing::new(mnemonic.to_seed(passphrase)))
+}
+
+/// Derive an Ed25519 secret key from a seed along hardened SLIP-0010 indices
+pub(crate) fn derive_slip10_ed25519(seed: &[u8], path: &[u32]) -> Zeroizing<[u8; 32]> {
+    let hmac_sha512 = |key: &[u8], parts: &[&[u8]]| {
+        let mut mac = Hmac::<Sha512>::new_varkey(key).expect("HMAC accepts any key length");
+        for part in parts {
+            mac.update(part);
+        }
+        let mut output = mac.finalize().into_bytes();
+        let mut secret = Zeroizing::new([0u8; 32]);
+        let mut chain_code = Zeroizing::new([0u8; 32]);
+        secret.copy_from_slice(&output[..32]);
+        chain_code.copy_from_slice(&output[32..]);
+        output.zeroize();
+        (secret, chain_code)
+    };
+
+    let (mut secret, mut chain_code) = hmac_sha512(b"ed25519 seed", &[seed]);
+    for index in path {
+        let index = (index | HARDENED).to_be_bytes();
+        (secret, chain_code) = hmac_sha512(chain_code.as_ref(), &[&[0], secret.as_ref(), &index]);
+    }
+    secret
+}
+
+/// Generate a random BIP-39 backup phrase of 12 or 24 English words
+///
+/// Derive its keypair with [`SecureKeypair::from_mnemonic`].
+pub fn generate_mnemonic(word_count: usize) -> errors::Result<String> {
+    let mut entropy = Zeroizing::new(match word_count {
+        12 => vec![0u8; 16],
+        24 => vec![0u8; 32],
+        _ => {
+            return Err(ProtocolError::invalid_input(format!(
+                "Mnemonic phrases have 12 or 24 words, not {}",
+                word_count
+            )))
+        }
+    });
+    OsRng.fill_bytes(&mut entropy);
+    let mnemonic = Mnemonic::from_entropy(&entropy)
+        .map_err(|e| ProtocolError::crypto(format!("Mnemonic generation failed: {}", e)))?;
+    Ok(mnemonic.to_string())
+}
+
 // Legacy functions for backward compatibility
 // These now return regular Keypair instances for compatibility,
 // but users should migrate to SecureKeypair for better security
@@ -147,4 +431,148 @@ pub fn generate_secure_keypair() -> SecureKeypair {
 /// automatic memory protection for sensitive key material.
 pub fn generate_secure_keypair_with_seed(seed: u64) -> SecureKeypair {
     SecureKeypair::generate_with_seed(seed)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap KDF parameters so the tests stay fast
+    const TEST_PARAMS: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn key_file_roundtrip() {
+        let keypair = generate_secure_keypair_with_seed(8);
+        let path = std::env::temp_dir().join(format!("pm-key-file-{}.bin", std::process::id()));
+
+        keypair.save_encrypted(&path, b"passphrase", TEST_PARAMS).unwrap();
+        let loaded = SecureKeypair::load_encrypted(&path, b"passphrase");
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap().to_bytes(), keypair.to_bytes());
+    }
+
+    #[test]
+    fn key_file_layout() {
+        let bytes = generate_secure_keypair_with_seed(8)
+            .to_encrypted_bytes(b"passphrase", TEST_PARAMS)
+            .unwrap();
+
+        assert_eq!(bytes.len(), 127);
+        assert_eq!(&bytes[..6], b"PMKF\x01\x01");
+        assert_eq!(&bytes[6..18], [0, 0, 0, 64, 0, 0, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(bytes[34], AEAD_AES_256_GCM);
+    }
+
+    #[test]
+    fn tampered_key_file_is_rejected() {
+        let bytes = generate_secure_keypair_with_seed(8)
+            .to_encrypted_bytes(b"passphrase", TEST_PARAMS)
+            .unwrap();
+
+        // Downgrading the KDF cost changes the authenticated header
+        let mut downgraded = bytes.clone();
+        downgraded[13] = 2;
+        assert!(matches!(
+            SecureKeypair::from_encrypted_bytes(&downgraded, b"passphrase"),
+            Err(ProtocolError::Crypto(_))
+        ));
+
+        let mut future = bytes.clone();
+        future[4] = 2;
+        assert!(matches!(
+            SecureKeypair::from_encrypted_bytes(&future, b"passphrase"),
+            Err(ProtocolError::Serialization(_))
+        ));
+
+        assert!(SecureKeypair::from_encrypted_bytes(&bytes[..100], b"passphrase").is_err());
+    }
+
+    #[test]
+    fn kdf_costs_above_the_limits_are_rejected() {
+        let bytes = generate_secure_keypair_with_seed(8)
+            .to_encrypted_bytes(b"passphrase", TEST_PARAMS)
+            .unwrap();
+        let with_cost = |offset: usize, value: u32| {
+            let mut expensive = bytes.clone();
+            expensive[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+            SecureKeypair::from_encrypted_bytes(&expensive, b"passphrase")
+        };
+
+        assert!(matches!(
+            with_cost(6, MAX_KDF_MEMORY_KIB + 1),
+            Err(ProtocolError::KdfLimitExceeded { parameter: "memory_kib", limit: MAX_KDF_MEMORY_KIB, .. })
+        ));
+        assert!(matches!(
+            with_cost(10, MAX_KDF_ITERATIONS + 1),
+            Err(ProtocolError::KdfLimitExceeded { parameter: "iterations", limit: MAX_KDF_ITERATIONS, .. })
+        ));
+        assert!(matches!(
+            with_cost(14, u32::MAX),
+            Err(ProtocolError::KdfLimitExceeded { parameter: "parallelism", value: u32::MAX, limit: MAX_KDF_PARALLELISM })
+        ));
+    }
+
+    #[test]
+    fn kdf_costs_at_the_limits_are_accepted() {
+        let keypair = generate_secure_keypair_with_seed(8);
+        // Argon2 needs at least 8 KiB of memory per lane
+        let params = KdfParams { memory_kib: 8 * MAX_KDF_PARALLELISM, iterations: MAX_KDF_ITERATIONS, parallelism: MAX_KDF_PARALLELISM };
+
+        let bytes = keypair.to_encrypted_bytes(b"passphrase", params).unwrap();
+
+        assert_eq!(SecureKeypair::from_encrypted_bytes(&bytes, b"passphrase").unwrap().to_bytes(), keypair.to_bytes());
+    }
+
+    #[test]
+    fn slip10_matches_the_published_ed25519_vectors() {
+        // SLIP-0010 test vector 1 for ed25519
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+
+        assert_eq!(
+            hex::encode(derive_slip10_ed25519(&seed, &[]).as_ref()),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            hex::encode(derive_slip10_ed25519(&seed, &[0]).as_ref()),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+        assert_eq!(
+            hex::encode(derive_slip10_ed25519(&seed, &[0, 1]).as_ref()),
+            "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2"
+        );
+    }
+
+    #[test]
+    fn mnemonic_recovers_the_same_keypair() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+        let keypair = SecureKeypair::from_mnemonic(phrase, "").unwrap();
+        let recovered = SecureKeypair::from_mnemonic(&phrase.to_uppercase().replace(' ', "  "), "").unwrap();
+        let with_passphrase = SecureKeypair::from_mnemonic(phrase, "TREZOR").unwrap();
+
+        assert_eq!(recovered.to_bytes(), keypair.to_bytes());
+        assert_ne!(with_passphrase.public_key_bytes(), keypair.public_key_bytes());
+        let signature = keypair.sign(b"backup");
+        assert!(recovered.public_key().verify_strict(b"backup", &signature).is_ok());
+    }
+
+    #[test]
+    fn invalid_mnemonics_are_rejected() {
+        // The last word carries the checksum
+        let swapped = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about abandon";
+        assert!(matches!(
+            SecureKeypair::from_mnemonic(swapped, ""),
+            Err(ProtocolError::InvalidInput(_))
+        ));
+        assert!(SecureKeypair::from_mnemonic("abandon ability", "").is_err());
+        assert!(SecureKeypair::from_mnemonic("", "").is_err());
+
+        assert_eq!(generate_mnemonic(12).unwrap().split(' ').count(), 12);
+        assert_eq!(generate_mnemonic(24).unwrap().split(' ').count(), 24);
+        assert!(matches!(generate_mnemonic(13), Err(ProtocolError::InvalidInput(_))));
+    }
+}
@@ -9,7 +9,23 @@
 //!
 //! ## Features
 //! - Secure keypair generation with automatic memory protection (Ed25519 or PQC-ready)
-//! - Proof and invite flows
+//! - Hybrid Ed25519 + ML-DSA-65 dual-signature proofs (`pqc` feature)
+//! - Proof and invite flows, including single-use invite redemption
+//! - Group encryption with a shared sender key, rotated on membership change
+//! - Forward-secret 1:1 sessions (X3DH setup and Double Ratchet)
+//! - Signed delivery receipts for acknowledged messages
+//! - Signed message amendments chained by version hash
+//! - Proof chains committing to the parent proofs they depend on
+//! - Multi-signature (m-of-n) approvals bound to their signer policy
+//! - Detached proofs over external documents (SHA-256 or BLAKE3 digests)
+//! - Streaming BLAKE3 digests of large signed contexts
+//! - Golden test vectors shared by the CLI, web and relay test suites
+//! - Merkle transparency log proofs and signed tree heads
+//! - Relay-signed evidence bundles verifiable offline
+//! - Canonical JSON (RFC 8785) for signed contexts
+//! - Versioned proof envelopes with algorithm agility
+//! - Typed relay HTTP client with retries (`client` feature)
+//! - Compact CBOR wire format for relayed messages (`cbor` feature)
 //! - Message context and verification
 //! - Automatic zeroization of sensitive key material
 //! - Formal specification (TLA+), property-based and integration tests
@@ -52,13 +68,41 @@
 
 pub mod key;
 pub mod proof;
+pub mod invite;
+pub mod group;
+pub mod ratchet;
+pub mod receipt;
+pub mod amendment;
+pub mod chain;
+pub mod multisig;
+pub mod detached;
+pub mod derivation;
+pub mod context_digest;
+pub mod test_vectors;
+pub mod transparency;
+pub mod evidence;
+pub mod canonical;
+pub mod render;
+pub mod envelope;
 pub mod errors;
 pub mod compliance;
+#[cfg(feature = "pqc")]
+pub mod hybrid;
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod relay_client;
+#[cfg(feature = "cbor")]
+pub mod wire;
+#[cfg(unix)]
+pub mod remote_signer;
 
 // Property-based tests for proof error handling
 #[cfg(test)]
 mod proof_property_tests;
 
+// Property-based tests for Double Ratchet message delivery
+#[cfg(test)]
+mod ratchet_property_tests;
+
 // Add more as your protocol evolves (message, group, recovery, etc.)
 
 #[cfg(test)]
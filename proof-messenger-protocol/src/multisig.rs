@@ -0,0 +1,284 @@
+//! Multi-signature approvals: payloads that need m of n keys to sign
+//!
+//! A [`MultisigPolicy`] names the keys allowed to approve and how many of
+//! them must. Every co-signer signs the same [`multisig_context`], which binds
+//! the policy (threshold and signer set) ahead of the payload, so a
+//! signature collected under one policy cannot be replayed under another.
+//! [`verify_multisig`] accepts a proof once the threshold of distinct,
+//! valid co-signatures from policy keys is met.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//! use proof_messenger_protocol::multisig::{cosign, verify_multisig, MultisigPolicy, MultisigProof};
+//!
+//! let alice = generate_secure_keypair();
+//! let bob = generate_secure_keypair();
+//! let carol = generate_secure_keypair();
+//! let policy = MultisigPolicy::new(2, vec![alice.public_key(), bob.public_key(), carol.public_key()]).unwrap();
+//!
+//! let mut proof = MultisigProof::new(policy.clone(), b"transfer 50000 to acme".to_vec());
+//! proof.add_signature(cosign(&alice, &policy, b"transfer 50000 to acme").unwrap()).unwrap();
+//! assert!(!proof.is_complete());
+//! proof.add_signature(cosign(&carol, &policy, b"transfer 50000 to acme").unwrap()).unwrap();
+//!
+//! assert_eq!(verify_multisig(&proof).unwrap(), 2);
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+use crate::key::SecureKeypair;
+use crate::proof::{make_secure_proof, verify_proof_result, ProofError};
+
+/// Most keys a single policy may name
+pub const MAX_MULTISIG_SIGNERS: usize = 16;
+
+/// Domain separation prefix for multisig contexts
+const MULTISIG_DOMAIN: &[u8] = b"proof-messenger/multisig/v1";
+
+/// Which keys may approve a payload, and how many must
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigPolicy {
+    /// Number of distinct signers required (`m`)
+    pub threshold: usize,
+    /// Keys allowed to sign (`n`), in policy order
+    pub signers: Vec<PublicKey>,
+}
+
+impl MultisigPolicy {
+    /// Create an m-of-n policy
+    ///
+    /// The threshold must be between 1 and the number of signers, and there
+    /// may be at most [`MAX_MULTISIG_SIGNERS`] distinct signers.
+    pub fn new(threshold: usize, signers: Vec<PublicKey>) -> Result<Self, ProofError> {
+        if signers.is_empty() || signers.len() > MAX_MULTISIG_SIGNERS {
+            return Err(ProofError::InvalidInput(format!(
+                "A multisig policy needs 1 to {} signers (got {})",
+                MAX_MULTISIG_SIGNERS,
+                signers.len()
+            )));
+        }
+        if threshold == 0 || threshold > signers.len() {
+            return Err(ProofError::InvalidInput(format!(
+                "Threshold must be between 1 and {} (got {})",
+                signers.len(),
+                threshold
+            )));
+        }
+        if signers.iter().map(PublicKey::as_bytes).collect::<HashSet<_>>().len() != signers.len() {
+            return Err(ProofError::InvalidInput("Multisig signers must be distinct".to_string()));
+        }
+        Ok(Self { threshold, signers })
+    }
+
+    /// Whether `key` is one of the policy's signers
+    pub fn contains(&self, key: &PublicKey) -> bool {
+        self.signers.contains(key)
+    }
+
+    /// SHA-256 of the policy's threshold and signers, identifying it compactly
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(MULTISIG_DOMAIN);
+        hasher.update(policy_bytes(self));
+        hasher.finalize().into()
+    }
+}
+
+/// One signer's approval of a multisig payload
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoSignature {
+    /// Public key of the co-signer
+    pub signer: PublicKey,
+    /// Signature over the [`multisig_context`]
+    pub signature: Signature,
+}
+
+/// A payload and the co-signatures collected for it so far
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultisigProof {
+    /// Policy the payload is approved under
+    pub policy: MultisigPolicy,
+    /// Application payload being approved
+    pub payload: Vec<u8>,
+    /// Co-signatures collected so far, in the order added
+    pub signatures: Vec<CoSignature>,
+}
+
+impl MultisigProof {
+    /// Start collecting signatures for `payload` under `policy`
+    pub fn new(policy: MultisigPolicy, payload: Vec<u8>) -> Self {
+        Self { policy, payload, signatures: Vec::new() }
+    }
+
+    /// The context every co-signer signs
+    pub fn context(&self) -> Vec<u8> {
+        multisig_context(&self.policy, &self.payload)
+    }
+
+    /// Verify a co-signature and add it
+    ///
+    /// Fails if the signer is not in the policy, has already signed, or the
+    /// signature does not cover this proof's context.
+    pub fn add_signature(&mut self, signature: CoSignature) -> Result<(), ProofError> {
+        if !self.policy.contains(&signature.signer) {
+            return Err(ProofError::InvalidData("Signer is not part of the multisig policy".to_string()));
+        }
+        if self.signatures.iter().any(|existing| existing.signer == signature.signer) {
+            return Err(ProofError::InvalidData("Signer has already signed".to_string()));
+        }
+        verify_proof_result(&signature.signer, &self.context(), &signature.signature)?;
+        self.signatures.push(signature);
+        Ok(())
+    }
+
+    /// Whether enough signers have signed to meet the threshold
+    pub fn is_complete(&self) -> bool {
+        self.signatures.len() >= self.policy.threshold
+    }
+}
+
+/// Encode a policy's threshold and signers
+fn policy_bytes(policy: &MultisigPolicy) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(2 + policy.signers.len() * 32);
+    bytes.push(policy.threshold as u8);
+    bytes.push(policy.signers.len() as u8);
+    for signer in &policy.signers {
+        bytes.extend_from_slice(signer.as_bytes());
+    }
+    bytes
+}
+
+/// Build the context co-signers sign: the policy followed by `payload`
+pub fn multisig_context(policy: &MultisigPolicy, payload: &[u8]) -> Vec<u8> {
+    let policy = policy_bytes(policy);
+    let mut context = Vec::with_capacity(MULTISIG_DOMAIN.len() + 1 + policy.len() + payload.len());
+    context.extend_from_slice(MULTISIG_DOMAIN);
+    context.push(0);
+    context.extend_from_slice(&policy);
+    context.extend_from_slice(payload);
+    context
+}
+
+/// Sign `payload` as one of the signers of `policy`
+pub fn cosign(keypair: &SecureKeypair, policy: &MultisigPolicy, payload: &[u8]) -> Result<CoSignature, ProofError> {
+    let signer = keypair.public_key();
+    if !policy.contains(&signer) {
+        return Err(ProofError::InvalidInput("Key is not part of the multisig policy".to_string()));
+    }
+    let signature = make_secure_proof(keypair, &multisig_context(policy, payload))?;
+    Ok(CoSignature { signer, signature })
+}
+
+/// Verify a multisig proof meets its policy's threshold
+///
+/// Every co-signature must come from a distinct policy signer and verify
+/// against the proof's context. Returns the number of valid signatures.
+pub fn verify_multisig(proof: &MultisigProof) -> Result<usize, ProofError> {
+    let policy = MultisigPolicy::new(proof.policy.threshold, proof.policy.signers.clone())?;
+    let context = proof.context();
+    let mut seen = HashSet::new();
+
+    for signature in &proof.signatures {
+        if !policy.contains(&signature.signer) {
+            return Err(ProofError::InvalidData("Signer is not part of the multisig policy".to_string()));
+        }
+        if !seen.insert(signature.signer.to_bytes()) {
+            return Err(ProofError::InvalidData("Signer appears more than once".to_string()));
+        }
+        verify_proof_result(&signature.signer, &context, &signature.signature)?;
+    }
+
+    if seen.len() < policy.threshold {
+        return Err(ProofError::InvalidData(format!(
+            "{} of {} required signatures present",
+            seen.len(),
+            policy.threshold
+        )));
+    }
+    Ok(seen.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+
+    fn signers() -> Vec<SecureKeypair> {
+        (1..=3).map(generate_secure_keypair_with_seed).collect()
+    }
+
+    fn two_of_three(keys: &[SecureKeypair]) -> MultisigPolicy {
+        MultisigPolicy::new(2, keys.iter().map(SecureKeypair::public_key).collect()).unwrap()
+    }
+
+    #[test]
+    fn threshold_is_enforced() {
+        let keys = signers();
+        let policy = two_of_three(&keys);
+        let mut proof = MultisigProof::new(policy.clone(), b"transfer".to_vec());
+
+        proof.add_signature(cosign(&keys[0], &policy, b"transfer").unwrap()).unwrap();
+        assert!(!proof.is_complete());
+        assert!(verify_multisig(&proof).is_err());
+
+        proof.add_signature(cosign(&keys[2], &policy, b"transfer").unwrap()).unwrap();
+        assert!(proof.is_complete());
+        assert_eq!(verify_multisig(&proof).unwrap(), 2);
+    }
+
+    #[test]
+    fn invalid_policies_are_rejected() {
+        let keys: Vec<PublicKey> = signers().iter().map(SecureKeypair::public_key).collect();
+
+        assert!(MultisigPolicy::new(0, keys.clone()).is_err());
+        assert!(MultisigPolicy::new(4, keys.clone()).is_err());
+        assert!(MultisigPolicy::new(1, vec![keys[0], keys[0]]).is_err());
+        assert!(MultisigPolicy::new(1, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn duplicate_and_outside_signers_are_rejected() {
+        let keys = signers();
+        let policy = two_of_three(&keys);
+        let outsider = generate_secure_keypair_with_seed(9);
+        let mut proof = MultisigProof::new(policy.clone(), b"transfer".to_vec());
+        let first = cosign(&keys[0], &policy, b"transfer").unwrap();
+
+        proof.add_signature(first.clone()).unwrap();
+
+        assert!(proof.add_signature(first.clone()).is_err());
+        assert!(cosign(&outsider, &policy, b"transfer").is_err());
+        // Verification rejects a duplicate smuggled into the signature list
+        proof.signatures.push(first);
+        assert!(verify_multisig(&proof).is_err());
+    }
+
+    #[test]
+    fn signature_under_another_policy_is_rejected() {
+        let keys = signers();
+        let policy = two_of_three(&keys);
+        let weaker = MultisigPolicy::new(1, policy.signers.clone()).unwrap();
+        let mut proof = MultisigProof::new(policy, b"transfer".to_vec());
+
+        let signature = cosign(&keys[0], &weaker, b"transfer").unwrap();
+
+        assert!(proof.add_signature(signature).is_err());
+        assert_ne!(weaker.hash(), proof.policy.hash());
+    }
+
+    #[test]
+    fn tampered_payload_fails_verification() {
+        let keys = signers();
+        let policy = two_of_three(&keys);
+        let mut proof = MultisigProof::new(policy.clone(), b"transfer 10".to_vec());
+        proof.add_signature(cosign(&keys[0], &policy, b"transfer 10").unwrap()).unwrap();
+        proof.add_signature(cosign(&keys[1], &policy, b"transfer 10").unwrap()).unwrap();
+
+        proof.payload = b"transfer 10000".to_vec();
+
+        assert!(verify_multisig(&proof).is_err());
+    }
+}
@@ -28,6 +28,10 @@ pub enum ProofError {
     /// Context data is empty when it shouldn't be
     #[error("Context data cannot be empty")]
     EmptyContext,
+    
+    /// Post-quantum half of a hybrid proof failed verification
+    #[error("Proof verification failed: invalid post-quantum signature")]
+    PqcVerificationFailed,
 }
 
 #[derive(Clone)]
@@ -62,7 +66,7 @@ impl Invite {
 }
 
 /// Validate context data for security constraints
-fn validate_context_data(data: &[u8]) -> Result<(), ProofError> {
+pub(crate) fn validate_context_data(data: &[u8]) -> Result<(), ProofError> {
     if data.len() > MAX_CONTEXT_SIZE {
         return Err(ProofError::ContextTooLarge {
             max: MAX_CONTEXT_SIZE,
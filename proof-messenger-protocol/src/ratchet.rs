@@ -0,0 +1,641 @@
+//! Forward-secret 1:1 sessions: X3DH key agreement and the Double Ratchet
+//!
+//! A responder publishes a [`PrekeyBundle`]: an X25519 identity key, a
+//! signed prekey (signed with their Ed25519 proof key) and an optional
+//! one-time prekey. An initiator runs an X3DH-style agreement against the
+//! bundle to derive a shared secret without the responder being online, and
+//! sends the resulting [`X3dhHeader`] with their first message.
+//!
+//! Both sides then run the Double Ratchet: every message is encrypted under
+//! a fresh message key from a symmetric chain, and every change of speaker
+//! mixes a new X25519 exchange into the root key. Compromising the current
+//! [`Session`] state exposes neither past messages nor, once the other side
+//! has replied, future ones. Messages may arrive out of order; keys for
+//! skipped messages are kept until they are used.
+//!
+//! [`Session`] serializes to JSON so it can be persisted between runs.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//! use proof_messenger_protocol::ratchet::{IdentityKey, PrekeySecrets, Session};
+//!
+//! // Bob publishes a bundle signed with his proof key
+//! let mut bob_prekeys = PrekeySecrets::generate(IdentityKey::generate());
+//! let bundle = bob_prekeys.bundle(&generate_secure_keypair());
+//!
+//! // Alice starts a session from the bundle; Bob accepts her header
+//! let (mut alice, header) = Session::initiate(&IdentityKey::generate(), &bundle).unwrap();
+//! let mut bob = Session::respond(&mut bob_prekeys, &header).unwrap();
+//!
+//! let first = alice.encrypt(b"hi bob").unwrap();
+//! let second = alice.encrypt(b"are you there?").unwrap();
+//! assert_eq!(bob.decrypt(&second).unwrap(), b"are you there?");
+//! assert_eq!(bob.decrypt(&first).unwrap(), b"hi bob");
+//!
+//! let reply = bob.encrypt(b"hi alice").unwrap();
+//! assert_eq!(alice.decrypt(&reply).unwrap(), b"hi alice");
+//! ```
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use ed25519_dalek::Verifier;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac, NewMac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::key::SecureKeypair;
+
+/// Length of X25519 keys, root keys and chain keys in bytes
+pub const RATCHET_KEY_LENGTH: usize = 32;
+
+/// Most message keys that may be skipped within one receiving chain
+pub const MAX_SKIP: u32 = 1000;
+
+/// Most skipped message keys a session keeps before dropping the oldest
+pub const MAX_STORED_SKIPPED_KEYS: usize = 2000;
+
+/// Domain separation prefix for signed prekeys
+const SIGNED_PREKEY_DOMAIN: &[u8] = b"proof-messenger/signed-prekey/v1";
+
+/// HKDF info strings
+const X3DH_INFO: &[u8] = b"proof-messenger/x3dh/v1";
+const ROOT_INFO: &[u8] = b"proof-messenger/ratchet-root/v1";
+const MESSAGE_INFO: &[u8] = b"proof-messenger/ratchet-message/v1";
+
+type Key = [u8; RATCHET_KEY_LENGTH];
+
+/// Errors raised by session setup and the ratchet
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RatchetError {
+    /// The signed prekey's signature does not verify
+    #[error("Invalid signed prekey signature")]
+    InvalidPrekeySignature,
+
+    /// A public key is not usable for key agreement
+    #[error("Invalid public key")]
+    InvalidPublicKey,
+
+    /// The initiator used a one-time prekey the responder no longer has
+    #[error("Unknown or already used one-time prekey")]
+    UnknownOneTimePrekey,
+
+    /// The responder cannot send before receiving the first message
+    #[error("Session cannot send until it has received a message")]
+    NotReady,
+
+    /// A message claims more skipped messages than allowed
+    #[error("Too many skipped messages: {0}")]
+    TooManySkipped(u32),
+
+    /// Authentication failed: wrong session, tampered data or a replay
+    #[error("Decryption failed")]
+    DecryptionFailed,
+}
+
+/// A long-term X25519 identity key for session setup
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct IdentityKey {
+    secret: Key,
+}
+
+impl IdentityKey {
+    /// Generate a new identity key using cryptographically secure randomness
+    pub fn generate() -> Self {
+        Self { secret: random_key() }
+    }
+
+    /// Restore an identity key from its 32 secret bytes
+    pub fn from_bytes(secret: Key) -> Self {
+        Self { secret }
+    }
+
+    /// The 32 secret bytes
+    ///
+    /// ⚠️  WARNING: The returned bytes contain sensitive key material.
+    pub fn to_bytes(&self) -> Key {
+        self.secret
+    }
+
+    /// The X25519 public identity key
+    pub fn public_key(&self) -> Key {
+        public_of(&self.secret)
+    }
+}
+
+/// The responder's private prekeys, matching a published [`PrekeyBundle`]
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct PrekeySecrets {
+    identity: IdentityKey,
+    signed_prekey: Key,
+    one_time_prekey: Option<Key>,
+}
+
+impl PrekeySecrets {
+    /// Generate a signed prekey and one one-time prekey for an identity
+    pub fn generate(identity: IdentityKey) -> Self {
+        Self {
+            identity,
+            signed_prekey: random_key(),
+            one_time_prekey: Some(random_key()),
+        }
+    }
+
+    /// Public bundle to publish, with the prekey signed by `signing_key`
+    pub fn bundle(&self, signing_key: &SecureKeypair) -> PrekeyBundle {
+        let signed_prekey = public_of(&self.signed_prekey);
+        PrekeyBundle {
+            identity_key: self.identity.public_key(),
+            signed_prekey,
+            prekey_signature: signing_key.sign(&signed_prekey_context(&signed_prekey)).to_bytes().to_vec(),
+            signing_key: signing_key.public_key_bytes(),
+            one_time_prekey: self.one_time_prekey.as_ref().map(public_of),
+        }
+    }
+}
+
+/// A responder's published keys for starting sessions with them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrekeyBundle {
+    /// X25519 identity key
+    #[serde(with = "hex::serde")]
+    pub identity_key: Key,
+    /// X25519 signed prekey
+    #[serde(with = "hex::serde")]
+    pub signed_prekey: Key,
+    /// Ed25519 signature over the signed prekey
+    #[serde(with = "hex::serde")]
+    pub prekey_signature: Vec<u8>,
+    /// Ed25519 proof key that signed the prekey
+    ///
+    /// Callers must check this is the key they expect for the responder.
+    #[serde(with = "hex::serde")]
+    pub signing_key: Key,
+    /// Optional X25519 one-time prekey
+    #[serde(default, with = "hex_option", skip_serializing_if = "Option::is_none")]
+    pub one_time_prekey: Option<Key>,
+}
+
+impl PrekeyBundle {
+    /// Check the signed prekey's signature
+    pub fn verify(&self) -> Result<(), RatchetError> {
+        let signing_key = ed25519_dalek::PublicKey::from_bytes(&self.signing_key)
+            .map_err(|_| RatchetError::InvalidPrekeySignature)?;
+        let signature = ed25519_dalek::Signature::from_bytes(&self.prekey_signature)
+            .map_err(|_| RatchetError::InvalidPrekeySignature)?;
+        signing_key
+            .verify(&signed_prekey_context(&self.signed_prekey), &signature)
+            .map_err(|_| RatchetError::InvalidPrekeySignature)
+    }
+}
+
+/// Keys the initiator sends with the first message so the responder can
+/// complete the agreement
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct X3dhHeader {
+    /// Initiator's X25519 identity key
+    #[serde(with = "hex::serde")]
+    pub identity_key: Key,
+    /// Initiator's one-time X25519 key
+    #[serde(with = "hex::serde")]
+    pub ephemeral_key: Key,
+    /// The responder's one-time prekey that was used, if any
+    #[serde(default, with = "hex_option", skip_serializing_if = "Option::is_none")]
+    pub one_time_prekey: Option<Key>,
+}
+
+/// Ratchet header sent in the clear with each message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageHeader {
+    /// Sender's current ratchet public key
+    #[serde(with = "hex::serde")]
+    pub ratchet_key: Key,
+    /// Number of messages in the sender's previous sending chain
+    pub previous_chain_length: u32,
+    /// Index of this message in the sending chain
+    pub message_number: u32,
+}
+
+/// An encrypted 1:1 message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RatchetMessage {
+    pub header: MessageHeader,
+    /// AES-256-GCM ciphertext and tag
+    #[serde(with = "hex::serde")]
+    pub ciphertext: Vec<u8>,
+}
+
+/// Message key for a message that has not arrived yet
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+#[serde(rename_all = "camelCase")]
+struct SkippedKey {
+    #[serde(with = "hex::serde")]
+    ratchet_key: Key,
+    message_number: u32,
+    #[serde(with = "hex::serde")]
+    message_key: Key,
+}
+
+/// One side of a Double Ratchet session
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    /// Both identity keys, bound into every message
+    #[serde(with = "hex::serde")]
+    associated_data: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    root_key: Key,
+    #[serde(with = "hex::serde")]
+    ratchet_secret: Key,
+    #[serde(with = "hex::serde")]
+    ratchet_public: Key,
+    #[serde(with = "hex_option")]
+    remote_ratchet_key: Option<Key>,
+    #[serde(with = "hex_option")]
+    sending_chain: Option<Key>,
+    #[serde(with = "hex_option")]
+    receiving_chain: Option<Key>,
+    sent: u32,
+    received: u32,
+    previous_chain_length: u32,
+    skipped: Vec<SkippedKey>,
+}
+
+impl Session {
+    /// Start a session with the owner of `bundle`
+    pub fn initiate(identity: &IdentityKey, bundle: &PrekeyBundle) -> Result<(Self, X3dhHeader), RatchetError> {
+        bundle.verify()?;
+        let ephemeral = Zeroizing::new(random_key());
+
+        let mut secrets = Zeroizing::new(Vec::with_capacity(4 * RATCHET_KEY_LENGTH));
+        secrets.extend_from_slice(dh(&identity.secret, &bundle.signed_prekey)?.as_slice());
+        secrets.extend_from_slice(dh(&ephemeral, &bundle.identity_key)?.as_slice());
+        secrets.extend_from_slice(dh(&ephemeral, &bundle.signed_prekey)?.as_slice());
+        if let Some(one_time_prekey) = &bundle.one_time_prekey {
+            secrets.extend_from_slice(dh(&ephemeral, one_time_prekey)?.as_slice());
+        }
+        let shared = x3dh_secret(&secrets);
+
+        let ratchet_secret = random_key();
+        let (root_key, sending_chain) = kdf_root(&shared, &*dh(&ratchet_secret, &bundle.signed_prekey)?);
+        let session = Self {
+            associated_data: [identity.public_key(), bundle.identity_key].concat(),
+            root_key,
+            ratchet_secret,
+            ratchet_public: public_of(&ratchet_secret),
+            remote_ratchet_key: Some(bundle.signed_prekey),
+            sending_chain: Some(sending_chain),
+            receiving_chain: None,
+            sent: 0,
+            received: 0,
+            previous_chain_length: 0,
+            skipped: Vec::new(),
+        };
+        let header = X3dhHeader {
+            identity_key: identity.public_key(),
+            ephemeral_key: public_of(&ephemeral),
+            one_time_prekey: bundle.one_time_prekey,
+        };
+        Ok((session, header))
+    }
+
+    /// Accept a session started from our bundle, consuming the one-time prekey
+    pub fn respond(prekeys: &mut PrekeySecrets, header: &X3dhHeader) -> Result<Self, RatchetError> {
+        let one_time_prekey = match header.one_time_prekey {
+            Some(public) => match prekeys.one_time_prekey {
+                Some(secret) if public_of(&secret) == public => Some(secret),
+                _ => return Err(RatchetError::UnknownOneTimePrekey),
+            },
+            None => None,
+        };
+
+        let mut secrets = Zeroizing::new(Vec::with_capacity(4 * RATCHET_KEY_LENGTH));
+        secrets.extend_from_slice(dh(&prekeys.signed_prekey, &header.identity_key)?.as_slice());
+        secrets.extend_from_slice(dh(&prekeys.identity.secret, &header.ephemeral_key)?.as_slice());
+        secrets.extend_from_slice(dh(&prekeys.signed_prekey, &header.ephemeral_key)?.as_slice());
+        if let Some(secret) = &one_time_prekey {
+            secrets.extend_from_slice(dh(secret, &header.ephemeral_key)?.as_slice());
+            prekeys.one_time_prekey.zeroize();
+            prekeys.one_time_prekey = None;
+        }
+
+        Ok(Self {
+            associated_data: [header.identity_key, prekeys.identity.public_key()].concat(),
+            root_key: *x3dh_secret(&secrets),
+            ratchet_secret: prekeys.signed_prekey,
+            ratchet_public: public_of(&prekeys.signed_prekey),
+            remote_ratchet_key: None,
+            sending_chain: None,
+            receiving_chain: None,
+            sent: 0,
+            received: 0,
+            previous_chain_length: 0,
+            skipped: Vec::new(),
+        })
+    }
+
+    /// Encrypt the next outgoing message
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<RatchetMessage, RatchetError> {
+        let chain = self.sending_chain.as_ref().ok_or(RatchetError::NotReady)?;
+        let (next_chain, message_key) = kdf_chain(chain);
+        let header = MessageHeader {
+            ratchet_key: self.ratchet_public,
+            previous_chain_length: self.previous_chain_length,
+            message_number: self.sent,
+        };
+        let ciphertext = seal(&message_key, &self.message_aad(&header), plaintext)?;
+
+        self.sending_chain = Some(next_chain);
+        self.sent += 1;
+        Ok(RatchetMessage { header, ciphertext })
+    }
+
+    /// Decrypt an incoming message
+    ///
+    /// The session is only updated if the message authenticates, so a
+    /// forged or corrupted message cannot desynchronize it.
+    pub fn decrypt(&mut self, message: &RatchetMessage) -> Result<Vec<u8>, RatchetError> {
+        let mut next = self.clone();
+        let plaintext = next.decrypt_in_place(message)?;
+        *self = next;
+        Ok(plaintext)
+    }
+
+    fn decrypt_in_place(&mut self, message: &RatchetMessage) -> Result<Vec<u8>, RatchetError> {
+        let header = &message.header;
+        let aad = self.message_aad(header);
+
+        if let Some(index) = self
+            .skipped
+            .iter()
+            .position(|key| key.ratchet_key == header.ratchet_key && key.message_number == header.message_number)
+        {
+            let skipped = self.skipped.remove(index);
+            return open(&skipped.message_key, &aad, &message.ciphertext);
+        }
+
+        if self.remote_ratchet_key != Some(header.ratchet_key) {
+            self.skip_until(header.previous_chain_length)?;
+            self.ratchet_step(&header.ratchet_key)?;
+        }
+        self.skip_until(header.message_number)?;
+
+        let chain = self.receiving_chain.as_ref().ok_or(RatchetError::DecryptionFailed)?;
+        let (next_chain, message_key) = kdf_chain(chain);
+        self.receiving_chain = Some(next_chain);
+        self.received += 1;
+        open(&message_key, &aad, &message.ciphertext)
+    }
+
+    /// Store keys for receiving-chain messages before `until`
+    fn skip_until(&mut self, until: u32) -> Result<(), RatchetError> {
+        let (Some(mut chain), Some(ratchet_key)) = (self.receiving_chain, self.remote_ratchet_key) else {
+            return Ok(());
+        };
+        if until > self.received.saturating_add(MAX_SKIP) {
+            return Err(RatchetError::TooManySkipped(until - self.received));
+        }
+        while self.received < until {
+            let (next_chain, message_key) = kdf_chain(&chain);
+            self.skipped.push(SkippedKey {
+                ratchet_key,
+                message_number: self.received,
+                message_key,
+            });
+            chain = next_chain;
+            self.received += 1;
+        }
+        if self.skipped.len() > MAX_STORED_SKIPPED_KEYS {
+            let excess = self.skipped.len() - MAX_STORED_SKIPPED_KEYS;
+            self.skipped.drain(..excess);
+        }
+        self.receiving_chain = Some(chain);
+        Ok(())
+    }
+
+    /// Mix a new remote ratchet key into the root key and start new chains
+    fn ratchet_step(&mut self, remote: &Key) -> Result<(), RatchetError> {
+        let (root_key, receiving_chain) = kdf_root(&self.root_key, &*dh(&self.ratchet_secret, remote)?);
+        let ratchet_secret = random_key();
+        let (root_key, sending_chain) = kdf_root(&root_key, &*dh(&ratchet_secret, remote)?);
+
+        self.previous_chain_length = self.sent;
+        self.sent = 0;
+        self.received = 0;
+        self.remote_ratchet_key = Some(*remote);
+        self.ratchet_secret = ratchet_secret;
+        self.ratchet_public = public_of(&ratchet_secret);
+        self.root_key = root_key;
+        self.receiving_chain = Some(receiving_chain);
+        self.sending_chain = Some(sending_chain);
+        Ok(())
+    }
+
+    fn message_aad(&self, header: &MessageHeader) -> Vec<u8> {
+        let mut aad = self.associated_data.clone();
+        aad.extend_from_slice(&header.ratchet_key);
+        aad.extend_from_slice(&header.previous_chain_length.to_be_bytes());
+        aad.extend_from_slice(&header.message_number.to_be_bytes());
+        aad
+    }
+}
+
+fn random_key() -> Key {
+    let mut key = [0u8; RATCHET_KEY_LENGTH];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+fn public_of(secret: &Key) -> Key {
+    PublicKey::from(&StaticSecret::from(*secret)).to_bytes()
+}
+
+fn signed_prekey_context(signed_prekey: &Key) -> Vec<u8> {
+    [SIGNED_PREKEY_DOMAIN, signed_prekey.as_slice()].concat()
+}
+
+/// X25519 exchange, rejecting low-order points
+fn dh(secret: &Key, public: &Key) -> Result<Zeroizing<Key>, RatchetError> {
+    let shared = StaticSecret::from(*secret).diffie_hellman(&PublicKey::from(*public));
+    if !shared.was_contributory() {
+        return Err(RatchetError::InvalidPublicKey);
+    }
+    Ok(Zeroizing::new(shared.to_bytes()))
+}
+
+/// Combine the X3DH exchanges into the initial root key
+fn x3dh_secret(secrets: &[u8]) -> Zeroizing<Key> {
+    // 32 0xFF bytes keep X3DH inputs distinct from an X25519 output
+    let input = Zeroizing::new([[0xFF; RATCHET_KEY_LENGTH].as_slice(), secrets].concat());
+    let mut key = Zeroizing::new([0u8; RATCHET_KEY_LENGTH]);
+    Hkdf::<Sha256>::new(Some(&[0u8; RATCHET_KEY_LENGTH]), &input)
+        .expand(X3DH_INFO, key.as_mut())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+/// Root chain step: (root key, DH output) -> (new root key, chain key)
+fn kdf_root(root_key: &Key, dh_output: &Key) -> (Key, Key) {
+    let mut output = Zeroizing::new([0u8; 2 * RATCHET_KEY_LENGTH]);
+    Hkdf::<Sha256>::new(Some(root_key), dh_output)
+        .expand(ROOT_INFO, output.as_mut())
+        .expect("64 bytes is a valid HKDF-SHA256 output length");
+    split(&output)
+}
+
+/// Symmetric chain step: chain key -> (next chain key, message key)
+fn kdf_chain(chain_key: &Key) -> (Key, Key) {
+    let step = |constant: u8| -> Key {
+        let mut mac = <Hmac<Sha256> as NewMac>::new_varkey(chain_key).expect("HMAC accepts any key length");
+        mac.update(&[constant]);
+        mac.finalize().into_bytes().into()
+    };
+    (step(0x02), step(0x01))
+}
+
+fn split(output: &[u8; 2 * RATCHET_KEY_LENGTH]) -> (Key, Key) {
+    let mut first = [0u8; RATCHET_KEY_LENGTH];
+    let mut second = [0u8; RATCHET_KEY_LENGTH];
+    first.copy_from_slice(&output[..RATCHET_KEY_LENGTH]);
+    second.copy_from_slice(&output[RATCHET_KEY_LENGTH..]);
+    (first, second)
+}
+
+/// AES-256-GCM key and nonce for a message key
+fn message_cipher(message_key: &Key) -> (Aes256Gcm, [u8; 12]) {
+    let mut output = Zeroizing::new([0u8; RATCHET_KEY_LENGTH + 12]);
+    Hkdf::<Sha256>::new(None, message_key)
+        .expand(MESSAGE_INFO, output.as_mut())
+        .expect("44 bytes is a valid HKDF-SHA256 output length");
+    let cipher = Aes256Gcm::new_from_slice(&output[..RATCHET_KEY_LENGTH]).expect("32 byte AES key");
+    let mut nonce = [0u8; 12];
+    nonce.copy_from_slice(&output[RATCHET_KEY_LENGTH..]);
+    (cipher, nonce)
+}
+
+fn seal(message_key: &Key, aad: &[u8], msg: &[u8]) -> Result<Vec<u8>, RatchetError> {
+    let (cipher, nonce) = message_cipher(message_key);
+    cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg, aad })
+        .map_err(|_| RatchetError::DecryptionFailed)
+}
+
+fn open(message_key: &Key, aad: &[u8], msg: &[u8]) -> Result<Vec<u8>, RatchetError> {
+    let (cipher, nonce) = message_cipher(message_key);
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), Payload { msg, aad })
+        .map_err(|_| RatchetError::DecryptionFailed)
+}
+
+/// Hex (de)serialization of optional keys
+mod hex_option {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(key: &Option<super::Key>, serializer: S) -> Result<S::Ok, S::Error> {
+        match key {
+            Some(key) => serializer.serialize_some(&hex::encode(key)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<super::Key>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|hex_key| {
+                let mut key = [0u8; super::RATCHET_KEY_LENGTH];
+                hex::decode_to_slice(hex_key, &mut key).map_err(serde::de::Error::custom)?;
+                Ok(key)
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+
+    /// Alice's and Bob's sessions, with Alice's first message already delivered
+    fn established() -> (Session, Session) {
+        let mut prekeys = PrekeySecrets::generate(IdentityKey::generate());
+        let bundle = prekeys.bundle(&generate_secure_keypair_with_seed(1));
+        let (mut alice, header) = Session::initiate(&IdentityKey::generate(), &bundle).unwrap();
+        let mut bob = Session::respond(&mut prekeys, &header).unwrap();
+        let hello = alice.encrypt(b"hello").unwrap();
+        assert_eq!(bob.decrypt(&hello).unwrap(), b"hello");
+        (alice, bob)
+    }
+
+    #[test]
+    fn forged_prekey_signature_is_rejected() {
+        let prekeys = PrekeySecrets::generate(IdentityKey::generate());
+        let mut bundle = prekeys.bundle(&generate_secure_keypair_with_seed(1));
+        bundle.signed_prekey = IdentityKey::generate().public_key();
+
+        assert_eq!(
+            Session::initiate(&IdentityKey::generate(), &bundle).err(),
+            Some(RatchetError::InvalidPrekeySignature)
+        );
+    }
+
+    #[test]
+    fn one_time_prekey_is_single_use() {
+        let mut prekeys = PrekeySecrets::generate(IdentityKey::generate());
+        let bundle = prekeys.bundle(&generate_secure_keypair_with_seed(1));
+        let (_, header) = Session::initiate(&IdentityKey::generate(), &bundle).unwrap();
+
+        assert!(Session::respond(&mut prekeys, &header).is_ok());
+        assert_eq!(Session::respond(&mut prekeys, &header).err(), Some(RatchetError::UnknownOneTimePrekey));
+        assert_eq!(prekeys.bundle(&generate_secure_keypair_with_seed(1)).one_time_prekey, None);
+    }
+
+    #[test]
+    fn responder_cannot_send_first() {
+        let mut prekeys = PrekeySecrets::generate(IdentityKey::generate());
+        let bundle = prekeys.bundle(&generate_secure_keypair_with_seed(1));
+        let (_, header) = Session::initiate(&IdentityKey::generate(), &bundle).unwrap();
+        let mut bob = Session::respond(&mut prekeys, &header).unwrap();
+
+        assert_eq!(bob.encrypt(b"hi").err(), Some(RatchetError::NotReady));
+    }
+
+    #[test]
+    fn tampered_and_replayed_messages_leave_the_session_intact() {
+        let (mut alice, mut bob) = established();
+        let message = alice.encrypt(b"pay 10").unwrap();
+
+        let mut tampered = message.clone();
+        tampered.ciphertext[0] ^= 1;
+        assert_eq!(bob.decrypt(&tampered), Err(RatchetError::DecryptionFailed));
+        let mut future = message.clone();
+        future.header.message_number = MAX_SKIP + 5;
+        assert!(matches!(bob.decrypt(&future), Err(RatchetError::TooManySkipped(_))));
+
+        assert_eq!(bob.decrypt(&message).unwrap(), b"pay 10");
+        assert_eq!(bob.decrypt(&message), Err(RatchetError::DecryptionFailed));
+    }
+
+    #[test]
+    fn session_state_survives_serialization() {
+        let (mut alice, bob) = established();
+        let delayed = alice.encrypt(b"delayed").unwrap();
+        let latest = alice.encrypt(b"latest").unwrap();
+
+        let mut bob: Session = serde_json::from_str(&serde_json::to_string(&bob).unwrap()).unwrap();
+        assert_eq!(bob.decrypt(&latest).unwrap(), b"latest");
+        let mut bob: Session = serde_json::from_str(&serde_json::to_string(&bob).unwrap()).unwrap();
+        assert_eq!(bob.decrypt(&delayed).unwrap(), b"delayed");
+
+        let reply = bob.encrypt(b"got both").unwrap();
+        assert_eq!(alice.decrypt(&reply).unwrap(), b"got both");
+    }
+}
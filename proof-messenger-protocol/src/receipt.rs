@@ -0,0 +1,177 @@
+//! Delivery receipts: recipient-signed acknowledgments of a message
+//!
+//! A receipt is an Ed25519 signature by the recipient over [`receipt_context`],
+//! which binds the message id assigned by the relay to a [`message_hash`] of
+//! the message contents. A receipt for one message can therefore never be
+//! presented as acknowledging another, or a tampered copy of the same one.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//! use proof_messenger_protocol::receipt::{make_receipt, message_hash, verify_receipt};
+//!
+//! let recipient = generate_secure_keypair();
+//! let hash = message_hash(b"sender-key", b"context", b"hello");
+//! let receipt = make_receipt(&recipient, "message-1", &hash).unwrap();
+//!
+//! assert!(verify_receipt(&receipt, "message-1", &hash).is_ok());
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature};
+use sha2::{Digest, Sha256};
+
+use crate::key::SecureKeypair;
+use crate::proof::{make_secure_proof, verify_proof_result, ProofError};
+
+/// Length of a message hash in bytes (SHA-256)
+pub const MESSAGE_HASH_LENGTH: usize = 32;
+
+/// Domain separation prefix for message hashes
+const MESSAGE_HASH_DOMAIN: &[u8] = b"proof-messenger/message-hash/v1";
+
+/// Domain separation prefix for receipt proofs
+const RECEIPT_DOMAIN: &[u8] = b"proof-messenger/receipt/v1";
+
+/// A recipient's signed acknowledgment of a delivered message
+#[derive(Debug, Clone, PartialEq)]
+pub struct Receipt {
+    /// Relay-assigned id of the acknowledged message
+    pub message_id: String,
+    /// Hash of the acknowledged message contents
+    pub message_hash: [u8; MESSAGE_HASH_LENGTH],
+    /// Public key of the recipient issuing the receipt
+    pub recipient: PublicKey,
+    /// Recipient's signature over the receipt context
+    pub signature: Signature,
+}
+
+impl Receipt {
+    /// The context the recipient signed for this receipt
+    pub fn context(&self) -> Vec<u8> {
+        receipt_context(&self.message_id, &self.message_hash)
+    }
+}
+
+/// Hash a message's sender, signed context and body
+///
+/// Each field is length prefixed so no two distinct messages share a hash
+/// by shifting bytes across field boundaries.
+pub fn message_hash(sender: &[u8], context: &[u8], body: &[u8]) -> [u8; MESSAGE_HASH_LENGTH] {
+    let mut hasher = Sha256::new();
+    hasher.update(MESSAGE_HASH_DOMAIN);
+    for field in [sender, context, body] {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field);
+    }
+    hasher.finalize().into()
+}
+
+/// Build the bytes a recipient signs to acknowledge `message_id`
+pub fn receipt_context(message_id: &str, message_hash: &[u8; MESSAGE_HASH_LENGTH]) -> Vec<u8> {
+    let mut context = Vec::with_capacity(RECEIPT_DOMAIN.len() + message_id.len() + MESSAGE_HASH_LENGTH + 2);
+    context.extend_from_slice(RECEIPT_DOMAIN);
+    context.push(0);
+    context.extend_from_slice(message_id.as_bytes());
+    context.push(0);
+    context.extend_from_slice(message_hash);
+    context
+}
+
+/// Sign a receipt for a delivered message
+pub fn make_receipt(
+    keypair: &SecureKeypair,
+    message_id: &str,
+    message_hash: &[u8; MESSAGE_HASH_LENGTH],
+) -> Result<Receipt, ProofError> {
+    if message_id.is_empty() {
+        return Err(ProofError::InvalidInput("Message id cannot be empty".to_string()));
+    }
+
+    let signature = make_secure_proof(keypair, &receipt_context(message_id, message_hash))?;
+    Ok(Receipt {
+        message_id: message_id.to_string(),
+        message_hash: *message_hash,
+        recipient: keypair.public_key(),
+        signature,
+    })
+}
+
+/// Verify a receipt acknowledges the expected message and is correctly signed
+pub fn verify_receipt(
+    receipt: &Receipt,
+    message_id: &str,
+    message_hash: &[u8; MESSAGE_HASH_LENGTH],
+) -> Result<(), ProofError> {
+    if receipt.message_id != message_id {
+        return Err(ProofError::InvalidData(format!(
+            "Receipt is for message {}, expected {}",
+            receipt.message_id, message_id
+        )));
+    }
+    if &receipt.message_hash != message_hash {
+        return Err(ProofError::InvalidData("Receipt message hash does not match".to_string()));
+    }
+
+    verify_proof_result(&receipt.recipient, &receipt.context(), &receipt.signature)
+}
+
+/// Parse a message hash from a byte slice
+pub fn message_hash_from_slice(bytes: &[u8]) -> Result<[u8; MESSAGE_HASH_LENGTH], ProofError> {
+    bytes.try_into().map_err(|_| {
+        ProofError::InvalidData(format!(
+            "Message hash must be {} bytes (got {})",
+            MESSAGE_HASH_LENGTH,
+            bytes.len()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+
+    #[test]
+    fn receipt_roundtrip_verifies() {
+        let keypair = generate_secure_keypair_with_seed(7);
+        let hash = message_hash(b"alice", b"ctx", b"hello");
+
+        let receipt = make_receipt(&keypair, "msg-1", &hash).unwrap();
+
+        assert_eq!(receipt.recipient, keypair.public_key());
+        assert!(verify_receipt(&receipt, "msg-1", &hash).is_ok());
+    }
+
+    #[test]
+    fn receipt_is_bound_to_message_id_and_hash() {
+        let keypair = generate_secure_keypair_with_seed(7);
+        let hash = message_hash(b"alice", b"ctx", b"hello");
+        let other_hash = message_hash(b"alice", b"ctx", b"hello!");
+        let receipt = make_receipt(&keypair, "msg-1", &hash).unwrap();
+
+        assert!(verify_receipt(&receipt, "msg-2", &hash).is_err());
+        assert!(verify_receipt(&receipt, "msg-1", &other_hash).is_err());
+
+        // Rewriting the claimed message breaks the signature
+        let mut forged = receipt.clone();
+        forged.message_id = "msg-2".to_string();
+        assert!(matches!(
+            verify_receipt(&forged, "msg-2", &hash),
+            Err(ProofError::VerificationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn message_hash_separates_fields() {
+        assert_ne!(message_hash(b"ab", b"c", b""), message_hash(b"a", b"bc", b""));
+        assert_eq!(message_hash(b"a", b"b", b"c"), message_hash(b"a", b"b", b"c"));
+    }
+
+    #[test]
+    fn empty_message_id_is_rejected() {
+        let keypair = generate_secure_keypair_with_seed(7);
+        let hash = message_hash(b"alice", b"ctx", b"hello");
+
+        assert!(matches!(make_receipt(&keypair, "", &hash), Err(ProofError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,708 @@
+//! Typed HTTP client for the relay API
+//!
+//! Wraps the relay's REST endpoints so applications don't have to hand-roll
+//! requests: messages are signed and sent with [`RelayClient::send_message`],
+//! read back with [`RelayClient::get_messages`], and so on. Requests that
+//! fail transiently are retried with exponential backoff (see
+//! [`RetryPolicy`]), and error responses are decoded into
+//! [`RelayClientError::Api`] carrying the relay's stable [`ErrorCode`].
+//!
+//! Requires the `client` feature and is not available on WASM.
+//!
+//! ## Example
+//! ```no_run
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//! use proof_messenger_protocol::relay_client::{OutgoingMessage, RelayClient};
+//!
+//! # async fn run() -> Result<(), proof_messenger_protocol::relay_client::RelayClientError> {
+//! let client = RelayClient::new("https://relay.example.com")?;
+//! let keypair = generate_secure_keypair();
+//!
+//! let message = OutgoingMessage::signed(&keypair, b"greeting", "hello")?;
+//! let message_id = client.send_message(&message).await?;
+//! let history = client.get_messages("default", Some(50)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use chrono::{DateTime, Utc};
+use reqwest::{header::RETRY_AFTER, Method, RequestBuilder, Response, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::key::SecureKeypair;
+use crate::proof::{make_secure_proof, ProofError};
+
+/// Header carrying the relay's request ID
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Path segment of the relay API version this client speaks
+const API_VERSION: &str = "v1";
+
+/// Time allowed on top of a long poll's wait for the relay to answer
+const POLL_GRACE: Duration = Duration::from_secs(10);
+
+/// Stable machine-readable error codes returned by the relay
+///
+/// Mirrors the relay's `ErrorCode`. Codes added by newer relays decode as
+/// [`ErrorCode::Unknown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    // Message verification
+    InvalidSignature,
+    InvalidPublicKey,
+    InvalidContext,
+    VerificationFailed,
+    ProofRevoked,
+    ProofAlreadyRevoked,
+    ReplayDetected,
+    PayloadTooLarge,
+
+    // Resources and queries
+    MessageNotFound,
+    MessageExpired,
+    InvalidGroupId,
+    InviteNotFound,
+    InviteUnavailable,
+    InvalidThread,
+    InvalidExpiry,
+    InvalidQuery,
+
+    // Authentication and authorization
+    MissingCredentials,
+    InvalidToken,
+    TokenExpired,
+    InsufficientScope,
+
+    // Federation
+    FederationDisabled,
+    UnknownPeer,
+    ClientCertificateRequired,
+    InvalidPeerSignature,
+    HopLimitExceeded,
+    LoopDetected,
+    PeerUnavailable,
+
+    // Transparency log
+    TransparencyDisabled,
+    LogEntryNotFound,
+    InvalidTreeSize,
+
+    // Webhooks
+    WebhooksDisabled,
+    WebhookNotFound,
+    InvalidWebhookUrl,
+
+    // Errors outside the relay's handlers
+    BadRequest,
+    NotFound,
+    MethodNotAllowed,
+    UnsupportedMediaType,
+    UnprocessableEntity,
+    RateLimited,
+
+    // Server-side failures
+    ConfigurationError,
+    DatabaseError,
+    ProcessingError,
+    InternalError,
+
+    /// A code this client does not know, or a response without one
+    #[serde(other)]
+    Unknown,
+}
+
+/// Errors returned by [`RelayClient`]
+#[derive(Error, Debug)]
+pub enum RelayClientError {
+    #[error("Invalid relay URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("Relay request failed: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    #[error("Relay returned {status} ({code:?}): {message}")]
+    Api {
+        /// HTTP status of the response
+        status: u16,
+        /// Machine-readable error code
+        code: ErrorCode,
+        /// Human-readable description of the error
+        message: String,
+        /// ID of the failed request, for correlating with relay logs
+        request_id: Option<String>,
+        /// Structured context for the error
+        details: Option<serde_json::Value>,
+    },
+
+    #[error("Unexpected relay response: {0}")]
+    Decode(String),
+
+    #[error("Failed to sign message: {0}")]
+    Signing(#[from] ProofError),
+}
+
+impl RelayClientError {
+    /// The relay's error code, for errors returned by the relay
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            RelayClientError::Api { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+/// How transient failures are retried
+///
+/// Connection failures and `429`/`503` responses are retried for every
+/// request. Timeouts and `502`/`504` responses are only retried for reads,
+/// since the relay may already have processed a write.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further retry
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between retries
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+}
+
+/// Post-quantum (ML-DSA-65) half of a hybrid proof
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PqcProof {
+    /// ML-DSA-65 public key of the sender (hex encoded)
+    pub public_key: String,
+    /// ML-DSA-65 signature over the context (hex encoded)
+    pub proof: String,
+}
+
+/// A message to send to the relay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutgoingMessage {
+    /// Public key of the sender (hex encoded)
+    pub sender: String,
+    /// Context data that was signed (hex encoded)
+    pub context: String,
+    /// Message body content
+    pub body: String,
+    /// Signature over the context (hex encoded)
+    pub proof: String,
+    /// Optional post-quantum half of a hybrid proof
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pqc: Option<PqcProof>,
+    /// Optional thread to post into (the thread's root message ID)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+    /// Optional ID of the message this one replies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    /// Optional time after which the relay stops serving the message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Optional public key of the only recipient (hex encoded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+}
+
+impl OutgoingMessage {
+    /// Build a message whose proof is the keypair's signature over `context`
+    pub fn signed(keypair: &SecureKeypair, context: &[u8], body: &str) -> Result<Self, RelayClientError> {
+        let signature = make_secure_proof(keypair, context)?;
+        Ok(Self {
+            sender: hex::encode(keypair.public_key_bytes()),
+            context: hex::encode(context),
+            body: body.to_string(),
+            proof: hex::encode(signature.to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        })
+    }
+
+    /// Post the message as a reply within a thread
+    pub fn in_reply_to(mut self, thread_id: &str, reply_to: &str) -> Self {
+        self.thread_id = Some(thread_id.to_string());
+        self.reply_to = Some(reply_to.to_string());
+        self
+    }
+
+    /// Have the relay stop serving the message after `expires_at`
+    pub fn expiring_at(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Send the message to a single recipient's inbox instead of its group
+    pub fn addressed_to(mut self, recipient: &[u8]) -> Self {
+        self.recipient = Some(hex::encode(recipient));
+        self
+    }
+}
+
+/// A verified message stored by the relay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelayedMessage {
+    /// Relay-assigned message ID
+    pub id: String,
+    /// Group the message was posted to
+    pub group_id: String,
+    /// Public key of the sender (hex encoded)
+    pub sender: String,
+    /// Context data that was signed (hex encoded)
+    pub context: String,
+    /// Message body content
+    pub body: String,
+    /// Signature over the context (hex encoded)
+    pub proof: String,
+    /// When the relay stored the message
+    pub created_at: DateTime<Utc>,
+    /// Whether the relay verified the proof
+    pub verified: bool,
+    /// Thread the message belongs to (the root message ID)
+    pub thread_id: Option<String>,
+    /// ID of the message this one replies to
+    pub reply_to: Option<String>,
+    /// When the relay stops serving the message, if it is ephemeral
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Public key of the recipient, if it is a direct message
+    #[serde(default)]
+    pub recipient: Option<String>,
+}
+
+/// Messages returned by a long poll
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PollPage {
+    /// Messages verified since the change token, oldest first
+    pub messages: Vec<RelayedMessage>,
+    /// Token to pass to the next poll to continue after these messages
+    pub change_token: String,
+}
+
+/// Health of a relay as reported by `/health`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthStatus {
+    /// `healthy` or `unhealthy`
+    pub status: String,
+    /// Database connectivity (`connected` or `disconnected`)
+    pub database: String,
+    /// Name of the service
+    pub service: String,
+    /// Version of the relay
+    pub version: String,
+    /// When the check ran (RFC 3339)
+    pub timestamp: String,
+    /// Why the relay is unhealthy, if it is
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl HealthStatus {
+    /// Whether the relay reported itself healthy
+    pub fn is_healthy(&self) -> bool {
+        self.status == "healthy"
+    }
+}
+
+#[derive(Deserialize)]
+struct SendMessageResponse {
+    message_id: String,
+}
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    messages: Vec<RelayedMessage>,
+}
+
+#[derive(Serialize)]
+struct RevokeProofRequest<'a> {
+    proof_signature: &'a str,
+    reason: Option<&'a str>,
+    ttl_hours: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: String,
+    code: ErrorCode,
+    request_id: Option<String>,
+    #[serde(default)]
+    details: Option<serde_json::Value>,
+}
+
+/// Client for a single relay
+#[derive(Debug, Clone)]
+pub struct RelayClient {
+    base_url: Url,
+    http: reqwest::Client,
+    bearer_token: Option<String>,
+    retry: RetryPolicy,
+}
+
+impl RelayClient {
+    /// Create a client for the relay at `base_url` (e.g. `https://relay.example.com`)
+    pub fn new(base_url: &str) -> Result<Self, RelayClientError> {
+        let base_url = Url::parse(base_url).map_err(|e| RelayClientError::InvalidUrl(format!("{}: {}", base_url, e)))?;
+        if !matches!(base_url.scheme(), "http" | "https") || base_url.cannot_be_a_base() {
+            return Err(RelayClientError::InvalidUrl(format!("{}: must be an http(s) URL", base_url)));
+        }
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        Ok(Self {
+            base_url,
+            http,
+            bearer_token: None,
+            retry: RetryPolicy::default(),
+        })
+    }
+
+    /// Authenticate requests with an OAuth bearer token
+    pub fn with_bearer_token(mut self, token: &str) -> Self {
+        self.bearer_token = Some(token.to_string());
+        self
+    }
+
+    /// Replace the retry policy
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Use a preconfigured HTTP client (custom timeouts, TLS roots, proxies)
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Send a signed message, returning the ID the relay assigned it
+    pub async fn send_message(&self, message: &OutgoingMessage) -> Result<String, RelayClientError> {
+        let response: SendMessageResponse = self
+            .request(Method::POST, &[API_VERSION, "relay"], |request| request.json(message))
+            .await?;
+        Ok(response.message_id)
+    }
+
+    /// Retrieve the most recent messages in a group
+    pub async fn get_messages(&self, group_id: &str, limit: Option<i64>) -> Result<Vec<RelayedMessage>, RelayClientError> {
+        let response: MessagesResponse = self
+            .request(Method::GET, &[API_VERSION, "messages", group_id], |request| match limit {
+                Some(limit) => request.query(&[("limit", limit)]),
+                None => request,
+            })
+            .await?;
+        Ok(response.messages)
+    }
+
+    /// Wait up to `timeout` for messages posted to a group
+    ///
+    /// Without a `since_token` only messages verified from now on are
+    /// returned. Pass the page's change token to the next call to continue
+    /// after its messages, including across reconnects. The relay may cap the
+    /// wait, and an empty page means nothing arrived in time.
+    pub async fn poll_messages(
+        &self,
+        group_id: &str,
+        since_token: Option<&str>,
+        timeout: Duration,
+    ) -> Result<PollPage, RelayClientError> {
+        self.request(Method::GET, &[API_VERSION, "messages", group_id, "poll"], |request| {
+            let request = request
+                .query(&[("timeout_secs", timeout.as_secs())])
+                // The relay holds the request open, so allow for the wait itself
+                .timeout(timeout + POLL_GRACE);
+            match since_token {
+                Some(token) => request.query(&[("since_token", token)]),
+                None => request,
+            }
+        })
+        .await
+    }
+
+    /// Revoke a proof so the relay rejects messages carrying it
+    ///
+    /// `ttl_hours` defaults to the relay's own default (24 hours).
+    pub async fn revoke_proof(
+        &self,
+        proof_signature: &str,
+        reason: Option<&str>,
+        ttl_hours: Option<i64>,
+    ) -> Result<(), RelayClientError> {
+        let body = RevokeProofRequest {
+            proof_signature,
+            reason,
+            ttl_hours,
+        };
+        let _: serde_json::Value = self
+            .request(Method::POST, &[API_VERSION, "revocation", "revoke"], |request| request.json(&body))
+            .await?;
+        Ok(())
+    }
+
+    /// Check the relay's health
+    ///
+    /// An unhealthy relay is reported as `Ok` with
+    /// [`HealthStatus::is_healthy`] false, and is not retried.
+    pub async fn check_health(&self) -> Result<HealthStatus, RelayClientError> {
+        let response = self
+            .builder(Method::GET, &["health"])?
+            .send()
+            .await?;
+        if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+            return decode_json(response).await;
+        }
+        decode(response).await
+    }
+
+    /// Send a request with retries and decode the JSON response
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &[&str],
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<T, RelayClientError> {
+        let idempotent = method == Method::GET;
+        let mut backoff = self.retry.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            let result = build(self.builder(method.clone(), path)?).send().await;
+            let retry_after = match &result {
+                Ok(response) if is_retryable_status(response.status(), idempotent) => Some(retry_after(response)),
+                Err(e) if e.is_connect() || (idempotent && e.is_timeout()) => Some(None),
+                _ => None,
+            };
+
+            match retry_after {
+                Some(delay) if attempt < self.retry.max_retries => {
+                    tokio::time::sleep(delay.unwrap_or(backoff).min(self.retry.max_backoff)).await;
+                    backoff = (backoff * 2).min(self.retry.max_backoff);
+                    attempt += 1;
+                }
+                _ => return decode(result?).await,
+            }
+        }
+    }
+
+    /// Start a request to a path below the base URL
+    fn builder(&self, method: Method, path: &[&str]) -> Result<RequestBuilder, RelayClientError> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| RelayClientError::InvalidUrl(self.base_url.to_string()))?
+            .pop_if_empty()
+            .extend(path);
+
+        let request = self.http.request(method, url);
+        Ok(match &self.bearer_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        })
+    }
+}
+
+/// Whether a response status is worth retrying
+fn is_retryable_status(status: StatusCode, idempotent: bool) -> bool {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => true,
+        StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => idempotent,
+        _ => false,
+    }
+}
+
+/// Delay requested by a `Retry-After` header given in seconds
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Decode a successful response, or turn an error response into [`RelayClientError::Api`]
+async fn decode<T: DeserializeOwned>(response: Response) -> Result<T, RelayClientError> {
+    if response.status().is_success() {
+        decode_json(response).await
+    } else {
+        Err(api_error(response).await)
+    }
+}
+
+async fn decode_json<T: DeserializeOwned>(response: Response) -> Result<T, RelayClientError> {
+    let bytes = response.bytes().await?;
+    serde_json::from_slice(&bytes).map_err(|e| RelayClientError::Decode(e.to_string()))
+}
+
+/// Build an API error from the relay's structured error body, when it has one
+async fn api_error(response: Response) -> RelayClientError {
+    let status = response.status();
+    let header_request_id = response
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let text = response.text().await.unwrap_or_default();
+
+    match serde_json::from_str::<ErrorBody>(&text) {
+        Ok(body) => RelayClientError::Api {
+            status: status.as_u16(),
+            code: body.code,
+            message: body.error,
+            request_id: body.request_id.or(header_request_id),
+            details: body.details,
+        },
+        Err(_) => RelayClientError::Api {
+            status: status.as_u16(),
+            code: ErrorCode::Unknown,
+            message: if text.trim().is_empty() {
+                status.canonical_reason().unwrap_or("Error").to_string()
+            } else {
+                text.trim().to_string()
+            },
+            request_id: header_request_id,
+            details: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn fast_retries() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_are_retried_until_the_relay_recovers() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/team%20a"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/team%20a"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "messages": [] })))
+            .mount(&server)
+            .await;
+        let client = RelayClient::new(&server.uri()).unwrap().with_retry_policy(fast_retries());
+
+        let messages = client.get_messages("team a", None).await.unwrap();
+
+        assert!(messages.is_empty());
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn writes_are_not_retried_after_a_gateway_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/relay"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&server)
+            .await;
+        let client = RelayClient::new(&server.uri()).unwrap().with_retry_policy(fast_retries());
+        let message = OutgoingMessage::signed(&generate_secure_keypair_with_seed(1), b"ctx", "hi").unwrap();
+
+        let error = client.send_message(&message).await.unwrap_err();
+
+        assert!(matches!(error, RelayClientError::Api { status: 502, code: ErrorCode::Unknown, .. }));
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn polls_resume_from_the_change_token() {
+        let server = MockServer::start().await;
+        let message = serde_json::json!({
+            "id": "m1", "group_id": "ops", "sender": "aa", "context": "bb", "body": "hi", "proof": "cc",
+            "created_at": "2026-01-01T00:00:00Z", "verified": true, "thread_id": null, "reply_to": null
+        });
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/ops/poll"))
+            .and(query_param("since_token", "t1"))
+            .and(query_param("timeout_secs", "5"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "messages": [message],
+                "change_token": "t2"
+            })))
+            .mount(&server)
+            .await;
+        let client = RelayClient::new(&server.uri()).unwrap();
+
+        let page = client.poll_messages("ops", Some("t1"), Duration::from_secs(5)).await.unwrap();
+
+        assert_eq!(page.change_token, "t2");
+        assert_eq!(page.messages.len(), 1);
+        assert_eq!(page.messages[0].id, "m1");
+    }
+
+    #[tokio::test]
+    async fn structured_errors_are_decoded() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/revocation/revoke"))
+            .respond_with(ResponseTemplate::new(409).set_body_json(serde_json::json!({
+                "error": "Proof already revoked: abc",
+                "code": "PROOF_ALREADY_REVOKED",
+                "request_id": "req-1"
+            })))
+            .mount(&server)
+            .await;
+        let client = RelayClient::new(&server.uri()).unwrap();
+
+        let error = client.revoke_proof("abc", None, None).await.unwrap_err();
+
+        assert_eq!(error.code(), Some(ErrorCode::ProofAlreadyRevoked));
+        match error {
+            RelayClientError::Api { status, request_id, .. } => {
+                assert_eq!(status, 409);
+                assert_eq!(request_id.as_deref(), Some("req-1"));
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_codes_and_bad_urls() {
+        let code: ErrorCode = serde_json::from_value(serde_json::json!("SOMETHING_NEW")).unwrap();
+        assert_eq!(code, ErrorCode::Unknown);
+
+        assert!(matches!(RelayClient::new("ftp://relay"), Err(RelayClientError::InvalidUrl(_))));
+        assert!(matches!(RelayClient::new("not a url"), Err(RelayClientError::InvalidUrl(_))));
+    }
+}
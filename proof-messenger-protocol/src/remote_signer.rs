@@ -0,0 +1,338 @@
+//! Remote signing over a Unix socket
+//!
+//! A signer daemon is a separate, hardened process that holds signing keys,
+//! so the CLI or the relay never load key material themselves. They connect
+//! to the daemon's Unix socket and exchange one JSON object per line:
+//!
+//! ```text
+//! → {"op":"public_key","keyId":"default"}
+//! ← {"publicKey":"<hex>"}
+//! → {"op":"sign","keyId":"default","context":"<hex>"}
+//! ← {"signature":"<hex>"}
+//! ← {"error":"Unknown key: backup"}
+//! ```
+//!
+//! A connection may carry any number of requests. [`SignerDaemon::bind`]
+//! creates the socket readable and writable by its owner only, so access to
+//! the keys is access to the socket file. Requests are capped at
+//! [`MAX_CONTEXT_LENGTH`] bytes of context, and connections that stall are
+//! dropped after [`IO_TIMEOUT`].
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//! use proof_messenger_protocol::remote_signer::{RemoteSignerClient, SignerDaemon};
+//!
+//! let socket = std::env::temp_dir().join(format!("pm-signer-doc-{}.sock", std::process::id()));
+//! let listener = SignerDaemon::bind(&socket).unwrap();
+//! let daemon = SignerDaemon::new().with_key("default", generate_secure_keypair());
+//! std::thread::spawn(move || daemon.serve(&listener));
+//!
+//! let client = RemoteSignerClient::new(&socket);
+//! let public_key = client.public_key("default").unwrap();
+//! let signature = client.sign("default", b"approve invoice 42").unwrap();
+//! assert!(public_key.verify_strict(b"approve invoice 42", &signature).is_ok());
+//! # std::fs::remove_file(&socket).unwrap();
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::key::SecureKeypair;
+
+/// Largest context the daemon signs, in bytes (1 MiB)
+///
+/// Larger documents are signed over a digest, see [`crate::context_digest`].
+pub const MAX_CONTEXT_LENGTH: usize = 1024 * 1024;
+
+/// How long either side waits on a stalled connection
+pub const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest request line: the hex context plus room for the other fields
+const MAX_LINE_LENGTH: u64 = 2 * MAX_CONTEXT_LENGTH as u64 + 1024;
+
+/// Errors talking to a signer daemon
+#[derive(Debug, Error)]
+pub enum RemoteSignerError {
+    /// The socket could not be reached or the connection failed
+    #[error("Signer daemon I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The daemon's answer was not a valid response
+    #[error("Invalid signer daemon response: {0}")]
+    Protocol(String),
+
+    /// The daemon refused the request
+    #[error("Signer daemon refused the request: {0}")]
+    Rejected(String),
+}
+
+/// A request to the daemon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum SignerRequest {
+    /// The public key of a held key
+    PublicKey { key_id: String },
+    /// A signature over `context` (hex encoded)
+    Sign { key_id: String, context: String },
+}
+
+/// The daemon's answer to one request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged, rename_all_fields = "camelCase")]
+pub enum SignerResponse {
+    /// Ed25519 public key (hex encoded)
+    PublicKey { public_key: String },
+    /// Ed25519 signature (hex encoded)
+    Signature { signature: String },
+    /// Why the request was refused
+    Error { error: String },
+}
+
+/// A signer daemon holding keys by id
+#[derive(Default)]
+pub struct SignerDaemon {
+    keys: HashMap<String, SecureKeypair>,
+}
+
+impl SignerDaemon {
+    /// A daemon holding no keys yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold `keypair` under `key_id`
+    pub fn with_key(mut self, key_id: impl Into<String>, keypair: SecureKeypair) -> Self {
+        self.keys.insert(key_id.into(), keypair);
+        self
+    }
+
+    /// Answer one request
+    pub fn handle(&self, request: &SignerRequest) -> SignerResponse {
+        let key_id = match request {
+            SignerRequest::PublicKey { key_id } | SignerRequest::Sign { key_id, .. } => key_id,
+        };
+        let Some(keypair) = self.keys.get(key_id) else {
+            return SignerResponse::Error { error: format!("Unknown key: {}", key_id) };
+        };
+        match request {
+            SignerRequest::PublicKey { .. } => SignerResponse::PublicKey {
+                public_key: hex::encode(keypair.public_key_bytes()),
+            },
+            SignerRequest::Sign { context, .. } => match hex::decode(context) {
+                Ok(context) if context.is_empty() => SignerResponse::Error { error: "Context is empty".to_string() },
+                Ok(context) if context.len() > MAX_CONTEXT_LENGTH => SignerResponse::Error {
+                    error: format!("Context exceeds {} bytes", MAX_CONTEXT_LENGTH),
+                },
+                Ok(context) => SignerResponse::Signature {
+                    signature: hex::encode(keypair.sign(&context).to_bytes()),
+                },
+                Err(e) => SignerResponse::Error { error: format!("Invalid context hex: {}", e) },
+            },
+        }
+    }
+
+    /// Create the daemon's socket, replacing a stale one, with owner-only permissions
+    pub fn bind(path: impl AsRef<Path>) -> std::io::Result<UnixListener> {
+        let path = path.as_ref();
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        Ok(listener)
+    }
+
+    /// Serve connections one at a time until the listener fails
+    ///
+    /// A failing connection is dropped without affecting the next one.
+    pub fn serve(&self, listener: &UnixListener) -> std::io::Result<()> {
+        loop {
+            let (stream, _) = listener.accept()?;
+            let _ = self.serve_connection(stream);
+        }
+    }
+
+    /// Answer every request on one connection until the client hangs up
+    pub fn serve_connection(&self, stream: UnixStream) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        let mut reader = BufReader::new(&stream);
+        let mut writer = &stream;
+        loop {
+            let mut line = String::new();
+            let read = (&mut reader).take(MAX_LINE_LENGTH).read_line(&mut line)?;
+            if read == 0 {
+                return Ok(());
+            }
+            if !line.ends_with('\n') {
+                // Too long to be a request; the rest of the stream cannot be framed
+                write_line(&mut writer, &SignerResponse::Error { error: "Request is too long".to_string() })?;
+                return Ok(());
+            }
+            let response = match serde_json::from_str(&line) {
+                Ok(request) => self.handle(&request),
+                Err(e) => SignerResponse::Error { error: format!("Invalid request: {}", e) },
+            };
+            write_line(&mut writer, &response)?;
+        }
+    }
+}
+
+/// Write one JSON object and a newline
+fn write_line(writer: &mut impl Write, message: &impl Serialize) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()
+}
+
+/// Client for a signer daemon's Unix socket
+#[derive(Debug, Clone)]
+pub struct RemoteSignerClient {
+    socket: PathBuf,
+}
+
+impl RemoteSignerClient {
+    /// A client for the daemon listening on `socket`
+    pub fn new(socket: impl Into<PathBuf>) -> Self {
+        Self { socket: socket.into() }
+    }
+
+    /// The daemon's socket
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    /// The public key the daemon holds under `key_id`
+    pub fn public_key(&self, key_id: &str) -> Result<PublicKey, RemoteSignerError> {
+        match self.request(&SignerRequest::PublicKey { key_id: key_id.to_string() })? {
+            SignerResponse::PublicKey { public_key } => hex::decode(&public_key)
+                .ok()
+                .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+                .ok_or_else(|| RemoteSignerError::Protocol(format!("Invalid public key: {}", public_key))),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Have the daemon sign `context` with the key under `key_id`
+    ///
+    /// The signature is not checked here; callers that know the expected
+    /// public key should verify it, as the daemon may hold another key now.
+    pub fn sign(&self, key_id: &str, context: &[u8]) -> Result<Signature, RemoteSignerError> {
+        let request = SignerRequest::Sign { key_id: key_id.to_string(), context: hex::encode(context) };
+        match self.request(&request)? {
+            SignerResponse::Signature { signature } => hex::decode(&signature)
+                .ok()
+                .and_then(|bytes| Signature::from_bytes(&bytes).ok())
+                .ok_or_else(|| RemoteSignerError::Protocol(format!("Invalid signature: {}", signature))),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Send one request on a fresh connection and read the answer
+    fn request(&self, request: &SignerRequest) -> Result<SignerResponse, RemoteSignerError> {
+        let stream = UnixStream::connect(&self.socket)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        write_line(&mut &stream, request)?;
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+        serde_json::from_str(&line).map_err(|e| RemoteSignerError::Protocol(e.to_string()))
+    }
+}
+
+/// The error for a response of the wrong kind
+fn unexpected(response: SignerResponse) -> RemoteSignerError {
+    match response {
+        SignerResponse::Error { error } => RemoteSignerError::Rejected(error),
+        other => RemoteSignerError::Protocol(format!("Unexpected response: {:?}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+    use crate::proof::verify_proof_result;
+
+    /// A daemon with one key serving on a socket in a fresh directory
+    fn start_daemon() -> (tempfile::TempDir, RemoteSignerClient, SecureKeypair) {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("signer.sock");
+        let keypair = generate_secure_keypair_with_seed(11);
+        let listener = SignerDaemon::bind(&socket).unwrap();
+        let daemon = SignerDaemon::new().with_key("default", keypair.clone());
+        std::thread::spawn(move || daemon.serve(&listener));
+        (dir, RemoteSignerClient::new(socket), keypair)
+    }
+
+    #[test]
+    fn requests_and_responses_have_a_stable_wire_format() {
+        let request = SignerRequest::Sign { key_id: "default".to_string(), context: "00ff".to_string() };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"op":"sign","keyId":"default","context":"00ff"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<SignerResponse>(r#"{"publicKey":"ab"}"#).unwrap(),
+            SignerResponse::PublicKey { public_key: "ab".to_string() }
+        );
+        assert_eq!(
+            serde_json::from_str::<SignerResponse>(r#"{"error":"no"}"#).unwrap(),
+            SignerResponse::Error { error: "no".to_string() }
+        );
+    }
+
+    #[test]
+    fn client_gets_signatures_from_the_daemon() {
+        let (dir, client, keypair) = start_daemon();
+
+        let public_key = client.public_key("default").unwrap();
+        let signature = client.sign("default", b"approve").unwrap();
+
+        assert_eq!(public_key, keypair.public_key());
+        assert!(verify_proof_result(&public_key, b"approve", &signature).is_ok());
+        let mode = std::fs::metadata(dir.path().join("signer.sock")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn daemon_refuses_unknown_keys_and_bad_contexts() {
+        let (_dir, client, _) = start_daemon();
+
+        assert!(matches!(client.public_key("backup"), Err(RemoteSignerError::Rejected(_))));
+        assert!(matches!(client.sign("default", b""), Err(RemoteSignerError::Rejected(_))));
+        let oversized = vec![0u8; MAX_CONTEXT_LENGTH + 1];
+        assert!(matches!(client.sign("default", &oversized), Err(RemoteSignerError::Rejected(_))));
+        assert!(matches!(
+            RemoteSignerClient::new("/nonexistent/signer.sock").public_key("default"),
+            Err(RemoteSignerError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_lines_get_an_error_and_the_connection_continues() {
+        let (_dir, client, _) = start_daemon();
+        let stream = UnixStream::connect(client.socket()).unwrap();
+        let mut reader = BufReader::new(&stream);
+
+        (&stream).write_all(b"not json\n{\"op\":\"public_key\",\"keyId\":\"default\"}\n").unwrap();
+        let mut first = String::new();
+        let mut second = String::new();
+        reader.read_line(&mut first).unwrap();
+        reader.read_line(&mut second).unwrap();
+
+        assert!(matches!(serde_json::from_str(&first).unwrap(), SignerResponse::Error { .. }));
+        assert!(matches!(serde_json::from_str(&second).unwrap(), SignerResponse::PublicKey { .. }));
+    }
+}
@@ -0,0 +1,451 @@
+//! Human-readable rendering of signed contexts for approval screens
+//!
+//! A signer should see what they approve in words, not as hex or JSON. A
+//! context is recognized when it is a JSON object naming one of the built-in
+//! templates and the values it fills in:
+//!
+//! ```json
+//! {"template": "wire_transfer", "values": {"amount": "50000.00", "currency": "USD", "beneficiary": "ACME-123"}}
+//! ```
+//!
+//! [`render_context`] turns it into a localized summary such as "Approve wire
+//! of $50,000.00 to ACME-123". Because the summary is what the signer
+//! consents to, rendering is strict:
+//!
+//! - every value must appear in the summary, so nothing is signed unseen
+//! - text may not contain control, bidirectional or invisible formatting
+//!   characters, which could make the summary read differently from the values
+//! - amounts are decimal strings with at most the currency's minor digits,
+//!   never JSON numbers
+//!
+//! Summaries are available in English (`en`), German (`de`) and French
+//! (`fr`). Other locales fall back to their language, then to English.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::render::render_context;
+//!
+//! let context = br#"{"template":"wire_transfer","values":{"amount":"50000","currency":"USD","beneficiary":"ACME-123"}}"#;
+//!
+//! assert_eq!(render_context(context, "en-US").unwrap().summary, "Approve wire of $50,000.00 to ACME-123");
+//! assert_eq!(render_context(context, "de-DE").unwrap().summary, "Überweisung von 50.000,00\u{A0}$ an ACME-123 freigeben");
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// Locales summaries are available in
+pub const SUPPORTED_LOCALES: &[&str] = &["en", "de", "fr"];
+
+/// Longest text value rendered, in characters
+const MAX_TEXT_LENGTH: usize = 200;
+
+/// Errors raised when a context cannot be rendered
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RenderError {
+    /// The context is not a template and values
+    #[error("Context is not a recognized template: {0}")]
+    NotRecognized(String),
+
+    /// The context names a template that does not exist
+    #[error("Unknown template: {0}")]
+    UnknownTemplate(String),
+
+    /// The template needs a value the context lacks
+    #[error("Missing value: {0}")]
+    MissingValue(String),
+
+    /// The context carries a value the template would not show
+    #[error("Value is not shown by the template: {0}")]
+    UnexpectedValue(String),
+
+    /// A value cannot be shown safely
+    #[error("Invalid value for {field}: {reason}")]
+    InvalidValue { field: String, reason: String },
+}
+
+/// A context rendered for display
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenderedContext {
+    /// Template the context uses
+    pub template: String,
+    /// Locale the summary is in
+    pub locale: String,
+    /// What the signer approves, in words
+    pub summary: String,
+}
+
+/// How a template value is checked and formatted
+#[derive(Debug, Clone, Copy)]
+enum FieldKind {
+    /// Free text
+    Text,
+    /// A decimal amount in the currency named by another field
+    Amount { currency: &'static str },
+    /// An ISO 4217 currency code, shown as part of its amount
+    Currency,
+}
+
+/// A summary with `{field}` placeholders and the values that fill them
+struct Template {
+    id: &'static str,
+    fields: &'static [(&'static str, FieldKind)],
+    /// Summary per locale, in [`SUPPORTED_LOCALES`] order
+    summaries: [&'static str; 3],
+}
+
+/// Built-in templates
+const TEMPLATES: &[Template] = &[
+    Template {
+        id: "wire_transfer",
+        fields: &[
+            ("amount", FieldKind::Amount { currency: "currency" }),
+            ("currency", FieldKind::Currency),
+            ("beneficiary", FieldKind::Text),
+        ],
+        summaries: [
+            "Approve wire of {amount} to {beneficiary}",
+            "Überweisung von {amount} an {beneficiary} freigeben",
+            "Approuver le virement de {amount} à {beneficiary}",
+        ],
+    },
+    Template {
+        id: "payment",
+        fields: &[
+            ("amount", FieldKind::Amount { currency: "currency" }),
+            ("currency", FieldKind::Currency),
+            ("merchant", FieldKind::Text),
+        ],
+        summaries: [
+            "Approve payment of {amount} to {merchant}",
+            "Zahlung von {amount} an {merchant} freigeben",
+            "Approuver le paiement de {amount} à {merchant}",
+        ],
+    },
+    Template {
+        id: "login",
+        fields: &[("service", FieldKind::Text), ("device", FieldKind::Text)],
+        summaries: [
+            "Approve sign-in to {service} from {device}",
+            "Anmeldung bei {service} von {device} freigeben",
+            "Approuver la connexion à {service} depuis {device}",
+        ],
+    },
+    Template {
+        id: "document_signature",
+        fields: &[("document", FieldKind::Text)],
+        summaries: [
+            "Approve signing of {document}",
+            "Unterzeichnung von {document} freigeben",
+            "Approuver la signature de {document}",
+        ],
+    },
+];
+
+/// How a locale writes numbers and currency amounts
+struct NumberFormat {
+    decimal: char,
+    group: char,
+    /// Whether the currency symbol follows the number (after a no-break space)
+    symbol_after: bool,
+}
+
+/// Number formats per locale, in [`SUPPORTED_LOCALES`] order
+const NUMBER_FORMATS: [NumberFormat; 3] = [
+    NumberFormat { decimal: '.', group: ',', symbol_after: false },
+    NumberFormat { decimal: ',', group: '.', symbol_after: true },
+    NumberFormat { decimal: ',', group: '\u{202F}', symbol_after: true },
+];
+
+/// Render a signed context as a summary in `locale` (such as `en-US` or `de`)
+pub fn render_context(context: &[u8], locale: &str) -> Result<RenderedContext, RenderError> {
+    let context: Value =
+        serde_json::from_slice(context).map_err(|e| RenderError::NotRecognized(format!("not JSON: {}", e)))?;
+    render_value(&context, locale)
+}
+
+/// Render a parsed JSON context as a summary in `locale`
+pub fn render_value(context: &Value, locale: &str) -> Result<RenderedContext, RenderError> {
+    let object = context
+        .as_object()
+        .ok_or_else(|| RenderError::NotRecognized("not a JSON object".to_string()))?;
+    if let Some(extra) = object.keys().find(|key| *key != "template" && *key != "values") {
+        return Err(RenderError::UnexpectedValue(extra.clone()));
+    }
+    let id = object
+        .get("template")
+        .and_then(Value::as_str)
+        .ok_or_else(|| RenderError::NotRecognized("no template name".to_string()))?;
+    let values = object
+        .get("values")
+        .and_then(Value::as_object)
+        .ok_or_else(|| RenderError::NotRecognized("no values object".to_string()))?;
+    let template = TEMPLATES
+        .iter()
+        .find(|template| template.id == id)
+        .ok_or_else(|| RenderError::UnknownTemplate(id.to_string()))?;
+
+    if let Some(extra) = values.keys().find(|key| !template.fields.iter().any(|(name, _)| name == key)) {
+        return Err(RenderError::UnexpectedValue(extra.clone()));
+    }
+    let index = locale_index(locale);
+    let mut formatted = Vec::with_capacity(template.fields.len());
+    for (name, kind) in template.fields {
+        let value = match kind {
+            FieldKind::Text => text(values, name)?.to_string(),
+            FieldKind::Currency => {
+                currency(values, name)?;
+                continue;
+            }
+            FieldKind::Amount { currency: currency_field } => {
+                let code = currency(values, currency_field)?;
+                amount(text(values, name)?, code, &NUMBER_FORMATS[index]).map_err(|reason| RenderError::InvalidValue {
+                    field: name.to_string(),
+                    reason,
+                })?
+            }
+        };
+        formatted.push((*name, value));
+    }
+
+    Ok(RenderedContext {
+        template: template.id.to_string(),
+        locale: SUPPORTED_LOCALES[index].to_string(),
+        summary: fill(template.summaries[index], &formatted),
+    })
+}
+
+/// Index of the supported locale best matching `locale`, English by default
+fn locale_index(locale: &str) -> usize {
+    let language = locale.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+    SUPPORTED_LOCALES.iter().position(|supported| *supported == language).unwrap_or(0)
+}
+
+/// A text value that can be shown as-is
+fn text<'a>(values: &'a Map<String, Value>, field: &str) -> Result<&'a str, RenderError> {
+    let invalid = |reason: &str| RenderError::InvalidValue { field: field.to_string(), reason: reason.to_string() };
+    let value = values.get(field).ok_or_else(|| RenderError::MissingValue(field.to_string()))?;
+    let value = value.as_str().ok_or_else(|| invalid("must be a string"))?;
+
+    if value.trim().is_empty() {
+        return Err(invalid("must not be empty"));
+    }
+    if value.chars().count() > MAX_TEXT_LENGTH {
+        return Err(invalid(&format!("must be at most {} characters", MAX_TEXT_LENGTH)));
+    }
+    if value.chars().any(is_hidden_formatting) {
+        return Err(invalid("must not contain control or formatting characters"));
+    }
+    Ok(value)
+}
+
+/// Characters that are invisible or change how surrounding text is displayed
+fn is_hidden_formatting(c: char) -> bool {
+    c.is_control()
+        || matches!(c, '\u{061C}' | '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{2069}' | '\u{FEFF}')
+}
+
+/// An ISO 4217 currency code
+fn currency<'a>(values: &'a Map<String, Value>, field: &str) -> Result<&'a str, RenderError> {
+    let code = text(values, field)?;
+    if code.len() != 3 || !code.bytes().all(|b| b.is_ascii_uppercase()) {
+        return Err(RenderError::InvalidValue {
+            field: field.to_string(),
+            reason: "must be a three-letter ISO 4217 code".to_string(),
+        });
+    }
+    Ok(code)
+}
+
+/// Symbol and minor digits of a currency; unknown currencies show their code
+fn currency_display(code: &str) -> (&str, usize) {
+    match code {
+        "USD" => ("$", 2),
+        "EUR" => ("€", 2),
+        "GBP" => ("£", 2),
+        "JPY" => ("¥", 0),
+        _ => (code, 2),
+    }
+}
+
+/// Format a decimal amount string as a currency amount
+fn amount(value: &str, code: &str, format: &NumberFormat) -> Result<String, String> {
+    let (symbol, minor_digits) = currency_display(code);
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if whole.is_empty() || !whole.bytes().all(|b| b.is_ascii_digit()) || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err("must be a non-negative decimal such as 1250.00".to_string());
+    }
+    if value.contains('.') && fraction.is_empty() {
+        return Err("must have digits after the decimal point".to_string());
+    }
+    if fraction.len() > minor_digits {
+        return Err(format!("{} allows at most {} decimal places", code, minor_digits));
+    }
+
+    let whole = whole.trim_start_matches('0');
+    let whole = if whole.is_empty() { "0" } else { whole };
+    let mut number = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            number.push(format.group);
+        }
+        number.push(digit);
+    }
+    if minor_digits > 0 {
+        number.push(format.decimal);
+        number.push_str(&format!("{:0<width$}", fraction, width = minor_digits));
+    }
+
+    Ok(if format.symbol_after {
+        format!("{}\u{A0}{}", number, symbol)
+    } else if symbol.len() == 3 && symbol.is_ascii() {
+        format!("{}\u{A0}{}", symbol, number)
+    } else {
+        format!("{}{}", symbol, number)
+    })
+}
+
+/// Replace each `{field}` placeholder in a summary in one pass
+fn fill(summary: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(summary.len());
+    let mut rest = summary;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else { break };
+        let name = &rest[start + 1..start + end];
+        match values.iter().find(|(field, _)| *field == name) {
+            Some((_, value)) => out.push_str(value),
+            None => out.push_str(&rest[start..=start + end]),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn wire(amount: &str, currency: &str) -> Value {
+        json!({
+            "template": "wire_transfer",
+            "values": { "amount": amount, "currency": currency, "beneficiary": "ACME-123" }
+        })
+    }
+
+    #[test]
+    fn test_amounts_are_localized() {
+        // ARRANGE: A wire transfer context
+        let context = wire("50000", "USD");
+
+        // ACT: Render it in each locale
+        let en = render_value(&context, "en-US").unwrap();
+        let de = render_value(&context, "de_AT").unwrap();
+        let fr = render_value(&context, "fr").unwrap();
+
+        // ASSERT: Each uses its language and number format
+        assert_eq!(en.summary, "Approve wire of $50,000.00 to ACME-123");
+        assert_eq!(de.summary, "Überweisung von 50.000,00\u{A0}$ an ACME-123 freigeben");
+        assert_eq!(de.locale, "de");
+        assert_eq!(fr.summary, "Approuver le virement de 50\u{202F}000,00\u{A0}$ à ACME-123");
+    }
+
+    #[test]
+    fn test_unknown_locales_fall_back_to_english() {
+        let rendered = render_value(&wire("1.5", "EUR"), "ja-JP").unwrap();
+
+        assert_eq!(rendered.locale, "en");
+        assert_eq!(rendered.summary, "Approve wire of €1.50 to ACME-123");
+    }
+
+    #[test]
+    fn test_currency_minor_digits() {
+        assert_eq!(render_value(&wire("1250000", "JPY"), "en").unwrap().summary, "Approve wire of ¥1,250,000 to ACME-123");
+        assert_eq!(render_value(&wire("007.25", "CHF"), "en").unwrap().summary, "Approve wire of CHF\u{A0}7.25 to ACME-123");
+        assert!(matches!(render_value(&wire("10.5", "JPY"), "en"), Err(RenderError::InvalidValue { .. })));
+        assert!(matches!(render_value(&wire("10.001", "USD"), "en"), Err(RenderError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn test_malformed_amounts_are_rejected() {
+        for amount in ["-5", "1e6", "", "1.", ".5", "1,000", " 10"] {
+            assert!(
+                matches!(render_value(&wire(amount, "USD"), "en"), Err(RenderError::InvalidValue { .. })),
+                "{:?}",
+                amount
+            );
+        }
+        let number = json!({ "template": "wire_transfer", "values": { "amount": 50000, "currency": "USD", "beneficiary": "ACME" } });
+        assert!(matches!(render_value(&number, "en"), Err(RenderError::InvalidValue { .. })));
+        assert!(matches!(render_value(&wire("5", "usd"), "en"), Err(RenderError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn test_every_value_must_be_shown() {
+        // ARRANGE: Contexts with a value the summary would not show
+        let mut hidden = wire("10", "USD");
+        hidden["values"]["memo"] = json!("also send the keys");
+        let mut outside = wire("10", "USD");
+        outside["memo"] = json!("also send the keys");
+
+        // ACT & ASSERT: Neither renders, nor does a context missing a value
+        assert_eq!(render_value(&hidden, "en"), Err(RenderError::UnexpectedValue("memo".to_string())));
+        assert_eq!(render_value(&outside, "en"), Err(RenderError::UnexpectedValue("memo".to_string())));
+        let missing = json!({ "template": "login", "values": { "service": "Payroll" } });
+        assert_eq!(render_value(&missing, "en"), Err(RenderError::MissingValue("device".to_string())));
+    }
+
+    #[test]
+    fn test_spoofing_characters_are_rejected() {
+        for beneficiary in ["ACME\u{202E}321", "ACME\u{200B}-123", "ACME\n-123", "   "] {
+            let context = json!({
+                "template": "wire_transfer",
+                "values": { "amount": "10", "currency": "USD", "beneficiary": beneficiary }
+            });
+            assert!(matches!(render_value(&context, "en"), Err(RenderError::InvalidValue { .. })), "{:?}", beneficiary);
+        }
+    }
+
+    #[test]
+    fn test_placeholders_in_values_are_not_expanded() {
+        let context = json!({ "template": "login", "values": { "service": "{device}", "device": "Laptop" } });
+
+        let rendered = render_value(&context, "en").unwrap();
+
+        assert_eq!(rendered.summary, "Approve sign-in to {device} from Laptop");
+    }
+
+    #[test]
+    fn test_unrecognized_contexts() {
+        assert!(matches!(render_context(b"\x01\x02", "en"), Err(RenderError::NotRecognized(_))));
+        assert!(matches!(render_context(b"[1, 2]", "en"), Err(RenderError::NotRecognized(_))));
+        assert!(matches!(render_context(br#"{"values": {}}"#, "en"), Err(RenderError::NotRecognized(_))));
+        assert_eq!(
+            render_context(br#"{"template": "mortgage", "values": {}}"#, "en"),
+            Err(RenderError::UnknownTemplate("mortgage".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_every_template_renders_in_every_locale() {
+        for template in TEMPLATES {
+            let mut values = Map::new();
+            for (name, kind) in template.fields {
+                let value = match kind {
+                    FieldKind::Text => "Example",
+                    FieldKind::Amount { .. } => "10",
+                    FieldKind::Currency => "EUR",
+                };
+                values.insert(name.to_string(), json!(value));
+            }
+            let context = json!({ "template": template.id, "values": values });
+            for locale in SUPPORTED_LOCALES {
+                let rendered = render_value(&context, locale).unwrap();
+                assert!(!rendered.summary.contains('{'), "{} {}", template.id, locale);
+            }
+        }
+    }
+}
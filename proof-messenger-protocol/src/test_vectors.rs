@@ -0,0 +1,359 @@
+//! Deterministic golden vectors shared by every implementation
+//!
+//! Seeded keypairs and Ed25519's deterministic signatures make every output
+//! of the protocol reproducible from a handful of inputs. [`generate`] builds
+//! the full golden set from fixed inputs, and [`golden`] loads the committed
+//! copy in `tests/vectors/golden.json`. The CLI, web and relay test suites
+//! check their own code paths against [`golden`], so an implementation that
+//! drifts from the protocol crate (signing different bytes, encoding keys
+//! differently) fails its tests instead of failing against a peer.
+//!
+//! To regenerate the committed file after an intentional protocol change:
+//! ```bash
+//! UPDATE_GOLDEN_VECTORS=1 cargo test -p proof-messenger-protocol --test golden_vectors
+//! ```
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::test_vectors::{check, golden};
+//!
+//! let vectors = golden();
+//! assert!(check(&vectors).is_ok());
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::envelope::ProofEnvelope;
+use crate::key::{generate_keypair_with_seed, generate_secure_keypair_with_seed};
+use crate::proof::{make_proof, make_secure_proof, verify_proof, verify_proof_result, Invite};
+use crate::receipt::{make_receipt, message_hash, message_hash_from_slice, verify_receipt, Receipt};
+
+/// Format version of the golden vector file
+pub const GOLDEN_VECTORS_VERSION: u32 = 1;
+
+/// The committed golden vectors, as JSON
+pub const GOLDEN_VECTORS_JSON: &str = include_str!("../tests/vectors/golden.json");
+
+/// Seeds of the keypair vectors
+const KEYPAIR_SEEDS: [u64; 4] = [0, 1, 42, u64::MAX];
+
+/// Seeds of the legacy invite proof vectors
+const INVITE_SEEDS: [u64; 2] = [1, 42];
+
+/// The full golden set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoldenVectors {
+    /// Format version, see [`GOLDEN_VECTORS_VERSION`]
+    pub version: u32,
+    /// Keypairs derived from seeds
+    pub keypairs: Vec<KeypairVector>,
+    /// Legacy invite proofs
+    pub invites: Vec<InviteVector>,
+    /// Plain Ed25519 proofs over a context
+    pub proofs: Vec<ProofVector>,
+    /// Proof envelopes over a context
+    pub envelopes: Vec<EnvelopeVector>,
+    /// Message hashes and the receipts acknowledging them
+    pub receipts: Vec<ReceiptVector>,
+}
+
+/// A keypair derived from a seed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeypairVector {
+    /// Seed passed to `generate_secure_keypair_with_seed`
+    pub seed: u64,
+    /// Secret key followed by public key
+    #[serde(with = "hex::serde")]
+    pub keypair: Vec<u8>,
+    /// Ed25519 public key
+    #[serde(with = "hex::serde")]
+    pub public_key: Vec<u8>,
+}
+
+/// A legacy proof over `Invite::new_with_seed(seed)`, signed by the keypair of the same seed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InviteVector {
+    /// Seed of both the invite and the signing keypair
+    pub seed: u64,
+    /// Invite data
+    #[serde(with = "hex::serde")]
+    pub invite_data: Vec<u8>,
+    /// Signer's public key
+    #[serde(with = "hex::serde")]
+    pub public_key: Vec<u8>,
+    /// Signature over the invite data
+    #[serde(with = "hex::serde")]
+    pub proof: Vec<u8>,
+}
+
+/// A plain Ed25519 proof over a context
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofVector {
+    /// Name identifying the vector in failures
+    pub name: String,
+    /// Seed of the signing keypair
+    pub seed: u64,
+    /// Signer's public key
+    #[serde(with = "hex::serde")]
+    pub public_key: Vec<u8>,
+    /// Signed context
+    #[serde(with = "hex::serde")]
+    pub context: Vec<u8>,
+    /// Signature over the context
+    #[serde(with = "hex::serde")]
+    pub signature: Vec<u8>,
+}
+
+/// A proof envelope over a context
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeVector {
+    /// Name identifying the vector in failures
+    pub name: String,
+    /// Seed of the signing keypair
+    pub seed: u64,
+    /// Signed context
+    #[serde(with = "hex::serde")]
+    pub context: Vec<u8>,
+    /// Signed envelope metadata
+    pub metadata: BTreeMap<String, String>,
+    /// Encoded envelope
+    #[serde(with = "hex::serde")]
+    pub envelope: Vec<u8>,
+}
+
+/// A message hash and a receipt acknowledging the message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptVector {
+    /// Name identifying the vector in failures
+    pub name: String,
+    /// Seed of the message sender's keypair
+    pub sender_seed: u64,
+    /// Message context
+    #[serde(with = "hex::serde")]
+    pub context: Vec<u8>,
+    /// Message body
+    pub body: String,
+    /// Relay-assigned message id
+    pub message_id: String,
+    /// Seed of the recipient's keypair
+    pub recipient_seed: u64,
+    /// Hash of the sender's public key, context and body
+    #[serde(with = "hex::serde")]
+    pub message_hash: Vec<u8>,
+    /// Recipient's receipt signature
+    #[serde(with = "hex::serde")]
+    pub signature: Vec<u8>,
+}
+
+/// Load the committed golden vectors
+pub fn golden() -> GoldenVectors {
+    serde_json::from_str(GOLDEN_VECTORS_JSON).expect("golden.json is valid golden vectors")
+}
+
+/// Build the golden set from its fixed inputs
+pub fn generate() -> GoldenVectors {
+    let binary_context: Vec<u8> = (0..=255u8).collect();
+    let key_metadata = BTreeMap::from([
+        ("key_id".to_string(), "device-1".to_string()),
+        ("sdk".to_string(), "rust/0.1".to_string()),
+    ]);
+
+    GoldenVectors {
+        version: GOLDEN_VECTORS_VERSION,
+        keypairs: KEYPAIR_SEEDS.iter().map(|&seed| keypair_vector(seed)).collect(),
+        invites: INVITE_SEEDS.iter().map(|&seed| invite_vector(seed)).collect(),
+        proofs: vec![
+            proof_vector("empty-context", 1, b""),
+            proof_vector("ascii-context", 1, b"approve transfer #1001"),
+            proof_vector("json-context", 2, br#"{"action":"login","user":"alice"}"#),
+            proof_vector("binary-context", 42, &binary_context),
+        ],
+        envelopes: vec![
+            envelope_vector("no-metadata", 3, b"login", BTreeMap::new()),
+            envelope_vector("key-metadata", 3, b"approve transfer #1001", key_metadata),
+        ],
+        receipts: vec![
+            receipt_vector("text-body", 1, b"approve transfer #1001", "Approved", "msg-0001", 2),
+            receipt_vector("empty-body", 2, b"ping", "", "7f9c2ba4-e88f-4b7a-9c1d-3a0e5d6f8b21", 1),
+        ],
+    }
+}
+
+/// Check every vector against this crate's implementation
+///
+/// Each vector is recomputed from its own inputs, so a hand-edited or
+/// foreign golden set can be checked too. Returns one line per mismatch.
+pub fn check(vectors: &GoldenVectors) -> Result<(), Vec<String>> {
+    let mut mismatches = Vec::new();
+    let mut expect = |kind: &str, name: String, ok: bool| {
+        if !ok {
+            mismatches.push(format!("{}/{}", kind, name));
+        }
+    };
+
+    if vectors.version != GOLDEN_VECTORS_VERSION {
+        expect("version", vectors.version.to_string(), false);
+    }
+    for vector in &vectors.keypairs {
+        expect("keypairs", vector.seed.to_string(), *vector == keypair_vector(vector.seed));
+    }
+    for vector in &vectors.invites {
+        expect("invites", vector.seed.to_string(), *vector == invite_vector(vector.seed) && invite_verifies(vector));
+    }
+    for vector in &vectors.proofs {
+        let expected = proof_vector(&vector.name, vector.seed, &vector.context);
+        expect("proofs", vector.name.clone(), *vector == expected && proof_verifies(vector));
+    }
+    for vector in &vectors.envelopes {
+        let expected = envelope_vector(&vector.name, vector.seed, &vector.context, vector.metadata.clone());
+        let verifies = ProofEnvelope::from_bytes(&vector.envelope)
+            .and_then(|envelope| envelope.verify(&vector.context))
+            .is_ok();
+        expect("envelopes", vector.name.clone(), *vector == expected && verifies);
+    }
+    for vector in &vectors.receipts {
+        let expected = receipt_vector(
+            &vector.name,
+            vector.sender_seed,
+            &vector.context,
+            &vector.body,
+            &vector.message_id,
+            vector.recipient_seed,
+        );
+        expect("receipts", vector.name.clone(), *vector == expected && receipt_verifies(vector));
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatches)
+    }
+}
+
+fn keypair_vector(seed: u64) -> KeypairVector {
+    let keypair = generate_secure_keypair_with_seed(seed);
+    KeypairVector {
+        seed,
+        keypair: keypair.to_bytes().to_vec(),
+        public_key: keypair.public_key_bytes().to_vec(),
+    }
+}
+
+fn invite_vector(seed: u64) -> InviteVector {
+    let keypair = generate_keypair_with_seed(seed);
+    let invite = Invite::new_with_seed(seed);
+    InviteVector {
+        seed,
+        invite_data: invite.data.clone(),
+        public_key: keypair.public.to_bytes().to_vec(),
+        proof: make_proof(&keypair, &invite).to_bytes().to_vec(),
+    }
+}
+
+fn proof_vector(name: &str, seed: u64, context: &[u8]) -> ProofVector {
+    let keypair = generate_secure_keypair_with_seed(seed);
+    ProofVector {
+        name: name.to_string(),
+        seed,
+        public_key: keypair.public_key_bytes().to_vec(),
+        context: context.to_vec(),
+        signature: make_secure_proof(&keypair, context)
+            .expect("golden contexts are within limits")
+            .to_bytes()
+            .to_vec(),
+    }
+}
+
+fn envelope_vector(name: &str, seed: u64, context: &[u8], metadata: BTreeMap<String, String>) -> EnvelopeVector {
+    let keypair = generate_secure_keypair_with_seed(seed);
+    let envelope = ProofEnvelope::sign(&keypair, context, metadata.clone()).expect("golden contexts are within limits");
+    EnvelopeVector {
+        name: name.to_string(),
+        seed,
+        context: context.to_vec(),
+        metadata,
+        envelope: envelope.to_bytes(),
+    }
+}
+
+fn receipt_vector(
+    name: &str,
+    sender_seed: u64,
+    context: &[u8],
+    body: &str,
+    message_id: &str,
+    recipient_seed: u64,
+) -> ReceiptVector {
+    let sender = generate_secure_keypair_with_seed(sender_seed).public_key_bytes();
+    let hash = message_hash(&sender, context, body.as_bytes());
+    let receipt = make_receipt(&generate_secure_keypair_with_seed(recipient_seed), message_id, &hash)
+        .expect("golden message ids are not empty");
+    ReceiptVector {
+        name: name.to_string(),
+        sender_seed,
+        context: context.to_vec(),
+        body: body.to_string(),
+        message_id: message_id.to_string(),
+        recipient_seed,
+        message_hash: hash.to_vec(),
+        signature: receipt.signature.to_bytes().to_vec(),
+    }
+}
+
+fn invite_verifies(vector: &InviteVector) -> bool {
+    match (PublicKey::from_bytes(&vector.public_key), Signature::from_bytes(&vector.proof)) {
+        (Ok(public), Ok(signature)) => {
+            verify_proof(&signature, &public, &Invite { data: vector.invite_data.clone() })
+        }
+        _ => false,
+    }
+}
+
+fn proof_verifies(vector: &ProofVector) -> bool {
+    match (PublicKey::from_bytes(&vector.public_key), Signature::from_bytes(&vector.signature)) {
+        (Ok(public), Ok(signature)) => verify_proof_result(&public, &vector.context, &signature).is_ok(),
+        _ => false,
+    }
+}
+
+fn receipt_verifies(vector: &ReceiptVector) -> bool {
+    let (Ok(hash), Ok(signature)) = (
+        message_hash_from_slice(&vector.message_hash),
+        Signature::from_bytes(&vector.signature),
+    ) else {
+        return false;
+    };
+    let receipt = Receipt {
+        message_id: vector.message_id.clone(),
+        message_hash: hash,
+        recipient: generate_secure_keypair_with_seed(vector.recipient_seed).public_key(),
+        signature,
+    };
+    verify_receipt(&receipt, &vector.message_id, &hash).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_reports_each_drifted_vector() {
+        let mut vectors = generate();
+        vectors.proofs[1].signature[0] ^= 1;
+        vectors.receipts[0].body.push('!');
+
+        let mismatches = check(&vectors).unwrap_err();
+
+        assert_eq!(mismatches, vec!["proofs/ascii-context".to_string(), "receipts/text-body".to_string()]);
+    }
+
+    #[test]
+    fn legacy_and_secure_seeded_keypairs_agree() {
+        for seed in KEYPAIR_SEEDS {
+            let legacy = generate_keypair_with_seed(seed);
+            assert_eq!(legacy.to_bytes().to_vec(), keypair_vector(seed).keypair, "seed {}", seed);
+        }
+    }
+}
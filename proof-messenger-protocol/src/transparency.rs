@@ -0,0 +1,286 @@
+//! Transparency log: a Merkle tree over every proof a relay has verified
+//!
+//! A relay appends one leaf per verified message and periodically publishes a
+//! [`SignedTreeHead`] committing to the whole log. Anyone holding a message
+//! can then check, with an inclusion proof, that the message is in the log
+//! exactly as it was accepted; a relay that later drops or alters a proof can
+//! no longer produce a tree head that is consistent with what it signed.
+//!
+//! Hashing follows RFC 6962 (Certificate Transparency): leaves are hashed
+//! with a `0x00` prefix and interior nodes with a `0x01` prefix, so a leaf
+//! can never be passed off as an interior node.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::receipt::message_hash;
+//! use proof_messenger_protocol::transparency::{inclusion_proof, leaf_hash, root_hash, verify_inclusion};
+//!
+//! let leaves: Vec<_> = (0..5)
+//!     .map(|i| leaf_hash(&format!("message-{}", i), &message_hash(b"sender", b"ctx", b"hi"), b"proof"))
+//!     .collect();
+//! let root = root_hash(&leaves);
+//! let proof = inclusion_proof(&leaves, 3).unwrap();
+//!
+//! assert!(verify_inclusion(&leaves[3], 3, leaves.len() as u64, &proof, &root).is_ok());
+//! ```
+
+use ed25519_dalek::{PublicKey, Signature};
+use sha2::{Digest, Sha256};
+
+use crate::key::SecureKeypair;
+use crate::proof::{make_secure_proof, verify_proof_result, ProofError};
+use crate::receipt::MESSAGE_HASH_LENGTH;
+
+/// Length of a tree hash in bytes (SHA-256)
+pub const TREE_HASH_LENGTH: usize = 32;
+
+/// A leaf or interior node hash
+pub type TreeHash = [u8; TREE_HASH_LENGTH];
+
+/// Domain separation prefix for log entries
+const LEAF_DOMAIN: &[u8] = b"proof-messenger/transparency-leaf/v1";
+
+/// Domain separation prefix for signed tree heads
+const TREE_HEAD_DOMAIN: &[u8] = b"proof-messenger/tree-head/v1";
+
+/// A relay's signed commitment to the first `tree_size` entries of its log
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedTreeHead {
+    /// Number of leaves covered by the tree head
+    pub tree_size: u64,
+    /// When the tree head was signed, in milliseconds since the Unix epoch
+    pub timestamp: i64,
+    /// Merkle root of the first `tree_size` leaves
+    pub root_hash: TreeHash,
+    /// Relay's signature over [`tree_head_context`]
+    pub signature: Signature,
+}
+
+impl SignedTreeHead {
+    /// The context the relay signed for this tree head
+    pub fn context(&self) -> Vec<u8> {
+        tree_head_context(self.tree_size, self.timestamp, &self.root_hash)
+    }
+}
+
+/// Hash the log entry for a verified message
+///
+/// The entry binds the relay-assigned message id to the hash of the message
+/// contents and the sender's proof, each length prefixed.
+pub fn leaf_hash(message_id: &str, message_hash: &[u8; MESSAGE_HASH_LENGTH], proof: &[u8]) -> TreeHash {
+    let mut hasher = Sha256::new();
+    hasher.update([0x00]);
+    hasher.update(LEAF_DOMAIN);
+    for field in [message_id.as_bytes(), message_hash, proof] {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field);
+    }
+    hasher.finalize().into()
+}
+
+/// Hash two child nodes into their parent
+pub fn node_hash(left: &TreeHash, right: &TreeHash) -> TreeHash {
+    let mut hasher = Sha256::new();
+    hasher.update([0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Merkle root of a list of leaf hashes
+///
+/// The root of an empty log is the hash of the empty string.
+pub fn root_hash(leaves: &[TreeHash]) -> TreeHash {
+    match leaves.len() {
+        0 => Sha256::digest(b"").into(),
+        1 => leaves[0],
+        n => {
+            let split = split_point(n);
+            node_hash(&root_hash(&leaves[..split]), &root_hash(&leaves[split..]))
+        }
+    }
+}
+
+/// Audit path proving the leaf at `index` is included in the tree of `leaves`
+pub fn inclusion_proof(leaves: &[TreeHash], index: u64) -> Result<Vec<TreeHash>, ProofError> {
+    let index = usize::try_from(index)
+        .ok()
+        .filter(|&index| index < leaves.len())
+        .ok_or_else(|| ProofError::InvalidInput(format!("Leaf {} is not in a tree of size {}", index, leaves.len())))?;
+
+    let mut path = Vec::new();
+    let (mut leaves, mut index) = (leaves, index);
+    while leaves.len() > 1 {
+        let split = split_point(leaves.len());
+        if index < split {
+            path.push(root_hash(&leaves[split..]));
+            leaves = &leaves[..split];
+        } else {
+            path.push(root_hash(&leaves[..split]));
+            leaves = &leaves[split..];
+            index -= split;
+        }
+    }
+    path.reverse();
+    Ok(path)
+}
+
+/// Verify an audit path from a leaf to the expected root
+pub fn verify_inclusion(
+    leaf: &TreeHash,
+    index: u64,
+    tree_size: u64,
+    proof: &[TreeHash],
+    root: &TreeHash,
+) -> Result<(), ProofError> {
+    if index >= tree_size {
+        return Err(ProofError::InvalidInput(format!("Leaf {} is not in a tree of size {}", index, tree_size)));
+    }
+
+    let (mut node, mut last) = (index, tree_size - 1);
+    let mut hash = *leaf;
+    for sibling in proof {
+        if last == 0 {
+            return Err(ProofError::InvalidData("Inclusion proof is too long".to_string()));
+        }
+        if node & 1 == 1 || node == last {
+            hash = node_hash(sibling, &hash);
+            while node & 1 == 0 && node != 0 {
+                node >>= 1;
+                last >>= 1;
+            }
+        } else {
+            hash = node_hash(&hash, sibling);
+        }
+        node >>= 1;
+        last >>= 1;
+    }
+
+    if last != 0 {
+        return Err(ProofError::InvalidData("Inclusion proof is too short".to_string()));
+    }
+    if &hash != root {
+        return Err(ProofError::InvalidData("Inclusion proof does not match the root hash".to_string()));
+    }
+    Ok(())
+}
+
+/// Build the bytes a relay signs to commit to a tree head
+pub fn tree_head_context(tree_size: u64, timestamp: i64, root_hash: &TreeHash) -> Vec<u8> {
+    let mut context = Vec::with_capacity(TREE_HEAD_DOMAIN.len() + 16 + TREE_HASH_LENGTH);
+    context.extend_from_slice(TREE_HEAD_DOMAIN);
+    context.extend_from_slice(&tree_size.to_be_bytes());
+    context.extend_from_slice(&timestamp.to_be_bytes());
+    context.extend_from_slice(root_hash);
+    context
+}
+
+/// Sign a tree head for the log's current state
+pub fn sign_tree_head(
+    keypair: &SecureKeypair,
+    tree_size: u64,
+    timestamp: i64,
+    root_hash: &TreeHash,
+) -> Result<SignedTreeHead, ProofError> {
+    let signature = make_secure_proof(keypair, &tree_head_context(tree_size, timestamp, root_hash))?;
+    Ok(SignedTreeHead {
+        tree_size,
+        timestamp,
+        root_hash: *root_hash,
+        signature,
+    })
+}
+
+/// Verify a tree head was signed by the relay's log key
+pub fn verify_tree_head(tree_head: &SignedTreeHead, public_key: &PublicKey) -> Result<(), ProofError> {
+    verify_proof_result(public_key, &tree_head.context(), &tree_head.signature)
+}
+
+/// Parse a tree hash from a byte slice
+pub fn tree_hash_from_slice(bytes: &[u8]) -> Result<TreeHash, ProofError> {
+    bytes.try_into().map_err(|_| {
+        ProofError::InvalidData(format!(
+            "Tree hash must be {} bytes (got {})",
+            TREE_HASH_LENGTH,
+            bytes.len()
+        ))
+    })
+}
+
+/// Size of the left subtree of a tree with `n > 1` leaves: the largest power of two below `n`
+fn split_point(n: usize) -> usize {
+    debug_assert!(n > 1);
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+    use crate::receipt::message_hash;
+
+    fn leaves(n: usize) -> Vec<TreeHash> {
+        (0..n)
+            .map(|i| leaf_hash(&format!("msg-{}", i), &message_hash(b"alice", b"ctx", b"hello"), b"proof"))
+            .collect()
+    }
+
+    #[test]
+    fn inclusion_proofs_verify_for_every_leaf() {
+        for size in 1..=17 {
+            let leaves = leaves(size);
+            let root = root_hash(&leaves);
+
+            for index in 0..size {
+                let proof = inclusion_proof(&leaves, index as u64).unwrap();
+                assert!(
+                    verify_inclusion(&leaves[index], index as u64, size as u64, &proof, &root).is_ok(),
+                    "leaf {} of {}",
+                    index,
+                    size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn inclusion_proof_rejects_wrong_leaf_index_or_root() {
+        let leaves = leaves(7);
+        let root = root_hash(&leaves);
+        let proof = inclusion_proof(&leaves, 2).unwrap();
+
+        assert!(verify_inclusion(&leaves[3], 2, 7, &proof, &root).is_err());
+        assert!(verify_inclusion(&leaves[2], 3, 7, &proof, &root).is_err());
+        assert!(verify_inclusion(&leaves[2], 2, 7, &proof, &root_hash(&leaves[..6])).is_err());
+        assert!(verify_inclusion(&leaves[2], 2, 7, &proof[..proof.len() - 1], &root).is_err());
+        assert!(inclusion_proof(&leaves, 7).is_err());
+    }
+
+    #[test]
+    fn root_matches_rfc6962_shape() {
+        let leaves = leaves(3);
+
+        assert_eq!(root_hash(&[]), <[u8; 32]>::from(Sha256::digest(b"")));
+        assert_eq!(root_hash(&leaves[..1]), leaves[0]);
+        assert_eq!(
+            root_hash(&leaves),
+            node_hash(&node_hash(&leaves[0], &leaves[1]), &leaves[2])
+        );
+    }
+
+    #[test]
+    fn tree_head_signature_covers_size_time_and_root() {
+        let keypair = generate_secure_keypair_with_seed(9);
+        let root = root_hash(&leaves(4));
+
+        let head = sign_tree_head(&keypair, 4, 1_700_000_000_000, &root).unwrap();
+        assert!(verify_tree_head(&head, &keypair.public_key()).is_ok());
+
+        let mut shrunk = head.clone();
+        shrunk.tree_size = 3;
+        assert!(matches!(
+            verify_tree_head(&shrunk, &keypair.public_key()),
+            Err(ProofError::VerificationFailed(_))
+        ));
+    }
+}
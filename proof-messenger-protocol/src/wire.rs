@@ -0,0 +1,164 @@
+//! Compact binary wire format for relayed messages
+//!
+//! The relay's JSON API carries keys, contexts and proofs as hex strings,
+//! which doubles their size on the wire. [`WireMessage`] holds the same
+//! fields as raw bytes and is encoded as CBOR (RFC 8949), so clients on
+//! constrained links can submit messages to `POST /relay` with a
+//! `Content-Type` of [`CBOR_CONTENT_TYPE`] at roughly half the size.
+//!
+//! ## Example
+//! ```rust
+//! use proof_messenger_protocol::key::generate_secure_keypair;
+//! use proof_messenger_protocol::proof::make_secure_proof;
+//! use proof_messenger_protocol::wire::WireMessage;
+//!
+//! let keypair = generate_secure_keypair();
+//! let context = b"sensor-42/reading";
+//! let proof = make_secure_proof(&keypair, context).unwrap();
+//!
+//! let message = WireMessage::new(&keypair.public_key(), context, "21.5C", &proof);
+//! let bytes = message.to_cbor().unwrap();
+//!
+//! assert_eq!(WireMessage::from_cbor(&bytes).unwrap(), message);
+//! ```
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{ProtocolError, Result};
+
+/// Media type of CBOR encoded messages
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+/// A message submitted to the relay, with binary fields as raw bytes
+///
+/// Field names and optional fields match the relay's JSON `Message`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WireMessage {
+    /// Ed25519 public key of the sender
+    #[serde(with = "serde_bytes")]
+    pub sender: Vec<u8>,
+    /// Context data that was signed
+    #[serde(with = "serde_bytes")]
+    pub context: Vec<u8>,
+    /// Message body content
+    pub body: String,
+    /// Ed25519 signature over the context
+    #[serde(with = "serde_bytes")]
+    pub proof: Vec<u8>,
+    /// Optional post-quantum half of a hybrid proof
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pqc: Option<WirePqcProof>,
+    /// Optional thread to post into (the thread's root message ID)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+    /// Optional ID of the message this one replies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    /// Optional time after which the relay stops serving the message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Optional Ed25519 public key of the only recipient
+    #[serde(default, with = "serde_bytes", skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<Vec<u8>>,
+}
+
+/// Post-quantum (ML-DSA-65) half of a hybrid proof, as raw bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WirePqcProof {
+    /// ML-DSA-65 public key of the sender
+    #[serde(with = "serde_bytes")]
+    pub public_key: Vec<u8>,
+    /// ML-DSA-65 signature over the context
+    #[serde(with = "serde_bytes")]
+    pub proof: Vec<u8>,
+}
+
+impl WireMessage {
+    /// Build a message from a sender's key, the signed context and its proof
+    pub fn new(sender: &PublicKey, context: &[u8], body: impl Into<String>, proof: &Signature) -> Self {
+        Self {
+            sender: sender.to_bytes().to_vec(),
+            context: context.to_vec(),
+            body: body.into(),
+            proof: proof.to_bytes().to_vec(),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    /// Encode the message as CBOR
+    pub fn to_cbor(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(self, &mut bytes)
+            .map_err(|e| ProtocolError::Serialization(format!("Failed to encode message as CBOR: {}", e)))?;
+        Ok(bytes)
+    }
+
+    /// Decode a CBOR encoded message
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self> {
+        ciborium::from_reader(bytes)
+            .map_err(|e| ProtocolError::Serialization(format!("Failed to decode CBOR message: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_secure_keypair_with_seed;
+    use crate::proof::make_secure_proof;
+
+    fn message(context: &[u8]) -> WireMessage {
+        let keypair = generate_secure_keypair_with_seed(3);
+        let proof = make_secure_proof(&keypair, context).unwrap();
+        WireMessage::new(&keypair.public_key(), context, "hello", &proof)
+    }
+
+    #[test]
+    fn cbor_roundtrip_preserves_optional_fields() {
+        let mut message = message(b"context");
+        message.thread_id = Some("thread-1".to_string());
+        message.expires_at = DateTime::from_timestamp(1_900_000_000, 0);
+        message.recipient = Some(vec![3; 32]);
+        message.pqc = Some(WirePqcProof {
+            public_key: vec![1; 1952],
+            proof: vec![2; 3309],
+        });
+
+        let decoded = WireMessage::from_cbor(&message.to_cbor().unwrap()).unwrap();
+
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn cbor_is_smaller_than_hex_json() {
+        let message = message(&[7; 4096]);
+        let hex_json = serde_json::json!({
+            "sender": "00".repeat(message.sender.len()),
+            "context": "00".repeat(message.context.len()),
+            "body": message.body,
+            "proof": "00".repeat(message.proof.len()),
+        });
+
+        let cbor = message.to_cbor().unwrap().len();
+        let json = hex_json.to_string().len();
+
+        // Under 60% of the hex JSON size
+        assert!(cbor * 10 < json * 6, "{} bytes of CBOR vs {} bytes of JSON", cbor, json);
+    }
+
+    #[test]
+    fn malformed_cbor_is_rejected() {
+        let bytes = message(b"context").to_cbor().unwrap();
+
+        assert!(matches!(
+            WireMessage::from_cbor(&bytes[..bytes.len() - 3]),
+            Err(ProtocolError::Serialization(_))
+        ));
+        assert!(WireMessage::from_cbor(b"{\"sender\": \"aa\"}").is_err());
+    }
+}
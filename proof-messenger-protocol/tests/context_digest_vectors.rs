@@ -0,0 +1,49 @@
+//! Context digest test vectors
+//!
+//! The same vectors are checked by the CLI and web crates, so every
+//! implementation hashes and signs large contexts identically.
+
+use ed25519_dalek::Signature;
+use proof_messenger_protocol::context_digest::{sign_context_digest, verify_context_digest, ContextHasher};
+use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+use serde_json::Value;
+
+const VECTORS: &str = include_str!("vectors/context_digest.json");
+
+/// The context bytes a vector describes
+fn vector_context(vector: &Value) -> Vec<u8> {
+    match vector["context_hex"].as_str() {
+        Some(context) => hex::decode(context).unwrap(),
+        None => {
+            let pattern = &vector["context_pattern"];
+            let modulus = pattern["modulus"].as_u64().unwrap();
+            (0..pattern["length"].as_u64().unwrap()).map(|i| (i % modulus) as u8).collect()
+        }
+    }
+}
+
+#[test]
+fn context_digest_vectors_match() {
+    let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+
+    for vector in vectors["vectors"].as_array().unwrap() {
+        let name = vector["name"].as_str().unwrap();
+        let context = vector_context(vector);
+        let keypair = generate_secure_keypair_with_seed(vector["seed"].as_u64().unwrap());
+
+        // Odd-sized chunks exercise the streaming path
+        let mut hasher = ContextHasher::new();
+        for chunk in context.chunks(1000) {
+            hasher.update(chunk);
+        }
+        let digest = hasher.finalize();
+        let signature = sign_context_digest(&keypair, &digest).unwrap();
+
+        assert_eq!(hex::encode(keypair.public_key_bytes()), vector["public_key"].as_str().unwrap(), "{}", name);
+        assert_eq!(hex::encode(digest), vector["digest"].as_str().unwrap(), "{}", name);
+        assert_eq!(hex::encode(signature.to_bytes()), vector["signature"].as_str().unwrap(), "{}", name);
+
+        let expected = Signature::from_bytes(&hex::decode(vector["signature"].as_str().unwrap()).unwrap()).unwrap();
+        assert!(verify_context_digest(&keypair.public_key(), &digest, &expected).is_ok(), "{}", name);
+    }
+}
@@ -0,0 +1,27 @@
+//! Golden vector conformance
+//!
+//! The CLI, web and relay crates check their own code paths against the
+//! same file. Set `UPDATE_GOLDEN_VECTORS=1` to rewrite it after an
+//! intentional protocol change.
+
+use proof_messenger_protocol::test_vectors::{check, generate, golden};
+
+#[test]
+fn golden_vectors_are_current() {
+    let generated = generate();
+
+    if std::env::var_os("UPDATE_GOLDEN_VECTORS").is_some() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/vectors/golden.json");
+        std::fs::write(path, serde_json::to_string_pretty(&generated).unwrap() + "\n").unwrap();
+        return;
+    }
+
+    assert_eq!(golden(), generated, "golden.json is stale; rerun with UPDATE_GOLDEN_VECTORS=1");
+}
+
+#[test]
+fn golden_vectors_match_the_protocol() {
+    if let Err(mismatches) = check(&golden()) {
+        panic!("golden vectors drifted from the protocol: {:?}", mismatches);
+    }
+}
@@ -1,4 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ed25519_dalek::Signer;
 use proof_messenger_relay::{Message, process_and_verify_message};
 use proof_messenger_protocol::key::generate_keypair_with_seed;
 
@@ -12,6 +13,11 @@ fn create_test_message(keypair_seed: u64, context: &[u8], body: &str) -> Message
         context: hex::encode(context),
         body: body.to_string(),
         proof: hex::encode(signature.to_bytes()),
+        pqc: None,
+        thread_id: None,
+        reply_to: None,
+        expires_at: None,
+        recipient: None,
     }
 }
 
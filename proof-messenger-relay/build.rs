@@ -0,0 +1,11 @@
+//! Compile the relay's protobuf definitions into gRPC server and client code
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use the vendored protoc unless one is provided explicitly
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+
+    tonic_build::configure().compile_protos(&["proto/relay.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,21 @@
+-- Migration for the invite lifecycle
+-- Creates the invites table for single-use invite codes bound to a group
+
+CREATE TABLE IF NOT EXISTS invites (
+    code TEXT PRIMARY KEY NOT NULL,
+    group_id TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL,
+    created_by TEXT,
+    redeemed_by TEXT,
+    redeemed_at DATETIME
+);
+
+-- Index for listing invites of a group
+CREATE INDEX IF NOT EXISTS idx_invites_group_id
+ON invites(group_id);
+
+-- Index for expiration-based cleanup
+CREATE INDEX IF NOT EXISTS idx_invites_expires_at
+ON invites(expires_at);
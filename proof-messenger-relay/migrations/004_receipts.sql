@@ -0,0 +1,16 @@
+-- Migration for delivery receipts
+-- Creates the receipts table for recipient-signed message acknowledgments
+
+CREATE TABLE IF NOT EXISTS receipts (
+    message_id TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    message_hash TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (message_id, recipient),
+    FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
+
+-- Index for listing receipts of a message in order
+CREATE INDEX IF NOT EXISTS idx_receipts_message_id_created_at
+ON receipts(message_id, created_at);
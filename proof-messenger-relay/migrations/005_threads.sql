@@ -0,0 +1,9 @@
+-- Migration for threaded conversations
+-- Adds thread and reply references to stored messages
+
+ALTER TABLE messages ADD COLUMN thread_id TEXT;
+ALTER TABLE messages ADD COLUMN reply_to TEXT;
+
+-- Index for retrieving a thread in order
+CREATE INDEX IF NOT EXISTS idx_messages_thread_id_created_at
+ON messages(thread_id, created_at);
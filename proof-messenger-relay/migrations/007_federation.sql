@@ -0,0 +1,21 @@
+-- Migration for relay federation
+-- Tracks the origin of every message exchanged with peer relays so that
+-- forwarded copies are stored once and reconciliation can find gaps
+
+CREATE TABLE IF NOT EXISTS federated_messages (
+    origin_relay TEXT NOT NULL,
+    origin_message_id TEXT NOT NULL,
+    local_message_id TEXT NOT NULL,
+    received_from TEXT,
+    received_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (origin_relay, origin_message_id),
+    FOREIGN KEY (local_message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
+
+-- Index for digest queries used by reconciliation
+CREATE INDEX IF NOT EXISTS idx_federated_messages_received_at
+ON federated_messages(received_at);
+
+-- Index for looking up the origin of a local message
+CREATE INDEX IF NOT EXISTS idx_federated_messages_local_message_id
+ON federated_messages(local_message_id);
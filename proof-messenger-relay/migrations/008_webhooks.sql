@@ -0,0 +1,39 @@
+-- Migration for webhook notifications
+-- Creates the webhooks table for registered callback URLs and the
+-- webhook_deliveries table tracking each event's delivery attempts
+
+CREATE TABLE IF NOT EXISTS webhooks (
+    id TEXT PRIMARY KEY NOT NULL,
+    url TEXT NOT NULL,
+    group_id TEXT,
+    secret TEXT NOT NULL,
+    created_by TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id TEXT PRIMARY KEY NOT NULL,
+    webhook_id TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    response_status INTEGER,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    next_attempt_at DATETIME NOT NULL,
+    delivered_at DATETIME,
+    FOREIGN KEY (webhook_id) REFERENCES webhooks(id) ON DELETE CASCADE
+);
+
+-- Index for finding webhooks subscribed to a group
+CREATE INDEX IF NOT EXISTS idx_webhooks_group_id
+ON webhooks(group_id);
+
+-- Index for the delivery worker picking up due deliveries
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_status_next_attempt
+ON webhook_deliveries(status, next_attempt_at);
+
+-- Index for listing a webhook's deliveries
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook_id_created_at
+ON webhook_deliveries(webhook_id, created_at);
@@ -0,0 +1,25 @@
+-- Migration for the dead-letter queue
+-- Creates the rejected_messages table holding messages that failed
+-- verification while quarantine mode is enabled
+
+CREATE TABLE IF NOT EXISTS rejected_messages (
+    id TEXT PRIMARY KEY NOT NULL,
+    sender TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    error TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    submitted_by TEXT,
+    rejected_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Index for listing recent rejections and purging old ones
+CREATE INDEX IF NOT EXISTS idx_rejected_messages_rejected_at
+ON rejected_messages(rejected_at);
+
+-- Index for filtering rejections by reason
+CREATE INDEX IF NOT EXISTS idx_rejected_messages_reason_rejected_at
+ON rejected_messages(reason, rejected_at);
+
+-- Index for filtering rejections by sender
+CREATE INDEX IF NOT EXISTS idx_rejected_messages_sender_rejected_at
+ON rejected_messages(sender, rejected_at);
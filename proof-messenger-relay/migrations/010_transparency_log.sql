@@ -0,0 +1,23 @@
+-- Migration for the transparency log
+-- Creates the append-only transparency_log table holding one Merkle leaf
+-- per verified message, in the order messages were accepted
+
+CREATE TABLE IF NOT EXISTS transparency_log (
+    leaf_index INTEGER PRIMARY KEY NOT NULL,
+    message_id TEXT NOT NULL UNIQUE,
+    leaf_hash TEXT NOT NULL,
+    appended_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Published tree heads commit to every leaf, so entries can never change
+CREATE TRIGGER IF NOT EXISTS transparency_log_no_update
+BEFORE UPDATE ON transparency_log
+BEGIN
+    SELECT RAISE(ABORT, 'transparency_log is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS transparency_log_no_delete
+BEFORE DELETE ON transparency_log
+BEGIN
+    SELECT RAISE(ABORT, 'transparency_log is append-only');
+END;
@@ -0,0 +1,19 @@
+-- Migration for the compliance audit trail
+-- Creates the compliance_audit_entries table holding the compliance audit
+-- entries forwarded by the `database` audit sink
+
+CREATE TABLE IF NOT EXISTS compliance_audit_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_type TEXT NOT NULL,
+    context_type TEXT NOT NULL,
+    risk_level TEXT NOT NULL,
+    compliance_status TEXT NOT NULL,
+    event_details TEXT NOT NULL,
+    session_id TEXT,
+    user_id TEXT,
+    logged_at DATETIME NOT NULL
+);
+
+-- Index for summarizing recent entries
+CREATE INDEX IF NOT EXISTS idx_compliance_audit_entries_logged_at
+ON compliance_audit_entries(logged_at);
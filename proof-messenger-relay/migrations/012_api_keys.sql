@@ -0,0 +1,16 @@
+-- Migration for API key authentication
+-- Creates the api_keys table holding machine-to-machine credentials; only
+-- the SHA-256 hash of each key is stored
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    key_prefix TEXT NOT NULL,
+    scopes TEXT NOT NULL,
+    requests_per_minute INTEGER,
+    created_by TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    rotated_at DATETIME,
+    revoked_at DATETIME
+);
@@ -0,0 +1,6 @@
+-- Migration for message deletion
+-- Deleted messages are kept as tombstones: the body is cleared, while the
+-- proof, metadata and the hash the transparency log commits to remain
+
+ALTER TABLE messages ADD COLUMN deleted_at DATETIME;
+ALTER TABLE messages ADD COLUMN message_hash TEXT;
@@ -0,0 +1,18 @@
+-- Migration for data subject erasure
+-- Creates the erasure_requests table holding scheduled erasures of a
+-- sender's messages or a user's audit references
+
+CREATE TABLE IF NOT EXISTS erasure_requests (
+    id TEXT PRIMARY KEY NOT NULL,
+    sender TEXT,
+    user_id TEXT,
+    requested_by TEXT,
+    requested_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    execute_after DATETIME NOT NULL,
+    status TEXT NOT NULL DEFAULT 'scheduled',
+    completed_at DATETIME
+);
+
+-- Index for finding erasures that are due
+CREATE INDEX IF NOT EXISTS idx_erasure_requests_status_execute_after
+ON erasure_requests(status, execute_after);
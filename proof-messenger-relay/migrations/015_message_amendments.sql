@@ -0,0 +1,15 @@
+-- Migration for message amendments
+-- Each amendment is a sender-signed new version of a message body, chained
+-- to the version it replaces by hash
+
+CREATE TABLE IF NOT EXISTS message_amendments (
+    message_id TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    body TEXT NOT NULL,
+    previous_hash TEXT NOT NULL,
+    amendment_hash TEXT NOT NULL,
+    proof TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (message_id, version),
+    FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
@@ -0,0 +1,19 @@
+-- Migration for detached proofs
+-- Signatures over the digest and metadata of documents the relay never sees
+
+CREATE TABLE IF NOT EXISTS detached_proofs (
+    id TEXT PRIMARY KEY NOT NULL,
+    algorithm TEXT NOT NULL,
+    digest TEXT NOT NULL,
+    filename TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    content_type TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    signature TEXT NOT NULL UNIQUE,
+    registered_by TEXT,
+    registered_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Index for looking up every proof over a document
+CREATE INDEX IF NOT EXISTS idx_detached_proofs_digest
+ON detached_proofs(digest, registered_at);
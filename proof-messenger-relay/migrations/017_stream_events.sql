@@ -0,0 +1,19 @@
+-- Migration for the verified-message event stream
+-- Queues each event until the streaming platform acknowledges it, so events
+-- survive broker outages and relay restarts
+
+CREATE TABLE IF NOT EXISTS stream_events (
+    id TEXT PRIMARY KEY NOT NULL,
+    message_id TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    next_attempt_at DATETIME NOT NULL,
+    published_at DATETIME
+);
+
+-- Index for the publisher worker picking up due events
+CREATE INDEX IF NOT EXISTS idx_stream_events_status_next_attempt
+ON stream_events(status, next_attempt_at);
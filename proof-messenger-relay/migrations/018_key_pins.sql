@@ -0,0 +1,31 @@
+-- Migration for trust-on-first-use key pinning
+-- Pins the first public key each authenticated user relays with, records
+-- every key change, and lets webhook deliveries carry event types other
+-- than message.verified
+
+CREATE TABLE IF NOT EXISTS key_pins (
+    user_id TEXT PRIMARY KEY NOT NULL,
+    public_key TEXT NOT NULL,
+    first_seen_at DATETIME NOT NULL,
+    pinned_at DATETIME NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS key_changes (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL,
+    previous_key TEXT NOT NULL,
+    new_key TEXT NOT NULL,
+    action TEXT NOT NULL,
+    request_id TEXT,
+    detected_at DATETIME NOT NULL
+);
+
+-- Index for listing a user's key changes
+CREATE INDEX IF NOT EXISTS idx_key_changes_user_id_detected_at
+ON key_changes(user_id, detected_at);
+
+-- Index for listing recent key changes
+CREATE INDEX IF NOT EXISTS idx_key_changes_detected_at
+ON key_changes(detected_at);
+
+ALTER TABLE webhook_deliveries ADD COLUMN event_type TEXT NOT NULL DEFAULT 'message.verified';
@@ -0,0 +1,20 @@
+-- Migration for proof chains
+-- Maps each relayed proof's hash to its message, and records the parent
+-- proofs a chained proof's context commits to
+
+CREATE TABLE IF NOT EXISTS proof_chain_nodes (
+    proof_hash TEXT PRIMARY KEY NOT NULL,
+    message_id TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS proof_chain_links (
+    child_hash TEXT NOT NULL,
+    parent_hash TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (child_hash, parent_hash)
+);
+
+-- Index for walking from a proof to its descendants
+CREATE INDEX IF NOT EXISTS idx_proof_chain_links_parent_hash
+ON proof_chain_links(parent_hash);
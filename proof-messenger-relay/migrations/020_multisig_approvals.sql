@@ -0,0 +1,26 @@
+-- Migration for multi-signature approvals
+-- Holds pending m-of-n approvals and the co-signatures collected for them
+
+CREATE TABLE IF NOT EXISTS multisig_approvals (
+    id TEXT PRIMARY KEY NOT NULL,
+    payload TEXT NOT NULL,
+    threshold INTEGER NOT NULL,
+    signers TEXT NOT NULL,
+    policy_hash TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_by TEXT,
+    created_at DATETIME NOT NULL,
+    approved_at DATETIME
+);
+
+CREATE TABLE IF NOT EXISTS multisig_signatures (
+    approval_id TEXT NOT NULL,
+    signer TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    signed_at DATETIME NOT NULL,
+    PRIMARY KEY (approval_id, signer)
+);
+
+-- Index for listing approvals by status
+CREATE INDEX IF NOT EXISTS idx_multisig_approvals_status_created_at
+ON multisig_approvals(status, created_at);
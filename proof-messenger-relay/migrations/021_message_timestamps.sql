@@ -0,0 +1,14 @@
+-- Migration for RFC 3161 timestamping
+-- Stores the timestamp token a Time-Stamp Authority issued for each stored
+-- message, so auditors can check the time independently of the relay
+
+CREATE TABLE IF NOT EXISTS message_timestamps (
+    message_id TEXT PRIMARY KEY NOT NULL,
+    tsa_url TEXT NOT NULL,
+    message_imprint TEXT NOT NULL,
+    serial_number TEXT NOT NULL,
+    policy TEXT NOT NULL,
+    gen_time DATETIME NOT NULL,
+    token TEXT NOT NULL,
+    created_at DATETIME NOT NULL
+);
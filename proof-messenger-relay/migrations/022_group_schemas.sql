@@ -0,0 +1,12 @@
+-- Migration for message body schemas
+-- Holds the JSON Schemas registered for each group; every registration adds
+-- a new version and message bodies are validated against the latest one
+
+CREATE TABLE IF NOT EXISTS group_schemas (
+    group_id TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    schema TEXT NOT NULL,
+    created_by TEXT,
+    created_at DATETIME NOT NULL,
+    PRIMARY KEY (group_id, version)
+);
@@ -0,0 +1,11 @@
+-- Migration for stored message integrity verification
+-- Flags stored messages whose proof no longer verifies against their sender
+-- and context, which points to storage corruption or tampering
+
+CREATE TABLE IF NOT EXISTS message_integrity_failures (
+    message_id TEXT PRIMARY KEY,
+    group_id TEXT NOT NULL,
+    sender TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    detected_at DATETIME NOT NULL
+);
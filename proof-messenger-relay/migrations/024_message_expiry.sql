@@ -0,0 +1,8 @@
+-- Migration for ephemeral messages
+-- Messages may carry an expiry, after which they are hidden and then purged
+
+ALTER TABLE messages ADD COLUMN expires_at DATETIME;
+
+-- Index for purging expired messages
+CREATE INDEX IF NOT EXISTS idx_messages_expires_at
+ON messages(expires_at) WHERE expires_at IS NOT NULL;
@@ -0,0 +1,22 @@
+-- Migration for scheduled messages
+-- Holds signed messages waiting to be relayed at the time their context names,
+-- and what became of them once released or cancelled
+
+CREATE TABLE IF NOT EXISTS scheduled_messages (
+    id TEXT PRIMARY KEY NOT NULL,
+    tenant TEXT,
+    sender TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    release_at DATETIME NOT NULL,
+    status TEXT NOT NULL DEFAULT 'scheduled',
+    created_by TEXT,
+    created_at DATETIME NOT NULL,
+    released_at DATETIME,
+    cancelled_at DATETIME,
+    message_id TEXT,
+    error TEXT
+);
+
+-- Index for finding messages that are due
+CREATE INDEX IF NOT EXISTS idx_scheduled_messages_status_release_at
+ON scheduled_messages(status, release_at);
@@ -0,0 +1,13 @@
+-- Migration for per-recipient delivery status
+-- Tracks how far each recipient got with a message: delivered once their
+-- client acknowledges it, read once they mark it read
+
+CREATE TABLE IF NOT EXISTS message_deliveries (
+    message_id TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    state TEXT NOT NULL,
+    delivered_at DATETIME NOT NULL,
+    read_at DATETIME,
+    PRIMARY KEY (message_id, recipient),
+    FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
@@ -0,0 +1,16 @@
+-- Migration for direct messages
+-- A message may name a single recipient public key, whose inbox serves it
+-- instead of its group
+
+ALTER TABLE messages ADD COLUMN recipient TEXT;
+
+-- Index for reading a recipient's inbox, newest first
+CREATE INDEX IF NOT EXISTS idx_messages_recipient
+ON messages(recipient, created_at) WHERE recipient IS NOT NULL;
+
+-- Single-use challenges a recipient signs to open its inbox
+CREATE TABLE IF NOT EXISTS inbox_challenges (
+    challenge TEXT PRIMARY KEY,
+    public_key TEXT NOT NULL,
+    expires_at DATETIME NOT NULL
+);
@@ -0,0 +1,19 @@
+-- Migration for Ed25519 challenge-response authentication
+-- Clients sign a single-use challenge with a configured key and receive a
+-- short-lived session token, stored only as its SHA-256 hash
+
+CREATE TABLE IF NOT EXISTS key_auth_challenges (
+    challenge TEXT PRIMARY KEY,
+    public_key TEXT NOT NULL,
+    expires_at DATETIME NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS key_sessions (
+    token_hash TEXT PRIMARY KEY,
+    public_key TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    expires_at DATETIME NOT NULL
+);
+
+-- Index for dropping expired sessions
+CREATE INDEX IF NOT EXISTS idx_key_sessions_expires_at ON key_sessions(expires_at);
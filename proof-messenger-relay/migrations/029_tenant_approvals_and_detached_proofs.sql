@@ -0,0 +1,37 @@
+-- Migration for tenant-scoped approvals and detached proofs
+-- On relays with tenants, approvals and detached proof registrations belong
+-- to the tenant that created them and are invisible to every other tenant
+
+ALTER TABLE multisig_approvals ADD COLUMN tenant TEXT;
+
+-- Detached proofs are unique per tenant rather than relay-wide, so the table
+-- is rebuilt without the column constraint on signature
+CREATE TABLE detached_proofs_by_tenant (
+    id TEXT PRIMARY KEY NOT NULL,
+    tenant TEXT,
+    algorithm TEXT NOT NULL,
+    digest TEXT NOT NULL,
+    filename TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    content_type TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    registered_by TEXT,
+    registered_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO detached_proofs_by_tenant (id, algorithm, digest, filename, size, content_type, public_key, signature, registered_by, registered_at)
+SELECT id, algorithm, digest, filename, size, content_type, public_key, signature, registered_by, registered_at
+FROM detached_proofs;
+
+DROP TABLE detached_proofs;
+
+ALTER TABLE detached_proofs_by_tenant RENAME TO detached_proofs;
+
+-- Registration stays idempotent per signature within a tenant
+CREATE UNIQUE INDEX IF NOT EXISTS idx_detached_proofs_tenant_signature
+ON detached_proofs(COALESCE(tenant, ''), signature);
+
+-- Index for looking up every proof a tenant registered over a document
+CREATE INDEX IF NOT EXISTS idx_detached_proofs_digest
+ON detached_proofs(digest, registered_at);
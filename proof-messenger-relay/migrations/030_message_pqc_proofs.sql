@@ -0,0 +1,6 @@
+-- Migration for storing the post-quantum half of hybrid proofs
+-- Keeps the ML-DSA-65 key and signature a message was relayed with, so
+-- stored and federated copies still verify under the strict hybrid policy
+
+ALTER TABLE messages ADD COLUMN pqc_public_key TEXT;
+ALTER TABLE messages ADD COLUMN pqc_proof TEXT;
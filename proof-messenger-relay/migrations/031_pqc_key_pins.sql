@@ -0,0 +1,9 @@
+-- Migration for binding post-quantum keys to senders
+-- Pins the first ML-DSA-65 key each Ed25519 sender key presents under the
+-- strict hybrid policy, so a forged Ed25519 signature cannot bring its own
+
+CREATE TABLE IF NOT EXISTS pqc_key_pins (
+    sender TEXT PRIMARY KEY NOT NULL,
+    pqc_public_key TEXT NOT NULL,
+    pinned_at DATETIME NOT NULL
+);
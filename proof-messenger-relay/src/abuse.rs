@@ -0,0 +1,643 @@
+//! Abuse Detection Module
+//!
+//! Verification stops a forged message, but not a sender who keeps sending
+//! them. With abuse detection enabled the relay counts, per sender key and
+//! over fixed windows of `abuse_detection.window_secs`:
+//!
+//! - messages that fail verification (bad signatures, keys or contexts)
+//! - messages carrying a revoked proof
+//! - verified messages, to catch bursts
+//!
+//! A sender exceeding any of the thresholds is quarantined for
+//! `abuse_detection.quarantine_secs`: its messages are refused before
+//! verification with `SENDER_QUARANTINED`, as `429 Too Many Requests` after a
+//! burst and `403 Forbidden` otherwise. Each quarantine is reported through
+//! the [`SecureLogger`] as a critical security event and counted per reason
+//! in the metrics registry. Administrators review quarantined senders with
+//! `GET /admin/abuse/quarantines` and lift a quarantine early with
+//! `DELETE /admin/abuse/quarantines/{sender}`.
+//!
+//! The sender of a rejected message is only claimed, so failures can be
+//! attributed to a key by anyone who knows it; bursts count verified
+//! messages only. Counters and quarantines live in each replica's memory.
+//!
+//! Detection is enabled by the `abuse_detection.enabled` relay setting or
+//! `ABUSE_DETECTION_ENABLED=true` (see [`crate::config::RelayConfig`]) and
+//! layering the resulting [`AbuseDetector`] onto the router as an
+//! [`axum::Extension`].
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get},
+    Extension, Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    auth_middleware::AuthContext,
+    config::AbuseDetectionConfig,
+    database::Database,
+    metrics, quarantine,
+    request_id::RequestId,
+    secure_logger::SecureLogger,
+    AppError,
+};
+
+/// Abuse detection errors
+#[derive(Error, Debug)]
+pub enum AbuseError {
+    #[error("Abuse detection is not enabled on this relay")]
+    Disabled,
+
+    #[error("{0}")]
+    Quarantined(SenderQuarantine),
+
+    #[error("Sender {0} is not quarantined")]
+    NotQuarantined(String),
+}
+
+/// Behaviour that got a sender quarantined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbuseReason {
+    /// Too many messages failed verification
+    VerificationFailures,
+    /// Too many messages carried a revoked proof
+    RevokedProofReplays,
+    /// Too many verified messages
+    Burst,
+}
+
+impl AbuseReason {
+    /// Label used in logs and metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AbuseReason::VerificationFailures => "verification_failures",
+            AbuseReason::RevokedProofReplays => "revoked_proof_replays",
+            AbuseReason::Burst => "burst",
+        }
+    }
+}
+
+/// A sender whose messages are refused
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SenderQuarantine {
+    /// Quarantined sender key (lowercase hex)
+    pub sender: String,
+    /// Threshold the sender exceeded
+    pub reason: AbuseReason,
+    /// Events counted in the window that exceeded it
+    pub events: u32,
+    /// When the sender was quarantined
+    pub quarantined_at: DateTime<Utc>,
+    /// When the sender's messages are accepted again
+    pub expires_at: DateTime<Utc>,
+}
+
+impl std::fmt::Display for SenderQuarantine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "sender is quarantined until {} ({})",
+            self.expires_at.to_rfc3339(),
+            self.reason.as_str()
+        )
+    }
+}
+
+/// A sender's events in the current window
+struct SenderActivity {
+    started: DateTime<Utc>,
+    verification_failures: u32,
+    revoked_proof_replays: u32,
+    messages: u32,
+}
+
+impl SenderActivity {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            started: now,
+            verification_failures: 0,
+            revoked_proof_replays: 0,
+            messages: 0,
+        }
+    }
+}
+
+/// Tracked senders and active quarantines
+#[derive(Default)]
+struct Tracker {
+    activity: HashMap<String, SenderActivity>,
+    quarantined: HashMap<String, SenderQuarantine>,
+}
+
+/// Tracks sender behaviour and quarantines abusive senders
+pub struct AbuseDetector {
+    config: AbuseDetectionConfig,
+    logger: Arc<SecureLogger>,
+    tracker: Mutex<Tracker>,
+}
+
+impl AbuseDetector {
+    /// A detector with the given thresholds, reporting quarantines to `logger`
+    pub fn new(config: &AbuseDetectionConfig, logger: Arc<SecureLogger>) -> Self {
+        Self {
+            config: config.clone(),
+            logger,
+            tracker: Mutex::new(Tracker::default()),
+        }
+    }
+
+    /// Refuse a message from a quarantined sender
+    pub fn check(&self, sender: &str) -> Result<(), AbuseError> {
+        self.check_at(sender, Utc::now())
+    }
+
+    fn check_at(&self, sender: &str, now: DateTime<Utc>) -> Result<(), AbuseError> {
+        let mut tracker = self.tracker.lock().unwrap();
+        match active_quarantine(&mut tracker, &sender_key(sender), now) {
+            Some(quarantine) => Err(AbuseError::Quarantined(quarantine)),
+            None => Ok(()),
+        }
+    }
+
+    /// Count a message that failed verification against its claimed sender
+    ///
+    /// Returns the quarantine if this rejection exceeded a threshold.
+    /// Errors that are not the sender's fault are ignored.
+    pub fn record_rejection(&self, sender: &str, error: &AppError, user_id: Option<&str>) -> Option<SenderQuarantine> {
+        let reason = match error {
+            AppError::ProofRevoked => AbuseReason::RevokedProofReplays,
+            error if quarantine::rejection_reason(error).is_some() => AbuseReason::VerificationFailures,
+            _ => return None,
+        };
+        let quarantine = self.record_at(sender, reason, Utc::now())?;
+        self.report(&quarantine, user_id);
+        Some(quarantine)
+    }
+
+    /// Count a verified message towards the sender's burst threshold
+    ///
+    /// The message that exceeds the threshold is refused.
+    pub fn record_accepted(&self, sender: &str, user_id: Option<&str>) -> Result<(), AbuseError> {
+        match self.record_at(sender, AbuseReason::Burst, Utc::now()) {
+            Some(quarantine) => {
+                self.report(&quarantine, user_id);
+                Err(AbuseError::Quarantined(quarantine))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Count one event, quarantining the sender once its threshold is exceeded
+    fn record_at(&self, sender: &str, reason: AbuseReason, now: DateTime<Utc>) -> Option<SenderQuarantine> {
+        let sender = sender_key(sender);
+        let window = Duration::seconds(self.config.window_secs as i64);
+        let mut tracker = self.tracker.lock().unwrap();
+
+        let activity = tracker.activity.entry(sender.clone()).or_insert_with(|| SenderActivity::new(now));
+        if now - activity.started >= window {
+            *activity = SenderActivity::new(now);
+        }
+        let (events, limit) = match reason {
+            AbuseReason::VerificationFailures => (&mut activity.verification_failures, self.config.max_verification_failures),
+            AbuseReason::RevokedProofReplays => (&mut activity.revoked_proof_replays, self.config.max_revoked_replays),
+            AbuseReason::Burst => (&mut activity.messages, self.config.max_messages),
+        };
+        *events += 1;
+        if *events <= limit {
+            return None;
+        }
+
+        let quarantine = SenderQuarantine {
+            sender: sender.clone(),
+            reason,
+            events: *events,
+            quarantined_at: now,
+            expires_at: now + Duration::seconds(self.config.quarantine_secs as i64),
+        };
+        tracker.activity.remove(&sender);
+        tracker.quarantined.insert(sender, quarantine.clone());
+        Some(quarantine)
+    }
+
+    /// Log and count a new quarantine
+    fn report(&self, quarantine: &SenderQuarantine, user_id: Option<&str>) {
+        warn!("Quarantined sender {} for {}", quarantine.sender, quarantine.reason.as_str());
+        metrics::SENDER_QUARANTINES_TOTAL
+            .get_or_create(&vec![("reason".to_string(), quarantine.reason.as_str().to_string())])
+            .inc();
+
+        let mut metadata = HashMap::new();
+        metadata.insert("sender".to_string(), quarantine.sender.clone());
+        metadata.insert("reason".to_string(), quarantine.reason.as_str().to_string());
+        metadata.insert("events".to_string(), quarantine.events.to_string());
+        metadata.insert("window_secs".to_string(), self.config.window_secs.to_string());
+        metadata.insert("expires_at".to_string(), quarantine.expires_at.to_rfc3339());
+        if let Err(e) = self.logger.critical_security_event(
+            "Sender quarantined for abuse".to_string(),
+            user_id.map(str::to_string),
+            None,
+            metadata,
+        ) {
+            warn!("Failed to log sender quarantine: {}", e);
+        }
+    }
+
+    /// Senders currently quarantined, oldest quarantine first
+    pub fn quarantined(&self) -> Vec<SenderQuarantine> {
+        let now = Utc::now();
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.quarantined.retain(|_, quarantine| quarantine.expires_at > now);
+        let mut quarantined: Vec<SenderQuarantine> = tracker.quarantined.values().cloned().collect();
+        quarantined.sort_by(|a, b| a.quarantined_at.cmp(&b.quarantined_at).then_with(|| a.sender.cmp(&b.sender)));
+        quarantined
+    }
+
+    /// Accept a quarantined sender's messages again, with fresh counters
+    pub fn lift(&self, sender: &str) -> Result<SenderQuarantine, AbuseError> {
+        let sender = sender_key(sender);
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.activity.remove(&sender);
+        active_quarantine(&mut tracker, &sender, Utc::now())
+            .and_then(|_| tracker.quarantined.remove(&sender))
+            .ok_or(AbuseError::NotQuarantined(sender))
+    }
+
+    /// Forget idle senders and expired quarantines periodically in the background
+    pub fn spawn_cleanup(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let period = std::time::Duration::from_secs(self.config.window_secs);
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                crate::readiness::BACKGROUND_JOBS.heartbeat("abuse_detection_cleanup", period);
+                self.prune(Utc::now());
+            }
+        })
+    }
+
+    fn prune(&self, now: DateTime<Utc>) {
+        let window = Duration::seconds(self.config.window_secs as i64);
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.activity.retain(|_, activity| now - activity.started < window);
+        tracker.quarantined.retain(|_, quarantine| quarantine.expires_at > now);
+    }
+}
+
+/// The sender's quarantine if it has not expired, dropping it otherwise
+fn active_quarantine(tracker: &mut Tracker, sender: &str, now: DateTime<Utc>) -> Option<SenderQuarantine> {
+    match tracker.quarantined.get(sender) {
+        Some(quarantine) if quarantine.expires_at > now => Some(quarantine.clone()),
+        Some(_) => {
+            tracker.quarantined.remove(sender);
+            None
+        }
+        None => None,
+    }
+}
+
+/// Senders are hex keys, so case changes name the same sender
+fn sender_key(sender: &str) -> String {
+    sender.to_ascii_lowercase()
+}
+
+/// Refuse a quarantined sender's message if abuse detection is enabled
+pub fn check_if_enabled(abuse: Option<&Arc<AbuseDetector>>, sender: &str) -> Result<(), AppError> {
+    match abuse {
+        Some(abuse) => abuse.check(sender).map_err(AppError::from),
+        None => Ok(()),
+    }
+}
+
+/// Count a verification failure if abuse detection is enabled
+pub fn record_rejection_if_enabled(
+    abuse: Option<&Arc<AbuseDetector>>,
+    sender: &str,
+    error: &AppError,
+    user_id: Option<&str>,
+) {
+    if let Some(abuse) = abuse {
+        abuse.record_rejection(sender, error, user_id);
+    }
+}
+
+/// Count a verified message if abuse detection is enabled
+pub fn record_accepted_if_enabled(
+    abuse: Option<&Arc<AbuseDetector>>,
+    sender: &str,
+    user_id: Option<&str>,
+) -> Result<(), AppError> {
+    match abuse {
+        Some(abuse) => abuse.record_accepted(sender, user_id).map_err(AppError::from),
+        None => Ok(()),
+    }
+}
+
+/// Create router for abuse detection endpoints
+pub fn abuse_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/quarantines", get(list_quarantines_handler))
+        .route("/quarantines/:sender", delete(lift_quarantine_handler))
+}
+
+/// Create router for authenticated abuse detection endpoints
+pub fn authenticated_abuse_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)> {
+    Router::new()
+        .route("/quarantines", get(authenticated_list_quarantines_handler))
+        .route("/quarantines/:sender", delete(authenticated_lift_quarantine_handler))
+}
+
+/// Handler to list quarantined senders
+#[utoipa::path(
+    get,
+    path = "/admin/abuse/quarantines",
+    operation_id = "listSenderQuarantines",
+    tag = "abuse",
+    responses(
+        (status = 200, description = "Quarantined senders", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn list_quarantines_handler(abuse: Option<Extension<Arc<AbuseDetector>>>) -> Result<impl IntoResponse, AppError> {
+    info!("Listing quarantined senders");
+
+    let Extension(abuse) = abuse.ok_or(AbuseError::Disabled)?;
+    let quarantined = abuse.quarantined();
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "count": quarantined.len(),
+        "quarantined_senders": quarantined
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler to lift a sender's quarantine
+#[utoipa::path(
+    delete,
+    path = "/admin/abuse/quarantines/{sender}",
+    operation_id = "liftSenderQuarantine",
+    tag = "abuse",
+    params(("sender" = String, Path, description = "Sender public key (hex encoded)")),
+    responses(
+        (status = 200, description = "The lifted quarantine", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn lift_quarantine_handler(
+    State(_db): State<Arc<Database>>,
+    abuse: Option<Extension<Arc<AbuseDetector>>>,
+    Path(sender): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Lifting quarantine of sender {}", sender);
+
+    let Extension(abuse) = abuse.ok_or(AbuseError::Disabled)?;
+    let lifted = abuse.lift(&sender)?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": "Quarantine lifted",
+        "quarantine": lifted
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to list quarantined senders
+#[instrument(skip_all)]
+async fn authenticated_list_quarantines_handler(
+    auth: AuthContext,
+    abuse: Option<Extension<Arc<AbuseDetector>>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} listing quarantined senders", auth.user_id);
+
+    let Extension(abuse) = abuse.ok_or(AbuseError::Disabled)?;
+    let quarantined = abuse.quarantined();
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "count": quarantined.len(),
+        "quarantined_senders": quarantined,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to lift a sender's quarantine
+#[instrument(skip_all)]
+async fn authenticated_lift_quarantine_handler(
+    State((_, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    abuse: Option<Extension<Arc<AbuseDetector>>>,
+    Path(sender): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} lifting quarantine of sender {}", auth.user_id, sender);
+
+    let Extension(abuse) = abuse.ok_or(AbuseError::Disabled)?;
+    let lifted = abuse.lift(&sender)?;
+
+    // Log who let the sender back in
+    let mut metadata = HashMap::new();
+    metadata.insert("sender".to_string(), lifted.sender.clone());
+    metadata.insert("reason".to_string(), lifted.reason.as_str().to_string());
+    if let Err(e) = secure_logger.audit_log(
+        "Sender quarantine lifted".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log quarantine lift: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": "Quarantine lifted",
+        "quarantine": lifted,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_app;
+    use axum::body::Body;
+    use axum::http::Request;
+    use ed25519_dalek::{Keypair, Signer};
+    use proof_messenger_protocol::key::generate_keypair_with_seed;
+    use tower::ServiceExt;
+
+    fn config() -> AbuseDetectionConfig {
+        AbuseDetectionConfig {
+            enabled: true,
+            window_secs: 60,
+            max_verification_failures: 2,
+            max_revoked_replays: 1,
+            max_messages: 3,
+            quarantine_secs: 600,
+        }
+    }
+
+    fn detector() -> Arc<AbuseDetector> {
+        Arc::new(AbuseDetector::new(&config(), Arc::new(SecureLogger::new(&SecureLogger::generate_key()))))
+    }
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    fn signed_message(keypair: &Keypair, context: &[u8]) -> serde_json::Value {
+        serde_json::json!({
+            "sender": hex::encode(keypair.public.to_bytes()),
+            "context": hex::encode(context),
+            "body": "hello",
+            "proof": hex::encode(keypair.sign(context).to_bytes()),
+        })
+    }
+
+    async fn call(app: &Router, method: &str, uri: &str, body: Option<&serde_json::Value>) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_repeated_verification_failures_quarantine_the_sender() {
+        // ARRANGE: A relay with abuse detection and a sender whose proofs never verify
+        let abuse = detector();
+        let app = create_app(setup_db().await).layer(Extension(abuse.clone()));
+        let keypair = generate_keypair_with_seed(7);
+        let mut forged = signed_message(&keypair, b"transfer");
+        forged["context"] = serde_json::json!(hex::encode(b"tampered"));
+
+        // ACT: Send forged messages until the threshold is exceeded, then a valid one
+        let mut statuses = Vec::new();
+        for _ in 0..3 {
+            statuses.push(call(&app, "POST", "/relay", Some(&forged)).await.0);
+        }
+        let (status, body) = call(&app, "POST", "/relay", Some(&signed_message(&keypair, b"hello"))).await;
+
+        // ASSERT: The failures are reported as such, then the sender is refused outright
+        assert_eq!(statuses, vec![StatusCode::UNAUTHORIZED; 3]);
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["code"], "SENDER_QUARANTINED");
+        assert_eq!(body["details"]["reason"], "verification_failures");
+        assert_eq!(body["details"]["events"], 3);
+        assert_eq!(abuse.quarantined().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bursts_are_refused_with_too_many_requests() {
+        let app = create_app(setup_db().await).layer(Extension(detector()));
+        let keypair = generate_keypair_with_seed(8);
+
+        let mut statuses = Vec::new();
+        for i in 0..5u8 {
+            statuses.push(call(&app, "POST", "/relay", Some(&signed_message(&keypair, &[i]))).await.0);
+        }
+
+        assert_eq!(
+            statuses,
+            vec![
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::TOO_MANY_REQUESTS
+            ]
+        );
+    }
+
+    #[test]
+    fn test_revoked_proof_replays_have_their_own_threshold() {
+        let abuse = detector();
+
+        let first = abuse.record_rejection("AB", &AppError::ProofRevoked, None);
+        let second = abuse.record_rejection("ab", &AppError::ProofRevoked, Some("alice"));
+        let ignored = abuse.record_rejection("cd", &AppError::ProcessingError("db down".to_string()), None);
+
+        assert!(first.is_none());
+        let quarantine = second.expect("second replay exceeds the threshold");
+        assert_eq!(quarantine.sender, "ab");
+        assert_eq!(quarantine.reason, AbuseReason::RevokedProofReplays);
+        assert!(ignored.is_none());
+        assert!(matches!(abuse.check("AB"), Err(AbuseError::Quarantined(_))));
+        assert!(abuse.check("cd").is_ok());
+    }
+
+    #[test]
+    fn test_counters_reset_each_window_and_quarantines_expire() {
+        let abuse = detector();
+        let start = Utc::now();
+
+        abuse.record_at("ab", AbuseReason::VerificationFailures, start);
+        abuse.record_at("ab", AbuseReason::VerificationFailures, start);
+        let next_window = abuse.record_at("ab", AbuseReason::VerificationFailures, start + Duration::seconds(61));
+        for _ in 0..3 {
+            abuse.record_at("cd", AbuseReason::VerificationFailures, start);
+        }
+        let during = abuse.check_at("cd", start + Duration::seconds(599));
+        let after = abuse.check_at("cd", start + Duration::seconds(600));
+
+        assert!(next_window.is_none());
+        assert!(during.is_err());
+        assert!(after.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoint_lists_and_lifts_quarantines() {
+        // ARRANGE: A quarantined sender
+        let abuse = detector();
+        let app = create_app(setup_db().await).layer(Extension(abuse.clone()));
+        for _ in 0..2 {
+            abuse.record_rejection("ab", &AppError::ProofRevoked, None);
+        }
+
+        // ACT: Review the quarantines, lift the sender's, and try to lift it again
+        let (listed, list) = call(&app, "GET", "/admin/abuse/quarantines", None).await;
+        let (lifted, _) = call(&app, "DELETE", "/admin/abuse/quarantines/AB", None).await;
+        let (again, body) = call(&app, "DELETE", "/admin/abuse/quarantines/ab", None).await;
+
+        // ASSERT: The sender is listed, then accepted again
+        assert_eq!(listed, StatusCode::OK);
+        assert_eq!(list["count"], 1);
+        assert_eq!(list["quarantined_senders"][0]["sender"], "ab");
+        assert_eq!(list["quarantined_senders"][0]["reason"], "revoked_proof_replays");
+        assert_eq!(lifted, StatusCode::OK);
+        assert!(abuse.check("ab").is_ok());
+        assert_eq!(again, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "SENDER_NOT_QUARANTINED");
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoint_without_detection_is_not_found() {
+        let app = create_app(setup_db().await);
+
+        let (status, body) = call(&app, "GET", "/admin/abuse/quarantines", None).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "ABUSE_DETECTION_DISABLED");
+    }
+}
@@ -0,0 +1,512 @@
+//! Message Amendment Module
+//!
+//! This module lets senders correct a relayed message with a signed
+//! amendment. Each amendment is a new version of the message body, signed by
+//! the original sender key over the message id, the new version number and
+//! the hash of the version it replaces, so the stored versions form a chain
+//! anyone can verify. The full chain is exposed as the message's history.
+
+use axum::{
+    extract::{Extension, Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{PublicKey, Signature};
+use proof_messenger_protocol::amendment::{verify_amendment, Amendment};
+use proof_messenger_protocol::receipt::{message_hash_from_slice, MESSAGE_HASH_LENGTH};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, StoredAmendment, StoredMessage},
+    get_tenant_message,
+    limits::{self, RequestLimits},
+    receipts::stored_message_hash,
+    request_id::RequestId,
+    tenancy::TenantScope,
+    AppError,
+};
+
+/// Errors raised when amending a message
+#[derive(Error, Debug)]
+pub enum AmendmentError {
+    #[error("Amendments must be signed by the original sender")]
+    NotOriginalSender,
+
+    #[error("Message has been deleted: {0}")]
+    MessageDeleted(String),
+
+    #[error("Expected version {expected}, got {actual}")]
+    VersionConflict { expected: i64, actual: u64 },
+}
+
+/// Request body for amending a message
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AmendMessageRequest {
+    /// Public key of the sender (hex encoded), which must match the original
+    pub sender: String,
+    /// Version the amendment creates: one more than the current version
+    pub version: u64,
+    /// The corrected message body
+    pub body: String,
+    /// Signature over the amendment hash (hex encoded)
+    pub proof: String,
+}
+
+/// One version of a message in its history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageVersion {
+    /// Version number (the original message is version 0)
+    pub version: i64,
+    /// Message body at this version (empty once the message is deleted)
+    pub body: String,
+    /// Hash of this version (hex encoded), unknown for a deleted original
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// Hash of the version this one replaced (hex encoded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_hash: Option<String>,
+    /// Sender's signature for this version (hex encoded)
+    pub proof: String,
+    /// When this version was stored
+    pub created_at: DateTime<Utc>,
+}
+
+/// Create router for amendment endpoints
+pub fn amendment_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/message/:message_id/amendments", post(amend_message_handler))
+        .route("/message/:message_id/history", get(get_history_handler))
+}
+
+/// Create router for authenticated amendment endpoints
+pub fn authenticated_amendment_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/message/:message_id/amendments", post(authenticated_amend_message_handler))
+        .route("/message/:message_id/history", get(authenticated_get_history_handler))
+}
+
+/// Hash of the latest version of a message, which the next amendment must extend
+fn current_hash(
+    message: &StoredMessage,
+    amendments: &[StoredAmendment],
+) -> Result<[u8; MESSAGE_HASH_LENGTH], AppError> {
+    match amendments.last() {
+        Some(latest) => decode_hash(&latest.amendment_hash),
+        None => stored_message_hash(message),
+    }
+}
+
+/// Decode a stored hex hash
+fn decode_hash(hash: &str) -> Result<[u8; MESSAGE_HASH_LENGTH], AppError> {
+    let bytes = hex::decode(hash)
+        .map_err(|e| AppError::ProcessingError(format!("Invalid stored hash: {}", e)))?;
+    message_hash_from_slice(&bytes).map_err(|e| AppError::ProcessingError(e.to_string()))
+}
+
+/// Verify an amendment against the message's current version and persist it
+pub async fn amend_message(
+    db: &Database,
+    limits: Option<&Arc<RequestLimits>>,
+    tenant: &TenantScope,
+    message_id: &str,
+    request: &AmendMessageRequest,
+) -> Result<StoredAmendment, AppError> {
+    limits::validate_body(limits, &request.body)?;
+
+    let message = get_tenant_message(db, tenant, message_id).await?;
+    if message.is_tombstone() {
+        return Err(AmendmentError::MessageDeleted(message_id.to_string()).into());
+    }
+
+    let sender_bytes = hex::decode(&message.sender)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid hex encoding: {}", e)))?;
+    let sender = PublicKey::from_bytes(&sender_bytes)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid public key: {}", e)))?;
+    let claimed_bytes = hex::decode(&request.sender)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid hex encoding: {}", e)))?;
+    if claimed_bytes != sender_bytes {
+        return Err(AmendmentError::NotOriginalSender.into());
+    }
+
+    let amendments = db.get_amendments_for_message(message_id).await?;
+    let expected = amendments.last().map_or(1, |latest| latest.version + 1);
+    if request.version != expected as u64 {
+        return Err(AmendmentError::VersionConflict { expected, actual: request.version }.into());
+    }
+    let previous_hash = current_hash(&message, &amendments)?;
+
+    let signature_bytes = hex::decode(&request.proof)
+        .map_err(|e| AppError::InvalidSignature(format!("Invalid hex encoding: {}", e)))?;
+    let signature = Signature::from_bytes(&signature_bytes)
+        .map_err(|e| AppError::InvalidSignature(format!("Invalid signature: {}", e)))?;
+
+    let amendment = Amendment {
+        message_id: message_id.to_string(),
+        version: request.version,
+        previous_hash,
+        body: request.body.clone(),
+        sender,
+        signature,
+    };
+    verify_amendment(&amendment, &sender, message_id, request.version, &previous_hash)
+        .map_err(|_| AppError::VerificationFailed)?;
+
+    let stored = db
+        .store_amendment(
+            message_id,
+            expected,
+            &request.body,
+            &hex::encode(previous_hash),
+            &hex::encode(amendment.hash()),
+            &request.proof,
+        )
+        .await?;
+
+    // Nothing is stored if another amendment won the race or the message was deleted meanwhile
+    match stored {
+        Some(stored) => Ok(stored),
+        None if db.get_message_by_id(message_id).await?.is_tombstone() => {
+            Err(AmendmentError::MessageDeleted(message_id.to_string()).into())
+        }
+        None => Err(AmendmentError::VersionConflict { expected: expected + 1, actual: request.version }.into()),
+    }
+}
+
+/// Every version of a message, original first
+pub async fn message_history(db: &Database, tenant: &TenantScope, message_id: &str) -> Result<Vec<MessageVersion>, AppError> {
+    let message = get_tenant_message(db, tenant, message_id).await?;
+    let amendments = db.get_amendments_for_message(message_id).await?;
+
+    // A deleted original's hash can only be recovered from the amendment extending it
+    let original_hash = if message.is_tombstone() {
+        amendments.first().map(|first| first.previous_hash.clone())
+    } else {
+        Some(hex::encode(stored_message_hash(&message)?))
+    };
+
+    let mut versions = Vec::with_capacity(amendments.len() + 1);
+    versions.push(MessageVersion {
+        version: 0,
+        body: message.body,
+        hash: original_hash,
+        previous_hash: None,
+        proof: message.proof,
+        created_at: message.created_at,
+    });
+    versions.extend(amendments.into_iter().map(|amendment| MessageVersion {
+        version: amendment.version,
+        body: amendment.body,
+        hash: Some(amendment.amendment_hash),
+        previous_hash: Some(amendment.previous_hash),
+        proof: amendment.proof,
+        created_at: amendment.created_at,
+    }));
+    Ok(versions)
+}
+
+/// Handler to amend a message
+#[utoipa::path(
+    post,
+    path = "/message/{message_id}/amendments",
+    operation_id = "amendMessage",
+    tag = "amendments",
+    params(("message_id" = String, Path, description = "Message ID")),
+    request_body = AmendMessageRequest,
+    responses(
+        (status = 201, description = "Amendment verified and stored", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn amend_message_handler(
+    State(db): State<Arc<Database>>,
+    limits: Option<Extension<Arc<RequestLimits>>>,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+    Json(payload): Json<AmendMessageRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Amending message: {}", message_id);
+
+    let amendment = amend_message(&db, limits.as_deref(), &tenant, &message_id, &payload).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "amendment": amendment
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Handler to retrieve every version of a message
+#[utoipa::path(
+    get,
+    path = "/message/{message_id}/history",
+    operation_id = "getMessageHistory",
+    tag = "amendments",
+    params(("message_id" = String, Path, description = "Message ID")),
+    responses(
+        (status = 200, description = "The message's versions, oldest first", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_history_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Retrieving history for message: {}", message_id);
+
+    let versions = message_history(&db, &tenant, &message_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message_id": message_id,
+        "current_version": versions.len() - 1,
+        "versions": versions
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to amend a message
+#[instrument(skip_all)]
+async fn authenticated_amend_message_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    limits: Option<Extension<Arc<RequestLimits>>>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    request_id: RequestId,
+    Path(message_id): Path<String>,
+    Json(payload): Json<AmendMessageRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} amending message: {}", auth.user_id, message_id);
+
+    let amendment = amend_message(&db, limits.as_deref(), &tenant, &message_id, &payload).await?;
+
+    // Log the amendment
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("message_id".to_string(), message_id.clone());
+    metadata.insert("version".to_string(), amendment.version.to_string());
+    metadata.insert("amendment_hash".to_string(), amendment.amendment_hash.clone());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Message amended".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log message amendment: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "amendment": amendment,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Authenticated handler to retrieve every version of a message
+#[instrument(skip_all)]
+async fn authenticated_get_history_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} retrieving history for message: {}", auth.user_id, message_id);
+
+    let versions = message_history(&db, &tenant, &message_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message_id": message_id,
+        "current_version": versions.len() - 1,
+        "versions": versions,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use ed25519_dalek::Signer;
+    use hyper::Method;
+    use proof_messenger_protocol::amendment::make_amendment;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use tower::ServiceExt;
+
+    async fn setup_test_app() -> (Router, Arc<Database>, StoredMessage) {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+
+        let sender = generate_secure_keypair_with_seed(1);
+        let context = b"amendment test context";
+        let message = crate::Message {
+            sender: hex::encode(sender.public_key_bytes()),
+            context: hex::encode(context),
+            body: "helo".to_string(),
+            proof: hex::encode(sender.as_keypair().sign(context).to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        };
+        let message_id = db.store_message(StoredMessage::from(message)).await.unwrap();
+        let stored = db.get_message_by_id(&message_id).await.unwrap();
+
+        let app = Router::new().merge(amendment_routes()).with_state(db.clone());
+        (app, db, stored)
+    }
+
+    fn amendment_request(
+        seed: u64,
+        message_id: &str,
+        version: u64,
+        previous_hash: &[u8; MESSAGE_HASH_LENGTH],
+        body: &str,
+    ) -> (AmendMessageRequest, Amendment) {
+        let amendment = make_amendment(&generate_secure_keypair_with_seed(seed), message_id, version, previous_hash, body).unwrap();
+        let request = AmendMessageRequest {
+            sender: hex::encode(amendment.sender.to_bytes()),
+            version,
+            body: body.to_string(),
+            proof: hex::encode(amendment.signature.to_bytes()),
+        };
+        (request, amendment)
+    }
+
+    async fn post_amendment(app: &Router, message_id: &str, request: &AmendMessageRequest) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(format!("/message/{}/amendments", message_id))
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(serde_json::to_string(request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn get_history(app: &Router, message_id: &str) -> serde_json::Value {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/message/{}/history", message_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_amendments_form_a_versioned_history() {
+        // ARRANGE: A stored message and two chained amendments from its sender
+        let (app, _, message) = setup_test_app().await;
+        let original = stored_message_hash(&message).unwrap();
+        let (first, first_amendment) = amendment_request(1, &message.id, 1, &original, "hello");
+        let (second, _) = amendment_request(1, &message.id, 2, &first_amendment.hash(), "hello!");
+
+        // ACT: Submit both amendments and read the history
+        let (first_status, _) = post_amendment(&app, &message.id, &first).await;
+        let (second_status, _) = post_amendment(&app, &message.id, &second).await;
+        let history = get_history(&app, &message.id).await;
+
+        // ASSERT: Every version is listed, each chained to the one before
+        assert_eq!(first_status, StatusCode::CREATED);
+        assert_eq!(second_status, StatusCode::CREATED);
+        assert_eq!(history["current_version"], 2);
+        let versions = history["versions"].as_array().unwrap();
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions[0]["body"], "helo");
+        assert_eq!(versions[2]["body"], "hello!");
+        assert_eq!(versions[1]["previous_hash"], versions[0]["hash"]);
+        assert_eq!(versions[2]["previous_hash"], versions[1]["hash"]);
+    }
+
+    #[tokio::test]
+    async fn test_amendment_from_another_key_is_rejected() {
+        let (app, db, message) = setup_test_app().await;
+        let original = stored_message_hash(&message).unwrap();
+        let (request, _) = amendment_request(2, &message.id, 1, &original, "hello");
+
+        let (status, json) = post_amendment(&app, &message.id, &request).await;
+
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(json["code"], "NOT_ORIGINAL_SENDER");
+        assert!(db.get_amendments_for_message(&message.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_forged_amendment_is_rejected() {
+        let (app, _, message) = setup_test_app().await;
+        let original = stored_message_hash(&message).unwrap();
+        let (mut request, _) = amendment_request(1, &message.id, 1, &original, "hello");
+        request.body = "goodbye".to_string();
+
+        let (status, _) = post_amendment(&app, &message.id, &request).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_stale_version_conflicts() {
+        let (app, _, message) = setup_test_app().await;
+        let original = stored_message_hash(&message).unwrap();
+        let (first, _) = amendment_request(1, &message.id, 1, &original, "hello");
+        let (stale, _) = amendment_request(1, &message.id, 1, &original, "hi");
+
+        post_amendment(&app, &message.id, &first).await;
+        let (status, json) = post_amendment(&app, &message.id, &stale).await;
+
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(json["code"], "AMENDMENT_CONFLICT");
+    }
+
+    #[tokio::test]
+    async fn test_deleting_a_message_clears_its_amendments() {
+        // ARRANGE: An amended message
+        let (app, db, message) = setup_test_app().await;
+        let original = stored_message_hash(&message).unwrap();
+        let (first, _) = amendment_request(1, &message.id, 1, &original, "hello");
+        post_amendment(&app, &message.id, &first).await;
+
+        // ACT: Delete the message and try to amend it again
+        db.tombstone_message(&message.id).await.unwrap();
+        let (second, _) = amendment_request(1, &message.id, 2, &original, "hello!");
+        let (status, json) = post_amendment(&app, &message.id, &second).await;
+        let history = get_history(&app, &message.id).await;
+
+        // ASSERT: No version keeps its body, but the hash chain remains
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(json["code"], "MESSAGE_DELETED");
+        let versions = history["versions"].as_array().unwrap();
+        assert!(versions.iter().all(|version| version["body"] == ""));
+        assert_eq!(versions[0]["hash"], hex::encode(original));
+        assert_eq!(versions[1]["previous_hash"], hex::encode(original));
+    }
+}
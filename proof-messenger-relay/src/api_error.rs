@@ -0,0 +1,367 @@
+//! Structured Error Response Module
+//!
+//! Every error the relay returns has the same JSON shape:
+//!
+//! ```json
+//! {
+//!   "error": "Proof has been revoked",
+//!   "code": "PROOF_REVOKED",
+//!   "request_id": "3f6c1c1e-...",
+//!   "details": { ... }
+//! }
+//! ```
+//!
+//! `code` is a stable, machine-readable [`ErrorCode`] that clients can
+//! branch on; `error` is a human-readable message that may change between
+//! releases. `request_id` is the request's ID (see [`crate::request_id`]),
+//! and `details` is present only for errors with structured context such as
+//! exceeded size limits.
+//!
+//! [`crate::AppError`] produces this shape directly. The
+//! [`structured_errors`] middleware gives the same shape to errors produced
+//! outside the relay's handlers, such as malformed JSON bodies or rate
+//! limiting.
+
+use axum::{
+    extract::Request,
+    http::{header::CONTENT_TYPE, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::request_id::{self, RequestId};
+
+/// Largest plain-text error body converted into a structured one
+const MAX_PLAIN_ERROR_BYTES: usize = 16 * 1024;
+
+/// Stable machine-readable error codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    // Message verification
+    InvalidSignature,
+    InvalidPublicKey,
+    InvalidContext,
+    VerificationFailed,
+    ProofRevoked,
+    ProofAlreadyRevoked,
+    ReplayDetected,
+    PayloadTooLarge,
+    PolicyViolation,
+
+    // Resources and queries
+    MessageNotFound,
+    MessageExpired,
+    InvalidGroupId,
+    InviteNotFound,
+    InviteUnavailable,
+    InvalidThread,
+    InvalidExpiry,
+    InvalidQuery,
+
+    // Message amendments
+    NotOriginalSender,
+    MessageDeleted,
+    AmendmentConflict,
+
+    // Authentication and authorization
+    MissingCredentials,
+    InvalidToken,
+    TokenExpired,
+    IntrospectionUnavailable,
+    InsufficientScope,
+    RouteNotAuthorized,
+    InvalidApiKey,
+
+    // API keys
+    ApiKeysDisabled,
+    ApiKeyNotFound,
+
+    // Key authentication
+    KeyAuthDisabled,
+    UnknownKey,
+    KeyChallengeFailed,
+    InvalidSession,
+
+    // Tenancy
+    MissingTenant,
+    UnknownTenant,
+    TenantDenied,
+
+    // Federation
+    FederationDisabled,
+    UnknownPeer,
+    ClientCertificateRequired,
+    InvalidPeerSignature,
+    HopLimitExceeded,
+    LoopDetected,
+    PeerUnavailable,
+
+    // Transparency log
+    TransparencyDisabled,
+    LogEntryNotFound,
+    InvalidTreeSize,
+
+    // Data subject requests
+    DataSubjectsDisabled,
+    MissingDataSubject,
+    ErasureNotFound,
+    ErasureNotScheduled,
+
+    // Live subscriptions
+    SubscriptionsDisabled,
+    InvalidResumeToken,
+
+    // Webhooks
+    WebhooksDisabled,
+    WebhookNotFound,
+    InvalidWebhookUrl,
+
+    // Key pinning
+    KeyPinningDisabled,
+    KeyChanged,
+    KeyNotPinned,
+    InvalidKeyRotation,
+
+    // Multi-signature approvals
+    ApprovalNotFound,
+    InvalidMultisigPolicy,
+    NotASigner,
+    AlreadySigned,
+    ApprovalNotPending,
+
+    // Timestamping
+    TimestampNotFound,
+    TimestampFailed,
+
+    // Body schemas
+    SchemaNotFound,
+    InvalidSchema,
+    SchemaValidationFailed,
+
+    // Maintenance
+    ReadOnlyMode,
+
+    // Abuse detection
+    AbuseDetectionDisabled,
+    SenderQuarantined,
+    SenderNotQuarantined,
+
+    // Audit log integrity
+    AuditChainDisabled,
+    AuditChainBroken,
+
+    // Stored message integrity
+    IntegrityCheckDisabled,
+
+    // Scheduled messages
+    SchedulingDisabled,
+    ScheduledMessageNotFound,
+    InvalidSchedule,
+    ScheduledMessageNotPending,
+
+    // Delivery status
+    InvalidRecipient,
+    InvalidAck,
+
+    // Direct messages
+    InboxChallengeFailed,
+
+    // Verification pipeline
+    MessageRejected,
+
+    // Errors outside the relay's handlers
+    BadRequest,
+    NotFound,
+    MethodNotAllowed,
+    UnsupportedMediaType,
+    UnprocessableEntity,
+    RateLimited,
+
+    // Server-side failures
+    ConfigurationError,
+    DatabaseError,
+    SharedStateUnavailable,
+    ProcessingError,
+    InternalError,
+}
+
+impl ErrorCode {
+    /// Code for an error response that carries no code of its own
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => ErrorCode::MissingCredentials,
+            StatusCode::FORBIDDEN => ErrorCode::InsufficientScope,
+            StatusCode::NOT_FOUND => ErrorCode::NotFound,
+            StatusCode::METHOD_NOT_ALLOWED => ErrorCode::MethodNotAllowed,
+            StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::PayloadTooLarge,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => ErrorCode::UnsupportedMediaType,
+            StatusCode::UNPROCESSABLE_ENTITY => ErrorCode::UnprocessableEntity,
+            StatusCode::TOO_MANY_REQUESTS => ErrorCode::RateLimited,
+            status if status.is_client_error() => ErrorCode::BadRequest,
+            _ => ErrorCode::InternalError,
+        }
+    }
+}
+
+/// JSON body of every error response
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorBody {
+    /// Human-readable description of the error
+    pub error: String,
+    /// Machine-readable error code
+    pub code: ErrorCode,
+    /// ID of the failed request
+    pub request_id: Option<String>,
+    /// Structured context for the error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl ErrorBody {
+    /// Build an error body for the current request
+    pub fn new(code: ErrorCode, error: impl Into<String>, details: Option<serde_json::Value>) -> Self {
+        Self {
+            error: error.into(),
+            code,
+            request_id: request_id::current(),
+            details,
+        }
+    }
+
+    /// Turn the body into a response with the given status
+    pub fn into_response_with(self, status: StatusCode) -> Response {
+        (status, Json(self)).into_response()
+    }
+}
+
+/// Middleware giving every error response the structured shape
+///
+/// Converts error responses without a JSON body into an [`ErrorBody`]
+/// whose code is derived from the status.
+pub async fn structured_errors(request: Request, next: Next) -> Response {
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(|id| id.0.clone())
+        .or_else(|| request_id::from_headers(request.headers()));
+    let response = next.run(request).await;
+
+    let status = response.status();
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !(status.is_client_error() || status.is_server_error()) || is_json {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let text = axum::body::to_bytes(body, MAX_PLAIN_ERROR_BYTES)
+        .await
+        .ok()
+        .map(|bytes| String::from_utf8_lossy(&bytes).trim().to_string())
+        .filter(|text| !text.is_empty())
+        .unwrap_or_else(|| status.canonical_reason().unwrap_or("Error").to_string());
+
+    let body = ErrorBody {
+        error: text,
+        code: ErrorCode::from_status(status),
+        request_id,
+        details: None,
+    };
+    let mut structured = body.into_response_with(status);
+    for (name, value) in parts.headers.iter() {
+        if name != CONTENT_TYPE && name != axum::http::header::CONTENT_LENGTH {
+            structured.headers_mut().insert(name.clone(), value.clone());
+        }
+    }
+    structured
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_app, database::Database, request_id::REQUEST_ID_HEADER};
+    use axum::body::Body;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    async fn error_body(response: Response) -> ErrorBody {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_app_errors_carry_code_and_request_id() {
+        // ARRANGE: A relay and a message with a malformed public key
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let app = create_app(db);
+        let message = serde_json::json!({
+            "sender": "zz",
+            "context": "00",
+            "body": "hello",
+            "proof": "00"
+        });
+
+        // ACT: Relay it with a request ID
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .method("POST")
+                    .uri("/relay")
+                    .header("content-type", "application/json")
+                    .header(REQUEST_ID_HEADER, "req-123")
+                    .body(Body::from(message.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // ASSERT: The error is identified by code and echoes the request ID
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = error_body(response).await;
+        assert_eq!(body.code, ErrorCode::InvalidPublicKey);
+        assert_eq!(body.request_id.as_deref(), Some("req-123"));
+        assert!(body.error.starts_with("Invalid public key format"));
+        assert!(body.details.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_framework_errors_are_structured() {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let app = create_app(db);
+
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .method("POST")
+                    .uri("/relay")
+                    .header("content-type", "application/json")
+                    .body(Body::from("{not json"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(response.headers()[CONTENT_TYPE].to_str().unwrap().starts_with("application/json"));
+        let generated_id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+        let body = error_body(response).await;
+        assert_eq!(body.code, ErrorCode::BadRequest);
+        assert_eq!(body.request_id, Some(generated_id));
+        assert!(!body.error.is_empty());
+    }
+
+    #[test]
+    fn test_codes_serialize_in_screaming_snake_case() {
+        assert_eq!(serde_json::to_value(ErrorCode::ProofRevoked).unwrap(), "PROOF_REVOKED");
+        assert_eq!(serde_json::to_value(ErrorCode::InvalidSignature).unwrap(), "INVALID_SIGNATURE");
+        assert_eq!(ErrorCode::from_status(StatusCode::TOO_MANY_REQUESTS), ErrorCode::RateLimited);
+    }
+}
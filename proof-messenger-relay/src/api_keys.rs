@@ -0,0 +1,516 @@
+//! API Key Authentication Module
+//!
+//! Machine-to-machine clients that cannot run an OAuth2.0 flow authenticate
+//! with an API key sent in the [`API_KEY_HEADER`] instead of a bearer token.
+//! Administrators create, rotate and revoke keys under `/admin/api-keys`
+//! (scope `apikey:manage`); each key carries its own scopes, which the
+//! authenticated request's [`AuthContext`] receives, and its own rate limit.
+//!
+//! Keys are shown once, when created or rotated. Only their SHA-256 hash is
+//! stored, so a leaked database does not leak usable keys. Rotation replaces
+//! the secret of an existing key, keeping its ID, scopes and limit; the old
+//! secret stops working immediately.
+//!
+//! API keys are enabled by the `api_keys.enabled` relay setting (see
+//! [`crate::config::ApiKeyConfig`]) and layering the resulting [`ApiKeys`]
+//! onto the router as an [`axum::Extension`].
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, post},
+    Extension, Router,
+};
+use rand::RngCore;
+use serde::Deserialize;
+use utoipa::ToSchema;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    auth_middleware::AuthContext,
+    config::ApiKeyConfig,
+    database::{Database, DatabaseError, StoredApiKey},
+    request_id::RequestId,
+    AppError,
+};
+
+/// Header carrying an API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Prefix of every generated key, to recognize leaked keys in scans
+const KEY_PREFIX: &str = "pmk_";
+
+/// Random bytes in a generated key
+const KEY_LENGTH: usize = 32;
+
+/// Characters of a key kept to recognize it
+const DISPLAY_PREFIX_LENGTH: usize = 12;
+
+/// Longest accepted key name
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Window of the per-key rate limit
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// API key error types
+#[derive(Error, Debug)]
+pub enum ApiKeyError {
+    #[error("API keys are not enabled on this relay")]
+    Disabled,
+
+    #[error("Invalid API key")]
+    Invalid,
+
+    #[error("API key {key_id} exceeded {requests_per_minute} requests per minute")]
+    RateLimited { key_id: String, requests_per_minute: u32 },
+
+    #[error("API key not found: {0}")]
+    NotFound(String),
+}
+
+/// Request to create an API key
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// Name of the client using the key
+    pub name: String,
+    /// Scopes granted to the key
+    pub scopes: Vec<String>,
+    /// Requests per minute (relay default if unset)
+    pub requests_per_minute: Option<u32>,
+}
+
+/// Requests counted in a key's current rate window
+struct RateWindow {
+    started: Instant,
+    requests: u32,
+}
+
+/// Authenticates API keys and enforces their rate limits
+pub struct ApiKeys {
+    db: Arc<Database>,
+    requests_per_minute: u32,
+    windows: Mutex<HashMap<String, RateWindow>>,
+}
+
+impl ApiKeys {
+    /// API key authentication against the keys stored in `db`
+    pub fn new(db: Arc<Database>, config: &ApiKeyConfig) -> Self {
+        Self {
+            db,
+            requests_per_minute: config.requests_per_minute,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Authenticate a presented key, counting the request against its limit
+    pub async fn authenticate(&self, presented: &str) -> Result<AuthContext, AppError> {
+        let key = self.db.find_active_api_key(&hash_key(presented)).await?.ok_or(ApiKeyError::Invalid)?;
+
+        let limit = key.requests_per_minute.map_or(self.requests_per_minute, |limit| limit as u32);
+        if !self.admit(&key.id, limit) {
+            return Err(ApiKeyError::RateLimited {
+                key_id: key.id,
+                requests_per_minute: limit,
+            }
+            .into());
+        }
+
+        Ok(AuthContext {
+            user_id: format!("api-key:{}", key.id),
+            scopes: key.scopes.split_whitespace().map(str::to_string).collect::<HashSet<_>>(),
+            tenant: None,
+        })
+    }
+
+    /// Count a request in the key's window, refusing it once the limit is reached
+    fn admit(&self, key_id: &str, limit: u32) -> bool {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.entry(key_id.to_string()).or_insert(RateWindow { started: now, requests: 0 });
+        if now.duration_since(window.started) >= RATE_WINDOW {
+            *window = RateWindow { started: now, requests: 0 };
+        }
+        if window.requests >= limit {
+            return false;
+        }
+        window.requests += 1;
+        true
+    }
+}
+
+/// Generate a new API key
+fn generate_key() -> String {
+    let mut bytes = [0u8; KEY_LENGTH];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", KEY_PREFIX, hex::encode(bytes))
+}
+
+/// SHA-256 hash under which a key is stored
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Characters of a key shown to recognize it
+fn display_prefix(key: &str) -> &str {
+    &key[..DISPLAY_PREFIX_LENGTH]
+}
+
+/// Replace a key's secret, returning the key and its new secret
+///
+/// The old secret stops working immediately.
+pub async fn rotate_key(db: &Database, key_id: &str) -> Result<(StoredApiKey, String), DatabaseError> {
+    let secret = generate_key();
+    let key = db.rotate_api_key(key_id, &hash_key(&secret), display_prefix(&secret)).await?;
+    Ok((key, secret))
+}
+
+/// Create router for authenticated API key management endpoints
+pub fn authenticated_api_key_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/", post(authenticated_create_api_key_handler).get(authenticated_list_api_keys_handler))
+        .route("/:key_id", delete(authenticated_revoke_api_key_handler))
+        .route("/:key_id/rotate", post(authenticated_rotate_api_key_handler))
+}
+
+/// Validate a key creation request
+fn validate_request(request: &CreateApiKeyRequest) -> Result<(), AppError> {
+    if request.name.trim().is_empty() || request.name.len() > MAX_NAME_LENGTH {
+        return Err(AppError::InvalidQuery(format!("name must be 1-{} characters", MAX_NAME_LENGTH)));
+    }
+    if request.scopes.is_empty() {
+        return Err(AppError::InvalidQuery("scopes must not be empty".to_string()));
+    }
+    if request.scopes.iter().any(|scope| scope.is_empty() || scope.contains(char::is_whitespace)) {
+        return Err(AppError::InvalidQuery("scopes must be non-empty words".to_string()));
+    }
+    if request.requests_per_minute == Some(0) {
+        return Err(AppError::InvalidQuery("requests_per_minute must be at least 1".to_string()));
+    }
+    Ok(())
+}
+
+/// Reject key management when API keys are disabled
+fn require_enabled(api_keys: Option<Extension<Arc<ApiKeys>>>) -> Result<(), ApiKeyError> {
+    api_keys.map(|_| ()).ok_or(ApiKeyError::Disabled)
+}
+
+/// Map a missing API key to an API key error
+fn api_key_not_found(error: DatabaseError) -> AppError {
+    match error {
+        DatabaseError::ApiKeyNotFound(id) => ApiKeyError::NotFound(id).into(),
+        other => AppError::DatabaseError(other),
+    }
+}
+
+/// Log an API key management event
+fn audit(
+    secure_logger: &crate::secure_logger::SecureLogger,
+    message: &str,
+    auth: &AuthContext,
+    request_id: &RequestId,
+    key: &StoredApiKey,
+) {
+    let mut metadata = HashMap::new();
+    metadata.insert("key_id".to_string(), key.id.clone());
+    metadata.insert("name".to_string(), key.name.clone());
+    metadata.insert("scopes".to_string(), key.scopes.clone());
+
+    if let Err(e) = secure_logger.audit_log(message.to_string(), auth.user_id.clone(), Some(request_id.to_string()), metadata) {
+        warn!("Failed to log API key event: {}", e);
+    }
+}
+
+/// Authenticated handler to create an API key
+#[utoipa::path(
+    post,
+    path = "/admin/api-keys",
+    operation_id = "createApiKey",
+    tag = "api-keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "API key created; the key is shown only once", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn authenticated_create_api_key_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    api_keys: Option<Extension<Arc<ApiKeys>>>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} creating API key", auth.user_id);
+
+    require_enabled(api_keys)?;
+    validate_request(&payload)?;
+
+    let secret = generate_key();
+    let key = db
+        .create_api_key(
+            payload.name.trim(),
+            &hash_key(&secret),
+            display_prefix(&secret),
+            &payload.scopes.join(" "),
+            payload.requests_per_minute.map(i64::from),
+            Some(&auth.user_id),
+        )
+        .await?;
+    audit(&secure_logger, "API key created", &auth, &request_id, &key);
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "api_key": key,
+        "key": secret,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Authenticated handler to list API keys
+#[utoipa::path(
+    get,
+    path = "/admin/api-keys",
+    operation_id = "listApiKeys",
+    tag = "api-keys",
+    responses(
+        (status = 200, description = "API keys, without their secrets", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn authenticated_list_api_keys_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    api_keys: Option<Extension<Arc<ApiKeys>>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} listing API keys", auth.user_id);
+
+    require_enabled(api_keys)?;
+    let keys = db.list_api_keys().await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "count": keys.len(),
+        "api_keys": keys,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to rotate an API key's secret
+#[utoipa::path(
+    post,
+    path = "/admin/api-keys/{key_id}/rotate",
+    operation_id = "rotateApiKey",
+    tag = "api-keys",
+    params(("key_id" = String, Path, description = "API key ID")),
+    responses(
+        (status = 200, description = "The replacement key; the key is shown only once", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn authenticated_rotate_api_key_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    api_keys: Option<Extension<Arc<ApiKeys>>>,
+    Path(key_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} rotating API key: {}", auth.user_id, key_id);
+
+    require_enabled(api_keys)?;
+    let (key, secret) = rotate_key(&db, &key_id).await.map_err(api_key_not_found)?;
+    audit(&secure_logger, "API key rotated", &auth, &request_id, &key);
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "api_key": key,
+        "key": secret,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to revoke an API key
+#[utoipa::path(
+    delete,
+    path = "/admin/api-keys/{key_id}",
+    operation_id = "revokeApiKey",
+    tag = "api-keys",
+    params(("key_id" = String, Path, description = "API key ID")),
+    responses(
+        (status = 200, description = "API key revoked", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn authenticated_revoke_api_key_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    api_keys: Option<Extension<Arc<ApiKeys>>>,
+    Path(key_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} revoking API key: {}", auth.user_id, key_id);
+
+    require_enabled(api_keys)?;
+    let key = db.revoke_api_key(&key_id).await.map_err(api_key_not_found)?;
+    audit(&secure_logger, "API key revoked", &auth, &request_id, &key);
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "api_key": key,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{jwt_validator::JwtValidator, secure_logger::SecureLogger};
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    async fn setup() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    /// The router the relay binary serves, with API keys enabled or not
+    fn app(db: Arc<Database>, enabled: bool) -> Router {
+        let validator = Arc::new(JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let logger = Arc::new(SecureLogger::new(&SecureLogger::generate_key()));
+        let config = crate::config::RelayConfig {
+            api_keys: ApiKeyConfig { enabled, requests_per_minute: 600 },
+            rate_limit: crate::config::RateLimitConfig { per_second: 100, burst_size: 100 },
+            ..Default::default()
+        };
+        crate::create_authenticated_app_with_config(db, &config, validator, logger)
+    }
+
+    fn admin_token() -> String {
+        let claims = serde_json::json!({ "sub": "admin", "iss": "issuer", "exp": 9999999999u64, "scope": "apikey:manage" });
+        jsonwebtoken::encode(&Default::default(), &claims, &jsonwebtoken::EncodingKey::from_secret(b"secret")).unwrap()
+    }
+
+    async fn send(app: &Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    fn with_key(uri: &str, key: &str) -> Request<Body> {
+        Request::builder().uri(uri).header(API_KEY_HEADER, key).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_api_key_lifecycle() {
+        // ARRANGE: An admin creates a key allowed to read the quarantine
+        let db = setup().await;
+        let app = app(db.clone(), true);
+        let (status, created) = send(
+            &app,
+            Request::builder()
+                .method("POST")
+                .uri("/admin/api-keys")
+                .header("authorization", format!("Bearer {}", admin_token()))
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"name":"billing-sync","scopes":["quarantine:read"]}"#))
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let key = created["key"].as_str().unwrap().to_string();
+        let key_id = created["api_key"]["id"].as_str().unwrap().to_string();
+
+        // ACT: Use the key, rotate it, then revoke it
+        let (allowed, _) = send(&app, with_key("/quarantine", &key)).await;
+        let (outside_scopes, _) = send(&app, with_key("/revocation/list", &key)).await;
+        let rotate = Request::builder()
+            .method("POST")
+            .uri(format!("/admin/api-keys/{}/rotate", key_id))
+            .header("authorization", format!("Bearer {}", admin_token()))
+            .body(Body::empty())
+            .unwrap();
+        let (_, rotated) = send(&app, rotate).await;
+        let new_key = rotated["key"].as_str().unwrap().to_string();
+        let (old_key_status, old_key_body) = send(&app, with_key("/quarantine", &key)).await;
+        let (new_key_status, _) = send(&app, with_key("/quarantine", &new_key)).await;
+        db.revoke_api_key(&key_id).await.unwrap();
+        let (revoked_status, _) = send(&app, with_key("/quarantine", &new_key)).await;
+
+        // ASSERT: Keys act with their scopes until rotated or revoked, and only hashes are stored
+        assert_eq!(allowed, StatusCode::OK);
+        assert_eq!(outside_scopes, StatusCode::FORBIDDEN);
+        assert_eq!(old_key_status, StatusCode::UNAUTHORIZED);
+        assert_eq!(old_key_body["code"], "INVALID_API_KEY");
+        assert_eq!(new_key_status, StatusCode::OK);
+        assert_eq!(revoked_status, StatusCode::UNAUTHORIZED);
+        let stored = db.get_api_key(&key_id).await.unwrap();
+        assert_eq!(stored.key_hash, hash_key(&new_key));
+        assert!(created["api_key"].get("key_hash").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_per_key_rate_limit() {
+        let db = setup().await;
+        let secret = generate_key();
+        db.create_api_key("probe", &hash_key(&secret), display_prefix(&secret), "quarantine:read", Some(2), None)
+            .await
+            .unwrap();
+        let app = app(db, true);
+
+        let mut statuses = Vec::new();
+        for _ in 0..3 {
+            statuses.push(send(&app, with_key("/quarantine", &secret)).await.0);
+        }
+
+        assert_eq!(statuses, vec![StatusCode::OK, StatusCode::OK, StatusCode::TOO_MANY_REQUESTS]);
+    }
+
+    #[tokio::test]
+    async fn test_key_management_requires_an_admin() {
+        let db = setup().await;
+        let app = app(db, true);
+        let create = || {
+            Request::builder()
+                .method("POST")
+                .uri("/v1/admin/api-keys")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"name":"intruder","scopes":["apikey:manage"]}"#))
+                .unwrap()
+        };
+
+        let (anonymous, _) = send(&app, create()).await;
+        let (keyed, _) = send(&app, {
+            let mut request = create();
+            request.headers_mut().insert(API_KEY_HEADER, generate_key().parse().unwrap());
+            request
+        })
+        .await;
+
+        assert_eq!(anonymous, StatusCode::UNAUTHORIZED);
+        assert_eq!(keyed, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_api_keys_are_rejected_when_disabled() {
+        let db = setup().await;
+        let app = app(db, false);
+
+        let (status, _) = send(&app, with_key("/quarantine", &generate_key())).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
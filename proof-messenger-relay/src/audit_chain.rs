@@ -0,0 +1,444 @@
+//! Audit Log Integrity Module
+//!
+//! Entries written by the `secure_log` audit sink cannot be read or forged
+//! without `AUDIT_LOG_KEY`, but lines can still be deleted from the file
+//! without a trace. The sink therefore writes a hash chain: each line holds an
+//! entry's sequence number, the hash of the previous entry and a hash over
+//! both and the encrypted entry, so a removed, reordered or modified line
+//! breaks the chain. Every `audit.checkpoint_interval` entries a checkpoint
+//! naming the chain's head is appended, signed with an Ed25519 key derived
+//! from `AUDIT_LOG_KEY`, so a rewritten chain cannot be passed off as the
+//! relay's. Entries after the last checkpoint can still be cut from the end of
+//! the file unnoticed; ship checkpoints off the host to close that gap.
+//!
+//! Chains are checked with [`verify_chain`], `relay-admin verify-audit-log`
+//! and `GET /admin/compliance/integrity` (scope `audit:read` on
+//! OAuth-protected relays). Lines written before chaining was introduced are
+//! reported as legacy entries when they precede the chain.
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature, Verifier};
+use proof_messenger_protocol::key::SecureKeypair;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::BufRead;
+use thiserror::Error;
+
+use crate::secure_logger::EncryptedLogEntry;
+
+/// Previous hash of the first entry in a chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Audit log chain errors
+#[derive(Error, Debug)]
+pub enum ChainError {
+    #[error("Audit log chaining requires the secure_log audit sink")]
+    Disabled,
+
+    #[error("Failed to read the audit log: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("line {line}: not an audit log record")]
+    Malformed { line: usize },
+
+    #[error("line {line}: unchained entry inside the chain")]
+    Unchained { line: usize },
+
+    #[error("line {line}: expected entry {expected} but found entry {found}")]
+    Gap { line: usize, expected: u64, found: u64 },
+
+    #[error("line {line}: entry {sequence} does not link to the previous entry")]
+    BrokenLink { line: usize, sequence: u64 },
+
+    #[error("line {line}: entry {sequence} does not match its hash")]
+    Tampered { line: usize, sequence: u64 },
+
+    #[error("line {line}: checkpoint at entry {sequence} {reason}")]
+    InvalidCheckpoint { line: usize, sequence: u64, reason: String },
+}
+
+/// A line of a chained audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum ChainRecord {
+    Entry(ChainedEntry),
+    Checkpoint(Checkpoint),
+}
+
+/// An encrypted entry linked to the one before it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainedEntry {
+    /// Position in the chain, starting at 0
+    pub sequence: u64,
+    /// Hash of the previous entry, or [`GENESIS_HASH`]
+    pub previous_hash: String,
+    /// Hash over the sequence number, previous hash and entry (hex encoded)
+    pub hash: String,
+    pub entry: EncryptedLogEntry,
+}
+
+/// A signed statement of the chain's head
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Sequence number of the last entry covered
+    pub sequence: u64,
+    /// Hash of that entry
+    pub hash: String,
+    pub signed_at: DateTime<Utc>,
+    /// Key the checkpoint is signed with (hex encoded)
+    pub public_key: String,
+    /// Ed25519 signature over the checkpoint statement (hex encoded)
+    pub signature: String,
+}
+
+/// Outcome of checking an intact chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChainReport {
+    /// Unchained entries written before the chain started
+    pub legacy_entries: u64,
+    /// Chained entries
+    pub entries: u64,
+    /// Signed checkpoints
+    pub checkpoints: u64,
+    /// Hash of the last entry
+    pub head_hash: String,
+    /// Sequence number of the last entry covered by a checkpoint
+    pub last_checkpoint: Option<u64>,
+    /// Entries after the last checkpoint, not yet covered by a signature
+    pub unsigned_entries: u64,
+}
+
+/// Checkpoint signing key derived from an `AUDIT_LOG_KEY`
+pub fn checkpoint_key(log_key: &[u8; 32]) -> SecureKeypair {
+    let mut hasher = Sha256::new();
+    hasher.update(b"proof-messenger-audit-checkpoint-key-v1");
+    hasher.update(log_key);
+    let secret = SecretKey::from_bytes(&hasher.finalize()).expect("SHA-256 output is a valid secret key");
+    let public = PublicKey::from(&secret);
+    SecureKeypair::from_bytes(&Keypair { secret, public }.to_bytes()).expect("derived keypair is valid")
+}
+
+/// Hash linking an entry to its predecessor
+pub fn entry_hash(sequence: u64, previous_hash: &str, entry: &EncryptedLogEntry) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"proof-messenger-audit-chain-v1");
+    hasher.update(sequence.to_be_bytes());
+    hasher.update(previous_hash.as_bytes());
+    hasher.update(entry.timestamp.to_rfc3339().as_bytes());
+    hasher.update(format!("{:?}", entry.level).as_bytes());
+    hasher.update((entry.nonce.len() as u64).to_be_bytes());
+    hasher.update(&entry.nonce);
+    hasher.update((entry.ciphertext.len() as u64).to_be_bytes());
+    hasher.update(&entry.ciphertext);
+    hex::encode(hasher.finalize())
+}
+
+/// Bytes a checkpoint's signature covers
+fn checkpoint_statement(sequence: u64, hash: &str, signed_at: &DateTime<Utc>) -> Vec<u8> {
+    format!("proof-messenger-audit-checkpoint-v1\n{}\n{}\n{}", sequence, hash, signed_at.to_rfc3339()).into_bytes()
+}
+
+/// The writing end of a chain
+pub struct AuditChain {
+    signing_key: SecureKeypair,
+    checkpoint_interval: usize,
+    next_sequence: u64,
+    head: String,
+    since_checkpoint: usize,
+}
+
+impl AuditChain {
+    /// A new chain, signing a checkpoint every `checkpoint_interval` entries
+    pub fn new(signing_key: SecureKeypair, checkpoint_interval: usize) -> Self {
+        Self {
+            signing_key,
+            checkpoint_interval,
+            next_sequence: 0,
+            head: GENESIS_HASH.to_string(),
+            since_checkpoint: 0,
+        }
+    }
+
+    /// Continue the chain already written to `log`
+    ///
+    /// Lines that are not chain records, such as legacy entries, are skipped.
+    pub fn resume(mut self, log: impl BufRead) -> std::io::Result<Self> {
+        for line in log.lines() {
+            match serde_json::from_str::<ChainRecord>(&line?) {
+                Ok(ChainRecord::Entry(entry)) => {
+                    self.next_sequence = entry.sequence + 1;
+                    self.head = entry.hash;
+                    self.since_checkpoint += 1;
+                }
+                Ok(ChainRecord::Checkpoint(_)) => self.since_checkpoint = 0,
+                Err(_) => {}
+            }
+        }
+        Ok(self)
+    }
+
+    /// Key checkpoints are verified with (hex encoded)
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.signing_key.public_key_bytes())
+    }
+
+    /// Link an entry into the chain, followed by a checkpoint when one is due
+    pub fn append(&mut self, entry: EncryptedLogEntry) -> Vec<ChainRecord> {
+        let hash = entry_hash(self.next_sequence, &self.head, &entry);
+        let mut records = vec![ChainRecord::Entry(ChainedEntry {
+            sequence: self.next_sequence,
+            previous_hash: std::mem::replace(&mut self.head, hash.clone()),
+            hash,
+            entry,
+        })];
+        self.next_sequence += 1;
+        self.since_checkpoint += 1;
+        if self.since_checkpoint >= self.checkpoint_interval {
+            records.extend(self.checkpoint());
+        }
+        records
+    }
+
+    /// Sign the chain's head, unless it is already covered
+    pub fn checkpoint(&mut self) -> Option<ChainRecord> {
+        if self.since_checkpoint == 0 {
+            return None;
+        }
+        self.since_checkpoint = 0;
+        let sequence = self.next_sequence - 1;
+        let signed_at = Utc::now();
+        let signature = self.signing_key.sign(&checkpoint_statement(sequence, &self.head, &signed_at));
+        Some(ChainRecord::Checkpoint(Checkpoint {
+            sequence,
+            hash: self.head.clone(),
+            signed_at,
+            public_key: self.public_key_hex(),
+            signature: hex::encode(signature.to_bytes()),
+        }))
+    }
+}
+
+/// Check a chained audit log for gaps, modified entries and bad checkpoints
+///
+/// With a `trusted_key` (hex encoded), checkpoints signed by any other key
+/// are rejected; without one, signatures are only checked against the key
+/// each checkpoint names.
+pub fn verify_chain(log: impl BufRead, trusted_key: Option<&str>) -> Result<ChainReport, ChainError> {
+    let mut report = ChainReport {
+        legacy_entries: 0,
+        entries: 0,
+        checkpoints: 0,
+        head_hash: GENESIS_HASH.to_string(),
+        last_checkpoint: None,
+        unsigned_entries: 0,
+    };
+
+    for (index, line) in log.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = match serde_json::from_str::<ChainRecord>(&line) {
+            Ok(record) => record,
+            Err(_) if serde_json::from_str::<EncryptedLogEntry>(&line).is_ok() => {
+                if report.entries > 0 || report.checkpoints > 0 {
+                    return Err(ChainError::Unchained { line: line_number });
+                }
+                report.legacy_entries += 1;
+                continue;
+            }
+            Err(_) => return Err(ChainError::Malformed { line: line_number }),
+        };
+
+        match record {
+            ChainRecord::Entry(entry) => {
+                if entry.sequence != report.entries {
+                    return Err(ChainError::Gap {
+                        line: line_number,
+                        expected: report.entries,
+                        found: entry.sequence,
+                    });
+                }
+                if entry.previous_hash != report.head_hash {
+                    return Err(ChainError::BrokenLink { line: line_number, sequence: entry.sequence });
+                }
+                if entry_hash(entry.sequence, &entry.previous_hash, &entry.entry) != entry.hash {
+                    return Err(ChainError::Tampered { line: line_number, sequence: entry.sequence });
+                }
+                report.entries += 1;
+                report.unsigned_entries += 1;
+                report.head_hash = entry.hash;
+            }
+            ChainRecord::Checkpoint(checkpoint) => {
+                let invalid = |reason: &str| ChainError::InvalidCheckpoint {
+                    line: line_number,
+                    sequence: checkpoint.sequence,
+                    reason: reason.to_string(),
+                };
+                if report.entries == 0 || checkpoint.sequence != report.entries - 1 || checkpoint.hash != report.head_hash {
+                    return Err(invalid("does not match the chain's head"));
+                }
+                if trusted_key.is_some_and(|key| !key.eq_ignore_ascii_case(&checkpoint.public_key)) {
+                    return Err(invalid("is signed by an untrusted key"));
+                }
+                let public_key = hex::decode(&checkpoint.public_key)
+                    .ok()
+                    .and_then(|key| PublicKey::from_bytes(&key).ok())
+                    .ok_or_else(|| invalid("names an invalid public key"))?;
+                let signature = hex::decode(&checkpoint.signature)
+                    .ok()
+                    .and_then(|signature| Signature::from_bytes(&signature).ok())
+                    .ok_or_else(|| invalid("has a malformed signature"))?;
+                let statement = checkpoint_statement(checkpoint.sequence, &checkpoint.hash, &checkpoint.signed_at);
+                if public_key.verify(&statement, &signature).is_err() {
+                    return Err(invalid("has an invalid signature"));
+                }
+                report.checkpoints += 1;
+                report.last_checkpoint = Some(checkpoint.sequence);
+                report.unsigned_entries = 0;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secure_logger::{LogEntry, LogLevel, SecureLogger};
+    use std::collections::HashMap;
+
+    const LOG_KEY: [u8; 32] = [7u8; 32];
+
+    fn encrypted(message: &str) -> EncryptedLogEntry {
+        SecureLogger::new(&LOG_KEY)
+            .encrypt_log_entry(&LogEntry {
+                timestamp: Utc::now(),
+                level: LogLevel::Audit,
+                message: message.to_string(),
+                user_id: None,
+                request_id: None,
+                metadata: HashMap::new(),
+            })
+            .unwrap()
+    }
+
+    /// The lines of a chain of `count` entries, checkpointed every 2
+    fn chain_lines(count: usize) -> Vec<String> {
+        let mut chain = AuditChain::new(checkpoint_key(&LOG_KEY), 2);
+        (0..count)
+            .flat_map(|i| chain.append(encrypted(&format!("entry {}", i))))
+            .map(|record| serde_json::to_string(&record).unwrap())
+            .collect()
+    }
+
+    fn verify(lines: &[String]) -> Result<ChainReport, ChainError> {
+        let trusted = hex::encode(checkpoint_key(&LOG_KEY).public_key_bytes());
+        verify_chain(lines.join("\n").as_bytes(), Some(&trusted))
+    }
+
+    #[test]
+    fn test_intact_chain_verifies() {
+        let lines = chain_lines(5);
+
+        let report = verify(&lines).unwrap();
+
+        assert_eq!(lines.len(), 7);
+        assert_eq!(report.entries, 5);
+        assert_eq!(report.checkpoints, 2);
+        assert_eq!(report.last_checkpoint, Some(3));
+        assert_eq!(report.unsigned_entries, 1);
+        assert_eq!(report.legacy_entries, 0);
+    }
+
+    #[test]
+    fn test_deleted_entry_is_a_gap() {
+        let mut lines = chain_lines(5);
+        lines.remove(3); // entry 2
+
+        assert!(matches!(verify(&lines), Err(ChainError::Gap { line: 4, expected: 2, found: 3 })));
+    }
+
+    #[test]
+    fn test_modified_entry_is_detected() {
+        // ARRANGE: A chain whose second entry was swapped for another ciphertext
+        let mut lines = chain_lines(3);
+        let mut record: ChainRecord = serde_json::from_str(&lines[1]).unwrap();
+        if let ChainRecord::Entry(entry) = &mut record {
+            entry.entry = encrypted("forged");
+        }
+        lines[1] = serde_json::to_string(&record).unwrap();
+
+        // ACT
+        let result = verify(&lines);
+
+        // ASSERT
+        assert!(matches!(result, Err(ChainError::Tampered { line: 2, sequence: 1 })));
+    }
+
+    #[test]
+    fn test_rehashed_chain_fails_the_checkpoint_signature() {
+        // ARRANGE: An attacker rewrites entry 1 and recomputes every hash after it
+        let lines = chain_lines(2);
+        let mut entries: Vec<ChainedEntry> = lines
+            .iter()
+            .filter_map(|line| match serde_json::from_str(line).unwrap() {
+                ChainRecord::Entry(entry) => Some(entry),
+                ChainRecord::Checkpoint(_) => None,
+            })
+            .collect();
+        entries[1].entry = encrypted("forged");
+        entries[1].hash = entry_hash(1, &entries[1].previous_hash, &entries[1].entry);
+        let mut forged: Vec<String> = entries
+            .iter()
+            .map(|entry| serde_json::to_string(&ChainRecord::Entry(entry.clone())).unwrap())
+            .collect();
+        forged.push(lines[2].clone());
+
+        // ACT: Verify against the relay's key, and with a checkpoint signed by another key
+        let result = verify(&forged);
+        let mut rogue = AuditChain::new(checkpoint_key(&[9u8; 32]), 100);
+        rogue.next_sequence = 2;
+        rogue.head = entries[1].hash.clone();
+        rogue.since_checkpoint = 2;
+        forged[2] = serde_json::to_string(&rogue.checkpoint().unwrap()).unwrap();
+
+        // ASSERT: The signed head no longer matches, and foreign signers are refused
+        assert!(matches!(result, Err(ChainError::InvalidCheckpoint { line: 3, sequence: 1, .. })));
+        assert!(matches!(
+            verify(&forged),
+            Err(ChainError::InvalidCheckpoint { ref reason, .. }) if reason == "is signed by an untrusted key"
+        ));
+    }
+
+    #[test]
+    fn test_resumed_chain_continues_after_the_last_entry() {
+        let lines = chain_lines(3);
+
+        let mut chain = AuditChain::new(checkpoint_key(&LOG_KEY), 2)
+            .resume(lines.join("\n").as_bytes())
+            .unwrap();
+        let mut all = lines.clone();
+        all.extend(chain.append(encrypted("after restart")).iter().map(|record| serde_json::to_string(record).unwrap()));
+        let report = verify(&all).unwrap();
+
+        assert_eq!(report.entries, 4);
+        assert_eq!(report.checkpoints, 2);
+        assert_eq!(report.unsigned_entries, 0);
+    }
+
+    #[test]
+    fn test_legacy_entries_may_only_precede_the_chain() {
+        let legacy = serde_json::to_string(&encrypted("before chaining")).unwrap();
+        let mut before = vec![legacy.clone()];
+        before.extend(chain_lines(2));
+        let mut after = chain_lines(2);
+        after.push(legacy);
+
+        assert_eq!(verify(&before).unwrap().legacy_entries, 1);
+        assert!(matches!(verify(&after), Err(ChainError::Unchained { line: 4 })));
+        assert!(matches!(verify(&["not json".to_string()]), Err(ChainError::Malformed { line: 1 })));
+    }
+}
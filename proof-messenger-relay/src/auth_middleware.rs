@@ -5,13 +5,16 @@ use axum::{
     response::Response,
 };
 use std::sync::Arc;
-use crate::jwt_validator::{JwtValidator, JwtValidationError, extract_user_from_bearer_token};
+use crate::jwt_validator::{JwtValidator, extract_user_from_bearer_token};
+use crate::AppError;
 
 /// Authentication context that gets added to request extensions
 #[derive(Debug, Clone)]
 pub struct AuthContext {
     pub user_id: String,
     pub scopes: std::collections::HashSet<String>,
+    /// Tenant named by the token, when tenants are identified by a claim
+    pub tenant: Option<String>,
 }
 
 /// Authentication middleware that validates JWT tokens
@@ -20,32 +23,90 @@ pub async fn auth_middleware(
     headers: HeaderMap,
     mut request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
+) -> Result<Response, AppError> {
+    // Machine clients may authenticate with an API key instead of a bearer token
+    if let (Some(api_keys), Some(key)) = (
+        request.extensions().get::<Arc<crate::api_keys::ApiKeys>>().cloned(),
+        headers.get(crate::api_keys::API_KEY_HEADER),
+    ) {
+        let key = key.to_str().map_err(|_| crate::api_keys::ApiKeyError::Invalid)?;
+        let auth_context = api_keys.authenticate(key).await?;
+        request.extensions_mut().insert(auth_context);
+        return Ok(next.run(request).await);
+    }
+
+    // Mesh workloads may authenticate with their client certificate instead
+    if !headers.contains_key("authorization") {
+        if let Some(client_identity) = request.extensions().get::<Arc<crate::client_identity::ClientIdentityAuth>>() {
+            let certificate = request.extensions().get::<crate::tls::ClientCertificate>();
+            if let Some(auth_context) = client_identity.authenticate(certificate, &headers) {
+                request.extensions_mut().insert(auth_context);
+                return Ok(next.run(request).await);
+            }
+        }
+    }
+
     // Extract Authorization header
     let auth_header = headers
         .get("authorization")
         .and_then(|h| h.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-
-    // Validate the JWT token
-    let user_id = extract_user_from_bearer_token(auth_header, &validator)
-        .map_err(|e| match e {
-            JwtValidationError::InvalidFormat => StatusCode::BAD_REQUEST,
-            JwtValidationError::InvalidSignature => StatusCode::UNAUTHORIZED,
-            JwtValidationError::Expired => StatusCode::UNAUTHORIZED,
-            JwtValidationError::InvalidIssuer => StatusCode::UNAUTHORIZED,
-            JwtValidationError::InvalidAudience => StatusCode::UNAUTHORIZED,
-            JwtValidationError::MissingClaim(_) => StatusCode::BAD_REQUEST,
-            JwtValidationError::ValidationError(_) => StatusCode::UNAUTHORIZED,
-        })?;
-
-    // Extract scopes for authorization
-    let token = &auth_header[7..]; // Remove "Bearer " prefix
-    let scopes = validator.extract_scopes(token)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+        .ok_or(AppError::MissingCredentials)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(crate::jwt_validator::JwtValidationError::InvalidFormat)?;
+
+    // Clients may present a session token issued for a signed key challenge
+    if crate::key_auth::is_session_token(token) {
+        if let Some(key_auth) = request.extensions().get::<Arc<crate::key_auth::KeyAuth>>().cloned() {
+            let auth_context = key_auth.authenticate(token).await?;
+            request.extensions_mut().insert(auth_context);
+            return Ok(next.run(request).await);
+        }
+    }
+    let roles = request.extensions().get::<Arc<crate::rbac::RoleMapping>>().cloned();
+    let tenant_claim = request
+        .extensions()
+        .get::<Arc<crate::tenancy::Tenancy>>()
+        .map(|tenancy| tenancy.claim().to_string());
+
+    let (user_id, mut scopes, groups, tenant) = match validator.introspect(token).await? {
+        // Opaque tokens are described by the issuer's introspection endpoint
+        Some(introspected) => (
+            introspected.subject().unwrap_or_default(),
+            introspected.scopes(),
+            roles.as_ref().map(|roles| introspected.claim_values(roles.claim())).unwrap_or_default(),
+            tenant_claim.and_then(|claim| introspected.claim(&claim)),
+        ),
+        None => {
+            // Validate the JWT token (format errors map to 400, the rest to 401)
+            let user_id = extract_user_from_bearer_token(auth_header, &validator)?;
+
+            // Extract scopes for authorization
+            let scopes = validator.extract_scopes(token)?;
+
+            // Read the caller's identity provider groups when they grant scopes
+            let groups = match &roles {
+                Some(roles) => validator.extract_claim_values(token, roles.claim())?,
+                None => Vec::new(),
+            };
+
+            // Read the tenant claim when the relay serves tenants
+            let tenant = match tenant_claim {
+                Some(claim) => validator.extract_claim(token, &claim)?,
+                None => None,
+            };
+            (user_id, scopes, groups, tenant)
+        }
+    };
+
+    // Grant the scopes of the caller's identity provider groups
+    if let Some(roles) = &roles {
+        scopes.extend(roles.scopes_for(&groups).into_iter().map(str::to_string));
+    }
 
     // Add authentication context to request extensions
-    let auth_context = AuthContext { user_id, scopes };
+    let auth_context = AuthContext { user_id, scopes, tenant };
     request.extensions_mut().insert(auth_context);
 
     // Continue to the next middleware/handler
@@ -67,7 +128,7 @@ impl<S> axum::extract::FromRequestParts<S> for AuthContext
 where
     S: Send + Sync,
 {
-    type Rejection = StatusCode;
+    type Rejection = AppError;
 
     async fn from_request_parts(
         parts: &mut axum::http::request::Parts,
@@ -77,7 +138,7 @@ where
             .extensions
             .get::<AuthContext>()
             .cloned()
-            .ok_or(StatusCode::UNAUTHORIZED)
+            .ok_or(AppError::MissingCredentials)
     }
 }
 
@@ -253,6 +314,7 @@ TlRFl3uJlwQWy845QynSRDnxW/9pElh5rY3B9/5cBmuJ9lAV4nCZW5FbDO0Iw/QI
         let auth_context = AuthContext {
             user_id: "user-123".to_string(),
             scopes,
+            tenant: None,
         };
 
         // ACT & ASSERT: Should allow access with valid scope
@@ -269,6 +331,7 @@ TlRFl3uJlwQWy845QynSRDnxW/9pElh5rY3B9/5cBmuJ9lAV4nCZW5FbDO0Iw/QI
         let auth_context = AuthContext {
             user_id: "user-123".to_string(),
             scopes,
+            tenant: None,
         };
 
         // ACT & ASSERT: Should deny access without required scope
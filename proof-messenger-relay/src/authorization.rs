@@ -0,0 +1,413 @@
+//! Route Authorization Module
+//!
+//! On OAuth-protected relays every authenticated route requires a set of
+//! token scopes, declared in one table instead of in each handler. The
+//! built-in table ([`DEFAULT_ROUTE_SCOPES`]) covers every authenticated
+//! route; entries under `[authorization.routes]` in the relay settings (see
+//! [`crate::config::AuthorizationConfig`]) replace the requirement of the
+//! routes they name.
+//!
+//! The [`authorize`] middleware runs after authentication and looks the
+//! request up by method and unversioned route pattern, as in
+//! `"GET /messages/:group_id"` for both `/v1/messages/...` and its legacy alias:
+//!
+//! - a caller missing any required scope gets `403 INSUFFICIENT_SCOPE`, with
+//!   the route and the missing scopes in `details`
+//! - a route absent from the table is denied with `403 ROUTE_NOT_AUTHORIZED`,
+//!   so a new route stays closed until its scopes are declared
+//!
+//! Custom tables are used by layering the resulting [`ScopePolicy`] onto the
+//! router as an [`axum::Extension`]; without one the built-in table applies.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+    Extension,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, OnceLock};
+use tracing::warn;
+
+use crate::{auth_middleware::AuthContext, config::AuthorizationConfig, request_id, secure_logger::SecureLogger, AppError};
+
+/// Scopes required by each authenticated route, keyed by `"METHOD /path"`
+pub const DEFAULT_ROUTE_SCOPES: &[(&str, &[&str])] = &[
+    ("POST /relay", &["proof:create"]),
+    ("GET /messages/:group_id", &["message:read"]),
+    ("GET /message/:message_id", &["message:read"]),
+    ("DELETE /message/:message_id", &["message:delete"]),
+    ("GET /senders/:pubkey/messages", &["message:read"]),
+    ("GET /messages/search", &["message:read"]),
+    ("GET /messages/:group_id/export", &["message:export"]),
+    ("GET /ws/:group_id", &["message:read"]),
+    ("GET /events/:group_id", &["message:read"]),
+    ("GET /messages/:group_id/poll", &["message:read"]),
+    ("GET /threads/:thread_id", &["message:read"]),
+    ("GET /message/:message_id/receipts", &["receipt:read"]),
+    ("POST /message/:message_id/receipts", &["receipt:create"]),
+    ("POST /message/:message_id/read", &["receipt:create"]),
+    ("GET /message/:message_id/deliveries", &["receipt:read"]),
+    ("POST /inbox/challenge", &["message:read"]),
+    ("GET /inbox", &["message:read"]),
+    ("GET /message/:message_id/history", &["message:read"]),
+    ("POST /message/:message_id/amendments", &["message:amend"]),
+    ("POST /detached-proofs", &["proof:create"]),
+    ("POST /detached-proofs/verify", &["proof:read"]),
+    ("GET /detached-proofs/:digest", &["proof:read"]),
+    ("POST /derivations/verify", &["proof:read"]),
+    ("GET /proofs/:proof_hash/descendants", &["proof:read"]),
+    ("GET /proofs/:proof_hash/ancestors", &["proof:read"]),
+    ("GET /message/:message_id/proof-chain", &["proof:read"]),
+    ("GET /message/:message_id/timestamp", &["proof:read"]),
+    ("GET /message/:message_id/evidence", &["message:export"]),
+    ("GET /groups/:group_id/schemas", &["schema:read"]),
+    ("POST /groups/:group_id/schemas", &["schema:manage"]),
+    ("GET /groups/:group_id/schemas/:version", &["schema:read"]),
+    ("POST /approvals", &["approval:create"]),
+    ("GET /approvals", &["approval:read"]),
+    ("GET /approvals/:approval_id", &["approval:read"]),
+    ("POST /approvals/:approval_id/signatures", &["approval:sign"]),
+    ("POST /scheduled", &["proof:create"]),
+    ("GET /scheduled", &["message:read"]),
+    ("GET /scheduled/:scheduled_id", &["message:read"]),
+    ("DELETE /scheduled/:scheduled_id", &["proof:create"]),
+    ("POST /revocation/revoke", &["proof:revoke"]),
+    ("GET /revocation/check/:signature", &["proof:read"]),
+    ("GET /revocation/list", &["proof:read"]),
+    ("POST /revocation/cleanup", &["proof:manage"]),
+    ("POST /invites", &["invite:create"]),
+    ("GET /invites/:code", &["invite:read"]),
+    // Redeeming proves membership with the invite code and a signature
+    ("POST /invites/:code/redeem", &[]),
+    ("GET /webhooks", &["webhook:manage"]),
+    ("POST /webhooks", &["webhook:manage"]),
+    ("DELETE /webhooks/:webhook_id", &["webhook:manage"]),
+    ("GET /webhooks/:webhook_id/deliveries", &["webhook:manage"]),
+    ("GET /quarantine", &["quarantine:read"]),
+    ("GET /admin/compliance/summary", &["audit:read"]),
+    ("GET /admin/compliance/integrity", &["audit:read"]),
+    ("GET /admin/data-subjects/export", &["subject:export"]),
+    ("GET /admin/data-subjects/erasures", &["subject:erase"]),
+    ("POST /admin/data-subjects/erasures", &["subject:erase"]),
+    ("DELETE /admin/data-subjects/erasures/:request_id", &["subject:erase"]),
+    ("GET /admin/api-keys", &["apikey:manage"]),
+    ("POST /admin/api-keys", &["apikey:manage"]),
+    ("POST /admin/api-keys/:key_id/rotate", &["apikey:manage"]),
+    ("DELETE /admin/api-keys/:key_id", &["apikey:manage"]),
+    ("GET /admin/maintenance", &["relay:manage"]),
+    ("POST /admin/maintenance", &["relay:manage"]),
+    ("GET /admin/abuse/quarantines", &["relay:manage"]),
+    ("DELETE /admin/abuse/quarantines/:sender", &["relay:manage"]),
+    ("GET /admin/integrity", &["audit:read"]),
+    ("POST /admin/integrity/verify", &["relay:manage"]),
+    ("POST /keys/rotate", &["key:rotate"]),
+    ("GET /keys/pins/:user_id", &["key:read"]),
+    ("GET /keys/changes", &["key:read"]),
+];
+
+/// Split a `"METHOD /path"` route key into its method and path
+pub fn parse_route(route: &str) -> Option<(Method, &str)> {
+    let (method, path) = route.trim().split_once(' ')?;
+    let method = Method::from_bytes(method.as_bytes()).ok()?;
+    let path = path.trim();
+    path.starts_with('/').then_some((method, path))
+}
+
+/// Why a caller was denied a route for lack of scopes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScopeDenial {
+    /// The route, as `"METHOD /path"`
+    pub route: String,
+    /// Every scope the route requires
+    pub required_scopes: Vec<String>,
+    /// Required scopes the caller's token lacks
+    pub missing_scopes: Vec<String>,
+}
+
+impl std::fmt::Display for ScopeDenial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} requires scope {}", self.route, self.missing_scopes.join(", "))
+    }
+}
+
+/// The scopes required by each authenticated route
+#[derive(Debug, Clone)]
+pub struct ScopePolicy {
+    routes: HashMap<(Method, String), Vec<String>>,
+}
+
+impl Default for ScopePolicy {
+    fn default() -> Self {
+        Self::new(&AuthorizationConfig::default())
+    }
+}
+
+impl ScopePolicy {
+    /// The built-in table with the configured routes replacing its entries
+    ///
+    /// Route keys are expected to be valid; see [`parse_route`].
+    pub fn new(config: &AuthorizationConfig) -> Self {
+        let defaults = DEFAULT_ROUTE_SCOPES
+            .iter()
+            .map(|(route, scopes)| (route.to_string(), scopes.iter().map(|s| s.to_string()).collect()));
+        let routes: BTreeMap<String, Vec<String>> = defaults
+            .chain(config.routes.iter().map(|(route, scopes)| (route.clone(), scopes.clone())))
+            .collect();
+        let routes = routes
+            .iter()
+            .filter_map(|(route, scopes)| {
+                let (method, path) = parse_route(route)?;
+                Some(((method, path.to_string()), scopes.clone()))
+            })
+            .collect();
+        Self { routes }
+    }
+
+    /// Scopes required by a route, or `None` when the route is not listed
+    pub fn required_scopes(&self, method: &Method, path: &str) -> Option<&[String]> {
+        self.routes.get(&(method.clone(), path.to_string())).map(Vec::as_slice)
+    }
+
+    /// Check that the caller holds every scope the route requires
+    pub fn check(&self, auth: &AuthContext, method: &Method, path: &str) -> Result<(), AppError> {
+        let route = format!("{} {}", method, path);
+        let required = self
+            .required_scopes(method, path)
+            .ok_or_else(|| AppError::RouteNotAuthorized(route.clone()))?;
+        let missing: Vec<String> = required.iter().filter(|scope| !auth.scopes.contains(*scope)).cloned().collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::ScopeDenied(ScopeDenial {
+                route,
+                required_scopes: required.to_vec(),
+                missing_scopes: missing,
+            }))
+        }
+    }
+}
+
+/// The built-in table, used when no [`ScopePolicy`] is layered onto the router
+fn default_policy() -> &'static ScopePolicy {
+    static POLICY: OnceLock<ScopePolicy> = OnceLock::new();
+    POLICY.get_or_init(ScopePolicy::default)
+}
+
+/// Middleware enforcing the route's required scopes on authenticated requests
+///
+/// Must run after [`crate::auth_middleware::auth_middleware`]. Requests that
+/// match no route pass through so the router can answer `404 Not Found`.
+pub async fn authorize(
+    State(secure_logger): State<Arc<SecureLogger>>,
+    policy: Option<Extension<Arc<ScopePolicy>>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(path) = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| crate::versioning::unversioned_path(path.as_str()).to_string())
+    else {
+        return Ok(next.run(request).await);
+    };
+    let auth = request.extensions().get::<AuthContext>().ok_or(AppError::MissingCredentials)?;
+    let policy = policy.as_deref().map_or(default_policy(), |policy| policy.as_ref());
+
+    if let Err(denied) = policy.check(auth, request.method(), &path) {
+        warn!("Denied {} {} to user {}: {}", request.method(), path, auth.user_id, denied);
+        let mut metadata = HashMap::new();
+        metadata.insert("route".to_string(), format!("{} {}", request.method(), path));
+        if let AppError::ScopeDenied(denial) = &denied {
+            metadata.insert("missing_scopes".to_string(), denial.missing_scopes.join(" "));
+        }
+        if let Err(e) = secure_logger.critical_security_event(
+            "Route authorization denied".to_string(),
+            Some(auth.user_id.clone()),
+            request_id::current(),
+            metadata,
+        ) {
+            warn!("Failed to log authorization failure: {}", e);
+        }
+        return Err(denied);
+    }
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+    use std::collections::HashSet;
+    use tower::ServiceExt;
+
+    fn auth(scopes: &[&str]) -> AuthContext {
+        AuthContext {
+            user_id: "user-123".to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect::<HashSet<_>>(),
+            tenant: None,
+        }
+    }
+
+    /// A router authorizing requests as a caller holding `scopes`
+    fn app(scopes: &'static [&'static str], policy: ScopePolicy) -> Router {
+        let logger = Arc::new(SecureLogger::new(&SecureLogger::generate_key()));
+        Router::new()
+            .route("/messages/:group_id", get(|| async { "messages" }))
+            .nest("/admin", Router::new().route("/unlisted", get(|| async { "unlisted" })))
+            .layer(middleware::from_fn_with_state(logger, authorize))
+            .layer(middleware::from_fn(move |mut request: Request, next: Next| async move {
+                request.extensions_mut().insert(auth(scopes));
+                next.run(request).await
+            }))
+            .layer(Extension(Arc::new(policy)))
+    }
+
+    async fn get_status(app: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .oneshot(axum::http::Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[test]
+    fn test_default_table_covers_required_scopes() {
+        let policy = ScopePolicy::default();
+
+        assert!(policy.check(&auth(&["proof:create"]), &Method::POST, "/relay").is_ok());
+        assert!(policy.check(&auth(&[]), &Method::POST, "/invites/:code/redeem").is_ok());
+        let denied = policy.check(&auth(&["message:read"]), &Method::POST, "/relay").unwrap_err();
+        assert!(matches!(denied, AppError::ScopeDenied(ref d) if d.missing_scopes == ["proof:create"]));
+        assert!(matches!(
+            policy.check(&auth(&["proof:create"]), &Method::PUT, "/relay"),
+            Err(AppError::RouteNotAuthorized(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_missing_scope_is_reported() {
+        // ARRANGE: A caller without message:read
+        let app = app(&["proof:create"], ScopePolicy::default());
+
+        // ACT: Read a group's messages
+        let (status, body) = get_status(app, "/messages/team").await;
+
+        // ASSERT: The 403 names the route and the missing scope
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["code"], "INSUFFICIENT_SCOPE");
+        assert_eq!(body["details"]["route"], "GET /messages/:group_id");
+        assert_eq!(body["details"]["missing_scopes"], serde_json::json!(["message:read"]));
+    }
+
+    #[tokio::test]
+    async fn test_unlisted_routes_are_denied_until_configured() {
+        let config = AuthorizationConfig {
+            routes: BTreeMap::from([("GET /admin/unlisted".to_string(), vec!["admin".to_string()])]),
+        };
+
+        let (denied, body) = get_status(app(&["admin"], ScopePolicy::default()), "/admin/unlisted").await;
+        let (allowed, _) = get_status(app(&["admin"], ScopePolicy::new(&config)), "/admin/unlisted").await;
+        let (unmatched, _) = get_status(app(&["admin"], ScopePolicy::default()), "/nowhere").await;
+
+        assert_eq!(denied, StatusCode::FORBIDDEN);
+        assert_eq!(body["code"], "ROUTE_NOT_AUTHORIZED");
+        assert_eq!(allowed, StatusCode::OK);
+        assert_eq!(unmatched, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_configured_routes_replace_defaults() {
+        let config = AuthorizationConfig {
+            routes: BTreeMap::from([("GET /quarantine".to_string(), vec!["admin".to_string(), "quarantine:read".to_string()])]),
+        };
+        let policy = ScopePolicy::new(&config);
+
+        assert_eq!(policy.required_scopes(&Method::GET, "/quarantine").unwrap(), ["admin", "quarantine:read"]);
+        assert_eq!(policy.required_scopes(&Method::POST, "/relay").unwrap(), ["proof:create"]);
+    }
+
+    #[tokio::test]
+    async fn test_oauth_router_enforces_the_table() {
+        // ARRANGE: The OAuth router and tokens with and without quarantine:read
+        let db = Arc::new(crate::database::Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let validator = Arc::new(crate::jwt_validator::JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let logger = Arc::new(SecureLogger::new(&SecureLogger::generate_key()));
+        let app = crate::create_app_with_oauth(db, validator, logger);
+        let token = |scope: &str| {
+            let claims = crate::jwt_validator::Claims {
+                sub: "admin".to_string(),
+                iss: "issuer".to_string(),
+                aud: None,
+                exp: 9999999999,
+                iat: None,
+                nbf: None,
+                scope: Some(scope.to_string()),
+            };
+            jsonwebtoken::encode(&Default::default(), &claims, &jsonwebtoken::EncodingKey::from_secret(b"secret")).unwrap()
+        };
+        let list = |token: String| {
+            axum::http::Request::builder()
+                .uri("/v1/quarantine")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // ACT: List the quarantine with each token
+        let denied = app.clone().oneshot(list(token("message:read"))).await.unwrap();
+        let allowed = app.oneshot(list(token("quarantine:read"))).await.unwrap();
+
+        // ASSERT: The versioned, nested route is matched by its full unversioned path
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+        let body = axum::body::to_bytes(denied.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["details"]["route"], "GET /quarantine");
+        assert_eq!(allowed.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_configured_relay_enforces_route_overrides() {
+        // ARRANGE: The router the relay binary serves, with the quarantine restricted to admins
+        let db = Arc::new(crate::database::Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let validator = Arc::new(crate::jwt_validator::JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let logger = Arc::new(SecureLogger::new(&SecureLogger::generate_key()));
+        let mut config = crate::config::RelayConfig::default();
+        config.authorization.routes.insert("GET /quarantine".to_string(), vec!["admin".to_string()]);
+        let app = crate::create_authenticated_app_with_config(db, &config, validator, logger);
+        let token = |scope: &str| {
+            let claims = crate::jwt_validator::Claims {
+                sub: "admin".to_string(),
+                iss: "issuer".to_string(),
+                aud: None,
+                exp: 9999999999,
+                iat: None,
+                nbf: None,
+                scope: Some(scope.to_string()),
+            };
+            jsonwebtoken::encode(&Default::default(), &claims, &jsonwebtoken::EncodingKey::from_secret(b"secret")).unwrap()
+        };
+        let list = |token: String| {
+            axum::http::Request::builder()
+                .uri("/v1/quarantine")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // ACT: List the quarantine with the default scope and with the configured one
+        let denied = app.clone().oneshot(list(token("quarantine:read"))).await.unwrap();
+        let allowed = app.oneshot(list(token("admin"))).await.unwrap();
+
+        // ASSERT: The configured requirement replaces the default one
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+        assert_eq!(allowed.status(), StatusCode::OK);
+    }
+}
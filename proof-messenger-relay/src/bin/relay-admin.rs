@@ -0,0 +1,459 @@
+//! Relay administration tool
+//!
+//! Runs maintenance tasks against the relay database so operators do not
+//! have to edit SQLite by hand inside the container. The database is the one
+//! the relay itself would use (`relay.toml` and `DATABASE_URL`, see
+//! [`RelayConfig::load`]) unless `--database-url` names another.
+//! `verify-audit-log` checks a `secure_log` audit file and needs no database.
+//! `backup` and `restore` copy SQLite databases with a metadata file next to
+//! each snapshot; the relay does not support other databases.
+
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use proof_messenger_relay::api_keys;
+use proof_messenger_relay::audit_chain;
+use proof_messenger_relay::config::RelayConfig;
+use proof_messenger_relay::database::{Database, SnapshotMetadata};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "relay-admin", version, about = "Administer a proof-messenger relay database")]
+struct Cli {
+    /// Database to administer instead of the relay's configured database
+    #[arg(long, global = true)]
+    database_url: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Apply pending database migrations
+    Migrate {
+        /// Stop after this migration version instead of applying all
+        #[arg(long)]
+        to: Option<i64>,
+        /// Check that the migrations apply cleanly, then roll them back
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List applied and pending database migrations
+    Migrations,
+    /// Add a proof signature to the revocation list
+    RevokeProof {
+        /// Signature of the proof to revoke (hex encoded)
+        signature: String,
+        /// Why the proof is revoked
+        #[arg(long)]
+        reason: Option<String>,
+        /// Who revoked the proof
+        #[arg(long, default_value = "relay-admin")]
+        revoked_by: String,
+        /// Hours until the revocation expires (never when unset)
+        #[arg(long)]
+        ttl_hours: Option<i64>,
+    },
+    /// List message groups with their message counts
+    Groups,
+    /// Manage API keys
+    #[command(subcommand)]
+    ApiKeys(ApiKeyCommand),
+    /// Export persisted compliance audit entries as JSON lines
+    ExportAudit {
+        /// Only entries logged at or after this RFC 3339 time
+        #[arg(long)]
+        since: Option<DateTime<Utc>>,
+        /// File to write instead of standard output
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Check the hash chain of a secure_log audit file for gaps and tampering
+    VerifyAuditLog {
+        /// Audit log file written by the secure_log sink
+        path: PathBuf,
+        /// Key checkpoints must be signed with (hex encoded); derived from AUDIT_LOG_KEY when unset
+        #[arg(long)]
+        public_key: Option<String>,
+    },
+    /// Drop expired revocations and reclaim free space
+    Compact,
+    /// Write a consistent snapshot of the database and its metadata
+    Backup {
+        /// Snapshot file to create; the metadata is written to <OUTPUT>.meta.json
+        output: PathBuf,
+    },
+    /// Replace the database with a snapshot after validating it against its metadata
+    Restore {
+        /// Snapshot file written by `backup`
+        snapshot: PathBuf,
+        /// Replace a database that already holds data
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ApiKeyCommand {
+    /// List API keys without their secrets
+    List,
+    /// Replace a key's secret and print the new one
+    Rotate {
+        /// ID of the key to rotate
+        key_id: String,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("relay-admin: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    if let Command::VerifyAuditLog { path, public_key } = &cli.command {
+        return verify_audit_log(path, public_key.clone());
+    }
+    let database_url = match cli.database_url {
+        Some(url) => url,
+        None => RelayConfig::load()?.database.url,
+    };
+    if let Command::Restore { snapshot, force } = &cli.command {
+        return restore(&database_url, snapshot, *force).await;
+    }
+    if matches!(cli.command, Command::Migrate { .. }) {
+        create_database_file(&database_url)?;
+    }
+    let db = Database::new(&database_url).await?;
+
+    match cli.command {
+        Command::Migrate { to, dry_run: true } => {
+            let pending = db.migrate_dry_run(to).await?;
+            for migration in &pending {
+                println!("Would apply {} {}", migration.version, migration.description);
+            }
+            println!("{} pending migrations apply cleanly; nothing was changed", pending.len());
+        }
+        Command::Migrate { to, dry_run: false } => {
+            let applied = db.migrate_to(to).await?;
+            for migration in &applied {
+                println!("Applied {} {}", migration.version, migration.description);
+            }
+            println!("Database migrations are up to date{}", to.map(|to| format!(" through {}", to)).unwrap_or_default());
+        }
+        Command::Migrations => {
+            println!("{:>8}  {:<10} {:<26} DESCRIPTION", "VERSION", "STATE", "APPLIED AT");
+            for migration in db.migration_status().await? {
+                let applied_at = migration.applied_at.map(|at| at.to_rfc3339()).unwrap_or_default();
+                println!("{:>8}  {:<10} {:<26} {}", migration.version, migration.state.label(), applied_at, migration.description);
+            }
+        }
+        Command::RevokeProof {
+            signature,
+            reason,
+            revoked_by,
+            ttl_hours,
+        } => {
+            db.revoke_proof(&signature, reason.as_deref(), Some(&revoked_by), ttl_hours).await?;
+            println!("Revoked proof {}", signature);
+        }
+        Command::Groups => {
+            println!("{:<32} {:>10}  LAST MESSAGE", "GROUP", "MESSAGES");
+            for group in db.list_groups().await? {
+                println!("{:<32} {:>10}  {}", group.group_id, group.message_count, group.last_message_at.to_rfc3339());
+            }
+        }
+        Command::ApiKeys(ApiKeyCommand::List) => {
+            println!("{:<36}  {:<24} {:<12} {:<8} SCOPES", "ID", "NAME", "PREFIX", "STATUS");
+            for key in db.list_api_keys().await? {
+                let status = if key.revoked_at.is_some() { "revoked" } else { "active" };
+                println!("{:<36}  {:<24} {:<12} {:<8} {}", key.id, key.name, key.key_prefix, status, key.scopes);
+            }
+        }
+        Command::ApiKeys(ApiKeyCommand::Rotate { key_id }) => {
+            let (key, secret) = api_keys::rotate_key(&db, &key_id).await?;
+            eprintln!("Rotated API key {} ({}); the old secret no longer works", key.id, key.name);
+            println!("{}", secret);
+        }
+        Command::ExportAudit { since, output } => {
+            let entries = db.get_audit_entries_since(since.unwrap_or(DateTime::UNIX_EPOCH)).await?;
+            let mut out: Box<dyn Write> = match &output {
+                Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+                None => Box::new(std::io::stdout().lock()),
+            };
+            for entry in &entries {
+                serde_json::to_writer(&mut out, entry)?;
+                writeln!(out)?;
+            }
+            out.flush()?;
+            eprintln!("Exported {} audit entries", entries.len());
+        }
+        Command::VerifyAuditLog { .. } | Command::Restore { .. } => {
+            unreachable!("handled before connecting to the database")
+        }
+        Command::Compact => {
+            let report = db.compact().await?;
+            println!(
+                "Dropped {} expired revocations; database size {} -> {} bytes",
+                report.expired_revocations, report.bytes_before, report.bytes_after
+            );
+        }
+        Command::Backup { output } => {
+            sqlite_path(&database_url).ok_or("backup needs an SQLite database file")?;
+            let metadata_file = metadata_path(&output);
+            if output.exists() || metadata_file.exists() {
+                return Err(format!("{} or its metadata already exists", output.display()).into());
+            }
+            let metadata = db.snapshot(&output).await?;
+            std::fs::write(&metadata_file, serde_json::to_vec_pretty(&metadata)?)?;
+            println!(
+                "Wrote {} (schema version {}, {} rows in {} tables) and {}",
+                output.display(),
+                metadata.schema_version,
+                metadata.row_counts.values().sum::<i64>(),
+                metadata.row_counts.len(),
+                metadata_file.display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Check an audit log's hash chain, failing on the first problem found
+fn verify_audit_log(path: &std::path::Path, public_key: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let public_key = match public_key {
+        Some(key) => Some(key),
+        None => match std::env::var("AUDIT_LOG_KEY") {
+            Ok(key_hex) => {
+                let key: [u8; 32] = hex::decode(key_hex.trim())
+                    .ok()
+                    .and_then(|key| key.try_into().ok())
+                    .ok_or("AUDIT_LOG_KEY must be 64 hex characters")?;
+                Some(hex::encode(audit_chain::checkpoint_key(&key).public_key_bytes()))
+            }
+            Err(_) => {
+                eprintln!("Warning: neither --public-key nor AUDIT_LOG_KEY is set; checkpoint signers are not checked");
+                None
+            }
+        },
+    };
+    let file = std::fs::File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let report = audit_chain::verify_chain(std::io::BufReader::new(file), public_key.as_deref())
+        .map_err(|e| format!("{}: audit log chain is broken: {}", path.display(), e))?;
+
+    println!(
+        "Audit log chain intact: {} entries, {} checkpoints, head {}",
+        report.entries, report.checkpoints, report.head_hash
+    );
+    if report.legacy_entries > 0 {
+        println!("{} unchained entries precede the chain", report.legacy_entries);
+    }
+    if report.unsigned_entries > 0 {
+        println!("{} entries after the last checkpoint are not yet signed", report.unsigned_entries);
+    }
+    Ok(())
+}
+
+/// Replace the database with a snapshot once it matches its metadata
+///
+/// The snapshot is copied next to the database and renamed over it, so the
+/// database is never left half written. Stop the relay first.
+async fn restore(database_url: &str, snapshot: &Path, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let target = sqlite_path(database_url).ok_or("restore needs an SQLite database file")?;
+    let metadata_file = metadata_path(snapshot);
+    let recorded: SnapshotMetadata = serde_json::from_slice(
+        &std::fs::read(&metadata_file).map_err(|e| format!("{}: {}", metadata_file.display(), e))?,
+    )?;
+    let actual = Database::open_read_only(snapshot).await?.snapshot_metadata().await?;
+    if let Some(mismatch) = recorded.mismatch(&actual) {
+        return Err(format!("{}: snapshot does not match its metadata: {}", snapshot.display(), mismatch).into());
+    }
+    let latest = Database::latest_migration_version();
+    if actual.schema_version > latest {
+        return Err(format!(
+            "{}: schema version {} is newer than this relay's latest migration {}",
+            snapshot.display(),
+            actual.schema_version,
+            latest
+        )
+        .into());
+    }
+    if !force && std::fs::metadata(&target).map(|file| file.len() > 0).unwrap_or(false) {
+        return Err(format!("{} already holds a database; pass --force to replace it", target.display()).into());
+    }
+
+    if let Some(parent) = target.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let staged = PathBuf::from(format!("{}.restoring", target.display()));
+    std::fs::copy(snapshot, &staged)?;
+    // A journal left by the replaced database must not be applied to the snapshot
+    for suffix in ["-wal", "-shm"] {
+        match std::fs::remove_file(format!("{}{}", target.display(), suffix)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    std::fs::rename(&staged, &target)?;
+
+    let restored = Database::open_read_only(&target).await?.snapshot_metadata().await?;
+    if let Some(mismatch) = recorded.mismatch(&restored) {
+        return Err(format!("{}: restored database does not match the snapshot: {}", target.display(), mismatch).into());
+    }
+    println!(
+        "Restored {} from {} (schema version {}, {} rows in {} tables, taken {})",
+        target.display(),
+        snapshot.display(),
+        restored.schema_version,
+        restored.row_counts.values().sum::<i64>(),
+        restored.row_counts.len(),
+        recorded.created_at.to_rfc3339()
+    );
+    if restored.schema_version < latest {
+        println!("Run `relay-admin migrate` to bring the schema up to version {}", latest);
+    }
+    Ok(())
+}
+
+/// Metadata file written next to a snapshot
+fn metadata_path(snapshot: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.meta.json", snapshot.display()))
+}
+
+/// File of an SQLite database URL, unless it is in memory
+fn sqlite_path(database_url: &str) -> Option<PathBuf> {
+    let path = database_url
+        .strip_prefix("sqlite://")
+        .or_else(|| database_url.strip_prefix("sqlite:"))
+        .filter(|path| !path.starts_with(":memory:"))?;
+    Some(PathBuf::from(path.split('?').next().unwrap_or(path)))
+}
+
+/// Create an SQLite database file and its directory if they do not exist yet
+fn create_database_file(database_url: &str) -> std::io::Result<()> {
+    let Some(path) = sqlite_path(database_url) else {
+        return Ok(());
+    };
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    if !path.exists() {
+        std::fs::File::create(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[tokio::test]
+    async fn test_admin_commands_run_against_a_database() {
+        // ARRANGE: An empty database file
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db").join("relay.db");
+        let database_url = format!("sqlite:{}", path.display());
+        let cli = |args: &[&str]| {
+            Cli::parse_from(["relay-admin", "--database-url", &database_url].iter().chain(args))
+        };
+
+        // ACT: Check and apply its migrations, revoke a proof and compact it
+        run(cli(&["migrate", "--dry-run"])).await.unwrap();
+        run(cli(&["migrate"])).await.unwrap();
+        run(cli(&["migrations"])).await.unwrap();
+        run(cli(&["revoke-proof", "abcd", "--reason", "leaked"])).await.unwrap();
+        run(cli(&["compact"])).await.unwrap();
+
+        // ASSERT: The schema exists and the revocation was recorded
+        let db = Database::new(&database_url).await.unwrap();
+        assert!(db.is_proof_revoked("abcd").await.unwrap());
+        assert!(db.list_groups().await.unwrap().is_empty());
+        assert!(run(cli(&["api-keys", "rotate", "missing"])).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_backup_is_validated_before_it_is_restored() {
+        // ARRANGE: A database with a revoked proof, backed up
+        let dir = tempfile::tempdir().unwrap();
+        let source_url = format!("sqlite:{}", dir.path().join("source.db").display());
+        let target_url = format!("sqlite:{}", dir.path().join("restored").join("relay.db").display());
+        let snapshot = dir.path().join("relay.snapshot");
+        let snapshot_arg = snapshot.to_str().unwrap();
+        let cli = |url: &str, args: &[&str]| Cli::parse_from(["relay-admin", "--database-url", url].iter().chain(args));
+        run(cli(&source_url, &["migrate"])).await.unwrap();
+        run(cli(&source_url, &["revoke-proof", "abcd"])).await.unwrap();
+        run(cli(&source_url, &["backup", snapshot_arg])).await.unwrap();
+
+        // ACT: Restore it into an empty location
+        run(cli(&target_url, &["restore", snapshot_arg])).await.unwrap();
+
+        // ASSERT: The revocation list came along, and the metadata records it
+        let restored = Database::new(&target_url).await.unwrap();
+        assert!(restored.is_proof_revoked("abcd").await.unwrap());
+        let mut metadata: SnapshotMetadata = serde_json::from_slice(&std::fs::read(metadata_path(&snapshot)).unwrap()).unwrap();
+        assert_eq!(metadata.row_counts["revoked_proofs"], 1);
+        assert_eq!(metadata.schema_version, Database::latest_migration_version());
+        assert!(run(cli(&source_url, &["backup", snapshot_arg])).await.is_err());
+
+        // ASSERT: An existing database is only replaced with --force
+        let error = run(cli(&target_url, &["restore", snapshot_arg])).await.unwrap_err();
+        assert!(error.to_string().contains("pass --force"));
+        run(cli(&target_url, &["restore", snapshot_arg, "--force"])).await.unwrap();
+
+        // ASSERT: A snapshot that no longer matches its metadata is refused
+        metadata.row_counts.insert("revoked_proofs".to_string(), 2);
+        std::fs::write(metadata_path(&snapshot), serde_json::to_vec(&metadata).unwrap()).unwrap();
+        let error = run(cli(&target_url, &["restore", snapshot_arg, "--force"])).await.unwrap_err();
+        assert!(error.to_string().contains("revoked_proofs has 1 rows but 2 were recorded"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_audit_log_detects_a_removed_line() {
+        // ARRANGE: A chained audit log of three entries
+        let key = [3u8; 32];
+        let logger = proof_messenger_relay::secure_logger::SecureLogger::new(&key);
+        let mut chain = audit_chain::AuditChain::new(audit_chain::checkpoint_key(&key), 2);
+        let mut lines = Vec::new();
+        for i in 0..3 {
+            let entry = logger
+                .log_security_event(
+                    proof_messenger_relay::secure_logger::LogLevel::Audit,
+                    format!("entry {}", i),
+                    None,
+                    None,
+                    Default::default(),
+                )
+                .unwrap();
+            lines.extend(chain.append(entry).iter().map(|record| serde_json::to_string(record).unwrap()));
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let intact = dir.path().join("intact.log");
+        let truncated = dir.path().join("truncated.log");
+        std::fs::write(&intact, lines.join("\n")).unwrap();
+        lines.remove(0);
+        std::fs::write(&truncated, lines.join("\n")).unwrap();
+        let verify = |path: &std::path::Path| {
+            Cli::parse_from(["relay-admin", "verify-audit-log", path.to_str().unwrap(), "--public-key", &chain.public_key_hex()])
+        };
+
+        // ACT & ASSERT: Only the intact log verifies
+        run(verify(&intact)).await.unwrap();
+        let error = run(verify(&truncated)).await.unwrap_err();
+        assert!(error.to_string().contains("expected entry 0 but found entry 1"));
+    }
+}
@@ -0,0 +1,346 @@
+//! Verification Pipeline Module
+//!
+//! Every relayed, scheduled or federated message passes through a
+//! [`VerificationPipeline`]: an ordered list of [`MessageCheck`]s, each of
+//! which can reject the message. The relay ships four checks:
+//!
+//! - `format`: the sender, context, proof and recipient are well-formed hex
+//! - `expiry`: `expires_at`, when set, is in the future
+//! - `revocation`: the proof is not on the revocation list, when revocation
+//!   checks are enabled
+//! - `proof`: the signature or proof envelope verifies under the hybrid policy;
+//!   under the strict policy, the post-quantum key must also be the first one
+//!   the sender key presented to this relay
+//!
+//! `verification.checks` (see [`crate::config::VerificationConfig`]) names the
+//! checks to run and their order; `proof` is always required. Deployments that
+//! need their own validation, such as sanctions screening, implement
+//! [`MessageCheck`], add it to a pipeline with [`VerificationPipeline::insert_before`]
+//! and [`install`] it before [`crate::config::RelayConfig::install`]. A check
+//! rejects a message with [`CheckError::Rejected`], or any other [`AppError`].
+//!
+//! Context policies and per-tenant limits depend on the caller and stay in
+//! the relay's request handling.
+
+use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use proof_messenger_protocol::hybrid::HybridPolicy;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::{database::Database, verification_pool, AppError, Message};
+
+/// Checks run, in order, when none are configured
+pub const STANDARD_CHECKS: [&str; 4] = ["format", "expiry", "revocation", "proof"];
+
+/// Pipeline installed for the process
+static INSTALLED: OnceCell<VerificationPipeline> = OnceCell::new();
+
+/// Errors raised by verification checks
+#[derive(Debug, Error)]
+pub enum CheckError {
+    #[error("Message rejected by the {check} check: {reason}")]
+    Rejected { check: String, reason: String },
+
+    #[error("Unknown verification check: {0}")]
+    Unknown(String),
+}
+
+impl CheckError {
+    /// Rejection of a message by the check named `check`
+    pub fn rejected(check: &str, reason: impl Into<String>) -> Self {
+        CheckError::Rejected { check: check.to_string(), reason: reason.into() }
+    }
+}
+
+/// What a check may use besides the message itself
+pub struct CheckContext<'a> {
+    /// Relay database, when the message is checked on behalf of a stored write
+    pub db: Option<&'a Arc<Database>>,
+    /// Hybrid proof policy in effect
+    pub policy: HybridPolicy,
+}
+
+/// One step of message verification
+#[async_trait]
+pub trait MessageCheck: Send + Sync {
+    /// Name of the check in `verification.checks` and in logs
+    fn name(&self) -> &str;
+
+    /// Accept the message or return why it is rejected
+    async fn check(&self, message: &Message, context: &CheckContext<'_>) -> Result<(), AppError>;
+}
+
+/// Rejects messages whose fields are not well-formed before any lookup or signature check
+pub struct FormatCheck;
+
+#[async_trait]
+impl MessageCheck for FormatCheck {
+    fn name(&self) -> &str {
+        "format"
+    }
+
+    async fn check(&self, message: &Message, _context: &CheckContext<'_>) -> Result<(), AppError> {
+        crate::parse_sender(message)?;
+        validate_hex(&message.context).map_err(|e| AppError::InvalidContext(format!("Invalid hex encoding: {}", e)))?;
+        validate_hex(&message.proof).map_err(|e| AppError::InvalidSignature(format!("Invalid hex encoding: {}", e)))?;
+        crate::inbox::validate_message(message)
+    }
+}
+
+/// Rejects messages that have already expired
+pub struct ExpiryCheck;
+
+#[async_trait]
+impl MessageCheck for ExpiryCheck {
+    fn name(&self) -> &str {
+        "expiry"
+    }
+
+    async fn check(&self, message: &Message, _context: &CheckContext<'_>) -> Result<(), AppError> {
+        crate::expiry::validate_message(message)
+    }
+}
+
+/// Rejects revoked proofs when revocation checks are enabled
+pub struct RevocationCheck;
+
+#[async_trait]
+impl MessageCheck for RevocationCheck {
+    fn name(&self) -> &str {
+        "revocation"
+    }
+
+    async fn check(&self, message: &Message, context: &CheckContext<'_>) -> Result<(), AppError> {
+        let Some(db) = context.db else {
+            return Ok(());
+        };
+        if !crate::config::revocation_check_enabled() {
+            return Ok(());
+        }
+
+        info!("Checking if proof has been revoked");
+        if db.is_proof_revoked(&message.proof).await? {
+            warn!("Proof has been revoked: {}", message.proof);
+            return Err(AppError::ProofRevoked);
+        }
+        Ok(())
+    }
+}
+
+/// Verifies the message's signature or proof envelope
+pub struct ProofCheck;
+
+#[async_trait]
+impl MessageCheck for ProofCheck {
+    fn name(&self) -> &str {
+        "proof"
+    }
+
+    async fn check(&self, message: &Message, context: &CheckContext<'_>) -> Result<(), AppError> {
+        let policy = context.policy;
+        // Verification is CPU-bound: keep it off the async runtime when a pool is installed
+        match verification_pool::installed() {
+            Some(pool) => {
+                let message = message.clone();
+                pool.run(move || crate::verify_message_proof(&message, policy)).await?
+            }
+            None => crate::verify_message_proof(message, policy)?,
+        }
+
+        // The PQC key travels with the proof: bind it to the sender key on first use
+        let (HybridPolicy::Strict, Some(db), Some(pqc_key)) = (policy, context.db, crate::pqc_public_key(message)) else {
+            return Ok(());
+        };
+        let pinned = db.pin_pqc_key_if_absent(&message.sender.to_ascii_lowercase(), &pqc_key).await?;
+        if pinned != pqc_key {
+            warn!("Post-quantum key of sender {} differs from its pinned key", message.sender);
+            return Err(AppError::InvalidPublicKey(
+                "Post-quantum key differs from the key pinned for this sender".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// An ordered list of checks every message must pass
+#[derive(Clone)]
+pub struct VerificationPipeline {
+    checks: Vec<Arc<dyn MessageCheck>>,
+}
+
+impl Default for VerificationPipeline {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl VerificationPipeline {
+    /// The relay's checks in their standard order
+    pub fn standard() -> Self {
+        Self {
+            checks: STANDARD_CHECKS.iter().filter_map(|name| builtin(name)).collect(),
+        }
+    }
+
+    /// The named built-in checks, in the given order
+    pub fn from_names(names: &[String]) -> Result<Self, CheckError> {
+        let checks = names
+            .iter()
+            .map(|name| builtin(name).ok_or_else(|| CheckError::Unknown(name.clone())))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { checks })
+    }
+
+    /// Run `check` after the others
+    pub fn with_check(mut self, check: Arc<dyn MessageCheck>) -> Self {
+        self.checks.push(check);
+        self
+    }
+
+    /// Run `check` just before the check named `before`, or last if there is none
+    pub fn insert_before(mut self, before: &str, check: Arc<dyn MessageCheck>) -> Self {
+        let index = self.checks.iter().position(|existing| existing.name() == before).unwrap_or(self.checks.len());
+        self.checks.insert(index, check);
+        self
+    }
+
+    /// Names of the checks, in the order they run
+    pub fn names(&self) -> Vec<&str> {
+        self.checks.iter().map(|check| check.name()).collect()
+    }
+
+    /// Run every check in order, stopping at the first rejection
+    pub async fn run(&self, message: &Message, context: &CheckContext<'_>) -> Result<(), AppError> {
+        for check in &self.checks {
+            if let Err(e) = check.check(message, context).await {
+                info!("Message rejected by the {} check: {}", check.name(), e);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The built-in check called `name`
+fn builtin(name: &str) -> Option<Arc<dyn MessageCheck>> {
+    match name {
+        "format" => Some(Arc::new(FormatCheck)),
+        "expiry" => Some(Arc::new(ExpiryCheck)),
+        "revocation" => Some(Arc::new(RevocationCheck)),
+        "proof" => Some(Arc::new(ProofCheck)),
+        _ => None,
+    }
+}
+
+/// Whether `name` is one of the relay's built-in checks
+pub fn is_builtin(name: &str) -> bool {
+    STANDARD_CHECKS.contains(&name)
+}
+
+/// Check that `value` is hex without decoding it
+fn validate_hex(value: &str) -> Result<(), hex::FromHexError> {
+    if !value.len().is_multiple_of(2) {
+        return Err(hex::FromHexError::OddLength);
+    }
+    match value.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        Some((index, c)) => Err(hex::FromHexError::InvalidHexCharacter { c, index }),
+        None => Ok(()),
+    }
+}
+
+/// Verify messages with `pipeline` for the rest of the process
+///
+/// Only the first installed pipeline takes effect.
+pub fn install(pipeline: VerificationPipeline) {
+    let _ = INSTALLED.set(pipeline);
+}
+
+/// The installed pipeline, or the standard one if none is installed
+pub fn current() -> &'static VerificationPipeline {
+    INSTALLED.get_or_init(VerificationPipeline::standard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Signer;
+    use proof_messenger_protocol::key::generate_keypair_with_seed;
+    use std::collections::HashSet;
+
+    /// A sanctions screen rejecting listed senders
+    struct Denylist(HashSet<String>);
+
+    #[async_trait]
+    impl MessageCheck for Denylist {
+        fn name(&self) -> &str {
+            "sanctions"
+        }
+
+        async fn check(&self, message: &Message, _context: &CheckContext<'_>) -> Result<(), AppError> {
+            if self.0.contains(&message.sender) {
+                return Err(CheckError::rejected(self.name(), "sender is on the sanctions list").into());
+            }
+            Ok(())
+        }
+    }
+
+    fn signed(seed: u64) -> Message {
+        let keypair = generate_keypair_with_seed(seed);
+        Message {
+            sender: hex::encode(keypair.public.to_bytes()),
+            context: hex::encode(b"pipeline"),
+            body: "checked".to_string(),
+            proof: hex::encode(keypair.sign(b"pipeline").to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    fn context() -> CheckContext<'static> {
+        CheckContext { db: None, policy: HybridPolicy::Transitional }
+    }
+
+    #[tokio::test]
+    async fn test_custom_checks_run_in_their_place() {
+        // ARRANGE: The standard pipeline with a sanctions screen before the proof check
+        let listed = signed(61);
+        let pipeline = VerificationPipeline::standard()
+            .insert_before("proof", Arc::new(Denylist(HashSet::from([listed.sender.clone()]))));
+
+        // ACT: Check a listed sender, an unlisted one and a listed one with a broken proof
+        let rejected = pipeline.run(&listed, &context()).await;
+        let accepted = pipeline.run(&signed(62), &context()).await;
+        let mut malformed = listed.clone();
+        malformed.proof = "not hex".to_string();
+        let malformed = pipeline.run(&malformed, &context()).await;
+
+        // ASSERT: The screen runs after the format check and before the proof check
+        assert_eq!(pipeline.names(), vec!["format", "expiry", "revocation", "sanctions", "proof"]);
+        assert!(matches!(rejected, Err(AppError::Check(CheckError::Rejected { ref check, .. })) if check == "sanctions"));
+        assert!(accepted.is_ok());
+        assert!(matches!(malformed, Err(AppError::InvalidSignature(_))));
+    }
+
+    #[tokio::test]
+    async fn test_pipelines_are_built_from_check_names() {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+
+        let pipeline = VerificationPipeline::from_names(&names(&["proof"])).unwrap();
+        let mut expired = signed(63);
+        expired.expires_at = Some(chrono::Utc::now() - chrono::Duration::seconds(1));
+        assert!(pipeline.run(&expired, &context()).await.is_ok());
+        assert!(VerificationPipeline::standard().run(&expired, &context()).await.is_err());
+
+        assert!(matches!(
+            VerificationPipeline::from_names(&names(&["format", "sanctions"])),
+            Err(CheckError::Unknown(name)) if name == "sanctions"
+        ));
+        assert_eq!(validate_hex("0aF9"), Ok(()));
+        assert_eq!(validate_hex("0g"), Err(hex::FromHexError::InvalidHexCharacter { c: 'g', index: 1 }));
+    }
+}
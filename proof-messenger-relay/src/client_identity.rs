@@ -0,0 +1,290 @@
+//! Client Certificate Identity Module
+//!
+//! In service mesh deployments workloads already authenticate each other
+//! with mutual TLS, so requiring a bearer token on top is redundant. With
+//! `[client_identity]` enabled in the relay settings (see
+//! [`crate::config::ClientIdentityConfig`]), a request without an
+//! `Authorization` header is authenticated by its client certificate:
+//!
+//! - when the relay terminates TLS with a client CA, from the verified
+//!   certificate of the connection (see [`crate::tls::ClientCertificate`])
+//! - behind a mesh sidecar, from the `X-Forwarded-Client-Cert` header, in
+//!   Envoy's `Key=Value;...` format, when `trust_forwarded_header` is set
+//!
+//! The certificate's identities are its URI SANs (such as SPIFFE IDs, e.g.
+//! `spiffe://mesh.example.com/ns/payments/sa/api`) followed by its DNS SANs.
+//! The first identity listed under `[client_identity.identities]` becomes the
+//! request's user and receives the configured scopes; a pattern ending in `*`
+//! matches every identity starting with the rest. Certificates with no
+//! mapped identity are not authenticated.
+//!
+//! Client identity is enabled by layering the resulting [`ClientIdentityAuth`]
+//! onto the router as an [`axum::Extension`].
+
+use axum::http::{HeaderMap, HeaderName};
+use std::collections::HashSet;
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+
+use crate::{auth_middleware::AuthContext, config::ClientIdentityConfig, tls::ClientCertificate};
+
+/// Identities named by a DER-encoded certificate: URI SANs, then DNS SANs
+pub fn certificate_identities(der: &[u8]) -> Vec<String> {
+    let Ok((_, certificate)) = X509Certificate::from_der(der) else {
+        return Vec::new();
+    };
+    let Ok(Some(san)) = certificate.subject_alternative_name() else {
+        return Vec::new();
+    };
+    let names = &san.value.general_names;
+    let uris = names.iter().filter_map(|name| match name {
+        GeneralName::URI(uri) => Some(uri.to_string()),
+        _ => None,
+    });
+    let dns_names = names.iter().filter_map(|name| match name {
+        GeneralName::DNSName(dns) => Some(dns.to_string()),
+        _ => None,
+    });
+    uris.chain(dns_names).collect()
+}
+
+/// Identities of the client in an `X-Forwarded-Client-Cert` header value
+///
+/// The header lists one element per proxy hop; the last element describes
+/// the certificate presented to the proxy next to the relay.
+pub fn forwarded_identities(header: &str) -> Vec<String> {
+    let Some(element) = split_unquoted(header, ',').pop() else {
+        return Vec::new();
+    };
+    let mut uris = Vec::new();
+    let mut dns_names = Vec::new();
+    for pair in split_unquoted(&element, ';') {
+        let Some((key, value)) = pair.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').to_string();
+        match key.trim().to_ascii_uppercase().as_str() {
+            "URI" => uris.push(value),
+            "DNS" => dns_names.push(value),
+            _ => {}
+        }
+    }
+    uris.into_iter().chain(dns_names).filter(|identity| !identity.is_empty()).collect()
+}
+
+/// Split on `separator` outside double quotes
+fn split_unquoted(value: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        parts.last_mut().unwrap().push(c);
+    }
+    parts.into_iter().filter(|part| !part.trim().is_empty()).collect()
+}
+
+/// Authenticates requests by the identity of their client certificate
+#[derive(Debug, Clone)]
+pub struct ClientIdentityAuth {
+    forwarded_header: Option<HeaderName>,
+    identities: Vec<(String, Vec<String>)>,
+}
+
+impl ClientIdentityAuth {
+    /// Client identity authentication with the configured identities
+    ///
+    /// The forwarded header name is expected to be valid.
+    pub fn new(config: &ClientIdentityConfig) -> Self {
+        let forwarded_header = config
+            .trust_forwarded_header
+            .then(|| HeaderName::from_bytes(config.forwarded_header.as_bytes()).ok())
+            .flatten();
+        Self {
+            forwarded_header,
+            identities: config.identities.iter().map(|(pattern, scopes)| (pattern.clone(), scopes.clone())).collect(),
+        }
+    }
+
+    /// The client's identities, from the connection or the forwarded header
+    fn presented_identities(&self, certificate: Option<&ClientCertificate>, headers: &HeaderMap) -> Vec<String> {
+        if let Some(ClientCertificate::Verified(certificate)) = certificate {
+            return certificate_identities(certificate.as_ref());
+        }
+        self.forwarded_header
+            .as_ref()
+            .and_then(|header| headers.get(header))
+            .and_then(|value| value.to_str().ok())
+            .map(forwarded_identities)
+            .unwrap_or_default()
+    }
+
+    /// Scopes configured for an identity, if it is mapped
+    fn scopes_for(&self, identity: &str) -> Option<&[String]> {
+        self.identities
+            .iter()
+            .find(|(pattern, _)| match pattern.strip_suffix('*') {
+                Some(prefix) => identity.starts_with(prefix),
+                None => identity == pattern,
+            })
+            .map(|(_, scopes)| scopes.as_slice())
+    }
+
+    /// Authenticate the request's client, if it presents a mapped identity
+    pub fn authenticate(&self, certificate: Option<&ClientCertificate>, headers: &HeaderMap) -> Option<AuthContext> {
+        self.presented_identities(certificate, headers).into_iter().find_map(|identity| {
+            let scopes = self.scopes_for(&identity)?.iter().cloned().collect::<HashSet<_>>();
+            Some(AuthContext {
+                user_id: identity,
+                scopes,
+                tenant: None,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{auth_middleware::auth_middleware, jwt_validator::JwtValidator};
+    use axum::{body::Body, http::{Request, StatusCode}, middleware, routing::post, Extension, Router};
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    const SPIFFE_ID: &str = "spiffe://mesh.example.com/ns/payments/sa/api";
+
+    fn auth(trust_forwarded_header: bool) -> ClientIdentityAuth {
+        ClientIdentityAuth::new(&ClientIdentityConfig {
+            enabled: true,
+            trust_forwarded_header,
+            identities: BTreeMap::from([
+                ("spiffe://mesh.example.com/ns/payments/*".to_string(), vec!["proof:create".to_string()]),
+                ("ledger.internal".to_string(), vec!["message:read".to_string()]),
+            ]),
+            ..ClientIdentityConfig::default()
+        })
+    }
+
+    fn certificate(names: Vec<rcgen::SanType>) -> ClientCertificate {
+        let key = rcgen::KeyPair::generate().unwrap();
+        let mut params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.subject_alt_names = names;
+        ClientCertificate::Verified(params.self_signed(&key).unwrap().der().clone())
+    }
+
+    #[test]
+    fn test_verified_certificate_identity_is_mapped() {
+        // ARRANGE: A verified certificate naming a SPIFFE ID and a DNS name
+        let certificate = certificate(vec![
+            rcgen::SanType::DnsName("api.payments.internal".try_into().unwrap()),
+            rcgen::SanType::URI(SPIFFE_ID.try_into().unwrap()),
+        ]);
+
+        // ACT: Authenticate with it
+        let context = auth(false).authenticate(Some(&certificate), &HeaderMap::new()).unwrap();
+
+        // ASSERT: The SPIFFE ID is the user and carries the scopes of its pattern
+        assert_eq!(context.user_id, SPIFFE_ID);
+        assert_eq!(context.scopes, HashSet::from(["proof:create".to_string()]));
+    }
+
+    #[test]
+    fn test_forwarded_header_is_only_trusted_when_configured() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-client-cert",
+            "By=spiffe://mesh.example.com/ns/relay/sa/relay;Hash=abc,By=spiffe://mesh.example.com/ns/relay/sa/relay;Hash=def;Subject=\"CN=ledger,O=Example\";DNS=ledger.internal"
+                .parse()
+                .unwrap(),
+        );
+
+        let trusted = auth(true).authenticate(None, &headers).unwrap();
+
+        assert_eq!(trusted.user_id, "ledger.internal");
+        assert!(auth(false).authenticate(None, &headers).is_none());
+    }
+
+    #[test]
+    fn test_unmapped_identities_are_not_authenticated() {
+        let certificate = certificate(vec![rcgen::SanType::URI("spiffe://mesh.example.com/ns/other/sa/x".try_into().unwrap())]);
+
+        assert!(auth(false).authenticate(Some(&certificate), &HeaderMap::new()).is_none());
+        assert!(auth(false).authenticate(Some(&ClientCertificate::Missing), &HeaderMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_forwarded_identities_parse_envoy_format() {
+        let header = format!("Hash=abc;URI={};DNS=api.internal;DNS=\"alt;internal\"", SPIFFE_ID);
+
+        assert_eq!(forwarded_identities(&header), vec![SPIFFE_ID, "api.internal", "alt;internal"]);
+        assert!(forwarded_identities("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_client_certificate_authenticates_without_bearer_token() {
+        // ARRANGE: A route behind the auth middleware with client identities enabled
+        let validator = Arc::new(JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let app = Router::new()
+            .route("/relay", post(|auth: AuthContext| async move { auth.user_id }))
+            .layer(middleware::from_fn_with_state(validator, auth_middleware))
+            .layer(Extension(Arc::new(auth(false))));
+        let request = |certificate: ClientCertificate| {
+            let mut request = Request::builder().method("POST").uri("/relay").body(Body::empty()).unwrap();
+            request.extensions_mut().insert(certificate);
+            request
+        };
+
+        // ACT: Submit with a mapped and an unmapped certificate, neither with a token
+        let mapped = app
+            .clone()
+            .oneshot(request(certificate(vec![rcgen::SanType::URI(SPIFFE_ID.try_into().unwrap())])))
+            .await
+            .unwrap();
+        let unmapped = app
+            .oneshot(request(certificate(vec![rcgen::SanType::DnsName("unknown.internal".try_into().unwrap())])))
+            .await
+            .unwrap();
+
+        // ASSERT: Only the mapped identity is authenticated
+        assert_eq!(mapped.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(mapped.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, SPIFFE_ID);
+        assert_eq!(unmapped.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_configured_relay_accepts_forwarded_identities() {
+        // ARRANGE: The router the relay binary serves, trusting the mesh proxy's forwarded header
+        let db = Arc::new(crate::database::Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let validator = Arc::new(JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let logger = Arc::new(crate::secure_logger::SecureLogger::new(&crate::secure_logger::SecureLogger::generate_key()));
+        let mut config = crate::config::RelayConfig::default();
+        config.client_identity.enabled = true;
+        config.client_identity.trust_forwarded_header = true;
+        config.client_identity.identities.insert("ledger.internal".to_string(), vec!["message:read".to_string()]);
+        let app = crate::create_authenticated_app_with_config(db, &config, validator, logger);
+        let list = |forwarded: Option<&str>| {
+            let request = Request::builder().uri("/v1/messages/default");
+            let request = match forwarded {
+                Some(forwarded) => request.header("x-forwarded-client-cert", forwarded),
+                None => request,
+            };
+            request.body(Body::empty()).unwrap()
+        };
+
+        // ACT: List messages as the ledger workload and anonymously
+        let ledger = app.clone().oneshot(list(Some("Hash=abc;DNS=ledger.internal"))).await.unwrap();
+        let anonymous = app.oneshot(list(None)).await.unwrap();
+
+        // ASSERT: The forwarded identity authenticates with its mapped scopes
+        assert_eq!(ledger.status(), StatusCode::OK);
+        assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,755 @@
+//! Compliance Audit Trail Module
+//!
+//! The context policy records its violations and PII detections in the
+//! protocol crate's [`ComplianceAuditLogger`]. On its own that logger only
+//! keeps entries in memory; with an `[audit]` sink configured (see
+//! [`crate::config::AuditConfig`]) the relay forwards them in batches to one
+//! of:
+//!
+//! - `secure_log`: each entry is encrypted by a [`SecureLogger`] keyed with
+//!   `AUDIT_LOG_KEY` (64 hex characters) and appended to a file as a JSON
+//!   [`EncryptedLogEntry`] per line, hash-chained to the previous entry (see
+//!   [`crate::audit_chain`])
+//! - `syslog`: RFC 5424 messages sent over UDP to a collector
+//! - `http`: JSON arrays of entries posted to a collector by a background
+//!   worker
+//! - `database`: stored in the relay's `compliance_audit_entries` table by a
+//!   background worker
+//!
+//! Buffered entries are forwarded when the relay shuts down via
+//! [`ComplianceAudit::shutdown`].
+//!
+//! Entries persisted by the `database` sink are summarized for dashboards by
+//! `GET /admin/compliance/summary` (scope `audit:read` on OAuth-protected
+//! relays): per time window, the entry counts by event type, risk level and
+//! compliance status, the policy violations, the PII detections by risk level
+//! and the resulting compliance score. `GET /admin/compliance/integrity`
+//! checks the `secure_log` sink's hash chain.
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Extension, Router,
+};
+use chrono::{DateTime, Utc};
+use proof_messenger_protocol::compliance::{
+    AuditEventType, AuditLogEntry, AuditSink, AuditSinkError, ComplianceAuditLogger, ComplianceSummary, SyslogSink,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    audit_chain::{self, AuditChain, ChainError, ChainReport},
+    auth_middleware::AuthContext,
+    config::{AuditConfig, AuditSinkKind},
+    database::{AuditEntryCount, Database},
+    egress::HttpClient,
+    secure_logger::{EncryptedLogEntry, LogEntry, LogLevel, SecureLogger},
+    AppError,
+};
+
+/// Attempts to deliver a batch to the HTTP collector before it is dropped
+const HTTP_DELIVERY_ATTEMPTS: u32 = 3;
+
+/// Forwards audit entries encrypted by a [`SecureLogger`], one chained JSON line each
+pub struct SecureLoggerSink<W: Write + Send> {
+    logger: Arc<SecureLogger>,
+    chain: AuditChain,
+    writer: W,
+}
+
+impl<W: Write + Send> SecureLoggerSink<W> {
+    /// Encrypt entries with `logger`, link them into `chain` and append them to `writer`
+    pub fn new(logger: Arc<SecureLogger>, chain: AuditChain, writer: W) -> Self {
+        Self { logger, chain, writer }
+    }
+
+    /// The underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send> AuditSink for SecureLoggerSink<W> {
+    fn write_batch(&mut self, entries: &[AuditLogEntry]) -> Result<(), AuditSinkError> {
+        for entry in entries {
+            let encrypted: EncryptedLogEntry = self
+                .logger
+                .encrypt_log_entry(&log_entry(entry))
+                .map_err(|e| AuditSinkError::Rejected(e.to_string()))?;
+            for record in self.chain.append(encrypted) {
+                serde_json::to_writer(&mut self.writer, &record)?;
+                self.writer.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), AuditSinkError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// A compliance audit entry as a secure log entry
+pub fn log_entry(entry: &AuditLogEntry) -> LogEntry {
+    let level = match entry.risk_level.as_str() {
+        "CRITICAL" => LogLevel::Critical,
+        "ERROR" => LogLevel::Error,
+        "WARNING" | "HIGH" => LogLevel::Warning,
+        _ => LogLevel::Audit,
+    };
+    let mut metadata: HashMap<String, String> = entry
+        .event_details
+        .iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(text) => (key.clone(), text.clone()),
+            other => (key.clone(), other.to_string()),
+        })
+        .collect();
+    metadata.insert("context_type".to_string(), entry.context_type.clone());
+    metadata.insert("risk_level".to_string(), entry.risk_level.clone());
+    metadata.insert("compliance_status".to_string(), entry.compliance_status.clone());
+    LogEntry {
+        timestamp: entry.timestamp,
+        level,
+        message: format!("Compliance audit: {:?}", entry.event_type),
+        user_id: entry.user_id.clone(),
+        request_id: entry.session_id.clone(),
+        metadata,
+    }
+}
+
+/// Queues batches of audit entries for a background task to deliver
+///
+/// Used by sinks whose delivery is asynchronous, such as HTTP and the database.
+pub struct QueuedAuditSink {
+    batches: mpsc::UnboundedSender<Vec<AuditLogEntry>>,
+}
+
+impl QueuedAuditSink {
+    /// Start a worker passing each batch to `deliver`
+    ///
+    /// The worker exits once the sink is dropped and every queued batch is delivered.
+    pub fn spawn<F, Fut>(mut deliver: F) -> (Self, tokio::task::JoinHandle<()>)
+    where
+        F: FnMut(Vec<AuditLogEntry>) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = ()> + Send,
+    {
+        let (batches, mut queue) = mpsc::unbounded_channel::<Vec<AuditLogEntry>>();
+        let worker = tokio::spawn(async move {
+            while let Some(batch) = queue.recv().await {
+                deliver(batch).await;
+            }
+        });
+        (Self { batches }, worker)
+    }
+
+    /// Start a worker posting batches to the collector at `url`
+    pub fn http(url: reqwest::Url) -> (Self, tokio::task::JoinHandle<()>) {
+        let client = HttpClient::default();
+        Self::spawn(move |batch| {
+            let client = client.clone();
+            let url = url.clone();
+            async move { post_batch(&client, &url, &batch).await }
+        })
+    }
+
+    /// Start a worker storing batches in the relay database
+    pub fn database(db: Arc<Database>) -> (Self, tokio::task::JoinHandle<()>) {
+        Self::spawn(move |batch| {
+            let db = db.clone();
+            async move {
+                if let Err(e) = db.store_audit_entries(&batch).await {
+                    warn!("Failed to store {} audit entries: {}", batch.len(), e);
+                }
+            }
+        })
+    }
+}
+
+impl AuditSink for QueuedAuditSink {
+    fn write_batch(&mut self, entries: &[AuditLogEntry]) -> Result<(), AuditSinkError> {
+        self.batches
+            .send(entries.to_vec())
+            .map_err(|_| AuditSinkError::Rejected("audit worker has stopped".to_string()))
+    }
+}
+
+/// Post a batch, retrying with backoff before giving up on it
+async fn post_batch(client: &HttpClient, url: &reqwest::Url, batch: &[AuditLogEntry]) {
+    for attempt in 1..=HTTP_DELIVERY_ATTEMPTS {
+        let request = match client.post(url.as_str()) {
+            Ok(request) => request,
+            Err(e) => {
+                warn!("Dropped {} audit entries: {}", batch.len(), e);
+                return;
+            }
+        };
+        match request.json(batch).send().await {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response) => warn!("Audit collector rejected {} entries: {}", batch.len(), response.status()),
+            Err(e) => warn!("Failed to post {} audit entries: {}", batch.len(), e),
+        }
+        if attempt < HTTP_DELIVERY_ATTEMPTS {
+            tokio::time::sleep(Duration::from_millis(500 * 2u64.pow(attempt - 1))).await;
+        }
+    }
+    warn!("Dropped {} audit entries after {} attempts", batch.len(), HTTP_DELIVERY_ATTEMPTS);
+}
+
+/// A hash-chained log file and the key its checkpoints are signed with
+struct ChainedLog {
+    path: PathBuf,
+    public_key: String,
+}
+
+/// The relay's compliance audit trail, shared by every context policy
+pub struct ComplianceAudit {
+    logger: Mutex<ComplianceAuditLogger>,
+    worker: Mutex<Option<tokio::task::JoinHandle<()>>>,
+    chained_log: Option<ChainedLog>,
+}
+
+impl ComplianceAudit {
+    /// An audit trail recording into `logger`
+    pub fn new(logger: ComplianceAuditLogger) -> Self {
+        Self {
+            logger: Mutex::new(logger),
+            worker: Mutex::new(None),
+            chained_log: None,
+        }
+    }
+
+    /// The audit trail configured in the relay settings, if a sink is set
+    ///
+    /// `key_hex` is the `AUDIT_LOG_KEY` used by the `secure_log` sink, and
+    /// `db` stores the entries of the `database` sink.
+    pub fn from_config(config: &AuditConfig, key_hex: Option<&str>, db: &Arc<Database>) -> Result<Option<Self>, String> {
+        let Some(kind) = config.sink else {
+            return Ok(None);
+        };
+        let audit = match kind {
+            AuditSinkKind::SecureLog => {
+                let key: [u8; 32] = hex::decode(key_hex.ok_or("AUDIT_LOG_KEY must be set")?.trim())
+                    .ok()
+                    .and_then(|key| key.try_into().ok())
+                    .ok_or("AUDIT_LOG_KEY must be 64 hex characters")?;
+                let path = config.path.as_ref().ok_or("audit.path must be set")?;
+                let mut chain = AuditChain::new(audit_chain::checkpoint_key(&key), config.checkpoint_interval);
+                if path.exists() {
+                    let existing = std::fs::File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                    chain = chain
+                        .resume(std::io::BufReader::new(existing))
+                        .map_err(|e| format!("{}: {}", path.display(), e))?;
+                }
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+                let public_key = chain.public_key_hex();
+                let sink = SecureLoggerSink::new(Arc::new(SecureLogger::new(&key)), chain, file);
+                Self {
+                    chained_log: Some(ChainedLog { path: path.clone(), public_key }),
+                    ..Self::new(ComplianceAuditLogger::with_sink(sink, config.batch_size))
+                }
+            }
+            AuditSinkKind::Syslog => {
+                let address = config.syslog_address.as_deref().ok_or("audit.syslog_address must be set")?;
+                let sink = SyslogSink::connect(address, "proof-messenger-relay").map_err(|e| e.to_string())?;
+                Self::new(ComplianceAuditLogger::with_sink(sink, config.batch_size))
+            }
+            AuditSinkKind::Http => {
+                let url = config.http_url.as_deref().ok_or("audit.http_url must be set")?;
+                let url = reqwest::Url::parse(url).map_err(|e| format!("audit.http_url: {}", e))?;
+                Self::queued(QueuedAuditSink::http(url), config.batch_size)
+            }
+            AuditSinkKind::Database => Self::queued(QueuedAuditSink::database(db.clone()), config.batch_size),
+        };
+        Ok(Some(audit))
+    }
+
+    /// An audit trail forwarding to a queued sink, awaiting its worker on shutdown
+    fn queued((sink, worker): (QueuedAuditSink, tokio::task::JoinHandle<()>), batch_size: usize) -> Self {
+        Self {
+            logger: Mutex::new(ComplianceAuditLogger::with_sink(sink, batch_size)),
+            worker: Mutex::new(Some(worker)),
+            chained_log: None,
+        }
+    }
+
+    /// Key the `secure_log` sink signs its checkpoints with (hex encoded)
+    pub fn checkpoint_public_key(&self) -> Option<&str> {
+        self.chained_log.as_ref().map(|log| log.public_key.as_str())
+    }
+
+    /// Check the `secure_log` sink's hash chain against this relay's checkpoint key
+    pub fn verify_chain(&self) -> Result<ChainReport, ChainError> {
+        let log = self.chained_log.as_ref().ok_or(ChainError::Disabled)?;
+        let file = std::fs::File::open(&log.path)?;
+        audit_chain::verify_chain(std::io::BufReader::new(file), Some(&log.public_key))
+    }
+
+    /// Record entries in the audit trail
+    pub fn record(&self, log: impl FnOnce(&mut ComplianceAuditLogger)) {
+        log(&mut self.logger.lock().unwrap());
+    }
+
+    /// Forward buffered entries, waiting for a queued sink's worker to deliver them
+    ///
+    /// Later entries are kept in memory.
+    pub async fn shutdown(&self) {
+        let sink = self.logger.lock().unwrap().take_sink();
+        drop(sink);
+        let worker = self.worker.lock().unwrap().take();
+        if let Some(worker) = worker {
+            if let Err(e) = worker.await {
+                warn!("Audit worker failed: {}", e);
+            }
+        }
+    }
+}
+
+/// Windows reported when the query names none
+const DEFAULT_SUMMARY_WINDOWS: &str = "1h,24h,7d,30d";
+
+/// Query parameters for the compliance summary
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SummaryQuery {
+    /// Comma-separated windows such as `24h` or `7d`
+    pub windows: Option<String>,
+}
+
+impl SummaryQuery {
+    /// The requested windows with their lengths
+    fn windows(&self) -> Result<Vec<(String, chrono::Duration)>, AppError> {
+        self.windows
+            .as_deref()
+            .unwrap_or(DEFAULT_SUMMARY_WINDOWS)
+            .split(',')
+            .map(str::trim)
+            .map(|window| parse_window(window).map(|length| (window.to_string(), length)))
+            .collect()
+    }
+}
+
+/// Parse a window of hours (`24h`) or days (`7d`)
+fn parse_window(window: &str) -> Result<chrono::Duration, AppError> {
+    let invalid = || AppError::InvalidQuery(format!("'{}' is not a window such as 24h or 7d", window));
+    let (count, unit) = window.split_at(window.len().saturating_sub(1));
+    let count: i64 = count.parse().ok().filter(|count| *count > 0).ok_or_else(invalid)?;
+    match unit {
+        "h" => Ok(chrono::Duration::hours(count)),
+        "d" => Ok(chrono::Duration::days(count)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Fold persisted entry counts into a compliance summary
+pub fn summarize(counts: &[AuditEntryCount]) -> ComplianceSummary {
+    let mut summary = ComplianceSummary {
+        total_entries: 0,
+        event_counts: HashMap::new(),
+        risk_level_counts: HashMap::new(),
+        compliance_status_counts: HashMap::new(),
+        generated_at: Utc::now(),
+    };
+    for entry in counts {
+        let count = entry.count as usize;
+        summary.total_entries += count;
+        if let Ok(event_type) = serde_json::from_value::<AuditEventType>(entry.event_type.clone().into()) {
+            *summary.event_counts.entry(event_type).or_default() += count;
+        }
+        *summary.risk_level_counts.entry(entry.risk_level.clone()).or_default() += count;
+        *summary.compliance_status_counts.entry(entry.compliance_status.clone()).or_default() += count;
+    }
+    summary
+}
+
+/// The summary of the audit entries logged in each requested window
+async fn summary_report(db: &Database, params: &SummaryQuery) -> Result<serde_json::Value, AppError> {
+    let now = Utc::now();
+    let mut windows = Vec::new();
+    for (window, length) in params.windows()? {
+        let since: DateTime<Utc> = now - length;
+        let counts = db.count_audit_entries_since(since).await?;
+        let summary = summarize(&counts);
+
+        let mut pii_detections_by_risk_level: HashMap<&str, i64> = HashMap::new();
+        for entry in counts.iter().filter(|entry| entry.event_type == "PIIDetection") {
+            *pii_detections_by_risk_level.entry(entry.risk_level.as_str()).or_default() += entry.count;
+        }
+
+        windows.push(serde_json::json!({
+            "window": window,
+            "since": since,
+            "total_entries": summary.total_entries,
+            "policy_violations": summary.event_counts.get(&AuditEventType::PolicyViolation).copied().unwrap_or_default(),
+            "pii_detections_by_risk_level": pii_detections_by_risk_level,
+            "compliance_score": summary.get_compliance_score(),
+            "has_critical_issues": summary.has_critical_issues(),
+            "event_counts": summary.event_counts,
+            "risk_level_counts": summary.risk_level_counts,
+            "compliance_status_counts": summary.compliance_status_counts,
+        }));
+    }
+
+    Ok(serde_json::json!({
+        "status": "success",
+        "generated_at": now,
+        "windows": windows
+    }))
+}
+
+/// Create router for compliance reporting endpoints
+pub fn compliance_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/summary", get(compliance_summary_handler))
+        .route("/integrity", get(integrity_handler))
+}
+
+/// Create router for authenticated compliance reporting endpoints
+pub fn authenticated_compliance_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)> {
+    Router::new()
+        .route("/summary", get(authenticated_compliance_summary_handler))
+        .route("/integrity", get(authenticated_integrity_handler))
+}
+
+/// Handler to summarize the persisted compliance audit entries
+#[utoipa::path(
+    get,
+    path = "/admin/compliance/summary",
+    operation_id = "getComplianceSummary",
+    tag = "compliance",
+    params(SummaryQuery),
+    responses(
+        (status = 200, description = "Compliance counters for each window", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn compliance_summary_handler(
+    State(db): State<Arc<Database>>,
+    Query(params): Query<SummaryQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Summarizing compliance audit entries");
+
+    let report = summary_report(&db, &params).await?;
+
+    Ok((StatusCode::OK, Json(report)))
+}
+
+/// Authenticated handler to summarize the persisted compliance audit entries
+#[instrument(skip_all)]
+async fn authenticated_compliance_summary_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    Query(params): Query<SummaryQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} summarizing compliance audit entries", auth.user_id);
+
+    let mut report = summary_report(&db, &params).await?;
+    report["authenticated_user"] = auth.user_id.into();
+
+    Ok((StatusCode::OK, Json(report)))
+}
+
+/// Check the audit log's hash chain, reporting a broken chain rather than failing
+fn integrity_report(audit: Option<Extension<Arc<ComplianceAudit>>>) -> Result<serde_json::Value, AppError> {
+    let Extension(audit) = audit.ok_or(ChainError::Disabled)?;
+    let public_key = audit.checkpoint_public_key();
+    match audit.verify_chain() {
+        Ok(report) => Ok(serde_json::json!({
+            "status": "success",
+            "valid": true,
+            "checkpoint_public_key": public_key,
+            "report": report
+        })),
+        Err(e @ (ChainError::Disabled | ChainError::Io(_))) => Err(e.into()),
+        Err(problem) => {
+            warn!("Audit log chain is broken: {}", problem);
+            Ok(serde_json::json!({
+                "status": "success",
+                "valid": false,
+                "checkpoint_public_key": public_key,
+                "problem": problem.to_string()
+            }))
+        }
+    }
+}
+
+/// Handler to check the audit log's hash chain
+#[utoipa::path(
+    get,
+    path = "/admin/compliance/integrity",
+    operation_id = "verifyAuditLogIntegrity",
+    tag = "compliance",
+    responses(
+        (status = 200, description = "Whether the audit log's hash chain is intact", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn integrity_handler(audit: Option<Extension<Arc<ComplianceAudit>>>) -> Result<impl IntoResponse, AppError> {
+    info!("Verifying the audit log hash chain");
+
+    let report = integrity_report(audit)?;
+
+    Ok((StatusCode::OK, Json(report)))
+}
+
+/// Authenticated handler to check the audit log's hash chain
+#[instrument(skip_all)]
+async fn authenticated_integrity_handler(
+    auth: AuthContext,
+    audit: Option<Extension<Arc<ComplianceAudit>>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} verifying the audit log hash chain", auth.user_id);
+
+    let mut report = integrity_report(audit)?;
+    report["authenticated_user"] = auth.user_id.into();
+
+    Ok((StatusCode::OK, Json(report)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::routing::post;
+    use proof_messenger_protocol::compliance::PIIType;
+    use std::collections::HashSet;
+    use tower::ServiceExt;
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    #[test]
+    fn test_secure_log_entries_are_encrypted() {
+        // ARRANGE: A secure log sink and an audit entry naming a PII type
+        let key = SecureLogger::generate_key();
+        let chain = AuditChain::new(audit_chain::checkpoint_key(&key), 100);
+        let mut sink = SecureLoggerSink::new(Arc::new(SecureLogger::new(&key)), chain, Vec::new());
+        let mut logger = ComplianceAuditLogger::new();
+        logger.log_pii_detection("login", "notes", &[PIIType::SocialSecurityNumber]);
+
+        // ACT: Forward it
+        sink.write_batch(logger.get_entries()).unwrap();
+
+        // ASSERT: The stored line is ciphertext that decrypts to the entry
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert!(!output.contains("notes"));
+        let record: audit_chain::ChainRecord = serde_json::from_str(output.trim()).unwrap();
+        let audit_chain::ChainRecord::Entry(chained) = record else {
+            panic!("expected a chained entry");
+        };
+        let entry = SecureLogger::new(&key).decrypt_log_entry(&chained.entry).unwrap();
+        assert_eq!(entry.level, LogLevel::Critical);
+        assert_eq!(entry.message, "Compliance audit: PIIDetection");
+        assert_eq!(entry.metadata["field_name"], "notes");
+        assert_eq!(entry.metadata["context_type"], "login");
+    }
+
+    #[tokio::test]
+    async fn test_http_sink_delivers_on_shutdown() {
+        // ARRANGE: A collector and an audit trail batching 10 entries
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let collector = Router::new().route(
+            "/ingest",
+            post({
+                let received = received.clone();
+                move |Json(batch): Json<Vec<AuditLogEntry>>| async move {
+                    received.lock().unwrap().extend(batch);
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/ingest", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, collector).await.unwrap() });
+        let config = AuditConfig {
+            sink: Some(AuditSinkKind::Http),
+            http_url: Some(url),
+            batch_size: 10,
+            ..AuditConfig::default()
+        };
+        let audit = ComplianceAudit::from_config(&config, None, &setup_db().await).unwrap().unwrap();
+
+        // ACT: Record fewer entries than a batch, then shut down
+        audit.record(|log| {
+            log.log_policy_violation("login", "password", "forbidden_field");
+            log.log_policy_violation("login", "user_id", "missing_required_field");
+        });
+        audit.shutdown().await;
+
+        // ASSERT: The partial batch reached the collector
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[1].event_details["field_name"], "user_id");
+    }
+
+    #[tokio::test]
+    async fn test_secure_log_requires_a_valid_key() {
+        let db = setup_db().await;
+        let config = AuditConfig {
+            sink: Some(AuditSinkKind::SecureLog),
+            path: Some(std::env::temp_dir().join("relay-audit-test.log")),
+            ..AuditConfig::default()
+        };
+
+        assert!(ComplianceAudit::from_config(&config, None, &db).is_err());
+        assert!(ComplianceAudit::from_config(&config, Some("abcd"), &db).is_err());
+        assert!(ComplianceAudit::from_config(&AuditConfig::default(), None, &db).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_secure_log_chain_survives_restarts_and_reports_tampering() {
+        // ARRANGE: A secure log written across two relay runs, checkpointed every 2 entries
+        let db = setup_db().await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("compliance-audit.log");
+        let config = AuditConfig {
+            sink: Some(AuditSinkKind::SecureLog),
+            path: Some(path.clone()),
+            batch_size: 1,
+            checkpoint_interval: 2,
+            ..AuditConfig::default()
+        };
+        let key = hex::encode([5u8; 32]);
+        for run in ["first", "second"] {
+            let audit = ComplianceAudit::from_config(&config, Some(&key), &db).unwrap().unwrap();
+            audit.record(|log| {
+                log.log_policy_violation(run, "password", "forbidden_field");
+                log.log_policy_violation(run, "ssn", "forbidden_field");
+                log.log_policy_violation(run, "user_id", "missing_required_field");
+            });
+            audit.shutdown().await;
+        }
+        let audit = Arc::new(ComplianceAudit::from_config(&config, Some(&key), &db).unwrap().unwrap());
+        let app = crate::create_app(db).layer(Extension(audit.clone()));
+        let check = || async {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri("/admin/compliance/integrity").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        // ACT: Check the chain, then delete an entry from the file and check again
+        let intact = check().await;
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let mut lines: Vec<&str> = contents.lines().collect();
+        lines.remove(0);
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        let tampered = check().await;
+
+        // ASSERT: The second run continued the chain, and the deletion is reported
+        assert_eq!(intact["valid"], true);
+        assert_eq!(intact["report"]["entries"], 6);
+        assert_eq!(intact["report"]["checkpoints"], 3);
+        assert_eq!(intact["checkpoint_public_key"], audit.checkpoint_public_key().unwrap());
+        assert_eq!(tampered["valid"], false);
+        assert_eq!(tampered["problem"], "line 1: expected entry 0 but found entry 1");
+    }
+
+    #[tokio::test]
+    async fn test_integrity_check_requires_the_secure_log_sink() {
+        let response = crate::create_app(setup_db().await)
+            .oneshot(Request::builder().uri("/admin/compliance/integrity").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_database_entries_are_summarized_per_window() {
+        // ARRANGE: A database sink holding a recent violation and PII detection, and an older violation
+        let db = setup_db().await;
+        let config = AuditConfig {
+            sink: Some(AuditSinkKind::Database),
+            ..AuditConfig::default()
+        };
+        let audit = ComplianceAudit::from_config(&config, None, &db).unwrap().unwrap();
+        audit.record(|log| {
+            log.log_policy_violation("login", "password", "forbidden_field");
+            log.log_pii_detection("login", "notes", &[PIIType::SocialSecurityNumber]);
+            log.log_sanitization_success("login", &serde_json::json!({}));
+        });
+        audit.shutdown().await;
+        let mut old = ComplianceAuditLogger::new();
+        old.log_policy_violation("login", "ssn", "forbidden_field");
+        let mut entries = old.get_entries().to_vec();
+        entries[0].timestamp = Utc::now() - chrono::Duration::days(3);
+        db.store_audit_entries(&entries).await.unwrap();
+
+        // ACT: Summarize the last day and week
+        let response = crate::create_app(db)
+            .oneshot(Request::builder().uri("/admin/compliance/summary?windows=24h,7d").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        // ASSERT: Each window counts only the entries logged within it
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let (day, week) = (&json["windows"][0], &json["windows"][1]);
+        assert_eq!(day["window"], "24h");
+        assert_eq!(day["total_entries"], 3);
+        assert_eq!(day["policy_violations"], 1);
+        assert_eq!(day["pii_detections_by_risk_level"]["CRITICAL"], 1);
+        assert_eq!(day["has_critical_issues"], true);
+        assert_eq!(week["total_entries"], 4);
+        assert_eq!(week["policy_violations"], 2);
+        assert_eq!(week["compliance_score"], 25.0);
+    }
+
+    #[tokio::test]
+    async fn test_summary_rejects_invalid_windows() {
+        let app = crate::create_app(setup_db().await);
+
+        for windows in ["0h", "7w", "d", "24h,"] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("/admin/compliance/summary?windows={}", windows))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", windows);
+        }
+    }
+
+    #[test]
+    fn test_summary_requires_audit_read_scope() {
+        let auth = |scope: &str| AuthContext {
+            user_id: "auditor".to_string(),
+            scopes: HashSet::from([scope.to_string()]),
+            tenant: None,
+        };
+        let policy = crate::authorization::ScopePolicy::default();
+        let route = "/admin/compliance/summary";
+
+        let denied = policy.check(&auth("message:read"), &axum::http::Method::GET, route);
+
+        assert!(matches!(denied, Err(AppError::ScopeDenied(ref d)) if d.missing_scopes == ["audit:read"]));
+        assert!(policy.check(&auth("audit:read"), &axum::http::Method::GET, route).is_ok());
+    }
+}
@@ -0,0 +1,3138 @@
+//! Relay Configuration Module
+//!
+//! This module gathers the relay's settings into a single [`RelayConfig`].
+//! Settings are read from a TOML file (the path in `RELAY_CONFIG`, or
+//! `relay.toml` in the working directory when present), then overridden by
+//! the environment variables the relay has always honoured, so existing
+//! deployments keep working without a file. The result is validated at
+//! startup and every problem found is reported at once.
+//!
+//! ```toml
+//! [server]
+//! bind_address = "0.0.0.0:8080"
+//! grpc_bind_address = "0.0.0.0:50051"
+//!
+//! [tls]
+//! cert_path = "/etc/relay/cert.pem"
+//! key_path = "/etc/relay/key.pem"
+//! client_ca_path = "/etc/relay/peers-ca.pem"
+//!
+//! [database]
+//! url = "sqlite:/app/db/messages.db"
+//!
+//! [rate_limit]
+//! per_second = 2
+//! burst_size = 5
+//!
+//! [cors]
+//! allowed_origins = ["https://app.example.com"]
+//!
+//! [retention]
+//! quarantine_days = 30
+//! replay_window_secs = 86400
+//! expiry_purge_interval_secs = 60
+//!
+//! [redis]
+//! url = "redis://redis:6379/0"
+//!
+//! [logging]
+//! redact_pii = true
+//! logged_headers = ["user-agent"]
+//!
+//! [audit]
+//! sink = "secure_log"
+//! path = "/var/log/relay/compliance-audit.log"
+//! batch_size = 50
+//! checkpoint_interval = 100
+//!
+//! [[oauth.issuers]]
+//! issuer = "https://auth.example.com/"
+//! audience = "proof-messenger-api"
+//! jwks_url = "https://auth.example.com/.well-known/jwks.json"
+//!
+//! [event_stream]
+//! sink = "kafka"
+//! servers = ["kafka-1:9092", "kafka-2:9092"]
+//! topic = "proof-messenger.messages.verified"
+//!
+//! [subscriptions]
+//! backplane = "redis"
+//!
+//! [timestamping]
+//! tsa_url = "https://freetsa.org/tsr"
+//!
+//! [api]
+//! legacy_sunset = "2027-06-30T00:00:00Z"
+//!
+//! [maintenance]
+//! read_only = false
+//! retry_after_secs = 300
+//!
+//! [abuse_detection]
+//! enabled = true
+//! window_secs = 60
+//! max_verification_failures = 10
+//! quarantine_secs = 3600
+//!
+//! [integrity]
+//! enabled = true
+//! interval_secs = 86400
+//! batch_size = 500
+//!
+//! [key_auth]
+//! enabled = true
+//! session_ttl_secs = 900
+//!
+//! [key_auth.keys]
+//! "3b6a27bcceb6a42d62a3a8d02a6f0d73653215771de243a63ac048a18b59da29" = ["proof:create"]
+//!
+//! [scheduling]
+//! enabled = true
+//! poll_interval_secs = 5
+//! max_delay_secs = 2592000
+//!
+//! [verification]
+//! checks = ["format", "expiry", "revocation", "proof"]
+//! offload = true
+//! max_concurrent = 8
+//!
+//! [[plugins]]
+//! name = "sanctions"
+//! path = "/etc/relay/plugins/sanctions.wasm"
+//! fuel = 10000000
+//! max_memory_bytes = 16777216
+//! timeout_ms = 50
+//!
+//! [egress]
+//! proxy = "http://proxy.corp.example:3128"
+//! no_proxy = ["localhost"]
+//! allowed_hosts = ["auth.example.com", "*.peers.example.com"]
+//!
+//! [siem]
+//! sink = "hec"
+//! url = "https://splunk.example.com:8088/services/collector/event"
+//! buffer_capacity = 10000
+//!
+//! [tenancy]
+//! source = "claim"
+//! claim = "tenant_id"
+//!
+//! [tenancy.tenants.acme]
+//! context_policy = "fintech_transfer"
+//! message_retention_days = 365
+//!
+//! [limits]
+//! max_request_body_bytes = 1048576
+//! max_context_bytes = 65536
+//!
+//! [readiness]
+//! check_timeout_ms = 2000
+//! max_webhook_queue = 1000
+//!
+//! [webhooks]
+//! max_attempts = 5
+//! retry_base_secs = 30
+//!
+//! [federation]
+//! relay_id = "us-east"
+//! max_hops = 3
+//!
+//! [[federation.peers]]
+//! id = "eu-west"
+//! url = "https://eu.relay.example.com"
+//! public_key = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+//!
+//! [features]
+//! revocation_check = true
+//! quarantine = false
+//! legacy_proofs = true
+//! context_policy = "transaction"
+//! replay_protection = true
+//! key_pinning = "flag"
+//! hybrid_policy = "transitional"
+//! ```
+
+use axum::http::HeaderValue;
+use once_cell::sync::OnceCell;
+use proof_messenger_protocol::hybrid::HybridPolicy;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tower_http::cors::{Any, CorsLayer};
+
+/// Configuration file read when `RELAY_CONFIG` is not set
+pub const DEFAULT_CONFIG_PATH: &str = "relay.toml";
+
+/// Whether revocation checks are enabled, once a configuration is installed
+static REVOCATION_CHECK: OnceCell<bool> = OnceCell::new();
+
+/// Whether legacy raw-signature proofs are accepted, once a configuration is installed
+static LEGACY_PROOFS: OnceCell<bool> = OnceCell::new();
+
+/// Which halves of hybrid proofs must verify, once a configuration is installed
+static HYBRID_POLICY: OnceCell<HybridPolicy> = OnceCell::new();
+
+/// Errors loading the relay configuration
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to parse config file {path}: {message}")]
+    Parse { path: PathBuf, message: String },
+    #[error("Invalid relay configuration:\n{}", .0.iter().map(|problem| format!("  - {}", problem)).collect::<Vec<_>>().join("\n"))]
+    Invalid(Vec<String>),
+}
+
+/// Complete relay configuration
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RelayConfig {
+    pub server: ServerConfig,
+    pub tls: TlsConfig,
+    pub database: DatabaseConfig,
+    pub rate_limit: RateLimitConfig,
+    pub redis: RedisConfig,
+    pub cors: CorsConfig,
+    pub retention: RetentionConfig,
+    pub logging: LoggingConfig,
+    pub audit: AuditConfig,
+    pub oauth: OAuthConfig,
+    pub authorization: AuthorizationConfig,
+    pub rbac: RbacConfig,
+    pub api_keys: ApiKeyConfig,
+    pub key_auth: KeyAuthConfig,
+    pub client_identity: ClientIdentityConfig,
+    pub tenancy: TenancyConfig,
+    pub event_stream: EventStreamConfig,
+    pub subscriptions: SubscriptionsConfig,
+    pub timestamping: TimestampingConfig,
+    pub api: ApiConfig,
+    pub maintenance: MaintenanceConfig,
+    pub abuse_detection: AbuseDetectionConfig,
+    pub integrity: IntegrityConfig,
+    pub scheduling: SchedulingConfig,
+    pub verification: VerificationConfig,
+    pub plugins: Vec<PluginConfig>,
+    pub egress: EgressConfig,
+    pub siem: SiemConfig,
+    pub limits: LimitsConfig,
+    pub readiness: ReadinessChecksConfig,
+    pub webhooks: WebhookDeliveryConfig,
+    pub federation: RelayFederationConfig,
+    pub features: FeatureToggles,
+}
+
+/// HTTP server settings
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address the relay listens on
+    pub bind_address: SocketAddr,
+    /// Address the gRPC services listen on (disabled when unset)
+    pub grpc_bind_address: Option<SocketAddr>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            grpc_bind_address: None,
+        }
+    }
+}
+
+/// TLS termination settings
+///
+/// TLS is enabled when both the certificate and key paths are set.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM file holding the certificate chain
+    pub cert_path: Option<PathBuf>,
+    /// PEM file holding the private key
+    pub key_path: Option<PathBuf>,
+    /// PEM file holding the CA that peer relays' client certificates must chain to
+    pub client_ca_path: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    /// Whether the relay terminates TLS itself
+    pub fn enabled(&self) -> bool {
+        self.cert_path.is_some() && self.key_path.is_some()
+    }
+}
+
+/// Database settings
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// sqlx connection URL
+    pub url: String,
+    /// What to do when the database was migrated by a newer relay
+    pub on_newer_schema: NewerSchemaPolicy,
+    /// Most connections kept open to each database
+    pub max_connections: u32,
+    /// Connections kept open even when idle
+    pub min_connections: u32,
+    /// How long a query waits for a free connection before failing
+    pub acquire_timeout_seconds: u64,
+    /// Longest a query may run before SQLite interrupts it (unlimited when unset)
+    pub statement_timeout_ms: Option<u64>,
+    /// Read-only replica serving message reads, e.g. a LiteFS or Litestream copy
+    pub read_replica_url: Option<String>,
+    /// Batching of message inserts
+    pub write_behind: WriteBehindConfig,
+    /// SQLite journal mode; WAL lets reads proceed during writes
+    pub journal_mode: JournalMode,
+    /// How long a connection waits for a locked database before failing
+    pub busy_timeout_ms: u64,
+    /// How often SQLite syncs to disk
+    pub synchronous: SynchronousMode,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "sqlite:/app/db/messages.db".to_string(),
+            on_newer_schema: NewerSchemaPolicy::default(),
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout_seconds: 30,
+            statement_timeout_ms: None,
+            read_replica_url: None,
+            write_behind: WriteBehindConfig::default(),
+            journal_mode: JournalMode::default(),
+            busy_timeout_ms: 5000,
+            synchronous: SynchronousMode::default(),
+        }
+    }
+}
+
+/// SQLite `journal_mode` pragma
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalMode {
+    /// Write-ahead log: readers do not block the writer
+    #[default]
+    Wal,
+    /// Rollback journal deleted after each transaction
+    Delete,
+    /// Rollback journal truncated after each transaction
+    Truncate,
+    /// Rollback journal header zeroed after each transaction
+    Persist,
+}
+
+/// SQLite `synchronous` pragma
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SynchronousMode {
+    /// Sync rarely; fast, but a power loss can corrupt the database
+    Off,
+    /// Sync at WAL checkpoints; durable except for the last commits on power loss
+    #[default]
+    Normal,
+    /// Sync on every commit
+    Full,
+    /// Sync on every commit and the directory after journal deletion
+    Extra,
+}
+
+/// Write-behind batching of message inserts
+///
+/// See [`crate::write_behind`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WriteBehindConfig {
+    /// Store messages in batches through a background writer
+    pub enabled: bool,
+    /// Most messages stored in one transaction
+    pub batch_size: usize,
+    /// How long a batch waits for more messages after its first
+    pub flush_interval_ms: u64,
+    /// Messages queued before submitters wait for room
+    pub queue_capacity: usize,
+}
+
+impl Default for WriteBehindConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            batch_size: 100,
+            flush_interval_ms: 10,
+            queue_capacity: 1000,
+        }
+    }
+}
+
+/// How the relay starts on a schema that is ahead of its migrations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NewerSchemaPolicy {
+    /// Log the schema and binary versions and exit
+    #[default]
+    Refuse,
+    /// Start without migrating, e.g. while rolling back a deployment
+    Ignore,
+}
+
+/// Rate limiting applied to the relay's API routes
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Requests replenished per second
+    pub per_second: u64,
+    /// Requests allowed in a burst
+    pub burst_size: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            per_second: 2,
+            burst_size: 5,
+        }
+    }
+}
+
+/// Redis server shared by relay replicas
+///
+/// When set, rate-limit buckets and the replay protection nonces live in
+/// Redis so they hold across every replica behind a load balancer; otherwise
+/// each relay keeps them in memory. Requires the `redis` feature.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedisConfig {
+    /// Connection URL such as `redis://redis:6379/0` (disabled when unset)
+    pub url: Option<String>,
+    /// Prefix of every key the relay writes, so relays can share a server
+    pub key_prefix: String,
+}
+
+impl Default for RedisConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            key_prefix: "proof-messenger".to_string(),
+        }
+    }
+}
+
+/// Cross-origin request settings
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Origins allowed to call the relay; empty allows any origin
+    pub allowed_origins: Vec<String>,
+}
+
+impl CorsConfig {
+    /// CORS layer enforcing the allowed origins
+    pub fn layer(&self) -> CorsLayer {
+        if self.allowed_origins.is_empty() {
+            return CorsLayer::permissive();
+        }
+        let origins: Vec<HeaderValue> = self
+            .allowed_origins
+            .iter()
+            .filter_map(|origin| HeaderValue::from_str(origin).ok())
+            .collect();
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(Any)
+            .allow_headers(Any)
+            .expose_headers([axum::http::HeaderName::from_static(crate::request_id::REQUEST_ID_HEADER)])
+    }
+}
+
+/// How long stored data is kept
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Days rejected messages are kept in quarantine
+    pub quarantine_days: i64,
+    /// Days a scheduled data subject erasure waits before it is carried out
+    pub erasure_grace_days: i64,
+    /// Seconds a relayed context is remembered to reject replays of it
+    pub replay_window_secs: u64,
+    /// Seconds between purges of messages past their `expires_at`
+    pub expiry_purge_interval_secs: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            quarantine_days: 30,
+            erasure_grace_days: 30,
+            replay_window_secs: 86_400,
+            expiry_purge_interval_secs: 60,
+        }
+    }
+}
+
+/// What request metadata the relay logs
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// Redact PII from logged paths, query strings and headers
+    pub redact_pii: bool,
+    /// Request and response headers included in request logs
+    pub logged_headers: Vec<String>,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            redact_pii: true,
+            logged_headers: Vec::new(),
+        }
+    }
+}
+
+/// Where compliance audit entries are forwarded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditSinkKind {
+    /// Encrypted with the `AUDIT_LOG_KEY` and appended to `path`
+    SecureLog,
+    /// Sent to the syslog collector at `syslog_address` over UDP
+    Syslog,
+    /// Posted in batches to `http_url`
+    Http,
+    /// Stored in the relay database, where `/admin/compliance/summary` reports on them
+    Database,
+}
+
+/// Compliance audit trail settings
+///
+/// Forwarding is enabled when `sink` is set; see [`crate::compliance_audit`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    /// Where audit entries are forwarded (disabled when unset)
+    pub sink: Option<AuditSinkKind>,
+    /// File the `secure_log` sink appends to
+    pub path: Option<PathBuf>,
+    /// Collector address of the `syslog` sink
+    pub syslog_address: Option<String>,
+    /// Endpoint of the `http` sink
+    pub http_url: Option<String>,
+    /// Entries buffered before they are forwarded
+    pub batch_size: usize,
+    /// Entries of the `secure_log` sink's hash chain between signed checkpoints
+    pub checkpoint_interval: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            sink: None,
+            path: None,
+            syslog_address: None,
+            http_url: None,
+            batch_size: 50,
+            checkpoint_interval: 100,
+        }
+    }
+}
+
+/// Streaming platform verified-message events are published to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventSinkKind {
+    /// Produced to the Kafka `topic` on the brokers in `servers` (`kafka` feature)
+    Kafka,
+    /// Published to the JetStream `topic` subject on the servers in `servers` (`nats` feature)
+    Nats,
+}
+
+impl EventSinkKind {
+    /// Cargo feature that builds this sink's client
+    pub fn feature(self) -> &'static str {
+        match self {
+            EventSinkKind::Kafka => "kafka",
+            EventSinkKind::Nats => "nats",
+        }
+    }
+
+    /// Whether the relay was built with this sink's client
+    pub fn is_available(self) -> bool {
+        match self {
+            EventSinkKind::Kafka => cfg!(feature = "kafka"),
+            EventSinkKind::Nats => cfg!(feature = "nats"),
+        }
+    }
+}
+
+/// Verified-message event stream settings
+///
+/// Publishing is enabled when `sink` is set; see [`crate::event_stream`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventStreamConfig {
+    /// Where events are published (disabled when unset)
+    pub sink: Option<EventSinkKind>,
+    /// Kafka bootstrap brokers or NATS server URLs
+    pub servers: Vec<String>,
+    /// Kafka topic or NATS subject events are published to
+    pub topic: String,
+    /// Attempts made before an event is marked failed
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each later retry
+    pub retry_base_ms: u64,
+    /// Upper bound on the delay between retries
+    pub retry_max_ms: u64,
+    /// How often the worker looks for due retries
+    pub poll_interval_ms: u64,
+    /// How long a publish may wait for the broker's acknowledgement
+    pub publish_timeout_ms: u64,
+}
+
+impl Default for EventStreamConfig {
+    fn default() -> Self {
+        Self {
+            sink: None,
+            servers: Vec::new(),
+            topic: "proof-messenger.messages.verified".to_string(),
+            max_attempts: 10,
+            retry_base_ms: 1000,
+            retry_max_ms: 300_000,
+            poll_interval_ms: 5000,
+            publish_timeout_ms: 10_000,
+        }
+    }
+}
+
+/// Message broker that fans live subscriptions out between relay replicas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackplaneKind {
+    /// Redis pub/sub on the server at `redis.url` (`redis` feature)
+    Redis,
+    /// NATS core subjects on the servers in `servers` (`nats` feature)
+    Nats,
+}
+
+impl BackplaneKind {
+    /// Cargo feature that builds this backplane's client
+    pub fn feature(self) -> &'static str {
+        match self {
+            BackplaneKind::Redis => "redis",
+            BackplaneKind::Nats => "nats",
+        }
+    }
+
+    /// Whether the relay was built with this backplane's client
+    pub fn is_available(self) -> bool {
+        match self {
+            BackplaneKind::Redis => cfg!(feature = "redis"),
+            BackplaneKind::Nats => cfg!(feature = "nats"),
+        }
+    }
+}
+
+/// Live message subscription settings
+///
+/// Subscribers always receive the messages verified by the relay they are
+/// connected to; with `backplane` set they also receive those verified by
+/// other replicas. See [`crate::subscriptions`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SubscriptionsConfig {
+    /// Broker shared with the other replicas (this relay only when unset)
+    pub backplane: Option<BackplaneKind>,
+    /// NATS server URLs; the Redis backplane uses `redis.url`
+    pub servers: Vec<String>,
+    /// Prefix of the per-group Redis channels or NATS subjects
+    pub channel_prefix: String,
+    /// Messages held per group for subscribers that fall behind
+    pub buffer: usize,
+    /// Seconds between keepalive pings sent to idle WebSocket subscribers
+    pub ping_interval_secs: u64,
+    /// Longest a long-poll request waits for new messages, in seconds
+    pub poll_timeout_secs: u64,
+}
+
+impl Default for SubscriptionsConfig {
+    fn default() -> Self {
+        Self {
+            backplane: None,
+            servers: Vec::new(),
+            channel_prefix: "proof-messenger.groups".to_string(),
+            buffer: 256,
+            ping_interval_secs: 30,
+            poll_timeout_secs: 30,
+        }
+    }
+}
+
+/// RFC 3161 timestamping settings
+///
+/// Stored messages are timestamped when `tsa_url` is set; see
+/// [`crate::timestamping`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimestampingConfig {
+    /// Time-Stamp Authority the relay requests tokens from (disabled when unset)
+    pub tsa_url: Option<String>,
+    /// Ask the authority to include its certificate in each token
+    pub cert_req: bool,
+    /// How long a timestamp request may take
+    pub timeout_ms: u64,
+}
+
+impl Default for TimestampingConfig {
+    fn default() -> Self {
+        Self {
+            tsa_url: None,
+            cert_req: true,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// API versioning settings
+///
+/// See [`crate::versioning`] for how versions are negotiated.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    /// When the unversioned legacy routes will be removed, announced in their `Sunset` header
+    pub legacy_sunset: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Read-only maintenance mode settings
+///
+/// See [`crate::maintenance`]; the mode can also be switched at runtime.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaintenanceConfig {
+    /// Start in read-only mode, refusing writes
+    pub read_only: bool,
+    /// Seconds refused clients are told to wait in `Retry-After`
+    pub retry_after_secs: u64,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            read_only: false,
+            retry_after_secs: 300,
+        }
+    }
+}
+
+/// Abuse detection settings
+///
+/// Senders are tracked and quarantined when `enabled`; see [`crate::abuse`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AbuseDetectionConfig {
+    /// Track senders and quarantine abusive ones
+    pub enabled: bool,
+    /// Length of the windows events are counted in, in seconds
+    pub window_secs: u64,
+    /// Messages failing verification tolerated per window
+    pub max_verification_failures: u32,
+    /// Messages carrying a revoked proof tolerated per window
+    pub max_revoked_replays: u32,
+    /// Verified messages tolerated per window
+    pub max_messages: u32,
+    /// How long an abusive sender's messages are refused, in seconds
+    pub quarantine_secs: u64,
+}
+
+impl Default for AbuseDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_secs: 60,
+            max_verification_failures: 10,
+            max_revoked_replays: 3,
+            max_messages: 600,
+            quarantine_secs: 3600,
+        }
+    }
+}
+
+/// Stored message integrity verification settings
+///
+/// Stored proofs are re-verified in the background when `enabled`; see
+/// [`crate::integrity`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IntegrityConfig {
+    /// Re-verify stored messages periodically
+    pub enabled: bool,
+    /// Time between the starts of two verification passes, in seconds
+    pub interval_secs: u64,
+    /// Messages read from the database at a time
+    pub batch_size: i64,
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 86_400,
+            batch_size: 500,
+        }
+    }
+}
+
+/// Scheduled message settings
+///
+/// Signed messages can be scheduled for later relay when `enabled`; see
+/// [`crate::scheduling`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulingConfig {
+    /// Accept scheduled messages and release them when due
+    pub enabled: bool,
+    /// Time between checks for due messages, in seconds
+    pub poll_interval_secs: u64,
+    /// How far ahead a message may be scheduled, in seconds
+    pub max_delay_secs: u64,
+    /// Due messages released per check
+    pub batch_size: i64,
+}
+
+impl Default for SchedulingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_secs: 5,
+            max_delay_secs: 30 * 86_400,
+            batch_size: 100,
+        }
+    }
+}
+
+/// Signature verification settings
+///
+/// `checks` orders the verification pipeline; see [`crate::checks`].
+/// Verification runs on a pool of blocking threads instead of the async
+/// runtime when `offload` is set; see [`crate::verification_pool`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VerificationConfig {
+    /// Checks every message passes, in order
+    pub checks: Vec<String>,
+    /// Verify signatures off the async runtime's worker threads
+    pub offload: bool,
+    /// Verifications running at once (the number of CPUs when unset)
+    pub max_concurrent: Option<usize>,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            checks: crate::checks::STANDARD_CHECKS.iter().map(|check| check.to_string()).collect(),
+            offload: false,
+            max_concurrent: None,
+        }
+    }
+}
+
+/// A WASM policy plugin
+///
+/// Each `[[plugins]]` entry loads a sandboxed module that runs as a
+/// verification check after the built-in ones; see [`crate::plugins`].
+/// Requires the `wasm-plugins` feature.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginConfig {
+    /// Name of the check in logs, metrics and rejections
+    pub name: String,
+    /// The module, as `.wasm` binary or `.wat` text
+    pub path: PathBuf,
+    /// Fuel (roughly, WASM instructions) one validation may consume
+    pub fuel: u64,
+    /// Largest linear memory the module may grow to, in bytes
+    pub max_memory_bytes: usize,
+    /// Wall-clock limit on one validation, in milliseconds
+    pub timeout_ms: u64,
+    /// Accept messages the plugin fails to evaluate instead of rejecting them
+    pub fail_open: bool,
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            path: PathBuf::new(),
+            fuel: 10_000_000,
+            max_memory_bytes: 16 * 1024 * 1024,
+            timeout_ms: 100,
+            fail_open: false,
+        }
+    }
+}
+
+/// Outbound HTTP settings
+///
+/// Applied to every request the relay sends; see [`crate::egress`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EgressConfig {
+    /// Proxy every outbound request goes through (direct connections when unset)
+    pub proxy: Option<String>,
+    /// Hosts reached directly rather than through `proxy`
+    pub no_proxy: Vec<String>,
+    /// Hosts outbound requests may reach, `*.example.com` for subdomains (any host when empty)
+    pub allowed_hosts: Vec<String>,
+}
+
+/// Destination security events are forwarded to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SiemSinkKind {
+    /// RFC 5424 messages sent over TCP to `address`, framed by octet counting
+    SyslogTcp,
+    /// Splunk HTTP Event Collector at `url`, authenticated with `token`
+    Hec,
+    /// JSON batches posted to `url`, signed with `token` when it is set
+    Webhook,
+}
+
+/// SIEM forwarding settings
+///
+/// Forwarding is enabled when `sink` is set; see [`crate::siem`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SiemConfig {
+    /// Where security events are forwarded (disabled when unset)
+    pub sink: Option<SiemSinkKind>,
+    /// Collector address of the `syslog_tcp` sink
+    pub address: Option<String>,
+    /// Endpoint of the `hec` and `webhook` sinks
+    pub url: Option<String>,
+    /// HEC token, or the secret webhook batches are signed with
+    pub token: Option<String>,
+    /// Name events are reported under (syslog APP-NAME, HEC `source`)
+    pub source: String,
+    /// Events kept while the SIEM is unreachable; the oldest are dropped beyond this
+    pub buffer_capacity: usize,
+    /// Events sent in one request
+    pub batch_size: usize,
+    /// Delay before the first retry; doubled for each later retry
+    pub retry_base_ms: u64,
+    /// Upper bound on the delay between retries
+    pub retry_max_ms: u64,
+    /// How long a delivery may take before it is treated as failed
+    pub timeout_ms: u64,
+}
+
+impl Default for SiemConfig {
+    fn default() -> Self {
+        Self {
+            sink: None,
+            address: None,
+            url: None,
+            token: None,
+            source: "proof-messenger-relay".to_string(),
+            buffer_capacity: 10_000,
+            batch_size: 100,
+            retry_base_ms: 1000,
+            retry_max_ms: 60_000,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// Request size limits
+///
+/// Requests and messages over a limit are refused with `413`; see [`crate::limits`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Maximum request body size in bytes
+    pub max_request_body_bytes: usize,
+    /// Maximum decoded size of a message's signed context in bytes
+    pub max_context_bytes: usize,
+    /// Maximum size of a message body in bytes
+    pub max_message_body_bytes: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_request_body_bytes: 1024 * 1024,
+            max_context_bytes: 64 * 1024,
+            max_message_body_bytes: 256 * 1024,
+        }
+    }
+}
+
+/// Readiness check settings
+///
+/// The JWKS endpoint probed is the first `[[oauth.issuers]]` entry's; see
+/// [`crate::readiness`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReadinessChecksConfig {
+    /// Time each check may take before it is reported as failed, in milliseconds
+    pub check_timeout_ms: u64,
+    /// Pending webhook deliveries above which the relay reports not ready
+    pub max_webhook_queue: i64,
+}
+
+impl Default for ReadinessChecksConfig {
+    fn default() -> Self {
+        Self {
+            check_timeout_ms: 2000,
+            max_webhook_queue: 1000,
+        }
+    }
+}
+
+/// Webhook delivery settings
+///
+/// See [`crate::webhooks`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookDeliveryConfig {
+    /// Attempts made before a delivery is marked failed
+    pub max_attempts: u32,
+    /// Delay before the first retry, in seconds; doubled for each later retry
+    pub retry_base_secs: u64,
+    /// Upper bound on the delay between retries, in seconds
+    pub retry_max_secs: u64,
+    /// How often the worker looks for due retries, in seconds
+    pub poll_interval_secs: u64,
+    /// Timeout for a single delivery request, in seconds
+    pub timeout_secs: u64,
+    /// Allow plain HTTP webhook URLs (local development and tests only)
+    pub allow_insecure_urls: bool,
+}
+
+impl Default for WebhookDeliveryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            retry_base_secs: 30,
+            retry_max_secs: 3600,
+            poll_interval_secs: 10,
+            timeout_secs: 10,
+            allow_insecure_urls: false,
+        }
+    }
+}
+
+/// Relay federation settings
+///
+/// Federation is enabled when `peers` are listed; see [`crate::federation`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RelayFederationConfig {
+    /// ID this relay signs its federation requests with
+    pub relay_id: Option<String>,
+    /// Hex encoded 64-byte signing keypair, usually set with `FEDERATION_SIGNING_KEY`
+    pub signing_key: Option<String>,
+    /// Peer relays to forward to and reconcile with
+    pub peers: Vec<crate::federation::FederationPeer>,
+    /// Maximum number of relay-to-relay hops a message may take
+    pub max_hops: u32,
+    /// How often the reconciliation job runs, in seconds
+    pub reconcile_interval_secs: u64,
+    /// How far back each reconciliation run compares digests, in minutes
+    pub reconcile_window_minutes: i64,
+    /// Allow plain HTTP peer URLs (local development and tests only)
+    pub allow_insecure_peers: bool,
+    /// PEM certificate presented to peers that require mutual TLS
+    pub client_cert_path: Option<PathBuf>,
+    /// PKCS#8 key for `client_cert_path`
+    pub client_key_path: Option<PathBuf>,
+}
+
+impl Default for RelayFederationConfig {
+    fn default() -> Self {
+        Self {
+            relay_id: None,
+            signing_key: None,
+            peers: Vec::new(),
+            max_hops: 3,
+            reconcile_interval_secs: 300,
+            reconcile_window_minutes: 60,
+            allow_insecure_peers: false,
+            client_cert_path: None,
+            client_key_path: None,
+        }
+    }
+}
+
+impl RelayFederationConfig {
+    /// Whether messages are exchanged with peer relays
+    pub fn enabled(&self) -> bool {
+        !self.peers.is_empty()
+    }
+}
+
+/// OAuth2.0 token issuers trusted by the relay
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OAuthConfig {
+    pub issuers: Vec<OAuthIssuer>,
+    /// Validate opaque access tokens at the issuer's introspection endpoint
+    pub introspection: Option<IntrospectionConfig>,
+}
+
+/// RFC 7662 token introspection settings
+///
+/// See [`crate::jwt_validator::TokenIntrospector`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IntrospectionConfig {
+    /// The issuer's introspection endpoint
+    pub endpoint: String,
+    /// Client ID the relay authenticates to the endpoint with
+    pub client_id: String,
+    /// Client secret, usually set with `OAUTH_INTROSPECTION_CLIENT_SECRET`
+    #[serde(default)]
+    pub client_secret: String,
+    /// How long an active result is reused before the token is introspected again
+    #[serde(default = "default_introspection_cache_seconds")]
+    pub cache_seconds: u64,
+}
+
+fn default_introspection_cache_seconds() -> u64 {
+    60
+}
+
+/// A trusted OAuth2.0 token issuer
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OAuthIssuer {
+    /// Expected `iss` claim
+    pub issuer: String,
+    /// Expected `aud` claim
+    pub audience: String,
+    /// Endpoint serving the issuer's signing keys
+    pub jwks_url: String,
+}
+
+/// Scopes required by authenticated routes
+///
+/// Entries replace the built-in requirement of the routes they name; see
+/// [`crate::authorization`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthorizationConfig {
+    /// Required scopes by `"METHOD /path"`, with paths as declared in the router
+    pub routes: BTreeMap<String, Vec<String>>,
+}
+
+/// Scopes granted to members of identity provider groups
+///
+/// Role mapping is enabled when at least one group is configured; see
+/// [`crate::rbac`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RbacConfig {
+    /// Token claim listing the caller's groups
+    pub groups_claim: String,
+    /// Scopes granted by each group, by group name or ID
+    pub groups: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for RbacConfig {
+    fn default() -> Self {
+        Self {
+            groups_claim: "groups".to_string(),
+            groups: BTreeMap::new(),
+        }
+    }
+}
+
+impl RbacConfig {
+    /// Whether group membership grants scopes
+    pub fn enabled(&self) -> bool {
+        !self.groups.is_empty()
+    }
+}
+
+/// API key authentication settings
+///
+/// See [`crate::api_keys`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    /// Accept `X-Api-Key` credentials on authenticated routes
+    pub enabled: bool,
+    /// Requests per minute allowed to keys created without their own limit
+    pub requests_per_minute: u32,
+}
+
+impl Default for ApiKeyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            requests_per_minute: 600,
+        }
+    }
+}
+
+/// Ed25519 challenge-response authentication settings
+///
+/// See [`crate::key_auth`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyAuthConfig {
+    /// Issue session tokens to clients that sign a challenge with a listed key
+    pub enabled: bool,
+    /// How long a challenge can be signed, in seconds
+    pub challenge_ttl_secs: u64,
+    /// How long a session token is accepted, in seconds
+    pub session_ttl_secs: u64,
+    /// Scopes granted to each public key (hex encoded)
+    pub keys: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for KeyAuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            challenge_ttl_secs: 60,
+            session_ttl_secs: 900,
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+/// Client certificate authentication settings
+///
+/// See [`crate::client_identity`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientIdentityConfig {
+    /// Authenticate requests without an `Authorization` header by client certificate
+    pub enabled: bool,
+    /// Header a mesh sidecar forwards the client certificate's details in
+    pub forwarded_header: String,
+    /// Read the client certificate from the forwarded header when the relay
+    /// does not verify it itself; only safe behind a proxy that sets it
+    pub trust_forwarded_header: bool,
+    /// Scopes granted to each identity; a trailing `*` matches by prefix
+    pub identities: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for ClientIdentityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            forwarded_header: "x-forwarded-client-cert".to_string(),
+            trust_forwarded_header: false,
+            identities: BTreeMap::new(),
+        }
+    }
+}
+
+/// Where the tenant of a request is read from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TenantSource {
+    /// A request header, `x-tenant-id` unless configured otherwise
+    ///
+    /// Unless `trusted_proxy` is set, the header must name the tenant of the
+    /// caller's token claim.
+    #[default]
+    Header,
+    /// A claim of the request's validated bearer token
+    Claim,
+}
+
+/// Multi-tenant settings
+///
+/// Tenancy is enabled when at least one tenant is configured; see
+/// [`crate::tenancy`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenancyConfig {
+    /// Where the tenant is read from
+    pub source: TenantSource,
+    /// Header naming the tenant when `source` is `header`
+    pub header: String,
+    /// Trust the header as set by a fronting proxy, without checking it
+    /// against the caller's token; only safe when clients cannot set it
+    pub trusted_proxy: bool,
+    /// Token claim naming the caller's tenant
+    pub claim: String,
+    /// Tenant of requests that do not name one (rejected when unset)
+    pub default_tenant: Option<String>,
+    /// Settings of each tenant, by tenant ID
+    pub tenants: BTreeMap<String, TenantSettings>,
+}
+
+impl Default for TenancyConfig {
+    fn default() -> Self {
+        Self {
+            source: TenantSource::Header,
+            header: "x-tenant-id".to_string(),
+            trusted_proxy: false,
+            claim: "tenant_id".to_string(),
+            default_tenant: None,
+            tenants: BTreeMap::new(),
+        }
+    }
+}
+
+impl TenancyConfig {
+    /// Whether the relay serves more than one tenant
+    pub fn enabled(&self) -> bool {
+        !self.tenants.is_empty()
+    }
+}
+
+/// Settings of one tenant
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenantSettings {
+    /// Compliance policy for the tenant's contexts, instead of `features.context_policy`
+    pub context_policy: Option<String>,
+    /// Days the tenant's messages are kept (kept indefinitely when unset)
+    pub message_retention_days: Option<i64>,
+}
+
+/// What the relay does when an authenticated user relays with a new public key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyPinningMode {
+    /// Relay the message, pin the new key and report the change
+    Flag,
+    /// Refuse the message and report the change; only a signed rotation moves the pin
+    Reject,
+}
+
+/// Which halves of a hybrid Ed25519 + ML-DSA-65 proof must verify
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HybridProofPolicy {
+    /// Every message must carry a valid post-quantum signature
+    Strict,
+    /// The Ed25519 signature must verify; a post-quantum signature is additive
+    #[default]
+    Transitional,
+}
+
+impl From<HybridProofPolicy> for HybridPolicy {
+    fn from(policy: HybridProofPolicy) -> Self {
+        match policy {
+            HybridProofPolicy::Strict => HybridPolicy::Strict,
+            HybridProofPolicy::Transitional => HybridPolicy::Transitional,
+        }
+    }
+}
+
+/// Optional relay features
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureToggles {
+    /// Reject messages whose proof has been revoked
+    pub revocation_check: bool,
+    /// Keep messages that fail verification for investigation
+    pub quarantine: bool,
+    /// Accept raw Ed25519 signatures alongside proof envelopes
+    ///
+    /// Enabled during the migration to proof envelopes; disable it once every
+    /// client sends enveloped proofs.
+    pub legacy_proofs: bool,
+    /// Compliance policy that relayed contexts must satisfy (disabled when unset)
+    ///
+    /// Names one of the protocol crate's standard policies, such as
+    /// `transaction` or `login`; see [`crate::context_policy`].
+    pub context_policy: Option<String>,
+    /// Reject messages whose sender already relayed the same signed context
+    ///
+    /// Contexts are remembered for `retention.replay_window_secs`, in Redis
+    /// when `[redis]` is configured.
+    pub replay_protection: bool,
+    /// Pin the first public key each authenticated user relays with (disabled when unset)
+    ///
+    /// See [`crate::key_pinning`].
+    pub key_pinning: Option<KeyPinningMode>,
+    /// Which halves of hybrid proofs must verify
+    pub hybrid_policy: HybridProofPolicy,
+}
+
+impl Default for FeatureToggles {
+    fn default() -> Self {
+        Self {
+            revocation_check: false,
+            quarantine: false,
+            legacy_proofs: true,
+            context_policy: None,
+            replay_protection: false,
+            key_pinning: None,
+            hybrid_policy: HybridProofPolicy::Transitional,
+        }
+    }
+}
+
+impl RelayConfig {
+    /// Load the configuration file and environment overrides, then validate the result
+    ///
+    /// The file is read from `RELAY_CONFIG` if set, otherwise from
+    /// [`DEFAULT_CONFIG_PATH`] if it exists; without a file the defaults are used.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match std::env::var("RELAY_CONFIG") {
+            Ok(path) => Self::from_file(path)?,
+            Err(_) if Path::new(DEFAULT_CONFIG_PATH).exists() => Self::from_file(DEFAULT_CONFIG_PATH)?,
+            Err(_) => Self::default(),
+        };
+        let mut problems = config.apply_overrides(|name| std::env::var(name).ok());
+        problems.extend(config.problems());
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    /// Read a configuration file without applying overrides or validation
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&contents).map_err(|e| ConfigError::Parse {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Override settings from environment variables
+    ///
+    /// - `DATABASE_URL`, `DATABASE_READ_REPLICA_URL`
+    /// - `HOST` and `PORT`: bind address parts
+    /// - `GRPC_BIND_ADDRESS`: address of the gRPC services
+    /// - `TLS_CERT_PATH`, `TLS_KEY_PATH`, `TLS_CLIENT_CA_PATH`
+    /// - `RATE_LIMIT_PER_SECOND`, `RATE_LIMIT_BURST_SIZE`
+    /// - `CORS_ALLOWED_ORIGINS`: comma-separated origins
+    /// - `QUARANTINE_RETENTION_DAYS`, `ERASURE_GRACE_DAYS`
+    /// - `MESSAGE_EXPIRY_PURGE_INTERVAL_SECS`: time between purges of expired messages
+    /// - `LOG_HEADERS`: comma-separated headers included in request logs
+    /// - `OAUTH_ISSUER`, `OAUTH_AUDIENCE`, `OAUTH_JWKS_URL`: a single trusted issuer
+    /// - `OAUTH_INTROSPECTION_CLIENT_SECRET`: secret for `[oauth.introspection]`
+    /// - `REDIS_URL`: Redis server shared by replicas, or empty to keep state in memory
+    /// - `REVOCATION_CHECK_ENABLED`, `QUARANTINE_REJECTED_MESSAGES`,
+    ///   `LEGACY_PROOFS_ACCEPTED`, `REPLAY_PROTECTION_ENABLED`,
+    ///   `LOG_REDACT_PII`, `READ_ONLY_MODE`, `ABUSE_DETECTION_ENABLED`,
+    ///   `INTEGRITY_CHECK_ENABLED`, `SCHEDULED_MESSAGES_ENABLED`,
+    ///   `KEY_AUTH_ENABLED`, `VERIFICATION_OFFLOAD`: `true` or `false`
+    /// - `READ_ONLY_RETRY_AFTER_SECS`: `Retry-After` of writes refused in read-only mode
+    /// - `ABUSE_QUARANTINE_SECS`: how long abusive senders are quarantined
+    /// - `INTEGRITY_CHECK_INTERVAL_SECS`: time between stored message verification passes
+    /// - `SCHEDULED_MESSAGES_POLL_INTERVAL_SECS`: time between checks for due scheduled messages
+    /// - `KEY_AUTH_SESSION_TTL_SECS`: lifetime of session tokens issued for signed challenges
+    /// - `VERIFICATION_MAX_CONCURRENT`: signature verifications running at once when offloaded
+    /// - `SIEM_TOKEN`: HEC token or webhook signing secret for `[siem]`
+    /// - `CONTEXT_POLICY`: compliance policy name, or empty to disable enforcement
+    /// - `HYBRID_PROOF_POLICY`: `strict` or `transitional`
+    /// - `TSA_URL`: RFC 3161 Time-Stamp Authority, or empty to disable timestamping
+    /// - `LEGACY_API_SUNSET`: RFC 3339 removal date of the unversioned routes, or empty for none
+    /// - `HTTPS_PROXY`: proxy for outbound requests, or empty for direct connections
+    /// - `NO_PROXY`, `EGRESS_ALLOWED_HOSTS`: comma-separated hosts
+    /// - `MAX_REQUEST_BODY_BYTES`, `MAX_CONTEXT_BYTES`, `MAX_MESSAGE_BODY_BYTES`: request size limits
+    /// - `READINESS_CHECK_TIMEOUT_MS`, `READINESS_MAX_WEBHOOK_QUEUE`: readiness check settings
+    /// - `WEBHOOK_MAX_ATTEMPTS`, `WEBHOOK_RETRY_BASE_SECS`, `WEBHOOK_RETRY_MAX_SECS`,
+    ///   `WEBHOOK_POLL_INTERVAL_SECS`, `WEBHOOK_TIMEOUT_SECS`: webhook delivery settings
+    /// - `FEDERATION_PEERS`: JSON array of `{"id", "url", "public_key"}` peers
+    /// - `FEDERATION_RELAY_ID`, `FEDERATION_SIGNING_KEY`, `FEDERATION_MAX_HOPS`,
+    ///   `FEDERATION_RECONCILE_INTERVAL_SECS`, `FEDERATION_RECONCILE_WINDOW_MINUTES`,
+    ///   `FEDERATION_CLIENT_CERT_PATH`, `FEDERATION_CLIENT_KEY_PATH`: federation settings
+    /// - `WEBHOOK_ALLOW_INSECURE_URLS`, `FEDERATION_ALLOW_INSECURE_PEERS`: `true` or `false`
+    ///
+    /// Returns a description of every variable that could not be parsed.
+    pub fn apply_overrides(&mut self, env: impl Fn(&str) -> Option<String>) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if let Some(url) = env("DATABASE_URL") {
+            self.database.url = url;
+        }
+        if let Some(url) = env("DATABASE_READ_REPLICA_URL") {
+            self.database.read_replica_url = Some(url).filter(|url| !url.is_empty());
+        }
+        if let Some(host) = env("HOST") {
+            match host.parse() {
+                Ok(ip) => self.server.bind_address.set_ip(ip),
+                Err(_) => problems.push(format!("HOST: '{}' is not an IP address", host)),
+            }
+        }
+        override_number(&env, "PORT", &mut problems, |port| self.server.bind_address.set_port(port));
+        if let Some(address) = env("GRPC_BIND_ADDRESS") {
+            match address.parse() {
+                Ok(address) => self.server.grpc_bind_address = Some(address),
+                Err(_) => problems.push(format!("GRPC_BIND_ADDRESS: '{}' is not a socket address", address)),
+            }
+        }
+        if let Some(path) = env("TLS_CERT_PATH") {
+            self.tls.cert_path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = env("TLS_KEY_PATH") {
+            self.tls.key_path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = env("TLS_CLIENT_CA_PATH") {
+            self.tls.client_ca_path = Some(PathBuf::from(path));
+        }
+        override_number(&env, "RATE_LIMIT_PER_SECOND", &mut problems, |n| self.rate_limit.per_second = n);
+        override_number(&env, "RATE_LIMIT_BURST_SIZE", &mut problems, |n| self.rate_limit.burst_size = n);
+        if let Some(url) = env("REDIS_URL") {
+            self.redis.url = Some(url).filter(|url| !url.is_empty());
+        }
+        if let Some(origins) = env("CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        override_number(&env, "QUARANTINE_RETENTION_DAYS", &mut problems, |days| {
+            self.retention.quarantine_days = days
+        });
+        override_number(&env, "ERASURE_GRACE_DAYS", &mut problems, |days| {
+            self.retention.erasure_grace_days = days
+        });
+        override_number(&env, "MESSAGE_EXPIRY_PURGE_INTERVAL_SECS", &mut problems, |secs| {
+            self.retention.expiry_purge_interval_secs = secs
+        });
+        if let Some(headers) = env("LOG_HEADERS") {
+            self.logging.logged_headers = headers
+                .split(',')
+                .map(str::trim)
+                .filter(|header| !header.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(issuer) = env("OAUTH_ISSUER") {
+            self.oauth.issuers = vec![OAuthIssuer {
+                issuer,
+                audience: env("OAUTH_AUDIENCE").unwrap_or_default(),
+                jwks_url: env("OAUTH_JWKS_URL").unwrap_or_default(),
+            }];
+        }
+        if let (Some(introspection), Some(secret)) = (&mut self.oauth.introspection, env("OAUTH_INTROSPECTION_CLIENT_SECRET")) {
+            introspection.client_secret = secret;
+        }
+        override_bool(&env, "REVOCATION_CHECK_ENABLED", &mut problems, |on| self.features.revocation_check = on);
+        override_bool(&env, "QUARANTINE_REJECTED_MESSAGES", &mut problems, |on| self.features.quarantine = on);
+        override_bool(&env, "LEGACY_PROOFS_ACCEPTED", &mut problems, |on| self.features.legacy_proofs = on);
+        override_bool(&env, "REPLAY_PROTECTION_ENABLED", &mut problems, |on| self.features.replay_protection = on);
+        override_bool(&env, "LOG_REDACT_PII", &mut problems, |on| self.logging.redact_pii = on);
+        override_bool(&env, "READ_ONLY_MODE", &mut problems, |on| self.maintenance.read_only = on);
+        override_number(&env, "READ_ONLY_RETRY_AFTER_SECS", &mut problems, |secs| {
+            self.maintenance.retry_after_secs = secs
+        });
+        override_bool(&env, "ABUSE_DETECTION_ENABLED", &mut problems, |on| self.abuse_detection.enabled = on);
+        override_number(&env, "ABUSE_QUARANTINE_SECS", &mut problems, |secs| {
+            self.abuse_detection.quarantine_secs = secs
+        });
+        override_bool(&env, "INTEGRITY_CHECK_ENABLED", &mut problems, |on| self.integrity.enabled = on);
+        override_number(&env, "INTEGRITY_CHECK_INTERVAL_SECS", &mut problems, |secs| {
+            self.integrity.interval_secs = secs
+        });
+        override_bool(&env, "SCHEDULED_MESSAGES_ENABLED", &mut problems, |on| self.scheduling.enabled = on);
+        override_number(&env, "SCHEDULED_MESSAGES_POLL_INTERVAL_SECS", &mut problems, |secs| {
+            self.scheduling.poll_interval_secs = secs
+        });
+        override_bool(&env, "KEY_AUTH_ENABLED", &mut problems, |on| self.key_auth.enabled = on);
+        override_number(&env, "KEY_AUTH_SESSION_TTL_SECS", &mut problems, |secs| {
+            self.key_auth.session_ttl_secs = secs
+        });
+        override_bool(&env, "VERIFICATION_OFFLOAD", &mut problems, |on| self.verification.offload = on);
+        override_number(&env, "VERIFICATION_MAX_CONCURRENT", &mut problems, |limit| {
+            self.verification.max_concurrent = Some(limit)
+        });
+        if let Some(proxy) = env("HTTPS_PROXY").or_else(|| env("https_proxy")) {
+            self.egress.proxy = Some(proxy).filter(|proxy| !proxy.is_empty());
+        }
+        if let Some(hosts) = env("NO_PROXY").or_else(|| env("no_proxy")) {
+            self.egress.no_proxy = hosts
+                .split(',')
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(hosts) = env("EGRESS_ALLOWED_HOSTS") {
+            self.egress.allowed_hosts = hosts
+                .split(',')
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(token) = env("SIEM_TOKEN") {
+            self.siem.token = Some(token).filter(|token| !token.is_empty());
+        }
+        if let Some(policy) = env("CONTEXT_POLICY") {
+            self.features.context_policy = Some(policy.trim().to_string()).filter(|policy| !policy.is_empty());
+        }
+        match env("KEY_PINNING").as_deref().map(str::trim) {
+            Some("" | "off") => self.features.key_pinning = None,
+            Some("flag") => self.features.key_pinning = Some(KeyPinningMode::Flag),
+            Some("reject") => self.features.key_pinning = Some(KeyPinningMode::Reject),
+            Some(other) => problems.push(format!("KEY_PINNING: '{}' must be 'off', 'flag' or 'reject'", other)),
+            None => {}
+        }
+        match env("HYBRID_PROOF_POLICY").as_deref().map(|policy| policy.trim().to_ascii_lowercase()).as_deref() {
+            Some("strict") => self.features.hybrid_policy = HybridProofPolicy::Strict,
+            Some("transitional") => self.features.hybrid_policy = HybridProofPolicy::Transitional,
+            Some(other) => problems.push(format!("HYBRID_PROOF_POLICY: '{}' must be 'strict' or 'transitional'", other)),
+            None => {}
+        }
+        if let Some(url) = env("TSA_URL") {
+            self.timestamping.tsa_url = Some(url.trim().to_string()).filter(|url| !url.is_empty());
+        }
+        override_number(&env, "MAX_REQUEST_BODY_BYTES", &mut problems, |bytes| {
+            self.limits.max_request_body_bytes = bytes
+        });
+        override_number(&env, "MAX_CONTEXT_BYTES", &mut problems, |bytes| self.limits.max_context_bytes = bytes);
+        override_number(&env, "MAX_MESSAGE_BODY_BYTES", &mut problems, |bytes| {
+            self.limits.max_message_body_bytes = bytes
+        });
+        override_number(&env, "READINESS_CHECK_TIMEOUT_MS", &mut problems, |ms| self.readiness.check_timeout_ms = ms);
+        override_number(&env, "READINESS_MAX_WEBHOOK_QUEUE", &mut problems, |depth| {
+            self.readiness.max_webhook_queue = depth
+        });
+        override_number(&env, "WEBHOOK_MAX_ATTEMPTS", &mut problems, |attempts| self.webhooks.max_attempts = attempts);
+        override_number(&env, "WEBHOOK_RETRY_BASE_SECS", &mut problems, |secs| self.webhooks.retry_base_secs = secs);
+        override_number(&env, "WEBHOOK_RETRY_MAX_SECS", &mut problems, |secs| self.webhooks.retry_max_secs = secs);
+        override_number(&env, "WEBHOOK_POLL_INTERVAL_SECS", &mut problems, |secs| {
+            self.webhooks.poll_interval_secs = secs
+        });
+        override_number(&env, "WEBHOOK_TIMEOUT_SECS", &mut problems, |secs| self.webhooks.timeout_secs = secs);
+        override_bool(&env, "WEBHOOK_ALLOW_INSECURE_URLS", &mut problems, |on| self.webhooks.allow_insecure_urls = on);
+        if let Some(peers) = env("FEDERATION_PEERS") {
+            match serde_json::from_str(&peers) {
+                Ok(peers) => self.federation.peers = peers,
+                Err(e) => problems.push(format!("FEDERATION_PEERS: {}", e)),
+            }
+        }
+        if let Some(relay_id) = env("FEDERATION_RELAY_ID") {
+            self.federation.relay_id = Some(relay_id).filter(|relay_id| !relay_id.is_empty());
+        }
+        if let Some(key) = env("FEDERATION_SIGNING_KEY") {
+            self.federation.signing_key = Some(key.trim().to_string()).filter(|key| !key.is_empty());
+        }
+        override_number(&env, "FEDERATION_MAX_HOPS", &mut problems, |hops| self.federation.max_hops = hops);
+        override_number(&env, "FEDERATION_RECONCILE_INTERVAL_SECS", &mut problems, |secs| {
+            self.federation.reconcile_interval_secs = secs
+        });
+        override_number(&env, "FEDERATION_RECONCILE_WINDOW_MINUTES", &mut problems, |minutes| {
+            self.federation.reconcile_window_minutes = minutes
+        });
+        override_bool(&env, "FEDERATION_ALLOW_INSECURE_PEERS", &mut problems, |on| {
+            self.federation.allow_insecure_peers = on
+        });
+        if let Some(path) = env("FEDERATION_CLIENT_CERT_PATH") {
+            self.federation.client_cert_path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = env("FEDERATION_CLIENT_KEY_PATH") {
+            self.federation.client_key_path = Some(PathBuf::from(path));
+        }
+        match env("LEGACY_API_SUNSET").as_deref().map(str::trim) {
+            Some("") => self.api.legacy_sunset = None,
+            Some(date) => match chrono::DateTime::parse_from_rfc3339(date) {
+                Ok(date) => self.api.legacy_sunset = Some(date.with_timezone(&chrono::Utc)),
+                Err(e) => problems.push(format!("LEGACY_API_SUNSET: '{}' is not an RFC 3339 date: {}", date, e)),
+            },
+            None => {}
+        }
+
+        problems
+    }
+
+    /// Whether clients must authenticate to use the API
+    ///
+    /// True when any authentication method is configured: a token issuer, API
+    /// keys, key challenges or client certificates.
+    pub fn authentication_enabled(&self) -> bool {
+        !self.oauth.issuers.is_empty() || self.api_keys.enabled || self.key_auth.enabled || self.client_identity.enabled
+    }
+
+    /// Check the configuration for invalid settings
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    /// Describe every invalid setting
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.server.grpc_bind_address == Some(self.server.bind_address) {
+            problems.push("server.grpc_bind_address must differ from server.bind_address".to_string());
+        }
+        if self.tls.cert_path.is_some() != self.tls.key_path.is_some() {
+            problems.push("tls.cert_path and tls.key_path must be set together".to_string());
+        }
+        if self.tls.client_ca_path.is_some() && !self.tls.enabled() {
+            problems.push("tls.client_ca_path requires tls.cert_path and tls.key_path".to_string());
+        }
+        if self.database.url.trim().is_empty() {
+            problems.push("database.url must not be empty".to_string());
+        }
+        if self.database.max_connections == 0 {
+            problems.push("database.max_connections must be at least 1".to_string());
+        }
+        if self.database.min_connections > self.database.max_connections {
+            problems.push("database.min_connections must not exceed database.max_connections".to_string());
+        }
+        if self.database.acquire_timeout_seconds == 0 {
+            problems.push("database.acquire_timeout_seconds must be at least 1".to_string());
+        }
+        if self.database.statement_timeout_ms == Some(0) {
+            problems.push("database.statement_timeout_ms must be at least 1".to_string());
+        }
+        if self.database.write_behind.batch_size == 0 {
+            problems.push("database.write_behind.batch_size must be at least 1".to_string());
+        }
+        if self.database.write_behind.queue_capacity == 0 {
+            problems.push("database.write_behind.queue_capacity must be at least 1".to_string());
+        }
+        if let Some(url) = &self.database.read_replica_url {
+            if url.trim().is_empty() || *url == self.database.url {
+                problems.push("database.read_replica_url must name a database other than database.url".to_string());
+            }
+        }
+        if self.rate_limit.per_second == 0 {
+            problems.push("rate_limit.per_second must be at least 1".to_string());
+        }
+        if self.rate_limit.burst_size == 0 {
+            problems.push("rate_limit.burst_size must be at least 1".to_string());
+        }
+        if self.abuse_detection.enabled {
+            if self.abuse_detection.window_secs == 0 {
+                problems.push("abuse_detection.window_secs must be at least 1".to_string());
+            }
+            if self.abuse_detection.max_messages == 0 {
+                problems.push("abuse_detection.max_messages must be at least 1".to_string());
+            }
+            if self.abuse_detection.quarantine_secs == 0 {
+                problems.push("abuse_detection.quarantine_secs must be at least 1".to_string());
+            }
+        }
+        if self.integrity.interval_secs == 0 {
+            problems.push("integrity.interval_secs must be at least 1".to_string());
+        }
+        if self.integrity.batch_size < 1 {
+            problems.push("integrity.batch_size must be at least 1".to_string());
+        }
+        if self.scheduling.poll_interval_secs == 0 {
+            problems.push("scheduling.poll_interval_secs must be at least 1".to_string());
+        }
+        if self.scheduling.max_delay_secs == 0 {
+            problems.push("scheduling.max_delay_secs must be at least 1".to_string());
+        }
+        if self.scheduling.batch_size < 1 {
+            problems.push("scheduling.batch_size must be at least 1".to_string());
+        }
+        if self.verification.max_concurrent == Some(0) {
+            problems.push("verification.max_concurrent must be at least 1".to_string());
+        }
+        for (index, check) in self.verification.checks.iter().enumerate() {
+            if !crate::checks::is_builtin(check) {
+                problems.push(format!("verification.checks: unknown check '{}'", check));
+            } else if self.verification.checks[..index].contains(check) {
+                problems.push(format!("verification.checks: '{}' is listed twice", check));
+            }
+        }
+        if !self.verification.checks.iter().any(|check| check == "proof") {
+            problems.push("verification.checks must include 'proof'".to_string());
+        }
+        if let Some(proxy) = &self.egress.proxy {
+            if !matches!(reqwest::Url::parse(proxy), Ok(url) if matches!(url.scheme(), "http" | "https")) {
+                problems.push(format!("egress.proxy: '{}' must be an http:// or https:// URL", proxy));
+            }
+        }
+        for host in &self.egress.allowed_hosts {
+            let name = host.strip_prefix("*.").unwrap_or(host);
+            if name.is_empty() || name.contains(|c: char| c.is_whitespace() || matches!(c, '/' | ':' | '*')) {
+                problems.push(format!("egress.allowed_hosts: '{}' must be a host such as api.example.com or *.example.com", host));
+            }
+        }
+        for (setting, url) in self.outbound_urls() {
+            if let Ok(host) = reqwest::Url::parse(url).map(|url| url.host_str().unwrap_or_default().to_string()) {
+                if !crate::egress::host_allowed(&self.egress.allowed_hosts, &host) {
+                    problems.push(format!("{}: {} is not in egress.allowed_hosts", setting, host));
+                }
+            }
+        }
+        if !self.plugins.is_empty() && !cfg!(feature = "wasm-plugins") {
+            problems.push("plugins require a relay built with the `wasm-plugins` feature".to_string());
+        }
+        for (index, plugin) in self.plugins.iter().enumerate() {
+            if plugin.name.is_empty() || plugin.name.contains(char::is_whitespace) {
+                problems.push(format!("plugins[{}].name must be a non-empty name without spaces", index));
+            } else if crate::checks::is_builtin(&plugin.name) {
+                problems.push(format!("plugins[{}].name: '{}' is a built-in check", index, plugin.name));
+            } else if self.plugins[..index].iter().any(|other| other.name == plugin.name) {
+                problems.push(format!("plugins[{}].name: '{}' is used twice", index, plugin.name));
+            }
+            if plugin.path.as_os_str().is_empty() {
+                problems.push(format!("plugins[{}].path must not be empty", index));
+            }
+            if plugin.fuel == 0 {
+                problems.push(format!("plugins[{}].fuel must be at least 1", index));
+            }
+            if plugin.max_memory_bytes < crate::plugins::WASM_PAGE_BYTES {
+                problems.push(format!("plugins[{}].max_memory_bytes must be at least {}", index, crate::plugins::WASM_PAGE_BYTES));
+            }
+            if plugin.timeout_ms == 0 {
+                problems.push(format!("plugins[{}].timeout_ms must be at least 1", index));
+            }
+        }
+        if let Some(url) = &self.redis.url {
+            if !["redis://", "rediss://", "unix://"].iter().any(|scheme| url.starts_with(scheme)) {
+                problems.push(format!("redis.url: '{}' must be a redis://, rediss:// or unix:// URL", url));
+            } else if !cfg!(feature = "redis") {
+                problems.push("redis.url requires a relay built with the `redis` feature".to_string());
+            }
+        }
+        if self.redis.key_prefix.is_empty() || self.redis.key_prefix.contains(char::is_whitespace) {
+            problems.push("redis.key_prefix must be a non-empty name without spaces".to_string());
+        }
+        for origin in &self.cors.allowed_origins {
+            let valid = (origin.starts_with("https://") || origin.starts_with("http://"))
+                && HeaderValue::from_str(origin).is_ok();
+            if !valid {
+                problems.push(format!(
+                    "cors.allowed_origins: '{}' must be an http:// or https:// origin",
+                    origin
+                ));
+            }
+        }
+        if self.retention.quarantine_days < 1 {
+            problems.push("retention.quarantine_days must be at least 1".to_string());
+        }
+        if self.retention.erasure_grace_days < 0 {
+            problems.push("retention.erasure_grace_days must not be negative".to_string());
+        }
+        if self.retention.replay_window_secs == 0 {
+            problems.push("retention.replay_window_secs must be at least 1".to_string());
+        }
+        if self.retention.expiry_purge_interval_secs == 0 {
+            problems.push("retention.expiry_purge_interval_secs must be at least 1".to_string());
+        }
+        for header in &self.logging.logged_headers {
+            if axum::http::HeaderName::from_bytes(header.as_bytes()).is_err() {
+                problems.push(format!("logging.logged_headers: '{}' is not a valid header name", header));
+            }
+        }
+        if let Some(policy) = &self.features.context_policy {
+            check_policy_name("features.context_policy", policy, &mut problems);
+        }
+        problems.extend(self.audit_problems());
+        problems.extend(self.siem_problems());
+        problems.extend(self.event_stream_problems());
+        problems.extend(self.subscription_problems());
+        problems.extend(self.timestamping_problems());
+        for (setting, bytes) in [
+            ("limits.max_request_body_bytes", self.limits.max_request_body_bytes),
+            ("limits.max_context_bytes", self.limits.max_context_bytes),
+            ("limits.max_message_body_bytes", self.limits.max_message_body_bytes),
+        ] {
+            if bytes == 0 {
+                problems.push(format!("{} must be at least 1", setting));
+            }
+        }
+        if self.readiness.check_timeout_ms == 0 {
+            problems.push("readiness.check_timeout_ms must be at least 1".to_string());
+        }
+        if self.webhooks.max_attempts == 0 {
+            problems.push("webhooks.max_attempts must be at least 1".to_string());
+        }
+        if self.webhooks.poll_interval_secs == 0 {
+            problems.push("webhooks.poll_interval_secs must be at least 1".to_string());
+        }
+        if self.federation.enabled() {
+            problems.extend(self.federation_problems());
+        }
+        if !self.authorization.routes.is_empty() && !self.authentication_enabled() {
+            problems.push(
+                "authorization.routes requires an authentication method (oauth.issuers, api_keys, key_auth or client_identity)"
+                    .to_string(),
+            );
+        }
+        for (route, scopes) in &self.authorization.routes {
+            if crate::authorization::parse_route(route).is_none() {
+                problems.push(format!("authorization.routes: '{}' must be a method and path such as 'GET /quarantine'", route));
+            }
+            if scopes.iter().any(|scope| scope.trim().is_empty() || scope.contains(char::is_whitespace)) {
+                problems.push(format!("authorization.routes.'{}': scopes must be non-empty words", route));
+            }
+        }
+        if self.rbac.enabled() {
+            if self.rbac.groups_claim.is_empty() {
+                problems.push("rbac.groups_claim must not be empty".to_string());
+            }
+            if self.oauth.issuers.is_empty() {
+                problems.push("rbac.groups requires an oauth issuer".to_string());
+            }
+            for (group, scopes) in &self.rbac.groups {
+                if scopes.iter().any(|scope| scope.trim().is_empty() || scope.contains(char::is_whitespace)) {
+                    problems.push(format!("rbac.groups.'{}': scopes must be non-empty words", group));
+                }
+            }
+        }
+        if self.api_keys.requests_per_minute == 0 {
+            problems.push("api_keys.requests_per_minute must be at least 1".to_string());
+        }
+        if self.key_auth.challenge_ttl_secs == 0 {
+            problems.push("key_auth.challenge_ttl_secs must be at least 1".to_string());
+        }
+        if self.key_auth.session_ttl_secs == 0 {
+            problems.push("key_auth.session_ttl_secs must be at least 1".to_string());
+        }
+        if self.key_auth.enabled && self.key_auth.keys.is_empty() {
+            problems.push("key_auth.enabled requires at least one key under key_auth.keys".to_string());
+        }
+        for (key, scopes) in &self.key_auth.keys {
+            if !hex::decode(key).is_ok_and(|bytes| bytes.len() == 32) {
+                problems.push(format!("key_auth.keys.'{}' must be a hex encoded Ed25519 public key", key));
+            }
+            if scopes.iter().any(|scope| scope.trim().is_empty() || scope.contains(char::is_whitespace)) {
+                problems.push(format!("key_auth.keys.'{}': scopes must be non-empty words", key));
+            }
+        }
+        if self.client_identity.enabled {
+            problems.extend(self.client_identity_problems());
+        }
+        if self.tenancy.enabled() {
+            problems.extend(self.tenancy_problems());
+        }
+        for (index, issuer) in self.oauth.issuers.iter().enumerate() {
+            if issuer.issuer.is_empty() {
+                problems.push(format!("oauth.issuers[{}].issuer must not be empty", index));
+            }
+            if issuer.audience.is_empty() {
+                problems.push(format!("oauth.issuers[{}].audience must not be empty", index));
+            }
+            if reqwest::Url::parse(&issuer.jwks_url).is_err() {
+                problems.push(format!(
+                    "oauth.issuers[{}].jwks_url: '{}' is not a valid URL",
+                    index, issuer.jwks_url
+                ));
+            }
+        }
+        if let Some(introspection) = &self.oauth.introspection {
+            if !matches!(reqwest::Url::parse(&introspection.endpoint), Ok(url) if matches!(url.scheme(), "http" | "https")) {
+                problems.push(format!(
+                    "oauth.introspection.endpoint: '{}' must be an http:// or https:// URL",
+                    introspection.endpoint
+                ));
+            }
+            if introspection.client_id.is_empty() {
+                problems.push("oauth.introspection.client_id must not be empty".to_string());
+            }
+            if introspection.client_secret.is_empty() {
+                problems.push(
+                    "oauth.introspection.client_secret must be set (or OAUTH_INTROSPECTION_CLIENT_SECRET)".to_string(),
+                );
+            }
+            if self.oauth.issuers.is_empty() {
+                problems.push("oauth.introspection requires an oauth issuer".to_string());
+            }
+        }
+
+        problems
+    }
+
+    /// Describe every invalid audit trail setting
+    fn audit_problems(&self) -> Vec<String> {
+        let audit = &self.audit;
+        let mut problems = Vec::new();
+
+        if audit.batch_size == 0 {
+            problems.push("audit.batch_size must be at least 1".to_string());
+        }
+        if audit.checkpoint_interval == 0 {
+            problems.push("audit.checkpoint_interval must be at least 1".to_string());
+        }
+        match audit.sink {
+            Some(AuditSinkKind::SecureLog) if audit.path.is_none() => {
+                problems.push("audit.sink = \"secure_log\" requires audit.path".to_string());
+            }
+            Some(AuditSinkKind::Syslog) => {
+                let address = audit.syslog_address.as_deref().unwrap_or_default();
+                if std::net::ToSocketAddrs::to_socket_addrs(address).is_err() {
+                    problems.push(format!("audit.syslog_address: '{}' is not a socket address", address));
+                }
+            }
+            Some(AuditSinkKind::Http) => {
+                let url = audit.http_url.as_deref().unwrap_or_default();
+                if !matches!(reqwest::Url::parse(url), Ok(url) if matches!(url.scheme(), "http" | "https")) {
+                    problems.push(format!("audit.http_url: '{}' must be an http:// or https:// URL", url));
+                }
+            }
+            _ => {}
+        }
+
+        problems
+    }
+
+    /// Describe every invalid SIEM forwarding setting
+    fn siem_problems(&self) -> Vec<String> {
+        let siem = &self.siem;
+        let mut problems = Vec::new();
+        let Some(sink) = siem.sink else {
+            return problems;
+        };
+
+        match sink {
+            SiemSinkKind::SyslogTcp => {
+                let address = siem.address.as_deref().unwrap_or_default();
+                if std::net::ToSocketAddrs::to_socket_addrs(address).is_err() {
+                    problems.push(format!("siem.address: '{}' is not a socket address", address));
+                }
+            }
+            SiemSinkKind::Hec | SiemSinkKind::Webhook => {
+                let url = siem.url.as_deref().unwrap_or_default();
+                if !matches!(reqwest::Url::parse(url), Ok(url) if matches!(url.scheme(), "http" | "https")) {
+                    problems.push(format!("siem.url: '{}' must be an http:// or https:// URL", url));
+                }
+            }
+        }
+        if sink == SiemSinkKind::Hec && siem.token.is_none() {
+            problems.push("siem.sink = \"hec\" requires siem.token (or SIEM_TOKEN)".to_string());
+        }
+        if siem.source.is_empty() || siem.source.contains(char::is_whitespace) {
+            problems.push("siem.source must be a non-empty name without spaces".to_string());
+        }
+        if siem.buffer_capacity == 0 {
+            problems.push("siem.buffer_capacity must be at least 1".to_string());
+        }
+        if siem.batch_size == 0 {
+            problems.push("siem.batch_size must be at least 1".to_string());
+        }
+        if siem.retry_base_ms == 0 {
+            problems.push("siem.retry_base_ms must be at least 1".to_string());
+        }
+        if siem.retry_max_ms < siem.retry_base_ms {
+            problems.push("siem.retry_max_ms must not be less than siem.retry_base_ms".to_string());
+        }
+        if siem.timeout_ms == 0 {
+            problems.push("siem.timeout_ms must be at least 1".to_string());
+        }
+
+        problems
+    }
+
+    /// Describe every invalid event stream setting
+    fn event_stream_problems(&self) -> Vec<String> {
+        let events = &self.event_stream;
+        let mut problems = Vec::new();
+        let Some(sink) = events.sink else {
+            return problems;
+        };
+
+        if !sink.is_available() {
+            problems.push(format!(
+                "event_stream.sink = \"{}\" requires a relay built with the `{}` feature",
+                sink.feature(),
+                sink.feature()
+            ));
+        }
+        if events.servers.is_empty() || events.servers.iter().any(|server| server.trim().is_empty()) {
+            problems.push("event_stream.servers must list at least one server".to_string());
+        }
+        if events.topic.is_empty() || events.topic.contains(char::is_whitespace) {
+            problems.push(format!("event_stream.topic: '{}' must be a non-empty name without spaces", events.topic));
+        }
+        if events.max_attempts == 0 {
+            problems.push("event_stream.max_attempts must be at least 1".to_string());
+        }
+        if events.poll_interval_ms == 0 {
+            problems.push("event_stream.poll_interval_ms must be at least 1".to_string());
+        }
+        if events.publish_timeout_ms == 0 {
+            problems.push("event_stream.publish_timeout_ms must be at least 1".to_string());
+        }
+
+        problems
+    }
+
+    /// Describe every invalid timestamping setting
+    fn timestamping_problems(&self) -> Vec<String> {
+        let timestamping = &self.timestamping;
+        let mut problems = Vec::new();
+        let Some(url) = &timestamping.tsa_url else {
+            return problems;
+        };
+
+        if !matches!(reqwest::Url::parse(url), Ok(url) if matches!(url.scheme(), "http" | "https")) {
+            problems.push(format!("timestamping.tsa_url: '{}' must be an http:// or https:// URL", url));
+        }
+        if timestamping.timeout_ms == 0 {
+            problems.push("timestamping.timeout_ms must be at least 1".to_string());
+        }
+
+        problems
+    }
+
+    /// Describe every invalid live subscription setting
+    fn subscription_problems(&self) -> Vec<String> {
+        let subscriptions = &self.subscriptions;
+        let mut problems = Vec::new();
+
+        let prefix = &subscriptions.channel_prefix;
+        if prefix.is_empty() || prefix.contains(|c: char| c.is_whitespace() || c == '*' || c == '>') {
+            problems.push(format!(
+                "subscriptions.channel_prefix: '{}' must be a non-empty name without spaces or wildcards",
+                prefix
+            ));
+        }
+        if subscriptions.buffer == 0 {
+            problems.push("subscriptions.buffer must be at least 1".to_string());
+        }
+        if subscriptions.ping_interval_secs == 0 {
+            problems.push("subscriptions.ping_interval_secs must be at least 1".to_string());
+        }
+        if subscriptions.poll_timeout_secs == 0 {
+            problems.push("subscriptions.poll_timeout_secs must be at least 1".to_string());
+        }
+        match subscriptions.backplane {
+            Some(backplane) if !backplane.is_available() => {
+                problems.push(format!(
+                    "subscriptions.backplane = \"{}\" requires a relay built with the `{}` feature",
+                    backplane.feature(),
+                    backplane.feature()
+                ));
+            }
+            Some(BackplaneKind::Redis) if self.redis.url.is_none() => {
+                problems.push("subscriptions.backplane = \"redis\" requires redis.url".to_string());
+            }
+            Some(BackplaneKind::Nats)
+                if subscriptions.servers.is_empty() || subscriptions.servers.iter().any(|server| server.trim().is_empty()) =>
+            {
+                problems.push("subscriptions.servers must list at least one NATS server".to_string());
+            }
+            _ => {}
+        }
+
+        problems
+    }
+
+    /// Describe every invalid multi-tenant setting
+    fn tenancy_problems(&self) -> Vec<String> {
+        let tenancy = &self.tenancy;
+        let mut problems = Vec::new();
+
+        if tenancy.source == TenantSource::Header {
+            if axum::http::HeaderName::from_bytes(tenancy.header.as_bytes()).is_err() {
+                problems.push(format!("tenancy.header: '{}' is not a valid header name", tenancy.header));
+            }
+            if !tenancy.trusted_proxy && self.oauth.issuers.is_empty() {
+                problems.push(
+                    "tenancy.source = \"header\" requires tenancy.trusted_proxy or an oauth issuer to check the header against"
+                        .to_string(),
+                );
+            }
+        }
+        if tenancy.source == TenantSource::Claim {
+            if tenancy.claim.is_empty() {
+                problems.push("tenancy.claim must not be empty".to_string());
+            }
+            if self.oauth.issuers.is_empty() {
+                problems.push("tenancy.source = \"claim\" requires an oauth issuer".to_string());
+            }
+        }
+        if let Some(default_tenant) = &tenancy.default_tenant {
+            if !tenancy.tenants.contains_key(default_tenant) {
+                problems.push(format!("tenancy.default_tenant: '{}' is not a configured tenant", default_tenant));
+            }
+        }
+        for (id, tenant) in &tenancy.tenants {
+            if !crate::tenancy::is_valid_tenant_id(id) {
+                problems.push(format!(
+                    "tenancy.tenants: '{}' must be 1-64 lowercase letters, digits, '-' or '_'",
+                    id
+                ));
+            }
+            if let Some(policy) = &tenant.context_policy {
+                check_policy_name(&format!("tenancy.tenants.{}.context_policy", id), policy, &mut problems);
+            }
+            if tenant.message_retention_days.is_some_and(|days| days < 1) {
+                problems.push(format!("tenancy.tenants.{}.message_retention_days must be at least 1", id));
+            }
+        }
+
+        problems
+    }
+
+    /// Describe every invalid federation setting
+    fn federation_problems(&self) -> Vec<String> {
+        let federation = &self.federation;
+        let mut problems = Vec::new();
+
+        if federation.relay_id.is_none() {
+            problems.push("federation.peers requires federation.relay_id".to_string());
+        }
+        match &federation.signing_key {
+            Some(key) if !hex::decode(key).is_ok_and(|bytes| bytes.len() == 64) => {
+                problems.push("federation.signing_key must be a hex encoded 64-byte keypair".to_string())
+            }
+            Some(_) => {}
+            None => problems.push("federation.peers requires federation.signing_key (or FEDERATION_SIGNING_KEY)".to_string()),
+        }
+        if federation.reconcile_interval_secs == 0 {
+            problems.push("federation.reconcile_interval_secs must be at least 1".to_string());
+        }
+        if federation.client_cert_path.is_some() != federation.client_key_path.is_some() {
+            problems.push("federation.client_cert_path and federation.client_key_path must be set together".to_string());
+        }
+
+        problems
+    }
+
+    /// Describe every invalid client certificate authentication setting
+    fn client_identity_problems(&self) -> Vec<String> {
+        let client_identity = &self.client_identity;
+        let mut problems = Vec::new();
+
+        if self.tls.client_ca_path.is_none() && !client_identity.trust_forwarded_header {
+            problems.push(
+                "client_identity.enabled requires tls.client_ca_path or client_identity.trust_forwarded_header".to_string(),
+            );
+        }
+        if client_identity.trust_forwarded_header
+            && axum::http::HeaderName::from_bytes(client_identity.forwarded_header.as_bytes()).is_err()
+        {
+            problems.push(format!(
+                "client_identity.forwarded_header: '{}' is not a valid header name",
+                client_identity.forwarded_header
+            ));
+        }
+        if client_identity.identities.is_empty() {
+            problems.push("client_identity.enabled requires at least one client_identity.identities entry".to_string());
+        }
+        for (identity, scopes) in &client_identity.identities {
+            if identity.is_empty() || identity == "*" {
+                problems.push(format!("client_identity.identities: '{}' must name an identity or prefix", identity));
+            }
+            if scopes.iter().any(|scope| scope.trim().is_empty() || scope.contains(char::is_whitespace)) {
+                problems.push(format!("client_identity.identities.'{}': scopes must be non-empty words", identity));
+            }
+        }
+
+        problems
+    }
+
+    /// URLs the relay sends requests to, by setting
+    fn outbound_urls(&self) -> Vec<(String, &str)> {
+        let mut urls: Vec<(String, &str)> = self
+            .oauth
+            .issuers
+            .iter()
+            .enumerate()
+            .map(|(index, issuer)| (format!("oauth.issuers[{}].jwks_url", index), issuer.jwks_url.as_str()))
+            .collect();
+        let optional = [
+            ("oauth.introspection.endpoint", self.oauth.introspection.as_ref().map(|introspection| introspection.endpoint.as_str())),
+            ("audit.http_url", self.audit.http_url.as_deref()),
+            ("timestamping.tsa_url", self.timestamping.tsa_url.as_deref()),
+            ("siem.url", self.siem.url.as_deref()),
+        ];
+        urls.extend(optional.into_iter().filter_map(|(setting, url)| Some((setting.to_string(), url?))));
+        urls
+    }
+
+    /// Make process-wide settings available to the request handlers
+    ///
+    /// Only the first installed configuration takes effect. Fails, installing
+    /// nothing, if a plugin cannot be loaded or a verification check is unknown.
+    pub fn install(&self) -> Result<(), ConfigError> {
+        let plugins = crate::plugins::load(&self.plugins).map_err(|e| ConfigError::Invalid(vec![e.to_string()]))?;
+        let pipeline = crate::checks::VerificationPipeline::from_names(&self.verification.checks)
+            .map_err(|e| ConfigError::Invalid(vec![format!("verification.checks: {}", e)]))?;
+        let _ = REVOCATION_CHECK.set(self.features.revocation_check);
+        let _ = LEGACY_PROOFS.set(self.features.legacy_proofs);
+        let _ = HYBRID_POLICY.set(self.features.hybrid_policy.into());
+        crate::egress::install(crate::egress::EgressPolicy::new(&self.egress));
+        crate::log_redaction::install(crate::log_redaction::LogRedactor::new(&self.logging));
+        crate::checks::install(plugins.into_iter().fold(pipeline, |pipeline, plugin| pipeline.with_check(plugin)));
+        if self.verification.offload {
+            crate::verification_pool::install(crate::verification_pool::VerificationPool::new(&self.verification));
+        }
+        Ok(())
+    }
+}
+
+/// Whether proofs are checked against the revocation list
+///
+/// Uses the installed configuration, falling back to `REVOCATION_CHECK_ENABLED`.
+pub fn revocation_check_enabled() -> bool {
+    match REVOCATION_CHECK.get() {
+        Some(enabled) => *enabled,
+        None => std::env::var("REVOCATION_CHECK_ENABLED").unwrap_or_else(|_| "false".to_string()) == "true",
+    }
+}
+
+/// Whether legacy raw-signature proofs are accepted
+///
+/// Uses the installed configuration, falling back to `LEGACY_PROOFS_ACCEPTED`
+/// (accepted unless set to `false`).
+pub fn legacy_proofs_accepted() -> bool {
+    match LEGACY_PROOFS.get() {
+        Some(accepted) => *accepted,
+        None => std::env::var("LEGACY_PROOFS_ACCEPTED").map_or(true, |value| value != "false"),
+    }
+}
+
+/// Which halves of hybrid proofs must verify
+///
+/// Uses the installed configuration, falling back to transitional.
+pub fn hybrid_policy() -> HybridPolicy {
+    HYBRID_POLICY.get().copied().unwrap_or(HybridPolicy::Transitional)
+}
+
+/// Record a problem if `policy` is not a standard compliance policy
+fn check_policy_name(setting: &str, policy: &str, problems: &mut Vec<String>) {
+    let registry = proof_messenger_protocol::compliance::PolicyRegistry::new();
+    if registry.get_policy(policy).is_none() {
+        let mut known = registry.list_policy_types();
+        known.sort();
+        problems.push(format!(
+            "{}: '{}' is not a known policy (expected one of: {})",
+            setting,
+            policy,
+            known.join(", ")
+        ));
+    }
+}
+
+/// Apply a numeric environment override, recording unparseable values
+fn override_number<T: std::str::FromStr>(
+    env: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    problems: &mut Vec<String>,
+    apply: impl FnOnce(T),
+) where
+    T::Err: std::fmt::Display,
+{
+    if let Some(value) = env(name) {
+        match value.parse() {
+            Ok(number) => apply(number),
+            Err(e) => problems.push(format!("{}: '{}' is not a valid number ({})", name, value, e)),
+        }
+    }
+}
+
+/// Apply a boolean environment override, recording unparseable values
+fn override_bool(
+    env: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    problems: &mut Vec<String>,
+    apply: impl FnOnce(bool),
+) {
+    match env(name).as_deref() {
+        Some("true") => apply(true),
+        Some("false") => apply(false),
+        Some(other) => problems.push(format!("{}: '{}' must be 'true' or 'false'", name, other)),
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_file_settings_with_env_overrides() {
+        // ARRANGE: A config file setting a few sections
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut file,
+            br#"
+                [server]
+                bind_address = "127.0.0.1:9000"
+
+                [rate_limit]
+                per_second = 10
+
+                [[oauth.issuers]]
+                issuer = "https://auth.example.com/"
+                audience = "proof-messenger-api"
+                jwks_url = "https://auth.example.com/.well-known/jwks.json"
+
+                [features]
+                quarantine = true
+            "#,
+        )
+        .unwrap();
+
+        // ACT: Load it and override the port and CORS origins
+        let mut config = RelayConfig::from_file(file.path()).unwrap();
+        let problems = config.apply_overrides(env(&[
+            ("PORT", "3000"),
+            ("CORS_ALLOWED_ORIGINS", "https://a.example.com, https://b.example.com"),
+            ("READ_ONLY_MODE", "true"),
+        ]));
+
+        // ASSERT: File values, env overrides and defaults are combined
+        assert!(problems.is_empty());
+        assert!(config.validate().is_ok());
+        assert_eq!(config.server.bind_address, "127.0.0.1:3000".parse().unwrap());
+        assert_eq!(config.rate_limit, RateLimitConfig { per_second: 10, burst_size: 5 });
+        assert_eq!(config.cors.allowed_origins, vec!["https://a.example.com", "https://b.example.com"]);
+        assert_eq!(config.oauth.issuers.len(), 1);
+        assert!(config.features.quarantine);
+        assert!(!config.features.revocation_check);
+        assert!(config.features.legacy_proofs);
+        assert_eq!(config.database, DatabaseConfig::default());
+        assert_eq!(config.maintenance, MaintenanceConfig { read_only: true, retry_after_secs: 300 });
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"[rate_limit]\nper_sec = 10\n").unwrap();
+
+        let error = RelayConfig::from_file(file.path()).unwrap_err().to_string();
+
+        assert!(error.contains("per_sec"), "{}", error);
+    }
+
+    #[test]
+    fn test_every_problem_is_reported() {
+        let mut config = RelayConfig::default();
+        let mut problems = config.apply_overrides(env(&[
+            ("PORT", "http"),
+            ("RATE_LIMIT_BURST_SIZE", "0"),
+            ("CORS_ALLOWED_ORIGINS", "app.example.com"),
+            ("REVOCATION_CHECK_ENABLED", "yes"),
+        ]));
+        problems.extend(config.problems());
+
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert!(problems[0].starts_with("PORT"));
+        assert!(problems[1].starts_with("REVOCATION_CHECK_ENABLED"));
+        assert!(problems[2].starts_with("rate_limit.burst_size"));
+        assert!(problems[3].starts_with("cors.allowed_origins"));
+    }
+
+    #[test]
+    fn test_grpc_address_must_differ_from_http_address() {
+        let mut config = RelayConfig::default();
+        let problems = config.apply_overrides(env(&[("GRPC_BIND_ADDRESS", "0.0.0.0:8080")]));
+
+        assert!(problems.is_empty());
+        assert_eq!(config.server.grpc_bind_address, Some(config.server.bind_address));
+        assert_eq!(config.problems(), vec!["server.grpc_bind_address must differ from server.bind_address"]);
+    }
+
+    #[test]
+    fn test_legacy_proofs_can_be_turned_off() {
+        let mut config = RelayConfig::default();
+        let problems = config.apply_overrides(env(&[("LEGACY_PROOFS_ACCEPTED", "false")]));
+
+        assert!(problems.is_empty());
+        assert!(!config.features.legacy_proofs);
+    }
+
+    #[test]
+    fn test_hybrid_policy_is_read_from_file_and_env() {
+        let mut config: RelayConfig = toml::from_str("[features]\nhybrid_policy = \"strict\"\n").unwrap();
+        assert_eq!(config.features.hybrid_policy, HybridProofPolicy::Strict);
+        assert_eq!(HybridPolicy::from(config.features.hybrid_policy), HybridPolicy::Strict);
+
+        let problems = config.apply_overrides(env(&[("HYBRID_PROOF_POLICY", "Transitional")]));
+        assert!(problems.is_empty());
+        assert_eq!(config.features.hybrid_policy, HybridProofPolicy::Transitional);
+
+        let problems = config.apply_overrides(env(&[("HYBRID_PROOF_POLICY", "lenient")]));
+        assert_eq!(problems, vec!["HYBRID_PROOF_POLICY: 'lenient' must be 'strict' or 'transitional'"]);
+        assert!(toml::from_str::<RelayConfig>("[features]\nhybrid_policy = \"lenient\"\n").is_err());
+    }
+
+    #[test]
+    fn test_redis_and_replay_protection_from_env() {
+        let mut config = RelayConfig::default();
+        let problems = config.apply_overrides(env(&[
+            ("REDIS_URL", "redis://redis:6379/0"),
+            ("REPLAY_PROTECTION_ENABLED", "true"),
+        ]));
+
+        assert!(problems.is_empty());
+        assert_eq!(config.redis.url.as_deref(), Some("redis://redis:6379/0"));
+        assert!(config.features.replay_protection);
+        if cfg!(feature = "redis") {
+            assert!(config.problems().is_empty());
+        } else {
+            assert_eq!(config.problems(), vec!["redis.url requires a relay built with the `redis` feature"]);
+        }
+
+        config.redis.url = Some("http://redis:6379".to_string());
+        config.retention.replay_window_secs = 0;
+        assert_eq!(config.problems(), vec![
+            "redis.url: 'http://redis:6379' must be a redis://, rediss:// or unix:// URL",
+            "retention.replay_window_secs must be at least 1",
+        ]);
+    }
+
+    #[test]
+    fn test_logged_headers_from_env() {
+        let mut config = RelayConfig::default();
+        let problems = config.apply_overrides(env(&[("LOG_HEADERS", "user-agent, x-forwarded-for"), ("LOG_REDACT_PII", "false")]));
+
+        assert!(problems.is_empty());
+        assert_eq!(config.logging.logged_headers, vec!["user-agent", "x-forwarded-for"]);
+        assert!(!config.logging.redact_pii);
+
+        config.logging.logged_headers.push("bad header".to_string());
+        assert_eq!(config.problems(), vec!["logging.logged_headers: 'bad header' is not a valid header name"]);
+    }
+
+    #[test]
+    fn test_context_policy_must_be_known() {
+        let mut config = RelayConfig::default();
+        let problems = config.apply_overrides(env(&[("CONTEXT_POLICY", "payroll")]));
+
+        assert!(problems.is_empty());
+        assert_eq!(config.features.context_policy.as_deref(), Some("payroll"));
+        assert_eq!(config.problems().len(), 1);
+        assert!(config.problems()[0].starts_with("features.context_policy"));
+
+        config.apply_overrides(env(&[("CONTEXT_POLICY", "transaction")]));
+        assert!(config.problems().is_empty());
+        config.apply_overrides(env(&[("CONTEXT_POLICY", "")]));
+        assert_eq!(config.features.context_policy, None);
+    }
+
+    #[test]
+    fn test_key_pinning_mode_is_parsed() {
+        let config: RelayConfig = toml::from_str("[features]\nkey_pinning = \"reject\"").unwrap();
+        assert_eq!(config.features.key_pinning, Some(KeyPinningMode::Reject));
+        assert_eq!(RelayConfig::default().features.key_pinning, None);
+
+        let mut config = RelayConfig::default();
+        assert!(config.apply_overrides(env(&[("KEY_PINNING", "flag")])).is_empty());
+        assert_eq!(config.features.key_pinning, Some(KeyPinningMode::Flag));
+        assert!(config.apply_overrides(env(&[("KEY_PINNING", "off")])).is_empty());
+        assert_eq!(config.features.key_pinning, None);
+        assert_eq!(
+            config.apply_overrides(env(&[("KEY_PINNING", "warn")])),
+            vec!["KEY_PINNING: 'warn' must be 'off', 'flag' or 'reject'"]
+        );
+    }
+
+    #[test]
+    fn test_tenancy_settings_are_validated() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut file,
+            br#"
+                [tenancy]
+                source = "claim"
+                default_tenant = "globex"
+
+                [tenancy.tenants.acme]
+                context_policy = "fintech_transfer"
+                message_retention_days = 365
+
+                [tenancy.tenants."Bad/Tenant"]
+                context_policy = "payroll"
+                message_retention_days = 0
+            "#,
+        )
+        .unwrap();
+
+        let config = RelayConfig::from_file(file.path()).unwrap();
+
+        assert!(config.tenancy.enabled());
+        assert_eq!(config.tenancy.source, TenantSource::Claim);
+        assert_eq!(config.tenancy.tenants["acme"].message_retention_days, Some(365));
+        let problems = config.problems();
+        assert_eq!(problems.len(), 5, "{:?}", problems);
+        assert!(problems[0].contains("requires an oauth issuer"));
+        assert!(problems[1].starts_with("tenancy.default_tenant"));
+        assert!(problems[2].contains("'Bad/Tenant'"));
+        assert!(problems[3].starts_with("tenancy.tenants.Bad/Tenant.context_policy"));
+        assert!(problems[4].ends_with("message_retention_days must be at least 1"));
+
+        // An unchecked tenant header is only accepted from a trusted proxy
+        let mut header: RelayConfig = toml::from_str("[tenancy.tenants.acme]\n").unwrap();
+        assert_eq!(header.tenancy.source, TenantSource::Header);
+        assert!(header.problems()[0].contains("requires tenancy.trusted_proxy"));
+        header.tenancy.trusted_proxy = true;
+        assert!(header.problems().is_empty(), "{:?}", header.problems());
+    }
+
+    #[test]
+    fn test_audit_sink_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+
+        let secure_log = parse("[audit]\nsink = \"secure_log\"\nbatch_size = 0\ncheckpoint_interval = 0\n");
+        let syslog = parse("[audit]\nsink = \"syslog\"\nsyslog_address = \"localhost\"\n");
+        let http = parse("[audit]\nsink = \"http\"\nhttp_url = \"ftp://siem.example.com\"\n");
+        let valid = parse("[audit]\nsink = \"http\"\nhttp_url = \"https://siem.example.com/ingest\"\n");
+
+        assert_eq!(
+            secure_log.problems(),
+            vec![
+                "audit.batch_size must be at least 1",
+                "audit.checkpoint_interval must be at least 1",
+                "audit.sink = \"secure_log\" requires audit.path",
+            ]
+        );
+        assert_eq!(syslog.problems(), vec!["audit.syslog_address: 'localhost' is not a socket address"]);
+        assert_eq!(http.problems().len(), 1);
+        assert!(valid.problems().is_empty());
+        assert_eq!(valid.audit.batch_size, 50);
+        assert_eq!(valid.audit.checkpoint_interval, 100);
+    }
+
+    #[test]
+    fn test_event_stream_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+
+        let disabled = parse("[event_stream]\nservers = []\n");
+        let invalid = parse("[event_stream]\nsink = \"nats\"\ntopic = \"verified messages\"\nmax_attempts = 0\n");
+        let valid = parse("[event_stream]\nsink = \"kafka\"\nservers = [\"localhost:9092\"]\n");
+
+        assert!(disabled.problems().is_empty());
+        let problems = invalid.problems();
+        assert!(problems.contains(&"event_stream.servers must list at least one server".to_string()));
+        assert!(problems.contains(&"event_stream.topic: 'verified messages' must be a non-empty name without spaces".to_string()));
+        assert!(problems.contains(&"event_stream.max_attempts must be at least 1".to_string()));
+        if cfg!(feature = "kafka") {
+            assert!(valid.problems().is_empty());
+        } else {
+            assert_eq!(valid.problems(), vec!["event_stream.sink = \"kafka\" requires a relay built with the `kafka` feature"]);
+        }
+        assert_eq!(valid.event_stream.topic, "proof-messenger.messages.verified");
+    }
+
+    #[test]
+    fn test_integrity_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+
+        let invalid = parse("[integrity]\ninterval_secs = 0\nbatch_size = 0\n");
+        assert_eq!(
+            invalid.problems(),
+            vec!["integrity.interval_secs must be at least 1", "integrity.batch_size must be at least 1"]
+        );
+
+        let mut config = parse("");
+        let problems = config.apply_overrides(env(&[("INTEGRITY_CHECK_ENABLED", "true"), ("INTEGRITY_CHECK_INTERVAL_SECS", "3600")]));
+        assert!(problems.is_empty());
+        assert!(config.integrity.enabled);
+        assert_eq!(config.integrity.interval_secs, 3600);
+        assert_eq!(config.integrity.batch_size, 500);
+    }
+
+    #[test]
+    fn test_scheduling_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+
+        let invalid = parse("[scheduling]\npoll_interval_secs = 0\nmax_delay_secs = 0\nbatch_size = 0\n");
+        assert_eq!(
+            invalid.problems(),
+            vec![
+                "scheduling.poll_interval_secs must be at least 1",
+                "scheduling.max_delay_secs must be at least 1",
+                "scheduling.batch_size must be at least 1"
+            ]
+        );
+
+        let mut config = parse("");
+        let problems = config.apply_overrides(env(&[("SCHEDULED_MESSAGES_ENABLED", "true"), ("SCHEDULED_MESSAGES_POLL_INTERVAL_SECS", "30")]));
+        assert!(problems.is_empty());
+        assert!(config.scheduling.enabled);
+        assert_eq!(config.scheduling.poll_interval_secs, 30);
+        assert_eq!(config.scheduling.max_delay_secs, 2_592_000);
+    }
+
+    #[test]
+    fn test_verification_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+
+        let invalid = parse("[verification]\nchecks = [\"format\", \"sanctions\", \"format\"]\noffload = true\nmax_concurrent = 0\n");
+        assert_eq!(
+            invalid.problems(),
+            vec![
+                "verification.max_concurrent must be at least 1",
+                "verification.checks: unknown check 'sanctions'",
+                "verification.checks: 'format' is listed twice",
+                "verification.checks must include 'proof'"
+            ]
+        );
+
+        let mut config = parse("");
+        assert_eq!(config.verification, VerificationConfig::default());
+        let problems = config.apply_overrides(env(&[("VERIFICATION_OFFLOAD", "true"), ("VERIFICATION_MAX_CONCURRENT", "4")]));
+        assert!(problems.is_empty());
+        assert!(config.verification.offload);
+        assert_eq!(config.verification.max_concurrent, Some(4));
+    }
+
+    #[test]
+    fn test_install_rejects_unknown_checks() {
+        let config: RelayConfig = toml::from_str("[verification]\nchecks = [\"format\", \"sanctions\", \"proof\"]\n").unwrap();
+
+        let result = config.install();
+
+        assert!(matches!(result, Err(ConfigError::Invalid(problems)) if problems[0].contains("sanctions")));
+    }
+
+    #[test]
+    fn test_limits_readiness_and_webhooks_from_file_and_env() {
+        let mut config: RelayConfig = toml::from_str(
+            "[limits]\nmax_context_bytes = 1024\n\n[readiness]\ncheck_timeout_ms = 500\n\n[webhooks]\nmax_attempts = 3\n",
+        )
+        .unwrap();
+
+        let problems = config.apply_overrides(env(&[
+            ("MAX_MESSAGE_BODY_BYTES", "2048"),
+            ("READINESS_MAX_WEBHOOK_QUEUE", "50"),
+            ("WEBHOOK_ALLOW_INSECURE_URLS", "true"),
+            ("WEBHOOK_TIMEOUT_SECS", "soon"),
+        ]));
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("WEBHOOK_TIMEOUT_SECS"));
+        assert_eq!(
+            config.limits,
+            LimitsConfig { max_context_bytes: 1024, max_message_body_bytes: 2048, ..LimitsConfig::default() }
+        );
+        assert_eq!(config.readiness, ReadinessChecksConfig { check_timeout_ms: 500, max_webhook_queue: 50 });
+        assert_eq!(config.webhooks.max_attempts, 3);
+        assert!(config.webhooks.allow_insecure_urls);
+        assert_eq!(config.webhooks.timeout_secs, 10);
+
+        config.limits.max_request_body_bytes = 0;
+        config.webhooks.max_attempts = 0;
+        assert_eq!(
+            config.problems(),
+            vec!["limits.max_request_body_bytes must be at least 1", "webhooks.max_attempts must be at least 1"]
+        );
+    }
+
+    #[test]
+    fn test_federation_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+        let peers = "[[federation.peers]]\nid = \"eu\"\nurl = \"https://eu.example.com\"\npublic_key = \"00\"\n";
+
+        let disabled = parse("[federation]\nrelay_id = \"us\"\n");
+        let incomplete = parse(&format!("[federation]\nclient_cert_path = \"/etc/relay/client.pem\"\n\n{}", peers));
+        let mut from_env = parse(&format!("[federation]\nrelay_id = \"us\"\nmax_hops = 2\n\n{}", peers));
+        let problems = from_env.apply_overrides(env(&[
+            ("FEDERATION_SIGNING_KEY", &"ab".repeat(64)),
+            ("FEDERATION_RECONCILE_WINDOW_MINUTES", "30"),
+        ]));
+
+        assert!(disabled.problems().is_empty());
+        assert_eq!(
+            incomplete.problems(),
+            vec![
+                "federation.peers requires federation.relay_id",
+                "federation.peers requires federation.signing_key (or FEDERATION_SIGNING_KEY)",
+                "federation.client_cert_path and federation.client_key_path must be set together",
+            ]
+        );
+        assert!(problems.is_empty());
+        assert!(from_env.problems().is_empty());
+        assert_eq!(from_env.federation.peers[0].id, "eu");
+        assert_eq!(from_env.federation.max_hops, 2);
+        assert_eq!(from_env.federation.reconcile_window_minutes, 30);
+
+        let mut bad_peers = RelayConfig::default();
+        let problems = bad_peers.apply_overrides(env(&[("FEDERATION_PEERS", "[{\"id\": \"eu\"}]")]));
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("FEDERATION_PEERS"));
+    }
+
+    #[test]
+    fn test_egress_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+
+        let invalid = parse(concat!(
+            "[egress]\nproxy = \"socks5://proxy:1080\"\nallowed_hosts = [\"https://auth.example.com\", \"*.peers.example.com\"]\n",
+            "[[oauth.issuers]]\nissuer = \"https://auth.example.com/\"\naudience = \"relay\"\njwks_url = \"https://keys.example.org/jwks.json\"\n",
+            "[timestamping]\ntsa_url = \"https://tsa.peers.example.com/tsr\"\n",
+        ));
+        assert_eq!(
+            invalid.problems(),
+            vec![
+                "egress.proxy: 'socks5://proxy:1080' must be an http:// or https:// URL",
+                "egress.allowed_hosts: 'https://auth.example.com' must be a host such as api.example.com or *.example.com",
+                "oauth.issuers[0].jwks_url: keys.example.org is not in egress.allowed_hosts",
+            ]
+        );
+
+        let mut config = parse("");
+        let problems = config.apply_overrides(env(&[
+            ("HTTPS_PROXY", "http://proxy.corp.example:3128"),
+            ("NO_PROXY", "localhost, .internal"),
+            ("EGRESS_ALLOWED_HOSTS", "auth.example.com,*.peers.example.com"),
+        ]));
+        assert!(problems.is_empty());
+        assert_eq!(config.egress.proxy.as_deref(), Some("http://proxy.corp.example:3128"));
+        assert_eq!(config.egress.no_proxy, vec!["localhost", ".internal"]);
+        assert_eq!(config.egress.allowed_hosts, vec!["auth.example.com", "*.peers.example.com"]);
+        assert!(config.problems().is_empty());
+    }
+
+    #[test]
+    fn test_plugin_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+
+        let invalid = parse(concat!(
+            "[[plugins]]\nname = \"sanctions\"\npath = \"/etc/relay/plugins/sanctions.wasm\"\n",
+            "[[plugins]]\nname = \"sanctions\"\npath = \"\"\nfuel = 0\n",
+            "[[plugins]]\nname = \"proof\"\npath = \"proof.wasm\"\nmax_memory_bytes = 1024\ntimeout_ms = 0\n",
+        ));
+        let mut expected = vec![
+            "plugins[1].name: 'sanctions' is used twice",
+            "plugins[1].path must not be empty",
+            "plugins[1].fuel must be at least 1",
+            "plugins[2].name: 'proof' is a built-in check",
+            "plugins[2].max_memory_bytes must be at least 65536",
+            "plugins[2].timeout_ms must be at least 1",
+        ];
+        if !cfg!(feature = "wasm-plugins") {
+            expected.insert(0, "plugins require a relay built with the `wasm-plugins` feature");
+        }
+        assert_eq!(invalid.problems(), expected);
+
+        let config = parse("[[plugins]]\nname = \"sanctions\"\npath = \"sanctions.wasm\"\n");
+        assert_eq!(config.plugins[0].fuel, 10_000_000);
+        assert_eq!(config.plugins[0].timeout_ms, 100);
+        assert!(!config.plugins[0].fail_open);
+    }
+
+    #[test]
+    fn test_key_auth_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+        let key = "ab".repeat(32);
+
+        let invalid = parse("[key_auth]\nenabled = true\nchallenge_ttl_secs = 0\nsession_ttl_secs = 0\n");
+        let bad_keys = parse("[key_auth.keys]\n\"not-a-key\" = [\"proof:create\"]\n");
+        let bad_scopes = parse(&format!("[key_auth.keys]\n\"{}\" = [\"proof create\"]\n", key));
+        assert_eq!(
+            invalid.problems(),
+            vec![
+                "key_auth.challenge_ttl_secs must be at least 1",
+                "key_auth.session_ttl_secs must be at least 1",
+                "key_auth.enabled requires at least one key under key_auth.keys"
+            ]
+        );
+        assert_eq!(bad_keys.problems(), vec!["key_auth.keys.'not-a-key' must be a hex encoded Ed25519 public key"]);
+        assert_eq!(bad_scopes.problems(), vec![format!("key_auth.keys.'{}': scopes must be non-empty words", key)]);
+
+        let mut config = parse(&format!("[key_auth.keys]\n\"{}\" = [\"proof:create\", \"message:read\"]\n", key));
+        let problems = config.apply_overrides(env(&[("KEY_AUTH_ENABLED", "true"), ("KEY_AUTH_SESSION_TTL_SECS", "300")]));
+        assert!(problems.is_empty());
+        assert!(config.problems().is_empty());
+        assert!(config.key_auth.enabled);
+        assert_eq!(config.key_auth.session_ttl_secs, 300);
+        assert_eq!(config.key_auth.challenge_ttl_secs, 60);
+    }
+
+    #[test]
+    fn test_siem_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+
+        let disabled = parse("[siem]\nbatch_size = 0\n");
+        let hec = parse("[siem]\nsink = \"hec\"\nurl = \"https://splunk.example.com:8088/services/collector/event\"\n");
+        let syslog = parse("[siem]\nsink = \"syslog_tcp\"\naddress = \"localhost\"\nbuffer_capacity = 0\n");
+        let webhook = parse("[siem]\nsink = \"webhook\"\nurl = \"https://soc.example.com/events\"\n");
+
+        assert!(disabled.problems().is_empty());
+        assert_eq!(hec.problems(), vec!["siem.sink = \"hec\" requires siem.token (or SIEM_TOKEN)"]);
+        assert_eq!(
+            syslog.problems(),
+            vec![
+                "siem.address: 'localhost' is not a socket address",
+                "siem.buffer_capacity must be at least 1",
+            ]
+        );
+        assert!(webhook.problems().is_empty());
+
+        let mut hec = hec;
+        assert!(hec.apply_overrides(env(&[("SIEM_TOKEN", "hec-token")])).is_empty());
+        assert!(hec.problems().is_empty());
+        assert_eq!(hec.siem.token.as_deref(), Some("hec-token"));
+    }
+
+    #[test]
+    fn test_timestamping_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+
+        let invalid = parse("[timestamping]\ntsa_url = \"ftp://tsa.example.com\"\ntimeout_ms = 0\n");
+        let valid = parse("[timestamping]\ntsa_url = \"https://tsa.example.com/tsr\"\n");
+
+        assert_eq!(
+            invalid.problems(),
+            vec![
+                "timestamping.tsa_url: 'ftp://tsa.example.com' must be an http:// or https:// URL",
+                "timestamping.timeout_ms must be at least 1",
+            ]
+        );
+        assert!(valid.problems().is_empty());
+        assert!(valid.timestamping.cert_req);
+
+        let mut config = RelayConfig::default();
+        assert!(config.apply_overrides(env(&[("TSA_URL", "http://localhost:3180")])).is_empty());
+        assert_eq!(config.timestamping.tsa_url.as_deref(), Some("http://localhost:3180"));
+        assert!(config.apply_overrides(env(&[("TSA_URL", "")])).is_empty());
+        assert_eq!(config.timestamping.tsa_url, None);
+    }
+
+    #[test]
+    fn test_legacy_sunset_is_read() {
+        let parsed: RelayConfig = toml::from_str("[api]\nlegacy_sunset = \"2027-06-30T00:00:00Z\"\n").unwrap();
+        assert_eq!(parsed.api.legacy_sunset.unwrap().to_rfc3339(), "2027-06-30T00:00:00+00:00");
+
+        let mut config = RelayConfig::default();
+        assert!(config.apply_overrides(env(&[("LEGACY_API_SUNSET", "2027-01-01T12:00:00+02:00")])).is_empty());
+        assert_eq!(config.api.legacy_sunset.unwrap().to_rfc3339(), "2027-01-01T10:00:00+00:00");
+        assert_eq!(
+            config.apply_overrides(env(&[("LEGACY_API_SUNSET", "next year")])).len(),
+            1
+        );
+        assert!(config.apply_overrides(env(&[("LEGACY_API_SUNSET", "")])).is_empty());
+        assert_eq!(config.api.legacy_sunset, None);
+    }
+
+    #[test]
+    fn test_abuse_detection_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+
+        let invalid = parse("[abuse_detection]\nenabled = true\nwindow_secs = 0\nmax_messages = 0\n");
+        let disabled = parse("[abuse_detection]\nwindow_secs = 0\n");
+
+        assert_eq!(
+            invalid.problems(),
+            vec![
+                "abuse_detection.window_secs must be at least 1",
+                "abuse_detection.max_messages must be at least 1",
+            ]
+        );
+        assert!(disabled.problems().is_empty());
+
+        let mut config = RelayConfig::default();
+        assert!(config
+            .apply_overrides(env(&[("ABUSE_DETECTION_ENABLED", "true"), ("ABUSE_QUARANTINE_SECS", "900")]))
+            .is_empty());
+        assert!(config.abuse_detection.enabled);
+        assert_eq!(config.abuse_detection.quarantine_secs, 900);
+        assert_eq!(config.abuse_detection.max_revoked_replays, 3);
+    }
+
+    #[test]
+    fn test_subscription_settings_are_validated() {
+        let parse = |toml: &str| -> RelayConfig { toml::from_str(toml).unwrap() };
+
+        let local = parse("[subscriptions]\nbuffer = 16\n");
+        let invalid = parse("[subscriptions]\nbackplane = \"nats\"\nchannel_prefix = \"groups.>\"\nbuffer = 0\npoll_timeout_secs = 0\n");
+        let redis = parse("[subscriptions]\nbackplane = \"redis\"\n");
+
+        assert!(local.problems().is_empty());
+        let problems = invalid.problems();
+        assert!(problems.contains(
+            &"subscriptions.channel_prefix: 'groups.>' must be a non-empty name without spaces or wildcards".to_string()
+        ));
+        assert!(problems.contains(&"subscriptions.buffer must be at least 1".to_string()));
+        assert!(problems.contains(&"subscriptions.poll_timeout_secs must be at least 1".to_string()));
+        if cfg!(feature = "nats") {
+            assert!(problems.contains(&"subscriptions.servers must list at least one NATS server".to_string()));
+        }
+        if cfg!(feature = "redis") {
+            assert_eq!(redis.problems(), vec!["subscriptions.backplane = \"redis\" requires redis.url"]);
+        } else {
+            assert_eq!(redis.problems(), vec!["subscriptions.backplane = \"redis\" requires a relay built with the `redis` feature"]);
+        }
+        assert_eq!(local.subscriptions.channel_prefix, "proof-messenger.groups");
+    }
+
+    #[test]
+    fn test_authorization_routes_are_validated() {
+        let config: RelayConfig = toml::from_str(
+            r#"
+            [authorization.routes]
+            "GET /quarantine" = ["quarantine:read", "admin"]
+            "POST /invites/:code/redeem" = []
+            "/relay" = ["proof:create"]
+            "GET /threads/:thread_id" = ["message read"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.problems(),
+            vec![
+                "authorization.routes requires an authentication method (oauth.issuers, api_keys, key_auth or client_identity)",
+                "authorization.routes: '/relay' must be a method and path such as 'GET /quarantine'",
+                "authorization.routes.'GET /threads/:thread_id': scopes must be non-empty words",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rbac_groups_are_validated() {
+        let config: RelayConfig = toml::from_str(
+            r#"
+            [rbac.groups]
+            "proof-admins" = ["proof:revoke", "proof:manage"]
+            "auditors" = ["audit read"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.rbac.groups_claim, "groups");
+        assert_eq!(
+            config.problems(),
+            vec![
+                "rbac.groups requires an oauth issuer",
+                "rbac.groups.'auditors': scopes must be non-empty words",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_client_identity_is_validated() {
+        let config: RelayConfig = toml::from_str(
+            r#"
+            [client_identity]
+            enabled = true
+
+            [client_identity.identities]
+            "spiffe://mesh.example.com/ns/payments/*" = ["proof:create"]
+            "*" = ["message:read"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.client_identity.forwarded_header, "x-forwarded-client-cert");
+        assert_eq!(
+            config.problems(),
+            vec![
+                "client_identity.enabled requires tls.client_ca_path or client_identity.trust_forwarded_header",
+                "client_identity.identities: '*' must name an identity or prefix",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_introspection_secret_comes_from_the_environment() {
+        let mut config: RelayConfig = toml::from_str(
+            r#"
+            [[oauth.issuers]]
+            issuer = "https://auth.example.com/"
+            audience = "proof-messenger-api"
+            jwks_url = "https://auth.example.com/.well-known/jwks.json"
+
+            [oauth.introspection]
+            endpoint = "https://auth.example.com/oauth2/introspect"
+            client_id = "relay"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.problems(),
+            vec!["oauth.introspection.client_secret must be set (or OAUTH_INTROSPECTION_CLIENT_SECRET)"]
+        );
+
+        let problems = config.apply_overrides(|name| {
+            (name == "OAUTH_INTROSPECTION_CLIENT_SECRET").then(|| "s3cret".to_string())
+        });
+
+        assert!(problems.is_empty());
+        assert!(config.problems().is_empty());
+        let introspection = config.oauth.introspection.unwrap();
+        assert_eq!(introspection.client_secret, "s3cret");
+        assert_eq!(introspection.cache_seconds, 60);
+    }
+
+    #[test]
+    fn test_newer_schema_policy_is_parsed() {
+        let config: RelayConfig = toml::from_str("[database]\non_newer_schema = \"ignore\"").unwrap();
+
+        assert_eq!(config.database.on_newer_schema, NewerSchemaPolicy::Ignore);
+        assert_eq!(RelayConfig::default().database.on_newer_schema, NewerSchemaPolicy::Refuse);
+        assert!(toml::from_str::<RelayConfig>("[database]\non_newer_schema = \"panic\"").is_err());
+    }
+
+    #[test]
+    fn test_pool_settings_are_validated() {
+        let config: RelayConfig = toml::from_str(
+            r#"
+            [database]
+            url = "sqlite:/app/db/messages.db"
+            max_connections = 4
+            min_connections = 8
+            statement_timeout_ms = 0
+            read_replica_url = "sqlite:/app/db/messages.db"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.problems(),
+            vec![
+                "database.min_connections must not exceed database.max_connections",
+                "database.statement_timeout_ms must be at least 1",
+                "database.read_replica_url must name a database other than database.url",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sqlite_pragmas_are_parsed() {
+        let config: RelayConfig = toml::from_str(
+            r#"
+            [database]
+            journal_mode = "delete"
+            busy_timeout_ms = 250
+            synchronous = "full"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.database.journal_mode, JournalMode::Delete);
+        assert_eq!(config.database.busy_timeout_ms, 250);
+        assert_eq!(config.database.synchronous, SynchronousMode::Full);
+        assert_eq!(RelayConfig::default().database.journal_mode, JournalMode::Wal);
+    }
+
+    #[test]
+    fn test_erasure_grace_period() {
+        let mut config: RelayConfig = toml::from_str("[retention]\nerasure_grace_days = 7").unwrap();
+        assert_eq!(config.retention.erasure_grace_days, 7);
+
+        let problems = config.apply_overrides(|name| (name == "ERASURE_GRACE_DAYS").then(|| "-1".to_string()));
+
+        assert!(problems.is_empty());
+        assert_eq!(config.problems(), vec!["retention.erasure_grace_days must not be negative"]);
+    }
+
+    #[test]
+    fn test_expiry_purge_interval() {
+        let mut config: RelayConfig = toml::from_str("[retention]\nexpiry_purge_interval_secs = 300").unwrap();
+        assert_eq!(config.retention.expiry_purge_interval_secs, 300);
+        assert_eq!(RelayConfig::default().retention.expiry_purge_interval_secs, 60);
+
+        let problems = config.apply_overrides(|name| (name == "MESSAGE_EXPIRY_PURGE_INTERVAL_SECS").then(|| "0".to_string()));
+
+        assert!(problems.is_empty());
+        assert_eq!(config.problems(), vec!["retention.expiry_purge_interval_secs must be at least 1"]);
+    }
+}
@@ -0,0 +1,320 @@
+//! Context Policy Enforcement Module
+//!
+//! The protocol crate's compliance module defines [`DataPolicy`]s naming the
+//! fields a signed context must, may and must never carry. By default the
+//! relay stores any context whose proof verifies. With a context policy
+//! configured, each verified message's context is also decoded as a JSON
+//! object and checked against the named policy before it is stored:
+//!
+//! - contexts carrying a forbidden field, missing a required field or not
+//!   holding a JSON object are rejected with `422 Unprocessable Entity`
+//! - PII found in an accepted context is reported through the
+//!   [`SecureLogger`] as a warning, without the offending values
+//!
+//! With a compliance audit trail attached (see [`crate::compliance_audit`]),
+//! violations and PII detections are also recorded there.
+//!
+//! Enforcement is enabled by the `features.context_policy` relay setting or
+//! `CONTEXT_POLICY` (see [`crate::config::RelayConfig`]) and layering the
+//! resulting [`ContextPolicy`] onto the router as an [`axum::Extension`].
+
+use proof_messenger_protocol::compliance::{DataPolicy, PIIDetector, PIIType, PolicyRegistry};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::{
+    compliance_audit::ComplianceAudit,
+    secure_logger::{LogLevel, SecureLogger},
+    AppError, Message,
+};
+
+/// A context that broke the configured policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PolicyViolation {
+    /// Name of the enforced policy
+    pub policy: String,
+    /// Forbidden fields present in the context
+    pub forbidden_fields: Vec<String>,
+    /// Required fields absent from the context
+    pub missing_fields: Vec<String>,
+}
+
+impl std::fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "context violates the '{}' policy", self.policy)?;
+        if !self.forbidden_fields.is_empty() {
+            write!(f, "; forbidden fields: {}", self.forbidden_fields.join(", "))?;
+        }
+        if !self.missing_fields.is_empty() {
+            write!(f, "; missing fields: {}", self.missing_fields.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A data policy enforced on relayed contexts
+pub struct ContextPolicy {
+    name: String,
+    policy: DataPolicy,
+    detector: PIIDetector,
+    logger: Arc<SecureLogger>,
+    audit: Option<Arc<ComplianceAudit>>,
+}
+
+impl ContextPolicy {
+    /// Enforce the standard policy registered under `name`
+    ///
+    /// PII detections are reported through `logger`.
+    pub fn new(name: &str, logger: Arc<SecureLogger>) -> Result<Self, String> {
+        let registry = PolicyRegistry::new();
+        let policy = registry.get_policy(name).cloned().ok_or_else(|| {
+            let mut known = registry.list_policy_types();
+            known.sort();
+            format!("unknown context policy '{}' (expected one of: {})", name, known.join(", "))
+        })?;
+        Ok(Self {
+            name: name.to_string(),
+            policy,
+            detector: PIIDetector::new(),
+            logger,
+            audit: None,
+        })
+    }
+
+    /// Also record violations and PII detections in `audit`
+    pub fn with_audit(mut self, audit: Arc<ComplianceAudit>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Name of the enforced policy
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Check a message's context against the policy
+    ///
+    /// `user_id` and `request_id` identify the submitter in PII warnings.
+    pub fn check(&self, message: &Message, user_id: Option<&str>, request_id: Option<&str>) -> Result<(), AppError> {
+        let context = hex::decode(&message.context)
+            .map_err(|e| AppError::InvalidContext(format!("Invalid hex encoding: {}", e)))?;
+        let Ok(Value::Object(fields)) = serde_json::from_slice::<Value>(&context) else {
+            return Err(AppError::InvalidContext(format!(
+                "context must be a JSON object under the '{}' policy",
+                self.name
+            )));
+        };
+
+        let mut forbidden_fields: Vec<String> = fields
+            .keys()
+            .filter(|field| self.policy.is_field_forbidden(field))
+            .cloned()
+            .collect();
+        let mut missing_fields: Vec<String> = self
+            .policy
+            .required_fields
+            .iter()
+            .filter(|field| !fields.contains_key(*field))
+            .cloned()
+            .collect();
+        if !forbidden_fields.is_empty() || !missing_fields.is_empty() {
+            forbidden_fields.sort();
+            missing_fields.sort();
+            if let Some(audit) = &self.audit {
+                audit.record(|log| {
+                    for field in &forbidden_fields {
+                        log.log_policy_violation(&self.name, field, "forbidden_field");
+                    }
+                    for field in &missing_fields {
+                        log.log_policy_violation(&self.name, field, "missing_required_field");
+                    }
+                });
+            }
+            return Err(AppError::PolicyViolation(PolicyViolation {
+                policy: self.name.clone(),
+                forbidden_fields,
+                missing_fields,
+            }));
+        }
+
+        if let Some(detection) = self.detector.detect_pii_detailed(&Value::Object(fields)) {
+            if let Some(audit) = &self.audit {
+                let detected: Vec<PIIType> = detection.pii_types.iter().cloned().collect();
+                audit.record(|log| log.log_pii_detection(&self.name, "context", &detected));
+            }
+            let mut pii_types: Vec<&str> = detection.pii_types.iter().map(|pii| pii.description()).collect();
+            pii_types.sort();
+            let mut metadata = HashMap::new();
+            metadata.insert("policy".to_string(), self.name.clone());
+            metadata.insert("sender".to_string(), message.sender.clone());
+            metadata.insert("pii_types".to_string(), pii_types.join(", "));
+            metadata.insert("risk_level".to_string(), format!("{:?}", detection.highest_risk_level));
+            if let Err(e) = self.logger.log_security_event(
+                LogLevel::Warning,
+                "PII detected in relayed context".to_string(),
+                user_id.map(str::to_string),
+                request_id.map(str::to_string),
+                metadata,
+            ) {
+                warn!("Failed to log PII detection: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check a message's context if a policy is configured
+pub fn enforce_if_enabled(
+    policy: Option<&Arc<ContextPolicy>>,
+    message: &Message,
+    user_id: Option<&str>,
+    request_id: Option<&str>,
+) -> Result<(), AppError> {
+    match policy {
+        Some(policy) => policy.check(message, user_id, request_id),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_app, database::Database};
+    use axum::{body::Body, extract::Request, http::StatusCode, Extension};
+    use ed25519_dalek::Signer;
+    use proof_messenger_protocol::key::generate_keypair_with_seed;
+    use tower::ServiceExt;
+
+    fn message(context: &Value) -> Message {
+        let keypair = generate_keypair_with_seed(7);
+        let context = context.to_string();
+        Message {
+            sender: hex::encode(keypair.public.to_bytes()),
+            context: hex::encode(&context),
+            body: "hello".to_string(),
+            proof: hex::encode(keypair.sign(context.as_bytes()).to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    fn policy() -> ContextPolicy {
+        ContextPolicy::new("login", Arc::new(SecureLogger::new(&SecureLogger::generate_key()))).unwrap()
+    }
+
+    fn login_context() -> Value {
+        serde_json::json!({
+            "action": "login",
+            "user_id": "user-42",
+            "timestamp": 1700000000,
+            "authentication_method": "webauthn",
+        })
+    }
+
+    #[test]
+    fn test_unknown_policy_is_rejected() {
+        let logger = Arc::new(SecureLogger::new(&SecureLogger::generate_key()));
+
+        let error = ContextPolicy::new("payroll", logger).err().unwrap();
+
+        assert!(error.contains("payroll") && error.contains("login"), "{}", error);
+    }
+
+    #[test]
+    fn test_conforming_context_is_accepted() {
+        assert!(policy().check(&message(&login_context()), None, None).is_ok());
+    }
+
+    #[test]
+    fn test_forbidden_and_missing_fields_are_reported() {
+        // ARRANGE: A login context leaking the client IP and lacking a timestamp
+        let mut context = login_context();
+        context["user_ip"] = "203.0.113.7".into();
+        context.as_object_mut().unwrap().remove("timestamp");
+
+        // ACT: Check it against the login policy
+        let error = policy().check(&message(&context), Some("user-42"), None).unwrap_err();
+
+        // ASSERT: The violation names the policy and every offending field
+        let AppError::PolicyViolation(violation) = &error else {
+            panic!("unexpected error: {:?}", error);
+        };
+        assert_eq!(violation.policy, "login");
+        assert_eq!(violation.forbidden_fields, vec!["user_ip"]);
+        assert_eq!(violation.missing_fields, vec!["timestamp"]);
+        assert_eq!(error.status(), axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn test_violations_and_pii_are_recorded_in_the_audit_trail() {
+        let audit = Arc::new(ComplianceAudit::new(Default::default()));
+        let policy = policy().with_audit(audit.clone());
+        let mut leaky = login_context();
+        leaky["user_ip"] = "203.0.113.7".into();
+        let mut with_pii = login_context();
+        with_pii["note"] = "reach me at jane@example.com".into();
+
+        let _ = policy.check(&message(&leaky), None, None);
+        let _ = policy.check(&message(&with_pii), None, None);
+
+        audit.record(|log| {
+            let entries = log.get_entries();
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries[0].compliance_status, "POLICY_VIOLATION");
+            assert_eq!(entries[0].event_details["field_name"], "user_ip");
+            assert_eq!(entries[1].compliance_status, "PII_DETECTED");
+        });
+    }
+
+    #[test]
+    fn test_non_object_contexts_are_rejected() {
+        let policy = policy();
+        let mut raw = message(&login_context());
+        raw.context = hex::encode("not json");
+
+        assert!(matches!(policy.check(&raw, None, None), Err(AppError::InvalidContext(_))));
+        assert!(matches!(
+            policy.check(&message(&serde_json::json!(["action"])), None, None),
+            Err(AppError::InvalidContext(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_relay_rejects_violations_before_storage() {
+        // ARRANGE: A relay enforcing the login policy
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let app = create_app(db.clone()).layer(Extension(Arc::new(policy())));
+        let mut leaky = login_context();
+        leaky["password"] = "hunter2".into();
+        let relay = |message: Message| {
+            Request::builder()
+                .method("POST")
+                .uri("/relay")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&message).unwrap()))
+                .unwrap()
+        };
+
+        // ACT: Relay a conforming and a leaky context
+        let accepted = app.clone().oneshot(relay(message(&login_context()))).await.unwrap();
+        let rejected = app.oneshot(relay(message(&leaky))).await.unwrap();
+
+        // ASSERT: Only the conforming message is stored; the rejection names the field
+        assert_eq!(accepted.status(), StatusCode::OK);
+        assert_eq!(rejected.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = axum::body::to_bytes(rejected.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "POLICY_VIOLATION");
+        assert_eq!(json["details"]["forbidden_fields"], serde_json::json!(["password"]));
+        let sender = message(&leaky).sender;
+        assert_eq!(db.get_messages_by_sender(&sender, None, None, None).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,660 @@
+//! Data Subject Requests Module
+//!
+//! Answers GDPR access and erasure requests for a sender public key, a user
+//! ID or both:
+//!
+//! - `GET /admin/data-subjects/export?sender=...&user_id=...` returns every
+//!   record the relay holds about the subject (see
+//!   [`DataSubjectRecords`]) as a [`SignedArchive`]: the archive as JSON text
+//!   and an Ed25519 signature over its bytes, so the recipient can show the
+//!   export came from this relay unaltered
+//! - `POST /admin/data-subjects/erasures` schedules an erasure that is
+//!   carried out once the grace period (`retention.erasure_grace_days`) has
+//!   passed; until then `DELETE /admin/data-subjects/erasures/:request_id`
+//!   cancels it, and `GET /admin/data-subjects/erasures` lists requests
+//!
+//! Erasure reuses message deletion: the sender's messages become tombstones,
+//! keeping their proofs and transparency log hashes. Receipts the sender
+//! signed, quarantined messages claiming it and delivery states recorded for
+//! the sender or user as a recipient are deleted. The user ID is
+//! replaced with a pseudonym in audit entries, revocations, the quarantine
+//! and detached proof registrations, so the audit trail stays intact
+//! without naming the user.
+//! Messages that retention purges before an erasure is due are simply gone.
+//!
+//! The endpoints are enabled by setting `DATA_SUBJECT_SIGNING_KEY` (see
+//! [`DataSubjects::from_env`]) and layering the resulting [`DataSubjects`]
+//! onto the router as an [`axum::Extension`]. On OAuth-protected relays,
+//! exports require the `subject:export` scope, erasure requests the
+//! `subject:erase` scope, and both are recorded in the audit log.
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{delete, get},
+    Extension, Router,
+};
+use chrono::{DateTime, Utc};
+use proof_messenger_protocol::key::SecureKeypair;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{DataSubjectRecords, Database, DatabaseError, ErasureRequest},
+    request_id::RequestId,
+    AppError,
+};
+
+/// How often scheduled erasures are checked for an ended grace period
+const ERASURE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Data-subject-request-specific error types
+#[derive(Error, Debug)]
+pub enum DataSubjectError {
+    #[error("Data subject requests are not enabled on this relay")]
+    Disabled,
+
+    #[error("Invalid data subject configuration: {0}")]
+    Config(String),
+
+    #[error("Name a sender public key, a user ID or both")]
+    MissingSubject,
+
+    #[error("Erasure request not found: {0}")]
+    NotFound(String),
+
+    #[error("Erasure request is no longer scheduled: {0}")]
+    NotScheduled(String),
+}
+
+/// The person a request is about
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DataSubject {
+    /// Sender public key (64 hex characters)
+    pub sender: Option<String>,
+    /// User ID as known to the identity provider
+    pub user_id: Option<String>,
+}
+
+impl DataSubject {
+    /// Check that a subject is named and normalize the sender key
+    fn validated(self) -> Result<Self, AppError> {
+        let sender = self.sender.filter(|sender| !sender.is_empty());
+        let user_id = self.user_id.filter(|user_id| !user_id.is_empty());
+        if sender.is_none() && user_id.is_none() {
+            return Err(DataSubjectError::MissingSubject.into());
+        }
+        if let Some(sender) = &sender {
+            crate::validate_sender_key(sender)?;
+        }
+        Ok(Self {
+            sender: sender.map(|sender| sender.to_lowercase()),
+            user_id,
+        })
+    }
+}
+
+/// Contents of a data subject export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataSubjectArchive {
+    /// Who the export is about
+    pub subject: DataSubject,
+    /// When the export was generated
+    pub generated_at: DateTime<Utc>,
+    /// Everything the relay holds about the subject
+    #[serde(flatten)]
+    pub records: DataSubjectRecords,
+}
+
+/// A data subject export and the relay's signature over it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedArchive {
+    /// The [`DataSubjectArchive`] as JSON text, exactly as signed
+    pub archive: String,
+    /// Ed25519 signature over the UTF-8 bytes of `archive` (hex encoded)
+    pub signature: String,
+    /// Public key the signature verifies under (hex encoded)
+    pub public_key: String,
+}
+
+/// Exports and erases data subject records
+pub struct DataSubjects {
+    signing_key: SecureKeypair,
+    erasure_grace: chrono::Duration,
+}
+
+impl DataSubjects {
+    /// Sign exports with `signing_key` and carry out erasures after `erasure_grace`
+    pub fn new(signing_key: SecureKeypair, erasure_grace: chrono::Duration) -> Self {
+        Self { signing_key, erasure_grace }
+    }
+
+    /// Load the export signing key from environment variables
+    ///
+    /// - `DATA_SUBJECT_SIGNING_KEY`: hex encoded 64-byte keypair
+    ///
+    /// Returns `Ok(None)` when no key is set.
+    pub fn from_env(erasure_grace: chrono::Duration) -> Result<Option<Self>, DataSubjectError> {
+        let Ok(key_hex) = std::env::var("DATA_SUBJECT_SIGNING_KEY") else {
+            return Ok(None);
+        };
+        let key_bytes = hex::decode(key_hex.trim())
+            .map_err(|e| DataSubjectError::Config(format!("DATA_SUBJECT_SIGNING_KEY: {}", e)))?;
+        let signing_key = SecureKeypair::from_bytes(&key_bytes)
+            .map_err(|e| DataSubjectError::Config(format!("DATA_SUBJECT_SIGNING_KEY: {}", e)))?;
+        Ok(Some(Self::new(signing_key, erasure_grace)))
+    }
+
+    /// Public key exports are verified with (hex encoded)
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.signing_key.public_key().to_bytes())
+    }
+
+    /// Collect and sign everything held about a subject
+    pub async fn export(&self, db: &Database, subject: DataSubject) -> Result<SignedArchive, AppError> {
+        let records = db
+            .get_data_subject_records(subject.sender.as_deref(), subject.user_id.as_deref())
+            .await?;
+        let archive = DataSubjectArchive {
+            subject,
+            generated_at: Utc::now(),
+            records,
+        };
+        let archive = serde_json::to_string(&archive)
+            .map_err(|e| AppError::ProcessingError(format!("Failed to serialize archive: {}", e)))?;
+        let signature = self.signing_key.sign(archive.as_bytes());
+        Ok(SignedArchive {
+            archive,
+            signature: hex::encode(signature.to_bytes()),
+            public_key: self.public_key_hex(),
+        })
+    }
+
+    /// Schedule an erasure to run once the grace period has passed
+    pub async fn schedule_erasure(
+        &self,
+        db: &Database,
+        subject: &DataSubject,
+        requested_by: Option<&str>,
+    ) -> Result<ErasureRequest, AppError> {
+        let request = db
+            .create_erasure_request(
+                subject.sender.as_deref(),
+                subject.user_id.as_deref(),
+                requested_by,
+                Utc::now() + self.erasure_grace,
+            )
+            .await?;
+        Ok(request)
+    }
+
+    /// Carry out every scheduled erasure whose grace period has ended
+    ///
+    /// Returns the number of erasures carried out.
+    pub async fn erase_due(&self, db: &Database) -> Result<u64, DatabaseError> {
+        let due = db.get_due_erasure_requests(Utc::now()).await?;
+        for request in &due {
+            // A fresh pseudonym per erasure keeps the user's entries linked
+            // to each other but not to the user or other erasures
+            let pseudonym = format!("erased-{}", Uuid::new_v4().simple());
+            let report = db.erase_data_subject(request, &pseudonym).await?;
+            info!(
+                "Erasure {} completed: {} messages tombstoned, {} receipts, {} delivery states and {} rejected messages deleted, {} references pseudonymized",
+                request.id,
+                report.tombstoned_messages,
+                report.deleted_receipts,
+                report.deleted_deliveries,
+                report.deleted_rejected_messages,
+                report.pseudonymized_references
+            );
+        }
+        Ok(due.len() as u64)
+    }
+
+    /// Carry out due erasures periodically in the background
+    pub fn spawn_erasure(self: Arc<Self>, db: Arc<Database>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ERASURE_INTERVAL);
+            loop {
+                interval.tick().await;
+                crate::readiness::BACKGROUND_JOBS.heartbeat("data_subject_erasure", ERASURE_INTERVAL);
+                if let Err(e) = self.erase_due(&db).await {
+                    warn!("Failed to carry out scheduled erasures: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Cancel a scheduled erasure, distinguishing unknown from finished requests
+async fn cancel_erasure(db: &Database, request_id: &str) -> Result<(), AppError> {
+    if db.cancel_erasure_request(request_id).await? {
+        return Ok(());
+    }
+    match db.get_erasure_request(request_id).await? {
+        Some(_) => Err(DataSubjectError::NotScheduled(request_id.to_string()).into()),
+        None => Err(DataSubjectError::NotFound(request_id.to_string()).into()),
+    }
+}
+
+/// The layered [`DataSubjects`], or the error for relays without one
+fn enabled(data_subjects: Option<Extension<Arc<DataSubjects>>>) -> Result<Arc<DataSubjects>, AppError> {
+    let Extension(data_subjects) = data_subjects.ok_or(DataSubjectError::Disabled)?;
+    Ok(data_subjects)
+}
+
+/// Response carrying a signed archive as a file download
+fn archive_response(archive: SignedArchive) -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [(header::CONTENT_DISPOSITION, "attachment; filename=\"data-subject-export.json\"")],
+        Json(archive),
+    )
+}
+
+/// Create router for data subject request endpoints
+pub fn data_subject_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/export", get(export_handler))
+        .route("/erasures", get(list_erasures_handler).post(schedule_erasure_handler))
+        .route("/erasures/:request_id", delete(cancel_erasure_handler))
+}
+
+/// Create router for authenticated data subject request endpoints
+pub fn authenticated_data_subject_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/export", get(authenticated_export_handler))
+        .route("/erasures", get(authenticated_list_erasures_handler).post(authenticated_schedule_erasure_handler))
+        .route("/erasures/:request_id", delete(authenticated_cancel_erasure_handler))
+}
+
+/// Handler to export a data subject's records
+#[utoipa::path(
+    get,
+    path = "/admin/data-subjects/export",
+    operation_id = "exportDataSubject",
+    tag = "data-subjects",
+    params(DataSubject),
+    responses(
+        (status = 200, description = "Signed archive of the subject's data", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn export_handler(
+    State(db): State<Arc<Database>>,
+    data_subjects: Option<Extension<Arc<DataSubjects>>>,
+    Query(subject): Query<DataSubject>,
+) -> Result<impl IntoResponse, AppError> {
+    let data_subjects = enabled(data_subjects)?;
+    let subject = subject.validated()?;
+    info!("Exporting data subject records");
+
+    Ok(archive_response(data_subjects.export(&db, subject).await?))
+}
+
+/// Handler to list erasure requests
+#[utoipa::path(
+    get,
+    path = "/admin/data-subjects/erasures",
+    operation_id = "listErasures",
+    tag = "data-subjects",
+    responses(
+        (status = 200, description = "Erasure requests", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn list_erasures_handler(
+    State(db): State<Arc<Database>>,
+    data_subjects: Option<Extension<Arc<DataSubjects>>>,
+) -> Result<impl IntoResponse, AppError> {
+    enabled(data_subjects)?;
+    info!("Listing erasure requests");
+
+    let requests = db.list_erasure_requests().await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "count": requests.len(),
+        "erasure_requests": requests
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler to schedule an erasure
+#[utoipa::path(
+    post,
+    path = "/admin/data-subjects/erasures",
+    operation_id = "scheduleErasure",
+    tag = "data-subjects",
+    request_body = DataSubject,
+    responses(
+        (status = 202, description = "Erasure scheduled after the grace period", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn schedule_erasure_handler(
+    State(db): State<Arc<Database>>,
+    data_subjects: Option<Extension<Arc<DataSubjects>>>,
+    Json(subject): Json<DataSubject>,
+) -> Result<impl IntoResponse, AppError> {
+    let data_subjects = enabled(data_subjects)?;
+    let subject = subject.validated()?;
+
+    let request = data_subjects.schedule_erasure(&db, &subject, None).await?;
+    info!("Scheduled erasure {} for {}", request.id, request.execute_after);
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": "Erasure scheduled",
+        "erasure_request": request
+    }));
+
+    Ok((StatusCode::ACCEPTED, response))
+}
+
+/// Handler to cancel a scheduled erasure
+#[utoipa::path(
+    delete,
+    path = "/admin/data-subjects/erasures/{request_id}",
+    operation_id = "cancelErasure",
+    tag = "data-subjects",
+    params(("request_id" = String, Path, description = "Erasure request ID")),
+    responses(
+        (status = 200, description = "Erasure request cancelled", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn cancel_erasure_handler(
+    State(db): State<Arc<Database>>,
+    data_subjects: Option<Extension<Arc<DataSubjects>>>,
+    Path(request_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    enabled(data_subjects)?;
+    info!("Cancelling erasure: {}", request_id);
+
+    cancel_erasure(&db, &request_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": "Erasure cancelled",
+        "request_id": request_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Record a data subject request in the audit log
+fn audit_log(
+    secure_logger: &crate::secure_logger::SecureLogger,
+    event: &str,
+    auth: &AuthContext,
+    request_id: &RequestId,
+    metadata: HashMap<String, String>,
+) {
+    if let Err(e) = secure_logger.audit_log(event.to_string(), auth.user_id.clone(), Some(request_id.to_string()), metadata) {
+        warn!("Failed to log data subject request: {}", e);
+    }
+}
+
+/// Audit metadata naming a subject
+fn subject_metadata(subject: &DataSubject) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+    if let Some(sender) = &subject.sender {
+        metadata.insert("sender".to_string(), sender.clone());
+    }
+    if let Some(user_id) = &subject.user_id {
+        metadata.insert("user_id".to_string(), user_id.clone());
+    }
+    metadata
+}
+
+/// Authenticated handler to export a data subject's records
+#[instrument(skip_all)]
+async fn authenticated_export_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    data_subjects: Option<Extension<Arc<DataSubjects>>>,
+    auth: AuthContext,
+    request_id: RequestId,
+    Query(subject): Query<DataSubject>,
+) -> Result<impl IntoResponse, AppError> {
+    let data_subjects = enabled(data_subjects)?;
+    let subject = subject.validated()?;
+    info!("Authenticated user {} exporting data subject records", auth.user_id);
+
+    let metadata = subject_metadata(&subject);
+    let archive = data_subjects.export(&db, subject).await?;
+    audit_log(&secure_logger, "Data subject records exported", &auth, &request_id, metadata);
+
+    Ok(archive_response(archive))
+}
+
+/// Authenticated handler to list erasure requests
+#[instrument(skip_all)]
+async fn authenticated_list_erasures_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    data_subjects: Option<Extension<Arc<DataSubjects>>>,
+    auth: AuthContext,
+) -> Result<impl IntoResponse, AppError> {
+    enabled(data_subjects)?;
+    info!("Authenticated user {} listing erasure requests", auth.user_id);
+
+    let requests = db.list_erasure_requests().await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "count": requests.len(),
+        "erasure_requests": requests,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to schedule an erasure
+#[instrument(skip_all)]
+async fn authenticated_schedule_erasure_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    data_subjects: Option<Extension<Arc<DataSubjects>>>,
+    auth: AuthContext,
+    request_id: RequestId,
+    Json(subject): Json<DataSubject>,
+) -> Result<impl IntoResponse, AppError> {
+    let data_subjects = enabled(data_subjects)?;
+    let subject = subject.validated()?;
+
+    let request = data_subjects.schedule_erasure(&db, &subject, Some(&auth.user_id)).await?;
+    info!("Authenticated user {} scheduled erasure {} for {}", auth.user_id, request.id, request.execute_after);
+
+    let mut metadata = subject_metadata(&subject);
+    metadata.insert("erasure_request_id".to_string(), request.id.clone());
+    metadata.insert("execute_after".to_string(), request.execute_after.to_rfc3339());
+    audit_log(&secure_logger, "Data subject erasure scheduled", &auth, &request_id, metadata);
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": "Erasure scheduled",
+        "erasure_request": request,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::ACCEPTED, response))
+}
+
+/// Authenticated handler to cancel a scheduled erasure
+#[instrument(skip_all)]
+async fn authenticated_cancel_erasure_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    data_subjects: Option<Extension<Arc<DataSubjects>>>,
+    auth: AuthContext,
+    request_id: RequestId,
+    Path(erasure_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    enabled(data_subjects)?;
+    info!("Authenticated user {} cancelling erasure: {}", auth.user_id, erasure_id);
+
+    cancel_erasure(&db, &erasure_id).await?;
+
+    let mut metadata = HashMap::new();
+    metadata.insert("erasure_request_id".to_string(), erasure_id.clone());
+    audit_log(&secure_logger, "Data subject erasure cancelled", &auth, &request_id, metadata);
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": "Erasure cancelled",
+        "request_id": erasure_id,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::StoredMessage;
+    use axum::body::Body;
+    use axum::http::Request;
+    use ed25519_dalek::{Signature, Verifier};
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use tower::ServiceExt;
+
+    const SENDER: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+    async fn setup(grace_days: i64) -> (Arc<Database>, Arc<DataSubjects>) {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        for body in ["first", "second"] {
+            db.store_message(StoredMessage::from(crate::Message {
+                sender: SENDER.to_string(),
+                context: "bb".to_string(),
+                body: body.to_string(),
+                proof: format!("{}{}", body, "cc".repeat(60)),
+                pqc: None,
+                thread_id: None,
+                reply_to: None,
+                expires_at: None,
+                recipient: None,
+            }))
+            .await
+            .unwrap();
+        }
+        db.revoke_proof(&format!("first{}", "cc".repeat(60)), Some("leaked"), Some("alice"), None)
+            .await
+            .unwrap();
+        let data_subjects = DataSubjects::new(generate_secure_keypair_with_seed(7), chrono::Duration::days(grace_days));
+        (db, Arc::new(data_subjects))
+    }
+
+    fn app(db: Arc<Database>, data_subjects: Arc<DataSubjects>) -> Router {
+        Router::new()
+            .nest("/admin/data-subjects", data_subject_routes())
+            .with_state(db)
+            .layer(Extension(data_subjects))
+    }
+
+    async fn send(app: Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_export_is_signed_and_complete() {
+        // ARRANGE: Two messages from the sender and a revocation by the user
+        let (db, data_subjects) = setup(30).await;
+        let uri = format!("/admin/data-subjects/export?sender={}&user_id=alice", SENDER.to_uppercase());
+
+        // ACT: Export the subject's records
+        let (status, body) = send(app(db, data_subjects.clone()), Request::builder().uri(uri).body(Body::empty()).unwrap()).await;
+
+        // ASSERT: The signature covers the archive, which holds every record
+        assert_eq!(status, StatusCode::OK);
+        let signed: SignedArchive = serde_json::from_value(body).unwrap();
+        let signature = Signature::from_bytes(&hex::decode(&signed.signature).unwrap()).unwrap();
+        let public_key = generate_secure_keypair_with_seed(7).public_key();
+        assert!(public_key.verify(signed.archive.as_bytes(), &signature).is_ok());
+        assert_eq!(signed.public_key, data_subjects.public_key_hex());
+        let archive: DataSubjectArchive = serde_json::from_str(&signed.archive).unwrap();
+        assert_eq!(archive.subject.sender.as_deref(), Some(SENDER));
+        assert_eq!(archive.records.messages.len(), 2);
+        assert_eq!(archive.records.revocations.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_erasure_waits_for_grace_period_and_can_be_cancelled() {
+        // ARRANGE: Two erasures for the sender, one cancelled
+        let (db, data_subjects) = setup(30).await;
+        let subject = DataSubject {
+            sender: Some(SENDER.to_string()),
+            user_id: None,
+        };
+        let kept = data_subjects.schedule_erasure(&db, &subject, None).await.unwrap();
+        let cancelled = data_subjects.schedule_erasure(&db, &subject, None).await.unwrap();
+        let cancel = |id: &str| Request::builder().method("DELETE").uri(format!("/admin/data-subjects/erasures/{}", id)).body(Body::empty()).unwrap();
+
+        // ACT: Cancel one twice, then run due erasures within the grace period
+        let (first, _) = send(app(db.clone(), data_subjects.clone()), cancel(&cancelled.id)).await;
+        let (second, body) = send(app(db.clone(), data_subjects.clone()), cancel(&cancelled.id)).await;
+        let erased = data_subjects.erase_due(&db).await.unwrap();
+
+        // ASSERT: Nothing is erased yet and only scheduled requests cancel
+        assert_eq!(first, StatusCode::OK);
+        assert_eq!(second, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "ERASURE_NOT_SCHEDULED");
+        assert_eq!(erased, 0);
+        assert_eq!(db.get_erasure_request(&kept.id).await.unwrap().unwrap().status, "scheduled");
+        assert!(db.get_messages_by_sender(SENDER, None, None, None).await.unwrap().iter().all(|m| !m.is_tombstone()));
+    }
+
+    #[tokio::test]
+    async fn test_due_erasure_tombstones_messages_and_pseudonymizes_user() {
+        // ARRANGE: An erasure of the sender and user without a grace period
+        let (db, data_subjects) = setup(0).await;
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/data-subjects/erasures")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "sender": SENDER, "user_id": "alice" }).to_string()))
+            .unwrap();
+        let (status, body) = send(app(db.clone(), data_subjects.clone()), request).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        // ACT: Carry out due erasures
+        let erased = data_subjects.erase_due(&db).await.unwrap();
+
+        // ASSERT: Messages are tombstones and the revocation no longer names the user
+        assert_eq!(erased, 1);
+        let id = body["erasure_request"]["id"].as_str().unwrap();
+        assert_eq!(db.get_erasure_request(id).await.unwrap().unwrap().status, "completed");
+        let messages = db.get_messages_by_sender(SENDER, None, None, None).await.unwrap();
+        assert!(messages.iter().all(|m| m.is_tombstone() && m.body.is_empty()));
+        let revocations = db.get_active_revocations().await.unwrap();
+        assert!(revocations[0].revoked_by.as_deref().unwrap().starts_with("erased-"));
+        assert!(db.get_data_subject_records(None, Some("alice")).await.unwrap().revocations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_requests_need_a_subject_and_a_signing_key() {
+        let (db, data_subjects) = setup(30).await;
+        let export = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let (missing, body) = send(app(db.clone(), data_subjects), export("/admin/data-subjects/export")).await;
+        let disabled = Router::new().nest("/admin/data-subjects", data_subject_routes()).with_state(db);
+        let (unavailable, _) = send(disabled, export("/admin/data-subjects/export?user_id=alice")).await;
+
+        assert_eq!(missing, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "MISSING_DATA_SUBJECT");
+        assert_eq!(unavailable, StatusCode::NOT_FOUND);
+    }
+}
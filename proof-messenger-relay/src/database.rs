@@ -5,12 +5,33 @@
 //! Also includes proof revocation functionality.
 
 use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::pool::PoolConnection;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
 use sqlx::{Pool, Sqlite, SqlitePool, Row};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use uuid::Uuid;
+use proof_messenger_protocol::compliance::AuditLogEntry;
+use proof_messenger_protocol::invite::{InviteState, InviteStatus};
+use proof_messenger_protocol::receipt::message_hash;
+use proof_messenger_protocol::transparency::{tree_hash_from_slice, TreeHash};
 
-use crate::Message;
+use crate::config::{JournalMode, SynchronousMode};
+use crate::write_behind::WriteBehind;
+use crate::{Message, PqcProof};
+
+/// Migrations embedded in the binary
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Number of rows buffered between the export cursor and its consumer
+const EXPORT_STREAM_BUFFER: usize = 64;
 
 /// Database-specific error types
 #[derive(Error, Debug)]
@@ -27,15 +48,78 @@ pub enum DatabaseError {
     #[error("Database migration error: {0}")]
     MigrationError(String),
     
+    #[error("Database schema version {database_version} is newer than this relay's latest migration {binary_version}")]
+    SchemaAhead { database_version: i64, binary_version: i64 },
+    
     #[error("Serialization error: {0}")]
     SerializationError(String),
     
     #[error("Proof already revoked: {0}")]
     ProofAlreadyRevoked(String),
+    
+    #[error("Invite not found: {0}")]
+    InviteNotFound(String),
+    
+    #[error("Invite cannot be redeemed: {0}")]
+    InviteUnavailable(String),
+    
+    #[error("Webhook not found: {0}")]
+    WebhookNotFound(String),
+    
+    #[error("API key not found: {0}")]
+    ApiKeyNotFound(String),
+    
+    #[error("Message is not in the transparency log: {0}")]
+    LogEntryNotFound(String),
+    
+    #[error("Batched write failed: {0}")]
+    BatchWriteFailed(String),
+}
+
+/// Where a migration stands in the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationState {
+    /// Applied as shipped in this binary
+    Applied,
+    /// Not applied yet
+    Pending,
+    /// Applied, but the binary ships different SQL for it
+    Modified,
+    /// Started but did not complete
+    Failed,
+    /// Applied by a newer binary
+    Unknown,
+}
+
+impl MigrationState {
+    /// Lowercase name of the state
+    pub fn label(&self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "modified",
+            MigrationState::Failed => "failed",
+            MigrationState::Unknown => "unknown",
+        }
+    }
+}
+
+/// A schema migration and its state in the database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationStatus {
+    /// Migration version, the number its file name starts with
+    pub version: i64,
+    /// Description taken from the file name
+    pub description: String,
+    /// Whether the migration has been applied
+    pub state: MigrationState,
+    /// When the migration was applied
+    pub applied_at: Option<DateTime<Utc>>,
 }
 
 /// Stored message with metadata
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct StoredMessage {
     /// Unique message ID
     pub id: String,
@@ -53,6 +137,118 @@ pub struct StoredMessage {
     pub created_at: DateTime<Utc>,
     /// Whether the message signature was verified
     pub verified: bool,
+    /// Thread the message belongs to (the root message ID)
+    pub thread_id: Option<String>,
+    /// ID of the message this one replies to
+    pub reply_to: Option<String>,
+    /// When the message was deleted; its body is then empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Hash of the deleted sender, context and body (hex encoded), which the
+    /// message's transparency log leaf commits to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_hash: Option<String>,
+    /// When the message expires; it is then hidden and later purged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Public key of the recipient of a direct message (hex encoded), which
+    /// only its inbox serves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    /// ML-DSA-65 public key of the hybrid proof's post-quantum half (hex encoded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pqc_public_key: Option<String>,
+    /// ML-DSA-65 signature of the hybrid proof's post-quantum half (hex encoded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pqc_proof: Option<String>,
+}
+
+impl StoredMessage {
+    /// Whether the message was deleted and only its tombstone remains
+    pub fn is_tombstone(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// Whether the message is addressed to a single recipient rather than its group
+    pub fn is_direct(&self) -> bool {
+        self.recipient.is_some()
+    }
+
+    /// Whether the message had expired by `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Post-quantum half of the message's hybrid proof, if it was relayed with one
+    pub fn pqc(&self) -> Option<PqcProof> {
+        Some(PqcProof {
+            public_key: self.pqc_public_key.clone()?,
+            proof: self.pqc_proof.clone()?,
+        })
+    }
+}
+
+/// A message matching a full-text search, with its relevance
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MessageSearchHit {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub message: StoredMessage,
+    /// BM25 relevance score (lower is more relevant)
+    pub rank: f64,
+    /// Excerpt of the body with matches wrapped in `<mark>` tags
+    pub snippet: String,
+}
+
+/// Origin of a message exchanged between federated relays
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::FromRow)]
+pub struct FederatedOrigin {
+    /// ID of the relay that first accepted the message
+    pub origin_relay: String,
+    /// Message ID assigned by the origin relay
+    pub origin_message_id: String,
+}
+
+/// A signed message waiting to be relayed at a scheduled time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredScheduledMessage {
+    /// Unique scheduled message ID
+    pub id: String,
+    /// Tenant the message is relayed for, on relays with tenants
+    pub tenant: Option<String>,
+    /// Public key of the sender (hex encoded)
+    pub sender: String,
+    /// The submitted message (JSON encoded)
+    #[serde(skip_serializing)]
+    pub payload: String,
+    /// When the message is relayed, as named by its context
+    pub release_at: DateTime<Utc>,
+    /// Scheduled, releasing, released, failed or cancelled
+    pub status: String,
+    /// Who scheduled the message (user ID or system)
+    pub created_by: Option<String>,
+    /// When the message was scheduled
+    pub created_at: DateTime<Utc>,
+    /// When the scheduler released the message or gave up on it
+    pub released_at: Option<DateTime<Utc>>,
+    /// When the message was cancelled
+    pub cancelled_at: Option<DateTime<Utc>>,
+    /// ID of the relayed message once released
+    pub message_id: Option<String>,
+    /// Why the relay refused the message at release
+    pub error: Option<String>,
+}
+
+/// A stored message whose proof no longer verifies
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct IntegrityFailure {
+    pub message_id: String,
+    pub group_id: String,
+    pub sender: String,
+    /// Why verification failed
+    pub reason: String,
+    /// When the failure was first detected
+    pub detected_at: DateTime<Utc>,
 }
 
 /// Revoked proof information
@@ -70,6 +266,518 @@ pub struct RevokedProof {
     pub expires_at: Option<DateTime<Utc>>,
 }
 
+/// Stored invite with lifecycle state
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredInvite {
+    /// Single-use invite code (hex encoded)
+    pub code: String,
+    /// Group the invite grants access to
+    pub group_id: String,
+    /// Lifecycle status (pending, redeemed or expired)
+    pub status: String,
+    /// When the invite was minted
+    pub created_at: DateTime<Utc>,
+    /// When the invite stops being redeemable
+    pub expires_at: DateTime<Utc>,
+    /// Who minted the invite (user ID or system)
+    pub created_by: Option<String>,
+    /// Public key of the member who redeemed the invite (hex encoded)
+    pub redeemed_by: Option<String>,
+    /// When the invite was redeemed
+    pub redeemed_at: Option<DateTime<Utc>>,
+}
+
+/// Stored delivery receipt for a message
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredReceipt {
+    /// ID of the acknowledged message
+    pub message_id: String,
+    /// Public key of the recipient who signed the receipt (hex encoded)
+    pub recipient: String,
+    /// Hash of the acknowledged message contents (hex encoded)
+    pub message_hash: String,
+    /// Recipient's signature over the receipt context (hex encoded)
+    pub signature: String,
+    /// When the receipt was stored
+    pub created_at: DateTime<Utc>,
+}
+
+/// How far one recipient got with a message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredDelivery {
+    /// ID of the message
+    pub message_id: String,
+    /// User ID or public key of the recipient
+    pub recipient: String,
+    /// Delivered or read
+    pub state: String,
+    /// When the recipient's client acknowledged the message
+    pub delivered_at: DateTime<Utc>,
+    /// When the recipient read the message
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+/// Stored sender-signed amendment to a message
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredAmendment {
+    /// ID of the amended message
+    pub message_id: String,
+    /// Version this amendment created (the original message is version 0)
+    pub version: i64,
+    /// The corrected message body (empty once the message is deleted)
+    pub body: String,
+    /// Hash of the version this amendment replaced (hex encoded)
+    pub previous_hash: String,
+    /// Hash of this version, which the sender signed (hex encoded)
+    pub amendment_hash: String,
+    /// Sender's signature over the amendment hash (hex encoded)
+    pub proof: String,
+    /// When the amendment was stored
+    pub created_at: DateTime<Utc>,
+}
+
+/// Registered detached proof over an external document
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredDetachedProof {
+    /// Unique registration ID
+    pub id: String,
+    /// Tenant the proof was registered for, on relays with tenants
+    pub tenant: Option<String>,
+    /// Hash function of the digest (sha256 or blake3)
+    pub algorithm: String,
+    /// Digest of the document contents (hex encoded)
+    pub digest: String,
+    /// Name of the document file
+    pub filename: String,
+    /// Document size in bytes
+    pub size: i64,
+    /// MIME type of the document
+    pub content_type: String,
+    /// Public key of the signer (hex encoded)
+    pub public_key: String,
+    /// Signer's signature over the digest and metadata (hex encoded)
+    pub signature: String,
+    /// Who registered the proof (user ID or system)
+    pub registered_by: Option<String>,
+    /// When the proof was registered
+    pub registered_at: DateTime<Utc>,
+}
+
+/// Registered webhook endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredWebhook {
+    /// Unique webhook ID
+    pub id: String,
+    /// HTTPS URL events are POSTed to
+    pub url: String,
+    /// Only notify for messages in this group (all groups if unset)
+    pub group_id: Option<String>,
+    /// Shared secret used to sign deliveries (never serialized)
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    /// Who registered the webhook (user ID or system)
+    pub created_by: Option<String>,
+    /// When the webhook was registered
+    pub created_at: DateTime<Utc>,
+}
+
+/// Machine-to-machine API key
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredApiKey {
+    /// Unique key ID
+    pub id: String,
+    /// Human-readable name of the client
+    pub name: String,
+    /// SHA-256 hash of the key (never serialized)
+    #[serde(skip_serializing, default)]
+    pub key_hash: String,
+    /// First characters of the key, to recognize it
+    pub key_prefix: String,
+    /// Space-separated scopes granted to the key
+    pub scopes: String,
+    /// Requests allowed per minute (relay default if unset)
+    pub requests_per_minute: Option<i64>,
+    /// Who created the key (user ID or system)
+    pub created_by: Option<String>,
+    /// When the key was created
+    pub created_at: DateTime<Utc>,
+    /// When the key was last rotated
+    pub rotated_at: Option<DateTime<Utc>>,
+    /// When the key was revoked
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// A single event delivery to a webhook, with its attempt history
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebhookDelivery {
+    /// Unique delivery ID
+    pub id: String,
+    /// Webhook the event is delivered to
+    pub webhook_id: String,
+    /// Message (or key change) the event is about
+    pub message_id: String,
+    /// Event type (e.g. `message.verified`)
+    pub event_type: String,
+    /// Serialized event body (identical across retries)
+    #[serde(skip_serializing, default)]
+    pub payload: String,
+    /// Delivery status (pending, delivered or failed)
+    pub status: String,
+    /// Number of delivery attempts made so far
+    pub attempts: i64,
+    /// Error from the most recent failed attempt
+    pub last_error: Option<String>,
+    /// HTTP status returned by the most recent attempt
+    pub response_status: Option<i64>,
+    /// When the delivery was queued
+    pub created_at: DateTime<Utc>,
+    /// When the next attempt is due
+    pub next_attempt_at: DateTime<Utc>,
+    /// When the event was delivered
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// A verified-message event queued for the streaming platform
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StreamEvent {
+    /// Unique event ID, sent with every publish attempt
+    pub id: String,
+    /// Message the event is about
+    pub message_id: String,
+    /// Serialized event (identical across retries)
+    pub payload: String,
+    /// Publish status (pending, published or failed)
+    pub status: String,
+    /// Number of publish attempts made so far
+    pub attempts: i64,
+    /// Error from the most recent failed attempt
+    pub last_error: Option<String>,
+    /// When the event was queued
+    pub created_at: DateTime<Utc>,
+    /// When the next attempt is due
+    pub next_attempt_at: DateTime<Utc>,
+    /// When the broker acknowledged the event
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// Number of persisted compliance audit entries sharing a classification
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct AuditEntryCount {
+    /// Audit event type, as serialized by the protocol crate
+    pub event_type: String,
+    /// Risk level of the entries
+    pub risk_level: String,
+    /// Compliance status of the entries
+    pub compliance_status: String,
+    /// Number of entries
+    pub count: i64,
+}
+
+/// A persisted compliance audit entry
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredAuditEntry {
+    /// Sequence number of the entry
+    pub id: i64,
+    /// Audit event type, as serialized by the protocol crate
+    pub event_type: String,
+    /// Context type of the audited event
+    pub context_type: String,
+    /// Risk level of the event
+    pub risk_level: String,
+    /// Compliance status of the event
+    pub compliance_status: String,
+    /// Event details as JSON
+    pub event_details: String,
+    /// Session the event belongs to
+    pub session_id: Option<String>,
+    /// User who caused the event
+    pub user_id: Option<String>,
+    /// When the event was logged
+    pub logged_at: DateTime<Utc>,
+}
+
+/// SQLite pragmas as reported by the database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SqlitePragmas {
+    /// Journal mode, `memory` for in-memory databases
+    pub journal_mode: String,
+    /// Wait for a locked database, in milliseconds
+    pub busy_timeout_ms: i64,
+    /// Disk sync level
+    pub synchronous: &'static str,
+}
+
+/// Outcome of compacting the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CompactionReport {
+    /// Database size before compaction
+    pub bytes_before: i64,
+    /// Database size after compaction
+    pub bytes_after: i64,
+    /// Expired revocations dropped
+    pub expired_revocations: u64,
+}
+
+/// Schema version and table sizes of a database snapshot
+///
+/// `relay-admin backup` writes it next to each snapshot, and `relay-admin
+/// restore` refuses a snapshot that no longer matches it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotMetadata {
+    /// When the metadata was read
+    pub created_at: DateTime<Utc>,
+    /// Latest migration applied to the database
+    pub schema_version: i64,
+    /// Rows in each table, including the revocation list and audit entries
+    pub row_counts: BTreeMap<String, i64>,
+}
+
+impl SnapshotMetadata {
+    /// Describe how `actual` differs from this metadata, if it does
+    pub fn mismatch(&self, actual: &SnapshotMetadata) -> Option<String> {
+        if self.schema_version != actual.schema_version {
+            return Some(format!(
+                "schema version is {} but {} was recorded",
+                actual.schema_version, self.schema_version
+            ));
+        }
+        let tables: std::collections::BTreeSet<&String> = self.row_counts.keys().chain(actual.row_counts.keys()).collect();
+        let differences: Vec<String> = tables
+            .into_iter()
+            .filter(|table| self.row_counts.get(*table) != actual.row_counts.get(*table))
+            .map(|table| {
+                format!(
+                    "{} has {} rows but {} were recorded",
+                    table,
+                    actual.row_counts.get(table).copied().unwrap_or_default(),
+                    self.row_counts.get(table).copied().unwrap_or_default()
+                )
+            })
+            .collect();
+        (!differences.is_empty()).then(|| differences.join(", "))
+    }
+}
+
+/// A message group and its activity
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct GroupSummary {
+    /// Group/channel ID
+    pub group_id: String,
+    /// Number of stored messages in the group
+    pub message_count: i64,
+    /// When the group's latest message was stored
+    pub last_message_at: DateTime<Utc>,
+}
+
+/// A message that failed verification, held for investigation
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RejectedMessage {
+    /// Unique rejection ID
+    pub id: String,
+    /// Public key the message claimed to be from (as submitted)
+    pub sender: String,
+    /// Rejection category (e.g. invalid_signature, proof_revoked)
+    pub reason: String,
+    /// Verification error returned to the client
+    pub error: String,
+    /// The submitted message as JSON
+    pub payload: String,
+    /// Authenticated user who submitted the message, if any
+    pub submitted_by: Option<String>,
+    /// When the message was rejected
+    pub rejected_at: DateTime<Utc>,
+}
+
+/// The public key pinned for an authenticated user
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct KeyPin {
+    /// Authenticated user the key belongs to
+    pub user_id: String,
+    /// Pinned public key (hex encoded)
+    pub public_key: String,
+    /// When the relay first saw the user relay a message
+    pub first_seen_at: DateTime<Utc>,
+    /// When the current key was pinned
+    pub pinned_at: DateTime<Utc>,
+}
+
+/// A change of an authenticated user's public key
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct KeyChange {
+    /// Unique key change ID
+    pub id: String,
+    /// Authenticated user whose key changed
+    pub user_id: String,
+    /// Key pinned before the change (hex encoded)
+    pub previous_key: String,
+    /// Key the user presented (hex encoded)
+    pub new_key: String,
+    /// What the relay did (rotated, flagged or rejected)
+    pub action: String,
+    /// ID of the request that presented the new key
+    pub request_id: Option<String>,
+    /// When the change was detected
+    pub detected_at: DateTime<Utc>,
+}
+
+/// An RFC 3161 timestamp token issued for a stored message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredTimestamp {
+    /// Message the token was issued for
+    pub message_id: String,
+    /// Time-Stamp Authority that issued the token
+    pub tsa_url: String,
+    /// SHA-256 message hash the token covers (hex encoded)
+    pub message_imprint: String,
+    /// Serial number the authority assigned the token (hex encoded)
+    pub serial_number: String,
+    /// Authority policy the token was issued under (dotted OID)
+    pub policy: String,
+    /// Time the authority attests to
+    pub gen_time: DateTime<Utc>,
+    /// DER encoded timestamp token (base64), verifiable with `openssl ts -verify`
+    pub token: String,
+    /// When the relay received the token
+    pub created_at: DateTime<Utc>,
+}
+
+/// A version of the JSON Schema registered for a group's message bodies
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredGroupSchema {
+    /// Group the schema applies to
+    pub group_id: String,
+    /// Version of the schema, counting from 1 per group
+    pub version: i64,
+    /// The JSON Schema document
+    #[sqlx(json)]
+    pub schema: serde_json::Value,
+    /// Who registered the schema (user ID or system)
+    pub created_by: Option<String>,
+    /// When the schema was registered
+    pub created_at: DateTime<Utc>,
+}
+
+/// A multi-signature approval and its policy
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredMultisigApproval {
+    /// Unique approval ID
+    pub id: String,
+    /// Tenant the approval belongs to, on relays with tenants
+    pub tenant: Option<String>,
+    /// Payload being approved (hex encoded)
+    pub payload: String,
+    /// Number of distinct signers required
+    pub threshold: i64,
+    /// Space-separated public keys allowed to sign (hex encoded)
+    pub signers: String,
+    /// Hash of the threshold and signers (hex encoded)
+    pub policy_hash: String,
+    /// Approval status (pending or approved)
+    pub status: String,
+    /// Who created the approval (user ID or system)
+    pub created_by: Option<String>,
+    /// When the approval was created
+    pub created_at: DateTime<Utc>,
+    /// When the threshold was met
+    pub approved_at: Option<DateTime<Utc>>,
+}
+
+/// A co-signature collected for a multi-signature approval
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StoredCoSignature {
+    /// Approval the signature belongs to
+    pub approval_id: String,
+    /// Public key of the co-signer (hex encoded)
+    pub signer: String,
+    /// Signature over the multisig context (hex encoded)
+    pub signature: String,
+    /// When the signature was added
+    pub signed_at: DateTime<Utc>,
+}
+
+/// A proof reached while walking a proof chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProofChainEntry {
+    /// Hash of the proof (hex encoded)
+    pub proof_hash: String,
+    /// Message carrying the proof, if it was relayed here
+    pub message_id: Option<String>,
+    /// Fewest links between this proof and the starting proof
+    pub depth: i64,
+}
+
+/// A message's leaf in the transparency log
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TransparencyLogEntry {
+    /// Position of the leaf in the log, starting at 0
+    pub leaf_index: i64,
+    /// ID of the logged message
+    pub message_id: String,
+    /// Merkle leaf hash of the message (hex encoded)
+    pub leaf_hash: String,
+    /// When the message was appended to the log
+    pub appended_at: DateTime<Utc>,
+}
+
+/// A scheduled erasure of a data subject's records
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ErasureRequest {
+    /// Unique request ID
+    pub id: String,
+    /// Sender public key whose messages are erased (hex encoded)
+    pub sender: Option<String>,
+    /// User ID whose audit references are pseudonymized
+    pub user_id: Option<String>,
+    /// Who requested the erasure (user ID or system)
+    pub requested_by: Option<String>,
+    /// When the erasure was requested
+    pub requested_at: DateTime<Utc>,
+    /// When the grace period ends and the erasure is carried out
+    pub execute_after: DateTime<Utc>,
+    /// Request status (scheduled, completed or cancelled)
+    pub status: String,
+    /// When the erasure was carried out
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Everything the relay holds about a sender public key or user ID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DataSubjectRecords {
+    /// Messages sent by the key, tombstones included
+    pub messages: Vec<StoredMessage>,
+    /// Amendments to the key's messages
+    pub amendments: Vec<StoredAmendment>,
+    /// Receipts the key signed
+    pub receipts: Vec<StoredReceipt>,
+    /// Delivery states recorded for the key or user as a recipient
+    pub deliveries: Vec<StoredDelivery>,
+    /// Detached proofs the key signed or the user registered
+    pub detached_proofs: Vec<StoredDetachedProof>,
+    /// Revocations of the key's proofs or made by the user
+    pub revocations: Vec<RevokedProof>,
+    /// Rejected messages claiming the key or submitted by the user
+    pub rejected_messages: Vec<RejectedMessage>,
+    /// Compliance audit entries about the user
+    pub audit_entries: Vec<StoredAuditEntry>,
+    /// Erasures requested for the key or user
+    pub erasure_requests: Vec<ErasureRequest>,
+}
+
+/// What carrying out an erasure changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ErasureReport {
+    /// Messages replaced with their tombstones
+    pub tombstoned_messages: u64,
+    /// Receipts deleted
+    pub deleted_receipts: u64,
+    /// Delivery states deleted
+    pub deleted_deliveries: u64,
+    /// Rejected messages deleted
+    pub deleted_rejected_messages: u64,
+    /// Audit, revocation, quarantine and detached proof references pseudonymized
+    pub pseudonymized_references: u64,
+}
+
 impl From<Message> for StoredMessage {
     fn from(message: Message) -> Self {
         Self {
@@ -81,6 +789,14 @@ impl From<Message> for StoredMessage {
             proof: message.proof,
             created_at: Utc::now(),
             verified: false, // Will be set after verification
+            thread_id: message.thread_id,
+            reply_to: message.reply_to,
+            deleted_at: None,
+            message_hash: None,
+            expires_at: message.expires_at,
+            recipient: message.recipient.map(|recipient| recipient.to_lowercase()),
+            pqc_public_key: message.pqc.as_ref().map(|pqc| pqc.public_key.clone()),
+            pqc_proof: message.pqc.map(|pqc| pqc.proof),
         }
     }
 }
@@ -114,118 +830,852 @@ impl DatabaseConfig {
 #[derive(Debug)]
 pub struct Database {
     pool: Pool<Sqlite>,
+    /// Read-only replica serving message reads, when configured
+    read_pool: Option<Pool<Sqlite>>,
+    /// Background writer batching message inserts, when enabled
+    write_behind: Option<WriteBehind>,
 }
 
 impl Database {
     /// Create a new database connection
     pub async fn new(database_url: &str) -> Result<Self, DatabaseError> {
         let pool = SqlitePool::connect(database_url).await?;
-        Ok(Self { pool })
+        Ok(Self { pool, read_pool: None, write_behind: None })
+    }
+
+    /// Connect with the pool settings and read replica of the relay configuration
+    pub async fn connect(config: &crate::config::DatabaseConfig) -> Result<Self, DatabaseError> {
+        let primary = SqliteConnectOptions::from_str(&config.url)?
+            .journal_mode(journal_mode(config.journal_mode))
+            .synchronous(synchronous(config.synchronous))
+            .busy_timeout(Duration::from_millis(config.busy_timeout_ms));
+        let pool = pool_options(config).connect_with(primary).await?;
+        // The replica's journal is managed by whatever keeps it up to date
+        let read_pool = match &config.read_replica_url {
+            Some(url) => Some(
+                pool_options(config)
+                    .connect_with(
+                        SqliteConnectOptions::from_str(url)?
+                            .read_only(true)
+                            .busy_timeout(Duration::from_millis(config.busy_timeout_ms)),
+                    )
+                    .await?,
+            ),
+            None => None,
+        };
+        let write_behind = config
+            .write_behind
+            .enabled
+            .then(|| WriteBehind::spawn(pool.clone(), &config.write_behind));
+        Ok(Self { pool, read_pool, write_behind })
+    }
+
+    /// Pragma values in effect on the primary database
+    pub async fn pragmas(&self) -> Result<SqlitePragmas, DatabaseError> {
+        let mut conn = self.pool.acquire().await?;
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(&mut *conn).await?;
+        let busy_timeout_ms: i64 = sqlx::query_scalar("PRAGMA busy_timeout").fetch_one(&mut *conn).await?;
+        let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous").fetch_one(&mut *conn).await?;
+        Ok(SqlitePragmas {
+            journal_mode,
+            busy_timeout_ms,
+            synchronous: match synchronous {
+                0 => "off",
+                1 => "normal",
+                2 => "full",
+                _ => "extra",
+            },
+        })
+    }
+
+    /// Pool serving message reads: the replica if configured, else the primary
+    ///
+    /// Reads that validate a write, such as looking up the parent of a reply,
+    /// stay on the primary so that replication lag cannot reject them.
+    fn reader(&self) -> &Pool<Sqlite> {
+        self.read_pool.as_ref().unwrap_or(&self.pool)
     }
 
     /// Initialize database schema
     pub async fn migrate(&self) -> Result<(), DatabaseError> {
-        sqlx::migrate!("./migrations")
-            .run(&self.pool)
-            .await
-            .map_err(|e| DatabaseError::MigrationError(e.to_string()))?;
+        self.migrate_to(None).await?;
         Ok(())
     }
 
+    /// State of every migration known to this binary or applied to the database
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>, DatabaseError> {
+        let table = sqlx::query("SELECT name FROM sqlite_master WHERE type='table' AND name='_sqlx_migrations'")
+            .fetch_optional(&self.pool)
+            .await?;
+        let applied = match table {
+            Some(_) => {
+                sqlx::query("SELECT version, description, installed_on, success, checksum FROM _sqlx_migrations ORDER BY version")
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+            None => Vec::new(),
+        };
+
+        let mut status: Vec<MigrationStatus> = MIGRATOR
+            .iter()
+            .filter(|migration| migration.migration_type.is_up_migration())
+            .map(|migration| MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state: MigrationState::Pending,
+                applied_at: None,
+            })
+            .collect();
+        for row in applied {
+            let version: i64 = row.get("version");
+            let checksum: Vec<u8> = row.get("checksum");
+            let state = match MIGRATOR.iter().find(|migration| migration.version == version) {
+                _ if !row.get::<bool, _>("success") => MigrationState::Failed,
+                Some(migration) if *migration.checksum != *checksum => MigrationState::Modified,
+                Some(_) => MigrationState::Applied,
+                None => MigrationState::Unknown,
+            };
+            let applied = MigrationStatus {
+                version,
+                description: row.get("description"),
+                state,
+                applied_at: row.get("installed_on"),
+            };
+            match status.iter_mut().find(|known| known.version == version) {
+                Some(known) => *known = applied,
+                None => status.push(applied),
+            }
+        }
+        status.sort_by_key(|migration| migration.version);
+
+        Ok(status)
+    }
+
+    /// Apply pending migrations up to and including `target` (all when `None`)
+    ///
+    /// Refuses to touch a schema that is ahead of this binary, was modified
+    /// after being applied, or has a failed migration. Returns the migrations
+    /// applied.
+    pub async fn migrate_to(&self, target: Option<i64>) -> Result<Vec<MigrationStatus>, DatabaseError> {
+        let pending = self.pending_migrations(target).await?;
+
+        let mut conn = self.pool.acquire().await?;
+        conn.ensure_migrations_table().await.map_err(migration_error)?;
+        for status in &pending {
+            let migration = MIGRATOR.iter().find(|migration| migration.version == status.version && migration.migration_type.is_up_migration());
+            if let Some(migration) = migration {
+                conn.apply(migration).await.map_err(migration_error)?;
+            }
+        }
+        drop(conn);
+
+        // Messages stored before the transparency log existed are logged once it does
+        if target.is_none() || self.pending_migrations(None).await?.is_empty() {
+            self.backfill_transparency_log().await?;
+        }
+
+        Ok(pending)
+    }
+
+    /// Check that pending migrations up to `target` apply cleanly, without applying them
+    ///
+    /// Each migration is run in a transaction that is rolled back. Returns the
+    /// migrations that [`Database::migrate_to`] would apply.
+    pub async fn migrate_dry_run(&self, target: Option<i64>) -> Result<Vec<MigrationStatus>, DatabaseError> {
+        let pending = self.pending_migrations(target).await?;
+
+        let mut tx = self.pool.begin().await?;
+        for status in &pending {
+            let migration = MIGRATOR.iter().find(|migration| migration.version == status.version && migration.migration_type.is_up_migration());
+            if let Some(migration) = migration {
+                sqlx::Executor::execute(&mut *tx, &*migration.sql).await.map_err(|e| {
+                    DatabaseError::MigrationError(format!("migration {} ({}) failed: {}", migration.version, migration.description, e))
+                })?;
+            }
+        }
+        tx.rollback().await?;
+
+        Ok(pending)
+    }
+
+    /// Migrations to apply up to `target`, after checking the schema can be migrated
+    async fn pending_migrations(&self, target: Option<i64>) -> Result<Vec<MigrationStatus>, DatabaseError> {
+        if let Some(target) = target {
+            if !MIGRATOR.version_exists(target) {
+                return Err(DatabaseError::MigrationError(format!("unknown migration version {}", target)));
+            }
+        }
+        let status = self.migration_status().await?;
+        let binary_version = MIGRATOR.iter().map(|migration| migration.version).max().unwrap_or_default();
+        if let Some(ahead) = status.iter().rev().find(|migration| migration.state == MigrationState::Unknown) {
+            return Err(DatabaseError::SchemaAhead {
+                database_version: ahead.version,
+                binary_version,
+            });
+        }
+        if let Some(broken) = status
+            .iter()
+            .find(|migration| matches!(migration.state, MigrationState::Failed | MigrationState::Modified))
+        {
+            return Err(DatabaseError::MigrationError(format!(
+                "migration {} ({}) is {}; repair the schema before migrating",
+                broken.version,
+                broken.description,
+                broken.state.label()
+            )));
+        }
+
+        Ok(status
+            .into_iter()
+            .filter(|migration| migration.state == MigrationState::Pending)
+            .filter(|migration| target.is_none_or(|target| migration.version <= target))
+            .collect())
+    }
+
     /// Store a verified message in the database
     pub async fn store_message(&self, mut message: StoredMessage) -> Result<String, DatabaseError> {
         message.verified = true; // Mark as verified since we only store verified messages
         
-        let result = sqlx::query(
-            r#"
-            INSERT INTO messages (id, group_id, sender, context, body, proof, created_at, verified)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-            "#
-        )
-        .bind(&message.id)
-        .bind(&message.group_id)
-        .bind(&message.sender)
-        .bind(&message.context)
-        .bind(&message.body)
-        .bind(&message.proof)
-        .bind(&message.created_at)
-        .bind(message.verified)
-        .execute(&self.pool)
-        .await?;
-
-        if result.rows_affected() == 1 {
-            Ok(message.id)
-        } else {
-            Err(DatabaseError::SerializationError("Failed to insert message".to_string()))
+        if let Some(write_behind) = &self.write_behind {
+            let id = message.id.clone();
+            write_behind.store(message).await?;
+            return Ok(id);
         }
+        let mut conn = message_writer(&self.pool).await?;
+        let mut tx = sqlx::Acquire::begin(&mut conn).await?;
+        insert_message(&mut *tx, &message).await?;
+        append_to_transparency_log(&mut *tx, &message).await?;
+        insert_proof_chain(&mut tx, &message).await?;
+        tx.commit().await?;
+        Ok(message.id)
     }
 
-    /// Retrieve messages for a specific group
+    /// Retrieve messages for a specific group, leaving out expired and direct messages
     pub async fn get_messages_by_group(&self, group_id: &str, limit: Option<i64>) -> Result<Vec<StoredMessage>, DatabaseError> {
         let limit = limit.unwrap_or(100); // Default limit
         
         let messages = sqlx::query_as::<_, StoredMessage>(
             r#"
-            SELECT id, group_id, sender, context, body, proof, created_at, verified
+            SELECT id, group_id, sender, context, body, proof, created_at, verified, thread_id, reply_to, deleted_at, message_hash, expires_at, recipient, pqc_public_key, pqc_proof
             FROM messages 
-            WHERE group_id = ?1 
+            WHERE group_id = ?1 AND recipient IS NULL AND (expires_at IS NULL OR expires_at > ?3)
             ORDER BY created_at DESC 
             LIMIT ?2
             "#
         )
         .bind(group_id)
         .bind(limit)
+        .bind(Utc::now())
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Stream every unexpired message in a group, other than direct messages, oldest first
+    ///
+    /// Rows are read from a database cursor by a background task and handed
+    /// over through a bounded channel, so only a handful of messages are held
+    /// in memory at once and reading pauses while the consumer falls behind.
+    /// Dropping the stream stops the query.
+    pub fn stream_messages_by_group(&self, group_id: &str) -> impl Stream<Item = Result<StoredMessage, DatabaseError>> + Send + 'static {
+        let (tx, rx) = tokio::sync::mpsc::channel(EXPORT_STREAM_BUFFER);
+        let pool = self.reader().clone();
+        let group_id = group_id.to_string();
+
+        tokio::spawn(async move {
+            let mut rows = sqlx::query_as::<_, StoredMessage>(
+                r#"
+                SELECT id, group_id, sender, context, body, proof, created_at, verified, thread_id, reply_to, deleted_at, message_hash, expires_at, recipient, pqc_public_key, pqc_proof
+                FROM messages
+                WHERE group_id = ?1 AND recipient IS NULL AND (expires_at IS NULL OR expires_at > ?2)
+                ORDER BY created_at ASC, id ASC
+                "#
+            )
+            .bind(&group_id)
+            .bind(Utc::now())
+            .fetch(&pool);
+
+            while let Some(row) = rows.next().await {
+                let failed = row.is_err();
+                if tx.send(row.map_err(DatabaseError::from)).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        futures::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|row| (row, rx)) })
+    }
+
+    /// Retrieve up to `limit` unexpired group messages stored after a given message, oldest first
+    ///
+    /// Messages are ordered by `created_at` and then `id`, so `after` (the
+    /// creation time and ID of the last message a subscriber received) names a
+    /// single position. Read from the primary so that a subscriber catching up
+    /// is not held back by replication lag.
+    pub async fn get_messages_by_group_after(
+        &self,
+        group_id: &str,
+        after: (DateTime<Utc>, &str),
+        limit: i64,
+    ) -> Result<Vec<StoredMessage>, DatabaseError> {
+        let messages = sqlx::query_as::<_, StoredMessage>(
+            r#"
+            SELECT id, group_id, sender, context, body, proof, created_at, verified, thread_id, reply_to, deleted_at, message_hash, expires_at, recipient, pqc_public_key, pqc_proof
+            FROM messages
+            WHERE group_id = ?1 AND (created_at > ?2 OR (created_at = ?2 AND id > ?3))
+              AND recipient IS NULL AND (expires_at IS NULL OR expires_at > ?5)
+            ORDER BY created_at ASC, id ASC
+            LIMIT ?4
+            "#
+        )
+        .bind(group_id)
+        .bind(after.0)
+        .bind(after.1)
+        .bind(limit)
+        .bind(Utc::now())
         .fetch_all(&self.pool)
         .await?;
 
         Ok(messages)
     }
 
-    /// Retrieve a specific message by ID
-    pub async fn get_message_by_id(&self, message_id: &str) -> Result<StoredMessage, DatabaseError> {
-        let message = sqlx::query_as::<_, StoredMessage>(
+    /// Connection pool, for tests that alter rows behind the database's back
+    #[cfg(test)]
+    pub(crate) fn pool(&self) -> &Pool<Sqlite> {
+        &self.pool
+    }
+
+    /// Retrieve messages of every group after a position, in storage order
+    ///
+    /// Messages are ordered by `created_at` and then `id`, as in
+    /// [`Database::get_messages_by_group_after`]; start from `None`.
+    pub async fn get_messages_after(
+        &self,
+        after: Option<(DateTime<Utc>, &str)>,
+        limit: i64,
+    ) -> Result<Vec<StoredMessage>, DatabaseError> {
+        let (created_at, id) = after.unwrap_or((DateTime::UNIX_EPOCH, ""));
+        let messages = sqlx::query_as::<_, StoredMessage>(
             r#"
-            SELECT id, group_id, sender, context, body, proof, created_at, verified
-            FROM messages 
-            WHERE id = ?1
+            SELECT id, group_id, sender, context, body, proof, created_at, verified, thread_id, reply_to, deleted_at, message_hash, expires_at, recipient, pqc_public_key, pqc_proof
+            FROM messages
+            WHERE created_at > ?1 OR (created_at = ?1 AND id > ?2)
+            ORDER BY created_at ASC, id ASC
+            LIMIT ?3
             "#
         )
-        .bind(message_id)
-        .fetch_optional(&self.pool)
+        .bind(created_at)
+        .bind(id)
+        .bind(limit)
+        .fetch_all(&self.pool)
         .await?;
 
-        message.ok_or_else(|| DatabaseError::MessageNotFound(message_id.to_string()))
+        Ok(messages)
     }
 
-    /// Get message count for a group
-    pub async fn get_message_count(&self, group_id: &str) -> Result<i64, DatabaseError> {
-        let row = sqlx::query("SELECT COUNT(*) as count FROM messages WHERE group_id = ?1")
-            .bind(group_id)
-            .fetch_one(&self.pool)
-            .await?;
+    /// Flag a message whose proof no longer verifies
+    ///
+    /// Returns whether the message was newly flagged; a flagged message keeps
+    /// its first detection time and reason.
+    pub async fn flag_integrity_failure(&self, message: &StoredMessage, reason: &str) -> Result<bool, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO message_integrity_failures (message_id, group_id, sender, reason, detected_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT (message_id) DO NOTHING
+            "#
+        )
+        .bind(&message.id)
+        .bind(&message.group_id)
+        .bind(&message.sender)
+        .bind(reason)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
 
-        let count: i64 = row.get("count");
-        Ok(count)
+        Ok(result.rows_affected() > 0)
     }
 
-    /// Delete old messages (for cleanup)
-    pub async fn delete_old_messages(&self, older_than: DateTime<Utc>) -> Result<u64, DatabaseError> {
-        let result = sqlx::query("DELETE FROM messages WHERE created_at < ?1")
-            .bind(older_than)
+    /// Clear a message's integrity flag once its proof verifies again
+    ///
+    /// Returns whether the message was flagged.
+    pub async fn clear_integrity_failure(&self, message_id: &str) -> Result<bool, DatabaseError> {
+        let result = sqlx::query("DELETE FROM message_integrity_failures WHERE message_id = ?1")
+            .bind(message_id)
             .execute(&self.pool)
             .await?;
 
-        Ok(result.rows_affected())
+        Ok(result.rows_affected() > 0)
     }
 
-    /// Get database health status
-    pub async fn health_check(&self) -> Result<(), DatabaseError> {
-        // Try to execute a simple query to verify database connection
-        sqlx::query("SELECT 1")
-            .fetch_one(&self.pool)
-            .await?;
-            
-        // Check if migrations table exists (indicates proper schema setup)
+    /// Messages currently flagged by integrity verification, oldest detection first
+    pub async fn get_integrity_failures(&self) -> Result<Vec<IntegrityFailure>, DatabaseError> {
+        let failures = sqlx::query_as::<_, IntegrityFailure>(
+            r#"
+            SELECT message_id, group_id, sender, reason, detected_at
+            FROM message_integrity_failures
+            ORDER BY detected_at ASC, message_id ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(failures)
+    }
+
+    /// Store a message to be relayed at its scheduled time
+    pub async fn create_scheduled_message(&self, scheduled: &StoredScheduledMessage) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO scheduled_messages (id, tenant, sender, payload, release_at, status, created_by, created_at, released_at, cancelled_at, message_id, error)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            "#
+        )
+        .bind(&scheduled.id)
+        .bind(&scheduled.tenant)
+        .bind(&scheduled.sender)
+        .bind(&scheduled.payload)
+        .bind(scheduled.release_at)
+        .bind(&scheduled.status)
+        .bind(&scheduled.created_by)
+        .bind(scheduled.created_at)
+        .bind(scheduled.released_at)
+        .bind(scheduled.cancelled_at)
+        .bind(&scheduled.message_id)
+        .bind(&scheduled.error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Retrieve a scheduled message by ID
+    pub async fn get_scheduled_message(&self, scheduled_id: &str) -> Result<Option<StoredScheduledMessage>, DatabaseError> {
+        let scheduled = sqlx::query_as::<_, StoredScheduledMessage>(
+            r#"
+            SELECT id, tenant, sender, payload, release_at, status, created_by, created_at, released_at, cancelled_at, message_id, error
+            FROM scheduled_messages
+            WHERE id = ?1
+            "#
+        )
+        .bind(scheduled_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(scheduled)
+    }
+
+    /// Retrieve up to `limit` of a tenant's scheduled messages, soonest release first
+    ///
+    /// Filters by status and sender when given.
+    pub async fn list_scheduled_messages(
+        &self,
+        tenant: Option<&str>,
+        status: Option<&str>,
+        sender: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<StoredScheduledMessage>, DatabaseError> {
+        let scheduled = sqlx::query_as::<_, StoredScheduledMessage>(
+            r#"
+            SELECT id, tenant, sender, payload, release_at, status, created_by, created_at, released_at, cancelled_at, message_id, error
+            FROM scheduled_messages
+            WHERE tenant IS ?1
+              AND (?2 IS NULL OR status = ?2)
+              AND (?3 IS NULL OR sender = ?3)
+            ORDER BY release_at ASC, id ASC
+            LIMIT ?4
+            "#
+        )
+        .bind(tenant)
+        .bind(status)
+        .bind(sender)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(scheduled)
+    }
+
+    /// Retrieve up to `limit` scheduled messages due by `now`, soonest release first
+    pub async fn get_due_scheduled_messages(&self, now: DateTime<Utc>, limit: i64) -> Result<Vec<StoredScheduledMessage>, DatabaseError> {
+        let scheduled = sqlx::query_as::<_, StoredScheduledMessage>(
+            r#"
+            SELECT id, tenant, sender, payload, release_at, status, created_by, created_at, released_at, cancelled_at, message_id, error
+            FROM scheduled_messages
+            WHERE status = 'scheduled' AND release_at <= ?1
+            ORDER BY release_at ASC, id ASC
+            LIMIT ?2
+            "#
+        )
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(scheduled)
+    }
+
+    /// Move a scheduled message from `from` to `to`, stamping `cancelled_at` on cancellation
+    ///
+    /// Returns whether the message was still in `from`, so a release and a
+    /// cancellation racing for the same message cannot both win.
+    pub async fn transition_scheduled_message(&self, scheduled_id: &str, from: &str, to: &str) -> Result<bool, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE scheduled_messages
+            SET status = ?3,
+                cancelled_at = CASE WHEN ?3 = 'cancelled' THEN ?4 ELSE cancelled_at END
+            WHERE id = ?1 AND status = ?2
+            "#
+        )
+        .bind(scheduled_id)
+        .bind(from)
+        .bind(to)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record the outcome of releasing a scheduled message
+    pub async fn finish_scheduled_message(
+        &self,
+        scheduled_id: &str,
+        status: &str,
+        message_id: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE scheduled_messages
+            SET status = ?2, released_at = ?3, message_id = ?4, error = ?5
+            WHERE id = ?1
+            "#
+        )
+        .bind(scheduled_id)
+        .bind(status)
+        .bind(Utc::now())
+        .bind(message_id)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Retrieve a specific message by ID
+    pub async fn get_message_by_id(&self, message_id: &str) -> Result<StoredMessage, DatabaseError> {
+        let message = sqlx::query_as::<_, StoredMessage>(
+            r#"
+            SELECT id, group_id, sender, context, body, proof, created_at, verified, thread_id, reply_to, deleted_at, message_hash, expires_at, recipient, pqc_public_key, pqc_proof
+            FROM messages 
+            WHERE id = ?1
+            "#
+        )
+        .bind(message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        message.ok_or_else(|| DatabaseError::MessageNotFound(message_id.to_string()))
+    }
+
+    /// Replace a message with its tombstone
+    ///
+    /// The body is cleared while the proof, metadata and the hash its
+    /// transparency log leaf commits to are kept. Returns `false` when the
+    /// message was already deleted.
+    pub async fn tombstone_message(&self, message_id: &str) -> Result<bool, DatabaseError> {
+        let message = self.get_message_by_id(message_id).await?;
+        if message.is_tombstone() {
+            return Ok(false);
+        }
+        let mut conn = message_writer(&self.pool).await?;
+        let mut tx = sqlx::Acquire::begin(&mut conn).await?;
+        let deleted = tombstone(&mut tx, &message, Utc::now()).await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Retrieve messages submitted by a sender, newest first
+    ///
+    /// `since` is inclusive and `until` exclusive; either may be omitted to
+    /// leave that end of the time range open. Expired and direct messages are
+    /// left out.
+    pub async fn get_messages_by_sender(
+        &self,
+        sender: &str,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        limit: Option<i64>,
+    ) -> Result<Vec<StoredMessage>, DatabaseError> {
+        let limit = limit.unwrap_or(100); // Default limit
+        
+        let messages = sqlx::query_as::<_, StoredMessage>(
+            r#"
+            SELECT id, group_id, sender, context, body, proof, created_at, verified, thread_id, reply_to, deleted_at, message_hash, expires_at, recipient, pqc_public_key, pqc_proof
+            FROM messages 
+            WHERE sender = ?1
+              AND (?2 IS NULL OR created_at >= ?2)
+              AND (?3 IS NULL OR created_at < ?3)
+              AND recipient IS NULL AND (expires_at IS NULL OR expires_at > ?5)
+            ORDER BY created_at DESC 
+            LIMIT ?4
+            "#
+        )
+        .bind(sender.to_lowercase())
+        .bind(since)
+        .bind(until)
+        .bind(limit)
+        .bind(Utc::now())
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Retrieve all messages in a thread, oldest first
+    ///
+    /// The thread ID is the ID of its root message, so the root is included
+    /// even though it does not carry a thread ID itself. Expired and direct
+    /// messages are left out.
+    pub async fn get_messages_by_thread(&self, thread_id: &str) -> Result<Vec<StoredMessage>, DatabaseError> {
+        let messages = sqlx::query_as::<_, StoredMessage>(
+            r#"
+            SELECT id, group_id, sender, context, body, proof, created_at, verified, thread_id, reply_to, deleted_at, message_hash, expires_at, recipient, pqc_public_key, pqc_proof
+            FROM messages 
+            WHERE (thread_id = ?1 OR id = ?1) AND recipient IS NULL AND (expires_at IS NULL OR expires_at > ?2)
+            ORDER BY created_at ASC
+            "#
+        )
+        .bind(thread_id)
+        .bind(Utc::now())
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Retrieve the unexpired direct messages sent to a recipient, newest first
+    pub async fn get_inbox_messages(&self, recipient: &str, limit: i64) -> Result<Vec<StoredMessage>, DatabaseError> {
+        let messages = sqlx::query_as::<_, StoredMessage>(
+            r#"
+            SELECT id, group_id, sender, context, body, proof, created_at, verified, thread_id, reply_to, deleted_at, message_hash, expires_at, recipient, pqc_public_key, pqc_proof
+            FROM messages
+            WHERE recipient = ?1 AND (expires_at IS NULL OR expires_at > ?3)
+            ORDER BY created_at DESC
+            LIMIT ?2
+            "#
+        )
+        .bind(recipient.to_lowercase())
+        .bind(limit)
+        .bind(Utc::now())
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Store a challenge issued to a public key, dropping challenges that expired unused
+    pub async fn create_inbox_challenge(&self, challenge: &str, public_key: &str, expires_at: DateTime<Utc>) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM inbox_challenges WHERE expires_at <= ?1")
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("INSERT INTO inbox_challenges (challenge, public_key, expires_at) VALUES (?1, ?2, ?3)")
+            .bind(challenge)
+            .bind(public_key.to_lowercase())
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Remove a challenge, returning the public key it was issued to if it had not expired
+    ///
+    /// A challenge can be taken once, so a signed challenge cannot be replayed.
+    pub async fn take_inbox_challenge(&self, challenge: &str) -> Result<Option<String>, DatabaseError> {
+        let row: Option<(String, DateTime<Utc>)> = sqlx::query_as(
+            "DELETE FROM inbox_challenges WHERE challenge = ?1 RETURNING public_key, expires_at"
+        )
+        .bind(challenge)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|(public_key, expires_at)| (expires_at > Utc::now()).then_some(public_key)))
+    }
+
+    /// Store a challenge issued to a key authenticating, dropping challenges that expired unused
+    pub async fn create_key_auth_challenge(&self, challenge: &str, public_key: &str, expires_at: DateTime<Utc>) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM key_auth_challenges WHERE expires_at <= ?1")
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("INSERT INTO key_auth_challenges (challenge, public_key, expires_at) VALUES (?1, ?2, ?3)")
+            .bind(challenge)
+            .bind(public_key)
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Remove an authentication challenge, returning its public key if it had not expired
+    pub async fn take_key_auth_challenge(&self, challenge: &str) -> Result<Option<String>, DatabaseError> {
+        let row: Option<(String, DateTime<Utc>)> = sqlx::query_as(
+            "DELETE FROM key_auth_challenges WHERE challenge = ?1 RETURNING public_key, expires_at"
+        )
+        .bind(challenge)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|(public_key, expires_at)| (expires_at > Utc::now()).then_some(public_key)))
+    }
+
+    /// Store a session token hash for a public key, dropping expired sessions
+    pub async fn create_key_session(&self, token_hash: &str, public_key: &str, expires_at: DateTime<Utc>) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM key_sessions WHERE expires_at <= ?1")
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("INSERT INTO key_sessions (token_hash, public_key, created_at, expires_at) VALUES (?1, ?2, ?3, ?4)")
+            .bind(token_hash)
+            .bind(public_key)
+            .bind(Utc::now())
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Find the public key of an unexpired session by its token hash
+    pub async fn find_key_session(&self, token_hash: &str) -> Result<Option<String>, DatabaseError> {
+        let public_key = sqlx::query_scalar("SELECT public_key FROM key_sessions WHERE token_hash = ?1 AND expires_at > ?2")
+            .bind(token_hash)
+            .bind(Utc::now())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(public_key)
+    }
+
+    /// Full-text search over message bodies, best matches first
+    ///
+    /// Each whitespace-separated term of `query` must appear in a matching
+    /// message; FTS5 operators in the input are treated as literal text.
+    /// When `group_ids` is given, only messages in those groups are searched.
+    /// Expired and direct messages are never matched.
+    pub async fn search_messages(
+        &self,
+        query: &str,
+        group_ids: Option<&[String]>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<MessageSearchHit>, DatabaseError> {
+        let groups = group_ids
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| DatabaseError::SerializationError(e.to_string()))?;
+
+        let hits = sqlx::query_as::<_, MessageSearchHit>(
+            r#"
+            SELECT m.id, m.group_id, m.sender, m.context, m.body, m.proof, m.created_at, m.verified,
+                   m.thread_id, m.reply_to, m.deleted_at, m.message_hash, m.expires_at, m.recipient, m.pqc_public_key, m.pqc_proof,
+                   bm25(messages_fts) AS rank,
+                   snippet(messages_fts, 0, '<mark>', '</mark>', '…', 16) AS snippet
+            FROM messages_fts
+            JOIN messages m ON m.rowid = messages_fts.rowid
+            WHERE messages_fts MATCH ?1
+              AND (?2 IS NULL OR m.group_id IN (SELECT value FROM json_each(?2)))
+              AND m.recipient IS NULL AND (m.expires_at IS NULL OR m.expires_at > ?5)
+            ORDER BY rank, m.created_at DESC
+            LIMIT ?3 OFFSET ?4
+            "#
+        )
+        .bind(fts_phrase_query(query))
+        .bind(groups)
+        .bind(limit)
+        .bind(offset)
+        .bind(Utc::now())
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(hits)
+    }
+
+    /// Get message count for a group
+    pub async fn get_message_count(&self, group_id: &str) -> Result<i64, DatabaseError> {
+        let row = sqlx::query("SELECT COUNT(*) as count FROM messages WHERE group_id = ?1")
+            .bind(group_id)
+            .fetch_one(self.reader())
+            .await?;
+
+        let count: i64 = row.get("count");
+        Ok(count)
+    }
+
+    /// List every group with stored messages, most recently active first
+    pub async fn list_groups(&self) -> Result<Vec<GroupSummary>, DatabaseError> {
+        let groups = sqlx::query_as::<_, GroupSummary>(
+            r#"
+            SELECT group_id, COUNT(*) AS message_count, MAX(created_at) AS last_message_at
+            FROM messages
+            GROUP BY group_id
+            ORDER BY last_message_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(groups)
+    }
+    
+    /// Delete old messages (for cleanup)
+    pub async fn delete_old_messages(&self, older_than: DateTime<Utc>) -> Result<u64, DatabaseError> {
+        let result = sqlx::query("DELETE FROM messages WHERE created_at < ?1")
+            .bind(older_than)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete messages that had expired by `now`
+    pub async fn delete_expired_messages(&self, now: DateTime<Utc>) -> Result<u64, DatabaseError> {
+        let result = sqlx::query("DELETE FROM messages WHERE expires_at IS NOT NULL AND expires_at <= ?1")
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete old messages in groups whose IDs start with `prefix`
+    ///
+    /// Used for per-tenant retention, where every group of a tenant shares its prefix.
+    pub async fn delete_namespaced_messages_before(&self, prefix: &str, older_than: DateTime<Utc>) -> Result<u64, DatabaseError> {
+        // Compare prefixes literally, since tenant IDs may contain LIKE wildcards such as '_'
+        let result = sqlx::query("DELETE FROM messages WHERE substr(group_id, 1, length(?1)) = ?1 AND created_at < ?2")
+            .bind(prefix)
+            .bind(older_than)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Get database health status
+    pub async fn health_check(&self) -> Result<(), DatabaseError> {
+        // Try to execute a simple query to verify database connection
+        sqlx::query("SELECT 1")
+            .fetch_one(&self.pool)
+            .await?;
+            
+        // Check if migrations table exists (indicates proper schema setup)
         let migrations_result = sqlx::query("SELECT name FROM sqlite_master WHERE type='table' AND name='_sqlx_migrations'")
             .fetch_optional(&self.pool)
             .await?;
@@ -237,6 +1687,80 @@ impl Database {
         Ok(())
     }
     
+    /// Size of the database file in bytes
+    pub async fn size_bytes(&self) -> Result<i64, DatabaseError> {
+        let size: i64 = sqlx::query_scalar("SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()")
+            .fetch_one(&self.pool)
+            .await?;
+        
+        Ok(size)
+    }
+    
+    /// Open a database file read-only, such as a snapshot taken by [`Database::snapshot`]
+    pub async fn open_read_only(path: &Path) -> Result<Self, DatabaseError> {
+        let options = SqliteConnectOptions::new().filename(path).read_only(true);
+        let pool = SqlitePoolOptions::new().max_connections(1).connect_with(options).await?;
+        Ok(Self { pool, read_pool: None, write_behind: None })
+    }
+    
+    /// Latest migration version this relay knows
+    pub fn latest_migration_version() -> i64 {
+        MIGRATOR.iter().map(|migration| migration.version).max().unwrap_or_default()
+    }
+    
+    /// Schema version and the number of rows in each table
+    ///
+    /// SQLite's internal tables and the migration history are not counted.
+    pub async fn snapshot_metadata(&self) -> Result<SnapshotMetadata, DatabaseError> {
+        let schema_version: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM _sqlx_migrations WHERE success")
+            .fetch_one(&self.pool)
+            .await?;
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != '_sqlx_migrations'"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut row_counts = BTreeMap::new();
+        for table in tables {
+            let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\"")))
+                .fetch_one(&self.pool)
+                .await?;
+            row_counts.insert(table, count);
+        }
+        
+        Ok(SnapshotMetadata {
+            created_at: Utc::now(),
+            schema_version,
+            row_counts,
+        })
+    }
+    
+    /// Write a transactionally consistent copy of the database to `path` with `VACUUM INTO`
+    ///
+    /// Returns the metadata of the written snapshot.
+    pub async fn snapshot(&self, path: &Path) -> Result<SnapshotMetadata, DatabaseError> {
+        sqlx::query("VACUUM INTO ?1")
+            .bind(path.to_string_lossy().into_owned())
+            .execute(&self.pool)
+            .await?;
+        
+        Database::open_read_only(path).await?.snapshot_metadata().await
+    }
+    
+    /// Drop expired revocations and rebuild the database file to reclaim free pages
+    pub async fn compact(&self) -> Result<CompactionReport, DatabaseError> {
+        let bytes_before = self.size_bytes().await?;
+        let expired_revocations = self.cleanup_expired_revocations().await?;
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+        sqlx::query("PRAGMA optimize").execute(&self.pool).await?;
+        
+        Ok(CompactionReport {
+            bytes_before,
+            bytes_after: self.size_bytes().await?,
+            expired_revocations,
+        })
+    }
+    
     /// Revoke a proof by adding it to the revocation list
     pub async fn revoke_proof(
         &self, 
@@ -260,79 +1784,2205 @@ impl Database {
             Utc::now() + chrono::Duration::hours(hours)
         });
         
-        // Insert into revocation list
+        // Insert into revocation list
+        sqlx::query(
+            r#"
+            INSERT INTO revoked_proofs (proof_signature, revoked_at, reason, revoked_by, expires_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#
+        )
+        .bind(proof_signature)
+        .bind(Utc::now())
+        .bind(reason)
+        .bind(revoked_by)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+        self.count_active_revocations().await?;
+        
+        Ok(())
+    }
+    
+    /// Count the proofs currently revoked, publishing the count as the `revocations_active` metric
+    pub async fn count_active_revocations(&self) -> Result<i64, DatabaseError> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM revoked_proofs WHERE expires_at IS NULL OR expires_at > ?1"
+        )
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await?;
+        crate::metrics::REVOCATIONS_ACTIVE.set(count);
+        
+        Ok(count)
+    }
+    
+    /// Check if a proof has been revoked
+    pub async fn is_proof_revoked(&self, proof_signature: &str) -> Result<bool, DatabaseError> {
+        // First, clean up expired revocations
+        self.cleanup_expired_revocations().await?;
+        
+        // Check if proof is in the revocation list
+        let result = sqlx::query(
+            r#"
+            SELECT proof_signature FROM revoked_proofs 
+            WHERE proof_signature = ?1
+            AND (expires_at IS NULL OR expires_at > ?2)
+            "#
+        )
+        .bind(proof_signature)
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await?;
+        let outcome = if result.is_some() { "revoked" } else { "not_revoked" };
+        crate::metrics::REVOCATION_CHECKS_TOTAL
+            .get_or_create(&vec![("result".to_string(), outcome.to_string())])
+            .inc();
+        
+        Ok(result.is_some())
+    }
+    
+    /// Get a proof's revocation, if it is currently revoked
+    pub async fn get_active_revocation(&self, proof_signature: &str) -> Result<Option<RevokedProof>, DatabaseError> {
+        let revocation = sqlx::query_as::<_, RevokedProof>(
+            r#"
+            SELECT proof_signature, revoked_at, reason, revoked_by, expires_at
+            FROM revoked_proofs
+            WHERE proof_signature = ?1
+            AND (expires_at IS NULL OR expires_at > ?2)
+            "#
+        )
+        .bind(proof_signature)
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(revocation)
+    }
+    
+    /// Clean up expired revocations
+    pub async fn cleanup_expired_revocations(&self) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM revoked_proofs
+            WHERE expires_at IS NOT NULL AND expires_at < ?1
+            "#
+        )
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        let removed = result.rows_affected();
+        if removed > 0 {
+            crate::metrics::REVOCATIONS_EXPIRED_TOTAL.inc_by(removed);
+            self.count_active_revocations().await?;
+        }
+        Ok(removed)
+    }
+    
+    /// Get all active revocations
+    pub async fn get_active_revocations(&self) -> Result<Vec<RevokedProof>, DatabaseError> {
+        // First, clean up expired revocations
+        self.cleanup_expired_revocations().await?;
+        
+        let revocations = sqlx::query_as::<_, RevokedProof>(
+            r#"
+            SELECT proof_signature, revoked_at, reason, revoked_by, expires_at
+            FROM revoked_proofs
+            WHERE expires_at IS NULL OR expires_at > ?1
+            ORDER BY revoked_at DESC
+            "#
+        )
+        .bind(Utc::now())
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(revocations)
+    }
+    
+    /// Mint a new single-use invite for a group
+    pub async fn create_invite(
+        &self,
+        group_id: &str,
+        ttl_hours: i64,
+        created_by: Option<&str>,
+    ) -> Result<StoredInvite, DatabaseError> {
+        let state = InviteState::new(group_id, chrono::Duration::hours(ttl_hours));
+        
+        sqlx::query(
+            r#"
+            INSERT INTO invites (code, group_id, status, created_at, expires_at, created_by)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#
+        )
+        .bind(&state.code)
+        .bind(&state.group_id)
+        .bind(state.status.as_str())
+        .bind(state.created_at)
+        .bind(state.expires_at)
+        .bind(created_by)
+        .execute(&self.pool)
+        .await?;
+        
+        self.get_invite(&state.code).await
+    }
+    
+    /// Retrieve an invite by code
+    pub async fn get_invite(&self, code: &str) -> Result<StoredInvite, DatabaseError> {
+        let invite = sqlx::query_as::<_, StoredInvite>(
+            r#"
+            SELECT code, group_id, status, created_at, expires_at, created_by, redeemed_by, redeemed_at
+            FROM invites
+            WHERE code = ?1
+            "#
+        )
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        invite.ok_or_else(|| DatabaseError::InviteNotFound(code.to_string()))
+    }
+    
+    /// Atomically consume a pending, unexpired invite
+    ///
+    /// The status check and update happen in a single statement, so two
+    /// concurrent redemptions of the same code cannot both succeed.
+    pub async fn redeem_invite(&self, code: &str, redeemed_by: &str) -> Result<StoredInvite, DatabaseError> {
+        let now = Utc::now();
+        let result = sqlx::query(
+            r#"
+            UPDATE invites
+            SET status = ?1, redeemed_by = ?2, redeemed_at = ?3
+            WHERE code = ?4 AND status = ?5 AND expires_at > ?3
+            "#
+        )
+        .bind(InviteStatus::Redeemed.as_str())
+        .bind(redeemed_by)
+        .bind(now)
+        .bind(code)
+        .bind(InviteStatus::Pending.as_str())
+        .execute(&self.pool)
+        .await?;
+        
+        if result.rows_affected() == 1 {
+            return self.get_invite(code).await;
+        }
+        
+        // Nothing was updated: work out why for a useful error
+        let invite = self.get_invite(code).await?;
+        if invite.status == InviteStatus::Pending.as_str() {
+            self.expire_invites().await?;
+            return Err(DatabaseError::InviteUnavailable(format!("{} has expired", code)));
+        }
+        Err(DatabaseError::InviteUnavailable(format!("{} is {}", code, invite.status)))
+    }
+    
+    /// Mark pending invites past their expiry as expired
+    pub async fn expire_invites(&self) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE invites
+            SET status = ?1
+            WHERE status = ?2 AND expires_at <= ?3
+            "#
+        )
+        .bind(InviteStatus::Expired.as_str())
+        .bind(InviteStatus::Pending.as_str())
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    /// Record a message accepted locally as originating from this relay
+    pub async fn record_local_origin(&self, relay_id: &str, message_id: &str) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO federated_messages (origin_relay, origin_message_id, local_message_id, received_from, received_at)
+            VALUES (?1, ?2, ?2, NULL, ?3)
+            "#
+        )
+        .bind(relay_id)
+        .bind(message_id)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Store a verified message received from a peer relay, once per origin
+    ///
+    /// The origin record and the message are written in one transaction, so
+    /// copies of the same message arriving concurrently over different paths
+    /// are stored exactly once. Returns `false` if the origin was already known.
+    pub async fn store_federated_message(
+        &self,
+        origin: &FederatedOrigin,
+        received_from: &str,
+        mut message: StoredMessage,
+    ) -> Result<bool, DatabaseError> {
+        message.verified = true; // Only verified messages are federated
+        
+        let mut conn = message_writer(&self.pool).await?;
+        let mut tx = sqlx::Acquire::begin(&mut conn).await?;
+        insert_message(&mut *tx, &message).await?;
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO federated_messages (origin_relay, origin_message_id, local_message_id, received_from, received_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#
+        )
+        .bind(&origin.origin_relay)
+        .bind(&origin.origin_message_id)
+        .bind(&message.id)
+        .bind(received_from)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+        
+        if result.rows_affected() == 0 {
+            // Already known: discard the copy inserted above
+            tx.rollback().await?;
+            return Ok(false);
+        }
+        
+        append_to_transparency_log(&mut *tx, &message).await?;
+        insert_proof_chain(&mut tx, &message).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+    
+    /// Check whether a federated message has already been stored
+    pub async fn is_federated_message_known(&self, origin: &FederatedOrigin) -> Result<bool, DatabaseError> {
+        let row = sqlx::query(
+            "SELECT 1 FROM federated_messages WHERE origin_relay = ?1 AND origin_message_id = ?2"
+        )
+        .bind(&origin.origin_relay)
+        .bind(&origin.origin_message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(row.is_some())
+    }
+    
+    /// List the origins of messages received since a point in time, oldest first
+    pub async fn get_federated_digest(&self, since: DateTime<Utc>, limit: i64) -> Result<Vec<FederatedOrigin>, DatabaseError> {
+        let origins = sqlx::query_as::<_, FederatedOrigin>(
+            r#"
+            SELECT origin_relay, origin_message_id
+            FROM federated_messages
+            WHERE received_at >= ?1
+            ORDER BY received_at ASC
+            LIMIT ?2
+            "#
+        )
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(origins)
+    }
+    
+    /// Retrieve the local copy of a federated message by its origin
+    pub async fn get_federated_message(&self, origin: &FederatedOrigin) -> Result<StoredMessage, DatabaseError> {
+        let message = sqlx::query_as::<_, StoredMessage>(
+            r#"
+            SELECT m.id, m.group_id, m.sender, m.context, m.body, m.proof, m.created_at, m.verified, m.thread_id, m.reply_to,
+                   m.deleted_at, m.message_hash, m.expires_at, m.recipient, m.pqc_public_key, m.pqc_proof
+            FROM federated_messages f
+            JOIN messages m ON m.id = f.local_message_id
+            WHERE f.origin_relay = ?1 AND f.origin_message_id = ?2
+            "#
+        )
+        .bind(&origin.origin_relay)
+        .bind(&origin.origin_message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        message.ok_or_else(|| DatabaseError::MessageNotFound(format!(
+            "{}/{}",
+            origin.origin_relay, origin.origin_message_id
+        )))
+    }
+    
+    /// Store a verified receipt for a message
+    ///
+    /// Receipts are idempotent per recipient: resubmitting a receipt for the
+    /// same message keeps the first one and returns it.
+    pub async fn store_receipt(
+        &self,
+        message_id: &str,
+        recipient: &str,
+        message_hash: &str,
+        signature: &str,
+    ) -> Result<StoredReceipt, DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO receipts (message_id, recipient, message_hash, signature, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#
+        )
+        .bind(message_id)
+        .bind(recipient)
+        .bind(message_hash)
+        .bind(signature)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        let receipt = sqlx::query_as::<_, StoredReceipt>(
+            r#"
+            SELECT message_id, recipient, message_hash, signature, created_at
+            FROM receipts
+            WHERE message_id = ?1 AND recipient = ?2
+            "#
+        )
+        .bind(message_id)
+        .bind(recipient)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(receipt)
+    }
+    
+    /// Retrieve all receipts for a message, oldest first
+    pub async fn get_receipts_for_message(&self, message_id: &str) -> Result<Vec<StoredReceipt>, DatabaseError> {
+        let receipts = sqlx::query_as::<_, StoredReceipt>(
+            r#"
+            SELECT message_id, recipient, message_hash, signature, created_at
+            FROM receipts
+            WHERE message_id = ?1
+            ORDER BY created_at ASC
+            "#
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(receipts)
+    }
+    
+    /// Record that a recipient got a message as far as `state`
+    ///
+    /// States only move forward: once read, a message stays read, and marking
+    /// a message read records its delivery too if no acknowledgement came first.
+    pub async fn record_delivery(&self, message_id: &str, recipient: &str, state: &str) -> Result<StoredDelivery, DatabaseError> {
+        let now = Utc::now();
+        sqlx::query(
+            r#"
+            INSERT INTO message_deliveries (message_id, recipient, state, delivered_at, read_at)
+            VALUES (?1, ?2, ?3, ?4, CASE WHEN ?3 = 'read' THEN ?4 END)
+            ON CONFLICT (message_id, recipient) DO UPDATE SET
+                state = CASE WHEN excluded.state = 'read' THEN 'read' ELSE state END,
+                read_at = COALESCE(read_at, excluded.read_at)
+            "#
+        )
+        .bind(message_id)
+        .bind(recipient)
+        .bind(state)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        
+        let delivery = sqlx::query_as::<_, StoredDelivery>(
+            r#"
+            SELECT message_id, recipient, state, delivered_at, read_at
+            FROM message_deliveries
+            WHERE message_id = ?1 AND recipient = ?2
+            "#
+        )
+        .bind(message_id)
+        .bind(recipient)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(delivery)
+    }
+    
+    /// Retrieve every recipient's delivery state for a message, earliest delivery first
+    pub async fn get_deliveries_for_message(&self, message_id: &str) -> Result<Vec<StoredDelivery>, DatabaseError> {
+        let deliveries = sqlx::query_as::<_, StoredDelivery>(
+            r#"
+            SELECT message_id, recipient, state, delivered_at, read_at
+            FROM message_deliveries
+            WHERE message_id = ?1
+            ORDER BY delivered_at ASC, recipient ASC
+            "#
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(deliveries)
+    }
+    
+    /// Store a verified amendment as the next version of a message
+    ///
+    /// Returns `None` without storing anything if the version already exists
+    /// or the message has been deleted, so concurrent amendments of the same
+    /// version cannot both be accepted.
+    pub async fn store_amendment(
+        &self,
+        message_id: &str,
+        version: i64,
+        body: &str,
+        previous_hash: &str,
+        amendment_hash: &str,
+        proof: &str,
+    ) -> Result<Option<StoredAmendment>, DatabaseError> {
+        let amendment = sqlx::query_as::<_, StoredAmendment>(
+            r#"
+            INSERT OR IGNORE INTO message_amendments (message_id, version, body, previous_hash, amendment_hash, proof, created_at)
+            SELECT id, ?2, ?3, ?4, ?5, ?6, ?7
+            FROM messages
+            WHERE id = ?1 AND deleted_at IS NULL
+            RETURNING message_id, version, body, previous_hash, amendment_hash, proof, created_at
+            "#
+        )
+        .bind(message_id)
+        .bind(version)
+        .bind(body)
+        .bind(previous_hash)
+        .bind(amendment_hash)
+        .bind(proof)
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(amendment)
+    }
+    
+    /// Retrieve all amendments to a message, oldest version first
+    pub async fn get_amendments_for_message(&self, message_id: &str) -> Result<Vec<StoredAmendment>, DatabaseError> {
+        let amendments = sqlx::query_as::<_, StoredAmendment>(
+            r#"
+            SELECT message_id, version, body, previous_hash, amendment_hash, proof, created_at
+            FROM message_amendments
+            WHERE message_id = ?1
+            ORDER BY version ASC
+            "#
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(amendments)
+    }
+    
+    /// Register a verified detached proof
+    ///
+    /// Registration is idempotent per tenant and signature: registering the
+    /// same proof again keeps the first registration and returns it.
+    pub async fn store_detached_proof(&self, proof: &StoredDetachedProof) -> Result<StoredDetachedProof, DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO detached_proofs (id, tenant, algorithm, digest, filename, size, content_type, public_key, signature, registered_by, registered_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+            "#
+        )
+        .bind(&proof.id)
+        .bind(&proof.tenant)
+        .bind(&proof.algorithm)
+        .bind(&proof.digest)
+        .bind(&proof.filename)
+        .bind(proof.size)
+        .bind(&proof.content_type)
+        .bind(&proof.public_key)
+        .bind(&proof.signature)
+        .bind(&proof.registered_by)
+        .bind(proof.registered_at)
+        .execute(&self.pool)
+        .await?;
+        
+        let stored = sqlx::query_as::<_, StoredDetachedProof>(
+            r#"
+            SELECT id, tenant, algorithm, digest, filename, size, content_type, public_key, signature, registered_by, registered_at
+            FROM detached_proofs
+            WHERE tenant IS ?1 AND signature = ?2
+            "#
+        )
+        .bind(&proof.tenant)
+        .bind(&proof.signature)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(stored)
+    }
+    
+    /// Retrieve a tenant's registration of a detached proof by its signature
+    pub async fn get_detached_proof_by_signature(&self, tenant: Option<&str>, signature: &str) -> Result<Option<StoredDetachedProof>, DatabaseError> {
+        let proof = sqlx::query_as::<_, StoredDetachedProof>(
+            r#"
+            SELECT id, tenant, algorithm, digest, filename, size, content_type, public_key, signature, registered_by, registered_at
+            FROM detached_proofs
+            WHERE tenant IS ?1 AND signature = ?2
+            "#
+        )
+        .bind(tenant)
+        .bind(signature)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(proof)
+    }
+    
+    /// Retrieve every detached proof a tenant registered for a document digest, oldest first
+    pub async fn get_detached_proofs_by_digest(&self, tenant: Option<&str>, digest: &str) -> Result<Vec<StoredDetachedProof>, DatabaseError> {
+        let proofs = sqlx::query_as::<_, StoredDetachedProof>(
+            r#"
+            SELECT id, tenant, algorithm, digest, filename, size, content_type, public_key, signature, registered_by, registered_at
+            FROM detached_proofs
+            WHERE tenant IS ?1 AND digest = ?2
+            ORDER BY registered_at ASC
+            "#
+        )
+        .bind(tenant)
+        .bind(digest)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(proofs)
+    }
+    
+    /// Store a message that failed verification
+    pub async fn store_rejected_message(&self, rejected: &RejectedMessage) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO rejected_messages (id, sender, reason, error, payload, submitted_by, rejected_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#
+        )
+        .bind(&rejected.id)
+        .bind(&rejected.sender)
+        .bind(&rejected.reason)
+        .bind(&rejected.error)
+        .bind(&rejected.payload)
+        .bind(&rejected.submitted_by)
+        .bind(rejected.rejected_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// List rejected messages, newest first, optionally filtered by reason and sender
+    pub async fn get_rejected_messages(
+        &self,
+        reason: Option<&str>,
+        sender: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RejectedMessage>, DatabaseError> {
+        let rejected = sqlx::query_as::<_, RejectedMessage>(
+            r#"
+            SELECT id, sender, reason, error, payload, submitted_by, rejected_at
+            FROM rejected_messages
+            WHERE (?1 IS NULL OR reason = ?1)
+              AND (?2 IS NULL OR sender = ?2)
+            ORDER BY rejected_at DESC
+            LIMIT ?3 OFFSET ?4
+            "#
+        )
+        .bind(reason)
+        .bind(sender)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rejected)
+    }
+    
+    /// Delete rejected messages older than a cutoff
+    pub async fn delete_rejected_messages_before(&self, cutoff: DateTime<Utc>) -> Result<u64, DatabaseError> {
+        let result = sqlx::query("DELETE FROM rejected_messages WHERE rejected_at < ?1")
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    /// Persist compliance audit entries
+    pub async fn store_audit_entries(&self, entries: &[AuditLogEntry]) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await?;
+        for entry in entries {
+            let event_type = serde_json::to_value(&entry.event_type)
+                .map_err(|e| DatabaseError::SerializationError(e.to_string()))?;
+            let details = serde_json::to_string(&entry.event_details)
+                .map_err(|e| DatabaseError::SerializationError(e.to_string()))?;
+            sqlx::query(
+                r#"
+                INSERT INTO compliance_audit_entries
+                    (event_type, context_type, risk_level, compliance_status, event_details, session_id, user_id, logged_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                "#
+            )
+            .bind(event_type.as_str().unwrap_or_default())
+            .bind(&entry.context_type)
+            .bind(&entry.risk_level)
+            .bind(&entry.compliance_status)
+            .bind(details)
+            .bind(&entry.session_id)
+            .bind(&entry.user_id)
+            .bind(entry.timestamp)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    /// Count the compliance audit entries logged since `since`, by classification
+    pub async fn count_audit_entries_since(&self, since: DateTime<Utc>) -> Result<Vec<AuditEntryCount>, DatabaseError> {
+        let counts = sqlx::query_as::<_, AuditEntryCount>(
+            r#"
+            SELECT event_type, risk_level, compliance_status, COUNT(*) AS count
+            FROM compliance_audit_entries
+            WHERE logged_at >= ?1
+            GROUP BY event_type, risk_level, compliance_status
+            "#
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(counts)
+    }
+    
+    /// Fetch the compliance audit entries logged since `since`, oldest first
+    pub async fn get_audit_entries_since(&self, since: DateTime<Utc>) -> Result<Vec<StoredAuditEntry>, DatabaseError> {
+        let entries = sqlx::query_as::<_, StoredAuditEntry>(
+            r#"
+            SELECT id, event_type, context_type, risk_level, compliance_status, event_details, session_id, user_id, logged_at
+            FROM compliance_audit_entries
+            WHERE logged_at >= ?1
+            ORDER BY id ASC
+            "#
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(entries)
+    }
+    
+    /// Collect every record held about a sender public key, a user ID or both
+    pub async fn get_data_subject_records(&self, sender: Option<&str>, user_id: Option<&str>) -> Result<DataSubjectRecords, DatabaseError> {
+        let messages = sqlx::query_as::<_, StoredMessage>(
+            r#"
+            SELECT id, group_id, sender, context, body, proof, created_at, verified, thread_id, reply_to, deleted_at, message_hash, expires_at, recipient, pqc_public_key, pqc_proof
+            FROM messages
+            WHERE sender = ?1
+            ORDER BY created_at ASC
+            "#
+        )
+        .bind(sender)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let amendments = sqlx::query_as::<_, StoredAmendment>(
+            r#"
+            SELECT message_id, version, body, previous_hash, amendment_hash, proof, created_at
+            FROM message_amendments
+            WHERE message_id IN (SELECT id FROM messages WHERE sender = ?1)
+            ORDER BY created_at ASC, version ASC
+            "#
+        )
+        .bind(sender)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let receipts = sqlx::query_as::<_, StoredReceipt>(
+            r#"
+            SELECT message_id, recipient, message_hash, signature, created_at
+            FROM receipts
+            WHERE recipient = ?1
+            ORDER BY created_at ASC
+            "#
+        )
+        .bind(sender)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let deliveries = sqlx::query_as::<_, StoredDelivery>(
+            r#"
+            SELECT message_id, recipient, state, delivered_at, read_at
+            FROM message_deliveries
+            WHERE recipient = ?1 OR recipient = ?2
+            ORDER BY delivered_at ASC
+            "#
+        )
+        .bind(sender)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let detached_proofs = sqlx::query_as::<_, StoredDetachedProof>(
+            r#"
+            SELECT id, tenant, algorithm, digest, filename, size, content_type, public_key, signature, registered_by, registered_at
+            FROM detached_proofs
+            WHERE public_key = ?1 OR registered_by = ?2
+            ORDER BY registered_at ASC
+            "#
+        )
+        .bind(sender)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let revocations = sqlx::query_as::<_, RevokedProof>(
+            r#"
+            SELECT proof_signature, revoked_at, reason, revoked_by, expires_at
+            FROM revoked_proofs
+            WHERE proof_signature IN (SELECT proof FROM messages WHERE sender = ?1)
+               OR revoked_by = ?2
+            ORDER BY revoked_at ASC
+            "#
+        )
+        .bind(sender)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let rejected_messages = sqlx::query_as::<_, RejectedMessage>(
+            r#"
+            SELECT id, sender, reason, error, payload, submitted_by, rejected_at
+            FROM rejected_messages
+            WHERE sender = ?1 OR submitted_by = ?2
+            ORDER BY rejected_at ASC
+            "#
+        )
+        .bind(sender)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let audit_entries = sqlx::query_as::<_, StoredAuditEntry>(
+            r#"
+            SELECT id, event_type, context_type, risk_level, compliance_status, event_details, session_id, user_id, logged_at
+            FROM compliance_audit_entries
+            WHERE user_id = ?1
+            ORDER BY id ASC
+            "#
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let erasure_requests = sqlx::query_as::<_, ErasureRequest>(
+            r#"
+            SELECT id, sender, user_id, requested_by, requested_at, execute_after, status, completed_at
+            FROM erasure_requests
+            WHERE sender = ?1 OR user_id = ?2
+            ORDER BY requested_at ASC
+            "#
+        )
+        .bind(sender)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(DataSubjectRecords {
+            messages,
+            amendments,
+            receipts,
+            deliveries,
+            detached_proofs,
+            revocations,
+            rejected_messages,
+            audit_entries,
+            erasure_requests,
+        })
+    }
+    
+    /// Schedule the erasure of a sender public key's or user ID's records
+    pub async fn create_erasure_request(
+        &self,
+        sender: Option<&str>,
+        user_id: Option<&str>,
+        requested_by: Option<&str>,
+        execute_after: DateTime<Utc>,
+    ) -> Result<ErasureRequest, DatabaseError> {
+        let request = sqlx::query_as::<_, ErasureRequest>(
+            r#"
+            INSERT INTO erasure_requests (id, sender, user_id, requested_by, requested_at, execute_after, status)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'scheduled')
+            RETURNING id, sender, user_id, requested_by, requested_at, execute_after, status, completed_at
+            "#
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(sender)
+        .bind(user_id)
+        .bind(requested_by)
+        .bind(Utc::now())
+        .bind(execute_after)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(request)
+    }
+    
+    /// Retrieve an erasure request by ID
+    pub async fn get_erasure_request(&self, request_id: &str) -> Result<Option<ErasureRequest>, DatabaseError> {
+        let request = sqlx::query_as::<_, ErasureRequest>(
+            r#"
+            SELECT id, sender, user_id, requested_by, requested_at, execute_after, status, completed_at
+            FROM erasure_requests
+            WHERE id = ?1
+            "#
+        )
+        .bind(request_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(request)
+    }
+    
+    /// List erasure requests, newest first
+    pub async fn list_erasure_requests(&self) -> Result<Vec<ErasureRequest>, DatabaseError> {
+        let requests = sqlx::query_as::<_, ErasureRequest>(
+            r#"
+            SELECT id, sender, user_id, requested_by, requested_at, execute_after, status, completed_at
+            FROM erasure_requests
+            ORDER BY requested_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(requests)
+    }
+    
+    /// Cancel a scheduled erasure; returns `false` unless it was still scheduled
+    pub async fn cancel_erasure_request(&self, request_id: &str) -> Result<bool, DatabaseError> {
+        let result = sqlx::query("UPDATE erasure_requests SET status = 'cancelled' WHERE id = ?1 AND status = 'scheduled'")
+            .bind(request_id)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected() == 1)
+    }
+    
+    /// Scheduled erasures whose grace period has ended, oldest first
+    pub async fn get_due_erasure_requests(&self, now: DateTime<Utc>) -> Result<Vec<ErasureRequest>, DatabaseError> {
+        let requests = sqlx::query_as::<_, ErasureRequest>(
+            r#"
+            SELECT id, sender, user_id, requested_by, requested_at, execute_after, status, completed_at
+            FROM erasure_requests
+            WHERE status = 'scheduled' AND execute_after <= ?1
+            ORDER BY execute_after ASC
+            "#
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(requests)
+    }
+    
+    /// Carry out an erasure and mark it completed, in one transaction
+    ///
+    /// The sender's messages become tombstones, and the receipts it signed,
+    /// the rejected messages claiming it and the delivery states recorded for
+    /// the sender or user as a recipient are deleted. References to the user
+    /// ID in audit entries, revocations, the quarantine and detached proof
+    /// registrations are replaced with `pseudonym`, so the audit trail stays intact without naming the user.
+    pub async fn erase_data_subject(&self, request: &ErasureRequest, pseudonym: &str) -> Result<ErasureReport, DatabaseError> {
+        let mut report = ErasureReport::default();
+        let mut conn = message_writer(&self.pool).await?;
+        let mut tx = sqlx::Acquire::begin(&mut conn).await?;
+        
+        if let Some(sender) = &request.sender {
+            let messages = sqlx::query_as::<_, StoredMessage>(
+                r#"
+                SELECT id, group_id, sender, context, body, proof, created_at, verified, thread_id, reply_to, deleted_at, message_hash, expires_at, recipient, pqc_public_key, pqc_proof
+                FROM messages
+                WHERE sender = ?1 AND deleted_at IS NULL
+                "#
+            )
+            .bind(sender)
+            .fetch_all(&mut *tx)
+            .await?;
+            let deleted_at = Utc::now();
+            for message in &messages {
+                tombstone(&mut tx, message, deleted_at).await?;
+            }
+            report.tombstoned_messages = messages.len() as u64;
+            report.deleted_receipts = sqlx::query("DELETE FROM receipts WHERE recipient = ?1")
+                .bind(sender)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+            report.deleted_rejected_messages = sqlx::query("DELETE FROM rejected_messages WHERE sender = ?1")
+                .bind(sender)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        
+        report.deleted_deliveries = sqlx::query("DELETE FROM message_deliveries WHERE recipient = ?1 OR recipient = ?2")
+            .bind(&request.sender)
+            .bind(&request.user_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        
+        if let Some(user_id) = &request.user_id {
+            for statement in [
+                "UPDATE compliance_audit_entries SET user_id = ?2 WHERE user_id = ?1",
+                "UPDATE revoked_proofs SET revoked_by = ?2 WHERE revoked_by = ?1",
+                "UPDATE rejected_messages SET submitted_by = ?2 WHERE submitted_by = ?1",
+                "UPDATE detached_proofs SET registered_by = ?2 WHERE registered_by = ?1",
+            ] {
+                report.pseudonymized_references += sqlx::query(statement)
+                    .bind(user_id)
+                    .bind(pseudonym)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected();
+            }
+        }
+        
+        sqlx::query("UPDATE erasure_requests SET status = 'completed', completed_at = ?2 WHERE id = ?1")
+            .bind(&request.id)
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        
+        Ok(report)
+    }
+    
+    /// Register a webhook endpoint
+    pub async fn create_webhook(
+        &self,
+        url: &str,
+        group_id: Option<&str>,
+        secret: &str,
+        created_by: Option<&str>,
+    ) -> Result<StoredWebhook, DatabaseError> {
+        let id = Uuid::new_v4().to_string();
+        
+        sqlx::query(
+            r#"
+            INSERT INTO webhooks (id, url, group_id, secret, created_by, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#
+        )
+        .bind(&id)
+        .bind(url)
+        .bind(group_id)
+        .bind(secret)
+        .bind(created_by)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        self.get_webhook(&id).await
+    }
+    
+    /// Retrieve a webhook by ID
+    pub async fn get_webhook(&self, webhook_id: &str) -> Result<StoredWebhook, DatabaseError> {
+        let webhook = sqlx::query_as::<_, StoredWebhook>(
+            r#"
+            SELECT id, url, group_id, secret, created_by, created_at
+            FROM webhooks
+            WHERE id = ?1
+            "#
+        )
+        .bind(webhook_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        webhook.ok_or_else(|| DatabaseError::WebhookNotFound(webhook_id.to_string()))
+    }
+    
+    /// List all registered webhooks, oldest first
+    pub async fn list_webhooks(&self) -> Result<Vec<StoredWebhook>, DatabaseError> {
+        let webhooks = sqlx::query_as::<_, StoredWebhook>(
+            r#"
+            SELECT id, url, group_id, secret, created_by, created_at
+            FROM webhooks
+            ORDER BY created_at ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(webhooks)
+    }
+    
+    /// Remove a webhook along with its delivery history
+    pub async fn delete_webhook(&self, webhook_id: &str) -> Result<(), DatabaseError> {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = ?1")
+            .bind(webhook_id)
+            .execute(&self.pool)
+            .await?;
+        
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::WebhookNotFound(webhook_id.to_string()));
+        }
+        Ok(())
+    }
+    
+    /// Store a new API key
+    pub async fn create_api_key(
+        &self,
+        name: &str,
+        key_hash: &str,
+        key_prefix: &str,
+        scopes: &str,
+        requests_per_minute: Option<i64>,
+        created_by: Option<&str>,
+    ) -> Result<StoredApiKey, DatabaseError> {
+        let id = Uuid::new_v4().to_string();
+        
+        sqlx::query(
+            r#"
+            INSERT INTO api_keys (id, name, key_hash, key_prefix, scopes, requests_per_minute, created_by, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#
+        )
+        .bind(&id)
+        .bind(name)
+        .bind(key_hash)
+        .bind(key_prefix)
+        .bind(scopes)
+        .bind(requests_per_minute)
+        .bind(created_by)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        self.get_api_key(&id).await
+    }
+    
+    /// Retrieve an API key by ID, including revoked keys
+    pub async fn get_api_key(&self, key_id: &str) -> Result<StoredApiKey, DatabaseError> {
+        let key = sqlx::query_as::<_, StoredApiKey>(
+            r#"
+            SELECT id, name, key_hash, key_prefix, scopes, requests_per_minute, created_by, created_at, rotated_at, revoked_at
+            FROM api_keys
+            WHERE id = ?1
+            "#
+        )
+        .bind(key_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        key.ok_or_else(|| DatabaseError::ApiKeyNotFound(key_id.to_string()))
+    }
+    
+    /// Find the unrevoked API key with this hash
+    pub async fn find_active_api_key(&self, key_hash: &str) -> Result<Option<StoredApiKey>, DatabaseError> {
+        let key = sqlx::query_as::<_, StoredApiKey>(
+            r#"
+            SELECT id, name, key_hash, key_prefix, scopes, requests_per_minute, created_by, created_at, rotated_at, revoked_at
+            FROM api_keys
+            WHERE key_hash = ?1 AND revoked_at IS NULL
+            "#
+        )
+        .bind(key_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(key)
+    }
+    
+    /// List all API keys, oldest first
+    pub async fn list_api_keys(&self) -> Result<Vec<StoredApiKey>, DatabaseError> {
+        let keys = sqlx::query_as::<_, StoredApiKey>(
+            r#"
+            SELECT id, name, key_hash, key_prefix, scopes, requests_per_minute, created_by, created_at, rotated_at, revoked_at
+            FROM api_keys
+            ORDER BY created_at ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(keys)
+    }
+    
+    /// Replace an unrevoked API key's secret, invalidating the old one
+    pub async fn rotate_api_key(&self, key_id: &str, key_hash: &str, key_prefix: &str) -> Result<StoredApiKey, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE api_keys
+            SET key_hash = ?2, key_prefix = ?3, rotated_at = ?4
+            WHERE id = ?1 AND revoked_at IS NULL
+            "#
+        )
+        .bind(key_id)
+        .bind(key_hash)
+        .bind(key_prefix)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::ApiKeyNotFound(key_id.to_string()));
+        }
+        self.get_api_key(key_id).await
+    }
+    
+    /// Revoke an API key; the record is kept for auditing
+    pub async fn revoke_api_key(&self, key_id: &str) -> Result<StoredApiKey, DatabaseError> {
+        let result = sqlx::query("UPDATE api_keys SET revoked_at = ?2 WHERE id = ?1 AND revoked_at IS NULL")
+            .bind(key_id)
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await?;
+        
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::ApiKeyNotFound(key_id.to_string()));
+        }
+        self.get_api_key(key_id).await
+    }
+    
+    /// Queue a `message.verified` event for every webhook subscribed to a message's group
+    ///
+    /// Deliveries become due at `first_attempt_at`. Returns the queued deliveries.
+    pub async fn enqueue_webhook_deliveries(
+        &self,
+        message_id: &str,
+        group_id: &str,
+        payload: &str,
+        first_attempt_at: DateTime<Utc>,
+    ) -> Result<Vec<WebhookDelivery>, DatabaseError> {
+        self.enqueue_webhook_event(crate::webhooks::MESSAGE_VERIFIED_EVENT, message_id, Some(group_id), payload, first_attempt_at)
+            .await
+    }
+    
+    /// Queue an event of any type about `subject_id`
+    ///
+    /// Events about a group go to the webhooks subscribed to it; events
+    /// without one (`group_id` of `None`) only go to unfiltered webhooks.
+    pub async fn enqueue_webhook_event(
+        &self,
+        event_type: &str,
+        subject_id: &str,
+        group_id: Option<&str>,
+        payload: &str,
+        first_attempt_at: DateTime<Utc>,
+    ) -> Result<Vec<WebhookDelivery>, DatabaseError> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        
+        let webhook_ids: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM webhooks WHERE group_id IS NULL OR group_id = ?1"
+        )
+        .bind(group_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        
+        let mut deliveries = Vec::with_capacity(webhook_ids.len());
+        for webhook_id in webhook_ids {
+            let delivery = WebhookDelivery {
+                id: Uuid::new_v4().to_string(),
+                webhook_id,
+                message_id: subject_id.to_string(),
+                event_type: event_type.to_string(),
+                payload: payload.to_string(),
+                status: "pending".to_string(),
+                attempts: 0,
+                last_error: None,
+                response_status: None,
+                created_at: now,
+                next_attempt_at: first_attempt_at,
+                delivered_at: None,
+            };
+            sqlx::query(
+                r#"
+                INSERT INTO webhook_deliveries (id, webhook_id, message_id, event_type, payload, status, attempts, created_at, next_attempt_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, ?7, ?8)
+                "#
+            )
+            .bind(&delivery.id)
+            .bind(&delivery.webhook_id)
+            .bind(&delivery.message_id)
+            .bind(&delivery.event_type)
+            .bind(&delivery.payload)
+            .bind(&delivery.status)
+            .bind(delivery.created_at)
+            .bind(delivery.next_attempt_at)
+            .execute(&mut *tx)
+            .await?;
+            deliveries.push(delivery);
+        }
+        
+        tx.commit().await?;
+        Ok(deliveries)
+    }
+    
+    /// Claim pending deliveries that are due, leasing them until `lease_until`
+    ///
+    /// Claiming and leasing happen in a single statement, so concurrent
+    /// workers never pick up the same delivery.
+    pub async fn claim_due_webhook_deliveries(
+        &self,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, DatabaseError> {
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+            r#"
+            UPDATE webhook_deliveries
+            SET next_attempt_at = ?1
+            WHERE id IN (
+                SELECT id FROM webhook_deliveries
+                WHERE status = 'pending' AND next_attempt_at <= ?2
+                ORDER BY next_attempt_at ASC
+                LIMIT ?3
+            )
+            RETURNING id, webhook_id, message_id, event_type, payload, status, attempts, last_error,
+                      response_status, created_at, next_attempt_at, delivered_at
+            "#
+        )
+        .bind(lease_until)
+        .bind(Utc::now())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(deliveries)
+    }
+    
+    /// Record a successful delivery attempt
+    pub async fn record_webhook_delivery_success(&self, delivery_id: &str, response_status: u16) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'delivered', attempts = attempts + 1, response_status = ?1, last_error = NULL, delivered_at = ?2
+            WHERE id = ?3
+            "#
+        )
+        .bind(response_status as i64)
+        .bind(Utc::now())
+        .bind(delivery_id)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Record a failed delivery attempt
+    ///
+    /// The delivery is retried at `retry_at`, or marked failed if no retry is scheduled.
+    pub async fn record_webhook_delivery_failure(
+        &self,
+        delivery_id: &str,
+        error: &str,
+        response_status: Option<u16>,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DatabaseError> {
+        let status = if retry_at.is_some() { "pending" } else { "failed" };
+        
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = ?1, attempts = attempts + 1, last_error = ?2, response_status = ?3,
+                next_attempt_at = COALESCE(?4, next_attempt_at)
+            WHERE id = ?5
+            "#
+        )
+        .bind(status)
+        .bind(error)
+        .bind(response_status.map(i64::from))
+        .bind(retry_at)
+        .bind(delivery_id)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Count webhook deliveries still waiting to be delivered
+    pub async fn count_pending_webhook_deliveries(&self) -> Result<i64, DatabaseError> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM webhook_deliveries WHERE status = 'pending'")
+            .fetch_one(&self.pool)
+            .await?;
+        
+        Ok(count)
+    }
+    
+    /// Retrieve the most recent deliveries for a webhook, newest first
+    pub async fn get_webhook_deliveries(&self, webhook_id: &str, limit: i64) -> Result<Vec<WebhookDelivery>, DatabaseError> {
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+            r#"
+            SELECT id, webhook_id, message_id, event_type, payload, status, attempts, last_error,
+                   response_status, created_at, next_attempt_at, delivered_at
+            FROM webhook_deliveries
+            WHERE webhook_id = ?1
+            ORDER BY created_at DESC
+            LIMIT ?2
+            "#
+        )
+        .bind(webhook_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(deliveries)
+    }
+
+    /// Retrieve the key pinned for a user
+    pub async fn get_key_pin(&self, user_id: &str) -> Result<Option<KeyPin>, DatabaseError> {
+        let pin = sqlx::query_as::<_, KeyPin>(
+            "SELECT user_id, public_key, first_seen_at, pinned_at FROM key_pins WHERE user_id = ?1"
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(pin)
+    }
+    
+    /// Pin `public_key` for a user unless a key is already pinned
+    ///
+    /// Returns the user's pin, which holds another key if one was pinned first.
+    pub async fn pin_key_if_absent(&self, user_id: &str, public_key: &str) -> Result<KeyPin, DatabaseError> {
+        let now = Utc::now();
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO key_pins (user_id, public_key, first_seen_at, pinned_at)
+            VALUES (?1, ?2, ?3, ?3)
+            "#
+        )
+        .bind(user_id)
+        .bind(public_key)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        
+        let pin = sqlx::query_as::<_, KeyPin>(
+            "SELECT user_id, public_key, first_seen_at, pinned_at FROM key_pins WHERE user_id = ?1"
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(pin)
+    }
+    
+    /// Pin the post-quantum key of a sender key unless one is already pinned
+    ///
+    /// Returns the pinned key, which is another one if it was pinned first.
+    pub async fn pin_pqc_key_if_absent(&self, sender: &str, pqc_public_key: &str) -> Result<String, DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO pqc_key_pins (sender, pqc_public_key, pinned_at)
+            VALUES (?1, ?2, ?3)
+            "#
+        )
+        .bind(sender)
+        .bind(pqc_public_key)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        let pinned = sqlx::query_scalar::<_, String>("SELECT pqc_public_key FROM pqc_key_pins WHERE sender = ?1")
+            .bind(sender)
+            .fetch_one(&self.pool)
+            .await?;
+        
+        Ok(pinned)
+    }
+    
+    /// Replace a user's pinned key, recording the change
+    ///
+    /// The pin only moves if it still holds `change.previous_key`, so two
+    /// concurrent changes cannot both succeed. Returns whether it moved; the
+    /// change is recorded either way.
+    pub async fn replace_key_pin(&self, change: &KeyChange) -> Result<bool, DatabaseError> {
+        let mut tx = self.pool.begin().await?;
+        
+        let replaced = sqlx::query(
+            "UPDATE key_pins SET public_key = ?1, pinned_at = ?2 WHERE user_id = ?3 AND public_key = ?4"
+        )
+        .bind(&change.new_key)
+        .bind(change.detected_at)
+        .bind(&change.user_id)
+        .bind(&change.previous_key)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() == 1;
+        insert_key_change(&mut *tx, change).await?;
+        
+        tx.commit().await?;
+        Ok(replaced)
+    }
+    
+    /// Record a key change that leaves the pin in place
+    pub async fn record_key_change(&self, change: &KeyChange) -> Result<(), DatabaseError> {
+        insert_key_change(&self.pool, change).await
+    }
+    
+    /// Retrieve the most recent key changes, newest first, optionally for one user
+    pub async fn get_key_changes(&self, user_id: Option<&str>, limit: i64) -> Result<Vec<KeyChange>, DatabaseError> {
+        let changes = sqlx::query_as::<_, KeyChange>(
+            r#"
+            SELECT id, user_id, previous_key, new_key, action, request_id, detected_at
+            FROM key_changes
+            WHERE ?1 IS NULL OR user_id = ?1
+            ORDER BY detected_at DESC
+            LIMIT ?2
+            "#
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(changes)
+    }
+
+    /// Store the timestamp token issued for a message, keeping any earlier token
+    pub async fn store_message_timestamp(&self, timestamp: &StoredTimestamp) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO message_timestamps (message_id, tsa_url, message_imprint, serial_number, policy, gen_time, token, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#
+        )
+        .bind(&timestamp.message_id)
+        .bind(&timestamp.tsa_url)
+        .bind(&timestamp.message_imprint)
+        .bind(&timestamp.serial_number)
+        .bind(&timestamp.policy)
+        .bind(timestamp.gen_time)
+        .bind(&timestamp.token)
+        .bind(timestamp.created_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Retrieve the timestamp token issued for a message
+    pub async fn get_message_timestamp(&self, message_id: &str) -> Result<Option<StoredTimestamp>, DatabaseError> {
+        let timestamp = sqlx::query_as::<_, StoredTimestamp>(
+            r#"
+            SELECT message_id, tsa_url, message_imprint, serial_number, policy, gen_time, token, created_at
+            FROM message_timestamps
+            WHERE message_id = ?1
+            "#
+        )
+        .bind(message_id)
+        .fetch_optional(self.reader())
+        .await?;
+        
+        Ok(timestamp)
+    }
+    
+    /// Store a new pending multi-signature approval
+    pub async fn create_multisig_approval(&self, approval: &StoredMultisigApproval) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO multisig_approvals (id, tenant, payload, threshold, signers, policy_hash, status, created_by, created_at, approved_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "#
+        )
+        .bind(&approval.id)
+        .bind(&approval.tenant)
+        .bind(&approval.payload)
+        .bind(approval.threshold)
+        .bind(&approval.signers)
+        .bind(&approval.policy_hash)
+        .bind(&approval.status)
+        .bind(&approval.created_by)
+        .bind(approval.created_at)
+        .bind(approval.approved_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Retrieve a multi-signature approval by ID
+    pub async fn get_multisig_approval(&self, approval_id: &str) -> Result<Option<StoredMultisigApproval>, DatabaseError> {
+        let approval = sqlx::query_as::<_, StoredMultisigApproval>(
+            r#"
+            SELECT id, tenant, payload, threshold, signers, policy_hash, status, created_by, created_at, approved_at
+            FROM multisig_approvals
+            WHERE id = ?1
+            "#
+        )
+        .bind(approval_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(approval)
+    }
+    
+    /// Retrieve up to `limit` of a tenant's approvals, newest first
+    ///
+    /// Filters by status and by a key named in the policy when given.
+    pub async fn list_multisig_approvals(
+        &self,
+        tenant: Option<&str>,
+        status: Option<&str>,
+        signer: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<StoredMultisigApproval>, DatabaseError> {
+        let approvals = sqlx::query_as::<_, StoredMultisigApproval>(
+            r#"
+            SELECT id, tenant, payload, threshold, signers, policy_hash, status, created_by, created_at, approved_at
+            FROM multisig_approvals
+            WHERE tenant IS ?1
+              AND (?2 IS NULL OR status = ?2)
+              AND (?3 IS NULL OR instr(' ' || signers || ' ', ' ' || ?3 || ' ') > 0)
+            ORDER BY created_at DESC
+            LIMIT ?4
+            "#
+        )
+        .bind(tenant)
+        .bind(status)
+        .bind(signer)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(approvals)
+    }
+    
+    /// Retrieve the co-signatures collected for an approval, oldest first
+    pub async fn get_multisig_signatures(&self, approval_id: &str) -> Result<Vec<StoredCoSignature>, DatabaseError> {
+        let signatures = sqlx::query_as::<_, StoredCoSignature>(
+            r#"
+            SELECT approval_id, signer, signature, signed_at
+            FROM multisig_signatures
+            WHERE approval_id = ?1
+            ORDER BY signed_at ASC, signer ASC
+            "#
+        )
+        .bind(approval_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(signatures)
+    }
+    
+    /// Add a verified co-signature, approving once the threshold is met
+    ///
+    /// Returns `false` without storing anything if the signer already signed
+    /// or the approval is no longer pending.
+    pub async fn add_multisig_signature(&self, signature: &StoredCoSignature) -> Result<bool, DatabaseError> {
+        let mut tx = self.pool.begin().await?;
+        
+        let added = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO multisig_signatures (approval_id, signer, signature, signed_at)
+            SELECT id, ?2, ?3, ?4
+            FROM multisig_approvals
+            WHERE id = ?1 AND status = 'pending'
+            "#
+        )
+        .bind(&signature.approval_id)
+        .bind(&signature.signer)
+        .bind(&signature.signature)
+        .bind(signature.signed_at)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() == 1;
+        
+        if added {
+            sqlx::query(
+                r#"
+                UPDATE multisig_approvals
+                SET status = 'approved', approved_at = ?2
+                WHERE id = ?1 AND status = 'pending'
+                  AND threshold <= (SELECT COUNT(*) FROM multisig_signatures WHERE approval_id = ?1)
+                "#
+            )
+            .bind(&signature.approval_id)
+            .bind(signature.signed_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        
+        tx.commit().await?;
+        Ok(added)
+    }
+
+    /// Register a new version of a group's body schema, returning its version
+    pub async fn create_group_schema(
+        &self,
+        group_id: &str,
+        schema: &serde_json::Value,
+        created_by: Option<&str>,
+    ) -> Result<i64, DatabaseError> {
+        let version = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO group_schemas (group_id, version, schema, created_by, created_at)
+            SELECT ?1, COALESCE(MAX(version), 0) + 1, ?2, ?3, ?4
+            FROM group_schemas
+            WHERE group_id = ?1
+            RETURNING version
+            "#
+        )
+        .bind(group_id)
+        .bind(schema.to_string())
+        .bind(created_by)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(version)
+    }
+    
+    /// Retrieve a version of a group's body schema, or its latest when `version` is `None`
+    pub async fn get_group_schema(&self, group_id: &str, version: Option<i64>) -> Result<Option<StoredGroupSchema>, DatabaseError> {
+        let schema = sqlx::query_as::<_, StoredGroupSchema>(
+            r#"
+            SELECT group_id, version, schema, created_by, created_at
+            FROM group_schemas
+            WHERE group_id = ?1 AND (?2 IS NULL OR version = ?2)
+            ORDER BY version DESC
+            LIMIT 1
+            "#
+        )
+        .bind(group_id)
+        .bind(version)
+        .fetch_optional(self.reader())
+        .await?;
+        
+        Ok(schema)
+    }
+    
+    /// Retrieve every version of a group's body schema, newest first
+    pub async fn list_group_schemas(&self, group_id: &str) -> Result<Vec<StoredGroupSchema>, DatabaseError> {
+        let schemas = sqlx::query_as::<_, StoredGroupSchema>(
+            r#"
+            SELECT group_id, version, schema, created_by, created_at
+            FROM group_schemas
+            WHERE group_id = ?1
+            ORDER BY version DESC
+            "#
+        )
+        .bind(group_id)
+        .fetch_all(self.reader())
+        .await?;
+        
+        Ok(schemas)
+    }
+
+    /// Retrieve the ID of the message carrying a proof
+    pub async fn get_proof_chain_message_id(&self, proof_hash: &str) -> Result<Option<String>, DatabaseError> {
+        let message_id = sqlx::query_scalar::<_, String>(
+            "SELECT message_id FROM proof_chain_nodes WHERE proof_hash = ?1"
+        )
+        .bind(proof_hash)
+        .fetch_optional(self.reader())
+        .await?;
+        
+        Ok(message_id)
+    }
+
+    /// Retrieve up to `limit` proofs that build on a proof, nearest first
+    ///
+    /// Links are followed at most `max_depth` deep. Each proof appears once,
+    /// at its shortest distance from `proof_hash`.
+    pub async fn get_proof_descendants(&self, proof_hash: &str, max_depth: i64, limit: i64) -> Result<Vec<ProofChainEntry>, DatabaseError> {
+        let entries = sqlx::query_as::<_, ProofChainEntry>(
+            r#"
+            WITH RECURSIVE chain(proof_hash, depth) AS (
+                SELECT child_hash, 1 FROM proof_chain_links WHERE parent_hash = ?1
+                UNION
+                SELECT l.child_hash, c.depth + 1
+                FROM proof_chain_links l JOIN chain c ON l.parent_hash = c.proof_hash
+                WHERE c.depth < ?2
+            )
+            SELECT c.proof_hash, n.message_id, MIN(c.depth) AS depth
+            FROM chain c LEFT JOIN proof_chain_nodes n ON n.proof_hash = c.proof_hash
+            GROUP BY c.proof_hash
+            ORDER BY depth ASC, c.proof_hash ASC
+            LIMIT ?3
+            "#
+        )
+        .bind(proof_hash)
+        .bind(max_depth)
+        .bind(limit)
+        .fetch_all(self.reader())
+        .await?;
+        
+        Ok(entries)
+    }
+
+    /// Retrieve up to `limit` proofs a proof builds on, nearest first
+    ///
+    /// Links are followed at most `max_depth` deep. Ancestors that were never
+    /// relayed here have no `message_id`.
+    pub async fn get_proof_ancestors(&self, proof_hash: &str, max_depth: i64, limit: i64) -> Result<Vec<ProofChainEntry>, DatabaseError> {
+        let entries = sqlx::query_as::<_, ProofChainEntry>(
+            r#"
+            WITH RECURSIVE chain(proof_hash, depth) AS (
+                SELECT parent_hash, 1 FROM proof_chain_links WHERE child_hash = ?1
+                UNION
+                SELECT l.parent_hash, c.depth + 1
+                FROM proof_chain_links l JOIN chain c ON l.child_hash = c.proof_hash
+                WHERE c.depth < ?2
+            )
+            SELECT c.proof_hash, n.message_id, MIN(c.depth) AS depth
+            FROM chain c LEFT JOIN proof_chain_nodes n ON n.proof_hash = c.proof_hash
+            GROUP BY c.proof_hash
+            ORDER BY depth ASC, c.proof_hash ASC
+            LIMIT ?3
+            "#
+        )
+        .bind(proof_hash)
+        .bind(max_depth)
+        .bind(limit)
+        .fetch_all(self.reader())
+        .await?;
+        
+        Ok(entries)
+    }
+
+    /// Queue an event for the streaming platform, first due at `first_attempt_at`
+    pub async fn enqueue_stream_event(
+        &self,
+        event_id: &str,
+        message_id: &str,
+        payload: &str,
+        first_attempt_at: DateTime<Utc>,
+    ) -> Result<StreamEvent, DatabaseError> {
+        let event = StreamEvent {
+            id: event_id.to_string(),
+            message_id: message_id.to_string(),
+            payload: payload.to_string(),
+            status: "pending".to_string(),
+            attempts: 0,
+            last_error: None,
+            created_at: Utc::now(),
+            next_attempt_at: first_attempt_at,
+            published_at: None,
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO stream_events (id, message_id, payload, status, attempts, created_at, next_attempt_at)
+            VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6)
+            "#
+        )
+        .bind(&event.id)
+        .bind(&event.message_id)
+        .bind(&event.payload)
+        .bind(&event.status)
+        .bind(event.created_at)
+        .bind(event.next_attempt_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(event)
+    }
+    
+    /// Claim pending stream events that are due, leasing them until `lease_until`
+    ///
+    /// Claiming and leasing happen in a single statement, so concurrent
+    /// workers never pick up the same event.
+    pub async fn claim_due_stream_events(
+        &self,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<StreamEvent>, DatabaseError> {
+        let events = sqlx::query_as::<_, StreamEvent>(
+            r#"
+            UPDATE stream_events
+            SET next_attempt_at = ?1
+            WHERE id IN (
+                SELECT id FROM stream_events
+                WHERE status = 'pending' AND next_attempt_at <= ?2
+                ORDER BY next_attempt_at ASC
+                LIMIT ?3
+            )
+            RETURNING id, message_id, payload, status, attempts, last_error, created_at, next_attempt_at, published_at
+            "#
+        )
+        .bind(lease_until)
+        .bind(Utc::now())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(events)
+    }
+    
+    /// Record that the broker acknowledged a stream event
+    pub async fn record_stream_event_published(&self, event_id: &str) -> Result<(), DatabaseError> {
         sqlx::query(
             r#"
-            INSERT INTO revoked_proofs (proof_signature, revoked_at, reason, revoked_by, expires_at)
-            VALUES (?1, ?2, ?3, ?4, ?5)
+            UPDATE stream_events
+            SET status = 'published', attempts = attempts + 1, last_error = NULL, published_at = ?1
+            WHERE id = ?2
             "#
         )
-        .bind(proof_signature)
         .bind(Utc::now())
-        .bind(reason)
-        .bind(revoked_by)
-        .bind(expires_at)
+        .bind(event_id)
         .execute(&self.pool)
         .await?;
         
         Ok(())
     }
     
-    /// Check if a proof has been revoked
-    pub async fn is_proof_revoked(&self, proof_signature: &str) -> Result<bool, DatabaseError> {
-        // First, clean up expired revocations
-        self.cleanup_expired_revocations().await?;
+    /// Record a failed publish attempt
+    ///
+    /// The event is retried at `retry_at`, or marked failed if no retry is scheduled.
+    pub async fn record_stream_event_failure(
+        &self,
+        event_id: &str,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DatabaseError> {
+        let status = if retry_at.is_some() { "pending" } else { "failed" };
         
-        // Check if proof is in the revocation list
-        let result = sqlx::query(
+        sqlx::query(
             r#"
-            SELECT proof_signature FROM revoked_proofs 
-            WHERE proof_signature = ?1
-            AND (expires_at IS NULL OR expires_at > ?2)
+            UPDATE stream_events
+            SET status = ?1, attempts = attempts + 1, last_error = ?2,
+                next_attempt_at = COALESCE(?3, next_attempt_at)
+            WHERE id = ?4
             "#
         )
-        .bind(proof_signature)
-        .bind(Utc::now())
+        .bind(status)
+        .bind(error)
+        .bind(retry_at)
+        .bind(event_id)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Retrieve a queued stream event
+    pub async fn get_stream_event(&self, event_id: &str) -> Result<Option<StreamEvent>, DatabaseError> {
+        let event = sqlx::query_as::<_, StreamEvent>(
+            r#"
+            SELECT id, message_id, payload, status, attempts, last_error, created_at, next_attempt_at, published_at
+            FROM stream_events
+            WHERE id = ?1
+            "#
+        )
+        .bind(event_id)
         .fetch_optional(&self.pool)
         .await?;
         
-        Ok(result.is_some())
+        Ok(event)
     }
     
-    /// Clean up expired revocations
-    pub async fn cleanup_expired_revocations(&self) -> Result<u64, DatabaseError> {
-        let result = sqlx::query(
+    /// Number of leaves in the transparency log
+    pub async fn get_transparency_log_size(&self) -> Result<i64, DatabaseError> {
+        let size: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(leaf_index) + 1, 0) FROM transparency_log")
+            .fetch_one(&self.pool)
+            .await?;
+        
+        Ok(size)
+    }
+    
+    /// Retrieve the transparency log entry for a message
+    pub async fn get_transparency_log_entry(&self, message_id: &str) -> Result<TransparencyLogEntry, DatabaseError> {
+        let entry = sqlx::query_as::<_, TransparencyLogEntry>(
             r#"
-            DELETE FROM revoked_proofs
-            WHERE expires_at IS NOT NULL AND expires_at < ?1
+            SELECT leaf_index, message_id, leaf_hash, appended_at
+            FROM transparency_log
+            WHERE message_id = ?1
             "#
         )
-        .bind(Utc::now())
-        .execute(&self.pool)
+        .bind(message_id)
+        .fetch_optional(&self.pool)
         .await?;
         
-        Ok(result.rows_affected())
+        entry.ok_or_else(|| DatabaseError::LogEntryNotFound(message_id.to_string()))
     }
     
-    /// Get all active revocations
-    pub async fn get_active_revocations(&self) -> Result<Vec<RevokedProof>, DatabaseError> {
-        // First, clean up expired revocations
-        self.cleanup_expired_revocations().await?;
+    /// Retrieve the leaf hashes of the first `tree_size` log entries, in order
+    pub async fn get_transparency_leaf_hashes(&self, tree_size: i64) -> Result<Vec<TreeHash>, DatabaseError> {
+        let hashes: Vec<String> = sqlx::query_scalar(
+            "SELECT leaf_hash FROM transparency_log WHERE leaf_index < ?1 ORDER BY leaf_index"
+        )
+        .bind(tree_size)
+        .fetch_all(&self.pool)
+        .await?;
         
-        let revocations = sqlx::query_as::<_, RevokedProof>(
+        hashes
+            .iter()
+            .map(|hash| {
+                hex::decode(hash)
+                    .ok()
+                    .and_then(|bytes| tree_hash_from_slice(&bytes).ok())
+                    .ok_or_else(|| DatabaseError::SerializationError(format!("Invalid leaf hash in transparency log: {}", hash)))
+            })
+            .collect()
+    }
+    
+    /// Append stored messages that are missing from the transparency log
+    ///
+    /// Covers messages stored before the log existed, oldest first.
+    pub async fn backfill_transparency_log(&self) -> Result<u64, DatabaseError> {
+        let missing = sqlx::query_as::<_, StoredMessage>(
             r#"
-            SELECT proof_signature, revoked_at, reason, revoked_by, expires_at
-            FROM revoked_proofs
-            WHERE expires_at IS NULL OR expires_at > ?1
-            ORDER BY revoked_at DESC
+            SELECT m.id, m.group_id, m.sender, m.context, m.body, m.proof, m.created_at, m.verified, m.thread_id, m.reply_to,
+                   m.deleted_at, m.message_hash, m.expires_at, m.recipient, m.pqc_public_key, m.pqc_proof
+            FROM messages m
+            LEFT JOIN transparency_log t ON t.message_id = m.id
+            WHERE t.message_id IS NULL
+            ORDER BY m.created_at ASC, m.id ASC
             "#
         )
-        .bind(Utc::now())
         .fetch_all(&self.pool)
         .await?;
         
-        Ok(revocations)
+        if missing.is_empty() {
+            return Ok(0);
+        }
+        
+        let mut tx = self.pool.begin().await?;
+        for message in &missing {
+            append_to_transparency_log(&mut *tx, message).await?;
+        }
+        tx.commit().await?;
+        
+        Ok(missing.len() as u64)
+    }
+}
+
+/// Pool settings from the relay configuration
+///
+/// With a statement timeout, SQLite interrupts work on a connection once it
+/// has been checked out of the pool for longer than the timeout.
+fn pool_options(config: &crate::config::DatabaseConfig) -> SqlitePoolOptions {
+    let options = SqlitePoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_seconds));
+    let Some(timeout) = config.statement_timeout_ms.map(Duration::from_millis) else {
+        return options;
+    };
+    options
+        .after_connect(move |conn, _| Box::pin(async move { interrupt_after(conn, timeout).await }))
+        .before_acquire(move |conn, _| Box::pin(async move { interrupt_after(conn, timeout).await.map(|_| true) }))
+}
+
+/// Interrupt statements on a connection once `timeout` has elapsed from now
+async fn interrupt_after(conn: &mut sqlx::SqliteConnection, timeout: Duration) -> Result<(), sqlx::Error> {
+    let checked_out = Instant::now();
+    conn.lock_handle()
+        .await?
+        .set_progress_handler(STATEMENT_TIMEOUT_CHECK_OPS, move || checked_out.elapsed() < timeout);
+    Ok(())
+}
+
+/// sqlx journal mode for a configured one
+fn journal_mode(mode: JournalMode) -> SqliteJournalMode {
+    match mode {
+        JournalMode::Wal => SqliteJournalMode::Wal,
+        JournalMode::Delete => SqliteJournalMode::Delete,
+        JournalMode::Truncate => SqliteJournalMode::Truncate,
+        JournalMode::Persist => SqliteJournalMode::Persist,
+    }
+}
+
+/// sqlx synchronous setting for a configured one
+fn synchronous(mode: SynchronousMode) -> SqliteSynchronous {
+    match mode {
+        SynchronousMode::Off => SqliteSynchronous::Off,
+        SynchronousMode::Normal => SqliteSynchronous::Normal,
+        SynchronousMode::Full => SqliteSynchronous::Full,
+        SynchronousMode::Extra => SqliteSynchronous::Extra,
+    }
+}
+
+/// Virtual machine instructions SQLite runs between statement timeout checks
+const STATEMENT_TIMEOUT_CHECK_OPS: i32 = 1000;
+
+/// Describe a failure of the sqlx migrator
+fn migration_error(error: MigrateError) -> DatabaseError {
+    DatabaseError::MigrationError(error.to_string())
+}
+
+/// A pooled connection ready for a transaction that writes `messages`
+///
+/// SQLite connects the FTS5 search table the first time a connection uses it,
+/// and connecting reads the database. When that happens in the search trigger
+/// of a write inside a deferred transaction, the read pins a snapshot, and the
+/// write then fails with `database is locked` straight away, without waiting
+/// out the busy timeout, if another connection has committed since. Touching
+/// the table before `BEGIN` keeps concurrent message writes queued on the
+/// lock instead.
+async fn message_writer(pool: &Pool<Sqlite>) -> Result<PoolConnection<Sqlite>, DatabaseError> {
+    let mut conn = pool.acquire().await?;
+    sqlx::query("SELECT rowid FROM messages_fts LIMIT 0").execute(&mut *conn).await?;
+    Ok(conn)
+}
+
+/// Store a batch of messages in one transaction, each under its own savepoint
+///
+/// Returns the outcome of each message; the error is for the batch as a whole.
+pub(crate) async fn store_message_batch(
+    pool: &Pool<Sqlite>,
+    messages: &[StoredMessage],
+) -> Result<Vec<Result<(), DatabaseError>>, DatabaseError> {
+    let mut conn = message_writer(pool).await?;
+    let mut tx = sqlx::Acquire::begin(&mut conn).await?;
+    let mut outcomes = Vec::with_capacity(messages.len());
+    for message in messages {
+        let mut savepoint = sqlx::Acquire::begin(&mut *tx).await?;
+        let outcome = match insert_message(&mut *savepoint, message).await {
+            Ok(()) => match append_to_transparency_log(&mut *savepoint, message).await {
+                Ok(()) => insert_proof_chain(&mut savepoint, message).await,
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+        match outcome {
+            Ok(()) => savepoint.commit().await?,
+            Err(_) => savepoint.rollback().await?,
+        }
+        outcomes.push(outcome);
+    }
+    tx.commit().await?;
+    Ok(outcomes)
+}
+
+/// Clear a message's body and record its hash, unless it is already a tombstone
+///
+/// Returns whether the message was changed.
+async fn tombstone(conn: &mut sqlx::SqliteConnection, message: &StoredMessage, deleted_at: DateTime<Utc>) -> Result<bool, DatabaseError> {
+    let hash = message_hash(message.sender.as_bytes(), message.context.as_bytes(), message.body.as_bytes());
+    let result = sqlx::query(
+        r#"
+        UPDATE messages
+        SET body = '', deleted_at = ?2, message_hash = ?3
+        WHERE id = ?1 AND deleted_at IS NULL
+        "#
+    )
+    .bind(&message.id)
+    .bind(deleted_at)
+    .bind(hex::encode(hash))
+    .execute(&mut *conn)
+    .await?;
+
+    // Amended bodies go with the original; their hashes keep the chain verifiable
+    sqlx::query("UPDATE message_amendments SET body = '' WHERE message_id = ?1")
+        .bind(&message.id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Insert a message row using any SQLite executor (pool or transaction)
+async fn insert_message<'e, E>(executor: E, message: &StoredMessage) -> Result<(), DatabaseError>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let result = sqlx::query(
+        r#"
+        INSERT INTO messages (id, group_id, sender, context, body, proof, created_at, verified, thread_id, reply_to, expires_at, recipient, pqc_public_key, pqc_proof)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+        "#
+    )
+    .bind(&message.id)
+    .bind(&message.group_id)
+    .bind(&message.sender)
+    .bind(&message.context)
+    .bind(&message.body)
+    .bind(&message.proof)
+    .bind(message.created_at)
+    .bind(message.verified)
+    .bind(&message.thread_id)
+    .bind(&message.reply_to)
+    .bind(message.expires_at)
+    .bind(&message.recipient)
+    .bind(&message.pqc_public_key)
+    .bind(&message.pqc_proof)
+    .execute(executor)
+    .await?;
+
+    if result.rows_affected() == 1 {
+        Ok(())
+    } else {
+        Err(DatabaseError::SerializationError("Failed to insert message".to_string()))
+    }
+}
+
+/// Append a stored message as the next leaf of the transparency log
+async fn append_to_transparency_log<'e, E>(executor: E, message: &StoredMessage) -> Result<(), DatabaseError>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO transparency_log (leaf_index, message_id, leaf_hash, appended_at)
+        VALUES ((SELECT COALESCE(MAX(leaf_index) + 1, 0) FROM transparency_log), ?1, ?2, ?3)
+        "#
+    )
+    .bind(&message.id)
+    .bind(hex::encode(crate::transparency::log_leaf_hash(message)))
+    .bind(Utc::now())
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Record a stored message's proof hash and the parents its context commits to
+async fn insert_proof_chain(conn: &mut sqlx::SqliteConnection, message: &StoredMessage) -> Result<(), DatabaseError> {
+    let Some((proof_hash, parents)) = crate::proof_chains::chain_links(message) else {
+        return Ok(());
+    };
+    let proof_hash = hex::encode(proof_hash);
+
+    sqlx::query("INSERT OR IGNORE INTO proof_chain_nodes (proof_hash, message_id, created_at) VALUES (?1, ?2, ?3)")
+        .bind(&proof_hash)
+        .bind(&message.id)
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await?;
+    for parent in parents {
+        sqlx::query("INSERT OR IGNORE INTO proof_chain_links (child_hash, parent_hash, created_at) VALUES (?1, ?2, ?3)")
+            .bind(&proof_hash)
+            .bind(hex::encode(parent))
+            .bind(Utc::now())
+            .execute(&mut *conn)
+            .await?;
     }
+
+    Ok(())
+}
+
+/// Insert a key change row using any SQLite executor (pool or transaction)
+async fn insert_key_change<'e, E>(executor: E, change: &KeyChange) -> Result<(), DatabaseError>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO key_changes (id, user_id, previous_key, new_key, action, request_id, detected_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#
+    )
+    .bind(&change.id)
+    .bind(&change.user_id)
+    .bind(&change.previous_key)
+    .bind(&change.new_key)
+    .bind(&change.action)
+    .bind(&change.request_id)
+    .bind(change.detected_at)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Quote each search term as an FTS5 phrase so user input cannot inject operators
+fn fts_phrase_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 #[cfg(test)]
@@ -354,6 +4004,11 @@ mod tests {
             context: "test_context".to_string(),
             body: "Test message body".to_string(),
             proof: "proof1234".to_string(),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
         }
     }
 
@@ -594,6 +4249,28 @@ mod tests {
         assert!(!is_revoked);
     }
     
+    #[tokio::test]
+    async fn test_active_revocations_are_counted() {
+        // ARRANGE: One permanent and one expired revocation
+        let db = setup_test_db().await;
+        db.revoke_proof("counted_permanent", None, None, None).await.unwrap();
+        db.revoke_proof("counted_expired", None, None, Some(0)).await.unwrap();
+        sqlx::query("UPDATE revoked_proofs SET expires_at = datetime('now', '-1 hour') WHERE proof_signature = ?1")
+            .bind("counted_expired")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let expired_before = crate::metrics::REVOCATIONS_EXPIRED_TOTAL.get();
+
+        // ACT
+        let removed = db.cleanup_expired_revocations().await.unwrap();
+
+        // ASSERT: Only the permanent revocation is counted, and the removal is recorded
+        assert_eq!(removed, 1);
+        assert_eq!(db.count_active_revocations().await.unwrap(), 1);
+        assert!(crate::metrics::REVOCATIONS_EXPIRED_TOTAL.get() > expired_before);
+    }
+    
     #[tokio::test]
     async fn test_get_active_revocations() {
         // ARRANGE: Setup database and add multiple revocations
@@ -635,6 +4312,400 @@ mod tests {
         assert!(contains_temporary);
     }
 
+    #[tokio::test]
+    async fn test_invite_is_single_use() {
+        // ARRANGE: Mint an invite
+        let db = setup_test_db().await;
+        let invite = db.create_invite("group1", 24, Some("admin")).await.unwrap();
+        assert_eq!(invite.status, "pending");
+
+        // ACT: Redeem it twice
+        let redeemed = db.redeem_invite(&invite.code, "member-key").await.unwrap();
+        let second = db.redeem_invite(&invite.code, "other-key").await;
+
+        // ASSERT: Only the first redemption succeeds
+        assert_eq!(redeemed.status, "redeemed");
+        assert_eq!(redeemed.redeemed_by.as_deref(), Some("member-key"));
+        assert!(matches!(second, Err(DatabaseError::InviteUnavailable(_))));
+    }
+
+    #[tokio::test]
+    async fn test_expired_invite_cannot_be_redeemed() {
+        let db = setup_test_db().await;
+        let invite = db.create_invite("group1", -1, None).await.unwrap();
+
+        let result = db.redeem_invite(&invite.code, "member-key").await;
+
+        assert!(matches!(result, Err(DatabaseError::InviteUnavailable(_))));
+        assert_eq!(db.get_invite(&invite.code).await.unwrap().status, "expired");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_invite_not_found() {
+        let db = setup_test_db().await;
+
+        let result = db.redeem_invite("does-not-exist", "member-key").await;
+
+        assert!(matches!(result, Err(DatabaseError::InviteNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_store_receipt_is_idempotent_per_recipient() {
+        // ARRANGE: Store a message to acknowledge
+        let db = setup_test_db().await;
+        let message_id = db.store_message(StoredMessage::from(create_test_message())).await.unwrap();
+
+        // ACT: Store two receipts from the same recipient and one from another
+        db.store_receipt(&message_id, "recipient-a", "hash", "sig-1").await.unwrap();
+        let duplicate = db.store_receipt(&message_id, "recipient-a", "hash", "sig-2").await.unwrap();
+        db.store_receipt(&message_id, "recipient-b", "hash", "sig-3").await.unwrap();
+
+        // ASSERT: The first receipt per recipient is kept
+        assert_eq!(duplicate.signature, "sig-1");
+        let receipts = db.get_receipts_for_message(&message_id).await.unwrap();
+        assert_eq!(receipts.len(), 2);
+        assert!(db.get_receipts_for_message("other").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_deliveries_follow_group_filter_and_claims() {
+        // ARRANGE: One webhook for all groups and one for another group
+        let db = setup_test_db().await;
+        let all = db.create_webhook("https://a.example/hook", None, "secret-a", Some("admin")).await.unwrap();
+        let other = db.create_webhook("https://b.example/hook", Some("group2"), "secret-b", None).await.unwrap();
+        let message_id = db.store_message(StoredMessage::from(create_test_message())).await.unwrap();
+
+        // ACT: Queue an event for a default-group message and claim it twice
+        let queued = db.enqueue_webhook_deliveries(&message_id, "default", "{}", Utc::now()).await.unwrap();
+        let lease = Utc::now() + chrono::Duration::minutes(1);
+        let claimed = db.claim_due_webhook_deliveries(lease, 10).await.unwrap();
+        let reclaimed = db.claim_due_webhook_deliveries(lease, 10).await.unwrap();
+
+        // ASSERT: Only the unfiltered webhook is notified, and a claim is exclusive
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].webhook_id, all.id);
+        assert_eq!(claimed.len(), 1);
+        assert!(reclaimed.is_empty());
+        assert!(db.get_webhook_deliveries(&other.id, 10).await.unwrap().is_empty());
+
+        db.record_webhook_delivery_failure(&claimed[0].id, "timeout", None, None).await.unwrap();
+        let history = db.get_webhook_deliveries(&all.id, 10).await.unwrap();
+        assert_eq!(history[0].status, "failed");
+        assert_eq!(history[0].attempts, 1);
+
+        db.delete_webhook(&all.id).await.unwrap();
+        assert!(matches!(db.get_webhook(&all.id).await, Err(DatabaseError::WebhookNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_messages_filter_and_purge() {
+        // ARRANGE: Two rejections with different reasons, one of them old
+        let db = setup_test_db().await;
+        for (id, reason, age_days) in [("r1", "invalid_signature", 0), ("r2", "proof_revoked", 40)] {
+            db.store_rejected_message(&RejectedMessage {
+                id: id.to_string(),
+                sender: "abcd1234".to_string(),
+                reason: reason.to_string(),
+                error: "rejected".to_string(),
+                payload: "{}".to_string(),
+                submitted_by: None,
+                rejected_at: Utc::now() - chrono::Duration::days(age_days),
+            }).await.unwrap();
+        }
+
+        // ACT: Filter by reason, then purge entries older than 30 days
+        let revoked = db.get_rejected_messages(Some("proof_revoked"), None, 10, 0).await.unwrap();
+        let purged = db.delete_rejected_messages_before(Utc::now() - chrono::Duration::days(30)).await.unwrap();
+        let remaining = db.get_rejected_messages(None, Some("abcd1234"), 10, 0).await.unwrap();
+
+        // ASSERT: Filters apply and only the old entry is purged
+        assert_eq!(revoked.len(), 1);
+        assert_eq!(revoked[0].id, "r2");
+        assert_eq!(purged, 1);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, "r1");
+    }
+
+    #[tokio::test]
+    async fn test_search_messages_ranks_and_scopes_by_group() {
+        // ARRANGE: Messages in two groups
+        let db = setup_test_db().await;
+        for (group, body) in [
+            ("group1", "quarterly audit report ready"),
+            ("group1", "lunch plans"),
+            ("group2", "audit findings for the audit committee"),
+        ] {
+            let mut message = StoredMessage::from(create_test_message());
+            message.group_id = group.to_string();
+            message.body = body.to_string();
+            db.store_message(message).await.unwrap();
+        }
+
+        // ACT: Search across all groups and within one group
+        let all = db.search_messages("audit", None, 10, 0).await.unwrap();
+        let scoped = db.search_messages("audit", Some(&["group1".to_string()]), 10, 0).await.unwrap();
+
+        // ASSERT: Matches are ranked, highlighted and scoped
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].message.group_id, "group2"); // Two occurrences rank higher
+        assert!(all[0].snippet.contains("<mark>audit</mark>"));
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].message.body, "quarterly audit report ready");
+    }
+
+    #[tokio::test]
+    async fn test_search_treats_operators_as_text() {
+        let db = setup_test_db().await;
+        db.store_message(StoredMessage::from(create_test_message())).await.unwrap();
+
+        let result = db.search_messages("NOT \"unbalanced AND (", None, 10, 0).await;
+
+        assert!(result.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_messages_by_sender_with_time_range() {
+        // ARRANGE: Messages from two senders at different times
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        for (sender, hours_ago) in [("aaaa", 3), ("aaaa", 2), ("aaaa", 1), ("bbbb", 1)] {
+            let mut message = StoredMessage::from(create_test_message());
+            message.sender = sender.to_string();
+            message.created_at = now - chrono::Duration::hours(hours_ago);
+            db.store_message(message).await.unwrap();
+        }
+
+        // ACT: List one sender's messages with and without a time range
+        let all = db.get_messages_by_sender("aaaa", None, None, None).await.unwrap();
+        let windowed = db
+            .get_messages_by_sender("AAAA", Some(now - chrono::Duration::minutes(150)), Some(now - chrono::Duration::minutes(30)), None)
+            .await
+            .unwrap();
+
+        // ASSERT: Only that sender's messages in range are returned, newest first
+        assert_eq!(all.len(), 3);
+        assert!(all.windows(2).all(|w| w[0].created_at >= w[1].created_at));
+        assert_eq!(windowed.len(), 2);
+        assert!(windowed.iter().all(|m| m.sender == "aaaa"));
+    }
+
+    #[tokio::test]
+    async fn test_federated_message_is_stored_once_per_origin() {
+        // ARRANGE: One origin delivered twice under different local IDs
+        let db = setup_test_db().await;
+        let origin = FederatedOrigin {
+            origin_relay: "relay-eu".to_string(),
+            origin_message_id: "msg-1".to_string(),
+        };
+
+        // ACT: Store both copies
+        let first = db
+            .store_federated_message(&origin, "relay-eu", StoredMessage::from(create_test_message()))
+            .await
+            .unwrap();
+        let second = db
+            .store_federated_message(&origin, "relay-us", StoredMessage::from(create_test_message()))
+            .await
+            .unwrap();
+
+        // ASSERT: Only the first copy is stored and it appears in the digest
+        assert!(first);
+        assert!(!second);
+        assert_eq!(db.get_message_count("default").await.unwrap(), 1);
+        assert!(db.is_federated_message_known(&origin).await.unwrap());
+        let digest = db.get_federated_digest(Utc::now() - chrono::Duration::minutes(1), 10).await.unwrap();
+        assert_eq!(digest, vec![origin.clone()]);
+        assert_eq!(db.get_federated_message(&origin).await.unwrap().body, "Test message body");
+    }
+
+    #[tokio::test]
+    async fn test_migrations_can_be_checked_and_applied_to_a_target() {
+        // ARRANGE: A database without any migrations
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let status = db.migration_status().await.unwrap();
+        assert!(status.iter().all(|migration| migration.state == MigrationState::Pending));
+
+        // ACT: Dry-run everything, then apply up to the second migration
+        let dry_run = db.migrate_dry_run(None).await.unwrap();
+        let applied = db.migrate_to(Some(status[1].version)).await.unwrap();
+
+        // ASSERT: The dry run changed nothing and only the first two were applied
+        assert_eq!(dry_run.len(), status.len());
+        assert_eq!(applied.len(), 2);
+        let states: Vec<MigrationState> = db.migration_status().await.unwrap().iter().map(|migration| migration.state).collect();
+        assert_eq!(&states[..3], [MigrationState::Applied, MigrationState::Applied, MigrationState::Pending]);
+        assert!(db.migrate_to(Some(9999)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_schema_ahead_of_binary_is_refused() {
+        // ARRANGE: A fully migrated database with a migration from a newer binary
+        let db = setup_test_db().await;
+        sqlx::query("INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) VALUES (9999, 'from the future', TRUE, X'00', 0)")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        // ACT: Migrate again
+        let result = db.migrate().await;
+
+        // ASSERT: The newer schema is reported rather than migrated
+        assert!(matches!(result, Err(DatabaseError::SchemaAhead { database_version: 9999, .. })));
+        let status = db.migration_status().await.unwrap();
+        assert_eq!(status.last().unwrap().state, MigrationState::Unknown);
+    }
+
+    #[tokio::test]
+    async fn test_message_reads_are_served_by_the_read_replica() {
+        // ARRANGE: A primary and a separately migrated replica that has not caught up
+        let dir = tempfile::tempdir().unwrap();
+        let url = |name: &str| format!("sqlite:{}?mode=rwc", dir.path().join(name).display());
+        for name in ["primary.db", "replica.db"] {
+            Database::new(&url(name)).await.unwrap().migrate().await.unwrap();
+        }
+        let config = crate::config::DatabaseConfig {
+            url: url("primary.db"),
+            read_replica_url: Some(url("replica.db")),
+            max_connections: 2,
+            ..Default::default()
+        };
+        let db = Database::connect(&config).await.unwrap();
+
+        // ACT: Store a message on the primary
+        let message_id = db.store_message(StoredMessage::from(create_test_message())).await.unwrap();
+
+        // ASSERT: Group reads come from the replica while lookups stay on the primary
+        assert!(db.get_messages_by_group("default", None).await.unwrap().is_empty());
+        assert_eq!(db.get_message_count("default").await.unwrap(), 0);
+        assert!(db.get_message_by_id(&message_id).await.is_ok());
+        let replica_write = sqlx::query("DELETE FROM messages").execute(db.reader()).await;
+        assert!(replica_write.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_configured_pragmas_are_applied() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::config::DatabaseConfig {
+            url: format!("sqlite:{}?mode=rwc", dir.path().join("relay.db").display()),
+            busy_timeout_ms: 1500,
+            ..Default::default()
+        };
+
+        let pragmas = Database::connect(&config).await.unwrap().pragmas().await.unwrap();
+
+        assert_eq!(
+            pragmas,
+            SqlitePragmas {
+                journal_mode: "wal".to_string(),
+                busy_timeout_ms: 1500,
+                synchronous: "normal",
+            }
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_message_writes_wait_for_the_lock() {
+        // ARRANGE: A file database written through several pooled connections
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::config::DatabaseConfig {
+            url: format!("sqlite:{}?mode=rwc", dir.path().join("relay.db").display()),
+            ..Default::default()
+        };
+        let db = std::sync::Arc::new(Database::connect(&config).await.unwrap());
+        db.migrate().await.unwrap();
+
+        // ACT: Store many messages at once
+        let mut writes = tokio::task::JoinSet::new();
+        for index in 0..100 {
+            let db = db.clone();
+            writes.spawn(async move {
+                let mut message = StoredMessage::from(create_test_message());
+                message.context = format!("concurrent_context_{}", index);
+                db.store_message(message).await
+            });
+        }
+        let mut failures = Vec::new();
+        while let Some(result) = writes.join_next().await {
+            if let Err(e) = result.unwrap() {
+                failures.push(e.to_string());
+            }
+        }
+
+        // ASSERT: Every write went through
+        assert!(failures.is_empty(), "{} writes failed, first: {}", failures.len(), failures[0]);
+        assert_eq!(db.get_message_count("default").await.unwrap(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_statement_timeout_interrupts_long_queries() {
+        let config = crate::config::DatabaseConfig {
+            url: "sqlite::memory:".to_string(),
+            statement_timeout_ms: Some(50),
+            ..Default::default()
+        };
+        let db = Database::connect(&config).await.unwrap();
+
+        let endless = sqlx::query("WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) SELECT COUNT(*) FROM n")
+            .fetch_one(&db.pool)
+            .await;
+
+        assert!(endless.is_err());
+        assert!(sqlx::query("SELECT 1").fetch_one(&db.pool).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_list_groups_and_export_audit_entries() {
+        // ARRANGE: Messages in two groups and two audit entries, one of them old
+        let db = setup_test_db().await;
+        for group_id in ["group1", "group1", "group2"] {
+            let mut message = StoredMessage::from(create_test_message());
+            message.group_id = group_id.to_string();
+            db.store_message(message).await.unwrap();
+        }
+        let mut logger = proof_messenger_protocol::compliance::ComplianceAuditLogger::new();
+        logger.log_policy_violation("login", "ssn", "forbidden_field");
+        logger.log_policy_violation("login", "password", "forbidden_field");
+        let mut entries = logger.get_entries().to_vec();
+        entries[0].timestamp = Utc::now() - chrono::Duration::days(3);
+        db.store_audit_entries(&entries).await.unwrap();
+
+        // ACT: List the groups and export the last day's audit entries
+        let groups = db.list_groups().await.unwrap();
+        let exported = db.get_audit_entries_since(Utc::now() - chrono::Duration::days(1)).await.unwrap();
+
+        // ASSERT: Groups are counted and only the recent entry is exported
+        let mut counts: Vec<(String, i64)> = groups.into_iter().map(|group| (group.group_id, group.message_count)).collect();
+        counts.sort();
+        assert_eq!(counts, vec![("group1".to_string(), 2), ("group2".to_string(), 1)]);
+        assert_eq!(exported.len(), 1);
+        assert!(exported[0].event_details.contains("password"));
+    }
+
+    #[tokio::test]
+    async fn test_tombstoned_message_keeps_its_proof_and_log_leaf() {
+        // ARRANGE: A stored, searchable message
+        let db = setup_test_db().await;
+        let message = StoredMessage::from(create_test_message());
+        let id = db.store_message(message.clone()).await.unwrap();
+
+        // ACT: Delete it twice
+        let first = db.tombstone_message(&id).await.unwrap();
+        let second = db.tombstone_message(&id).await.unwrap();
+
+        // ASSERT: The body is gone but the transparency leaf can still be checked
+        assert!(first);
+        assert!(!second);
+        let tombstone = db.get_message_by_id(&id).await.unwrap();
+        assert!(tombstone.is_tombstone());
+        assert_eq!(tombstone.body, "");
+        assert_eq!(tombstone.proof, message.proof);
+        let hash: [u8; 32] = hex::decode(tombstone.message_hash.as_deref().unwrap()).unwrap().try_into().unwrap();
+        let leaf = proof_messenger_protocol::transparency::leaf_hash(&id, &hash, message.proof.as_bytes());
+        assert_eq!(db.get_transparency_log_entry(&id).await.unwrap().leaf_hash, hex::encode(leaf));
+        assert!(db.search_messages("Test", None, 10, 0).await.unwrap().is_empty());
+        assert!(matches!(db.tombstone_message("missing").await, Err(DatabaseError::MessageNotFound(_))));
+    }
+
     #[tokio::test]
     async fn test_database_health_check() {
         // ARRANGE: Setup database
@@ -671,4 +4742,4 @@ mod tests {
         assert_eq!(messages[0].body, "Second message");
         assert_eq!(messages[1].body, "First message");
     }
-}
\ No newline at end of file
+}
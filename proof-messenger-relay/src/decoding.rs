@@ -0,0 +1,117 @@
+//! Hex Decoding Module
+//!
+//! Proof verification decodes a sender key, a context and a proof from hex on
+//! every relayed message. Keys and raw signatures have fixed sizes, so
+//! [`decode_fixed`] checks the hex length before decoding and writes into a
+//! stack array. Contexts, proof envelopes and post-quantum halves vary in size
+//! and are decoded into the calling thread's [`Scratch`] buffers, which are
+//! reused from one message to the next instead of allocated per request.
+//!
+//! Buffers that grew past [`MAX_RETAINED_BYTES`] are released after use, so a
+//! single oversized request does not pin memory on a worker thread.
+
+use std::cell::RefCell;
+use thiserror::Error;
+
+/// Largest buffer kept between messages
+pub const MAX_RETAINED_BYTES: usize = 64 * 1024;
+
+/// Errors raised decoding a fixed-size hex value
+#[derive(Debug, Error, PartialEq)]
+pub enum HexError {
+    #[error("expected {expected} bytes, got {actual} hex characters")]
+    Length { expected: usize, actual: usize },
+
+    #[error("Invalid hex encoding: {0}")]
+    Encoding(#[from] hex::FromHexError),
+}
+
+/// Reusable buffers for the variable-length parts of a message
+#[derive(Debug, Default)]
+pub struct Scratch {
+    pub context: Vec<u8>,
+    pub proof: Vec<u8>,
+    pub pqc_public_key: Vec<u8>,
+    pub pqc_proof: Vec<u8>,
+}
+
+impl Scratch {
+    /// Release any buffer that grew past [`MAX_RETAINED_BYTES`]
+    fn trim(&mut self) {
+        for buffer in [&mut self.context, &mut self.proof, &mut self.pqc_public_key, &mut self.pqc_proof] {
+            if buffer.capacity() > MAX_RETAINED_BYTES {
+                *buffer = Vec::new();
+            }
+        }
+    }
+}
+
+thread_local! {
+    static SCRATCH: RefCell<Scratch> = RefCell::new(Scratch::default());
+}
+
+/// Decode exactly `N` bytes of hex into a stack array
+///
+/// The length is checked before any character is decoded.
+pub fn decode_fixed<const N: usize>(hex: &str) -> Result<[u8; N], HexError> {
+    if hex.len() != N * 2 {
+        return Err(HexError::Length { expected: N, actual: hex.len() });
+    }
+    let mut bytes = [0u8; N];
+    hex::decode_to_slice(hex, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Decode hex into `buffer`, replacing its contents and reusing its capacity
+pub fn decode_into(hex: &str, buffer: &mut Vec<u8>) -> Result<(), hex::FromHexError> {
+    buffer.clear();
+    buffer.resize(hex.len() / 2, 0);
+    hex::decode_to_slice(hex, buffer)
+}
+
+/// Run `f` with this thread's scratch buffers
+///
+/// A nested call gets fresh buffers rather than panicking.
+pub fn with_scratch<R>(f: impl FnOnce(&mut Scratch) -> R) -> R {
+    SCRATCH.with(|cell| match cell.try_borrow_mut() {
+        Ok(mut scratch) => {
+            let result = f(&mut scratch);
+            scratch.trim();
+            result
+        }
+        Err(_) => f(&mut Scratch::default()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_values_are_length_checked_before_decoding() {
+        assert_eq!(decode_fixed::<2>("0aFf"), Ok([0x0a, 0xff]));
+        assert_eq!(decode_fixed::<2>("zz"), Err(HexError::Length { expected: 2, actual: 2 }));
+        assert!(matches!(decode_fixed::<2>("0azz"), Err(HexError::Encoding(_))));
+    }
+
+    #[test]
+    fn test_scratch_buffers_are_reused_and_trimmed() {
+        let first = with_scratch(|scratch| {
+            decode_into("00112233", &mut scratch.context).unwrap();
+            assert_eq!(scratch.context, [0x00, 0x11, 0x22, 0x33]);
+            scratch.context.as_ptr()
+        });
+        let second = with_scratch(|scratch| {
+            decode_into("4455", &mut scratch.context).unwrap();
+            assert_eq!(scratch.context, [0x44, 0x55]);
+            assert!(decode_into("445", &mut scratch.proof).is_err());
+            with_scratch(|nested| assert!(nested.context.is_empty()));
+            scratch.context.as_ptr()
+        });
+        assert_eq!(first, second);
+
+        let oversized = "00".repeat(MAX_RETAINED_BYTES + 1);
+        with_scratch(|scratch| decode_into(&oversized, &mut scratch.context).unwrap());
+        with_scratch(|scratch| assert_eq!(scratch.context.capacity(), 0));
+    }
+}
@@ -0,0 +1,468 @@
+//! Delivery Status Module
+//!
+//! Tracks how far each recipient got with a message, so a sender can tell
+//! when an approver has seen a request. A message moves through
+//! [`DeliveryState`]s: `submitted` to the relay, `stored` once verified,
+//! then, per recipient, `delivered` when the recipient's client acknowledges
+//! it and `read` when the recipient marks it read. States only move forward.
+//!
+//! - WebSocket subscribers acknowledge a message by sending
+//!   `{"ack": "<message_id>"}` (or `{"ack": "<message_id>", "state": "read"}`)
+//!   on the socket it arrived on
+//! - `POST /message/:message_id/read` marks a message read
+//! - `GET /message/:message_id/deliveries` lists every recipient's state
+//!
+//! Recipients are the caller's user ID on authenticated routes. Without
+//! authentication they name themselves: in the `recipient` query parameter
+//! of `GET /ws/:group_id` or the body of `POST /message/:message_id/read`.
+//! Unlike receipts (see [`crate::receipts`]) delivery states are not signed,
+//! so they show progress rather than prove it.
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+use utoipa::ToSchema;
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, DatabaseError, StoredDelivery},
+    get_tenant_message,
+    request_id::RequestId,
+    tenancy::TenantScope,
+    AppError,
+};
+
+/// Longest recipient name accepted
+const MAX_RECIPIENT_LENGTH: usize = 256;
+
+/// Errors raised when recording delivery states
+#[derive(Debug, Error)]
+pub enum DeliveryError {
+    #[error("Invalid recipient: {0}")]
+    InvalidRecipient(String),
+
+    #[error("Invalid acknowledgement: {0}")]
+    InvalidAck(String),
+}
+
+/// How far a message got, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryState {
+    /// Received by the relay but not stored yet, such as a scheduled message
+    Submitted,
+    /// Verified and stored, waiting for recipients
+    Stored,
+    /// Acknowledged by the recipient's client
+    Delivered,
+    /// Marked read by the recipient
+    Read,
+}
+
+impl DeliveryState {
+    /// Name of the state as stored and serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryState::Submitted => "submitted",
+            DeliveryState::Stored => "stored",
+            DeliveryState::Delivered => "delivered",
+            DeliveryState::Read => "read",
+        }
+    }
+
+    /// State of a stored delivery
+    fn of(delivery: &StoredDelivery) -> Self {
+        if delivery.state == DeliveryState::Read.as_str() {
+            DeliveryState::Read
+        } else {
+            DeliveryState::Delivered
+        }
+    }
+}
+
+/// Request body for marking a message read without authentication
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MarkReadRequest {
+    /// User ID or public key of the recipient
+    pub recipient: String,
+}
+
+/// A frame a WebSocket subscriber sends to acknowledge a message
+#[derive(Debug, Deserialize)]
+pub struct AckFrame {
+    /// ID of the acknowledged message
+    pub ack: String,
+    /// State reached, `delivered` unless given
+    #[serde(default)]
+    pub state: Option<DeliveryState>,
+}
+
+/// Every recipient's progress with a message
+#[derive(Debug, Clone, Serialize)]
+pub struct DeliveryStatus {
+    pub message_id: String,
+    /// Public key of the sender (hex encoded)
+    pub sender: String,
+    /// Furthest state any recipient reached, `stored` until one acknowledges
+    pub state: DeliveryState,
+    /// Recipients that got the message, including those that read it
+    pub delivered_count: usize,
+    /// Recipients that read the message
+    pub read_count: usize,
+    /// Each recipient's state, earliest delivery first
+    pub deliveries: Vec<StoredDelivery>,
+}
+
+/// Create router for delivery status endpoints
+pub fn delivery_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/message/:message_id/read", post(mark_read_handler))
+        .route("/message/:message_id/deliveries", get(get_deliveries_handler))
+}
+
+/// Create router for authenticated delivery status endpoints
+pub fn authenticated_delivery_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/message/:message_id/read", post(authenticated_mark_read_handler))
+        .route("/message/:message_id/deliveries", get(authenticated_get_deliveries_handler))
+}
+
+/// Check a recipient name
+fn validate_recipient(recipient: &str) -> Result<(), AppError> {
+    if recipient.trim().is_empty() || recipient.len() > MAX_RECIPIENT_LENGTH {
+        return Err(DeliveryError::InvalidRecipient(format!(
+            "recipient must be 1 to {} characters",
+            MAX_RECIPIENT_LENGTH
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Record that a recipient got a tenant's message as far as `state`
+pub async fn record(
+    db: &Database,
+    tenant: &TenantScope,
+    message_id: &str,
+    recipient: &str,
+    state: DeliveryState,
+) -> Result<StoredDelivery, AppError> {
+    validate_recipient(recipient)?;
+    if state < DeliveryState::Delivered {
+        return Err(DeliveryError::InvalidAck(format!("recipients can only report delivered or read, not {}", state.as_str())).into());
+    }
+    get_tenant_message(db, tenant, message_id).await?;
+    Ok(db.record_delivery(message_id, recipient, state.as_str()).await?)
+}
+
+/// Record an acknowledgement sent by a subscriber to `group_id`
+///
+/// Only messages of the subscribed group can be acknowledged.
+pub async fn acknowledge(db: &Database, group_id: &str, recipient: Option<&str>, frame: &str) -> Result<StoredDelivery, AppError> {
+    let frame: AckFrame = serde_json::from_str(frame).map_err(|e| DeliveryError::InvalidAck(e.to_string()))?;
+    let recipient = recipient.ok_or_else(|| {
+        DeliveryError::InvalidRecipient("subscribe with a recipient to acknowledge messages".to_string())
+    })?;
+    validate_recipient(recipient)?;
+    let state = frame.state.unwrap_or(DeliveryState::Delivered);
+    if state < DeliveryState::Delivered {
+        return Err(DeliveryError::InvalidAck(format!("recipients can only report delivered or read, not {}", state.as_str())).into());
+    }
+
+    let message = db.get_message_by_id(&frame.ack).await?;
+    if message.group_id != group_id || message.is_direct() {
+        return Err(DatabaseError::MessageNotFound(frame.ack).into());
+    }
+    Ok(db.record_delivery(&message.id, recipient, state.as_str()).await?)
+}
+
+/// Every recipient's progress with a tenant's message
+pub async fn delivery_status(db: &Database, tenant: &TenantScope, message_id: &str) -> Result<DeliveryStatus, AppError> {
+    let message = get_tenant_message(db, tenant, message_id).await?;
+    let deliveries = db.get_deliveries_for_message(message_id).await?;
+    let read_count = deliveries.iter().filter(|delivery| DeliveryState::of(delivery) == DeliveryState::Read).count();
+
+    Ok(DeliveryStatus {
+        message_id: message.id,
+        sender: message.sender,
+        state: deliveries.iter().map(DeliveryState::of).max().unwrap_or(DeliveryState::Stored),
+        delivered_count: deliveries.len(),
+        read_count,
+        deliveries,
+    })
+}
+
+/// Handler to mark a message read for a recipient
+#[utoipa::path(
+    post,
+    path = "/message/{message_id}/read",
+    operation_id = "markMessageRead",
+    tag = "deliveries",
+    params(("message_id" = String, Path, description = "Message ID")),
+    request_body = MarkReadRequest,
+    responses(
+        (status = 200, description = "The recipient's delivery state", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn mark_read_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+    Json(payload): Json<MarkReadRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Marking message {} read", message_id);
+
+    let delivery = record(&db, &tenant, &message_id, &payload.recipient, DeliveryState::Read).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "delivery": delivery
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler to list every recipient's delivery state for a message
+#[utoipa::path(
+    get,
+    path = "/message/{message_id}/deliveries",
+    operation_id = "listDeliveries",
+    tag = "deliveries",
+    params(("message_id" = String, Path, description = "Message ID")),
+    responses(
+        (status = 200, description = "The message's delivery states", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_deliveries_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Retrieving delivery states for message: {}", message_id);
+
+    let status = delivery_status(&db, &tenant, &message_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "delivery": status
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to mark a message read for the caller
+#[instrument(skip_all)]
+async fn authenticated_mark_read_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} marking message {} read", auth.user_id, message_id);
+
+    let delivery = record(&db, &tenant, &message_id, &auth.user_id, DeliveryState::Read).await?;
+
+    // Log the read
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("message_id".to_string(), message_id.clone());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Message marked read".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log message read: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "delivery": delivery,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to list every recipient's delivery state for a message
+#[instrument(skip_all)]
+async fn authenticated_get_deliveries_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} retrieving delivery states for message: {}", auth.user_id, message_id);
+
+    let status = delivery_status(&db, &tenant, &message_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "delivery": status,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::StoredMessage;
+    use crate::subscriptions::Subscriptions;
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::Extension;
+    use futures::{SinkExt, StreamExt};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    async fn store(db: &Database, group_id: &str) -> String {
+        let mut message = StoredMessage::from(crate::Message {
+            sender: "a".repeat(64),
+            context: "00".to_string(),
+            body: "approve transfer".to_string(),
+            proof: "00".repeat(64),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        });
+        message.group_id = group_id.to_string();
+        db.store_message(message).await.unwrap()
+    }
+
+    async fn call(app: &Router, method: &str, uri: &str, body: Option<serde_json::Value>) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body.map(|body| Body::from(body.to_string())).unwrap_or_else(Body::empty))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_states_only_move_forward() {
+        // ARRANGE: A stored message nobody has acknowledged yet
+        let db = setup_db().await;
+        let message_id = store(&db, "default").await;
+        let app = crate::create_app(db.clone());
+        let (_, before) = call(&app, "GET", &format!("/message/{}/deliveries", message_id), None).await;
+
+        // ACT: One approver reads it, then their client acknowledges it late; another only receives it
+        let (read_status, read) = call(
+            &app,
+            "POST",
+            &format!("/message/{}/read", message_id),
+            Some(serde_json::json!({ "recipient": "approver-1" })),
+        )
+        .await;
+        db.record_delivery(&message_id, "approver-1", "delivered").await.unwrap();
+        db.record_delivery(&message_id, "approver-2", "delivered").await.unwrap();
+        let (_, after) = call(&app, "GET", &format!("/message/{}/deliveries", message_id), None).await;
+
+        // ASSERT: Reading implies delivery and is never undone
+        assert_eq!(before["delivery"]["state"], "stored");
+        assert_eq!(read_status, StatusCode::OK);
+        assert_eq!(read["delivery"]["state"], "read");
+        assert!(read["delivery"]["delivered_at"].is_string());
+        let status = &after["delivery"];
+        assert_eq!(status["state"], "read");
+        assert_eq!(status["delivered_count"], 2);
+        assert_eq!(status["read_count"], 1);
+        assert_eq!(status["deliveries"][0]["recipient"], "approver-1");
+        assert_eq!(status["deliveries"][0]["state"], "read");
+        assert_eq!(status["deliveries"][1]["state"], "delivered");
+    }
+
+    #[tokio::test]
+    async fn test_reads_need_a_recipient_and_a_stored_message() {
+        let db = setup_db().await;
+        let message_id = store(&db, "default").await;
+        let app = crate::create_app(db.clone());
+
+        let (blank, body) = call(&app, "POST", &format!("/message/{}/read", message_id), Some(serde_json::json!({ "recipient": " " }))).await;
+        let (missing, _) = call(&app, "POST", "/message/missing/read", Some(serde_json::json!({ "recipient": "approver-1" }))).await;
+
+        assert_eq!(blank, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INVALID_RECIPIENT");
+        assert_ne!(missing, StatusCode::OK);
+        assert!(db.get_deliveries_for_message(&message_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_acknowledgements_are_limited_to_the_subscribed_group() {
+        let db = setup_db().await;
+        let ours = store(&db, "default").await;
+        let theirs = store(&db, "other").await;
+        let ack = |id: &str| serde_json::json!({ "ack": id }).to_string();
+
+        assert!(acknowledge(&db, "default", Some("approver-1"), &ack(&ours)).await.is_ok());
+        assert!(acknowledge(&db, "default", Some("approver-1"), &ack(&theirs)).await.is_err());
+        assert!(acknowledge(&db, "default", None, &ack(&ours)).await.is_err());
+        assert!(acknowledge(&db, "default", Some("approver-1"), "not json").await.is_err());
+        let stored = acknowledge(&db, "default", Some("approver-1"), &serde_json::json!({ "ack": ours, "state": "stored" }).to_string()).await;
+        assert!(matches!(stored, Err(AppError::Delivery(DeliveryError::InvalidAck(_)))));
+        assert!(db.get_deliveries_for_message(&theirs).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_websocket_acks_mark_messages_delivered() {
+        // ARRANGE: A recipient subscribed to a group over a WebSocket
+        let db = setup_db().await;
+        let subscriptions = Arc::new(Subscriptions::new(16));
+        let app = crate::create_app(db.clone()).layer(Extension(subscriptions.clone()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws/default?recipient=approver-1", address))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // ACT: Deliver a message and acknowledge it on the socket
+        let message_id = store(&db, "default").await;
+        subscriptions.publish(&db.get_message_by_id(&message_id).await.unwrap()).await;
+        let frame = tokio::time::timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap();
+        assert!(frame.to_text().unwrap().contains(&message_id));
+        socket
+            .send(tokio_tungstenite::tungstenite::Message::Text(serde_json::json!({ "ack": message_id }).to_string()))
+            .await
+            .unwrap();
+
+        // ASSERT: The recipient's state becomes delivered
+        let mut deliveries = Vec::new();
+        for _ in 0..50 {
+            deliveries = db.get_deliveries_for_message(&message_id).await.unwrap();
+            if !deliveries.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].recipient, "approver-1");
+        assert_eq!(deliveries[0].state, "delivered");
+    }
+}
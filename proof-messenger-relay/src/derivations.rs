@@ -0,0 +1,182 @@
+//! Derivation Attestation Module
+//!
+//! Clients sign with a separate key per group or purpose, derived from one
+//! master identity (see [`proof_messenger_protocol::derivation`]). When a
+//! client needs to show that such a child key belongs to its identity, it
+//! presents a [`DerivationAttestation`] signed by both keys, and the relay
+//! checks it here.
+//!
+//! Attestations are verified, not stored: a lookup from child keys to their
+//! master would link exactly the keys derivation keeps apart. Revoking either
+//! signature through the revocation endpoints withdraws the attestation.
+
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::post,
+    Router,
+};
+use proof_messenger_protocol::derivation::DerivationAttestation;
+use std::sync::Arc;
+use tracing::{info, instrument};
+
+use crate::{auth_middleware::AuthContext, database::Database, AppError};
+
+/// Create router for derivation attestation endpoints
+pub fn derivation_routes() -> Router<Arc<Database>> {
+    Router::new().route("/derivations/verify", post(verify_handler))
+}
+
+/// Create router for authenticated derivation attestation endpoints
+pub fn authenticated_derivation_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new().route("/derivations/verify", post(authenticated_verify_handler))
+}
+
+/// Check an attestation's signatures and whether either was revoked
+pub async fn verify_derivation_attestation(
+    db: &Database,
+    attestation: &DerivationAttestation,
+) -> Result<serde_json::Value, AppError> {
+    let verification = attestation.verify();
+    let mut revoked = false;
+    for signature in [&attestation.master_signature, &attestation.child_signature] {
+        revoked |= db.is_proof_revoked(&hex::encode(signature)).await?;
+    }
+
+    Ok(serde_json::json!({
+        "valid": verification.is_ok() && !revoked,
+        "signature_valid": verification.is_ok(),
+        "revoked": revoked,
+        "master_public_key": hex::encode(attestation.master_public_key),
+        "child_public_key": hex::encode(attestation.child_public_key),
+        "path": attestation.path,
+        "error": verification.err().map(|e| e.to_string()),
+    }))
+}
+
+/// Handler to verify a derivation attestation
+#[utoipa::path(
+    post,
+    path = "/derivations/verify",
+    operation_id = "verifyDerivationAttestation",
+    tag = "derivations",
+    request_body = Object,
+    responses(
+        (status = 200, description = "Whether the attestation links the child key to the master identity", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn verify_handler(
+    State(db): State<Arc<Database>>,
+    Json(attestation): Json<DerivationAttestation>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Verifying derivation attestation at {}", attestation.path);
+
+    let mut response = verify_derivation_attestation(&db, &attestation).await?;
+    response["status"] = "success".into();
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Authenticated handler to verify a derivation attestation
+#[instrument(skip_all)]
+async fn authenticated_verify_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    Json(attestation): Json<DerivationAttestation>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} verifying derivation attestation at {}", auth.user_id, attestation.path);
+
+    let mut response = verify_derivation_attestation(&db, &attestation).await?;
+    response["status"] = "success".into();
+    response["authenticated_user"] = auth.user_id.into();
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use proof_messenger_protocol::derivation::{DerivationPath, MasterSeed};
+    use tower::ServiceExt;
+
+    async fn setup_test_app() -> (Router, Arc<Database>) {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+
+        let app = Router::new().merge(derivation_routes()).with_state(db.clone());
+        (app, db)
+    }
+
+    fn attestation(context: &str) -> DerivationAttestation {
+        MasterSeed::from_bytes(&[4u8; 32]).unwrap().attest(&DerivationPath::for_context(context)).1
+    }
+
+    async fn verify(app: &Router, body: serde_json::Value) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/derivations/verify")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_attestation_links_child_to_master() {
+        // ARRANGE: A per-group key attested by its master identity
+        let (app, _) = setup_test_app().await;
+        let attestation = attestation("group:ops");
+
+        // ACT: Verify it
+        let (status, verified) = verify(&app, serde_json::to_value(&attestation).unwrap()).await;
+
+        // ASSERT: The relay reports the link
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(verified["valid"], true);
+        assert_eq!(verified["master_public_key"], hex::encode(attestation.master_public_key));
+        assert_eq!(verified["child_public_key"], hex::encode(attestation.child_public_key));
+        assert_eq!(verified["path"], attestation.path.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_or_revoked_attestation_is_invalid() {
+        let (app, db) = setup_test_app().await;
+        let mut moved = attestation("group:ops");
+        moved.path = DerivationPath::for_context("group:finance");
+        let revoked = attestation("group:payroll");
+        db.revoke_proof(&hex::encode(&revoked.child_signature), Some("device lost"), None, None).await.unwrap();
+
+        let (_, tampered) = verify(&app, serde_json::to_value(&moved).unwrap()).await;
+        let (_, withdrawn) = verify(&app, serde_json::to_value(&revoked).unwrap()).await;
+
+        assert_eq!(tampered["valid"], false);
+        assert_eq!(tampered["signature_valid"], false);
+        assert!(tampered["error"].is_string());
+        assert_eq!(withdrawn["signature_valid"], true);
+        assert_eq!(withdrawn["revoked"], true);
+        assert_eq!(withdrawn["valid"], false);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_path_is_rejected() {
+        let (app, _) = setup_test_app().await;
+        let mut body = serde_json::to_value(attestation("group:ops")).unwrap();
+        body["path"] = "m/44'/0".into();
+
+        let (status, _) = verify(&app, body).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
@@ -0,0 +1,405 @@
+//! Detached Proof Module
+//!
+//! This module registers and verifies detached proofs: signatures over the
+//! digest and metadata of a document that is never uploaded to the relay.
+//! Registered proofs can be looked up by document digest, so anyone holding
+//! the document can find out who approved it. On relays with tenants, a
+//! registration is only visible to the tenant that made it.
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use chrono::Utc;
+use proof_messenger_protocol::detached::{DetachedProof, DOCUMENT_DIGEST_LENGTH};
+use std::sync::Arc;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, StoredDetachedProof},
+    request_id::RequestId,
+    tenancy::TenantScope,
+    AppError,
+};
+
+/// Longest accepted filename or content type, in bytes
+const MAX_METADATA_FIELD_LENGTH: usize = 255;
+
+/// Create router for detached proof endpoints
+pub fn detached_proof_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/detached-proofs", post(register_handler))
+        .route("/detached-proofs/verify", post(verify_handler))
+        .route("/detached-proofs/:digest", get(lookup_handler))
+}
+
+/// Create router for authenticated detached proof endpoints
+pub fn authenticated_detached_proof_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/detached-proofs", post(authenticated_register_handler))
+        .route("/detached-proofs/verify", post(authenticated_verify_handler))
+        .route("/detached-proofs/:digest", get(authenticated_lookup_handler))
+}
+
+/// Verify a detached proof and register it for the tenant
+pub async fn register_detached_proof(
+    db: &Database,
+    tenant: &TenantScope,
+    proof: &DetachedProof,
+    registered_by: Option<&str>,
+) -> Result<StoredDetachedProof, AppError> {
+    for (field, value) in [("filename", &proof.metadata.filename), ("content_type", &proof.metadata.content_type)] {
+        if value.len() > MAX_METADATA_FIELD_LENGTH {
+            return Err(AppError::InvalidContext(format!(
+                "{} must be at most {} bytes",
+                field, MAX_METADATA_FIELD_LENGTH
+            )));
+        }
+    }
+    proof.verify().map_err(|_| AppError::VerificationFailed)?;
+
+    let signature = hex::encode(&proof.signature);
+    if db.is_proof_revoked(&signature).await? {
+        return Err(AppError::ProofRevoked);
+    }
+
+    let size = i64::try_from(proof.metadata.size)
+        .map_err(|_| AppError::InvalidContext("Document size is too large".to_string()))?;
+    Ok(db
+        .store_detached_proof(&StoredDetachedProof {
+            id: Uuid::new_v4().to_string(),
+            tenant: tenant.tenant().map(str::to_string),
+            algorithm: proof.algorithm.to_string(),
+            digest: hex::encode(proof.digest),
+            filename: proof.metadata.filename.clone(),
+            size,
+            content_type: proof.metadata.content_type.clone(),
+            public_key: hex::encode(proof.public_key),
+            signature,
+            registered_by: registered_by.map(str::to_string),
+            registered_at: Utc::now(),
+        })
+        .await?)
+}
+
+/// Check a detached proof's signature, revocation and registration with the tenant
+pub async fn verify_detached_proof(db: &Database, tenant: &TenantScope, proof: &DetachedProof) -> Result<serde_json::Value, AppError> {
+    let signature = hex::encode(&proof.signature);
+    let verification = proof.verify();
+    let revoked = db.is_proof_revoked(&signature).await?;
+    let registration = db.get_detached_proof_by_signature(tenant.tenant(), &signature).await?;
+
+    Ok(serde_json::json!({
+        "valid": verification.is_ok() && !revoked,
+        "signature_valid": verification.is_ok(),
+        "revoked": revoked,
+        "registered": registration.is_some(),
+        "registration": registration,
+        "error": verification.err().map(|e| e.to_string()),
+    }))
+}
+
+/// Normalize a document digest from a path
+fn parse_digest(digest: &str) -> Result<String, AppError> {
+    let bytes = hex::decode(digest)
+        .map_err(|e| AppError::InvalidQuery(format!("Invalid digest hex: {}", e)))?;
+    if bytes.len() != DOCUMENT_DIGEST_LENGTH {
+        return Err(AppError::InvalidQuery(format!(
+            "Digest must be {} bytes (got {})",
+            DOCUMENT_DIGEST_LENGTH,
+            bytes.len()
+        )));
+    }
+    Ok(hex::encode(bytes))
+}
+
+/// Handler to register a detached proof
+#[utoipa::path(
+    post,
+    path = "/detached-proofs",
+    operation_id = "registerDetachedProof",
+    tag = "detached-proofs",
+    request_body = Object,
+    responses(
+        (status = 201, description = "Proof verified and registered", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn register_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Json(proof): Json<DetachedProof>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Registering detached proof for {}", proof.metadata.filename);
+
+    let registration = register_detached_proof(&db, &tenant, &proof, None).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "proof": registration
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Handler to verify a detached proof
+#[utoipa::path(
+    post,
+    path = "/detached-proofs/verify",
+    operation_id = "verifyDetachedProof",
+    tag = "detached-proofs",
+    request_body = Object,
+    responses(
+        (status = 200, description = "Whether the proof verifies and matches a registration", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn verify_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Json(proof): Json<DetachedProof>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Verifying detached proof for {}", proof.metadata.filename);
+
+    let mut response = verify_detached_proof(&db, &tenant, &proof).await?;
+    response["status"] = "success".into();
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to list the detached proofs registered for a document
+#[utoipa::path(
+    get,
+    path = "/detached-proofs/{digest}",
+    operation_id = "getDetachedProofs",
+    tag = "detached-proofs",
+    params(("digest" = String, Path, description = "Document digest (hex encoded)")),
+    responses(
+        (status = 200, description = "Proofs registered for the document", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn lookup_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(digest): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Looking up detached proofs for digest: {}", digest);
+
+    let digest = parse_digest(&digest)?;
+    let proofs = db.get_detached_proofs_by_digest(tenant.tenant(), &digest).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "digest": digest,
+        "count": proofs.len(),
+        "proofs": proofs
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to register a detached proof
+#[instrument(skip_all)]
+async fn authenticated_register_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    request_id: RequestId,
+    Json(proof): Json<DetachedProof>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} registering detached proof for {}", auth.user_id, proof.metadata.filename);
+
+    let registration = register_detached_proof(&db, &tenant, &proof, Some(&auth.user_id)).await?;
+
+    // Log the registration
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("proof_id".to_string(), registration.id.clone());
+    metadata.insert("digest".to_string(), registration.digest.clone());
+    metadata.insert("public_key".to_string(), registration.public_key.clone());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Detached proof registered".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log detached proof registration: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "proof": registration,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Authenticated handler to verify a detached proof
+#[instrument(skip_all)]
+async fn authenticated_verify_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Json(proof): Json<DetachedProof>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} verifying detached proof for {}", auth.user_id, proof.metadata.filename);
+
+    let mut response = verify_detached_proof(&db, &tenant, &proof).await?;
+    response["status"] = "success".into();
+    response["authenticated_user"] = auth.user_id.into();
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Authenticated handler to list the detached proofs registered for a document
+#[instrument(skip_all)]
+async fn authenticated_lookup_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Path(digest): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} looking up detached proofs for digest: {}", auth.user_id, digest);
+
+    let digest = parse_digest(&digest)?;
+    let proofs = db.get_detached_proofs_by_digest(tenant.tenant(), &digest).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "digest": digest,
+        "count": proofs.len(),
+        "proofs": proofs,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use hyper::Method;
+    use proof_messenger_protocol::detached::{digest_document, make_detached_proof, DigestAlgorithm, DocumentMetadata};
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use tower::ServiceExt;
+
+    async fn setup_test_app() -> (Router, Arc<Database>) {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+
+        let app = Router::new().merge(detached_proof_routes()).with_state(db.clone());
+        (app, db)
+    }
+
+    fn document_proof(document: &[u8]) -> DetachedProof {
+        let (digest, size) = digest_document(DigestAlgorithm::Sha256, document).unwrap();
+        let metadata = DocumentMetadata {
+            filename: "approval.pdf".to_string(),
+            size,
+            content_type: "application/pdf".to_string(),
+        };
+        make_detached_proof(&generate_secure_keypair_with_seed(5), DigestAlgorithm::Sha256, digest, metadata).unwrap()
+    }
+
+    async fn send(app: &Router, method: Method, uri: &str, body: Option<&DetachedProof>) -> (StatusCode, serde_json::Value) {
+        let body = match body {
+            Some(proof) => Body::from(serde_json::to_string(proof).unwrap()),
+            None => Body::empty(),
+        };
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("Content-Type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_register_verify_and_look_up_proof() {
+        // ARRANGE: A detached proof over a document the relay never sees
+        let (app, _) = setup_test_app().await;
+        let proof = document_proof(b"%PDF-1.7 purchase approval");
+
+        // ACT: Register it, verify it and look it up by digest
+        let (register_status, registered) = send(&app, Method::POST, "/detached-proofs", Some(&proof)).await;
+        let (_, verified) = send(&app, Method::POST, "/detached-proofs/verify", Some(&proof)).await;
+        let (lookup_status, found) =
+            send(&app, Method::GET, &format!("/detached-proofs/{}", hex::encode(proof.digest)), None).await;
+
+        // ASSERT: The registration is reported by both verify and lookup
+        assert_eq!(register_status, StatusCode::CREATED);
+        assert_eq!(verified["valid"], true);
+        assert_eq!(verified["registered"], true);
+        assert_eq!(verified["registration"]["id"], registered["proof"]["id"]);
+        assert_eq!(lookup_status, StatusCode::OK);
+        assert_eq!(found["count"], 1);
+        assert_eq!(found["proofs"][0]["filename"], "approval.pdf");
+    }
+
+    #[tokio::test]
+    async fn test_tampered_proof_is_not_registered() {
+        let (app, db) = setup_test_app().await;
+        let mut proof = document_proof(b"%PDF-1.7 purchase approval");
+        proof.metadata.size += 1;
+
+        let (status, _) = send(&app, Method::POST, "/detached-proofs", Some(&proof)).await;
+        let (_, verified) = send(&app, Method::POST, "/detached-proofs/verify", Some(&proof)).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(verified["valid"], false);
+        assert!(db.get_detached_proofs_by_digest(None, &hex::encode(proof.digest)).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_registration_is_idempotent() {
+        let (app, _) = setup_test_app().await;
+        let proof = document_proof(b"%PDF-1.7 purchase approval");
+
+        let (_, first) = send(&app, Method::POST, "/detached-proofs", Some(&proof)).await;
+        let (_, second) = send(&app, Method::POST, "/detached-proofs", Some(&proof)).await;
+
+        assert_eq!(first["proof"]["id"], second["proof"]["id"]);
+    }
+
+    #[tokio::test]
+    async fn test_revoked_proof_is_rejected() {
+        let (app, db) = setup_test_app().await;
+        let proof = document_proof(b"%PDF-1.7 purchase approval");
+        db.revoke_proof(&hex::encode(&proof.signature), Some("withdrawn"), None, None).await.unwrap();
+
+        let (status, _) = send(&app, Method::POST, "/detached-proofs", Some(&proof)).await;
+        let (_, verified) = send(&app, Method::POST, "/detached-proofs/verify", Some(&proof)).await;
+
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(verified["signature_valid"], true);
+        assert_eq!(verified["revoked"], true);
+        assert_eq!(verified["valid"], false);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_digest_is_rejected() {
+        let (app, _) = setup_test_app().await;
+
+        let (status, _) = send(&app, Method::GET, "/detached-proofs/abc", None).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
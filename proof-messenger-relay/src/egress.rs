@@ -0,0 +1,238 @@
+//! Egress Module
+//!
+//! Every outbound HTTP request the relay makes (JWKS fetches, token
+//! introspection, webhooks, federation, SIEM forwarding, timestamping and
+//! audit shipping) is sent by an [`HttpClient`] built by [`client`], which
+//! applies the `[egress]` settings (see [`crate::config::EgressConfig`]):
+//!
+//! - Requests go through `egress.proxy`, which defaults to `HTTPS_PROXY`,
+//!   except to the hosts in `no_proxy` (`NO_PROXY`). Without a proxy,
+//!   requests connect directly.
+//! - With `allowed_hosts` set, requests and redirects to any other host are
+//!   refused before a connection is opened. `*.example.com` allows every
+//!   subdomain of `example.com`.
+//!
+//! A refused request fails as an unreachable host would, is logged with the
+//! host it targeted and is counted in the `egress_blocked` metric.
+
+use once_cell::sync::OnceCell;
+use reqwest::{redirect, ClientBuilder, Method, RequestBuilder, Url};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::{config::EgressConfig, metrics};
+
+/// Redirects followed before a request fails, as reqwest does by default
+const MAX_REDIRECTS: usize = 10;
+
+/// Policy installed for the process
+static INSTALLED: OnceCell<Arc<EgressPolicy>> = OnceCell::new();
+
+/// Errors raised by outbound requests
+#[derive(Debug, Error)]
+pub enum EgressError {
+    #[error("Outbound requests to {0} are not allowed")]
+    Blocked(String),
+
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("Invalid egress configuration: {0}")]
+    Config(String),
+}
+
+/// Where outbound requests may go, and how
+#[derive(Debug, Default)]
+pub struct EgressPolicy {
+    proxy: Option<String>,
+    no_proxy: Vec<String>,
+    allowed_hosts: Vec<String>,
+}
+
+impl EgressPolicy {
+    /// Policy for the given settings
+    pub fn new(config: &EgressConfig) -> Self {
+        Self {
+            proxy: config.proxy.clone(),
+            no_proxy: config.no_proxy.clone(),
+            allowed_hosts: config.allowed_hosts.clone(),
+        }
+    }
+
+    /// Refuse `url` unless its host is allowed
+    pub fn check(&self, url: &Url) -> Result<(), EgressError> {
+        let host = url.host_str().ok_or_else(|| EgressError::InvalidUrl(format!("{} has no host", url)))?;
+        if host_allowed(&self.allowed_hosts, host) {
+            return Ok(());
+        }
+        metrics::EGRESS_BLOCKED_TOTAL.inc();
+        warn!("Blocked outbound request to {}: not in egress.allowed_hosts", host);
+        Err(EgressError::Blocked(host.to_string()))
+    }
+
+    /// An HTTP client from `builder` that routes and restricts requests by this policy
+    pub fn client(self: &Arc<Self>, builder: ClientBuilder) -> Result<HttpClient, EgressError> {
+        let policy = self.clone();
+        // The proxy comes from the policy alone, never from reqwest's own reading of the environment
+        let mut builder = builder.no_proxy().redirect(redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error(EgressError::InvalidUrl("too many redirects".to_string()));
+            }
+            match policy.check(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e),
+            }
+        }));
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| EgressError::Config(format!("proxy: {}", e)))?
+                .no_proxy(reqwest::NoProxy::from_string(&self.no_proxy.join(",")));
+            builder = builder.proxy(proxy);
+        }
+        let inner = builder.build().map_err(|e| EgressError::Config(e.to_string()))?;
+        Ok(HttpClient { inner, policy: self.clone() })
+    }
+}
+
+/// An HTTP client whose requests are checked against the egress policy
+#[derive(Clone)]
+pub struct HttpClient {
+    inner: reqwest::Client,
+    policy: Arc<EgressPolicy>,
+}
+
+impl HttpClient {
+    /// Start a request to `url`, unless the policy refuses its host
+    pub fn request(&self, method: Method, url: &str) -> Result<RequestBuilder, EgressError> {
+        let url = Url::parse(url).map_err(|e| EgressError::InvalidUrl(format!("{}: {}", url, e)))?;
+        self.policy.check(&url)?;
+        Ok(self.inner.request(method, url))
+    }
+
+    /// Start a GET request to `url`
+    pub fn get(&self, url: &str) -> Result<RequestBuilder, EgressError> {
+        self.request(Method::GET, url)
+    }
+
+    /// Start a POST request to `url`
+    pub fn post(&self, url: &str) -> Result<RequestBuilder, EgressError> {
+        self.request(Method::POST, url)
+    }
+}
+
+impl Default for HttpClient {
+    /// A client with reqwest's default settings under the installed policy
+    ///
+    /// Falls back to a client without the proxy only if it cannot be built;
+    /// hosts are still checked against the allow-list.
+    fn default() -> Self {
+        let policy = current();
+        policy.client(reqwest::Client::builder()).unwrap_or_else(|e| {
+            warn!("Failed to build the outbound HTTP client: {}", e);
+            HttpClient { inner: reqwest::Client::default(), policy }
+        })
+    }
+}
+
+/// Whether `host` matches an entry of `allowed`; any host does when it is empty
+pub fn host_allowed(allowed: &[String], host: &str) -> bool {
+    if allowed.is_empty() {
+        return true;
+    }
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    allowed.iter().map(|pattern| pattern.to_ascii_lowercase()).any(|pattern| match pattern.strip_prefix("*.") {
+        Some(domain) => host.strip_suffix(domain).is_some_and(|subdomain| subdomain.len() > 1 && subdomain.ends_with('.')),
+        None => host == pattern,
+    })
+}
+
+/// Send outbound requests under `policy` for the rest of the process
+///
+/// Only the first installed policy takes effect.
+pub fn install(policy: EgressPolicy) {
+    let _ = INSTALLED.set(Arc::new(policy));
+}
+
+/// The installed policy, or one allowing any host without a proxy if none is installed
+pub fn current() -> Arc<EgressPolicy> {
+    INSTALLED.get_or_init(Arc::default).clone()
+}
+
+/// An HTTP client from `builder` under the installed policy
+pub fn client(builder: ClientBuilder) -> Result<HttpClient, EgressError> {
+    current().client(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn policy(allowed_hosts: &[&str]) -> Arc<EgressPolicy> {
+        Arc::new(EgressPolicy::new(&EgressConfig {
+            allowed_hosts: allowed_hosts.iter().map(|host| host.to_string()).collect(),
+            ..EgressConfig::default()
+        }))
+    }
+
+    #[test]
+    fn test_hosts_are_matched_against_the_allow_list() {
+        let allowed = ["api.example.com".to_string(), "*.Corp.example".to_string()];
+
+        assert!(host_allowed(&[], "anywhere.example.org"));
+        assert!(host_allowed(&allowed, "API.example.com"));
+        assert!(host_allowed(&allowed, "jwks.corp.example"));
+        assert!(host_allowed(&allowed, "a.b.corp.example."));
+        assert!(!host_allowed(&allowed, "corp.example"));
+        assert!(!host_allowed(&allowed, "evilcorp.example"));
+        assert!(!host_allowed(&allowed, "api.example.com.evil.org"));
+    }
+
+    #[tokio::test]
+    async fn test_requests_and_redirects_outside_the_allow_list_are_refused() {
+        // ARRANGE: A server on 127.0.0.1 that redirects to localhost
+        let server = MockServer::start().await;
+        let port = server.address().port();
+        Mock::given(method("GET"))
+            .and(path("/redirect"))
+            .respond_with(ResponseTemplate::new(302).insert_header("location", format!("http://localhost:{}/jwks", port).as_str()))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET")).and(path("/jwks")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+        let client = policy(&["127.0.0.1"]).client(reqwest::Client::builder()).unwrap();
+
+        // ACT: Call the allowed host directly, through the redirect, and a host off the list
+        let direct = client.get(&format!("{}/jwks", server.uri())).unwrap().send().await;
+        let redirected = client.get(&format!("{}/redirect", server.uri())).unwrap().send().await;
+        let refused = client.post("https://collector.example.org/events");
+
+        // ASSERT: Only the request that stays on the allowed host succeeds
+        assert_eq!(direct.unwrap().status(), 200);
+        assert!(redirected.unwrap_err().is_redirect());
+        assert!(matches!(refused, Err(EgressError::Blocked(host)) if host == "collector.example.org"));
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_the_configured_proxy() {
+        // ARRANGE: A "proxy" that answers every request itself
+        let proxy = MockServer::start().await;
+        Mock::given(method("GET")).respond_with(ResponseTemplate::new(203)).mount(&proxy).await;
+        let policy = Arc::new(EgressPolicy::new(&EgressConfig {
+            proxy: Some(proxy.uri()),
+            no_proxy: vec!["direct.invalid".to_string()],
+            ..EgressConfig::default()
+        }));
+        let client = policy.client(reqwest::Client::builder()).unwrap();
+
+        // ACT: Request a host that does not resolve, through the proxy and around it
+        let proxied = client.get("http://relay-peer.invalid/federation/digest").unwrap().send().await;
+        let direct = client.get("http://direct.invalid/").unwrap().send().await;
+
+        // ASSERT: The proxy received the first request; the second tried to connect directly
+        assert_eq!(proxied.unwrap().status(), 203);
+        assert!(direct.is_err());
+        assert_eq!(proxy.received_requests().await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,490 @@
+//! Verified-Message Event Stream Module
+//!
+//! With an `[event_stream]` sink configured (see
+//! [`crate::config::EventStreamConfig`]) the relay publishes a compact
+//! [`VerifiedMessageEvent`] to a streaming platform every time it stores a
+//! verified message, so downstream systems can follow the relay without
+//! polling it. Publishers implement [`EventPublisher`]; the relay ships with:
+//!
+//! - `kafka` (`kafka` feature): produced to the configured topic, keyed and
+//!   partitioned by group so a group's events stay in order
+//! - `nats` (`nats` feature): published to the configured JetStream subject
+//!
+//! Delivery is at-least-once. Each event is queued in the database before it
+//! is published and is only marked published once the broker acknowledges
+//! it; failed attempts are retried with exponential backoff, also across
+//! relay restarts. An event may therefore be published more than once, and
+//! consumers should deduplicate on its `id` (sent as the Kafka `event-id`
+//! header and as the NATS `Nats-Msg-Id` header, which JetStream uses to drop
+//! duplicates within its deduplication window).
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use proof_messenger_protocol::context_digest::context_digest;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    config::{EventSinkKind, EventStreamConfig},
+    database::{Database, StoredMessage, StreamEvent},
+    metrics,
+    AppError,
+};
+
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "nats")]
+pub mod nats;
+
+/// Maximum number of events claimed per worker pass
+const PUBLISH_BATCH_SIZE: i64 = 100;
+
+/// Event stream error types
+#[derive(Error, Debug)]
+pub enum EventStreamError {
+    #[error("Invalid event stream configuration: {0}")]
+    Config(String),
+
+    #[error("Failed to connect to the streaming platform: {0}")]
+    Connect(String),
+
+    #[error("Failed to publish event: {0}")]
+    Publish(String),
+
+    #[error("No acknowledgement from the streaming platform within {0:?}")]
+    Timeout(Duration),
+}
+
+/// Compact event published when a message passes verification and is stored
+///
+/// Carries no message content: consumers that need the body fetch it from
+/// the relay by `message_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedMessageEvent {
+    /// Unique event ID, identical across publish attempts
+    pub id: String,
+    /// Relay-assigned message ID
+    pub message_id: String,
+    /// Public key of the sender (hex encoded)
+    pub sender: String,
+    /// Group the message was posted to
+    pub group_id: String,
+    /// BLAKE3 digest of the signed context (hex encoded)
+    pub context_hash: String,
+    /// When the relay stored the message
+    pub timestamp: DateTime<Utc>,
+}
+
+impl VerifiedMessageEvent {
+    /// A new event about a stored message
+    pub fn new(message: &StoredMessage) -> Self {
+        // Stored contexts passed verification, so they are always valid hex
+        let context = hex::decode(&message.context).unwrap_or_else(|_| message.context.clone().into_bytes());
+        Self {
+            id: Uuid::new_v4().to_string(),
+            message_id: message.id.clone(),
+            sender: message.sender.clone(),
+            group_id: message.group_id.clone(),
+            context_hash: hex::encode(context_digest(&context)),
+            timestamp: message.created_at,
+        }
+    }
+}
+
+/// A streaming platform verified-message events are published to
+#[async_trait]
+pub trait EventPublisher: Send + Sync {
+    /// Publish one event, returning once the platform has acknowledged it
+    ///
+    /// `payload` is the event serialized as JSON. Returning an error
+    /// schedules a retry, so implementations should not retry internally.
+    async fn publish(&self, event: &VerifiedMessageEvent, payload: &[u8]) -> Result<(), EventStreamError>;
+}
+
+/// Queues and publishes verified-message events with retries
+pub struct EventStream {
+    publisher: Arc<dyn EventPublisher>,
+    config: EventStreamConfig,
+}
+
+impl EventStream {
+    /// Publish through `publisher` with the retry settings in `config`
+    pub fn new(publisher: Arc<dyn EventPublisher>, config: EventStreamConfig) -> Result<Self, EventStreamError> {
+        if config.max_attempts == 0 {
+            return Err(EventStreamError::Config("max attempts must be at least 1".to_string()));
+        }
+        Ok(Self { publisher, config })
+    }
+
+    /// Build the publisher for the configured sink, or `None` when no sink is set
+    ///
+    /// Publishers connect on their first publish, so an unreachable broker
+    /// at startup only delays events rather than stopping the relay.
+    pub fn from_config(config: &EventStreamConfig) -> Result<Option<Self>, EventStreamError> {
+        let Some(sink) = config.sink else {
+            return Ok(None);
+        };
+        if config.servers.is_empty() {
+            return Err(EventStreamError::Config("event_stream.servers must be set".to_string()));
+        }
+        Self::new(publisher(sink, config)?, config.clone()).map(Some)
+    }
+
+    /// Delay before retrying after `attempts` failed attempts
+    pub fn retry_delay(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        Duration::from_millis(self.config.retry_base_ms)
+            .saturating_mul(factor)
+            .min(Duration::from_millis(self.config.retry_max_ms))
+    }
+
+    /// How long a claimed event is reserved for the claiming worker
+    fn lease(&self) -> chrono::Duration {
+        chrono::Duration::from_std(Duration::from_millis(self.config.publish_timeout_ms) * 2)
+            .unwrap_or_else(|_| chrono::Duration::minutes(1))
+    }
+
+    /// Queue an event about a stored message and publish it
+    ///
+    /// The event is leased to this call's background task; if the relay
+    /// stops before the broker acknowledges it, the worker picks it up once
+    /// the lease expires.
+    pub async fn notify(self: &Arc<Self>, db: &Arc<Database>, message: &StoredMessage) -> Result<(), AppError> {
+        let event = VerifiedMessageEvent::new(message);
+        let payload = serde_json::to_string(&event)
+            .map_err(|e| AppError::ProcessingError(format!("Failed to serialize stream event: {}", e)))?;
+
+        let queued = db
+            .enqueue_stream_event(&event.id, &event.message_id, &payload, Utc::now() + self.lease())
+            .await?;
+
+        let stream = Arc::clone(self);
+        let db = Arc::clone(db);
+        tokio::spawn(async move {
+            stream.attempt(&db, &queued).await;
+        });
+        Ok(())
+    }
+
+    /// Claim and attempt every event that is due, returning how many were attempted
+    pub async fn publish_due(&self, db: &Database) -> usize {
+        let lease_until = Utc::now() + self.lease();
+        match db.claim_due_stream_events(lease_until, PUBLISH_BATCH_SIZE).await {
+            Ok(events) => {
+                let count = events.len();
+                for event in &events {
+                    self.attempt(db, event).await;
+                }
+                count
+            }
+            Err(e) => {
+                warn!("Failed to claim due stream events: {}", e);
+                0
+            }
+        }
+    }
+
+    /// Make one publish attempt and record its outcome
+    async fn attempt(&self, db: &Database, queued: &StreamEvent) {
+        let timeout = Duration::from_millis(self.config.publish_timeout_ms);
+        let result = match serde_json::from_str::<VerifiedMessageEvent>(&queued.payload) {
+            Ok(event) => tokio::time::timeout(timeout, self.publisher.publish(&event, queued.payload.as_bytes()))
+                .await
+                .unwrap_or(Err(EventStreamError::Timeout(timeout))),
+            Err(e) => Err(EventStreamError::Publish(format!("queued event is malformed: {}", e))),
+        };
+
+        let error = match result {
+            Ok(()) => {
+                metrics::EVENT_STREAM_PUBLISHED_TOTAL.inc();
+                if let Err(e) = db.record_stream_event_published(&queued.id).await {
+                    warn!("Failed to record published stream event {}: {}", queued.id, e);
+                }
+                return;
+            }
+            Err(e) => e.to_string(),
+        };
+
+        let attempts = queued.attempts as u32 + 1;
+        let retry_at = (attempts < self.config.max_attempts).then(|| {
+            Utc::now() + chrono::Duration::from_std(self.retry_delay(attempts)).unwrap_or_else(|_| chrono::Duration::hours(1))
+        });
+        if retry_at.is_some() {
+            metrics::EVENT_STREAM_RETRIES_TOTAL.inc();
+        } else {
+            metrics::EVENT_STREAM_FAILED_TOTAL.inc();
+        }
+        warn!(
+            "Publishing stream event {} for message {} failed (attempt {} of {}): {}",
+            queued.id, queued.message_id, attempts, self.config.max_attempts, error
+        );
+
+        if let Err(e) = db.record_stream_event_failure(&queued.id, &error, retry_at).await {
+            warn!("Failed to record stream event {}: {}", queued.id, e);
+        }
+    }
+
+    /// Retry due events periodically in the background
+    pub fn spawn_worker(self: Arc<Self>, db: Arc<Database>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let poll_interval = Duration::from_millis(self.config.poll_interval_ms);
+            let mut interval = tokio::time::interval(poll_interval);
+            loop {
+                interval.tick().await;
+                crate::readiness::BACKGROUND_JOBS.heartbeat("event_stream", poll_interval);
+                self.publish_due(&db).await;
+            }
+        })
+    }
+}
+
+/// Publisher for a sink, if the relay was built with its feature
+#[cfg_attr(not(any(feature = "kafka", feature = "nats")), allow(unused_variables))]
+fn publisher(sink: EventSinkKind, config: &EventStreamConfig) -> Result<Arc<dyn EventPublisher>, EventStreamError> {
+    match sink {
+        #[cfg(feature = "kafka")]
+        EventSinkKind::Kafka => Ok(Arc::new(kafka::KafkaPublisher::new(config.servers.clone(), config.topic.clone()))),
+        #[cfg(feature = "nats")]
+        EventSinkKind::Nats => Ok(Arc::new(nats::NatsPublisher::new(config.servers.clone(), config.topic.clone()))),
+        #[allow(unreachable_patterns)] // every sink is matched above when all features are enabled
+        other => Err(EventStreamError::Config(format!(
+            "the {} sink requires a relay built with the `{}` feature",
+            other.feature(),
+            other.feature()
+        ))),
+    }
+}
+
+/// Publish an event about a stored message if the event stream is enabled
+pub async fn publish_if_enabled(
+    stream: Option<&Arc<EventStream>>,
+    db: &Arc<Database>,
+    message: &StoredMessage,
+) -> Result<(), AppError> {
+    match stream {
+        Some(stream) => stream.notify(db, message).await,
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Records published events, failing the first `failures` attempts
+    #[derive(Default)]
+    struct RecordingPublisher {
+        failures: AtomicUsize,
+        published: Mutex<Vec<VerifiedMessageEvent>>,
+    }
+
+    impl RecordingPublisher {
+        fn failing(failures: usize) -> Arc<Self> {
+            Arc::new(Self { failures: AtomicUsize::new(failures), ..Default::default() })
+        }
+
+        fn published(&self) -> Vec<VerifiedMessageEvent> {
+            self.published.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl EventPublisher for RecordingPublisher {
+        async fn publish(&self, event: &VerifiedMessageEvent, payload: &[u8]) -> Result<(), EventStreamError> {
+            if self.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+                return Err(EventStreamError::Publish("broker unavailable".to_string()));
+            }
+            assert_eq!(serde_json::from_slice::<VerifiedMessageEvent>(payload).unwrap(), *event);
+            self.published.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    fn stream(publisher: Arc<RecordingPublisher>, max_attempts: u32) -> Arc<EventStream> {
+        let config = EventStreamConfig { max_attempts, retry_base_ms: 0, ..Default::default() };
+        Arc::new(EventStream::new(publisher, config).unwrap())
+    }
+
+    async fn stored_message(db: &Database) -> StoredMessage {
+        let mut message = StoredMessage::from(crate::Message {
+            sender: "aa".repeat(32),
+            context: hex::encode(b"transfer:42"),
+            body: "hello".to_string(),
+            proof: "cc".repeat(64),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        });
+        message.group_id = "group1".to_string();
+        let id = db.store_message(message).await.unwrap();
+        db.get_message_by_id(&id).await.unwrap()
+    }
+
+    /// Queue an event that is due immediately, as the worker would find it
+    async fn queue_due_event(db: &Database, message: &StoredMessage) -> VerifiedMessageEvent {
+        let event = VerifiedMessageEvent::new(message);
+        let payload = serde_json::to_string(&event).unwrap();
+        db.enqueue_stream_event(&event.id, &event.message_id, &payload, Utc::now()).await.unwrap();
+        event
+    }
+
+    #[tokio::test]
+    async fn test_stored_message_is_published_as_compact_event() {
+        // ARRANGE: An event stream over a working publisher
+        let db = setup_db().await;
+        let publisher = RecordingPublisher::failing(0);
+        let stream = stream(publisher.clone(), 3);
+        let message = stored_message(&db).await;
+
+        // ACT: Notify the stream of the stored message
+        publish_if_enabled(Some(&stream), &db, &message).await.unwrap();
+        for _ in 0..100 {
+            if !publisher.published().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // ASSERT: The event identifies the message without its content and is marked published
+        let published = publisher.published();
+        assert_eq!(published.len(), 1);
+        let event = &published[0];
+        assert_eq!(event.message_id, message.id);
+        assert_eq!(event.sender, message.sender);
+        assert_eq!(event.group_id, "group1");
+        assert_eq!(event.context_hash, hex::encode(context_digest(b"transfer:42")));
+        assert_eq!(event.timestamp, message.created_at);
+        let mut queued = db.get_stream_event(&event.id).await.unwrap().unwrap();
+        for _ in 0..100 {
+            if queued.status == "published" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            queued = db.get_stream_event(&event.id).await.unwrap().unwrap();
+        }
+        assert_eq!(queued.status, "published");
+        assert_eq!(queued.attempts, 1);
+        assert!(queued.published_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failed_publish_is_retried_with_the_same_event() {
+        // ARRANGE: A broker that rejects the first attempt
+        let db = setup_db().await;
+        let publisher = RecordingPublisher::failing(1);
+        let stream = stream(publisher.clone(), 3);
+        let message = stored_message(&db).await;
+        let event = queue_due_event(&db, &message).await;
+
+        // ACT: Run the worker until nothing is due
+        let first = stream.publish_due(&db).await;
+        let second = stream.publish_due(&db).await;
+        let third = stream.publish_due(&db).await;
+
+        // ASSERT: The retry published the original event exactly once
+        assert_eq!((first, second, third), (1, 1, 0));
+        assert_eq!(publisher.published(), vec![event.clone()]);
+        let queued = db.get_stream_event(&event.id).await.unwrap().unwrap();
+        assert_eq!(queued.status, "published");
+        assert_eq!(queued.attempts, 2);
+        assert_eq!(queued.last_error, None);
+    }
+
+    #[tokio::test]
+    async fn test_event_is_failed_after_max_attempts() {
+        let db = setup_db().await;
+        let publisher = RecordingPublisher::failing(usize::MAX);
+        let stream = stream(publisher.clone(), 2);
+        let message = stored_message(&db).await;
+        let event = queue_due_event(&db, &message).await;
+
+        let attempted: Vec<_> = [
+            stream.publish_due(&db).await,
+            stream.publish_due(&db).await,
+            stream.publish_due(&db).await,
+        ]
+        .into();
+
+        assert_eq!(attempted, vec![1, 1, 0]);
+        assert!(publisher.published().is_empty());
+        let queued = db.get_stream_event(&event.id).await.unwrap().unwrap();
+        assert_eq!(queued.status, "failed");
+        assert_eq!(queued.attempts, 2);
+        assert_eq!(queued.last_error.as_deref(), Some("Failed to publish event: broker unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_relayed_message_emits_event() {
+        // ARRANGE: A relay with an event stream and a signed message
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+        use proof_messenger_protocol::proof::make_secure_proof;
+        use tower::ServiceExt;
+
+        let db = setup_db().await;
+        let publisher = RecordingPublisher::failing(0);
+        let app = crate::create_app(db.clone()).layer(axum::Extension(stream(publisher.clone(), 3)));
+        let keypair = generate_secure_keypair_with_seed(43);
+        let proof = make_secure_proof(&keypair, b"stream context").unwrap();
+        let body = serde_json::json!({
+            "sender": hex::encode(keypair.public_key_bytes()),
+            "context": hex::encode(b"stream context"),
+            "body": "hello",
+            "proof": hex::encode(proof.to_bytes()),
+        });
+
+        // ACT: Relay the message
+        let request = Request::post("/relay")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        for _ in 0..100 {
+            if !publisher.published().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // ASSERT: An event was published for the stored message
+        assert_eq!(response.status(), StatusCode::OK);
+        let published = publisher.published();
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].sender, hex::encode(keypair.public_key_bytes()));
+        assert_eq!(published[0].context_hash, hex::encode(context_digest(b"stream context")));
+        assert!(db.get_message_by_id(&published[0].message_id).await.is_ok());
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        let config = EventStreamConfig { retry_base_ms: 1000, retry_max_ms: 5000, ..Default::default() };
+        let stream = EventStream::new(RecordingPublisher::failing(0), config).unwrap();
+
+        assert_eq!(stream.retry_delay(1), Duration::from_secs(1));
+        assert_eq!(stream.retry_delay(2), Duration::from_secs(2));
+        assert_eq!(stream.retry_delay(3), Duration::from_secs(4));
+        assert_eq!(stream.retry_delay(4), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_stream_is_disabled_without_a_sink() {
+        assert!(EventStream::from_config(&EventStreamConfig::default()).unwrap().is_none());
+        let no_servers = EventStreamConfig { sink: Some(EventSinkKind::Nats), ..Default::default() };
+        assert!(matches!(EventStream::from_config(&no_servers), Err(EventStreamError::Config(_))));
+    }
+}
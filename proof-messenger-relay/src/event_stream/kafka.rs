@@ -0,0 +1,124 @@
+//! Kafka event publisher
+//!
+//! Events are produced to a single topic with the group ID as record key,
+//! and the partition is chosen from a stable hash of that key so every event
+//! for a group lands on the same partition, in order. The topic must already
+//! exist; the relay does not create it.
+
+use async_trait::async_trait;
+use rskafka::client::partition::{Compression, PartitionClient, UnknownTopicHandling};
+use rskafka::client::{Client, ClientBuilder};
+use rskafka::record::Record;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::{Mutex, OnceCell};
+
+use super::{EventPublisher, EventStreamError, VerifiedMessageEvent};
+
+/// Record header carrying the event ID, for consumer-side deduplication
+pub const EVENT_ID_HEADER: &str = "event-id";
+
+/// Publishes events to a Kafka topic
+pub struct KafkaPublisher {
+    brokers: Vec<String>,
+    topic: String,
+    client: OnceCell<Client>,
+    partitions: Mutex<HashMap<i32, Arc<PartitionClient>>>,
+}
+
+impl KafkaPublisher {
+    /// A publisher for `topic` on the cluster reachable through `brokers`
+    ///
+    /// No connection is made until the first event is published.
+    pub fn new(brokers: Vec<String>, topic: String) -> Self {
+        Self {
+            brokers,
+            topic,
+            client: OnceCell::new(),
+            partitions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Connect to the cluster, reusing the connection once established
+    async fn client(&self) -> Result<&Client, EventStreamError> {
+        self.client
+            .get_or_try_init(|| async {
+                ClientBuilder::new(self.brokers.clone())
+                    .build()
+                    .await
+                    .map_err(|e| EventStreamError::Connect(e.to_string()))
+            })
+            .await
+    }
+
+    /// Client for the partition that events keyed by `key` are produced to
+    async fn partition_for(&self, key: &str) -> Result<Arc<PartitionClient>, EventStreamError> {
+        let client = self.client().await?;
+        let topics = client.list_topics().await.map_err(|e| EventStreamError::Connect(e.to_string()))?;
+        let partitions: Vec<i32> = topics
+            .into_iter()
+            .find(|topic| topic.name == self.topic)
+            .map(|topic| topic.partitions.into_iter().collect())
+            .unwrap_or_default();
+        let partition = partition_index(key, partitions.len())
+            .map(|index| partitions[index])
+            .ok_or_else(|| EventStreamError::Publish(format!("Kafka topic '{}' does not exist", self.topic)))?;
+
+        let mut cache = self.partitions.lock().await;
+        if let Some(partition_client) = cache.get(&partition) {
+            return Ok(partition_client.clone());
+        }
+        let partition_client = Arc::new(
+            client
+                .partition_client(self.topic.clone(), partition, UnknownTopicHandling::Error)
+                .await
+                .map_err(|e| EventStreamError::Connect(e.to_string()))?,
+        );
+        cache.insert(partition, partition_client.clone());
+        Ok(partition_client)
+    }
+}
+
+/// Index of the partition for `key` among `count` partitions, or `None` when there are none
+fn partition_index(key: &str, count: usize) -> Option<usize> {
+    if count == 0 {
+        return None;
+    }
+    let hash = Sha256::digest(key.as_bytes());
+    let value = u64::from_be_bytes(hash[..8].try_into().expect("SHA-256 digests are 32 bytes"));
+    Some((value % count as u64) as usize)
+}
+
+#[async_trait]
+impl EventPublisher for KafkaPublisher {
+    async fn publish(&self, event: &VerifiedMessageEvent, payload: &[u8]) -> Result<(), EventStreamError> {
+        let partition = self.partition_for(&event.group_id).await?;
+        let record = Record {
+            key: Some(event.group_id.clone().into_bytes()),
+            value: Some(payload.to_vec()),
+            headers: BTreeMap::from([(EVENT_ID_HEADER.to_string(), event.id.clone().into_bytes())]),
+            timestamp: event.timestamp,
+        };
+        partition
+            .produce(vec![record], Compression::NoCompression)
+            .await
+            .map_err(|e| EventStreamError::Publish(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition_is_stable_per_group() {
+        let first = partition_index("group1", 12).unwrap();
+
+        assert_eq!(partition_index("group1", 12), Some(first));
+        assert!(first < 12);
+        assert_eq!(partition_index("group1", 1), Some(0));
+        assert_eq!(partition_index("group1", 0), None);
+    }
+}
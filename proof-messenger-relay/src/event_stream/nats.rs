@@ -0,0 +1,62 @@
+//! NATS JetStream event publisher
+//!
+//! Events are published to a single subject, which must be bound to a
+//! JetStream stream; the relay does not create it. Each event carries its ID
+//! in the `Nats-Msg-Id` header, so JetStream drops a retried event it has
+//! already stored within the stream's deduplication window.
+
+use async_nats::header::{HeaderMap, NATS_MESSAGE_ID};
+use async_nats::jetstream::{self, Context};
+use async_trait::async_trait;
+use tokio::sync::OnceCell;
+
+use super::{EventPublisher, EventStreamError, VerifiedMessageEvent};
+
+/// Publishes events to a JetStream subject
+pub struct NatsPublisher {
+    servers: Vec<String>,
+    subject: String,
+    context: OnceCell<Context>,
+}
+
+impl NatsPublisher {
+    /// A publisher for `subject` on the NATS servers at `servers`
+    ///
+    /// No connection is made until the first event is published.
+    pub fn new(servers: Vec<String>, subject: String) -> Self {
+        Self {
+            servers,
+            subject,
+            context: OnceCell::new(),
+        }
+    }
+
+    /// Connect to NATS, reusing the connection once established
+    async fn context(&self) -> Result<&Context, EventStreamError> {
+        self.context
+            .get_or_try_init(|| async {
+                let client = async_nats::connect(&self.servers)
+                    .await
+                    .map_err(|e| EventStreamError::Connect(e.to_string()))?;
+                Ok(jetstream::new(client))
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl EventPublisher for NatsPublisher {
+    async fn publish(&self, event: &VerifiedMessageEvent, payload: &[u8]) -> Result<(), EventStreamError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(NATS_MESSAGE_ID, event.id.as_str());
+
+        let ack = self
+            .context()
+            .await?
+            .publish_with_headers(self.subject.clone(), headers, payload.to_vec().into())
+            .await
+            .map_err(|e| EventStreamError::Publish(e.to_string()))?;
+        ack.await.map_err(|e| EventStreamError::Publish(e.to_string()))?;
+        Ok(())
+    }
+}
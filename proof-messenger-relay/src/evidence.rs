@@ -0,0 +1,258 @@
+//! Evidence Bundle Module
+//!
+//! `GET /message/:message_id/evidence` exports a self-contained bundle an
+//! auditor can check without trusting or contacting the relay again (see
+//! [`proof_messenger_protocol::evidence`]). The bundle holds the message and
+//! its proof, the proof's revocation status at export time, and an inclusion
+//! proof against a freshly signed tree head; the relay signs it with its
+//! transparency log key. `proof-messenger-cli verify-bundle` verifies it.
+//!
+//! Bundles need the log's signing key: without `TRANSPARENCY_SIGNING_KEY` the
+//! endpoint answers `404 TRANSPARENCY_DISABLED`.
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Extension, Router,
+};
+use proof_messenger_protocol::evidence::{
+    EvidenceBundle, EvidenceMessage, InclusionEvidence, RevocationEvidence, EVIDENCE_BUNDLE_VERSION,
+};
+use proof_messenger_protocol::transparency::inclusion_proof;
+use std::sync::Arc;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::Database,
+    request_id::RequestId,
+    tenancy::TenantScope,
+    transparency::{log_entry, TransparencyError, TransparencyLog},
+    AppError,
+};
+
+/// Build and sign the evidence bundle for a message
+pub async fn evidence_bundle(
+    db: &Database,
+    log: &TransparencyLog,
+    tenant: &TenantScope,
+    message_id: &str,
+) -> Result<EvidenceBundle, AppError> {
+    let message = crate::get_tenant_message(db, tenant, message_id).await?;
+    let entry = log_entry(db, message_id).await?;
+
+    // Prove inclusion in the whole log as it stands, under a tree head signed now
+    let tree_size = db.get_transparency_log_size().await?;
+    let leaves = db.get_transparency_leaf_hashes(tree_size).await?;
+    let audit_path = inclusion_proof(&leaves, entry.leaf_index as u64)
+        .map_err(|e| AppError::ProcessingError(format!("Failed to build inclusion proof: {}", e)))?;
+    let head = log.sign_leaves(&leaves)?;
+
+    let revocation = db.get_active_revocation(&message.proof).await?;
+
+    let mut bundle = EvidenceBundle {
+        version: EVIDENCE_BUNDLE_VERSION,
+        exported_at: chrono::Utc::now().timestamp_millis(),
+        message: EvidenceMessage {
+            id: message.id,
+            sender: message.sender,
+            context: message.context,
+            body: message.body,
+            proof: message.proof,
+            message_hash: message.message_hash,
+        },
+        revocation: RevocationEvidence {
+            revoked: revocation.is_some(),
+            revoked_at: revocation.as_ref().map(|r| r.revoked_at.timestamp_millis()),
+            reason: revocation.and_then(|r| r.reason),
+        },
+        inclusion: InclusionEvidence {
+            leaf_index: entry.leaf_index as u64,
+            tree_size: head.tree_size,
+            root_hash: hex::encode(head.root_hash),
+            audit_path: audit_path.iter().map(hex::encode).collect(),
+            tree_head_timestamp: head.timestamp,
+            tree_head_signature: hex::encode(head.signature.to_bytes()),
+        },
+        relay_public_key: String::new(),
+        signature: String::new(),
+    };
+    log.sign_evidence(&mut bundle)?;
+    Ok(bundle)
+}
+
+/// Create router for evidence bundle endpoints
+pub fn evidence_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/message/:message_id/evidence", get(get_evidence_handler))
+}
+
+/// Create router for authenticated evidence bundle endpoints
+pub fn authenticated_evidence_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/message/:message_id/evidence", get(authenticated_get_evidence_handler))
+}
+
+/// Handler to export a message's evidence bundle
+#[utoipa::path(
+    get,
+    path = "/message/{message_id}/evidence",
+    operation_id = "getEvidenceBundle",
+    tag = "evidence",
+    params(("message_id" = String, Path, description = "Message ID")),
+    responses(
+        (status = 200, description = "The message's relay-signed evidence bundle", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_evidence_handler(
+    State(db): State<Arc<Database>>,
+    log: Option<Extension<Arc<TransparencyLog>>>,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let Extension(log) = log.ok_or(TransparencyError::Disabled)?;
+    info!("Exporting evidence bundle for message: {}", message_id);
+
+    let bundle = evidence_bundle(&db, &log, &tenant, &message_id).await?;
+
+    Ok((StatusCode::OK, Json(bundle)))
+}
+
+/// Authenticated handler to export a message's evidence bundle
+#[instrument(skip_all)]
+async fn authenticated_get_evidence_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    log: Option<Extension<Arc<TransparencyLog>>>,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let Extension(log) = log.ok_or(TransparencyError::Disabled)?;
+    info!("Authenticated user {} exporting evidence bundle for message: {}", auth.user_id, message_id);
+
+    let bundle = evidence_bundle(&db, &log, &tenant, &message_id).await?;
+
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("message_id".to_string(), message_id);
+    metadata.insert("tree_size".to_string(), bundle.inclusion.tree_size.to_string());
+    metadata.insert("revoked".to_string(), bundle.revocation.revoked.to_string());
+    if let Err(e) = secure_logger.audit_log(
+        "Evidence bundle exported".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log evidence export: {}", e);
+    }
+
+    Ok((StatusCode::OK, Json(bundle)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use ed25519_dalek::Signer;
+    use proof_messenger_protocol::evidence::verify_evidence_bundle;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use tower::ServiceExt;
+
+    use crate::database::StoredMessage;
+
+    async fn setup(count: usize) -> (Arc<Database>, Vec<String>) {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let sender = generate_secure_keypair_with_seed(4);
+        let mut ids = Vec::new();
+        for i in 0..count {
+            let context = format!("invoice {}", i);
+            let message = crate::Message {
+                sender: hex::encode(sender.public_key_bytes()),
+                context: hex::encode(&context),
+                body: format!("pay invoice {}", i),
+                proof: hex::encode(sender.as_keypair().sign(context.as_bytes()).to_bytes()),
+                pqc: None,
+                thread_id: None,
+                reply_to: None,
+                expires_at: None,
+                recipient: None,
+            };
+            ids.push(db.store_message(StoredMessage::from(message)).await.unwrap());
+        }
+        (db, ids)
+    }
+
+    fn app(db: Arc<Database>, log: Option<Arc<TransparencyLog>>) -> Router {
+        let app = Router::new().merge(evidence_routes()).with_state(db);
+        match log {
+            Some(log) => app.layer(Extension(log)),
+            None => app,
+        }
+    }
+
+    async fn get(app: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_bundle_verifies_offline() {
+        // ARRANGE: Three logged messages and a log signing key
+        let (db, ids) = setup(3).await;
+        let relay_key = generate_secure_keypair_with_seed(9);
+        let log = Arc::new(TransparencyLog::new(generate_secure_keypair_with_seed(9)));
+
+        // ACT: Export the middle message's bundle
+        let (status, json) = get(app(db, Some(log)), &format!("/message/{}/evidence", ids[1])).await;
+
+        // ASSERT: The bundle verifies against the relay key with nothing else
+        assert_eq!(status, StatusCode::OK);
+        let bundle: EvidenceBundle = serde_json::from_value(json).unwrap();
+        assert_eq!(bundle.message.id, ids[1]);
+        assert_eq!(bundle.inclusion.leaf_index, 1);
+        assert_eq!(bundle.inclusion.tree_size, 3);
+        assert!(!bundle.revocation.revoked);
+        assert!(verify_evidence_bundle(&bundle, Some(&relay_key.public_key())).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bundle_records_revocation_and_deletion() {
+        // ARRANGE: A message whose proof is revoked, then deleted
+        let (db, ids) = setup(2).await;
+        let message = db.get_message_by_id(&ids[0]).await.unwrap();
+        db.revoke_proof(&message.proof, Some("key compromised"), Some("admin"), None).await.unwrap();
+        db.tombstone_message(&ids[0]).await.unwrap();
+        let log = Arc::new(TransparencyLog::new(generate_secure_keypair_with_seed(9)));
+
+        // ACT: Export its bundle
+        let (status, json) = get(app(db, Some(log)), &format!("/message/{}/evidence", ids[0])).await;
+
+        // ASSERT: The revocation is recorded and the tombstone still verifies
+        assert_eq!(status, StatusCode::OK);
+        let bundle: EvidenceBundle = serde_json::from_value(json).unwrap();
+        assert!(bundle.revocation.revoked);
+        assert_eq!(bundle.revocation.reason.as_deref(), Some("key compromised"));
+        assert_eq!(bundle.message.body, "");
+        assert!(verify_evidence_bundle(&bundle, None).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bundles_need_a_signing_key() {
+        let (db, ids) = setup(1).await;
+
+        let (status, json) = get(app(db, None), &format!("/message/{}/evidence", ids[0])).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json["code"], "TRANSPARENCY_DISABLED");
+    }
+}
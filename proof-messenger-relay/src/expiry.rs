@@ -0,0 +1,141 @@
+//! Message Expiry Module
+//!
+//! Senders may give a message an `expires_at` time, as the protocol's
+//! message metadata does, to make it ephemeral. The relay stores the expiry
+//! with the message and, once it has passed:
+//!
+//! - leaves the message out of group, sender, thread and search results,
+//!   exports and subscription catch-up
+//! - answers direct fetches of the message with `410 Gone`
+//! - purges the message every `retention.expiry_purge_interval_secs`
+//!
+//! Messages submitted with an expiry that has already passed are rejected.
+//! The expiry is not covered by the message's proof, so it only tells the
+//! relay how long to serve the message; recipients may keep their own copy.
+//! As with other retention, purged messages stay in the transparency log.
+
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::database::Database;
+use crate::{AppError, Message};
+
+/// Reject a message whose expiry has already passed
+pub fn validate_message(message: &Message) -> Result<(), AppError> {
+    match message.expires_at {
+        Some(expires_at) if expires_at <= Utc::now() => Err(AppError::InvalidExpiry(format!(
+            "expires_at {} is in the past",
+            expires_at.to_rfc3339()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Purge expired messages periodically in the background
+pub fn spawn_purge(db: Arc<Database>, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            crate::readiness::BACKGROUND_JOBS.heartbeat("message_expiry", interval);
+            match db.delete_expired_messages(Utc::now()).await {
+                Ok(0) => {}
+                Ok(purged) => {
+                    info!("Purged {} expired messages", purged);
+                    crate::metrics::RETENTION_DELETIONS_TOTAL
+                        .get_or_create(&vec![("job".to_string(), "message_expiry".to_string())])
+                        .inc_by(purged);
+                }
+                Err(e) => warn!("Failed to purge expired messages: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_app;
+    use crate::database::StoredMessage;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::Router;
+    use chrono::{DateTime, Duration as ChronoDuration};
+    use tower::ServiceExt;
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    fn message(body: &str, expires_at: Option<DateTime<Utc>>) -> Message {
+        Message {
+            sender: "a".repeat(64),
+            context: "00".to_string(),
+            body: body.to_string(),
+            proof: "00".repeat(64),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at,
+            recipient: None,
+        }
+    }
+
+    async fn get(app: &Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[test]
+    fn test_messages_that_already_expired_are_rejected() {
+        let past = Utc::now() - ChronoDuration::seconds(1);
+        let future = Utc::now() + ChronoDuration::hours(1);
+
+        assert!(matches!(validate_message(&message("late", Some(past))), Err(AppError::InvalidExpiry(_))));
+        assert!(validate_message(&message("ephemeral", Some(future))).is_ok());
+        assert!(validate_message(&message("kept", None)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_expired_messages_are_hidden_until_purged() {
+        // ARRANGE: A kept, an ephemeral and an expired message in one group
+        let db = setup_db().await;
+        let now = Utc::now();
+        let kept = db.store_message(StoredMessage::from(message("kept", None))).await.unwrap();
+        let ephemeral = db
+            .store_message(StoredMessage::from(message("ephemeral", Some(now + ChronoDuration::hours(1)))))
+            .await
+            .unwrap();
+        let expired = db
+            .store_message(StoredMessage::from(message("expired", Some(now - ChronoDuration::seconds(1)))))
+            .await
+            .unwrap();
+        let app = create_app(db.clone());
+
+        // ACT: List the group, fetch each message, then purge
+        let (_, listed) = get(&app, "/messages/default").await;
+        let (ephemeral_status, fetched) = get(&app, &format!("/message/{}", ephemeral)).await;
+        let (expired_status, gone) = get(&app, &format!("/message/{}", expired)).await;
+        let purged = db.delete_expired_messages(Utc::now()).await.unwrap();
+
+        // ASSERT: The expired message is gone from listings and fetches, then from storage
+        let ids: Vec<_> = listed["messages"].as_array().unwrap().iter().map(|m| m["id"].clone()).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&kept.clone().into()) && ids.contains(&ephemeral.clone().into()));
+        assert_eq!(ephemeral_status, StatusCode::OK);
+        assert!(fetched["message"]["expires_at"].is_string());
+        assert_eq!(expired_status, StatusCode::GONE);
+        assert_eq!(gone["code"], "MESSAGE_EXPIRED");
+        assert_eq!(purged, 1);
+        assert!(db.get_message_by_id(&expired).await.is_err());
+        assert!(db.get_message_by_id(&ephemeral).await.is_ok());
+        assert_eq!(get(&app, &format!("/message/{}", expired)).await.1["code"], "MESSAGE_NOT_FOUND");
+    }
+}
@@ -0,0 +1,311 @@
+//! Group History Export Module
+//!
+//! This module exports the full history of a group for compliance review.
+//! Messages are streamed straight from a database cursor into a chunked
+//! response, as NDJSON (one message per line) or CSV, so exports of any size
+//! run in constant memory and are paced by how fast the client reads.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use futures::{stream, Stream, StreamExt};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use std::sync::Arc;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, DatabaseError, StoredMessage},
+    request_id::RequestId,
+    search::accessible_groups,
+    tenancy::TenantScope,
+    AppError,
+};
+
+/// Column order of CSV exports
+const CSV_COLUMNS: [&str; 12] = [
+    "id", "group_id", "sender", "context", "body", "proof", "created_at", "verified", "thread_id", "reply_to",
+    "deleted_at", "message_hash",
+];
+
+/// Output format of an export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Newline-delimited JSON, one message object per line
+    #[default]
+    Ndjson,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+impl ExportFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    /// Lines written before the first message
+    fn preamble(self) -> Option<String> {
+        match self {
+            ExportFormat::Ndjson => None,
+            ExportFormat::Csv => Some(format!("{}\r\n", CSV_COLUMNS.join(","))),
+        }
+    }
+
+    /// Serialize one message as a complete line
+    fn line(self, message: &StoredMessage) -> String {
+        match self {
+            ExportFormat::Ndjson => {
+                let mut line = serde_json::to_string(message).expect("stored messages serialize to JSON");
+                line.push('\n');
+                line
+            }
+            ExportFormat::Csv => {
+                let created_at = message.created_at.to_rfc3339();
+                let verified = message.verified.to_string();
+                let deleted_at = message.deleted_at.map(|at| at.to_rfc3339()).unwrap_or_default();
+                let fields = [
+                    message.id.as_str(),
+                    message.group_id.as_str(),
+                    message.sender.as_str(),
+                    message.context.as_str(),
+                    message.body.as_str(),
+                    message.proof.as_str(),
+                    created_at.as_str(),
+                    verified.as_str(),
+                    message.thread_id.as_deref().unwrap_or(""),
+                    message.reply_to.as_deref().unwrap_or(""),
+                    deleted_at.as_str(),
+                    message.message_hash.as_deref().unwrap_or(""),
+                ];
+                let mut line = fields.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(",");
+                line.push_str("\r\n");
+                line
+            }
+        }
+    }
+}
+
+/// Query parameters for group history export
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// Output format (`ndjson` by default, or `csv`)
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+/// Encode a stream of messages in the requested format
+///
+/// A database error ends the stream with an error, which aborts the chunked
+/// response so the client sees a truncated transfer rather than a complete
+/// but partial export.
+fn encode(
+    format: ExportFormat,
+    messages: impl Stream<Item = Result<StoredMessage, DatabaseError>> + Send + 'static,
+) -> impl Stream<Item = Result<Bytes, DatabaseError>> + Send + 'static {
+    let preamble = stream::iter(format.preamble().map(|line| Ok(Bytes::from(line))));
+    let lines = messages.map(move |message| match message {
+        Ok(message) => Ok(Bytes::from(format.line(&message))),
+        Err(e) => {
+            warn!("Export aborted by database error: {}", e);
+            Err(e)
+        }
+    });
+    preamble.chain(lines)
+}
+
+/// Build the streaming response for an export of one of the tenant's groups
+fn export_response(db: &Database, tenant: &TenantScope, group_id: &str, format: ExportFormat) -> Response {
+    let body = Body::from_stream(encode(format, db.stream_messages_by_group(&tenant.group_id(group_id))));
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, format.content_type()),
+            (header::CACHE_CONTROL, "no-store"),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Create router for export endpoints
+pub fn export_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/messages/:group_id/export", get(export_messages_handler))
+}
+
+/// Create router for authenticated export endpoints
+pub fn authenticated_export_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/messages/:group_id/export", get(authenticated_export_messages_handler))
+}
+
+/// Handler to export the full history of a group
+#[utoipa::path(
+    get,
+    path = "/messages/{group_id}/export",
+    operation_id = "exportGroupMessages",
+    tag = "messages",
+    params(("group_id" = String, Path, description = "Group ID"), ExportQuery),
+    responses(
+        (status = 200, description = "The group's messages as NDJSON or CSV", body = String, content_type = "application/x-ndjson"),
+    )
+)]
+#[instrument(skip_all)]
+async fn export_messages_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(group_id): Path<String>,
+    Query(params): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    info!("Exporting messages for group: {}", group_id);
+
+    Ok(export_response(&db, &tenant, &group_id, params.format))
+}
+
+/// Authenticated handler to export the full history of a group
+///
+/// Requires the `message:export` scope. Callers holding `group:<id>`
+/// scopes may only export those groups.
+#[instrument(skip_all)]
+async fn authenticated_export_messages_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    request_id: RequestId,
+    Path(group_id): Path<String>,
+    Query(params): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    info!("Authenticated user {} exporting messages for group: {}", auth.user_id, group_id);
+
+    if accessible_groups(&auth).is_some_and(|groups| !groups.contains(&group_id)) {
+        return Err(AppError::InsufficientScope("Insufficient permissions to export this group".to_string()));
+    }
+
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("group_id".to_string(), group_id.clone());
+    metadata.insert("format".to_string(), format!("{:?}", params.format).to_lowercase());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Group history export started".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log group export: {}", e);
+    }
+
+    Ok(export_response(&db, &tenant, &group_id, params.format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    async fn setup_test_app(bodies: &[&str]) -> Router {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        for body in bodies {
+            let mut message = StoredMessage::from(crate::Message {
+                sender: "aa".repeat(32),
+                context: "bb".to_string(),
+                body: body.to_string(),
+                proof: "cc".repeat(64),
+                pqc: None,
+                thread_id: None,
+                reply_to: None,
+                expires_at: None,
+                recipient: None,
+            });
+            message.group_id = "group1".to_string();
+            db.store_message(message).await.unwrap();
+        }
+        Router::new().merge(export_routes()).with_state(db)
+    }
+
+    async fn export(app: Router, uri: &str) -> (StatusCode, String, String) {
+        let response = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let content_type = response.headers()[header::CONTENT_TYPE].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, content_type, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_export_streams_ndjson_oldest_first() {
+        // ARRANGE: A group with more messages than the stream buffer holds
+        let bodies: Vec<String> = (0..150).map(|i| format!("message {}", i)).collect();
+        let app = setup_test_app(&bodies.iter().map(String::as_str).collect::<Vec<_>>()).await;
+
+        // ACT: Export it with the default format
+        let (status, content_type, body) = export(app, "/messages/group1/export").await;
+
+        // ASSERT: Every message arrives as its own JSON line, in order
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "application/x-ndjson");
+        let lines: Vec<StoredMessage> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 150);
+        assert_eq!(lines[0].body, "message 0");
+        assert_eq!(lines[149].body, "message 149");
+    }
+
+    #[tokio::test]
+    async fn test_export_csv_quotes_fields() {
+        let app = setup_test_app(&["plain", "has, comma and \"quotes\"\nand a newline"]).await;
+
+        let (status, content_type, body) = export(app, "/messages/group1/export?format=csv").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(content_type.starts_with("text/csv"));
+        assert!(body.starts_with("id,group_id,sender,context,body,proof,created_at,verified,thread_id,reply_to,deleted_at,message_hash\r\n"));
+        assert!(body.contains(",plain,"));
+        assert!(body.contains(",\"has, comma and \"\"quotes\"\"\nand a newline\","));
+        assert_eq!(body.matches("\r\n").count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_export_of_empty_group() {
+        let app = setup_test_app(&["elsewhere"]).await;
+
+        let (status, _, body) = export(app, "/messages/group2/export").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_format_is_rejected() {
+        let app = setup_test_app(&[]).await;
+
+        let response = app
+            .oneshot(Request::builder().uri("/messages/group1/export?format=xml").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,1153 @@
+//! Relay Federation Module
+//!
+//! This module lets relay instances in different regions share verified
+//! messages. Every message a relay accepts is forwarded to its configured
+//! peers over HTTPS; each relay-to-relay request is signed with the
+//! forwarding relay's Ed25519 key and carries hop headers for loop
+//! prevention. The signature also covers a timestamp and a random nonce, so
+//! a captured request is only accepted once and only for
+//! [`REQUEST_WINDOW_SECS`]. A periodic reconciliation job compares recent message
+//! digests with each peer and pulls in anything that was missed.
+//!
+//! Receiving relays never trust a peer's verification: the sender's proof
+//! is checked again before a forwarded message is stored.
+//!
+//! Federation is enabled by listing peers under `[federation]` in the relay
+//! configuration (see [`FederationConfig::from_config`]) and layering the
+//! resulting [`Federation`] onto the router as an [`axum::Extension`].
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode, Uri},
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Router,
+};
+use axum::body::Bytes;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use proof_messenger_protocol::key::SecureKeypair;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    database::{Database, FederatedOrigin, StoredMessage},
+    egress::HttpClient,
+    metrics, process_and_verify_message, AppError, Message,
+};
+
+/// Header naming the relay that sent a federation request
+pub const RELAY_HEADER: &str = "x-federation-relay";
+/// Header counting how many relay-to-relay hops a message has taken
+pub const HOPS_HEADER: &str = "x-federation-hops";
+/// Header listing the relays a message has passed through, comma separated
+pub const PATH_HEADER: &str = "x-federation-path";
+/// Header carrying the sending relay's signature (hex encoded)
+pub const SIGNATURE_HEADER: &str = "x-federation-signature";
+/// Header carrying the Unix time (seconds) a request was signed at
+pub const TIMESTAMP_HEADER: &str = "x-federation-timestamp";
+/// Header carrying the random nonce a request was signed with (hex encoded)
+pub const NONCE_HEADER: &str = "x-federation-nonce";
+
+/// How far a request's timestamp may be from the receiving relay's clock
+pub const REQUEST_WINDOW_SECS: i64 = 300;
+
+/// Random bytes in a request nonce
+const NONCE_LENGTH: usize = 16;
+
+/// Domain separation prefix for relay-to-relay signatures
+const SIGNING_DOMAIN: &[u8] = b"proof-messenger/relay-federation/v1";
+
+/// Default and maximum number of origins returned by a digest request
+const MAX_DIGEST_ENTRIES: i64 = 1000;
+
+/// Federation-specific error types
+#[derive(Error, Debug)]
+pub enum FederationError {
+    #[error("Federation is not enabled on this relay")]
+    Disabled,
+
+    #[error("Invalid federation configuration: {0}")]
+    Config(String),
+
+    #[error("Unknown peer relay: {0}")]
+    UnknownPeer(String),
+
+    #[error("Invalid federation signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("A verified client certificate is required for federation requests")]
+    ClientCertificateRequired,
+
+    #[error("Hop limit of {0} exceeded")]
+    HopLimitExceeded(u32),
+
+    #[error("Forwarding loop detected through relay {0}")]
+    LoopDetected(String),
+
+    #[error("Peer request failed: {0}")]
+    Transport(String),
+}
+
+/// A peer relay messages are exchanged with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FederationPeer {
+    /// Relay ID the peer signs its requests with
+    pub id: String,
+    /// Base URL of the peer (must be HTTPS unless insecure peers are allowed)
+    pub url: String,
+    /// Ed25519 public key of the peer (hex encoded)
+    pub public_key: String,
+}
+
+/// Federation settings for this relay
+pub struct FederationConfig {
+    /// ID this relay signs its federation requests with
+    pub relay_id: String,
+    /// Key used to sign relay-to-relay requests
+    pub signing_key: SecureKeypair,
+    /// Peer relays to forward to and reconcile with
+    pub peers: Vec<FederationPeer>,
+    /// Maximum number of relay-to-relay hops a message may take
+    pub max_hops: u32,
+    /// How often the reconciliation job runs
+    pub reconcile_interval: std::time::Duration,
+    /// How far back each reconciliation run compares digests
+    pub reconcile_window: chrono::Duration,
+    /// Allow plain HTTP peer URLs (local development and tests only)
+    pub allow_insecure_peers: bool,
+    /// PEM certificate and PKCS#8 key presented to peers that require mutual TLS
+    pub client_identity: Option<(PathBuf, PathBuf)>,
+}
+
+impl FederationConfig {
+    /// Create a configuration with default hop limit and reconciliation timing
+    pub fn new(relay_id: &str, signing_key: SecureKeypair, peers: Vec<FederationPeer>) -> Self {
+        Self {
+            relay_id: relay_id.to_string(),
+            signing_key,
+            peers,
+            max_hops: 3,
+            reconcile_interval: std::time::Duration::from_secs(300),
+            reconcile_window: chrono::Duration::minutes(60),
+            allow_insecure_peers: false,
+            client_identity: None,
+        }
+    }
+
+    /// Build federation settings from the relay configuration
+    ///
+    /// Returns `Ok(None)` when no peers are configured.
+    pub fn from_config(config: &crate::config::RelayFederationConfig) -> Result<Option<Self>, FederationError> {
+        if !config.enabled() {
+            return Ok(None);
+        }
+        let relay_id = config
+            .relay_id
+            .as_deref()
+            .ok_or_else(|| FederationError::Config("federation.relay_id is required".to_string()))?;
+        let key_hex = config
+            .signing_key
+            .as_deref()
+            .ok_or_else(|| FederationError::Config("federation.signing_key is required".to_string()))?;
+        let key_bytes = hex::decode(key_hex)
+            .map_err(|e| FederationError::Config(format!("federation.signing_key: {}", e)))?;
+        let signing_key = SecureKeypair::from_bytes(&key_bytes)
+            .map_err(|e| FederationError::Config(format!("federation.signing_key: {}", e)))?;
+
+        let mut federation = Self::new(relay_id, signing_key, config.peers.clone());
+        federation.max_hops = config.max_hops;
+        federation.reconcile_interval = std::time::Duration::from_secs(config.reconcile_interval_secs);
+        federation.reconcile_window = chrono::Duration::minutes(config.reconcile_window_minutes);
+        federation.allow_insecure_peers = config.allow_insecure_peers;
+        federation.client_identity = match (&config.client_cert_path, &config.client_key_path) {
+            (Some(cert), Some(key)) => Some((cert.clone(), key.clone())),
+            (None, None) => None,
+            _ => {
+                return Err(FederationError::Config(
+                    "federation.client_cert_path and federation.client_key_path must be set together".to_string(),
+                ))
+            }
+        };
+
+        Ok(Some(federation))
+    }
+}
+
+/// A verified message as exchanged between relays
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederatedMessage {
+    /// ID of the relay that first accepted the message
+    pub origin_relay: String,
+    /// Message ID assigned by the origin relay
+    pub origin_message_id: String,
+    /// The message as submitted by its sender
+    pub message: Message,
+}
+
+impl FederatedMessage {
+    /// The origin key identifying this message across relays
+    pub fn origin(&self) -> FederatedOrigin {
+        FederatedOrigin {
+            origin_relay: self.origin_relay.clone(),
+            origin_message_id: self.origin_message_id.clone(),
+        }
+    }
+}
+
+/// Hop information carried by a verified federation request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FederationHop {
+    /// Relay that sent the request
+    pub relay_id: String,
+    /// Number of relay-to-relay hops so far
+    pub hops: u32,
+    /// Relays the message has passed through, in order
+    pub path: Vec<String>,
+}
+
+/// Outcome of reconciling with one peer
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReconcileReport {
+    /// Peer the digests were compared with
+    pub peer: String,
+    /// Number of origins in the peer's digest
+    pub checked: usize,
+    /// Number of those origins missing locally
+    pub missing: usize,
+    /// Number of missing messages fetched and stored
+    pub recovered: usize,
+}
+
+/// Query parameters for digest requests
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DigestQuery {
+    /// Only origins received at or after this Unix timestamp (seconds)
+    pub since: i64,
+    /// Maximum number of origins to return
+    pub limit: Option<i64>,
+}
+
+/// Response body for digest requests
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DigestResponse {
+    /// ID of the relay that produced the digest
+    pub relay_id: String,
+    /// Origins of messages received in the requested window
+    pub origins: Vec<FederatedOrigin>,
+}
+
+/// Build the bytes a relay signs for a federation request
+///
+/// `payload` is the request body for POSTs and the path and query (relative
+/// to the federation endpoint) for GETs.
+pub fn signing_payload(relay_id: &str, hops: u32, path: &[String], timestamp: i64, nonce: &str, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(SIGNING_DOMAIN.len() + relay_id.len() + payload.len() + 96);
+    bytes.extend_from_slice(SIGNING_DOMAIN);
+    bytes.push(0);
+    bytes.extend_from_slice(relay_id.as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(hops.to_string().as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(path.join(",").as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(timestamp.to_string().as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(nonce.as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// The signature headers of one federation request
+#[derive(Debug, Clone)]
+pub struct RequestSignature {
+    /// Unix time (seconds) the request was signed at
+    pub timestamp: i64,
+    /// Random nonce the request was signed with (hex encoded)
+    pub nonce: String,
+    /// Signature over [`signing_payload`] (hex encoded)
+    pub signature: String,
+}
+
+impl RequestSignature {
+    /// The header names and values carrying this signature
+    pub fn headers(&self) -> [(&'static str, String); 3] {
+        [
+            (TIMESTAMP_HEADER, self.timestamp.to_string()),
+            (NONCE_HEADER, self.nonce.clone()),
+            (SIGNATURE_HEADER, self.signature.clone()),
+        ]
+    }
+}
+
+/// A running federation: configuration, verified peer keys and HTTP client
+pub struct Federation {
+    config: FederationConfig,
+    peer_keys: HashMap<String, PublicKey>,
+    client: HttpClient,
+    /// Nonces accepted within the request window, keyed by peer and nonce
+    seen_nonces: Mutex<HashMap<(String, String), i64>>,
+}
+
+impl Federation {
+    /// Validate the configuration and prepare the peer HTTP client
+    pub fn new(config: FederationConfig) -> Result<Self, FederationError> {
+        if config.relay_id.is_empty() || config.relay_id.contains(',') {
+            return Err(FederationError::Config("relay ID must be non-empty and contain no commas".to_string()));
+        }
+
+        let mut peer_keys = HashMap::new();
+        for peer in &config.peers {
+            if peer.id == config.relay_id || peer.id.contains(',') {
+                return Err(FederationError::Config(format!("invalid peer ID {}", peer.id)));
+            }
+            if !peer.url.starts_with("https://") && !config.allow_insecure_peers {
+                return Err(FederationError::Config(format!("peer {} must use HTTPS", peer.id)));
+            }
+            reqwest::Url::parse(&peer.url)
+                .map_err(|e| e.to_string())
+                .and_then(|url| crate::egress::current().check(&url).map_err(|e| e.to_string()))
+                .map_err(|e| FederationError::Config(format!("peer {}: {}", peer.id, e)))?;
+            let key_bytes = hex::decode(&peer.public_key)
+                .map_err(|e| FederationError::Config(format!("peer {} key: {}", peer.id, e)))?;
+            let key = PublicKey::from_bytes(&key_bytes)
+                .map_err(|e| FederationError::Config(format!("peer {} key: {}", peer.id, e)))?;
+            if peer_keys.insert(peer.id.clone(), key).is_some() {
+                return Err(FederationError::Config(format!("duplicate peer ID {}", peer.id)));
+            }
+        }
+
+        let mut client = reqwest::Client::builder().timeout(std::time::Duration::from_secs(10));
+        if let Some((cert_path, key_path)) = &config.client_identity {
+            let read = |path: &PathBuf| {
+                std::fs::read(path).map_err(|e| FederationError::Config(format!("{}: {}", path.display(), e)))
+            };
+            let identity = reqwest::Identity::from_pkcs8_pem(&read(cert_path)?, &read(key_path)?)
+                .map_err(|e| FederationError::Config(format!("client certificate: {}", e)))?;
+            client = client.identity(identity);
+        }
+        let client = crate::egress::client(client).map_err(|e| FederationError::Config(e.to_string()))?;
+
+        Ok(Self { config, peer_keys, client, seen_nonces: Mutex::new(HashMap::new()) })
+    }
+
+    /// ID this relay uses in federation requests
+    pub fn relay_id(&self) -> &str {
+        &self.config.relay_id
+    }
+
+    /// Configured peers
+    pub fn peers(&self) -> &[FederationPeer] {
+        &self.config.peers
+    }
+
+    /// Sign a federation request sent by this relay
+    pub fn sign(&self, hops: u32, path: &[String], payload: &[u8]) -> RequestSignature {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        self.sign_at(Utc::now().timestamp(), &hex::encode(nonce), hops, path, payload)
+    }
+
+    /// Sign a federation request with a given timestamp and nonce
+    pub fn sign_at(&self, timestamp: i64, nonce: &str, hops: u32, path: &[String], payload: &[u8]) -> RequestSignature {
+        let bytes = signing_payload(&self.config.relay_id, hops, path, timestamp, nonce, payload);
+        RequestSignature {
+            timestamp,
+            nonce: nonce.to_string(),
+            signature: hex::encode(self.config.signing_key.sign(&bytes).to_bytes()),
+        }
+    }
+
+    /// Authenticate an inbound federation request and enforce loop prevention
+    pub fn verify_request(&self, headers: &HeaderMap, payload: &[u8]) -> Result<FederationHop, FederationError> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| FederationError::InvalidSignature(format!("missing {} header", name)))
+        };
+
+        let relay_id = header(RELAY_HEADER)?.to_string();
+        let key = self
+            .peer_keys
+            .get(&relay_id)
+            .ok_or_else(|| FederationError::UnknownPeer(relay_id.clone()))?;
+        let hops: u32 = header(HOPS_HEADER)?
+            .parse()
+            .map_err(|_| FederationError::InvalidSignature("malformed hop count".to_string()))?;
+        let path: Vec<String> = header(PATH_HEADER)?
+            .split(',')
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        let timestamp: i64 = header(TIMESTAMP_HEADER)?
+            .parse()
+            .map_err(|_| FederationError::InvalidSignature("malformed timestamp".to_string()))?;
+        let nonce = header(NONCE_HEADER)?;
+
+        let signature_bytes = hex::decode(header(SIGNATURE_HEADER)?)
+            .map_err(|e| FederationError::InvalidSignature(e.to_string()))?;
+        let signature = Signature::from_bytes(&signature_bytes)
+            .map_err(|e| FederationError::InvalidSignature(e.to_string()))?;
+        key.verify(&signing_payload(&relay_id, hops, &path, timestamp, nonce, payload), &signature)
+            .map_err(|_| FederationError::InvalidSignature(format!("signature from {} does not verify", relay_id)))?;
+
+        let now = Utc::now().timestamp();
+        if (now - timestamp).abs() > REQUEST_WINDOW_SECS {
+            return Err(FederationError::InvalidSignature(format!("request from {} is outside the request window", relay_id)));
+        }
+        {
+            let mut seen = self.seen_nonces.lock().unwrap_or_else(|e| e.into_inner());
+            seen.retain(|_, signed_at| now - *signed_at <= REQUEST_WINDOW_SECS);
+            if seen.insert((relay_id.clone(), nonce.to_string()), timestamp).is_some() {
+                return Err(FederationError::InvalidSignature(format!("request from {} was replayed", relay_id)));
+            }
+        }
+
+        if hops > self.config.max_hops {
+            return Err(FederationError::HopLimitExceeded(self.config.max_hops));
+        }
+        if path.iter().any(|id| id == &self.config.relay_id) {
+            return Err(FederationError::LoopDetected(self.config.relay_id.clone()));
+        }
+
+        Ok(FederationHop { relay_id, hops, path })
+    }
+
+    /// Record a locally accepted message and forward it to every peer
+    pub async fn publish(self: &Arc<Self>, db: &Database, message_id: &str, message: &Message) -> Result<(), AppError> {
+        db.record_local_origin(self.relay_id(), message_id).await?;
+
+        let envelope = FederatedMessage {
+            origin_relay: self.relay_id().to_string(),
+            origin_message_id: message_id.to_string(),
+            message: message.clone(),
+        };
+        let federation = Arc::clone(self);
+        tokio::spawn(async move {
+            federation.forward(&envelope, 1, &[]).await;
+        });
+        Ok(())
+    }
+
+    /// Forward a message to every peer not already on its path
+    ///
+    /// Failures are logged and counted; reconciliation picks up whatever a
+    /// peer missed.
+    pub async fn forward(&self, envelope: &FederatedMessage, hops: u32, path: &[String]) -> Vec<(String, Result<(), FederationError>)> {
+        let mut path = path.to_vec();
+        path.push(self.config.relay_id.clone());
+
+        let body = match serde_json::to_vec(envelope) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to serialize federated message: {}", e);
+                return Vec::new();
+            }
+        };
+        let signature = self.sign(hops, &path, &body);
+
+        let request_id = crate::request_id::current();
+
+        let mut results = Vec::new();
+        for peer in self.config.peers.iter().filter(|peer| !path.contains(&peer.id)) {
+            let result = match self.client.post(&format!("{}/federation/inbound", peer.url.trim_end_matches('/'))) {
+                Ok(request) => {
+                    let mut request = request
+                        .header("content-type", "application/json")
+                        .header(RELAY_HEADER, &self.config.relay_id)
+                        .header(HOPS_HEADER, hops.to_string())
+                        .header(PATH_HEADER, path.join(","));
+                    for (name, value) in signature.headers() {
+                        request = request.header(name, value);
+                    }
+                    // Let the peer's logs be correlated with the request that caused the forward
+                    if let Some(request_id) = &request_id {
+                        request = request.header(crate::request_id::REQUEST_ID_HEADER, request_id);
+                    }
+                    request
+                        .body(body.clone())
+                        .send()
+                        .await
+                        .and_then(|response| response.error_for_status())
+                        .map(|_| ())
+                        .map_err(|e| FederationError::Transport(e.to_string()))
+                }
+                Err(e) => Err(FederationError::Transport(e.to_string())),
+            };
+
+            match &result {
+                Ok(()) => {
+                    metrics::FEDERATION_FORWARDED_TOTAL.inc();
+                }
+                Err(e) => {
+                    metrics::FEDERATION_FORWARD_FAILURES_TOTAL.inc();
+                    warn!("Failed to forward message to peer {}: {}", peer.id, e);
+                }
+            }
+            results.push((peer.id.clone(), result));
+        }
+        results
+    }
+
+    /// Verify and store a message received from a peer
+    ///
+    /// Thread references are relay-local IDs, so they are dropped from
+    /// federated copies. Returns `false` if the message was already stored.
+    pub async fn ingest(&self, db: &Arc<Database>, envelope: &FederatedMessage, received_from: &str) -> Result<bool, AppError> {
+        process_and_verify_message(&envelope.message, Some(db)).await?;
+
+        let mut message = envelope.message.clone();
+        message.thread_id = None;
+        message.reply_to = None;
+        let stored = db
+            .store_federated_message(&envelope.origin(), received_from, StoredMessage::from(message))
+            .await?;
+        if stored {
+            metrics::FEDERATION_RECEIVED_TOTAL.inc();
+        }
+        Ok(stored)
+    }
+
+    /// Sign and send a GET request to a peer's federation endpoint
+    async fn get_from_peer<T: serde::de::DeserializeOwned>(&self, peer: &FederationPeer, endpoint: &str) -> Result<T, FederationError> {
+        let path = vec![self.config.relay_id.clone()];
+        let mut request = self
+            .client
+            .get(&format!("{}/federation{}", peer.url.trim_end_matches('/'), endpoint))
+            .map_err(|e| FederationError::Transport(e.to_string()))?
+            .header(RELAY_HEADER, &self.config.relay_id)
+            .header(HOPS_HEADER, "0")
+            .header(PATH_HEADER, path.join(","));
+        for (name, value) in self.sign(0, &path, endpoint.as_bytes()).headers() {
+            request = request.header(name, value);
+        }
+        request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| FederationError::Transport(e.to_string()))?
+            .json::<T>()
+            .await
+            .map_err(|e| FederationError::Transport(e.to_string()))
+    }
+
+    /// Compare recent digests with one peer and fetch any missed messages
+    pub async fn reconcile_peer(&self, db: &Arc<Database>, peer: &FederationPeer) -> Result<ReconcileReport, FederationError> {
+        let since = (Utc::now() - self.config.reconcile_window).timestamp();
+        let digest: DigestResponse = self
+            .get_from_peer(peer, &format!("/digest?since={}&limit={}", since, MAX_DIGEST_ENTRIES))
+            .await?;
+
+        let mut report = ReconcileReport {
+            peer: peer.id.clone(),
+            checked: digest.origins.len(),
+            ..Default::default()
+        };
+        for origin in digest.origins {
+            let known = db
+                .is_federated_message_known(&origin)
+                .await
+                .map_err(|e| FederationError::Transport(e.to_string()))?;
+            if known {
+                continue;
+            }
+
+            report.missing += 1;
+            metrics::FEDERATION_MISSED_TOTAL.inc();
+            warn!(
+                "Message {}/{} held by peer {} is missing locally",
+                origin.origin_relay, origin.origin_message_id, peer.id
+            );
+
+            let endpoint = format!("/messages/{}/{}", origin.origin_relay, origin.origin_message_id);
+            let envelope = match self.get_from_peer::<FederatedMessage>(peer, &endpoint).await {
+                Ok(envelope) if envelope.origin() == origin => envelope,
+                Ok(_) => {
+                    warn!("Peer {} returned a different message for {}", peer.id, endpoint);
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Failed to fetch missed message from peer {}: {}", peer.id, e);
+                    continue;
+                }
+            };
+            match self.ingest(db, &envelope, &peer.id).await {
+                Ok(_) => report.recovered += 1,
+                Err(e) => warn!("Missed message from peer {} failed verification: {}", peer.id, e),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Reconcile with every peer, logging per-peer failures
+    pub async fn reconcile(&self, db: &Arc<Database>) -> Vec<ReconcileReport> {
+        let mut reports = Vec::new();
+        for peer in &self.config.peers {
+            match self.reconcile_peer(db, peer).await {
+                Ok(report) => {
+                    info!(
+                        "Reconciled with peer {}: {} checked, {} missing, {} recovered",
+                        report.peer, report.checked, report.missing, report.recovered
+                    );
+                    reports.push(report);
+                }
+                Err(e) => warn!("Reconciliation with peer {} failed: {}", peer.id, e),
+            }
+        }
+        reports
+    }
+
+    /// Run reconciliation periodically in the background
+    pub fn spawn_reconciliation(self: Arc<Self>, db: Arc<Database>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.config.reconcile_interval);
+            loop {
+                interval.tick().await;
+                crate::readiness::BACKGROUND_JOBS.heartbeat("federation_reconciliation", self.config.reconcile_interval);
+                self.reconcile(&db).await;
+            }
+        })
+    }
+}
+
+/// Create router for relay-to-relay federation endpoints
+///
+/// These endpoints are authenticated by peer signatures rather than user
+/// tokens, and respond 404 when federation is not enabled.
+pub fn federation_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/inbound", post(inbound_handler))
+        .route("/digest", get(digest_handler))
+        .route("/messages/:origin_relay/:origin_message_id", get(get_federated_message_handler))
+        .route_layer(axum::middleware::from_fn(crate::tls::require_client_certificate))
+}
+
+/// Publish a locally accepted message if federation is enabled
+pub async fn publish_if_enabled(
+    federation: Option<&Arc<Federation>>,
+    db: &Database,
+    message_id: &str,
+    message: &Message,
+) -> Result<(), AppError> {
+    match federation {
+        Some(federation) => federation.publish(db, message_id, message).await,
+        None => Ok(()),
+    }
+}
+
+/// Handler for messages forwarded by a peer relay
+#[utoipa::path(
+    post,
+    path = "/federation/inbound",
+    operation_id = "receiveFederatedMessage",
+    tag = "federation",
+    request_body = Object,
+    responses(
+        (status = 200, description = "Forwarded message accepted", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn inbound_handler(
+    State(db): State<Arc<Database>>,
+    federation: Option<Extension<Arc<Federation>>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    let Extension(federation) = federation.ok_or(FederationError::Disabled)?;
+    let hop = federation.verify_request(&headers, &body)?;
+    info!("Received federated message from relay {} after {} hops", hop.relay_id, hop.hops);
+
+    let envelope: FederatedMessage = serde_json::from_slice(&body)
+        .map_err(|e| AppError::ProcessingError(format!("Invalid federated message: {}", e)))?;
+    let stored = federation.ingest(&db, &envelope, &hop.relay_id).await?;
+
+    // Pass new messages on while hops remain; duplicates stop here
+    if stored && hop.hops < federation.config.max_hops {
+        let federation = Arc::clone(&federation);
+        tokio::spawn(async move {
+            federation.forward(&envelope, hop.hops + 1, &hop.path).await;
+        });
+    }
+
+    let response = Json(serde_json::json!({
+        "status": if stored { "accepted" } else { "duplicate" },
+        "relay_id": federation.relay_id()
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler listing the origins of recently received messages
+#[utoipa::path(
+    get,
+    path = "/federation/digest",
+    operation_id = "getFederationDigest",
+    tag = "federation",
+    params(DigestQuery),
+    responses(
+        (status = 200, description = "Origins of the messages received since a time", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn digest_handler(
+    State(db): State<Arc<Database>>,
+    federation: Option<Extension<Arc<Federation>>>,
+    headers: HeaderMap,
+    uri: Uri,
+    Query(params): Query<DigestQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let Extension(federation) = federation.ok_or(FederationError::Disabled)?;
+    let hop = federation.verify_request(&headers, request_target(&uri).as_bytes())?;
+    info!("Serving federation digest to relay {}", hop.relay_id);
+
+    let since = DateTime::<Utc>::from_timestamp(params.since, 0)
+        .ok_or_else(|| AppError::InvalidQuery("since is out of range".to_string()))?;
+    let limit = params.limit.unwrap_or(MAX_DIGEST_ENTRIES).clamp(1, MAX_DIGEST_ENTRIES);
+    let origins = db.get_federated_digest(since, limit).await?;
+
+    Ok((StatusCode::OK, Json(DigestResponse {
+        relay_id: federation.relay_id().to_string(),
+        origins,
+    })))
+}
+
+/// Handler returning a federated message by its origin
+#[utoipa::path(
+    get,
+    path = "/federation/messages/{origin_relay}/{origin_message_id}",
+    operation_id = "getFederatedMessage",
+    tag = "federation",
+    params(("origin_relay" = String, Path, description = "ID of the relay the message came from"), ("origin_message_id" = String, Path, description = "Message ID on the origin relay")),
+    responses(
+        (status = 200, description = "A message received from a peer", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_federated_message_handler(
+    State(db): State<Arc<Database>>,
+    federation: Option<Extension<Arc<Federation>>>,
+    headers: HeaderMap,
+    uri: Uri,
+    Path((origin_relay, origin_message_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    let Extension(federation) = federation.ok_or(FederationError::Disabled)?;
+    let hop = federation.verify_request(&headers, request_target(&uri).as_bytes())?;
+    info!("Serving federated message to relay {}", hop.relay_id);
+
+    let origin = FederatedOrigin { origin_relay, origin_message_id };
+    let stored = db.get_federated_message(&origin).await?;
+    let pqc = stored.pqc();
+
+    Ok((StatusCode::OK, Json(FederatedMessage {
+        origin_relay: origin.origin_relay,
+        origin_message_id: origin.origin_message_id,
+        message: Message {
+            sender: stored.sender,
+            context: stored.context,
+            body: stored.body,
+            proof: stored.proof,
+            pqc,
+            thread_id: None,
+            reply_to: None,
+            expires_at: stored.expires_at,
+            recipient: stored.recipient,
+        },
+    })))
+}
+
+/// Path and query of a request relative to the federation endpoint
+fn request_target(uri: &Uri) -> String {
+    uri.path_and_query()
+        .map(|target| target.as_str().to_string())
+        .unwrap_or_else(|| uri.path().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use ed25519_dalek::Signer;
+    use proof_messenger_protocol::key::{generate_keypair_with_seed, generate_secure_keypair_with_seed};
+    use tower::ServiceExt;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn peer(id: &str, url: &str, seed: u64) -> FederationPeer {
+        FederationPeer {
+            id: id.to_string(),
+            url: url.to_string(),
+            public_key: hex::encode(generate_secure_keypair_with_seed(seed).public_key_bytes()),
+        }
+    }
+
+    fn federation(relay_id: &str, seed: u64, peers: Vec<FederationPeer>) -> Arc<Federation> {
+        let mut config = FederationConfig::new(relay_id, generate_secure_keypair_with_seed(seed), peers);
+        config.allow_insecure_peers = true;
+        Arc::new(Federation::new(config).unwrap())
+    }
+
+    fn signed_message(seed: u64, body: &str) -> Message {
+        let keypair = generate_keypair_with_seed(seed);
+        let context = format!("context for {}", body).into_bytes();
+        Message {
+            sender: hex::encode(keypair.public.to_bytes()),
+            context: hex::encode(&context),
+            body: body.to_string(),
+            proof: hex::encode(keypair.sign(&context).to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    /// Build an inbound request as relay `sender` (keyed by `seed`) would send it
+    fn inbound_request(sender: &Federation, hops: u32, path: &[String], envelope: &FederatedMessage) -> Request<Body> {
+        let body = serde_json::to_vec(envelope).unwrap();
+        let signature = sender.sign(hops, path, &body);
+        inbound_request_with(sender, hops, path, &signature, body)
+    }
+
+    /// Build an inbound request carrying a given signature
+    fn inbound_request_with(sender: &Federation, hops: u32, path: &[String], signature: &RequestSignature, body: Vec<u8>) -> Request<Body> {
+        signed(
+            Request::builder()
+                .method("POST")
+                .uri("/federation/inbound")
+                .header("content-type", "application/json")
+                .header(RELAY_HEADER, sender.relay_id())
+                .header(HOPS_HEADER, hops.to_string())
+                .header(PATH_HEADER, path.join(",")),
+            signature,
+        )
+        .body(Body::from(body))
+        .unwrap()
+    }
+
+    /// Add a request signature's headers to a request
+    fn signed(mut request: axum::http::request::Builder, signature: &RequestSignature) -> axum::http::request::Builder {
+        for (name, value) in signature.headers() {
+            request = request.header(name, value);
+        }
+        request
+    }
+
+    #[tokio::test]
+    async fn test_inbound_message_is_verified_and_stored_once() {
+        // ARRANGE: Relay "us" accepts forwards from relay "eu" (which has no peers to re-forward to)
+        let db = setup_db().await;
+        let us = federation("us", 1, vec![peer("eu", "http://127.0.0.1:9", 2)]);
+        let eu = federation("eu", 2, vec![]);
+        let app = Router::new()
+            .nest("/federation", federation_routes())
+            .layer(Extension(us.clone()))
+            .with_state(db.clone());
+        let envelope = FederatedMessage {
+            origin_relay: "eu".to_string(),
+            origin_message_id: "msg-1".to_string(),
+            message: signed_message(7, "hello from eu"),
+        };
+        let path = vec!["eu".to_string()];
+
+        // ACT: Deliver the same message twice
+        let first = app.clone().oneshot(inbound_request(&eu, 3, &path, &envelope)).await.unwrap();
+        let second = app.clone().oneshot(inbound_request(&eu, 3, &path, &envelope)).await.unwrap();
+
+        // ASSERT: Stored once, second delivery reported as duplicate
+        assert_eq!(first.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(second.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], "duplicate");
+        assert_eq!(db.get_message_count("default").await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_inbound_rejects_unknown_peer_bad_signature_and_loops() {
+        let db = setup_db().await;
+        let us = federation("us", 1, vec![peer("eu", "http://127.0.0.1:9", 2)]);
+        let eu = federation("eu", 2, vec![]);
+        let impostor = federation("eu", 3, vec![]);
+        let stranger = federation("asia", 4, vec![]);
+        let app = Router::new()
+            .nest("/federation", federation_routes())
+            .layer(Extension(us))
+            .with_state(db.clone());
+        let envelope = FederatedMessage {
+            origin_relay: "eu".to_string(),
+            origin_message_id: "msg-1".to_string(),
+            message: signed_message(7, "hello"),
+        };
+        let status = |request: Request<Body>| {
+            let app = app.clone();
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+
+        let unknown = status(inbound_request(&stranger, 1, &["asia".to_string()], &envelope)).await;
+        let forged = status(inbound_request(&impostor, 1, &["eu".to_string()], &envelope)).await;
+        let looped = status(inbound_request(&eu, 2, &["us".to_string(), "eu".to_string()], &envelope)).await;
+        let too_far = status(inbound_request(&eu, 4, &["eu".to_string()], &envelope)).await;
+
+        assert_eq!(unknown, StatusCode::FORBIDDEN);
+        assert_eq!(forged, StatusCode::UNAUTHORIZED);
+        assert_eq!(looped, StatusCode::LOOP_DETECTED);
+        assert_eq!(too_far, StatusCode::LOOP_DETECTED);
+        assert_eq!(db.get_message_count("default").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_inbound_rejects_replayed_and_stale_requests() {
+        // ARRANGE: A signed request captured in transit, and one signed long ago
+        let db = setup_db().await;
+        let us = federation("us", 1, vec![peer("eu", "http://127.0.0.1:9", 2)]);
+        let eu = federation("eu", 2, vec![]);
+        let app = Router::new()
+            .nest("/federation", federation_routes())
+            .layer(Extension(us))
+            .with_state(db.clone());
+        let path = vec!["eu".to_string()];
+        let envelope = |id: &str| FederatedMessage {
+            origin_relay: "eu".to_string(),
+            origin_message_id: id.to_string(),
+            message: signed_message(7, id),
+        };
+        let captured = serde_json::to_vec(&envelope("msg-1")).unwrap();
+        let captured_signature = eu.sign(1, &path, &captured);
+        let old = serde_json::to_vec(&envelope("msg-2")).unwrap();
+        let old_signature = eu.sign_at(Utc::now().timestamp() - REQUEST_WINDOW_SECS - 60, "00", 1, &path, &old);
+
+        // ACT: Deliver the captured request twice, then the stale one
+        let first = app.clone().oneshot(inbound_request_with(&eu, 1, &path, &captured_signature, captured.clone())).await.unwrap();
+        let replayed = app.clone().oneshot(inbound_request_with(&eu, 1, &path, &captured_signature, captured)).await.unwrap();
+        let stale = app.oneshot(inbound_request_with(&eu, 1, &path, &old_signature, old)).await.unwrap();
+
+        // ASSERT: Only the first delivery is accepted
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(replayed.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(stale.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(db.get_message_count("default").await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_inbound_without_federation_is_not_found() {
+        let db = setup_db().await;
+        let eu = federation("eu", 2, vec![]);
+        let app = Router::new().nest("/federation", federation_routes()).with_state(db);
+        let envelope = FederatedMessage {
+            origin_relay: "eu".to_string(),
+            origin_message_id: "msg-1".to_string(),
+            message: signed_message(7, "hello"),
+        };
+
+        let response = app.oneshot(inbound_request(&eu, 1, &["eu".to_string()], &envelope)).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_forward_signs_request_with_hop_headers() {
+        // ARRANGE: A peer relay that accepts forwards
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/federation/inbound"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let us = federation("us", 1, vec![peer("eu", &server.uri(), 2)]);
+        let envelope = FederatedMessage {
+            origin_relay: "us".to_string(),
+            origin_message_id: "msg-1".to_string(),
+            message: signed_message(7, "hello"),
+        };
+
+        // ACT: Forward the message
+        let results = us.forward(&envelope, 1, &[]).await;
+
+        // ASSERT: The request verifies as coming from "us" with the expected hops
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        let request = &server.received_requests().await.unwrap()[0];
+        let eu = federation("eu", 2, vec![peer("us", "http://127.0.0.1:9", 1)]);
+        let mut headers = HeaderMap::new();
+        for (name, values) in request.headers.iter() {
+            headers.insert(
+                axum::http::HeaderName::from_bytes(name.as_str().as_bytes()).unwrap(),
+                axum::http::HeaderValue::from_str(values.last().as_str()).unwrap(),
+            );
+        }
+        let hop = eu.verify_request(&headers, &request.body).unwrap();
+        assert_eq!(hop, FederationHop { relay_id: "us".to_string(), hops: 1, path: vec!["us".to_string()] });
+    }
+
+    #[tokio::test]
+    async fn test_digest_requires_signed_request() {
+        let db = setup_db().await;
+        let message_id = db.store_message(StoredMessage::from(signed_message(7, "hello"))).await.unwrap();
+        db.record_local_origin("us", &message_id).await.unwrap();
+        let us = federation("us", 1, vec![peer("eu", "http://127.0.0.1:9", 2)]);
+        let eu = federation("eu", 2, vec![]);
+        let app = Router::new()
+            .nest("/federation", federation_routes())
+            .layer(Extension(us))
+            .with_state(db);
+        let path = vec!["eu".to_string()];
+        let request = |signature: RequestSignature| {
+            signed(
+                Request::builder()
+                    .uri("/federation/digest?since=0")
+                    .header(RELAY_HEADER, "eu")
+                    .header(HOPS_HEADER, "0")
+                    .header(PATH_HEADER, "eu"),
+                &signature,
+            )
+            .body(Body::empty())
+            .unwrap()
+        };
+
+        let signed = app.clone().oneshot(request(eu.sign(0, &path, b"/digest?since=0"))).await.unwrap();
+        let replayed = app.oneshot(request(eu.sign(0, &path, b"/digest?since=1"))).await.unwrap();
+
+        assert_eq!(signed.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(signed.into_body(), usize::MAX).await.unwrap();
+        let digest: DigestResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(digest.origins.len(), 1);
+        assert_eq!(replayed.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_served_messages_keep_their_hybrid_proof() {
+        use proof_messenger_protocol::hybrid::{make_hybrid_proof, HybridKeypair, HybridPolicy};
+
+        // ARRANGE: A stored message relayed with a hybrid proof
+        let db = setup_db().await;
+        let keypair = HybridKeypair::generate_with_seed(7);
+        let (public_key, proof) = (keypair.public_key(), make_hybrid_proof(&keypair, b"hybrid").unwrap());
+        let message = Message {
+            sender: hex::encode(public_key.ed25519.to_bytes()),
+            context: hex::encode(b"hybrid"),
+            body: "post-quantum".to_string(),
+            proof: hex::encode(proof.ed25519.to_bytes()),
+            pqc: Some(crate::PqcProof {
+                public_key: hex::encode(public_key.pqc.as_bytes()),
+                proof: hex::encode(proof.pqc.as_bytes()),
+            }),
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        };
+        let message_id = db.store_message(StoredMessage::from(message)).await.unwrap();
+        db.record_local_origin("us", &message_id).await.unwrap();
+        let us = federation("us", 1, vec![peer("eu", "http://127.0.0.1:9", 2)]);
+        let eu = federation("eu", 2, vec![]);
+        let app = Router::new()
+            .nest("/federation", federation_routes())
+            .layer(Extension(us))
+            .with_state(db);
+        let target = format!("/messages/us/{}", message_id);
+
+        // ACT: Fetch the message as peer "eu"
+        let response = app
+            .oneshot(
+                signed(
+                    Request::builder()
+                        .uri(format!("/federation{}", target))
+                        .header(RELAY_HEADER, "eu")
+                        .header(HOPS_HEADER, "0")
+                        .header(PATH_HEADER, "eu"),
+                    &eu.sign(0, &["eu".to_string()], target.as_bytes()),
+                )
+                .body(Body::empty())
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // ASSERT: The served copy still verifies under the strict policy
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let served: FederatedMessage = serde_json::from_slice(&body).unwrap();
+        assert!(served.message.pqc.is_some());
+        assert!(crate::verify_message_proof(&served.message, HybridPolicy::Strict).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_forward_skips_peers_already_on_path() {
+        let us = federation("us", 1, vec![peer("eu", "http://127.0.0.1:9", 2)]);
+        let envelope = FederatedMessage {
+            origin_relay: "eu".to_string(),
+            origin_message_id: "msg-1".to_string(),
+            message: signed_message(7, "hello"),
+        };
+
+        let results = us.forward(&envelope, 2, &["eu".to_string()]).await;
+
+        assert!(results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_recovers_missed_messages() {
+        // ARRANGE: Peer "eu" holds one message we have and one we missed
+        let db = setup_db().await;
+        let held = signed_message(7, "already here");
+        let held_id = db.store_message(StoredMessage::from(held)).await.unwrap();
+        db.record_local_origin("us", &held_id).await.unwrap();
+        let missed = FederatedMessage {
+            origin_relay: "eu".to_string(),
+            origin_message_id: "msg-missed".to_string(),
+            message: signed_message(8, "missed"),
+        };
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/federation/digest"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(DigestResponse {
+                relay_id: "eu".to_string(),
+                origins: vec![
+                    FederatedOrigin { origin_relay: "us".to_string(), origin_message_id: held_id.clone() },
+                    missed.origin(),
+                ],
+            }))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/federation/messages/eu/msg-missed"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&missed))
+            .mount(&server)
+            .await;
+        let us = federation("us", 1, vec![peer("eu", &server.uri(), 2)]);
+
+        // ACT: Reconcile with the peer
+        let reports = us.reconcile(&db).await;
+
+        // ASSERT: The gap is detected and filled
+        assert_eq!(reports, vec![ReconcileReport { peer: "eu".to_string(), checked: 2, missing: 1, recovered: 1 }]);
+        assert!(db.is_federated_message_known(&missed.origin()).await.unwrap());
+        assert_eq!(db.get_message_count("default").await.unwrap(), 2);
+    }
+
+    #[test]
+    fn test_config_requires_https_peers() {
+        let config = FederationConfig::new("us", generate_secure_keypair_with_seed(1), vec![peer("eu", "http://eu.example", 2)]);
+
+        assert!(matches!(Federation::new(config), Err(FederationError::Config(_))));
+    }
+}
@@ -0,0 +1,535 @@
+//! gRPC Interface Module
+//!
+//! This module serves the relay's `Relay`, `MessageQuery` and `Revocation`
+//! gRPC services (defined in `proto/relay.proto`) for internal callers that
+//! prefer gRPC over HTTP. The services share the HTTP API's verification
+//! pipeline and database, so a message sent over either interface is
+//! handled identically.
+//!
+//! Errors are returned as gRPC statuses carrying the relay's stable
+//! [`ErrorCode`] in the `x-error-code` metadata entry.
+//!
+//! The server listens on its own port, enabled by setting
+//! `server.grpc_bind_address` (see [`crate::config`]).
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tonic::{metadata::MetadataValue, transport::Server, Code, Request, Response, Status};
+use tracing::{info, instrument};
+
+use crate::{
+    abuse::AbuseDetector,
+    api_error::ErrorCode,
+    context_policy::ContextPolicy,
+    database::{Database, RevokedProof, StoredMessage},
+    event_stream::EventStream,
+    federation::Federation,
+    limits::RequestLimits,
+    maintenance::MaintenanceMode,
+    quarantine::Quarantine,
+    replay::ReplayGuard,
+    subscriptions::Subscriptions,
+    tenancy::{Tenancy, TenantScope},
+    timestamping::TimestampAuthority,
+    webhooks::WebhookDispatcher,
+    AppError, Message, PqcProof,
+};
+
+/// Generated protobuf types and service definitions
+pub mod proto {
+    tonic::include_proto!("proofmessenger.relay.v1");
+}
+
+use proto::{
+    message_query_server::{MessageQuery, MessageQueryServer},
+    relay_server::{Relay, RelayServer},
+    revocation_server::{Revocation, RevocationServer},
+};
+
+/// Metadata key carrying the relay's error code on failed calls
+pub const ERROR_CODE_METADATA: &str = "x-error-code";
+
+/// Default revocation lifetime, matching the HTTP API
+const DEFAULT_REVOCATION_TTL_HOURS: i64 = 24;
+
+/// Shared state of the gRPC services
+///
+/// Optional features mirror the extensions layered onto the HTTP router;
+/// leave them `None` to disable the feature for gRPC callers too.
+#[derive(Clone)]
+pub struct GrpcState {
+    pub db: Arc<Database>,
+    pub federation: Option<Arc<Federation>>,
+    pub webhooks: Option<Arc<WebhookDispatcher>>,
+    pub events: Option<Arc<EventStream>>,
+    pub replay: Option<Arc<ReplayGuard>>,
+    pub subscriptions: Option<Arc<Subscriptions>>,
+    pub quarantine: Option<Arc<Quarantine>>,
+    pub abuse: Option<Arc<AbuseDetector>>,
+    pub limits: Arc<RequestLimits>,
+    pub context_policy: Option<Arc<ContextPolicy>>,
+    pub tenancy: Option<Arc<Tenancy>>,
+    pub timestamping: Option<Arc<TimestampAuthority>>,
+    pub maintenance: Arc<MaintenanceMode>,
+}
+
+impl GrpcState {
+    /// State with only a database and the default size limits
+    pub fn new(db: Arc<Database>) -> Self {
+        Self {
+            db,
+            federation: None,
+            webhooks: None,
+            events: None,
+            replay: None,
+            subscriptions: None,
+            quarantine: None,
+            abuse: None,
+            limits: Arc::new(RequestLimits::default()),
+            context_policy: None,
+            tenancy: None,
+            timestamping: None,
+            maintenance: Arc::default(),
+        }
+    }
+
+    /// Scope of the tenant named by a call's metadata
+    ///
+    /// gRPC calls carry no bearer token, so they can only name a tenant by
+    /// a trusted proxy's header; other calls act for the default tenant.
+    fn tenant<T>(&self, request: &Request<T>) -> Result<TenantScope, AppError> {
+        match &self.tenancy {
+            Some(tenancy) => {
+                let requested = tenancy
+                    .header()
+                    .and_then(|header| request.metadata().get(header.as_str()))
+                    .map(|value| value.to_str().map_err(|_| AppError::UnknownTenant("<non-ASCII>".to_string())))
+                    .transpose()?;
+                tenancy.resolve(tenancy.requested(requested, None)?)
+            }
+            None => Ok(TenantScope::default()),
+        }
+    }
+}
+
+/// Serve the gRPC services on `addr` until the process exits
+pub async fn serve(addr: SocketAddr, state: GrpcState) -> Result<(), tonic::transport::Error> {
+    info!("📨 gRPC server listening on {}", addr);
+    router(state).serve(addr).await
+}
+
+/// Build a server router with every gRPC service registered
+pub fn router(state: GrpcState) -> tonic::transport::server::Router {
+    let max_message_bytes = state.limits.max_body_bytes;
+    Server::builder()
+        .add_service(RelayServer::new(state.clone()).max_decoding_message_size(max_message_bytes))
+        .add_service(MessageQueryServer::new(state.clone()).max_decoding_message_size(max_message_bytes))
+        .add_service(RevocationServer::new(state).max_decoding_message_size(max_message_bytes))
+}
+
+impl From<AppError> for Status {
+    fn from(error: AppError) -> Self {
+        let code = error.code();
+        let mut status = Status::new(grpc_code(code, error.status()), error.to_string());
+        if let Ok(value) = serde_json::to_value(code) {
+            if let Some(value) = value.as_str().and_then(|value| MetadataValue::try_from(value).ok()) {
+                status.metadata_mut().insert(ERROR_CODE_METADATA, value);
+            }
+        }
+        status
+    }
+}
+
+/// gRPC status code for a relay error
+fn grpc_code(code: ErrorCode, status: StatusCode) -> Code {
+    match (code, status) {
+        (ErrorCode::MessageNotFound | ErrorCode::MessageExpired | ErrorCode::InviteNotFound | ErrorCode::WebhookNotFound, _) => Code::NotFound,
+        (ErrorCode::ProofAlreadyRevoked, _) => Code::AlreadyExists,
+        (_, StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY) => Code::InvalidArgument,
+        (_, StatusCode::UNAUTHORIZED) => Code::Unauthenticated,
+        (_, StatusCode::FORBIDDEN) => Code::PermissionDenied,
+        (_, StatusCode::NOT_FOUND) => Code::NotFound,
+        (_, StatusCode::CONFLICT) => Code::FailedPrecondition,
+        (_, StatusCode::PAYLOAD_TOO_LARGE | StatusCode::TOO_MANY_REQUESTS) => Code::ResourceExhausted,
+        (_, StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT) => Code::Unavailable,
+        _ => Code::Internal,
+    }
+}
+
+fn timestamp(time: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: time.timestamp(),
+        nanos: time.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn from_timestamp(field: &str, time: Option<prost_types::Timestamp>) -> Result<Option<DateTime<Utc>>, AppError> {
+    time.map(|time| {
+        u32::try_from(time.nanos)
+            .ok()
+            .and_then(|nanos| DateTime::from_timestamp(time.seconds, nanos))
+            .ok_or_else(|| AppError::InvalidQuery(format!("{} is not a valid timestamp", field)))
+    })
+    .transpose()
+}
+
+impl TryFrom<proto::Message> for Message {
+    type Error = AppError;
+
+    fn try_from(message: proto::Message) -> Result<Self, AppError> {
+        Ok(Self {
+            sender: message.sender,
+            context: message.context,
+            body: message.body,
+            proof: message.proof,
+            pqc: message.pqc.map(|pqc| PqcProof {
+                public_key: pqc.public_key,
+                proof: pqc.proof,
+            }),
+            thread_id: message.thread_id,
+            reply_to: message.reply_to,
+            expires_at: from_timestamp("expires_at", message.expires_at)?,
+            recipient: message.recipient,
+        })
+    }
+}
+
+impl From<StoredMessage> for proto::StoredMessage {
+    fn from(message: StoredMessage) -> Self {
+        let pqc = message.pqc().map(|pqc| proto::PqcProof {
+            public_key: pqc.public_key,
+            proof: pqc.proof,
+        });
+        Self {
+            id: message.id,
+            group_id: message.group_id,
+            sender: message.sender,
+            context: message.context,
+            body: message.body,
+            proof: message.proof,
+            created_at: Some(timestamp(message.created_at)),
+            verified: message.verified,
+            thread_id: message.thread_id,
+            reply_to: message.reply_to,
+            deleted_at: message.deleted_at.map(timestamp),
+            message_hash: message.message_hash,
+            expires_at: message.expires_at.map(timestamp),
+            recipient: message.recipient,
+            pqc,
+        }
+    }
+}
+
+impl From<RevokedProof> for proto::RevokedProof {
+    fn from(revoked: RevokedProof) -> Self {
+        Self {
+            proof_signature: revoked.proof_signature,
+            revoked_at: Some(timestamp(revoked.revoked_at)),
+            reason: revoked.reason,
+            revoked_by: revoked.revoked_by,
+            expires_at: revoked.expires_at.map(timestamp),
+        }
+    }
+}
+
+fn messages_response(messages: Vec<StoredMessage>) -> Response<proto::GetMessagesResponse> {
+    Response::new(proto::GetMessagesResponse {
+        messages: messages.into_iter().map(Into::into).collect(),
+    })
+}
+
+#[tonic::async_trait]
+impl Relay for GrpcState {
+    #[instrument(skip_all)]
+    async fn send_message(
+        &self,
+        request: Request<proto::SendMessageRequest>,
+    ) -> Result<Response<proto::SendMessageResponse>, Status> {
+        info!("Received message for relay over gRPC");
+
+        self.maintenance.ensure_writable()?;
+        let tenant = self.tenant(&request)?;
+        let message = request
+            .into_inner()
+            .message
+            .ok_or_else(|| Status::invalid_argument("message is required"))?;
+        let message_id = crate::relay_message(
+            &self.db,
+            message.try_into()?,
+            self.federation.as_ref(),
+            self.webhooks.as_ref(),
+            self.events.as_ref(),
+            self.replay.as_ref(),
+            self.subscriptions.as_ref(),
+            self.quarantine.as_ref(),
+            self.abuse.as_ref(),
+            Some(&self.limits),
+            self.context_policy.as_ref(),
+            self.timestamping.as_ref(),
+            &tenant,
+        )
+        .await?;
+
+        Ok(Response::new(proto::SendMessageResponse { message_id }))
+    }
+}
+
+#[tonic::async_trait]
+impl MessageQuery for GrpcState {
+    #[instrument(skip_all)]
+    async fn get_messages(
+        &self,
+        request: Request<proto::GetMessagesRequest>,
+    ) -> Result<Response<proto::GetMessagesResponse>, Status> {
+        let tenant = self.tenant(&request)?;
+        let request = request.into_inner();
+        info!("Retrieving messages for group over gRPC: {}", request.group_id);
+
+        let messages = self
+            .db
+            .get_messages_by_group(&tenant.group_id(&request.group_id), request.limit)
+            .await
+            .map_err(AppError::from)?;
+        Ok(messages_response(messages))
+    }
+
+    #[instrument(skip_all)]
+    async fn get_message(
+        &self,
+        request: Request<proto::GetMessageRequest>,
+    ) -> Result<Response<proto::StoredMessage>, Status> {
+        let tenant = self.tenant(&request)?;
+        let message_id = request.into_inner().message_id;
+        info!("Retrieving message over gRPC: {}", message_id);
+
+        let message = crate::get_tenant_message(&self.db, &tenant, &message_id).await?;
+        Ok(Response::new(message.into()))
+    }
+
+    #[instrument(skip_all)]
+    async fn get_messages_by_sender(
+        &self,
+        request: Request<proto::GetMessagesBySenderRequest>,
+    ) -> Result<Response<proto::GetMessagesResponse>, Status> {
+        let tenant = self.tenant(&request)?;
+        let request = request.into_inner();
+        info!("Retrieving messages for sender over gRPC: {}", request.sender);
+
+        crate::validate_sender_key(&request.sender)?;
+        let since = from_timestamp("since", request.since)?;
+        let until = from_timestamp("until", request.until)?;
+        let mut messages = self
+            .db
+            .get_messages_by_sender(&request.sender, since, until, request.limit)
+            .await
+            .map_err(AppError::from)?;
+        messages.retain(|message| tenant.owns_group(&message.group_id));
+        Ok(messages_response(messages))
+    }
+}
+
+#[tonic::async_trait]
+impl Revocation for GrpcState {
+    #[instrument(skip_all)]
+    async fn revoke_proof(
+        &self,
+        request: Request<proto::RevokeProofRequest>,
+    ) -> Result<Response<proto::RevokeProofResponse>, Status> {
+        let request = request.into_inner();
+        info!("Revoking proof over gRPC: {}", request.proof_signature);
+
+        self.maintenance.ensure_writable()?;
+        let ttl_hours = request.ttl_hours.unwrap_or(DEFAULT_REVOCATION_TTL_HOURS);
+        self.db
+            .revoke_proof(&request.proof_signature, request.reason.as_deref(), None, Some(ttl_hours))
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(Response::new(proto::RevokeProofResponse {
+            proof_signature: request.proof_signature,
+            ttl_hours,
+        }))
+    }
+
+    #[instrument(skip_all)]
+    async fn check_revocation(
+        &self,
+        request: Request<proto::CheckRevocationRequest>,
+    ) -> Result<Response<proto::CheckRevocationResponse>, Status> {
+        let signature = request.into_inner().proof_signature;
+        info!("Checking revocation status over gRPC for proof: {}", signature);
+
+        let is_revoked = self.db.is_proof_revoked(&signature).await.map_err(AppError::from)?;
+        Ok(Response::new(proto::CheckRevocationResponse {
+            is_revoked,
+            checked_at: Some(timestamp(Utc::now())),
+        }))
+    }
+
+    #[instrument(skip_all)]
+    async fn list_revocations(
+        &self,
+        _request: Request<proto::ListRevocationsRequest>,
+    ) -> Result<Response<proto::ListRevocationsResponse>, Status> {
+        info!("Listing active revocations over gRPC");
+
+        let revocations = self.db.get_active_revocations().await.map_err(AppError::from)?;
+        Ok(Response::new(proto::ListRevocationsResponse {
+            revocations: revocations.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Signer;
+    use proof_messenger_protocol::key::generate_keypair_with_seed;
+    use proto::{message_query_client::MessageQueryClient, relay_client::RelayClient};
+
+    async fn setup_state() -> GrpcState {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        GrpcState::new(db)
+    }
+
+    fn signed_message(seed: u64, body: &str) -> proto::Message {
+        let keypair = generate_keypair_with_seed(seed);
+        let context = b"grpc-context";
+        proto::Message {
+            sender: hex::encode(keypair.public.to_bytes()),
+            context: hex::encode(context),
+            body: body.to_string(),
+            proof: hex::encode(keypair.sign(context).to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_and_query_over_the_network() {
+        // ARRANGE: The gRPC services on an ephemeral port
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = setup_state().await;
+        tokio::spawn(router(state).serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)));
+        let endpoint = format!("http://{}", addr);
+        let mut relay = RelayClient::connect(endpoint.clone()).await.unwrap();
+        let mut query = MessageQueryClient::connect(endpoint).await.unwrap();
+
+        // ACT: Send a signed message and read the group back
+        let sent = relay
+            .send_message(proto::SendMessageRequest {
+                message: Some(signed_message(1, "hello over grpc")),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        let messages = query
+            .get_messages(proto::GetMessagesRequest {
+                group_id: "default".to_string(),
+                limit: None,
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .messages;
+
+        // ASSERT: The message was verified and stored like an HTTP submission
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].id, sent.message_id);
+        assert_eq!(messages[0].body, "hello over grpc");
+        assert!(messages[0].verified);
+        assert!(messages[0].created_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_proof_maps_to_status_with_error_code() {
+        let state = setup_state().await;
+        let mut message = signed_message(2, "tampered");
+        message.context = hex::encode(b"something else");
+
+        let status = state
+            .send_message(Request::new(proto::SendMessageRequest { message: Some(message) }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), Code::Unauthenticated);
+        assert_eq!(status.metadata().get(ERROR_CODE_METADATA).unwrap(), "VERIFICATION_FAILED");
+    }
+
+    #[tokio::test]
+    async fn test_read_only_relay_refuses_writes() {
+        let mut state = setup_state().await;
+        state.maintenance = Arc::new(MaintenanceMode::new(&crate::config::MaintenanceConfig {
+            read_only: true,
+            retry_after_secs: 60,
+        }));
+
+        let status = state
+            .send_message(Request::new(proto::SendMessageRequest { message: Some(signed_message(3, "during migration")) }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), Code::Unavailable);
+        assert_eq!(status.metadata().get(ERROR_CODE_METADATA).unwrap(), "READ_ONLY_MODE");
+    }
+
+    #[tokio::test]
+    async fn test_revocation_service() {
+        let state = setup_state().await;
+        let revoke = || proto::RevokeProofRequest {
+            proof_signature: "ab".repeat(64),
+            reason: Some("compromised".to_string()),
+            ttl_hours: None,
+        };
+
+        let revoked = state.revoke_proof(Request::new(revoke())).await.unwrap().into_inner();
+        let again = state.revoke_proof(Request::new(revoke())).await.unwrap_err();
+        let check = state
+            .check_revocation(Request::new(proto::CheckRevocationRequest {
+                proof_signature: "ab".repeat(64),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let list = state
+            .list_revocations(Request::new(proto::ListRevocationsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(revoked.ttl_hours, 24);
+        assert_eq!(again.code(), Code::AlreadyExists);
+        assert!(check.is_revoked);
+        assert_eq!(list.revocations.len(), 1);
+        assert_eq!(list.revocations[0].reason.as_deref(), Some("compromised"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_message_and_bad_sender() {
+        let state = setup_state().await;
+
+        let missing = state
+            .get_message(Request::new(proto::GetMessageRequest {
+                message_id: "nope".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        let bad_sender = state
+            .get_messages_by_sender(Request::new(proto::GetMessagesBySenderRequest {
+                sender: "zz".to_string(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(missing.code(), Code::NotFound);
+        assert_eq!(bad_sender.code(), Code::InvalidArgument);
+    }
+}
@@ -158,8 +158,10 @@ async fn fetch_jwks(okta_domain: &str) -> Result<Jwks, OktaJwtError> {
     // Cache miss or expired, fetch from Okta
     let jwks_url = format!("{}/.well-known/jwks.json", domain_key);
     
-    let client = reqwest::Client::new();
+    let client = crate::egress::client(reqwest::Client::builder())
+        .map_err(|e| OktaJwtError::JwksFetchError(e.to_string()))?;
     let response = client.get(&jwks_url)
+        .map_err(|e| OktaJwtError::JwksFetchError(format!("Failed to fetch JWKS: {}", e)))?
         .send()
         .await
         .map_err(|e| OktaJwtError::JwksFetchError(format!("Failed to fetch JWKS: {}", e)))?;
@@ -342,8 +344,10 @@ mod tests {
         };
         
         // Create a JWT with a random key (not the Okta key)
+        let mut header = Header::default();
+        header.kid = Some("test-key-id".to_string());
         let token = encode(
-            &Header::default(),
+            &header,
             &claims,
             &EncodingKey::from_secret("some_random_secret".as_bytes())
         ).unwrap();
@@ -381,8 +385,10 @@ mod tests {
         };
         
         // Create a JWT with a random key
+        let mut header = Header::default();
+        header.kid = Some("test-key-id".to_string());
         let token = encode(
-            &Header::default(),
+            &header,
             &claims,
             &EncodingKey::from_secret("some_random_secret".as_bytes())
         ).unwrap();
@@ -419,8 +425,10 @@ mod tests {
         };
         
         // Create a JWT with a random key
+        let mut header = Header::default();
+        header.kid = Some("test-key-id".to_string());
         let token = encode(
-            &Header::default(),
+            &header,
             &claims,
             &EncodingKey::from_secret("some_random_secret".as_bytes())
         ).unwrap();
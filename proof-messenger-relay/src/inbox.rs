@@ -0,0 +1,426 @@
+//! Direct Message Inbox Module
+//!
+//! A message whose `recipient` names a public key is a direct message: the
+//! relay verifies and stores it like any other, but leaves it out of group
+//! listings, threads, search, exports, live subscriptions and webhooks, and
+//! serves it only from the recipient's inbox.
+//!
+//! Reading an inbox takes proof of the recipient key. The recipient asks for
+//! a challenge with `POST /inbox/challenge`, signs [`challenge_statement`]
+//! with the key and sends the challenge and signature in the
+//! `X-Inbox-Challenge` and `X-Inbox-Signature` headers of `GET /inbox`.
+//! Challenges expire after [`CHALLENGE_TTL_SECS`] and open the inbox once.
+
+use axum::{
+    extract::{Json, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use chrono::{Duration, Utc};
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, StoredMessage},
+    request_id::RequestId,
+    tenancy::TenantScope,
+    AppError, Message,
+};
+
+/// Header carrying the challenge the recipient signed
+pub const CHALLENGE_HEADER: &str = "x-inbox-challenge";
+/// Header carrying the recipient's signature over the challenge statement (hex encoded)
+pub const SIGNATURE_HEADER: &str = "x-inbox-signature";
+
+/// Domain separation prefix for inbox challenge signatures
+const INBOX_DOMAIN: &[u8] = b"proof-messenger/inbox/v1";
+
+/// How long a challenge can be used to open an inbox
+pub const CHALLENGE_TTL_SECS: i64 = 300;
+
+/// Random bytes in a challenge
+const CHALLENGE_LENGTH: usize = 32;
+
+/// Default and maximum number of messages returned from an inbox
+const DEFAULT_INBOX_LIMIT: i64 = 50;
+const MAX_INBOX_LIMIT: i64 = 500;
+
+/// Errors raised when opening an inbox
+#[derive(Debug, Error)]
+pub enum InboxError {
+    #[error("Inbox challenge failed: {0}")]
+    ChallengeFailed(String),
+}
+
+/// Request body for a challenge to open an inbox
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChallengeRequest {
+    /// Public key of the recipient (hex encoded)
+    pub public_key: String,
+}
+
+/// Query parameters for reading an inbox
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct InboxQuery {
+    /// Maximum number of messages to return (default 50, at most 500)
+    pub limit: Option<i64>,
+}
+
+/// Create router for inbox endpoints
+pub fn inbox_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/inbox/challenge", post(challenge_handler))
+        .route("/inbox", get(inbox_handler))
+}
+
+/// Create router for authenticated inbox endpoints
+pub fn authenticated_inbox_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/inbox/challenge", post(authenticated_challenge_handler))
+        .route("/inbox", get(authenticated_inbox_handler))
+}
+
+/// The statement a recipient signs to open its inbox with `challenge`
+pub fn challenge_statement(public_key: &str, challenge: &str) -> Vec<u8> {
+    let mut bytes = INBOX_DOMAIN.to_vec();
+    bytes.push(0);
+    bytes.extend_from_slice(public_key.to_lowercase().as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(challenge.as_bytes());
+    bytes
+}
+
+/// Parse a hex encoded Ed25519 public key
+fn parse_public_key(public_key: &str) -> Result<PublicKey, AppError> {
+    let bytes = hex::decode(public_key)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid hex encoding: {}", e)))?;
+    PublicKey::from_bytes(&bytes).map_err(|e| AppError::InvalidPublicKey(format!("Invalid public key: {}", e)))
+}
+
+/// Check the recipient of a submitted direct message
+pub fn validate_message(message: &Message) -> Result<(), AppError> {
+    match &message.recipient {
+        Some(recipient) => parse_public_key(recipient)
+            .map(|_| ())
+            .map_err(|e| AppError::InvalidPublicKey(format!("Invalid recipient: {}", e))),
+        None => Ok(()),
+    }
+}
+
+/// Issue a challenge for `public_key` to sign
+pub async fn issue_challenge(db: &Database, public_key: &str) -> Result<serde_json::Value, AppError> {
+    parse_public_key(public_key)?;
+    let mut bytes = [0u8; CHALLENGE_LENGTH];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let challenge = hex::encode(bytes);
+    let expires_at = Utc::now() + Duration::seconds(CHALLENGE_TTL_SECS);
+    db.create_inbox_challenge(&challenge, public_key, expires_at).await?;
+
+    Ok(serde_json::json!({
+        "challenge": challenge,
+        "public_key": public_key.to_lowercase(),
+        "expires_at": expires_at
+    }))
+}
+
+/// Redeem the signed challenge in a request's headers, returning the recipient key it proves
+pub async fn verify_challenge(db: &Database, headers: &HeaderMap) -> Result<String, AppError> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| InboxError::ChallengeFailed(format!("missing {} header", name)))
+    };
+    let challenge = header(CHALLENGE_HEADER)?;
+    let signature = header(SIGNATURE_HEADER)?;
+
+    let public_key = db
+        .take_inbox_challenge(challenge)
+        .await?
+        .ok_or_else(|| InboxError::ChallengeFailed("unknown, used or expired challenge".to_string()))?;
+    let signature = hex::decode(signature)
+        .ok()
+        .and_then(|bytes| Signature::from_bytes(&bytes).ok())
+        .ok_or_else(|| InboxError::ChallengeFailed("malformed signature".to_string()))?;
+    parse_public_key(&public_key)?
+        .verify(&challenge_statement(&public_key, challenge), &signature)
+        .map_err(|_| InboxError::ChallengeFailed("signature does not verify".to_string()))?;
+
+    Ok(public_key)
+}
+
+/// Read the direct messages sent to `recipient` in a tenant's groups, newest first
+pub async fn read_inbox(db: &Database, tenant: &TenantScope, recipient: &str, query: &InboxQuery) -> Result<Vec<StoredMessage>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_INBOX_LIMIT);
+    if !(1..=MAX_INBOX_LIMIT).contains(&limit) {
+        return Err(AppError::InvalidQuery(format!("limit must be between 1 and {}", MAX_INBOX_LIMIT)));
+    }
+    let mut messages = db.get_inbox_messages(recipient, limit).await?;
+    messages.retain(|message| tenant.owns_group(&message.group_id));
+    Ok(messages)
+}
+
+/// Handler to issue a challenge for opening an inbox
+#[utoipa::path(
+    post,
+    path = "/inbox/challenge",
+    operation_id = "createInboxChallenge",
+    tag = "inbox",
+    request_body = ChallengeRequest,
+    responses(
+        (status = 201, description = "Challenge for the recipient key to sign", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn challenge_handler(
+    State(db): State<Arc<Database>>,
+    Json(payload): Json<ChallengeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Issuing inbox challenge");
+
+    let challenge = issue_challenge(&db, &payload.public_key).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "challenge": challenge
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Handler to read the direct messages for a signed challenge's recipient
+#[utoipa::path(
+    get,
+    path = "/inbox",
+    operation_id = "readInbox",
+    tag = "inbox",
+    params(
+        InboxQuery,
+        ("X-Inbox-Challenge" = String, Header, description = "Challenge issued by POST /inbox/challenge"),
+        ("X-Inbox-Signature" = String, Header, description = "Recipient's signature over the challenge statement (hex encoded)"),
+    ),
+    responses(
+        (status = 200, description = "Direct messages to the recipient, newest first", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn inbox_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Query(params): Query<InboxQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let recipient = verify_challenge(&db, &headers).await?;
+    info!("Reading inbox of {}", recipient);
+
+    let messages = read_inbox(&db, &tenant, &recipient, &params).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "recipient": recipient,
+        "count": messages.len(),
+        "messages": messages
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to issue a challenge for opening an inbox
+#[instrument(skip_all)]
+async fn authenticated_challenge_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    Json(payload): Json<ChallengeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Issuing inbox challenge for authenticated user {}", auth.user_id);
+
+    let challenge = issue_challenge(&db, &payload.public_key).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "challenge": challenge,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Authenticated handler to read the direct messages for a signed challenge's recipient
+#[instrument(skip_all)]
+async fn authenticated_inbox_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    tenant: TenantScope,
+    Query(params): Query<InboxQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let recipient = verify_challenge(&db, &headers).await?;
+    info!("Authenticated user {} reading inbox of {}", auth.user_id, recipient);
+
+    let messages = read_inbox(&db, &tenant, &recipient, &params).await?;
+
+    // Log the inbox read
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("recipient".to_string(), recipient.clone());
+    metadata.insert("count".to_string(), messages.len().to_string());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Inbox read".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log inbox read: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "recipient": recipient,
+        "count": messages.len(),
+        "messages": messages,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use ed25519_dalek::Signer;
+    use proof_messenger_protocol::key::generate_keypair_with_seed;
+    use tower::ServiceExt;
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    async fn store(db: &Database, recipient: Option<&str>) -> String {
+        db.store_message(StoredMessage::from(Message {
+            sender: "a".repeat(64),
+            context: "00".to_string(),
+            body: "for your eyes only".to_string(),
+            proof: "00".repeat(64),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: recipient.map(str::to_string),
+        }))
+        .await
+        .unwrap()
+    }
+
+    async fn call(app: &Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    async fn challenge(app: &Router, public_key: &str) -> String {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/inbox/challenge")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "public_key": public_key }).to_string()))
+            .unwrap();
+        let (status, body) = call(app, request).await;
+        assert_eq!(status, StatusCode::CREATED);
+        body["challenge"]["challenge"].as_str().unwrap().to_string()
+    }
+
+    fn inbox_request(challenge: &str, signature: &str) -> Request<Body> {
+        Request::builder()
+            .uri("/inbox")
+            .header(CHALLENGE_HEADER, challenge)
+            .header(SIGNATURE_HEADER, signature)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_only_the_recipient_reads_direct_messages() {
+        // ARRANGE: A direct message to one key, alongside a group message
+        let db = setup_db().await;
+        let recipient = generate_keypair_with_seed(41);
+        let public_key = hex::encode(recipient.public.to_bytes());
+        let direct_id = store(&db, Some(&public_key)).await;
+        let group_id = store(&db, None).await;
+        let app = crate::create_app(db.clone());
+
+        // ACT: The recipient signs a challenge; an impostor signs one for the same key
+        let issued = challenge(&app, &public_key).await;
+        let signature = hex::encode(recipient.sign(&challenge_statement(&public_key, &issued)).to_bytes());
+        let (status, inbox) = call(&app, inbox_request(&issued, &signature)).await;
+        let (replayed, _) = call(&app, inbox_request(&issued, &signature)).await;
+        let stolen = challenge(&app, &public_key).await;
+        let forged = hex::encode(generate_keypair_with_seed(42).sign(&challenge_statement(&public_key, &stolen)).to_bytes());
+        let (impostor, _) = call(&app, inbox_request(&stolen, &forged)).await;
+
+        // ASSERT: Only the signed, unused challenge opens the inbox, which holds only the direct message
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(inbox["recipient"], public_key);
+        assert_eq!(inbox["count"], 1);
+        assert_eq!(inbox["messages"][0]["id"], direct_id);
+        assert_eq!(inbox["messages"][0]["recipient"], public_key);
+        assert_eq!(replayed, StatusCode::UNAUTHORIZED);
+        assert_eq!(impostor, StatusCode::UNAUTHORIZED);
+
+        // ASSERT: The direct message stays out of group reads
+        let listed = db.get_messages_by_group("default", None).await.unwrap();
+        assert_eq!(listed.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), vec![group_id.as_str()]);
+        let by_id = Request::builder().uri(format!("/message/{}", direct_id)).body(Body::empty()).unwrap();
+        assert_ne!(call(&app, by_id).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_expired_challenges_do_not_open_inboxes() {
+        let db = setup_db().await;
+        let recipient = generate_keypair_with_seed(43);
+        let public_key = hex::encode(recipient.public.to_bytes());
+        db.create_inbox_challenge("stale", &public_key, Utc::now() - Duration::seconds(1)).await.unwrap();
+
+        let signature = hex::encode(recipient.sign(&challenge_statement(&public_key, "stale")).to_bytes());
+        let mut headers = HeaderMap::new();
+        headers.insert(CHALLENGE_HEADER, "stale".parse().unwrap());
+        headers.insert(SIGNATURE_HEADER, signature.parse().unwrap());
+
+        let result = verify_challenge(&db, &headers).await;
+        assert!(matches!(result, Err(AppError::Inbox(InboxError::ChallengeFailed(_)))));
+        assert!(verify_challenge(&db, &HeaderMap::new()).await.is_err());
+    }
+
+    #[test]
+    fn test_recipients_must_be_public_keys() {
+        let mut message = Message {
+            sender: "a".repeat(64),
+            context: "00".to_string(),
+            body: "hello".to_string(),
+            proof: "00".repeat(64),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: Some("not-a-key".to_string()),
+        };
+        assert!(matches!(validate_message(&message), Err(AppError::InvalidPublicKey(_))));
+
+        message.recipient = Some(hex::encode(generate_keypair_with_seed(44).public.to_bytes()));
+        assert!(validate_message(&message).is_ok());
+    }
+}
@@ -0,0 +1,491 @@
+//! Stored Message Integrity Module
+//!
+//! A message is only stored once its proof verifies, so a stored proof that
+//! stops verifying means the row was corrupted or tampered with after it was
+//! accepted. With integrity verification enabled, the relay re-runs signature
+//! verification over every stored message every `integrity.interval_secs`,
+//! reading `integrity.batch_size` messages at a time:
+//!
+//! - a message whose proof no longer verifies against its `sender` and
+//!   `context` is flagged in `message_integrity_failures`, logged as a
+//!   critical security event and recorded in the compliance audit trail
+//! - a flagged message that verifies again, for example after a restore, is
+//!   no longer flagged
+//! - deleted messages are skipped, as only their tombstone remains
+//!
+//! Messages are checked against the proof they were accepted with: neither
+//! revocations nor the current proof policy apply, and only the Ed25519 part
+//! of a hybrid proof is stored. Proofs cover the context, not the body; the
+//! transparency log (see [`crate::transparency`]) commits to bodies.
+//!
+//! Administrators see the last pass and the flagged messages with
+//! `GET /admin/integrity` and run a pass on demand with
+//! `POST /admin/integrity/verify`. Passes are counted in the metrics registry.
+//!
+//! Verification is enabled by the `integrity.enabled` relay setting or
+//! `INTEGRITY_CHECK_ENABLED=true` (see [`crate::config::RelayConfig`]) and
+//! layering the resulting [`IntegrityVerifier`] onto the router as an
+//! [`axum::Extension`].
+
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Router,
+};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{PublicKey, Signature};
+use proof_messenger_protocol::envelope::ProofEnvelope;
+use proof_messenger_protocol::proof::verify_proof_result;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+
+use crate::auth_middleware::AuthContext;
+use crate::compliance_audit::ComplianceAudit;
+use crate::config::IntegrityConfig;
+use crate::database::{Database, DatabaseError, StoredMessage};
+use crate::metrics::{INTEGRITY_FAILURES_TOTAL, INTEGRITY_FLAGGED_MESSAGES, INTEGRITY_MESSAGES_CHECKED_TOTAL};
+use crate::request_id::RequestId;
+use crate::secure_logger::SecureLogger;
+use crate::AppError;
+
+/// Errors from integrity verification endpoints
+#[derive(Debug, Error)]
+pub enum IntegrityError {
+    #[error("Stored message integrity verification is not enabled on this relay")]
+    Disabled,
+}
+
+/// Outcome of one verification pass over the stored messages
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IntegrityReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Messages whose proof was verified
+    pub checked: u64,
+    /// Deleted messages, which are not verified
+    pub skipped: u64,
+    /// Messages whose proof did not verify in this pass
+    pub failed: u64,
+    /// Of those, messages that were not flagged before
+    pub newly_flagged: u64,
+    /// Flagged messages that verified again and are no longer flagged
+    pub cleared: u64,
+}
+
+/// Check a stored message's proof against its sender and context
+///
+/// Returns why the proof does not verify.
+pub fn verify_stored_message(message: &StoredMessage) -> Result<(), String> {
+    let sender = hex::decode(&message.sender).map_err(|e| format!("sender is not hex encoded: {}", e))?;
+    let public_key = PublicKey::from_bytes(&sender).map_err(|e| format!("sender is not a public key: {}", e))?;
+    let context = hex::decode(&message.context).map_err(|e| format!("context is not hex encoded: {}", e))?;
+    let proof = hex::decode(&message.proof).map_err(|e| format!("proof is not hex encoded: {}", e))?;
+
+    if ProofEnvelope::is_envelope(&proof) {
+        let envelope = ProofEnvelope::from_bytes(&proof).map_err(|e| format!("proof envelope is malformed: {}", e))?;
+        let signer = envelope.ed25519_public_key().map_err(|e| format!("proof envelope key is invalid: {}", e))?;
+        if signer != public_key {
+            return Err("proof envelope was not signed by the sender".to_string());
+        }
+        return envelope.verify(&context).map_err(|e| format!("proof does not verify: {}", e));
+    }
+    let signature = Signature::from_bytes(&proof).map_err(|e| format!("proof is not a signature: {}", e))?;
+    verify_proof_result(&public_key, &context, &signature).map_err(|e| format!("proof does not verify: {}", e))
+}
+
+/// Re-verifies stored messages and flags those whose proof no longer verifies
+pub struct IntegrityVerifier {
+    config: IntegrityConfig,
+    logger: Arc<SecureLogger>,
+    audit: Option<Arc<ComplianceAudit>>,
+    /// Held for the duration of a pass, so passes never overlap
+    pass: tokio::sync::Mutex<()>,
+    last_report: Mutex<Option<IntegrityReport>>,
+}
+
+impl IntegrityVerifier {
+    /// Verifier reporting failures through `logger`
+    pub fn new(config: &IntegrityConfig, logger: Arc<SecureLogger>) -> Self {
+        Self {
+            config: config.clone(),
+            logger,
+            audit: None,
+            pass: tokio::sync::Mutex::new(()),
+            last_report: Mutex::new(None),
+        }
+    }
+
+    /// Record passes and failures in the compliance audit trail
+    pub fn with_audit(mut self, audit: Arc<ComplianceAudit>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Report of the last completed pass
+    pub fn last_report(&self) -> Option<IntegrityReport> {
+        self.last_report.lock().unwrap().clone()
+    }
+
+    /// Verify every stored message, waiting for a pass already running to finish first
+    pub async fn verify_all(&self, db: &Database) -> Result<IntegrityReport, DatabaseError> {
+        let _pass = self.pass.lock().await;
+        let mut report = IntegrityReport {
+            started_at: Utc::now(),
+            finished_at: Utc::now(),
+            checked: 0,
+            skipped: 0,
+            failed: 0,
+            newly_flagged: 0,
+            cleared: 0,
+        };
+
+        let mut after: Option<(DateTime<Utc>, String)> = None;
+        loop {
+            let position = after.as_ref().map(|(created_at, id)| (*created_at, id.as_str()));
+            let batch = db.get_messages_after(position, self.config.batch_size).await?;
+            let Some(last) = batch.last() else {
+                break;
+            };
+            after = Some((last.created_at, last.id.clone()));
+
+            for message in &batch {
+                if message.is_tombstone() {
+                    report.skipped += 1;
+                    report.cleared += u64::from(db.clear_integrity_failure(&message.id).await?);
+                    continue;
+                }
+                report.checked += 1;
+                INTEGRITY_MESSAGES_CHECKED_TOTAL.inc();
+                match verify_stored_message(message) {
+                    Ok(()) => report.cleared += u64::from(db.clear_integrity_failure(&message.id).await?),
+                    Err(reason) => {
+                        report.failed += 1;
+                        if db.flag_integrity_failure(message, &reason).await? {
+                            report.newly_flagged += 1;
+                            self.report_failure(message, &reason);
+                        }
+                    }
+                }
+            }
+        }
+
+        report.finished_at = Utc::now();
+        INTEGRITY_FLAGGED_MESSAGES.set(db.get_integrity_failures().await?.len() as i64);
+        self.report_pass(&report);
+        *self.last_report.lock().unwrap() = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Log a newly detected failure as a critical security event and audit entry
+    fn report_failure(&self, message: &StoredMessage, reason: &str) {
+        INTEGRITY_FAILURES_TOTAL.inc();
+        let mut metadata = HashMap::new();
+        metadata.insert("message_id".to_string(), message.id.clone());
+        metadata.insert("group_id".to_string(), message.group_id.clone());
+        metadata.insert("sender".to_string(), message.sender.clone());
+        metadata.insert("reason".to_string(), reason.to_string());
+        if let Err(e) = self.logger.critical_security_event(
+            format!("Stored message {} failed integrity verification", message.id),
+            None,
+            None,
+            metadata,
+        ) {
+            warn!("Failed to log integrity failure: {}", e);
+        }
+
+        if let Some(audit) = &self.audit {
+            let details = HashMap::from([
+                ("message_id".to_string(), Value::String(message.id.clone())),
+                ("reason".to_string(), Value::String(reason.to_string())),
+            ]);
+            audit.record(|log| log.log_compliance_check("message_integrity", "stored_proof", false, details));
+        }
+    }
+
+    /// Log a completed pass
+    fn report_pass(&self, report: &IntegrityReport) {
+        info!(
+            "Integrity verification checked {} messages ({} deleted skipped): {} failed, {} newly flagged, {} cleared",
+            report.checked, report.skipped, report.failed, report.newly_flagged, report.cleared
+        );
+        if let Some(audit) = &self.audit {
+            let details = HashMap::from([
+                ("checked".to_string(), Value::from(report.checked)),
+                ("failed".to_string(), Value::from(report.failed)),
+            ]);
+            audit.record(|log| log.log_compliance_check("message_integrity", "verification_pass", report.failed == 0, details));
+        }
+    }
+
+    /// Run a pass periodically in the background
+    pub fn spawn(self: Arc<Self>, db: Arc<Database>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let period = std::time::Duration::from_secs(self.config.interval_secs);
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                crate::readiness::BACKGROUND_JOBS.heartbeat("message_integrity", period);
+                if let Err(e) = self.verify_all(&db).await {
+                    warn!("Failed to verify stored messages: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Create router for integrity verification endpoints
+pub fn integrity_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/", get(integrity_status_handler))
+        .route("/verify", post(verify_handler))
+}
+
+/// Create router for authenticated integrity verification endpoints
+pub fn authenticated_integrity_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)> {
+    Router::new()
+        .route("/", get(authenticated_integrity_status_handler))
+        .route("/verify", post(authenticated_verify_handler))
+}
+
+/// Handler to show the last verification pass and the flagged messages
+#[utoipa::path(
+    get,
+    path = "/admin/integrity",
+    operation_id = "getIntegrityStatus",
+    tag = "integrity",
+    responses(
+        (status = 200, description = "Last verification pass and flagged messages", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn integrity_status_handler(
+    State(db): State<Arc<Database>>,
+    verifier: Option<Extension<Arc<IntegrityVerifier>>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Reading stored message integrity status");
+
+    let Extension(verifier) = verifier.ok_or(IntegrityError::Disabled)?;
+    let failures = db.get_integrity_failures().await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "last_pass": verifier.last_report(),
+        "flagged_count": failures.len(),
+        "flagged_messages": failures
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler to verify every stored message now
+#[utoipa::path(
+    post,
+    path = "/admin/integrity/verify",
+    operation_id = "verifyStoredMessages",
+    tag = "integrity",
+    responses(
+        (status = 200, description = "Report of the verification pass", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn verify_handler(
+    State(db): State<Arc<Database>>,
+    verifier: Option<Extension<Arc<IntegrityVerifier>>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Verifying stored messages on request");
+
+    let Extension(verifier) = verifier.ok_or(IntegrityError::Disabled)?;
+    let report = verifier.verify_all(&db).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "report": report
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to show the last verification pass and the flagged messages
+#[instrument(skip_all)]
+async fn authenticated_integrity_status_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    verifier: Option<Extension<Arc<IntegrityVerifier>>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} reading stored message integrity status", auth.user_id);
+
+    let Extension(verifier) = verifier.ok_or(IntegrityError::Disabled)?;
+    let failures = db.get_integrity_failures().await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "last_pass": verifier.last_report(),
+        "flagged_count": failures.len(),
+        "flagged_messages": failures,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to verify every stored message now
+#[instrument(skip_all)]
+async fn authenticated_verify_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    verifier: Option<Extension<Arc<IntegrityVerifier>>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} verifying stored messages", auth.user_id);
+
+    let Extension(verifier) = verifier.ok_or(IntegrityError::Disabled)?;
+    let report = verifier.verify_all(&db).await?;
+
+    // Log who ran the pass and what it found
+    let mut metadata = HashMap::new();
+    metadata.insert("checked".to_string(), report.checked.to_string());
+    metadata.insert("failed".to_string(), report.failed.to_string());
+    if let Err(e) = secure_logger.audit_log(
+        "Stored message integrity verified".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log integrity verification: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "report": report,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_app;
+    use axum::body::Body;
+    use axum::http::Request;
+    use ed25519_dalek::Signer;
+    use proof_messenger_protocol::key::generate_keypair_with_seed;
+    use tower::ServiceExt;
+
+    fn verifier() -> Arc<IntegrityVerifier> {
+        let config = IntegrityConfig {
+            enabled: true,
+            batch_size: 2,
+            ..IntegrityConfig::default()
+        };
+        Arc::new(IntegrityVerifier::new(&config, Arc::new(SecureLogger::new(&SecureLogger::generate_key()))))
+    }
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    async fn call(app: &Router, method: &str, uri: &str) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    /// Store `count` signed messages, returning their IDs
+    async fn store_messages(db: &Database, count: u8) -> Vec<String> {
+        let keypair = generate_keypair_with_seed(21);
+        let mut ids = Vec::new();
+        for i in 0..count {
+            let context = [i];
+            let message = crate::Message {
+                sender: hex::encode(keypair.public.to_bytes()),
+                context: hex::encode(context),
+                body: format!("message {}", i),
+                proof: hex::encode(keypair.sign(&context).to_bytes()),
+                pqc: None,
+                thread_id: None,
+                reply_to: None,
+                expires_at: None,
+                recipient: None,
+            };
+            ids.push(db.store_message(StoredMessage::from(message)).await.unwrap());
+        }
+        ids
+    }
+
+    /// Overwrite a column of a stored message, as corruption or tampering would
+    async fn tamper(db: &Database, id: &str, column: &str, value: String) {
+        sqlx::query(&format!("UPDATE messages SET {} = ?1 WHERE id = ?2", column))
+            .bind(value)
+            .bind(id)
+            .execute(db.pool())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tampered_messages_are_flagged_until_they_verify_again() {
+        // ARRANGE: Five stored messages, one of them tampered with in storage
+        let db = setup_db().await;
+        let ids = store_messages(&db, 5).await;
+        tamper(&db, &ids[3], "context", hex::encode(b"forged")).await;
+        let verifier = verifier();
+
+        // ACT: Verify twice, then repair the row and verify again
+        let first = verifier.verify_all(&db).await.unwrap();
+        let second = verifier.verify_all(&db).await.unwrap();
+        let flagged = db.get_integrity_failures().await.unwrap();
+        tamper(&db, &ids[3], "context", hex::encode([3u8])).await;
+        let repaired = verifier.verify_all(&db).await.unwrap();
+
+        // ASSERT: Every batch was read and only the tampered message flagged, once
+        assert_eq!((first.checked, first.failed, first.newly_flagged), (5, 1, 1));
+        assert_eq!((second.failed, second.newly_flagged), (1, 0));
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].message_id, ids[3]);
+        assert!(flagged[0].reason.starts_with("proof does not verify"));
+        assert_eq!((repaired.failed, repaired.cleared), (0, 1));
+        assert!(db.get_integrity_failures().await.unwrap().is_empty());
+        assert_eq!(verifier.last_report(), Some(repaired));
+    }
+
+    #[tokio::test]
+    async fn test_verification_can_be_triggered_by_an_administrator() {
+        let db = setup_db().await;
+        let ids = store_messages(&db, 2).await;
+        tamper(&db, &ids[0], "proof", "00".repeat(64)).await;
+        let app = create_app(db.clone()).layer(Extension(verifier()));
+
+        let (status, body) = call(&app, "POST", "/admin/integrity/verify").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["report"]["checked"], 2);
+        assert_eq!(body["report"]["failed"], 1);
+
+        let (status, body) = call(&app, "GET", "/admin/integrity").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["flagged_count"], 1);
+        assert_eq!(body["flagged_messages"][0]["message_id"], ids[0]);
+        assert_eq!(body["last_pass"]["failed"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_are_not_found_when_disabled() {
+        let app = create_app(setup_db().await);
+
+        let (status, body) = call(&app, "POST", "/admin/integrity/verify").await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "INTEGRITY_CHECK_DISABLED");
+    }
+}
@@ -0,0 +1,392 @@
+//! Invite Lifecycle Module
+//!
+//! This module exposes the single-use invite flow over HTTP: minting invite
+//! codes bound to a group, checking their status, and redeeming them with an
+//! onboarding proof signed over the protocol's redemption context.
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use ed25519_dalek::{PublicKey, Signature};
+use proof_messenger_protocol::invite::{redemption_context, validate_invite_code, InviteStatus};
+use proof_messenger_protocol::proof::verify_proof_result;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::sync::Arc;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, DatabaseError, StoredInvite},
+    request_id::RequestId,
+    AppError,
+};
+
+/// Default invite lifetime when the request does not specify one
+const DEFAULT_INVITE_TTL_HOURS: i64 = 24;
+
+/// Request body for minting an invite
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CreateInviteRequest {
+    /// Group the invite grants access to
+    pub group_id: String,
+    /// Optional TTL in hours (default: 24 hours)
+    pub ttl_hours: Option<i64>,
+}
+
+/// Request body for redeeming an invite
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RedeemInviteRequest {
+    /// Public key of the new member (hex encoded)
+    pub sender: String,
+    /// Onboarding proof: signature over the redemption context (hex encoded)
+    pub proof: String,
+}
+
+/// Create router for invite endpoints
+pub fn invite_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/", post(create_invite_handler))
+        .route("/:code", get(get_invite_handler))
+        .route("/:code/redeem", post(redeem_invite_handler))
+}
+
+/// Create router for authenticated invite endpoints
+pub fn authenticated_invite_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/", post(authenticated_create_invite_handler))
+        .route("/:code", get(authenticated_get_invite_handler))
+        .route("/:code/redeem", post(authenticated_redeem_invite_handler))
+}
+
+/// Map invite-specific database errors to their HTTP-facing variants
+fn invite_error(error: DatabaseError) -> AppError {
+    match error {
+        DatabaseError::InviteNotFound(code) => AppError::InviteNotFound(code),
+        DatabaseError::InviteUnavailable(reason) => AppError::InviteUnavailable(reason),
+        other => AppError::DatabaseError(other),
+    }
+}
+
+/// Verify the onboarding proof and atomically consume the invite
+///
+/// The proof is checked before the invite is consumed so a bad proof never
+/// burns a valid invite; the consuming update itself is conditional on the
+/// invite still being pending, so concurrent redemptions cannot both win.
+pub async fn redeem_invite(
+    db: &Database,
+    code: &str,
+    request: &RedeemInviteRequest,
+) -> Result<StoredInvite, AppError> {
+    validate_invite_code(code).map_err(|e| AppError::InviteNotFound(e.to_string()))?;
+
+    let invite = db.get_invite(code).await.map_err(invite_error)?;
+    if invite.status != InviteStatus::Pending.as_str() {
+        return Err(AppError::InviteUnavailable(format!("{} is {}", code, invite.status)));
+    }
+
+    let sender_bytes = hex::decode(&request.sender)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid hex encoding: {}", e)))?;
+    let public_key = PublicKey::from_bytes(&sender_bytes)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid public key: {}", e)))?;
+    let proof_bytes = hex::decode(&request.proof)
+        .map_err(|e| AppError::InvalidSignature(format!("Invalid hex encoding: {}", e)))?;
+    let signature = Signature::from_bytes(&proof_bytes)
+        .map_err(|e| AppError::InvalidSignature(format!("Invalid signature: {}", e)))?;
+
+    let context = redemption_context(&invite.code, &invite.group_id);
+    verify_proof_result(&public_key, &context, &signature)
+        .map_err(|_| AppError::VerificationFailed)?;
+
+    db.redeem_invite(code, &request.sender).await.map_err(invite_error)
+}
+
+/// Handler to mint a new invite
+#[utoipa::path(
+    post,
+    path = "/invites",
+    operation_id = "createInvite",
+    tag = "invites",
+    request_body = CreateInviteRequest,
+    responses(
+        (status = 201, description = "Invite created", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn create_invite_handler(
+    State(db): State<Arc<Database>>,
+    Json(payload): Json<CreateInviteRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Minting invite for group: {}", payload.group_id);
+
+    let ttl_hours = payload.ttl_hours.unwrap_or(DEFAULT_INVITE_TTL_HOURS);
+    let invite = db.create_invite(&payload.group_id, ttl_hours, None).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "invite": invite
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Handler to check the status of an invite
+#[utoipa::path(
+    get,
+    path = "/invites/{code}",
+    operation_id = "getInvite",
+    tag = "invites",
+    params(("code" = String, Path, description = "Invite code")),
+    responses(
+        (status = 200, description = "The invite", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_invite_handler(
+    State(db): State<Arc<Database>>,
+    Path(code): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Checking invite status");
+
+    db.expire_invites().await?;
+    let invite = db.get_invite(&code).await.map_err(invite_error)?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "invite": invite
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler to redeem an invite with an onboarding proof
+#[utoipa::path(
+    post,
+    path = "/invites/{code}/redeem",
+    operation_id = "redeemInvite",
+    tag = "invites",
+    params(("code" = String, Path, description = "Invite code")),
+    request_body = RedeemInviteRequest,
+    responses(
+        (status = 201, description = "Invite redeemed", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn redeem_invite_handler(
+    State(db): State<Arc<Database>>,
+    Path(code): Path<String>,
+    Json(payload): Json<RedeemInviteRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Redeeming invite");
+
+    let invite = redeem_invite(&db, &code, &payload).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": "Invite redeemed successfully",
+        "group_id": invite.group_id,
+        "member": invite.redeemed_by
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to mint a new invite
+#[instrument(skip_all)]
+async fn authenticated_create_invite_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    Json(payload): Json<CreateInviteRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} minting invite for group: {}", auth.user_id, payload.group_id);
+
+    let ttl_hours = payload.ttl_hours.unwrap_or(DEFAULT_INVITE_TTL_HOURS);
+    let invite = db.create_invite(&payload.group_id, ttl_hours, Some(&auth.user_id)).await?;
+
+    // Log the invite creation
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("group_id".to_string(), invite.group_id.clone());
+    metadata.insert("ttl_hours".to_string(), ttl_hours.to_string());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Invite created".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log invite creation: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "invite": invite,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Authenticated handler to check the status of an invite
+#[instrument(skip_all)]
+async fn authenticated_get_invite_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    Path(code): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} checking invite status", auth.user_id);
+
+    db.expire_invites().await?;
+    let invite = db.get_invite(&code).await.map_err(invite_error)?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "invite": invite,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to redeem an invite with an onboarding proof
+///
+/// Any authenticated user may redeem; possession of the code and a valid
+/// onboarding proof is what grants membership.
+#[instrument(skip_all)]
+async fn authenticated_redeem_invite_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    Path(code): Path<String>,
+    Json(payload): Json<RedeemInviteRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} redeeming invite", auth.user_id);
+
+    let invite = redeem_invite(&db, &code, &payload).await?;
+
+    // Log the redemption
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("group_id".to_string(), invite.group_id.clone());
+    metadata.insert("member".to_string(), payload.sender.clone());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Invite redeemed".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log invite redemption: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": "Invite redeemed successfully",
+        "group_id": invite.group_id,
+        "member": invite.redeemed_by,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use hyper::Method;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use tower::ServiceExt;
+
+    async fn setup_test_app() -> (Router, Arc<Database>) {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+
+        let app = Router::new().merge(invite_routes()).with_state(db.clone());
+        (app, db)
+    }
+
+    fn redeem_request(seed: u64, code: &str, group_id: &str) -> RedeemInviteRequest {
+        let keypair = generate_secure_keypair_with_seed(seed);
+        let signature = keypair.sign(&redemption_context(code, group_id));
+        RedeemInviteRequest {
+            sender: hex::encode(keypair.public_key_bytes()),
+            proof: hex::encode(signature.to_bytes()),
+        }
+    }
+
+    async fn post_redeem(app: &Router, code: &str, request: &RedeemInviteRequest) -> StatusCode {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(format!("/{}/redeem", code))
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(serde_json::to_string(request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_mint_and_redeem_invite() {
+        // ARRANGE: Mint an invite over HTTP
+        let (app, db) = setup_test_app().await;
+        let create_request = CreateInviteRequest {
+            group_id: "group1".to_string(),
+            ttl_hours: Some(1),
+        };
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(serde_json::to_string(&create_request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let code = json["invite"]["code"].as_str().unwrap().to_string();
+
+        // ACT: Redeem it twice with valid onboarding proofs
+        let first = post_redeem(&app, &code, &redeem_request(1, &code, "group1")).await;
+        let second = post_redeem(&app, &code, &redeem_request(2, &code, "group1")).await;
+
+        // ASSERT: The invite is consumed by the first redemption only
+        assert_eq!(first, StatusCode::OK);
+        assert_eq!(second, StatusCode::CONFLICT);
+        assert_eq!(db.get_invite(&code).await.unwrap().status, "redeemed");
+    }
+
+    #[tokio::test]
+    async fn test_proof_for_other_group_does_not_consume_invite() {
+        let (app, db) = setup_test_app().await;
+        let invite = db.create_invite("group1", 1, None).await.unwrap();
+
+        let status = post_redeem(&app, &invite.code, &redeem_request(1, &invite.code, "group2")).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(db.get_invite(&invite.code).await.unwrap().status, "pending");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_invite_returns_not_found() {
+        let (app, _) = setup_test_app().await;
+        let code = "00".repeat(16);
+
+        let status = post_redeem(&app, &code, &redeem_request(1, &code, "group1")).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
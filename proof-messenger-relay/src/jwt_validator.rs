@@ -1,8 +1,16 @@
-use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation, TokenData};
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet, PublicKeyUse};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation, TokenData};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
+use crate::config::{IntrospectionConfig, OAuthConfig, OAuthIssuer};
+
 #[derive(Debug, Error)]
 pub enum JwtValidationError {
     #[error("Invalid token format")]
@@ -19,6 +27,14 @@ pub enum JwtValidationError {
     MissingClaim(String),
     #[error("JWT validation error: {0}")]
     ValidationError(#[from] jsonwebtoken::errors::Error),
+    #[error("Token is not active")]
+    Inactive,
+    #[error("Token introspection failed: {0}")]
+    Introspection(String),
+    #[error("No signing key matches the token")]
+    UnknownKey,
+    #[error("Failed to load the issuer's signing keys: {0}")]
+    KeySet(String),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -32,11 +48,52 @@ pub struct Claims {
     pub scope: Option<String>, // OAuth2 scopes
 }
 
-pub struct JwtValidator {
-    public_key: DecodingKey,
-    expected_issuer: String,
-    expected_audience: Option<String>,
+/// A key an issuer signs tokens with
+struct SigningKey {
+    /// The `kid` tokens name this key by; a key without one matches any token
+    key_id: Option<String>,
     algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+/// A trusted token issuer and the keys it signs with
+struct TrustedIssuer {
+    issuer: String,
+    audience: Option<String>,
+    keys: Vec<SigningKey>,
+}
+
+impl TrustedIssuer {
+    /// The key a token's header names, or the issuer's unnamed key
+    fn key_for(&self, kid: Option<&str>) -> Option<&SigningKey> {
+        self.keys
+            .iter()
+            .find(|key| key.key_id.is_some() && key.key_id.as_deref() == kid)
+            .or_else(|| self.keys.iter().find(|key| key.key_id.is_none()))
+    }
+
+    /// Validation parameters for this issuer's claims and one of its keys
+    fn validation(&self, algorithm: Algorithm) -> Validation {
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[&self.issuer]);
+
+        if let Some(ref audience) = self.audience {
+            validation.set_audience(&[audience]);
+        } else {
+            validation.validate_aud = false;
+        }
+        validation
+    }
+}
+
+/// Validates bearer tokens from one or more trusted issuers
+///
+/// A validator built with [`JwtValidator::from_config`] and no issuers
+/// accepts no bearer tokens; the relay then authenticates clients by API
+/// key, session token or client certificate only.
+pub struct JwtValidator {
+    issuers: Vec<TrustedIssuer>,
+    introspector: Option<TokenIntrospector>,
 }
 
 impl JwtValidator {
@@ -49,12 +106,7 @@ impl JwtValidator {
         let public_key = DecodingKey::from_rsa_pem(public_key_pem.as_bytes())
             .map_err(JwtValidationError::ValidationError)?;
 
-        Ok(Self {
-            public_key,
-            expected_issuer,
-            expected_audience,
-            algorithm: Algorithm::RS256,
-        })
+        Ok(Self::single(expected_issuer, expected_audience, Algorithm::RS256, public_key))
     }
 
     /// Create a new JWT validator with HMAC secret (for testing)
@@ -63,14 +115,79 @@ impl JwtValidator {
         expected_issuer: String,
         expected_audience: Option<String>,
     ) -> Self {
+        Self::single(expected_issuer, expected_audience, Algorithm::HS256, DecodingKey::from_secret(secret.as_bytes()))
+    }
+
+    /// Create a validator for the keys an issuer publishes as a JSON Web Key Set
+    ///
+    /// Keys that cannot verify signatures (such as encryption keys) are skipped.
+    pub fn from_jwks(
+        jwks: &JwkSet,
+        expected_issuer: String,
+        expected_audience: Option<String>,
+    ) -> Result<Self, JwtValidationError> {
+        Ok(Self { issuers: vec![trusted_issuer(jwks, expected_issuer, expected_audience)?], introspector: None })
+    }
+
+    /// Create the validator for the relay's `[oauth]` settings
+    ///
+    /// Fetches each issuer's signing keys through the egress policy, so it
+    /// fails rather than returning a validator that cannot check tokens.
+    pub async fn from_config(config: &OAuthConfig) -> Result<Self, JwtValidationError> {
+        let mut issuers = Vec::with_capacity(config.issuers.len());
+        for issuer in &config.issuers {
+            let jwks = fetch_jwks(issuer).await?;
+            issuers.push(trusted_issuer(&jwks, issuer.issuer.clone(), Some(issuer.audience.clone()))?);
+        }
+        let validator = Self { issuers, introspector: None };
+        Ok(match &config.introspection {
+            Some(introspection) => validator.with_introspection(TokenIntrospector::new(introspection)),
+            None => validator,
+        })
+    }
+
+    /// A validator trusting one issuer that signs with one key
+    fn single(issuer: String, audience: Option<String>, algorithm: Algorithm, key: DecodingKey) -> Self {
         Self {
-            public_key: DecodingKey::from_secret(secret.as_bytes()),
-            expected_issuer,
-            expected_audience,
-            algorithm: Algorithm::HS256,
+            issuers: vec![TrustedIssuer { issuer, audience, keys: vec![SigningKey { key_id: None, algorithm, key }] }],
+            introspector: None,
         }
     }
 
+    /// Validate opaque tokens by introspection at the issuer
+    ///
+    /// JWTs are still validated locally with this validator's key.
+    pub fn with_introspection(mut self, introspector: TokenIntrospector) -> Self {
+        self.introspector = Some(introspector);
+        self
+    }
+
+    /// Introspect an opaque token, if introspection is configured
+    ///
+    /// Returns `None` for JWTs and when introspection is not configured; such
+    /// tokens are validated locally instead.
+    pub async fn introspect(&self, token: &str) -> Result<Option<IntrospectedToken>, JwtValidationError> {
+        let Some(introspector) = &self.introspector else {
+            return Ok(None);
+        };
+        if is_jwt(token) {
+            return Ok(None);
+        }
+        let introspected = introspector.introspect(token).await?;
+        let trusted = match introspected.claim("iss") {
+            Some(issuer) => self.issuers.iter().find(|trusted| trusted.issuer == issuer),
+            None => self.issuers.first(),
+        }
+        .ok_or(JwtValidationError::InvalidIssuer)?;
+        if let Some(audience) = &trusted.audience {
+            let audiences = claim_values_of(&introspected.claims, "aud");
+            if !audiences.is_empty() && !audiences.contains(audience) {
+                return Err(JwtValidationError::InvalidAudience);
+            }
+        }
+        Ok(Some(introspected))
+    }
+
     /// Validate a JWT token and extract user ID
     pub fn validate_token(&self, token: &str) -> Result<String, JwtValidationError> {
         let token_data = self.decode_and_validate(token)?;
@@ -83,27 +200,59 @@ impl JwtValidator {
         Ok(token_data.claims)
     }
 
-    /// Internal method to decode and validate JWT
-    fn decode_and_validate(&self, token: &str) -> Result<TokenData<Claims>, JwtValidationError> {
-        // Set up validation parameters
-        let mut validation = Validation::new(self.algorithm);
-        validation.set_issuer(&[&self.expected_issuer]);
-        
-        if let Some(ref audience) = self.expected_audience {
-            validation.set_audience(&[audience]);
-        } else {
-            validation.validate_aud = false;
+    /// Validate a JWT token and return one of its claims as a string
+    ///
+    /// Returns `None` if the claim is absent or not a string.
+    pub fn extract_claim(&self, token: &str, claim: &str) -> Result<Option<String>, JwtValidationError> {
+        self.decode_and_validate(token)?;
+        let token_data = self.decode::<serde_json::Map<String, serde_json::Value>>(token)?;
+        Ok(token_data.claims.get(claim).and_then(|value| value.as_str()).map(str::to_string))
+    }
+
+    /// Validate a JWT token and return the values of a list claim
+    ///
+    /// Accepts an array of strings or a space-separated string, as IdPs differ
+    /// in how they encode group claims. Returns no values if the claim is absent.
+    pub fn extract_claim_values(&self, token: &str, claim: &str) -> Result<Vec<String>, JwtValidationError> {
+        self.decode_and_validate(token)?;
+        let token_data = self.decode::<serde_json::Map<String, serde_json::Value>>(token)?;
+        Ok(claim_values_of(&token_data.claims, claim))
+    }
+
+    /// Decode a token with the key of the issuer it names
+    fn decode<T: DeserializeOwned>(&self, token: &str) -> Result<TokenData<T>, JwtValidationError> {
+        let header = decode_header(token).map_err(map_decode_error)?;
+        let trusted = self.issuer_of(token)?;
+        let key = trusted.key_for(header.kid.as_deref()).ok_or(JwtValidationError::UnknownKey)?;
+        decode::<T>(token, &key.key, &trusted.validation(key.algorithm)).map_err(map_decode_error)
+    }
+
+    /// The trusted issuer a token claims to come from
+    ///
+    /// The claim is read before the signature is checked only to pick the
+    /// keys to check it with; decoding then validates `iss` against that issuer.
+    fn issuer_of(&self, token: &str) -> Result<&TrustedIssuer, JwtValidationError> {
+        if let [trusted] = self.issuers.as_slice() {
+            return Ok(trusted);
         }
+        let mut validation = Validation::default();
+        validation.insecure_disable_signature_validation();
+        validation.validate_exp = false;
+        validation.validate_aud = false;
+        validation.required_spec_claims.clear();
+        let unverified = decode::<serde_json::Map<String, serde_json::Value>>(token, &DecodingKey::from_secret(&[]), &validation)
+            .map_err(map_decode_error)?;
+        let issuer = unverified.claims.get("iss").and_then(|issuer| issuer.as_str());
+        self.issuers
+            .iter()
+            .find(|trusted| Some(trusted.issuer.as_str()) == issuer)
+            .ok_or(JwtValidationError::InvalidIssuer)
+    }
 
+    /// Internal method to decode and validate JWT
+    fn decode_and_validate(&self, token: &str) -> Result<TokenData<Claims>, JwtValidationError> {
         // Decode and validate the token
-        let token_data = decode::<Claims>(token, &self.public_key, &validation)
-            .map_err(|e| match e.kind() {
-                jsonwebtoken::errors::ErrorKind::ExpiredSignature => JwtValidationError::Expired,
-                jsonwebtoken::errors::ErrorKind::InvalidSignature => JwtValidationError::InvalidSignature,
-                jsonwebtoken::errors::ErrorKind::InvalidIssuer => JwtValidationError::InvalidIssuer,
-                jsonwebtoken::errors::ErrorKind::InvalidAudience => JwtValidationError::InvalidAudience,
-                _ => JwtValidationError::ValidationError(e),
-            })?;
+        let token_data = self.decode::<Claims>(token)?;
 
         // Additional validation
         self.validate_required_claims(&token_data.claims)?;
@@ -138,6 +287,209 @@ impl JwtValidator {
     }
 }
 
+/// Fetch the JSON Web Key Set an issuer publishes
+async fn fetch_jwks(issuer: &OAuthIssuer) -> Result<JwkSet, JwtValidationError> {
+    let key_set_error = |e: &dyn std::fmt::Display| JwtValidationError::KeySet(format!("{}: {}", issuer.jwks_url, e));
+    let http = crate::egress::client(reqwest::Client::builder().timeout(Duration::from_secs(10)))
+        .map_err(|e| key_set_error(&e))?;
+    http.get(&issuer.jwks_url)
+        .map_err(|e| key_set_error(&e))?
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| key_set_error(&e))?
+        .json()
+        .await
+        .map_err(|e| key_set_error(&e))
+}
+
+/// The signing keys of a key set, trusted for one issuer
+fn trusted_issuer(jwks: &JwkSet, issuer: String, audience: Option<String>) -> Result<TrustedIssuer, JwtValidationError> {
+    let keys: Vec<SigningKey> = jwks
+        .keys
+        .iter()
+        .filter(|jwk| jwk.common.public_key_use != Some(PublicKeyUse::Encryption))
+        .filter_map(|jwk| {
+            Some(SigningKey {
+                key_id: jwk.common.key_id.clone(),
+                algorithm: signing_algorithm(jwk)?,
+                key: DecodingKey::from_jwk(jwk).ok()?,
+            })
+        })
+        .collect();
+    if keys.is_empty() {
+        return Err(JwtValidationError::KeySet(format!("{} publishes no signing keys", issuer)));
+    }
+    Ok(TrustedIssuer { issuer, audience, keys })
+}
+
+/// The signature algorithm a key is used with: its `alg`, or the usual one for its type
+fn signing_algorithm(jwk: &Jwk) -> Option<Algorithm> {
+    if let Some(algorithm) = jwk.common.key_algorithm {
+        return Algorithm::from_str(&algorithm.to_string()).ok();
+    }
+    match &jwk.algorithm {
+        AlgorithmParameters::RSA(_) => Some(Algorithm::RS256),
+        AlgorithmParameters::EllipticCurve(params) => match params.curve {
+            EllipticCurve::P256 => Some(Algorithm::ES256),
+            EllipticCurve::P384 => Some(Algorithm::ES384),
+            _ => None,
+        },
+        AlgorithmParameters::OctetKeyPair(_) => Some(Algorithm::EdDSA),
+        AlgorithmParameters::OctetKey(_) => None,
+    }
+}
+
+/// Values of a list claim, as an array of strings or a space-separated string
+fn claim_values_of(claims: &serde_json::Map<String, serde_json::Value>, claim: &str) -> Vec<String> {
+    match claims.get(claim) {
+        Some(serde_json::Value::Array(values)) => {
+            values.iter().filter_map(|value| value.as_str()).map(str::to_string).collect()
+        }
+        Some(serde_json::Value::String(values)) => values.split_whitespace().map(str::to_string).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether a token has the three segments of a JWT rather than being opaque
+fn is_jwt(token: &str) -> bool {
+    token.split('.').count() == 3
+}
+
+/// Seconds since the Unix epoch
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Most introspection results kept before expired ones are evicted
+const MAX_CACHED_INTROSPECTIONS: usize = 10_000;
+
+/// An active token as described by the issuer's introspection endpoint
+#[derive(Debug, Clone)]
+pub struct IntrospectedToken {
+    claims: serde_json::Map<String, serde_json::Value>,
+}
+
+impl IntrospectedToken {
+    /// The token's user: `sub`, or `username` or `client_id` for tokens without one
+    pub fn subject(&self) -> Option<String> {
+        ["sub", "username", "client_id"].into_iter().find_map(|claim| self.claim(claim))
+    }
+
+    /// Scopes granted by the token's `scope` member
+    pub fn scopes(&self) -> HashSet<String> {
+        claim_values_of(&self.claims, "scope").into_iter().collect()
+    }
+
+    /// One member of the response as a string
+    pub fn claim(&self, claim: &str) -> Option<String> {
+        self.claims.get(claim).and_then(|value| value.as_str()).map(str::to_string)
+    }
+
+    /// Values of a list member of the response
+    pub fn claim_values(&self, claim: &str) -> Vec<String> {
+        claim_values_of(&self.claims, claim)
+    }
+
+    /// Expiry of the token, if the issuer reported one
+    fn expires_at(&self) -> Option<u64> {
+        self.claims.get("exp").and_then(|value| value.as_u64())
+    }
+}
+
+/// Client for an RFC 7662 token introspection endpoint
+///
+/// Authenticates to the endpoint with HTTP Basic client credentials and
+/// caches active results for a short time, bounded by the token's expiry, so
+/// a burst of requests with one token costs one round trip. Inactive results
+/// are not cached so that newly issued tokens are accepted immediately.
+pub struct TokenIntrospector {
+    endpoint: String,
+    client_id: String,
+    client_secret: String,
+    cache_ttl: Duration,
+    http: crate::egress::HttpClient,
+    cache: Mutex<HashMap<[u8; 32], (Instant, IntrospectedToken)>>,
+}
+
+impl TokenIntrospector {
+    /// Introspection client for the configured endpoint
+    pub fn new(config: &IntrospectionConfig) -> Self {
+        Self {
+            endpoint: config.endpoint.clone(),
+            client_id: config.client_id.clone(),
+            client_secret: config.client_secret.clone(),
+            cache_ttl: Duration::from_secs(config.cache_seconds),
+            http: crate::egress::client(reqwest::Client::builder().timeout(Duration::from_secs(10))).unwrap_or_default(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Ask the issuer whether a token is active
+    pub async fn introspect(&self, token: &str) -> Result<IntrospectedToken, JwtValidationError> {
+        // Tokens are cached by hash so that the cache holds no credentials
+        let key: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+        if let Some((expires, introspected)) = self.cache.lock().unwrap().get(&key) {
+            if *expires > Instant::now() {
+                return Ok(introspected.clone());
+            }
+        }
+
+        let response = self
+            .http
+            .post(&self.endpoint)
+            .map_err(|e| JwtValidationError::Introspection(e.to_string()))?
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&[("token", token), ("token_type_hint", "access_token")])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| JwtValidationError::Introspection(e.to_string()))?;
+        let claims: serde_json::Map<String, serde_json::Value> = response
+            .json()
+            .await
+            .map_err(|e| JwtValidationError::Introspection(e.to_string()))?;
+        let introspected = IntrospectedToken { claims };
+
+        if introspected.claims.get("active").and_then(|active| active.as_bool()) != Some(true) {
+            return Err(JwtValidationError::Inactive);
+        }
+        let now = unix_now();
+        let remaining = match introspected.expires_at() {
+            Some(exp) if exp <= now => return Err(JwtValidationError::Expired),
+            Some(exp) => Duration::from_secs(exp - now),
+            None => self.cache_ttl,
+        };
+        if introspected.subject().is_none() {
+            return Err(JwtValidationError::MissingClaim("sub".to_string()));
+        }
+
+        let ttl = self.cache_ttl.min(remaining);
+        if !ttl.is_zero() {
+            let mut cache = self.cache.lock().unwrap();
+            if cache.len() >= MAX_CACHED_INTROSPECTIONS {
+                let now = Instant::now();
+                cache.retain(|_, (expires, _)| *expires > now);
+            }
+            if cache.len() < MAX_CACHED_INTROSPECTIONS {
+                cache.insert(key, (Instant::now() + ttl, introspected.clone()));
+            }
+        }
+        Ok(introspected)
+    }
+}
+
+/// Map a token decoding failure to a validation error
+fn map_decode_error(e: jsonwebtoken::errors::Error) -> JwtValidationError {
+    match e.kind() {
+        jsonwebtoken::errors::ErrorKind::ExpiredSignature => JwtValidationError::Expired,
+        jsonwebtoken::errors::ErrorKind::InvalidSignature => JwtValidationError::InvalidSignature,
+        jsonwebtoken::errors::ErrorKind::InvalidIssuer => JwtValidationError::InvalidIssuer,
+        jsonwebtoken::errors::ErrorKind::InvalidAudience => JwtValidationError::InvalidAudience,
+        _ => JwtValidationError::ValidationError(e),
+    }
+}
+
 /// Utility function for extracting user ID from Authorization header
 pub fn extract_user_from_bearer_token(
     auth_header: &str,
@@ -369,4 +721,189 @@ MIIEvgIBADANBgkqhkiG9w0BAQEFAASCBKgwggSkAgEAAoIBAQDGGPLuP0qfmENH
         let result = extract_user_from_bearer_token("Invalid token", &validator);
         assert!(matches!(result, Err(JwtValidationError::InvalidFormat)));
     }
-}
\ No newline at end of file
+
+    fn introspecting_validator(endpoint: String) -> JwtValidator {
+        JwtValidator::new_hmac("secret", "https://auth.example.com/".to_string(), Some("proof-messenger-api".to_string()))
+            .with_introspection(TokenIntrospector::new(&IntrospectionConfig {
+                endpoint,
+                client_id: "relay".to_string(),
+                client_secret: "s3cret".to_string(),
+                cache_seconds: 60,
+            }))
+    }
+
+    #[tokio::test]
+    async fn test_opaque_tokens_are_introspected_and_cached() {
+        use wiremock::matchers::{body_string_contains, header, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // ARRANGE: An introspection endpoint describing one active token, expecting one call
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("authorization", "Basic cmVsYXk6czNjcmV0"))
+            .and(body_string_contains("token=opaque-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "active": true,
+                "sub": "alice",
+                "iss": "https://auth.example.com/",
+                "aud": ["proof-messenger-api"],
+                "scope": "proof:create message:read",
+                "groups": ["auditors"],
+                "exp": 9999999999u64,
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let validator = introspecting_validator(server.uri());
+
+        // ACT: Introspect the token twice
+        let first = validator.introspect("opaque-token").await.unwrap().unwrap();
+        let second = validator.introspect("opaque-token").await.unwrap().unwrap();
+
+        // ASSERT: The response describes the caller and the second lookup is cached
+        assert_eq!(first.subject().as_deref(), Some("alice"));
+        assert_eq!(first.scopes(), HashSet::from(["proof:create".to_string(), "message:read".to_string()]));
+        assert_eq!(first.claim_values("groups"), vec!["auditors"]);
+        assert_eq!(second.subject().as_deref(), Some("alice"));
+    }
+
+    #[tokio::test]
+    async fn test_inactive_and_foreign_tokens_are_rejected() {
+        use wiremock::matchers::{body_string_contains, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("token=revoked"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "active": false })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("token=foreign"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "active": true,
+                "sub": "mallory",
+                "aud": "another-api",
+            })))
+            .mount(&server)
+            .await;
+        let validator = introspecting_validator(server.uri());
+
+        assert!(matches!(validator.introspect("revoked").await, Err(JwtValidationError::Inactive)));
+        assert!(matches!(validator.introspect("foreign").await, Err(JwtValidationError::InvalidAudience)));
+        // JWTs are validated locally rather than introspected
+        assert!(validator.introspect("header.payload.signature").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_introspection_endpoint_is_reported() {
+        let validator = introspecting_validator("http://127.0.0.1:9/introspect".to_string());
+
+        let result = validator.introspect("opaque-token").await;
+
+        assert!(matches!(result, Err(JwtValidationError::Introspection(_))));
+    }
+
+    /// An Ed25519 signing key for `seed` and its JWK, as an issuer would publish it
+    fn ed25519_issuer_key(seed: u8, kid: &str) -> (EncodingKey, serde_json::Value) {
+        use base64::Engine;
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        let mut der = hex::decode("302e020100300506032b657004220420").unwrap();
+        der.extend_from_slice(secret.as_bytes());
+        let jwk = serde_json::json!({
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "x": base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(public.as_bytes()),
+            "kid": kid,
+            "use": "sig",
+        });
+        (EncodingKey::from_ed_der(&der), jwk)
+    }
+
+    fn issued_token(issuer: &str, kid: &str, key: &EncodingKey) -> String {
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(kid.to_string());
+        let claims = serde_json::json!({
+            "sub": "alice",
+            "iss": issuer,
+            "aud": "proof-messenger-api",
+            "exp": 9999999999u64,
+            "scope": "message:read",
+        });
+        encode(&header, &claims, key).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_validator_is_built_from_the_oauth_config() {
+        use crate::config::OAuthIssuer;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // ARRANGE: Two issuers publishing their keys, and an introspection endpoint
+        let (first_key, first_jwk) = ed25519_issuer_key(1, "first-2026");
+        let (second_key, second_jwk) = ed25519_issuer_key(2, "second-2026");
+        let server = MockServer::start().await;
+        for (route, jwk) in [("/first/jwks.json", first_jwk), ("/second/jwks.json", second_jwk)] {
+            Mock::given(method("GET"))
+                .and(path(route))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "keys": [jwk] })))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/introspect"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "active": true,
+                "sub": "machine",
+                "iss": "https://second.example.com/",
+            })))
+            .mount(&server)
+            .await;
+        let issuer = |name: &str| OAuthIssuer {
+            issuer: format!("https://{}.example.com/", name),
+            audience: "proof-messenger-api".to_string(),
+            jwks_url: format!("{}/{}/jwks.json", server.uri(), name),
+        };
+        let config = OAuthConfig {
+            issuers: vec![issuer("first"), issuer("second")],
+            introspection: Some(IntrospectionConfig {
+                endpoint: format!("{}/introspect", server.uri()),
+                client_id: "relay".to_string(),
+                client_secret: "s3cret".to_string(),
+                cache_seconds: 60,
+            }),
+        };
+
+        // ACT: Build the validator the relay serves
+        let validator = JwtValidator::from_config(&config).await.unwrap();
+
+        // ASSERT: Each issuer's tokens verify with its own keys only, and opaque tokens are introspected
+        let first = issued_token("https://first.example.com/", "first-2026", &first_key);
+        let second = issued_token("https://second.example.com/", "second-2026", &second_key);
+        let forged = issued_token("https://first.example.com/", "first-2026", &second_key);
+        let unknown = issued_token("https://first.example.com/", "retired", &first_key);
+        assert_eq!(validator.validate_token(&first).unwrap(), "alice");
+        assert_eq!(validator.validate_token(&second).unwrap(), "alice");
+        assert!(matches!(validator.validate_token(&forged), Err(JwtValidationError::InvalidSignature)));
+        assert!(matches!(validator.validate_token(&unknown), Err(JwtValidationError::UnknownKey)));
+        let introspected = validator.introspect("opaque-token").await.unwrap().unwrap();
+        assert_eq!(introspected.subject().as_deref(), Some("machine"));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_key_set_fails_validator_construction() {
+        let config = OAuthConfig {
+            issuers: vec![crate::config::OAuthIssuer {
+                issuer: "https://auth.example.com/".to_string(),
+                audience: "proof-messenger-api".to_string(),
+                jwks_url: "http://127.0.0.1:9/jwks.json".to_string(),
+            }],
+            introspection: None,
+        };
+
+        let result = JwtValidator::from_config(&config).await;
+
+        assert!(matches!(result, Err(JwtValidationError::KeySet(_))));
+    }
+}
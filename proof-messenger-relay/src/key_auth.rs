@@ -0,0 +1,429 @@
+//! Key Authentication Module
+//!
+//! CLI and IoT clients that already hold an Ed25519 signing key can
+//! authenticate with it instead of an external identity provider. The client
+//! asks for a challenge with `GET /auth/challenge?public_key=...`, signs
+//! [`challenge_statement`] with the key and sends the challenge and signature
+//! to `POST /auth/session`. The relay answers with a short-lived session
+//! token, prefixed [`SESSION_TOKEN_PREFIX`], that the client presents as a
+//! bearer token until it expires.
+//!
+//! Only keys listed under `key_auth.keys` can authenticate, each with the
+//! scopes configured for it; scopes are read from the configuration on every
+//! request, so removing a key ends its sessions. Challenges are single use and
+//! session tokens are stored only as their SHA-256 hash, both in the database
+//! so that any replica can redeem them.
+//!
+//! Key authentication is enabled by the `key_auth.enabled` relay setting (see
+//! [`crate::config::KeyAuthConfig`]) and layering the resulting [`KeyAuth`]
+//! onto the router as an [`axum::Extension`].
+
+use axum::{
+    extract::{Json, Query},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{auth_middleware::AuthContext, config::KeyAuthConfig, database::Database, AppError};
+
+/// Prefix of every session token, to tell them apart from identity provider tokens
+pub const SESSION_TOKEN_PREFIX: &str = "pms_";
+
+/// Domain separation prefix for key authentication signatures
+const KEY_AUTH_DOMAIN: &[u8] = b"proof-messenger/key-auth/v1";
+
+/// Random bytes in a challenge or session token
+const TOKEN_LENGTH: usize = 32;
+
+/// Key authentication error types
+#[derive(Error, Debug)]
+pub enum KeyAuthError {
+    #[error("Key authentication is not enabled on this relay")]
+    Disabled,
+
+    #[error("Public key is not allowed to authenticate")]
+    UnknownKey,
+
+    #[error("Key challenge failed: {0}")]
+    ChallengeFailed(String),
+
+    #[error("Invalid or expired session token")]
+    InvalidSession,
+}
+
+/// Query parameters for requesting a challenge
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ChallengeQuery {
+    /// Public key that will sign the challenge (hex encoded)
+    pub public_key: String,
+}
+
+/// Request body exchanging a signed challenge for a session token
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SessionRequest {
+    /// Public key that signed the challenge (hex encoded)
+    pub public_key: String,
+    /// Challenge issued by `GET /auth/challenge`
+    pub challenge: String,
+    /// Signature over the challenge statement (hex encoded)
+    pub signature: String,
+}
+
+/// Session token issued for a signed challenge
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Session {
+    /// Bearer token for authenticated requests
+    pub token: String,
+    /// Public key the session authenticates
+    pub public_key: String,
+    /// Scopes granted to the session
+    pub scopes: Vec<String>,
+    /// When the token stops being accepted
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Issues challenges and session tokens to configured Ed25519 keys
+pub struct KeyAuth {
+    db: Arc<Database>,
+    challenge_ttl: Duration,
+    session_ttl: Duration,
+    keys: HashMap<String, HashSet<String>>,
+}
+
+impl KeyAuth {
+    /// Key authentication for the keys in `config`, storing challenges and sessions in `db`
+    pub fn new(db: Arc<Database>, config: &KeyAuthConfig) -> Self {
+        Self {
+            db,
+            challenge_ttl: Duration::seconds(config.challenge_ttl_secs as i64),
+            session_ttl: Duration::seconds(config.session_ttl_secs as i64),
+            keys: config
+                .keys
+                .iter()
+                .map(|(key, scopes)| (key.to_lowercase(), scopes.iter().cloned().collect()))
+                .collect(),
+        }
+    }
+
+    /// Issue a challenge for a configured `public_key` to sign
+    pub async fn issue_challenge(&self, public_key: &str) -> Result<serde_json::Value, AppError> {
+        let public_key = public_key.to_lowercase();
+        if !self.keys.contains_key(&public_key) {
+            return Err(KeyAuthError::UnknownKey.into());
+        }
+
+        let challenge = random_token();
+        let expires_at = Utc::now() + self.challenge_ttl;
+        self.db.create_key_auth_challenge(&challenge, &public_key, expires_at).await?;
+
+        Ok(serde_json::json!({
+            "challenge": challenge,
+            "public_key": public_key,
+            "expires_at": expires_at
+        }))
+    }
+
+    /// Redeem a signed challenge for a session token
+    pub async fn open_session(&self, request: &SessionRequest) -> Result<Session, AppError> {
+        let public_key = request.public_key.to_lowercase();
+        let scopes = self.keys.get(&public_key).ok_or(KeyAuthError::UnknownKey)?;
+
+        let challenged = self
+            .db
+            .take_key_auth_challenge(&request.challenge)
+            .await?
+            .ok_or_else(|| KeyAuthError::ChallengeFailed("unknown, used or expired challenge".to_string()))?;
+        if challenged != public_key {
+            return Err(KeyAuthError::ChallengeFailed("challenge was issued to another key".to_string()).into());
+        }
+
+        let verifying_key = hex::decode(&public_key)
+            .ok()
+            .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+            .ok_or(KeyAuthError::UnknownKey)?;
+        let signature = hex::decode(&request.signature)
+            .ok()
+            .and_then(|bytes| Signature::from_bytes(&bytes).ok())
+            .ok_or_else(|| KeyAuthError::ChallengeFailed("malformed signature".to_string()))?;
+        verifying_key
+            .verify(&challenge_statement(&public_key, &request.challenge), &signature)
+            .map_err(|_| KeyAuthError::ChallengeFailed("signature does not verify".to_string()))?;
+
+        let token = format!("{}{}", SESSION_TOKEN_PREFIX, random_token());
+        let expires_at = Utc::now() + self.session_ttl;
+        self.db.create_key_session(&hash_token(&token), &public_key, expires_at).await?;
+
+        let mut scopes: Vec<String> = scopes.iter().cloned().collect();
+        scopes.sort();
+        Ok(Session { token, public_key, scopes, expires_at })
+    }
+
+    /// Authenticate a presented session token with its key's current scopes
+    pub async fn authenticate(&self, token: &str) -> Result<AuthContext, AppError> {
+        let public_key = self.db.find_key_session(&hash_token(token)).await?.ok_or(KeyAuthError::InvalidSession)?;
+        let scopes = self.keys.get(&public_key).ok_or(KeyAuthError::InvalidSession)?;
+
+        Ok(AuthContext {
+            user_id: format!("key:{}", public_key),
+            scopes: scopes.clone(),
+            tenant: None,
+        })
+    }
+}
+
+/// The statement a client signs to authenticate `public_key` with `challenge`
+pub fn challenge_statement(public_key: &str, challenge: &str) -> Vec<u8> {
+    let mut bytes = KEY_AUTH_DOMAIN.to_vec();
+    bytes.push(0);
+    bytes.extend_from_slice(public_key.to_lowercase().as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(challenge.as_bytes());
+    bytes
+}
+
+/// Whether a bearer token was issued by this relay rather than an identity provider
+pub fn is_session_token(token: &str) -> bool {
+    token.starts_with(SESSION_TOKEN_PREFIX)
+}
+
+/// Generate a hex encoded random token
+fn random_token() -> String {
+    let mut bytes = [0u8; TOKEN_LENGTH];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Hash a session token for storage and lookup
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Create router for key authentication endpoints
+///
+/// These routes are public: the signed challenge is the credential.
+pub fn key_auth_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/auth/challenge", get(challenge_handler))
+        .route("/auth/session", post(session_handler))
+}
+
+/// Handler to issue a challenge for a key to sign
+#[utoipa::path(
+    get,
+    path = "/auth/challenge",
+    operation_id = "createKeyAuthChallenge",
+    tag = "key-auth",
+    params(ChallengeQuery),
+    responses(
+        (status = 201, description = "Challenge for the key to sign", body = Object),
+        (status = 401, description = "Key is not allowed to authenticate"),
+        (status = 404, description = "Key authentication is not enabled"),
+    )
+)]
+#[instrument(skip_all)]
+async fn challenge_handler(
+    key_auth: Option<Extension<Arc<KeyAuth>>>,
+    Query(params): Query<ChallengeQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let Extension(key_auth) = key_auth.ok_or(KeyAuthError::Disabled)?;
+    info!("Issuing key authentication challenge");
+
+    let challenge = key_auth.issue_challenge(&params.public_key).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "challenge": challenge
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Handler to exchange a signed challenge for a session token
+#[utoipa::path(
+    post,
+    path = "/auth/session",
+    operation_id = "createKeySession",
+    tag = "key-auth",
+    request_body = SessionRequest,
+    responses(
+        (status = 201, description = "Session token for the key", body = Object),
+        (status = 401, description = "Unknown key, or the challenge or signature failed"),
+        (status = 404, description = "Key authentication is not enabled"),
+    )
+)]
+#[instrument(skip_all)]
+async fn session_handler(
+    key_auth: Option<Extension<Arc<KeyAuth>>>,
+    Json(payload): Json<SessionRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let Extension(key_auth) = key_auth.ok_or(KeyAuthError::Disabled)?;
+
+    let session = match key_auth.open_session(&payload).await {
+        Ok(session) => session,
+        Err(e) => {
+            warn!("Key authentication failed for {}: {}", payload.public_key, e);
+            return Err(e);
+        }
+    };
+    info!("Opened key session for {}", session.public_key);
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "session": session
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{jwt_validator::JwtValidator, secure_logger::SecureLogger};
+    use axum::{body::Body, http::Request};
+    use ed25519_dalek::{Keypair, Signer};
+    use proof_messenger_protocol::key::generate_keypair_with_seed;
+    use std::collections::BTreeMap;
+    use tower::ServiceExt;
+
+    async fn setup(keypair: &Keypair, scopes: &[&str]) -> (Arc<Database>, Router) {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let config = KeyAuthConfig {
+            enabled: true,
+            keys: BTreeMap::from([(
+                hex::encode(keypair.public.to_bytes()),
+                scopes.iter().map(|scope| scope.to_string()).collect(),
+            )]),
+            ..KeyAuthConfig::default()
+        };
+        let config = crate::config::RelayConfig {
+            key_auth: config,
+            rate_limit: crate::config::RateLimitConfig { per_second: 100, burst_size: 100 },
+            ..Default::default()
+        };
+        let validator = Arc::new(JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let logger = Arc::new(SecureLogger::new(&SecureLogger::generate_key()));
+        let app = crate::create_authenticated_app_with_config(db.clone(), &config, validator, logger);
+        (db, app)
+    }
+
+    async fn send(app: &Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    async fn challenge(app: &Router, public_key: &str) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .uri(format!("/auth/challenge?public_key={}", public_key))
+            .body(Body::empty())
+            .unwrap();
+        send(app, request).await
+    }
+
+    async fn open_session(app: &Router, public_key: &str, challenge: &str, signature: &str) -> (StatusCode, serde_json::Value) {
+        let body = serde_json::json!({ "public_key": public_key, "challenge": challenge, "signature": signature });
+        let request = Request::builder()
+            .method("POST")
+            .uri("/auth/session")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        send(app, request).await
+    }
+
+    fn with_token(uri: &str, token: &str) -> Request<Body> {
+        Request::builder()
+            .uri(uri)
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_signed_challenge_opens_a_scoped_session() {
+        // ARRANGE: A device key allowed to read the quarantine
+        let keypair = generate_keypair_with_seed(81);
+        let public_key = hex::encode(keypair.public.to_bytes());
+        let (db, app) = setup(&keypair, &["quarantine:read"]).await;
+
+        // ACT: Sign a challenge, open a session and use its token
+        let (challenge_status, issued) = challenge(&app, &public_key).await;
+        let nonce = issued["challenge"]["challenge"].as_str().unwrap().to_string();
+        let signature = hex::encode(keypair.sign(&challenge_statement(&public_key, &nonce)).to_bytes());
+        let (session_status, opened) = open_session(&app, &public_key, &nonce, &signature).await;
+        let token = opened["session"]["token"].as_str().unwrap().to_string();
+        let (allowed, _) = send(&app, with_token("/quarantine", &token)).await;
+        let (outside_scopes, _) = send(&app, with_token("/revocation/list", &token)).await;
+        let (replayed, replay_body) = open_session(&app, &public_key, &nonce, &signature).await;
+
+        // ASSERT: The session acts with the key's scopes, and the challenge works once
+        assert_eq!(challenge_status, StatusCode::CREATED);
+        assert_eq!(session_status, StatusCode::CREATED);
+        assert!(token.starts_with(SESSION_TOKEN_PREFIX));
+        assert_eq!(opened["session"]["scopes"], serde_json::json!(["quarantine:read"]));
+        assert_eq!(allowed, StatusCode::OK);
+        assert_eq!(outside_scopes, StatusCode::FORBIDDEN);
+        assert_eq!(replayed, StatusCode::UNAUTHORIZED);
+        assert_eq!(replay_body["code"], "KEY_CHALLENGE_FAILED");
+        assert!(db.find_key_session(&token).await.unwrap().is_none());
+        assert_eq!(db.find_key_session(&hash_token(&token)).await.unwrap(), Some(public_key));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_keys_bad_signatures_and_tokens_are_rejected() {
+        // ARRANGE: One configured key and one stranger
+        let keypair = generate_keypair_with_seed(82);
+        let stranger = generate_keypair_with_seed(83);
+        let public_key = hex::encode(keypair.public.to_bytes());
+        let (_db, app) = setup(&keypair, &["quarantine:read"]).await;
+
+        // ACT: Ask for a challenge as a stranger, sign one with the wrong key, present a made-up token
+        let (stranger_status, stranger_body) = challenge(&app, &hex::encode(stranger.public.to_bytes())).await;
+        let (_, issued) = challenge(&app, &public_key).await;
+        let nonce = issued["challenge"]["challenge"].as_str().unwrap().to_string();
+        let forged = hex::encode(stranger.sign(&challenge_statement(&public_key, &nonce)).to_bytes());
+        let (forged_status, _) = open_session(&app, &public_key, &nonce, &forged).await;
+        let (token_status, token_body) = send(&app, with_token("/quarantine", "pms_not-a-session")).await;
+
+        // ASSERT: None of them authenticate
+        assert_eq!(stranger_status, StatusCode::UNAUTHORIZED);
+        assert_eq!(stranger_body["code"], "UNKNOWN_KEY");
+        assert_eq!(forged_status, StatusCode::UNAUTHORIZED);
+        assert_eq!(token_status, StatusCode::UNAUTHORIZED);
+        assert_eq!(token_body["code"], "INVALID_SESSION");
+    }
+
+    #[tokio::test]
+    async fn test_expired_challenges_and_disabled_relays() {
+        let keypair = generate_keypair_with_seed(84);
+        let public_key = hex::encode(keypair.public.to_bytes());
+        let (db, app) = setup(&keypair, &["quarantine:read"]).await;
+        db.create_key_auth_challenge("stale", &public_key, Utc::now() - Duration::seconds(1)).await.unwrap();
+        let signature = hex::encode(keypair.sign(&challenge_statement(&public_key, "stale")).to_bytes());
+
+        let (expired, _) = open_session(&app, &public_key, "stale", &signature).await;
+        assert_eq!(expired, StatusCode::UNAUTHORIZED);
+
+        let validator = Arc::new(JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let logger = Arc::new(SecureLogger::new(&SecureLogger::generate_key()));
+        let disabled = crate::create_app_with_oauth(db, validator, logger);
+        let (status, body) = challenge(&disabled, &public_key).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "KEY_AUTH_DISABLED");
+    }
+}
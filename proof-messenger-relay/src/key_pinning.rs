@@ -0,0 +1,680 @@
+//! Key Pinning Module
+//!
+//! By default the relay accepts any sender key whose proof verifies. With key
+//! pinning, the authenticated relay remembers the first public key each user
+//! (the `user_id` of their [`AuthContext`]) relays with, trust-on-first-use
+//! style, and treats a later message under a different key as a key change:
+//!
+//! - in `flag` mode the message is relayed, the new key is pinned and the
+//!   relay response carries the `key_change`
+//! - in `reject` mode the message is refused with `409 Conflict` and the pin
+//!   stays, until the user submits a signed rotation statement to
+//!   `POST /keys/rotate`
+//!
+//! Every change is stored in the `key_changes` table, written to the audit
+//! log, counted in the metrics registry and sent to unfiltered webhooks as a
+//! `key.changed` event. With a compliance audit trail attached (see
+//! [`crate::compliance_audit`]), changes are also recorded there.
+//!
+//! A rotation statement is [`rotation_statement`] signed by both the pinned
+//! key and the new one, so a stolen token alone cannot move the pin.
+//!
+//! Key pinning is enabled by the `features.key_pinning` relay setting or
+//! `KEY_PINNING=flag|reject` (see [`crate::config::RelayConfig`]) and
+//! layering the resulting [`KeyPinning`] onto the router as an
+//! [`axum::Extension`].
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Router,
+};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth_middleware::AuthContext,
+    compliance_audit::ComplianceAudit,
+    config::KeyPinningMode,
+    database::{Database, KeyChange},
+    metrics,
+    request_id::RequestId,
+    secure_logger::{LogLevel, SecureLogger},
+    webhooks::{self, WebhookDispatcher},
+    AppError,
+};
+
+/// Domain separator at the start of every rotation statement
+pub const ROTATION_DOMAIN: &str = "proof-messenger-key-rotation-v1";
+
+/// How far a rotation statement's `issued_at` may be from the relay's clock
+const ROTATION_MAX_SKEW_SECS: i64 = 300;
+
+/// Default and maximum number of key changes returned when listing
+const MAX_CHANGE_LIST: i64 = 100;
+
+/// Key pinning errors
+#[derive(Error, Debug)]
+pub enum KeyPinningError {
+    #[error("Key pinning is not enabled on this relay")]
+    Disabled,
+
+    #[error("{0}")]
+    KeyChanged(KeyMismatch),
+
+    #[error("No key is pinned for user {0}")]
+    NotPinned(String),
+
+    #[error("Invalid key rotation: {0}")]
+    InvalidRotation(String),
+}
+
+/// A message signed with a key other than its user's pinned key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyMismatch {
+    /// Authenticated user who sent the message
+    pub user_id: String,
+    /// Key pinned for the user (hex encoded)
+    pub pinned_key: String,
+    /// Key the message was signed with (hex encoded)
+    pub presented_key: String,
+}
+
+impl std::fmt::Display for KeyMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "public key for user {} changed without a signed rotation (pinned {}, presented {})",
+            self.user_id, self.pinned_key, self.presented_key
+        )
+    }
+}
+
+/// What the relay did about a key change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyChangeAction {
+    /// The user moved the pin with a signed rotation statement
+    Rotated,
+    /// The message was relayed and the new key pinned
+    Flagged,
+    /// The message was refused and the pin kept
+    Rejected,
+}
+
+impl KeyChangeAction {
+    /// Name stored with the change and used as its metrics label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyChangeAction::Rotated => "rotated",
+            KeyChangeAction::Flagged => "flagged",
+            KeyChangeAction::Rejected => "rejected",
+        }
+    }
+}
+
+/// Request body for rotating the caller's pinned key
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct KeyRotationRequest {
+    /// Key to pin instead of the current one (hex encoded)
+    pub new_public_key: String,
+    /// When the statement was signed (Unix seconds)
+    pub issued_at: i64,
+    /// Signature over the rotation statement by the pinned key (hex encoded)
+    pub signature: String,
+    /// Signature over the same statement by the new key (hex encoded)
+    pub new_key_signature: String,
+}
+
+/// Query parameters for listing key changes
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct KeyChangeQuery {
+    /// Only changes of this user's key
+    pub user_id: Option<String>,
+    /// Maximum number of changes to return (default and maximum 100)
+    pub limit: Option<i64>,
+}
+
+/// The statement both keys sign to rotate `user_id` from `previous_key` to `new_key`
+///
+/// Keys are lowercase hex; `issued_at` is in Unix seconds.
+pub fn rotation_statement(user_id: &str, previous_key: &str, new_key: &str, issued_at: i64) -> Vec<u8> {
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        ROTATION_DOMAIN,
+        user_id,
+        previous_key.to_ascii_lowercase(),
+        new_key.to_ascii_lowercase(),
+        issued_at
+    )
+    .into_bytes()
+}
+
+/// Trust-on-first-use pins of authenticated users' public keys
+pub struct KeyPinning {
+    mode: KeyPinningMode,
+    audit: Option<Arc<ComplianceAudit>>,
+}
+
+impl KeyPinning {
+    /// Pin keys, handling key changes as `mode` says
+    pub fn new(mode: KeyPinningMode) -> Self {
+        Self { mode, audit: None }
+    }
+
+    /// Also record key changes in `audit`
+    pub fn with_audit(mut self, audit: Arc<ComplianceAudit>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// How key changes are handled
+    pub fn mode(&self) -> KeyPinningMode {
+        self.mode
+    }
+
+    /// Check the key `user_id` signed a message with against their pin
+    ///
+    /// The first key a user relays with is pinned. A different key is a key
+    /// change, which is recorded and returned; in `flag` mode the new key is
+    /// pinned as well.
+    pub async fn check(
+        &self,
+        db: &Database,
+        user_id: &str,
+        public_key: &str,
+        request_id: Option<&str>,
+    ) -> Result<Option<KeyChange>, AppError> {
+        let public_key = public_key.to_ascii_lowercase();
+        let pin = db.pin_key_if_absent(user_id, &public_key).await?;
+        if pin.public_key == public_key {
+            return Ok(None);
+        }
+
+        let change = match self.mode {
+            KeyPinningMode::Flag => {
+                let change = key_change(user_id, &pin.public_key, &public_key, KeyChangeAction::Flagged, request_id);
+                // A concurrent change may have moved the pin first; this one is still recorded
+                db.replace_key_pin(&change).await?;
+                change
+            }
+            KeyPinningMode::Reject => {
+                let change = key_change(user_id, &pin.public_key, &public_key, KeyChangeAction::Rejected, request_id);
+                db.record_key_change(&change).await?;
+                change
+            }
+        };
+        Ok(Some(change))
+    }
+
+    /// Move `user_id`'s pin to a new key with a signed rotation statement
+    pub async fn rotate(
+        &self,
+        db: &Database,
+        user_id: &str,
+        request: &KeyRotationRequest,
+        request_id: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<KeyChange, AppError> {
+        let pin = db
+            .get_key_pin(user_id)
+            .await?
+            .ok_or_else(|| KeyPinningError::NotPinned(user_id.to_string()))?;
+        let new_key = request.new_public_key.to_ascii_lowercase();
+        if new_key == pin.public_key {
+            return Err(KeyPinningError::InvalidRotation("the new key is already pinned".to_string()).into());
+        }
+        if (now.timestamp() - request.issued_at).abs() > ROTATION_MAX_SKEW_SECS {
+            return Err(KeyPinningError::InvalidRotation(format!(
+                "issued_at must be within {} seconds of the relay's clock",
+                ROTATION_MAX_SKEW_SECS
+            ))
+            .into());
+        }
+
+        let statement = rotation_statement(user_id, &pin.public_key, &new_key, request.issued_at);
+        verify_statement(&pin.public_key, &statement, &request.signature, "pinned key")?;
+        verify_statement(&new_key, &statement, &request.new_key_signature, "new key")?;
+
+        let change = key_change(user_id, &pin.public_key, &new_key, KeyChangeAction::Rotated, request_id);
+        if !db.replace_key_pin(&change).await? {
+            return Err(KeyPinningError::InvalidRotation("the pinned key changed while rotating".to_string()).into());
+        }
+        Ok(change)
+    }
+
+    /// Report a key change to the audit log, metrics and webhooks
+    ///
+    /// Failures are logged rather than returned, so the caller always sees
+    /// the outcome of the check itself.
+    pub async fn report(
+        &self,
+        change: &KeyChange,
+        db: &Arc<Database>,
+        webhooks: Option<&Arc<WebhookDispatcher>>,
+        logger: &SecureLogger,
+    ) {
+        let mut metadata = HashMap::new();
+        metadata.insert("previous_key".to_string(), change.previous_key.clone());
+        metadata.insert("new_key".to_string(), change.new_key.clone());
+        metadata.insert("action".to_string(), change.action.clone());
+        metadata.insert("key_change_id".to_string(), change.id.clone());
+        let (level, message) = match change.action.as_str() {
+            "rotated" => (LogLevel::Audit, "Pinned public key rotated"),
+            "flagged" => (LogLevel::Warning, "Public key changed without a signed rotation"),
+            _ => (LogLevel::Warning, "Message rejected: public key does not match the pinned key"),
+        };
+        if let Err(e) = logger.log_security_event(
+            level,
+            message.to_string(),
+            Some(change.user_id.clone()),
+            change.request_id.clone(),
+            metadata,
+        ) {
+            warn!("Failed to log key change: {}", e);
+        }
+
+        if let Some(audit) = &self.audit {
+            let details = HashMap::from([
+                ("user_id".to_string(), Value::from(change.user_id.clone())),
+                ("action".to_string(), Value::from(change.action.clone())),
+            ]);
+            audit.record(|log| log.log_compliance_check("key_pinning", "key_change", change.action != "rejected", details));
+        }
+
+        metrics::KEY_CHANGES_TOTAL
+            .get_or_create(&vec![("action".to_string(), change.action.clone())])
+            .inc();
+
+        if let Some(dispatcher) = webhooks {
+            if let Err(e) = dispatcher.notify_key_change(db, change).await {
+                warn!("Failed to queue key change webhooks: {}", e);
+            }
+        }
+    }
+}
+
+/// Check a message's key against its user's pin if key pinning is enabled
+///
+/// Key changes are reported whether or not the message is let through.
+/// Returns the change if the message may still be relayed.
+pub async fn enforce_if_enabled(
+    pinning: Option<&Arc<KeyPinning>>,
+    db: &Arc<Database>,
+    webhooks: Option<&Arc<WebhookDispatcher>>,
+    logger: &SecureLogger,
+    user_id: &str,
+    public_key: &str,
+    request_id: Option<&str>,
+) -> Result<Option<KeyChange>, AppError> {
+    let Some(pinning) = pinning else {
+        return Ok(None);
+    };
+    let Some(change) = pinning.check(db, user_id, public_key, request_id).await? else {
+        return Ok(None);
+    };
+    pinning.report(&change, db, webhooks, logger).await;
+    if change.action == KeyChangeAction::Rejected.as_str() {
+        return Err(KeyPinningError::KeyChanged(KeyMismatch {
+            user_id: change.user_id,
+            pinned_key: change.previous_key,
+            presented_key: change.new_key,
+        })
+        .into());
+    }
+    Ok(Some(change))
+}
+
+/// A new key change record
+fn key_change(
+    user_id: &str,
+    previous_key: &str,
+    new_key: &str,
+    action: KeyChangeAction,
+    request_id: Option<&str>,
+) -> KeyChange {
+    KeyChange {
+        id: Uuid::new_v4().to_string(),
+        user_id: user_id.to_string(),
+        previous_key: previous_key.to_string(),
+        new_key: new_key.to_string(),
+        action: action.as_str().to_string(),
+        request_id: request_id.map(str::to_string),
+        detected_at: Utc::now(),
+    }
+}
+
+/// Verify `signature` over a rotation statement by `public_key`
+fn verify_statement(public_key: &str, statement: &[u8], signature: &str, signer: &str) -> Result<(), AppError> {
+    let key_bytes = hex::decode(public_key)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid hex encoding: {}", e)))?;
+    let key = PublicKey::from_bytes(&key_bytes)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid public key: {}", e)))?;
+    let signature_bytes = hex::decode(signature)
+        .map_err(|e| AppError::InvalidSignature(format!("Invalid hex encoding: {}", e)))?;
+    let signature = Signature::from_bytes(&signature_bytes)
+        .map_err(|e| AppError::InvalidSignature(format!("Invalid signature: {}", e)))?;
+    key.verify(statement, &signature)
+        .map_err(|_| KeyPinningError::InvalidRotation(format!("the {} did not sign the rotation statement", signer)).into())
+}
+
+/// Create router for authenticated key pinning endpoints
+///
+/// Pins are per authenticated user, so there are no unauthenticated routes.
+pub fn authenticated_key_pinning_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)> {
+    Router::new()
+        .route("/rotate", post(authenticated_rotate_key_handler))
+        .route("/pins/:user_id", get(authenticated_get_key_pin_handler))
+        .route("/changes", get(authenticated_list_key_changes_handler))
+}
+
+/// Authenticated handler to rotate the caller's pinned key
+#[utoipa::path(
+    post,
+    path = "/keys/rotate",
+    operation_id = "rotateKey",
+    tag = "keys",
+    request_body = KeyRotationRequest,
+    responses(
+        (status = 200, description = "The caller's newly pinned key", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn authenticated_rotate_key_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    pinning: Option<Extension<Arc<KeyPinning>>>,
+    webhooks: Option<Extension<Arc<webhooks::WebhookDispatcher>>>,
+    Json(payload): Json<KeyRotationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} rotating their pinned key", auth.user_id);
+
+    let Extension(pinning) = pinning.ok_or(KeyPinningError::Disabled)?;
+    let change = pinning
+        .rotate(&db, &auth.user_id, &payload, Some(&request_id.to_string()), Utc::now())
+        .await?;
+    pinning.report(&change, &db, webhooks.as_deref(), &secure_logger).await;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": "Pinned key rotated",
+        "key_change": change,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to look up the key pinned for a user
+#[utoipa::path(
+    get,
+    path = "/keys/pins/{user_id}",
+    operation_id = "getKeyPin",
+    tag = "keys",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The user's pinned key", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn authenticated_get_key_pin_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    pinning: Option<Extension<Arc<KeyPinning>>>,
+    Path(user_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} looking up the key pinned for {}", auth.user_id, user_id);
+
+    pinning.map(|_| ()).ok_or(KeyPinningError::Disabled)?;
+    let pin = db.get_key_pin(&user_id).await?.ok_or(KeyPinningError::NotPinned(user_id))?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "key_pin": pin,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to list recent key changes
+#[utoipa::path(
+    get,
+    path = "/keys/changes",
+    operation_id = "listKeyChanges",
+    tag = "keys",
+    params(KeyChangeQuery),
+    responses(
+        (status = 200, description = "Recorded key changes, newest first", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn authenticated_list_key_changes_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    pinning: Option<Extension<Arc<KeyPinning>>>,
+    Query(params): Query<KeyChangeQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} listing key changes", auth.user_id);
+
+    pinning.map(|_| ()).ok_or(KeyPinningError::Disabled)?;
+    let limit = params.limit.unwrap_or(MAX_CHANGE_LIST).clamp(1, MAX_CHANGE_LIST);
+    let changes = db.get_key_changes(params.user_id.as_deref(), limit).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "count": changes.len(),
+        "key_changes": changes,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jwt_validator::JwtValidator;
+    use axum::{body::Body, http::Request};
+    use ed25519_dalek::{Keypair, Signer};
+    use proof_messenger_protocol::key::generate_keypair_with_seed;
+    use tower::ServiceExt;
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    fn app(db: Arc<Database>, mode: KeyPinningMode) -> Router {
+        let validator = Arc::new(JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let logger = Arc::new(SecureLogger::new(&SecureLogger::generate_key()));
+        crate::create_app_with_oauth(db, validator, logger).layer(Extension(Arc::new(KeyPinning::new(mode))))
+    }
+
+    fn token(sub: &str) -> String {
+        let claims = serde_json::json!({ "sub": sub, "iss": "issuer", "exp": 9999999999u64, "scope": "proof:create key:rotate key:read" });
+        jsonwebtoken::encode(&Default::default(), &claims, &jsonwebtoken::EncodingKey::from_secret(b"secret")).unwrap()
+    }
+
+    fn public_key(keypair: &Keypair) -> String {
+        hex::encode(keypair.public.to_bytes())
+    }
+
+    /// A relay request from `sub`, signed with the key generated from `seed`
+    fn relay(sub: &str, seed: u64) -> Request<Body> {
+        let keypair = generate_keypair_with_seed(seed);
+        let context = b"pinned context".to_vec();
+        let message = serde_json::json!({
+            "sender": public_key(&keypair),
+            "context": hex::encode(&context),
+            "body": "hello",
+            "proof": hex::encode(keypair.sign(&context).to_bytes()),
+        });
+        Request::builder()
+            .method("POST")
+            .uri("/relay")
+            .header("authorization", format!("Bearer {}", token(sub)))
+            .header("content-type", "application/json")
+            .body(Body::from(message.to_string()))
+            .unwrap()
+    }
+
+    /// A rotation request from `sub` moving its pin from `old` to `new`
+    fn rotation(sub: &str, old: &Keypair, new: &Keypair, issued_at: i64) -> Request<Body> {
+        let statement = rotation_statement(sub, &public_key(old), &public_key(new), issued_at);
+        let body = KeyRotationRequest {
+            new_public_key: public_key(new),
+            issued_at,
+            signature: hex::encode(old.sign(&statement).to_bytes()),
+            new_key_signature: hex::encode(new.sign(&statement).to_bytes()),
+        };
+        Request::builder()
+            .method("POST")
+            .uri("/keys/rotate")
+            .header("authorization", format!("Bearer {}", token(sub)))
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(&body).unwrap()))
+            .unwrap()
+    }
+
+    async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_reject_mode_refuses_a_changed_key() {
+        // ARRANGE: alice relays once, pinning her first key
+        let db = setup_db().await;
+        let app = app(db.clone(), KeyPinningMode::Reject);
+        let (status, _) = send(&app, relay("alice", 1)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // ACT: Relay under alice's name with another key
+        let (status, body) = send(&app, relay("alice", 2)).await;
+
+        // ASSERT: The message is refused, the pin kept and the change recorded
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "KEY_CHANGED");
+        assert_eq!(body["details"]["pinned_key"], public_key(&generate_keypair_with_seed(1)));
+        assert_eq!(body["details"]["presented_key"], public_key(&generate_keypair_with_seed(2)));
+        let pin = db.get_key_pin("alice").await.unwrap().unwrap();
+        assert_eq!(pin.public_key, public_key(&generate_keypair_with_seed(1)));
+        let changes = db.get_key_changes(Some("alice"), 10).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].action, "rejected");
+    }
+
+    #[tokio::test]
+    async fn test_flag_mode_relays_and_moves_the_pin() {
+        // ARRANGE: alice's first key is pinned and an unfiltered webhook is registered
+        let db = setup_db().await;
+        let webhook = db.create_webhook("http://127.0.0.1:9/hook", None, "secret", None).await.unwrap();
+        let dispatcher = Arc::new(WebhookDispatcher::new(webhooks::WebhookConfig {
+            max_attempts: 1,
+            allow_insecure_urls: true,
+            ..Default::default()
+        }).unwrap());
+        let app = app(db.clone(), KeyPinningMode::Flag).layer(Extension(dispatcher));
+        send(&app, relay("alice", 1)).await;
+
+        // ACT: Relay under alice's name with another key
+        let (status, body) = send(&app, relay("alice", 2)).await;
+
+        // ASSERT: The message is relayed with the change, which is also sent to the webhook
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["key_change"]["action"], "flagged");
+        assert_eq!(body["key_change"]["new_key"], public_key(&generate_keypair_with_seed(2)));
+        let pin = db.get_key_pin("alice").await.unwrap().unwrap();
+        assert_eq!(pin.public_key, public_key(&generate_keypair_with_seed(2)));
+        let deliveries = db.get_webhook_deliveries(&webhook.id, 10).await.unwrap();
+        assert!(deliveries.iter().any(|d| d.event_type == webhooks::KEY_CHANGED_EVENT));
+    }
+
+    #[tokio::test]
+    async fn test_signed_rotation_moves_the_pin() {
+        // ARRANGE: alice's first key is pinned
+        let db = setup_db().await;
+        let app = app(db.clone(), KeyPinningMode::Reject);
+        send(&app, relay("alice", 1)).await;
+        let (old, new) = (generate_keypair_with_seed(1), generate_keypair_with_seed(2));
+
+        // ACT: Rotate to the new key, then relay with it
+        let (status, body) = send(&app, rotation("alice", &old, &new, Utc::now().timestamp())).await;
+        let (relayed, _) = send(&app, relay("alice", 2)).await;
+
+        // ASSERT: The rotation is recorded and the new key is accepted
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["key_change"]["action"], "rotated");
+        assert_eq!(relayed, StatusCode::OK);
+        let (status, body) = send(
+            &app,
+            Request::builder()
+                .uri("/keys/pins/alice")
+                .header("authorization", format!("Bearer {}", token("admin")))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["key_pin"]["public_key"], public_key(&new));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_rotation_is_refused() {
+        // ARRANGE: alice's first key is pinned and an attacker holds another key
+        let db = setup_db().await;
+        let app = app(db.clone(), KeyPinningMode::Reject);
+        send(&app, relay("alice", 1)).await;
+        let (old, new, attacker) = (generate_keypair_with_seed(1), generate_keypair_with_seed(2), generate_keypair_with_seed(3));
+
+        // ACT: Rotate without the pinned key, with a stale statement, and for an unpinned user
+        let (forged, forged_body) = send(&app, rotation("alice", &attacker, &new, Utc::now().timestamp())).await;
+        let (stale, _) = send(&app, rotation("alice", &old, &new, Utc::now().timestamp() - 3600)).await;
+        let (unpinned, _) = send(&app, rotation("bob", &old, &new, Utc::now().timestamp())).await;
+
+        // ASSERT: Every rotation is refused and the pin is unchanged
+        assert_eq!(forged, StatusCode::BAD_REQUEST);
+        assert_eq!(forged_body["code"], "INVALID_KEY_ROTATION");
+        assert_eq!(stale, StatusCode::BAD_REQUEST);
+        assert_eq!(unpinned, StatusCode::NOT_FOUND);
+        let pin = db.get_key_pin("alice").await.unwrap().unwrap();
+        assert_eq!(pin.public_key, public_key(&old));
+        assert!(db.get_key_changes(None, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_routes_report_disabled_without_pinning() {
+        let db = setup_db().await;
+        let validator = Arc::new(JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let logger = Arc::new(SecureLogger::new(&SecureLogger::generate_key()));
+        let app = crate::create_app_with_oauth(db, validator, logger);
+
+        let (status, body) = send(
+            &app,
+            Request::builder()
+                .uri("/keys/changes")
+                .header("authorization", format!("Bearer {}", token("admin")))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "KEY_PINNING_DISABLED");
+    }
+}
@@ -3,44 +3,123 @@
 //! This library provides the core functionality for the relay server,
 //! including message verification, database operations, and HTTP handlers.
 
+pub mod config;
+pub mod api_error;
+pub mod decoding;
 pub mod database;
 pub mod jwt_validator;
 pub mod auth_middleware;
+pub mod authorization;
 pub mod secure_logger;
 pub mod revocation;
+pub mod invites;
+pub mod receipts;
+pub mod deliveries;
+pub mod inbox;
+pub mod amendments;
+pub mod detached_proofs;
+pub mod derivations;
+pub mod proof_chains;
+pub mod threads;
+pub mod schemas;
+pub mod search;
+pub mod export;
+pub mod federation;
+pub mod webhooks;
+pub mod event_stream;
+pub mod shared_state;
+pub mod rate_limit;
+pub mod replay;
+pub mod subscriptions;
+pub mod transparency;
+pub mod grpc;
+pub mod wire;
+pub mod quarantine;
+pub mod context_policy;
+pub mod compliance_audit;
+pub mod audit_chain;
+pub mod integrity;
+pub mod scheduling;
+pub mod log_redaction;
+pub mod siem;
+pub mod limits;
+pub mod expiry;
+pub mod maintenance;
+pub mod abuse;
+pub mod readiness;
+pub mod request_id;
+pub mod tls;
+pub mod egress;
 pub mod metrics;
 pub mod iam_connectors;
+pub mod tenancy;
+pub mod rbac;
+pub mod api_keys;
+pub mod key_auth;
+pub mod client_identity;
+pub mod write_behind;
+pub mod data_subjects;
+pub mod key_pinning;
+pub mod multisig;
+pub mod timestamping;
+pub mod evidence;
+pub mod openapi;
+pub mod versioning;
+pub mod verification_pool;
+pub mod checks;
+pub mod plugins;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 
 use axum::{
     extract::{Json, Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Response},
     routing::{get, post},
-    Router,
+    Extension, Router,
 };
 use ed25519_dalek::{PublicKey, Signature};
+use proof_messenger_protocol::envelope::{ProofAlgorithm, ProofEnvelope};
+use proof_messenger_protocol::hybrid::{verify_hybrid_proof, HybridPolicy, HybridPublicKey, HybridSignature};
 use proof_messenger_protocol::proof::{verify_proof_result, ProofError};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use thiserror::Error;
 use tracing::{info, instrument, warn};
 use std::sync::Arc;
 use chrono;
 use hex;
 
+use api_error::{ErrorBody, ErrorCode};
+use request_id::RequestId;
 use database::{Database, DatabaseError, StoredMessage};
-use auth_middleware::{AuthContext, auth_middleware, require_scope};
+use auth_middleware::{AuthContext, auth_middleware};
 use jwt_validator::JwtValidator;
-use secure_logger::{SecureLogger, LogLevel};
+use secure_logger::SecureLogger;
+use readiness::ready_handler;
 
 /// Query parameters for message retrieval
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct MessageQuery {
     /// Maximum number of messages to return
     pub limit: Option<i64>,
 }
 
+/// Query parameters for listing a sender's messages
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SenderMessageQuery {
+    /// Only messages stored at or after this time (RFC 3339)
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only messages stored before this time (RFC 3339)
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+    /// Maximum number of messages to return
+    pub limit: Option<i64>,
+}
+
 /// Message structure for relay operations
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
 pub struct Message {
     /// Public key of the sender (hex encoded)
     pub sender: String,
@@ -50,6 +129,30 @@ pub struct Message {
     pub body: String,
     /// Cryptographic proof/signature (hex encoded)
     pub proof: String,
+    /// Optional post-quantum half of a hybrid proof
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pqc: Option<PqcProof>,
+    /// Optional thread to post into (the thread's root message ID)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+    /// Optional ID of the message this one replies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    /// Optional time after which the relay stops serving the message (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Optional public key of the only recipient of a direct message (hex encoded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+}
+
+/// Post-quantum (ML-DSA-65) half of a hybrid Ed25519+PQC proof
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+pub struct PqcProof {
+    /// ML-DSA-65 public key of the sender (hex encoded)
+    pub public_key: String,
+    /// ML-DSA-65 signature over the context (hex encoded)
+    pub proof: String,
 }
 
 /// Application-specific error types
@@ -70,6 +173,120 @@ pub enum AppError {
     #[error("Proof has been revoked")]
     ProofRevoked,
     
+    #[error("Message replays a context its sender already relayed")]
+    ReplayDetected,
+    
+    #[error("Invite not found: {0}")]
+    InviteNotFound(String),
+    
+    #[error("Invite cannot be redeemed: {0}")]
+    InviteUnavailable(String),
+    
+    #[error("Invalid thread reference: {0}")]
+    InvalidThread(String),
+    
+    #[error("Invalid expiry: {0}")]
+    InvalidExpiry(String),
+    
+    #[error("Message has expired: {0}")]
+    MessageExpired(String),
+    
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    
+    #[error("Missing bearer token")]
+    MissingCredentials,
+    
+    #[error("Invalid bearer token: {0}")]
+    Authentication(#[from] jwt_validator::JwtValidationError),
+    
+    #[error("{0}")]
+    InsufficientScope(String),
+    
+    #[error("Insufficient scope: {0}")]
+    ScopeDenied(authorization::ScopeDenial),
+    
+    #[error("No scopes are declared for route {0}")]
+    RouteNotAuthorized(String),
+    
+    #[error("API key error: {0}")]
+    ApiKey(#[from] api_keys::ApiKeyError),
+    
+    #[error("Missing tenant identifier")]
+    MissingTenant,
+    
+    #[error("Unknown tenant: {0}")]
+    UnknownTenant(String),
+    
+    #[error("Tenant not permitted for this caller: {0}")]
+    TenantDenied(String),
+    
+    #[error("Federation error: {0}")]
+    Federation(#[from] federation::FederationError),
+    
+    #[error("Webhook error: {0}")]
+    Webhook(#[from] webhooks::WebhookError),
+    
+    #[error("Transparency log error: {0}")]
+    Transparency(#[from] transparency::TransparencyError),
+    
+    #[error("Amendment error: {0}")]
+    Amendment(#[from] amendments::AmendmentError),
+    
+    #[error("Data subject request error: {0}")]
+    DataSubject(#[from] data_subjects::DataSubjectError),
+    
+    #[error("Shared state error: {0}")]
+    SharedState(#[from] shared_state::SharedStateError),
+    
+    #[error("Subscription error: {0}")]
+    Subscription(#[from] subscriptions::SubscriptionError),
+    
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(limits::LimitExceeded),
+    
+    #[error("Policy violation: {0}")]
+    PolicyViolation(context_policy::PolicyViolation),
+    
+    #[error("Key pinning error: {0}")]
+    KeyPinning(#[from] key_pinning::KeyPinningError),
+    
+    #[error("Multisig approval error: {0}")]
+    Multisig(#[from] multisig::MultisigError),
+    
+    #[error("Timestamp error: {0}")]
+    Timestamp(#[from] timestamping::TimestampError),
+    
+    #[error("Schema error: {0}")]
+    Schema(#[from] schemas::SchemaError),
+    
+    #[error("Relay is read-only: {0}")]
+    ReadOnly(maintenance::ReadOnly),
+    
+    #[error("Abuse detection error: {0}")]
+    Abuse(#[from] abuse::AbuseError),
+    
+    #[error("Audit log chain error: {0}")]
+    AuditChain(#[from] audit_chain::ChainError),
+    
+    #[error("Integrity verification error: {0}")]
+    Integrity(#[from] integrity::IntegrityError),
+    
+    #[error("Scheduling error: {0}")]
+    Scheduling(#[from] scheduling::SchedulingError),
+
+    #[error("Delivery error: {0}")]
+    Delivery(#[from] deliveries::DeliveryError),
+
+    #[error("Inbox error: {0}")]
+    Inbox(#[from] inbox::InboxError),
+
+    #[error("Key authentication error: {0}")]
+    KeyAuth(#[from] key_auth::KeyAuthError),
+
+    #[error("Verification check error: {0}")]
+    Check(#[from] checks::CheckError),
+    
     #[error("Message processing error: {0}")]
     ProcessingError(String),
     
@@ -77,23 +294,422 @@ pub enum AppError {
     DatabaseError(#[from] DatabaseError),
 }
 
+impl AppError {
+    /// HTTP status returned for this error
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::InvalidSignature(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidPublicKey(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidContext(_) => StatusCode::BAD_REQUEST,
+            AppError::VerificationFailed => StatusCode::UNAUTHORIZED,
+            AppError::ProofRevoked => StatusCode::FORBIDDEN,
+            AppError::ReplayDetected => StatusCode::CONFLICT,
+            AppError::InviteNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::InviteUnavailable(_) => StatusCode::CONFLICT,
+            AppError::InvalidThread(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidExpiry(_) => StatusCode::BAD_REQUEST,
+            AppError::MessageExpired(_) => StatusCode::GONE,
+            AppError::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+            AppError::MissingCredentials => StatusCode::UNAUTHORIZED,
+            AppError::Authentication(e) => authentication_status(e),
+            AppError::InsufficientScope(_) => StatusCode::FORBIDDEN,
+            AppError::ScopeDenied(_) => StatusCode::FORBIDDEN,
+            AppError::RouteNotAuthorized(_) => StatusCode::FORBIDDEN,
+            AppError::ApiKey(e) => api_key_status(e),
+            AppError::MissingTenant => StatusCode::BAD_REQUEST,
+            AppError::UnknownTenant(_) => StatusCode::FORBIDDEN,
+            AppError::TenantDenied(_) => StatusCode::FORBIDDEN,
+            AppError::Federation(e) => federation_status(e),
+            AppError::Webhook(e) => webhook_status(e),
+            AppError::Transparency(e) => transparency_status(e),
+            AppError::Amendment(e) => amendment_status(e),
+            AppError::DataSubject(e) => data_subject_status(e),
+            AppError::SharedState(e) => shared_state_status(e),
+            AppError::Subscription(e) => subscription_status(e),
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::PolicyViolation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::KeyPinning(e) => key_pinning_status(e),
+            AppError::Multisig(e) => multisig_status(e),
+            AppError::Timestamp(e) => timestamp_status(e),
+            AppError::Schema(e) => schema_status(e),
+            AppError::ReadOnly(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Abuse(e) => abuse_status(e),
+            AppError::AuditChain(e) => audit_chain_status(e),
+            AppError::Integrity(_) => StatusCode::NOT_FOUND,
+            AppError::Scheduling(e) => scheduling_status(e),
+            AppError::Delivery(_) => StatusCode::BAD_REQUEST,
+            AppError::Inbox(_) => StatusCode::UNAUTHORIZED,
+            AppError::KeyAuth(key_auth::KeyAuthError::Disabled) => StatusCode::NOT_FOUND,
+            AppError::KeyAuth(_) => StatusCode::UNAUTHORIZED,
+            AppError::Check(checks::CheckError::Rejected { .. }) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Check(checks::CheckError::Unknown(_)) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::ProcessingError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Machine-readable code identifying this error
+    pub fn code(&self) -> ErrorCode {
+        use abuse::AbuseError;
+        use amendments::AmendmentError;
+        use api_keys::ApiKeyError;
+        use audit_chain::ChainError;
+        use federation::FederationError;
+        use jwt_validator::JwtValidationError;
+        use data_subjects::DataSubjectError;
+        use deliveries::DeliveryError;
+        use inbox::InboxError;
+        use key_auth::KeyAuthError;
+        use checks::CheckError;
+        use key_pinning::KeyPinningError;
+        use multisig::MultisigError;
+        use scheduling::SchedulingError;
+        use schemas::SchemaError;
+        use shared_state::SharedStateError;
+        use subscriptions::SubscriptionError;
+        use timestamping::TimestampError;
+        use transparency::TransparencyError;
+        use webhooks::WebhookError;
+        match self {
+            AppError::InvalidSignature(_) => ErrorCode::InvalidSignature,
+            AppError::InvalidPublicKey(_) => ErrorCode::InvalidPublicKey,
+            AppError::InvalidContext(_) => ErrorCode::InvalidContext,
+            AppError::VerificationFailed => ErrorCode::VerificationFailed,
+            AppError::ProofRevoked => ErrorCode::ProofRevoked,
+            AppError::ReplayDetected => ErrorCode::ReplayDetected,
+            AppError::InviteNotFound(_) => ErrorCode::InviteNotFound,
+            AppError::InviteUnavailable(_) => ErrorCode::InviteUnavailable,
+            AppError::InvalidThread(_) => ErrorCode::InvalidThread,
+            AppError::InvalidExpiry(_) => ErrorCode::InvalidExpiry,
+            AppError::MessageExpired(_) => ErrorCode::MessageExpired,
+            AppError::InvalidQuery(_) => ErrorCode::InvalidQuery,
+            AppError::MissingCredentials => ErrorCode::MissingCredentials,
+            AppError::Authentication(JwtValidationError::Expired) => ErrorCode::TokenExpired,
+            AppError::Authentication(JwtValidationError::Introspection(_)) => ErrorCode::IntrospectionUnavailable,
+            AppError::Authentication(_) => ErrorCode::InvalidToken,
+            AppError::InsufficientScope(_) => ErrorCode::InsufficientScope,
+            AppError::ScopeDenied(_) => ErrorCode::InsufficientScope,
+            AppError::RouteNotAuthorized(_) => ErrorCode::RouteNotAuthorized,
+            AppError::ApiKey(e) => match e {
+                ApiKeyError::Disabled => ErrorCode::ApiKeysDisabled,
+                ApiKeyError::Invalid => ErrorCode::InvalidApiKey,
+                ApiKeyError::RateLimited { .. } => ErrorCode::RateLimited,
+                ApiKeyError::NotFound(_) => ErrorCode::ApiKeyNotFound,
+            },
+            AppError::MissingTenant => ErrorCode::MissingTenant,
+            AppError::UnknownTenant(_) => ErrorCode::UnknownTenant,
+            AppError::TenantDenied(_) => ErrorCode::TenantDenied,
+            AppError::Federation(e) => match e {
+                FederationError::Disabled => ErrorCode::FederationDisabled,
+                FederationError::Config(_) => ErrorCode::ConfigurationError,
+                FederationError::UnknownPeer(_) => ErrorCode::UnknownPeer,
+                FederationError::InvalidSignature(_) => ErrorCode::InvalidPeerSignature,
+                FederationError::ClientCertificateRequired => ErrorCode::ClientCertificateRequired,
+                FederationError::HopLimitExceeded(_) => ErrorCode::HopLimitExceeded,
+                FederationError::LoopDetected(_) => ErrorCode::LoopDetected,
+                FederationError::Transport(_) => ErrorCode::PeerUnavailable,
+            },
+            AppError::Webhook(e) => match e {
+                WebhookError::Disabled => ErrorCode::WebhooksDisabled,
+                WebhookError::Config(_) => ErrorCode::ConfigurationError,
+                WebhookError::InvalidUrl(_) => ErrorCode::InvalidWebhookUrl,
+                WebhookError::NotFound(_) => ErrorCode::WebhookNotFound,
+            },
+            AppError::Transparency(e) => match e {
+                TransparencyError::Disabled => ErrorCode::TransparencyDisabled,
+                TransparencyError::Config(_) => ErrorCode::ConfigurationError,
+                TransparencyError::NotLogged(_) => ErrorCode::LogEntryNotFound,
+                TransparencyError::InvalidTreeSize(_) => ErrorCode::InvalidTreeSize,
+            },
+            AppError::Amendment(e) => match e {
+                AmendmentError::NotOriginalSender => ErrorCode::NotOriginalSender,
+                AmendmentError::MessageDeleted(_) => ErrorCode::MessageDeleted,
+                AmendmentError::VersionConflict { .. } => ErrorCode::AmendmentConflict,
+            },
+            AppError::DataSubject(e) => match e {
+                DataSubjectError::Disabled => ErrorCode::DataSubjectsDisabled,
+                DataSubjectError::Config(_) => ErrorCode::ConfigurationError,
+                DataSubjectError::MissingSubject => ErrorCode::MissingDataSubject,
+                DataSubjectError::NotFound(_) => ErrorCode::ErasureNotFound,
+                DataSubjectError::NotScheduled(_) => ErrorCode::ErasureNotScheduled,
+            },
+            AppError::SharedState(e) => match e {
+                SharedStateError::Config(_) => ErrorCode::ConfigurationError,
+                SharedStateError::Unavailable(_) => ErrorCode::SharedStateUnavailable,
+            },
+            AppError::Subscription(e) => match e {
+                SubscriptionError::Disabled => ErrorCode::SubscriptionsDisabled,
+                SubscriptionError::Config(_) => ErrorCode::ConfigurationError,
+                SubscriptionError::InvalidResumeToken(_) => ErrorCode::InvalidResumeToken,
+                SubscriptionError::Backplane(_) => ErrorCode::ProcessingError,
+            },
+            AppError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            AppError::PolicyViolation(_) => ErrorCode::PolicyViolation,
+            AppError::KeyPinning(e) => match e {
+                KeyPinningError::Disabled => ErrorCode::KeyPinningDisabled,
+                KeyPinningError::KeyChanged(_) => ErrorCode::KeyChanged,
+                KeyPinningError::NotPinned(_) => ErrorCode::KeyNotPinned,
+                KeyPinningError::InvalidRotation(_) => ErrorCode::InvalidKeyRotation,
+            },
+            AppError::Multisig(e) => match e {
+                MultisigError::NotFound(_) => ErrorCode::ApprovalNotFound,
+                MultisigError::InvalidPolicy(_) => ErrorCode::InvalidMultisigPolicy,
+                MultisigError::NotASigner => ErrorCode::NotASigner,
+                MultisigError::AlreadySigned => ErrorCode::AlreadySigned,
+                MultisigError::NotPending(_) => ErrorCode::ApprovalNotPending,
+            },
+            AppError::Timestamp(e) => match e {
+                TimestampError::NotFound(_) => ErrorCode::TimestampNotFound,
+                TimestampError::Config(_) | TimestampError::Request(_) | TimestampError::Rejected { .. } | TimestampError::Malformed(_) | TimestampError::Mismatch(_) => ErrorCode::TimestampFailed,
+            },
+            AppError::Schema(e) => match e {
+                SchemaError::NotFound(_) => ErrorCode::SchemaNotFound,
+                SchemaError::Invalid(_) => ErrorCode::InvalidSchema,
+                SchemaError::Validation(_) => ErrorCode::SchemaValidationFailed,
+            },
+            AppError::ReadOnly(_) => ErrorCode::ReadOnlyMode,
+            AppError::Abuse(e) => match e {
+                AbuseError::Disabled => ErrorCode::AbuseDetectionDisabled,
+                AbuseError::Quarantined(_) => ErrorCode::SenderQuarantined,
+                AbuseError::NotQuarantined(_) => ErrorCode::SenderNotQuarantined,
+            },
+            AppError::AuditChain(e) => match e {
+                ChainError::Disabled => ErrorCode::AuditChainDisabled,
+                ChainError::Io(_) => ErrorCode::ProcessingError,
+                _ => ErrorCode::AuditChainBroken,
+            },
+            AppError::Integrity(integrity::IntegrityError::Disabled) => ErrorCode::IntegrityCheckDisabled,
+            AppError::Scheduling(e) => match e {
+                SchedulingError::Disabled => ErrorCode::SchedulingDisabled,
+                SchedulingError::NotFound(_) => ErrorCode::ScheduledMessageNotFound,
+                SchedulingError::InvalidSchedule(_) => ErrorCode::InvalidSchedule,
+                SchedulingError::NotScheduled(_) => ErrorCode::ScheduledMessageNotPending,
+            },
+            AppError::Delivery(e) => match e {
+                DeliveryError::InvalidRecipient(_) => ErrorCode::InvalidRecipient,
+                DeliveryError::InvalidAck(_) => ErrorCode::InvalidAck,
+            },
+            AppError::Inbox(e) => match e {
+                InboxError::ChallengeFailed(_) => ErrorCode::InboxChallengeFailed,
+            },
+            AppError::KeyAuth(e) => match e {
+                KeyAuthError::Disabled => ErrorCode::KeyAuthDisabled,
+                KeyAuthError::UnknownKey => ErrorCode::UnknownKey,
+                KeyAuthError::ChallengeFailed(_) => ErrorCode::KeyChallengeFailed,
+                KeyAuthError::InvalidSession => ErrorCode::InvalidSession,
+            },
+            AppError::Check(e) => match e {
+                CheckError::Rejected { .. } => ErrorCode::MessageRejected,
+                CheckError::Unknown(_) => ErrorCode::ProcessingError,
+            },
+            AppError::ProcessingError(_) => ErrorCode::ProcessingError,
+            AppError::DatabaseError(e) => match e {
+                DatabaseError::MessageNotFound(_) => ErrorCode::MessageNotFound,
+                DatabaseError::InvalidGroupId(_) => ErrorCode::InvalidGroupId,
+                DatabaseError::ProofAlreadyRevoked(_) => ErrorCode::ProofAlreadyRevoked,
+                DatabaseError::InviteNotFound(_) => ErrorCode::InviteNotFound,
+                DatabaseError::InviteUnavailable(_) => ErrorCode::InviteUnavailable,
+                DatabaseError::WebhookNotFound(_) => ErrorCode::WebhookNotFound,
+                DatabaseError::ApiKeyNotFound(_) => ErrorCode::ApiKeyNotFound,
+                _ => ErrorCode::DatabaseError,
+            },
+        }
+    }
+
+    /// Structured context returned alongside the error, if any
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            // Size violations name the exceeded limit so clients can adapt
+            AppError::PayloadTooLarge(exceeded) => serde_json::to_value(exceeded).ok(),
+            AppError::PolicyViolation(violation) => serde_json::to_value(violation).ok(),
+            // Schema violations name each failing location in the body
+            AppError::Schema(schemas::SchemaError::Validation(violation)) => serde_json::to_value(violation).ok(),
+            // Key changes name both keys so the client can show them to its user
+            AppError::KeyPinning(key_pinning::KeyPinningError::KeyChanged(mismatch)) => serde_json::to_value(mismatch).ok(),
+            // Scope denials name the missing scopes so callers can request them
+            AppError::ScopeDenied(denial) => serde_json::to_value(denial).ok(),
+            AppError::RouteNotAuthorized(route) => Some(serde_json::json!({ "route": route })),
+            AppError::ApiKey(api_keys::ApiKeyError::RateLimited { requests_per_minute, .. }) => {
+                Some(serde_json::json!({ "requests_per_minute": requests_per_minute }))
+            }
+            // Read-only refusals say why and when to retry
+            AppError::ReadOnly(read_only) => serde_json::to_value(read_only).ok(),
+            // Quarantined senders learn why and until when
+            AppError::Abuse(abuse::AbuseError::Quarantined(quarantine)) => serde_json::to_value(quarantine).ok(),
+            AppError::Federation(federation::FederationError::HopLimitExceeded(max_hops)) => {
+                Some(serde_json::json!({ "max_hops": max_hops }))
+            }
+            _ => None,
+        }
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            AppError::InvalidSignature(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            AppError::InvalidPublicKey(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            AppError::InvalidContext(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            AppError::VerificationFailed => (StatusCode::UNAUTHORIZED, self.to_string()),
-            AppError::ProofRevoked => (StatusCode::FORBIDDEN, self.to_string()),
-            AppError::ProcessingError(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-            AppError::DatabaseError(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-        };
+        ErrorBody::new(self.code(), self.to_string(), self.details()).into_response_with(self.status())
+    }
+}
+
+/// HTTP status for a token validation failure
+fn authentication_status(error: &jwt_validator::JwtValidationError) -> StatusCode {
+    use jwt_validator::JwtValidationError;
+    match error {
+        JwtValidationError::InvalidFormat | JwtValidationError::MissingClaim(_) => StatusCode::BAD_REQUEST,
+        // The issuer could not say whether the token is active
+        JwtValidationError::Introspection(_) => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::UNAUTHORIZED,
+    }
+}
+
+/// HTTP status for an API key failure
+fn api_key_status(error: &api_keys::ApiKeyError) -> StatusCode {
+    use api_keys::ApiKeyError;
+    match error {
+        ApiKeyError::Disabled | ApiKeyError::NotFound(_) => StatusCode::NOT_FOUND,
+        ApiKeyError::Invalid => StatusCode::UNAUTHORIZED,
+        ApiKeyError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+    }
+}
+
+/// HTTP status for a federation failure
+fn federation_status(error: &federation::FederationError) -> StatusCode {
+    use federation::FederationError;
+    match error {
+        FederationError::Disabled => StatusCode::NOT_FOUND,
+        FederationError::UnknownPeer(_) | FederationError::ClientCertificateRequired => StatusCode::FORBIDDEN,
+        FederationError::InvalidSignature(_) => StatusCode::UNAUTHORIZED,
+        FederationError::HopLimitExceeded(_) | FederationError::LoopDetected(_) => StatusCode::LOOP_DETECTED,
+        FederationError::Transport(_) => StatusCode::BAD_GATEWAY,
+        FederationError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// HTTP status for a webhook failure
+fn webhook_status(error: &webhooks::WebhookError) -> StatusCode {
+    use webhooks::WebhookError;
+    match error {
+        WebhookError::Disabled | WebhookError::NotFound(_) => StatusCode::NOT_FOUND,
+        WebhookError::InvalidUrl(_) => StatusCode::BAD_REQUEST,
+        WebhookError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// HTTP status for a rejected amendment
+fn amendment_status(error: &amendments::AmendmentError) -> StatusCode {
+    use amendments::AmendmentError;
+    match error {
+        AmendmentError::NotOriginalSender => StatusCode::FORBIDDEN,
+        AmendmentError::MessageDeleted(_) | AmendmentError::VersionConflict { .. } => StatusCode::CONFLICT,
+    }
+}
+
+/// HTTP status for a shared state failure
+fn shared_state_status(error: &shared_state::SharedStateError) -> StatusCode {
+    use shared_state::SharedStateError;
+    match error {
+        SharedStateError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        SharedStateError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
 
-        let body = Json(serde_json::json!({
-            "error": error_message
-        }));
+/// HTTP status for a live subscription failure
+fn subscription_status(error: &subscriptions::SubscriptionError) -> StatusCode {
+    use subscriptions::SubscriptionError;
+    match error {
+        SubscriptionError::Disabled => StatusCode::NOT_FOUND,
+        SubscriptionError::InvalidResumeToken(_) => StatusCode::BAD_REQUEST,
+        SubscriptionError::Config(_) | SubscriptionError::Backplane(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
 
-        (status, body).into_response()
+/// HTTP status for a key pinning failure
+fn key_pinning_status(error: &key_pinning::KeyPinningError) -> StatusCode {
+    use key_pinning::KeyPinningError;
+    match error {
+        KeyPinningError::Disabled | KeyPinningError::NotPinned(_) => StatusCode::NOT_FOUND,
+        KeyPinningError::KeyChanged(_) => StatusCode::CONFLICT,
+        KeyPinningError::InvalidRotation(_) => StatusCode::BAD_REQUEST,
+    }
+}
+
+/// HTTP status for a multi-signature approval failure
+fn multisig_status(error: &multisig::MultisigError) -> StatusCode {
+    use multisig::MultisigError;
+    match error {
+        MultisigError::NotFound(_) => StatusCode::NOT_FOUND,
+        MultisigError::InvalidPolicy(_) => StatusCode::BAD_REQUEST,
+        MultisigError::NotASigner => StatusCode::FORBIDDEN,
+        MultisigError::AlreadySigned | MultisigError::NotPending(_) => StatusCode::CONFLICT,
+    }
+}
+
+/// HTTP status for a scheduled message failure
+fn scheduling_status(error: &scheduling::SchedulingError) -> StatusCode {
+    use scheduling::SchedulingError;
+    match error {
+        SchedulingError::Disabled | SchedulingError::NotFound(_) => StatusCode::NOT_FOUND,
+        SchedulingError::InvalidSchedule(_) => StatusCode::BAD_REQUEST,
+        SchedulingError::NotScheduled(_) => StatusCode::CONFLICT,
+    }
+}
+
+/// HTTP status for a timestamping failure
+fn timestamp_status(error: &timestamping::TimestampError) -> StatusCode {
+    use timestamping::TimestampError;
+    match error {
+        TimestampError::NotFound(_) => StatusCode::NOT_FOUND,
+        TimestampError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        TimestampError::Request(_) | TimestampError::Rejected { .. } | TimestampError::Malformed(_) | TimestampError::Mismatch(_) => StatusCode::BAD_GATEWAY,
+    }
+}
+
+/// HTTP status for a body schema failure
+fn schema_status(error: &schemas::SchemaError) -> StatusCode {
+    use schemas::SchemaError;
+    match error {
+        SchemaError::NotFound(_) => StatusCode::NOT_FOUND,
+        SchemaError::Invalid(_) | SchemaError::Validation(_) => StatusCode::BAD_REQUEST,
+    }
+}
+
+/// HTTP status for an abuse detection failure
+fn abuse_status(error: &abuse::AbuseError) -> StatusCode {
+    use abuse::{AbuseError, AbuseReason};
+    match error {
+        AbuseError::Disabled | AbuseError::NotQuarantined(_) => StatusCode::NOT_FOUND,
+        AbuseError::Quarantined(quarantine) if quarantine.reason == AbuseReason::Burst => StatusCode::TOO_MANY_REQUESTS,
+        AbuseError::Quarantined(_) => StatusCode::FORBIDDEN,
+    }
+}
+
+/// HTTP status for an audit log chain failure
+fn audit_chain_status(error: &audit_chain::ChainError) -> StatusCode {
+    use audit_chain::ChainError;
+    match error {
+        ChainError::Disabled => StatusCode::NOT_FOUND,
+        ChainError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::CONFLICT,
+    }
+}
+
+/// HTTP status for a data subject request failure
+fn data_subject_status(error: &data_subjects::DataSubjectError) -> StatusCode {
+    use data_subjects::DataSubjectError;
+    match error {
+        DataSubjectError::Disabled | DataSubjectError::NotFound(_) => StatusCode::NOT_FOUND,
+        DataSubjectError::MissingSubject => StatusCode::BAD_REQUEST,
+        DataSubjectError::NotScheduled(_) => StatusCode::CONFLICT,
+        DataSubjectError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// HTTP status for a transparency log failure
+fn transparency_status(error: &transparency::TransparencyError) -> StatusCode {
+    use transparency::TransparencyError;
+    match error {
+        TransparencyError::Disabled | TransparencyError::NotLogged(_) => StatusCode::NOT_FOUND,
+        TransparencyError::InvalidTreeSize(_) => StatusCode::BAD_REQUEST,
+        TransparencyError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
     }
 }
 
@@ -102,99 +718,285 @@ impl IntoResponse for AppError {
 /// This function is decoupled from the web framework and can be unit tested
 /// independently. It performs the core business logic of message verification.
 /// 
-/// If a database is provided, it will also check if the proof has been revoked.
+/// The message runs through the installed [`checks::VerificationPipeline`].
+/// If a database is provided, the revocation check also looks the proof up in
+/// the revocation list.
 #[instrument(skip_all, fields(sender = %message.sender))]
 pub async fn process_and_verify_message(
     message: &Message, 
     db: Option<&Arc<Database>>
+) -> Result<(), AppError> {
+    process_and_verify_message_with_policy(message, db, config::hybrid_policy()).await
+}
+
+/// Hex length of a raw Ed25519 signature
+const SIGNATURE_HEX_LENGTH: usize = 128;
+
+/// Process and verify a message under an explicit hybrid proof policy
+///
+/// Messages carrying a `pqc` section are verified as hybrid proofs; messages
+/// without one are rejected under [`HybridPolicy::Strict`].
+#[instrument(skip_all, fields(sender = %message.sender, policy = %policy))]
+pub async fn process_and_verify_message_with_policy(
+    message: &Message, 
+    db: Option<&Arc<Database>>,
+    policy: HybridPolicy,
 ) -> Result<(), AppError> {
     info!("Processing message verification");
 
-    // If a database is provided, check if the proof has been revoked
-    if let Some(db) = db {
-        // Check if REVOCATION_CHECK_ENABLED environment variable is set
-        if std::env::var("REVOCATION_CHECK_ENABLED").unwrap_or_else(|_| "false".to_string()) == "true" {
-            info!("Checking if proof has been revoked");
-            
-            // Check if the proof is in the revocation list
-            if db.is_proof_revoked(&message.proof).await? {
-                warn!("Proof has been revoked: {}", message.proof);
-                return Err(AppError::ProofRevoked);
-            }
-        }
-    }
+    checks::current().run(message, &checks::CheckContext { db, policy }).await?;
 
-    // Parse the public key from hex
-    let sender_bytes = hex::decode(&message.sender)
-        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid hex encoding: {}", e)))?;
-    
-    if sender_bytes.len() != 32 {
-        return Err(AppError::InvalidPublicKey("Public key must be 32 bytes".to_string()));
-    }
-    
-    let mut pubkey_bytes = [0u8; 32];
-    pubkey_bytes.copy_from_slice(&sender_bytes);
+    info!("Proof successfully verified");
+    Ok(())
+}
+
+/// Decode a message's sender key
+///
+/// Keys are a fixed size: they are decoded onto the stack once the length checks out.
+pub(crate) fn parse_sender(message: &Message) -> Result<[u8; 32], AppError> {
+    decoding::decode_fixed(&message.sender).map_err(|e| match e {
+        decoding::HexError::Length { .. } => AppError::InvalidPublicKey("Public key must be 32 bytes".to_string()),
+        decoding::HexError::Encoding(e) => AppError::InvalidPublicKey(format!("Invalid hex encoding: {}", e)),
+    })
+}
+
+/// Decode a message and verify its proof
+pub(crate) fn verify_message_proof(message: &Message, policy: HybridPolicy) -> Result<(), AppError> {
+    let pubkey_bytes = parse_sender(message)?;
     let public_key = PublicKey::from_bytes(&pubkey_bytes)
         .map_err(|e| AppError::InvalidPublicKey(format!("Invalid public key: {}", e)))?;
 
+    // Variable-length parts are decoded into this thread's reusable buffers
+    decoding::with_scratch(|scratch| verify_decoded(message, &pubkey_bytes, &public_key, scratch, policy))
+}
+
+/// Verify a message's proof once its sender key is decoded
+fn verify_decoded(
+    message: &Message,
+    pubkey_bytes: &[u8; 32],
+    public_key: &PublicKey,
+    scratch: &mut decoding::Scratch,
+    policy: HybridPolicy,
+) -> Result<(), AppError> {
     // Parse the context from hex
-    let context = hex::decode(&message.context)
+    decoding::decode_into(&message.context, &mut scratch.context)
         .map_err(|e| AppError::InvalidContext(format!("Invalid hex encoding: {}", e)))?;
-
-    // Parse the signature from hex
-    let proof_bytes = hex::decode(&message.proof)
-        .map_err(|e| AppError::InvalidSignature(format!("Invalid hex encoding: {}", e)))?;
-    
-    if proof_bytes.len() != 64 {
+    let context = &scratch.context;
+
+    // A raw signature is exactly 64 bytes, which an envelope never is
+    let sig_bytes: [u8; 64] = if message.proof.len() == SIGNATURE_HEX_LENGTH {
+        decoding::decode_fixed(&message.proof)
+            .map_err(|e| AppError::InvalidSignature(e.to_string()))?
+    } else {
+        decoding::decode_into(&message.proof, &mut scratch.proof)
+            .map_err(|e| AppError::InvalidSignature(format!("Invalid hex encoding: {}", e)))?;
+        if ProofEnvelope::is_envelope(&scratch.proof) {
+            verify_proof_envelope(message, public_key, context, &scratch.proof, policy)?;
+            info!("Proof envelope successfully verified");
+            return Ok(());
+        }
+        if !config::legacy_proofs_accepted() {
+            return Err(legacy_proof_rejected());
+        }
         return Err(AppError::InvalidSignature("Signature must be 64 bytes".to_string()));
+    };
+    if !config::legacy_proofs_accepted() {
+        return Err(legacy_proof_rejected());
     }
-    
-    let mut sig_bytes = [0u8; 64];
-    sig_bytes.copy_from_slice(&proof_bytes);
+
     let signature = Signature::from_bytes(&sig_bytes)
         .map_err(|e| AppError::InvalidSignature(format!("Invalid signature: {}", e)))?;
 
-    // Use the improved protocol function with Result-based error handling!
-    verify_proof_result(&public_key, &context, &signature)
-        .map_err(|e| match e {
-            ProofError::VerificationFailed(_) => AppError::VerificationFailed,
-            _ => AppError::ProcessingError(format!("Verification error: {}", e)),
-        })?;
+    let result = match &message.pqc {
+        Some(pqc) => {
+            decoding::decode_into(&pqc.public_key, &mut scratch.pqc_public_key)
+                .map_err(|e| AppError::InvalidPublicKey(format!("Invalid PQC key hex encoding: {}", e)))?;
+            decoding::decode_into(&pqc.proof, &mut scratch.pqc_proof)
+                .map_err(|e| AppError::InvalidSignature(format!("Invalid PQC proof hex encoding: {}", e)))?;
+            let hybrid_key = HybridPublicKey::from_parts(pubkey_bytes, &scratch.pqc_public_key)
+                .map_err(|e| AppError::InvalidPublicKey(e.to_string()))?;
+            let hybrid_sig = HybridSignature::from_parts(&sig_bytes, &scratch.pqc_proof)
+                .map_err(|e| AppError::InvalidSignature(e.to_string()))?;
+            verify_hybrid_proof(&hybrid_key, context, &hybrid_sig, policy)
+        }
+        None if policy == HybridPolicy::Strict => {
+            return Err(AppError::InvalidSignature(
+                "Hybrid proof required: message carries no post-quantum signature".to_string(),
+            ));
+        }
+        // Use the improved protocol function with Result-based error handling!
+        None => verify_proof_result(public_key, context, &signature),
+    };
 
-    info!("Proof successfully verified");
-    Ok(())
+    result.map_err(verification_error)
+}
+
+/// ML-DSA-65 key of a message's hybrid proof (lowercase hex), from its `pqc` section or envelope
+pub(crate) fn pqc_public_key(message: &Message) -> Option<String> {
+    match &message.pqc {
+        Some(pqc) => Some(pqc.public_key.to_ascii_lowercase()),
+        None => ProofEnvelope::from_hex(&message.proof)
+            .ok()
+            .and_then(|envelope| envelope.pqc_public_key().map(hex::encode)),
+    }
+}
+
+/// Error for a raw signature once only proof envelopes are accepted
+fn legacy_proof_rejected() -> AppError {
+    AppError::InvalidSignature("Raw signatures are no longer accepted: send a proof envelope".to_string())
+}
+
+/// Verify an enveloped proof for a message
+///
+/// The envelope must be signed by the message's sender and, under
+/// [`HybridPolicy::Strict`], declare a hybrid algorithm. An envelope carries
+/// its own post-quantum signature, so the message must not have a `pqc` section.
+fn verify_proof_envelope(
+    message: &Message,
+    sender: &PublicKey,
+    context: &[u8],
+    proof_bytes: &[u8],
+    policy: HybridPolicy,
+) -> Result<(), AppError> {
+    let envelope = ProofEnvelope::from_bytes(proof_bytes)
+        .map_err(|e| AppError::InvalidSignature(format!("Invalid proof envelope: {}", e)))?;
+    if message.pqc.is_some() {
+        return Err(AppError::InvalidSignature(
+            "A proof envelope carries its own post-quantum signature: omit `pqc`".to_string(),
+        ));
+    }
+    let signer = envelope
+        .ed25519_public_key()
+        .map_err(|e| AppError::InvalidPublicKey(e.to_string()))?;
+    if signer != *sender {
+        return Err(AppError::InvalidPublicKey("Proof envelope was not signed by the sender".to_string()));
+    }
+    if policy == HybridPolicy::Strict && envelope.algorithm != ProofAlgorithm::HybridEd25519MlDsa65 {
+        return Err(AppError::InvalidSignature(format!(
+            "Hybrid proof required: envelope uses {}",
+            envelope.algorithm
+        )));
+    }
+
+    envelope.verify(context).map_err(verification_error)
+}
+
+/// Map a proof verification failure to the relay's error
+fn verification_error(error: ProofError) -> AppError {
+    match error {
+        ProofError::VerificationFailed(_) | ProofError::PqcVerificationFailed => AppError::VerificationFailed,
+        ProofError::InvalidData(details) => AppError::InvalidSignature(details),
+        _ => AppError::ProcessingError(format!("Verification error: {}", error)),
+    }
 }
 
 /// Create the application router with database state
 pub fn create_app(db: Arc<Database>) -> Router {
-    Router::new()
+    let api = Router::new()
         .route("/relay", post(relay_handler))
         .route("/messages/:group_id", get(get_messages_handler))
-        .route("/message/:message_id", get(get_message_by_id_handler))
+        .route("/message/:message_id", get(get_message_by_id_handler).delete(delete_message_handler))
+        .route("/senders/:pubkey/messages", get(get_messages_by_sender_handler))
+        .nest("/revocation", revocation::revocation_routes())
+        .nest("/invites", invites::invite_routes())
+        .nest("/webhooks", webhooks::webhook_routes())
+        .nest("/quarantine", quarantine::quarantine_routes())
+        .nest("/admin/compliance", compliance_audit::compliance_routes())
+        .nest("/admin/data-subjects", data_subjects::data_subject_routes())
+        .nest("/admin/maintenance", maintenance::maintenance_routes())
+        .nest("/admin/abuse", abuse::abuse_routes())
+        .nest("/admin/integrity", integrity::integrity_routes())
+        .merge(receipts::receipt_routes())
+        .merge(amendments::amendment_routes())
+        .merge(detached_proofs::detached_proof_routes())
+        .merge(derivations::derivation_routes())
+        .merge(proof_chains::proof_chain_routes())
+        .merge(multisig::multisig_routes())
+        .merge(scheduling::scheduling_routes())
+        .merge(deliveries::delivery_routes())
+        .merge(inbox::inbox_routes())
+        .merge(key_auth::key_auth_routes())
+        .merge(timestamping::timestamp_routes())
+        .merge(schemas::schema_routes())
+        .merge(evidence::evidence_routes())
+        .merge(threads::thread_routes())
+        .merge(search::search_routes())
+        .merge(export::export_routes())
+        .merge(subscriptions::subscription_routes())
+        .nest("/transparency", transparency::transparency_routes());
+
+    let app = Router::new()
         .route("/health", get(health_handler))
         .route("/ready", get(ready_handler))
-        .nest("/revocation", revocation::revocation_routes())
-        .with_state(db)
+        .nest("/federation", federation::federation_routes())
+        .merge(versioning::versioned(api))
+        .merge(openapi::openapi_routes())
+        .with_state(db);
+
+    // Refuse writes while the relay is in read-only maintenance mode
+    let app = app.layer(axum::middleware::from_fn(maintenance::reject_writes_when_read_only));
+
+    limits::with_request_limits(app, limits::RequestLimits::default())
+        .layer(axum::middleware::from_fn(api_error::structured_errors))
+        .layer(axum::middleware::from_fn(request_id::propagate_request_id))
 }
 
 /// Create the application router with security enhancements
 /// This includes security headers and tracing (rate limiting configured separately)
 pub fn create_app_with_security(db: Arc<Database>) -> Router {
-    use tower_http::trace::TraceLayer;
     use tower_http::cors::CorsLayer;
     use tower_http::set_header::SetResponseHeaderLayer;
 
     // Create the base router
-    Router::new()
+    let api = Router::new()
         .route("/relay", post(relay_handler))
         .route("/messages/:group_id", get(get_messages_handler))
-        .route("/message/:message_id", get(get_message_by_id_handler))
+        .route("/message/:message_id", get(get_message_by_id_handler).delete(delete_message_handler))
+        .route("/senders/:pubkey/messages", get(get_messages_by_sender_handler))
+        .nest("/revocation", revocation::revocation_routes())
+        .nest("/invites", invites::invite_routes())
+        .nest("/webhooks", webhooks::webhook_routes())
+        .nest("/quarantine", quarantine::quarantine_routes())
+        .nest("/admin/compliance", compliance_audit::compliance_routes())
+        .nest("/admin/data-subjects", data_subjects::data_subject_routes())
+        .nest("/admin/maintenance", maintenance::maintenance_routes())
+        .nest("/admin/abuse", abuse::abuse_routes())
+        .nest("/admin/integrity", integrity::integrity_routes())
+        .merge(receipts::receipt_routes())
+        .merge(amendments::amendment_routes())
+        .merge(detached_proofs::detached_proof_routes())
+        .merge(derivations::derivation_routes())
+        .merge(proof_chains::proof_chain_routes())
+        .merge(multisig::multisig_routes())
+        .merge(scheduling::scheduling_routes())
+        .merge(deliveries::delivery_routes())
+        .merge(inbox::inbox_routes())
+        .merge(key_auth::key_auth_routes())
+        .merge(timestamping::timestamp_routes())
+        .merge(schemas::schema_routes())
+        .merge(evidence::evidence_routes())
+        .merge(threads::thread_routes())
+        .merge(search::search_routes())
+        .merge(export::export_routes())
+        .merge(subscriptions::subscription_routes())
+        .nest("/transparency", transparency::transparency_routes());
+
+    let routes = Router::new()
         .route("/health", get(health_handler))
         .route("/ready", get(ready_handler))
-        .nest("/revocation", revocation::revocation_routes())
-        .with_state(db)
+        .nest("/federation", federation::federation_routes())
+        .merge(versioning::versioned(api))
+        .merge(openapi::openapi_routes())
+        .with_state(db);
+
+    // Refuse writes while the relay is in read-only maintenance mode
+    let routes = routes.layer(axum::middleware::from_fn(maintenance::reject_writes_when_read_only));
+
+    limits::with_request_limits(routes, limits::RequestLimits::default())
+        .layer(axum::middleware::from_fn(api_error::structured_errors))
+        .layer(axum::middleware::from_fn(request_id::propagate_request_id))
         // Apply security layers
-        .layer(TraceLayer::new_for_http())
+        .layer(log_redaction::trace_layer())
         // Security headers
         .layer(SetResponseHeaderLayer::if_not_present(
             axum::http::header::STRICT_TRANSPORT_SECURITY,
@@ -215,7 +1017,6 @@ pub fn create_app_with_security(db: Arc<Database>) -> Router {
 /// Create the minimal application router with no middleware at all
 /// This is for debugging purposes only
 pub fn create_app_minimal(db: Arc<Database>) -> Router {
-    use tower_http::trace::TraceLayer;
     use tower_http::set_header::SetResponseHeaderLayer;
     use tower_http::cors::CorsLayer;
     use tower_governor::{GovernorLayer, governor::GovernorConfigBuilder, key_extractor::GlobalKeyExtractor};
@@ -234,7 +1035,7 @@ pub fn create_app_minimal(db: Arc<Database>) -> Router {
         .layer(GovernorLayer {
             config: std::sync::Arc::new(governor_conf),
         })
-        .layer(TraceLayer::new_for_http())
+        .layer(log_redaction::trace_layer())
         .layer(SetResponseHeaderLayer::if_not_present(
             axum::http::header::STRICT_TRANSPORT_SECURITY,
             axum::http::HeaderValue::from_static("max-age=63072000; includeSubDomains"),
@@ -253,21 +1054,59 @@ pub fn create_app_minimal(db: Arc<Database>) -> Router {
 /// Create the basic application router without rate limiting or authentication
 /// This is suitable for debugging and testing
 pub fn create_app_basic(db: Arc<Database>) -> Router {
-    use tower_http::trace::TraceLayer;
     use tower_http::cors::CorsLayer;
     use tower_http::set_header::SetResponseHeaderLayer;
 
     // Create the base router
-    Router::new()
+    let api = Router::new()
         .route("/relay", post(relay_handler))
         .route("/messages/:group_id", get(get_messages_handler))
-        .route("/message/:message_id", get(get_message_by_id_handler))
+        .route("/message/:message_id", get(get_message_by_id_handler).delete(delete_message_handler))
+        .route("/senders/:pubkey/messages", get(get_messages_by_sender_handler))
+        .nest("/revocation", revocation::revocation_routes())
+        .nest("/invites", invites::invite_routes())
+        .nest("/webhooks", webhooks::webhook_routes())
+        .nest("/quarantine", quarantine::quarantine_routes())
+        .nest("/admin/compliance", compliance_audit::compliance_routes())
+        .nest("/admin/data-subjects", data_subjects::data_subject_routes())
+        .nest("/admin/maintenance", maintenance::maintenance_routes())
+        .nest("/admin/abuse", abuse::abuse_routes())
+        .nest("/admin/integrity", integrity::integrity_routes())
+        .merge(receipts::receipt_routes())
+        .merge(amendments::amendment_routes())
+        .merge(detached_proofs::detached_proof_routes())
+        .merge(derivations::derivation_routes())
+        .merge(proof_chains::proof_chain_routes())
+        .merge(multisig::multisig_routes())
+        .merge(scheduling::scheduling_routes())
+        .merge(deliveries::delivery_routes())
+        .merge(inbox::inbox_routes())
+        .merge(key_auth::key_auth_routes())
+        .merge(timestamping::timestamp_routes())
+        .merge(schemas::schema_routes())
+        .merge(evidence::evidence_routes())
+        .merge(threads::thread_routes())
+        .merge(search::search_routes())
+        .merge(export::export_routes())
+        .merge(subscriptions::subscription_routes())
+        .nest("/transparency", transparency::transparency_routes());
+
+    let routes = Router::new()
         .route("/health", get(health_handler))
         .route("/ready", get(ready_handler))
         .route("/test", get(test_handler))
-        .nest("/revocation", revocation::revocation_routes())
-        .with_state(db)
-        .layer(TraceLayer::new_for_http())
+        .nest("/federation", federation::federation_routes())
+        .merge(versioning::versioned(api))
+        .merge(openapi::openapi_routes())
+        .with_state(db);
+
+    // Refuse writes while the relay is in read-only maintenance mode
+    let routes = routes.layer(axum::middleware::from_fn(maintenance::reject_writes_when_read_only));
+
+    limits::with_request_limits(routes, limits::RequestLimits::default())
+        .layer(axum::middleware::from_fn(api_error::structured_errors))
+        .layer(axum::middleware::from_fn(request_id::propagate_request_id))
+        .layer(log_redaction::trace_layer())
         // Security headers
         .layer(SetResponseHeaderLayer::if_not_present(
             axum::http::header::STRICT_TRANSPORT_SECURITY,
@@ -288,46 +1127,122 @@ pub fn create_app_basic(db: Arc<Database>) -> Router {
 /// Create the application router with full production security including rate limiting
 /// This version includes rate limiting that works in production environments
 pub fn create_app_with_rate_limiting(db: Arc<Database>) -> Router {
-    use tower_http::trace::TraceLayer;
-    use tower_http::cors::CorsLayer;
-    use tower_http::set_header::SetResponseHeaderLayer;
-    use tower_governor::{GovernorLayer, governor::GovernorConfigBuilder, key_extractor::GlobalKeyExtractor};
-
-    // Configure rate limiting: 5 requests per burst, 1 new request every 2 seconds
-    // Use GlobalKeyExtractor to avoid "Unable To Extract Key!" error
-    let governor_conf = GovernorConfigBuilder::default()
-        .per_second(2)
-        .burst_size(5)
-        .key_extractor(GlobalKeyExtractor)
-        .finish()
-        .unwrap();
+    create_app_with_config(db, &config::RelayConfig::default())
+}
 
+/// Create the production application router using the rate limits and CORS origins of a relay configuration
+///
+/// The API routes are open; see [`create_authenticated_app_with_config`] for
+/// the router relays that authenticate clients serve. The administrative
+/// routes (`/admin/*` and `/quarantine`) and webhook registration are left
+/// out, since anyone could otherwise call them.
+pub fn create_app_with_config(db: Arc<Database>, relay_config: &config::RelayConfig) -> Router {
     // Create protected routes (with rate limiting)
-    let protected_routes = Router::new()
+    let api = Router::new()
         .route("/relay", post(relay_handler))
         .route("/messages/:group_id", get(get_messages_handler))
-        .route("/message/:message_id", get(get_message_by_id_handler))
-        .route("/test", get(test_handler))
+        .route("/message/:message_id", get(get_message_by_id_handler).delete(delete_message_handler))
+        .route("/senders/:pubkey/messages", get(get_messages_by_sender_handler))
         .nest("/revocation", revocation::revocation_routes())
-        .with_state(db.clone())
-        // Apply rate limiting only to protected routes
-        .layer(GovernorLayer {
-            config: std::sync::Arc::new(governor_conf),
-        });
+        .nest("/invites", invites::invite_routes())
+        .merge(receipts::receipt_routes())
+        .merge(amendments::amendment_routes())
+        .merge(detached_proofs::detached_proof_routes())
+        .merge(derivations::derivation_routes())
+        .merge(proof_chains::proof_chain_routes())
+        .merge(multisig::multisig_routes())
+        .merge(scheduling::scheduling_routes())
+        .merge(deliveries::delivery_routes())
+        .merge(inbox::inbox_routes())
+        .merge(key_auth::key_auth_routes())
+        .merge(timestamping::timestamp_routes())
+        .merge(schemas::schema_routes())
+        .merge(evidence::evidence_routes())
+        .merge(threads::thread_routes())
+        .merge(search::search_routes())
+        .merge(export::export_routes())
+        .merge(subscriptions::subscription_routes())
+        .nest("/transparency", transparency::transparency_routes());
+    let protected_routes = Router::new()
+        .route("/test", get(test_handler))
+        .nest("/federation", federation::federation_routes())
+        .merge(versioning::versioned(api))
+        .with_state(db.clone());
+    // Apply rate limiting only to protected routes, shared across replicas when Redis is configured
+    let protected_routes = rate_limit::with_rate_limit(protected_routes, relay_config);
+
+    with_public_routes_and_layers(protected_routes, db, relay_config)
+}
+
+/// Create the production application router for a relay that authenticates its clients
+///
+/// Every API route requires a bearer token from a configured issuer, an API
+/// key, a key-challenge session token or a client certificate, with the scopes
+/// of the configured route table. The authentication settings of
+/// `relay_config` are layered onto the router, so this is what the relay
+/// binary serves whenever [`config::RelayConfig::authentication_enabled`].
+pub fn create_authenticated_app_with_config(
+    db: Arc<Database>,
+    relay_config: &config::RelayConfig,
+    jwt_validator: Arc<JwtValidator>,
+    secure_logger: Arc<SecureLogger>,
+) -> Router {
+    let protected_routes = versioning::versioned(authenticated_api(jwt_validator.clone(), secure_logger.clone()))
+        .with_state((db.clone(), jwt_validator, secure_logger));
+    let unauthenticated_routes = Router::new()
+        // Federation endpoints authenticate peers by signature, not user tokens
+        .nest("/federation", federation::federation_routes())
+        .merge(versioning::versioned(Router::new().nest("/transparency", transparency::transparency_routes())))
+        // Key authentication issues the session tokens the protected routes accept
+        .merge(versioning::versioned(key_auth::key_auth_routes()))
+        .with_state(db.clone());
+    let rate_limited_routes = rate_limit::with_rate_limit(
+        Router::new().merge(protected_routes).merge(unauthenticated_routes),
+        relay_config,
+    );
+
+    let mut app = with_public_routes_and_layers(rate_limited_routes, db.clone(), relay_config)
+        .layer(axum::Extension(Arc::new(authorization::ScopePolicy::new(&relay_config.authorization))));
+    if relay_config.rbac.enabled() {
+        app = app.layer(axum::Extension(Arc::new(rbac::RoleMapping::new(&relay_config.rbac))));
+    }
+    if relay_config.api_keys.enabled {
+        app = app.layer(axum::Extension(Arc::new(api_keys::ApiKeys::new(db.clone(), &relay_config.api_keys))));
+    }
+    if relay_config.key_auth.enabled {
+        app = app.layer(axum::Extension(Arc::new(key_auth::KeyAuth::new(db, &relay_config.key_auth))));
+    }
+    if relay_config.client_identity.enabled {
+        app = app.layer(axum::Extension(Arc::new(client_identity::ClientIdentityAuth::new(&relay_config.client_identity))));
+    }
+    app
+}
+
+/// Add the unauthenticated operational routes and the production middleware of a relay configuration
+fn with_public_routes_and_layers(routes: Router, db: Arc<Database>, relay_config: &config::RelayConfig) -> Router {
+    use tower_http::set_header::SetResponseHeaderLayer;
 
     // Create public routes (no rate limiting for health checks)
     let public_routes = Router::new()
         .route("/health", get(health_handler))
         .route("/ready", get(ready_handler))
         .route("/metrics", get(metrics::metrics_handler))
+        .merge(openapi::openapi_routes())
         .with_state(db);
 
     // Combine routes
-    Router::new()
-        .merge(protected_routes)
-        .merge(public_routes)
+    let routes = Router::new()
+        .merge(routes)
+        .merge(public_routes);
+
+    // Refuse writes while the relay is in read-only maintenance mode
+    let routes = routes.layer(axum::middleware::from_fn(maintenance::reject_writes_when_read_only));
+
+    limits::with_request_limits(routes, limits::RequestLimits::from_config(&relay_config.limits))
+        .layer(axum::middleware::from_fn(api_error::structured_errors))
+        .layer(axum::middleware::from_fn(request_id::propagate_request_id))
         .layer(axum::middleware::from_fn(metrics::metrics_middleware))
-        .layer(TraceLayer::new_for_http())
+        .layer(log_redaction::trace_layer())
         // Security headers
         .layer(SetResponseHeaderLayer::if_not_present(
             axum::http::header::STRICT_TRANSPORT_SECURITY,
@@ -341,8 +1256,53 @@ pub fn create_app_with_rate_limiting(db: Arc<Database>) -> Router {
             axum::http::header::X_FRAME_OPTIONS,
             axum::http::HeaderValue::from_static("DENY"),
         ))
-        // CORS layer (configure as needed)
-        .layer(CorsLayer::permissive()) // Note: Configure restrictively in production
+        // CORS restricted to the configured origins
+        .layer(relay_config.cors.layer())
+}
+
+/// State of the routes that require authentication
+type AuthenticatedState = (Arc<Database>, Arc<JwtValidator>, Arc<SecureLogger>);
+
+/// The API routes of a relay that authenticates its clients, before versioning
+///
+/// Requests are authenticated, then authorized against the route scope table.
+fn authenticated_api(jwt_validator: Arc<JwtValidator>, secure_logger: Arc<SecureLogger>) -> Router<AuthenticatedState> {
+    use axum::middleware;
+
+    Router::new()
+        .route("/relay", post(authenticated_relay_handler))
+        .route("/messages/:group_id", get(authenticated_get_messages_handler))
+        .route("/message/:message_id", get(authenticated_get_message_by_id_handler).delete(authenticated_delete_message_handler))
+        .route("/senders/:pubkey/messages", get(authenticated_get_messages_by_sender_handler))
+        .nest("/revocation", revocation::authenticated_revocation_routes())
+        .nest("/invites", invites::authenticated_invite_routes())
+        .nest("/webhooks", webhooks::authenticated_webhook_routes())
+        .nest("/quarantine", quarantine::authenticated_quarantine_routes())
+        .nest("/admin/compliance", compliance_audit::authenticated_compliance_routes())
+        .nest("/admin/data-subjects", data_subjects::authenticated_data_subject_routes())
+        .nest("/admin/api-keys", api_keys::authenticated_api_key_routes())
+        .nest("/admin/maintenance", maintenance::authenticated_maintenance_routes())
+        .nest("/admin/abuse", abuse::authenticated_abuse_routes())
+        .nest("/admin/integrity", integrity::authenticated_integrity_routes())
+        .nest("/keys", key_pinning::authenticated_key_pinning_routes())
+        .merge(receipts::authenticated_receipt_routes())
+        .merge(amendments::authenticated_amendment_routes())
+        .merge(detached_proofs::authenticated_detached_proof_routes())
+        .merge(derivations::authenticated_derivation_routes())
+        .merge(proof_chains::authenticated_proof_chain_routes())
+        .merge(multisig::authenticated_multisig_routes())
+        .merge(scheduling::authenticated_scheduling_routes())
+        .merge(deliveries::authenticated_delivery_routes())
+        .merge(inbox::authenticated_inbox_routes())
+        .merge(timestamping::authenticated_timestamp_routes())
+        .merge(schemas::authenticated_schema_routes())
+        .merge(evidence::authenticated_evidence_routes())
+        .merge(threads::authenticated_thread_routes())
+        .merge(search::authenticated_search_routes())
+        .merge(export::authenticated_export_routes())
+        .merge(subscriptions::authenticated_subscription_routes())
+        .layer(middleware::from_fn_with_state(secure_logger, authorization::authorize))
+        .layer(middleware::from_fn_with_state(jwt_validator, auth_middleware))
 }
 
 /// Create the application router with OAuth2.0 JWT authentication and secure logging
@@ -352,24 +1312,23 @@ pub fn create_app_with_oauth(
     jwt_validator: Arc<JwtValidator>,
     secure_logger: Arc<SecureLogger>,
 ) -> Router {
-    use tower_http::trace::TraceLayer;
     use tower_http::cors::CorsLayer;
     use tower_http::set_header::SetResponseHeaderLayer;
-    use axum::middleware;
 
     // Create protected routes that require authentication
-    let protected_routes = Router::new()
-        .route("/relay", post(authenticated_relay_handler))
-        .route("/messages/:group_id", get(authenticated_get_messages_handler))
-        .route("/message/:message_id", get(authenticated_get_message_by_id_handler))
-        .nest("/revocation", revocation::authenticated_revocation_routes())
-        .layer(middleware::from_fn_with_state(jwt_validator.clone(), auth_middleware))
+    let protected_routes = versioning::versioned(authenticated_api(jwt_validator.clone(), secure_logger.clone()))
         .with_state((db.clone(), jwt_validator.clone(), secure_logger.clone()));
 
     // Create public routes (health checks don't need authentication)
     let public_routes = Router::new()
         .route("/health", get(health_handler))
         .route("/ready", get(ready_handler))
+        // Federation endpoints authenticate peers by signature, not user tokens
+        .nest("/federation", federation::federation_routes())
+        .merge(versioning::versioned(Router::new().nest("/transparency", transparency::transparency_routes())))
+        // Key authentication issues the session tokens the protected routes accept
+        .merge(versioning::versioned(key_auth::key_auth_routes()))
+        .merge(openapi::openapi_routes())
         .with_state(db.clone());
     
     // Create metrics route (doesn't need database state)
@@ -378,12 +1337,19 @@ pub fn create_app_with_oauth(
         .route("/metrics", get(metrics::metrics_handler));
 
     // Combine routes and apply security layers
-    Router::new()
+    let routes = Router::new()
         .merge(protected_routes)
         .merge(public_routes)
-        .merge(metrics_routes)
+        .merge(metrics_routes);
+
+    // Refuse writes while the relay is in read-only maintenance mode
+    let routes = routes.layer(axum::middleware::from_fn(maintenance::reject_writes_when_read_only));
+
+    limits::with_request_limits(routes, limits::RequestLimits::default())
+        .layer(axum::middleware::from_fn(api_error::structured_errors))
+        .layer(axum::middleware::from_fn(request_id::propagate_request_id))
         .layer(axum::middleware::from_fn(metrics::metrics_middleware))
-        .layer(TraceLayer::new_for_http())
+        .layer(log_redaction::trace_layer())
         // Security headers
         .layer(SetResponseHeaderLayer::if_not_present(
             axum::http::header::STRICT_TRANSPORT_SECURITY,
@@ -402,19 +1368,54 @@ pub fn create_app_with_oauth(
 }
 
 /// The Axum handler for message relay
+#[utoipa::path(
+    post,
+    path = "/relay",
+    operation_id = "relayMessage",
+    tag = "messages",
+    request_body(
+        content((Message = "application/json"), (Object = "application/cbor")),
+        description = "The message as JSON, or as CBOR in the compact wire encoding"
+    ),
+    responses(
+        (status = 200, description = "Message verified and stored", body = Object),
+    )
+)]
 #[instrument(skip_all)]
+#[allow(clippy::too_many_arguments)] // one extractor per optional feature
 async fn relay_handler(
     State(db): State<Arc<Database>>,
-    Json(payload): Json<Message>,
+    federation: Option<Extension<Arc<federation::Federation>>>,
+    webhooks: Option<Extension<Arc<webhooks::WebhookDispatcher>>>,
+    events: Option<Extension<Arc<event_stream::EventStream>>>,
+    replay: Option<Extension<Arc<replay::ReplayGuard>>>,
+    subscriptions: Option<Extension<Arc<subscriptions::Subscriptions>>>,
+    quarantine: Option<Extension<Arc<quarantine::Quarantine>>>,
+    abuse: Option<Extension<Arc<abuse::AbuseDetector>>>,
+    limits: Option<Extension<Arc<limits::RequestLimits>>>,
+    context_policy: Option<Extension<Arc<context_policy::ContextPolicy>>>,
+    timestamping: Option<Extension<Arc<timestamping::TimestampAuthority>>>,
+    tenant: tenancy::TenantScope,
+    wire::MessagePayload(payload): wire::MessagePayload,
 ) -> Result<impl IntoResponse, AppError> {
     info!("Received message for relay");
     
-    // Delegate to the unit-tested function, passing the database for revocation check
-    process_and_verify_message(&payload, Some(&db)).await?;
-    
-    // Store the verified message in the database
-    let stored_message = StoredMessage::from(payload);
-    let message_id = db.store_message(stored_message).await?;
+    let message_id = relay_message(
+        &db,
+        payload,
+        federation.as_deref(),
+        webhooks.as_deref(),
+        events.as_deref(),
+        replay.as_deref(),
+        subscriptions.as_deref(),
+        quarantine.as_deref(),
+        abuse.as_deref(),
+        limits.as_deref(),
+        context_policy.as_deref(),
+        timestamping.as_deref(),
+        &tenant,
+    )
+    .await?;
     
     let success_response = Json(serde_json::json!({
         "status": "success",
@@ -425,16 +1426,85 @@ async fn relay_handler(
     Ok((StatusCode::OK, success_response))
 }
 
+/// Verify, store and distribute a submitted message, returning its ID
+///
+/// The pipeline behind `POST /relay`, shared with the gRPC `Relay` service.
+#[allow(clippy::too_many_arguments)] // one parameter per optional feature
+pub(crate) async fn relay_message(
+    db: &Arc<Database>,
+    payload: Message,
+    federation: Option<&Arc<federation::Federation>>,
+    webhooks: Option<&Arc<webhooks::WebhookDispatcher>>,
+    events: Option<&Arc<event_stream::EventStream>>,
+    replay: Option<&Arc<replay::ReplayGuard>>,
+    subscriptions: Option<&Arc<subscriptions::Subscriptions>>,
+    quarantine: Option<&Arc<quarantine::Quarantine>>,
+    abuse: Option<&Arc<abuse::AbuseDetector>>,
+    limits: Option<&Arc<limits::RequestLimits>>,
+    context_policy: Option<&Arc<context_policy::ContextPolicy>>,
+    timestamping: Option<&Arc<timestamping::TimestampAuthority>>,
+    tenant: &tenancy::TenantScope,
+) -> Result<String, AppError> {
+    // Reject oversized messages before any signature parsing
+    limits::validate_message(limits, &payload)?;
+    abuse::check_if_enabled(abuse, &payload.sender)?;
+    
+    // Delegate to the unit-tested function, passing the database for revocation check
+    if let Err(e) = process_and_verify_message(&payload, Some(db)).await {
+        quarantine::record_if_enabled(quarantine, db, &payload, &e, None).await;
+        abuse::record_rejection_if_enabled(abuse, &payload.sender, &e, None);
+        return Err(e);
+    }
+    abuse::record_accepted_if_enabled(abuse, &payload.sender, None)?;
+    if let Err(e) = context_policy::enforce_if_enabled(tenant.context_policy(context_policy), &payload, None, None) {
+        tenant.record_policy_violation();
+        return Err(e);
+    }
+    
+    // Store the verified message in the database, in the tenant's namespace
+    let mut stored_message = StoredMessage::from(payload.clone());
+    stored_message.group_id = tenant.group_id(&stored_message.group_id);
+    schemas::validate_body(db, &stored_message.group_id, &stored_message.body).await?;
+    threads::assign_thread(db, &mut stored_message).await?;
+    let nonce = replay::claim_if_enabled(replay, &payload).await?;
+    let message_id = match db.store_message(stored_message.clone()).await {
+        Ok(message_id) => message_id,
+        Err(e) => {
+            replay::release_if_enabled(replay, nonce).await;
+            return Err(e.into());
+        }
+    };
+    federation::publish_if_enabled(federation, db, &message_id, &payload).await?;
+    webhooks::notify_if_enabled(webhooks, db, &stored_message).await?;
+    event_stream::publish_if_enabled(events, db, &stored_message).await?;
+    subscriptions::publish_if_enabled(subscriptions, &stored_message).await;
+    timestamping::timestamp_if_enabled(timestamping, db, &stored_message);
+    tenant.record_relayed();
+    
+    Ok(message_id)
+}
+
 /// Handler to retrieve messages for a specific group
+#[utoipa::path(
+    get,
+    path = "/messages/{group_id}",
+    operation_id = "listGroupMessages",
+    tag = "messages",
+    params(("group_id" = String, Path, description = "Group ID"), MessageQuery),
+    responses(
+        (status = 200, description = "The group's messages", body = Object),
+    )
+)]
 #[instrument(skip_all)]
 async fn get_messages_handler(
     State(db): State<Arc<Database>>,
+    tenant: tenancy::TenantScope,
     Path(group_id): Path<String>,
     Query(params): Query<MessageQuery>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("Retrieving messages for group: {}", group_id);
     
-    let messages = db.get_messages_by_group(&group_id, params.limit).await?;
+    let messages = db.get_messages_by_group(&tenant.group_id(&group_id), params.limit).await?;
     
     let response = Json(serde_json::json!({
         "status": "success",
@@ -447,24 +1517,151 @@ async fn get_messages_handler(
 }
 
 /// Handler to retrieve a specific message by ID
+#[utoipa::path(
+    get,
+    path = "/message/{message_id}",
+    operation_id = "getMessage",
+    tag = "messages",
+    params(("message_id" = String, Path, description = "Message ID")),
+    responses(
+        (status = 200, description = "The message and its timestamp token, if any", body = Object),
+    )
+)]
 #[instrument(skip_all)]
 async fn get_message_by_id_handler(
     State(db): State<Arc<Database>>,
+    tenant: tenancy::TenantScope,
     Path(message_id): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("Retrieving message: {}", message_id);
     
-    let message = db.get_message_by_id(&message_id).await?;
+    let message = get_tenant_message(&db, &tenant, &message_id).await?;
+    let timestamp = db.get_message_timestamp(&message_id).await?;
+    
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": message,
+        "timestamp": timestamp
+    }));
+    
+    Ok((StatusCode::OK, response))
+}
+
+/// Retrieve a message by ID, as if it did not exist when it belongs to another tenant
+///
+/// Expired messages that have not been purged yet are reported as gone.
+/// Direct messages are only served from their recipient's inbox, so they are
+/// not found here either.
+pub(crate) async fn get_tenant_message(db: &Database, tenant: &tenancy::TenantScope, message_id: &str) -> Result<StoredMessage, AppError> {
+    let message = db.get_message_by_id(message_id).await?;
+    if !tenant.owns_group(&message.group_id) || message.is_direct() {
+        return Err(DatabaseError::MessageNotFound(message_id.to_string()).into());
+    }
+    if message.is_expired(chrono::Utc::now()) {
+        return Err(AppError::MessageExpired(message_id.to_string()));
+    }
+    Ok(message)
+}
+
+/// Handler to delete a message, leaving its tombstone
+#[utoipa::path(
+    delete,
+    path = "/message/{message_id}",
+    operation_id = "deleteMessage",
+    tag = "messages",
+    params(("message_id" = String, Path, description = "Message ID")),
+    responses(
+        (status = 200, description = "The message's tombstone", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn delete_message_handler(
+    State(db): State<Arc<Database>>,
+    tenant: tenancy::TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Deleting message: {}", message_id);
+    
+    let (tombstone, _) = delete_tenant_message(&db, &tenant, &message_id).await?;
     
     let response = Json(serde_json::json!({
         "status": "success",
-        "message": message
+        "message": "Message deleted successfully",
+        "tombstone": tombstone
+    }));
+    
+    Ok((StatusCode::OK, response))
+}
+
+/// Replace a tenant's message with its tombstone
+///
+/// Returns the tombstone and whether this call deleted the message, as
+/// opposed to it having been deleted before.
+pub(crate) async fn delete_tenant_message(
+    db: &Database,
+    tenant: &tenancy::TenantScope,
+    message_id: &str,
+) -> Result<(StoredMessage, bool), AppError> {
+    get_tenant_message(db, tenant, message_id).await?;
+    let deleted = db.tombstone_message(message_id).await?;
+    Ok((db.get_message_by_id(message_id).await?, deleted))
+}
+
+/// Validate a sender public key path parameter (64 hex characters)
+pub(crate) fn validate_sender_key(pubkey: &str) -> Result<(), AppError> {
+    let bytes = hex::decode(pubkey)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid hex encoding: {}", e)))?;
+    if bytes.len() != 32 {
+        return Err(AppError::InvalidPublicKey("Public key must be 32 bytes".to_string()));
+    }
+    Ok(())
+}
+
+/// Handler to list the messages submitted by a sender
+#[utoipa::path(
+    get,
+    path = "/senders/{pubkey}/messages",
+    operation_id = "listSenderMessages",
+    tag = "messages",
+    params(("pubkey" = String, Path, description = "Sender public key (hex encoded)"), SenderMessageQuery),
+    responses(
+        (status = 200, description = "The sender's messages", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_messages_by_sender_handler(
+    State(db): State<Arc<Database>>,
+    tenant: tenancy::TenantScope,
+    Path(pubkey): Path<String>,
+    Query(params): Query<SenderMessageQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Retrieving messages for sender: {}", pubkey);
+    
+    validate_sender_key(&pubkey)?;
+    let mut messages = db.get_messages_by_sender(&pubkey, params.since, params.until, params.limit).await?;
+    messages.retain(|message| tenant.owns_group(&message.group_id));
+    
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "sender": pubkey,
+        "message_count": messages.len(),
+        "messages": messages
     }));
     
     Ok((StatusCode::OK, response))
 }
 
 /// Health check endpoint for container orchestration
+#[utoipa::path(
+    get,
+    path = "/health",
+    operation_id = "health",
+    tag = "health",
+    responses(
+        (status = 200, description = "The relay and its database are healthy", body = Object),
+        (status = 503, description = "The database is unreachable", body = Object),
+    )
+)]
 #[instrument(skip_all)]
 async fn health_handler(
     State(db): State<Arc<Database>>,
@@ -499,28 +1696,6 @@ async fn health_handler(
     }
 }
 
-/// Readiness check endpoint
-#[instrument]
-async fn ready_handler(State(db): State<Arc<Database>>) -> impl IntoResponse {
-    // Check if all systems are ready
-    let db_ready = db.health_check().await.is_ok();
-    
-    let overall_ready = db_ready;
-    let status_code = if overall_ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
-    
-    let ready_response = Json(serde_json::json!({
-        "status": if overall_ready { "ready" } else { "not_ready" },
-        "service": "proof-messenger-relay",
-        "checks": {
-            "crypto": "ok",
-            "memory": "ok",
-            "database": if db_ready { "ok" } else { "error" }
-        }
-    }));
-    
-    (status_code, ready_response)
-}
-
 /// Simple test endpoint without database dependency
 #[instrument(skip_all)]
 async fn test_handler() -> impl IntoResponse {
@@ -531,10 +1706,24 @@ async fn test_handler() -> impl IntoResponse {
 
 /// OAuth2.0-protected relay handler that requires authentication and proper scopes
 #[instrument(skip_all)]
+#[allow(clippy::too_many_arguments)] // one extractor per optional feature
 async fn authenticated_relay_handler(
     State((db, _validator, secure_logger)): State<(Arc<Database>, Arc<JwtValidator>, Arc<SecureLogger>)>,
     auth: AuthContext,
-    Json(payload): Json<Message>,
+    request_id: RequestId,
+    federation: Option<Extension<Arc<federation::Federation>>>,
+    webhooks: Option<Extension<Arc<webhooks::WebhookDispatcher>>>,
+    events: Option<Extension<Arc<event_stream::EventStream>>>,
+    replay: Option<Extension<Arc<replay::ReplayGuard>>>,
+    subscriptions: Option<Extension<Arc<subscriptions::Subscriptions>>>,
+    quarantine: Option<Extension<Arc<quarantine::Quarantine>>>,
+    abuse: Option<Extension<Arc<abuse::AbuseDetector>>>,
+    limits: Option<Extension<Arc<limits::RequestLimits>>>,
+    context_policy: Option<Extension<Arc<context_policy::ContextPolicy>>>,
+    key_pinning: Option<Extension<Arc<key_pinning::KeyPinning>>>,
+    timestamping: Option<Extension<Arc<timestamping::TimestampAuthority>>>,
+    tenant: tenancy::TenantScope,
+    wire::MessagePayload(payload): wire::MessagePayload,
 ) -> Result<impl IntoResponse, AppError> {
     info!("Received authenticated message for relay from user: {}", auth.user_id);
     
@@ -547,49 +1736,62 @@ async fn authenticated_relay_handler(
     if let Err(e) = secure_logger.audit_log(
         "User authenticated for proof creation".to_string(),
         auth.user_id.clone(),
-        None, // Could extract request ID from headers
+        Some(request_id.to_string()),
         metadata.clone(),
     ) {
         warn!("Failed to log authentication event: {}", e);
     }
     
-    // Check if user has required scope for creating proofs
-    match require_scope(&auth, "proof:create") {
-        Ok(_) => {
-            // Log successful authorization
-            metadata.insert("authorization_result".to_string(), "granted".to_string());
-            if let Err(e) = secure_logger.log_security_event(
-                LogLevel::Audit,
-                "Proof creation authorization granted".to_string(),
-                Some(auth.user_id.clone()),
-                None,
-                metadata.clone(),
-            ) {
-                warn!("Failed to log authorization event: {}", e);
-            }
-        }
-        Err(_) => {
-            // Log authorization failure
-            metadata.insert("authorization_result".to_string(), "denied".to_string());
-            metadata.insert("required_scope".to_string(), "proof:create".to_string());
-            if let Err(e) = secure_logger.critical_security_event(
-                "Proof creation authorization denied - insufficient scope".to_string(),
-                Some(auth.user_id.clone()),
-                None,
-                metadata,
-            ) {
-                warn!("Failed to log authorization failure: {}", e);
-            }
-            return Err(AppError::ProcessingError("Insufficient permissions to create proofs".to_string()));
-        }
-    }
+    // Reject oversized messages before any signature parsing
+    limits::validate_message(limits.as_deref(), &payload)?;
+    abuse::check_if_enabled(abuse.as_deref(), &payload.sender)?;
     
     // Delegate to the unit-tested function, passing the database for revocation check
-    process_and_verify_message(&payload, Some(&db)).await?;
+    if let Err(e) = process_and_verify_message(&payload, Some(&db)).await {
+        quarantine::record_if_enabled(quarantine.as_deref(), &db, &payload, &e, Some(&auth.user_id)).await;
+        abuse::record_rejection_if_enabled(abuse.as_deref(), &payload.sender, &e, Some(&auth.user_id));
+        return Err(e);
+    }
+    abuse::record_accepted_if_enabled(abuse.as_deref(), &payload.sender, Some(&auth.user_id))?;
+    if let Err(e) = context_policy::enforce_if_enabled(
+        tenant.context_policy(context_policy.as_deref()),
+        &payload,
+        Some(&auth.user_id),
+        Some(&request_id.to_string()),
+    ) {
+        tenant.record_policy_violation();
+        return Err(e);
+    }
+    let key_change = key_pinning::enforce_if_enabled(
+        key_pinning.as_deref(),
+        &db,
+        webhooks.as_deref(),
+        &secure_logger,
+        &auth.user_id,
+        &payload.sender,
+        Some(&request_id.to_string()),
+    )
+    .await?;
     
-    // Store the verified message in the database with user context
-    let stored_message = StoredMessage::from(payload.clone());
-    let message_id = db.store_message(stored_message).await?;
+    // Store the verified message in the database with user context, in the tenant's namespace
+    let mut stored_message = StoredMessage::from(payload.clone());
+    stored_message.group_id = tenant.group_id(&stored_message.group_id);
+    schemas::validate_body(&db, &stored_message.group_id, &stored_message.body).await?;
+    threads::assign_thread(&db, &mut stored_message).await?;
+    let nonce = replay::claim_if_enabled(replay.as_deref(), &payload).await?;
+    let message_id = match db.store_message(stored_message.clone()).await {
+        Ok(message_id) => message_id,
+        Err(e) => {
+            replay::release_if_enabled(replay.as_deref(), nonce).await;
+            return Err(e.into());
+        }
+    };
+    federation::publish_if_enabled(federation.as_deref(), &db, &message_id, &payload).await?;
+    webhooks::notify_if_enabled(webhooks.as_deref(), &db, &stored_message).await?;
+    event_stream::publish_if_enabled(events.as_deref(), &db, &stored_message).await?;
+    subscriptions::publish_if_enabled(subscriptions.as_deref(), &stored_message).await;
+    timestamping::timestamp_if_enabled(timestamping.as_deref(), &db, &stored_message);
+    tenant.record_relayed();
     
     // Log successful proof creation
     let mut success_metadata = std::collections::HashMap::new();
@@ -601,20 +1803,24 @@ async fn authenticated_relay_handler(
     if let Err(e) = secure_logger.audit_log(
         "Proof creation and verification completed successfully".to_string(),
         auth.user_id.clone(),
-        None,
+        Some(request_id.to_string()),
         success_metadata,
     ) {
         warn!("Failed to log proof creation success: {}", e);
     }
     
-    let success_response = Json(serde_json::json!({
+    let mut success_response = serde_json::json!({
         "status": "success",
         "message": "Message verified and relayed successfully",
         "message_id": message_id,
         "authenticated_user": auth.user_id
-    }));
+    });
+    // A flagged key change is relayed but reported back to the sender
+    if let Some(change) = key_change {
+        success_response["key_change"] = serde_json::to_value(change).unwrap_or_default();
+    }
     
-    Ok((StatusCode::OK, success_response))
+    Ok((StatusCode::OK, Json(success_response)))
 }
 
 /// OAuth2.0-protected handler to retrieve messages for a specific group
@@ -622,33 +1828,14 @@ async fn authenticated_relay_handler(
 async fn authenticated_get_messages_handler(
     State((db, _validator, secure_logger)): State<(Arc<Database>, Arc<JwtValidator>, Arc<SecureLogger>)>,
     auth: AuthContext,
+    request_id: RequestId,
+    tenant: tenancy::TenantScope,
     Path(group_id): Path<String>,
     Query(params): Query<MessageQuery>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("Authenticated user {} retrieving messages for group: {}", auth.user_id, group_id);
     
-    // Check if user has required scope for reading messages
-    require_scope(&auth, "message:read")
-        .map_err(|_| {
-            // Log authorization failure
-            let mut metadata = std::collections::HashMap::new();
-            metadata.insert("endpoint".to_string(), "/messages".to_string());
-            metadata.insert("group_id".to_string(), group_id.clone());
-            metadata.insert("required_scope".to_string(), "message:read".to_string());
-            
-            if let Err(e) = secure_logger.critical_security_event(
-                "Message read authorization denied - insufficient scope".to_string(),
-                Some(auth.user_id.clone()),
-                None,
-                metadata,
-            ) {
-                warn!("Failed to log authorization failure: {}", e);
-            }
-            
-            AppError::ProcessingError("Insufficient permissions to read messages".to_string())
-        })?;
-    
-    let messages = db.get_messages_by_group(&group_id, params.limit).await?;
+    let messages = db.get_messages_by_group(&tenant.group_id(&group_id), params.limit).await?;
     
     // Log successful message retrieval
     let mut metadata = std::collections::HashMap::new();
@@ -659,7 +1846,7 @@ async fn authenticated_get_messages_handler(
     if let Err(e) = secure_logger.audit_log(
         "Messages retrieved successfully".to_string(),
         auth.user_id.clone(),
-        None,
+        Some(request_id.to_string()),
         metadata,
     ) {
         warn!("Failed to log message retrieval: {}", e);
@@ -681,15 +1868,14 @@ async fn authenticated_get_messages_handler(
 async fn authenticated_get_message_by_id_handler(
     State((db, _validator, secure_logger)): State<(Arc<Database>, Arc<JwtValidator>, Arc<SecureLogger>)>,
     auth: AuthContext,
+    request_id: RequestId,
+    tenant: tenancy::TenantScope,
     Path(message_id): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("Authenticated user {} retrieving message: {}", auth.user_id, message_id);
     
-    // Check if user has required scope for reading messages
-    require_scope(&auth, "message:read")
-        .map_err(|_| AppError::ProcessingError("Insufficient permissions to read messages".to_string()))?;
-    
-    let message = db.get_message_by_id(&message_id).await?;
+    let message = get_tenant_message(&db, &tenant, &message_id).await?;
+    let timestamp = db.get_message_timestamp(&message_id).await?;
     
     // Log successful message retrieval
     let mut metadata = std::collections::HashMap::new();
@@ -699,7 +1885,7 @@ async fn authenticated_get_message_by_id_handler(
     if let Err(e) = secure_logger.audit_log(
         "Individual message retrieved successfully".to_string(),
         auth.user_id.clone(),
-        None,
+        Some(request_id.to_string()),
         metadata,
     ) {
         warn!("Failed to log message retrieval: {}", e);
@@ -708,6 +1894,100 @@ async fn authenticated_get_message_by_id_handler(
     let response = Json(serde_json::json!({
         "status": "success",
         "message": message,
+        "timestamp": timestamp,
+        "authenticated_user": auth.user_id
+    }));
+    
+    Ok((StatusCode::OK, response))
+}
+
+/// OAuth2.0-protected handler to delete a message, leaving its tombstone
+///
+/// Each deletion is audit logged with the message's sender and group, so the
+/// audit trail still shows what was removed and by whom.
+#[instrument(skip_all)]
+async fn authenticated_delete_message_handler(
+    State((db, _validator, secure_logger)): State<(Arc<Database>, Arc<JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    tenant: tenancy::TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} deleting message: {}", auth.user_id, message_id);
+    
+    let (tombstone, deleted) = delete_tenant_message(&db, &tenant, &message_id).await?;
+    
+    if deleted {
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert("message_id".to_string(), message_id.clone());
+        metadata.insert("group_id".to_string(), tombstone.group_id.clone());
+        metadata.insert("sender".to_string(), tombstone.sender.clone());
+        metadata.insert("message_hash".to_string(), tombstone.message_hash.clone().unwrap_or_default());
+        
+        if let Err(e) = secure_logger.audit_log(
+            "Message deleted".to_string(),
+            auth.user_id.clone(),
+            Some(request_id.to_string()),
+            metadata,
+        ) {
+            warn!("Failed to log message deletion: {}", e);
+        }
+    }
+    
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": "Message deleted successfully",
+        "tombstone": tombstone,
+        "authenticated_user": auth.user_id
+    }));
+    
+    Ok((StatusCode::OK, response))
+}
+
+/// OAuth2.0-protected handler to list the messages submitted by a sender
+///
+/// Every lookup is audit logged, since listing a key's history is typically
+/// part of investigating a compromised key.
+#[instrument(skip_all)]
+async fn authenticated_get_messages_by_sender_handler(
+    State((db, _validator, secure_logger)): State<(Arc<Database>, Arc<JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    tenant: tenancy::TenantScope,
+    Path(pubkey): Path<String>,
+    Query(params): Query<SenderMessageQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} retrieving messages for sender: {}", auth.user_id, pubkey);
+    
+    validate_sender_key(&pubkey)?;
+    let mut messages = db.get_messages_by_sender(&pubkey, params.since, params.until, params.limit).await?;
+    messages.retain(|message| tenant.owns_group(&message.group_id));
+    
+    // Log the sender lookup
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("sender".to_string(), pubkey.clone());
+    metadata.insert("message_count".to_string(), messages.len().to_string());
+    if let Some(since) = params.since {
+        metadata.insert("since".to_string(), since.to_rfc3339());
+    }
+    if let Some(until) = params.until {
+        metadata.insert("until".to_string(), until.to_rfc3339());
+    }
+    
+    if let Err(e) = secure_logger.audit_log(
+        "Sender messages retrieved successfully".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log sender message retrieval: {}", e);
+    }
+    
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "sender": pubkey,
+        "message_count": messages.len(),
+        "messages": messages,
         "authenticated_user": auth.user_id
     }));
     
@@ -731,6 +2011,11 @@ mod tests {
             context: hex::encode(context),
             body: body.to_string(),
             proof: hex::encode(signature.to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
         }
     }
 
@@ -747,6 +2032,11 @@ mod tests {
             context: hex::encode(tampered_context), // The context doesn't match the signature
             body: "This is a test".to_string(),
             proof: hex::encode(signature.to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
         };
 
         // ACT: Call the logic function directly
@@ -756,6 +2046,335 @@ mod tests {
         assert!(matches!(result, Err(AppError::VerificationFailed)));
     }
 
+    /// Helper function to create a valid hybrid (Ed25519 + ML-DSA-65) message for testing
+    fn create_hybrid_test_message(keypair_seed: u64, context: &[u8]) -> Message {
+        use proof_messenger_protocol::hybrid::{make_hybrid_proof, HybridKeypair};
+
+        let keypair = HybridKeypair::generate_with_seed(keypair_seed);
+        let public_key = keypair.public_key();
+        let proof = make_hybrid_proof(&keypair, context).unwrap();
+
+        Message {
+            sender: hex::encode(public_key.ed25519.to_bytes()),
+            context: hex::encode(context),
+            body: "Hybrid test message".to_string(),
+            proof: hex::encode(proof.ed25519.to_bytes()),
+            pqc: Some(PqcProof {
+                public_key: hex::encode(public_key.pqc.as_bytes()),
+                proof: hex::encode(proof.pqc.as_bytes()),
+            }),
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn hybrid_message_verifies_under_strict_policy() {
+        let message = create_hybrid_test_message(42, b"hybrid context");
+
+        let result = process_and_verify_message_with_policy(&message, None, HybridPolicy::Strict).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn strict_policy_rejects_classic_only_message() {
+        let message = create_test_message(42, b"classic context", "No PQC signature");
+
+        let result = process_and_verify_message_with_policy(&message, None, HybridPolicy::Strict).await;
+
+        assert!(matches!(result, Err(AppError::InvalidSignature(_))));
+    }
+
+    #[tokio::test]
+    async fn hybrid_policy_decides_on_tampered_pqc_signature() {
+        // ARRANGE: A hybrid message whose PQC half was produced by another key
+        let mut message = create_hybrid_test_message(42, b"hybrid context");
+        let other = create_hybrid_test_message(43, b"hybrid context");
+        message.pqc.as_mut().unwrap().proof = other.pqc.unwrap().proof;
+
+        // ACT & ASSERT: Strict requires both halves, transitional accepts the valid Ed25519 half
+        let strict = process_and_verify_message_with_policy(&message, None, HybridPolicy::Strict).await;
+        assert!(matches!(strict, Err(AppError::VerificationFailed)));
+
+        let transitional =
+            process_and_verify_message_with_policy(&message, None, HybridPolicy::Transitional).await;
+        assert!(transitional.is_ok());
+    }
+
+    #[tokio::test]
+    async fn foreign_pqc_half_cannot_stand_in_for_sender_signature() {
+        // ARRANGE: The attacker's own message, relabelled as coming from sender 42:
+        // its Ed25519 half no longer matches, its ML-DSA key and signature still do
+        let mut message = create_hybrid_test_message(43, b"hybrid context");
+        message.sender = create_hybrid_test_message(42, b"hybrid context").sender;
+
+        // ACT & ASSERT: Neither policy accepts the proof
+        for policy in [HybridPolicy::Strict, HybridPolicy::Transitional] {
+            let result = process_and_verify_message_with_policy(&message, None, policy).await;
+            assert!(matches!(result, Err(AppError::VerificationFailed)), "{}: {:?}", policy, result);
+        }
+        let default = process_and_verify_message(&message, None).await;
+        assert!(matches!(default, Err(AppError::VerificationFailed)));
+    }
+
+    #[tokio::test]
+    async fn strict_policy_binds_pqc_key_to_sender() {
+        use proof_messenger_protocol::hybrid::{make_hybrid_proof, HybridKeypair};
+        use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+
+        // ARRANGE: Sender 42's first hybrid message, and a forger holding its
+        // Ed25519 key who brings an ML-DSA key of their own
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let genuine = create_hybrid_test_message(42, b"first");
+        let forger = HybridKeypair::from_parts(generate_secure_keypair_with_seed(42), &[7u8; 32]).unwrap();
+        let (forged_key, forged_proof) = (forger.public_key(), make_hybrid_proof(&forger, b"second").unwrap());
+        let forged = Message {
+            context: hex::encode(b"second"),
+            proof: hex::encode(forged_proof.ed25519.to_bytes()),
+            pqc: Some(PqcProof {
+                public_key: hex::encode(forged_key.pqc.as_bytes()),
+                proof: hex::encode(forged_proof.pqc.as_bytes()),
+            }),
+            ..genuine.clone()
+        };
+        let enveloped = Message {
+            proof: ProofEnvelope::sign_hybrid(&forger, b"second", Default::default()).unwrap().to_hex(),
+            pqc: None,
+            ..forged.clone()
+        };
+
+        // ACT: Verify the genuine message, then the forgeries, under each policy
+        let first = process_and_verify_message_with_policy(&genuine, Some(&db), HybridPolicy::Strict).await;
+        let strict = process_and_verify_message_with_policy(&forged, Some(&db), HybridPolicy::Strict).await;
+        let strict_envelope = process_and_verify_message_with_policy(&enveloped, Some(&db), HybridPolicy::Strict).await;
+        let again = process_and_verify_message_with_policy(&create_hybrid_test_message(42, b"third"), Some(&db), HybridPolicy::Strict).await;
+
+        // ASSERT: Only the ML-DSA key the sender first presented is accepted
+        assert!(first.is_ok(), "{:?}", first);
+        assert!(matches!(strict, Err(AppError::InvalidPublicKey(_))), "{:?}", strict);
+        assert!(matches!(strict_envelope, Err(AppError::InvalidPublicKey(_))), "{:?}", strict_envelope);
+        assert!(again.is_ok(), "{:?}", again);
+    }
+
+    /// Helper function to create a message whose proof is an Ed25519 envelope
+    fn create_envelope_test_message(keypair_seed: u64, context: &[u8]) -> Message {
+        let keypair = proof_messenger_protocol::key::generate_secure_keypair_with_seed(keypair_seed);
+        let envelope = ProofEnvelope::sign(&keypair, context, Default::default()).unwrap();
+
+        Message {
+            sender: hex::encode(keypair.public_key().to_bytes()),
+            context: hex::encode(context),
+            body: "Enveloped test message".to_string(),
+            proof: envelope.to_hex(),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn enveloped_and_legacy_proofs_are_both_accepted() {
+        let enveloped = create_envelope_test_message(42, b"migrating context");
+        let legacy = create_test_message(42, b"migrating context", "Legacy message");
+
+        assert!(process_and_verify_message(&enveloped, None).await.is_ok());
+        assert!(process_and_verify_message(&legacy, None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn envelope_must_match_sender_and_context() {
+        let mut wrong_sender = create_envelope_test_message(42, b"envelope context");
+        wrong_sender.sender = create_test_message(43, b"", "").sender;
+        let mut wrong_context = create_envelope_test_message(42, b"envelope context");
+        wrong_context.context = hex::encode(b"another context");
+
+        let sender_result = process_and_verify_message(&wrong_sender, None).await;
+        let context_result = process_and_verify_message(&wrong_context, None).await;
+
+        assert!(matches!(sender_result, Err(AppError::InvalidPublicKey(_))));
+        assert!(matches!(context_result, Err(AppError::VerificationFailed)));
+    }
+
+    #[tokio::test]
+    async fn golden_vectors_verify_as_relay_messages() {
+        let vectors = proof_messenger_protocol::test_vectors::golden();
+
+        for vector in &vectors.proofs {
+            let message = Message {
+                sender: hex::encode(&vector.public_key),
+                context: hex::encode(&vector.context),
+                body: vector.name.clone(),
+                proof: hex::encode(&vector.signature),
+                pqc: None,
+                thread_id: None,
+                reply_to: None,
+                expires_at: None,
+                recipient: None,
+            };
+            let result = process_and_verify_message_with_policy(&message, None, HybridPolicy::Transitional).await;
+            assert!(result.is_ok(), "proofs/{}: {:?}", vector.name, result);
+        }
+
+        for vector in &vectors.envelopes {
+            let mut message = create_envelope_test_message(vector.seed, &vector.context);
+            message.proof = hex::encode(&vector.envelope);
+            let result = process_and_verify_message_with_policy(&message, None, HybridPolicy::Transitional).await;
+            assert!(result.is_ok(), "envelopes/{}: {:?}", vector.name, result);
+        }
+
+        for vector in &vectors.receipts {
+            let sender = proof_messenger_protocol::key::generate_secure_keypair_with_seed(vector.sender_seed);
+            let stored = StoredMessage::from(create_test_message(vector.sender_seed, &vector.context, &vector.body));
+
+            assert_eq!(stored.sender, hex::encode(sender.public_key_bytes()), "receipts/{}", vector.name);
+            assert_eq!(
+                receipts::stored_message_hash(&stored).unwrap().to_vec(),
+                vector.message_hash,
+                "receipts/{}",
+                vector.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn strict_policy_requires_hybrid_envelope() {
+        use proof_messenger_protocol::hybrid::HybridKeypair;
+
+        let keypair = HybridKeypair::generate_with_seed(42);
+        let envelope = ProofEnvelope::sign_hybrid(&keypair, b"hybrid context", Default::default()).unwrap();
+        let mut hybrid = create_envelope_test_message(42, b"hybrid context");
+        hybrid.proof = envelope.to_hex();
+        let classic = create_envelope_test_message(42, b"hybrid context");
+
+        let hybrid_result = process_and_verify_message_with_policy(&hybrid, None, HybridPolicy::Strict).await;
+        let classic_result = process_and_verify_message_with_policy(&classic, None, HybridPolicy::Strict).await;
+
+        assert!(hybrid_result.is_ok());
+        assert!(matches!(classic_result, Err(AppError::InvalidSignature(_))));
+    }
+
+    #[tokio::test]
+    async fn deleted_message_is_listed_as_tombstone() {
+        use tower::ServiceExt;
+
+        // ARRANGE: A relay holding one stored message
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let message = create_test_message(42, b"deletion context", "Please forget me");
+        let id = db.store_message(StoredMessage::from(message)).await.unwrap();
+        let request = |method: &str, uri: &str| {
+            axum::http::Request::builder().method(method).uri(uri).body(axum::body::Body::empty()).unwrap()
+        };
+
+        // ACT: Delete it, then list its group
+        let deleted = create_app(db.clone()).oneshot(request("DELETE", &format!("/message/{}", id))).await.unwrap();
+        let missing = create_app(db.clone()).oneshot(request("DELETE", "/message/missing")).await.unwrap();
+        let listed = create_app(db).oneshot(request("GET", "/messages/default")).await.unwrap();
+
+        // ASSERT: The listing shows a tombstone without the body
+        assert_eq!(deleted.status(), StatusCode::OK);
+        let missing = axum::body::to_bytes(missing.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&missing).unwrap()["code"], "MESSAGE_NOT_FOUND");
+        let body = axum::body::to_bytes(listed.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let tombstone = &json["messages"][0];
+        assert_eq!(tombstone["id"], id.as_str());
+        assert_eq!(tombstone["body"], "");
+        assert!(tombstone["deleted_at"].is_string());
+        assert!(tombstone["message_hash"].is_string());
+    }
+
+    #[tokio::test]
+    async fn authenticated_relay_rejects_anonymous_api_requests() {
+        use tower::ServiceExt;
+
+        // ARRANGE: The router the relay binary serves when authentication is configured
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let validator = Arc::new(JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let logger = Arc::new(SecureLogger::new(&SecureLogger::generate_key()));
+        let app = create_authenticated_app_with_config(db, &config::RelayConfig::default(), validator, logger);
+        let claims = jwt_validator::Claims {
+            sub: "reader".to_string(),
+            iss: "issuer".to_string(),
+            aud: None,
+            exp: 9999999999,
+            iat: None,
+            nbf: None,
+            scope: Some("message:read".to_string()),
+        };
+        let token = jsonwebtoken::encode(&Default::default(), &claims, &jsonwebtoken::EncodingKey::from_secret(b"secret")).unwrap();
+        let request = |uri: &str, token: Option<&str>| {
+            let request = axum::http::Request::builder().uri(uri);
+            let request = match token {
+                Some(token) => request.header("authorization", format!("Bearer {}", token)),
+                None => request,
+            };
+            request.body(axum::body::Body::empty()).unwrap()
+        };
+
+        // ACT: Read messages and the maintenance state anonymously, then with a token
+        let anonymous = app.clone().oneshot(request("/v1/messages/default", None)).await.unwrap();
+        let legacy = app.clone().oneshot(request("/messages/default", None)).await.unwrap();
+        let admin = app.clone().oneshot(request("/v1/admin/maintenance", None)).await.unwrap();
+        let authenticated = app.clone().oneshot(request("/v1/messages/default", Some(&token))).await.unwrap();
+        let health = app.oneshot(request("/health", None)).await.unwrap();
+
+        // ASSERT: Only the operational routes stay open
+        assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(legacy.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(admin.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(authenticated.status(), StatusCode::OK);
+        assert_eq!(health.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn open_relay_does_not_serve_admin_or_webhook_routes() {
+        use tower::ServiceExt;
+
+        // ARRANGE: The router the relay binary serves when no authentication is configured
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let app = create_app_with_config(db, &config::RelayConfig::default());
+        let request = |method: &str, uri: &str| {
+            axum::http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from("{}"))
+                .unwrap()
+        };
+
+        // ACT: Call each administrative route anonymously
+        let mut statuses = Vec::new();
+        for (method, uri) in [
+            ("POST", "/v1/admin/maintenance"),
+            ("GET", "/admin/maintenance"),
+            ("POST", "/v1/admin/data-subjects/erasures"),
+            ("GET", "/v1/admin/abuse/reports"),
+            ("GET", "/v1/admin/integrity"),
+            ("GET", "/v1/admin/compliance/summary"),
+            ("GET", "/v1/quarantine"),
+            ("POST", "/v1/webhooks"),
+            ("GET", "/webhooks"),
+        ] {
+            statuses.push((uri, app.clone().oneshot(request(method, uri)).await.unwrap().status()));
+        }
+        let health = app.oneshot(request("GET", "/health")).await.unwrap();
+
+        // ASSERT: None of them exist on the open router
+        for (uri, status) in statuses {
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+        }
+        assert_eq!(health.status(), StatusCode::OK);
+    }
+
     #[tokio::test]
     async fn process_and_verify_message_accepts_valid_message() {
         // ARRANGE: Create a valid message
@@ -0,0 +1,243 @@
+//! Request Size Limits Module
+//!
+//! This module caps how much data a client can make the relay buffer and
+//! verify. The whole request body is bounded before any handler runs, and
+//! the context and body of a relayed message are checked before its
+//! signature is parsed, so an oversized message is never hashed or
+//! verified. Every violation is answered with `413 Payload Too Large` and a
+//! JSON body naming the exceeded limit.
+
+use axum::{
+    body::Body,
+    extract::{DefaultBodyLimit, Request, State},
+    http::header::CONTENT_LENGTH,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension, Router,
+};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::{AppError, Message};
+
+/// A size limit that a request exceeded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LimitExceeded {
+    /// Which limit was exceeded (`body`, `context` or `message_body`)
+    pub limit: &'static str,
+    /// Maximum allowed size in bytes
+    pub max_bytes: usize,
+    /// Actual size in bytes, when known
+    pub actual_bytes: Option<usize>,
+}
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.actual_bytes {
+            Some(actual) => write!(f, "{} is {} bytes, limit is {} bytes", self.limit, actual, self.max_bytes),
+            None => write!(f, "{} exceeds the limit of {} bytes", self.limit, self.max_bytes),
+        }
+    }
+}
+
+/// Size limits for incoming requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestLimits {
+    /// Maximum request body size in bytes
+    pub max_body_bytes: usize,
+    /// Maximum decoded size of a message's signed context in bytes
+    pub max_context_bytes: usize,
+    /// Maximum size of a message body in bytes
+    pub max_message_body_bytes: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 1024 * 1024,
+            max_context_bytes: 64 * 1024,
+            max_message_body_bytes: 256 * 1024,
+        }
+    }
+}
+
+impl RequestLimits {
+    /// Build limits from the relay configuration
+    pub fn from_config(config: &crate::config::LimitsConfig) -> Self {
+        Self {
+            max_body_bytes: config.max_request_body_bytes,
+            max_context_bytes: config.max_context_bytes,
+            max_message_body_bytes: config.max_message_body_bytes,
+        }
+    }
+
+    /// Check a message's context and body sizes
+    ///
+    /// The context is hex encoded, so its decoded size is half its length.
+    pub fn validate_message(&self, message: &Message) -> Result<(), AppError> {
+        let context_bytes = message.context.len().div_ceil(2);
+        if context_bytes > self.max_context_bytes {
+            return Err(AppError::PayloadTooLarge(LimitExceeded {
+                limit: "context",
+                max_bytes: self.max_context_bytes,
+                actual_bytes: Some(context_bytes),
+            }));
+        }
+        self.validate_body(&message.body)
+    }
+
+    /// Check a message body's size
+    pub fn validate_body(&self, body: &str) -> Result<(), AppError> {
+        if body.len() > self.max_message_body_bytes {
+            return Err(AppError::PayloadTooLarge(LimitExceeded {
+                limit: "message_body",
+                max_bytes: self.max_message_body_bytes,
+                actual_bytes: Some(body.len()),
+            }));
+        }
+        Ok(())
+    }
+
+    /// The error for a request body over the limit
+    fn body_too_large(&self, actual_bytes: Option<usize>) -> AppError {
+        AppError::PayloadTooLarge(LimitExceeded {
+            limit: "body",
+            max_bytes: self.max_body_bytes,
+            actual_bytes,
+        })
+    }
+}
+
+/// Check a message against the configured limits, or the defaults if none are configured
+pub fn validate_message(limits: Option<&Arc<RequestLimits>>, message: &Message) -> Result<(), AppError> {
+    match limits {
+        Some(limits) => limits.validate_message(message),
+        None => RequestLimits::default().validate_message(message),
+    }
+}
+
+/// Check a message body against the configured limits, or the defaults if none are configured
+pub fn validate_body(limits: Option<&Arc<RequestLimits>>, body: &str) -> Result<(), AppError> {
+    match limits {
+        Some(limits) => limits.validate_body(body),
+        None => RequestLimits::default().validate_body(body),
+    }
+}
+
+/// Middleware rejecting request bodies over the size limit
+///
+/// Requests declaring an oversized `Content-Length` are rejected without
+/// reading the body; other bodies are read up to the limit.
+pub async fn enforce_body_limit(
+    State(limits): State<Arc<RequestLimits>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let declared = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if let Some(length) = declared {
+        if length > limits.max_body_bytes {
+            return limits.body_too_large(Some(length)).into_response();
+        }
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, limits.max_body_bytes).await {
+        Ok(bytes) => bytes,
+        Err(_) => return limits.body_too_large(None).into_response(),
+    };
+
+    let mut request = Request::from_parts(parts, Body::from(bytes));
+    request.extensions_mut().insert(limits);
+    next.run(request).await
+}
+
+/// Apply request size limits to every route of a router
+///
+/// Handlers can read the limits through an `Extension<Arc<RequestLimits>>`.
+pub fn with_request_limits(router: Router, limits: RequestLimits) -> Router {
+    let limits = Arc::new(limits);
+    router
+        .layer(DefaultBodyLimit::max(limits.max_body_bytes))
+        .layer(axum::middleware::from_fn_with_state(limits.clone(), enforce_body_limit))
+        .layer(Extension(limits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_app, database::Database};
+    use axum::http::StatusCode;
+    use tower::ServiceExt;
+
+    fn message(context_bytes: usize, body_bytes: usize) -> Message {
+        Message {
+            sender: "aa".repeat(32),
+            context: "bb".repeat(context_bytes),
+            body: "x".repeat(body_bytes),
+            proof: "cc".repeat(64),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    #[test]
+    fn test_message_limits() {
+        let limits = RequestLimits {
+            max_body_bytes: 1024,
+            max_context_bytes: 16,
+            max_message_body_bytes: 32,
+        };
+
+        assert!(limits.validate_message(&message(16, 32)).is_ok());
+        assert!(matches!(
+            limits.validate_message(&message(17, 0)),
+            Err(AppError::PayloadTooLarge(LimitExceeded { limit: "context", actual_bytes: Some(17), .. }))
+        ));
+        assert!(matches!(
+            limits.validate_message(&message(0, 33)),
+            Err(AppError::PayloadTooLarge(LimitExceeded { limit: "message_body", .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_requests_get_structured_413() {
+        // ARRANGE: A relay with the default limits
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let app = create_app(db);
+        let defaults = RequestLimits::default();
+        let relay = |message: &Message| {
+            Request::builder()
+                .method("POST")
+                .uri("/relay")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(message).unwrap()))
+                .unwrap()
+        };
+
+        // ACT: Send an oversized context and an oversized request body
+        let context = app.clone().oneshot(relay(&message(defaults.max_context_bytes + 1, 0))).await.unwrap();
+        let body = app.oneshot(relay(&message(0, defaults.max_body_bytes + 1))).await.unwrap();
+
+        // ASSERT: Both are rejected with the limit that was exceeded
+        assert_eq!(context.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let json: serde_json::Value =
+            serde_json::from_slice(&axum::body::to_bytes(context.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(json["code"], "PAYLOAD_TOO_LARGE");
+        assert_eq!(json["details"]["limit"], "context");
+        assert_eq!(json["details"]["max_bytes"], defaults.max_context_bytes);
+        assert_eq!(json["details"]["actual_bytes"], defaults.max_context_bytes + 1);
+
+        assert_eq!(body.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let json: serde_json::Value =
+            serde_json::from_slice(&axum::body::to_bytes(body.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(json["details"]["limit"], "body");
+    }
+}
@@ -0,0 +1,263 @@
+//! Request Log Redaction Module
+//!
+//! Request paths and query strings can carry personal data, such as an email
+//! address used as a search term or a client IP passed as a parameter, and
+//! anything recorded on a tracing span ends up in the relay's logs. This
+//! module runs the metadata the relay logs about each request and response
+//! through the protocol crate's [`PIIDetector`] first:
+//!
+//! - each path segment and query value is checked on its own and replaced
+//!   with `[REDACTED]` when it looks like PII
+//! - only the headers named in `logging.logged_headers` are logged, and their
+//!   values are checked the same way; credentials are never logged
+//!
+//! [`trace_layer`] is a drop-in replacement for
+//! `TraceLayer::new_for_http()` that records redacted metadata, and the
+//! request span opened by [`crate::request_id`] uses the same redaction.
+//! Redaction is on by default; it is configured by the `[logging]` relay
+//! settings (see [`crate::config::LoggingConfig`]).
+
+use axum::http::{HeaderMap, HeaderName, Request, Response, Uri};
+use once_cell::sync::OnceCell;
+use percent_encoding::percent_decode_str;
+use proof_messenger_protocol::compliance::PIIDetector;
+use serde_json::Value;
+use std::time::Duration;
+use tower_http::{
+    classify::{ServerErrorsAsFailures, SharedClassifier},
+    trace::{DefaultOnRequest, MakeSpan, OnResponse, TraceLayer},
+};
+use tracing::Span;
+
+use crate::config::LoggingConfig;
+
+/// Replacement for redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Headers whose values are never logged
+const CREDENTIAL_HEADERS: [&str; 4] = ["authorization", "proxy-authorization", "cookie", "set-cookie"];
+
+/// Redactor installed for the process
+static INSTALLED: OnceCell<LogRedactor> = OnceCell::new();
+
+/// Redacts PII from request and response metadata before it is logged
+pub struct LogRedactor {
+    /// `None` when redaction is disabled
+    detector: Option<PIIDetector>,
+    logged_headers: Vec<HeaderName>,
+}
+
+impl LogRedactor {
+    /// Redactor for the given logging settings
+    ///
+    /// Header names that are not valid are skipped; the configuration
+    /// validation reports them.
+    pub fn new(config: &LoggingConfig) -> Self {
+        Self {
+            detector: config.redact_pii.then(PIIDetector::new),
+            logged_headers: config
+                .logged_headers
+                .iter()
+                .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+                .collect(),
+        }
+    }
+
+    /// Whether a single decoded value looks like PII
+    fn is_pii(&self, value: &str) -> bool {
+        match &self.detector {
+            Some(detector) => detector.detect_pii(&Value::String(value.to_string())).is_some(),
+            None => false,
+        }
+    }
+
+    /// A value as it may be logged
+    pub fn redact_value<'a>(&self, value: &'a str) -> &'a str {
+        if self.is_pii(value) {
+            REDACTED
+        } else {
+            value
+        }
+    }
+
+    /// A URI path with every PII-carrying segment redacted
+    pub fn redact_path(&self, path: &str) -> String {
+        path.split('/')
+            .map(|segment| {
+                if self.is_pii(&percent_decode_str(segment).decode_utf8_lossy()) {
+                    REDACTED
+                } else {
+                    segment
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// A query string with every PII-carrying value redacted
+    ///
+    /// Parameter names are kept so the shape of the request stays visible.
+    pub fn redact_query(&self, query: &str) -> String {
+        query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, value)) => {
+                    let decoded = value.replace('+', " ");
+                    if self.is_pii(&percent_decode_str(&decoded).decode_utf8_lossy()) {
+                        format!("{}={}", name, REDACTED)
+                    } else {
+                        pair.to_string()
+                    }
+                }
+                None => self.redact_path(pair),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// A request URI's path and query as they may be logged
+    pub fn redact_uri(&self, uri: &Uri) -> String {
+        let path = self.redact_path(uri.path());
+        match uri.query() {
+            Some(query) => format!("{}?{}", path, self.redact_query(query)),
+            None => path,
+        }
+    }
+
+    /// The configured headers present in `headers`, with their values redacted
+    pub fn redact_headers(&self, headers: &HeaderMap) -> Vec<(String, String)> {
+        self.logged_headers
+            .iter()
+            .flat_map(|name| headers.get_all(name).iter().map(move |value| (name, value)))
+            .map(|(name, value)| {
+                let value = if CREDENTIAL_HEADERS.contains(&name.as_str()) {
+                    REDACTED
+                } else {
+                    value.to_str().map_or(REDACTED, |value| self.redact_value(value))
+                };
+                (name.to_string(), value.to_string())
+            })
+            .collect()
+    }
+}
+
+impl Default for LogRedactor {
+    fn default() -> Self {
+        Self::new(&LoggingConfig::default())
+    }
+}
+
+/// Use a redactor for the rest of the process
+///
+/// Only the first installed redactor takes effect.
+pub fn install(redactor: LogRedactor) {
+    let _ = INSTALLED.set(redactor);
+}
+
+/// The installed redactor, or one using the default settings if none is installed
+pub fn current() -> &'static LogRedactor {
+    INSTALLED.get_or_init(LogRedactor::default)
+}
+
+/// Opens each request's trace span with redacted metadata
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedactedMakeSpan;
+
+impl<B> MakeSpan<B> for RedactedMakeSpan {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        let redactor = current();
+        tracing::debug_span!(
+            "request",
+            method = %request.method(),
+            uri = %redactor.redact_uri(request.uri()),
+            version = ?request.version(),
+            headers = ?redactor.redact_headers(request.headers()),
+        )
+    }
+}
+
+/// Logs each response's status, latency and redacted headers
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedactedOnResponse;
+
+impl<B> OnResponse<B> for RedactedOnResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, _span: &Span) {
+        tracing::debug!(
+            status = response.status().as_u16(),
+            latency = %format!("{} ms", latency.as_millis()),
+            headers = ?current().redact_headers(response.headers()),
+            "finished processing request"
+        );
+    }
+}
+
+/// HTTP trace layer that never logs unredacted request metadata
+pub fn trace_layer(
+) -> TraceLayer<SharedClassifier<ServerErrorsAsFailures>, RedactedMakeSpan, DefaultOnRequest, RedactedOnResponse> {
+    TraceLayer::new_for_http()
+        .make_span_with(RedactedMakeSpan)
+        .on_response(RedactedOnResponse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor(logged_headers: &[&str]) -> LogRedactor {
+        LogRedactor::new(&LoggingConfig {
+            redact_pii: true,
+            logged_headers: logged_headers.iter().map(|name| name.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn test_pii_in_paths_and_queries_is_redacted() {
+        let redactor = redactor(&[]);
+        let uri: Uri = "/search/alice%40example.com/messages?q=bob@example.com&limit=10&ip=203.0.113.7"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            redactor.redact_uri(&uri),
+            "/search/[REDACTED]/messages?q=[REDACTED]&limit=10&ip=[REDACTED]"
+        );
+        assert_eq!(redactor.redact_path("/messages/general"), "/messages/general");
+    }
+
+    #[test]
+    fn test_only_configured_headers_are_logged() {
+        // ARRANGE: Headers carrying a credential, PII and harmless metadata
+        let redactor = redactor(&["authorization", "x-forwarded-for", "accept"]);
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer abc".parse().unwrap());
+        headers.insert("x-forwarded-for", "198.51.100.23".parse().unwrap());
+        headers.insert("accept", "application/json".parse().unwrap());
+        headers.insert("user-agent", "curl/8.5.0".parse().unwrap());
+
+        // ACT: Select the headers to log
+        let logged = redactor.redact_headers(&headers);
+
+        // ASSERT: Credentials and PII are redacted, unlisted headers are dropped
+        assert_eq!(
+            logged,
+            vec![
+                ("authorization".to_string(), REDACTED.to_string()),
+                ("x-forwarded-for".to_string(), REDACTED.to_string()),
+                ("accept".to_string(), "application/json".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redaction_can_be_disabled() {
+        let redactor = LogRedactor::new(&LoggingConfig {
+            redact_pii: false,
+            logged_headers: vec!["authorization".to_string()],
+        });
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer abc".parse().unwrap());
+
+        assert_eq!(redactor.redact_query("q=bob@example.com"), "q=bob@example.com");
+        assert_eq!(redactor.redact_headers(&headers)[0].1, REDACTED);
+    }
+}
@@ -1,15 +1,59 @@
-use proof_messenger_relay::{database::Database, create_app_with_rate_limiting};
+use proof_messenger_relay::{database::{Database, DatabaseError}, create_app_with_config, create_authenticated_app_with_config};
+use proof_messenger_relay::config::{NewerSchemaPolicy, RelayConfig};
+use proof_messenger_relay::grpc::{self, GrpcState};
+use proof_messenger_relay::limits::RequestLimits;
+use proof_messenger_relay::maintenance::MaintenanceMode;
+use proof_messenger_relay::federation::{Federation, FederationConfig};
+use proof_messenger_relay::webhooks::{WebhookConfig, WebhookDispatcher};
+use proof_messenger_relay::event_stream::EventStream;
+use proof_messenger_relay::quarantine::{Quarantine, QuarantineConfig};
+use proof_messenger_relay::abuse::AbuseDetector;
+use proof_messenger_relay::replay::ReplayGuard;
+use proof_messenger_relay::subscriptions::Subscriptions;
+use proof_messenger_relay::context_policy::ContextPolicy;
+use proof_messenger_relay::key_pinning::KeyPinning;
+use proof_messenger_relay::timestamping::TimestampAuthority;
+use proof_messenger_relay::compliance_audit::ComplianceAudit;
+use proof_messenger_relay::integrity::IntegrityVerifier;
+use proof_messenger_relay::scheduling::{ReleasePipeline, Scheduler};
+use proof_messenger_relay::tenancy::Tenancy;
+use proof_messenger_relay::versioning::ApiVersioning;
+use proof_messenger_relay::jwt_validator::JwtValidator;
+use proof_messenger_relay::secure_logger::SecureLogger;
+use proof_messenger_relay::siem::SiemForwarder;
+use proof_messenger_relay::transparency::TransparencyLog;
+use proof_messenger_relay::data_subjects::DataSubjects;
+use proof_messenger_relay::readiness::{Readiness, ReadinessConfig};
+use proof_messenger_relay::tls;
+use proof_messenger_relay::expiry;
+use axum_server::tls_rustls::RustlsConfig;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{error, info, warn};
 
 #[tokio::main]
 async fn main() {
     // Initialize tracing
     tracing_subscriber::fmt::init();
 
+    // Load the relay configuration file and environment overrides
+    let config = match RelayConfig::load() {
+        Ok(config) => config,
+        Err(e) => panic!("{}", e),
+    };
+    if let Err(e) = config.install() {
+        panic!("{}", e);
+    }
+
+    // Outbound requests use the egress policy installed with the configuration
+    if config.egress.proxy.is_some() {
+        info!("🌐 Outbound requests go through the configured proxy");
+    }
+    if !config.egress.allowed_hosts.is_empty() {
+        info!("🌐 Outbound requests restricted to: {}", config.egress.allowed_hosts.join(", "));
+    }
+
     // Initialize database
-    let database_url = std::env::var("DATABASE_URL")
-        .unwrap_or_else(|_| "sqlite:/app/db/messages.db".to_string());
+    let database_url = config.database.url.clone();
     
     info!("Connecting to database: {}", database_url);
     
@@ -56,9 +100,18 @@ async fn main() {
     }
     
     // Connect to database with better error handling
-    let db = match Database::new(&database_url).await {
+    let db = match Database::connect(&config.database).await {
         Ok(db) => {
-            info!("Successfully connected to database");
+            info!("Successfully connected to database (up to {} connections)", config.database.max_connections);
+            if let Some(replica) = &config.database.read_replica_url {
+                info!("📖 Message reads served by read replica: {}", replica);
+            }
+            if config.database.write_behind.enabled {
+                info!(
+                    "📦 Message writes batched (up to {} per {}ms)",
+                    config.database.write_behind.batch_size, config.database.write_behind.flush_interval_ms
+                );
+            }
             db
         },
         Err(e) => {
@@ -70,22 +123,501 @@ async fn main() {
     info!("Running database migrations...");
     match db.migrate().await {
         Ok(_) => info!("Database migrations completed successfully"),
+        Err(e @ DatabaseError::SchemaAhead { .. }) => match config.database.on_newer_schema {
+            NewerSchemaPolicy::Ignore => warn!("{}; starting without migrating", e),
+            NewerSchemaPolicy::Refuse => {
+                error!("{}; refusing to start (set database.on_newer_schema = \"ignore\" to start anyway)", e);
+                std::process::exit(1);
+            }
+        },
         Err(e) => {
             info!("Database migration error: {:?}", e);
             panic!("Failed to run database migrations: {:?}", e);
         }
     };
     
+    match db.pragmas().await {
+        Ok(pragmas) => info!(
+            "SQLite pragmas: journal_mode={}, busy_timeout={}ms, synchronous={}",
+            pragmas.journal_mode, pragmas.busy_timeout_ms, pragmas.synchronous
+        ),
+        Err(e) => warn!("Failed to read SQLite pragmas: {}", e),
+    }
+    // Publish the revocation list size before the first revocation updates it
+    if let Err(e) = db.count_active_revocations().await {
+        warn!("Failed to count active revocations: {}", e);
+    }
+
     let db = Arc::new(db);
 
-    let app = create_app_with_rate_limiting(db);
+    // Forward security events to the SIEM when configured
+    let siem = match SiemForwarder::from_config(&config.siem) {
+        Ok(Some(forwarder)) => {
+            let forwarder = Arc::new(forwarder);
+            info!("🛰️ Security events forwarded to the SIEM ({:?} sink)", forwarder.sink());
+            forwarder.clone().spawn();
+            Some(forwarder)
+        }
+        Ok(None) => {
+            info!("SIEM forwarding disabled (siem.sink not set)");
+            None
+        }
+        Err(e) => panic!("Invalid SIEM configuration: {}", e),
+    };
+
+    // Security events are encrypted with the AUDIT_LOG_KEY when it is set, so payloads
+    // forwarded to the SIEM can be decrypted later, and with a throwaway key otherwise
+    let security_key = std::env::var("AUDIT_LOG_KEY")
+        .ok()
+        .and_then(|key| hex::decode(key.trim()).ok())
+        .and_then(|key| <[u8; 32]>::try_from(key).ok())
+        .unwrap_or_else(SecureLogger::generate_key);
+    let security_logger = || {
+        let logger = SecureLogger::new(&security_key);
+        Arc::new(match &siem {
+            Some(siem) => logger.with_forwarder(siem.clone()),
+            None => logger,
+        })
+    };
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
+    // Authenticate API requests whenever an authentication method is configured
+    let mut app = if config.authentication_enabled() {
+        let validator = match JwtValidator::from_config(&config.oauth).await {
+            Ok(validator) => Arc::new(validator),
+            Err(e) => panic!("Invalid OAuth configuration: {}", e),
+        };
+        for issuer in &config.oauth.issuers {
+            info!("🔐 Bearer tokens accepted from {} (keys from {})", issuer.issuer, issuer.jwks_url);
+        }
+        if let Some(introspection) = &config.oauth.introspection {
+            info!("🔎 Opaque tokens introspected at {}", introspection.endpoint);
+        }
+        create_authenticated_app_with_config(db.clone(), &config, validator, security_logger())
+    } else {
+        warn!("⚠️ No authentication configured (oauth.issuers, api_keys, key_auth, client_identity): API routes are open and admin and webhook routes are disabled");
+        create_app_with_config(db.clone(), &config)
+    };
+    match &config.redis.url {
+        Some(_) => info!("🧮 Rate limits shared across replicas through Redis"),
+        None => info!("Rate limits kept in memory (redis.url not set)"),
+    }
     
+    // Enable federation with peer relays when configured
+    let federation = match FederationConfig::from_config(&config.federation).and_then(|config| config.map(Federation::new).transpose()) {
+        Ok(Some(federation)) => {
+            let federation = Arc::new(federation);
+            info!("🌐 Federation enabled as relay '{}' with {} peers", federation.relay_id(), federation.peers().len());
+            federation.clone().spawn_reconciliation(db.clone());
+            app = app.layer(axum::Extension(federation.clone()));
+            Some(federation)
+        }
+        Ok(None) => {
+            info!("Federation disabled (federation.peers not set)");
+            None
+        }
+        Err(e) => panic!("Invalid federation configuration: {}", e),
+    };
+
+    // Deliver webhook notifications for verified messages
+    let webhooks = match WebhookDispatcher::new(WebhookConfig::from_config(&config.webhooks)) {
+        Ok(dispatcher) => {
+            let dispatcher = Arc::new(dispatcher);
+            info!("🔔 Webhook notifications enabled");
+            dispatcher.clone().spawn_worker(db.clone());
+            app = app.layer(axum::Extension(dispatcher.clone()));
+            dispatcher
+        }
+        Err(e) => panic!("Invalid webhook configuration: {}", e),
+    };
+
+    // Publish verified-message events to a streaming platform when configured
+    let events = match EventStream::from_config(&config.event_stream) {
+        Ok(Some(stream)) => {
+            let stream = Arc::new(stream);
+            info!(
+                "📡 Verified-message events published to {:?} topic '{}'",
+                config.event_stream.sink.unwrap(),
+                config.event_stream.topic
+            );
+            stream.clone().spawn_worker(db.clone());
+            app = app.layer(axum::Extension(stream.clone()));
+            Some(stream)
+        }
+        Ok(None) => {
+            info!("Event stream disabled (event_stream.sink not set)");
+            None
+        }
+        Err(e) => panic!("Invalid event stream configuration: {}", e),
+    };
+
+    // Deliver verified messages to WebSocket subscribers, across replicas when a backplane is configured
+    let subscriptions = match Subscriptions::from_config(&config) {
+        Ok(subscriptions) => {
+            let subscriptions = Arc::new(subscriptions);
+            match config.subscriptions.backplane {
+                Some(backplane) => info!("📣 Live subscriptions shared across replicas through {:?}", backplane),
+                None => info!("📣 Live subscriptions served by this relay only (subscriptions.backplane not set)"),
+            }
+            subscriptions.clone().spawn_backplane_listener();
+            app = app.layer(axum::Extension(subscriptions.clone()));
+            subscriptions
+        }
+        Err(e) => panic!("Invalid subscription configuration: {}", e),
+    };
+
+    // Sign transparency log tree heads when a key is configured
+    match TransparencyLog::from_env() {
+        Ok(Some(log)) => {
+            let log = Arc::new(log);
+            info!("🌳 Transparency log tree heads signed with key {}", log.public_key_hex());
+            app = app.layer(axum::Extension(log));
+        }
+        Ok(None) => info!("Signed tree heads disabled (TRANSPARENCY_SIGNING_KEY not set)"),
+        Err(e) => panic!("Invalid transparency log configuration: {}", e),
+    }
+
+    // Answer data subject export and erasure requests when a signing key is configured
+    match DataSubjects::from_env(chrono::Duration::days(config.retention.erasure_grace_days)) {
+        Ok(Some(data_subjects)) => {
+            let data_subjects = Arc::new(data_subjects);
+            info!(
+                "🗃️ Data subject requests enabled: exports signed with key {}, erasures after {} days",
+                data_subjects.public_key_hex(),
+                config.retention.erasure_grace_days
+            );
+            data_subjects.clone().spawn_erasure(db.clone());
+            app = app.layer(axum::Extension(data_subjects));
+        }
+        Ok(None) => info!("Data subject requests disabled (DATA_SUBJECT_SIGNING_KEY not set)"),
+        Err(e) => panic!("Invalid data subject configuration: {}", e),
+    }
+
+    // Purge ephemeral messages once their expiry has passed
+    expiry::spawn_purge(db.clone(), std::time::Duration::from_secs(config.retention.expiry_purge_interval_secs));
+
+    // Keep messages that fail verification for investigation when enabled
+    let quarantine = if config.features.quarantine {
+        let quarantine = Arc::new(Quarantine::new(QuarantineConfig {
+            retention: chrono::Duration::days(config.retention.quarantine_days),
+            ..Default::default()
+        }));
+        info!("🧪 Quarantine mode enabled for rejected messages");
+        quarantine.clone().spawn_cleanup(db.clone());
+        app = app.layer(axum::Extension(quarantine.clone()));
+        Some(quarantine)
+    } else {
+        info!("Quarantine mode disabled");
+        None
+    };
+
+    // Quarantine senders that keep failing verification, replay revoked proofs or burst
+    let abuse = if config.abuse_detection.enabled {
+        let abuse = Arc::new(AbuseDetector::new(&config.abuse_detection, security_logger()));
+        info!(
+            "🚨 Abuse detection enabled: senders are quarantined for {}s",
+            config.abuse_detection.quarantine_secs
+        );
+        abuse.clone().spawn_cleanup();
+        app = app.layer(axum::Extension(abuse.clone()));
+        Some(abuse)
+    } else {
+        info!("Abuse detection disabled");
+        None
+    };
+
+    // Forward compliance audit entries to the configured sink
+    let audit = match ComplianceAudit::from_config(&config.audit, std::env::var("AUDIT_LOG_KEY").ok().as_deref(), &db) {
+        Ok(Some(audit)) => {
+            info!("🗂️ Compliance audit entries forwarded to the {:?} sink", config.audit.sink.unwrap());
+            if let Some(public_key) = audit.checkpoint_public_key() {
+                info!("🔗 Audit log checkpoints signed by {}", public_key);
+            }
+            let audit = Arc::new(audit);
+            app = app.layer(axum::Extension(audit.clone()));
+            Some(audit)
+        }
+        Ok(None) => {
+            info!("Compliance audit forwarding disabled (audit.sink not set)");
+            None
+        }
+        Err(e) => panic!("Invalid audit configuration: {}", e),
+    };
+
+    // Reject replays of already relayed contexts when enabled, across replicas when Redis is configured
+    let replay = match ReplayGuard::from_config(&config) {
+        Ok(Some(guard)) => {
+            let guard = Arc::new(guard);
+            match &config.redis.url {
+                Some(_) => info!("🔁 Replay protection enabled with nonces shared through Redis"),
+                None => info!("🔁 Replay protection enabled with nonces kept in memory"),
+            }
+            app = app.layer(axum::Extension(guard.clone()));
+            Some(guard)
+        }
+        Ok(None) => {
+            info!("Replay protection disabled");
+            None
+        }
+        Err(e) => panic!("Invalid replay protection configuration: {}", e),
+    };
+
+    // Check relayed contexts against a compliance policy when configured
+    let context_policy = match &config.features.context_policy {
+        Some(name) => {
+            let policy = match ContextPolicy::new(name, security_logger()) {
+                Ok(policy) => policy,
+                Err(e) => panic!("Invalid context policy configuration: {}", e),
+            };
+            let policy = Arc::new(match &audit {
+                Some(audit) => policy.with_audit(audit.clone()),
+                None => policy,
+            });
+            info!("📋 Relayed contexts must satisfy the '{}' policy", policy.name());
+            app = app.layer(axum::Extension(policy.clone()));
+            Some(policy)
+        }
+        None => {
+            info!("Context policy enforcement disabled");
+            None
+        }
+    };
+
+    // Pin each authenticated user's first public key and watch for changes when configured
+    match config.features.key_pinning {
+        Some(mode) => {
+            let pinning = match &audit {
+                Some(audit) => KeyPinning::new(mode).with_audit(audit.clone()),
+                None => KeyPinning::new(mode),
+            };
+            info!("📌 Key pinning enabled ({:?} on key change)", mode);
+            app = app.layer(axum::Extension(Arc::new(pinning)));
+        }
+        None => info!("Key pinning disabled"),
+    }
+
+    // Request an RFC 3161 timestamp token for each stored message when a TSA is configured
+    let timestamping = match TimestampAuthority::from_config(&config.timestamping) {
+        Ok(Some(authority)) => {
+            let authority = Arc::new(authority);
+            info!("🕰️ Message timestamps requested from {}", authority.url());
+            app = app.layer(axum::Extension(authority.clone()));
+            Some(authority)
+        }
+        Ok(None) => {
+            info!("Timestamping disabled (timestamping.tsa_url not set)");
+            None
+        }
+        Err(e) => panic!("Invalid timestamping configuration: {}", e),
+    };
+
+    // Re-verify stored message proofs periodically when enabled
+    if config.integrity.enabled {
+        let verifier = IntegrityVerifier::new(&config.integrity, security_logger());
+        let verifier = Arc::new(match &audit {
+            Some(audit) => verifier.with_audit(audit.clone()),
+            None => verifier,
+        });
+        info!("🧾 Stored message proofs re-verified every {}s", config.integrity.interval_secs);
+        verifier.clone().spawn(db.clone());
+        app = app.layer(axum::Extension(verifier));
+    } else {
+        info!("Stored message integrity verification disabled");
+    }
+
+    // Attribute requests to tenants with their own groups, policies and retention when configured
+    let tenancy = if config.tenancy.enabled() {
+        let tenancy = match Tenancy::new(&config.tenancy, security_logger(), audit.clone()) {
+            Ok(tenancy) => Arc::new(tenancy),
+            Err(e) => panic!("Invalid tenancy configuration: {}", e),
+        };
+        info!("🏢 Multi-tenancy enabled for {} tenants", config.tenancy.tenants.len());
+        tenancy.clone().spawn_retention(db.clone());
+        app = app.layer(axum::Extension(tenancy.clone()));
+        Some(tenancy)
+    } else {
+        info!("Multi-tenancy disabled");
+        None
+    };
+
+    // Group scopes are granted by the authenticated router
+    if config.rbac.enabled() {
+        info!("👥 Scopes granted to {} identity provider groups", config.rbac.groups.len());
+    }
+
+    // API keys from machine clients are accepted by the authenticated router when enabled
+    if config.api_keys.enabled {
+        info!("🔑 API key authentication enabled ({} requests/minute by default)", config.api_keys.requests_per_minute);
+    } else {
+        info!("API key authentication disabled");
+    }
+
+    // Session tokens for clients that sign a challenge are issued by the authenticated router
+    if config.key_auth.enabled {
+        info!("🔏 Key challenge authentication enabled for {} keys ({}s sessions)", config.key_auth.keys.len(), config.key_auth.session_ttl_secs);
+    }
+
+    // Client certificate identities are accepted by the authenticated router when enabled
+    if config.client_identity.enabled {
+        info!("🪪 Client certificate authentication enabled for {} identities", config.client_identity.identities.len());
+    }
+
+    // Scopes required by authenticated routes are layered by the authenticated router
+    if !config.authorization.routes.is_empty() {
+        info!("🔐 {} route scope requirements configured", config.authorization.routes.len());
+    }
+
+    // Announce when the deprecated unversioned routes will be removed
+    if let Some(sunset) = config.api.legacy_sunset {
+        info!("🕰️ Unversioned API routes sunset at {}", sunset);
+    }
+    app = app.layer(axum::Extension(Arc::new(ApiVersioning::new(&config.api))));
+
+    // Probe the first configured token issuer's JWKS endpoint for readiness
+    let readiness = Readiness::new(ReadinessConfig::from_config(&config));
+    app = app.layer(axum::Extension(Arc::new(readiness)));
+
+    // Start in read-only mode when configured; POST /admin/maintenance switches it
+    let maintenance = Arc::new(MaintenanceMode::new(&config.maintenance));
+    if config.maintenance.read_only {
+        warn!("🚧 Relay starting in read-only mode: writes are refused until it is switched off");
+    }
+    app = app.layer(axum::Extension(maintenance.clone()));
+
+    // The verification pipeline and pool are installed with the configuration
+    info!("🔎 Verification checks: {}", proof_messenger_relay::checks::current().names().join(", "));
+    for plugin in &config.plugins {
+        info!("🧩 Policy plugin '{}' loaded from {}", plugin.name, plugin.path.display());
+    }
+    match proof_messenger_relay::verification_pool::installed() {
+        Some(pool) => info!("🧮 Signature verification offloaded, {} at once", pool.max_concurrent()),
+        None => info!("Signature verification runs on the async runtime"),
+    }
+
+    // Release scheduled messages through the relay pipeline when enabled
+    if config.scheduling.enabled {
+        let scheduler = Arc::new(Scheduler::new(&config.scheduling).with_pipeline(ReleasePipeline {
+            federation: federation.clone(),
+            webhooks: Some(webhooks.clone()),
+            events: events.clone(),
+            replay: replay.clone(),
+            subscriptions: Some(subscriptions.clone()),
+            quarantine: quarantine.clone(),
+            abuse: abuse.clone(),
+            limits: Some(Arc::new(RequestLimits::from_config(&config.limits))),
+            context_policy: context_policy.clone(),
+            timestamping: timestamping.clone(),
+            tenancy: tenancy.clone(),
+            maintenance: Some(maintenance.clone()),
+        }));
+        info!("⏰ Scheduled messages released every {}s", config.scheduling.poll_interval_secs);
+        scheduler.clone().spawn(db.clone());
+        app = app.layer(axum::Extension(scheduler));
+    } else {
+        info!("Scheduled messages disabled");
+    }
+
+    // Serve the gRPC services on their own port when configured
+    if let Some(grpc_address) = config.server.grpc_bind_address {
+        let state = GrpcState {
+            db: db.clone(),
+            federation,
+            webhooks: Some(webhooks),
+            events,
+            replay,
+            subscriptions: Some(subscriptions),
+            quarantine,
+            abuse,
+            limits: Arc::new(RequestLimits::from_config(&config.limits)),
+            context_policy,
+            tenancy,
+            timestamping,
+            maintenance,
+        };
+        tokio::spawn(async move {
+            if let Err(e) = grpc::serve(grpc_address, state).await {
+                panic!("gRPC server failed: {}", e);
+            }
+        });
+    } else {
+        info!("gRPC services disabled (GRPC_BIND_ADDRESS not set)");
+    }
+
     info!("🚀 Relay server starting...");
-    info!("📡 Listening on 0.0.0.0:8080");
     info!("💾 Database initialized and ready");
-    info!("✅ Server ready to accept connections");
-    
-    axum::serve(listener, app).await.unwrap();
-}
\ No newline at end of file
+
+    // Terminate TLS in the relay when a certificate is configured
+    if config.tls.enabled() {
+        let server_config = match tls::load_server_config(&config.tls) {
+            Ok(server_config) => server_config,
+            Err(e) => panic!("Invalid TLS configuration: {}", e),
+        };
+        let rustls_config = RustlsConfig::from_config(server_config);
+        #[cfg(unix)]
+        if let Err(e) = tls::spawn_reload_on_sighup(config.tls.clone(), rustls_config.clone()) {
+            panic!("Failed to install SIGHUP handler for certificate reload: {}", e);
+        }
+        let verify_clients = config.tls.client_ca_path.is_some();
+        if verify_clients {
+            info!("🤝 Client certificates required for federation endpoints");
+        }
+
+        info!("🔐 Listening with TLS on {}", config.server.bind_address);
+        info!("✅ Server ready to accept connections");
+        let handle = axum_server::Handle::new();
+        tokio::spawn({
+            let handle = handle.clone();
+            async move {
+                shutdown_signal().await;
+                handle.graceful_shutdown(Some(std::time::Duration::from_secs(30)));
+            }
+        });
+        axum_server::bind(config.server.bind_address)
+            .handle(handle)
+            .acceptor(tls::RelayTlsAcceptor::new(rustls_config, verify_clients))
+            .serve(app.into_make_service())
+            .await
+            .unwrap();
+    } else {
+        let listener = tokio::net::TcpListener::bind(config.server.bind_address).await.unwrap();
+
+        info!("📡 Listening on {}", config.server.bind_address);
+        info!("✅ Server ready to accept connections");
+        axum::serve(listener, app).with_graceful_shutdown(shutdown_signal()).await.unwrap();
+    }
+
+    // Forward audit entries still buffered before exiting
+    if let Some(audit) = audit {
+        audit.shutdown().await;
+    }
+    // Likewise for security events waiting for the SIEM
+    if let Some(siem) = siem {
+        siem.shutdown().await;
+    }
+    info!("👋 Relay stopped");
+}
+
+/// Resolve when the process is asked to stop (Ctrl+C, or SIGTERM on Unix)
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            panic!("Failed to install Ctrl+C handler: {}", e);
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => panic!("Failed to install SIGTERM handler: {}", e),
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!("Shutdown requested, finishing in-flight requests");
+}
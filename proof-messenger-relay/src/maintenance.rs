@@ -0,0 +1,401 @@
+//! Maintenance Mode Module
+//!
+//! During migrations the relay can be switched to read-only mode: it keeps
+//! serving reads, but refuses writes with `503 READ_ONLY_MODE` and a
+//! `Retry-After` header, so well-behaved clients back off and retry once the
+//! maintenance is over. Rejected writes include `POST /relay`, revocations,
+//! invites, approvals and the other routes that store data, over HTTP and
+//! gRPC alike. Verification endpoints that only read stay available.
+//!
+//! The mode starts from the `maintenance.read_only` relay setting or
+//! `READ_ONLY_MODE=true` (see [`crate::config::RelayConfig`]) and is switched
+//! at runtime with `POST /admin/maintenance`. It is reported by `/ready`
+//! (which stays ready, since reads are still served) and by the
+//! `read_only_mode` gauge in the metrics registry. The switch applies to the
+//! replica that receives it; set the configuration flag to switch a fleet.
+//!
+//! The mode is shared by layering a [`MaintenanceMode`] onto the router as an
+//! [`axum::Extension`]; without one the relay accepts writes.
+
+use axum::{
+    extract::{Json, MatchedPath, Request, State},
+    http::{header::RETRY_AFTER, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use tracing::{info, instrument, warn};
+
+use crate::{
+    auth_middleware::AuthContext, config::MaintenanceConfig, database::Database, metrics, request_id::RequestId, AppError,
+};
+
+/// Routes that accept a write method but store nothing, as `"METHOD /path"`
+///
+/// The maintenance switch itself must stay reachable to leave read-only mode.
+/// Sender quarantines are held in memory, so they can still be lifted.
+const READ_ONLY_SAFE_ROUTES: &[&str] = &[
+    "POST /admin/maintenance",
+    "DELETE /admin/abuse/quarantines/:sender",
+    "POST /detached-proofs/verify",
+    "POST /derivations/verify",
+];
+
+/// Current maintenance state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MaintenanceStatus {
+    /// Whether writes are refused
+    pub read_only: bool,
+    /// Why the relay is read-only, shown to rejected clients
+    pub reason: Option<String>,
+    /// When read-only mode was entered
+    pub since: Option<DateTime<Utc>>,
+    /// Seconds clients are told to wait before retrying a write
+    pub retry_after_secs: u64,
+}
+
+/// Why a write was refused
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReadOnly {
+    /// Why the relay is read-only, if given
+    pub reason: Option<String>,
+    /// Seconds to wait before retrying
+    pub retry_after_secs: u64,
+}
+
+impl std::fmt::Display for ReadOnly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.reason {
+            Some(reason) => write!(f, "{}", reason),
+            None => write!(f, "maintenance in progress"),
+        }
+    }
+}
+
+/// The relay's read-only switch
+#[derive(Debug)]
+pub struct MaintenanceMode {
+    status: RwLock<MaintenanceStatus>,
+}
+
+impl Default for MaintenanceMode {
+    fn default() -> Self {
+        Self::new(&MaintenanceConfig::default())
+    }
+}
+
+impl MaintenanceMode {
+    /// Start in the mode the configuration asks for
+    pub fn new(config: &MaintenanceConfig) -> Self {
+        let mode = Self {
+            status: RwLock::new(MaintenanceStatus {
+                read_only: false,
+                reason: None,
+                since: None,
+                retry_after_secs: config.retry_after_secs,
+            }),
+        };
+        if config.read_only {
+            mode.set(true, Some("Read-only mode set in the relay configuration".to_string()), None);
+        }
+        mode
+    }
+
+    /// The current state
+    pub fn status(&self) -> MaintenanceStatus {
+        self.status.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Whether writes are refused
+    pub fn is_read_only(&self) -> bool {
+        self.status().read_only
+    }
+
+    /// Enter or leave read-only mode, returning the new state
+    ///
+    /// `retry_after_secs` replaces the advertised retry delay when given.
+    pub fn set(&self, read_only: bool, reason: Option<String>, retry_after_secs: Option<u64>) -> MaintenanceStatus {
+        let mut status = self.status.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if read_only && !status.read_only {
+            status.since = Some(Utc::now());
+        }
+        if !read_only {
+            status.since = None;
+        }
+        status.read_only = read_only;
+        status.reason = reason.filter(|_| read_only);
+        if let Some(seconds) = retry_after_secs {
+            status.retry_after_secs = seconds;
+        }
+        metrics::READ_ONLY_MODE.set(i64::from(read_only));
+        status.clone()
+    }
+
+    /// Fail with [`AppError::ReadOnly`] while writes are refused
+    pub fn ensure_writable(&self) -> Result<(), AppError> {
+        let status = self.status();
+        if status.read_only {
+            return Err(AppError::ReadOnly(ReadOnly {
+                reason: status.reason,
+                retry_after_secs: status.retry_after_secs,
+            }));
+        }
+        Ok(())
+    }
+}
+
+/// The mode layered onto the router, or a writable one
+fn layered(mode: Option<Extension<Arc<MaintenanceMode>>>) -> Arc<MaintenanceMode> {
+    mode.map(|Extension(mode)| mode).unwrap_or_default()
+}
+
+/// Middleware refusing writes while the relay is read-only
+///
+/// Requests that match no route pass through so the router can answer
+/// `404 Not Found`.
+pub async fn reject_writes_when_read_only(
+    mode: Option<Extension<Arc<MaintenanceMode>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let safe_method = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    let Some(path) = request.extensions().get::<MatchedPath>() else {
+        return next.run(request).await;
+    };
+    let route = format!("{} {}", request.method(), crate::versioning::unversioned_path(path.as_str()));
+    if safe_method || READ_ONLY_SAFE_ROUTES.contains(&route.as_str()) {
+        return next.run(request).await;
+    }
+
+    match layered(mode).ensure_writable() {
+        Err(AppError::ReadOnly(read_only)) => {
+            warn!("Refused {} while read-only", route);
+            read_only_response(read_only)
+        }
+        _ => next.run(request).await,
+    }
+}
+
+/// Error response for a refused write, with its `Retry-After` header
+fn read_only_response(read_only: ReadOnly) -> Response {
+    let retry_after = HeaderValue::from(read_only.retry_after_secs);
+    let mut response = AppError::ReadOnly(read_only).into_response();
+    response.headers_mut().insert(RETRY_AFTER, retry_after);
+    response
+}
+
+/// Request to enter or leave read-only mode
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceRequest {
+    /// `true` to refuse writes, `false` to accept them again
+    pub read_only: bool,
+    /// Why the relay is read-only, shown to rejected clients
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Seconds clients should wait before retrying a write
+    #[serde(default)]
+    pub retry_after_secs: Option<u64>,
+}
+
+/// Create router for maintenance mode endpoints
+pub fn maintenance_routes() -> Router<Arc<Database>> {
+    Router::new().route("/", get(status_handler).post(set_mode_handler))
+}
+
+/// Create router for authenticated maintenance mode endpoints
+pub fn authenticated_maintenance_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new().route("/", get(authenticated_status_handler).post(authenticated_set_mode_handler))
+}
+
+/// Handler reporting whether the relay is read-only
+#[utoipa::path(
+    get,
+    path = "/admin/maintenance",
+    operation_id = "getMaintenanceMode",
+    tag = "maintenance",
+    responses(
+        (status = 200, description = "Current maintenance mode", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn status_handler(mode: Option<Extension<Arc<MaintenanceMode>>>) -> impl IntoResponse {
+    Json(layered(mode).status())
+}
+
+/// Handler switching read-only mode on or off
+#[utoipa::path(
+    post,
+    path = "/admin/maintenance",
+    operation_id = "setMaintenanceMode",
+    tag = "maintenance",
+    request_body = Object,
+    responses(
+        (status = 200, description = "New maintenance mode", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn set_mode_handler(
+    State(_db): State<Arc<Database>>,
+    mode: Option<Extension<Arc<MaintenanceMode>>>,
+    Json(request): Json<MaintenanceRequest>,
+) -> impl IntoResponse {
+    info!("Setting read-only mode to {}", request.read_only);
+    let status = layered(mode).set(request.read_only, request.reason, request.retry_after_secs);
+    (StatusCode::OK, Json(status))
+}
+
+/// Authenticated handler reporting whether the relay is read-only
+#[instrument(skip_all)]
+async fn authenticated_status_handler(mode: Option<Extension<Arc<MaintenanceMode>>>) -> impl IntoResponse {
+    Json(layered(mode).status())
+}
+
+/// Authenticated handler switching read-only mode on or off
+#[instrument(skip_all)]
+async fn authenticated_set_mode_handler(
+    State((_, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    mode: Option<Extension<Arc<MaintenanceMode>>>,
+    Json(request): Json<MaintenanceRequest>,
+) -> impl IntoResponse {
+    info!("Authenticated user {} setting read-only mode to {}", auth.user_id, request.read_only);
+    let status = layered(mode).set(request.read_only, request.reason, request.retry_after_secs);
+
+    // Log who took the relay out of (or back into) service
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("read_only".to_string(), status.read_only.to_string());
+    if let Err(e) = secure_logger.audit_log(
+        "Maintenance mode changed".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log maintenance mode change: {}", e);
+    }
+    (StatusCode::OK, Json(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    /// A relay-like app with a write, a read and a read-only POST route
+    fn setup_app(mode: Arc<MaintenanceMode>) -> Router {
+        Router::new()
+            .route("/relay", post(|| async { "stored" }))
+            .route("/messages/:group_id", get(|| async { "messages" }))
+            .route("/derivations/verify", post(|| async { "verified" }))
+            .layer(axum::middleware::from_fn(reject_writes_when_read_only))
+            .layer(Extension(mode))
+    }
+
+    async fn call(app: &Router, method: &str, uri: &str) -> Response {
+        app.clone()
+            .oneshot(Request::builder().method(method).uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_read_only_mode_refuses_writes_only() {
+        // ARRANGE: A relay switched to read-only mode
+        let mode = Arc::new(MaintenanceMode::default());
+        let app = setup_app(mode.clone());
+        mode.set(true, Some("Database migration".to_string()), Some(120));
+
+        // ACT: Write, read and verify
+        let write = call(&app, "POST", "/relay").await;
+        let read = call(&app, "GET", "/messages/ops").await;
+        let verify = call(&app, "POST", "/derivations/verify").await;
+
+        // ASSERT: Only the write is refused, with a retry hint
+        assert_eq!(write.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(write.headers()[RETRY_AFTER], "120");
+        let body = axum::body::to_bytes(write.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "READ_ONLY_MODE");
+        assert_eq!(body["details"]["reason"], "Database migration");
+        assert_eq!(read.status(), StatusCode::OK);
+        assert_eq!(verify.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_leaving_read_only_mode_accepts_writes_again() {
+        let mode = Arc::new(MaintenanceMode::new(&MaintenanceConfig { read_only: true, retry_after_secs: 30 }));
+        let app = setup_app(mode.clone());
+        let refused = call(&app, "POST", "/relay").await;
+
+        let status = mode.set(false, Some("ignored".to_string()), None);
+        let accepted = call(&app, "POST", "/relay").await;
+
+        assert_eq!(refused.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(accepted.status(), StatusCode::OK);
+        assert_eq!(status, MaintenanceStatus { read_only: false, reason: None, since: None, retry_after_secs: 30 });
+    }
+
+    #[tokio::test]
+    async fn test_read_only_relay_keeps_serving_reads() {
+        // ARRANGE: The full relay router in read-only mode
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let mode = Arc::new(MaintenanceMode::new(&MaintenanceConfig { read_only: true, retry_after_secs: 45 }));
+        let app = crate::create_app(db).layer(Extension(mode));
+        let relay = Request::builder()
+            .method("POST")
+            .uri("/v1/relay")
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"sender":"00","context":"00","body":"hi","proof":"00"}"#))
+            .unwrap();
+
+        // ACT: Relay a message, read a group and check readiness
+        let relayed = app.clone().oneshot(relay).await.unwrap();
+        let read = call(&app, "GET", "/v1/messages/ops").await;
+        let ready = call(&app, "GET", "/ready").await;
+
+        // ASSERT: Only the write is refused, and readiness reports the mode
+        assert_eq!(relayed.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(relayed.headers()[RETRY_AFTER], "45");
+        assert_eq!(read.status(), StatusCode::OK);
+        let ready = axum::body::to_bytes(ready.into_body(), usize::MAX).await.unwrap();
+        let ready: serde_json::Value = serde_json::from_slice(&ready).unwrap();
+        assert_eq!(ready["mode"], "read_only");
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoint_switches_the_mode() {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        let mode = Arc::new(MaintenanceMode::default());
+        let app = Router::new()
+            .nest("/admin/maintenance", maintenance_routes())
+            .with_state(db)
+            .layer(axum::middleware::from_fn(reject_writes_when_read_only))
+            .layer(Extension(mode.clone()));
+        let set = |read_only: bool| {
+            Request::builder()
+                .method("POST")
+                .uri("/admin/maintenance")
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::json!({ "read_only": read_only, "reason": "Upgrade" }).to_string()))
+                .unwrap()
+        };
+
+        let entered = app.clone().oneshot(set(true)).await.unwrap();
+        let read_only = mode.status();
+        let left = app.clone().oneshot(set(false)).await.unwrap();
+
+        assert_eq!(entered.status(), StatusCode::OK);
+        assert!(read_only.read_only);
+        assert_eq!(read_only.reason.as_deref(), Some("Upgrade"));
+        assert!(read_only.since.is_some());
+        assert_eq!(left.status(), StatusCode::OK);
+        assert!(!mode.is_read_only());
+    }
+}
@@ -2,6 +2,8 @@
 use once_cell::sync::Lazy;
 use prometheus_client::encoding::text::encode;
 use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
 use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
 use prometheus_client::registry::Registry;
 use std::sync::Arc;
@@ -31,6 +33,246 @@ pub static APP_REGISTRY: Lazy<Arc<Registry>> = Lazy::new(|| {
         HTTP_REQUESTS_LATENCY_SECONDS.clone(),
     );
     
+    registry.register(
+        "federation_forwarded_messages",
+        "Messages forwarded to peer relays",
+        FEDERATION_FORWARDED_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "federation_forward_failures",
+        "Failed attempts to forward messages to peer relays",
+        FEDERATION_FORWARD_FAILURES_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "federation_received_messages",
+        "Messages received from peer relays and stored",
+        FEDERATION_RECEIVED_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "federation_missed_messages",
+        "Messages found missing during reconciliation with peer relays",
+        FEDERATION_MISSED_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "webhook_delivered_events",
+        "Webhook events delivered successfully",
+        WEBHOOK_DELIVERED_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "webhook_delivery_retries",
+        "Failed webhook delivery attempts scheduled for retry",
+        WEBHOOK_RETRIES_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "webhook_failed_deliveries",
+        "Webhook deliveries abandoned after exhausting retries",
+        WEBHOOK_FAILED_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "event_stream_published",
+        "Verified-message events acknowledged by the streaming platform",
+        EVENT_STREAM_PUBLISHED_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "event_stream_retries",
+        "Failed event publish attempts scheduled for retry",
+        EVENT_STREAM_RETRIES_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "event_stream_failed",
+        "Verified-message events abandoned after exhausting retries",
+        EVENT_STREAM_FAILED_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "live_subscribers",
+        "Clients currently subscribed to live group messages",
+        LIVE_SUBSCRIBERS.clone(),
+    );
+    
+    registry.register(
+        "backplane_errors",
+        "Messages that could not be fanned out to or received from other replicas",
+        BACKPLANE_ERRORS_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "replayed_messages_rejected",
+        "Messages rejected because their sender already relayed the same context",
+        REPLAYS_REJECTED_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "quarantined_messages",
+        "Messages that failed verification and were quarantined, by reason",
+        QUARANTINED_MESSAGES_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "key_changes",
+        "Public key changes of authenticated users, by action taken",
+        KEY_CHANGES_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "tenant_relayed_messages",
+        "Messages relayed, by tenant",
+        TENANT_RELAYED_MESSAGES_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "tenant_policy_violations",
+        "Messages rejected by a context policy, by tenant",
+        TENANT_POLICY_VIOLATIONS_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "read_only_mode",
+        "Whether the relay refuses writes for maintenance (1) or accepts them (0)",
+        READ_ONLY_MODE.clone(),
+    );
+    
+    registry.register(
+        "sender_quarantines",
+        "Senders quarantined by abuse detection, by reason",
+        SENDER_QUARANTINES_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "siem_events_forwarded",
+        "Security events delivered to the SIEM",
+        SIEM_EVENTS_FORWARDED_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "siem_events_dropped",
+        "Security events dropped because the SIEM buffer was full",
+        SIEM_EVENTS_DROPPED_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "siem_delivery_failures",
+        "Failed attempts to deliver security events to the SIEM",
+        SIEM_DELIVERY_FAILURES_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "siem_buffered_events",
+        "Security events waiting to be delivered to the SIEM",
+        SIEM_BUFFERED_EVENTS.clone(),
+    );
+    
+    registry.register(
+        "revocations_active",
+        "Proofs currently on the revocation list",
+        REVOCATIONS_ACTIVE.clone(),
+    );
+    
+    registry.register(
+        "revocation_checks",
+        "Revocation list lookups, by result (revoked or not_revoked)",
+        REVOCATION_CHECKS_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "revocations_expired",
+        "Expired revocations removed from the revocation list",
+        REVOCATIONS_EXPIRED_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "retention_deletions",
+        "Records deleted by retention jobs, by job",
+        RETENTION_DELETIONS_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "background_job_last_run_timestamp_seconds",
+        "Unix time each background job last started a run, by job",
+        BACKGROUND_JOB_LAST_RUN_SECONDS.clone(),
+    );
+    
+    registry.register(
+        "background_job_interval_seconds",
+        "How often each background job is scheduled to run, by job",
+        BACKGROUND_JOB_INTERVAL_SECONDS.clone(),
+    );
+    
+    registry.register(
+        "integrity_messages_checked",
+        "Stored messages whose proof was re-verified",
+        INTEGRITY_MESSAGES_CHECKED_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "integrity_failures",
+        "Stored messages newly flagged because their proof no longer verifies",
+        INTEGRITY_FAILURES_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "integrity_flagged_messages",
+        "Stored messages currently flagged by integrity verification",
+        INTEGRITY_FLAGGED_MESSAGES.clone(),
+    );
+    
+    registry.register(
+        "scheduled_releases",
+        "Scheduled messages released by the scheduler, by outcome (released or failed)",
+        SCHEDULED_RELEASES_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "verification_queue_depth",
+        "Signature verifications waiting for a slot in the verification pool",
+        VERIFICATION_QUEUE_DEPTH.clone(),
+    );
+    
+    registry.register(
+        "verifications_in_flight",
+        "Signature verifications running on the verification pool",
+        VERIFICATIONS_IN_FLIGHT.clone(),
+    );
+    
+    registry.register(
+        "verification_queue_wait_seconds",
+        "Time signature verifications waited for a slot in the verification pool",
+        VERIFICATION_QUEUE_WAIT_SECONDS.clone(),
+    );
+    
+    registry.register(
+        "plugin_decisions",
+        "Messages evaluated by WASM policy plugins, by plugin and decision (accept, reject or error)",
+        PLUGIN_DECISIONS_TOTAL.clone(),
+    );
+    
+    registry.register(
+        "plugin_duration_seconds",
+        "Time WASM policy plugins took to evaluate a message, by plugin",
+        PLUGIN_DURATION_SECONDS.clone(),
+    );
+    
+    registry.register(
+        "plugin_fuel_consumed",
+        "Fuel WASM policy plugins consumed evaluating a message, by plugin",
+        PLUGIN_FUEL_CONSUMED.clone(),
+    );
+    
+    registry.register(
+        "egress_blocked",
+        "Outbound requests and redirects refused because their host is not in egress.allowed_hosts",
+        EGRESS_BLOCKED_TOTAL.clone(),
+    );
+    
     Arc::new(registry)
 });
 
@@ -45,7 +287,104 @@ pub static HTTP_REQUESTS_LATENCY_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     Histogram::new(buckets)
 });
 
+// Federation counters.
+pub static FEDERATION_FORWARDED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+pub static FEDERATION_FORWARD_FAILURES_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+pub static FEDERATION_RECEIVED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+pub static FEDERATION_MISSED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+
+// Webhook counters.
+pub static WEBHOOK_DELIVERED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+pub static WEBHOOK_RETRIES_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+pub static WEBHOOK_FAILED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+
+// Event stream counters.
+pub static EVENT_STREAM_PUBLISHED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+pub static EVENT_STREAM_RETRIES_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+pub static EVENT_STREAM_FAILED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+
+// Live subscriptions and their cross-replica fanout.
+pub static LIVE_SUBSCRIBERS: Lazy<Gauge> = Lazy::new(Gauge::default);
+pub static BACKPLANE_ERRORS_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+
+// Messages rejected by replay protection.
+pub static REPLAYS_REJECTED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+
+// Quarantined messages, labelled by rejection reason.
+pub static QUARANTINED_MESSAGES_TOTAL: Lazy<Family<Vec<(String, String)>, Counter>> = Lazy::new(Family::default);
+
+// Key changes under key pinning, labelled by action (rotated, flagged or rejected).
+pub static KEY_CHANGES_TOTAL: Lazy<Family<Vec<(String, String)>, Counter>> = Lazy::new(Family::default);
+
+// Tenant counters, labelled by tenant ID.
+pub static TENANT_RELAYED_MESSAGES_TOTAL: Lazy<Family<Vec<(String, String)>, Counter>> = Lazy::new(Family::default);
+pub static TENANT_POLICY_VIOLATIONS_TOTAL: Lazy<Family<Vec<(String, String)>, Counter>> = Lazy::new(Family::default);
+
+// Read-only maintenance mode (see crate::maintenance).
+pub static READ_ONLY_MODE: Lazy<Gauge> = Lazy::new(Gauge::default);
+
+// Senders quarantined by abuse detection, labelled by reason (verification_failures, revoked_proof_replays or burst).
+pub static SENDER_QUARANTINES_TOTAL: Lazy<Family<Vec<(String, String)>, Counter>> = Lazy::new(Family::default);
+
+// SIEM forwarding of security events (see crate::siem).
+pub static SIEM_EVENTS_FORWARDED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+pub static SIEM_EVENTS_DROPPED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+pub static SIEM_DELIVERY_FAILURES_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+pub static SIEM_BUFFERED_EVENTS: Lazy<Gauge> = Lazy::new(Gauge::default);
+
+// Revocation list size, lookups labelled by result, and expired revocations removed.
+pub static REVOCATIONS_ACTIVE: Lazy<Gauge> = Lazy::new(Gauge::default);
+pub static REVOCATION_CHECKS_TOTAL: Lazy<Family<Vec<(String, String)>, Counter>> = Lazy::new(Family::default);
+pub static REVOCATIONS_EXPIRED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+
+// Records deleted by retention jobs, labelled by job (quarantine_cleanup, tenant_retention or message_expiry).
+pub static RETENTION_DELETIONS_TOTAL: Lazy<Family<Vec<(String, String)>, Counter>> = Lazy::new(Family::default);
+
+// Background job heartbeats, labelled by job, so alerts can spot a job that stopped running.
+pub static BACKGROUND_JOB_LAST_RUN_SECONDS: Lazy<Family<Vec<(String, String)>, Gauge>> = Lazy::new(Family::default);
+pub static BACKGROUND_JOB_INTERVAL_SECONDS: Lazy<Family<Vec<(String, String)>, Gauge>> = Lazy::new(Family::default);
+
+// Re-verification of stored message proofs (see crate::integrity).
+pub static INTEGRITY_MESSAGES_CHECKED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+pub static INTEGRITY_FAILURES_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+pub static INTEGRITY_FLAGGED_MESSAGES: Lazy<Gauge> = Lazy::new(Gauge::default);
+
+// Releases of scheduled messages, labelled by outcome (see crate::scheduling).
+pub static SCHEDULED_RELEASES_TOTAL: Lazy<Family<Vec<(String, String)>, Counter>> = Lazy::new(Family::default);
+
+// Signature verifications offloaded to the verification pool (see crate::verification_pool).
+pub static VERIFICATION_QUEUE_DEPTH: Lazy<Gauge> = Lazy::new(Gauge::default);
+pub static VERIFICATIONS_IN_FLIGHT: Lazy<Gauge> = Lazy::new(Gauge::default);
+pub static VERIFICATION_QUEUE_WAIT_SECONDS: Lazy<Histogram> = Lazy::new(|| {
+    // Start at 10 microseconds, multiply by 4 for each bucket, 10 buckets total.
+    Histogram::new(exponential_buckets(0.00001, 4.0, 10))
+});
+
+// Outbound requests refused by the egress allow-list (see crate::egress).
+pub static EGRESS_BLOCKED_TOTAL: Lazy<Counter> = Lazy::new(Counter::default);
+
+// WASM policy plugins, labelled by plugin (see crate::plugins).
+type HistogramFamily = Family<Vec<(String, String)>, Histogram, fn() -> Histogram>;
+pub static PLUGIN_DECISIONS_TOTAL: Lazy<Family<Vec<(String, String)>, Counter>> = Lazy::new(Family::default);
+pub static PLUGIN_DURATION_SECONDS: Lazy<HistogramFamily> = Lazy::new(|| {
+    // Start at 10 microseconds, multiply by 4 for each bucket, 10 buckets total.
+    Family::new_with_constructor(|| Histogram::new(exponential_buckets(0.00001, 4.0, 10)))
+});
+pub static PLUGIN_FUEL_CONSUMED: Lazy<HistogramFamily> = Lazy::new(|| {
+    // Start at 1,000 units of fuel, multiply by 10 for each bucket, 6 buckets total.
+    Family::new_with_constructor(|| Histogram::new(exponential_buckets(1_000.0, 10.0, 6)))
+});
+
 // 3. A handler function that we'll use for our /metrics endpoint.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    operation_id = "metrics",
+    tag = "health",
+    responses(
+        (status = 200, description = "Prometheus metrics", body = String, content_type = "application/openmetrics-text"),
+    )
+)]
 pub async fn metrics_handler() -> (
     axum::http::StatusCode,
     axum::http::HeaderMap,
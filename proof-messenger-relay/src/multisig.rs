@@ -0,0 +1,724 @@
+//! Multi-Signature Approval Module
+//!
+//! This module collects m-of-n approvals (see
+//! `proof_messenger_protocol::multisig`). A client creates a pending
+//! approval naming the payload, the keys allowed to sign and how many must;
+//! co-signers then append their signatures one at a time. Each signature is
+//! verified against the approval's policy and payload when it arrives, and
+//! the approval flips to `approved` once the threshold is met. On relays
+//! with tenants, an approval is only visible to the tenant that created it.
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use chrono::Utc;
+use ed25519_dalek::{PublicKey, Signature};
+use proof_messenger_protocol::multisig::{CoSignature, MultisigPolicy, MultisigProof};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, StoredCoSignature, StoredMultisigApproval},
+    request_id::RequestId,
+    tenancy::TenantScope,
+    AppError,
+};
+
+/// Status of an approval still collecting signatures
+pub const STATUS_PENDING: &str = "pending";
+
+/// Status of an approval whose threshold was met
+pub const STATUS_APPROVED: &str = "approved";
+
+/// Default number of approvals returned when listing
+const DEFAULT_LIST_LIMIT: i64 = 50;
+
+/// Maximum number of approvals returned when listing
+const MAX_LIST_LIMIT: i64 = 500;
+
+/// Errors raised when collecting multi-signature approvals
+#[derive(Error, Debug)]
+pub enum MultisigError {
+    #[error("Approval not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid multisig policy: {0}")]
+    InvalidPolicy(String),
+
+    #[error("Signer is not part of the approval's policy")]
+    NotASigner,
+
+    #[error("Signer has already signed this approval")]
+    AlreadySigned,
+
+    #[error("Approval is no longer pending: {0}")]
+    NotPending(String),
+}
+
+/// One co-signature, as submitted by a client
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CoSignatureRequest {
+    /// Public key of the co-signer (hex encoded)
+    pub signer: String,
+    /// Signature over the multisig context (hex encoded)
+    pub signature: String,
+}
+
+/// Request body for creating a pending approval
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateApprovalRequest {
+    /// Number of distinct signers required
+    pub threshold: usize,
+    /// Public keys allowed to sign (hex encoded)
+    pub signers: Vec<String>,
+    /// Payload being approved (hex encoded)
+    pub payload: String,
+    /// Co-signatures to add straight away, such as the creator's own
+    #[serde(default)]
+    pub signatures: Vec<CoSignatureRequest>,
+}
+
+/// Query parameters for listing approvals
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ApprovalQuery {
+    /// Only approvals with this status (pending or approved)
+    pub status: Option<String>,
+    /// Only approvals this key may sign (hex encoded)
+    pub signer: Option<String>,
+    /// Maximum number of approvals to return (default 50, at most 500)
+    pub limit: Option<i64>,
+}
+
+impl ApprovalQuery {
+    /// Validate the query and return the effective limit
+    fn limit(&self) -> Result<i64, AppError> {
+        if let Some(status) = &self.status {
+            if status != STATUS_PENDING && status != STATUS_APPROVED {
+                return Err(AppError::InvalidQuery(format!(
+                    "status must be {} or {}",
+                    STATUS_PENDING, STATUS_APPROVED
+                )));
+            }
+        }
+        let limit = self.limit.unwrap_or(DEFAULT_LIST_LIMIT);
+        if !(1..=MAX_LIST_LIMIT).contains(&limit) {
+            return Err(AppError::InvalidQuery(format!("limit must be between 1 and {}", MAX_LIST_LIMIT)));
+        }
+        Ok(limit)
+    }
+}
+
+/// An approval with the signatures collected so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalStatus {
+    #[serde(flatten)]
+    pub approval: StoredMultisigApproval,
+    /// Co-signatures collected so far, oldest first
+    pub signatures: Vec<StoredCoSignature>,
+    /// Signatures still needed to meet the threshold
+    pub remaining: i64,
+}
+
+/// Create router for multi-signature approval endpoints
+pub fn multisig_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/approvals", post(create_approval_handler).get(list_approvals_handler))
+        .route("/approvals/:approval_id", get(get_approval_handler))
+        .route("/approvals/:approval_id/signatures", post(add_signature_handler))
+}
+
+/// Create router for authenticated multi-signature approval endpoints
+pub fn authenticated_multisig_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/approvals", post(authenticated_create_approval_handler).get(authenticated_list_approvals_handler))
+        .route("/approvals/:approval_id", get(authenticated_get_approval_handler))
+        .route("/approvals/:approval_id/signatures", post(authenticated_add_signature_handler))
+}
+
+/// Decode a hex public key
+fn decode_key(key: &str) -> Result<PublicKey, AppError> {
+    let bytes = hex::decode(key)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid hex encoding: {}", e)))?;
+    PublicKey::from_bytes(&bytes).map_err(|e| AppError::InvalidPublicKey(format!("Invalid public key: {}", e)))
+}
+
+/// Decode a submitted co-signature
+fn decode_cosignature(request: &CoSignatureRequest) -> Result<CoSignature, AppError> {
+    let signer = decode_key(&request.signer)?;
+    let bytes = hex::decode(&request.signature)
+        .map_err(|e| AppError::InvalidSignature(format!("Invalid hex encoding: {}", e)))?;
+    let signature = Signature::from_bytes(&bytes)
+        .map_err(|e| AppError::InvalidSignature(format!("Invalid signature: {}", e)))?;
+    Ok(CoSignature { signer, signature })
+}
+
+/// Rebuild the protocol proof for a stored approval and its signatures
+fn stored_proof(approval: &StoredMultisigApproval, signatures: &[StoredCoSignature]) -> Result<MultisigProof, AppError> {
+    let signers = approval
+        .signers
+        .split_whitespace()
+        .map(decode_key)
+        .collect::<Result<Vec<_>, _>>()?;
+    let policy = MultisigPolicy::new(approval.threshold as usize, signers)
+        .map_err(|e| AppError::ProcessingError(format!("Invalid stored policy: {}", e)))?;
+    let payload = hex::decode(&approval.payload)
+        .map_err(|e| AppError::ProcessingError(format!("Invalid stored payload: {}", e)))?;
+
+    let mut proof = MultisigProof::new(policy, payload);
+    for stored in signatures {
+        proof.signatures.push(decode_cosignature(&CoSignatureRequest {
+            signer: stored.signer.clone(),
+            signature: stored.signature.clone(),
+        })?);
+    }
+    Ok(proof)
+}
+
+/// Verify a submitted co-signature and add it to `proof`
+fn verify_cosignature(proof: &mut MultisigProof, request: &CoSignatureRequest) -> Result<CoSignature, AppError> {
+    let signature = decode_cosignature(request)?;
+    if !proof.policy.contains(&signature.signer) {
+        return Err(MultisigError::NotASigner.into());
+    }
+    if proof.signatures.iter().any(|existing| existing.signer == signature.signer) {
+        return Err(MultisigError::AlreadySigned.into());
+    }
+    proof.add_signature(signature.clone()).map_err(|_| AppError::VerificationFailed)?;
+    Ok(signature)
+}
+
+/// Store a verified co-signature for an approval
+async fn store_cosignature(db: &Database, approval_id: &str, signature: &CoSignature) -> Result<(), AppError> {
+    let added = db
+        .add_multisig_signature(&StoredCoSignature {
+            approval_id: approval_id.to_string(),
+            signer: hex::encode(signature.signer.as_bytes()),
+            signature: hex::encode(signature.signature.to_bytes()),
+            signed_at: Utc::now(),
+        })
+        .await?;
+
+    // Nothing is stored if the signer raced themselves or the approval completed meanwhile
+    if !added {
+        return match db.get_multisig_approval(approval_id).await? {
+            Some(current) if current.status != STATUS_PENDING => Err(MultisigError::NotPending(approval_id.to_string()).into()),
+            _ => Err(MultisigError::AlreadySigned.into()),
+        };
+    }
+    Ok(())
+}
+
+/// Create a pending approval, adding any co-signatures submitted with it
+pub async fn create_approval(
+    db: &Database,
+    tenant: &TenantScope,
+    request: &CreateApprovalRequest,
+    created_by: Option<&str>,
+) -> Result<ApprovalStatus, AppError> {
+    let signers = request.signers.iter().map(|key| decode_key(key)).collect::<Result<Vec<_>, _>>()?;
+    let policy = MultisigPolicy::new(request.threshold, signers)
+        .map_err(|e| MultisigError::InvalidPolicy(e.to_string()))?;
+    let payload = hex::decode(&request.payload)
+        .map_err(|e| AppError::InvalidContext(format!("Invalid hex encoding: {}", e)))?;
+    if payload.is_empty() {
+        return Err(AppError::InvalidContext("Payload cannot be empty".to_string()));
+    }
+
+    // Check every submitted signature before storing anything
+    let mut proof = MultisigProof::new(policy.clone(), payload);
+    let signatures = request
+        .signatures
+        .iter()
+        .map(|submitted| verify_cosignature(&mut proof, submitted))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let approval = StoredMultisigApproval {
+        id: Uuid::new_v4().to_string(),
+        tenant: tenant.tenant().map(str::to_string),
+        payload: hex::encode(&proof.payload),
+        threshold: policy.threshold as i64,
+        signers: policy.signers.iter().map(|key| hex::encode(key.as_bytes())).collect::<Vec<_>>().join(" "),
+        policy_hash: hex::encode(policy.hash()),
+        status: STATUS_PENDING.to_string(),
+        created_by: created_by.map(str::to_string),
+        created_at: Utc::now(),
+        approved_at: None,
+    };
+    db.create_multisig_approval(&approval).await?;
+    for signature in &signatures {
+        store_cosignature(db, &approval.id, signature).await?;
+    }
+
+    approval_status(db, tenant, &approval.id).await
+}
+
+/// Verify a co-signature and add it to a pending approval
+pub async fn add_signature(
+    db: &Database,
+    tenant: &TenantScope,
+    approval_id: &str,
+    request: &CoSignatureRequest,
+) -> Result<ApprovalStatus, AppError> {
+    let approval = tenant_approval(db, tenant, approval_id).await?;
+    if approval.status != STATUS_PENDING {
+        return Err(MultisigError::NotPending(approval_id.to_string()).into());
+    }
+
+    let signatures = db.get_multisig_signatures(approval_id).await?;
+    let mut proof = stored_proof(&approval, &signatures)?;
+    let signature = verify_cosignature(&mut proof, request)?;
+    store_cosignature(db, approval_id, &signature).await?;
+
+    approval_status(db, tenant, approval_id).await
+}
+
+/// A tenant's approval, as if it did not exist when it belongs to another tenant
+async fn tenant_approval(db: &Database, tenant: &TenantScope, approval_id: &str) -> Result<StoredMultisigApproval, AppError> {
+    db.get_multisig_approval(approval_id)
+        .await?
+        .filter(|approval| approval.tenant.as_deref() == tenant.tenant())
+        .ok_or_else(|| MultisigError::NotFound(approval_id.to_string()).into())
+}
+
+/// A tenant's approval and the signatures collected for it
+pub async fn approval_status(db: &Database, tenant: &TenantScope, approval_id: &str) -> Result<ApprovalStatus, AppError> {
+    let approval = tenant_approval(db, tenant, approval_id).await?;
+    let signatures = db.get_multisig_signatures(approval_id).await?;
+    let remaining = (approval.threshold - signatures.len() as i64).max(0);
+
+    Ok(ApprovalStatus { approval, signatures, remaining })
+}
+
+/// A tenant's approvals matching a query, newest first
+pub async fn list_approvals(db: &Database, tenant: &TenantScope, query: &ApprovalQuery) -> Result<Vec<ApprovalStatus>, AppError> {
+    let limit = query.limit()?;
+    let signer = query.signer.as_deref().map(decode_key).transpose()?.map(|key| hex::encode(key.as_bytes()));
+
+    let approvals = db.list_multisig_approvals(tenant.tenant(), query.status.as_deref(), signer.as_deref(), limit).await?;
+    let mut statuses = Vec::with_capacity(approvals.len());
+    for approval in approvals {
+        let signatures = db.get_multisig_signatures(&approval.id).await?;
+        let remaining = (approval.threshold - signatures.len() as i64).max(0);
+        statuses.push(ApprovalStatus { approval, signatures, remaining });
+    }
+    Ok(statuses)
+}
+
+/// Handler to create a pending approval
+#[utoipa::path(
+    post,
+    path = "/approvals",
+    operation_id = "createApproval",
+    tag = "approvals",
+    request_body = CreateApprovalRequest,
+    responses(
+        (status = 201, description = "Approval created", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn create_approval_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Json(payload): Json<CreateApprovalRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Creating {}-of-{} approval", payload.threshold, payload.signers.len());
+
+    let approval = create_approval(&db, &tenant, &payload, None).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "approval": approval
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Handler to add a co-signature to an approval
+#[utoipa::path(
+    post,
+    path = "/approvals/{approval_id}/signatures",
+    operation_id = "addApprovalSignature",
+    tag = "approvals",
+    params(("approval_id" = String, Path, description = "Approval ID")),
+    request_body = CoSignatureRequest,
+    responses(
+        (status = 200, description = "Co-signature added", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn add_signature_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(approval_id): Path<String>,
+    Json(payload): Json<CoSignatureRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Adding co-signature to approval: {}", approval_id);
+
+    let approval = add_signature(&db, &tenant, &approval_id, &payload).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "approval": approval
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler to retrieve an approval's status
+#[utoipa::path(
+    get,
+    path = "/approvals/{approval_id}",
+    operation_id = "getApproval",
+    tag = "approvals",
+    params(("approval_id" = String, Path, description = "Approval ID")),
+    responses(
+        (status = 200, description = "The approval and its co-signatures", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_approval_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(approval_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Retrieving approval: {}", approval_id);
+
+    let approval = approval_status(&db, &tenant, &approval_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "approval": approval
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler to list approvals
+#[utoipa::path(
+    get,
+    path = "/approvals",
+    operation_id = "listApprovals",
+    tag = "approvals",
+    params(ApprovalQuery),
+    responses(
+        (status = 200, description = "Matching approvals", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn list_approvals_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Query(params): Query<ApprovalQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Listing approvals");
+
+    let approvals = list_approvals(&db, &tenant, &params).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "count": approvals.len(),
+        "approvals": approvals
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to create a pending approval
+#[instrument(skip_all)]
+async fn authenticated_create_approval_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    request_id: RequestId,
+    Json(payload): Json<CreateApprovalRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} creating {}-of-{} approval", auth.user_id, payload.threshold, payload.signers.len());
+
+    let approval = create_approval(&db, &tenant, &payload, Some(&auth.user_id)).await?;
+
+    // Log the new approval
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("approval_id".to_string(), approval.approval.id.clone());
+    metadata.insert("policy_hash".to_string(), approval.approval.policy_hash.clone());
+    metadata.insert("threshold".to_string(), approval.approval.threshold.to_string());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Multisig approval created".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log approval creation: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "approval": approval,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Authenticated handler to add a co-signature to an approval
+#[instrument(skip_all)]
+async fn authenticated_add_signature_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    request_id: RequestId,
+    Path(approval_id): Path<String>,
+    Json(payload): Json<CoSignatureRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} adding co-signature to approval: {}", auth.user_id, approval_id);
+
+    let approval = add_signature(&db, &tenant, &approval_id, &payload).await?;
+
+    // Log the co-signature
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("approval_id".to_string(), approval_id.clone());
+    metadata.insert("signer".to_string(), payload.signer.clone());
+    metadata.insert("approval_status".to_string(), approval.approval.status.clone());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Multisig approval co-signed".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log approval co-signature: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "approval": approval,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to retrieve an approval's status
+#[instrument(skip_all)]
+async fn authenticated_get_approval_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Path(approval_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} retrieving approval: {}", auth.user_id, approval_id);
+
+    let approval = approval_status(&db, &tenant, &approval_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "approval": approval,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to list approvals
+#[instrument(skip_all)]
+async fn authenticated_list_approvals_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Query(params): Query<ApprovalQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} listing approvals", auth.user_id);
+
+    let approvals = list_approvals(&db, &tenant, &params).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "count": approvals.len(),
+        "approvals": approvals,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use hyper::Method;
+    use proof_messenger_protocol::key::{generate_secure_keypair_with_seed, SecureKeypair};
+    use proof_messenger_protocol::multisig::cosign;
+    use tower::ServiceExt;
+
+    const PAYLOAD: &[u8] = b"transfer 50000 to acme";
+
+    async fn setup_test_app() -> (Router, Arc<Database>) {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+
+        let app = Router::new().merge(multisig_routes()).with_state(db.clone());
+        (app, db)
+    }
+
+    fn keys() -> Vec<SecureKeypair> {
+        (1..=3).map(generate_secure_keypair_with_seed).collect()
+    }
+
+    fn policy(keys: &[SecureKeypair]) -> MultisigPolicy {
+        MultisigPolicy::new(2, keys.iter().map(SecureKeypair::public_key).collect()).unwrap()
+    }
+
+    fn signature_request(keypair: &SecureKeypair, policy: &MultisigPolicy, payload: &[u8]) -> CoSignatureRequest {
+        let signature = cosign(keypair, policy, payload).unwrap();
+        CoSignatureRequest {
+            signer: hex::encode(signature.signer.as_bytes()),
+            signature: hex::encode(signature.signature.to_bytes()),
+        }
+    }
+
+    fn create_request(keys: &[SecureKeypair], signatures: Vec<CoSignatureRequest>) -> CreateApprovalRequest {
+        CreateApprovalRequest {
+            threshold: 2,
+            signers: keys.iter().map(|key| hex::encode(key.public_key_bytes())).collect(),
+            payload: hex::encode(PAYLOAD),
+            signatures,
+        }
+    }
+
+    async fn send<T: Serialize>(app: &Router, method: Method, uri: &str, body: Option<&T>) -> (StatusCode, serde_json::Value) {
+        let body = match body {
+            Some(body) => Body::from(serde_json::to_string(body).unwrap()),
+            None => Body::empty(),
+        };
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("Content-Type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_approval_completes_at_threshold() {
+        // ARRANGE: A 2-of-3 approval created with the first signature
+        let (app, _) = setup_test_app().await;
+        let keys = keys();
+        let policy = policy(&keys);
+        let request = create_request(&keys, vec![signature_request(&keys[0], &policy, PAYLOAD)]);
+        let (create_status, created) = send(&app, Method::POST, "/approvals", Some(&request)).await;
+        let id = created["approval"]["id"].as_str().unwrap().to_string();
+
+        // ACT: List pending approvals, then add the second signature
+        let (_, pending) = send::<()>(&app, Method::GET, "/approvals?status=pending", None).await;
+        let second = signature_request(&keys[2], &policy, PAYLOAD);
+        let (sign_status, signed) = send(&app, Method::POST, &format!("/approvals/{}/signatures", id), Some(&second)).await;
+        let (_, fetched) = send::<()>(&app, Method::GET, &format!("/approvals/{}", id), None).await;
+
+        // ASSERT: The approval was pending with one signature, then approved
+        assert_eq!(create_status, StatusCode::CREATED);
+        assert_eq!(created["approval"]["status"], STATUS_PENDING);
+        assert_eq!(created["approval"]["remaining"], 1);
+        assert_eq!(pending["count"], 1);
+        assert_eq!(sign_status, StatusCode::OK);
+        assert_eq!(signed["approval"]["status"], STATUS_APPROVED);
+        assert_eq!(fetched["approval"]["signatures"].as_array().unwrap().len(), 2);
+        assert_eq!(fetched["approval"]["remaining"], 0);
+        assert!(fetched["approval"]["approved_at"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_outside_and_duplicate_signers_are_rejected() {
+        let (app, _) = setup_test_app().await;
+        let keys = keys();
+        let policy = policy(&keys);
+        let first = signature_request(&keys[0], &policy, PAYLOAD);
+        let (_, created) = send(&app, Method::POST, "/approvals", Some(&create_request(&keys, vec![first.clone()]))).await;
+        let uri = format!("/approvals/{}/signatures", created["approval"]["id"].as_str().unwrap());
+
+        // An outsider signs the same context, but is not in the policy
+        let outsider = generate_secure_keypair_with_seed(9);
+        let outsider_request = CoSignatureRequest {
+            signer: hex::encode(outsider.public_key_bytes()),
+            signature: hex::encode(outsider.sign(&MultisigProof::new(policy, PAYLOAD.to_vec()).context()).to_bytes()),
+        };
+        let (outsider_status, outsider_json) = send(&app, Method::POST, &uri, Some(&outsider_request)).await;
+        let (duplicate_status, duplicate_json) = send(&app, Method::POST, &uri, Some(&first)).await;
+
+        assert_eq!(outsider_status, StatusCode::FORBIDDEN);
+        assert_eq!(outsider_json["code"], "NOT_A_SIGNER");
+        assert_eq!(duplicate_status, StatusCode::CONFLICT);
+        assert_eq!(duplicate_json["code"], "ALREADY_SIGNED");
+    }
+
+    #[tokio::test]
+    async fn test_signature_over_another_payload_is_rejected() {
+        let (app, db) = setup_test_app().await;
+        let keys = keys();
+        let policy = policy(&keys);
+        let (_, created) = send(&app, Method::POST, "/approvals", Some(&create_request(&keys, Vec::new()))).await;
+        let id = created["approval"]["id"].as_str().unwrap();
+
+        let forged = signature_request(&keys[1], &policy, b"transfer 500000 to acme");
+        let (status, _) = send(&app, Method::POST, &format!("/approvals/{}/signatures", id), Some(&forged)).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(db.get_multisig_signatures(id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_approved_approval_takes_no_more_signatures() {
+        let (app, _) = setup_test_app().await;
+        let keys = keys();
+        let policy = policy(&keys);
+        let request = create_request(
+            &keys,
+            vec![signature_request(&keys[0], &policy, PAYLOAD), signature_request(&keys[1], &policy, PAYLOAD)],
+        );
+        let (_, created) = send(&app, Method::POST, "/approvals", Some(&request)).await;
+        let id = created["approval"]["id"].as_str().unwrap();
+
+        let late = signature_request(&keys[2], &policy, PAYLOAD);
+        let (status, json) = send(&app, Method::POST, &format!("/approvals/{}/signatures", id), Some(&late)).await;
+
+        assert_eq!(created["approval"]["status"], STATUS_APPROVED);
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(json["code"], "APPROVAL_NOT_PENDING");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_policy_and_unknown_approval() {
+        let (app, _) = setup_test_app().await;
+        let keys = keys();
+        let mut request = create_request(&keys, Vec::new());
+        request.threshold = 4;
+
+        let (policy_status, policy_json) = send(&app, Method::POST, "/approvals", Some(&request)).await;
+        let (missing_status, _) = send::<()>(&app, Method::GET, "/approvals/unknown", None).await;
+        let (query_status, _) = send::<()>(&app, Method::GET, "/approvals?status=rejected", None).await;
+
+        assert_eq!(policy_status, StatusCode::BAD_REQUEST);
+        assert_eq!(policy_json["code"], "INVALID_MULTISIG_POLICY");
+        assert_eq!(missing_status, StatusCode::NOT_FOUND);
+        assert_eq!(query_status, StatusCode::BAD_REQUEST);
+    }
+}
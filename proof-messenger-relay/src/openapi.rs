@@ -0,0 +1,360 @@
+//! OpenAPI Specification Module
+//!
+//! The relay describes its HTTP API as an OpenAPI 3.1 document generated
+//! from the `#[utoipa::path]` annotations on its handlers, and serves it at
+//! `GET /openapi.json` so clients can generate SDKs from it.
+//!
+//! Operations document their success responses only. Every error has the
+//! same [`ErrorBody`] shape (see [`crate::api_error`]), so it is documented
+//! once as the `Error` response and used as each operation's `default`
+//! response. Routes in [`DEFAULT_ROUTE_SCOPES`] are marked as requiring a
+//! bearer token or API key carrying their scopes, which applies when the
+//! relay runs with OAuth enabled.
+//!
+//! The document describes the current API version under `/v1` (see
+//! [`crate::versioning`]); the deprecated unversioned aliases are left out.
+//!
+//! Building the relay with the `swagger-ui` feature also serves Swagger UI
+//! at `/swagger-ui`.
+
+use axum::{extract::Json, routing::get, Router};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme};
+use utoipa::openapi::{ContentBuilder, PathItem, Ref, RefOr, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+
+use crate::api_error::ErrorBody;
+use crate::api_keys::API_KEY_HEADER;
+use crate::authorization::{parse_route, DEFAULT_ROUTE_SCOPES};
+use crate::export::ExportFormat;
+use crate::versioning::V1_PREFIX;
+
+/// Name of the shared error response
+const ERROR_RESPONSE: &str = "Error";
+
+/// Name of the bearer token security scheme
+const BEARER_SCHEME: &str = "bearer_auth";
+
+/// Name of the API key security scheme
+const API_KEY_SCHEME: &str = "api_key";
+
+/// The relay's OpenAPI document
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Proof Messenger Relay",
+        description = "Verifies, stores and distributes cryptographically signed messages."
+    ),
+    paths(
+        crate::federation::inbound_handler,
+        crate::federation::digest_handler,
+        crate::federation::get_federated_message_handler,
+        crate::health_handler,
+        crate::readiness::ready_handler,
+        crate::metrics::metrics_handler,
+    ),
+    nest((path = "/v1", api = V1Api)),
+    components(schemas(ErrorBody, ExportFormat)),
+    modifiers(&RelayApiModifier),
+    tags(
+        (name = "messages", description = "Relaying, reading, searching and deleting messages"),
+        (name = "subscriptions", description = "Live delivery over WebSockets, server-sent events and long polling"),
+        (name = "receipts", description = "Signed read receipts"),
+        (name = "deliveries", description = "Unsigned per-recipient delivery and read states"),
+        (name = "inbox", description = "Direct messages, read with a signed challenge"),
+        (name = "key-auth", description = "Session tokens for clients that sign a challenge with an Ed25519 key"),
+        (name = "amendments", description = "Signed corrections to messages"),
+        (name = "detached-proofs", description = "Proofs over documents kept outside the relay"),
+        (name = "derivations", description = "Attestations linking per-context keys to a master identity"),
+        (name = "proof-chains", description = "Proofs that commit to a parent proof"),
+        (name = "timestamping", description = "RFC 3161 timestamp tokens"),
+        (name = "evidence", description = "Relay-signed evidence bundles"),
+        (name = "schemas", description = "JSON Schemas for group message bodies"),
+        (name = "approvals", description = "m-of-n multi-signature approvals"),
+        (name = "scheduling", description = "Signed messages relayed at a scheduled time"),
+        (name = "revocation", description = "Proof revocation"),
+        (name = "invites", description = "Group invites"),
+        (name = "webhooks", description = "Webhook registration and deliveries"),
+        (name = "quarantine", description = "Messages rejected by verification"),
+        (name = "compliance", description = "Compliance reporting"),
+        (name = "data-subjects", description = "GDPR access and erasure requests"),
+        (name = "api-keys", description = "API key management"),
+        (name = "maintenance", description = "Read-only maintenance mode"),
+        (name = "abuse", description = "Abuse detection and sender quarantines"),
+        (name = "integrity", description = "Re-verification of stored message proofs"),
+        (name = "keys", description = "Sender key pinning and rotation"),
+        (name = "federation", description = "Relay-to-relay federation, authenticated by peer signatures"),
+        (name = "transparency", description = "Append-only transparency log"),
+        (name = "health", description = "Health, readiness and metrics"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Version 1 of the relay's API, served under [`V1_PREFIX`]
+#[derive(OpenApi)]
+#[openapi(paths(
+    crate::relay_handler,
+    crate::get_messages_handler,
+    crate::get_message_by_id_handler,
+    crate::delete_message_handler,
+    crate::get_messages_by_sender_handler,
+    crate::search::search_messages_handler,
+    crate::export::export_messages_handler,
+    crate::threads::get_thread_handler,
+    crate::subscriptions::subscribe_handler,
+    crate::subscriptions::events_handler,
+    crate::subscriptions::poll_handler,
+    crate::receipts::submit_receipt_handler,
+    crate::receipts::get_receipts_handler,
+    crate::deliveries::mark_read_handler,
+    crate::deliveries::get_deliveries_handler,
+    crate::inbox::challenge_handler,
+    crate::inbox::inbox_handler,
+    crate::key_auth::challenge_handler,
+    crate::key_auth::session_handler,
+    crate::amendments::amend_message_handler,
+    crate::amendments::get_history_handler,
+    crate::detached_proofs::register_handler,
+    crate::detached_proofs::verify_handler,
+    crate::detached_proofs::lookup_handler,
+    crate::derivations::verify_handler,
+    crate::proof_chains::descendants_handler,
+    crate::proof_chains::ancestors_handler,
+    crate::proof_chains::verify_chain_handler,
+    crate::timestamping::get_timestamp_handler,
+    crate::evidence::get_evidence_handler,
+    crate::schemas::register_schema_handler,
+    crate::schemas::list_schemas_handler,
+    crate::schemas::get_schema_handler,
+    crate::multisig::create_approval_handler,
+    crate::multisig::list_approvals_handler,
+    crate::multisig::get_approval_handler,
+    crate::multisig::add_signature_handler,
+    crate::scheduling::schedule_handler,
+    crate::scheduling::list_scheduled_handler,
+    crate::scheduling::get_scheduled_handler,
+    crate::scheduling::cancel_handler,
+    crate::revocation::revoke_proof_handler,
+    crate::revocation::check_revocation_handler,
+    crate::revocation::list_revocations_handler,
+    crate::revocation::cleanup_revocations_handler,
+    crate::invites::create_invite_handler,
+    crate::invites::get_invite_handler,
+    crate::invites::redeem_invite_handler,
+    crate::webhooks::register_webhook_handler,
+    crate::webhooks::list_webhooks_handler,
+    crate::webhooks::delete_webhook_handler,
+    crate::webhooks::list_deliveries_handler,
+    crate::quarantine::list_rejected_messages_handler,
+    crate::compliance_audit::compliance_summary_handler,
+    crate::compliance_audit::integrity_handler,
+    crate::data_subjects::export_handler,
+    crate::data_subjects::list_erasures_handler,
+    crate::data_subjects::schedule_erasure_handler,
+    crate::data_subjects::cancel_erasure_handler,
+    crate::maintenance::status_handler,
+    crate::maintenance::set_mode_handler,
+    crate::abuse::list_quarantines_handler,
+    crate::abuse::lift_quarantine_handler,
+    crate::integrity::integrity_status_handler,
+    crate::integrity::verify_handler,
+    crate::api_keys::authenticated_create_api_key_handler,
+    crate::api_keys::authenticated_list_api_keys_handler,
+    crate::api_keys::authenticated_rotate_api_key_handler,
+    crate::api_keys::authenticated_revoke_api_key_handler,
+    crate::key_pinning::authenticated_rotate_key_handler,
+    crate::key_pinning::authenticated_get_key_pin_handler,
+    crate::key_pinning::authenticated_list_key_changes_handler,
+    crate::transparency::tree_head_handler,
+    crate::transparency::inclusion_proof_handler,
+))]
+struct V1Api;
+
+/// Adds the shared error response and security requirements to the document
+struct RelayApiModifier;
+
+impl Modify for RelayApiModifier {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        // The relay has no license metadata to publish
+        openapi.info.license = None;
+
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.responses.insert(
+            ERROR_RESPONSE.to_string(),
+            RefOr::T(
+                ResponseBuilder::new()
+                    .description("Error with a stable machine-readable code")
+                    .content(
+                        "application/json",
+                        ContentBuilder::new().schema(Some(Ref::from_schema_name("ErrorBody"))).build(),
+                    )
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            BEARER_SCHEME,
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+        components.add_security_scheme(
+            API_KEY_SCHEME,
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))),
+        );
+
+        for item in openapi.paths.paths.values_mut() {
+            for operation in operations(item) {
+                operation
+                    .responses
+                    .responses
+                    .insert("default".to_string(), RefOr::Ref(Ref::from_response_name(ERROR_RESPONSE)));
+            }
+        }
+
+        for (route, scopes) in DEFAULT_ROUTE_SCOPES {
+            let Some((method, path)) = parse_route(route) else { continue };
+            let Some(item) = openapi.paths.paths.get_mut(&format!("{}{}", V1_PREFIX, openapi_path(path))) else { continue };
+            let operation = match method.as_str() {
+                "GET" => item.get.as_mut(),
+                "POST" => item.post.as_mut(),
+                "PUT" => item.put.as_mut(),
+                "PATCH" => item.patch.as_mut(),
+                "DELETE" => item.delete.as_mut(),
+                _ => None,
+            };
+            if let Some(operation) = operation {
+                operation.security = Some(vec![
+                    SecurityRequirement::new(BEARER_SCHEME, scopes.iter().copied()),
+                    SecurityRequirement::new(API_KEY_SCHEME, scopes.iter().copied()),
+                ]);
+            }
+        }
+    }
+}
+
+/// Every operation of a path
+fn operations(item: &mut PathItem) -> impl Iterator<Item = &mut utoipa::openapi::path::Operation> {
+    [
+        item.get.as_mut(),
+        item.post.as_mut(),
+        item.put.as_mut(),
+        item.patch.as_mut(),
+        item.delete.as_mut(),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// Convert an axum route pattern (`/message/:message_id`) to an OpenAPI path (`/message/{message_id}`)
+fn openapi_path(route: &str) -> String {
+    route
+        .split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) => format!("{{{}}}", name),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Create router serving the OpenAPI document, and Swagger UI with the `swagger-ui` feature
+pub fn openapi_routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let routes = Router::new().route("/openapi.json", get(openapi_handler));
+
+    #[cfg(feature = "swagger-ui")]
+    let routes = routes.merge(
+        utoipa_swagger_ui::SwaggerUi::new("/swagger-ui").config(utoipa_swagger_ui::Config::from("/openapi.json")),
+    );
+
+    routes
+}
+
+/// Handler serving the OpenAPI document
+async fn openapi_handler() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    use crate::database::Database;
+
+    #[test]
+    fn test_every_authorized_route_is_documented() {
+        // ARRANGE: The generated document
+        let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+        // ACT & ASSERT: Each route in the scope table has an operation requiring its scopes
+        for (route, scopes) in DEFAULT_ROUTE_SCOPES {
+            let (method, path) = parse_route(route).unwrap();
+            let operation = &doc["paths"][format!("{}{}", V1_PREFIX, openapi_path(path))][method.as_str().to_lowercase()];
+            assert!(operation.is_object(), "{} is not documented", route);
+            assert_eq!(operation["security"][0][BEARER_SCHEME], serde_json::json!(scopes), "{}", route);
+        }
+    }
+
+    #[test]
+    fn test_errors_share_the_error_body() {
+        let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+        let relay = &doc["paths"]["/v1/relay"]["post"];
+        assert_eq!(relay["responses"]["default"]["$ref"], "#/components/responses/Error");
+        assert_eq!(
+            doc["components"]["responses"]["Error"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/ErrorBody"
+        );
+        assert!(doc["components"]["schemas"]["ErrorCode"].is_object());
+        assert!(doc["components"]["schemas"]["Message"].is_object());
+    }
+
+    #[test]
+    fn test_route_patterns_become_openapi_paths() {
+        assert_eq!(openapi_path("/relay"), "/relay");
+        assert_eq!(
+            openapi_path("/federation/messages/:origin_relay/:origin_message_id"),
+            "/federation/messages/{origin_relay}/{origin_message_id}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_document_is_served() {
+        // ARRANGE: The relay's router
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let app = crate::create_app(db);
+
+        // ACT: Fetch the document
+        let response = app
+            .oneshot(Request::builder().uri("/openapi.json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        // ASSERT: It is the relay's OpenAPI document
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let doc: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+        assert!(doc["paths"]["/v1/message/{message_id}/evidence"]["get"].is_object());
+        assert!(doc["paths"]["/health"]["get"].is_object());
+        assert!(doc["paths"]["/message/{message_id}/evidence"].is_null());
+    }
+
+    #[cfg(feature = "swagger-ui")]
+    #[tokio::test]
+    async fn test_swagger_ui_is_served() {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+
+        let response = crate::create_app(db)
+            .oneshot(Request::builder().uri("/swagger-ui/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,195 @@
+//! WASM Policy Plugins Module
+//!
+//! Deployments with proprietary validation logic can ship it as a WASM
+//! module instead of a fork of the relay. Each `[[plugins]]` entry (see
+//! [`crate::config::PluginConfig`]) is loaded into a sandbox and runs as a
+//! [`MessageCheck`] after the built-in checks, in the order listed, so a
+//! plugin only sees messages whose proofs verified. Requires the
+//! `wasm-plugins` feature.
+//!
+//! # Plugin ABI, version 1
+//!
+//! A plugin is a core WASM module that exports:
+//!
+//! - `memory`: its linear memory
+//! - `plugin_abi_version() -> i32`: returns [`PLUGIN_ABI_VERSION`]; modules
+//!   built for another version are refused at startup
+//! - `alloc(len: i32) -> i32`: a pointer to `len` bytes the relay may write
+//! - `validate(context_ptr, context_len, metadata_ptr, metadata_len: i32) -> i32`:
+//!   `0` accepts the message and `1` rejects it
+//! - `reason() -> i64` (optional): after a rejection, the reason as UTF-8
+//!   bytes at `ptr << 32 | len`, truncated to [`MAX_REASON_BYTES`]
+//!
+//! `context` is the decoded context the sender signed; `metadata` is a JSON
+//! object with the message's `sender`, `recipient`, `thread_id`, `reply_to`,
+//! `expires_at` and `body`. Every message is evaluated in a fresh instance,
+//! so nothing carries over from one message to the next.
+//!
+//! # Sandbox
+//!
+//! Modules may import WASI preview 1 and nothing else. The WASI context has no
+//! arguments, environment, preopened directories or sockets. Each evaluation
+//! is limited to `fuel` (roughly, WASM instructions), `timeout_ms` of wall
+//! clock time and `max_memory_bytes` of linear memory. A plugin that exceeds a
+//! limit, traps or returns an unknown decision has failed: the message is
+//! rejected, or accepted with `fail_open` set.
+//!
+//! Decisions, evaluation time and fuel consumed are reported per plugin in
+//! the `plugin_decisions_total`, `plugin_duration_seconds` and
+//! `plugin_fuel_consumed` metrics.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::{
+    checks::{CheckError, MessageCheck},
+    config::PluginConfig,
+    metrics, AppError, Message,
+};
+
+#[cfg(feature = "wasm-plugins")]
+pub mod wasm;
+
+/// Plugin ABI version the relay implements
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Size of a WASM memory page, the smallest memory a module can have
+pub const WASM_PAGE_BYTES: usize = 64 * 1024;
+
+/// Longest rejection reason read from a plugin
+pub const MAX_REASON_BYTES: usize = 256;
+
+/// Errors raised loading plugins
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("Failed to load plugin {plugin}: {reason}")]
+    Load { plugin: String, reason: String },
+
+    #[error("plugins require a relay built with the `wasm-plugins` feature")]
+    Unavailable,
+}
+
+/// A plugin's verdict on a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Accept,
+    Reject(String),
+}
+
+/// Message fields passed to plugins as JSON
+#[derive(Serialize)]
+struct PluginMetadata<'a> {
+    sender: &'a str,
+    recipient: Option<&'a str>,
+    thread_id: Option<&'a str>,
+    reply_to: Option<&'a str>,
+    expires_at: Option<DateTime<Utc>>,
+    body: &'a str,
+}
+
+/// The metadata document a plugin receives for `message`
+pub fn metadata(message: &Message) -> Vec<u8> {
+    serde_json::to_vec(&PluginMetadata {
+        sender: &message.sender,
+        recipient: message.recipient.as_deref(),
+        thread_id: message.thread_id.as_deref(),
+        reply_to: message.reply_to.as_deref(),
+        expires_at: message.expires_at,
+        body: &message.body,
+    })
+    .unwrap_or_default()
+}
+
+/// Load the configured plugins as verification checks, in order
+pub fn load(plugins: &[PluginConfig]) -> Result<Vec<Arc<dyn MessageCheck>>, PluginError> {
+    if plugins.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    #[cfg(feature = "wasm-plugins")]
+    {
+        plugins
+            .iter()
+            .map(|config| wasm::WasmPlugin::load(config).map(|plugin| Arc::new(plugin) as Arc<dyn MessageCheck>))
+            .collect()
+    }
+    #[cfg(not(feature = "wasm-plugins"))]
+    Err(PluginError::Unavailable)
+}
+
+/// Record a plugin's evaluation and turn it into the check's result
+///
+/// `outcome` is the plugin's decision, or why it failed to reach one.
+#[cfg_attr(not(feature = "wasm-plugins"), allow(dead_code))]
+pub(crate) fn settle(plugin: &str, fail_open: bool, started: Instant, outcome: Result<Decision, String>) -> Result<(), AppError> {
+    let label = |decision: &str| vec![("plugin".to_string(), plugin.to_string()), ("decision".to_string(), decision.to_string())];
+    metrics::PLUGIN_DURATION_SECONDS
+        .get_or_create(&vec![("plugin".to_string(), plugin.to_string())])
+        .observe(started.elapsed().as_secs_f64());
+
+    match outcome {
+        Ok(Decision::Accept) => {
+            metrics::PLUGIN_DECISIONS_TOTAL.get_or_create(&label("accept")).inc();
+            Ok(())
+        }
+        Ok(Decision::Reject(reason)) => {
+            metrics::PLUGIN_DECISIONS_TOTAL.get_or_create(&label("reject")).inc();
+            info!("Plugin {} rejected a message: {}", plugin, reason);
+            Err(CheckError::rejected(plugin, reason).into())
+        }
+        Err(error) => {
+            metrics::PLUGIN_DECISIONS_TOTAL.get_or_create(&label("error")).inc();
+            warn!("Plugin {} failed to evaluate a message: {}", plugin, error);
+            if fail_open {
+                return Ok(());
+            }
+            Err(CheckError::rejected(plugin, "the plugin could not evaluate the message").into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decisions(plugin: &str, decision: &str) -> u64 {
+        metrics::PLUGIN_DECISIONS_TOTAL
+            .get_or_create(&vec![("plugin".to_string(), plugin.to_string()), ("decision".to_string(), decision.to_string())])
+            .get()
+    }
+
+    #[test]
+    fn test_plugin_outcomes_become_check_results() {
+        let started = Instant::now();
+
+        assert!(settle("outcomes", false, started, Ok(Decision::Accept)).is_ok());
+        let rejected = settle("outcomes", false, started, Err("ran out of fuel".to_string()));
+        assert!(matches!(
+            rejected,
+            Err(AppError::Check(CheckError::Rejected { ref check, ref reason })) if check == "outcomes" && reason == "the plugin could not evaluate the message"
+        ));
+        assert!(settle("outcomes", true, started, Err("timed out".to_string())).is_ok());
+        let rejected = settle("outcomes", true, started, Ok(Decision::Reject("sanctioned sender".to_string())));
+        assert!(matches!(rejected, Err(AppError::Check(CheckError::Rejected { ref reason, .. })) if reason == "sanctioned sender"));
+
+        assert_eq!(decisions("outcomes", "accept"), 1);
+        assert_eq!(decisions("outcomes", "reject"), 1);
+        assert_eq!(decisions("outcomes", "error"), 2);
+    }
+
+    #[test]
+    fn test_plugins_need_the_wasm_plugins_feature() {
+        assert!(load(&[]).unwrap().is_empty());
+
+        let loaded = load(&[PluginConfig { name: "sanctions".to_string(), path: "missing.wasm".into(), ..PluginConfig::default() }]);
+        if cfg!(feature = "wasm-plugins") {
+            assert!(matches!(loaded, Err(PluginError::Load { plugin, .. }) if plugin == "sanctions"));
+        } else {
+            assert!(matches!(loaded, Err(PluginError::Unavailable)));
+        }
+    }
+}
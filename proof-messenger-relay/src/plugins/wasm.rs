@@ -0,0 +1,313 @@
+//! Wasmtime host for policy plugins (`wasm-plugins` feature)
+//!
+//! Modules are compiled once at startup and pre-linked against WASI preview
+//! 1; each evaluation then instantiates the module in a new store carrying
+//! the plugin's fuel, epoch deadline and memory limits. Evaluation is
+//! synchronous and CPU-bound, so it runs on tokio's blocking thread pool.
+
+use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use std::time::{Duration, Instant};
+use wasmtime::{Config, Engine, Instance, InstancePre, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, Trap, TypedFunc};
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::WasiCtxBuilder;
+
+use super::{settle, Decision, PluginError, MAX_REASON_BYTES, PLUGIN_ABI_VERSION};
+use crate::{
+    checks::{CheckContext, MessageCheck},
+    config::PluginConfig,
+    metrics, AppError, Message,
+};
+
+/// How often running plugins are checked against their timeouts
+const EPOCH_TICK: Duration = Duration::from_millis(5);
+
+/// Engine shared by every plugin, with fuel and epoch interruption enabled
+static ENGINE: OnceCell<Engine> = OnceCell::new();
+
+/// The shared engine, started with a thread advancing its epoch
+fn engine() -> Result<&'static Engine, String> {
+    ENGINE.get_or_try_init(|| {
+        let mut config = Config::new();
+        config.consume_fuel(true).epoch_interruption(true).max_wasm_stack(512 * 1024);
+        let engine = Engine::new(&config).map_err(|e| format!("{:#}", e))?;
+
+        let ticker = engine.clone();
+        std::thread::Builder::new()
+            .name("plugin-epoch".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(EPOCH_TICK);
+                ticker.increment_epoch();
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(engine)
+    })
+}
+
+/// Per-evaluation state of a plugin's store
+struct PluginState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+/// A loaded plugin module
+#[derive(Clone)]
+pub struct WasmPlugin {
+    name: String,
+    fail_open: bool,
+    fuel: u64,
+    timeout_ticks: u64,
+    max_memory_bytes: usize,
+    pre: InstancePre<PluginState>,
+}
+
+impl WasmPlugin {
+    /// Compile the plugin at `config.path` and check that it implements the ABI
+    pub fn load(config: &PluginConfig) -> Result<Self, PluginError> {
+        let failed = |reason: String| PluginError::Load { plugin: config.name.clone(), reason };
+        let engine = engine().map_err(failed)?;
+        let module = Module::from_file(engine, &config.path).map_err(|e| failed(format!("{}: {:#}", config.path.display(), e)))?;
+
+        // Only WASI imports resolve; a module importing anything else is refused here
+        let mut linker = Linker::new(engine);
+        preview1::add_to_linker_sync(&mut linker, |state: &mut PluginState| &mut state.wasi).map_err(|e| failed(format!("{:#}", e)))?;
+        let pre = linker.instantiate_pre(&module).map_err(|e| failed(format!("{:#}", e)))?;
+
+        let plugin = Self {
+            name: config.name.clone(),
+            fail_open: config.fail_open,
+            fuel: config.fuel,
+            timeout_ticks: config.timeout_ms.div_ceil(EPOCH_TICK.as_millis() as u64).max(1),
+            max_memory_bytes: config.max_memory_bytes,
+            pre,
+        };
+        let mut store = plugin.store();
+        let version = plugin
+            .pre
+            .instantiate(&mut store)
+            .and_then(|instance| instance.get_typed_func::<(), i32>(&mut store, "plugin_abi_version"))
+            .and_then(|version| version.call(&mut store, ()))
+            .map_err(|e| failed(describe(&e)))?;
+        if version != PLUGIN_ABI_VERSION as i32 {
+            return Err(failed(format!(
+                "implements plugin ABI version {}, the relay supports version {}",
+                version, PLUGIN_ABI_VERSION
+            )));
+        }
+        Ok(plugin)
+    }
+
+    /// A store holding a sandboxed WASI context and the plugin's limits
+    fn store(&self) -> Store<PluginState> {
+        // No arguments, environment, preopened directories or sockets
+        let wasi = WasiCtxBuilder::new().build_p1();
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.max_memory_bytes)
+            .instances(1)
+            .memories(1)
+            .tables(1)
+            .build();
+        let mut store = Store::new(self.pre.module().engine(), PluginState { wasi, limits });
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(self.fuel).expect("fuel is enabled on the plugin engine");
+        store.set_epoch_deadline(self.timeout_ticks);
+        store
+    }
+
+    /// Evaluate a message in a fresh instance, recording the fuel it took
+    fn evaluate(&self, context: &[u8], metadata: &[u8]) -> Result<Decision, String> {
+        let mut store = self.store();
+        let decision = self.call(&mut store, context, metadata).map_err(|e| describe(&e));
+        let consumed = self.fuel.saturating_sub(store.get_fuel().unwrap_or(0));
+        metrics::PLUGIN_FUEL_CONSUMED
+            .get_or_create(&vec![("plugin".to_string(), self.name.clone())])
+            .observe(consumed as f64);
+        decision
+    }
+
+    fn call(&self, store: &mut Store<PluginState>, context: &[u8], metadata: &[u8]) -> wasmtime::Result<Decision> {
+        let instance = self.pre.instantiate(&mut *store)?;
+        let memory = instance
+            .get_memory(&mut *store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("the module does not export its memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+        let validate = instance.get_typed_func::<(i32, i32, i32, i32), i32>(&mut *store, "validate")?;
+
+        let (context_ptr, context_len) = write(store, &memory, &alloc, context)?;
+        let (metadata_ptr, metadata_len) = write(store, &memory, &alloc, metadata)?;
+        match validate.call(&mut *store, (context_ptr, context_len, metadata_ptr, metadata_len))? {
+            0 => Ok(Decision::Accept),
+            1 => Ok(Decision::Reject(reason(store, &instance, &memory))),
+            other => Err(wasmtime::Error::msg(format!("returned unknown decision {}", other))),
+        }
+    }
+}
+
+#[async_trait]
+impl MessageCheck for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self, message: &Message, _context: &CheckContext<'_>) -> Result<(), AppError> {
+        let context = hex::decode(&message.context).map_err(|e| AppError::InvalidContext(format!("Invalid hex encoding: {}", e)))?;
+        let metadata = super::metadata(message);
+        let plugin = self.clone();
+        let started = Instant::now();
+        let outcome = tokio::task::spawn_blocking(move || plugin.evaluate(&context, &metadata))
+            .await
+            .map_err(|e| AppError::ProcessingError(format!("Plugin task failed: {}", e)))?;
+        settle(&self.name, self.fail_open, started, outcome)
+    }
+}
+
+/// Copy `bytes` into memory the plugin allocated for them
+fn write(store: &mut Store<PluginState>, memory: &Memory, alloc: &TypedFunc<i32, i32>, bytes: &[u8]) -> wasmtime::Result<(i32, i32)> {
+    let len = i32::try_from(bytes.len())?;
+    let ptr = alloc.call(&mut *store, len)?;
+    memory.write(&mut *store, ptr as u32 as usize, bytes)?;
+    Ok((ptr, len))
+}
+
+/// The reason a plugin gave for its rejection, if it exports one
+fn reason(store: &mut Store<PluginState>, instance: &Instance, memory: &Memory) -> String {
+    let given = instance
+        .get_typed_func::<(), i64>(&mut *store, "reason")
+        .and_then(|reason| reason.call(&mut *store, ()))
+        .ok()
+        .and_then(|packed| {
+            let (ptr, len) = ((packed as u64 >> 32) as usize, (packed as u64 & 0xffff_ffff) as usize);
+            let mut bytes = vec![0; len.min(MAX_REASON_BYTES)];
+            memory.read(&*store, ptr, &mut bytes).ok()?;
+            Some(String::from_utf8_lossy(&bytes).into_owned())
+        });
+    given.filter(|reason| !reason.is_empty()).unwrap_or_else(|| "rejected by plugin".to_string())
+}
+
+/// Why a plugin failed, naming the limit it hit if any
+fn describe(error: &wasmtime::Error) -> String {
+    match error.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => "ran out of fuel".to_string(),
+        Some(Trap::Interrupt) => "timed out".to_string(),
+        _ => format!("{:#}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::CheckError;
+    use proof_messenger_protocol::hybrid::HybridPolicy;
+
+    /// A plugin implementing the ABI with the given version and `validate` body
+    fn plugin_source(version: i32, validate: &str) -> String {
+        format!(
+            r#"(module
+                (import "wasi_snapshot_preview1" "random_get" (func $random_get (param i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (global $next (mut i32) (i32.const 1024))
+                (data (i32.const 16) "denied context")
+                (func (export "plugin_abi_version") (result i32) (i32.const {version}))
+                (func (export "alloc") (param $len i32) (result i32)
+                    (local $ptr i32)
+                    (local.set $ptr (global.get $next))
+                    (global.set $next (i32.add (global.get $next) (local.get $len)))
+                    (local.get $ptr))
+                (func (export "validate") (param $context i32) (param $context_len i32) (param $metadata i32) (param $metadata_len i32) (result i32)
+                    {validate})
+                (func (export "reason") (result i64) (i64.const 0x100000000e)))"#
+        )
+    }
+
+    /// Rejects contexts starting with `d`
+    const DENY_D: &str = "(if (i32.eqz (local.get $context_len)) (then (return (i32.const 0)))) (i32.eq (i32.load8_u (local.get $context)) (i32.const 100))";
+
+    fn load(dir: &tempfile::TempDir, name: &str, source: &str, tweak: impl FnOnce(&mut PluginConfig)) -> Result<WasmPlugin, PluginError> {
+        let path = dir.path().join(format!("{}.wat", name));
+        std::fs::write(&path, source).unwrap();
+        let mut config = PluginConfig { name: name.to_string(), path, ..PluginConfig::default() };
+        tweak(&mut config);
+        WasmPlugin::load(&config)
+    }
+
+    fn message(context: &[u8]) -> Message {
+        Message {
+            sender: "ab".repeat(32),
+            context: hex::encode(context),
+            body: "hello".to_string(),
+            proof: "cd".repeat(64),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    fn context() -> CheckContext<'static> {
+        CheckContext { db: None, policy: HybridPolicy::Transitional }
+    }
+
+    #[tokio::test]
+    async fn test_plugins_accept_and_reject_messages() {
+        // ARRANGE: A plugin rejecting contexts that start with "d"
+        let dir = tempfile::tempdir().unwrap();
+        let plugin = load(&dir, "deny-d", &plugin_source(1, DENY_D), |_| {}).unwrap();
+
+        // ACT: Evaluate an allowed and a denied context
+        let accepted = plugin.check(&message(b"allowed"), &context()).await;
+        let rejected = plugin.check(&message(b"denied"), &context()).await;
+
+        // ASSERT: The rejection carries the plugin's name and reason
+        assert!(accepted.is_ok());
+        assert!(matches!(
+            rejected,
+            Err(AppError::Check(CheckError::Rejected { ref check, ref reason })) if check == "deny-d" && reason == "denied context"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_plugins_are_held_to_their_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let spin = plugin_source(1, "(loop $spin (br $spin)) (i32.const 0)");
+
+        let out_of_fuel = load(&dir, "fuel", &spin, |config| config.fuel = 10_000).unwrap();
+        assert_eq!(out_of_fuel.evaluate(b"", b"{}"), Err("ran out of fuel".to_string()));
+
+        let slow = load(&dir, "slow", &spin, |config| {
+            config.fuel = u64::MAX;
+            config.timeout_ms = 20;
+        })
+        .unwrap();
+        assert_eq!(slow.evaluate(b"", b"{}"), Err("timed out".to_string()));
+
+        // A plugin that fails is rejected unless it fails open
+        assert!(slow.check(&message(b"allowed"), &context()).await.is_err());
+        let lenient = load(&dir, "lenient", &spin, |config| {
+            config.fuel = 10_000;
+            config.fail_open = true;
+        })
+        .unwrap();
+        assert!(lenient.check(&message(b"allowed"), &context()).await.is_ok());
+
+        // Growing memory past the limit fails inside the plugin instead of succeeding
+        let greedy = plugin_source(1, "(i32.ne (memory.grow (i32.const 100)) (i32.const -1))");
+        let capped = load(&dir, "capped", &greedy, |config| config.max_memory_bytes = 1024 * 1024).unwrap();
+        assert_eq!(capped.evaluate(b"", b"{}"), Ok(Decision::Accept));
+    }
+
+    #[test]
+    fn test_plugins_must_match_the_abi() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let future = load(&dir, "future", &plugin_source(2, DENY_D), |_| {});
+        assert!(matches!(future, Err(PluginError::Load { reason, .. }) if reason.contains("ABI version 2")));
+
+        let host_call = plugin_source(1, DENY_D).replacen("(memory", "(import \"env\" \"host_call\" (func)) (memory", 1);
+        assert!(matches!(load(&dir, "host-call", &host_call, |_| {}), Err(PluginError::Load { .. })));
+
+        let missing = dir.path().join("missing.wasm");
+        let config = PluginConfig { name: "missing".to_string(), path: missing, ..PluginConfig::default() };
+        assert!(matches!(WasmPlugin::load(&config), Err(PluginError::Load { plugin, .. }) if plugin == "missing"));
+    }
+}
@@ -0,0 +1,465 @@
+//! Proof Chain Module
+//!
+//! This module indexes relayed messages whose context is a proof chain
+//! context (see `proof_messenger_protocol::chain`). Every stored message's
+//! proof hash is recorded alongside the parent hashes its context commits
+//! to, so the relay can answer "which approvals build on proof X" and verify
+//! a message's chain against the proofs it has relayed.
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use ed25519_dalek::PublicKey;
+use proof_messenger_protocol::chain::{
+    parse_chain_context, proof_hash, verify_chain, ChainedProof, ProofHash, MAX_CHAIN_PROOFS, PROOF_HASH_LENGTH,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, instrument};
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, DatabaseError, ProofChainEntry, StoredMessage},
+    get_tenant_message,
+    tenancy::TenantScope,
+    AppError,
+};
+
+/// Default number of proofs returned by a chain query
+const DEFAULT_CHAIN_LIMIT: i64 = 100;
+
+/// Maximum number of proofs returned by a chain query
+const MAX_CHAIN_LIMIT: i64 = 1000;
+
+/// Default number of links followed by a chain query
+const DEFAULT_CHAIN_DEPTH: i64 = 32;
+
+/// Query parameters for walking a proof chain
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProofChainQuery {
+    /// Most links to follow from the starting proof (default 32)
+    pub max_depth: Option<i64>,
+    /// Maximum number of proofs to return (default 100, at most 1000)
+    pub limit: Option<i64>,
+}
+
+impl ProofChainQuery {
+    /// Validate the query and return the effective (max_depth, limit)
+    fn bounds(&self) -> Result<(i64, i64), AppError> {
+        let max_depth = self.max_depth.unwrap_or(DEFAULT_CHAIN_DEPTH);
+        if !(1..=MAX_CHAIN_PROOFS as i64).contains(&max_depth) {
+            return Err(AppError::InvalidQuery(format!("max_depth must be between 1 and {}", MAX_CHAIN_PROOFS)));
+        }
+        let limit = self.limit.unwrap_or(DEFAULT_CHAIN_LIMIT);
+        if !(1..=MAX_CHAIN_LIMIT).contains(&limit) {
+            return Err(AppError::InvalidQuery(format!("limit must be between 1 and {}", MAX_CHAIN_LIMIT)));
+        }
+        Ok((max_depth, limit))
+    }
+}
+
+/// Create router for proof chain endpoints
+pub fn proof_chain_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/proofs/:proof_hash/descendants", get(descendants_handler))
+        .route("/proofs/:proof_hash/ancestors", get(ancestors_handler))
+        .route("/message/:message_id/proof-chain", get(verify_chain_handler))
+}
+
+/// Create router for authenticated proof chain endpoints
+pub fn authenticated_proof_chain_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/proofs/:proof_hash/descendants", get(authenticated_descendants_handler))
+        .route("/proofs/:proof_hash/ancestors", get(authenticated_ancestors_handler))
+        .route("/message/:message_id/proof-chain", get(authenticated_verify_chain_handler))
+}
+
+/// A stored message's proof hash and the parents its context commits to
+///
+/// Returns `None` when the message's fields are not valid hex. A context
+/// that is not a well-formed chain context has no parents.
+pub(crate) fn chain_links(message: &StoredMessage) -> Option<(ProofHash, Vec<ProofHash>)> {
+    let sender = hex::decode(&message.sender).ok()?;
+    let context = hex::decode(&message.context).ok()?;
+    let proof = hex::decode(&message.proof).ok()?;
+
+    let parents = match parse_chain_context(&context) {
+        Ok(Some(chain)) => chain.parents,
+        _ => Vec::new(),
+    };
+    Some((proof_hash(&sender, &context, &proof), parents))
+}
+
+/// Rebuild the chained proof carried by a stored message
+pub fn stored_chained_proof(message: &StoredMessage) -> Result<ChainedProof, AppError> {
+    let sender_bytes = hex::decode(&message.sender)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid hex encoding: {}", e)))?;
+    let sender = PublicKey::from_bytes(&sender_bytes)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid public key: {}", e)))?;
+    let context = hex::decode(&message.context)
+        .map_err(|e| AppError::InvalidContext(format!("Invalid hex encoding: {}", e)))?;
+    let proof = hex::decode(&message.proof)
+        .map_err(|e| AppError::InvalidSignature(format!("Invalid hex encoding: {}", e)))?;
+    Ok(ChainedProof { sender, context, proof })
+}
+
+/// Whether a proof in a chain is visible to the tenant
+///
+/// Proofs relayed in another tenant's groups are hidden; proofs that were
+/// only named as parents, or whose message was purged, are not.
+async fn tenant_sees_proof(db: &Database, tenant: &TenantScope, message_id: Option<&str>) -> Result<bool, AppError> {
+    let (Some(message_id), Some(_)) = (message_id, tenant.tenant()) else {
+        return Ok(true);
+    };
+    match db.get_message_by_id(message_id).await {
+        Ok(message) => Ok(tenant.owns_group(&message.group_id)),
+        Err(DatabaseError::MessageNotFound(_)) => Ok(true),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Verify a stored message's proof and every ancestor the relay has seen
+///
+/// Ancestors are loaded from the messages that carried them; a parent that
+/// was never relayed here, or only in another tenant's groups, makes the
+/// chain invalid.
+pub async fn verify_message_chain(db: &Database, tenant: &TenantScope, message_id: &str) -> Result<serde_json::Value, AppError> {
+    let message = get_tenant_message(db, tenant, message_id).await?;
+    let proof = stored_chained_proof(&message)?;
+    let hash = hex::encode(proof.hash());
+
+    let mut known = HashMap::new();
+    let ancestors = db
+        .get_proof_ancestors(&hash, MAX_CHAIN_PROOFS as i64, MAX_CHAIN_PROOFS as i64)
+        .await?;
+    for ancestor in &ancestors {
+        let Some(ancestor_id) = &ancestor.message_id else {
+            continue;
+        };
+        let stored = match db.get_message_by_id(ancestor_id).await {
+            Ok(stored) if !tenant.owns_group(&stored.group_id) => continue,
+            Ok(stored) => stored,
+            // Removed by retention; verification reports the parent as missing
+            Err(DatabaseError::MessageNotFound(_)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let ancestor_proof = stored_chained_proof(&stored)?;
+        known.insert(ancestor_proof.hash(), ancestor_proof);
+    }
+
+    let verification = verify_chain(&proof, |parent| known.get(parent).cloned());
+    let parents = proof.parents().unwrap_or_default();
+
+    Ok(serde_json::json!({
+        "message_id": message_id,
+        "proof_hash": hash,
+        "parents": parents.iter().map(hex::encode).collect::<Vec<_>>(),
+        "valid": verification.is_ok(),
+        "proofs": verification.as_ref().ok().map(|v| v.proofs),
+        "depth": verification.as_ref().ok().map(|v| v.depth),
+        "roots": verification.as_ref().ok().map(|v| v.roots.iter().map(hex::encode).collect::<Vec<_>>()),
+        "error": verification.err().map(|e| e.to_string()),
+    }))
+}
+
+/// Normalize a proof hash from a path
+fn parse_proof_hash(proof_hash: &str) -> Result<String, AppError> {
+    let bytes = hex::decode(proof_hash)
+        .map_err(|e| AppError::InvalidQuery(format!("Invalid proof hash hex: {}", e)))?;
+    if bytes.len() != PROOF_HASH_LENGTH {
+        return Err(AppError::InvalidQuery(format!(
+            "Proof hash must be {} bytes (got {})",
+            PROOF_HASH_LENGTH,
+            bytes.len()
+        )));
+    }
+    Ok(hex::encode(bytes))
+}
+
+/// Walk a proof chain in either direction and wrap the result
+async fn chain_response(
+    db: &Database,
+    tenant: &TenantScope,
+    proof_hash: &str,
+    params: &ProofChainQuery,
+    descendants: bool,
+) -> Result<serde_json::Value, AppError> {
+    let proof_hash = parse_proof_hash(proof_hash)?;
+    let (max_depth, limit) = params.bounds()?;
+
+    let message_id = db.get_proof_chain_message_id(&proof_hash).await?;
+    if !tenant_sees_proof(db, tenant, message_id.as_deref()).await? {
+        return Err(DatabaseError::MessageNotFound(proof_hash).into());
+    }
+    let chain: Vec<ProofChainEntry> = if descendants {
+        db.get_proof_descendants(&proof_hash, max_depth, limit).await?
+    } else {
+        db.get_proof_ancestors(&proof_hash, max_depth, limit).await?
+    };
+    let mut proofs = Vec::with_capacity(chain.len());
+    for entry in chain {
+        if tenant_sees_proof(db, tenant, entry.message_id.as_deref()).await? {
+            proofs.push(entry);
+        }
+    }
+
+    Ok(serde_json::json!({
+        "status": "success",
+        "proof_hash": proof_hash,
+        "message_id": message_id,
+        "count": proofs.len(),
+        "proofs": proofs
+    }))
+}
+
+/// Handler to list the proofs that build on a proof
+#[utoipa::path(
+    get,
+    path = "/proofs/{proof_hash}/descendants",
+    operation_id = "listProofDescendants",
+    tag = "proof-chains",
+    params(("proof_hash" = String, Path, description = "Proof hash (hex encoded)"), ProofChainQuery),
+    responses(
+        (status = 200, description = "Proofs chained from this one", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn descendants_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(proof_hash): Path<String>,
+    Query(params): Query<ProofChainQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Retrieving descendants of proof: {}", proof_hash);
+
+    let response = chain_response(&db, &tenant, &proof_hash, &params, true).await?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to list the proofs a proof builds on
+#[utoipa::path(
+    get,
+    path = "/proofs/{proof_hash}/ancestors",
+    operation_id = "listProofAncestors",
+    tag = "proof-chains",
+    params(("proof_hash" = String, Path, description = "Proof hash (hex encoded)"), ProofChainQuery),
+    responses(
+        (status = 200, description = "Proofs this one chains from", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn ancestors_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(proof_hash): Path<String>,
+    Query(params): Query<ProofChainQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Retrieving ancestors of proof: {}", proof_hash);
+
+    let response = chain_response(&db, &tenant, &proof_hash, &params, false).await?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Handler to verify a message's proof chain
+#[utoipa::path(
+    get,
+    path = "/message/{message_id}/proof-chain",
+    operation_id = "verifyProofChain",
+    tag = "proof-chains",
+    params(("message_id" = String, Path, description = "Message ID")),
+    responses(
+        (status = 200, description = "The message's verified proof chain", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn verify_chain_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Verifying proof chain of message: {}", message_id);
+
+    let mut response = verify_message_chain(&db, &tenant, &message_id).await?;
+    response["status"] = "success".into();
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Authenticated handler to list the proofs that build on a proof
+#[instrument(skip_all)]
+async fn authenticated_descendants_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Path(proof_hash): Path<String>,
+    Query(params): Query<ProofChainQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} retrieving descendants of proof: {}", auth.user_id, proof_hash);
+
+    let mut response = chain_response(&db, &tenant, &proof_hash, &params, true).await?;
+    response["authenticated_user"] = auth.user_id.into();
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Authenticated handler to list the proofs a proof builds on
+#[instrument(skip_all)]
+async fn authenticated_ancestors_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Path(proof_hash): Path<String>,
+    Query(params): Query<ProofChainQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} retrieving ancestors of proof: {}", auth.user_id, proof_hash);
+
+    let mut response = chain_response(&db, &tenant, &proof_hash, &params, false).await?;
+    response["authenticated_user"] = auth.user_id.into();
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Authenticated handler to verify a message's proof chain
+#[instrument(skip_all)]
+async fn authenticated_verify_chain_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} verifying proof chain of message: {}", auth.user_id, message_id);
+
+    let mut response = verify_message_chain(&db, &tenant, &message_id).await?;
+    response["status"] = "success".into();
+    response["authenticated_user"] = auth.user_id.into();
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use proof_messenger_protocol::chain::make_chained_proof;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use tower::ServiceExt;
+
+    async fn setup_test_app() -> (Router, Arc<Database>) {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+
+        let app = Router::new().merge(proof_chain_routes()).with_state(db.clone());
+        (app, db)
+    }
+
+    async fn relay(db: &Database, proof: &ChainedProof) -> String {
+        let message = crate::Message {
+            sender: hex::encode(proof.sender.as_bytes()),
+            context: hex::encode(&proof.context),
+            body: "approval".to_string(),
+            proof: hex::encode(&proof.proof),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        };
+        db.store_message(StoredMessage::from(message)).await.unwrap()
+    }
+
+    async fn get(app: &Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_descendants_and_ancestors_follow_links() {
+        // ARRANGE: a <- b <- c, all relayed
+        let (app, db) = setup_test_app().await;
+        let signer = generate_secure_keypair_with_seed(1);
+        let a = make_chained_proof(&signer, &[], b"a").unwrap();
+        let b = make_chained_proof(&signer, &[a.hash()], b"b").unwrap();
+        let c = make_chained_proof(&signer, &[b.hash()], b"c").unwrap();
+        let a_id = relay(&db, &a).await;
+        let b_id = relay(&db, &b).await;
+        let c_id = relay(&db, &c).await;
+
+        // ACT: Walk down from the root and up from the leaf
+        let (status, descendants) = get(&app, &format!("/proofs/{}/descendants", hex::encode(a.hash()))).await;
+        let (_, ancestors) = get(&app, &format!("/proofs/{}/ancestors", hex::encode(c.hash()))).await;
+        let (_, shallow) = get(&app, &format!("/proofs/{}/descendants?max_depth=1", hex::encode(a.hash()))).await;
+
+        // ASSERT: Proofs are listed nearest first with the messages carrying them
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(descendants["message_id"], a_id);
+        assert_eq!(descendants["count"], 2);
+        assert_eq!(descendants["proofs"][0]["message_id"], b_id);
+        assert_eq!(descendants["proofs"][1]["message_id"], c_id);
+        assert_eq!(descendants["proofs"][1]["depth"], 2);
+        assert_eq!(ancestors["proofs"][0]["proof_hash"], hex::encode(b.hash()));
+        assert_eq!(ancestors["proofs"][1]["proof_hash"], hex::encode(a.hash()));
+        assert_eq!(shallow["count"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_message_chain_verifies_against_relayed_parents() {
+        let (app, db) = setup_test_app().await;
+        let alice = generate_secure_keypair_with_seed(1);
+        let bob = generate_secure_keypair_with_seed(2);
+        let a = make_chained_proof(&alice, &[], b"approve budget").unwrap();
+        let b = make_chained_proof(&bob, &[a.hash()], b"approve purchase").unwrap();
+        relay(&db, &a).await;
+        let b_id = relay(&db, &b).await;
+
+        let (status, json) = get(&app, &format!("/message/{}/proof-chain", b_id)).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["valid"], true);
+        assert_eq!(json["proofs"], 2);
+        assert_eq!(json["roots"][0], hex::encode(a.hash()));
+    }
+
+    #[tokio::test]
+    async fn test_chain_with_unrelayed_parent_is_invalid() {
+        let (app, db) = setup_test_app().await;
+        let signer = generate_secure_keypair_with_seed(1);
+        let a = make_chained_proof(&signer, &[], b"a").unwrap();
+        let b = make_chained_proof(&signer, &[a.hash()], b"b").unwrap();
+        let b_id = relay(&db, &b).await;
+
+        let (_, json) = get(&app, &format!("/message/{}/proof-chain", b_id)).await;
+
+        assert_eq!(json["valid"], false);
+        assert!(json["error"].as_str().unwrap().contains("not found"));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_queries_are_rejected() {
+        let (app, _) = setup_test_app().await;
+        let hash = hex::encode([0u8; PROOF_HASH_LENGTH]);
+
+        let (short, _) = get(&app, "/proofs/abcd/descendants").await;
+        let (limit, _) = get(&app, &format!("/proofs/{}/descendants?limit=0", hash)).await;
+        let (depth, _) = get(&app, &format!("/proofs/{}/ancestors?max_depth=0", hash)).await;
+
+        assert_eq!(short, StatusCode::BAD_REQUEST);
+        assert_eq!(limit, StatusCode::BAD_REQUEST);
+        assert_eq!(depth, StatusCode::BAD_REQUEST);
+    }
+}
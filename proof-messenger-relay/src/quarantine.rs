@@ -0,0 +1,347 @@
+//! Dead-Letter Queue Module
+//!
+//! By default, messages that fail verification are rejected and discarded.
+//! In quarantine mode they are also stored in the `rejected_messages` table
+//! with the reason they were rejected. Administrators can list them to
+//! investigate abuse patterns, and rejections are counted per reason in the
+//! metrics registry.
+//!
+//! Quarantine mode is enabled by the `features.quarantine` relay setting or
+//! `QUARANTINE_REJECTED_MESSAGES=true` (see [`crate::config::RelayConfig`])
+//! and layering the resulting [`Quarantine`] onto the router as an
+//! [`axum::Extension`].
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use utoipa::IntoParams;
+use std::sync::Arc;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, RejectedMessage},
+    metrics, AppError, Message,
+};
+
+/// Default number of rejected messages per page
+const DEFAULT_LIST_LIMIT: i64 = 50;
+
+/// Maximum number of rejected messages per page
+const MAX_LIST_LIMIT: i64 = 500;
+
+/// Quarantine settings
+#[derive(Debug, Clone)]
+pub struct QuarantineConfig {
+    /// How long rejected messages are kept
+    pub retention: chrono::Duration,
+    /// How often expired rejected messages are purged
+    pub cleanup_interval: std::time::Duration,
+}
+
+impl Default for QuarantineConfig {
+    fn default() -> Self {
+        Self {
+            retention: chrono::Duration::days(30),
+            cleanup_interval: std::time::Duration::from_secs(3600),
+        }
+    }
+}
+
+/// Rejection category for a verification failure
+///
+/// Returns `None` for errors that are not the sender's fault (such as
+/// database failures), which are never quarantined.
+pub fn rejection_reason(error: &AppError) -> Option<&'static str> {
+    match error {
+        AppError::InvalidSignature(_) => Some("invalid_signature"),
+        AppError::InvalidPublicKey(_) => Some("invalid_public_key"),
+        AppError::InvalidContext(_) => Some("invalid_context"),
+        AppError::VerificationFailed => Some("verification_failed"),
+        AppError::ProofRevoked => Some("proof_revoked"),
+        AppError::Check(crate::checks::CheckError::Rejected { .. }) => Some("rejected_by_check"),
+        _ => None,
+    }
+}
+
+/// Stores rejected messages while quarantine mode is enabled
+pub struct Quarantine {
+    config: QuarantineConfig,
+}
+
+impl Quarantine {
+    /// Create a quarantine with the given settings
+    pub fn new(config: QuarantineConfig) -> Self {
+        Self { config }
+    }
+
+    /// Store a message that failed verification
+    ///
+    /// Returns `Ok(false)` without storing anything if the error is not a
+    /// verification failure.
+    pub async fn record(
+        &self,
+        db: &Database,
+        message: &Message,
+        error: &AppError,
+        submitted_by: Option<&str>,
+    ) -> Result<bool, AppError> {
+        let reason = match rejection_reason(error) {
+            Some(reason) => reason,
+            None => return Ok(false),
+        };
+        let payload = serde_json::to_string(message)
+            .map_err(|e| AppError::ProcessingError(format!("Failed to serialize rejected message: {}", e)))?;
+
+        db.store_rejected_message(&RejectedMessage {
+            id: Uuid::new_v4().to_string(),
+            sender: message.sender.clone(),
+            reason: reason.to_string(),
+            error: error.to_string(),
+            payload,
+            submitted_by: submitted_by.map(str::to_string),
+            rejected_at: Utc::now(),
+        })
+        .await?;
+
+        metrics::QUARANTINED_MESSAGES_TOTAL
+            .get_or_create(&vec![("reason".to_string(), reason.to_string())])
+            .inc();
+        Ok(true)
+    }
+
+    /// Purge rejected messages past the retention period periodically in the background
+    pub fn spawn_cleanup(self: Arc<Self>, db: Arc<Database>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.config.cleanup_interval);
+            loop {
+                interval.tick().await;
+                crate::readiness::BACKGROUND_JOBS.heartbeat("quarantine_cleanup", self.config.cleanup_interval);
+                match db.delete_rejected_messages_before(Utc::now() - self.config.retention).await {
+                    Ok(0) => {}
+                    Ok(purged) => {
+                        info!("Purged {} expired rejected messages", purged);
+                        crate::metrics::RETENTION_DELETIONS_TOTAL
+                            .get_or_create(&vec![("job".to_string(), "quarantine_cleanup".to_string())])
+                            .inc_by(purged);
+                    }
+                    Err(e) => warn!("Failed to purge rejected messages: {}", e),
+                }
+            }
+        })
+    }
+}
+
+/// Quarantine a rejected message if quarantine mode is enabled
+///
+/// Storage failures are logged rather than returned, so the client always
+/// sees the original verification error.
+pub async fn record_if_enabled(
+    quarantine: Option<&Arc<Quarantine>>,
+    db: &Database,
+    message: &Message,
+    error: &AppError,
+    submitted_by: Option<&str>,
+) {
+    if let Some(quarantine) = quarantine {
+        if let Err(e) = quarantine.record(db, message, error, submitted_by).await {
+            warn!("Failed to quarantine rejected message: {}", e);
+        }
+    }
+}
+
+/// Query parameters for listing rejected messages
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RejectedMessageQuery {
+    /// Only rejections with this reason
+    pub reason: Option<String>,
+    /// Only rejections claiming this sender
+    pub sender: Option<String>,
+    /// Maximum number of results to return (default 50, at most 500)
+    pub limit: Option<i64>,
+    /// Number of results to skip for pagination
+    pub offset: Option<i64>,
+}
+
+impl RejectedMessageQuery {
+    /// Validate the query and return the effective (limit, offset)
+    fn page(&self) -> Result<(i64, i64), AppError> {
+        let limit = self.limit.unwrap_or(DEFAULT_LIST_LIMIT);
+        if !(1..=MAX_LIST_LIMIT).contains(&limit) {
+            return Err(AppError::InvalidQuery(format!("limit must be between 1 and {}", MAX_LIST_LIMIT)));
+        }
+        let offset = self.offset.unwrap_or(0);
+        if offset < 0 {
+            return Err(AppError::InvalidQuery("offset cannot be negative".to_string()));
+        }
+        Ok((limit, offset))
+    }
+}
+
+/// Create router for quarantine endpoints
+pub fn quarantine_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/", get(list_rejected_messages_handler))
+}
+
+/// Create router for authenticated quarantine endpoints
+pub fn authenticated_quarantine_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/", get(authenticated_list_rejected_messages_handler))
+}
+
+/// Handler to list rejected messages
+#[utoipa::path(
+    get,
+    path = "/quarantine",
+    operation_id = "listQuarantinedMessages",
+    tag = "quarantine",
+    params(RejectedMessageQuery),
+    responses(
+        (status = 200, description = "Quarantined messages", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn list_rejected_messages_handler(
+    State(db): State<Arc<Database>>,
+    Query(params): Query<RejectedMessageQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Listing rejected messages");
+
+    let (limit, offset) = params.page()?;
+    let rejected = db
+        .get_rejected_messages(params.reason.as_deref(), params.sender.as_deref(), limit, offset)
+        .await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "limit": limit,
+        "offset": offset,
+        "count": rejected.len(),
+        "rejected_messages": rejected
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to list rejected messages
+#[instrument(skip_all)]
+async fn authenticated_list_rejected_messages_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    Query(params): Query<RejectedMessageQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} listing rejected messages", auth.user_id);
+
+    let (limit, offset) = params.page()?;
+    let rejected = db
+        .get_rejected_messages(params.reason.as_deref(), params.sender.as_deref(), limit, offset)
+        .await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "limit": limit,
+        "offset": offset,
+        "count": rejected.len(),
+        "rejected_messages": rejected,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_app;
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::Extension;
+    use tower::ServiceExt;
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    fn bad_message() -> Message {
+        Message {
+            sender: "not-a-key".to_string(),
+            context: "bb".to_string(),
+            body: "spam".to_string(),
+            proof: "cc".repeat(64),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    async fn relay(app: &Router, message: &Message) -> StatusCode {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/relay")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(message).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_rejected_message_is_quarantined_and_listed() {
+        // ARRANGE: A relay with quarantine mode enabled
+        let db = setup_db().await;
+        let app = create_app(db.clone()).layer(Extension(Arc::new(Quarantine::new(QuarantineConfig::default()))));
+
+        // ACT: Relay a message with a bad signature and list the quarantine
+        let status = relay(&app, &bad_message()).await;
+        let response = app
+            .oneshot(Request::builder().uri("/quarantine?reason=invalid_public_key").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        // ASSERT: The client still sees the rejection, and the message is held with its reason
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["count"], 1);
+        let rejected = &json["rejected_messages"][0];
+        assert_eq!(rejected["sender"], "not-a-key");
+        let payload: Message = serde_json::from_str(rejected["payload"].as_str().unwrap()).unwrap();
+        assert_eq!(payload.body, "spam");
+    }
+
+    #[tokio::test]
+    async fn test_rejections_are_discarded_without_quarantine() {
+        let db = setup_db().await;
+        let app = create_app(db.clone());
+
+        let status = relay(&app, &bad_message()).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(db.get_rejected_messages(None, None, 10, 0).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_only_verification_failures_are_quarantined() {
+        assert_eq!(rejection_reason(&AppError::ProofRevoked), Some("proof_revoked"));
+        assert_eq!(rejection_reason(&AppError::VerificationFailed), Some("verification_failed"));
+        assert_eq!(rejection_reason(&AppError::ProcessingError("db down".to_string())), None);
+    }
+}
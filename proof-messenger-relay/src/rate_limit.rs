@@ -0,0 +1,176 @@
+//! Rate Limiting Module
+//!
+//! The relay's API routes share a single rate-limit bucket sized by
+//! `[rate_limit]`. Without `[redis]` the bucket is the in-process
+//! `tower_governor` limiter, so each replica behind a load balancer enforces
+//! the limit on its own. With `[redis]` the bucket lives in Redis (see
+//! [`crate::shared_state`]) and the limit holds across every replica.
+//!
+//! If Redis cannot be reached the request is let through and a warning is
+//! logged, so an outage of the shared store does not take the relay down.
+
+use axum::{
+    extract::{Request, State},
+    http::{header::RETRY_AFTER, HeaderName, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Router,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::RelayConfig;
+use crate::shared_state::{Quota, RateLimitStore};
+
+/// Bucket shared by every rate-limited route
+const GLOBAL_BUCKET: &str = "global";
+
+/// Rate limiter backed by a store shared between relay replicas
+#[derive(Clone)]
+pub struct SharedRateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    quota: Quota,
+}
+
+impl SharedRateLimiter {
+    /// A limiter enforcing `quota` through `store`
+    pub fn new(store: Arc<dyn RateLimitStore>, quota: Quota) -> Self {
+        Self { store, quota }
+    }
+}
+
+/// Apply the configured rate limit to `routes`
+///
+/// Uses the Redis bucket when `redis.url` is set and the relay was built
+/// with the `redis` feature, and the in-memory limiter otherwise.
+pub fn with_rate_limit(routes: Router, config: &RelayConfig) -> Router {
+    #[cfg(feature = "redis")]
+    if config.redis.url.is_some() {
+        let store = crate::shared_state::redis::RedisStore::new(&config.redis)
+            .expect("redis.url is validated with the relay configuration");
+        let limiter = SharedRateLimiter::new(Arc::new(store), Quota::from_config(&config.rate_limit));
+        return with_shared_rate_limit(routes, limiter);
+    }
+
+    in_memory_rate_limit(routes, config)
+}
+
+/// Apply a shared rate limiter to `routes`
+pub fn with_shared_rate_limit(routes: Router, limiter: SharedRateLimiter) -> Router {
+    routes.layer(axum::middleware::from_fn_with_state(limiter, enforce_shared_rate_limit))
+}
+
+/// Apply this relay's own in-memory limiter to `routes`
+fn in_memory_rate_limit(routes: Router, config: &RelayConfig) -> Router {
+    use tower_governor::{GovernorLayer, governor::GovernorConfigBuilder, key_extractor::GlobalKeyExtractor};
+
+    // Use GlobalKeyExtractor to avoid "Unable To Extract Key!" error
+    let governor_conf = GovernorConfigBuilder::default()
+        .per_second(config.rate_limit.per_second)
+        .burst_size(config.rate_limit.burst_size)
+        .key_extractor(GlobalKeyExtractor)
+        .finish()
+        .unwrap();
+
+    routes.layer(GovernorLayer {
+        config: Arc::new(governor_conf),
+    })
+}
+
+/// Middleware taking one request from the shared bucket
+async fn enforce_shared_rate_limit(State(limiter): State<SharedRateLimiter>, request: Request, next: Next) -> Response {
+    match limiter.store.acquire(GLOBAL_BUCKET, limiter.quota).await {
+        Ok(None) => next.run(request).await,
+        Ok(Some(wait)) => too_many_requests(wait),
+        Err(e) => {
+            warn!("Rate limit not enforced: {}", e);
+            next.run(request).await
+        }
+    }
+}
+
+/// Rejection matching the in-memory limiter's, which clients already handle
+fn too_many_requests(wait: Duration) -> Response {
+    let seconds = wait.as_secs_f64().ceil().max(1.0) as u64;
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(RETRY_AFTER, seconds.to_string()), (HeaderName::from_static("x-ratelimit-after"), seconds.to_string())],
+        format!("Too Many Requests! Wait for {}s", seconds),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared_state::SharedStateError;
+    use async_trait::async_trait;
+    use axum::{body::Body, routing::get};
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+
+    /// Bucket counting down from a fixed number of requests, or failing when unset
+    struct CountingStore {
+        remaining: Mutex<Option<u32>>,
+    }
+
+    #[async_trait]
+    impl RateLimitStore for CountingStore {
+        async fn acquire(&self, key: &str, quota: Quota) -> Result<Option<Duration>, SharedStateError> {
+            assert_eq!(key, GLOBAL_BUCKET);
+            match self.remaining.lock().unwrap().as_mut() {
+                Some(0) => Ok(Some(quota.period)),
+                Some(remaining) => {
+                    *remaining -= 1;
+                    Ok(None)
+                }
+                None => Err(SharedStateError::Unavailable("connection refused".to_string())),
+            }
+        }
+    }
+
+    fn app(remaining: Option<u32>) -> Router {
+        let store = Arc::new(CountingStore { remaining: Mutex::new(remaining) });
+        let limiter = SharedRateLimiter::new(store, Quota { period: Duration::from_millis(1500), burst: 1 });
+        with_shared_rate_limit(Router::new().route("/", get(|| async { "ok" })), limiter)
+    }
+
+    async fn status(app: &Router) -> Response {
+        app.clone().oneshot(axum::http::Request::get("/").body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requests_beyond_the_shared_bucket_are_rejected() {
+        let app = app(Some(1));
+
+        let allowed = status(&app).await;
+        let limited = status(&app).await;
+
+        assert_eq!(allowed.status(), StatusCode::OK);
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(limited.headers()[RETRY_AFTER], "2");
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_store_lets_requests_through() {
+        let app = app(None);
+
+        assert_eq!(status(&app).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_limiter_is_used_without_redis() {
+        let config = RelayConfig {
+            rate_limit: crate::config::RateLimitConfig { per_second: 60, burst_size: 1 },
+            ..Default::default()
+        };
+        let app = with_rate_limit(Router::new().route("/", get(|| async { "ok" })), &config);
+
+        let allowed = status(&app).await;
+        let limited = status(&app).await;
+
+        assert_eq!(allowed.status(), StatusCode::OK);
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
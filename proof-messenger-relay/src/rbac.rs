@@ -0,0 +1,156 @@
+//! Role-Based Access Control Module
+//!
+//! Enterprise identity providers such as Okta and Azure AD list the groups a
+//! user belongs to in a token claim (`groups` by default). With groups
+//! configured under `[rbac.groups]` in the relay settings (see
+//! [`crate::config::RbacConfig`]), authentication grants the members of each
+//! group its scopes on top of the token's own `scope` claim, so
+//! administrators manage permissions by group membership in their IdP rather
+//! than by minting scopes per user.
+//!
+//! Groups may be named or identified by ID, whichever the IdP puts in the
+//! claim; unmapped groups grant nothing. Role mapping is enabled by layering
+//! the resulting [`RoleMapping`] onto the router as an [`axum::Extension`].
+
+use std::collections::{HashMap, HashSet};
+
+use crate::config::RbacConfig;
+
+/// Scopes granted by identity provider groups
+#[derive(Debug, Clone)]
+pub struct RoleMapping {
+    claim: String,
+    groups: HashMap<String, Vec<String>>,
+}
+
+impl RoleMapping {
+    /// The mapping configured in the relay settings
+    pub fn new(config: &RbacConfig) -> Self {
+        Self {
+            claim: config.groups_claim.clone(),
+            groups: config.groups.iter().map(|(group, scopes)| (group.clone(), scopes.clone())).collect(),
+        }
+    }
+
+    /// Token claim listing the caller's groups
+    pub fn claim(&self) -> &str {
+        &self.claim
+    }
+
+    /// Scopes granted to a member of `groups`
+    pub fn scopes_for<'a>(&'a self, groups: &[String]) -> HashSet<&'a str> {
+        groups
+            .iter()
+            .filter_map(|group| self.groups.get(group))
+            .flatten()
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{auth_middleware::{auth_middleware, AuthContext}, jwt_validator::JwtValidator};
+    use axum::{body::Body, http::Request, middleware, routing::get, Extension, Router};
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn mapping() -> RoleMapping {
+        RoleMapping::new(&RbacConfig {
+            groups: BTreeMap::from([
+                ("proof-admins".to_string(), vec!["proof:revoke".to_string(), "proof:manage".to_string()]),
+                ("auditors".to_string(), vec!["audit:read".to_string()]),
+            ]),
+            ..RbacConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_only_mapped_groups_grant_scopes() {
+        let mapping = mapping();
+
+        let scopes = mapping.scopes_for(&["auditors".to_string(), "everyone".to_string()]);
+
+        assert_eq!(scopes, HashSet::from(["audit:read"]));
+        assert!(mapping.scopes_for(&[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_group_scopes_are_added_to_the_auth_context() {
+        // ARRANGE: A token with one scope of its own and two groups, one of them mapped
+        let validator = Arc::new(JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let claims = serde_json::json!({
+            "sub": "alice",
+            "iss": "issuer",
+            "exp": 9999999999u64,
+            "scope": "message:read",
+            "groups": ["proof-admins", "everyone"],
+        });
+        let token = jsonwebtoken::encode(&Default::default(), &claims, &jsonwebtoken::EncodingKey::from_secret(b"secret")).unwrap();
+        let app = Router::new()
+            .route(
+                "/scopes",
+                get(|auth: AuthContext| async move {
+                    let mut scopes: Vec<String> = auth.scopes.into_iter().collect();
+                    scopes.sort();
+                    scopes.join(" ")
+                }),
+            )
+            .layer(middleware::from_fn_with_state(validator, auth_middleware))
+            .layer(Extension(Arc::new(mapping())));
+
+        // ACT: Authenticate
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/scopes")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // ASSERT: The token's scope is kept and the admin group's scopes are granted
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "message:read proof:manage proof:revoke");
+    }
+
+    #[tokio::test]
+    async fn test_configured_relay_grants_group_scopes() {
+        // ARRANGE: The router the relay binary serves, with auditors allowed to read the quarantine
+        let db = Arc::new(crate::database::Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let validator = Arc::new(JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let logger = Arc::new(crate::secure_logger::SecureLogger::new(&crate::secure_logger::SecureLogger::generate_key()));
+        let mut config = crate::config::RelayConfig::default();
+        config.rbac.groups.insert("auditors".to_string(), vec!["quarantine:read".to_string()]);
+        let app = crate::create_authenticated_app_with_config(db, &config, validator, logger);
+        let token = |groups: &[&str]| {
+            let claims = serde_json::json!({
+                "sub": "alice",
+                "iss": "issuer",
+                "exp": 9999999999u64,
+                "groups": groups,
+            });
+            jsonwebtoken::encode(&Default::default(), &claims, &jsonwebtoken::EncodingKey::from_secret(b"secret")).unwrap()
+        };
+        let list = |token: String| {
+            Request::builder()
+                .uri("/v1/quarantine")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // ACT: List the quarantine as an auditor and as someone else
+        let auditor = app.clone().oneshot(list(token(&["auditors"]))).await.unwrap();
+        let other = app.oneshot(list(token(&["everyone"]))).await.unwrap();
+
+        // ASSERT: Only the mapped group is granted the scope
+        assert_eq!(auditor.status(), axum::http::StatusCode::OK);
+        assert_eq!(other.status(), axum::http::StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,393 @@
+//! Readiness Check Module
+//!
+//! This module backs the `/ready` endpoint. Besides the database, readiness
+//! covers the JWKS endpoint tokens are validated against (when OAuth is
+//! configured), the depth of the webhook delivery queue, and the liveness of
+//! the relay's background jobs. Each check runs under a timeout and reports
+//! its latency, so a slow dependency shows up before it becomes an outage.
+//! The response also reports whether the relay is in read-only maintenance
+//! mode (see [`crate::maintenance`]), which does not make it unready.
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    Extension,
+};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::instrument;
+
+use crate::database::Database;
+use crate::maintenance::MaintenanceMode;
+
+/// Extra time a background job may overrun its interval before it is considered stalled
+const STALE_GRACE: Duration = Duration::from_secs(30);
+
+/// Heartbeats of the relay's background jobs
+pub static BACKGROUND_JOBS: Lazy<JobMonitor> = Lazy::new(JobMonitor::default);
+
+/// Readiness used when none is layered onto the router
+static DEFAULT_READINESS: Lazy<Arc<Readiness>> = Lazy::new(|| Arc::new(Readiness::new(ReadinessConfig::default())));
+
+/// Readiness check settings
+#[derive(Debug, Clone)]
+pub struct ReadinessConfig {
+    /// Time each check may take before it is reported as failed
+    pub check_timeout: Duration,
+    /// JWKS endpoint to probe when OAuth is enabled
+    pub jwks_url: Option<String>,
+    /// Pending webhook deliveries above which the relay reports not ready
+    pub max_webhook_queue_depth: i64,
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        Self {
+            check_timeout: Duration::from_secs(2),
+            jwks_url: None,
+            max_webhook_queue_depth: 1000,
+        }
+    }
+}
+
+impl ReadinessConfig {
+    /// Build readiness settings from the relay configuration
+    ///
+    /// The JWKS endpoint probed is the first configured token issuer's.
+    pub fn from_config(config: &crate::config::RelayConfig) -> Self {
+        Self {
+            check_timeout: Duration::from_millis(config.readiness.check_timeout_ms),
+            jwks_url: config.oauth.issuers.first().map(|issuer| issuer.jwks_url.clone()),
+            max_webhook_queue_depth: config.readiness.max_webhook_queue,
+        }
+    }
+}
+
+/// Tracks when each background job last ran
+#[derive(Debug, Default)]
+pub struct JobMonitor {
+    jobs: Mutex<HashMap<String, (Duration, Instant)>>,
+}
+
+impl JobMonitor {
+    /// Record that a job running every `interval` has started a run
+    ///
+    /// The run is also published in the `background_job_last_run_timestamp_seconds`
+    /// and `background_job_interval_seconds` metrics.
+    pub fn heartbeat(&self, job: &str, interval: Duration) {
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.insert(job.to_string(), (interval, Instant::now()));
+        }
+        let labels = vec![("job".to_string(), job.to_string())];
+        crate::metrics::BACKGROUND_JOB_LAST_RUN_SECONDS
+            .get_or_create(&labels)
+            .set(chrono::Utc::now().timestamp());
+        crate::metrics::BACKGROUND_JOB_INTERVAL_SECONDS
+            .get_or_create(&labels)
+            .set(interval.as_secs() as i64);
+    }
+
+    /// Registered jobs with whether each is still running on schedule, sorted by name
+    pub fn status(&self) -> Vec<(String, bool)> {
+        let jobs = match self.jobs.lock() {
+            Ok(jobs) => jobs,
+            Err(_) => return Vec::new(),
+        };
+        let mut status: Vec<(String, bool)> = jobs
+            .iter()
+            .map(|(job, (interval, last_run))| (job.clone(), last_run.elapsed() <= *interval * 2 + STALE_GRACE))
+            .collect();
+        status.sort();
+        status
+    }
+}
+
+/// Outcome of one readiness check
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    /// `ok` or `error`
+    pub status: &'static str,
+    /// Time the check took in milliseconds
+    pub latency_ms: f64,
+    /// What was observed, or why the check failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl CheckResult {
+    /// Whether the check passed
+    pub fn is_ok(&self) -> bool {
+        self.status == "ok"
+    }
+}
+
+/// Outcome of all readiness checks
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    /// Whether every check passed
+    pub ready: bool,
+    /// Results by check name
+    pub checks: BTreeMap<&'static str, CheckResult>,
+}
+
+/// Runs the relay's readiness checks
+pub struct Readiness {
+    config: ReadinessConfig,
+    client: crate::egress::HttpClient,
+}
+
+impl Readiness {
+    /// Create a readiness checker with the given settings
+    pub fn new(config: ReadinessConfig) -> Self {
+        Self {
+            config,
+            client: crate::egress::HttpClient::default(),
+        }
+    }
+
+    /// Run every applicable check concurrently
+    pub async fn check(&self, db: &Database, jobs: &JobMonitor) -> ReadinessReport {
+        let (database, webhook_queue) = tokio::join!(
+            self.timed(async { db.health_check().await.map(|_| None).map_err(|e| e.to_string()) }),
+            self.timed(self.check_webhook_queue(db)),
+        );
+
+        let mut checks = BTreeMap::new();
+        checks.insert("database", database);
+        checks.insert("webhook_queue", webhook_queue);
+        if let Some(jwks_url) = &self.config.jwks_url {
+            checks.insert("jwks", self.timed(self.check_jwks(jwks_url)).await);
+        }
+        checks.insert("background_jobs", self.timed(async { check_jobs(jobs) }).await);
+
+        ReadinessReport {
+            ready: checks.values().all(CheckResult::is_ok),
+            checks,
+        }
+    }
+
+    /// Run a check under the configured timeout and measure its latency
+    async fn timed<F>(&self, check: F) -> CheckResult
+    where
+        F: Future<Output = Result<Option<String>, String>>,
+    {
+        let started = Instant::now();
+        let outcome = tokio::time::timeout(self.config.check_timeout, check).await;
+        let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        match outcome {
+            Ok(Ok(detail)) => CheckResult { status: "ok", latency_ms, detail },
+            Ok(Err(error)) => CheckResult { status: "error", latency_ms, detail: Some(error) },
+            Err(_) => CheckResult {
+                status: "error",
+                latency_ms,
+                detail: Some(format!("timed out after {}ms", self.config.check_timeout.as_millis())),
+            },
+        }
+    }
+
+    /// Check the JWKS endpoint responds with a key set
+    async fn check_jwks(&self, jwks_url: &str) -> Result<Option<String>, String> {
+        let jwks: serde_json::Value = self
+            .client
+            .get(jwks_url)
+            .map_err(|e| e.to_string())?
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| format!("invalid JWKS: {}", e))?;
+
+        match jwks["keys"].as_array() {
+            Some(keys) if !keys.is_empty() => Ok(Some(format!("{} keys", keys.len()))),
+            _ => Err("JWKS contains no keys".to_string()),
+        }
+    }
+
+    /// Check the webhook delivery backlog is under the limit
+    async fn check_webhook_queue(&self, db: &Database) -> Result<Option<String>, String> {
+        let depth = db.count_pending_webhook_deliveries().await.map_err(|e| e.to_string())?;
+        let detail = format!("{} pending deliveries", depth);
+        if depth > self.config.max_webhook_queue_depth {
+            return Err(format!("{} (limit {})", detail, self.config.max_webhook_queue_depth));
+        }
+        Ok(Some(detail))
+    }
+}
+
+/// Check every registered background job has run recently
+fn check_jobs(jobs: &JobMonitor) -> Result<Option<String>, String> {
+    let status = jobs.status();
+    let stalled: Vec<&str> = status
+        .iter()
+        .filter(|(_, alive)| !alive)
+        .map(|(job, _)| job.as_str())
+        .collect();
+    if !stalled.is_empty() {
+        return Err(format!("stalled: {}", stalled.join(", ")));
+    }
+    let running: Vec<&str> = status.iter().map(|(job, _)| job.as_str()).collect();
+    Ok(Some(if running.is_empty() {
+        "no background jobs".to_string()
+    } else {
+        format!("running: {}", running.join(", "))
+    }))
+}
+
+/// `read_only` or `read_write`
+///
+/// A read-only relay still serves reads, so it stays ready for traffic.
+fn maintenance_mode(maintenance: Option<Extension<Arc<MaintenanceMode>>>) -> &'static str {
+    match maintenance {
+        Some(Extension(mode)) if mode.is_read_only() => "read_only",
+        _ => "read_write",
+    }
+}
+
+/// Readiness check endpoint
+#[utoipa::path(
+    get,
+    path = "/ready",
+    operation_id = "ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "The relay is ready to serve traffic", body = Object),
+        (status = 503, description = "A readiness check failed", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn ready_handler(
+    State(db): State<Arc<Database>>,
+    readiness: Option<Extension<Arc<Readiness>>>,
+    maintenance: Option<Extension<Arc<MaintenanceMode>>>,
+) -> impl IntoResponse {
+    let readiness = readiness.map(|Extension(readiness)| readiness).unwrap_or_else(|| DEFAULT_READINESS.clone());
+    let report = readiness.check(&db, &BACKGROUND_JOBS).await;
+    let status_code = if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    let ready_response = Json(serde_json::json!({
+        "status": if report.ready { "ready" } else { "not_ready" },
+        "service": "proof-messenger-relay",
+        "mode": maintenance_mode(maintenance),
+        "checks": report.checks
+    }));
+
+    (status_code, ready_response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn setup_db() -> Database {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_all_checks_pass_with_reachable_jwks() {
+        // ARRANGE: A JWKS endpoint with one key
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/.well-known/jwks.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "keys": [{ "kid": "1" }] })))
+            .mount(&server)
+            .await;
+        let db = setup_db().await;
+        let readiness = Readiness::new(ReadinessConfig {
+            jwks_url: Some(format!("{}/.well-known/jwks.json", server.uri())),
+            ..Default::default()
+        });
+        let jobs = JobMonitor::default();
+        jobs.heartbeat("webhook_delivery", Duration::from_secs(10));
+
+        // ACT: Run the checks
+        let report = readiness.check(&db, &jobs).await;
+
+        // ASSERT: Every check passes and reports its latency
+        assert!(report.ready);
+        assert_eq!(report.checks.len(), 4);
+        assert_eq!(report.checks["jwks"].detail.as_deref(), Some("1 keys"));
+        assert!(report.checks.values().all(|check| check.latency_ms >= 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_slow_jwks_times_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+        let db = setup_db().await;
+        let readiness = Readiness::new(ReadinessConfig {
+            check_timeout: Duration::from_millis(50),
+            jwks_url: Some(server.uri()),
+            ..Default::default()
+        });
+
+        let report = readiness.check(&db, &JobMonitor::default()).await;
+
+        assert!(!report.ready);
+        assert!(report.checks["jwks"].detail.as_deref().unwrap().starts_with("timed out"));
+        assert!(report.checks["database"].is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_backlog_fails_readiness() {
+        let db = setup_db().await;
+        db.create_webhook("https://example.com/hook", None, "secret", None).await.unwrap();
+        let message_id = db
+            .store_message(crate::database::StoredMessage::from(crate::Message {
+                sender: "aa".repeat(32),
+                context: "bb".to_string(),
+                body: "hello".to_string(),
+                proof: "cc".repeat(64),
+                pqc: None,
+                thread_id: None,
+                reply_to: None,
+                expires_at: None,
+                recipient: None,
+            }))
+            .await
+            .unwrap();
+        db.enqueue_webhook_deliveries(&message_id, "default", "{}", chrono::Utc::now()).await.unwrap();
+        let readiness = Readiness::new(ReadinessConfig {
+            max_webhook_queue_depth: 0,
+            ..Default::default()
+        });
+
+        let report = readiness.check(&db, &JobMonitor::default()).await;
+
+        assert!(!report.ready);
+        assert!(!report.checks["webhook_queue"].is_ok());
+    }
+
+    #[test]
+    fn test_stalled_job_is_reported() {
+        let jobs = JobMonitor::default();
+        jobs.heartbeat("reconciliation", Duration::from_secs(60));
+        jobs.jobs.lock().unwrap().get_mut("reconciliation").unwrap().1 -= Duration::from_secs(600);
+
+        assert_eq!(check_jobs(&jobs), Err("stalled: reconciliation".to_string()));
+    }
+
+    #[test]
+    fn test_heartbeat_publishes_last_run_metrics() {
+        let before = chrono::Utc::now().timestamp();
+        JobMonitor::default().heartbeat("metrics_probe", Duration::from_secs(90));
+
+        let labels = vec![("job".to_string(), "metrics_probe".to_string())];
+        assert!(crate::metrics::BACKGROUND_JOB_LAST_RUN_SECONDS.get_or_create(&labels).get() >= before);
+        assert_eq!(crate::metrics::BACKGROUND_JOB_INTERVAL_SECONDS.get_or_create(&labels).get(), 90);
+    }
+}
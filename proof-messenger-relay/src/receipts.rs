@@ -0,0 +1,333 @@
+//! Delivery Receipt Module
+//!
+//! This module lets recipients acknowledge delivered messages with a signed
+//! receipt over the message id and content hash, and exposes the receipts
+//! collected for each message.
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use ed25519_dalek::{PublicKey, Signature};
+use proof_messenger_protocol::receipt::{message_hash, message_hash_from_slice, verify_receipt, Receipt, MESSAGE_HASH_LENGTH};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::sync::Arc;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, StoredMessage, StoredReceipt},
+    get_tenant_message,
+    request_id::RequestId,
+    tenancy::TenantScope,
+    AppError,
+};
+
+/// Request body for submitting a receipt
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SubmitReceiptRequest {
+    /// Public key of the recipient (hex encoded)
+    pub recipient: String,
+    /// Hash of the acknowledged message contents (hex encoded)
+    pub message_hash: String,
+    /// Signature over the receipt context (hex encoded)
+    pub signature: String,
+}
+
+/// Create router for receipt endpoints
+pub fn receipt_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/message/:message_id/receipts", get(get_receipts_handler).post(submit_receipt_handler))
+}
+
+/// Create router for authenticated receipt endpoints
+pub fn authenticated_receipt_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route(
+            "/message/:message_id/receipts",
+            get(authenticated_get_receipts_handler).post(authenticated_submit_receipt_handler),
+        )
+}
+
+/// Hash of a stored message's contents, as recipients sign it
+pub fn stored_message_hash(message: &StoredMessage) -> Result<[u8; MESSAGE_HASH_LENGTH], AppError> {
+    let sender = hex::decode(&message.sender)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid hex encoding: {}", e)))?;
+    let context = hex::decode(&message.context)
+        .map_err(|e| AppError::InvalidContext(format!("Invalid hex encoding: {}", e)))?;
+    Ok(message_hash(&sender, &context, message.body.as_bytes()))
+}
+
+/// Verify a receipt against the stored message and persist it
+pub async fn submit_receipt(
+    db: &Database,
+    tenant: &TenantScope,
+    message_id: &str,
+    request: &SubmitReceiptRequest,
+) -> Result<StoredReceipt, AppError> {
+    let message = get_tenant_message(db, tenant, message_id).await?;
+    let expected_hash = stored_message_hash(&message)?;
+
+    let recipient_bytes = hex::decode(&request.recipient)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid hex encoding: {}", e)))?;
+    let recipient = PublicKey::from_bytes(&recipient_bytes)
+        .map_err(|e| AppError::InvalidPublicKey(format!("Invalid public key: {}", e)))?;
+    let hash_bytes = hex::decode(&request.message_hash)
+        .map_err(|e| AppError::InvalidContext(format!("Invalid hex encoding: {}", e)))?;
+    let claimed_hash = message_hash_from_slice(&hash_bytes)
+        .map_err(|e| AppError::InvalidContext(e.to_string()))?;
+    let signature_bytes = hex::decode(&request.signature)
+        .map_err(|e| AppError::InvalidSignature(format!("Invalid hex encoding: {}", e)))?;
+    let signature = Signature::from_bytes(&signature_bytes)
+        .map_err(|e| AppError::InvalidSignature(format!("Invalid signature: {}", e)))?;
+
+    let receipt = Receipt {
+        message_id: message_id.to_string(),
+        message_hash: claimed_hash,
+        recipient,
+        signature,
+    };
+    verify_receipt(&receipt, message_id, &expected_hash)
+        .map_err(|_| AppError::VerificationFailed)?;
+
+    Ok(db
+        .store_receipt(message_id, &request.recipient, &request.message_hash, &request.signature)
+        .await?)
+}
+
+/// Handler to submit a receipt for a message
+#[utoipa::path(
+    post,
+    path = "/message/{message_id}/receipts",
+    operation_id = "submitReceipt",
+    tag = "receipts",
+    params(("message_id" = String, Path, description = "Message ID")),
+    request_body = SubmitReceiptRequest,
+    responses(
+        (status = 201, description = "Receipt verified and stored", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn submit_receipt_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+    Json(payload): Json<SubmitReceiptRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Submitting receipt for message: {}", message_id);
+
+    let receipt = submit_receipt(&db, &tenant, &message_id, &payload).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "receipt": receipt
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Handler to list the receipts for a message
+#[utoipa::path(
+    get,
+    path = "/message/{message_id}/receipts",
+    operation_id = "listReceipts",
+    tag = "receipts",
+    params(("message_id" = String, Path, description = "Message ID")),
+    responses(
+        (status = 200, description = "The message's receipts", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_receipts_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Retrieving receipts for message: {}", message_id);
+
+    get_tenant_message(&db, &tenant, &message_id).await?;
+    let receipts = db.get_receipts_for_message(&message_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message_id": message_id,
+        "count": receipts.len(),
+        "receipts": receipts
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to submit a receipt for a message
+#[instrument(skip_all)]
+async fn authenticated_submit_receipt_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    request_id: RequestId,
+    Path(message_id): Path<String>,
+    Json(payload): Json<SubmitReceiptRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} submitting receipt for message: {}", auth.user_id, message_id);
+
+    let receipt = submit_receipt(&db, &tenant, &message_id, &payload).await?;
+
+    // Log the receipt
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("message_id".to_string(), message_id.clone());
+    metadata.insert("recipient".to_string(), receipt.recipient.clone());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Receipt submitted".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log receipt submission: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "receipt": receipt,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Authenticated handler to list the receipts for a message
+#[instrument(skip_all)]
+async fn authenticated_get_receipts_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} retrieving receipts for message: {}", auth.user_id, message_id);
+
+    get_tenant_message(&db, &tenant, &message_id).await?;
+    let receipts = db.get_receipts_for_message(&message_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message_id": message_id,
+        "count": receipts.len(),
+        "receipts": receipts,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use ed25519_dalek::Signer;
+    use hyper::Method;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_protocol::receipt::make_receipt;
+    use tower::ServiceExt;
+
+    async fn setup_test_app() -> (Router, Arc<Database>, StoredMessage) {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+
+        let sender = generate_secure_keypair_with_seed(1);
+        let context = b"receipt test context";
+        let message = crate::Message {
+            sender: hex::encode(sender.public_key_bytes()),
+            context: hex::encode(context),
+            body: "hello".to_string(),
+            proof: hex::encode(sender.as_keypair().sign(context).to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        };
+        let message_id = db.store_message(StoredMessage::from(message)).await.unwrap();
+        let stored = db.get_message_by_id(&message_id).await.unwrap();
+
+        let app = Router::new().merge(receipt_routes()).with_state(db.clone());
+        (app, db, stored)
+    }
+
+    fn receipt_request(seed: u64, message_id: &str, hash: &[u8; MESSAGE_HASH_LENGTH]) -> SubmitReceiptRequest {
+        let receipt = make_receipt(&generate_secure_keypair_with_seed(seed), message_id, hash).unwrap();
+        SubmitReceiptRequest {
+            recipient: hex::encode(receipt.recipient.to_bytes()),
+            message_hash: hex::encode(receipt.message_hash),
+            signature: hex::encode(receipt.signature.to_bytes()),
+        }
+    }
+
+    async fn post_receipt(app: &Router, message_id: &str, request: &SubmitReceiptRequest) -> StatusCode {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(format!("/message/{}/receipts", message_id))
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(serde_json::to_string(request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_list_receipts() {
+        // ARRANGE: A stored message and a valid receipt for it
+        let (app, _, message) = setup_test_app().await;
+        let hash = stored_message_hash(&message).unwrap();
+
+        // ACT: Submit the receipt and list receipts for the message
+        let status = post_receipt(&app, &message.id, &receipt_request(2, &message.id, &hash)).await;
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/message/{}/receipts", message.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // ASSERT: The receipt is stored and exposed
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["count"], 1);
+        assert_eq!(json["receipts"][0]["message_hash"], hex::encode(hash));
+    }
+
+    #[tokio::test]
+    async fn test_receipt_for_tampered_hash_is_rejected() {
+        let (app, db, message) = setup_test_app().await;
+        let wrong_hash = message_hash(b"other", b"message", b"contents");
+
+        let status = post_receipt(&app, &message.id, &receipt_request(2, &message.id, &wrong_hash)).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(db.get_receipts_for_message(&message.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_receipt_for_other_message_is_rejected() {
+        let (app, _, message) = setup_test_app().await;
+        let hash = stored_message_hash(&message).unwrap();
+
+        let status = post_receipt(&app, &message.id, &receipt_request(2, "other-message", &hash)).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
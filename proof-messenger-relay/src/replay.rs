@@ -0,0 +1,233 @@
+//! Replay Protection Module
+//!
+//! A captured message is still correctly signed, so verification alone
+//! cannot stop it being relayed again. With `features.replay_protection`
+//! enabled the relay remembers each verified message's sender and signed
+//! context for `retention.replay_window_secs`, and rejects a message whose
+//! sender already relayed the same context within that window with
+//! `409 REPLAY_DETECTED`. Clients that legitimately repeat an action should
+//! sign a fresh context for it, e.g. by including a nonce or timestamp.
+//!
+//! The remembered contexts live in Redis when `[redis]` is configured, so a
+//! replay sent to a different replica is rejected too, and in memory
+//! otherwise. If Redis cannot be reached messages are refused with
+//! `503 SHARED_STATE_UNAVAILABLE` rather than relayed unchecked.
+
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::RelayConfig;
+use crate::shared_state::{MemoryNonceStore, NonceStore, SharedStateError};
+use crate::{metrics, AppError, Message};
+
+/// Remembers relayed contexts and rejects replays of them
+pub struct ReplayGuard {
+    store: Arc<dyn NonceStore>,
+    window: Duration,
+}
+
+/// A claimed nonce, released again if its message is not stored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nonce(String);
+
+impl ReplayGuard {
+    /// A guard remembering contexts in `store` for `window`
+    pub fn new(store: Arc<dyn NonceStore>, window: Duration) -> Self {
+        Self { store, window }
+    }
+
+    /// The guard a relay configuration describes, or `None` when replay protection is off
+    ///
+    /// Contexts are remembered in Redis when `redis.url` is set and in this
+    /// relay's memory otherwise.
+    pub fn from_config(config: &RelayConfig) -> Result<Option<Self>, SharedStateError> {
+        if !config.features.replay_protection {
+            return Ok(None);
+        }
+        let window = Duration::from_secs(config.retention.replay_window_secs);
+        let store: Arc<dyn NonceStore> = match &config.redis.url {
+            #[cfg(feature = "redis")]
+            Some(_) => Arc::new(crate::shared_state::redis::RedisStore::new(&config.redis)?),
+            #[cfg(not(feature = "redis"))]
+            Some(_) => {
+                return Err(SharedStateError::Config(
+                    "redis.url requires a relay built with the `redis` feature".to_string(),
+                ))
+            }
+            None => Arc::new(MemoryNonceStore::new()),
+        };
+        Ok(Some(Self::new(store, window)))
+    }
+
+    /// Nonce identifying a message's sender and signed context
+    pub fn nonce(message: &Message) -> Nonce {
+        // Verified messages are valid hex; decoding means case changes are still replays
+        let decode = |field: &str| hex::decode(field).unwrap_or_else(|_| field.as_bytes().to_vec());
+        let sender = decode(&message.sender);
+        let context = decode(&message.context);
+
+        let mut hasher = Sha256::new();
+        hasher.update((sender.len() as u64).to_be_bytes());
+        hasher.update(&sender);
+        hasher.update(&context);
+        Nonce(hex::encode(hasher.finalize()))
+    }
+
+    /// Claim a message's nonce, failing if it was claimed within the window
+    pub async fn claim(&self, message: &Message) -> Result<Nonce, AppError> {
+        let nonce = Self::nonce(message);
+        if self.store.insert(&nonce.0, self.window).await? {
+            Ok(nonce)
+        } else {
+            metrics::REPLAYS_REJECTED_TOTAL.inc();
+            Err(AppError::ReplayDetected)
+        }
+    }
+
+    /// Release a claimed nonce so its message can be sent again
+    pub async fn release(&self, nonce: &Nonce) {
+        if let Err(e) = self.store.remove(&nonce.0).await {
+            warn!("Failed to release replay nonce: {}", e);
+        }
+    }
+}
+
+/// Claim a message's nonce if replay protection is enabled
+pub async fn claim_if_enabled(guard: Option<&Arc<ReplayGuard>>, message: &Message) -> Result<Option<Nonce>, AppError> {
+    match guard {
+        Some(guard) => guard.claim(message).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Release a nonce claimed by [`claim_if_enabled`]
+pub async fn release_if_enabled(guard: Option<&Arc<ReplayGuard>>, nonce: Option<Nonce>) {
+    if let (Some(guard), Some(nonce)) = (guard, nonce) {
+        guard.release(&nonce).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    fn message(sender: &str, context: &str) -> Message {
+        Message {
+            sender: sender.to_string(),
+            context: context.to_string(),
+            body: "hello".to_string(),
+            proof: "cc".repeat(64),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    fn guard() -> ReplayGuard {
+        ReplayGuard::new(Arc::new(MemoryNonceStore::new()), Duration::from_secs(60))
+    }
+
+    /// A store whose server cannot be reached
+    struct UnavailableStore;
+
+    #[async_trait]
+    impl NonceStore for UnavailableStore {
+        async fn insert(&self, _nonce: &str, _ttl: Duration) -> Result<bool, SharedStateError> {
+            Err(SharedStateError::Unavailable("connection refused".to_string()))
+        }
+
+        async fn remove(&self, _nonce: &str) -> Result<(), SharedStateError> {
+            Err(SharedStateError::Unavailable("connection refused".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replayed_context_is_rejected() {
+        let guard = guard();
+
+        let first = guard.claim(&message("aa", "0102")).await;
+        let replay = guard.claim(&message("AA", "0102")).await;
+        let other_sender = guard.claim(&message("bb", "0102")).await;
+        let other_context = guard.claim(&message("aa", "0103")).await;
+
+        assert!(first.is_ok());
+        assert!(matches!(replay, Err(AppError::ReplayDetected)));
+        assert!(other_sender.is_ok());
+        assert!(other_context.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_released_nonce_can_be_claimed_again() {
+        let guard = Arc::new(guard());
+        let message = message("aa", "0102");
+
+        let nonce = claim_if_enabled(Some(&guard), &message).await.unwrap();
+        release_if_enabled(Some(&guard), nonce).await;
+
+        assert!(claim_if_enabled(Some(&guard), &message).await.unwrap().is_some());
+        assert_eq!(claim_if_enabled(None, &message).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_store_refuses_messages() {
+        let guard = ReplayGuard::new(Arc::new(UnavailableStore), Duration::from_secs(60));
+
+        let error = guard.claim(&message("aa", "0102")).await.unwrap_err();
+
+        assert_eq!(error.status(), axum::http::StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_relay_rejects_a_replayed_message() {
+        // ARRANGE: A relay with replay protection and a signed message
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+        use proof_messenger_protocol::proof::make_secure_proof;
+        use tower::ServiceExt;
+
+        let db = Arc::new(crate::database::Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let app = crate::create_app(db).layer(axum::Extension(Arc::new(guard())));
+        let keypair = generate_secure_keypair_with_seed(44);
+        let proof = make_secure_proof(&keypair, b"transfer:100").unwrap();
+        let body = serde_json::json!({
+            "sender": hex::encode(keypair.public_key_bytes()),
+            "context": hex::encode(b"transfer:100"),
+            "body": "hello",
+            "proof": hex::encode(proof.to_bytes()),
+        })
+        .to_string();
+        let request = || {
+            Request::post("/relay")
+                .header("content-type", "application/json")
+                .body(Body::from(body.clone()))
+                .unwrap()
+        };
+
+        // ACT: Relay it twice
+        let first = app.clone().oneshot(request()).await.unwrap();
+        let replay = app.oneshot(request()).await.unwrap();
+
+        // ASSERT: Only the first is accepted
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(replay.status(), StatusCode::CONFLICT);
+        let body = axum::body::to_bytes(replay.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "REPLAY_DETECTED");
+    }
+
+    #[test]
+    fn test_guard_follows_the_feature_toggle() {
+        let mut config = RelayConfig::default();
+        assert!(ReplayGuard::from_config(&config).unwrap().is_none());
+
+        config.features.replay_protection = true;
+        assert!(ReplayGuard::from_config(&config).unwrap().is_some());
+    }
+}
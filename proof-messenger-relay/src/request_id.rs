@@ -0,0 +1,151 @@
+//! Request ID Module
+//!
+//! Every request handled by the relay gets an ID. A usable `X-Request-Id`
+//! sent by the client (or a proxy in front of the relay) is kept; otherwise
+//! a UUID is generated. The ID is stored in the request extensions as a
+//! [`RequestId`], recorded on the request's tracing span, returned in the
+//! `X-Request-Id` response header and error bodies, written to audit logs,
+//! and forwarded to peer relays, so a single request can be followed across
+//! logs and services.
+
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{request::Parts, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::convert::Infallible;
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Header carrying the request ID
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied request ID that is kept
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static CURRENT: RequestId;
+}
+
+/// ID of a request, available to handlers as an extractor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for RequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    /// The ID assigned by [`propagate_request_id`], or a fresh one on routers without it
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_else(|| RequestId(Uuid::new_v4().to_string())))
+    }
+}
+
+/// ID of the request currently being handled, when called inside [`propagate_request_id`]
+pub fn current() -> Option<String> {
+    CURRENT.try_with(|id| id.0.clone()).ok()
+}
+
+/// Read a usable request ID from the request headers
+///
+/// IDs that are too long or contain characters other than visible ASCII are ignored.
+pub fn from_headers(headers: &HeaderMap) -> Option<String> {
+    let id = headers.get(REQUEST_ID_HEADER)?.to_str().ok()?;
+    let valid = !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic());
+    valid.then(|| id.to_string())
+}
+
+/// Middleware assigning every request an ID and propagating it
+pub async fn propagate_request_id(mut request: Request, next: Next) -> Response {
+    let id = RequestId(from_headers(request.headers()).unwrap_or_else(|| Uuid::new_v4().to_string()));
+    let header = HeaderValue::from_str(&id.0).expect("request IDs are visible ASCII");
+    request.headers_mut().insert(REQUEST_ID_HEADER, header.clone());
+    request.extensions_mut().insert(id.clone());
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %crate::log_redaction::current().redact_path(request.uri().path())
+    );
+    let mut response = CURRENT.scope(id, next.run(request)).instrument(span).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|RequestId(id): RequestId| async move { format!("{}|{}", id, current().unwrap_or_default()) }),
+            )
+            .layer(axum::middleware::from_fn(propagate_request_id))
+    }
+
+    async fn body_text(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_client_request_id_is_propagated() {
+        // ARRANGE: A request carrying its own ID
+        let request = axum::http::Request::builder()
+            .uri("/")
+            .header(REQUEST_ID_HEADER, "client-42")
+            .body(Body::empty())
+            .unwrap();
+
+        // ACT: Handle it
+        let response = app().oneshot(request).await.unwrap();
+
+        // ASSERT: The handler, the task-local and the response header all see the same ID
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "client-42");
+        assert_eq!(body_text(response).await, "client-42|client-42");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_generated_when_missing_or_unusable() {
+        let request = axum::http::Request::builder()
+            .uri("/")
+            .header(REQUEST_ID_HEADER, "has space")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app().oneshot(request).await.unwrap();
+
+        let id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+        assert!(Uuid::parse_str(&id).is_ok());
+        assert_eq!(body_text(response).await, format!("{}|{}", id, id));
+    }
+
+    #[test]
+    fn test_unusable_request_ids_are_ignored() {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, "x".repeat(MAX_REQUEST_ID_LEN + 1).parse().unwrap());
+        assert_eq!(from_headers(&headers), None);
+
+        headers.insert(REQUEST_ID_HEADER, "abc-123".parse().unwrap());
+        assert_eq!(from_headers(&headers).as_deref(), Some("abc-123"));
+    }
+}
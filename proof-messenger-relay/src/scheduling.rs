@@ -0,0 +1,830 @@
+//! Scheduled Message Module
+//!
+//! Clients may hand the relay a signed message to relay later, for example
+//! an approval that only takes effect at a given time. The release time is
+//! part of what the sender signed: the message's context must be a JSON
+//! object whose `scheduled_at` field names it, as an RFC 3339 time or unix
+//! seconds, so the relay cannot release a message earlier or later than the
+//! sender agreed to without the proof showing it.
+//!
+//! - `POST /scheduled` checks the message's size and proof and stores it
+//!   until `scheduled_at`, which must be in the future and at most
+//!   `scheduling.max_delay_secs` away
+//! - every `scheduling.poll_interval_secs` the scheduler releases due
+//!   messages through the same pipeline as `POST /relay`, so revocation,
+//!   replay protection, context policies, schemas and delivery all apply at
+//!   release time; a message the pipeline refuses is marked `failed` with
+//!   the reason
+//! - `DELETE /scheduled/:scheduled_id` cancels a message that has not been
+//!   released yet
+//!
+//! Scheduled messages belong to the tenant that scheduled them and are
+//! released for that tenant. No releases happen while the relay is
+//! read-only; due messages are released once it is writable again. A
+//! message the scheduler was releasing when the relay stopped stays
+//! `releasing` rather than risk being relayed twice.
+//!
+//! Scheduling is enabled by the `scheduling.enabled` relay setting or
+//! `SCHEDULED_MESSAGES_ENABLED=true` (see [`crate::config::RelayConfig`])
+//! and layering the resulting [`Scheduler`] onto the router as an
+//! [`axum::Extension`].
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Extension, Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::abuse::AbuseDetector;
+use crate::auth_middleware::AuthContext;
+use crate::config::SchedulingConfig;
+use crate::context_policy::ContextPolicy;
+use crate::database::{Database, DatabaseError, StoredScheduledMessage};
+use crate::event_stream::EventStream;
+use crate::federation::Federation;
+use crate::limits::RequestLimits;
+use crate::maintenance::MaintenanceMode;
+use crate::metrics::SCHEDULED_RELEASES_TOTAL;
+use crate::quarantine::Quarantine;
+use crate::replay::ReplayGuard;
+use crate::request_id::RequestId;
+use crate::secure_logger::SecureLogger;
+use crate::subscriptions::Subscriptions;
+use crate::tenancy::{Tenancy, TenantScope};
+use crate::timestamping::TimestampAuthority;
+use crate::webhooks::WebhookDispatcher;
+use crate::{wire, AppError, Message};
+
+/// Context field naming when a scheduled message is released
+pub const SCHEDULED_AT_FIELD: &str = "scheduled_at";
+
+/// Status of a message waiting for its release time
+pub const STATUS_SCHEDULED: &str = "scheduled";
+
+/// Status of a message the scheduler is relaying
+pub const STATUS_RELEASING: &str = "releasing";
+
+/// Status of a message that was relayed
+pub const STATUS_RELEASED: &str = "released";
+
+/// Status of a message the relay refused at release time
+pub const STATUS_FAILED: &str = "failed";
+
+/// Status of a message cancelled before its release
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+const STATUSES: [&str; 5] = [STATUS_SCHEDULED, STATUS_RELEASING, STATUS_RELEASED, STATUS_FAILED, STATUS_CANCELLED];
+
+/// Default number of scheduled messages returned when listing
+const DEFAULT_LIST_LIMIT: i64 = 50;
+
+/// Maximum number of scheduled messages returned when listing
+const MAX_LIST_LIMIT: i64 = 500;
+
+/// Errors raised when scheduling messages
+#[derive(Debug, Error)]
+pub enum SchedulingError {
+    #[error("Scheduled messages are not enabled on this relay")]
+    Disabled,
+
+    #[error("Scheduled message not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid schedule: {0}")]
+    InvalidSchedule(String),
+
+    #[error("Scheduled message is no longer waiting for release: {0}")]
+    NotScheduled(String),
+}
+
+/// Query parameters for listing scheduled messages
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ScheduledQuery {
+    /// Only messages with this status (scheduled, releasing, released, failed or cancelled)
+    pub status: Option<String>,
+    /// Only messages from this sender (hex encoded public key)
+    pub sender: Option<String>,
+    /// Maximum number of messages to return (default 50, at most 500)
+    pub limit: Option<i64>,
+}
+
+impl ScheduledQuery {
+    /// Validate the query and return the effective limit
+    fn limit(&self) -> Result<i64, AppError> {
+        if let Some(status) = &self.status {
+            if !STATUSES.contains(&status.as_str()) {
+                return Err(AppError::InvalidQuery(format!("status must be one of {}", STATUSES.join(", "))));
+            }
+        }
+        let limit = self.limit.unwrap_or(DEFAULT_LIST_LIMIT);
+        if !(1..=MAX_LIST_LIMIT).contains(&limit) {
+            return Err(AppError::InvalidQuery(format!("limit must be between 1 and {}", MAX_LIST_LIMIT)));
+        }
+        Ok(limit)
+    }
+}
+
+/// A scheduled message and what became of it
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledMessage {
+    #[serde(flatten)]
+    pub scheduled: StoredScheduledMessage,
+    /// The message as submitted
+    pub message: Message,
+}
+
+impl TryFrom<StoredScheduledMessage> for ScheduledMessage {
+    type Error = AppError;
+
+    fn try_from(scheduled: StoredScheduledMessage) -> Result<Self, AppError> {
+        let message = serde_json::from_str(&scheduled.payload)
+            .map_err(|e| AppError::ProcessingError(format!("Invalid stored scheduled message: {}", e)))?;
+        Ok(Self { scheduled, message })
+    }
+}
+
+/// Release time named by a message's signed context
+pub fn scheduled_time(message: &Message) -> Result<DateTime<Utc>, AppError> {
+    let invalid = |reason: String| AppError::from(SchedulingError::InvalidSchedule(reason));
+    let context = hex::decode(&message.context).map_err(|e| AppError::InvalidContext(format!("Invalid hex encoding: {}", e)))?;
+    let Ok(Value::Object(fields)) = serde_json::from_slice::<Value>(&context) else {
+        return Err(invalid("context must be a JSON object".to_string()));
+    };
+    match fields.get(SCHEDULED_AT_FIELD) {
+        Some(Value::String(time)) => DateTime::parse_from_rfc3339(time)
+            .map(|time| time.with_timezone(&Utc))
+            .map_err(|e| invalid(format!("{} is not an RFC 3339 time: {}", SCHEDULED_AT_FIELD, e))),
+        Some(Value::Number(seconds)) => seconds
+            .as_i64()
+            .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+            .ok_or_else(|| invalid(format!("{} is not a valid unix time", SCHEDULED_AT_FIELD))),
+        Some(_) => Err(invalid(format!("{} must be an RFC 3339 time or unix seconds", SCHEDULED_AT_FIELD))),
+        None => Err(invalid(format!("context has no {} field", SCHEDULED_AT_FIELD))),
+    }
+}
+
+/// Optional stages of the relay pipeline that scheduled messages pass through
+///
+/// Mirrors the extensions `POST /relay` reads from the router; stages left
+/// unset are skipped, as they are when the extension is missing.
+#[derive(Clone, Default)]
+pub struct ReleasePipeline {
+    pub federation: Option<Arc<Federation>>,
+    pub webhooks: Option<Arc<WebhookDispatcher>>,
+    pub events: Option<Arc<EventStream>>,
+    pub replay: Option<Arc<ReplayGuard>>,
+    pub subscriptions: Option<Arc<Subscriptions>>,
+    pub quarantine: Option<Arc<Quarantine>>,
+    pub abuse: Option<Arc<AbuseDetector>>,
+    pub limits: Option<Arc<RequestLimits>>,
+    pub context_policy: Option<Arc<ContextPolicy>>,
+    pub timestamping: Option<Arc<TimestampAuthority>>,
+    pub tenancy: Option<Arc<Tenancy>>,
+    pub maintenance: Option<Arc<MaintenanceMode>>,
+}
+
+/// Stores scheduled messages and releases them once due
+pub struct Scheduler {
+    config: SchedulingConfig,
+    pipeline: ReleasePipeline,
+    /// Held while releasing, so releases never overlap
+    releasing: tokio::sync::Mutex<()>,
+}
+
+impl Scheduler {
+    /// Scheduler releasing messages through the bare relay pipeline
+    pub fn new(config: &SchedulingConfig) -> Self {
+        Self {
+            config: config.clone(),
+            pipeline: ReleasePipeline::default(),
+            releasing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Release messages through these pipeline stages
+    pub fn with_pipeline(mut self, pipeline: ReleasePipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Check a signed message and store it until the time its context names
+    pub async fn schedule(
+        &self,
+        db: &Arc<Database>,
+        message: Message,
+        tenant: &TenantScope,
+        created_by: Option<&str>,
+    ) -> Result<ScheduledMessage, AppError> {
+        crate::limits::validate_message(self.pipeline.limits.as_ref(), &message)?;
+        let release_at = scheduled_time(&message)?;
+        let now = Utc::now();
+        if release_at <= now {
+            return Err(SchedulingError::InvalidSchedule(format!("{} {} is in the past", SCHEDULED_AT_FIELD, release_at.to_rfc3339())).into());
+        }
+        if release_at > now + Duration::seconds(self.config.max_delay_secs as i64) {
+            return Err(SchedulingError::InvalidSchedule(format!(
+                "{} must be at most {} seconds ahead",
+                SCHEDULED_AT_FIELD, self.config.max_delay_secs
+            ))
+            .into());
+        }
+        if message.expires_at.is_some_and(|expires_at| expires_at <= release_at) {
+            return Err(AppError::InvalidExpiry(format!("expires_at must be after {}", SCHEDULED_AT_FIELD)));
+        }
+
+        // Refuse messages that could never be released; the full pipeline runs at release time
+        crate::process_and_verify_message(&message, Some(db)).await?;
+
+        let scheduled = StoredScheduledMessage {
+            id: Uuid::new_v4().to_string(),
+            tenant: tenant.tenant().map(str::to_string),
+            sender: message.sender.clone(),
+            payload: serde_json::to_string(&message)
+                .map_err(|e| AppError::ProcessingError(format!("Failed to encode scheduled message: {}", e)))?,
+            release_at,
+            status: STATUS_SCHEDULED.to_string(),
+            created_by: created_by.map(str::to_string),
+            created_at: now,
+            released_at: None,
+            cancelled_at: None,
+            message_id: None,
+            error: None,
+        };
+        db.create_scheduled_message(&scheduled).await?;
+        info!("Scheduled message {} for release at {}", scheduled.id, release_at.to_rfc3339());
+
+        Ok(ScheduledMessage { scheduled, message })
+    }
+
+    /// Relay every due message, returning each one released or refused
+    ///
+    /// Does nothing while the relay is read-only.
+    pub async fn release_due(&self, db: &Arc<Database>) -> Result<Vec<ScheduledMessage>, DatabaseError> {
+        let _releasing = self.releasing.lock().await;
+        if self.pipeline.maintenance.as_ref().is_some_and(|maintenance| maintenance.is_read_only()) {
+            return Ok(Vec::new());
+        }
+
+        let mut processed = Vec::new();
+        for scheduled in db.get_due_scheduled_messages(Utc::now(), self.config.batch_size).await? {
+            // Skip messages cancelled since they were read
+            if !db.transition_scheduled_message(&scheduled.id, STATUS_SCHEDULED, STATUS_RELEASING).await? {
+                continue;
+            }
+            match self.release(db, &scheduled).await {
+                Ok(message_id) => {
+                    info!("Released scheduled message {} as message {}", scheduled.id, message_id);
+                    db.finish_scheduled_message(&scheduled.id, STATUS_RELEASED, Some(&message_id), None).await?;
+                    record_release(STATUS_RELEASED);
+                }
+                Err(e) => {
+                    warn!("Scheduled message {} was refused at release: {}", scheduled.id, e);
+                    db.finish_scheduled_message(&scheduled.id, STATUS_FAILED, None, Some(&e.to_string())).await?;
+                    record_release(STATUS_FAILED);
+                }
+            }
+            if let Some(finished) = db.get_scheduled_message(&scheduled.id).await? {
+                match ScheduledMessage::try_from(finished) {
+                    Ok(finished) => processed.push(finished),
+                    Err(e) => warn!("Failed to read released message {}: {}", scheduled.id, e),
+                }
+            }
+        }
+        Ok(processed)
+    }
+
+    /// Relay a scheduled message for the tenant that scheduled it
+    async fn release(&self, db: &Arc<Database>, scheduled: &StoredScheduledMessage) -> Result<String, AppError> {
+        let message = ScheduledMessage::try_from(scheduled.clone())?.message;
+        let tenant = match &self.pipeline.tenancy {
+            Some(tenancy) => tenancy.resolve(scheduled.tenant.as_deref())?,
+            None => TenantScope::default(),
+        };
+        let pipeline = &self.pipeline;
+        crate::relay_message(
+            db,
+            message,
+            pipeline.federation.as_ref(),
+            pipeline.webhooks.as_ref(),
+            pipeline.events.as_ref(),
+            pipeline.replay.as_ref(),
+            pipeline.subscriptions.as_ref(),
+            pipeline.quarantine.as_ref(),
+            pipeline.abuse.as_ref(),
+            pipeline.limits.as_ref(),
+            pipeline.context_policy.as_ref(),
+            pipeline.timestamping.as_ref(),
+            &tenant,
+        )
+        .await
+    }
+
+    /// Release due messages periodically in the background
+    pub fn spawn(self: Arc<Self>, db: Arc<Database>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let period = std::time::Duration::from_secs(self.config.poll_interval_secs);
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                crate::readiness::BACKGROUND_JOBS.heartbeat("scheduled_release", period);
+                if let Err(e) = self.release_due(&db).await {
+                    warn!("Failed to release scheduled messages: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Count a release by outcome
+fn record_release(outcome: &str) {
+    SCHEDULED_RELEASES_TOTAL
+        .get_or_create(&vec![("outcome".to_string(), outcome.to_string())])
+        .inc();
+}
+
+/// A tenant's scheduled message, as if it did not exist when it belongs to another tenant
+async fn tenant_scheduled(db: &Database, tenant: &TenantScope, scheduled_id: &str) -> Result<StoredScheduledMessage, AppError> {
+    db.get_scheduled_message(scheduled_id)
+        .await?
+        .filter(|scheduled| scheduled.tenant.as_deref() == tenant.tenant())
+        .ok_or_else(|| SchedulingError::NotFound(scheduled_id.to_string()).into())
+}
+
+/// Retrieve a tenant's scheduled message
+pub async fn get_scheduled(db: &Database, tenant: &TenantScope, scheduled_id: &str) -> Result<ScheduledMessage, AppError> {
+    tenant_scheduled(db, tenant, scheduled_id).await?.try_into()
+}
+
+/// A tenant's scheduled messages matching a query, soonest release first
+pub async fn list_scheduled(db: &Database, tenant: &TenantScope, query: &ScheduledQuery) -> Result<Vec<ScheduledMessage>, AppError> {
+    let limit = query.limit()?;
+    db.list_scheduled_messages(tenant.tenant(), query.status.as_deref(), query.sender.as_deref(), limit)
+        .await?
+        .into_iter()
+        .map(ScheduledMessage::try_from)
+        .collect()
+}
+
+/// Cancel a tenant's scheduled message that has not been released yet
+pub async fn cancel_scheduled(db: &Database, tenant: &TenantScope, scheduled_id: &str) -> Result<ScheduledMessage, AppError> {
+    tenant_scheduled(db, tenant, scheduled_id).await?;
+    if !db.transition_scheduled_message(scheduled_id, STATUS_SCHEDULED, STATUS_CANCELLED).await? {
+        return Err(SchedulingError::NotScheduled(scheduled_id.to_string()).into());
+    }
+    info!("Cancelled scheduled message {}", scheduled_id);
+    get_scheduled(db, tenant, scheduled_id).await
+}
+
+/// Create router for scheduled message endpoints
+pub fn scheduling_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/scheduled", get(list_scheduled_handler).post(schedule_handler))
+        .route("/scheduled/:scheduled_id", get(get_scheduled_handler).delete(cancel_handler))
+}
+
+/// Create router for authenticated scheduled message endpoints
+pub fn authenticated_scheduling_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)> {
+    Router::new()
+        .route("/scheduled", get(authenticated_list_scheduled_handler).post(authenticated_schedule_handler))
+        .route("/scheduled/:scheduled_id", get(authenticated_get_scheduled_handler).delete(authenticated_cancel_handler))
+}
+
+/// Handler to schedule a signed message for later relay
+#[utoipa::path(
+    post,
+    path = "/scheduled",
+    operation_id = "scheduleMessage",
+    tag = "scheduling",
+    request_body = Message,
+    responses(
+        (status = 201, description = "Message scheduled for the time its context names", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn schedule_handler(
+    State(db): State<Arc<Database>>,
+    scheduler: Option<Extension<Arc<Scheduler>>>,
+    tenant: TenantScope,
+    wire::MessagePayload(payload): wire::MessagePayload,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Scheduling message");
+
+    let Extension(scheduler) = scheduler.ok_or(SchedulingError::Disabled)?;
+    let scheduled = scheduler.schedule(&db, payload, &tenant, None).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "scheduled": scheduled
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Handler to list scheduled messages
+#[utoipa::path(
+    get,
+    path = "/scheduled",
+    operation_id = "listScheduledMessages",
+    tag = "scheduling",
+    params(ScheduledQuery),
+    responses(
+        (status = 200, description = "Matching scheduled messages", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn list_scheduled_handler(
+    State(db): State<Arc<Database>>,
+    scheduler: Option<Extension<Arc<Scheduler>>>,
+    tenant: TenantScope,
+    Query(params): Query<ScheduledQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Listing scheduled messages");
+
+    scheduler.map(|_| ()).ok_or(SchedulingError::Disabled)?;
+    let scheduled = list_scheduled(&db, &tenant, &params).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "count": scheduled.len(),
+        "scheduled": scheduled
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler to retrieve a scheduled message
+#[utoipa::path(
+    get,
+    path = "/scheduled/{scheduled_id}",
+    operation_id = "getScheduledMessage",
+    tag = "scheduling",
+    params(("scheduled_id" = String, Path, description = "Scheduled message ID")),
+    responses(
+        (status = 200, description = "The scheduled message and its status", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_scheduled_handler(
+    State(db): State<Arc<Database>>,
+    scheduler: Option<Extension<Arc<Scheduler>>>,
+    tenant: TenantScope,
+    Path(scheduled_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Retrieving scheduled message: {}", scheduled_id);
+
+    scheduler.map(|_| ()).ok_or(SchedulingError::Disabled)?;
+    let scheduled = get_scheduled(&db, &tenant, &scheduled_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "scheduled": scheduled
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler to cancel a scheduled message before its release
+#[utoipa::path(
+    delete,
+    path = "/scheduled/{scheduled_id}",
+    operation_id = "cancelScheduledMessage",
+    tag = "scheduling",
+    params(("scheduled_id" = String, Path, description = "Scheduled message ID")),
+    responses(
+        (status = 200, description = "The cancelled message", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn cancel_handler(
+    State(db): State<Arc<Database>>,
+    scheduler: Option<Extension<Arc<Scheduler>>>,
+    tenant: TenantScope,
+    Path(scheduled_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Cancelling scheduled message: {}", scheduled_id);
+
+    scheduler.map(|_| ()).ok_or(SchedulingError::Disabled)?;
+    let scheduled = cancel_scheduled(&db, &tenant, &scheduled_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "scheduled": scheduled
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to schedule a signed message for later relay
+#[instrument(skip_all)]
+async fn authenticated_schedule_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    scheduler: Option<Extension<Arc<Scheduler>>>,
+    tenant: TenantScope,
+    wire::MessagePayload(payload): wire::MessagePayload,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} scheduling message", auth.user_id);
+
+    let Extension(scheduler) = scheduler.ok_or(SchedulingError::Disabled)?;
+    let scheduled = scheduler.schedule(&db, payload, &tenant, Some(&auth.user_id)).await?;
+
+    // Log the scheduled message
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("scheduled_id".to_string(), scheduled.scheduled.id.clone());
+    metadata.insert("sender".to_string(), scheduled.scheduled.sender.clone());
+    metadata.insert("release_at".to_string(), scheduled.scheduled.release_at.to_rfc3339());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Message scheduled".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log scheduled message: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "scheduled": scheduled,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Authenticated handler to list scheduled messages
+#[instrument(skip_all)]
+async fn authenticated_list_scheduled_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    scheduler: Option<Extension<Arc<Scheduler>>>,
+    tenant: TenantScope,
+    Query(params): Query<ScheduledQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} listing scheduled messages", auth.user_id);
+
+    scheduler.map(|_| ()).ok_or(SchedulingError::Disabled)?;
+    let scheduled = list_scheduled(&db, &tenant, &params).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "count": scheduled.len(),
+        "scheduled": scheduled,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to retrieve a scheduled message
+#[instrument(skip_all)]
+async fn authenticated_get_scheduled_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    scheduler: Option<Extension<Arc<Scheduler>>>,
+    tenant: TenantScope,
+    Path(scheduled_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} retrieving scheduled message: {}", auth.user_id, scheduled_id);
+
+    scheduler.map(|_| ()).ok_or(SchedulingError::Disabled)?;
+    let scheduled = get_scheduled(&db, &tenant, &scheduled_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "scheduled": scheduled,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to cancel a scheduled message before its release
+#[instrument(skip_all)]
+async fn authenticated_cancel_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    scheduler: Option<Extension<Arc<Scheduler>>>,
+    tenant: TenantScope,
+    Path(scheduled_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} cancelling scheduled message: {}", auth.user_id, scheduled_id);
+
+    scheduler.map(|_| ()).ok_or(SchedulingError::Disabled)?;
+    let scheduled = cancel_scheduled(&db, &tenant, &scheduled_id).await?;
+
+    // Log the cancellation
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("scheduled_id".to_string(), scheduled_id.clone());
+    metadata.insert("sender".to_string(), scheduled.scheduled.sender.clone());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Scheduled message cancelled".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log scheduled message cancellation: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "scheduled": scheduled,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_app;
+    use axum::body::Body;
+    use axum::http::Request;
+    use ed25519_dalek::{Keypair, Signer};
+    use proof_messenger_protocol::key::generate_keypair_with_seed;
+    use tower::ServiceExt;
+
+    fn scheduler() -> Arc<Scheduler> {
+        let config = SchedulingConfig {
+            enabled: true,
+            ..SchedulingConfig::default()
+        };
+        Arc::new(Scheduler::new(&config))
+    }
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    /// A message signed over a context scheduling it for `scheduled_at`
+    fn signed(keypair: &Keypair, scheduled_at: Value) -> Message {
+        let context = serde_json::to_vec(&serde_json::json!({ "action": "approve", SCHEDULED_AT_FIELD: scheduled_at })).unwrap();
+        Message {
+            sender: hex::encode(keypair.public.to_bytes()),
+            context: hex::encode(&context),
+            body: "approved".to_string(),
+            proof: hex::encode(keypair.sign(&context).to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    async fn call(app: &Router, method: &str, uri: &str, body: Option<&Message>) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body.map(|body| Body::from(serde_json::to_vec(body).unwrap())).unwrap_or_else(Body::empty))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    /// Make a scheduled message due by moving its release time into the past
+    async fn make_due(db: &Database, scheduled_id: &str) {
+        sqlx::query("UPDATE scheduled_messages SET release_at = ?1 WHERE id = ?2")
+            .bind(Utc::now() - Duration::seconds(1))
+            .bind(scheduled_id)
+            .execute(db.pool())
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_release_time_is_read_from_the_signed_context() {
+        let keypair = generate_keypair_with_seed(31);
+
+        let rfc3339 = signed(&keypair, Value::from("2030-01-01T09:00:00Z"));
+        let unix = signed(&keypair, Value::from(1_893_488_400));
+        let missing = Message {
+            context: hex::encode(br#"{"action":"approve"}"#),
+            ..rfc3339.clone()
+        };
+        let opaque = Message {
+            context: "00".to_string(),
+            ..rfc3339.clone()
+        };
+
+        let expected = DateTime::parse_from_rfc3339("2030-01-01T09:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(scheduled_time(&rfc3339).unwrap(), expected);
+        assert_eq!(scheduled_time(&unix).unwrap(), expected);
+        assert!(matches!(scheduled_time(&missing), Err(AppError::Scheduling(SchedulingError::InvalidSchedule(_)))));
+        assert!(matches!(scheduled_time(&opaque), Err(AppError::Scheduling(SchedulingError::InvalidSchedule(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_messages_are_checked_when_submitted() {
+        // ARRANGE: A relay with scheduling enabled
+        let db = setup_db().await;
+        let app = create_app(db.clone()).layer(Extension(scheduler()));
+        let keypair = generate_keypair_with_seed(32);
+        let past = signed(&keypair, Value::from((Utc::now() - Duration::minutes(1)).to_rfc3339()));
+        let too_far = signed(&keypair, Value::from((Utc::now() + Duration::days(365)).to_rfc3339()));
+        let mut forged = signed(&keypair, Value::from((Utc::now() + Duration::hours(1)).to_rfc3339()));
+        forged.proof = signed(&keypair, Value::from((Utc::now() + Duration::hours(2)).to_rfc3339())).proof;
+
+        // ACT: Schedule messages for the past, too far ahead and with a proof over another time
+        let (past_status, past_body) = call(&app, "POST", "/scheduled", Some(&past)).await;
+        let (too_far_status, _) = call(&app, "POST", "/scheduled", Some(&too_far)).await;
+        let (forged_status, _) = call(&app, "POST", "/scheduled", Some(&forged)).await;
+        let (disabled_status, _) = call(&create_app(db.clone()), "POST", "/scheduled", Some(&past)).await;
+
+        // ASSERT: Nothing is stored
+        assert_eq!(past_status, StatusCode::BAD_REQUEST);
+        assert_eq!(past_body["code"], "INVALID_SCHEDULE");
+        assert_eq!(too_far_status, StatusCode::BAD_REQUEST);
+        assert_eq!(forged_status, StatusCode::UNAUTHORIZED);
+        assert_eq!(disabled_status, StatusCode::NOT_FOUND);
+        assert!(db.list_scheduled_messages(None, None, None, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_due_messages_are_released_and_cancelled_ones_are_not() {
+        // ARRANGE: Two messages scheduled an hour ahead
+        let db = setup_db().await;
+        let scheduler = scheduler();
+        let app = create_app(db.clone()).layer(Extension(scheduler.clone()));
+        let keypair = generate_keypair_with_seed(33);
+        let release_at = Value::from((Utc::now() + Duration::hours(1)).to_rfc3339());
+        let (status, kept) = call(&app, "POST", "/scheduled", Some(&signed(&keypair, release_at.clone()))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let mut cancelled_message = signed(&keypair, release_at);
+        cancelled_message.body = "withdrawn".to_string();
+        let (_, cancelled) = call(&app, "POST", "/scheduled", Some(&cancelled_message)).await;
+        let kept_id = kept["scheduled"]["id"].as_str().unwrap().to_string();
+        let cancelled_id = cancelled["scheduled"]["id"].as_str().unwrap().to_string();
+
+        // ACT: Release before they are due, cancel one, then release once both are due
+        let early = scheduler.release_due(&db).await.unwrap();
+        let (cancel_status, _) = call(&app, "DELETE", &format!("/scheduled/{}", cancelled_id), None).await;
+        make_due(&db, &kept_id).await;
+        make_due(&db, &cancelled_id).await;
+        let released = scheduler.release_due(&db).await.unwrap();
+        let (recancel_status, recancel) = call(&app, "DELETE", &format!("/scheduled/{}", kept_id), None).await;
+        let (_, listed) = call(&app, "GET", "/scheduled?status=cancelled", None).await;
+
+        // ASSERT: Only the kept message was relayed, once it was due
+        assert!(early.is_empty());
+        assert_eq!(cancel_status, StatusCode::OK);
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].scheduled.id, kept_id);
+        assert_eq!(released[0].scheduled.status, STATUS_RELEASED);
+        let message_id = released[0].scheduled.message_id.clone().unwrap();
+        assert_eq!(db.get_message_by_id(&message_id).await.unwrap().body, "approved");
+        assert_eq!(recancel_status, StatusCode::CONFLICT);
+        assert_eq!(recancel["code"], "SCHEDULED_MESSAGE_NOT_PENDING");
+        assert_eq!(listed["count"], 1);
+        assert_eq!(listed["scheduled"][0]["id"], cancelled_id.as_str());
+        assert!(listed["scheduled"][0]["cancelled_at"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_messages_refused_at_release_are_marked_failed() {
+        // ARRANGE: The same signed context scheduled twice, released through replay protection
+        let db = setup_db().await;
+        let replay = ReplayGuard::new(Arc::new(crate::shared_state::MemoryNonceStore::new()), std::time::Duration::from_secs(60));
+        let scheduler = Scheduler::new(&SchedulingConfig::default()).with_pipeline(ReleasePipeline {
+            replay: Some(Arc::new(replay)),
+            ..ReleasePipeline::default()
+        });
+        let keypair = generate_keypair_with_seed(34);
+        let message = signed(&keypair, Value::from((Utc::now() + Duration::hours(1)).to_rfc3339()));
+        let first = scheduler.schedule(&db, message.clone(), &TenantScope::default(), None).await.unwrap();
+        let replayed = scheduler.schedule(&db, message, &TenantScope::default(), None).await.unwrap();
+        make_due(&db, &first.scheduled.id).await;
+        make_due(&db, &replayed.scheduled.id).await;
+
+        // ACT: Release both due messages
+        let released = scheduler.release_due(&db).await.unwrap();
+
+        // ASSERT: The replay was refused at release and the reason was kept
+        assert_eq!(released.len(), 2);
+        assert_eq!(released[0].scheduled.id, first.scheduled.id);
+        assert_eq!(released[0].scheduled.status, STATUS_RELEASED);
+        assert_eq!(released[1].scheduled.status, STATUS_FAILED);
+        assert!(released[1].scheduled.message_id.is_none());
+        assert!(released[1].scheduled.error.as_deref().unwrap().contains("replays"));
+    }
+}
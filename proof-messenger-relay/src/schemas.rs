@@ -0,0 +1,487 @@
+//! Message Body Schema Module
+//!
+//! Groups can require their message bodies to be JSON matching a registered
+//! JSON Schema, so malformed payloads are rejected before they are stored.
+//! Registering a schema for a group adds a new version of it; earlier
+//! versions are kept and can still be read, but messages are always
+//! validated against the latest one. Groups without a schema accept any body.
+//!
+//! A message whose body is not JSON, or does not match, is rejected with
+//! `400 SCHEMA_VALIDATION_FAILED`, naming the schema version and each
+//! failing location in `details`. Schemas must be self-contained: references
+//! to external documents are not resolved.
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+use utoipa::ToSchema;
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, StoredGroupSchema},
+    request_id::RequestId,
+    tenancy::TenantScope,
+    AppError,
+};
+
+/// Most validation errors reported for one message
+const MAX_REPORTED_ERRORS: usize = 20;
+
+/// Errors raised when registering schemas or validating bodies against them
+#[derive(Error, Debug)]
+pub enum SchemaError {
+    #[error("Schema not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid JSON Schema: {0}")]
+    Invalid(String),
+
+    #[error("{0}")]
+    Validation(SchemaViolation),
+}
+
+/// Why a message body was rejected by its group's schema
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaViolation {
+    /// Version of the schema the body was validated against
+    pub schema_version: i64,
+    /// The failing locations, at most 20
+    pub errors: Vec<SchemaViolationError>,
+}
+
+/// One location in a message body that does not match the schema
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaViolationError {
+    /// JSON Pointer to the failing value in the body
+    pub instance_path: String,
+    /// JSON Pointer to the schema keyword it fails
+    pub schema_path: String,
+    /// What is wrong with the value
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Message body does not match schema version {}", self.schema_version)?;
+        if let Some(first) = self.errors.first() {
+            write!(f, ": {}", first.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Request body for registering a group's schema
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RegisterSchemaRequest {
+    /// JSON Schema message bodies must match
+    #[schema(value_type = Object)]
+    pub schema: serde_json::Value,
+}
+
+/// Create router for body schema endpoints
+pub fn schema_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/groups/:group_id/schemas", post(register_schema_handler).get(list_schemas_handler))
+        .route("/groups/:group_id/schemas/:version", get(get_schema_handler))
+}
+
+/// Create router for authenticated body schema endpoints
+pub fn authenticated_schema_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/groups/:group_id/schemas", post(authenticated_register_schema_handler).get(authenticated_list_schemas_handler))
+        .route("/groups/:group_id/schemas/:version", get(authenticated_get_schema_handler))
+}
+
+/// Compile a schema, rejecting invalid or non-self-contained ones
+fn compile(schema: &serde_json::Value) -> Result<jsonschema::Validator, SchemaError> {
+    jsonschema::validator_for(schema).map_err(|e| SchemaError::Invalid(e.to_string()))
+}
+
+/// Register a new version of a group's schema
+pub async fn register_schema(
+    db: &Database,
+    group_id: &str,
+    schema: &serde_json::Value,
+    created_by: Option<&str>,
+) -> Result<StoredGroupSchema, AppError> {
+    compile(schema)?;
+    let version = db.create_group_schema(group_id, schema, created_by).await?;
+    get_schema(db, group_id, version).await
+}
+
+/// A version of a group's schema
+pub async fn get_schema(db: &Database, group_id: &str, version: i64) -> Result<StoredGroupSchema, AppError> {
+    db.get_group_schema(group_id, Some(version))
+        .await?
+        .ok_or_else(|| SchemaError::NotFound(format!("{} version {}", group_id, version)).into())
+}
+
+/// Validate a message body against the latest schema of its group
+///
+/// `group_id` is the stored (tenant-namespaced) group ID.
+pub async fn validate_body(db: &Database, group_id: &str, body: &str) -> Result<(), AppError> {
+    let Some(schema) = db.get_group_schema(group_id, None).await? else {
+        return Ok(());
+    };
+    let violation = |errors| SchemaError::Validation(SchemaViolation { schema_version: schema.version, errors });
+
+    let body: serde_json::Value = serde_json::from_str(body).map_err(|e| {
+        violation(vec![SchemaViolationError {
+            instance_path: String::new(),
+            schema_path: String::new(),
+            message: format!("body is not JSON: {}", e),
+        }])
+    })?;
+    let validator = compile(&schema.schema)
+        .map_err(|e| AppError::ProcessingError(format!("Stored schema for {} is unusable: {}", group_id, e)))?;
+    let errors: Vec<_> = validator
+        .iter_errors(&body)
+        .take(MAX_REPORTED_ERRORS)
+        .map(|error| SchemaViolationError {
+            instance_path: error.instance_path().to_string(),
+            schema_path: error.schema_path().to_string(),
+            message: error.to_string(),
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(violation(errors).into())
+    }
+}
+
+/// Handler to register a new version of a group's schema
+#[utoipa::path(
+    post,
+    path = "/groups/{group_id}/schemas",
+    operation_id = "registerGroupSchema",
+    tag = "schemas",
+    params(("group_id" = String, Path, description = "Group ID")),
+    request_body = RegisterSchemaRequest,
+    responses(
+        (status = 201, description = "Schema registered as the group's latest version", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn register_schema_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(group_id): Path<String>,
+    Json(payload): Json<RegisterSchemaRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Registering body schema for group: {}", group_id);
+
+    let schema = register_schema(&db, &tenant.group_id(&group_id), &payload.schema, None).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "group_id": group_id,
+        "version": schema.version,
+        "schema": schema.schema,
+        "created_at": schema.created_at
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Handler to list every version of a group's schema
+#[utoipa::path(
+    get,
+    path = "/groups/{group_id}/schemas",
+    operation_id = "listGroupSchemas",
+    tag = "schemas",
+    params(("group_id" = String, Path, description = "Group ID")),
+    responses(
+        (status = 200, description = "The group's schema versions, newest first", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn list_schemas_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(group_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Listing body schemas for group: {}", group_id);
+
+    let schemas = db.list_group_schemas(&tenant.group_id(&group_id)).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "group_id": group_id,
+        "count": schemas.len(),
+        "schemas": schemas.iter().map(schema_json).collect::<Vec<_>>()
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler to retrieve a version of a group's schema
+#[utoipa::path(
+    get,
+    path = "/groups/{group_id}/schemas/{version}",
+    operation_id = "getGroupSchema",
+    tag = "schemas",
+    params(
+        ("group_id" = String, Path, description = "Group ID"),
+        ("version" = i64, Path, description = "Schema version"),
+    ),
+    responses(
+        (status = 200, description = "The schema version", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_schema_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path((group_id, version)): Path<(String, i64)>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Retrieving body schema {} for group: {}", version, group_id);
+
+    let schema = get_schema(&db, &tenant.group_id(&group_id), version).await?;
+
+    let mut response = schema_json(&schema);
+    response["status"] = "success".into();
+    response["group_id"] = group_id.into();
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Authenticated handler to register a new version of a group's schema
+#[instrument(skip_all)]
+async fn authenticated_register_schema_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    tenant: TenantScope,
+    Path(group_id): Path<String>,
+    Json(payload): Json<RegisterSchemaRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} registering body schema for group: {}", auth.user_id, group_id);
+
+    let schema = register_schema(&db, &tenant.group_id(&group_id), &payload.schema, Some(&auth.user_id)).await?;
+
+    // Log the new schema version
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("group_id".to_string(), group_id.clone());
+    metadata.insert("version".to_string(), schema.version.to_string());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Group body schema registered".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log schema registration: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "group_id": group_id,
+        "version": schema.version,
+        "schema": schema.schema,
+        "created_at": schema.created_at,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Authenticated handler to list every version of a group's schema
+#[instrument(skip_all)]
+async fn authenticated_list_schemas_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Path(group_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} listing body schemas for group: {}", auth.user_id, group_id);
+
+    let schemas = db.list_group_schemas(&tenant.group_id(&group_id)).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "group_id": group_id,
+        "count": schemas.len(),
+        "schemas": schemas.iter().map(schema_json).collect::<Vec<_>>(),
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to retrieve a version of a group's schema
+#[instrument(skip_all)]
+async fn authenticated_get_schema_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Path((group_id, version)): Path<(String, i64)>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} retrieving body schema {} for group: {}", auth.user_id, version, group_id);
+
+    let schema = get_schema(&db, &tenant.group_id(&group_id), version).await?;
+
+    let mut response = schema_json(&schema);
+    response["status"] = "success".into();
+    response["group_id"] = group_id.into();
+    response["authenticated_user"] = auth.user_id.into();
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// A schema version as returned to clients, without its tenant-namespaced group ID
+fn schema_json(schema: &StoredGroupSchema) -> serde_json::Value {
+    serde_json::json!({
+        "version": schema.version,
+        "schema": schema.schema,
+        "created_by": schema.created_by,
+        "created_at": schema.created_at
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use ed25519_dalek::Signer;
+    use tower::ServiceExt;
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    fn signed_message(body: &str) -> crate::Message {
+        let keypair = proof_messenger_protocol::key::generate_keypair_with_seed(42);
+        let context = b"order context".to_vec();
+        crate::Message {
+            sender: hex::encode(keypair.public.to_bytes()),
+            context: hex::encode(&context),
+            body: body.to_string(),
+            proof: hex::encode(keypair.sign(&context).to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        }
+    }
+
+    fn order_schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "required": ["order_id", "amount"],
+            "properties": {
+                "order_id": { "type": "string" },
+                "amount": { "type": "integer", "minimum": 0 }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_registering_adds_versions() {
+        // ARRANGE: A group with no schema
+        let db = setup_db().await;
+
+        // ACT: Register two schemas
+        let first = register_schema(&db, "orders", &order_schema(), Some("admin")).await.unwrap();
+        let second = register_schema(&db, "orders", &serde_json::json!({ "type": "object" }), None).await.unwrap();
+
+        // ASSERT: Each registration is a new version, and both are kept
+        assert_eq!((first.version, second.version), (1, 2));
+        let listed = db.list_group_schemas("orders").await.unwrap();
+        assert_eq!(listed.iter().map(|schema| schema.version).collect::<Vec<_>>(), [2, 1]);
+        assert_eq!(get_schema(&db, "orders", 1).await.unwrap().schema, order_schema());
+        assert!(matches!(
+            get_schema(&db, "orders", 3).await,
+            Err(AppError::Schema(SchemaError::NotFound(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_schemas_are_rejected() {
+        let db = setup_db().await;
+
+        let invalid = register_schema(&db, "orders", &serde_json::json!({ "type": "money" }), None).await;
+        let external = register_schema(&db, "orders", &serde_json::json!({ "$ref": "https://example.com/order.json" }), None).await;
+
+        assert!(matches!(invalid, Err(AppError::Schema(SchemaError::Invalid(_)))));
+        assert!(matches!(external, Err(AppError::Schema(SchemaError::Invalid(_)))));
+        assert!(db.list_group_schemas("orders").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bodies_are_validated_against_the_latest_version() {
+        // ARRANGE: A group whose schema was tightened in version 2
+        let db = setup_db().await;
+        register_schema(&db, "orders", &serde_json::json!({ "type": "object" }), None).await.unwrap();
+        register_schema(&db, "orders", &order_schema(), None).await.unwrap();
+
+        // ACT: Validate matching, mismatching and non-JSON bodies
+        let valid = validate_body(&db, "orders", r#"{"order_id": "A1", "amount": 5}"#).await;
+        let invalid = validate_body(&db, "orders", r#"{"order_id": 7, "amount": -1}"#).await;
+        let not_json = validate_body(&db, "orders", "hello").await;
+        let unschematized = validate_body(&db, "chat", "hello").await;
+
+        // ASSERT: Only matching bodies, or groups without a schema, pass
+        assert!(valid.is_ok());
+        assert!(unschematized.is_ok());
+        let Err(AppError::Schema(SchemaError::Validation(violation))) = invalid else {
+            panic!("expected a schema violation");
+        };
+        assert_eq!(violation.schema_version, 2);
+        let mut paths: Vec<_> = violation.errors.iter().map(|error| error.instance_path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, ["/amount", "/order_id"]);
+        assert!(matches!(not_json, Err(AppError::Schema(SchemaError::Validation(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_relay_rejects_bodies_that_do_not_match() {
+        // ARRANGE: A relay whose default group requires order bodies
+        let db = setup_db().await;
+        let app = crate::create_app(db.clone());
+        let register = Request::builder()
+            .method("POST")
+            .uri("/v1/groups/default/schemas")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "schema": order_schema() }).to_string()))
+            .unwrap();
+        assert_eq!(app.clone().oneshot(register).await.unwrap().status(), StatusCode::CREATED);
+        let message = signed_message(r#"{"order_id": "A1"}"#);
+
+        // ACT: Relay a message missing the amount
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/relay")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&message).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // ASSERT: It is rejected with the failing location, and not stored
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "SCHEMA_VALIDATION_FAILED");
+        assert_eq!(body["details"]["schema_version"], 1);
+        assert_eq!(body["details"]["errors"][0]["schema_path"], "/required");
+        assert!(db.get_messages_by_group("default", None).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+//! Message Search Module
+//!
+//! This module exposes full-text search over stored message bodies, backed
+//! by the SQLite FTS5 index. Results are ranked by relevance, paginated and
+//! returned with highlighted snippets.
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use std::sync::Arc;
+use tracing::{info, instrument};
+
+use crate::{auth_middleware::AuthContext, database::Database, AppError};
+
+/// Default number of results per page
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+
+/// Maximum number of results per page
+const MAX_SEARCH_LIMIT: i64 = 100;
+
+/// Scope prefix granting access to a single group (e.g. `group:engineering`)
+const GROUP_SCOPE_PREFIX: &str = "group:";
+
+/// Query parameters for message search
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Search terms; every term must match
+    pub q: String,
+    /// Optional group to restrict the search to
+    pub group: Option<String>,
+    /// Maximum number of results to return (default 20, at most 100)
+    pub limit: Option<i64>,
+    /// Number of results to skip for pagination
+    pub offset: Option<i64>,
+}
+
+impl SearchQuery {
+    /// Validate the query and return the effective (limit, offset)
+    fn page(&self) -> Result<(i64, i64), AppError> {
+        if self.q.trim().is_empty() {
+            return Err(AppError::InvalidQuery("Search query cannot be empty".to_string()));
+        }
+        let limit = self.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+        if !(1..=MAX_SEARCH_LIMIT).contains(&limit) {
+            return Err(AppError::InvalidQuery(format!("limit must be between 1 and {}", MAX_SEARCH_LIMIT)));
+        }
+        let offset = self.offset.unwrap_or(0);
+        if offset < 0 {
+            return Err(AppError::InvalidQuery("offset cannot be negative".to_string()));
+        }
+        Ok((limit, offset))
+    }
+}
+
+/// Create router for search endpoints
+pub fn search_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/messages/search", get(search_messages_handler))
+}
+
+/// Create router for authenticated search endpoints
+pub fn authenticated_search_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/messages/search", get(authenticated_search_messages_handler))
+}
+
+/// Groups a caller may search, from their `group:<id>` scopes
+///
+/// Callers without any group scopes are not restricted, matching the
+/// access `message:read` grants on the group message endpoints.
+pub fn accessible_groups(auth: &AuthContext) -> Option<Vec<String>> {
+    let groups: Vec<String> = auth
+        .scopes
+        .iter()
+        .filter_map(|scope| scope.strip_prefix(GROUP_SCOPE_PREFIX))
+        .map(str::to_string)
+        .collect();
+    if groups.is_empty() {
+        None
+    } else {
+        Some(groups)
+    }
+}
+
+/// Narrow the accessible groups to the requested one, if any
+fn search_scope(requested: Option<&String>, accessible: Option<Vec<String>>) -> Result<Option<Vec<String>>, AppError> {
+    match (requested, accessible) {
+        (Some(group), Some(groups)) if !groups.contains(group) => Err(AppError::InsufficientScope(
+            "Insufficient permissions to search this group".to_string(),
+        )),
+        (Some(group), _) => Ok(Some(vec![group.clone()])),
+        (None, accessible) => Ok(accessible),
+    }
+}
+
+/// Handler to search messages
+#[utoipa::path(
+    get,
+    path = "/messages/search",
+    operation_id = "searchMessages",
+    tag = "messages",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Matching messages, most relevant first", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn search_messages_handler(
+    State(db): State<Arc<Database>>,
+    Query(params): Query<SearchQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Searching messages");
+
+    let (limit, offset) = params.page()?;
+    let groups = search_scope(params.group.as_ref(), None)?;
+    let results = db.search_messages(&params.q, groups.as_deref(), limit, offset).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "query": params.q,
+        "limit": limit,
+        "offset": offset,
+        "count": results.len(),
+        "results": results
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to search messages in the caller's groups
+#[instrument(skip_all)]
+async fn authenticated_search_messages_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    Query(params): Query<SearchQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} searching messages", auth.user_id);
+
+    let (limit, offset) = params.page()?;
+    let groups = search_scope(params.group.as_ref(), accessible_groups(&auth))?;
+    let results = db.search_messages(&params.q, groups.as_deref(), limit, offset).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "query": params.q,
+        "limit": limit,
+        "offset": offset,
+        "count": results.len(),
+        "results": results,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::StoredMessage;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    async fn setup_test_app() -> Router {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        for body in ["deploy the relay", "relay is down", "coffee"] {
+            let mut message = StoredMessage::from(crate::Message {
+                sender: "aa".repeat(32),
+                context: "bb".to_string(),
+                body: body.to_string(),
+                proof: "cc".repeat(64),
+                pqc: None,
+                thread_id: None,
+                reply_to: None,
+                expires_at: None,
+                recipient: None,
+            });
+            message.group_id = "group1".to_string();
+            db.store_message(message).await.unwrap();
+        }
+        Router::new().merge(search_routes()).with_state(db)
+    }
+
+    async fn get(app: &Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_search_paginates_results() {
+        let app = setup_test_app().await;
+
+        let (status, first) = get(&app, "/messages/search?q=relay&limit=1").await;
+        let (_, second) = get(&app, "/messages/search?q=relay&limit=1&offset=1").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(first["count"], 1);
+        assert_eq!(second["count"], 1);
+        assert_ne!(first["results"][0]["id"], second["results"][0]["id"]);
+        assert!(first["results"][0]["snippet"].as_str().unwrap().contains("<mark>relay</mark>"));
+    }
+
+    #[tokio::test]
+    async fn test_search_scoped_to_group() {
+        let app = setup_test_app().await;
+
+        let (_, json) = get(&app, "/messages/search?q=relay&group=group2").await;
+
+        assert_eq!(json["count"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_empty_query_is_rejected() {
+        let app = setup_test_app().await;
+
+        let (status, _) = get(&app, "/messages/search?q=%20").await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_group_scopes_restrict_search() {
+        let auth = AuthContext {
+            user_id: "user".to_string(),
+            scopes: ["message:read", "group:group1"].iter().map(|s| s.to_string()).collect(),
+            tenant: None,
+        };
+        let accessible = accessible_groups(&auth);
+
+        assert_eq!(accessible, Some(vec!["group1".to_string()]));
+        assert!(search_scope(Some(&"group2".to_string()), accessible.clone()).is_err());
+        assert_eq!(search_scope(None, accessible).unwrap(), Some(vec!["group1".to_string()]));
+    }
+}
@@ -6,6 +6,9 @@ use std::collections::HashMap;
 use thiserror::Error;
 use tracing::{info, warn, error};
 use rand::RngCore;
+use std::sync::Arc;
+
+use crate::siem::SiemForwarder;
 
 /// Errors that can occur during secure logging operations
 #[derive(Error, Debug)]
@@ -62,6 +65,8 @@ pub struct EncryptedLogEntry {
 /// Secure logger that encrypts sensitive log data using AES-GCM
 pub struct SecureLogger {
     cipher: Aes256Gcm,
+    /// Where events are forwarded to a SIEM, when configured
+    forwarder: Option<Arc<SiemForwarder>>,
 }
 
 impl SecureLogger {
@@ -72,9 +77,16 @@ impl SecureLogger {
         
         Self {
             cipher,
+            forwarder: None,
         }
     }
 
+    /// Forward every security event this logger records to a SIEM
+    pub fn with_forwarder(mut self, forwarder: Arc<SiemForwarder>) -> Self {
+        self.forwarder = Some(forwarder);
+        self
+    }
+
     /// Generate a cryptographically secure random key
     pub fn generate_key() -> [u8; 32] {
         let mut key = [0u8; 32];
@@ -165,7 +177,11 @@ impl SecureLogger {
         }
 
         // Encrypt the full entry for secure storage
-        self.encrypt_log_entry(&entry)
+        let encrypted = self.encrypt_log_entry(&entry)?;
+        if let Some(forwarder) = &self.forwarder {
+            forwarder.forward(&entry, &encrypted);
+        }
+        Ok(encrypted)
     }
 
     /// Convenience method for audit logging
@@ -0,0 +1,184 @@
+//! Shared State Module
+//!
+//! Rate limiting and replay protection only hold across a deployment if
+//! every relay replica sees the same counters and the same remembered
+//! contexts. This module defines the stores they use: [`RateLimitStore`]
+//! for rate-limit buckets and [`NonceStore`] for replay protection.
+//!
+//! With `[redis]` configured (see [`crate::config::RedisConfig`]) both are
+//! backed by [`redis::RedisStore`] and shared by every replica. Otherwise
+//! each relay keeps its own state in memory: rate limiting falls back to the
+//! in-process `tower_governor` limiter and nonces to [`MemoryNonceStore`].
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[cfg(feature = "redis")]
+pub mod redis;
+
+/// Insertions between sweeps of expired in-memory nonces
+const NONCE_SWEEP_INTERVAL: usize = 1024;
+
+/// Shared state error types
+#[derive(Error, Debug)]
+pub enum SharedStateError {
+    #[error("Invalid shared state configuration: {0}")]
+    Config(String),
+
+    #[error("Shared state store unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// A rate-limit quota enforced with the generic cell rate algorithm
+///
+/// One request is replenished every `period`, and at most `burst` requests
+/// are allowed at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    /// Time for one request to be replenished
+    pub period: Duration,
+    /// Requests allowed in a burst
+    pub burst: u32,
+}
+
+impl Quota {
+    /// The quota a rate limit configuration describes
+    ///
+    /// `per_second` is taken as the seconds between replenishments, the way
+    /// the in-memory `tower_governor` limiter reads it, so a relay enforces
+    /// the same limit with or without Redis.
+    pub fn from_config(config: &crate::config::RateLimitConfig) -> Self {
+        Self {
+            period: Duration::from_secs(config.per_second),
+            burst: config.burst_size,
+        }
+    }
+}
+
+/// Rate-limit buckets shared by relay replicas
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Take one request from the bucket at `key`
+    ///
+    /// Returns `None` if the request is allowed, or how long to wait before
+    /// retrying if the bucket is empty.
+    async fn acquire(&self, key: &str, quota: Quota) -> Result<Option<Duration>, SharedStateError>;
+}
+
+/// Nonces remembered for replay protection
+#[async_trait]
+pub trait NonceStore: Send + Sync {
+    /// Remember `nonce` for `ttl`, returning `false` if it is already remembered
+    ///
+    /// Checking and remembering happen atomically, so of two replicas
+    /// seeing the same nonce at once only one is told it is new.
+    async fn insert(&self, nonce: &str, ttl: Duration) -> Result<bool, SharedStateError>;
+
+    /// Forget `nonce`, e.g. because the message it guarded was never stored
+    async fn remove(&self, nonce: &str) -> Result<(), SharedStateError>;
+}
+
+/// Nonces kept in this relay's memory
+#[derive(Default)]
+pub struct MemoryNonceStore {
+    nonces: Mutex<MemoryNonces>,
+}
+
+#[derive(Default)]
+struct MemoryNonces {
+    expiries: HashMap<String, Instant>,
+    insertions: usize,
+}
+
+impl MemoryNonceStore {
+    /// An empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of nonces held, including expired ones not yet swept
+    pub fn len(&self) -> usize {
+        self.nonces.lock().unwrap().expiries.len()
+    }
+
+    /// Whether no nonces are held
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl NonceStore for MemoryNonceStore {
+    async fn insert(&self, nonce: &str, ttl: Duration) -> Result<bool, SharedStateError> {
+        let now = Instant::now();
+        let mut nonces = self.nonces.lock().unwrap();
+        nonces.insertions += 1;
+        if nonces.insertions.is_multiple_of(NONCE_SWEEP_INTERVAL) {
+            nonces.expiries.retain(|_, expiry| *expiry > now);
+        }
+
+        match nonces.expiries.get(nonce) {
+            Some(expiry) if *expiry > now => Ok(false),
+            _ => {
+                nonces.expiries.insert(nonce.to_string(), now + ttl);
+                Ok(true)
+            }
+        }
+    }
+
+    async fn remove(&self, nonce: &str) -> Result<(), SharedStateError> {
+        self.nonces.lock().unwrap().expiries.remove(nonce);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_nonce_is_accepted_once_until_it_expires() {
+        let store = MemoryNonceStore::new();
+
+        let first = store.insert("nonce", Duration::from_millis(50)).await.unwrap();
+        let replay = store.insert("nonce", Duration::from_millis(50)).await.unwrap();
+        let other = store.insert("other", Duration::from_millis(50)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let after_expiry = store.insert("nonce", Duration::from_millis(50)).await.unwrap();
+
+        assert_eq!((first, replay, other, after_expiry), (true, false, true, true));
+    }
+
+    #[tokio::test]
+    async fn test_removed_nonce_is_accepted_again() {
+        let store = MemoryNonceStore::new();
+        store.insert("nonce", Duration::from_secs(60)).await.unwrap();
+
+        store.remove("nonce").await.unwrap();
+
+        assert!(store.insert("nonce", Duration::from_secs(60)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_expired_nonces_are_swept() {
+        let store = MemoryNonceStore::new();
+        for index in 0..NONCE_SWEEP_INTERVAL - 1 {
+            store.insert(&index.to_string(), Duration::ZERO).await.unwrap();
+        }
+        assert_eq!(store.len(), NONCE_SWEEP_INTERVAL - 1);
+
+        store.insert("live", Duration::from_secs(60)).await.unwrap();
+
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_quota_matches_the_in_memory_limiter() {
+        let config = crate::config::RateLimitConfig { per_second: 2, burst_size: 5 };
+
+        assert_eq!(Quota::from_config(&config), Quota { period: Duration::from_secs(2), burst: 5 });
+    }
+}
@@ -0,0 +1,209 @@
+//! Redis-backed shared state
+//!
+//! Rate-limit buckets are stored as a GCRA "theoretical arrival time" per
+//! key, updated by a Lua script so the check and the update are atomic and
+//! every replica uses the Redis server's clock. Nonces are plain keys set
+//! with `SET NX PX`, so they expire on their own.
+
+use ::redis::aio::ConnectionManager;
+use ::redis::{Client, Script};
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+use super::{NonceStore, Quota, RateLimitStore, SharedStateError};
+use crate::config::RedisConfig;
+
+/// Longest a rate-limit or nonce operation may wait for Redis
+const OPERATION_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// GCRA over the Redis server's clock
+///
+/// KEYS[1] holds the bucket's theoretical arrival time in milliseconds.
+/// ARGV[1] is the period and ARGV[2] the burst. Returns 0 if the request is
+/// allowed, otherwise the milliseconds to wait.
+const GCRA_SCRIPT: &str = r#"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local period = tonumber(ARGV[1])
+local burst = tonumber(ARGV[2])
+local tat = tonumber(redis.call('GET', KEYS[1]) or now)
+if tat < now then
+    tat = now
+end
+local new_tat = tat + period
+local wait = new_tat - now - period * burst
+if wait > 0 then
+    return wait
+end
+redis.call('SET', KEYS[1], new_tat, 'PX', new_tat - now)
+return 0
+"#;
+
+/// Rate-limit buckets and nonces kept in Redis
+pub struct RedisStore {
+    client: Client,
+    key_prefix: String,
+    connection: OnceCell<ConnectionManager>,
+    gcra: Script,
+}
+
+impl RedisStore {
+    /// A store on the server named by `config.url`
+    ///
+    /// No connection is made until the store is first used, and a dropped
+    /// connection is re-established automatically.
+    pub fn new(config: &RedisConfig) -> Result<Self, SharedStateError> {
+        let url = config
+            .url
+            .as_deref()
+            .ok_or_else(|| SharedStateError::Config("redis.url must be set".to_string()))?;
+        let client = Client::open(url).map_err(|e| SharedStateError::Config(format!("redis.url: {}", e)))?;
+
+        Ok(Self {
+            client,
+            key_prefix: config.key_prefix.clone(),
+            connection: OnceCell::new(),
+            gcra: Script::new(GCRA_SCRIPT),
+        })
+    }
+
+    /// Full key for a relay-relative name
+    fn key(&self, kind: &str, name: &str) -> String {
+        format!("{}:{}:{}", self.key_prefix, kind, name)
+    }
+
+    /// Connection to the server, connecting on first use
+    async fn connection(&self) -> Result<ConnectionManager, SharedStateError> {
+        self.connection
+            .get_or_try_init(|| async {
+                self.client
+                    .get_connection_manager()
+                    .await
+                    .map_err(|e| SharedStateError::Unavailable(e.to_string()))
+            })
+            .await
+            .cloned()
+    }
+}
+
+/// Run a Redis operation, giving up after [`OPERATION_TIMEOUT`]
+async fn with_timeout<T>(
+    operation: impl std::future::Future<Output = Result<T, SharedStateError>>,
+) -> Result<T, SharedStateError> {
+    tokio::time::timeout(OPERATION_TIMEOUT, operation)
+        .await
+        .unwrap_or_else(|_| Err(SharedStateError::Unavailable(format!("no reply within {:?}", OPERATION_TIMEOUT))))
+}
+
+#[async_trait]
+impl RateLimitStore for RedisStore {
+    async fn acquire(&self, key: &str, quota: Quota) -> Result<Option<Duration>, SharedStateError> {
+        with_timeout(async {
+            let mut connection = self.connection().await?;
+            let wait_ms: u64 = self
+                .gcra
+                .key(self.key("rate", key))
+                .arg(quota.period.as_millis() as u64)
+                .arg(quota.burst)
+                .invoke_async(&mut connection)
+                .await
+                .map_err(|e| SharedStateError::Unavailable(e.to_string()))?;
+            Ok((wait_ms > 0).then(|| Duration::from_millis(wait_ms)))
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl NonceStore for RedisStore {
+    async fn insert(&self, nonce: &str, ttl: Duration) -> Result<bool, SharedStateError> {
+        with_timeout(async {
+            let mut connection = self.connection().await?;
+            let reply: Option<String> = ::redis::cmd("SET")
+                .arg(self.key("nonce", nonce))
+                .arg(1)
+                .arg("NX")
+                .arg("PX")
+                .arg(ttl.as_millis().max(1) as u64)
+                .query_async(&mut connection)
+                .await
+                .map_err(|e| SharedStateError::Unavailable(e.to_string()))?;
+            Ok(reply.is_some())
+        })
+        .await
+    }
+
+    async fn remove(&self, nonce: &str) -> Result<(), SharedStateError> {
+        with_timeout(async {
+            let mut connection = self.connection().await?;
+            ::redis::cmd("DEL")
+                .arg(self.key("nonce", nonce))
+                .query_async::<()>(&mut connection)
+                .await
+                .map_err(|e| SharedStateError::Unavailable(e.to_string()))
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    /// A store on the server at `REDIS_URL`, under a prefix unique to the test
+    fn store() -> RedisStore {
+        let url = std::env::var("REDIS_URL").expect("REDIS_URL must name a Redis server for this test");
+        RedisStore::new(&RedisConfig { url: Some(url), key_prefix: format!("test-{}", Uuid::new_v4()) }).unwrap()
+    }
+
+    #[test]
+    fn test_invalid_url_is_rejected() {
+        let config = RedisConfig { url: Some("not a url".to_string()), ..Default::default() };
+
+        assert!(matches!(RedisStore::new(&config), Err(SharedStateError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server_is_reported_unavailable() {
+        let config = RedisConfig { url: Some("redis://127.0.0.1:9".to_string()), ..Default::default() };
+        let store = RedisStore::new(&config).unwrap();
+
+        let result = store.insert("nonce", Duration::from_secs(60)).await;
+
+        assert!(matches!(result, Err(SharedStateError::Unavailable(_))));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server at REDIS_URL"]
+    async fn test_replicas_share_nonces() {
+        let first = store();
+        let second = RedisStore::new(&RedisConfig {
+            url: std::env::var("REDIS_URL").ok(),
+            key_prefix: first.key_prefix.clone(),
+        })
+        .unwrap();
+
+        assert!(first.insert("nonce", Duration::from_secs(60)).await.unwrap());
+        assert!(!second.insert("nonce", Duration::from_secs(60)).await.unwrap());
+        second.remove("nonce").await.unwrap();
+        assert!(first.insert("nonce", Duration::from_secs(60)).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server at REDIS_URL"]
+    async fn test_bucket_allows_a_burst_then_limits() {
+        let store = store();
+        let quota = Quota { period: Duration::from_secs(10), burst: 3 };
+
+        let mut results = Vec::new();
+        for _ in 0..4 {
+            results.push(store.acquire("global", quota).await.unwrap());
+        }
+
+        assert_eq!(&results[..3], &[None, None, None]);
+        let wait = results[3].expect("the fourth request exceeds the burst");
+        assert!(wait > Duration::from_secs(9) && wait <= Duration::from_secs(10), "{:?}", wait);
+    }
+}
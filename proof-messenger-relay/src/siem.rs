@@ -0,0 +1,598 @@
+//! SIEM Forwarding Module
+//!
+//! Security events logged through the
+//! [`SecureLogger`](crate::secure_logger::SecureLogger) are encrypted for
+//! storage and only summarised in the tracing output. With a `[siem]` sink
+//! configured, every event is also forwarded to the security operations
+//! team's SIEM as a [`SiemEvent`]:
+//!
+//! - a sanitised plaintext summary: level, timestamp, request ID, and the
+//!   message and user ID with PII redacted as in the request logs (see
+//!   [`crate::log_redaction`]); metadata values are left out, only their
+//!   keys are listed
+//! - a reference to the encrypted entry (the SHA-256 digest of its nonce
+//!   and ciphertext) along with the encrypted entry itself, which can be
+//!   decrypted with the logger's key when an investigation needs the details
+//!
+//! Three sinks are supported:
+//!
+//! - `syslog_tcp`: RFC 5424 messages on the `authpriv` facility, framed by
+//!   octet counting (RFC 6587) over a TCP connection to `siem.address`
+//! - `hec`: batches posted to a Splunk HTTP Event Collector at `siem.url`
+//!   with the `siem.token`
+//! - `webhook`: JSON arrays posted to `siem.url`, signed like webhook
+//!   deliveries (see [`crate::webhooks::sign_payload`]) when `siem.token` is set
+//!
+//! Events are buffered in memory and delivered by a background task, so
+//! logging never waits on the SIEM. While the SIEM is unreachable, delivery
+//! is retried with exponential backoff between `siem.retry_base_ms` and
+//! `siem.retry_max_ms`; once `siem.buffer_capacity` events are waiting, the
+//! oldest are dropped. Forwarded, dropped and buffered events and failed
+//! deliveries are reported in the metrics registry, and the buffer is
+//! flushed when the relay shuts down.
+//!
+//! Forwarding is enabled by the `[siem]` relay settings (see
+//! [`crate::config::SiemConfig`]) and attaching the resulting
+//! [`SiemForwarder`] to each logger with
+//! [`SecureLogger::with_forwarder`](crate::secure_logger::SecureLogger::with_forwarder).
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::config::{SiemConfig, SiemSinkKind};
+use crate::egress::HttpClient;
+use crate::metrics::{
+    SIEM_BUFFERED_EVENTS, SIEM_DELIVERY_FAILURES_TOTAL, SIEM_EVENTS_DROPPED_TOTAL, SIEM_EVENTS_FORWARDED_TOTAL,
+};
+use crate::secure_logger::{EncryptedLogEntry, LogEntry, LogLevel};
+
+/// Splunk `sourcetype` of forwarded events
+pub const HEC_SOURCETYPE: &str = "proof-messenger:security";
+
+/// How long buffered events may take to deliver when the relay shuts down
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// Errors forwarding security events
+#[derive(Debug, Error)]
+pub enum SiemError {
+    #[error("Invalid SIEM configuration: {0}")]
+    Config(String),
+    #[error("SIEM delivery failed: {0}")]
+    Delivery(String),
+}
+
+/// A security event as forwarded to the SIEM
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SiemEvent {
+    pub timestamp: DateTime<Utc>,
+    pub level: LogLevel,
+    /// Event message with PII redacted
+    pub message: String,
+    /// User the event concerns, redacted if it looks like PII
+    pub user_id: Option<String>,
+    pub request_id: Option<String>,
+    /// Keys of the event's metadata, whose values are only in the encrypted payload
+    pub metadata_keys: Vec<String>,
+    /// Hex SHA-256 digest of the encrypted payload's nonce and ciphertext
+    pub payload_ref: String,
+    /// Base64 AES-256-GCM nonce (12 bytes) followed by the ciphertext of the full entry
+    pub encrypted_payload: String,
+}
+
+impl SiemEvent {
+    /// Summarise a logged entry and reference its encrypted form
+    pub fn summarize(entry: &LogEntry, encrypted: &EncryptedLogEntry) -> Self {
+        let redactor = crate::log_redaction::current();
+        let message = entry
+            .message
+            .split(' ')
+            .map(|word| redactor.redact_value(word))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut metadata_keys: Vec<String> = entry.metadata.keys().cloned().collect();
+        metadata_keys.sort();
+        let payload = [encrypted.nonce.as_slice(), encrypted.ciphertext.as_slice()].concat();
+
+        Self {
+            timestamp: entry.timestamp,
+            level: entry.level.clone(),
+            message,
+            user_id: entry.user_id.as_deref().map(|user| redactor.redact_value(user).to_string()),
+            request_id: entry.request_id.clone(),
+            metadata_keys,
+            payload_ref: hex::encode(Sha256::digest(&payload)),
+            encrypted_payload: STANDARD.encode(&payload),
+        }
+    }
+}
+
+/// Connection to the configured SIEM
+enum Transport {
+    SyslogTcp {
+        address: String,
+        hostname: String,
+        app_name: String,
+        timeout: Duration,
+        stream: Option<TcpStream>,
+    },
+    Hec {
+        client: HttpClient,
+        url: reqwest::Url,
+        token: String,
+        hostname: String,
+        source: String,
+    },
+    Webhook {
+        client: HttpClient,
+        url: reqwest::Url,
+        secret: Option<String>,
+    },
+}
+
+impl Transport {
+    fn from_config(sink: SiemSinkKind, config: &SiemConfig) -> Result<Self, SiemError> {
+        let timeout = Duration::from_millis(config.timeout_ms);
+        let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "-".to_string());
+        let url = || {
+            let url = config.url.as_deref().ok_or_else(|| SiemError::Config("siem.url must be set".to_string()))?;
+            reqwest::Url::parse(url).map_err(|e| SiemError::Config(format!("siem.url: {}", e)))
+        };
+        let client = || {
+            crate::egress::client(reqwest::Client::builder().timeout(timeout)).map_err(|e| SiemError::Config(e.to_string()))
+        };
+
+        Ok(match sink {
+            SiemSinkKind::SyslogTcp => Self::SyslogTcp {
+                address: config
+                    .address
+                    .clone()
+                    .ok_or_else(|| SiemError::Config("siem.address must be set".to_string()))?,
+                hostname,
+                app_name: config.source.clone(),
+                timeout,
+                stream: None,
+            },
+            SiemSinkKind::Hec => Self::Hec {
+                client: client()?,
+                url: url()?,
+                token: config
+                    .token
+                    .clone()
+                    .ok_or_else(|| SiemError::Config("siem.token must be set".to_string()))?,
+                hostname,
+                source: config.source.clone(),
+            },
+            SiemSinkKind::Webhook => Self::Webhook {
+                client: client()?,
+                url: url()?,
+                secret: config.token.clone(),
+            },
+        })
+    }
+
+    /// Deliver a batch, reconnecting to a syslog collector when needed
+    async fn send(&mut self, batch: &[SiemEvent]) -> Result<(), SiemError> {
+        match self {
+            Self::SyslogTcp { address, hostname, app_name, timeout, stream } => {
+                let mut frames = String::new();
+                for event in batch {
+                    let message = syslog_message(event, hostname, app_name)?;
+                    frames.push_str(&format!("{} {}", message.len(), message));
+                }
+                let delivery = async {
+                    if stream.is_none() {
+                        *stream = Some(TcpStream::connect(address.as_str()).await?);
+                    }
+                    let connection = stream.as_mut().expect("connected above");
+                    connection.write_all(frames.as_bytes()).await?;
+                    connection.flush().await
+                };
+                let result = match tokio::time::timeout(*timeout, delivery).await {
+                    Ok(result) => result.map_err(|e| SiemError::Delivery(e.to_string())),
+                    Err(_) => Err(SiemError::Delivery(format!("no progress within {:?}", timeout))),
+                };
+                if result.is_err() {
+                    *stream = None;
+                }
+                result
+            }
+            Self::Hec { client, url, token, hostname, source } => {
+                let mut body = String::new();
+                for event in batch {
+                    let record = serde_json::json!({
+                        "time": event.timestamp.timestamp_millis() as f64 / 1000.0,
+                        "host": hostname,
+                        "source": source,
+                        "sourcetype": HEC_SOURCETYPE,
+                        "event": event,
+                    });
+                    body.push_str(&record.to_string());
+                    body.push('\n');
+                }
+                let request = client
+                    .post(url.as_str())
+                    .map_err(|e| SiemError::Delivery(e.to_string()))?
+                    .header("authorization", format!("Splunk {}", token))
+                    .header("content-type", "application/json")
+                    .body(body);
+                check_response(request.send().await)
+            }
+            Self::Webhook { client, url, secret } => {
+                let body = serde_json::to_vec(batch).map_err(|e| SiemError::Delivery(e.to_string()))?;
+                let mut request = client
+                    .post(url.as_str())
+                    .map_err(|e| SiemError::Delivery(e.to_string()))?
+                    .header("content-type", "application/json");
+                if let Some(secret) = secret {
+                    let signature = crate::webhooks::sign_payload(secret, Utc::now().timestamp(), &body);
+                    request = request.header(crate::webhooks::SIGNATURE_HEADER, signature);
+                }
+                check_response(request.body(body).send().await)
+            }
+        }
+    }
+}
+
+/// Treat anything but a success status as a failed delivery
+fn check_response(response: Result<reqwest::Response, reqwest::Error>) -> Result<(), SiemError> {
+    match response {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => Err(SiemError::Delivery(format!("collector answered {}", response.status()))),
+        Err(e) => Err(SiemError::Delivery(e.to_string())),
+    }
+}
+
+/// Format an event as an RFC 5424 message on the `authpriv` facility
+fn syslog_message(event: &SiemEvent, hostname: &str, app_name: &str) -> Result<String, SiemError> {
+    const FACILITY: u8 = 10;
+    let severity = match event.level {
+        LogLevel::Critical => 2,
+        LogLevel::Error => 3,
+        LogLevel::Warning => 4,
+        LogLevel::Audit => 5,
+        LogLevel::Info => 6,
+    };
+    Ok(format!(
+        "<{}>1 {} {} {} - {:?} - {}",
+        FACILITY * 8 + severity,
+        event.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        hostname,
+        app_name,
+        event.level,
+        serde_json::to_string(event).map_err(|e| SiemError::Delivery(e.to_string()))?,
+    ))
+}
+
+/// Buffers security events and forwards them to the SIEM in the background
+pub struct SiemForwarder {
+    sink: SiemSinkKind,
+    transport: tokio::sync::Mutex<Transport>,
+    buffer: Mutex<VecDeque<SiemEvent>>,
+    capacity: usize,
+    batch_size: usize,
+    retry_base: Duration,
+    retry_max: Duration,
+    pending: Notify,
+}
+
+impl SiemForwarder {
+    /// Forwarder for the configured sink, or `None` when forwarding is disabled
+    pub fn from_config(config: &SiemConfig) -> Result<Option<Self>, SiemError> {
+        let Some(sink) = config.sink else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            sink,
+            transport: tokio::sync::Mutex::new(Transport::from_config(sink, config)?),
+            buffer: Mutex::new(VecDeque::new()),
+            capacity: config.buffer_capacity.max(1),
+            batch_size: config.batch_size.max(1),
+            retry_base: Duration::from_millis(config.retry_base_ms),
+            retry_max: Duration::from_millis(config.retry_max_ms),
+            pending: Notify::new(),
+        }))
+    }
+
+    /// The sink events are forwarded to
+    pub fn sink(&self) -> SiemSinkKind {
+        self.sink
+    }
+
+    /// Events waiting to be delivered
+    pub fn buffered(&self) -> usize {
+        self.buffer.lock().unwrap().len()
+    }
+
+    /// Queue a logged entry for delivery, dropping the oldest event when the buffer is full
+    pub fn forward(&self, entry: &LogEntry, encrypted: &EncryptedLogEntry) {
+        let event = SiemEvent::summarize(entry, encrypted);
+        {
+            let mut buffer = self.buffer.lock().unwrap();
+            buffer.push_back(event);
+            self.trim(&mut buffer);
+        }
+        self.pending.notify_one();
+    }
+
+    /// Drop the oldest events beyond the capacity and update the gauge
+    fn trim(&self, buffer: &mut VecDeque<SiemEvent>) {
+        while buffer.len() > self.capacity {
+            buffer.pop_front();
+            SIEM_EVENTS_DROPPED_TOTAL.inc();
+        }
+        SIEM_BUFFERED_EVENTS.set(buffer.len() as i64);
+    }
+
+    /// Delay before retry number `failures`
+    fn retry_delay(&self, failures: u32) -> Duration {
+        self.retry_base
+            .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+            .min(self.retry_max)
+    }
+
+    /// Deliver every buffered event, returning how many were delivered
+    ///
+    /// A batch that cannot be delivered is put back at the front of the buffer.
+    pub async fn deliver_pending(&self) -> Result<usize, SiemError> {
+        let mut transport = self.transport.lock().await;
+        let mut delivered = 0;
+        loop {
+            let batch: Vec<SiemEvent> = {
+                let mut buffer = self.buffer.lock().unwrap();
+                let count = buffer.len().min(self.batch_size);
+                let batch = buffer.drain(..count).collect();
+                SIEM_BUFFERED_EVENTS.set(buffer.len() as i64);
+                batch
+            };
+            if batch.is_empty() {
+                return Ok(delivered);
+            }
+            if let Err(e) = transport.send(&batch).await {
+                let mut buffer = self.buffer.lock().unwrap();
+                for event in batch.into_iter().rev() {
+                    buffer.push_front(event);
+                }
+                self.trim(&mut buffer);
+                return Err(e);
+            }
+            delivered += batch.len();
+            SIEM_EVENTS_FORWARDED_TOTAL.inc_by(batch.len() as u64);
+        }
+    }
+
+    /// Start delivering queued events, retrying with backoff while the SIEM is unreachable
+    pub fn spawn(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut failures = 0;
+            loop {
+                if failures == 0 {
+                    self.pending.notified().await;
+                }
+                match self.deliver_pending().await {
+                    Ok(_) => failures = 0,
+                    Err(e) => {
+                        failures += 1;
+                        SIEM_DELIVERY_FAILURES_TOTAL.inc();
+                        let delay = self.retry_delay(failures);
+                        warn!("{}; {} events buffered, retrying in {:?}", e, self.buffered(), delay);
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Deliver what is still buffered before the relay exits
+    pub async fn shutdown(&self) {
+        match tokio::time::timeout(SHUTDOWN_GRACE, self.deliver_pending()).await {
+            Ok(Ok(delivered)) => info!("Forwarded {} buffered security events to the SIEM", delivered),
+            Ok(Err(e)) => warn!("{}; {} security events were not forwarded", e, self.buffered()),
+            Err(_) => warn!("SIEM did not accept {} security events in time", self.buffered()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secure_logger::SecureLogger;
+    use axum::{http::HeaderMap, routing::post, Router};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::AsyncReadExt;
+
+    fn config(sink: SiemSinkKind) -> SiemConfig {
+        SiemConfig {
+            sink: Some(sink),
+            retry_base_ms: 10,
+            retry_max_ms: 50,
+            ..SiemConfig::default()
+        }
+    }
+
+    fn log_event(logger: &SecureLogger, message: &str) {
+        let mut metadata = HashMap::new();
+        metadata.insert("ip_address".to_string(), "192.168.1.100".to_string());
+        logger
+            .log_security_event(LogLevel::Critical, message.to_string(), Some("alice".to_string()), None, metadata)
+            .unwrap();
+    }
+
+    /// Serve `collector` on a local port, returning its base URL
+    async fn serve(collector: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, collector).await.unwrap() });
+        format!("http://{}", address)
+    }
+
+    #[test]
+    fn test_events_are_summarized_without_pii_and_reference_the_encrypted_entry() {
+        // ARRANGE
+        let key = SecureLogger::generate_key();
+        let logger = SecureLogger::new(&key);
+        let mut metadata = HashMap::new();
+        metadata.insert("email".to_string(), "alice@example.com".to_string());
+
+        // ACT
+        let encrypted = logger
+            .log_security_event(
+                LogLevel::Warning,
+                "Login failed for alice@example.com".to_string(),
+                Some("alice@example.com".to_string()),
+                Some("req-1".to_string()),
+                metadata,
+            )
+            .unwrap();
+        let entry = logger.decrypt_log_entry(&encrypted).unwrap();
+        let event = SiemEvent::summarize(&entry, &encrypted);
+
+        // ASSERT: no PII in the summary, only the metadata keys
+        assert_eq!(event.message, "Login failed for [REDACTED]");
+        assert_eq!(event.user_id.as_deref(), Some("[REDACTED]"));
+        assert_eq!(event.request_id.as_deref(), Some("req-1"));
+        assert_eq!(event.metadata_keys, vec!["email"]);
+        assert!(!serde_json::to_string(&event).unwrap().contains("alice@example.com"));
+
+        // ASSERT: the payload decrypts to the full entry and matches its reference
+        let payload = STANDARD.decode(&event.encrypted_payload).unwrap();
+        assert_eq!(event.payload_ref, hex::encode(Sha256::digest(&payload)));
+        let (nonce, ciphertext) = payload.split_at(12);
+        let stored = EncryptedLogEntry {
+            nonce: nonce.to_vec(),
+            ciphertext: ciphertext.to_vec(),
+            timestamp: encrypted.timestamp,
+            level: encrypted.level.clone(),
+        };
+        assert_eq!(logger.decrypt_log_entry(&stored).unwrap(), entry);
+    }
+
+    #[tokio::test]
+    async fn test_full_buffer_drops_the_oldest_events() {
+        let mut config = config(SiemSinkKind::Webhook);
+        config.url = Some("http://127.0.0.1:9/events".to_string());
+        config.buffer_capacity = 2;
+        let forwarder = Arc::new(SiemForwarder::from_config(&config).unwrap().unwrap());
+        let logger = SecureLogger::new(&SecureLogger::generate_key()).with_forwarder(forwarder.clone());
+
+        for message in ["first", "second", "third"] {
+            log_event(&logger, message);
+        }
+
+        let buffered: Vec<String> = forwarder.buffer.lock().unwrap().iter().map(|e| e.message.clone()).collect();
+        assert_eq!(buffered, vec!["second", "third"]);
+        assert!(forwarder.deliver_pending().await.is_err());
+        assert_eq!(forwarder.buffered(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_delivery_is_retried_until_the_collector_accepts() {
+        // ARRANGE: a collector refusing the first delivery
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let received = Arc::new(Mutex::new(Vec::<(HeaderMap, Vec<SiemEvent>)>::new()));
+        let collector = Router::new().route(
+            "/events",
+            post({
+                let attempts = attempts.clone();
+                let received = received.clone();
+                move |headers: HeaderMap, axum::Json(events): axum::Json<Vec<SiemEvent>>| async move {
+                    if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                        return axum::http::StatusCode::SERVICE_UNAVAILABLE;
+                    }
+                    received.lock().unwrap().push((headers, events));
+                    axum::http::StatusCode::OK
+                }
+            }),
+        );
+        let mut config = config(SiemSinkKind::Webhook);
+        config.url = Some(format!("{}/events", serve(collector).await));
+        config.token = Some("soc-secret".to_string());
+        let forwarder = Arc::new(SiemForwarder::from_config(&config).unwrap().unwrap());
+        forwarder.clone().spawn();
+        let logger = SecureLogger::new(&SecureLogger::generate_key()).with_forwarder(forwarder.clone());
+
+        // ACT
+        log_event(&logger, "Sender quarantined");
+        for _ in 0..100 {
+            if !received.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // ASSERT
+        let received = received.lock().unwrap();
+        let (headers, events) = &received[0];
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert!(headers[crate::webhooks::SIGNATURE_HEADER].to_str().unwrap().contains("v1="));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].message, "Sender quarantined");
+        assert_eq!(events[0].metadata_keys, vec!["ip_address"]);
+        assert_eq!(forwarder.buffered(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_hec_batches_carry_the_token_and_event_envelope() {
+        let received = Arc::new(Mutex::new(Vec::<(HeaderMap, String)>::new()));
+        let collector = Router::new().route(
+            "/services/collector/event",
+            post({
+                let received = received.clone();
+                move |headers: HeaderMap, body: String| async move {
+                    received.lock().unwrap().push((headers, body));
+                }
+            }),
+        );
+        let mut config = config(SiemSinkKind::Hec);
+        config.url = Some(format!("{}/services/collector/event", serve(collector).await));
+        config.token = Some("hec-token".to_string());
+        let forwarder = Arc::new(SiemForwarder::from_config(&config).unwrap().unwrap());
+        let logger = SecureLogger::new(&SecureLogger::generate_key()).with_forwarder(forwarder.clone());
+
+        log_event(&logger, "first");
+        log_event(&logger, "second");
+        assert_eq!(forwarder.deliver_pending().await.unwrap(), 2);
+
+        let received = received.lock().unwrap();
+        let (headers, body) = &received[0];
+        assert_eq!(headers["authorization"], "Splunk hec-token");
+        let records: Vec<serde_json::Value> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["source"], "proof-messenger-relay");
+        assert_eq!(records[0]["sourcetype"], HEC_SOURCETYPE);
+        assert_eq!(records[1]["event"]["message"], "second");
+        assert_eq!(records[1]["event"]["level"], "Critical");
+    }
+
+    #[tokio::test]
+    async fn test_syslog_messages_are_octet_counted_over_tcp() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut config = config(SiemSinkKind::SyslogTcp);
+        config.address = Some(listener.local_addr().unwrap().to_string());
+        let forwarder = Arc::new(SiemForwarder::from_config(&config).unwrap().unwrap());
+        let logger = SecureLogger::new(&SecureLogger::generate_key()).with_forwarder(forwarder.clone());
+
+        log_event(&logger, "Audit log tampering detected");
+        assert_eq!(forwarder.deliver_pending().await.unwrap(), 1);
+        let (mut connection, _) = listener.accept().await.unwrap();
+        let mut received = vec![0; 4096];
+        let read = connection.read(&mut received).await.unwrap();
+        let received = String::from_utf8_lossy(&received[..read]);
+
+        let (length, message) = received.split_once(' ').unwrap();
+        assert_eq!(length.parse::<usize>().unwrap(), message.len());
+        assert!(message.starts_with("<82>1 "));
+        assert!(message.contains(" proof-messenger-relay - Critical - {"));
+        assert!(message.contains("\"message\":\"Audit log tampering detected\""));
+    }
+}
@@ -0,0 +1,1190 @@
+//! Live Subscription Module
+//!
+//! Clients subscribe to a group over a WebSocket (`GET /ws/:group_id`), or as
+//! a Server-Sent Events stream (`GET /events/:group_id`) where proxies block
+//! WebSockets, and receive every message verified for it from then on,
+//! without polling. Where neither gets through, clients long-poll
+//! `GET /messages/:group_id/poll` with a change token. Each relay keeps a
+//! broadcast channel per subscribed group, fed by the relay pipeline as
+//! messages are stored; every transport reads it through a [`Subscription`].
+//!
+//! Behind a load balancer a client is connected to one replica, while the
+//! messages for its group may be verified by any of them. With
+//! `subscriptions.backplane` set (see [`crate::config::SubscriptionsConfig`])
+//! every replica also publishes the messages it verifies to a per-group
+//! channel on a [`Backplane`], and delivers the messages the other replicas
+//! publish to its own subscribers. The relay ships with:
+//!
+//! - `redis` (`redis` feature): Redis pub/sub on the server at `redis.url`
+//! - `nats` (`nats` feature): NATS core subjects on `subscriptions.servers`
+//!
+//! The backplane carries no history; the database does. Each delivered
+//! message comes with a resume token (the SSE event ID), and a client that
+//! reconnects with its last token, or polls with its last change token, to
+//! any replica, first receives the messages it missed from the database and
+//! then continues live. The same catch-up runs when a subscriber falls more
+//! than `subscriptions.buffer` messages behind, and after a replica
+//! reconnects to the backplane, so neither slow clients nor broker outages
+//! lose messages.
+//!
+//! WebSocket subscribers acknowledge the messages they receive by sending
+//! them back by ID, which records their delivery state (see
+//! [`crate::deliveries`]).
+
+use axum::{
+    extract::{
+        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::HeaderMap,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Extension, Json, Router,
+};
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::broadcast::{
+    self,
+    error::{RecvError, TryRecvError},
+};
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth_middleware::AuthContext,
+    config::{BackplaneKind, RelayConfig},
+    database::{Database, DatabaseError, StoredMessage},
+    metrics,
+    search::accessible_groups,
+    tenancy::TenantScope,
+    AppError,
+};
+
+#[cfg(feature = "nats")]
+pub mod nats;
+#[cfg(feature = "redis")]
+pub mod redis;
+
+/// Messages read from the database per catch-up query
+const CATCH_UP_PAGE_SIZE: i64 = 100;
+
+/// Longest a message may wait to be published to the backplane
+const BACKPLANE_PUBLISH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Longest wait before reconnecting to the backplane
+const BACKPLANE_RECONNECT_MAX: Duration = Duration::from_secs(30);
+
+/// Header an SSE client reconnects with, holding the last event ID it received
+const LAST_EVENT_ID: &str = "last-event-id";
+
+/// Most messages returned by one long-poll request
+const POLL_PAGE_SIZE: usize = 100;
+
+/// Live subscription error types
+#[derive(Error, Debug)]
+pub enum SubscriptionError {
+    #[error("Live subscriptions are not enabled on this relay")]
+    Disabled,
+
+    #[error("Invalid subscription configuration: {0}")]
+    Config(String),
+
+    #[error("Unknown resume token: {0}")]
+    InvalidResumeToken(String),
+
+    #[error("Backplane error: {0}")]
+    Backplane(String),
+}
+
+/// Broker carrying verified messages between relay replicas
+#[async_trait::async_trait]
+pub trait Backplane: Send + Sync {
+    /// Publish an encoded message to the channel of its group
+    async fn publish(&self, group_id: &str, payload: Vec<u8>) -> Result<(), SubscriptionError>;
+
+    /// Receive the messages every replica publishes, for every group
+    ///
+    /// The stream ends when the connection to the broker is lost.
+    async fn subscribe(&self) -> Result<BoxStream<'static, Vec<u8>>, SubscriptionError>;
+}
+
+/// Name of a group's backplane channel
+///
+/// The group ID is hex encoded so that any ID is a single valid channel or
+/// subject token, and every group's channel matches `<prefix>.*`.
+pub fn channel_name(prefix: &str, group_id: &str) -> String {
+    format!("{}.{}", prefix, hex::encode(group_id))
+}
+
+/// A verified message as sent over the backplane
+#[derive(Serialize, Deserialize)]
+struct BackplaneMessage {
+    /// Replica that verified the message, which has already delivered it
+    origin: String,
+    message: StoredMessage,
+}
+
+/// What a group's broadcast channel carries
+#[derive(Debug, Clone)]
+enum Delivery {
+    /// A newly verified message
+    Message(Arc<StoredMessage>),
+    /// Messages may have been missed; catch up from the database
+    Resync,
+}
+
+/// Per-group broadcast channels, optionally shared with other replicas
+pub struct Subscriptions {
+    instance_id: String,
+    groups: Mutex<HashMap<String, broadcast::Sender<Delivery>>>,
+    buffer: usize,
+    ping_interval: Duration,
+    poll_timeout: Duration,
+    backplane: Option<Arc<dyn Backplane>>,
+}
+
+impl Subscriptions {
+    /// Subscriptions served by this relay alone, holding `buffer` messages per group
+    pub fn new(buffer: usize) -> Self {
+        Self {
+            instance_id: Uuid::new_v4().to_string(),
+            groups: Mutex::new(HashMap::new()),
+            buffer: buffer.max(1),
+            ping_interval: Duration::from_secs(30),
+            poll_timeout: Duration::from_secs(30),
+            backplane: None,
+        }
+    }
+
+    /// Share messages with the other replicas through `backplane`
+    ///
+    /// Call [`Subscriptions::spawn_backplane_listener`] to receive theirs.
+    pub fn with_backplane(mut self, backplane: Arc<dyn Backplane>) -> Self {
+        self.backplane = Some(backplane);
+        self
+    }
+
+    /// Send idle WebSocket subscribers a ping this often
+    pub fn with_ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    /// Hold long-poll requests open for at most `poll_timeout`
+    pub fn with_poll_timeout(mut self, poll_timeout: Duration) -> Self {
+        self.poll_timeout = poll_timeout;
+        self
+    }
+
+    /// The subscriptions a relay configuration describes
+    pub fn from_config(config: &RelayConfig) -> Result<Self, SubscriptionError> {
+        let settings = &config.subscriptions;
+        let subscriptions = Self::new(settings.buffer)
+            .with_ping_interval(Duration::from_secs(settings.ping_interval_secs))
+            .with_poll_timeout(Duration::from_secs(settings.poll_timeout_secs));
+        match settings.backplane {
+            Some(kind) => Ok(subscriptions.with_backplane(backplane(kind, config)?)),
+            None => Ok(subscriptions),
+        }
+    }
+
+    /// Whether messages are shared with other replicas
+    pub fn has_backplane(&self) -> bool {
+        self.backplane.is_some()
+    }
+
+    /// Interval between pings to idle WebSocket subscribers
+    pub fn ping_interval(&self) -> Duration {
+        self.ping_interval
+    }
+
+    /// Longest a long-poll request is held open
+    pub fn poll_timeout(&self) -> Duration {
+        self.poll_timeout
+    }
+
+    /// Receive the messages delivered to a group from now on
+    fn receiver(&self, group_id: &str) -> broadcast::Receiver<Delivery> {
+        let mut groups = self.groups.lock().unwrap();
+        groups
+            .entry(group_id.to_string())
+            .or_insert_with(|| broadcast::channel(self.buffer).0)
+            .subscribe()
+    }
+
+    /// Hand a message to this relay's subscribers of its group
+    fn deliver(&self, message: Arc<StoredMessage>) {
+        let mut groups = self.groups.lock().unwrap();
+        if let Some(sender) = groups.get(&message.group_id) {
+            // Drop the channel once its last subscriber has gone
+            if sender.send(Delivery::Message(message.clone())).is_err() {
+                groups.remove(&message.group_id);
+            }
+        }
+    }
+
+    /// Tell every subscriber of this relay to catch up from the database
+    fn resync(&self) {
+        let mut groups = self.groups.lock().unwrap();
+        groups.retain(|_, sender| sender.send(Delivery::Resync).is_ok());
+    }
+
+    /// Deliver a stored message to its subscribers on every replica
+    ///
+    /// A backplane failure is logged rather than returned: the message is
+    /// already stored, and subscribers on other replicas receive it when they
+    /// next catch up.
+    pub async fn publish(&self, message: &StoredMessage) {
+        self.deliver(Arc::new(message.clone()));
+
+        let Some(backplane) = &self.backplane else {
+            return;
+        };
+        let payload = serde_json::to_vec(&BackplaneMessage {
+            origin: self.instance_id.clone(),
+            message: message.clone(),
+        })
+        .expect("stored messages serialize to JSON");
+        let result = tokio::time::timeout(BACKPLANE_PUBLISH_TIMEOUT, backplane.publish(&message.group_id, payload))
+            .await
+            .unwrap_or_else(|_| Err(SubscriptionError::Backplane(format!("no reply within {:?}", BACKPLANE_PUBLISH_TIMEOUT))));
+        if let Err(e) = result {
+            metrics::BACKPLANE_ERRORS_TOTAL.inc();
+            warn!("Failed to publish message {} to the backplane: {}", message.id, e);
+        }
+    }
+
+    /// Deliver one message received from the backplane
+    fn receive(&self, payload: &[u8]) {
+        match serde_json::from_slice::<BackplaneMessage>(payload) {
+            // This replica delivered its own messages when it published them
+            Ok(received) if received.origin == self.instance_id => {}
+            Ok(received) => self.deliver(Arc::new(received.message)),
+            Err(e) => {
+                metrics::BACKPLANE_ERRORS_TOTAL.inc();
+                warn!("Ignoring malformed backplane message: {}", e);
+            }
+        }
+    }
+
+    /// Spawn the task delivering other replicas' messages to this relay's subscribers
+    ///
+    /// The task reconnects with exponential backoff when the backplane
+    /// connection drops, and makes every subscriber catch up from the
+    /// database once it is back. Does nothing without a backplane.
+    pub fn spawn_backplane_listener(self: Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let backplane = self.backplane.clone()?;
+        Some(tokio::spawn(async move {
+            let mut delay = Duration::from_secs(1);
+            let mut connected_before = false;
+            loop {
+                match backplane.subscribe().await {
+                    Ok(mut messages) => {
+                        if connected_before {
+                            info!("Reconnected to the subscription backplane");
+                            self.resync();
+                        }
+                        connected_before = true;
+                        delay = Duration::from_secs(1);
+                        while let Some(payload) = messages.next().await {
+                            self.receive(&payload);
+                        }
+                        warn!("Lost the subscription backplane connection; reconnecting");
+                    }
+                    Err(e) => warn!("Failed to subscribe to the backplane: {}", e),
+                }
+                metrics::BACKPLANE_ERRORS_TOTAL.inc();
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(BACKPLANE_RECONNECT_MAX);
+            }
+        }))
+    }
+}
+
+/// Backplane of a kind, if the relay was built with its feature
+#[cfg_attr(not(any(feature = "redis", feature = "nats")), allow(unused_variables))]
+fn backplane(kind: BackplaneKind, config: &RelayConfig) -> Result<Arc<dyn Backplane>, SubscriptionError> {
+    let prefix = config.subscriptions.channel_prefix.clone();
+    match kind {
+        #[cfg(feature = "redis")]
+        BackplaneKind::Redis => Ok(Arc::new(redis::RedisBackplane::new(&config.redis, prefix)?)),
+        #[cfg(feature = "nats")]
+        BackplaneKind::Nats => Ok(Arc::new(nats::NatsBackplane::new(config.subscriptions.servers.clone(), prefix))),
+        #[allow(unreachable_patterns)] // every backplane is matched above when all features are enabled
+        other => Err(SubscriptionError::Config(format!(
+            "the {} backplane requires a relay built with the `{}` feature",
+            other.feature(),
+            other.feature()
+        ))),
+    }
+}
+
+/// Deliver a stored message to live subscribers if subscriptions are enabled
+///
+/// Direct messages are left to their recipient's inbox.
+pub async fn publish_if_enabled(subscriptions: Option<&Arc<Subscriptions>>, message: &StoredMessage) {
+    if let Some(subscriptions) = subscriptions.filter(|_| !message.is_direct()) {
+        // The database marks its own copy verified when storing it
+        let message = StoredMessage { verified: true, ..message.clone() };
+        subscriptions.publish(&message).await;
+    }
+}
+
+/// One client's subscription to a group
+///
+/// Yields each message at most once, in the order the relay delivered them,
+/// reading from the database whenever live delivery may have missed some.
+pub struct Subscription {
+    db: Arc<Database>,
+    group_id: String,
+    receiver: broadcast::Receiver<Delivery>,
+    /// Position of the newest message yielded, by creation time and ID
+    cursor: (DateTime<Utc>, String),
+    catching_up: bool,
+    backlog: VecDeque<StoredMessage>,
+    /// IDs of the latest messages read from the database, which may also be
+    /// waiting in the broadcast channel
+    caught_up: VecDeque<String>,
+    caught_up_capacity: usize,
+}
+
+impl Subscription {
+    /// Subscribe to a group, after the message named by `resume` or from now on
+    ///
+    /// `resume` is the resume token of the last message the client received.
+    /// The token must name a message of the same group that is still stored.
+    pub async fn open(
+        subscriptions: &Subscriptions,
+        db: Arc<Database>,
+        group_id: String,
+        resume: Option<&str>,
+    ) -> Result<Self, AppError> {
+        // Subscribe before reading the database so nothing stored in between is missed
+        let receiver = subscriptions.receiver(&group_id);
+        let (cursor, catching_up) = match resume {
+            Some(token) => {
+                let message = match db.get_message_by_id(token).await {
+                    Ok(message) if message.group_id == group_id => message,
+                    Ok(_) | Err(DatabaseError::MessageNotFound(_)) => {
+                        return Err(SubscriptionError::InvalidResumeToken(token.to_string()).into())
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+                ((message.created_at, message.id), true)
+            }
+            None => ((Utc::now(), String::new()), false),
+        };
+
+        Ok(Self::at(subscriptions, db, group_id, receiver, cursor, catching_up))
+    }
+
+    /// Subscribe to a group, after the position named by a change token or from now on
+    ///
+    /// Change tokens come from [`Subscription::change_token`]. Unlike resume
+    /// tokens they stay valid when the message they follow is deleted.
+    pub fn open_at(
+        subscriptions: &Subscriptions,
+        db: Arc<Database>,
+        group_id: String,
+        change_token: Option<&str>,
+    ) -> Result<Self, AppError> {
+        let receiver = subscriptions.receiver(&group_id);
+        let (cursor, catching_up) = match change_token {
+            Some(token) => (parse_change_token(token)?, true),
+            None => ((Utc::now(), String::new()), false),
+        };
+
+        Ok(Self::at(subscriptions, db, group_id, receiver, cursor, catching_up))
+    }
+
+    fn at(
+        subscriptions: &Subscriptions,
+        db: Arc<Database>,
+        group_id: String,
+        receiver: broadcast::Receiver<Delivery>,
+        cursor: (DateTime<Utc>, String),
+        catching_up: bool,
+    ) -> Self {
+        Self {
+            db,
+            group_id,
+            receiver,
+            cursor,
+            catching_up,
+            backlog: VecDeque::new(),
+            caught_up: VecDeque::new(),
+            caught_up_capacity: subscriptions.buffer,
+        }
+    }
+
+    /// Token naming this subscription's position, to continue after the messages yielded so far
+    pub fn change_token(&self) -> String {
+        let (created_at, id) = &self.cursor;
+        format!("{}.{}", created_at.timestamp_nanos_opt().unwrap_or(i64::MAX), id)
+    }
+
+    /// The next message, or `None` once the relay stops delivering
+    ///
+    /// Cancel safe, so it can be awaited in a `select!` loop.
+    pub async fn next(&mut self) -> Result<Option<StoredMessage>, AppError> {
+        loop {
+            if let Some(message) = self.try_next() {
+                return Ok(Some(message));
+            }
+            if self.catching_up {
+                let page = self
+                    .db
+                    .get_messages_by_group_after(&self.group_id, (self.cursor.0, &self.cursor.1), CATCH_UP_PAGE_SIZE)
+                    .await?;
+                if page.is_empty() {
+                    self.catching_up = false;
+                }
+                self.backlog.extend(page);
+                continue;
+            }
+            match self.receiver.recv().await {
+                Ok(Delivery::Message(message)) => {
+                    if let Some(message) = self.accept(&message) {
+                        return Ok(Some(message));
+                    }
+                }
+                Ok(Delivery::Resync) | Err(RecvError::Lagged(_)) => self.catching_up = true,
+                Err(RecvError::Closed) => return Ok(None),
+            }
+        }
+    }
+
+    /// The next message if one is ready without waiting for the database or the relay
+    pub fn try_next(&mut self) -> Option<StoredMessage> {
+        loop {
+            if let Some(message) = self.backlog.pop_front() {
+                self.remember_caught_up(&message.id);
+                self.advance(&message);
+                return Some(message);
+            }
+            if self.catching_up {
+                return None;
+            }
+            match self.receiver.try_recv() {
+                Ok(Delivery::Message(message)) => {
+                    if let Some(message) = self.accept(&message) {
+                        return Some(message);
+                    }
+                }
+                Ok(Delivery::Resync) | Err(TryRecvError::Lagged(_)) => self.catching_up = true,
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// A live message to yield, unless it was already read from the database
+    fn accept(&mut self, message: &Arc<StoredMessage>) -> Option<StoredMessage> {
+        if self.caught_up.contains(&message.id) {
+            return None;
+        }
+        self.advance(message);
+        Some(message.as_ref().clone())
+    }
+
+    /// Move the cursor past a yielded message
+    fn advance(&mut self, message: &StoredMessage) {
+        if (message.created_at, message.id.as_str()) > (self.cursor.0, self.cursor.1.as_str()) {
+            self.cursor = (message.created_at, message.id.clone());
+        }
+    }
+
+    fn remember_caught_up(&mut self, message_id: &str) {
+        if self.caught_up.len() == self.caught_up_capacity {
+            self.caught_up.pop_front();
+        }
+        self.caught_up.push_back(message_id.to_string());
+    }
+}
+
+/// Position named by a change token
+fn parse_change_token(token: &str) -> Result<(DateTime<Utc>, String), SubscriptionError> {
+    let invalid = || SubscriptionError::InvalidResumeToken(token.to_string());
+    let (nanos, id) = token.split_once('.').ok_or_else(invalid)?;
+    let nanos = nanos.parse::<i64>().map_err(|_| invalid())?;
+    Ok((DateTime::from_timestamp_nanos(nanos), id.to_string()))
+}
+
+/// Frame sent to WebSocket subscribers for each message
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LiveMessage {
+    /// Token to pass as `resume` when reconnecting after this message
+    pub resume_token: String,
+    pub message: StoredMessage,
+}
+
+impl From<StoredMessage> for LiveMessage {
+    fn from(message: StoredMessage) -> Self {
+        Self {
+            resume_token: message.id.clone(),
+            message,
+        }
+    }
+}
+
+/// Query parameters for subscribing to a group
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SubscribeQuery {
+    /// Resume token of the last message received before reconnecting
+    pub resume: Option<String>,
+    /// Recipient acknowledging messages on a WebSocket; authenticated
+    /// subscribers are always their user ID
+    pub recipient: Option<String>,
+}
+
+impl SubscribeQuery {
+    /// Resume token of an SSE request, preferring the `Last-Event-ID` header
+    /// that browsers send when they reconnect
+    fn resume_token<'a>(&'a self, headers: &'a HeaderMap) -> Option<&'a str> {
+        headers
+            .get(LAST_EVENT_ID)
+            .and_then(|value| value.to_str().ok())
+            .filter(|token| !token.is_empty())
+            .or(self.resume.as_deref())
+    }
+}
+
+/// Query parameters for long-polling a group
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PollQuery {
+    /// Change token returned by the previous poll
+    pub since_token: Option<String>,
+    /// Seconds to wait for a message, at most `subscriptions.poll_timeout_secs`
+    pub timeout_secs: Option<u64>,
+}
+
+impl PollQuery {
+    /// How long to hold the request open
+    fn timeout(&self, limit: Duration) -> Duration {
+        self.timeout_secs.map_or(limit, |secs| Duration::from_secs(secs).min(limit))
+    }
+}
+
+/// Response to a long-poll request
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PollResponse {
+    /// Messages verified since the change token, oldest first
+    pub messages: Vec<StoredMessage>,
+    /// Token to pass as `since_token` in the next poll
+    pub change_token: String,
+}
+
+/// Counts a subscriber in the `live_subscribers` gauge while it is alive
+struct SubscriberGauge;
+
+impl SubscriberGauge {
+    fn new() -> Self {
+        metrics::LIVE_SUBSCRIBERS.inc();
+        Self
+    }
+}
+
+impl Drop for SubscriberGauge {
+    fn drop(&mut self) {
+        metrics::LIVE_SUBSCRIBERS.dec();
+    }
+}
+
+/// Create router for live subscription endpoints
+pub fn subscription_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/ws/:group_id", get(subscribe_handler))
+        .route("/events/:group_id", get(events_handler))
+        .route("/messages/:group_id/poll", get(poll_handler))
+}
+
+/// Create router for authenticated live subscription endpoints
+pub fn authenticated_subscription_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/ws/:group_id", get(authenticated_subscribe_handler))
+        .route("/events/:group_id", get(authenticated_events_handler))
+        .route("/messages/:group_id/poll", get(authenticated_poll_handler))
+}
+
+fn enabled(subscriptions: Option<Extension<Arc<Subscriptions>>>) -> Result<Arc<Subscriptions>, AppError> {
+    let Extension(subscriptions) = subscriptions.ok_or(SubscriptionError::Disabled)?;
+    Ok(subscriptions)
+}
+
+/// Handler to subscribe to a group's messages over a WebSocket
+#[utoipa::path(
+    get,
+    path = "/ws/{group_id}",
+    operation_id = "subscribeGroup",
+    tag = "subscriptions",
+    params(("group_id" = String, Path, description = "Group ID"), SubscribeQuery),
+    responses(
+        (status = 101, description = "WebSocket streaming the group's new messages"),
+    )
+)]
+#[instrument(skip_all)]
+async fn subscribe_handler(
+    State(db): State<Arc<Database>>,
+    subscriptions: Option<Extension<Arc<Subscriptions>>>,
+    tenant: TenantScope,
+    Path(group_id): Path<String>,
+    Query(params): Query<SubscribeQuery>,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    info!("Subscribing to group: {}", group_id);
+
+    let subscriptions = enabled(subscriptions)?;
+    let subscription = Subscription::open(&subscriptions, db, tenant.group_id(&group_id), params.resume.as_deref()).await?;
+    let ping_interval = subscriptions.ping_interval();
+    Ok(upgrade.on_upgrade(move |socket| serve_subscriber(socket, subscription, ping_interval, params.recipient)))
+}
+
+/// Authenticated handler to subscribe to a group's messages over a WebSocket
+///
+/// Requires the `message:read` scope. Callers holding `group:<id>` scopes
+/// may only subscribe to those groups.
+#[instrument(skip_all)]
+#[allow(clippy::too_many_arguments)] // one extractor per request part
+async fn authenticated_subscribe_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    subscriptions: Option<Extension<Arc<Subscriptions>>>,
+    tenant: TenantScope,
+    Path(group_id): Path<String>,
+    Query(params): Query<SubscribeQuery>,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    info!("Authenticated user {} subscribing to group: {}", auth.user_id, group_id);
+
+    if accessible_groups(&auth).is_some_and(|groups| !groups.contains(&group_id)) {
+        return Err(AppError::InsufficientScope("Insufficient permissions to subscribe to this group".to_string()));
+    }
+
+    let subscriptions = enabled(subscriptions)?;
+    let subscription = Subscription::open(&subscriptions, db, tenant.group_id(&group_id), params.resume.as_deref()).await?;
+    let ping_interval = subscriptions.ping_interval();
+    let recipient = Some(auth.user_id);
+    Ok(upgrade.on_upgrade(move |socket| serve_subscriber(socket, subscription, ping_interval, recipient)))
+}
+
+/// Handler to subscribe to a group's messages as a Server-Sent Events stream
+#[utoipa::path(
+    get,
+    path = "/events/{group_id}",
+    operation_id = "streamGroupEvents",
+    tag = "subscriptions",
+    params(("group_id" = String, Path, description = "Group ID"), SubscribeQuery),
+    responses(
+        (status = 200, description = "Server-sent events for the group's new messages", body = String, content_type = "text/event-stream"),
+    )
+)]
+#[instrument(skip_all)]
+async fn events_handler(
+    State(db): State<Arc<Database>>,
+    subscriptions: Option<Extension<Arc<Subscriptions>>>,
+    tenant: TenantScope,
+    Path(group_id): Path<String>,
+    Query(params): Query<SubscribeQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    info!("Streaming events for group: {}", group_id);
+
+    let subscriptions = enabled(subscriptions)?;
+    let resume = params.resume_token(&headers);
+    let subscription = Subscription::open(&subscriptions, db, tenant.group_id(&group_id), resume).await?;
+    Ok(event_stream(subscription, subscriptions.ping_interval()))
+}
+
+/// Authenticated handler to subscribe to a group's messages as a Server-Sent Events stream
+///
+/// Requires the `message:read` scope. Callers holding `group:<id>` scopes
+/// may only subscribe to those groups.
+#[instrument(skip_all)]
+#[allow(clippy::too_many_arguments)] // one extractor per request part
+async fn authenticated_events_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    subscriptions: Option<Extension<Arc<Subscriptions>>>,
+    tenant: TenantScope,
+    Path(group_id): Path<String>,
+    Query(params): Query<SubscribeQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    info!("Authenticated user {} streaming events for group: {}", auth.user_id, group_id);
+
+    if accessible_groups(&auth).is_some_and(|groups| !groups.contains(&group_id)) {
+        return Err(AppError::InsufficientScope("Insufficient permissions to subscribe to this group".to_string()));
+    }
+
+    let subscriptions = enabled(subscriptions)?;
+    let resume = params.resume_token(&headers);
+    let subscription = Subscription::open(&subscriptions, db, tenant.group_id(&group_id), resume).await?;
+    Ok(event_stream(subscription, subscriptions.ping_interval()))
+}
+
+/// Handler to wait for a group's next messages
+#[utoipa::path(
+    get,
+    path = "/messages/{group_id}/poll",
+    operation_id = "pollGroupMessages",
+    tag = "subscriptions",
+    params(("group_id" = String, Path, description = "Group ID"), PollQuery),
+    responses(
+        (status = 200, description = "Messages since the change token", body = PollResponse),
+    )
+)]
+#[instrument(skip_all)]
+async fn poll_handler(
+    State(db): State<Arc<Database>>,
+    subscriptions: Option<Extension<Arc<Subscriptions>>>,
+    tenant: TenantScope,
+    Path(group_id): Path<String>,
+    Query(params): Query<PollQuery>,
+) -> Result<Json<PollResponse>, AppError> {
+    info!("Long-polling group: {}", group_id);
+
+    let subscriptions = enabled(subscriptions)?;
+    let subscription = Subscription::open_at(&subscriptions, db, tenant.group_id(&group_id), params.since_token.as_deref())?;
+    poll(subscription, params.timeout(subscriptions.poll_timeout())).await.map(Json)
+}
+
+/// Authenticated handler to wait for a group's next messages
+///
+/// Requires the `message:read` scope. Callers holding `group:<id>` scopes
+/// may only poll those groups.
+#[instrument(skip_all)]
+async fn authenticated_poll_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    subscriptions: Option<Extension<Arc<Subscriptions>>>,
+    tenant: TenantScope,
+    Path(group_id): Path<String>,
+    Query(params): Query<PollQuery>,
+) -> Result<Json<PollResponse>, AppError> {
+    info!("Authenticated user {} long-polling group: {}", auth.user_id, group_id);
+
+    if accessible_groups(&auth).is_some_and(|groups| !groups.contains(&group_id)) {
+        return Err(AppError::InsufficientScope("Insufficient permissions to poll this group".to_string()));
+    }
+
+    let subscriptions = enabled(subscriptions)?;
+    let subscription = Subscription::open_at(&subscriptions, db, tenant.group_id(&group_id), params.since_token.as_deref())?;
+    poll(subscription, params.timeout(subscriptions.poll_timeout())).await.map(Json)
+}
+
+/// Wait up to `timeout` for a subscription's next message, then take every message ready with it
+async fn poll(mut subscription: Subscription, timeout: Duration) -> Result<PollResponse, AppError> {
+    let mut messages = Vec::new();
+    if let Ok(first) = tokio::time::timeout(timeout, subscription.next()).await {
+        messages.extend(first?);
+        while messages.len() < POLL_PAGE_SIZE {
+            match subscription.try_next() {
+                Some(message) => messages.push(message),
+                None => break,
+            }
+        }
+    }
+
+    Ok(PollResponse { messages, change_token: subscription.change_token() })
+}
+
+/// Server-Sent Events response streaming a subscription
+///
+/// Each message is a `message` event whose ID is its resume token, so a
+/// reconnecting `EventSource` resumes where it left off. Comments are sent
+/// every `ping_interval` to keep idle connections open through proxies.
+fn event_stream(subscription: Subscription, ping_interval: Duration) -> Response {
+    let events = futures::stream::unfold((subscription, SubscriberGauge::new()), |(mut subscription, gauge)| async move {
+        match subscription.next().await {
+            Ok(Some(message)) => {
+                let event = Event::default()
+                    .event("message")
+                    .id(message.id.clone())
+                    .json_data(&message)
+                    .expect("stored messages serialize to JSON");
+                Some((Ok::<_, Infallible>(event), (subscription, gauge)))
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Subscription to group {} failed: {}", subscription.group_id, e);
+                None
+            }
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::new().interval(ping_interval)).into_response()
+}
+
+/// Stream a subscription to a WebSocket until either side closes it
+///
+/// Text frames from the client acknowledge messages for `recipient` (see
+/// [`crate::deliveries`]).
+async fn serve_subscriber(mut socket: WebSocket, mut subscription: Subscription, ping_interval: Duration, recipient: Option<String>) {
+    let _gauge = SubscriberGauge::new();
+    let mut ping = tokio::time::interval_at(tokio::time::Instant::now() + ping_interval, ping_interval);
+
+    loop {
+        tokio::select! {
+            next = subscription.next() => match next {
+                Ok(Some(message)) => {
+                    let frame = serde_json::to_string(&LiveMessage::from(message)).expect("stored messages serialize to JSON");
+                    if socket.send(WsMessage::Text(frame)).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Subscription to group {} failed: {}", subscription.group_id, e);
+                    break;
+                }
+            },
+            incoming = socket.recv() => match incoming {
+                // Pings are answered by the WebSocket itself; other client frames are ignored
+                Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(WsMessage::Text(frame))) => {
+                    if let Err(e) = crate::deliveries::acknowledge(&subscription.db, &subscription.group_id, recipient.as_deref(), &frame).await {
+                        warn!("Acknowledgement on group {} rejected: {}", subscription.group_id, e);
+                    }
+                }
+                Some(Ok(_)) => {}
+            },
+            _ = ping.tick() => {
+                if socket.send(WsMessage::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Message;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_protocol::proof::make_secure_proof;
+    use tokio::time::timeout;
+
+    async fn database() -> Arc<Database> {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        Arc::new(db)
+    }
+
+    /// Store a message in `group_id` the way the relay pipeline does
+    async fn store(db: &Database, group_id: &str, body: &str) -> StoredMessage {
+        let mut message = StoredMessage::from(Message {
+            sender: "aa".repeat(32),
+            context: hex::encode(body),
+            body: body.to_string(),
+            proof: "cc".repeat(64),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        });
+        message.group_id = group_id.to_string();
+        db.store_message(message.clone()).await.unwrap();
+        db.get_message_by_id(&message.id).await.unwrap()
+    }
+
+    async fn next_body(subscription: &mut Subscription) -> String {
+        timeout(Duration::from_secs(5), subscription.next()).await.unwrap().unwrap().unwrap().body
+    }
+
+    /// Backplane connecting the replicas of one test
+    #[derive(Clone)]
+    struct MemoryBackplane {
+        sender: broadcast::Sender<Vec<u8>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Backplane for MemoryBackplane {
+        async fn publish(&self, _group_id: &str, payload: Vec<u8>) -> Result<(), SubscriptionError> {
+            let _ = self.sender.send(payload);
+            Ok(())
+        }
+
+        async fn subscribe(&self) -> Result<BoxStream<'static, Vec<u8>>, SubscriptionError> {
+            let receiver = self.sender.subscribe();
+            Ok(futures::stream::unfold(receiver, |mut receiver| async move {
+                receiver.recv().await.ok().map(|payload| (payload, receiver))
+            })
+            .boxed())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_receives_its_groups_messages() {
+        let db = database().await;
+        let subscriptions = Subscriptions::new(16);
+        let mut subscription = Subscription::open(&subscriptions, db.clone(), "team".to_string(), None).await.unwrap();
+
+        subscriptions.publish(&store(&db, "other", "elsewhere").await).await;
+        subscriptions.publish(&store(&db, "team", "first").await).await;
+
+        assert_eq!(next_body(&mut subscription).await, "first");
+    }
+
+    #[tokio::test]
+    async fn test_messages_reach_subscribers_on_other_replicas() {
+        // ARRANGE: Two replicas sharing a backplane, with a subscriber on each
+        let db = database().await;
+        let backplane = Arc::new(MemoryBackplane { sender: broadcast::channel(16).0 });
+        let first = Arc::new(Subscriptions::new(16).with_backplane(backplane.clone()));
+        let second = Arc::new(Subscriptions::new(16).with_backplane(backplane.clone()));
+        first.clone().spawn_backplane_listener();
+        second.clone().spawn_backplane_listener();
+        while backplane.sender.receiver_count() < 2 {
+            tokio::task::yield_now().await;
+        }
+        let mut on_first = Subscription::open(&first, db.clone(), "team".to_string(), None).await.unwrap();
+        let mut on_second = Subscription::open(&second, db.clone(), "team".to_string(), None).await.unwrap();
+
+        // ACT: Each replica verifies one message
+        first.publish(&store(&db, "team", "from first").await).await;
+        second.publish(&store(&db, "team", "from second").await).await;
+
+        // ASSERT: Both subscribers see both messages exactly once
+        let mut seen_on_first = vec![next_body(&mut on_first).await, next_body(&mut on_first).await];
+        let mut seen_on_second = vec![next_body(&mut on_second).await, next_body(&mut on_second).await];
+        seen_on_first.sort();
+        seen_on_second.sort();
+        assert_eq!(seen_on_first, vec!["from first", "from second"]);
+        assert_eq!(seen_on_second, vec!["from first", "from second"]);
+        assert!(timeout(Duration::from_millis(100), on_first.next()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resume_token_replays_missed_messages_once() {
+        // ARRANGE: A client that received "first" and then disconnected
+        let db = database().await;
+        let subscriptions = Subscriptions::new(16);
+        let first = store(&db, "team", "first").await;
+        store(&db, "team", "second").await;
+        store(&db, "team", "third").await;
+
+        // ACT: Reconnect with the token of "first" while a new message arrives
+        let mut subscription =
+            Subscription::open(&subscriptions, db.clone(), "team".to_string(), Some(&first.id)).await.unwrap();
+        subscriptions.publish(&store(&db, "team", "fourth").await).await;
+
+        // ASSERT: The missed messages come first, and "fourth" is not repeated
+        assert_eq!(next_body(&mut subscription).await, "second");
+        assert_eq!(next_body(&mut subscription).await, "third");
+        assert_eq!(next_body(&mut subscription).await, "fourth");
+        assert!(timeout(Duration::from_millis(100), subscription.next()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_catches_up_from_the_database() {
+        let db = database().await;
+        let subscriptions = Subscriptions::new(2);
+        let mut subscription = Subscription::open(&subscriptions, db.clone(), "team".to_string(), None).await.unwrap();
+
+        for index in 0..5 {
+            subscriptions.publish(&store(&db, "team", &format!("message {}", index)).await).await;
+        }
+
+        let mut bodies = Vec::new();
+        for _ in 0..5 {
+            bodies.push(next_body(&mut subscription).await);
+        }
+        assert_eq!(bodies, (0..5).map(|index| format!("message {}", index)).collect::<Vec<_>>());
+        assert!(timeout(Duration::from_millis(100), subscription.next()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resume_token_must_name_a_message_of_the_group() {
+        let db = database().await;
+        let subscriptions = Subscriptions::new(16);
+        let elsewhere = store(&db, "other", "elsewhere").await;
+
+        for token in [elsewhere.id.as_str(), "missing"] {
+            let error = Subscription::open(&subscriptions, db.clone(), "team".to_string(), Some(token)).await.err().unwrap();
+            assert_eq!(error.status(), axum::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_websocket_subscriber_receives_relayed_messages() {
+        // ARRANGE: A relay with subscriptions and a WebSocket client on a group
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let db = database().await;
+        let app = crate::create_app(db.clone()).layer(Extension(Arc::new(Subscriptions::new(16))));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = app.clone();
+        tokio::spawn(async move { axum::serve(listener, server).await.unwrap() });
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws/default", address)).await.unwrap();
+
+        // ACT: Relay a signed message
+        let keypair = generate_secure_keypair_with_seed(45);
+        let proof = make_secure_proof(&keypair, b"approve:7").unwrap();
+        let body = serde_json::json!({
+            "sender": hex::encode(keypair.public_key_bytes()),
+            "context": hex::encode(b"approve:7"),
+            "body": "approved",
+            "proof": hex::encode(proof.to_bytes()),
+        });
+        let request = Request::post("/relay")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        // ASSERT: The subscriber receives it with its resume token
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let frame = timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap();
+        let live: LiveMessage = serde_json::from_str(frame.to_text().unwrap()).unwrap();
+        assert_eq!(live.message.body, "approved");
+        assert!(live.message.verified);
+        assert_eq!(live.resume_token, live.message.id);
+    }
+
+    #[tokio::test]
+    async fn test_subscribing_requires_enabled_subscriptions() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let app = crate::create_app(database().await);
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let result = tokio_tungstenite::connect_async(format!("ws://{}/ws/default", address)).await;
+
+        match result {
+            Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+                assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND)
+            }
+            other => panic!("expected a 404 response, got {:?}", other.map(|(_, response)| response.status())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_resumes_from_last_event_id() {
+        // ARRANGE: A relay with subscriptions, and a client that received "first"
+        use axum::body::Body;
+        use axum::http::{header::CONTENT_TYPE, Request};
+        use tower::ServiceExt;
+
+        let db = database().await;
+        let app = crate::create_app(db.clone()).layer(Extension(Arc::new(Subscriptions::new(16))));
+        let first = store(&db, "default", "first").await;
+        let second = store(&db, "default", "second").await;
+
+        // ACT: Reconnect the event stream with the ID of "first"
+        let request = Request::get("/events/default?resume=ignored")
+            .header(LAST_EVENT_ID, first.id.as_str())
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        // ASSERT: The stream replays "second" as a message event carrying its ID
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/event-stream");
+        let mut body = response.into_body().into_data_stream();
+        let mut text = String::new();
+        while !text.contains("\n\n") {
+            let chunk = timeout(Duration::from_secs(5), body.next()).await.unwrap().unwrap().unwrap();
+            text.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        assert!(text.contains("event: message\n"), "{}", text);
+        assert!(text.contains(&format!("id: {}\n", second.id)), "{}", text);
+        let data = text.lines().find_map(|line| line.strip_prefix("data: ")).unwrap();
+        let message: StoredMessage = serde_json::from_str(data).unwrap();
+        assert_eq!(message.body, "second");
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_requires_enabled_subscriptions() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let app = crate::create_app(database().await);
+
+        let response = app.oneshot(Request::get("/events/default").body(Body::empty()).unwrap()).await.unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_poll_continues_from_its_change_token() {
+        // ARRANGE: A relay with subscriptions and a client that has polled once
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let db = database().await;
+        let subscriptions = Arc::new(Subscriptions::new(16));
+        let app = crate::create_app(db.clone()).layer(Extension(subscriptions.clone()));
+        let poll = |uri: String| {
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+                assert_eq!(response.status(), axum::http::StatusCode::OK);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<PollResponse>(&body).unwrap()
+            }
+        };
+        let idle = poll("/messages/default/poll?timeout_secs=0".to_string()).await;
+
+        // ACT: Two messages are stored, then poll again, and once more while a message arrives
+        store(&db, "default", "first").await;
+        store(&db, "default", "second").await;
+        let missed = poll(format!("/messages/default/poll?since_token={}", idle.change_token)).await;
+        let waiting = tokio::spawn(poll(format!("/messages/default/poll?since_token={}", missed.change_token)));
+        while subscriptions.groups.lock().unwrap().get("default").map_or(0, |sender| sender.receiver_count()) == 0 {
+            tokio::task::yield_now().await;
+        }
+        subscriptions.publish(&store(&db, "default", "third").await).await;
+        let live = waiting.await.unwrap();
+
+        // ASSERT: Each poll returns only the messages after its token
+        assert!(idle.messages.is_empty());
+        let bodies = |response: &PollResponse| response.messages.iter().map(|m| m.body.clone()).collect::<Vec<_>>();
+        assert_eq!(bodies(&missed), vec!["first", "second"]);
+        assert_eq!(bodies(&live), vec!["third"]);
+        assert_ne!(live.change_token, missed.change_token);
+    }
+
+    #[tokio::test]
+    async fn test_poll_times_out_without_new_messages() {
+        let db = database().await;
+        let subscriptions = Subscriptions::new(16);
+        store(&db, "team", "first").await;
+        let subscription = Subscription::open_at(&subscriptions, db.clone(), "team".to_string(), None).unwrap();
+        let token = subscription.change_token();
+
+        let response = poll(subscription, Duration::from_millis(50)).await.unwrap();
+
+        assert!(response.messages.is_empty());
+        assert_eq!(response.change_token, token);
+    }
+
+    #[tokio::test]
+    async fn test_change_token_must_be_well_formed() {
+        let db = database().await;
+        let subscriptions = Subscriptions::new(16);
+
+        for token in ["", "not-a-token", "abc.123"] {
+            let error = Subscription::open_at(&subscriptions, db.clone(), "team".to_string(), Some(token)).err().unwrap();
+            assert_eq!(error.status(), axum::http::StatusCode::BAD_REQUEST);
+        }
+    }
+}
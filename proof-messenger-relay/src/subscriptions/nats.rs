@@ -0,0 +1,86 @@
+//! NATS backplane
+//!
+//! Each group has its own subject, named by [`super::channel_name`], and every
+//! replica subscribes to all of them with a `<prefix>.*` wildcard. Core NATS
+//! keeps no messages, so a replica that is disconnected misses what is
+//! published meanwhile. The client reconnects on its own; the subscription
+//! stream then ends so that the listener subscribes again and has its
+//! subscribers catch up from the database.
+
+use async_nats::{Client, ConnectOptions, Event};
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use tokio::sync::{broadcast, OnceCell};
+
+use super::{channel_name, Backplane, SubscriptionError};
+
+/// Fans messages out through the NATS servers in `subscriptions.servers`
+pub struct NatsBackplane {
+    servers: Vec<String>,
+    subject_prefix: String,
+    client: OnceCell<Client>,
+    connected: broadcast::Sender<()>,
+}
+
+impl NatsBackplane {
+    /// A backplane on the NATS servers at `servers`, using subjects under `subject_prefix`
+    ///
+    /// No connection is made until the first message is published or the
+    /// listener subscribes.
+    pub fn new(servers: Vec<String>, subject_prefix: String) -> Self {
+        Self {
+            servers,
+            subject_prefix,
+            client: OnceCell::new(),
+            connected: broadcast::channel(1).0,
+        }
+    }
+
+    /// Connect to NATS, reusing the connection once established
+    async fn client(&self) -> Result<&Client, SubscriptionError> {
+        self.client
+            .get_or_try_init(|| async {
+                let connected = self.connected.clone();
+                ConnectOptions::new()
+                    .event_callback(move |event| {
+                        let connected = connected.clone();
+                        async move {
+                            if matches!(event, Event::Connected) {
+                                let _ = connected.send(());
+                            }
+                        }
+                    })
+                    .connect(&self.servers)
+                    .await
+                    .map_err(|e| SubscriptionError::Backplane(e.to_string()))
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl Backplane for NatsBackplane {
+    async fn publish(&self, group_id: &str, payload: Vec<u8>) -> Result<(), SubscriptionError> {
+        self.client()
+            .await?
+            .publish(channel_name(&self.subject_prefix, group_id), payload.into())
+            .await
+            .map_err(|e| SubscriptionError::Backplane(e.to_string()))
+    }
+
+    async fn subscribe(&self) -> Result<BoxStream<'static, Vec<u8>>, SubscriptionError> {
+        let client = self.client().await?;
+        let mut reconnected = self.connected.subscribe();
+        let subscriber = client
+            .subscribe(format!("{}.*", self.subject_prefix))
+            .await
+            .map_err(|e| SubscriptionError::Backplane(e.to_string()))?;
+
+        Ok(subscriber
+            .map(|message| message.payload.to_vec())
+            .take_until(async move {
+                let _ = reconnected.recv().await;
+            })
+            .boxed())
+    }
+}
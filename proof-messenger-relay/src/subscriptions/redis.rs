@@ -0,0 +1,94 @@
+//! Redis pub/sub backplane
+//!
+//! Each group has its own channel, named by [`super::channel_name`], and every
+//! replica pattern-subscribes to all of them. Pub/sub keeps no messages, so a
+//! replica that is disconnected misses what is published meanwhile; its
+//! subscribers catch up from the database once it reconnects.
+
+use ::redis::aio::ConnectionManager;
+use ::redis::Client;
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use tokio::sync::OnceCell;
+
+use super::{channel_name, Backplane, SubscriptionError};
+use crate::config::RedisConfig;
+
+/// Fans messages out through the Redis server at `redis.url`
+pub struct RedisBackplane {
+    client: Client,
+    channel_prefix: String,
+    connection: OnceCell<ConnectionManager>,
+}
+
+impl RedisBackplane {
+    /// A backplane on the server named by `config.url`, using channels under `channel_prefix`
+    ///
+    /// No connection is made until the first message is published or the
+    /// listener subscribes.
+    pub fn new(config: &RedisConfig, channel_prefix: String) -> Result<Self, SubscriptionError> {
+        let url = config
+            .url
+            .as_deref()
+            .ok_or_else(|| SubscriptionError::Config("the redis backplane requires redis.url".to_string()))?;
+        let client = Client::open(url).map_err(|e| SubscriptionError::Config(format!("redis.url: {}", e)))?;
+
+        Ok(Self {
+            client,
+            channel_prefix,
+            connection: OnceCell::new(),
+        })
+    }
+
+    /// Connection used for publishing, connecting on first use
+    async fn connection(&self) -> Result<ConnectionManager, SubscriptionError> {
+        self.connection
+            .get_or_try_init(|| async {
+                self.client
+                    .get_connection_manager()
+                    .await
+                    .map_err(|e| SubscriptionError::Backplane(e.to_string()))
+            })
+            .await
+            .cloned()
+    }
+}
+
+#[async_trait]
+impl Backplane for RedisBackplane {
+    async fn publish(&self, group_id: &str, payload: Vec<u8>) -> Result<(), SubscriptionError> {
+        let mut connection = self.connection().await?;
+        ::redis::cmd("PUBLISH")
+            .arg(channel_name(&self.channel_prefix, group_id))
+            .arg(payload)
+            .query_async::<()>(&mut connection)
+            .await
+            .map_err(|e| SubscriptionError::Backplane(e.to_string()))
+    }
+
+    async fn subscribe(&self) -> Result<BoxStream<'static, Vec<u8>>, SubscriptionError> {
+        let mut pubsub = self
+            .client
+            .get_async_pubsub()
+            .await
+            .map_err(|e| SubscriptionError::Backplane(e.to_string()))?;
+        pubsub
+            .psubscribe(format!("{}.*", self.channel_prefix))
+            .await
+            .map_err(|e| SubscriptionError::Backplane(e.to_string()))?;
+
+        Ok(pubsub.into_on_message().map(|message| message.get_payload_bytes().to_vec()).boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backplane_requires_a_redis_url() {
+        let result = RedisBackplane::new(&RedisConfig::default(), "groups".to_string());
+
+        assert!(matches!(result, Err(SubscriptionError::Config(_))));
+    }
+}
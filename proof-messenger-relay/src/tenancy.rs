@@ -0,0 +1,557 @@
+//! Multi-Tenancy Module
+//!
+//! By default the relay serves a single tenant: every request shares the
+//! same groups, the same context policy and the same retention. With
+//! tenants configured in the `[tenancy]` relay settings (see
+//! [`crate::config::TenancyConfig`]), each request is attributed to a tenant
+//! read from a header or from a claim of the caller's bearer token, and:
+//!
+//! - groups are namespaced per tenant, so `acme` and `globex` both posting
+//!   to group `default` never see each other's messages
+//! - messages, threads, approvals and detached proofs looked up by ID are
+//!   reported as not found to every other tenant
+//! - a tenant's `context_policy` replaces the relay-wide one for its messages
+//! - a tenant's `message_retention_days` purges its old messages in the
+//!   background
+//! - relayed messages and policy violations are counted per tenant in the
+//!   metrics registry
+//!
+//! A tenant header is only taken at face value with `trusted_proxy` set,
+//! for a proxy that authenticates callers and sets the header itself.
+//! Otherwise the header must name the tenant of the caller's token claim,
+//! and requests naming any other tenant are rejected with `403 Forbidden`.
+//!
+//! Requests naming no tenant use `default_tenant`, or are rejected with
+//! `400 Bad Request` when there is none; requests naming an unconfigured
+//! tenant are rejected with `403 Forbidden`. Tenancy is enabled by layering
+//! the resulting [`Tenancy`] onto the router as an [`axum::Extension`];
+//! handlers read the request's tenant with the [`TenantScope`] extractor.
+
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderName},
+};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::{
+    auth_middleware::AuthContext,
+    compliance_audit::ComplianceAudit,
+    config::{TenancyConfig, TenantSource},
+    context_policy::ContextPolicy,
+    database::{Database, DatabaseError},
+    metrics,
+    secure_logger::SecureLogger,
+    AppError,
+};
+
+/// Separator between a tenant ID and a group ID in stored group IDs
+const NAMESPACE_SEPARATOR: char = '/';
+
+/// Longest accepted tenant ID
+const MAX_TENANT_ID_LEN: usize = 64;
+
+/// How often expired tenant messages are purged
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Whether `id` is a valid tenant ID: 1-64 lowercase letters, digits, `-` or `_`
+pub fn is_valid_tenant_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_TENANT_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
+/// Settings of one configured tenant
+struct Tenant {
+    context_policy: Option<Arc<ContextPolicy>>,
+    retention: Option<chrono::Duration>,
+}
+
+/// The relay's tenants and how requests are attributed to them
+pub struct Tenancy {
+    source: TenantSource,
+    header: HeaderName,
+    trusted_proxy: bool,
+    claim: String,
+    default_tenant: Option<String>,
+    tenants: HashMap<String, Tenant>,
+}
+
+impl Tenancy {
+    /// Build the tenants from the relay settings
+    ///
+    /// Tenant policies record into `audit` when given. Fails if a tenant
+    /// names an unknown context policy or the header is invalid.
+    pub fn new(config: &TenancyConfig, logger: Arc<SecureLogger>, audit: Option<Arc<ComplianceAudit>>) -> Result<Self, String> {
+        let header = HeaderName::from_bytes(config.header.as_bytes())
+            .map_err(|_| format!("'{}' is not a valid header name", config.header))?;
+        let mut tenants = HashMap::new();
+        for (id, settings) in &config.tenants {
+            let context_policy = match &settings.context_policy {
+                Some(name) => {
+                    let policy = ContextPolicy::new(name, logger.clone())?;
+                    Some(Arc::new(match &audit {
+                        Some(audit) => policy.with_audit(audit.clone()),
+                        None => policy,
+                    }))
+                }
+                None => None,
+            };
+            tenants.insert(
+                id.clone(),
+                Tenant {
+                    context_policy,
+                    retention: settings.message_retention_days.map(chrono::Duration::days),
+                },
+            );
+        }
+        Ok(Self {
+            source: config.source,
+            header,
+            trusted_proxy: config.trusted_proxy,
+            claim: config.claim.clone(),
+            default_tenant: config.default_tenant.clone(),
+            tenants,
+        })
+    }
+
+    /// Token claim naming the caller's tenant
+    pub fn claim(&self) -> &str {
+        &self.claim
+    }
+
+    /// Header naming the tenant, when tenants are identified by header
+    pub fn header(&self) -> Option<&HeaderName> {
+        match self.source {
+            TenantSource::Header => Some(&self.header),
+            TenantSource::Claim => None,
+        }
+    }
+
+    /// Tenant a request names by its tenant header and its caller's token
+    ///
+    /// An untrusted header must agree with the tenant of the caller's token,
+    /// so callers cannot act for another tenant by setting it.
+    pub fn requested<'a>(&self, header: Option<&'a str>, auth: Option<&'a AuthContext>) -> Result<Option<&'a str>, AppError> {
+        let claimed = auth.and_then(|auth| auth.tenant.as_deref());
+        match self.source {
+            TenantSource::Claim => Ok(claimed),
+            TenantSource::Header if self.trusted_proxy => Ok(header),
+            TenantSource::Header => match header {
+                Some(named) if Some(named) != claimed => Err(AppError::TenantDenied(named.to_string())),
+                _ => Ok(claimed),
+            },
+        }
+    }
+
+    /// Scope of the tenant a request names, falling back to the default tenant
+    pub fn resolve(&self, requested: Option<&str>) -> Result<TenantScope, AppError> {
+        let id = requested
+            .or(self.default_tenant.as_deref())
+            .ok_or(AppError::MissingTenant)?;
+        let tenant = self
+            .tenants
+            .get(id)
+            .ok_or_else(|| AppError::UnknownTenant(id.to_string()))?;
+        Ok(TenantScope {
+            tenant: Some(id.to_string()),
+            context_policy: tenant.context_policy.clone(),
+        })
+    }
+
+    /// Delete every tenant's messages older than its retention period
+    ///
+    /// Returns the number of messages deleted.
+    pub async fn purge_expired_messages(&self, db: &Database) -> Result<u64, DatabaseError> {
+        let mut purged = 0;
+        for (id, tenant) in &self.tenants {
+            if let Some(retention) = tenant.retention {
+                let prefix = format!("{}{}", id, NAMESPACE_SEPARATOR);
+                purged += db.delete_namespaced_messages_before(&prefix, Utc::now() - retention).await?;
+            }
+        }
+        Ok(purged)
+    }
+
+    /// Purge expired tenant messages periodically in the background
+    pub fn spawn_retention(self: Arc<Self>, db: Arc<Database>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+            loop {
+                interval.tick().await;
+                crate::readiness::BACKGROUND_JOBS.heartbeat("tenant_retention", RETENTION_INTERVAL);
+                match self.purge_expired_messages(&db).await {
+                    Ok(0) => {}
+                    Ok(purged) => {
+                        info!("Purged {} expired tenant messages", purged);
+                        crate::metrics::RETENTION_DELETIONS_TOTAL
+                            .get_or_create(&vec![("job".to_string(), "tenant_retention".to_string())])
+                            .inc_by(purged);
+                    }
+                    Err(e) => warn!("Failed to purge expired tenant messages: {}", e),
+                }
+            }
+        })
+    }
+}
+
+/// The tenant a request acts for, available to handlers as an extractor
+///
+/// On relays without tenants the scope is empty: groups are not namespaced
+/// and the relay-wide context policy applies.
+#[derive(Clone, Default)]
+pub struct TenantScope {
+    tenant: Option<String>,
+    context_policy: Option<Arc<ContextPolicy>>,
+}
+
+impl TenantScope {
+    /// ID of the tenant, if the relay has tenants
+    pub fn tenant(&self) -> Option<&str> {
+        self.tenant.as_deref()
+    }
+
+    /// Stored ID of a group as seen by this tenant
+    pub fn group_id(&self, group_id: &str) -> String {
+        match &self.tenant {
+            Some(tenant) => format!("{}{}{}", tenant, NAMESPACE_SEPARATOR, group_id),
+            None => group_id.to_string(),
+        }
+    }
+
+    /// Whether a stored group ID belongs to this tenant
+    pub fn owns_group(&self, stored_group_id: &str) -> bool {
+        match &self.tenant {
+            Some(tenant) => stored_group_id
+                .strip_prefix(tenant.as_str())
+                .is_some_and(|rest| rest.starts_with(NAMESPACE_SEPARATOR)),
+            None => true,
+        }
+    }
+
+    /// The tenant's context policy, or `fallback` if it has none
+    pub fn context_policy<'a>(&'a self, fallback: Option<&'a Arc<ContextPolicy>>) -> Option<&'a Arc<ContextPolicy>> {
+        self.context_policy.as_ref().or(fallback)
+    }
+
+    /// Count a relayed message against the tenant
+    pub fn record_relayed(&self) {
+        if let Some(tenant) = &self.tenant {
+            metrics::TENANT_RELAYED_MESSAGES_TOTAL
+                .get_or_create(&vec![("tenant".to_string(), tenant.clone())])
+                .inc();
+        }
+    }
+
+    /// Count a context policy violation against the tenant
+    pub fn record_policy_violation(&self) {
+        if let Some(tenant) = &self.tenant {
+            metrics::TENANT_POLICY_VIOLATIONS_TOTAL
+                .get_or_create(&vec![("tenant".to_string(), tenant.clone())])
+                .inc();
+        }
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for TenantScope
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    /// The scope of the tenant named by the request, or an empty scope on relays without tenants
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let tenancy = match parts.extensions.get::<Arc<Tenancy>>() {
+            Some(tenancy) => tenancy.clone(),
+            None => return Ok(TenantScope::default()),
+        };
+        let header = match tenancy.header() {
+            Some(header) => parts
+                .headers
+                .get(header)
+                .map(|value| value.to_str().map_err(|_| AppError::UnknownTenant("<non-ASCII>".to_string())))
+                .transpose()?,
+            None => None,
+        };
+        let requested = tenancy.requested(header, parts.extensions.get::<AuthContext>())?;
+        tenancy.resolve(requested)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::TenantSettings, create_app, database::StoredMessage};
+    use axum::{body::Body, extract::Request, http::StatusCode, Extension, Router};
+    use ed25519_dalek::Signer;
+    use proof_messenger_protocol::detached::{digest_document, make_detached_proof, DigestAlgorithm, DocumentMetadata};
+    use proof_messenger_protocol::key::generate_keypair_with_seed;
+    use tower::ServiceExt;
+
+    fn tenancy(default_tenant: Option<&str>) -> Arc<Tenancy> {
+        let mut config = TenancyConfig {
+            default_tenant: default_tenant.map(str::to_string),
+            trusted_proxy: true,
+            ..TenancyConfig::default()
+        };
+        config.tenants.insert(
+            "acme".to_string(),
+            TenantSettings {
+                context_policy: Some("login".to_string()),
+                message_retention_days: Some(30),
+            },
+        );
+        config.tenants.insert("globex".to_string(), TenantSettings::default());
+        let logger = Arc::new(SecureLogger::new(&SecureLogger::generate_key()));
+        Arc::new(Tenancy::new(&config, logger, None).unwrap())
+    }
+
+    fn relay_request(tenant: Option<&str>, context: &str) -> Request<Body> {
+        let keypair = generate_keypair_with_seed(11);
+        let body = serde_json::json!({
+            "sender": hex::encode(keypair.public.to_bytes()),
+            "context": hex::encode(context),
+            "body": "hello",
+            "proof": hex::encode(keypair.sign(context.as_bytes()).to_bytes()),
+        });
+        let mut request = Request::post("/relay").header("content-type", "application/json");
+        if let Some(tenant) = tenant {
+            request = request.header("x-tenant-id", tenant);
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    }
+
+    fn app(db: Arc<Database>, tenancy: Arc<Tenancy>) -> Router {
+        create_app(db).layer(Extension(tenancy))
+    }
+
+    async fn message_count(app: &Router, tenant: &str) -> u64 {
+        let request = Request::get("/messages/default")
+            .header("x-tenant-id", tenant)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()["message_count"]
+            .as_u64()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_tenant_ids_are_validated() {
+        assert!(is_valid_tenant_id("acme"));
+        assert!(is_valid_tenant_id("team-7_eu"));
+        assert!(!is_valid_tenant_id(""));
+        assert!(!is_valid_tenant_id("Acme"));
+        assert!(!is_valid_tenant_id("acme/eu"));
+        assert!(!is_valid_tenant_id(&"a".repeat(65)));
+    }
+
+    #[test]
+    fn test_group_namespaces_are_isolated() {
+        let acme = tenancy(None).resolve(Some("acme")).unwrap();
+
+        assert_eq!(acme.group_id("default"), "acme/default");
+        assert!(acme.owns_group("acme/default"));
+        assert!(!acme.owns_group("acme-eu/default"));
+        assert!(!acme.owns_group("globex/default"));
+        assert_eq!(TenantScope::default().group_id("default"), "default");
+    }
+
+    #[test]
+    fn test_missing_and_unknown_tenants_are_rejected() {
+        assert!(matches!(tenancy(None).resolve(None), Err(AppError::MissingTenant)));
+        assert!(matches!(tenancy(None).resolve(Some("initech")), Err(AppError::UnknownTenant(_))));
+        assert_eq!(tenancy(Some("globex")).resolve(None).unwrap().tenant(), Some("globex"));
+    }
+
+    #[tokio::test]
+    async fn test_tenants_have_separate_groups_and_policies() {
+        // ARRANGE: A relay with two tenants, only acme enforcing the login policy
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let app = app(db, tenancy(None));
+
+        // ACT: Each tenant relays a context missing the login policy's fields
+        let acme = app.clone().oneshot(relay_request(Some("acme"), r#"{"note":"hi"}"#)).await.unwrap();
+        let globex = app.clone().oneshot(relay_request(Some("globex"), r#"{"note":"hi"}"#)).await.unwrap();
+        let anonymous = app.clone().oneshot(relay_request(None, r#"{"note":"hi"}"#)).await.unwrap();
+        let unknown = app.clone().oneshot(relay_request(Some("initech"), r#"{"note":"hi"}"#)).await.unwrap();
+
+        // ASSERT: Only acme's policy rejects it, and globex's message stays in its namespace
+        assert_eq!(acme.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(globex.status(), StatusCode::OK);
+        assert_eq!(anonymous.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(unknown.status(), StatusCode::FORBIDDEN);
+        assert_eq!(message_count(&app, "globex").await, 1);
+        assert_eq!(message_count(&app, "acme").await, 0);
+    }
+
+    async fn send(app: &Router, tenant: &str, method: &str, uri: &str, body: Option<serde_json::Value>) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-tenant-id", tenant)
+            .header("content-type", "application/json")
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_lookups_by_id_are_scoped_to_the_tenant() {
+        // ARRANGE: globex relays a message, opens an approval and registers a detached proof
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let app = app(db, tenancy(None));
+        let relayed = app.clone().oneshot(relay_request(Some("globex"), r#"{"note":"hi"}"#)).await.unwrap();
+        let body = axum::body::to_bytes(relayed.into_body(), usize::MAX).await.unwrap();
+        let message_id = serde_json::from_slice::<serde_json::Value>(&body).unwrap()["message_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let signer = proof_messenger_protocol::key::generate_secure_keypair_with_seed(5);
+        let approval = serde_json::json!({
+            "threshold": 1,
+            "signers": [hex::encode(signer.public_key_bytes())],
+            "payload": hex::encode("wire transfer"),
+        });
+        let (_, created) = send(&app, "globex", "POST", "/approvals", Some(approval)).await;
+        let approval_id = serde_json::from_str::<serde_json::Value>(&created).unwrap()["approval"]["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let (digest, size) = digest_document(DigestAlgorithm::Sha256, &b"%PDF-1.7 purchase approval"[..]).unwrap();
+        let metadata = DocumentMetadata {
+            filename: "approval.pdf".to_string(),
+            size,
+            content_type: "application/pdf".to_string(),
+        };
+        let proof = make_detached_proof(&signer, DigestAlgorithm::Sha256, digest, metadata).unwrap();
+        let (registered, _) = send(&app, "globex", "POST", "/detached-proofs", Some(serde_json::to_value(&proof).unwrap())).await;
+        assert_eq!(registered, StatusCode::CREATED);
+        let by_id = [
+            format!("/threads/{}", message_id),
+            format!("/message/{}/receipts", message_id),
+            format!("/message/{}/history", message_id),
+            format!("/message/{}/proof-chain", message_id),
+            format!("/approvals/{}", approval_id),
+        ];
+
+        // ACT: Look everything up as acme and as globex
+        let mut acme = Vec::new();
+        let mut globex = Vec::new();
+        for uri in &by_id {
+            let (_, body) = send(&app, "acme", "GET", uri, None).await;
+            acme.push(serde_json::from_str::<serde_json::Value>(&body).unwrap()["code"].clone());
+            globex.push(send(&app, "globex", "GET", uri, None).await.0);
+        }
+        let acme_approvals = send(&app, "acme", "GET", "/approvals", None).await.1;
+        let acme_proofs = send(&app, "acme", "GET", &format!("/detached-proofs/{}", hex::encode(digest)), None).await.1;
+        let globex_proofs = send(&app, "globex", "GET", &format!("/detached-proofs/{}", hex::encode(digest)), None).await.1;
+        let acme_export = send(&app, "acme", "GET", "/messages/default/export", None).await.1;
+        let globex_export = send(&app, "globex", "GET", "/messages/default/export", None).await.1;
+
+        // ASSERT: Another tenant's records are not found, and exports cover only the tenant's group
+        assert_eq!(
+            acme,
+            ["MESSAGE_NOT_FOUND", "MESSAGE_NOT_FOUND", "MESSAGE_NOT_FOUND", "MESSAGE_NOT_FOUND", "APPROVAL_NOT_FOUND"]
+        );
+        assert_eq!(globex, vec![StatusCode::OK; by_id.len()]);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&acme_approvals).unwrap()["count"], 0);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&acme_proofs).unwrap()["count"], 0);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&globex_proofs).unwrap()["count"], 1);
+        assert!(acme_export.is_empty());
+        assert_eq!(globex_export.lines().count(), 1);
+        assert!(globex_export.contains(&message_id));
+    }
+
+    #[tokio::test]
+    async fn test_header_must_match_the_callers_tenant() {
+        // ARRANGE: An authenticated relay whose callers' tokens name their tenant
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let validator = Arc::new(crate::jwt_validator::JwtValidator::new_hmac("secret", "issuer".to_string(), None));
+        let logger = Arc::new(SecureLogger::new(&SecureLogger::generate_key()));
+        let mut config = TenancyConfig::default();
+        config.tenants.insert("acme".to_string(), TenantSettings::default());
+        config.tenants.insert("globex".to_string(), TenantSettings::default());
+        let tenancy = Arc::new(Tenancy::new(&config, logger.clone(), None).unwrap());
+        let app = crate::create_authenticated_app_with_config(db, &crate::config::RelayConfig::default(), validator, logger)
+            .layer(Extension(tenancy));
+        let claims = serde_json::json!({
+            "sub": "alice",
+            "iss": "issuer",
+            "exp": 9999999999u64,
+            "scope": "message:read",
+            "tenant_id": "acme",
+        });
+        let token = jsonwebtoken::encode(&Default::default(), &claims, &jsonwebtoken::EncodingKey::from_secret(b"secret")).unwrap();
+        let list = |tenant: Option<&str>| {
+            let mut request = Request::get("/v1/messages/default").header("authorization", format!("Bearer {}", token));
+            if let Some(tenant) = tenant {
+                request = request.header("x-tenant-id", tenant);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        // ACT: Read as acme's caller naming acme, naming no tenant and naming globex
+        let own = app.clone().oneshot(list(Some("acme"))).await.unwrap();
+        let implied = app.clone().oneshot(list(None)).await.unwrap();
+        let other = app.oneshot(list(Some("globex"))).await.unwrap();
+
+        // ASSERT: The header cannot name another tenant than the caller's
+        assert_eq!(own.status(), StatusCode::OK);
+        assert_eq!(implied.status(), StatusCode::OK);
+        assert_eq!(other.status(), StatusCode::FORBIDDEN);
+        let body = axum::body::to_bytes(other.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("TENANT_DENIED"));
+    }
+
+    #[tokio::test]
+    async fn test_retention_purges_only_expired_tenant_messages() {
+        // ARRANGE: Old and new messages for a tenant with retention, and an old one without
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let stored = |id: &str, group_id: &str, age_days: i64| StoredMessage {
+            id: id.to_string(),
+            group_id: group_id.to_string(),
+            sender: "sender".to_string(),
+            context: "context".to_string(),
+            body: "body".to_string(),
+            proof: "proof".to_string(),
+            created_at: Utc::now() - chrono::Duration::days(age_days),
+            verified: true,
+            thread_id: None,
+            reply_to: None,
+            deleted_at: None,
+            message_hash: None,
+            expires_at: None,
+            recipient: None,
+            pqc_public_key: None,
+            pqc_proof: None,
+        };
+        db.store_message(stored("old", "acme/default", 60)).await.unwrap();
+        db.store_message(stored("new", "acme/default", 1)).await.unwrap();
+        db.store_message(stored("kept", "globex/default", 60)).await.unwrap();
+
+        // ACT: Apply the tenants' retention
+        let purged = tenancy(None).purge_expired_messages(&db).await.unwrap();
+
+        // ASSERT: Only acme's expired message is deleted
+        assert_eq!(purged, 1);
+        assert!(db.get_message_by_id("old").await.is_err());
+        assert!(db.get_message_by_id("new").await.is_ok());
+        assert!(db.get_message_by_id("kept").await.is_ok());
+    }
+}
@@ -0,0 +1,141 @@
+//! Test Relay Module
+//!
+//! [`TestRelay`] serves the relay on a random local port with a fresh
+//! in-memory database and hands back a [`RelayClient`] for it, so crates that
+//! talk to a relay can write integration tests without repeating the setup.
+//! Enable it with the `test-util` feature, usually from `[dev-dependencies]`:
+//!
+//! ```toml
+//! proof-messenger-relay = { path = "../proof-messenger-relay", features = ["test-util"] }
+//! ```
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::Router;
+use proof_messenger_protocol::relay_client::{RelayClient, RetryPolicy};
+use tokio::task::JoinHandle;
+
+use crate::create_app;
+use crate::database::Database;
+
+/// A relay serving on a random local port for the lifetime of the value
+///
+/// The server task is aborted when the `TestRelay` is dropped. Each relay has
+/// its own in-memory database, so tests running in parallel don't see each
+/// other's messages.
+pub struct TestRelay {
+    address: SocketAddr,
+    database: Arc<Database>,
+    client: RelayClient,
+    server: JoinHandle<()>,
+}
+
+impl TestRelay {
+    /// Start a relay serving [`create_app`] over a migrated in-memory database
+    ///
+    /// # Panics
+    ///
+    /// Panics if the database cannot be created or the port cannot be bound.
+    pub async fn start() -> Self {
+        let database = Database::new("sqlite::memory:").await.expect("Failed to create test database");
+        database.migrate().await.expect("Failed to migrate test database");
+        Self::start_with(Arc::new(database), create_app).await
+    }
+
+    /// Start a relay serving the router `app` builds over `database`
+    ///
+    /// Use this to seed the database before the relay starts, or to test one
+    /// of the other router constructors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the port cannot be bound.
+    pub async fn start_with(database: Arc<Database>, app: impl FnOnce(Arc<Database>) -> Router) -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind test relay");
+        let address = listener.local_addr().expect("Failed to read test relay address");
+        let router = app(database.clone());
+        let server = tokio::spawn(async move {
+            axum::serve(listener, router).await.expect("Test relay stopped serving");
+        });
+        let client = RelayClient::new(&format!("http://{}", address))
+            .expect("Test relay URL is valid")
+            .with_retry_policy(RetryPolicy::none());
+
+        Self { address, database, client, server }
+    }
+
+    /// Client for the relay, with retries disabled so failures surface at once
+    pub fn client(&self) -> &RelayClient {
+        &self.client
+    }
+
+    /// Base URL of the relay, such as `http://127.0.0.1:49152`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Address the relay is listening on
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The relay's database, for seeding data or checking what was stored
+    pub fn database(&self) -> &Arc<Database> {
+        &self.database
+    }
+}
+
+impl Drop for TestRelay {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_protocol::relay_client::OutgoingMessage;
+
+    #[tokio::test]
+    async fn test_relay_accepts_messages_from_its_client() {
+        // ARRANGE: A running test relay and a signed message
+        let relay = TestRelay::start().await;
+        let keypair = generate_secure_keypair_with_seed(31);
+        let message = OutgoingMessage::signed(&keypair, b"test-relay-context", "hello").unwrap();
+
+        // ACT: Send it through the relay's client
+        let message_id = relay.client().send_message(&message).await.unwrap();
+
+        // ASSERT: The message landed in the relay's own database
+        let stored = relay.database().get_message_by_id(&message_id).await.unwrap();
+        assert_eq!(stored.body, "hello");
+        assert!(relay.client().check_health().await.unwrap().is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_relays_do_not_share_state() {
+        let first = TestRelay::start().await;
+        let second = TestRelay::start().await;
+        let keypair = generate_secure_keypair_with_seed(32);
+        let message = OutgoingMessage::signed(&keypair, b"isolated-context", "only here").unwrap();
+
+        first.client().send_message(&message).await.unwrap();
+
+        assert_ne!(first.address(), second.address());
+        assert_eq!(first.client().get_messages("default", None).await.unwrap().len(), 1);
+        assert!(second.client().get_messages("default", None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dropping_the_relay_stops_it() {
+        let relay = TestRelay::start().await;
+        let address = relay.address();
+
+        drop(relay);
+        tokio::task::yield_now().await;
+
+        assert!(tokio::net::TcpStream::connect(address).await.is_err());
+    }
+}
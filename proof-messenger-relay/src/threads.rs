@@ -0,0 +1,293 @@
+//! Threaded Conversation Module
+//!
+//! This module groups relayed messages into threads. A thread is identified
+//! by the ID of its root message; replies carry the thread ID and the ID of
+//! the message they answer, so clients can render a conversation from a
+//! single request without joining messages themselves.
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, instrument};
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, DatabaseError, StoredMessage},
+    tenancy::TenantScope,
+    AppError,
+};
+
+/// A message within a thread, with its position in the reply chain
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreadEntry {
+    #[serde(flatten)]
+    pub message: StoredMessage,
+    /// Number of reply hops from the thread root (the root has depth 0)
+    pub depth: usize,
+    /// IDs of direct replies to this message, oldest first
+    pub replies: Vec<String>,
+}
+
+/// A thread's messages in chronological order
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreadView {
+    /// ID of the thread (its root message ID)
+    pub thread_id: String,
+    /// Messages in the thread, oldest first
+    pub messages: Vec<ThreadEntry>,
+}
+
+/// Create router for thread endpoints
+pub fn thread_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/threads/:thread_id", get(get_thread_handler))
+}
+
+/// Create router for authenticated thread endpoints
+pub fn authenticated_thread_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/threads/:thread_id", get(authenticated_get_thread_handler))
+}
+
+/// Resolve the thread of a message before it is stored
+///
+/// A message naming only a thread is treated as a reply to the thread root.
+/// A reply inherits its parent's thread, and is rejected if it names a
+/// different thread or a parent that does not exist.
+pub async fn assign_thread(db: &Database, message: &mut StoredMessage) -> Result<(), AppError> {
+    if message.reply_to.is_none() {
+        message.reply_to = message.thread_id.clone();
+    }
+    let parent_id = match &message.reply_to {
+        Some(parent_id) => parent_id.clone(),
+        None => return Ok(()),
+    };
+
+    let parent = db.get_message_by_id(&parent_id).await.map_err(|e| match e {
+        DatabaseError::MessageNotFound(id) => AppError::InvalidThread(format!("Parent message {} not found", id)),
+        other => AppError::DatabaseError(other),
+    })?;
+    if parent.is_expired(chrono::Utc::now()) {
+        return Err(AppError::InvalidThread(format!("Parent message {} has expired", parent_id)));
+    }
+    if parent.group_id != message.group_id {
+        return Err(AppError::InvalidThread(format!(
+            "Parent message {} is in a different group",
+            parent_id
+        )));
+    }
+
+    let parent_thread = parent.thread_id.unwrap_or(parent.id);
+    if let Some(thread_id) = &message.thread_id {
+        if *thread_id != parent_thread {
+            return Err(AppError::InvalidThread(format!(
+                "Message {} belongs to thread {}, not {}",
+                parent_id, parent_thread, thread_id
+            )));
+        }
+    }
+
+    message.thread_id = Some(parent_thread);
+    Ok(())
+}
+
+/// Assemble a thread view from its messages
+///
+/// Messages are expected in chronological order, as returned by
+/// [`Database::get_messages_by_thread`].
+pub fn build_thread(thread_id: &str, messages: Vec<StoredMessage>) -> ThreadView {
+    let parents: HashMap<String, Option<String>> = messages
+        .iter()
+        .map(|m| (m.id.clone(), m.reply_to.clone()))
+        .collect();
+
+    let mut replies: HashMap<String, Vec<String>> = HashMap::new();
+    for message in &messages {
+        if let Some(parent) = &message.reply_to {
+            replies.entry(parent.clone()).or_default().push(message.id.clone());
+        }
+    }
+
+    let depth_of = |id: &str| {
+        let mut depth = 0;
+        let mut current = parents.get(id).cloned().flatten();
+        // Bounded by the thread size so a malformed chain cannot loop forever
+        while let Some(parent) = current {
+            if depth >= parents.len() {
+                break;
+            }
+            depth += 1;
+            current = parents.get(&parent).cloned().flatten();
+        }
+        depth
+    };
+
+    let messages = messages
+        .into_iter()
+        .map(|message| ThreadEntry {
+            depth: depth_of(&message.id),
+            replies: replies.remove(&message.id).unwrap_or_default(),
+            message,
+        })
+        .collect();
+
+    ThreadView {
+        thread_id: thread_id.to_string(),
+        messages,
+    }
+}
+
+/// Retrieve a thread of the tenant's groups, as if it did not exist when it belongs to another tenant
+pub async fn tenant_thread(db: &Database, tenant: &TenantScope, thread_id: &str) -> Result<ThreadView, AppError> {
+    let messages: Vec<StoredMessage> = db
+        .get_messages_by_thread(thread_id)
+        .await?
+        .into_iter()
+        .filter(|message| tenant.owns_group(&message.group_id))
+        .collect();
+    if messages.is_empty() {
+        return Err(DatabaseError::MessageNotFound(thread_id.to_string()).into());
+    }
+    Ok(build_thread(thread_id, messages))
+}
+
+/// Handler to retrieve a thread with its reply chains
+#[utoipa::path(
+    get,
+    path = "/threads/{thread_id}",
+    operation_id = "getThread",
+    tag = "messages",
+    params(("thread_id" = String, Path, description = "Thread ID (the root message ID)")),
+    responses(
+        (status = 200, description = "The thread's messages", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_thread_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(thread_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Retrieving thread: {}", thread_id);
+
+    let thread = tenant_thread(&db, &tenant, &thread_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "thread_id": thread.thread_id,
+        "message_count": thread.messages.len(),
+        "messages": thread.messages
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to retrieve a thread with its reply chains
+#[instrument(skip_all)]
+async fn authenticated_get_thread_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Path(thread_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} retrieving thread: {}", auth.user_id, thread_id);
+
+    let thread = tenant_thread(&db, &tenant, &thread_id).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "thread_id": thread.thread_id,
+        "message_count": thread.messages.len(),
+        "messages": thread.messages,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    async fn setup_test_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    async fn post(db: &Database, body: &str, thread_id: Option<&str>, reply_to: Option<&str>) -> Result<String, AppError> {
+        let mut message = StoredMessage::from(crate::Message {
+            sender: "aa".repeat(32),
+            context: "bb".to_string(),
+            body: body.to_string(),
+            proof: "cc".repeat(64),
+            pqc: None,
+            thread_id: thread_id.map(str::to_string),
+            reply_to: reply_to.map(str::to_string),
+            expires_at: None,
+            recipient: None,
+        });
+        assign_thread(db, &mut message).await?;
+        Ok(db.store_message(message).await?)
+    }
+
+    #[tokio::test]
+    async fn test_thread_with_reply_chain() {
+        // ARRANGE: A root message, a reply, a reply to the reply and a thread post
+        let db = setup_test_db().await;
+        let root = post(&db, "root", None, None).await.unwrap();
+        let reply = post(&db, "reply", None, Some(&root)).await.unwrap();
+        let nested = post(&db, "nested", None, Some(&reply)).await.unwrap();
+        let sibling = post(&db, "sibling", Some(&root), None).await.unwrap();
+        post(&db, "unrelated", None, None).await.unwrap();
+
+        // ACT: Fetch the thread over HTTP
+        let app = Router::new().merge(thread_routes()).with_state(db.clone());
+        let response = app
+            .oneshot(Request::builder().uri(format!("/threads/{}", root)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        // ASSERT: Messages are ordered with their reply chains
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let messages = json["messages"].as_array().unwrap();
+        let ids: Vec<&str> = messages.iter().map(|m| m["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec![root.as_str(), reply.as_str(), nested.as_str(), sibling.as_str()]);
+        let depths: Vec<u64> = messages.iter().map(|m| m["depth"].as_u64().unwrap()).collect();
+        assert_eq!(depths, vec![0, 1, 2, 1]);
+        assert_eq!(messages[0]["replies"], serde_json::json!([reply, sibling]));
+        assert_eq!(messages[2]["thread_id"], root.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_reply_to_unknown_message_is_rejected() {
+        let db = setup_test_db().await;
+
+        let result = post(&db, "orphan", None, Some("missing")).await;
+
+        assert!(matches!(result, Err(AppError::InvalidThread(_))));
+    }
+
+    #[tokio::test]
+    async fn test_reply_naming_other_thread_is_rejected() {
+        let db = setup_test_db().await;
+        let first = post(&db, "first", None, None).await.unwrap();
+        let second = post(&db, "second", None, None).await.unwrap();
+
+        let result = post(&db, "confused", Some(&second), Some(&first)).await;
+
+        assert!(matches!(result, Err(AppError::InvalidThread(_))));
+    }
+}
@@ -0,0 +1,667 @@
+//! RFC 3161 Timestamping Module
+//!
+//! With a Time-Stamp Authority (TSA) configured, the relay requests a
+//! timestamp token for every message it stores. The token covers the
+//! message's SHA-256 message hash (see
+//! `proof_messenger_protocol::receipt::message_hash`), so it attests that
+//! the exact sender, context and body existed at the authority's time,
+//! independently of the relay's clock.
+//!
+//! Requests are made in the background once the message is stored; a
+//! failed request is logged and leaves the message without a token. Tokens
+//! are stored as DER and served by `GET /message/:message_id/timestamp`.
+//! The relay checks that a token covers the requested hash and nonce but not
+//! the authority's signature: auditors verify that offline, e.g.
+//!
+//! ```text
+//! openssl ts -verify -digest <message_imprint> -in token.tsr -token_in -CAfile tsa-ca.pem
+//! ```
+//!
+//! Timestamping is enabled by the `[timestamping]` relay settings or
+//! `TSA_URL` (see [`crate::config::TimestampingConfig`]) and layering the
+//! resulting [`TimestampAuthority`] onto the router as an [`axum::Extension`].
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rand::RngCore;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    auth_middleware::AuthContext,
+    config::TimestampingConfig,
+    database::{Database, StoredMessage, StoredTimestamp},
+    receipts::stored_message_hash,
+    tenancy::TenantScope,
+    AppError,
+};
+
+/// Content type of an RFC 3161 timestamp request
+pub const TIMESTAMP_QUERY_CONTENT_TYPE: &str = "application/timestamp-query";
+
+/// Content type of an RFC 3161 timestamp response
+pub const TIMESTAMP_REPLY_CONTENT_TYPE: &str = "application/timestamp-reply";
+
+/// DER encoded OID of SHA-256 (2.16.840.1.101.3.4.2.1)
+const SHA256_OID: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+
+/// DER encoded OID of CMS signed data (1.2.840.113549.1.7.2)
+const SIGNED_DATA_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+
+/// DER encoded OID of a TSTInfo payload (1.2.840.113549.1.9.16.1.4)
+const TST_INFO_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x01, 0x04];
+
+// DER tags used by timestamp requests and responses
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_EXPLICIT_0: u8 = 0xa0;
+
+/// Timestamping errors
+#[derive(Error, Debug)]
+pub enum TimestampError {
+    #[error("Invalid timestamping configuration: {0}")]
+    Config(String),
+
+    #[error("Timestamp request failed: {0}")]
+    Request(String),
+
+    #[error("Time-Stamp Authority refused the request (status {status}): {message}")]
+    Rejected { status: u64, message: String },
+
+    #[error("Malformed timestamp response: {0}")]
+    Malformed(String),
+
+    #[error("Timestamp token does not match the request: {0}")]
+    Mismatch(String),
+
+    #[error("No timestamp token for message {0}")]
+    NotFound(String),
+}
+
+/// The fields of a timestamp token the relay records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampToken {
+    /// DER encoded token (a CMS `ContentInfo`)
+    pub der: Vec<u8>,
+    /// Hash the token covers
+    pub message_imprint: Vec<u8>,
+    /// Serial number the authority assigned
+    pub serial_number: Vec<u8>,
+    /// Authority policy the token was issued under (dotted OID)
+    pub policy: String,
+    /// Time the authority attests to
+    pub gen_time: DateTime<Utc>,
+    /// Nonce echoed from the request, if any
+    pub nonce: Option<u64>,
+}
+
+/// Requests timestamp tokens from a Time-Stamp Authority
+pub struct TimestampAuthority {
+    url: String,
+    cert_req: bool,
+    client: crate::egress::HttpClient,
+}
+
+impl TimestampAuthority {
+    /// Request tokens from the authority at `url`
+    pub fn new(url: &str, cert_req: bool, timeout: Duration) -> Result<Self, TimestampError> {
+        if !matches!(reqwest::Url::parse(url), Ok(parsed) if matches!(parsed.scheme(), "http" | "https")) {
+            return Err(TimestampError::Config(format!("'{}' must be an http:// or https:// URL", url)));
+        }
+        let client = crate::egress::client(reqwest::Client::builder().timeout(timeout))
+            .map_err(|e| TimestampError::Config(e.to_string()))?;
+        Ok(Self {
+            url: url.to_string(),
+            cert_req,
+            client,
+        })
+    }
+
+    /// Build the authority for the configured URL, or `None` when timestamping is disabled
+    pub fn from_config(config: &TimestampingConfig) -> Result<Option<Self>, TimestampError> {
+        match &config.tsa_url {
+            Some(url) => Self::new(url, config.cert_req, Duration::from_millis(config.timeout_ms)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// URL of the authority
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Request a token covering a SHA-256 `digest`
+    pub async fn request(&self, digest: &[u8; 32]) -> Result<TimestampToken, TimestampError> {
+        let nonce = rand::thread_rng().next_u64();
+        let response = self
+            .client
+            .post(&self.url)
+            .map_err(|e| TimestampError::Request(e.to_string()))?
+            .header(reqwest::header::CONTENT_TYPE, TIMESTAMP_QUERY_CONTENT_TYPE)
+            .body(timestamp_request(digest, nonce, self.cert_req))
+            .send()
+            .await
+            .map_err(|e| TimestampError::Request(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(TimestampError::Request(format!("authority returned HTTP {}", response.status())));
+        }
+        let body = response.bytes().await.map_err(|e| TimestampError::Request(e.to_string()))?;
+
+        let token = parse_timestamp_response(&body)?;
+        if token.message_imprint != digest {
+            return Err(TimestampError::Mismatch("token covers a different hash".to_string()));
+        }
+        if token.nonce != Some(nonce) {
+            return Err(TimestampError::Mismatch("token does not echo the request nonce".to_string()));
+        }
+        Ok(token)
+    }
+
+    /// Timestamp a stored message's hash and store the token
+    pub async fn timestamp_message(&self, db: &Database, message: &StoredMessage) -> Result<StoredTimestamp, AppError> {
+        let digest = stored_message_hash(message)?;
+        let token = self.request(&digest).await?;
+
+        let timestamp = StoredTimestamp {
+            message_id: message.id.clone(),
+            tsa_url: self.url.clone(),
+            message_imprint: hex::encode(&token.message_imprint),
+            serial_number: hex::encode(&token.serial_number),
+            policy: token.policy,
+            gen_time: token.gen_time,
+            token: STANDARD.encode(&token.der),
+            created_at: Utc::now(),
+        };
+        db.store_message_timestamp(&timestamp).await?;
+        Ok(timestamp)
+    }
+
+    /// Timestamp a stored message in the background
+    pub fn spawn_timestamp(self: &Arc<Self>, db: &Arc<Database>, message: &StoredMessage) {
+        let authority = Arc::clone(self);
+        let db = Arc::clone(db);
+        let message = message.clone();
+        tokio::spawn(async move {
+            if let Err(e) = authority.timestamp_message(&db, &message).await {
+                warn!("Failed to timestamp message {}: {}", message.id, e);
+            }
+        });
+    }
+}
+
+/// Timestamp a stored message in the background if timestamping is enabled
+pub fn timestamp_if_enabled(authority: Option<&Arc<TimestampAuthority>>, db: &Arc<Database>, message: &StoredMessage) {
+    if let Some(authority) = authority {
+        authority.spawn_timestamp(db, message);
+    }
+}
+
+/// Encode one DER element
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend_from_slice(&bytes);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// Encode an unsigned DER INTEGER
+fn der_uint(value: u64) -> Vec<u8> {
+    let mut bytes: Vec<u8> = value.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+    if bytes.first().is_none_or(|b| b & 0x80 != 0) {
+        bytes.insert(0, 0);
+    }
+    der(TAG_INTEGER, &bytes)
+}
+
+/// Encode an RFC 3161 `TimeStampReq` for a SHA-256 `digest`
+pub fn timestamp_request(digest: &[u8; 32], nonce: u64, cert_req: bool) -> Vec<u8> {
+    let algorithm = der(TAG_SEQUENCE, &[der(TAG_OID, SHA256_OID), der(TAG_NULL, &[])].concat());
+    let imprint = der(TAG_SEQUENCE, &[algorithm, der(TAG_OCTET_STRING, digest)].concat());
+
+    let mut request = [der_uint(1), imprint, der_uint(nonce)].concat();
+    if cert_req {
+        request.extend(der(TAG_BOOLEAN, &[0xff]));
+    }
+    der(TAG_SEQUENCE, &request)
+}
+
+/// One DER element read from a buffer
+struct Element<'a> {
+    tag: u8,
+    content: &'a [u8],
+    /// The whole element: tag, length and content
+    raw: &'a [u8],
+}
+
+/// Read the DER element at the start of `input`, returning it and the rest
+fn read(input: &[u8]) -> Result<(Element<'_>, &[u8]), TimestampError> {
+    let truncated = || TimestampError::Malformed("truncated DER element".to_string());
+    let (&tag, rest) = input.split_first().ok_or_else(truncated)?;
+    let (&first, rest) = rest.split_first().ok_or_else(truncated)?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > std::mem::size_of::<usize>() || rest.len() < count {
+            return Err(TimestampError::Malformed("unsupported DER length".to_string()));
+        }
+        let len = rest[..count].iter().fold(0usize, |len, b| (len << 8) | *b as usize);
+        (len, &rest[count..])
+    };
+    if rest.len() < len {
+        return Err(truncated());
+    }
+    let header = input.len() - rest.len();
+    Ok((
+        Element {
+            tag,
+            content: &rest[..len],
+            raw: &input[..header + len],
+        },
+        &rest[len..],
+    ))
+}
+
+/// Read an element that must carry `tag`
+fn expect<'a>(input: &'a [u8], tag: u8, what: &str) -> Result<(Element<'a>, &'a [u8]), TimestampError> {
+    let (element, rest) = read(input)?;
+    if element.tag != tag {
+        return Err(TimestampError::Malformed(format!(
+            "expected {} (tag {:#04x}), found tag {:#04x}",
+            what, tag, element.tag
+        )));
+    }
+    Ok((element, rest))
+}
+
+/// Decode a non-negative DER INTEGER that fits in a u64
+fn uint(content: &[u8]) -> Result<u64, TimestampError> {
+    let trimmed = match content {
+        [0, rest @ ..] => rest,
+        other => other,
+    };
+    if trimmed.len() > 8 || content.first().is_some_and(|b| b & 0x80 != 0) {
+        return Err(TimestampError::Malformed("integer out of range".to_string()));
+    }
+    Ok(trimmed.iter().fold(0u64, |value, b| (value << 8) | *b as u64))
+}
+
+/// Decode a DER OID into dotted form
+fn oid_string(content: &[u8]) -> Result<String, TimestampError> {
+    let (&first, rest) = content
+        .split_first()
+        .ok_or_else(|| TimestampError::Malformed("empty OID".to_string()))?;
+    let mut arcs = vec![u64::from(first / 40).min(2), u64::from(first) - 40 * u64::from(first / 40).min(2)];
+    let mut value = 0u64;
+    for b in rest {
+        value = (value << 7) | u64::from(b & 0x7f);
+        if b & 0x80 == 0 {
+            arcs.push(value);
+            value = 0;
+        }
+    }
+    Ok(arcs.iter().map(u64::to_string).collect::<Vec<_>>().join("."))
+}
+
+/// Decode a DER GeneralizedTime (`YYYYMMDDHHMMSS[.fff]Z`)
+fn generalized_time(content: &[u8]) -> Result<DateTime<Utc>, TimestampError> {
+    let text = std::str::from_utf8(content)
+        .map_err(|_| TimestampError::Malformed("genTime is not ASCII".to_string()))?;
+    let naive = NaiveDateTime::parse_from_str(text, "%Y%m%d%H%M%S%.fZ")
+        .map_err(|e| TimestampError::Malformed(format!("invalid genTime '{}': {}", text, e)))?;
+    Ok(naive.and_utc())
+}
+
+/// Parse an RFC 3161 `TimeStampResp`, returning the granted token
+pub fn parse_timestamp_response(bytes: &[u8]) -> Result<TimestampToken, TimestampError> {
+    let (response, _) = expect(bytes, TAG_SEQUENCE, "TimeStampResp")?;
+    let (status_info, rest) = expect(response.content, TAG_SEQUENCE, "PKIStatusInfo")?;
+    let (status, status_rest) = expect(status_info.content, TAG_INTEGER, "PKIStatus")?;
+    let status = uint(status.content)?;
+    // 0 is granted, 1 granted with modifications
+    if status > 1 {
+        let message = read(status_rest)
+            .ok()
+            .and_then(|(texts, _)| read(texts.content).ok())
+            .map(|(text, _)| String::from_utf8_lossy(text.content).into_owned())
+            .unwrap_or_default();
+        return Err(TimestampError::Rejected { status, message });
+    }
+    let (token, _) = expect(rest, TAG_SEQUENCE, "TimeStampToken")?;
+    parse_timestamp_token(token.raw)
+}
+
+/// Parse a timestamp token (a CMS `ContentInfo` wrapping a `TSTInfo`)
+pub fn parse_timestamp_token(der: &[u8]) -> Result<TimestampToken, TimestampError> {
+    let (content_info, _) = expect(der, TAG_SEQUENCE, "ContentInfo")?;
+    let (content_type, rest) = expect(content_info.content, TAG_OID, "contentType")?;
+    if content_type.content != SIGNED_DATA_OID {
+        return Err(TimestampError::Malformed("token is not CMS signed data".to_string()));
+    }
+    let (explicit, _) = expect(rest, TAG_EXPLICIT_0, "content")?;
+    let (signed_data, _) = expect(explicit.content, TAG_SEQUENCE, "SignedData")?;
+    let (_, rest) = expect(signed_data.content, TAG_INTEGER, "version")?;
+    let (_, rest) = expect(rest, TAG_SET, "digestAlgorithms")?;
+    let (encap, _) = expect(rest, TAG_SEQUENCE, "encapContentInfo")?;
+    let (econtent_type, rest) = expect(encap.content, TAG_OID, "eContentType")?;
+    if econtent_type.content != TST_INFO_OID {
+        return Err(TimestampError::Malformed("token does not carry a TSTInfo".to_string()));
+    }
+    let (explicit, _) = expect(rest, TAG_EXPLICIT_0, "eContent")?;
+    let (econtent, _) = expect(explicit.content, TAG_OCTET_STRING, "eContent")?;
+
+    let (tst_info, _) = expect(econtent.content, TAG_SEQUENCE, "TSTInfo")?;
+    let (_, rest) = expect(tst_info.content, TAG_INTEGER, "version")?;
+    let (policy, rest) = expect(rest, TAG_OID, "policy")?;
+    let (imprint, rest) = expect(rest, TAG_SEQUENCE, "messageImprint")?;
+    let (algorithm, imprint_rest) = expect(imprint.content, TAG_SEQUENCE, "hashAlgorithm")?;
+    let (algorithm_oid, _) = expect(algorithm.content, TAG_OID, "hashAlgorithm")?;
+    if algorithm_oid.content != SHA256_OID {
+        return Err(TimestampError::Mismatch("token imprint is not SHA-256".to_string()));
+    }
+    let (hashed, _) = expect(imprint_rest, TAG_OCTET_STRING, "hashedMessage")?;
+    let (serial, rest) = expect(rest, TAG_INTEGER, "serialNumber")?;
+    let (gen_time, mut rest) = expect(rest, TAG_GENERALIZED_TIME, "genTime")?;
+
+    // accuracy and ordering may precede the nonce
+    let mut nonce = None;
+    while !rest.is_empty() {
+        let (element, next) = read(rest)?;
+        if element.tag == TAG_INTEGER {
+            nonce = Some(uint(element.content)?);
+            break;
+        }
+        rest = next;
+    }
+
+    Ok(TimestampToken {
+        der: der.to_vec(),
+        message_imprint: hashed.content.to_vec(),
+        serial_number: serial.content.to_vec(),
+        policy: oid_string(policy.content)?,
+        gen_time: generalized_time(gen_time.content)?,
+        nonce,
+    })
+}
+
+/// A message's timestamp token, with whether it still covers the message
+pub async fn message_timestamp(db: &Database, tenant: &TenantScope, message_id: &str) -> Result<serde_json::Value, AppError> {
+    let message = crate::get_tenant_message(db, tenant, message_id).await?;
+    let timestamp = db
+        .get_message_timestamp(message_id)
+        .await?
+        .ok_or_else(|| TimestampError::NotFound(message_id.to_string()))?;
+
+    // A deleted message's body is gone, so its hash can no longer be recomputed
+    let message_hash = if message.is_tombstone() {
+        None
+    } else {
+        Some(hex::encode(stored_message_hash(&message)?))
+    };
+
+    Ok(serde_json::json!({
+        "message_id": message_id,
+        "message_hash": message_hash,
+        "imprint_matches": message_hash.map(|hash| timestamp.message_imprint == hash),
+        "timestamp": timestamp,
+    }))
+}
+
+/// Create router for timestamp endpoints
+pub fn timestamp_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/message/:message_id/timestamp", get(get_timestamp_handler))
+}
+
+/// Create router for authenticated timestamp endpoints
+pub fn authenticated_timestamp_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/message/:message_id/timestamp", get(authenticated_get_timestamp_handler))
+}
+
+/// Handler to retrieve a message's timestamp token
+#[utoipa::path(
+    get,
+    path = "/message/{message_id}/timestamp",
+    operation_id = "getTimestamp",
+    tag = "timestamping",
+    params(("message_id" = String, Path, description = "Message ID")),
+    responses(
+        (status = 200, description = "The message's RFC 3161 timestamp token", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_timestamp_handler(
+    State(db): State<Arc<Database>>,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Retrieving timestamp for message: {}", message_id);
+
+    let mut response = message_timestamp(&db, &tenant, &message_id).await?;
+    response["status"] = "success".into();
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Authenticated handler to retrieve a message's timestamp token
+#[instrument(skip_all)]
+async fn authenticated_get_timestamp_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    tenant: TenantScope,
+    Path(message_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} retrieving timestamp for message: {}", auth.user_id, message_id);
+
+    let mut response = message_timestamp(&db, &tenant, &message_id).await?;
+    response["status"] = "success".into();
+    response["authenticated_user"] = auth.user_id.into();
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use ed25519_dalek::Signer;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use tower::ServiceExt;
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, Respond, ResponseTemplate};
+
+    /// A granted `TimeStampResp` for `digest`, in the shape a real TSA returns
+    fn granted_response(digest: &[u8], nonce: u64) -> Vec<u8> {
+        let algorithm = der(TAG_SEQUENCE, &[der(TAG_OID, SHA256_OID), der(TAG_NULL, &[])].concat());
+        let imprint = der(TAG_SEQUENCE, &[algorithm.clone(), der(TAG_OCTET_STRING, digest)].concat());
+        let accuracy = der(TAG_SEQUENCE, &der_uint(1));
+        let tst_info = der(
+            TAG_SEQUENCE,
+            &[
+                der_uint(1),
+                der(TAG_OID, &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x03]),
+                imprint,
+                der_uint(0x1234),
+                der(TAG_GENERALIZED_TIME, b"20261017060536.25Z"),
+                accuracy,
+                der_uint(nonce),
+            ]
+            .concat(),
+        );
+        let encap = der(
+            TAG_SEQUENCE,
+            &[der(TAG_OID, TST_INFO_OID), der(TAG_EXPLICIT_0, &der(TAG_OCTET_STRING, &tst_info))].concat(),
+        );
+        let signed_data = der(
+            TAG_SEQUENCE,
+            &[der_uint(3), der(TAG_SET, &algorithm), encap, der(TAG_SET, &[])].concat(),
+        );
+        let token = der(TAG_SEQUENCE, &[der(TAG_OID, SIGNED_DATA_OID), der(TAG_EXPLICIT_0, &signed_data)].concat());
+        der(TAG_SEQUENCE, &[der(TAG_SEQUENCE, &der_uint(0)), token].concat())
+    }
+
+    /// Answers each request with a token for the digest and nonce it carries
+    struct FakeAuthority;
+
+    impl Respond for FakeAuthority {
+        fn respond(&self, request: &wiremock::Request) -> ResponseTemplate {
+            let (request, _) = read(&request.body).unwrap();
+            let (_, rest) = read(request.content).unwrap();
+            let (imprint, rest) = read(rest).unwrap();
+            let (_, hashed) = read(imprint.content).unwrap();
+            let (hashed, _) = read(hashed).unwrap();
+            let (nonce, _) = read(rest).unwrap();
+            ResponseTemplate::new(200)
+                .insert_header("Content-Type", TIMESTAMP_REPLY_CONTENT_TYPE)
+                .set_body_bytes(granted_response(hashed.content, uint(nonce.content).unwrap()))
+        }
+    }
+
+    async fn stored_message(db: &Database) -> StoredMessage {
+        let sender = generate_secure_keypair_with_seed(3);
+        let context = b"timestamped context";
+        let message = crate::Message {
+            sender: hex::encode(sender.public_key_bytes()),
+            context: hex::encode(context),
+            body: "pay invoice 42".to_string(),
+            proof: hex::encode(sender.as_keypair().sign(context).to_bytes()),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        };
+        let message_id = db.store_message(StoredMessage::from(message)).await.unwrap();
+        db.get_message_by_id(&message_id).await.unwrap()
+    }
+
+    #[test]
+    fn test_request_encodes_imprint_nonce_and_cert_req() {
+        let request = timestamp_request(&[7u8; 32], 0x80, true);
+
+        let (request, rest) = expect(&request, TAG_SEQUENCE, "TimeStampReq").unwrap();
+        let (version, fields) = expect(request.content, TAG_INTEGER, "version").unwrap();
+        let (_, fields) = expect(fields, TAG_SEQUENCE, "messageImprint").unwrap();
+        let (nonce, fields) = expect(fields, TAG_INTEGER, "nonce").unwrap();
+        let (cert_req, _) = expect(fields, TAG_BOOLEAN, "certReq").unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(uint(version.content).unwrap(), 1);
+        // A high bit needs a leading zero to stay positive
+        assert_eq!(nonce.content, &[0x00, 0x80]);
+        assert_eq!(cert_req.content, &[0xff]);
+    }
+
+    #[test]
+    fn test_granted_response_is_parsed() {
+        let token = parse_timestamp_response(&granted_response(&[9u8; 32], 42)).unwrap();
+
+        assert_eq!(token.message_imprint, vec![9u8; 32]);
+        assert_eq!(token.serial_number, vec![0x12, 0x34]);
+        assert_eq!(token.policy, "1.3.6.1.4.1.311.3");
+        assert_eq!(token.nonce, Some(42));
+        assert_eq!(token.gen_time.to_rfc3339(), "2026-10-17T06:05:36.250+00:00");
+        assert_eq!(parse_timestamp_token(&token.der).unwrap(), token);
+    }
+
+    #[test]
+    fn test_rejected_and_malformed_responses_fail() {
+        let status = der(TAG_SEQUENCE, &[der_uint(2), der(TAG_SEQUENCE, &der(0x0c, b"bad request"))].concat());
+        let rejected = der(TAG_SEQUENCE, &status);
+
+        assert!(matches!(
+            parse_timestamp_response(&rejected),
+            Err(TimestampError::Rejected { status: 2, message }) if message == "bad request"
+        ));
+        let mut truncated = granted_response(&[9u8; 32], 42);
+        truncated.truncate(truncated.len() - 5);
+        assert!(parse_timestamp_response(&truncated).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_message_is_timestamped_and_served() {
+        // ARRANGE: A TSA answering every request, and a stored message
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("Content-Type", TIMESTAMP_QUERY_CONTENT_TYPE))
+            .respond_with(FakeAuthority)
+            .mount(&server)
+            .await;
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let message = stored_message(&db).await;
+        let authority = TimestampAuthority::new(&server.uri(), true, Duration::from_secs(5)).unwrap();
+
+        // ACT: Timestamp the message and fetch the token
+        let stored = authority.timestamp_message(&db, &message).await.unwrap();
+        let app = Router::new().merge(timestamp_routes()).with_state(db.clone());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/message/{}/timestamp", message.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        // ASSERT: The token covers the message hash and round-trips as DER
+        assert_eq!(stored.message_imprint, hex::encode(stored_message_hash(&message).unwrap()));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["imprint_matches"], true);
+        assert_eq!(json["timestamp"]["tsa_url"], server.uri());
+        let der = STANDARD.decode(json["timestamp"]["token"].as_str().unwrap()).unwrap();
+        assert_eq!(parse_timestamp_token(&der).unwrap().serial_number, vec![0x12, 0x34]);
+    }
+
+    #[tokio::test]
+    async fn test_token_for_another_hash_is_rejected() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(granted_response(&[1u8; 32], 1)))
+            .mount(&server)
+            .await;
+        let authority = TimestampAuthority::new(&server.uri(), true, Duration::from_secs(5)).unwrap();
+
+        let result = authority.request(&[2u8; 32]).await;
+
+        assert!(matches!(result, Err(TimestampError::Mismatch(_))));
+    }
+
+    #[tokio::test]
+    async fn test_message_without_token_is_not_found() {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let message = stored_message(&db).await;
+
+        let result = message_timestamp(&db, &TenantScope::default(), &message.id).await;
+
+        assert!(matches!(result, Err(AppError::Timestamp(TimestampError::NotFound(_)))));
+    }
+}
@@ -0,0 +1,371 @@
+//! TLS Termination Module
+//!
+//! This module lets the relay terminate TLS itself with rustls instead of
+//! relying on a reverse proxy. The certificate chain and private key are
+//! read from the PEM files named in the relay configuration and can be
+//! replaced without a restart: sending the process `SIGHUP` reloads them,
+//! and new connections use the new certificate.
+//!
+//! When a client CA is configured, connecting clients are asked for a
+//! certificate signed by that CA. Clients without one may still connect,
+//! but relay-to-relay federation endpoints reject them (see
+//! [`require_client_certificate`]), so peers must authenticate at the TLS
+//! layer in addition to signing their requests.
+
+use axum::{
+    extract::Request,
+    middleware::{AddExtension, Next},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use axum_server::{
+    accept::Accept,
+    tls_rustls::{RustlsAcceptor, RustlsConfig},
+};
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::server::TlsStream;
+use tower::Layer;
+use tracing::{info, warn};
+
+use crate::{config::TlsConfig, federation::FederationError, AppError};
+
+/// TLS-specific error types
+#[derive(Error, Debug)]
+pub enum TlsError {
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("TLS certificate and key paths must both be configured")]
+    NotConfigured,
+
+    #[error("No certificates found in {0}")]
+    NoCertificates(PathBuf),
+
+    #[error("No private key found in {0}")]
+    NoPrivateKey(PathBuf),
+
+    #[error("Invalid client CA: {0}")]
+    InvalidClientCa(String),
+
+    #[error("TLS configuration error: {0}")]
+    Rustls(#[from] rustls::Error),
+}
+
+/// Client certificate status of a TLS connection, available to handlers as an extension
+#[derive(Debug, Clone)]
+pub enum ClientCertificate {
+    /// No client CA is configured, so no certificate was requested
+    NotRequested,
+    /// A certificate was requested but the client did not present one
+    Missing,
+    /// The client's end-entity certificate, verified against the client CA
+    Verified(CertificateDer<'static>),
+}
+
+/// Build a rustls server configuration from the configured PEM files
+pub fn load_server_config(tls: &TlsConfig) -> Result<Arc<ServerConfig>, TlsError> {
+    let (cert_path, key_path) = match (&tls.cert_path, &tls.key_path) {
+        (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+        _ => return Err(TlsError::NotConfigured),
+    };
+    let certs = read_certificates(cert_path)?;
+    let key = read_private_key(key_path)?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone()).with_safe_default_protocol_versions()?;
+    let builder = match &tls.client_ca_path {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certificates(ca_path)? {
+                roots.add(cert).map_err(|e| TlsError::InvalidClientCa(e.to_string()))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .allow_unauthenticated()
+                .build()
+                .map_err(|e| TlsError::InvalidClientCa(e.to_string()))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut config = builder.with_single_cert(certs, key)?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// Read every certificate in a PEM file
+fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let pem = read_file(path)?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|source| TlsError::Read { path: path.to_path_buf(), source })?;
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificates(path.to_path_buf()));
+    }
+    Ok(certs)
+}
+
+/// Read the first private key in a PEM file
+fn read_private_key(path: &Path) -> Result<PrivateKeyDer<'static>, TlsError> {
+    let pem = read_file(path)?;
+    rustls_pemfile::private_key(&mut pem.as_slice())
+        .map_err(|source| TlsError::Read { path: path.to_path_buf(), source })?
+        .ok_or_else(|| TlsError::NoPrivateKey(path.to_path_buf()))
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, TlsError> {
+    std::fs::read(path).map_err(|source| TlsError::Read { path: path.to_path_buf(), source })
+}
+
+/// Reload the certificate and key whenever the process receives `SIGHUP`
+///
+/// A failed reload is logged and the previous certificate stays in use.
+#[cfg(unix)]
+pub fn spawn_reload_on_sighup(tls: TlsConfig, rustls_config: RustlsConfig) -> std::io::Result<tokio::task::JoinHandle<()>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    Ok(tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match load_server_config(&tls) {
+                Ok(server_config) => {
+                    rustls_config.reload_from_config(server_config);
+                    info!("🔐 TLS certificate reloaded");
+                }
+                Err(e) => warn!("Failed to reload TLS certificate, keeping the current one: {}", e),
+            }
+        }
+    }))
+}
+
+/// Acceptor performing the TLS handshake and exposing the client certificate to handlers
+#[derive(Clone)]
+pub struct RelayTlsAcceptor {
+    inner: RustlsAcceptor,
+    verify_clients: bool,
+}
+
+impl RelayTlsAcceptor {
+    /// Create an acceptor for the given rustls configuration
+    ///
+    /// `verify_clients` should be set when a client CA is configured.
+    pub fn new(config: RustlsConfig, verify_clients: bool) -> Self {
+        Self {
+            inner: RustlsAcceptor::new(config),
+            verify_clients,
+        }
+    }
+}
+
+impl<I, S> Accept<I, S> for RelayTlsAcceptor
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    S: Send + 'static,
+{
+    type Stream = TlsStream<I>;
+    type Service = AddExtension<S, ClientCertificate>;
+    type Future = Pin<Box<dyn Future<Output = std::io::Result<(Self::Stream, Self::Service)>> + Send>>;
+
+    fn accept(&self, stream: I, service: S) -> Self::Future {
+        let acceptor = self.inner.clone();
+        let verify_clients = self.verify_clients;
+
+        Box::pin(async move {
+            let (stream, service) = acceptor.accept(stream, service).await?;
+            let presented = stream.get_ref().1.peer_certificates().and_then(|certs| certs.first());
+            let certificate = match presented {
+                Some(cert) => ClientCertificate::Verified(cert.clone().into_owned()),
+                None if verify_clients => ClientCertificate::Missing,
+                None => ClientCertificate::NotRequested,
+            };
+            Ok((stream, Extension(certificate).layer(service)))
+        })
+    }
+}
+
+/// Middleware rejecting requests without a client certificate when client verification is enabled
+///
+/// Requests on plain connections, or TLS connections without a client CA,
+/// are passed through.
+pub async fn require_client_certificate(request: Request, next: Next) -> Response {
+    if let Some(ClientCertificate::Missing) = request.extensions().get::<ClientCertificate>() {
+        return AppError::Federation(FederationError::ClientCertificateRequired).into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use tower::ServiceExt;
+
+    struct Certificates {
+        dir: tempfile::TempDir,
+        config: TlsConfig,
+        ca: rcgen::Certificate,
+        ca_key: rcgen::KeyPair,
+    }
+
+    /// Write a CA and a server certificate and key it signed, and return their paths
+    fn write_certificates(with_client_ca: bool) -> Certificates {
+        let dir = tempfile::tempdir().unwrap();
+        let ca_key = rcgen::KeyPair::generate().unwrap();
+        let mut ca_params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let server_key = rcgen::KeyPair::generate().unwrap();
+        let server = rcgen::CertificateParams::new(vec!["localhost".to_string()])
+            .unwrap()
+            .signed_by(&server_key, &ca, &ca_key)
+            .unwrap();
+
+        let path = |name: &str| dir.path().join(name);
+        std::fs::write(path("ca.pem"), ca.pem()).unwrap();
+        std::fs::write(path("cert.pem"), server.pem()).unwrap();
+        std::fs::write(path("key.pem"), server_key.serialize_pem()).unwrap();
+
+        let config = TlsConfig {
+            cert_path: Some(path("cert.pem")),
+            key_path: Some(path("key.pem")),
+            client_ca_path: with_client_ca.then(|| path("ca.pem")),
+        };
+        Certificates { dir, config, ca, ca_key }
+    }
+
+    /// Connect over TLS, optionally presenting a client certificate, and return the response body
+    async fn get_over_tls(addr: std::net::SocketAddr, certificates: &Certificates, client_certificate: bool) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut roots = RootCertStore::empty();
+        roots.add(certificates.ca.der().clone()).unwrap();
+        let builder = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+        let client_config = if client_certificate {
+            let key = rcgen::KeyPair::generate().unwrap();
+            let cert = rcgen::CertificateParams::new(vec!["peer-relay".to_string()])
+                .unwrap()
+                .signed_by(&key, &certificates.ca, &certificates.ca_key)
+                .unwrap();
+            let key = PrivateKeyDer::try_from(key.serialize_der()).unwrap();
+            builder.with_client_auth_cert(vec![cert.der().clone()], key).unwrap()
+        } else {
+            builder.with_no_client_auth()
+        };
+
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config));
+        let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let server_name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut stream = connector.connect(server_name, tcp).await.unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn test_server_config_loads_from_pem_files() {
+        // ARRANGE: A certificate, key and client CA on disk
+        let certificates = write_certificates(true);
+
+        // ACT: Build the server configuration
+        let config = load_server_config(&certificates.config).unwrap();
+
+        // ASSERT: HTTP/2 and HTTP/1.1 are offered
+        assert_eq!(config.alpn_protocols, vec![b"h2".to_vec(), b"http/1.1".to_vec()]);
+    }
+
+    #[test]
+    fn test_invalid_pem_files_are_reported() {
+        let certificates = write_certificates(false);
+        std::fs::write(certificates.dir.path().join("key.pem"), "not a key").unwrap();
+
+        let missing_key = load_server_config(&certificates.config).unwrap_err();
+        let missing_file = load_server_config(&TlsConfig {
+            cert_path: Some(certificates.dir.path().join("missing.pem")),
+            ..certificates.config.clone()
+        })
+        .unwrap_err();
+
+        assert!(matches!(missing_key, TlsError::NoPrivateKey(_)));
+        assert!(matches!(missing_file, TlsError::Read { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_federation_requires_client_certificate_under_mutual_tls() {
+        let app = Router::new()
+            .route("/inbound", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn(require_client_certificate));
+        let request = |certificate: Option<ClientCertificate>| {
+            let mut request = axum::http::Request::builder().uri("/inbound").body(Body::empty()).unwrap();
+            if let Some(certificate) = certificate {
+                request.extensions_mut().insert(certificate);
+            }
+            request
+        };
+
+        let missing = app.clone().oneshot(request(Some(ClientCertificate::Missing))).await.unwrap();
+        let verified = app
+            .clone()
+            .oneshot(request(Some(ClientCertificate::Verified(CertificateDer::from(vec![1, 2, 3])))))
+            .await
+            .unwrap();
+        let not_requested = app.clone().oneshot(request(Some(ClientCertificate::NotRequested))).await.unwrap();
+        let plain = app.oneshot(request(None)).await.unwrap();
+
+        assert_eq!(missing.status(), StatusCode::FORBIDDEN);
+        assert_eq!(verified.status(), StatusCode::OK);
+        assert_eq!(not_requested.status(), StatusCode::OK);
+        assert_eq!(plain.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_acceptor_exposes_client_certificate() {
+        // ARRANGE: A TLS server with client verification reporting the client certificate status
+        let certificates = write_certificates(true);
+        let rustls_config = RustlsConfig::from_config(load_server_config(&certificates.config).unwrap());
+        let app = Router::new().route(
+            "/",
+            get(|Extension(certificate): Extension<ClientCertificate>| async move {
+                match certificate {
+                    ClientCertificate::Verified(_) => "verified",
+                    ClientCertificate::Missing => "missing",
+                    ClientCertificate::NotRequested => "not requested",
+                }
+            }),
+        );
+        let handle = axum_server::Handle::new();
+        let server = axum_server::bind("127.0.0.1:0".parse().unwrap())
+            .acceptor(RelayTlsAcceptor::new(rustls_config, true))
+            .handle(handle.clone());
+        tokio::spawn(server.serve(app.into_make_service()));
+        let addr = handle.listening().await.unwrap();
+
+        // ACT: Connect with and without a certificate signed by the client CA
+        let with_certificate = get_over_tls(addr, &certificates, true).await;
+        let without_certificate = get_over_tls(addr, &certificates, false).await;
+
+        // ASSERT: Handlers can tell the two connections apart
+        assert!(with_certificate.ends_with("verified"), "{}", with_certificate);
+        assert!(without_certificate.ends_with("missing"), "{}", without_certificate);
+        handle.shutdown();
+    }
+}
@@ -0,0 +1,348 @@
+//! Transparency Log Module
+//!
+//! Every verified message the relay stores is appended to an append-only
+//! Merkle tree (see [`proof_messenger_protocol::transparency`]). Third parties
+//! can fetch a signed tree head committing to the whole log and an inclusion
+//! proof for any message, and so audit that the relay has not retroactively
+//! dropped or altered a proof it accepted.
+//!
+//! A message's leaf is [`leaf_hash`] over its relay-assigned `id`, the
+//! [`message_hash`] of its `sender`, `context` and `body`, and its `proof`,
+//! each exactly as the relay returns them (hex strings are hashed as text).
+//!
+//! Messages are always logged. Signing tree heads is enabled by setting
+//! `TRANSPARENCY_SIGNING_KEY` (see [`TransparencyLog::from_env`]) and layering
+//! the resulting [`TransparencyLog`] onto the router as an [`axum::Extension`].
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Extension, Router,
+};
+use proof_messenger_protocol::evidence::{sign_evidence_bundle, EvidenceBundle};
+use proof_messenger_protocol::key::SecureKeypair;
+use proof_messenger_protocol::receipt::message_hash;
+use proof_messenger_protocol::transparency::{
+    inclusion_proof, leaf_hash, root_hash, sign_tree_head, SignedTreeHead, TreeHash,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, instrument};
+
+use crate::{
+    database::{Database, DatabaseError, StoredMessage, TransparencyLogEntry},
+    AppError,
+};
+
+/// Transparency-log-specific error types
+#[derive(Error, Debug)]
+pub enum TransparencyError {
+    #[error("Signed tree heads are not enabled on this relay")]
+    Disabled,
+
+    #[error("Invalid transparency log configuration: {0}")]
+    Config(String),
+
+    #[error("Message is not in the transparency log: {0}")]
+    NotLogged(String),
+
+    #[error("Invalid tree size: {0}")]
+    InvalidTreeSize(String),
+}
+
+/// Signs tree heads for the relay's transparency log
+pub struct TransparencyLog {
+    signing_key: SecureKeypair,
+}
+
+impl TransparencyLog {
+    /// Create a log signer from a keypair
+    pub fn new(signing_key: SecureKeypair) -> Self {
+        Self { signing_key }
+    }
+
+    /// Load the tree head signing key from environment variables
+    ///
+    /// - `TRANSPARENCY_SIGNING_KEY`: hex encoded 64-byte keypair
+    ///
+    /// Returns `Ok(None)` when no key is set.
+    pub fn from_env() -> Result<Option<Self>, TransparencyError> {
+        let Ok(key_hex) = std::env::var("TRANSPARENCY_SIGNING_KEY") else {
+            return Ok(None);
+        };
+        let key_bytes = hex::decode(key_hex.trim())
+            .map_err(|e| TransparencyError::Config(format!("TRANSPARENCY_SIGNING_KEY: {}", e)))?;
+        let signing_key = SecureKeypair::from_bytes(&key_bytes)
+            .map_err(|e| TransparencyError::Config(format!("TRANSPARENCY_SIGNING_KEY: {}", e)))?;
+        Ok(Some(Self::new(signing_key)))
+    }
+
+    /// Public key auditors verify tree heads with (hex encoded)
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.signing_key.public_key().to_bytes())
+    }
+
+    /// Sign a tree head over the log's current contents
+    pub async fn tree_head(&self, db: &Database) -> Result<SignedTreeHead, AppError> {
+        let tree_size = db.get_transparency_log_size().await?;
+        let leaves = db.get_transparency_leaf_hashes(tree_size).await?;
+        self.sign_leaves(&leaves)
+    }
+
+    /// Sign a tree head over `leaves`, the first entries of the log
+    pub fn sign_leaves(&self, leaves: &[TreeHash]) -> Result<SignedTreeHead, AppError> {
+        sign_tree_head(
+            &self.signing_key,
+            leaves.len() as u64,
+            chrono::Utc::now().timestamp_millis(),
+            &root_hash(leaves),
+        )
+        .map_err(|e| AppError::ProcessingError(format!("Failed to sign tree head: {}", e)))
+    }
+
+    /// Sign an evidence bundle with the log key
+    pub fn sign_evidence(&self, bundle: &mut EvidenceBundle) -> Result<(), AppError> {
+        sign_evidence_bundle(&self.signing_key, bundle)
+            .map_err(|e| AppError::ProcessingError(format!("Failed to sign evidence bundle: {}", e)))
+    }
+}
+
+/// A message's transparency log entry
+pub async fn log_entry(db: &Database, message_id: &str) -> Result<TransparencyLogEntry, AppError> {
+    db.get_transparency_log_entry(message_id).await.map_err(|e| match e {
+        DatabaseError::LogEntryNotFound(id) => TransparencyError::NotLogged(id).into(),
+        e => AppError::from(e),
+    })
+}
+
+/// Leaf hash of a stored message in the transparency log
+pub fn log_leaf_hash(message: &StoredMessage) -> TreeHash {
+    let hash = message_hash(message.sender.as_bytes(), message.context.as_bytes(), message.body.as_bytes());
+    leaf_hash(&message.id, &hash, message.proof.as_bytes())
+}
+
+/// Query parameters for an inclusion proof
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct InclusionProofQuery {
+    /// Size of the tree to prove inclusion in (defaults to the current size)
+    pub tree_size: Option<u64>,
+}
+
+/// Create router for transparency log endpoints
+pub fn transparency_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/tree-head", get(tree_head_handler))
+        .route("/proof/:message_id", get(inclusion_proof_handler))
+}
+
+/// Handler returning the current signed tree head
+#[utoipa::path(
+    get,
+    path = "/transparency/tree-head",
+    operation_id = "getTreeHead",
+    tag = "transparency",
+    responses(
+        (status = 200, description = "The log's signed tree head", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn tree_head_handler(
+    State(db): State<Arc<Database>>,
+    log: Option<Extension<Arc<TransparencyLog>>>,
+) -> Result<impl IntoResponse, AppError> {
+    let Extension(log) = log.ok_or(TransparencyError::Disabled)?;
+
+    let head = log.tree_head(&db).await?;
+    info!("Signed tree head at size {}", head.tree_size);
+
+    let response = Json(serde_json::json!({
+        "tree_size": head.tree_size,
+        "timestamp": head.timestamp,
+        "root_hash": hex::encode(head.root_hash),
+        "signature": hex::encode(head.signature.to_bytes()),
+        "public_key": log.public_key_hex()
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler returning an inclusion proof for a message
+#[utoipa::path(
+    get,
+    path = "/transparency/proof/{message_id}",
+    operation_id = "getInclusionProof",
+    tag = "transparency",
+    params(("message_id" = String, Path, description = "Message ID"), InclusionProofQuery),
+    responses(
+        (status = 200, description = "Inclusion proof for the message's leaf", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn inclusion_proof_handler(
+    State(db): State<Arc<Database>>,
+    Path(message_id): Path<String>,
+    Query(params): Query<InclusionProofQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Building inclusion proof for message: {}", message_id);
+
+    let entry = log_entry(&db, &message_id).await?;
+    let current_size = db.get_transparency_log_size().await? as u64;
+    let tree_size = params.tree_size.unwrap_or(current_size);
+    if tree_size > current_size {
+        return Err(TransparencyError::InvalidTreeSize(format!(
+            "the log only has {} entries",
+            current_size
+        ))
+        .into());
+    }
+    if entry.leaf_index as u64 >= tree_size {
+        return Err(TransparencyError::InvalidTreeSize(format!(
+            "message was appended at index {}, after a tree of size {}",
+            entry.leaf_index, tree_size
+        ))
+        .into());
+    }
+
+    let leaves = db.get_transparency_leaf_hashes(tree_size as i64).await?;
+    let path = inclusion_proof(&leaves, entry.leaf_index as u64)
+        .map_err(|e| AppError::ProcessingError(format!("Failed to build inclusion proof: {}", e)))?;
+
+    let response = Json(serde_json::json!({
+        "message_id": entry.message_id,
+        "leaf_index": entry.leaf_index,
+        "leaf_hash": entry.leaf_hash,
+        "tree_size": tree_size,
+        "root_hash": hex::encode(root_hash(&leaves)),
+        "audit_path": path.iter().map(hex::encode).collect::<Vec<_>>()
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_protocol::transparency::{
+        tree_hash_from_slice, verify_inclusion, verify_tree_head,
+    };
+    use tower::ServiceExt;
+
+    async fn setup(count: usize) -> (Arc<Database>, Vec<String>) {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        let mut ids = Vec::new();
+        for i in 0..count {
+            let message = StoredMessage::from(crate::Message {
+                sender: "aa".repeat(32),
+                context: "bb".to_string(),
+                body: format!("message {}", i),
+                proof: "cc".repeat(64),
+                pqc: None,
+                thread_id: None,
+                reply_to: None,
+                expires_at: None,
+                recipient: None,
+            });
+            ids.push(db.store_message(message).await.unwrap());
+        }
+        (db, ids)
+    }
+
+    fn app(db: Arc<Database>, log: Option<TransparencyLog>) -> Router {
+        let app = Router::new().nest("/transparency", transparency_routes()).with_state(db);
+        match log {
+            Some(log) => app.layer(Extension(Arc::new(log))),
+            None => app,
+        }
+    }
+
+    async fn get(app: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn hash(value: &serde_json::Value) -> TreeHash {
+        tree_hash_from_slice(&hex::decode(value.as_str().unwrap()).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_inclusion_proof_verifies_against_signed_tree_head() {
+        // ARRANGE: A log of five messages and a tree head signing key
+        let (db, ids) = setup(5).await;
+        let keypair = generate_secure_keypair_with_seed(11);
+        let public_key = keypair.public_key();
+        let app = app(db.clone(), Some(TransparencyLog::new(keypair)));
+
+        // ACT: Fetch the tree head and a proof for the fourth message
+        let (status, head) = get(app.clone(), "/transparency/tree-head").await;
+        let (_, proof) = get(app, &format!("/transparency/proof/{}", ids[3])).await;
+
+        // ASSERT: The head is signed, and the recomputed leaf is proven in it
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(head["tree_size"], 5);
+        let signed = SignedTreeHead {
+            tree_size: 5,
+            timestamp: head["timestamp"].as_i64().unwrap(),
+            root_hash: hash(&head["root_hash"]),
+            signature: ed25519_dalek::Signature::from_bytes(&hex::decode(head["signature"].as_str().unwrap()).unwrap()).unwrap(),
+        };
+        assert!(verify_tree_head(&signed, &public_key).is_ok());
+
+        let message = db.get_message_by_id(&ids[3]).await.unwrap();
+        let leaf = log_leaf_hash(&message);
+        assert_eq!(hash(&proof["leaf_hash"]), leaf);
+        let path: Vec<TreeHash> = proof["audit_path"].as_array().unwrap().iter().map(hash).collect();
+        assert!(verify_inclusion(&leaf, 3, 5, &path, &signed.root_hash).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_proof_for_earlier_tree_size() {
+        let (db, ids) = setup(4).await;
+
+        let (status, proof) = get(app(db.clone(), None), &format!("/transparency/proof/{}?tree_size=2", ids[1])).await;
+        let (too_small, _) = get(app(db.clone(), None), &format!("/transparency/proof/{}?tree_size=2", ids[2])).await;
+        let (too_large, _) = get(app(db, None), &format!("/transparency/proof/{}?tree_size=9", ids[1])).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(proof["tree_size"], 2);
+        assert_eq!(proof["audit_path"].as_array().unwrap().len(), 1);
+        assert_eq!(too_small, StatusCode::BAD_REQUEST);
+        assert_eq!(too_large, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_unlogged_message_and_disabled_signing() {
+        let (db, _) = setup(1).await;
+
+        let (missing, body) = get(app(db.clone(), None), "/transparency/proof/nope").await;
+        let (disabled, _) = get(app(db, None), "/transparency/tree-head").await;
+
+        assert_eq!(missing, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "LOG_ENTRY_NOT_FOUND");
+        assert_eq!(disabled, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_log_is_append_only() {
+        let (db, ids) = setup(2).await;
+
+        // Retention deletes messages but never their log entries
+        db.delete_old_messages(chrono::Utc::now() + chrono::Duration::days(1)).await.unwrap();
+
+        assert_eq!(db.get_transparency_log_size().await.unwrap(), 2);
+        assert_eq!(db.get_transparency_log_entry(&ids[1]).await.unwrap().leaf_index, 1);
+    }
+}
@@ -0,0 +1,155 @@
+//! Verification Pool Module
+//!
+//! Signature verification is CPU-bound: a burst of relayed messages verified
+//! on the async runtime's worker threads delays every other request those
+//! threads are serving. With `verification.offload` set (see
+//! [`crate::config::VerificationConfig`]) the relay verifies on tokio's
+//! blocking thread pool instead, at most `max_concurrent` messages at once.
+//! Verifications beyond the limit wait their turn without holding a thread.
+//!
+//! The wait is visible in the `verification_queue_depth`,
+//! `verifications_in_flight` and `verification_queue_wait_seconds` metrics.
+//! A queue that stays deep means verification, not I/O, is the bottleneck.
+
+use once_cell::sync::OnceCell;
+use prometheus_client::metrics::gauge::Gauge;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Semaphore;
+
+use crate::{config::VerificationConfig, metrics, AppError};
+
+/// Pool installed for the process
+static INSTALLED: OnceCell<VerificationPool> = OnceCell::new();
+
+/// Runs verifications on blocking threads, a bounded number at once
+pub struct VerificationPool {
+    permits: Arc<Semaphore>,
+    max_concurrent: usize,
+}
+
+/// Counts a verification in a gauge for as long as it is held
+struct Tracked(&'static Gauge);
+
+impl Tracked {
+    fn new(gauge: &'static Gauge) -> Self {
+        gauge.inc();
+        Self(gauge)
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+impl VerificationPool {
+    /// Pool for the given settings
+    pub fn new(config: &VerificationConfig) -> Self {
+        let max_concurrent = config
+            .max_concurrent
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |cpus| cpus.get()))
+            .max(1);
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+        }
+    }
+
+    /// Verifications that may run at once
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    /// Run `verify` on a blocking thread once a slot is free
+    pub async fn run<T, F>(&self, verify: F) -> Result<T, AppError>
+    where
+        F: FnOnce() -> Result<T, AppError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let queued = Tracked::new(&metrics::VERIFICATION_QUEUE_DEPTH);
+        let waiting_since = Instant::now();
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| AppError::ProcessingError(format!("Verification pool closed: {}", e)))?;
+        drop(queued);
+        metrics::VERIFICATION_QUEUE_WAIT_SECONDS.observe(waiting_since.elapsed().as_secs_f64());
+
+        // The slot is released when verification finishes, even if the caller stopped waiting
+        let running = Tracked::new(&metrics::VERIFICATIONS_IN_FLIGHT);
+        tokio::task::spawn_blocking(move || {
+            let _slot = (permit, running);
+            verify()
+        })
+        .await
+        .map_err(|e| AppError::ProcessingError(format!("Verification task failed: {}", e)))?
+    }
+}
+
+/// Verify on `pool` for the rest of the process
+///
+/// Only the first installed pool takes effect.
+pub fn install(pool: VerificationPool) {
+    let _ = INSTALLED.set(pool);
+}
+
+/// The installed pool, if verification is offloaded
+pub fn installed() -> Option<&'static VerificationPool> {
+    INSTALLED.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    async fn wait_for(condition: impl Fn() -> bool) {
+        for _ in 0..500 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("condition not reached");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_verifications_beyond_the_limit_wait_for_a_slot() {
+        // ARRANGE: A single-slot pool whose first verification blocks until released
+        let pool = Arc::new(VerificationPool::new(&VerificationConfig { offload: true, max_concurrent: Some(1), ..VerificationConfig::default() }));
+        let (release, blocked) = mpsc::channel::<()>();
+        let first = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.run(move || Ok(blocked.recv().is_ok())).await }
+        });
+        wait_for(|| pool.permits.available_permits() == 0).await;
+
+        // ACT: Queue a second verification, then release the first
+        let second = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.run(|| Ok("verified")).await }
+        });
+        wait_for(|| metrics::VERIFICATION_QUEUE_DEPTH.get() >= 1).await;
+        assert!(!second.is_finished());
+        release.send(()).unwrap();
+
+        // ASSERT: Both complete in turn and the slot is free again
+        assert!(first.await.unwrap().unwrap());
+        assert_eq!(second.await.unwrap().unwrap(), "verified");
+        assert_eq!(pool.permits.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_verification_errors_are_returned() {
+        let pool = VerificationPool::new(&VerificationConfig { offload: true, max_concurrent: None, ..VerificationConfig::default() });
+        assert!(pool.max_concurrent() >= 1);
+
+        let result: Result<(), AppError> = pool.run(|| Err(AppError::VerificationFailed)).await;
+        assert!(matches!(result, Err(AppError::VerificationFailed)));
+    }
+}
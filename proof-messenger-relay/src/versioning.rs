@@ -0,0 +1,190 @@
+//! API Versioning Module
+//!
+//! The relay's API is versioned by path: `/v1/relay`, `/v1/messages/:group_id`
+//! and so on. Versions are negotiated as follows:
+//!
+//! - The major version in the path is the whole contract; there is no
+//!   version header or media type to negotiate. Every versioned response
+//!   carries `API-Version: 1` so clients can log what they talked to.
+//! - Within a version, changes are additive only: new endpoints, new
+//!   optional request fields (which must deserialize with `#[serde(default)]`)
+//!   and new response fields. Clients must ignore response fields they do
+//!   not know.
+//! - Anything else is breaking, such as removing or renaming a `Message`
+//!   field, changing its type or meaning, or making an optional field
+//!   required. It ships as a new version (`/v2`) next to the old one, which
+//!   keeps its shapes until it is sunset.
+//! - The unversioned paths served before versioning are aliases for `/v1`.
+//!   Their responses carry `Deprecation` (RFC 9745), a `Link` to the
+//!   successor path, and, once `api.legacy_sunset` is set, `Sunset` (RFC 8594).
+//!
+//! Operational endpoints (`/health`, `/ready`, `/metrics`, `/openapi.json`)
+//! and relay-to-relay federation, whose peers sign the request path, stay
+//! unversioned. Route scopes (see [`crate::authorization`]) are declared once
+//! for the unversioned pattern and apply to every version of the route.
+//!
+//! The sunset date is set by layering an [`ApiVersioning`] onto the router as
+//! an [`axum::Extension`].
+
+use axum::{
+    extract::Request,
+    http::{header::LINK, HeaderName, HeaderValue},
+    middleware::{self, Next},
+    response::Response,
+    Extension, Router,
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+use crate::config::ApiConfig;
+
+/// Path prefix of the current API version
+pub const V1_PREFIX: &str = "/v1";
+
+/// Header naming the API version that served a response
+pub const API_VERSION_HEADER: HeaderName = HeaderName::from_static("api-version");
+
+/// Header marking a deprecated route (RFC 9745)
+pub const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+
+/// Header announcing when a deprecated route will be removed (RFC 8594)
+pub const SUNSET_HEADER: HeaderName = HeaderName::from_static("sunset");
+
+/// When the unversioned routes were deprecated (2026-10-17), as an RFC 9745 date
+const LEGACY_DEPRECATED_AT: &str = "@1792195200";
+
+/// Versioning settings for the relay's legacy routes
+#[derive(Debug, Clone, Default)]
+pub struct ApiVersioning {
+    legacy_sunset: Option<DateTime<Utc>>,
+}
+
+impl ApiVersioning {
+    /// Build the versioning settings from the relay configuration
+    pub fn new(config: &ApiConfig) -> Self {
+        Self {
+            legacy_sunset: config.legacy_sunset,
+        }
+    }
+
+    /// When the unversioned routes will be removed, if announced
+    pub fn legacy_sunset(&self) -> Option<DateTime<Utc>> {
+        self.legacy_sunset
+    }
+}
+
+/// Serve an API router under `/v1`, and deprecated at its unversioned paths
+pub fn versioned<S>(api: Router<S>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .nest(V1_PREFIX, api.clone().layer(middleware::from_fn(current_version)))
+        .merge(api.layer(middleware::from_fn(legacy_version)))
+}
+
+/// Strip the version prefix from a route path, leaving unversioned paths as they are
+pub fn unversioned_path(path: &str) -> &str {
+    match path.strip_prefix(V1_PREFIX) {
+        Some(rest) if rest.starts_with('/') => rest,
+        _ => path,
+    }
+}
+
+/// Middleware labelling responses of the current version
+async fn current_version(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(API_VERSION_HEADER, HeaderValue::from_static("1"));
+    response
+}
+
+/// Middleware labelling responses of a legacy unversioned route as deprecated
+async fn legacy_version(versioning: Option<Extension<Arc<ApiVersioning>>>, request: Request, next: Next) -> Response {
+    let successor = format!("<{}{}>; rel=\"successor-version\"", V1_PREFIX, request.uri().path());
+    let mut response = next.run(request).await;
+
+    let headers = response.headers_mut();
+    headers.insert(API_VERSION_HEADER, HeaderValue::from_static("1"));
+    headers.insert(DEPRECATION_HEADER, HeaderValue::from_static(LEGACY_DEPRECATED_AT));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.append(LINK, link);
+    }
+    if let Some(sunset) = versioning.and_then(|Extension(versioning)| versioning.legacy_sunset()) {
+        let date = sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        if let Ok(date) = HeaderValue::from_str(&date) {
+            headers.insert(SUNSET_HEADER, date);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn app(versioning: Option<ApiVersioning>) -> Router {
+        let api = Router::new()
+            .route("/messages/:group_id", get(|| async { "messages" }))
+            .nest("/invites", Router::new().route("/", get(|| async { "invites" })));
+        let app = versioned(api).route("/health", get(|| async { "ok" }));
+        match versioning {
+            Some(versioning) => app.layer(Extension(Arc::new(versioning))),
+            None => app,
+        }
+    }
+
+    async fn get_response(app: Router, uri: &str) -> Response {
+        app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_versioned_routes_are_current() {
+        // ARRANGE: An API served under /v1
+        let app = app(None);
+
+        // ACT: Call a versioned route
+        let response = get_response(app, "/v1/messages/team").await;
+
+        // ASSERT: It is served as v1 and not deprecated
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[API_VERSION_HEADER], "1");
+        assert!(response.headers().get(DEPRECATION_HEADER).is_none());
+        assert!(response.headers().get(SUNSET_HEADER).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_routes_are_deprecated() {
+        // ARRANGE: An API with an announced sunset
+        let sunset = DateTime::parse_from_rfc3339("2027-06-30T00:00:00Z").unwrap().with_timezone(&Utc);
+        let app = app(Some(ApiVersioning { legacy_sunset: Some(sunset) }));
+
+        // ACT: Call the unversioned alias of a route
+        let response = get_response(app, "/invites").await;
+
+        // ASSERT: It still works but points to its successor
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[DEPRECATION_HEADER], LEGACY_DEPRECATED_AT);
+        assert_eq!(response.headers()[LINK], "</v1/invites>; rel=\"successor-version\"");
+        assert_eq!(response.headers()[SUNSET_HEADER], "Wed, 30 Jun 2027 00:00:00 GMT");
+    }
+
+    #[tokio::test]
+    async fn test_operational_routes_are_unversioned() {
+        let response = get_response(app(None), "/health").await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(API_VERSION_HEADER).is_none());
+        assert!(response.headers().get(DEPRECATION_HEADER).is_none());
+    }
+
+    #[test]
+    fn test_unversioned_path() {
+        assert_eq!(unversioned_path("/v1/messages/:group_id"), "/messages/:group_id");
+        assert_eq!(unversioned_path("/messages/:group_id"), "/messages/:group_id");
+        assert_eq!(unversioned_path("/v10/relay"), "/v10/relay");
+    }
+}
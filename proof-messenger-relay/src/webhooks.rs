@@ -0,0 +1,890 @@
+//! Webhook Notification Module
+//!
+//! This module lets administrators register HTTPS callback URLs that are
+//! notified whenever a message passes verification, optionally filtered by
+//! group. With key pinning enabled (see [`crate::key_pinning`]), unfiltered
+//! webhooks are also notified when a user's public key changes. Each event is queued in the database and POSTed as JSON with an
+//! HMAC-SHA256 signature header; failed deliveries are retried with
+//! exponential backoff and their status is exposed for inspection.
+//!
+//! Receivers verify a delivery by recomputing the HMAC of
+//! `"<timestamp>.<body>"` with the webhook secret and comparing it to the
+//! `v1` value of the [`SIGNATURE_HEADER`] (see [`verify_signature`]).
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get},
+    Extension, Router,
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth_middleware::AuthContext,
+    database::{Database, DatabaseError, KeyChange, StoredMessage, StoredWebhook, WebhookDelivery},
+    egress::HttpClient,
+    metrics,
+    request_id::RequestId,
+    AppError,
+};
+
+/// Header carrying the delivery timestamp and HMAC signature
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+/// Header carrying the event type
+pub const EVENT_HEADER: &str = "x-webhook-event";
+/// Header carrying the delivery ID (stable across retries)
+pub const DELIVERY_HEADER: &str = "x-webhook-delivery";
+
+/// Event type sent when a message passes verification
+pub const MESSAGE_VERIFIED_EVENT: &str = "message.verified";
+/// Event type sent when an authenticated user's public key changes
+pub const KEY_CHANGED_EVENT: &str = "key.changed";
+
+/// Length of generated webhook secrets in bytes
+const SECRET_LENGTH: usize = 32;
+
+/// Maximum number of deliveries claimed per worker pass
+const DELIVERY_BATCH_SIZE: i64 = 50;
+
+/// Default and maximum number of deliveries returned when listing
+const MAX_DELIVERY_LIST: i64 = 100;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Webhook-specific error types
+#[derive(Error, Debug)]
+pub enum WebhookError {
+    #[error("Webhooks are not enabled on this relay")]
+    Disabled,
+
+    #[error("Invalid webhook configuration: {0}")]
+    Config(String),
+
+    #[error("Invalid webhook URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("Webhook not found: {0}")]
+    NotFound(String),
+}
+
+/// Webhook delivery settings
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// Attempts made before a delivery is marked failed
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each later retry
+    pub retry_base: std::time::Duration,
+    /// Upper bound on the delay between retries
+    pub retry_max: std::time::Duration,
+    /// How often the worker looks for due retries
+    pub poll_interval: std::time::Duration,
+    /// Timeout for a single delivery request
+    pub request_timeout: std::time::Duration,
+    /// Allow plain HTTP webhook URLs (local development and tests only)
+    pub allow_insecure_urls: bool,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            retry_base: std::time::Duration::from_secs(30),
+            retry_max: std::time::Duration::from_secs(3600),
+            poll_interval: std::time::Duration::from_secs(10),
+            request_timeout: std::time::Duration::from_secs(10),
+            allow_insecure_urls: false,
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Build webhook settings from the relay configuration
+    pub fn from_config(config: &crate::config::WebhookDeliveryConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts,
+            retry_base: std::time::Duration::from_secs(config.retry_base_secs),
+            retry_max: std::time::Duration::from_secs(config.retry_max_secs),
+            poll_interval: std::time::Duration::from_secs(config.poll_interval_secs),
+            request_timeout: std::time::Duration::from_secs(config.timeout_secs),
+            allow_insecure_urls: config.allow_insecure_urls,
+        }
+    }
+}
+
+/// A verified message as delivered to webhooks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookMessage {
+    /// Relay-assigned message ID
+    pub message_id: String,
+    /// Group the message was posted to
+    pub group_id: String,
+    /// Public key of the sender (hex encoded)
+    pub sender: String,
+    /// Context data that was signed (hex encoded)
+    pub context: String,
+    /// Message body content
+    pub body: String,
+    /// Sender's verified proof, so receivers can check it themselves (hex encoded)
+    pub proof: String,
+    /// Thread the message belongs to
+    pub thread_id: Option<String>,
+    /// ID of the message this one replies to
+    pub reply_to: Option<String>,
+    /// When the relay stored the message
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&StoredMessage> for WebhookMessage {
+    fn from(message: &StoredMessage) -> Self {
+        Self {
+            message_id: message.id.clone(),
+            group_id: message.group_id.clone(),
+            sender: message.sender.clone(),
+            context: message.context.clone(),
+            body: message.body.clone(),
+            proof: message.proof.clone(),
+            thread_id: message.thread_id.clone(),
+            reply_to: message.reply_to.clone(),
+            created_at: message.created_at,
+        }
+    }
+}
+
+/// JSON body POSTed to webhooks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEvent<T = WebhookMessage> {
+    /// Unique event ID, shared by every webhook notified of the event
+    pub id: String,
+    /// Event type (e.g. `message.verified`)
+    #[serde(rename = "type")]
+    pub event_type: String,
+    /// When the event occurred
+    pub created_at: DateTime<Utc>,
+    /// The verified message, or the key change for `key.changed`
+    pub data: T,
+}
+
+/// Request body for registering a webhook
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RegisterWebhookRequest {
+    /// HTTPS URL to POST events to
+    pub url: String,
+    /// Only notify for messages in this group
+    pub group_id: Option<String>,
+}
+
+/// Query parameters for listing deliveries
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeliveryListQuery {
+    /// Maximum number of deliveries to return (default and maximum 100)
+    pub limit: Option<i64>,
+}
+
+/// Compute the signature header value for a delivery body
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!("t={},v1={}", timestamp, hex::encode(payload_mac(secret, timestamp, body).finalize().into_bytes()))
+}
+
+/// Verify a signature header against a delivery body
+///
+/// Rejects signatures whose timestamp is more than `tolerance` away from
+/// `now`, so captured deliveries cannot be replayed later.
+pub fn verify_signature(secret: &str, header: &str, body: &[u8], now: DateTime<Utc>, tolerance: chrono::Duration) -> bool {
+    let mut timestamp = None;
+    let mut signature = None;
+    for part in header.split(',') {
+        match part.split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signature = hex::decode(value).ok(),
+            _ => {}
+        }
+    }
+    let (timestamp, signature) = match (timestamp, signature) {
+        (Some(timestamp), Some(signature)) => (timestamp, signature),
+        _ => return false,
+    };
+    if (now.timestamp() - timestamp).abs() > tolerance.num_seconds() {
+        return false;
+    }
+    payload_mac(secret, timestamp, body).verify_slice(&signature).is_ok()
+}
+
+/// HMAC over the timestamp and body of a delivery
+fn payload_mac(secret: &str, timestamp: i64, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Generate a random webhook secret (hex encoded)
+fn generate_secret() -> String {
+    let mut secret = [0u8; SECRET_LENGTH];
+    rand::thread_rng().fill_bytes(&mut secret);
+    hex::encode(secret)
+}
+
+/// Queues, signs and delivers webhook events
+pub struct WebhookDispatcher {
+    config: WebhookConfig,
+    client: HttpClient,
+}
+
+impl WebhookDispatcher {
+    /// Prepare the HTTP client used for deliveries
+    pub fn new(config: WebhookConfig) -> Result<Self, WebhookError> {
+        if config.max_attempts == 0 {
+            return Err(WebhookError::Config("max attempts must be at least 1".to_string()));
+        }
+        let client = crate::egress::client(reqwest::Client::builder().timeout(config.request_timeout))
+            .map_err(|e| WebhookError::Config(e.to_string()))?;
+
+        Ok(Self { config, client })
+    }
+
+    /// Check a webhook URL is absolute, uses HTTPS and targets an allowed host
+    pub fn validate_url(&self, url: &str) -> Result<(), WebhookError> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| WebhookError::InvalidUrl(e.to_string()))?;
+        match parsed.scheme() {
+            "https" => {}
+            "http" if self.config.allow_insecure_urls => {}
+            scheme => return Err(WebhookError::InvalidUrl(format!("{} URLs are not allowed, use HTTPS", scheme))),
+        }
+        crate::egress::current().check(&parsed).map_err(|e| WebhookError::InvalidUrl(e.to_string()))
+    }
+
+    /// Delay before retrying after `attempts` failed attempts
+    pub fn retry_delay(&self, attempts: u32) -> std::time::Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.config.retry_base.saturating_mul(factor).min(self.config.retry_max)
+    }
+
+    /// How long a claimed delivery is reserved for the claiming worker
+    fn lease(&self) -> chrono::Duration {
+        chrono::Duration::from_std(self.config.request_timeout * 2).unwrap_or_else(|_| chrono::Duration::minutes(1))
+    }
+
+    /// Queue a verified message for every subscribed webhook and deliver it
+    ///
+    /// Deliveries are leased to this call's background task; if the relay
+    /// stops before they complete, the worker picks them up once the lease
+    /// expires.
+    pub async fn notify(self: &Arc<Self>, db: &Arc<Database>, message: &StoredMessage) -> Result<(), AppError> {
+        let data = WebhookMessage::from(message);
+        self.publish(db, MESSAGE_VERIFIED_EVENT, &message.id, Some(&message.group_id), data).await
+    }
+
+    /// Queue a key change for every unfiltered webhook and deliver it
+    pub async fn notify_key_change(self: &Arc<Self>, db: &Arc<Database>, change: &KeyChange) -> Result<(), AppError> {
+        self.publish(db, KEY_CHANGED_EVENT, &change.id, None, change).await
+    }
+
+    /// Queue an event about `subject_id` for the webhooks subscribed to `group_id`, then deliver it
+    async fn publish<T: Serialize>(
+        self: &Arc<Self>,
+        db: &Arc<Database>,
+        event_type: &str,
+        subject_id: &str,
+        group_id: Option<&str>,
+        data: T,
+    ) -> Result<(), AppError> {
+        let event = WebhookEvent {
+            id: Uuid::new_v4().to_string(),
+            event_type: event_type.to_string(),
+            created_at: Utc::now(),
+            data,
+        };
+        let payload = serde_json::to_string(&event)
+            .map_err(|e| AppError::ProcessingError(format!("Failed to serialize webhook event: {}", e)))?;
+
+        let deliveries = db
+            .enqueue_webhook_event(event_type, subject_id, group_id, &payload, Utc::now() + self.lease())
+            .await?;
+        if deliveries.is_empty() {
+            return Ok(());
+        }
+
+        let dispatcher = Arc::clone(self);
+        let db = Arc::clone(db);
+        tokio::spawn(async move {
+            dispatcher.deliver_all(&db, deliveries).await;
+        });
+        Ok(())
+    }
+
+    /// Claim and attempt every delivery that is due, returning how many were attempted
+    pub async fn deliver_due(&self, db: &Database) -> usize {
+        let lease_until = Utc::now() + self.lease();
+        match db.claim_due_webhook_deliveries(lease_until, DELIVERY_BATCH_SIZE).await {
+            Ok(deliveries) => {
+                let count = deliveries.len();
+                self.deliver_all(db, deliveries).await;
+                count
+            }
+            Err(e) => {
+                warn!("Failed to claim due webhook deliveries: {}", e);
+                0
+            }
+        }
+    }
+
+    /// Attempt a batch of claimed deliveries
+    async fn deliver_all(&self, db: &Database, deliveries: Vec<WebhookDelivery>) {
+        let mut webhooks: HashMap<String, Option<StoredWebhook>> = HashMap::new();
+        for delivery in deliveries {
+            if !webhooks.contains_key(&delivery.webhook_id) {
+                let webhook = match db.get_webhook(&delivery.webhook_id).await {
+                    Ok(webhook) => Some(webhook),
+                    Err(DatabaseError::WebhookNotFound(_)) => None,
+                    Err(e) => {
+                        warn!("Failed to load webhook {}: {}", delivery.webhook_id, e);
+                        continue;
+                    }
+                };
+                webhooks.insert(delivery.webhook_id.clone(), webhook);
+            }
+            // Deleted webhooks take their queued deliveries with them
+            if let Some(Some(webhook)) = webhooks.get(&delivery.webhook_id) {
+                self.attempt(db, webhook, &delivery).await;
+            }
+        }
+    }
+
+    /// Make one delivery attempt and record its outcome
+    async fn attempt(&self, db: &Database, webhook: &StoredWebhook, delivery: &WebhookDelivery) {
+        let signature = sign_payload(&webhook.secret, Utc::now().timestamp(), delivery.payload.as_bytes());
+        let result = match self.client.post(&webhook.url) {
+            Ok(request) => request
+                .header("content-type", "application/json")
+                .header(SIGNATURE_HEADER, signature)
+                .header(EVENT_HEADER, &delivery.event_type)
+                .header(DELIVERY_HEADER, &delivery.id)
+                .body(delivery.payload.clone())
+                .send()
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        let (error, response_status) = match result {
+            Ok(response) if response.status().is_success() => {
+                metrics::WEBHOOK_DELIVERED_TOTAL.inc();
+                if let Err(e) = db.record_webhook_delivery_success(&delivery.id, response.status().as_u16()).await {
+                    warn!("Failed to record webhook delivery {}: {}", delivery.id, e);
+                }
+                return;
+            }
+            Ok(response) => (format!("HTTP {}", response.status()), Some(response.status().as_u16())),
+            Err(e) => (e, None),
+        };
+
+        let attempts = delivery.attempts as u32 + 1;
+        let retry_at = (attempts < self.config.max_attempts).then(|| {
+            Utc::now() + chrono::Duration::from_std(self.retry_delay(attempts)).unwrap_or_else(|_| chrono::Duration::hours(1))
+        });
+        if retry_at.is_some() {
+            metrics::WEBHOOK_RETRIES_TOTAL.inc();
+        } else {
+            metrics::WEBHOOK_FAILED_TOTAL.inc();
+        }
+        warn!(
+            "Webhook delivery {} to {} failed (attempt {} of {}): {}",
+            delivery.id, webhook.id, attempts, self.config.max_attempts, error
+        );
+
+        if let Err(e) = db.record_webhook_delivery_failure(&delivery.id, &error, response_status, retry_at).await {
+            warn!("Failed to record webhook delivery {}: {}", delivery.id, e);
+        }
+    }
+
+    /// Retry due deliveries periodically in the background
+    pub fn spawn_worker(self: Arc<Self>, db: Arc<Database>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.config.poll_interval);
+            loop {
+                interval.tick().await;
+                crate::readiness::BACKGROUND_JOBS.heartbeat("webhook_delivery", self.config.poll_interval);
+                self.deliver_due(&db).await;
+            }
+        })
+    }
+}
+
+/// Notify webhooks of a verified message if webhooks are enabled
+///
+/// Direct messages are left to their recipient's inbox.
+pub async fn notify_if_enabled(
+    dispatcher: Option<&Arc<WebhookDispatcher>>,
+    db: &Arc<Database>,
+    message: &StoredMessage,
+) -> Result<(), AppError> {
+    match dispatcher {
+        Some(dispatcher) if !message.is_direct() => dispatcher.notify(db, message).await,
+        _ => Ok(()),
+    }
+}
+
+/// Create router for webhook administration endpoints
+pub fn webhook_routes() -> Router<Arc<Database>> {
+    Router::new()
+        .route("/", get(list_webhooks_handler).post(register_webhook_handler))
+        .route("/:webhook_id", delete(delete_webhook_handler))
+        .route("/:webhook_id/deliveries", get(list_deliveries_handler))
+}
+
+/// Create router for authenticated webhook administration endpoints
+pub fn authenticated_webhook_routes() -> Router<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)> {
+    Router::new()
+        .route("/", get(authenticated_list_webhooks_handler).post(authenticated_register_webhook_handler))
+        .route("/:webhook_id", delete(authenticated_delete_webhook_handler))
+        .route("/:webhook_id/deliveries", get(authenticated_list_deliveries_handler))
+}
+
+/// Validate and store a new webhook with a freshly generated secret
+async fn register_webhook(
+    db: &Database,
+    dispatcher: Option<Extension<Arc<WebhookDispatcher>>>,
+    request: &RegisterWebhookRequest,
+    created_by: Option<&str>,
+) -> Result<StoredWebhook, AppError> {
+    let Extension(dispatcher) = dispatcher.ok_or(WebhookError::Disabled)?;
+    dispatcher.validate_url(&request.url)?;
+    if matches!(&request.group_id, Some(group_id) if group_id.is_empty()) {
+        return Err(AppError::InvalidQuery("group_id cannot be empty".to_string()));
+    }
+
+    Ok(db
+        .create_webhook(&request.url, request.group_id.as_deref(), &generate_secret(), created_by)
+        .await?)
+}
+
+/// Map a missing webhook to a webhook error
+fn webhook_not_found(error: DatabaseError) -> AppError {
+    match error {
+        DatabaseError::WebhookNotFound(id) => WebhookError::NotFound(id).into(),
+        other => AppError::DatabaseError(other),
+    }
+}
+
+/// Load the most recent deliveries for a webhook
+async fn list_deliveries(db: &Database, webhook_id: &str, params: &DeliveryListQuery) -> Result<Vec<WebhookDelivery>, AppError> {
+    db.get_webhook(webhook_id).await.map_err(webhook_not_found)?;
+    let limit = params.limit.unwrap_or(MAX_DELIVERY_LIST).clamp(1, MAX_DELIVERY_LIST);
+    Ok(db.get_webhook_deliveries(webhook_id, limit).await?)
+}
+
+/// Handler to register a webhook
+///
+/// The response is the only place the signing secret is ever returned.
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    operation_id = "registerWebhook",
+    tag = "webhooks",
+    request_body = RegisterWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook registered, with its signing secret", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn register_webhook_handler(
+    State(db): State<Arc<Database>>,
+    dispatcher: Option<Extension<Arc<WebhookDispatcher>>>,
+    Json(payload): Json<RegisterWebhookRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Registering webhook");
+
+    let webhook = register_webhook(&db, dispatcher, &payload, None).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "webhook": webhook,
+        "secret": webhook.secret
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Handler to list registered webhooks
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    operation_id = "listWebhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Registered webhooks", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn list_webhooks_handler(
+    State(db): State<Arc<Database>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Listing webhooks");
+
+    let webhooks = db.list_webhooks().await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "count": webhooks.len(),
+        "webhooks": webhooks
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler to delete a webhook
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{webhook_id}",
+    operation_id = "deleteWebhook",
+    tag = "webhooks",
+    params(("webhook_id" = String, Path, description = "Webhook ID")),
+    responses(
+        (status = 200, description = "Webhook deleted", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn delete_webhook_handler(
+    State(db): State<Arc<Database>>,
+    Path(webhook_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Deleting webhook: {}", webhook_id);
+
+    db.delete_webhook(&webhook_id).await.map_err(webhook_not_found)?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": "Webhook deleted successfully",
+        "webhook_id": webhook_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Handler to list a webhook's recent deliveries
+#[utoipa::path(
+    get,
+    path = "/webhooks/{webhook_id}/deliveries",
+    operation_id = "listWebhookDeliveries",
+    tag = "webhooks",
+    params(("webhook_id" = String, Path, description = "Webhook ID"), DeliveryListQuery),
+    responses(
+        (status = 200, description = "The webhook's recent deliveries", body = Object),
+    )
+)]
+#[instrument(skip_all)]
+async fn list_deliveries_handler(
+    State(db): State<Arc<Database>>,
+    Path(webhook_id): Path<String>,
+    Query(params): Query<DeliveryListQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Listing deliveries for webhook: {}", webhook_id);
+
+    let deliveries = list_deliveries(&db, &webhook_id, &params).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "webhook_id": webhook_id,
+        "count": deliveries.len(),
+        "deliveries": deliveries
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to register a webhook
+#[instrument(skip_all)]
+async fn authenticated_register_webhook_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    dispatcher: Option<Extension<Arc<WebhookDispatcher>>>,
+    Json(payload): Json<RegisterWebhookRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} registering webhook", auth.user_id);
+
+    let webhook = register_webhook(&db, dispatcher, &payload, Some(&auth.user_id)).await?;
+
+    // Log the registration
+    let mut metadata = HashMap::new();
+    metadata.insert("webhook_id".to_string(), webhook.id.clone());
+    metadata.insert("url".to_string(), webhook.url.clone());
+    metadata.insert("group_id".to_string(), webhook.group_id.clone().unwrap_or_else(|| "*".to_string()));
+
+    if let Err(e) = secure_logger.audit_log(
+        "Webhook registered".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log webhook registration: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "webhook": webhook,
+        "secret": webhook.secret,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::CREATED, response))
+}
+
+/// Authenticated handler to list registered webhooks
+#[instrument(skip_all)]
+async fn authenticated_list_webhooks_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} listing webhooks", auth.user_id);
+
+    let webhooks = db.list_webhooks().await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "count": webhooks.len(),
+        "webhooks": webhooks,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to delete a webhook
+#[instrument(skip_all)]
+async fn authenticated_delete_webhook_handler(
+    State((db, _, secure_logger)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    request_id: RequestId,
+    Path(webhook_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} deleting webhook: {}", auth.user_id, webhook_id);
+
+    db.delete_webhook(&webhook_id).await.map_err(webhook_not_found)?;
+
+    // Log the deletion
+    let mut metadata = HashMap::new();
+    metadata.insert("webhook_id".to_string(), webhook_id.clone());
+
+    if let Err(e) = secure_logger.audit_log(
+        "Webhook deleted".to_string(),
+        auth.user_id.clone(),
+        Some(request_id.to_string()),
+        metadata,
+    ) {
+        warn!("Failed to log webhook deletion: {}", e);
+    }
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "message": "Webhook deleted successfully",
+        "webhook_id": webhook_id,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Authenticated handler to list a webhook's recent deliveries
+#[instrument(skip_all)]
+async fn authenticated_list_deliveries_handler(
+    State((db, _, _)): State<(Arc<Database>, Arc<crate::jwt_validator::JwtValidator>, Arc<crate::secure_logger::SecureLogger>)>,
+    auth: AuthContext,
+    Path(webhook_id): Path<String>,
+    Query(params): Query<DeliveryListQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Authenticated user {} listing deliveries for webhook: {}", auth.user_id, webhook_id);
+
+    let deliveries = list_deliveries(&db, &webhook_id, &params).await?;
+
+    let response = Json(serde_json::json!({
+        "status": "success",
+        "webhook_id": webhook_id,
+        "count": deliveries.len(),
+        "deliveries": deliveries,
+        "authenticated_user": auth.user_id
+    }));
+
+    Ok((StatusCode::OK, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+    use wiremock::matchers::{header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn setup_db() -> Arc<Database> {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        db
+    }
+
+    fn dispatcher() -> Arc<WebhookDispatcher> {
+        Arc::new(WebhookDispatcher::new(WebhookConfig {
+            retry_base: std::time::Duration::ZERO,
+            allow_insecure_urls: true,
+            ..Default::default()
+        }).unwrap())
+    }
+
+    async fn stored_message(db: &Database, group_id: &str) -> StoredMessage {
+        let mut message = StoredMessage::from(crate::Message {
+            sender: "aa".repeat(32),
+            context: "bb".to_string(),
+            body: "hello".to_string(),
+            proof: "cc".repeat(64),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        });
+        message.group_id = group_id.to_string();
+        let id = db.store_message(message).await.unwrap();
+        db.get_message_by_id(&id).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_verified_message_is_delivered_with_valid_signature() {
+        // ARRANGE: A receiver subscribed to group1
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(header_exists(SIGNATURE_HEADER))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let db = setup_db().await;
+        let dispatcher = dispatcher();
+        let webhook = db
+            .create_webhook(&format!("{}/hook", server.uri()), Some("group1"), "secret", None)
+            .await
+            .unwrap();
+        let message = stored_message(&db, "group1").await;
+
+        // ACT: Queue the event and deliver it
+        let deliveries = db.enqueue_webhook_deliveries(&message.id, "group1", "{\"id\":\"evt\"}", Utc::now()).await.unwrap();
+        dispatcher.deliver_all(&db, deliveries).await;
+
+        // ASSERT: The receiver can verify the signature and the delivery is recorded
+        let request = &server.received_requests().await.unwrap()[0];
+        let header = request
+            .headers
+            .iter()
+            .find(|(name, _)| name.as_str() == SIGNATURE_HEADER)
+            .map(|(_, values)| values.iter().map(|value| value.as_str()).collect::<Vec<_>>().join(","))
+            .unwrap();
+        assert!(verify_signature("secret", &header, &request.body, Utc::now(), chrono::Duration::minutes(5)));
+        assert!(!verify_signature("other", &header, &request.body, Utc::now(), chrono::Duration::minutes(5)));
+        let history = db.get_webhook_deliveries(&webhook.id, 10).await.unwrap();
+        assert_eq!(history[0].status, "delivered");
+        assert_eq!(history[0].response_status, Some(204));
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_retried_until_exhausted() {
+        // ARRANGE: A receiver that always fails and a two-attempt limit
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let db = setup_db().await;
+        let dispatcher = Arc::new(WebhookDispatcher::new(WebhookConfig {
+            max_attempts: 2,
+            retry_base: std::time::Duration::ZERO,
+            allow_insecure_urls: true,
+            ..Default::default()
+        }).unwrap());
+        let webhook = db.create_webhook(&server.uri(), None, "secret", None).await.unwrap();
+        let message = stored_message(&db, "group1").await;
+        db.enqueue_webhook_deliveries(&message.id, "group1", "{}", Utc::now()).await.unwrap();
+
+        // ACT: Run the worker three times
+        let first = dispatcher.deliver_due(&db).await;
+        let second = dispatcher.deliver_due(&db).await;
+        let third = dispatcher.deliver_due(&db).await;
+
+        // ASSERT: Two attempts were made before the delivery was marked failed
+        assert_eq!((first, second, third), (1, 1, 0));
+        let history = db.get_webhook_deliveries(&webhook.id, 10).await.unwrap();
+        assert_eq!(history[0].status, "failed");
+        assert_eq!(history[0].attempts, 2);
+        assert_eq!(history[0].last_error.as_deref(), Some("HTTP 500 Internal Server Error"));
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        let dispatcher = WebhookDispatcher::new(WebhookConfig {
+            retry_base: std::time::Duration::from_secs(30),
+            retry_max: std::time::Duration::from_secs(100),
+            ..Default::default()
+        }).unwrap();
+
+        assert_eq!(dispatcher.retry_delay(1).as_secs(), 30);
+        assert_eq!(dispatcher.retry_delay(2).as_secs(), 60);
+        assert_eq!(dispatcher.retry_delay(3).as_secs(), 100);
+    }
+
+    #[test]
+    fn test_signature_outside_tolerance_is_rejected() {
+        let issued = Utc::now() - chrono::Duration::minutes(10);
+        let header = sign_payload("secret", issued.timestamp(), b"{}");
+
+        assert!(verify_signature("secret", &header, b"{}", issued, chrono::Duration::minutes(5)));
+        assert!(!verify_signature("secret", &header, b"{}", Utc::now(), chrono::Duration::minutes(5)));
+    }
+
+    #[tokio::test]
+    async fn test_register_requires_https_and_hides_secret_in_listing() {
+        let db = setup_db().await;
+        let app = Router::new()
+            .nest("/webhooks", webhook_routes())
+            .layer(Extension(Arc::new(WebhookDispatcher::new(WebhookConfig::default()).unwrap())))
+            .with_state(db);
+        let register = |url: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/webhooks")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::json!({ "url": url }).to_string()))
+                .unwrap()
+        };
+
+        let insecure = app.clone().oneshot(register("http://example.com/hook")).await.unwrap();
+        let created = app.clone().oneshot(register("https://example.com/hook")).await.unwrap();
+        let listed = app
+            .oneshot(Request::builder().uri("/webhooks").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(insecure.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(created.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(created.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["secret"].as_str().unwrap().len(), SECRET_LENGTH * 2);
+        let body = axum::body::to_bytes(listed.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["count"], 1);
+        assert!(json["webhooks"][0].get("secret").is_none());
+    }
+}
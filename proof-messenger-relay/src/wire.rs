@@ -0,0 +1,149 @@
+//! Message Wire Format Module
+//!
+//! `POST /relay` accepts a message either as JSON, with binary fields hex
+//! encoded, or as CBOR (`Content-Type: application/cbor`) using the compact
+//! [`WireMessage`] encoding from the protocol crate. Both are decoded into
+//! the same [`Message`], so the rest of the pipeline is format agnostic.
+
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use proof_messenger_protocol::wire::{WireMessage, CBOR_CONTENT_TYPE};
+
+use crate::{Message, PqcProof};
+
+/// A submitted message, decoded from JSON or CBOR by its content type
+pub struct MessagePayload(pub Message);
+
+impl From<WireMessage> for Message {
+    fn from(message: WireMessage) -> Self {
+        Self {
+            sender: hex::encode(message.sender),
+            context: hex::encode(message.context),
+            body: message.body,
+            proof: hex::encode(message.proof),
+            pqc: message.pqc.map(|pqc| PqcProof {
+                public_key: hex::encode(pqc.public_key),
+                proof: hex::encode(pqc.proof),
+            }),
+            thread_id: message.thread_id,
+            reply_to: message.reply_to,
+            expires_at: message.expires_at,
+            recipient: message.recipient.map(hex::encode),
+        }
+    }
+}
+
+/// Whether the request body is declared as CBOR
+fn is_cbor(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case(CBOR_CONTENT_TYPE))
+}
+
+#[axum::async_trait]
+impl<S> FromRequest<S> for MessagePayload
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_cbor(request.headers()) {
+            let Json(message) = Json::<Message>::from_request(request, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return Ok(Self(message));
+        }
+
+        let bytes = Bytes::from_request(request, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let message = WireMessage::from_cbor(&bytes)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()).into_response())?;
+        Ok(Self(message.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use axum::body::Body;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_protocol::proof::make_secure_proof;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    async fn setup() -> (Arc<Database>, axum::Router) {
+        let db = Arc::new(Database::new("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        (db.clone(), crate::create_app(db))
+    }
+
+    fn post(content_type: &str, body: Vec<u8>) -> Request {
+        Request::builder()
+            .method("POST")
+            .uri("/relay")
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cbor_message_is_verified_and_stored() {
+        // ARRANGE: A signed message in the compact CBOR encoding
+        let (db, app) = setup().await;
+        let keypair = generate_secure_keypair_with_seed(21);
+        let context = [9u8; 512];
+        let proof = make_secure_proof(&keypair, &context).unwrap();
+        let message = WireMessage::new(&keypair.public_key(), &context, "binary hello", &proof);
+
+        // ACT: Submit it as application/cbor
+        let response = app.oneshot(post(CBOR_CONTENT_TYPE, message.to_cbor().unwrap())).await.unwrap();
+
+        // ASSERT: It is stored exactly as if it had been sent as hex JSON
+        assert_eq!(response.status(), StatusCode::OK);
+        let stored = db.get_messages_by_group("default", None).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].sender, hex::encode(keypair.public_key().to_bytes()));
+        assert_eq!(stored[0].context, hex::encode(context));
+        assert_eq!(stored[0].proof, hex::encode(proof.to_bytes()));
+        assert!(stored[0].verified);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_cbor_is_rejected() {
+        let (_, app) = setup().await;
+
+        let response = app
+            .oneshot(post("application/cbor; charset=binary", b"\xa1\x66sender".to_vec()))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "BAD_REQUEST");
+    }
+
+    #[test]
+    fn test_content_type_detection() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, value.parse().unwrap());
+            headers
+        };
+
+        assert!(is_cbor(&headers("application/cbor")));
+        assert!(is_cbor(&headers("Application/CBOR; foo=bar")));
+        assert!(!is_cbor(&headers("application/json")));
+        assert!(!is_cbor(&HeaderMap::new()));
+    }
+}
@@ -0,0 +1,173 @@
+//! Write-Behind Message Storage Module
+//!
+//! SQLite commits are the relay's throughput ceiling: each verified message
+//! is its own transaction, and every commit waits on the disk. With
+//! `[database.write_behind]` enabled in the relay settings (see
+//! [`crate::config::WriteBehindConfig`]), [`crate::database::Database`] hands
+//! messages to a background writer instead. The writer stores up to
+//! `batch_size` messages, or whatever arrived within `flush_interval_ms` of
+//! the first, in a single transaction.
+//!
+//! Submitters still wait until their message is committed, so a successful
+//! response means the message is stored exactly as before. Each message is
+//! inserted under its own savepoint, so one failing message does not fail
+//! the rest of its batch. The queue holds at most `queue_capacity` messages;
+//! when it is full, submitters wait for room, which slows clients down rather
+//! than letting memory grow.
+
+use sqlx::{Pool, Sqlite};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::warn;
+
+use crate::config::WriteBehindConfig;
+use crate::database::{store_message_batch, DatabaseError, StoredMessage};
+
+/// A message waiting to be written and the submitter awaiting the outcome
+type PendingWrite = (StoredMessage, oneshot::Sender<Result<(), DatabaseError>>);
+
+/// Queue feeding the background message writer
+#[derive(Debug)]
+pub struct WriteBehind {
+    queue: mpsc::Sender<PendingWrite>,
+}
+
+impl WriteBehind {
+    /// Start a writer storing batches of messages in `pool`
+    ///
+    /// The writer stops once the queue is dropped and drained.
+    pub fn spawn(pool: Pool<Sqlite>, config: &WriteBehindConfig) -> Self {
+        let (queue, pending) = mpsc::channel(config.queue_capacity);
+        tokio::spawn(write_batches(
+            pool,
+            pending,
+            config.batch_size,
+            Duration::from_millis(config.flush_interval_ms),
+        ));
+        Self { queue }
+    }
+
+    /// Queue a message and wait until its batch is committed
+    pub async fn store(&self, message: StoredMessage) -> Result<(), DatabaseError> {
+        let (done, outcome) = oneshot::channel();
+        self.queue
+            .send((message, done))
+            .await
+            .map_err(|_| DatabaseError::BatchWriteFailed("message writer stopped".to_string()))?;
+        outcome
+            .await
+            .map_err(|_| DatabaseError::BatchWriteFailed("message writer stopped".to_string()))?
+    }
+}
+
+/// Collect queued messages into batches and store each in one transaction
+async fn write_batches(
+    pool: Pool<Sqlite>,
+    mut pending: mpsc::Receiver<PendingWrite>,
+    batch_size: usize,
+    flush_interval: Duration,
+) {
+    while let Some(first) = pending.recv().await {
+        let deadline = tokio::time::Instant::now() + flush_interval;
+        let mut batch = vec![first];
+        while batch.len() < batch_size {
+            match tokio::time::timeout_at(deadline, pending.recv()).await {
+                Ok(Some(write)) => batch.push(write),
+                _ => break,
+            }
+        }
+
+        let (messages, waiting): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+        match store_message_batch(&pool, &messages).await {
+            Ok(outcomes) => {
+                for (done, outcome) in waiting.into_iter().zip(outcomes) {
+                    let _ = done.send(outcome);
+                }
+            }
+            Err(e) => {
+                warn!("Failed to store a batch of {} messages: {}", messages.len(), e);
+                for done in waiting {
+                    let _ = done.send(Err(DatabaseError::BatchWriteFailed(e.to_string())));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::database::Database;
+    use std::sync::Arc;
+
+    fn message(body: &str) -> StoredMessage {
+        StoredMessage::from(crate::Message {
+            sender: "aa".repeat(32),
+            context: "bb".to_string(),
+            body: body.to_string(),
+            proof: "cc".repeat(64),
+            pqc: None,
+            thread_id: None,
+            reply_to: None,
+            expires_at: None,
+            recipient: None,
+        })
+    }
+
+    async fn setup_db(batch_size: usize) -> Arc<Database> {
+        let config = DatabaseConfig {
+            url: "sqlite::memory:".to_string(),
+            write_behind: WriteBehindConfig {
+                enabled: true,
+                batch_size,
+                ..WriteBehindConfig::default()
+            },
+            ..DatabaseConfig::default()
+        };
+        let db = Database::connect(&config).await.unwrap();
+        db.migrate().await.unwrap();
+        Arc::new(db)
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_messages_are_stored_in_batches() {
+        // ARRANGE: A database with write-behind batches of up to 10 messages
+        let db = setup_db(10).await;
+
+        // ACT: Store 25 messages concurrently
+        let stores = (0..25).map(|i| {
+            let db = db.clone();
+            tokio::spawn(async move { db.store_message(message(&format!("message {}", i))).await })
+        });
+        let ids: Vec<String> = futures::future::join_all(stores)
+            .await
+            .into_iter()
+            .map(|stored| stored.unwrap().unwrap())
+            .collect();
+
+        // ASSERT: Every message is stored and appended to the transparency log
+        assert_eq!(db.get_message_count("default").await.unwrap(), 25);
+        assert_eq!(db.get_transparency_log_size().await.unwrap(), 25);
+        for id in &ids {
+            assert!(db.get_message_by_id(id).await.unwrap().verified);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_message_does_not_fail_its_batch() {
+        // ARRANGE: A stored message and a batch holding a duplicate of it
+        let db = setup_db(10).await;
+        let existing = message("first");
+        db.store_message(existing.clone()).await.unwrap();
+
+        // ACT: Store the duplicate alongside a new message
+        let (duplicate, fresh) = tokio::join!(db.store_message(existing), db.store_message(message("second")));
+
+        // ASSERT: Only the duplicate is rejected
+        assert!(duplicate.is_err());
+        assert!(fresh.is_ok());
+        assert_eq!(db.get_message_count("default").await.unwrap(), 2);
+        assert_eq!(db.get_transparency_log_size().await.unwrap(), 2);
+    }
+}
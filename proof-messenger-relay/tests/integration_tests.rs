@@ -116,6 +116,11 @@ fn create_test_message(keypair_seed: u64, context: &[u8], body: &str) -> Message
         context: hex::encode(context),
         body: body.to_string(),
         proof: hex::encode(signature.to_bytes()),
+        pqc: None,
+        thread_id: None,
+        reply_to: None,
+        expires_at: None,
+        recipient: None,
     }
 }
 
@@ -345,4 +350,46 @@ async fn test_message_ordering() {
     assert_eq!(stored_messages[0]["body"], "Third message");
     assert_eq!(stored_messages[1]["body"], "Second message");
     assert_eq!(stored_messages[2]["body"], "First message");
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_list_messages_by_sender() {
+    // ARRANGE: Relay messages from two senders through the real router
+    let db = Database::new("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let app = proof_messenger_relay::create_app(Arc::new(db));
+    let alice = create_test_message(1, b"alice context", "from alice");
+    let bob = create_test_message(2, b"bob context", "from bob");
+
+    for message in [&alice, &bob, &alice] {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/relay")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(message).unwrap()))
+            .unwrap();
+        assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::OK);
+    }
+
+    // ACT: List alice's messages
+    let request = Request::builder()
+        .method("GET")
+        .uri(format!("/senders/{}/messages", alice.sender))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+
+    // ASSERT: Only alice's messages are returned
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["message_count"], 2);
+    assert!(json["messages"].as_array().unwrap().iter().all(|m| m["sender"] == alice.sender));
+
+    // A malformed key is rejected
+    let request = Request::builder()
+        .method("GET")
+        .uri("/senders/not-a-key/messages")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::BAD_REQUEST);
+}
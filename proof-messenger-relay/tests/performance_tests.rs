@@ -81,6 +81,11 @@ fn create_test_message(keypair_seed: u64, context: &[u8], body: &str) -> Message
         context: hex::encode(context),
         body: body.to_string(),
         proof: hex::encode(signature.to_bytes()),
+        pqc: None,
+        thread_id: None,
+        reply_to: None,
+        expires_at: None,
+        recipient: None,
     }
 }
 
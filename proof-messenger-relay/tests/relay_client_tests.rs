@@ -0,0 +1,57 @@
+//! End-to-end tests of the protocol crate's relay client against a running relay
+
+use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+use proof_messenger_protocol::relay_client::{ErrorCode, OutgoingMessage};
+use proof_messenger_relay::test_util::TestRelay;
+
+#[tokio::test]
+async fn test_send_and_read_back_a_message() {
+    // ARRANGE: A running relay and a signed message
+    let relay = TestRelay::start().await;
+    let client = relay.client();
+    let keypair = generate_secure_keypair_with_seed(21);
+    let message = OutgoingMessage::signed(&keypair, b"sdk-context", "hello from the sdk").unwrap();
+
+    // ACT: Send it and read the group back
+    let message_id = client.send_message(&message).await.unwrap();
+    let messages = client.get_messages("default", Some(10)).await.unwrap();
+
+    // ASSERT: The relay stored exactly what was sent
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].id, message_id);
+    assert_eq!(messages[0].body, "hello from the sdk");
+    assert_eq!(messages[0].sender, message.sender);
+    assert!(messages[0].verified);
+}
+
+#[tokio::test]
+async fn test_revoked_proof_is_reported_by_code() {
+    // Revocation checks are opt-in; no other test in this binary depends on them
+    std::env::set_var("REVOCATION_CHECK_ENABLED", "true");
+    let relay = TestRelay::start().await;
+    let client = relay.client();
+    let keypair = generate_secure_keypair_with_seed(22);
+    let message = OutgoingMessage::signed(&keypair, b"revoke-me", "soon revoked").unwrap();
+
+    client.revoke_proof(&message.proof, Some("compromised"), None).await.unwrap();
+    let error = client.send_message(&message).await.unwrap_err();
+    let again = client.revoke_proof(&message.proof, None, None).await.unwrap_err();
+
+    assert_eq!(error.code(), Some(ErrorCode::ProofRevoked));
+    assert_eq!(again.code(), Some(ErrorCode::ProofAlreadyRevoked));
+}
+
+#[tokio::test]
+async fn test_health_and_tampered_message() {
+    let relay = TestRelay::start().await;
+    let client = relay.client();
+    let keypair = generate_secure_keypair_with_seed(23);
+    let mut message = OutgoingMessage::signed(&keypair, b"original", "hi").unwrap();
+    message.context = hex::encode(b"tampered");
+
+    let health = client.check_health().await.unwrap();
+    let error = client.send_message(&message).await.unwrap_err();
+
+    assert!(health.is_healthy());
+    assert_eq!(error.code(), Some(ErrorCode::VerificationFailed));
+}
@@ -0,0 +1,57 @@
+// Main-thread side of `signing-worker.js`.
+//
+//   const signer = createSigner();
+//   const signatures = await signer.signAll(keypairBytes, [fileBuffer, otherBuffer]);
+//   signatures[0]; // Uint8Array(64)
+
+/**
+ * Start a signing worker
+ * @param {URL|string} [url] - Location of signing-worker.js
+ * @returns {{ signAll: Function, terminate: Function }}
+ */
+export function createSigner(url = new URL('./signing-worker.js', import.meta.url)) {
+    const worker = new Worker(url, { type: 'module' });
+    const pending = new Map();
+    let nextId = 0;
+
+    worker.onmessage = ({ data: { id, signatures, error } }) => {
+        const { resolve, reject } = pending.get(id);
+        pending.delete(id);
+        if (error) {
+            reject(Object.assign(new Error(error.message), { code: error.code }));
+        } else {
+            resolve(split(signatures));
+        }
+    };
+
+    return {
+        /**
+         * Sign every context in one round trip. The context buffers are
+         * transferred to the worker and are detached afterwards.
+         * @param {Uint8Array} keypair - 64-byte keypair
+         * @param {ArrayBuffer[]} contexts
+         * @returns {Promise<Uint8Array[]>} One 64-byte signature per context
+         */
+        signAll(keypair, contexts) {
+            const id = nextId++;
+            return new Promise((resolve, reject) => {
+                pending.set(id, { resolve, reject });
+                // Copy the keypair so the caller's key bytes are never detached
+                const keypairBuffer = keypair.slice().buffer;
+                worker.postMessage({ id, keypair: keypairBuffer, contexts }, [keypairBuffer, ...contexts]);
+            });
+        },
+
+        terminate() {
+            worker.terminate();
+        },
+    };
+}
+
+function split(signatures) {
+    const result = [];
+    for (let offset = 0; offset < signatures.byteLength; offset += 64) {
+        result.push(new Uint8Array(signatures, offset, 64));
+    }
+    return result;
+}
@@ -0,0 +1,22 @@
+// Module worker that signs contexts off the main thread.
+//
+// Start it with `new Worker(new URL("./signing-worker.js", import.meta.url), { type: "module" })`
+// and talk to it through `signing-worker-client.js`.
+//
+// Request:  { id, keypair: ArrayBuffer, contexts: ArrayBuffer[] }
+// Response: { id, signatures: ArrayBuffer }  (64 bytes per context, in order)
+//       or  { id, error: { code, message } }
+import init, { sign_all_wasm } from '../pkg/proof_messenger_web.js';
+
+const ready = init();
+
+self.onmessage = async ({ data: { id, keypair, contexts } }) => {
+    await ready;
+    try {
+        const signatures = sign_all_wasm(keypair, contexts);
+        // Transfer the result back instead of copying it
+        self.postMessage({ id, signatures }, [signatures]);
+    } catch (error) {
+        self.postMessage({ id, error: { code: error.code ?? 'INTERNAL_ERROR', message: error.message } });
+    }
+};
@@ -0,0 +1,63 @@
+import type {
+    ProofMessengerClient,
+    ProofMessengerError,
+    RelayMessage,
+    RelayReceipt,
+    InitInput,
+} from 'proof-messenger-web';
+
+export type Status = 'loading' | 'ready' | 'error';
+
+/** Initialize the WASM module; later calls share the first call's promise */
+export function initWasm(moduleOrPath?: InitInput | Promise<InitInput>): Promise<void>;
+
+export function useWasm(): {
+    status: Status;
+    error: Error | null;
+};
+
+export interface KeyPairState {
+    status: Status;
+    error: ProofMessengerError | Error | null;
+    /** Hex keypair; keep it secret */
+    keypair: string | null;
+    /** Hex public key */
+    publicKey: string | null;
+    /** Replace the keypair with a new one */
+    regenerate(): void;
+    /** Forget the keypair, including its stored copy */
+    clear(): void;
+}
+
+export function useKeyPair(options?: { storageKey?: string }): KeyPairState;
+
+export interface RelayConnectionState {
+    status: Status;
+    error: ProofMessengerError | Error | null;
+    client: ProofMessengerClient | null;
+    /** The relay URL without a trailing slash */
+    relayUrl: string;
+    /** Sign `context` (bytes, or a string to UTF-8 encode) and relay it with `body` */
+    send(context: Uint8Array | string, body: string): Promise<RelayReceipt>;
+    /** Whether a `send` is in flight */
+    sending: boolean;
+}
+
+export function useRelayConnection(relayUrl: string, keypair: string | null): RelayConnectionState;
+
+export interface MessagesState {
+    status: Status;
+    error: ProofMessengerError | Error | null;
+    /** Oldest first */
+    messages: RelayMessage[];
+    /** Whether new messages are streamed rather than polled */
+    live: boolean;
+    /** Refetch the latest messages */
+    refresh(): Promise<void>;
+}
+
+export function useMessages(
+    connection: RelayConnectionState,
+    groupId: string,
+    options?: { limit?: number; pollIntervalMs?: number },
+): MessagesState;
@@ -0,0 +1,290 @@
+// React hooks over the proof-messenger-web WASM bindings.
+//
+// Every hook initializes the WASM module itself, once per page, so components
+// only deal with `status` ('loading' | 'ready' | 'error') and the data.
+
+import { useCallback, useEffect, useRef, useState } from 'react';
+import init, { ProofMessengerClient } from 'proof-messenger-web';
+
+let initialized = null;
+
+/**
+ * Initialize the WASM module; later calls share the first call's promise
+ * @param {*} [moduleOrPath] - Passed to the wasm-bindgen `init` on the first call
+ * @returns {Promise<void>}
+ */
+export function initWasm(moduleOrPath) {
+    if (!initialized) {
+        initialized = init(moduleOrPath).then(() => undefined);
+        // Let a failed load be retried by the next caller
+        initialized.catch(() => {
+            initialized = null;
+        });
+    }
+    return initialized;
+}
+
+/**
+ * Track WASM initialization
+ * @returns {{ status: string, error: Error|null }}
+ */
+export function useWasm() {
+    const [state, setState] = useState({ status: 'loading', error: null });
+
+    useEffect(() => {
+        let active = true;
+        initWasm().then(
+            () => active && setState({ status: 'ready', error: null }),
+            (error) => active && setState({ status: 'error', error }),
+        );
+        return () => {
+            active = false;
+        };
+    }, []);
+
+    return state;
+}
+
+/**
+ * This browser's identity
+ *
+ * With a `storageKey`, the keypair is kept in `localStorage` under that key
+ * and restored on the next visit; without one it lasts until the page closes.
+ * @param {{ storageKey?: string }} [options]
+ */
+export function useKeyPair({ storageKey } = {}) {
+    const wasm = useWasm();
+    const [identity, setIdentity] = useState(null);
+    const [error, setError] = useState(null);
+
+    const load = useCallback(
+        (fresh) => {
+            try {
+                const saved = !fresh && storageKey ? localStorage.getItem(storageKey) : null;
+                const loaded = openIdentity(saved);
+                if (storageKey) {
+                    localStorage.setItem(storageKey, loaded.keypair);
+                }
+                setIdentity(loaded);
+                setError(null);
+            } catch (e) {
+                setError(e);
+            }
+        },
+        [storageKey],
+    );
+
+    useEffect(() => {
+        if (wasm.status === 'ready') {
+            load(false);
+        }
+    }, [wasm.status, load]);
+
+    const regenerate = useCallback(() => load(true), [load]);
+
+    const clear = useCallback(() => {
+        if (storageKey) {
+            localStorage.removeItem(storageKey);
+        }
+        setIdentity(null);
+    }, [storageKey]);
+
+    return {
+        status: combinedStatus(wasm, error, identity !== null),
+        error: wasm.error ?? error,
+        /** Hex keypair, as `ProofMessengerClient` and `useRelayConnection` take it; keep it secret */
+        keypair: identity?.keypair ?? null,
+        publicKey: identity?.publicKey ?? null,
+        regenerate,
+        clear,
+    };
+}
+
+/** Parse (or, given nothing, generate) a hex keypair, returning it with its public key */
+function openIdentity(keypairHex) {
+    const client = new ProofMessengerClient('', keypairHex ?? undefined);
+    try {
+        return { keypair: client.exportKeypair(), publicKey: client.publicKey };
+    } finally {
+        client.free();
+    }
+}
+
+/**
+ * A client for `relayUrl` signing as `keypair`, plus a `send` that tracks its progress
+ * @param {string} relayUrl
+ * @param {string|null} keypair - Hex keypair, e.g. `useKeyPair().keypair`
+ */
+export function useRelayConnection(relayUrl, keypair) {
+    const wasm = useWasm();
+    const [sending, setSending] = useState(false);
+    const [error, setError] = useState(null);
+
+    const [client, setClient] = useState(null);
+
+    useEffect(() => {
+        if (wasm.status !== 'ready' || !keypair) {
+            setClient(null);
+            return undefined;
+        }
+        let created;
+        try {
+            created = new ProofMessengerClient(relayUrl, keypair);
+            setClient(created);
+            setError(null);
+        } catch (e) {
+            setClient(null);
+            setError(e);
+        }
+        return () => created?.free();
+    }, [wasm.status, relayUrl, keypair]);
+
+    const send = useCallback(
+        async (context, body) => {
+            if (!client) {
+                throw new Error('Relay connection is not ready');
+            }
+            setSending(true);
+            setError(null);
+            try {
+                return await client.send(toBytes(context), body);
+            } catch (e) {
+                setError(e);
+                throw e;
+            } finally {
+                setSending(false);
+            }
+        },
+        [client],
+    );
+
+    return {
+        status: combinedStatus(wasm, client ? null : error, client !== null),
+        error: wasm.error ?? error,
+        client,
+        relayUrl: relayUrl.replace(/\/+$/, ''),
+        send,
+        sending,
+    };
+}
+
+/**
+ * A group's messages, oldest first, kept up to date
+ *
+ * Loads the latest `limit` messages, then follows the relay's
+ * `GET /events/:group_id` stream. If the relay has subscriptions disabled,
+ * it falls back to refetching every `pollIntervalMs`.
+ * @param {ReturnType<typeof useRelayConnection>} connection
+ * @param {string} groupId
+ * @param {{ limit?: number, pollIntervalMs?: number }} [options]
+ */
+export function useMessages(connection, groupId, { limit = 50, pollIntervalMs = 5000 } = {}) {
+    const { client, relayUrl } = connection;
+    const [messages, setMessages] = useState([]);
+    const [status, setStatus] = useState('loading');
+    const [error, setError] = useState(null);
+    const [live, setLive] = useState(false);
+    const seen = useRef(new Set());
+
+    const refresh = useCallback(async () => {
+        if (!client) {
+            return;
+        }
+        try {
+            const latest = await client.messages(groupId, limit);
+            seen.current = new Set(latest.map((message) => message.id));
+            setMessages(latest.slice().reverse());
+            setStatus('ready');
+            setError(null);
+        } catch (e) {
+            setStatus('error');
+            setError(e);
+        }
+    }, [client, groupId, limit]);
+
+    useEffect(() => {
+        if (!client) {
+            return undefined;
+        }
+        let active = true;
+        let timer = null;
+        let events = null;
+
+        const poll = () => {
+            timer = setInterval(() => active && refresh(), pollIntervalMs);
+        };
+
+        setStatus('loading');
+        setMessages([]);
+        refresh().then(() => {
+            if (!active) {
+                return;
+            }
+            if (typeof EventSource === 'undefined') {
+                poll();
+                return;
+            }
+            events = new EventSource(`${relayUrl}/events/${encodeURIComponent(groupId)}`);
+            events.onopen = () => setLive(true);
+            events.addEventListener('message', (event) => {
+                const message = fromRelay(JSON.parse(event.data));
+                if (!seen.current.has(message.id)) {
+                    seen.current.add(message.id);
+                    setMessages((current) => [...current, message]);
+                }
+            });
+            events.onerror = () => {
+                // A closed source means the relay refused the stream, e.g. 404 with subscriptions off
+                if (events.readyState === EventSource.CLOSED) {
+                    setLive(false);
+                    poll();
+                }
+            };
+        });
+
+        return () => {
+            active = false;
+            clearInterval(timer);
+            events?.close();
+            setLive(false);
+        };
+    }, [client, relayUrl, groupId, refresh, pollIntervalMs]);
+
+    return { status: client ? status : connection.status, error: error ?? connection.error, messages, live, refresh };
+}
+
+/** Stream messages arrive in the relay's snake_case; match `client.messages()` */
+function fromRelay(message) {
+    const camel = {};
+    for (const [key, value] of Object.entries(message)) {
+        if (value !== null && value !== undefined) {
+            camel[key.replace(/_([a-z])/g, (_, c) => c.toUpperCase())] = value;
+        }
+    }
+    if (camel.verified === undefined) {
+        try {
+            camel.verified = ProofMessengerClient.verify(camel.sender, fromHex(camel.context), camel.proof);
+        } catch {
+            camel.verified = false;
+        }
+    }
+    return camel;
+}
+
+function toBytes(context) {
+    return typeof context === 'string' ? new TextEncoder().encode(context) : context;
+}
+
+function fromHex(hex) {
+    return Uint8Array.from(hex.match(/../g) ?? [], (byte) => parseInt(byte, 16));
+}
+
+function combinedStatus(wasm, error, ready) {
+    if (wasm.status !== 'ready') {
+        return wasm.status;
+    }
+    if (error) {
+        return 'error';
+    }
+    return ready ? 'ready' : 'loading';
+}
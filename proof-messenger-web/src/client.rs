@@ -0,0 +1,340 @@
+//! Typed JavaScript client
+//!
+//! The free `*_wasm` functions take and return raw byte arrays.
+//! [`ProofMessengerClient`] wraps key generation, signing and the relay API
+//! behind hex strings and typed objects, and turns both WASM and relay
+//! failures into `ProofMessengerError`s carrying an `errorType`. The
+//! TypeScript interfaces below are emitted into the package's generated
+//! `.d.ts` alongside the class.
+
+use proof_messenger_protocol::key::{generate_secure_keypair, SecureKeypair};
+use proof_messenger_protocol::proof::{make_secure_proof, verify_proof_secure};
+use ed25519_dalek::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::relay_http::fetch;
+use crate::WasmProofError;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+/** A signed message, in the form the relay's `POST /relay` accepts */
+export interface SignedMessage {
+    /** Sender public key (hex) */
+    sender: string;
+    /** Signed context (hex) */
+    context: string;
+    body: string;
+    /** Signature over the context (hex) */
+    proof: string;
+}
+
+/** The relay's acknowledgement of a relayed message */
+export interface RelayReceipt {
+    messageId: string;
+}
+
+/** A message stored by the relay */
+export interface RelayMessage {
+    id: string;
+    groupId: string;
+    sender: string;
+    context: string;
+    body: string;
+    proof: string;
+    verified: boolean;
+    createdAt: string;
+    threadId?: string;
+    replyTo?: string;
+    deletedAt?: string;
+}
+
+/** Every error thrown by ProofMessengerClient */
+export interface ProofMessengerError extends Error {
+    /** e.g. "InvalidInput", "InvalidPrivateKey", "RelayRejected", "NetworkError" */
+    errorType: string;
+    /** `errorType` as SCREAMING_SNAKE_CASE, e.g. "INVALID_KEYPAIR" */
+    code: string;
+    isProofMessengerError: true;
+    /** HTTP status, for relay errors */
+    status?: number;
+    /** The relay's error code, e.g. "REPLAY_DETECTED", for relay errors */
+    relayCode?: string;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SignedMessage")]
+    pub type JsSignedMessage;
+
+    #[wasm_bindgen(typescript_type = "RelayReceipt")]
+    pub type JsRelayReceipt;
+
+    #[wasm_bindgen(typescript_type = "RelayMessage[]")]
+    pub type JsRelayMessages;
+}
+
+/// A signed message, as [`JsSignedMessage`] describes it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedMessage {
+    pub sender: String,
+    pub context: String,
+    pub body: String,
+    pub proof: String,
+}
+
+/// The relay's acknowledgement of a relayed message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "snake_case"))]
+pub struct RelayReceipt {
+    pub message_id: String,
+}
+
+/// A stored message, read in the relay's snake_case and handed to JavaScript in camelCase
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "snake_case"))]
+pub struct RelayMessage {
+    pub id: String,
+    pub group_id: String,
+    pub sender: String,
+    pub context: String,
+    pub body: String,
+    pub proof: String,
+    pub verified: bool,
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+}
+
+/// A client error: a [`WasmProofError`], plus the status and code of relay errors
+#[derive(Debug, Clone)]
+pub struct ClientError {
+    pub error: WasmProofError,
+    pub status: Option<u16>,
+    pub relay_code: Option<String>,
+}
+
+impl ClientError {
+    /// The error for a relay response that was not a success
+    pub fn from_response(status: u16, body: &str) -> Self {
+        let json: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+        let message = json["message"]
+            .as_str()
+            .or_else(|| json["error"].as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| if body.is_empty() { format!("HTTP {}", status) } else { body.to_string() });
+        Self {
+            error: WasmProofError::new("RelayRejected", &format!("Relay rejected the request ({}): {}", status, message)),
+            status: Some(status),
+            relay_code: json["code"].as_str().map(str::to_string),
+        }
+    }
+}
+
+impl From<WasmProofError> for ClientError {
+    fn from(error: WasmProofError) -> Self {
+        Self { error, status: None, relay_code: None }
+    }
+}
+
+impl From<ClientError> for JsValue {
+    fn from(error: ClientError) -> Self {
+        let value = JsValue::from(error.error);
+        if let Some(status) = error.status {
+            js_sys::Reflect::set(&value, &JsValue::from_str("status"), &JsValue::from(status)).unwrap_or_default();
+        }
+        if let Some(code) = error.relay_code {
+            js_sys::Reflect::set(&value, &JsValue::from_str("relayCode"), &JsValue::from_str(&code)).unwrap_or_default();
+        }
+        value
+    }
+}
+
+/// Client for one identity talking to one relay
+#[wasm_bindgen]
+pub struct ProofMessengerClient {
+    relay_url: String,
+    keypair: SecureKeypair,
+}
+
+#[wasm_bindgen]
+impl ProofMessengerClient {
+    /// A client for `relayUrl`, using the hex keypair from `exportKeypair()` or a new one
+    #[wasm_bindgen(constructor)]
+    pub fn new(relay_url: &str, keypair_hex: Option<String>) -> Result<ProofMessengerClient, JsValue> {
+        let keypair = match keypair_hex {
+            Some(keypair_hex) => {
+                let bytes = hex::decode(keypair_hex.trim())
+                    .map_err(|e| WasmProofError::invalid_private_key(&format!("keypair must be hex: {}", e)))?;
+                SecureKeypair::from_bytes(&bytes).map_err(WasmProofError::invalid_private_key)?
+            }
+            None => generate_secure_keypair(),
+        };
+        Ok(Self { relay_url: relay_url.trim_end_matches('/').to_string(), keypair })
+    }
+
+    /// This identity's public key (hex)
+    #[wasm_bindgen(getter, js_name = publicKey)]
+    pub fn public_key(&self) -> String {
+        hex::encode(self.keypair.public_key_bytes())
+    }
+
+    /// The keypair (hex), to restore this identity later; keep it secret
+    #[wasm_bindgen(js_name = exportKeypair)]
+    pub fn export_keypair(&self) -> String {
+        hex::encode(self.keypair.to_bytes())
+    }
+
+    /// Sign `context`, returning the proof (hex)
+    pub fn sign(&self, context: &[u8]) -> Result<String, JsValue> {
+        let proof = make_secure_proof(&self.keypair, context).map_err(WasmProofError::from)?;
+        Ok(hex::encode(proof.to_bytes()))
+    }
+
+    /// Whether `proofHex` is `publicKeyHex`'s proof over `context`
+    ///
+    /// Malformed keys or proofs throw; a well-formed proof that does not
+    /// match returns `false`.
+    pub fn verify(public_key_hex: &str, context: &[u8], proof_hex: &str) -> Result<bool, JsValue> {
+        verify_hex(public_key_hex, context, proof_hex).map_err(JsValue::from)
+    }
+
+    /// Sign `context` and wrap it with `body` as a message for the relay
+    #[wasm_bindgen(js_name = signMessage)]
+    pub fn sign_message(&self, context: &[u8], body: &str) -> Result<JsSignedMessage, JsValue> {
+        to_js(&self.signed_message(context, body)?)
+    }
+
+    /// Sign and relay a message, resolving to the relay's receipt
+    pub async fn send(&self, context: Vec<u8>, body: String) -> Result<JsRelayReceipt, JsValue> {
+        let message = self.signed_message(&context, &body)?;
+        let json = serde_json::to_string(&message)
+            .map_err(|e| WasmProofError::serialization_error(&e.to_string()))?;
+        let response = self.request(&format!("{}/v1/relay", self.relay_url), Some(&json)).await?;
+        let receipt: RelayReceipt = serde_json::from_str(&response)
+            .map_err(|e| WasmProofError::serialization_error(&e.to_string()))?;
+        to_js(&receipt)
+    }
+
+    /// The latest `limit` messages of `groupId`
+    pub async fn messages(&self, group_id: String, limit: Option<u32>) -> Result<JsRelayMessages, JsValue> {
+        let mut url = format!("{}/v1/messages/{}", self.relay_url, String::from(js_sys::encode_uri_component(&group_id)));
+        if let Some(limit) = limit {
+            url.push_str(&format!("?limit={}", limit));
+        }
+        let response = self.request(&url, None).await?;
+        let json: serde_json::Value = serde_json::from_str(&response)
+            .map_err(|e| WasmProofError::serialization_error(&e.to_string()))?;
+        let messages: Vec<RelayMessage> = serde_json::from_value(json["messages"].clone())
+            .map_err(|e| WasmProofError::serialization_error(&e.to_string()))?;
+        to_js(&messages)
+    }
+}
+
+impl ProofMessengerClient {
+    /// Sign `context` and wrap it with `body`
+    pub fn signed_message(&self, context: &[u8], body: &str) -> Result<SignedMessage, WasmProofError> {
+        let proof = make_secure_proof(&self.keypair, context)?;
+        Ok(SignedMessage {
+            sender: self.public_key(),
+            context: hex::encode(context),
+            body: body.to_string(),
+            proof: hex::encode(proof.to_bytes()),
+        })
+    }
+
+    /// Send a request to the relay, returning the body of a successful response
+    async fn request(&self, url: &str, body: Option<&str>) -> Result<String, ClientError> {
+        let (status, text) = fetch(url, body, &[]).await.map_err(|e| {
+            let details = e.as_string().or_else(|| e.dyn_ref::<js_sys::Error>().map(|e| e.message().into()));
+            WasmProofError::new("NetworkError", &format!("Could not reach the relay: {}", details.unwrap_or_default()))
+        })?;
+        if (200..300).contains(&status) {
+            Ok(text)
+        } else {
+            Err(ClientError::from_response(status, &text))
+        }
+    }
+}
+
+/// Verify a hex proof, distinguishing malformed input from a failed verification
+pub fn verify_hex(public_key_hex: &str, context: &[u8], proof_hex: &str) -> Result<bool, WasmProofError> {
+    let public_key = hex::decode(public_key_hex)
+        .ok()
+        .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| WasmProofError::invalid_public_key("expected 32 bytes of hex"))?;
+    let signature = hex::decode(proof_hex)
+        .ok()
+        .and_then(|bytes| Signature::from_bytes(&bytes).ok())
+        .ok_or_else(|| WasmProofError::invalid_signature("expected 64 bytes of hex"))?;
+    Ok(verify_proof_secure(&public_key, context, &signature).is_ok())
+}
+
+/// Hand a value to JavaScript as a plain object
+fn to_js<T: Serialize, U: JsCast>(value: &T) -> Result<U, JsValue> {
+    let json = serde_json::to_string(value).map_err(|e| WasmProofError::serialization_error(&e.to_string()))?;
+    Ok(js_sys::JSON::parse(&json)?.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_message_verifies_and_restores_from_the_exported_keypair() {
+        let client = ProofMessengerClient::new("https://relay.example.com/", None).unwrap();
+        let restored = ProofMessengerClient::new("https://relay.example.com", Some(client.export_keypair())).unwrap();
+
+        let message = restored.signed_message(b"approve:7", "approved").unwrap();
+
+        assert_eq!(restored.public_key(), client.public_key());
+        assert_eq!(restored.relay_url, "https://relay.example.com");
+        assert_eq!(message.sender, client.public_key());
+        assert_eq!(message.context, hex::encode(b"approve:7"));
+        assert!(verify_hex(&message.sender, b"approve:7", &message.proof).unwrap());
+        assert!(!verify_hex(&message.sender, b"approve:8", &message.proof).unwrap());
+    }
+
+    #[test]
+    fn test_malformed_keys_and_proofs_are_errors() {
+        let client = ProofMessengerClient::new("https://relay.example.com", None).unwrap();
+        let proof = client.sign(b"context").unwrap();
+
+        assert_eq!(verify_hex("zz", b"context", &proof).unwrap_err().error_type(), "InvalidPublicKey");
+        assert_eq!(verify_hex(&client.public_key(), b"context", "abcd").unwrap_err().error_type(), "InvalidSignature");
+    }
+
+    #[test]
+    fn test_relay_errors_carry_their_status_and_code() {
+        let replay = ClientError::from_response(409, r#"{"code":"REPLAY_DETECTED","message":"Replay detected"}"#);
+        let limited = ClientError::from_response(429, "Too Many Requests! Wait for 2s");
+
+        assert_eq!(replay.error.error_type(), "RelayRejected");
+        assert_eq!(replay.error.message(), "Relay rejected the request (409): Replay detected");
+        assert_eq!((replay.status, replay.relay_code.as_deref()), (Some(409), Some("REPLAY_DETECTED")));
+        assert_eq!(limited.error.message(), "Relay rejected the request (429): Too Many Requests! Wait for 2s");
+        assert_eq!(limited.relay_code, None);
+    }
+
+    #[test]
+    fn test_relay_messages_are_handed_over_in_camel_case() {
+        let stored = serde_json::json!({
+            "id": "m-1", "group_id": "default", "sender": "aa", "context": "bb", "body": "hi",
+            "proof": "cc", "verified": true, "created_at": "2024-01-01T00:00:00Z", "thread_id": null,
+            "reply_to": null, "deleted_at": null, "message_hash": null
+        });
+
+        let message: RelayMessage = serde_json::from_value(stored).unwrap();
+        let handed_over = serde_json::to_value(&message).unwrap();
+
+        assert_eq!(handed_over["groupId"], "default");
+        assert_eq!(handed_over["createdAt"], "2024-01-01T00:00:00Z");
+        assert!(handed_over.get("threadId").is_none());
+    }
+}
@@ -212,8 +212,8 @@ const Contracts = {
                 parameters: [
                     { name: 'json', type: 'string', required: true, validator: TypeValidators.isString }
                 ],
-                returns: { type: 'WasmMessage|undefined', validator: (v) => v === undefined || v instanceof WasmMessage },
-                throws: []
+                returns: { type: 'WasmMessage', validator: (v) => v instanceof WasmMessage },
+                throws: ['SerializationError for invalid JSON']
             }
         },
         properties: {
@@ -0,0 +1,88 @@
+//! Minimal IndexedDB access for the outbox
+//!
+//! Each store holds JSON strings keyed by a string. IndexedDB requests
+//! report through callbacks; [`request`] turns one into a future.
+
+use js_sys::{Array, Function, Promise};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbObjectStore, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+/// Schema version; bump it when adding stores
+const VERSION: u32 = 1;
+
+/// Open (creating if needed) the database `name` with one object store per name in `stores`
+pub async fn open(name: &str, stores: &'static [&'static str]) -> Result<IdbDatabase, JsValue> {
+    let factory = web_sys::window()
+        .ok_or_else(|| JsValue::from_str("IndexedDB needs a browser window"))?
+        .indexed_db()?
+        .ok_or_else(|| JsValue::from_str("IndexedDB is not available"))?;
+    let open_request: IdbOpenDbRequest = factory.open_with_u32(name, VERSION)?;
+
+    let on_upgrade = Closure::once(move |event: web_sys::Event| {
+        let db: IdbDatabase = event
+            .target()
+            .and_then(|target| target.dyn_into::<IdbOpenDbRequest>().ok())
+            .and_then(|request| request.result().ok())
+            .and_then(|result| result.dyn_into().ok())
+            .expect("upgradeneeded is raised on an open request");
+        let existing = db.object_store_names();
+        for store in stores {
+            if !existing.contains(store) {
+                db.create_object_store(store).expect("object store names are valid");
+            }
+        }
+    });
+    open_request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+
+    let db = request(&open_request).await?;
+    open_request.set_onupgradeneeded(None);
+    db.dyn_into()
+}
+
+/// Store `value` under `key`, replacing any previous value
+pub async fn put(db: &IdbDatabase, store: &str, key: &str, value: &str) -> Result<(), JsValue> {
+    let store = object_store(db, store, IdbTransactionMode::Readwrite)?;
+    request(&store.put_with_key(&JsValue::from_str(value), &JsValue::from_str(key))?).await?;
+    Ok(())
+}
+
+/// Remove the value under `key`, if any
+pub async fn delete(db: &IdbDatabase, store: &str, key: &str) -> Result<(), JsValue> {
+    let store = object_store(db, store, IdbTransactionMode::Readwrite)?;
+    request(&store.delete(&JsValue::from_str(key))?).await?;
+    Ok(())
+}
+
+/// Every value in `store`, in key order
+pub async fn get_all(db: &IdbDatabase, store: &str) -> Result<Vec<String>, JsValue> {
+    let store = object_store(db, store, IdbTransactionMode::Readonly)?;
+    let values: Array = request(&store.get_all()?).await?.dyn_into()?;
+    values
+        .iter()
+        .map(|value| value.as_string().ok_or_else(|| JsValue::from_str("IndexedDB value is not a string")))
+        .collect()
+}
+
+fn object_store(db: &IdbDatabase, store: &str, mode: IdbTransactionMode) -> Result<IdbObjectStore, JsValue> {
+    db.transaction_with_str_and_mode(store, mode)?.object_store(store)
+}
+
+/// Wait for an IndexedDB request to succeed, resolving to its result
+async fn request(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = Promise::new(&mut |resolve: Function, reject: Function| {
+        let success_request = request.clone();
+        let on_success = Closure::once_into_js(move |_: web_sys::Event| {
+            let _ = resolve.call1(&JsValue::NULL, &success_request.result().unwrap_or(JsValue::UNDEFINED));
+        });
+        let error_request = request.clone();
+        let on_error = Closure::once_into_js(move |_: web_sys::Event| {
+            let error = error_request.error().ok().flatten().map(JsValue::from).unwrap_or(JsValue::NULL);
+            let _ = reject.call1(&JsValue::NULL, &error);
+        });
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    JsFuture::from(promise).await
+}
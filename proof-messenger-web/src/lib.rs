@@ -7,12 +7,32 @@ use proof_messenger_protocol::proof::{
     make_secure_proof, make_secure_proof_strict, verify_proof_secure, verify_proof_strict,
     ProofError as ProtocolProofError
 };
-use proof_messenger_protocol::key::{generate_secure_keypair, SecureKeypair};
+use proof_messenger_protocol::key::{generate_mnemonic, generate_secure_keypair, SecureKeypair};
+use proof_messenger_protocol::canonical::canonicalize_str;
+use proof_messenger_protocol::render::render_context;
+use proof_messenger_protocol::invite::{InviteError, InviteUri};
+use proof_messenger_protocol::group::{
+    GroupCiphertext, GroupError, GroupKey, GroupSession, MemberSecret, WrappedGroupKey,
+    GROUP_KEY_LENGTH,
+};
+use proof_messenger_protocol::receipt::{
+    make_receipt, message_hash, message_hash_from_slice, verify_receipt, Receipt,
+};
+use proof_messenger_protocol::context_digest::{
+    context_digest_from_slice, sign_context_digest, verify_context_digest, ContextHasher,
+};
+use wasm_bindgen_futures::JsFuture;
 
 // Property-based tests module
 #[cfg(test)]
 mod property_tests;
 
+pub mod client;
+mod idb;
+pub mod outbox;
+mod relay_http;
+pub mod worker;
+
 // WASM-compatible error handling for rich error propagation to JavaScript
 // Since wasm-bindgen doesn't support enum variants with data, we use a struct approach
 
@@ -46,6 +66,10 @@ impl WasmProofError {
         Self::new("InvalidPrivateKey", &format!("Invalid private key format: {}", details))
     }
     
+    pub fn invalid_keypair(details: &str) -> Self {
+        Self::new("InvalidKeypair", &format!("Invalid keypair format: {}", details))
+    }
+    
     pub fn context_too_large(max: usize, actual: usize) -> Self {
         Self::new("ContextTooLarge", &format!("Context data is too large: {} bytes (max: {} bytes)", actual, max))
     }
@@ -74,6 +98,18 @@ impl WasmProofError {
         &self.error_type
     }
     
+    /// Stable machine-readable code, e.g. `INVALID_PUBLIC_KEY` for `InvalidPublicKey`
+    pub fn code(&self) -> String {
+        let mut code = String::with_capacity(self.error_type.len() + 4);
+        for (i, c) in self.error_type.chars().enumerate() {
+            if c.is_ascii_uppercase() && i > 0 {
+                code.push('_');
+            }
+            code.push(c.to_ascii_uppercase());
+        }
+        code
+    }
+    
     pub fn message(&self) -> &str {
         &self.message
     }
@@ -84,6 +120,7 @@ impl From<ProtocolProofError> for WasmProofError {
     fn from(error: ProtocolProofError) -> Self {
         match error {
             ProtocolProofError::VerificationFailed(_) => WasmProofError::verification_failed(),
+            ProtocolProofError::PqcVerificationFailed => WasmProofError::verification_failed(),
             ProtocolProofError::ContextTooLarge { max, actual } => {
                 WasmProofError::context_too_large(max, actual)
             },
@@ -107,6 +144,12 @@ impl From<WasmProofError> for JsValue {
         let error_obj = js_sys::Error::new(&error.message);
         
         // Add custom properties to the error object
+        js_sys::Reflect::set(
+            &error_obj,
+            &JsValue::from_str("code"),
+            &JsValue::from_str(&error.code()),
+        ).unwrap_or_default();
+        
         js_sys::Reflect::set(
             &error_obj,
             &JsValue::from_str("errorType"),
@@ -165,22 +208,33 @@ pub fn generate_keypair_wasm() -> Vec<u8> {
     out
 }
 
-/// Extract public key from keypair bytes
-#[wasm_bindgen]
-pub fn get_public_key_from_keypair(keypair_bytes: &[u8]) -> Vec<u8> {
+/// Split keypair bytes into the secret and public key halves
+fn split_keypair(keypair_bytes: &[u8]) -> Result<(&[u8], &[u8]), WasmProofError> {
     if keypair_bytes.len() != SECRET_KEY_LENGTH + PUBLIC_KEY_LENGTH {
-        panic!("Invalid keypair length");
+        return Err(WasmProofError::invalid_keypair(&format!(
+            "expected {} bytes, got {}",
+            SECRET_KEY_LENGTH + PUBLIC_KEY_LENGTH,
+            keypair_bytes.len()
+        )));
     }
-    keypair_bytes[SECRET_KEY_LENGTH..].to_vec()
+    Ok(keypair_bytes.split_at(SECRET_KEY_LENGTH))
+}
+
+/// Current time as an ISO 8601 string
+fn now_iso() -> String {
+    String::from(js_sys::Date::new_0().to_iso_string())
+}
+
+/// Extract public key from keypair bytes
+#[wasm_bindgen]
+pub fn get_public_key_from_keypair(keypair_bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
+    Ok(split_keypair(keypair_bytes)?.1.to_vec())
 }
 
 /// Extract private key from keypair bytes
 #[wasm_bindgen]
-pub fn get_private_key_from_keypair(keypair_bytes: &[u8]) -> Vec<u8> {
-    if keypair_bytes.len() != SECRET_KEY_LENGTH + PUBLIC_KEY_LENGTH {
-        panic!("Invalid keypair length");
-    }
-    keypair_bytes[..SECRET_KEY_LENGTH].to_vec()
+pub fn get_private_key_from_keypair(keypair_bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
+    Ok(split_keypair(keypair_bytes)?.0.to_vec())
 }
 
 // B. Hex/Bytes Helpers
@@ -191,17 +245,18 @@ pub fn bytes_to_hex(bytes: &[u8]) -> String {
 
 #[wasm_bindgen]
 pub fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, JsValue> {
-    hex::decode(hex).map_err(|e| JsValue::from_str(&format!("Hex decode error: {e}")))
+    hex::decode(hex).map_err(|e| WasmProofError::invalid_input(&format!("Hex decode error: {e}")).into())
 }
 
 /// Sign some context data with the secret key
 #[wasm_bindgen]
-pub fn make_proof_wasm(privkey_bytes: &[u8], context: &[u8]) -> Vec<u8> {
-    let secret = ed25519_dalek::SecretKey::from_bytes(privkey_bytes).unwrap();
+pub fn make_proof_wasm(privkey_bytes: &[u8], context: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let secret = ed25519_dalek::SecretKey::from_bytes(privkey_bytes)
+        .map_err(|e| WasmProofError::invalid_private_key(&format!("Failed to parse private key: {}", e)))?;
     let public = ed25519_dalek::PublicKey::from(&secret);
     let keypair = Keypair { secret, public };
     let sig = keypair.sign(context);
-    sig.to_bytes().to_vec()
+    Ok(sig.to_bytes().to_vec())
 }
 
 /// Verify a proof given pubkey, context, and proof (signature)
@@ -282,6 +337,258 @@ pub fn verify_proof_strict_wasm(pubkey_bytes: &[u8], context: &[u8], proof_bytes
     Ok(true)
 }
 
+/// Hash a relayed message's sender, signed context and body for receipts
+#[wasm_bindgen]
+pub fn message_hash_wasm(sender: &[u8], context: &[u8], body: &str) -> Vec<u8> {
+    message_hash(sender, context, body.as_bytes()).to_vec()
+}
+
+/// Sign a delivery receipt over a message id and message hash, returning the signature
+#[wasm_bindgen]
+pub fn make_receipt_wasm(keypair_bytes: &[u8], message_id: &str, message_hash: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let secure_keypair = SecureKeypair::from_bytes(keypair_bytes)
+        .map_err(|e| WasmProofError::invalid_private_key(&format!("Failed to parse keypair: {}", e)))?;
+    let message_hash = message_hash_from_slice(message_hash).map_err(WasmProofError::from)?;
+    
+    let receipt = make_receipt(&secure_keypair, message_id, &message_hash)
+        .map_err(WasmProofError::from)?;
+    
+    Ok(receipt.signature.to_bytes().to_vec())
+}
+
+/// Verify a delivery receipt signed by `recipient_bytes` for a message
+#[wasm_bindgen]
+pub fn verify_receipt_wasm(recipient_bytes: &[u8], message_id: &str, message_hash: &[u8], signature_bytes: &[u8]) -> Result<bool, JsValue> {
+    let recipient = PublicKey::from_bytes(recipient_bytes)
+        .map_err(|e| WasmProofError::invalid_public_key(&format!("Failed to parse public key: {}", e)))?;
+    let signature = Signature::from_bytes(signature_bytes)
+        .map_err(|e| WasmProofError::invalid_signature(&format!("Failed to parse signature: {}", e)))?;
+    let message_hash = message_hash_from_slice(message_hash).map_err(WasmProofError::from)?;
+    
+    let receipt = Receipt {
+        message_id: message_id.to_string(),
+        message_hash,
+        recipient,
+        signature,
+    };
+    Ok(verify_receipt(&receipt, message_id, &message_hash).is_ok())
+}
+
+/// Serialize a JSON context in canonical form (RFC 8785)
+#[wasm_bindgen]
+pub fn canonicalize_json_wasm(json: &str) -> Result<String, JsValue> {
+    canonicalize_str(json).map_err(|e| WasmProofError::serialization_error(&e.to_string()).into())
+}
+
+/// Render a recognized context as a localized approval summary
+///
+/// Returns JSON with `template`, `locale` and `summary`; contexts that are not
+/// a known template with safe values are rejected rather than shown raw.
+#[wasm_bindgen]
+pub fn render_context_wasm(context: &[u8], locale: &str) -> Result<String, JsValue> {
+    let rendered = render_context(context, locale).map_err(|e| WasmProofError::invalid_input(&e.to_string()))?;
+    serde_json::to_string(&rendered).map_err(|e| WasmProofError::serialization_error(&e.to_string()).into())
+}
+
+/// Generate a BIP-39 backup phrase of 12 or 24 words
+///
+/// Restore its keypair with `WasmSecureKeyPair.from_mnemonic`.
+#[wasm_bindgen]
+pub fn generate_mnemonic_wasm(word_count: usize) -> Result<String, JsValue> {
+    generate_mnemonic(word_count).map_err(|e| WasmProofError::invalid_input(&e.to_string()).into())
+}
+
+/// Sign the canonical form of a JSON context, returning the signature
+#[wasm_bindgen]
+pub fn make_canonical_proof_wasm(keypair_bytes: &[u8], json: &str) -> Result<Vec<u8>, JsValue> {
+    let context = canonicalize_json_wasm(json)?;
+    make_secure_proof_wasm(keypair_bytes, context.as_bytes())
+}
+
+/// Verify a proof over the canonical form of a JSON context
+#[wasm_bindgen]
+pub fn verify_canonical_proof_wasm(pubkey_bytes: &[u8], json: &str, proof_bytes: &[u8]) -> Result<bool, JsValue> {
+    let context = canonicalize_json_wasm(json)?;
+    verify_proof_secure_wasm(pubkey_bytes, context.as_bytes(), proof_bytes)
+}
+
+/// Incremental BLAKE3 digest of a large signed context
+///
+/// Feed the context in chunks with `update`, then sign the result of
+/// `finalize` with `sign_context_digest_wasm`.
+#[wasm_bindgen]
+pub struct WasmContextHasher {
+    hasher: ContextHasher,
+}
+
+#[wasm_bindgen]
+impl WasmContextHasher {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmContextHasher {
+        WasmContextHasher { hasher: ContextHasher::new() }
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+    }
+
+    /// Number of context bytes hashed so far
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> u64 {
+        self.hasher.len()
+    }
+
+    /// The 32-byte context digest of everything fed so far
+    pub fn finalize(&self) -> Vec<u8> {
+        self.hasher.finalize().to_vec()
+    }
+}
+
+impl Default for WasmContextHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Digest a context read from a JS `ReadableStream` of `Uint8Array` chunks
+#[wasm_bindgen]
+pub async fn hash_context_stream_wasm(stream: web_sys::ReadableStream) -> Result<Vec<u8>, JsValue> {
+    let reader: web_sys::ReadableStreamDefaultReader = stream.get_reader().unchecked_into();
+    let mut hasher = ContextHasher::new();
+    loop {
+        let result: web_sys::ReadableStreamReadResult = JsFuture::from(reader.read())
+            .await
+            .map_err(|e| WasmProofError::invalid_input(&format!("Failed to read stream: {:?}", e)))?
+            .unchecked_into();
+        if result.get_done().unwrap_or(false) {
+            break;
+        }
+        let chunk = result.get_value();
+        if !chunk.is_instance_of::<js_sys::Uint8Array>() {
+            return Err(WasmProofError::invalid_input("Stream chunks must be Uint8Array").into());
+        }
+        hasher.update(&js_sys::Uint8Array::from(chunk).to_vec());
+    }
+    reader.release_lock();
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Sign a context digest, returning the signature
+#[wasm_bindgen]
+pub fn sign_context_digest_wasm(keypair_bytes: &[u8], digest: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let secure_keypair = SecureKeypair::from_bytes(keypair_bytes)
+        .map_err(|e| WasmProofError::invalid_private_key(&format!("Failed to parse keypair: {}", e)))?;
+    let digest = context_digest_from_slice(digest).map_err(WasmProofError::from)?;
+    
+    let signature = sign_context_digest(&secure_keypair, &digest).map_err(WasmProofError::from)?;
+    Ok(signature.to_bytes().to_vec())
+}
+
+/// Verify a signature over a context digest
+#[wasm_bindgen]
+pub fn verify_context_digest_wasm(pubkey_bytes: &[u8], digest: &[u8], signature_bytes: &[u8]) -> Result<bool, JsValue> {
+    let pubkey = PublicKey::from_bytes(pubkey_bytes)
+        .map_err(|e| WasmProofError::invalid_public_key(&format!("Failed to parse public key: {}", e)))?;
+    let signature = Signature::from_bytes(signature_bytes)
+        .map_err(|e| WasmProofError::invalid_signature(&format!("Failed to parse signature: {}", e)))?;
+    let digest = context_digest_from_slice(digest).map_err(WasmProofError::from)?;
+    
+    Ok(verify_context_digest(&pubkey, &digest, &signature).is_ok())
+}
+
+fn group_error(error: GroupError) -> JsValue {
+    WasmProofError::cryptographic_error(&error.to_string()).into()
+}
+
+fn group_json<T: Serialize>(value: &T) -> Result<String, JsValue> {
+    serde_json::to_string(value).map_err(|e| WasmProofError::serialization_error(&e.to_string()).into())
+}
+
+fn parse_group_json<'a, T: Deserialize<'a>>(json: &'a str) -> Result<T, JsValue> {
+    serde_json::from_str(json).map_err(|e| WasmProofError::serialization_error(&e.to_string()).into())
+}
+
+fn group_key_bytes(bytes: &[u8], what: &str) -> Result<[u8; GROUP_KEY_LENGTH], JsValue> {
+    bytes
+        .try_into()
+        .map_err(|_| WasmProofError::invalid_input(&format!("{} must be {} bytes", what, GROUP_KEY_LENGTH)).into())
+}
+
+/// Generate an X25519 member secret for receiving group keys (32 bytes)
+#[wasm_bindgen]
+pub fn generate_group_member_secret_wasm() -> Vec<u8> {
+    MemberSecret::generate().to_bytes().to_vec()
+}
+
+/// Public key that group keys are wrapped for, from a member secret
+#[wasm_bindgen]
+pub fn group_member_public_key_wasm(secret_bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let secret = MemberSecret::from_bytes(group_key_bytes(secret_bytes, "Member secret")?);
+    Ok(secret.public_key().to_vec())
+}
+
+/// Open a wrapped group key (JSON) with a member secret, returning the group key as JSON
+#[wasm_bindgen]
+pub fn unwrap_group_key_wasm(wrapped_json: &str, secret_bytes: &[u8]) -> Result<String, JsValue> {
+    let wrapped: WrappedGroupKey = parse_group_json(wrapped_json)?;
+    let secret = MemberSecret::from_bytes(group_key_bytes(secret_bytes, "Member secret")?);
+    let key = GroupKey::unwrap(&wrapped, &secret).map_err(group_error)?;
+    group_json(&key)
+}
+
+/// Encrypt a message under a group key (JSON), returning the ciphertext as JSON
+#[wasm_bindgen]
+pub fn group_encrypt_wasm(group_key_json: &str, plaintext: &[u8]) -> Result<String, JsValue> {
+    let key: GroupKey = parse_group_json(group_key_json)?;
+    group_json(&key.encrypt(plaintext).map_err(group_error)?)
+}
+
+/// Decrypt a group ciphertext (JSON) with a group key (JSON)
+#[wasm_bindgen]
+pub fn group_decrypt_wasm(group_key_json: &str, ciphertext_json: &str) -> Result<Vec<u8>, JsValue> {
+    let key: GroupKey = parse_group_json(group_key_json)?;
+    let ciphertext: GroupCiphertext = parse_group_json(ciphertext_json)?;
+    key.decrypt(&ciphertext).map_err(group_error)
+}
+
+/// Group membership and key rotation, kept by the group's administrator
+///
+/// Membership changes return the new epoch's key wrapped for every member,
+/// as a JSON array to hand to the relay.
+#[wasm_bindgen]
+pub struct WasmGroupSession {
+    session: GroupSession,
+}
+
+#[wasm_bindgen]
+impl WasmGroupSession {
+    #[wasm_bindgen(constructor)]
+    pub fn new(group_id: &str) -> Result<WasmGroupSession, JsValue> {
+        let (session, _) = GroupSession::new(group_id, &[]).map_err(group_error)?;
+        Ok(WasmGroupSession { session })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn epoch(&self) -> u64 {
+        self.session.current_key().epoch
+    }
+
+    /// The current group key as JSON, for the administrator's own messages
+    pub fn group_key_json(&self) -> Result<String, JsValue> {
+        group_json(self.session.current_key())
+    }
+
+    pub fn add_member(&mut self, public_key: &[u8]) -> Result<String, JsValue> {
+        let member = group_key_bytes(public_key, "Member public key")?;
+        group_json(&self.session.add_member(member).map_err(group_error)?)
+    }
+
+    pub fn remove_member(&mut self, public_key: &[u8]) -> Result<String, JsValue> {
+        let member = group_key_bytes(public_key, "Member public key")?;
+        group_json(&self.session.remove_member(&member).map_err(group_error)?)
+    }
+}
+
 /// Extract public key from secure keypair bytes
 #[wasm_bindgen]
 pub fn get_public_key_from_secure_keypair(keypair_bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
@@ -307,10 +614,53 @@ pub fn generate_invite_code() -> Result<String, JsValue> {
     
     match base32::encode(base32::Alphabet::RFC4648 { padding: false }, &buf).get(..16) {
         Some(code) => Ok(code.to_string()),
-        None => Err(JsValue::from_str("Failed to generate invite code"))
+        None => Err(WasmProofError::internal_error("Failed to generate invite code").into())
     }
 }
 
+/// Parse a `pm://invite?...` URI, e.g. from a scanned QR code, into JSON
+///
+/// `{"data": hex, "inviter": hex, "groupId"?, "relayUrl"?, "expiresAt"?}`,
+/// with `expiresAt` in Unix seconds. `data` is the context to sign when
+/// onboarding.
+#[wasm_bindgen]
+pub fn parse_invite_uri_wasm(uri: &str) -> Result<String, JsValue> {
+    let uri: InviteUri = uri.parse().map_err(|e: InviteError| WasmProofError::invalid_input(&e.to_string()))?;
+    let mut json = serde_json::json!({
+        "data": hex::encode(&uri.data),
+        "inviter": hex::encode(uri.inviter),
+    });
+    if let Some(group_id) = uri.group_id {
+        json["groupId"] = group_id.into();
+    }
+    if let Some(relay_url) = uri.relay_url {
+        json["relayUrl"] = relay_url.into();
+    }
+    if let Some(expires_at) = uri.expires_at {
+        json["expiresAt"] = expires_at.timestamp().into();
+    }
+    Ok(json.to_string())
+}
+
+/// Build a `pm://invite?...` URI for `data`, signed for by `inviter`'s invitees
+#[wasm_bindgen]
+pub fn make_invite_uri_wasm(data: &[u8], inviter: &[u8], group_id: Option<String>, relay_url: Option<String>) -> Result<String, JsValue> {
+    if data.is_empty() {
+        return Err(WasmProofError::invalid_input("Invite data cannot be empty").into());
+    }
+    let inviter: [u8; PUBLIC_KEY_LENGTH] = inviter
+        .try_into()
+        .map_err(|_| WasmProofError::invalid_public_key(&format!("expected {} bytes, got {}", PUBLIC_KEY_LENGTH, inviter.len())))?;
+    let mut uri = InviteUri::new(data.to_vec(), inviter);
+    if let Some(group_id) = group_id {
+        uri = uri.with_group(&group_id);
+    }
+    if let Some(relay_url) = relay_url {
+        uri = uri.with_relay(&relay_url);
+    }
+    Ok(uri.to_string())
+}
+
 /// Verify a signature with separate public key
 #[wasm_bindgen]
 pub fn verify_signature(public_key_bytes: &[u8], message: &[u8], signature_bytes: &[u8]) -> Result<bool, JsValue> {
@@ -348,7 +698,7 @@ impl WasmMessage {
     #[wasm_bindgen(constructor)]
     pub fn new(sender: &[u8], recipient: &[u8], content: &str) -> WasmMessage {
         let id = uuid::Uuid::new_v4().to_string();
-        let timestamp = js_sys::Date::new_0().to_iso_string().as_string().unwrap();
+        let timestamp = now_iso();
         
         WasmMessage {
             sender: sender.to_vec(),
@@ -401,10 +751,11 @@ impl WasmMessage {
     }
     
     pub fn sign(&mut self, keypair_bytes: &[u8]) -> Result<(), JsValue> {
-        let secret = SecretKey::from_bytes(&keypair_bytes[0..SECRET_KEY_LENGTH])
-            .map_err(|e| JsValue::from_str(&format!("SecretKey error: {e}")))?;
-        let public = PublicKey::from_bytes(&keypair_bytes[SECRET_KEY_LENGTH..])
-            .map_err(|e| JsValue::from_str(&format!("PublicKey error: {e}")))?;
+        let (secret, public) = split_keypair(keypair_bytes)?;
+        let secret = SecretKey::from_bytes(secret)
+            .map_err(|e| WasmProofError::invalid_private_key(&format!("Failed to parse private key: {e}")))?;
+        let public = PublicKey::from_bytes(public)
+            .map_err(|e| WasmProofError::invalid_public_key(&format!("Failed to parse public key: {e}")))?;
         let keypair = Keypair { secret, public };
         
         // Create message to sign: sender + recipient + content
@@ -419,7 +770,7 @@ impl WasmMessage {
     pub fn verify(&self, pubkey_bytes: &[u8]) -> Result<bool, JsValue> {
         if let Some(ref sig) = self.proof {
             let public = PublicKey::from_bytes(pubkey_bytes)
-                .map_err(|e| JsValue::from_str(&format!("PublicKey error: {e}")))?;
+                .map_err(|e| WasmProofError::invalid_public_key(&format!("Failed to parse public key: {e}")))?;
             
             // Reconstruct message to verify: sender + recipient + content
             let mut to_sign = self.sender.clone();
@@ -427,7 +778,7 @@ impl WasmMessage {
             to_sign.extend(self.content.as_bytes());
             
             let signature = Signature::from_bytes(sig)
-                .map_err(|e| JsValue::from_str(&format!("Signature error: {e}")))?;
+                .map_err(|e| WasmProofError::invalid_signature(&format!("Failed to parse signature: {e}")))?;
             
             Ok(public.verify(&to_sign, &signature).is_ok())
         } else {
@@ -435,12 +786,12 @@ impl WasmMessage {
         }
     }
     
-    pub fn to_json(&self) -> String {
-        serde_json::to_string(self).unwrap_or_default()
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        serde_json::to_string(self).map_err(|e| WasmProofError::serialization_error(&e.to_string()).into())
     }
     
-    pub fn from_json(json: &str) -> Option<WasmMessage> {
-        serde_json::from_str(json).ok()
+    pub fn from_json(json: &str) -> Result<WasmMessage, JsValue> {
+        serde_json::from_str(json).map_err(|e| WasmProofError::serialization_error(&e.to_string()).into())
     }
 }
 
@@ -541,6 +892,15 @@ impl WasmSecureKeyPair {
         }
     }
     
+    /// Recover the keypair backed up as a BIP-39 phrase, with the same key the CLI derives
+    #[wasm_bindgen(js_name = from_mnemonic)]
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<WasmSecureKeyPair, JsValue> {
+        let secure_keypair = SecureKeypair::from_mnemonic(phrase, passphrase)
+            .map_err(|e| WasmProofError::invalid_private_key(&e.to_string()))?;
+        
+        Ok(WasmSecureKeyPair { secure_keypair })
+    }
+    
     #[wasm_bindgen(getter, js_name = public_key_hex)]
     pub fn public_key_hex(&self) -> String {
         hex::encode(self.secure_keypair.public_key_bytes())
@@ -613,7 +973,7 @@ impl WasmProof {
     #[wasm_bindgen(constructor)]
     pub fn new(proof_type: &str, context: &[u8]) -> WasmProof {
         let id = format!("proof_{}", js_sys::Date::now() as u64);
-        let timestamp = js_sys::Date::new_0().to_iso_string().as_string().unwrap();
+        let timestamp = now_iso();
         
         WasmProof {
             id,
@@ -645,8 +1005,8 @@ impl WasmProof {
         }
     }
     
-    pub fn to_json(&self) -> String {
-        serde_json::to_string(self).unwrap_or_default()
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        serde_json::to_string(self).map_err(|e| WasmProofError::serialization_error(&e.to_string()).into())
     }
 }
 
@@ -696,7 +1056,7 @@ impl Utils {
     }
     
     pub fn current_timestamp() -> String {
-        js_sys::Date::new_0().to_iso_string().as_string().unwrap()
+        now_iso()
     }
     
     pub fn format_timestamp(timestamp: &str) -> String {
@@ -749,14 +1109,16 @@ impl RelayConnection {
     }
     
     pub fn connect(&mut self) -> Result<(), JsValue> {
-        let ws = web_sys::WebSocket::new(&self.url)?;
+        let ws = web_sys::WebSocket::new(&self.url)
+            .map_err(|e| WasmProofError::new("ConnectionError", &format!("Failed to open {}: {:?}", self.url, e)))?;
         self.websocket = Some(ws);
         Ok(())
     }
     
     pub fn send(&self, message: &str) -> Result<(), JsValue> {
         if let Some(ref ws) = self.websocket {
-            ws.send_with_str(message)?;
+            ws.send_with_str(message)
+                .map_err(|e| WasmProofError::new("ConnectionError", &format!("Failed to send: {:?}", e)))?;
         }
         Ok(())
     }
@@ -927,5 +1289,171 @@ mod tests {
         // Wrong key should fail
         let invalid = message.verify(&bob.public_key_bytes()).unwrap();
         assert!(!invalid);
+    }    
+    #[test]
+    fn test_malformed_keypairs_are_errors_not_panics() {
+        let keypair = WasmKeyPair::from_seed(7).keypair_bytes();
+        
+        let (secret, public) = split_keypair(&keypair).unwrap();
+        assert_eq!((secret.len(), public.len()), (SECRET_KEY_LENGTH, PUBLIC_KEY_LENGTH));
+        for bytes in [&keypair[..0], &keypair[..31], &keypair[..63], &[0u8; 65][..]] {
+            let error = split_keypair(bytes).unwrap_err();
+            assert_eq!(error.error_type(), "InvalidKeypair");
+            assert_eq!(error.message(), format!("Invalid keypair format: expected 64 bytes, got {}", bytes.len()));
+        }
+    }
+    
+    #[test]
+    fn test_error_codes_are_screaming_snake_case() {
+        assert_eq!(WasmProofError::invalid_public_key("x").code(), "INVALID_PUBLIC_KEY");
+        assert_eq!(WasmProofError::empty_context().code(), "EMPTY_CONTEXT");
+        assert_eq!(WasmProofError::verification_failed().code(), "VERIFICATION_FAILED");
+        assert_eq!(WasmProofError::new("RelayRejected", "x").code(), "RELAY_REJECTED");
+    }
+    
+    #[test]
+    fn test_invite_uri_roundtrip() {
+        let alice = WasmKeyPair::new();
+        let uri = make_invite_uri_wasm(&[0, 43], &alice.public_key_bytes(), Some("eng team".into()), None).unwrap();
+        
+        let parsed: serde_json::Value = serde_json::from_str(&parse_invite_uri_wasm(&uri).unwrap()).unwrap();
+        
+        assert!(uri.starts_with("pm://invite?"));
+        assert_eq!(parsed["data"], "002b");
+        assert_eq!(parsed["inviter"], alice.public_key_hex());
+        assert_eq!(parsed["groupId"], "eng team");
+        assert!(parsed.get("relayUrl").is_none());
+    }
+    
+    #[test]
+    fn test_receipt_operations() {
+        let alice = WasmKeyPair::new();
+        let bob = WasmKeyPair::new();
+        let hash = message_hash_wasm(&alice.public_key_bytes(), b"context", "Hello Bob!");
+        
+        // Bob acknowledges Alice's message
+        let signature = make_receipt_wasm(&bob.keypair_bytes(), "msg-1", &hash).unwrap();
+        assert!(verify_receipt_wasm(&bob.public_key_bytes(), "msg-1", &hash, &signature).unwrap());
+        
+        // The receipt does not cover another message or another recipient
+        assert!(!verify_receipt_wasm(&bob.public_key_bytes(), "msg-2", &hash, &signature).unwrap());
+        assert!(!verify_receipt_wasm(&alice.public_key_bytes(), "msg-1", &hash, &signature).unwrap());
+    }
+    
+    #[test]
+    fn test_canonical_proof_ignores_key_order_and_formatting() {
+        let alice = WasmKeyPair::new();
+        
+        let signature = make_canonical_proof_wasm(&alice.keypair_bytes(), r#"{"to": "bob", "amount": 10}"#).unwrap();
+        
+        // Another SDK serializing the same context differently still verifies
+        assert!(verify_canonical_proof_wasm(&alice.public_key_bytes(), r#"{ "amount":1.0e1,"to":"bob" }"#, &signature).unwrap());
+        assert_eq!(canonicalize_json_wasm(r#"{ "b": [1.50], "a": null }"#).unwrap(), r#"{"a":null,"b":[1.5]}"#);
+    }
+
+    #[test]
+    fn test_render_context_wasm() {
+        let context = br#"{"template":"payment","values":{"amount":"19.9","currency":"GBP","merchant":"Bookshop"}}"#;
+
+        let rendered: serde_json::Value = serde_json::from_str(&render_context_wasm(context, "en-GB").unwrap()).unwrap();
+
+        assert_eq!(rendered["template"], "payment");
+        assert_eq!(rendered["locale"], "en");
+        assert_eq!(rendered["summary"], "Approve payment of £19.90 to Bookshop");
+    }
+
+    #[test]
+    fn test_mnemonic_backup_roundtrip() {
+        let phrase = generate_mnemonic_wasm(12).unwrap();
+
+        let keypair = WasmSecureKeyPair::from_mnemonic(&phrase, "").unwrap();
+        let recovered = WasmSecureKeyPair::from_mnemonic(&phrase, "").unwrap();
+
+        assert_eq!(phrase.split(' ').count(), 12);
+        assert_eq!(recovered.keypair_bytes(), keypair.keypair_bytes());
+    }
+
+    #[test]
+    fn test_group_session_roundtrip_through_json() {
+        let secret = generate_group_member_secret_wasm();
+        let public_key = group_member_public_key_wasm(&secret).unwrap();
+        let mut session = WasmGroupSession::new("demo").unwrap();
+
+        let wrapped: Vec<serde_json::Value> = serde_json::from_str(&session.add_member(&public_key).unwrap()).unwrap();
+        let member_key = unwrap_group_key_wasm(&wrapped[0].to_string(), &secret).unwrap();
+        let ciphertext = group_encrypt_wasm(&member_key, b"hello group").unwrap();
+
+        assert_eq!(session.epoch(), 1);
+        assert_eq!(group_decrypt_wasm(&session.group_key_json().unwrap(), &ciphertext).unwrap(), b"hello group");
+    }
+
+    #[test]
+    fn test_context_hasher_matches_shared_vectors() {
+        use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+        
+        let vectors: serde_json::Value =
+            serde_json::from_str(include_str!("../../proof-messenger-protocol/tests/vectors/context_digest.json")).unwrap();
+        
+        for vector in vectors["vectors"].as_array().unwrap() {
+            let context = match vector["context_hex"].as_str() {
+                Some(context) => hex::decode(context).unwrap(),
+                None => {
+                    let pattern = &vector["context_pattern"];
+                    let modulus = pattern["modulus"].as_u64().unwrap();
+                    (0..pattern["length"].as_u64().unwrap()).map(|i| (i % modulus) as u8).collect()
+                }
+            };
+            let keypair = generate_secure_keypair_with_seed(vector["seed"].as_u64().unwrap());
+            
+            // Browser stream chunks are typically 64 KiB
+            let mut hasher = WasmContextHasher::new();
+            for chunk in context.chunks(64 * 1024) {
+                hasher.update(chunk);
+            }
+            let digest = hasher.finalize();
+            let signature = sign_context_digest_wasm(&keypair.to_bytes(), &digest).unwrap();
+            
+            assert_eq!(hasher.length(), context.len() as u64);
+            assert_eq!(hex::encode(&digest), vector["digest"].as_str().unwrap());
+            assert_eq!(hex::encode(&signature), vector["signature"].as_str().unwrap());
+            assert!(verify_context_digest_wasm(&keypair.public_key_bytes(), &digest, &signature).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_bindings_match_golden_vectors() {
+        let vectors = proof_messenger_protocol::test_vectors::golden();
+        
+        for vector in &vectors.keypairs {
+            let keypair = generate_secure_keypair_with_seed_wasm(vector.seed).unwrap();
+            assert_eq!(keypair, vector.keypair, "keypair seed {}", vector.seed);
+            assert_eq!(WasmKeyPair::from_seed(vector.seed).keypair_bytes(), vector.keypair);
+            assert_eq!(WasmSecureKeyPair::from_seed(vector.seed).keypair_bytes(), vector.keypair);
+            assert_eq!(get_public_key_from_keypair(&keypair).unwrap(), vector.public_key);
+        }
+        
+        for vector in &vectors.invites {
+            let secret = get_private_key_from_keypair(&generate_secure_keypair_with_seed_wasm(vector.seed).unwrap()).unwrap();
+            assert_eq!(make_proof_wasm(&secret, &vector.invite_data).unwrap(), vector.proof, "invite seed {}", vector.seed);
+            assert!(verify_proof_wasm(&vector.public_key, &vector.invite_data, &vector.proof).unwrap());
+        }
+        
+        for vector in &vectors.proofs {
+            let keypair = generate_secure_keypair_with_seed_wasm(vector.seed).unwrap();
+            assert_eq!(make_secure_proof_wasm(&keypair, &vector.context).unwrap(), vector.signature, "{}", vector.name);
+            assert_eq!(make_proof_wasm(&keypair[..SECRET_KEY_LENGTH], &vector.context).unwrap(), vector.signature, "{}", vector.name);
+            assert!(verify_proof_secure_wasm(&vector.public_key, &vector.context, &vector.signature).unwrap());
+        }
+        
+        for vector in &vectors.receipts {
+            let sender = get_public_key_from_keypair(&generate_secure_keypair_with_seed_wasm(vector.sender_seed).unwrap()).unwrap();
+            let recipient = generate_secure_keypair_with_seed_wasm(vector.recipient_seed).unwrap();
+            let hash = message_hash_wasm(&sender, &vector.context, &vector.body);
+            let signature = make_receipt_wasm(&recipient, &vector.message_id, &hash).unwrap();
+            
+            assert_eq!(hash, vector.message_hash, "{}", vector.name);
+            assert_eq!(signature, vector.signature, "{}", vector.name);
+            assert!(verify_receipt_wasm(&get_public_key_from_keypair(&recipient).unwrap(), &vector.message_id, &hash, &signature).unwrap());
+        }
     }
 }
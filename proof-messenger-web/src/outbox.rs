@@ -0,0 +1,567 @@
+//! Offline outbox and sync
+//!
+//! Browsers go offline. Signed messages sent through [`WasmOutbox`] are
+//! queued in IndexedDB first, so they survive page reloads, and delivered to
+//! the relay by [`WasmOutbox::flush`] whenever the browser is back online.
+//! Failed deliveries are retried with exponential backoff.
+//!
+//! Each message's idempotency key is the protocol's message hash of its
+//! sender, signed context and body, sent as the `Idempotency-Key` header.
+//! Retrying a message the relay already stored is harmless: the relay
+//! answers `409 REPLAY_DETECTED`, which counts as delivered, and
+//! [`WasmOutbox::sync`] recognises it in the relay's history by the same key.
+
+use proof_messenger_protocol::receipt::message_hash;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+use web_sys::IdbDatabase;
+
+use crate::relay_http::fetch;
+use crate::{idb, WasmProofError};
+
+/// Database used when none is named
+const DEFAULT_DATABASE: &str = "proof-messenger";
+
+/// Object store holding outbox entries
+const OUTBOX_STORE: &str = "outbox";
+
+/// Delay before the first retry
+const RETRY_BASE_MS: f64 = 1_000.0;
+
+/// Longest delay between retries
+const RETRY_MAX_MS: f64 = 300_000.0;
+
+/// Messages fetched from the relay's history by [`WasmOutbox::sync`] by default
+const DEFAULT_SYNC_LIMIT: u32 = 100;
+
+/// Outbox error types
+#[derive(Error, Debug, PartialEq)]
+pub enum OutboxError {
+    #[error("Invalid outgoing message: {0}")]
+    InvalidMessage(String),
+
+    #[error("Unknown outbox entry: {0}")]
+    UnknownEntry(String),
+}
+
+impl From<OutboxError> for WasmProofError {
+    fn from(error: OutboxError) -> Self {
+        WasmProofError::invalid_input(&error.to_string())
+    }
+}
+
+/// A signed message as the relay's `POST /relay` accepts it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutgoingMessage {
+    /// Sender public key (hex encoded)
+    pub sender: String,
+    /// Signed context (hex encoded)
+    pub context: String,
+    pub body: String,
+    /// Signature over the context (hex encoded)
+    pub proof: String,
+}
+
+impl OutgoingMessage {
+    /// Idempotency key: the hex message hash of the sender, context and body
+    pub fn idempotency_key(&self) -> Result<String, OutboxError> {
+        key(&self.sender, &self.context, &self.body)
+            .ok_or_else(|| OutboxError::InvalidMessage("sender and context must be hex encoded".to_string()))
+    }
+}
+
+fn key(sender: &str, context: &str, body: &str) -> Option<String> {
+    let sender = hex::decode(sender).ok()?;
+    let context = hex::decode(context).ok()?;
+    Some(hex::encode(message_hash(&sender, &context, body.as_bytes())))
+}
+
+/// Where a queued message stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+    /// Waiting to be delivered, or to be retried
+    Pending,
+    /// Accepted by the relay
+    Delivered,
+    /// Refused by the relay; retrying would not help
+    Rejected,
+}
+
+/// A queued message and its delivery state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    /// Idempotency key, see [`OutgoingMessage::idempotency_key`]
+    pub key: String,
+    pub message: OutgoingMessage,
+    pub status: OutboxStatus,
+    /// Delivery attempts made so far
+    pub attempts: u32,
+    /// When the message was queued, in milliseconds since the epoch
+    pub queued_at_ms: f64,
+    /// Earliest time of the next attempt, in milliseconds since the epoch
+    pub next_attempt_ms: f64,
+    /// Relay message ID, once known
+    pub message_id: Option<String>,
+    /// Why the last attempt failed
+    pub last_error: Option<String>,
+}
+
+/// Result of one delivery attempt
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeliveryOutcome {
+    /// The relay stored the message, or had already stored it
+    Delivered { message_id: Option<String> },
+    /// A transient failure; try again later
+    Retry(String),
+    /// The relay refused the message
+    Rejected(String),
+}
+
+impl DeliveryOutcome {
+    /// Classify the relay's response to `POST /relay`
+    pub fn from_response(status: u16, body: &str) -> Self {
+        let json: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+        let code = json["code"].as_str().unwrap_or_default();
+        match status {
+            200..=299 => DeliveryOutcome::Delivered { message_id: json["message_id"].as_str().map(str::to_string) },
+            409 if code == "REPLAY_DETECTED" => DeliveryOutcome::Delivered { message_id: None },
+            408 | 429 | 500..=599 => DeliveryOutcome::Retry(format!("relay answered {}", status)),
+            _ => DeliveryOutcome::Rejected(match json["message"].as_str() {
+                Some(message) => format!("relay answered {}: {}", status, message),
+                None => format!("relay answered {}", status),
+            }),
+        }
+    }
+}
+
+/// A message from the relay's history, as `GET /messages/:group_id` returns it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelayMessage {
+    pub id: String,
+    pub sender: String,
+    pub context: String,
+    pub body: String,
+    pub created_at: String,
+}
+
+/// A message in the reconciled local state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncedMessage {
+    /// Idempotency key, or `None` for relay messages with undecodable fields
+    pub key: Option<String>,
+    /// Relay message ID, or `None` while the message is only local
+    pub message_id: Option<String>,
+    pub sender: String,
+    pub context: String,
+    pub body: String,
+    pub status: OutboxStatus,
+    /// Relay timestamp, or `None` while the message is only local
+    pub created_at: Option<String>,
+}
+
+/// Outcome of reconciling the outbox with the relay's history
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncReport {
+    /// Keys of queued messages found delivered, now dropped from the outbox
+    pub confirmed: Vec<String>,
+    /// The relay's history followed by messages not yet on the relay
+    pub messages: Vec<SyncedMessage>,
+}
+
+/// Outcome of delivering the due messages
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FlushReport {
+    pub delivered: Vec<String>,
+    pub retrying: Vec<String>,
+    pub rejected: Vec<String>,
+}
+
+/// Queued messages in the order they were queued
+#[derive(Debug, Clone, Default)]
+pub struct Outbox {
+    entries: Vec<OutboxEntry>,
+}
+
+impl Outbox {
+    /// An outbox holding previously persisted entries
+    pub fn from_entries(mut entries: Vec<OutboxEntry>) -> Self {
+        entries.sort_by(|a, b| a.queued_at_ms.total_cmp(&b.queued_at_ms));
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &[OutboxEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, key: &str) -> Option<&OutboxEntry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+
+    /// Queue a message for delivery
+    ///
+    /// Queuing a message that is already queued returns the existing entry.
+    pub fn enqueue(&mut self, message: OutgoingMessage, now_ms: f64) -> Result<&OutboxEntry, OutboxError> {
+        let key = message.idempotency_key()?;
+        if let Some(index) = self.entries.iter().position(|entry| entry.key == key) {
+            return Ok(&self.entries[index]);
+        }
+
+        self.entries.push(OutboxEntry {
+            key,
+            message,
+            status: OutboxStatus::Pending,
+            attempts: 0,
+            queued_at_ms: now_ms,
+            next_attempt_ms: now_ms,
+            message_id: None,
+            last_error: None,
+        });
+        Ok(self.entries.last().expect("just pushed"))
+    }
+
+    /// Pending messages whose next attempt is due, oldest first
+    pub fn due(&self, now_ms: f64) -> Vec<OutboxEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status == OutboxStatus::Pending && entry.next_attempt_ms <= now_ms)
+            .cloned()
+            .collect()
+    }
+
+    /// Record the outcome of an attempt to deliver `key`
+    pub fn record(&mut self, key: &str, outcome: DeliveryOutcome, now_ms: f64) -> Result<&OutboxEntry, OutboxError> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.key == key)
+            .ok_or_else(|| OutboxError::UnknownEntry(key.to_string()))?;
+        entry.attempts += 1;
+        match outcome {
+            DeliveryOutcome::Delivered { message_id } => {
+                entry.status = OutboxStatus::Delivered;
+                entry.message_id = message_id.or(entry.message_id.take());
+                entry.last_error = None;
+            }
+            DeliveryOutcome::Retry(error) => {
+                entry.next_attempt_ms = now_ms + retry_delay_ms(entry.attempts);
+                entry.last_error = Some(error);
+            }
+            DeliveryOutcome::Rejected(error) => {
+                entry.status = OutboxStatus::Rejected;
+                entry.last_error = Some(error);
+            }
+        }
+        Ok(entry)
+    }
+
+    /// Make every pending message due now, e.g. when the browser comes back online
+    pub fn retry_now(&mut self, now_ms: f64) {
+        for entry in self.entries.iter_mut().filter(|entry| entry.status == OutboxStatus::Pending) {
+            entry.next_attempt_ms = entry.next_attempt_ms.min(now_ms);
+        }
+    }
+
+    /// Reconcile with the relay's history of a group, oldest message first
+    ///
+    /// Queued messages found in the history, and messages the relay already
+    /// accepted, are dropped from the outbox: the history now holds them.
+    pub fn reconcile(&mut self, history: &[RelayMessage]) -> SyncReport {
+        let mut report = SyncReport::default();
+        let mut seen = HashSet::new();
+
+        for message in history {
+            let key = key(&message.sender, &message.context, &message.body);
+            if let Some(key) = &key {
+                seen.insert(key.clone());
+            }
+            report.messages.push(SyncedMessage {
+                key,
+                message_id: Some(message.id.clone()),
+                sender: message.sender.clone(),
+                context: message.context.clone(),
+                body: message.body.clone(),
+                status: OutboxStatus::Delivered,
+                created_at: Some(message.created_at.clone()),
+            });
+        }
+
+        self.entries.retain(|entry| {
+            let delivered = seen.contains(&entry.key) || entry.status == OutboxStatus::Delivered;
+            if delivered {
+                report.confirmed.push(entry.key.clone());
+            } else {
+                report.messages.push(SyncedMessage {
+                    key: Some(entry.key.clone()),
+                    message_id: None,
+                    sender: entry.message.sender.clone(),
+                    context: entry.message.context.clone(),
+                    body: entry.message.body.clone(),
+                    status: entry.status,
+                    created_at: None,
+                });
+            }
+            !delivered
+        });
+
+        report
+    }
+}
+
+/// Delay before retry number `attempts`, doubling from one second up to five minutes
+pub fn retry_delay_ms(attempts: u32) -> f64 {
+    (RETRY_BASE_MS * 2f64.powi(attempts.saturating_sub(1).min(16) as i32)).min(RETRY_MAX_MS)
+}
+
+/// Outbox persisted in IndexedDB and delivered to a relay
+#[wasm_bindgen]
+pub struct WasmOutbox {
+    relay_url: String,
+    db: IdbDatabase,
+    outbox: Outbox,
+}
+
+#[wasm_bindgen]
+impl WasmOutbox {
+    /// Open the outbox for `relay_url`, restoring messages queued before a reload
+    pub async fn open(relay_url: &str, db_name: Option<String>) -> Result<WasmOutbox, JsValue> {
+        let db = idb::open(db_name.as_deref().unwrap_or(DEFAULT_DATABASE), &[OUTBOX_STORE]).await?;
+        let entries = idb::get_all(&db, OUTBOX_STORE)
+            .await?
+            .iter()
+            .map(|json| serde_json::from_str(json))
+            .collect::<Result<Vec<OutboxEntry>, _>>()
+            .map_err(|e| WasmProofError::serialization_error(&e.to_string()))?;
+
+        Ok(WasmOutbox {
+            relay_url: relay_url.trim_end_matches('/').to_string(),
+            db,
+            outbox: Outbox::from_entries(entries),
+        })
+    }
+
+    /// Queue a signed message, returning its idempotency key
+    pub async fn enqueue(&mut self, sender_hex: &str, context_hex: &str, body: &str, proof_hex: &str) -> Result<String, JsValue> {
+        let message = OutgoingMessage {
+            sender: sender_hex.to_string(),
+            context: context_hex.to_string(),
+            body: body.to_string(),
+            proof: proof_hex.to_string(),
+        };
+        let entry = self.outbox.enqueue(message, js_sys::Date::now()).map_err(WasmProofError::from)?.clone();
+        self.persist(&entry).await?;
+        Ok(entry.key)
+    }
+
+    /// Deliver every due message; call when the browser comes back online
+    ///
+    /// Returns a JSON [`FlushReport`] of the keys delivered, left to retry
+    /// and rejected.
+    pub async fn flush(&mut self) -> Result<String, JsValue> {
+        let now = js_sys::Date::now();
+        self.outbox.retry_now(now);
+
+        let mut report = FlushReport::default();
+        for entry in self.outbox.due(now) {
+            let outcome = match self.deliver(&entry).await {
+                Ok(outcome) => outcome,
+                Err(e) => DeliveryOutcome::Retry(e.as_string().unwrap_or_else(|| "network error".to_string())),
+            };
+            let list = match &outcome {
+                DeliveryOutcome::Delivered { .. } => &mut report.delivered,
+                DeliveryOutcome::Retry(_) => &mut report.retrying,
+                DeliveryOutcome::Rejected(_) => &mut report.rejected,
+            };
+            list.push(entry.key.clone());
+            let updated = self.outbox.record(&entry.key, outcome, js_sys::Date::now()).map_err(WasmProofError::from)?.clone();
+            self.persist(&updated).await?;
+        }
+
+        to_json(&report)
+    }
+
+    /// Reconcile the outbox with the relay's history of `group_id`
+    ///
+    /// Returns a JSON [`SyncReport`]: the latest `limit` relay messages
+    /// followed by the queued messages not yet on the relay.
+    pub async fn sync(&mut self, group_id: &str, limit: Option<u32>) -> Result<String, JsValue> {
+        let url = format!(
+            "{}/v1/messages/{}?limit={}",
+            self.relay_url,
+            String::from(js_sys::encode_uri_component(group_id)),
+            limit.unwrap_or(DEFAULT_SYNC_LIMIT)
+        );
+        let (status, body) = fetch(&url, None, &[]).await?;
+        if !(200..300).contains(&status) {
+            return Err(WasmProofError::internal_error(&format!("relay answered {} to {}", status, url)).into());
+        }
+
+        let json: serde_json::Value =
+            serde_json::from_str(&body).map_err(|e| WasmProofError::serialization_error(&e.to_string()))?;
+        let mut history: Vec<RelayMessage> = serde_json::from_value(json["messages"].clone())
+            .map_err(|e| WasmProofError::serialization_error(&e.to_string()))?;
+        history.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+        let report = self.outbox.reconcile(&history);
+        for key in &report.confirmed {
+            idb::delete(&self.db, OUTBOX_STORE, key).await?;
+        }
+        to_json(&report)
+    }
+
+    /// Every queued entry as a JSON array of [`OutboxEntry`]
+    pub fn entries(&self) -> Result<String, JsValue> {
+        to_json(&self.outbox.entries())
+    }
+
+    /// Number of messages still waiting to be delivered
+    pub fn pending_count(&self) -> usize {
+        self.outbox.entries().iter().filter(|entry| entry.status == OutboxStatus::Pending).count()
+    }
+
+    async fn persist(&self, entry: &OutboxEntry) -> Result<(), JsValue> {
+        idb::put(&self.db, OUTBOX_STORE, &entry.key, &to_json(entry)?).await
+    }
+
+    async fn deliver(&self, entry: &OutboxEntry) -> Result<DeliveryOutcome, JsValue> {
+        let body = to_json(&entry.message)?;
+        let url = format!("{}/v1/relay", self.relay_url);
+        let (status, body) = fetch(&url, Some(&body), &[("Idempotency-Key", &entry.key)]).await?;
+        Ok(DeliveryOutcome::from_response(status, &body))
+    }
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String, JsValue> {
+    serde_json::to_string(value).map_err(|e| WasmProofError::serialization_error(&e.to_string()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+    use proof_messenger_protocol::proof::make_secure_proof;
+
+    fn signed(context: &str, body: &str) -> OutgoingMessage {
+        let keypair = generate_secure_keypair_with_seed(48);
+        let proof = make_secure_proof(&keypair, context.as_bytes()).unwrap();
+        OutgoingMessage {
+            sender: hex::encode(keypair.public_key_bytes()),
+            context: hex::encode(context),
+            body: body.to_string(),
+            proof: hex::encode(proof.to_bytes()),
+        }
+    }
+
+    fn relayed(id: &str, message: &OutgoingMessage, created_at: &str) -> RelayMessage {
+        RelayMessage {
+            id: id.to_string(),
+            sender: message.sender.clone(),
+            context: message.context.clone(),
+            body: message.body.clone(),
+            created_at: created_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_enqueuing_the_same_message_twice_queues_it_once() {
+        let mut outbox = Outbox::default();
+
+        let first = outbox.enqueue(signed("pay:1", "hello"), 0.0).unwrap().key.clone();
+        let again = outbox.enqueue(signed("pay:1", "hello"), 5.0).unwrap().key.clone();
+        let other = outbox.enqueue(signed("pay:2", "hello"), 5.0).unwrap().key.clone();
+
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert_eq!(outbox.entries().len(), 2);
+        assert_eq!(
+            outbox.enqueue(OutgoingMessage { sender: "zz".to_string(), ..signed("pay:3", "hi") }, 0.0).err(),
+            Some(OutboxError::InvalidMessage("sender and context must be hex encoded".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_failed_deliveries_back_off_until_the_browser_is_online() {
+        // ARRANGE: A queued message
+        let mut outbox = Outbox::default();
+        let key = outbox.enqueue(signed("pay:1", "hello"), 0.0).unwrap().key.clone();
+
+        // ACT: Two attempts fail while offline
+        outbox.record(&key, DeliveryOutcome::Retry("offline".to_string()), 0.0).unwrap();
+        let after_first = outbox.due(999.0).len();
+        outbox.record(&key, DeliveryOutcome::Retry("offline".to_string()), 1_000.0).unwrap();
+        let after_second = (outbox.due(2_999.0).len(), outbox.due(3_000.0).len());
+        outbox.retry_now(1_500.0);
+
+        // ASSERT: Retries wait 1s, then 2s, unless the browser comes back online
+        assert_eq!(after_first, 0);
+        assert_eq!(after_second, (0, 1));
+        assert_eq!(outbox.due(1_500.0).len(), 1);
+        assert_eq!(outbox.get(&key).unwrap().attempts, 2);
+        assert_eq!(retry_delay_ms(30), RETRY_MAX_MS);
+    }
+
+    #[test]
+    fn test_relay_responses_are_classified() {
+        let stored = DeliveryOutcome::from_response(200, r#"{"status":"success","message_id":"m-1"}"#);
+        let replayed = DeliveryOutcome::from_response(409, r#"{"code":"REPLAY_DETECTED","message":"Replay detected"}"#);
+        let invalid = DeliveryOutcome::from_response(400, r#"{"code":"INVALID_SIGNATURE","message":"Bad proof"}"#);
+
+        assert_eq!(stored, DeliveryOutcome::Delivered { message_id: Some("m-1".to_string()) });
+        assert_eq!(replayed, DeliveryOutcome::Delivered { message_id: None });
+        assert_eq!(invalid, DeliveryOutcome::Rejected("relay answered 400: Bad proof".to_string()));
+        assert!(matches!(DeliveryOutcome::from_response(503, ""), DeliveryOutcome::Retry(_)));
+        assert!(matches!(DeliveryOutcome::from_response(429, "Too Many Requests!"), DeliveryOutcome::Retry(_)));
+    }
+
+    #[test]
+    fn test_sync_confirms_delivered_messages_and_keeps_the_rest() {
+        // ARRANGE: Three queued messages; the first reached the relay before a
+        // timeout hid the response, and the second was refused
+        let mut outbox = Outbox::default();
+        let lost_response = signed("pay:1", "first");
+        let refused = signed("pay:2", "second");
+        let offline = signed("pay:3", "third");
+        let lost_key = outbox.enqueue(lost_response.clone(), 0.0).unwrap().key.clone();
+        let refused_key = outbox.enqueue(refused, 1.0).unwrap().key.clone();
+        outbox.enqueue(offline, 2.0).unwrap();
+        outbox.record(&lost_key, DeliveryOutcome::Retry("timed out".to_string()), 3.0).unwrap();
+        outbox.record(&refused_key, DeliveryOutcome::Rejected("relay answered 400".to_string()), 3.0).unwrap();
+        let someone_else = OutgoingMessage { sender: hex::encode([7u8; 32]), ..signed("hi", "from bob") };
+
+        // ACT: Reconcile with the relay's history
+        let report = outbox.reconcile(&[
+            relayed("m-0", &someone_else, "2024-01-01T00:00:00Z"),
+            relayed("m-1", &lost_response, "2024-01-01T00:00:01Z"),
+        ]);
+
+        // ASSERT: The relayed message is confirmed and the others stay queued after the history
+        assert_eq!(report.confirmed, vec![lost_key]);
+        let statuses: Vec<_> = report.messages.iter().map(|m| (m.body.as_str(), m.status, m.message_id.as_deref())).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("from bob", OutboxStatus::Delivered, Some("m-0")),
+                ("first", OutboxStatus::Delivered, Some("m-1")),
+                ("second", OutboxStatus::Rejected, None),
+                ("third", OutboxStatus::Pending, None),
+            ]
+        );
+        assert_eq!(outbox.entries().len(), 2);
+    }
+
+    #[test]
+    fn test_entries_survive_a_persistence_roundtrip_in_queue_order() {
+        let mut outbox = Outbox::default();
+        outbox.enqueue(signed("pay:1", "first"), 10.0).unwrap();
+        outbox.enqueue(signed("pay:2", "second"), 20.0).unwrap();
+
+        // IndexedDB returns entries in key order, not queue order
+        let mut persisted: Vec<String> = outbox.entries().iter().map(|e| serde_json::to_string(e).unwrap()).collect();
+        persisted.reverse();
+        let restored = Outbox::from_entries(persisted.iter().map(|json| serde_json::from_str(json).unwrap()).collect());
+
+        assert_eq!(restored.entries(), outbox.entries());
+    }
+}
@@ -0,0 +1,28 @@
+//! HTTP requests to the relay through the browser's `fetch`
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+/// GET `url`, or POST `body` as JSON when given; returns the status and response text
+///
+/// Network failures are returned as errors; HTTP error statuses are not.
+pub async fn fetch(url: &str, body: Option<&str>, headers: &[(&str, &str)]) -> Result<(u16, String), JsValue> {
+    let init = web_sys::RequestInit::new();
+    let request_headers = web_sys::Headers::new()?;
+    for (name, value) in headers {
+        request_headers.set(name, value)?;
+    }
+    if let Some(body) = body {
+        request_headers.set("Content-Type", "application/json")?;
+        init.set_method("POST");
+        init.set_body(&JsValue::from_str(body));
+    }
+    init.set_headers(&request_headers);
+
+    let request = web_sys::Request::new_with_str_and_init(url, &init)?;
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("fetch needs a browser window"))?;
+    let response: web_sys::Response = JsFuture::from(window.fetch_with_request(&request)).await?.dyn_into()?;
+    let text = JsFuture::from(response.text()?).await?.as_string().unwrap_or_default();
+    Ok((response.status(), text))
+}
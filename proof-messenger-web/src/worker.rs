@@ -0,0 +1,135 @@
+//! Signing from a Web Worker
+//!
+//! Signing a large context on the main thread stalls the UI. These bindings
+//! take and return `ArrayBuffer`s so a page can move the work into a worker:
+//! inputs are transferred with `postMessage(data, [buffer])` instead of being
+//! copied, and every returned buffer is a fresh one outside WASM memory, so it
+//! can be transferred back the same way. [`sign_all_wasm`] signs a batch of
+//! contexts in one call, so a worker pays the message round trip once.
+//!
+//! `examples/signing-worker.js` is a worker harness built on them.
+
+use ed25519_dalek::{PublicKey, Signature, SIGNATURE_LENGTH};
+use js_sys::{Array, ArrayBuffer, Uint8Array};
+use proof_messenger_protocol::key::SecureKeypair;
+use proof_messenger_protocol::proof::{make_secure_proof, verify_proof_secure};
+use wasm_bindgen::prelude::*;
+
+use crate::WasmProofError;
+
+/// Sign `context` with a 64-byte keypair, returning the 64-byte signature
+#[wasm_bindgen]
+pub fn sign_buffer_wasm(keypair: &ArrayBuffer, context: &ArrayBuffer) -> Result<ArrayBuffer, JsValue> {
+    let keypair = parse_keypair(&bytes(keypair, "keypair")?)?;
+    let signature = sign(&keypair, &bytes(context, "context")?)?;
+    Ok(to_buffer(&signature))
+}
+
+/// Verify a signature made by [`sign_buffer_wasm`] or [`sign_all_wasm`]
+#[wasm_bindgen]
+pub fn verify_buffer_wasm(public_key: &ArrayBuffer, context: &ArrayBuffer, signature: &ArrayBuffer) -> Result<bool, JsValue> {
+    let public_key = PublicKey::from_bytes(&bytes(public_key, "public key")?)
+        .map_err(|e| WasmProofError::invalid_public_key(&format!("Failed to parse public key: {}", e)))?;
+    let signature = Signature::from_bytes(&bytes(signature, "signature")?)
+        .map_err(|e| WasmProofError::invalid_signature(&format!("Failed to parse signature: {}", e)))?;
+    Ok(verify_proof_secure(&public_key, &bytes(context, "context")?, &signature).is_ok())
+}
+
+/// Sign every context in `contexts`, an array of `ArrayBuffer`s
+///
+/// Returns one buffer holding the 64-byte signatures back to back, in the
+/// order of `contexts`: signature `i` is bytes `64 * i .. 64 * (i + 1)`.
+/// Nothing is returned if any context fails; the error names its index.
+#[wasm_bindgen]
+pub fn sign_all_wasm(keypair: &ArrayBuffer, contexts: &Array) -> Result<ArrayBuffer, JsValue> {
+    let keypair = parse_keypair(&bytes(keypair, "keypair")?)?;
+    let contexts = contexts
+        .iter()
+        .enumerate()
+        .map(|(i, context)| bytes(&context, &format!("contexts[{}]", i)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(to_buffer(&sign_all(&keypair, &contexts)?))
+}
+
+/// Signatures of `contexts`, concatenated in order
+fn sign_all(keypair: &SecureKeypair, contexts: &[Vec<u8>]) -> Result<Vec<u8>, WasmProofError> {
+    let mut signatures = Vec::with_capacity(contexts.len() * SIGNATURE_LENGTH);
+    for (i, context) in contexts.iter().enumerate() {
+        let signature = sign(keypair, context).map_err(|error| {
+            WasmProofError::new(error.error_type(), &format!("contexts[{}]: {}", i, error.message()))
+        })?;
+        signatures.extend_from_slice(&signature);
+    }
+    Ok(signatures)
+}
+
+fn sign(keypair: &SecureKeypair, context: &[u8]) -> Result<[u8; SIGNATURE_LENGTH], WasmProofError> {
+    Ok(make_secure_proof(keypair, context)?.to_bytes())
+}
+
+fn parse_keypair(bytes: &[u8]) -> Result<SecureKeypair, WasmProofError> {
+    SecureKeypair::from_bytes(bytes)
+        .map_err(|e| WasmProofError::invalid_keypair(&format!("Failed to parse keypair: {}", e)))
+}
+
+/// Copy the bytes out of an `ArrayBuffer`, or a typed array view of one
+fn bytes(value: &JsValue, what: &str) -> Result<Vec<u8>, WasmProofError> {
+    if value.is_instance_of::<ArrayBuffer>() || ArrayBuffer::is_view(value) {
+        Ok(Uint8Array::new(value).to_vec())
+    } else {
+        Err(WasmProofError::invalid_input(&format!("{} must be an ArrayBuffer", what)))
+    }
+}
+
+/// A new `ArrayBuffer` holding `bytes`, detached from WASM memory and so transferable
+fn to_buffer(bytes: &[u8]) -> ArrayBuffer {
+    Uint8Array::from(bytes).buffer()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proof_messenger_protocol::key::generate_secure_keypair_with_seed;
+
+    #[test]
+    fn test_sign_all_concatenates_signatures_in_order() {
+        // ARRANGE
+        let keypair = generate_secure_keypair_with_seed(7);
+        let contexts = vec![b"first".to_vec(), b"second".to_vec(), vec![0u8; 4096]];
+
+        // ACT
+        let signatures = sign_all(&keypair, &contexts).unwrap();
+
+        // ASSERT
+        assert_eq!(signatures.len(), contexts.len() * SIGNATURE_LENGTH);
+        let public_key = keypair.public_key();
+        for (context, signature) in contexts.iter().zip(signatures.chunks(SIGNATURE_LENGTH)) {
+            let signature = Signature::from_bytes(signature).unwrap();
+            assert!(verify_proof_secure(&public_key, context, &signature).is_ok());
+            assert_eq!(signature.to_bytes(), sign(&keypair, context).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_sign_all_of_nothing_is_empty() {
+        let keypair = generate_secure_keypair_with_seed(7);
+        assert!(sign_all(&keypair, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sign_all_names_the_failing_context() {
+        let keypair = generate_secure_keypair_with_seed(7);
+        let too_large = vec![0u8; proof_messenger_protocol::proof::MAX_CONTEXT_SIZE + 1];
+
+        let error = sign_all(&keypair, &[b"fine".to_vec(), too_large]).unwrap_err();
+
+        assert_eq!(error.code(), "CONTEXT_TOO_LARGE");
+        assert!(error.message().starts_with("contexts[1]: "));
+    }
+
+    #[test]
+    fn test_malformed_keypair_is_an_error() {
+        let error = parse_keypair(&[0u8; 10]).err().unwrap();
+        assert_eq!(error.code(), "INVALID_KEYPAIR");
+    }
+}
@@ -0,0 +1,128 @@
+// Tests for the proof-messenger-react hooks
+import { describe, it, expect, beforeAll, beforeEach, afterEach } from 'vitest';
+import { createElement } from 'react';
+import { createRoot } from 'react-dom/client';
+import { act } from 'react-dom/test-utils';
+import { readFileSync } from 'fs';
+import { fileURLToPath } from 'url';
+import path from 'path';
+import { initWasm, useKeyPair, useMessages } from '../react/src/index.js';
+
+const __dirname = path.dirname(fileURLToPath(import.meta.url));
+
+globalThis.IS_REACT_ACT_ENVIRONMENT = true;
+
+// Prime the hooks' shared init with the bytes, as there is no fetch of the .wasm under Node
+beforeAll(async () => {
+    await initWasm(readFileSync(path.join(__dirname, '../pkg/proof_messenger_web_bg.wasm')));
+});
+
+/** Render `hook` in a component, returning a getter for its latest result */
+async function renderHook(hook) {
+    let result;
+    function Probe() {
+        result = hook();
+        return null;
+    }
+    const container = document.createElement('div');
+    const root = createRoot(container);
+    await act(async () => root.render(createElement(Probe)));
+    roots.push(root);
+    return () => result;
+}
+
+const roots = [];
+
+async function settle() {
+    await act(async () => {
+        await new Promise((resolve) => setTimeout(resolve, 0));
+    });
+}
+
+beforeEach(() => localStorage.clear());
+
+afterEach(async () => {
+    await act(async () => roots.splice(0).forEach((root) => root.unmount()));
+});
+
+describe('useKeyPair', () => {
+    it('generates a keypair once WASM is ready', async () => {
+        const current = await renderHook(() => useKeyPair());
+        await settle();
+
+        expect(current().status).toBe('ready');
+        expect(current().publicKey).toMatch(/^[0-9a-f]{64}$/);
+        expect(current().keypair).toMatch(/^[0-9a-f]{128}$/);
+        expect(current().keypair.endsWith(current().publicKey)).toBe(true);
+    });
+
+    it('restores the stored keypair on the next mount', async () => {
+        // ARRANGE
+        const first = await renderHook(() => useKeyPair({ storageKey: 'identity' }));
+        await settle();
+        const publicKey = first().publicKey;
+
+        // ACT
+        const second = await renderHook(() => useKeyPair({ storageKey: 'identity' }));
+        await settle();
+
+        // ASSERT
+        expect(second().publicKey).toBe(publicKey);
+        expect(localStorage.getItem('identity')).toBe(second().keypair);
+    });
+
+    it('regenerate replaces the stored keypair and clear forgets it', async () => {
+        const current = await renderHook(() => useKeyPair({ storageKey: 'identity' }));
+        await settle();
+        const publicKey = current().publicKey;
+
+        await act(async () => current().regenerate());
+        expect(current().publicKey).not.toBe(publicKey);
+        expect(localStorage.getItem('identity')).toBe(current().keypair);
+
+        await act(async () => current().clear());
+        expect(current().keypair).toBeNull();
+        expect(localStorage.getItem('identity')).toBeNull();
+    });
+
+    it('reports a corrupt stored keypair as an error', async () => {
+        localStorage.setItem('identity', 'not hex');
+
+        const current = await renderHook(() => useKeyPair({ storageKey: 'identity' }));
+        await settle();
+
+        expect(current().status).toBe('error');
+        expect(current().error.code).toBe('INVALID_PRIVATE_KEY');
+    });
+});
+
+describe('useMessages', () => {
+    it('lists the latest messages oldest first', async () => {
+        // ARRANGE: the relay answers newest first
+        const client = {
+            messages: async (groupId, limit) => {
+                expect(groupId).toBe('team');
+                expect(limit).toBe(2);
+                return [{ id: 'b' }, { id: 'a' }];
+            },
+        };
+        const connection = { status: 'ready', error: null, client, relayUrl: 'http://relay' };
+
+        // ACT
+        const current = await renderHook(() => useMessages(connection, 'team', { limit: 2 }));
+        await settle();
+
+        // ASSERT
+        expect(current().status).toBe('ready');
+        expect(current().messages.map((message) => message.id)).toEqual(['a', 'b']);
+    });
+
+    it('follows the connection status until it has a client', async () => {
+        const connection = { status: 'loading', error: null, client: null, relayUrl: 'http://relay' };
+
+        const current = await renderHook(() => useMessages(connection, 'team'));
+
+        expect(current().status).toBe('loading');
+        expect(current().messages).toEqual([]);
+    });
+});
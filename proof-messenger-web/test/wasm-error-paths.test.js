@@ -0,0 +1,108 @@
+// Failure paths of the WASM surface: each must throw a structured error, never abort the instance
+import { expect, test, describe, beforeAll } from 'vitest';
+
+let wasm;
+
+beforeAll(async () => {
+    wasm = await import('../pkg/proof_messenger_web.js');
+});
+
+/**
+ * Assert that `fn` throws a ProofMessengerError with the given code
+ * @param {Function} fn - Call expected to fail
+ * @param {string} code - Expected error code, e.g. "INVALID_KEYPAIR"
+ */
+function expectError(fn, code) {
+    let thrown;
+    try {
+        fn();
+    } catch (error) {
+        thrown = error;
+    }
+    expect(thrown, 'expected an error to be thrown').toBeInstanceOf(Error);
+    expect(thrown.code).toBe(code);
+    expect(thrown.isProofMessengerError).toBe(true);
+    expect(typeof thrown.message).toBe('string');
+    expect(thrown.message.length).toBeGreaterThan(0);
+    return thrown;
+}
+
+describe('WASM failure paths throw structured errors', () => {
+    const context = new TextEncoder().encode('test context');
+
+    test('keypair accessors reject the wrong length', () => {
+        const error = expectError(() => wasm.get_public_key_from_keypair(new Uint8Array(10)), 'INVALID_KEYPAIR');
+        expect(error.message).toBe('Invalid keypair format: expected 64 bytes, got 10');
+        expectError(() => wasm.get_private_key_from_keypair(new Uint8Array(65)), 'INVALID_KEYPAIR');
+    });
+
+    test('make_proof_wasm rejects a short private key', () => {
+        expectError(() => wasm.make_proof_wasm(new Uint8Array(31), context), 'INVALID_PRIVATE_KEY');
+    });
+
+    test('hex_to_bytes rejects non-hex input', () => {
+        expectError(() => wasm.hex_to_bytes('not hex'), 'INVALID_INPUT');
+    });
+
+    test('keypairs reject malformed bytes', () => {
+        expectError(() => wasm.WasmKeyPair.from_bytes(new Uint8Array(3)), 'INVALID_PRIVATE_KEY');
+        expectError(() => wasm.WasmSecureKeyPair.from_bytes(new Uint8Array(3)), 'INVALID_PRIVATE_KEY');
+        expectError(() => wasm.make_secure_proof_wasm(new Uint8Array(3), context), 'INVALID_PRIVATE_KEY');
+    });
+
+    test('strict signing rejects an empty context', () => {
+        const keypair = new wasm.WasmSecureKeyPair();
+        expectError(() => keypair.sign_strict(new Uint8Array(0)), 'EMPTY_CONTEXT');
+    });
+
+    test('verification rejects malformed keys and signatures', () => {
+        const keypair = new wasm.WasmSecureKeyPair();
+        expectError(() => wasm.verify_proof_wasm(new Uint8Array(31), context, new Uint8Array(64)), 'INVALID_PUBLIC_KEY');
+        expectError(() => wasm.verify_proof_wasm(keypair.public_key_bytes, context, new Uint8Array(63)), 'INVALID_SIGNATURE');
+        expectError(() => wasm.verify_proof_secure_wasm(keypair.public_key_bytes, context, keypair.sign(new TextEncoder().encode('other'))), 'VERIFICATION_FAILED');
+    });
+
+    test('WasmMessage rejects malformed keys and JSON', () => {
+        const alice = new wasm.WasmKeyPair();
+        const message = new wasm.WasmMessage(alice.public_key_bytes, alice.public_key_bytes, 'hi');
+
+        expectError(() => message.sign(new Uint8Array(32)), 'INVALID_KEYPAIR');
+        message.sign(alice.keypair_bytes);
+        expectError(() => message.verify(new Uint8Array(5)), 'INVALID_PUBLIC_KEY');
+        expectError(() => wasm.WasmMessage.from_json('{"not": "a message"}'), 'SERIALIZATION_ERROR');
+        expect(wasm.WasmMessage.from_json(message.to_json()).content).toBe('hi');
+    });
+
+    test('receipts reject malformed hashes and signatures', () => {
+        const keypair = new wasm.WasmSecureKeyPair();
+        expectError(() => wasm.make_receipt_wasm(keypair.keypair_bytes, 'msg-1', new Uint8Array(5)), 'INVALID_INPUT');
+        expectError(() => wasm.verify_receipt_wasm(keypair.public_key_bytes, 'msg-1', new Uint8Array(32), new Uint8Array(5)), 'INVALID_SIGNATURE');
+    });
+
+    test('canonical proofs reject invalid JSON', () => {
+        const keypair = new wasm.WasmSecureKeyPair();
+        expectError(() => wasm.canonicalize_json_wasm('{'), 'SERIALIZATION_ERROR');
+        expectError(() => wasm.make_canonical_proof_wasm(keypair.keypair_bytes, '{'), 'SERIALIZATION_ERROR');
+    });
+
+    test('context digests reject the wrong length', () => {
+        const keypair = new wasm.WasmSecureKeyPair();
+        expectError(() => wasm.sign_context_digest_wasm(keypair.keypair_bytes, new Uint8Array(5)), 'INVALID_INPUT');
+    });
+
+    test('group operations reject malformed keys and payloads', () => {
+        const session = new wasm.WasmGroupSession('demo');
+        expectError(() => wasm.group_member_public_key_wasm(new Uint8Array(5)), 'INVALID_INPUT');
+        expectError(() => session.add_member(new Uint8Array(5)), 'INVALID_INPUT');
+        expectError(() => wasm.unwrap_group_key_wasm('{', new Uint8Array(32)), 'SERIALIZATION_ERROR');
+        expectError(() => wasm.group_encrypt_wasm('{', context), 'SERIALIZATION_ERROR');
+        expectError(() => wasm.group_decrypt_wasm(session.group_key_json(), '{'), 'SERIALIZATION_ERROR');
+    });
+
+    test('the instance keeps working after every failure', () => {
+        expectError(() => wasm.get_public_key_from_keypair(new Uint8Array(1)), 'INVALID_KEYPAIR');
+        const keypair = wasm.generate_keypair_wasm();
+        const proof = wasm.make_proof_wasm(wasm.get_private_key_from_keypair(keypair), context);
+        expect(wasm.verify_proof_wasm(wasm.get_public_key_from_keypair(keypair), context, proof)).toBe(true);
+    });
+});
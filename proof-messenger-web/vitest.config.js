@@ -26,7 +26,9 @@ export default defineConfig({
   resolve: {
     alias: {
       '@': new URL('./src', import.meta.url).pathname,
-      '@pkg': new URL('./pkg', import.meta.url).pathname
+      '@pkg': new URL('./pkg', import.meta.url).pathname,
+      // What proof-messenger-react imports; the package's file:../pkg dependency
+      'proof-messenger-web': new URL('./pkg/proof_messenger_web.js', import.meta.url).pathname
     }
   },
   define: {